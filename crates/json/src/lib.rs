@@ -0,0 +1,629 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A small library for parsing [JSON][1].
+//!
+//! Many system tools can report their results as JSON (e.g. `systemctl` or
+//! `osqueryi`) and a lot of applications store their configuration in it. This
+//! library is not a fully generic implementation: it only parses and does not
+//! offer any functionality for serializing values.
+//!
+//! [1]: https://www.rfc-editor.org/rfc/rfc8259
+
+use std::collections::BTreeMap;
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Null value.
+    Null,
+    /// Boolean value.
+    Bool(bool),
+    /// Number without a fraction or an exponent.
+    ///
+    /// JSON does not limit the range of numbers, so we use the widest available
+    /// type. Integers that do not fit are parsed as reals.
+    Integer(i128),
+    /// Number with a fraction or an exponent.
+    Real(f64),
+    /// Unicode string.
+    String(String),
+    /// Ordered sequence of values.
+    Array(Vec<Value>),
+    /// Mapping from string keys to values.
+    ///
+    /// If an object has duplicated keys, the last value takes precedence.
+    Object(BTreeMap<String, Value>),
+}
+
+impl Value {
+
+    /// Checks whether the value is null.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Returns the string if the value is of the string type.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if the value is of the boolean type.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(bool) => Some(*bool),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the value is of the integer type.
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as a real if the value is of any numeric type.
+    pub fn as_real(&self) -> Option<f64> {
+        match self {
+            Value::Integer(integer) => Some(*integer as f64),
+            Value::Real(real) => Some(*real),
+            _ => None,
+        }
+    }
+
+    /// Returns the items if the value is of the array type.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if the value is of the object type.
+    pub fn as_object(&self) -> Option<&BTreeMap<String, Value>> {
+        match self {
+            Value::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns the value for the given key if the value is an object.
+    ///
+    /// `None` is returned both when the value is not an object and when the
+    /// object has no such key.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_object()?.get(key)
+    }
+}
+
+/// Parses a JSON value from the given bytes.
+///
+/// The input must contain exactly one value (optionally surrounded by
+/// whitespace).
+///
+/// # Errors
+///
+/// This function will return an error if the input is not a valid JSON.
+///
+/// # Examples
+///
+/// ```
+/// let value = json::parse(br#"{"unit": "cron.service", "active": true}"#)
+///     .unwrap();
+///
+/// assert_eq!(value.get("unit").and_then(json::Value::as_str), Some("cron.service"));
+/// assert_eq!(value.get("active").and_then(json::Value::as_bool), Some(true));
+/// ```
+pub fn parse(buf: &[u8]) -> Result<Value, ParseError> {
+    let mut parser = Parser {
+        buf,
+        offset: 0,
+    };
+
+    let value = parser.value(0)?;
+
+    parser.skip_whitespace();
+    if parser.offset < buf.len() {
+        return Err(ParseError::new(ParseErrorKind::TrailingData, parser.offset));
+    }
+
+    Ok(value)
+}
+
+/// Parses a JSON value from the file at the given path.
+///
+/// See [`parse`] for more details.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or if its
+/// contents are not a valid JSON (in which case the error is of the
+/// [`InvalidData`](std::io::ErrorKind::InvalidData) kind).
+pub fn parse_file<P>(path: P) -> std::io::Result<Value>
+where
+    P: AsRef<std::path::Path>,
+{
+    let buf = std::fs::read(path)?;
+    Ok(parse(&buf)?)
+}
+
+/// An error that might be returned when parsing JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// A kind of the error.
+    kind: ParseErrorKind,
+    /// Byte offset in the input at which the error occurred.
+    offset: usize,
+}
+
+impl ParseError {
+
+    /// Creates a new error of the given kind at the given offset.
+    fn new(kind: ParseErrorKind, offset: usize) -> ParseError {
+        ParseError {
+            kind,
+            offset,
+        }
+    }
+
+    /// Returns the corresponding [`ParseErrorKind`] of the error.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte offset in the input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Kinds of errors that can happen when parsing JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The input ended before the value was complete.
+    UnexpectedEof,
+    /// An unexpected character was encountered.
+    InvalidSyntax,
+    /// A string is not valid UTF-8 or has an invalid escape sequence.
+    InvalidString,
+    /// A number cannot be parsed.
+    InvalidNumber,
+    /// Values are nested too deeply.
+    NestingTooDeep,
+    /// There is something else in the input after the value.
+    TrailingData,
+}
+
+impl std::fmt::Display for ParseErrorKind {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ParseErrorKind::*;
+        match *self {
+            UnexpectedEof => write!(fmt, "unexpected end of input"),
+            InvalidSyntax => write!(fmt, "invalid syntax"),
+            InvalidString => write!(fmt, "invalid string"),
+            InvalidNumber => write!(fmt, "invalid number"),
+            NestingTooDeep => write!(fmt, "nesting too deep"),
+            TrailingData => write!(fmt, "trailing data"),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{} (at offset {})", self.kind, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+impl From<ParseError> for std::io::Error {
+
+    fn from(error: ParseError) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Maximum depth of nested values that we allow.
+///
+/// The parser is recursive, so we need some limit to avoid overflowing the
+/// stack on malicious inputs. Real-world documents are very shallow, so the
+/// limit should never be hit for legitimate inputs.
+const MAX_DEPTH: usize = 256;
+
+/// State of the recursive descent parser.
+struct Parser<'b> {
+    /// Input that is being parsed.
+    buf: &'b [u8],
+    /// Offset of the next byte to consume.
+    offset: usize,
+}
+
+impl<'b> Parser<'b> {
+
+    /// Parses a value of any type at the current offset.
+    fn value(&mut self, depth: usize) -> Result<Value, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(ParseErrorKind::NestingTooDeep));
+        }
+
+        self.skip_whitespace();
+        match self.peek()? {
+            b'n' => self.literal(b"null", Value::Null),
+            b't' => self.literal(b"true", Value::Bool(true)),
+            b'f' => self.literal(b"false", Value::Bool(false)),
+            b'"' => Ok(Value::String(self.string()?)),
+            b'[' => self.array(depth),
+            b'{' => self.object(depth),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(self.error(ParseErrorKind::InvalidSyntax)),
+        }
+    }
+
+    /// Parses the given literal, returning the given value if it matches.
+    fn literal(&mut self, literal: &[u8], value: Value) -> Result<Value, ParseError> {
+        let rest = &self.buf[self.offset..];
+        if rest.starts_with(literal) {
+            self.offset += literal.len();
+            Ok(value)
+        } else if literal.starts_with(rest) {
+            Err(ParseError::new(ParseErrorKind::UnexpectedEof, self.buf.len()))
+        } else {
+            Err(self.error(ParseErrorKind::InvalidSyntax))
+        }
+    }
+
+    /// Parses an array (the current byte must be an opening bracket).
+    fn array(&mut self, depth: usize) -> Result<Value, ParseError> {
+        self.offset += 1;
+
+        let mut array = Vec::new();
+
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.offset += 1;
+            return Ok(Value::Array(array));
+        }
+
+        loop {
+            array.push(self.value(depth + 1)?);
+
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b']' => return Ok(Value::Array(array)),
+                _ => return Err(self.error_prev(ParseErrorKind::InvalidSyntax)),
+            }
+        }
+    }
+
+    /// Parses an object (the current byte must be an opening brace).
+    fn object(&mut self, depth: usize) -> Result<Value, ParseError> {
+        self.offset += 1;
+
+        let mut object = BTreeMap::new();
+
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.offset += 1;
+            return Ok(Value::Object(object));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek()? != b'"' {
+                return Err(self.error(ParseErrorKind::InvalidSyntax));
+            }
+            let key = self.string()?;
+
+            self.skip_whitespace();
+            if self.next()? != b':' {
+                return Err(self.error_prev(ParseErrorKind::InvalidSyntax));
+            }
+
+            let value = self.value(depth + 1)?;
+            object.insert(key, value);
+
+            self.skip_whitespace();
+            match self.next()? {
+                b',' => continue,
+                b'}' => return Ok(Value::Object(object)),
+                _ => return Err(self.error_prev(ParseErrorKind::InvalidSyntax)),
+            }
+        }
+    }
+
+    /// Parses a string (the current byte must be a quote).
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.offset += 1;
+
+        let mut string = Vec::new();
+
+        loop {
+            match self.next()? {
+                b'"' => break,
+                b'\\' => {
+                    let unescaped = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{0008}',
+                        b'f' => '\u{000C}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error_prev(ParseErrorKind::InvalidString)),
+                    };
+
+                    let mut buf = [0; 4];
+                    string.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+                }
+                // Control characters have to be escaped.
+                0x00..=0x1F => return Err(self.error_prev(ParseErrorKind::InvalidString)),
+                byte => string.push(byte),
+            }
+        }
+
+        String::from_utf8(string)
+            .map_err(|_| self.error_prev(ParseErrorKind::InvalidString))
+    }
+
+    /// Parses the `XXXX` part of a `\uXXXX` escape sequence.
+    ///
+    /// Characters outside of the Basic Multilingual Plane are escaped as UTF-16
+    /// surrogate pairs, so in such cases this consumes both escape sequences.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(u32::from(high))
+                .ok_or_else(|| self.error_prev(ParseErrorKind::InvalidString));
+        }
+
+        if self.next()? != b'\\' || self.next()? != b'u' {
+            return Err(self.error_prev(ParseErrorKind::InvalidString));
+        }
+
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error_prev(ParseErrorKind::InvalidString));
+        }
+
+        let code = 0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(low) - 0xDC00);
+        char::from_u32(code)
+            .ok_or_else(|| self.error_prev(ParseErrorKind::InvalidString))
+    }
+
+    /// Parses four hexadecimal digits.
+    fn hex4(&mut self) -> Result<u16, ParseError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = match self.next()? {
+                byte @ b'0'..=b'9' => byte - b'0',
+                byte @ b'a'..=b'f' => byte - b'a' + 10,
+                byte @ b'A'..=b'F' => byte - b'A' + 10,
+                _ => return Err(self.error_prev(ParseErrorKind::InvalidString)),
+            };
+            code = code << 4 | u16::from(digit);
+        }
+
+        Ok(code)
+    }
+
+    /// Parses a number (the current byte must be a minus or a digit).
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.offset;
+        let mut is_integer = true;
+
+        if self.peek()? == b'-' {
+            self.offset += 1;
+        }
+
+        // Leading zeros are not allowed, so zero has to be the only digit of
+        // the integer part.
+        match self.peek()? {
+            b'0' => self.offset += 1,
+            b'1'..=b'9' => self.skip_digits(),
+            _ => return Err(self.error(ParseErrorKind::InvalidNumber)),
+        }
+
+        if self.buf.get(self.offset) == Some(&b'.') {
+            self.offset += 1;
+            is_integer = false;
+
+            if !self.peek()?.is_ascii_digit() {
+                return Err(self.error(ParseErrorKind::InvalidNumber));
+            }
+            self.skip_digits();
+        }
+
+        if let Some(b'e' | b'E') = self.buf.get(self.offset) {
+            self.offset += 1;
+            is_integer = false;
+
+            if let b'+' | b'-' = self.peek()? {
+                self.offset += 1;
+            }
+
+            if !self.peek()?.is_ascii_digit() {
+                return Err(self.error(ParseErrorKind::InvalidNumber));
+            }
+            self.skip_digits();
+        }
+
+        // The grammar we have just verified is a subset of what Rust accepts,
+        // so the number is guaranteed to be ASCII and parseable as a real.
+        let number = std::str::from_utf8(&self.buf[start..self.offset])
+            .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, start))?;
+
+        if is_integer {
+            if let Ok(integer) = number.parse::<i128>() {
+                return Ok(Value::Integer(integer));
+            }
+        }
+
+        number.parse::<f64>()
+            .map(Value::Real)
+            .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, start))
+    }
+
+    /// Skips all the consecutive digits at the current offset.
+    fn skip_digits(&mut self) {
+        while self.buf.get(self.offset).is_some_and(u8::is_ascii_digit) {
+            self.offset += 1;
+        }
+    }
+
+    /// Skips all the consecutive whitespace at the current offset.
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.buf.get(self.offset) {
+            self.offset += 1;
+        }
+    }
+
+    /// Returns the byte at the current offset without consuming it.
+    fn peek(&self) -> Result<u8, ParseError> {
+        self.buf.get(self.offset).copied()
+            .ok_or_else(|| self.error(ParseErrorKind::UnexpectedEof))
+    }
+
+    /// Consumes the byte at the current offset and returns it.
+    fn next(&mut self) -> Result<u8, ParseError> {
+        let byte = self.peek()?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    /// Creates an error of the given kind at the current offset.
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind, self.offset)
+    }
+
+    /// Creates an error of the given kind at the previously consumed byte.
+    fn error_prev(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind, self.offset.saturating_sub(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_literals() {
+        assert_eq!(parse(b"null"), Ok(Value::Null));
+        assert_eq!(parse(b"true"), Ok(Value::Bool(true)));
+        assert_eq!(parse(b" false\n"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn parse_integers() {
+        assert_eq!(parse(b"0"), Ok(Value::Integer(0)));
+        assert_eq!(parse(b"-42"), Ok(Value::Integer(-42)));
+        assert_eq!(parse(b"18446744073709551616"), Ok(Value::Integer(1 << 64)));
+    }
+
+    #[test]
+    fn parse_reals() {
+        assert_eq!(parse(b"3.5"), Ok(Value::Real(3.5)));
+        assert_eq!(parse(b"-1e3"), Ok(Value::Real(-1000.0)));
+        assert_eq!(parse(b"2.5E-1"), Ok(Value::Real(0.25)));
+    }
+
+    #[test]
+    fn parse_invalid_numbers() {
+        assert_eq!(parse(b"01").unwrap_err().kind(), ParseErrorKind::TrailingData);
+        assert_eq!(parse(b"-").unwrap_err().kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(parse(b"1.").unwrap_err().kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(parse(b"1.e5").unwrap_err().kind(), ParseErrorKind::InvalidNumber);
+        assert_eq!(parse(b"-x").unwrap_err().kind(), ParseErrorKind::InvalidNumber);
+    }
+
+    #[test]
+    fn parse_strings() {
+        assert_eq!(parse(br#""foo""#), Ok(Value::String(String::from("foo"))));
+        assert_eq!(parse(r#""zażółć""#.as_bytes()), Ok(Value::String(String::from("zażółć"))));
+        assert_eq!(parse(br#""a\"b\\c\/d\n""#), Ok(Value::String(String::from("a\"b\\c/d\n"))));
+    }
+
+    #[test]
+    fn parse_string_unicode_escapes() {
+        assert_eq!(parse(br#""\u0041\u017c""#), Ok(Value::String(String::from("Aż"))));
+        assert_eq!(parse(br#""\ud83e\udd80""#), Ok(Value::String(String::from("🦀"))));
+    }
+
+    #[test]
+    fn parse_invalid_strings() {
+        assert_eq!(parse(br#""foo"#).unwrap_err().kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(parse(br#""\x""#).unwrap_err().kind(), ParseErrorKind::InvalidString);
+        assert_eq!(parse(br#""\ud83e""#).unwrap_err().kind(), ParseErrorKind::InvalidString);
+        assert_eq!(parse(b"\"\xFF\"").unwrap_err().kind(), ParseErrorKind::InvalidString);
+        assert_eq!(parse(b"\"a\nb\"").unwrap_err().kind(), ParseErrorKind::InvalidString);
+    }
+
+    #[test]
+    fn parse_arrays() {
+        assert_eq!(parse(b"[]"), Ok(Value::Array(vec![])));
+        assert_eq!(parse(b"[1, [true], null]"), Ok(Value::Array(vec![
+            Value::Integer(1),
+            Value::Array(vec![Value::Bool(true)]),
+            Value::Null,
+        ])));
+    }
+
+    #[test]
+    fn parse_objects() {
+        let value = parse(br#"{ "foo": 1, "bar": { "baz": "quux" }, "foo": 2 }"#)
+            .unwrap();
+
+        assert_eq!(value.get("foo"), Some(&Value::Integer(2)));
+        assert_eq!(value.get("bar").and_then(|bar| bar.get("baz")), Some(&Value::String(String::from("quux"))));
+        assert_eq!(value.get("norf"), None);
+    }
+
+    #[test]
+    fn parse_invalid_syntax() {
+        assert_eq!(parse(b"").unwrap_err().kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(parse(b"nul").unwrap_err().kind(), ParseErrorKind::UnexpectedEof);
+        assert_eq!(parse(b"nope").unwrap_err().kind(), ParseErrorKind::InvalidSyntax);
+        assert_eq!(parse(b"[1,]").unwrap_err().kind(), ParseErrorKind::InvalidSyntax);
+        assert_eq!(parse(b"[1 2]").unwrap_err().kind(), ParseErrorKind::InvalidSyntax);
+        assert_eq!(parse(b"{1: 2}").unwrap_err().kind(), ParseErrorKind::InvalidSyntax);
+        assert_eq!(parse(br#"{"foo" 2}"#).unwrap_err().kind(), ParseErrorKind::InvalidSyntax);
+        assert_eq!(parse(b"[] []").unwrap_err().kind(), ParseErrorKind::TrailingData);
+    }
+
+    #[test]
+    fn parse_nesting_too_deep() {
+        let buf = "[".repeat(MAX_DEPTH + 2);
+
+        let error = parse(buf.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ParseErrorKind::NestingTooDeep);
+    }
+
+    #[test]
+    fn value_as_real() {
+        assert_eq!(Value::Integer(2).as_real(), Some(2.0));
+        assert_eq!(Value::Real(0.5).as_real(), Some(0.5));
+        assert_eq!(Value::Null.as_real(), None);
+    }
+
+    #[test]
+    fn parse_error_into_io_error() {
+        let error = parse(b"foo").unwrap_err();
+
+        let error = std::io::Error::from(error);
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
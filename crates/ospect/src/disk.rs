@@ -0,0 +1,187 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about disks and their partitions.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+mod sys {
+    #[cfg(target_os = "linux")]
+    pub use crate::disk::linux::*;
+
+    #[cfg(target_os = "macos")]
+    pub use crate::disk::macos::*;
+
+    #[cfg(target_os = "windows")]
+    pub use crate::disk::windows::*;
+}
+
+/// Information about a disk.
+#[derive(Clone, Debug, Default)]
+pub struct Disk {
+    /// Path to the device of the disk (e.g. `/dev/sda` or `\\.\PhysicalDrive0`).
+    pub path: std::path::PathBuf,
+    /// Size of the disk (in bytes).
+    pub size: u64,
+    /// Size of a logical sector of the disk (in bytes), if known.
+    pub sector_size: Option<u32>,
+    /// Bus the disk is attached through (e.g. `sata`, `nvme` or `usb`).
+    pub bus_type: Option<String>,
+    /// Model of the disk, if known.
+    pub model: Option<String>,
+    /// Serial number of the disk, if known.
+    pub serial: Option<String>,
+    /// Type of the partition table of the disk, if known.
+    pub partition_table: Option<PartitionTable>,
+    /// Identifier of the partition table (GUID or MBR signature), if known.
+    pub partition_table_id: Option<String>,
+    /// Partitions of the disk (ordered by their numbers).
+    pub partitions: Vec<Partition>,
+}
+
+/// Type of a partition table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartitionTable {
+    /// Master boot record.
+    Mbr,
+    /// GUID partition table.
+    Gpt,
+}
+
+/// Information about a disk partition.
+#[derive(Clone, Debug, Default)]
+pub struct Partition {
+    /// Path to the device of the partition, if it has one.
+    pub path: Option<std::path::PathBuf>,
+    /// Number of the partition within the partition table, if known.
+    pub number: Option<u32>,
+    /// Offset of the partition from the start of the disk (in bytes).
+    pub offset: u64,
+    /// Size of the partition (in bytes).
+    pub size: u64,
+    /// Type of the partition (GUID for GPT, hexadecimal byte for MBR).
+    pub type_id: Option<String>,
+    /// Unique GUID of the partition (GPT only).
+    pub guid: Option<String>,
+    /// Name of the partition (GPT only).
+    pub name: Option<String>,
+    /// Filesystem that the partition likely contains (e.g. `ntfs` or `vfat`).
+    ///
+    /// Depending on the system this is either the filesystem detected on the
+    /// partition or a guess based on the partition type.
+    pub fs_hint: Option<String>,
+}
+
+/// Returns an iterator over all disks on the system.
+///
+/// Virtual block devices that are not backed by a hardware device (e.g. loop
+/// or device mapper devices on Linux) are not reported.
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to enumerate the disks. Each
+/// yielded item can also be an error if the information for a particular disk
+/// was not available.
+///
+/// # Examples
+///
+/// ```
+/// let disks = ospect::disk::disks()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for disk in disks {
+///     println!("{}: {} bytes", disk.path.display(), disk.size);
+/// }
+/// ```
+pub fn disks() -> std::io::Result<impl Iterator<Item = std::io::Result<Disk>>> {
+    self::sys::disks()
+}
+
+/// Guesses the filesystem of a partition of the given type.
+///
+/// The type is either a GPT partition type GUID or a hexadecimal MBR partition
+/// type (e.g. `0x07`). Types that can contain many filesystems (e.g. Linux data
+/// partitions) do not yield any guess.
+fn fs_hint(type_id: &str) -> Option<&'static str> {
+    let type_id = type_id.to_ascii_lowercase();
+
+    let fs_hint = match type_id.as_str() {
+        // EFI system partition.
+        "c12a7328-f81f-11d2-ba4b-00a0c93ec93b" => "vfat",
+        // Microsoft basic data partition.
+        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7" => "ntfs",
+        // Windows recovery environment.
+        "de94bba4-06d1-4d40-a16a-bfd50179d6ac" => "ntfs",
+        // Linux swap.
+        "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f" => "swap",
+        // Apple APFS container.
+        "7c3457ef-0000-11aa-aa11-00306543ecac" => "apfs",
+        // Apple HFS+.
+        "48465300-0000-11aa-aa11-00306543ecac" => "hfsplus",
+        // NTFS (or exFAT).
+        "0x07" => "ntfs",
+        // FAT32 (CHS or LBA addressed).
+        "0x0b" | "0x0c" => "vfat",
+        // FAT16 (LBA addressed).
+        "0x0e" => "vfat",
+        // EFI system partition on MBR disks.
+        "0xef" => "vfat",
+        // Linux swap.
+        "0x82" => "swap",
+        // Apple HFS+.
+        "0xaf" => "hfsplus",
+        _ => return None,
+    };
+
+    Some(fs_hint)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn disks_ok() {
+        let disks = disks().unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        for disk in disks {
+            assert!(!disk.path.as_os_str().is_empty());
+
+            for partition in disk.partitions {
+                assert!(partition.offset + partition.size <= disk.size);
+            }
+        }
+    }
+
+    #[test]
+    fn fs_hint_gpt() {
+        assert_eq!(fs_hint("C12A7328-F81F-11D2-BA4B-00A0C93EC93B"), Some("vfat"));
+        assert_eq!(fs_hint("ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"), Some("ntfs"));
+    }
+
+    #[test]
+    fn fs_hint_mbr() {
+        assert_eq!(fs_hint("0x07"), Some("ntfs"));
+        assert_eq!(fs_hint("0x0C"), Some("vfat"));
+    }
+
+    #[test]
+    fn fs_hint_unknown() {
+        // Linux filesystem data, can be anything.
+        assert_eq!(fs_hint("0fc63daf-8483-4772-8e79-3d69d8477de4"), None);
+        assert_eq!(fs_hint("0x83"), None);
+    }
+}
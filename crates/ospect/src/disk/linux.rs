@@ -0,0 +1,342 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::disk::{Disk, Partition, PartitionTable};
+
+/// Size of a sector in which sizes and offsets are reported by sysfs.
+///
+/// The kernel always uses 512-byte units there, regardless of the actual
+/// sector size of the device [1].
+///
+/// [1]: https://www.kernel.org/doc/Documentation/block/stat.txt
+const SYSFS_SECTOR_SIZE: u64 = 512;
+
+/// Returns an iterator over all disks on the system.
+pub fn disks() -> std::io::Result<impl Iterator<Item = std::io::Result<Disk>>> {
+    disks_in(Path::new("/sys/block"), Path::new("/run/udev/data"))
+}
+
+/// Returns disks described by the given sysfs and udev database folders.
+fn disks_in(
+    sys_block: &Path,
+    udev_data: &Path,
+) -> std::io::Result<std::vec::IntoIter<std::io::Result<Disk>>> {
+    let mut names = std::fs::read_dir(sys_block)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    names.sort();
+
+    let disks = names.into_iter()
+        // Block devices not backed by hardware (loop devices, RAM disks, device
+        // mapper targets and the like) do not have a device link.
+        .filter(|name| sys_block.join(name).join("device").exists())
+        .map(|name| disk(&sys_block.join(&name), udev_data))
+        .collect::<Vec<_>>();
+
+    Ok(disks.into_iter())
+}
+
+/// Collects information about the disk described by the given sysfs folder.
+fn disk(dir: &Path, udev_data: &Path) -> std::io::Result<Disk> {
+    let udev = udev_properties(dir, udev_data)?;
+
+    let sector_size = match read_u64(&dir.join("queue").join("logical_block_size")) {
+        Ok(sector_size) => u32::try_from(sector_size).ok(),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error),
+    };
+
+    // The udev database is not available in all environments (e.g. within
+    // containers), so we fall back to what sysfs provides.
+    let model = read_string(&dir.join("device").join("model"))
+        .or_else(|| udev.get("ID_MODEL").cloned());
+    let serial = udev.get("ID_SERIAL_SHORT").cloned()
+        .or_else(|| read_string(&dir.join("device").join("serial")))
+        .or_else(|| read_string(&dir.join("serial")));
+    let bus_type = udev.get("ID_BUS").cloned()
+        .or_else(|| subsystem(&dir.join("device")));
+
+    let partition_table = match udev.get("ID_PART_TABLE_TYPE").map(String::as_str) {
+        Some("gpt") => Some(PartitionTable::Gpt),
+        Some("dos") => Some(PartitionTable::Mbr),
+        _ => None,
+    };
+
+    let mut partitions = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+
+        // Partitions are subfolders with the `partition` file (there are other
+        // subfolders like `queue` or `power` that we are not interested in).
+        if entry.path().join("partition").exists() {
+            partitions.push(partition(&entry.path(), udev_data)?);
+        }
+    }
+    partitions.sort_by_key(|partition| partition.number);
+
+    Ok(Disk {
+        path: dev_path(dir),
+        size: read_u64(&dir.join("size"))? * SYSFS_SECTOR_SIZE,
+        sector_size,
+        bus_type,
+        model,
+        serial,
+        partition_table,
+        partition_table_id: udev.get("ID_PART_TABLE_UUID").cloned(),
+        partitions,
+    })
+}
+
+/// Collects information about the partition described by the given folder.
+fn partition(dir: &Path, udev_data: &Path) -> std::io::Result<Partition> {
+    let udev = udev_properties(dir, udev_data)?;
+
+    let type_id = udev.get("ID_PART_ENTRY_TYPE").cloned();
+    let fs_hint = udev.get("ID_FS_TYPE").cloned()
+        .or_else(|| Some(String::from(crate::disk::fs_hint(type_id.as_deref()?)?)));
+
+    Ok(Partition {
+        path: Some(dev_path(dir)),
+        number: u32::try_from(read_u64(&dir.join("partition"))?).ok(),
+        offset: read_u64(&dir.join("start"))? * SYSFS_SECTOR_SIZE,
+        size: read_u64(&dir.join("size"))? * SYSFS_SECTOR_SIZE,
+        // MBR partitions have the "unique identifier" made of the disk signature
+        // and the partition number, which is not a GUID.
+        guid: udev.get("ID_PART_ENTRY_UUID").cloned()
+            .filter(|guid| guid.len() == 36),
+        name: udev.get("ID_PART_ENTRY_NAME").cloned(),
+        type_id,
+        fs_hint,
+    })
+}
+
+/// Returns the `/dev` path of the block device described by the given folder.
+fn dev_path(dir: &Path) -> PathBuf {
+    let name = dir.file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    // Slashes in device names are replaced with `!` in sysfs (e.g. for the
+    // `cciss/c0d0` device).
+    Path::new("/dev").join(name.replace('!', "/"))
+}
+
+/// Returns the name of the subsystem the device at the given path belongs to.
+fn subsystem(device: &Path) -> Option<String> {
+    let subsystem = std::fs::read_link(device.join("subsystem")).ok()?;
+
+    Some(subsystem.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Returns udev properties of the block device described by the given folder.
+///
+/// An empty map is returned if the udev database has no entry for the device.
+fn udev_properties(
+    dir: &Path,
+    udev_data: &Path,
+) -> std::io::Result<HashMap<String, String>> {
+    let dev = std::fs::read_to_string(dir.join("dev"))?;
+
+    match std::fs::read_to_string(udev_data.join(format!("b{}", dev.trim()))) {
+        Ok(data) => Ok(parse_udev_data(&data)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => {
+            Ok(HashMap::new())
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Parses device properties out of the udev database entry.
+///
+/// The entry consists of lines prefixed with a single letter denoting the kind
+/// of the line. Properties are the ones with the `E:` prefix (e.g.
+/// `E:ID_BUS=ata`).
+fn parse_udev_data(data: &str) -> HashMap<String, String> {
+    data.lines()
+        .filter_map(|line| line.strip_prefix("E:"))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (String::from(key), String::from(value)))
+        .collect()
+}
+
+/// Reads a single integer from the given sysfs file.
+fn read_u64(path: &Path) -> std::io::Result<u64> {
+    std::fs::read_to_string(path)?
+        .trim()
+        .parse::<u64>()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Reads a single non-empty string from the given sysfs file (if it exists).
+fn read_string(path: &Path) -> Option<String> {
+    let string = std::fs::read_to_string(path).ok()?;
+
+    // Values are padded (e.g. models of ATA devices are padded with spaces to
+    // the full length of the field).
+    Some(String::from(string.trim()))
+        .filter(|string| !string.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn disks_sys_block() {
+        let disks = disks().unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        for disk in disks {
+            assert!(disk.path.starts_with("/dev"));
+            if let Some(sector_size) = disk.sector_size {
+                assert!(sector_size > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn disks_in_fixture() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let sys_block = tempdir.path().join("block");
+        let udev_data = tempdir.path().join("udev");
+        std::fs::create_dir(&udev_data)
+            .unwrap();
+
+        let sda = sys_block.join("sda");
+        write(&sda.join("dev"), "8:0\n");
+        write(&sda.join("size"), "2048\n");
+        write(&sda.join("queue").join("logical_block_size"), "4096\n");
+        write(&sda.join("device").join("model"), "Foo SSD         \n");
+        write(&sda.join("device").join("serial"), "\n");
+        write(&udev_data.join("b8:0"), "\
+S:disk/by-id/ata-Foo_SSD_1337
+I:1234
+E:ID_BUS=ata
+E:ID_SERIAL_SHORT=1337
+E:ID_PART_TABLE_TYPE=gpt
+E:ID_PART_TABLE_UUID=8a8f2ab5-24ba-4e3d-8bd3-3e5bfb3c26dd
+");
+
+        let sda1 = sda.join("sda1");
+        write(&sda1.join("dev"), "8:1\n");
+        write(&sda1.join("partition"), "1\n");
+        write(&sda1.join("start"), "34\n");
+        write(&sda1.join("size"), "512\n");
+        write(&udev_data.join("b8:1"), "\
+E:ID_PART_ENTRY_TYPE=c12a7328-f81f-11d2-ba4b-00a0c93ec93b
+E:ID_PART_ENTRY_UUID=4f2b6a1c-0d6e-4a55-9d38-1b3c3a1f7e21
+E:ID_PART_ENTRY_NAME=EFI
+");
+
+        let sda2 = sda.join("sda2");
+        write(&sda2.join("dev"), "8:2\n");
+        write(&sda2.join("partition"), "2\n");
+        write(&sda2.join("start"), "546\n");
+        write(&sda2.join("size"), "1024\n");
+        write(&udev_data.join("b8:2"), "\
+E:ID_FS_TYPE=ext4
+E:ID_PART_ENTRY_TYPE=0fc63daf-8483-4772-8e79-3d69d8477de4
+");
+
+        // Loop devices do not have a device link and should not be reported.
+        write(&sys_block.join("loop0").join("dev"), "7:0\n");
+        write(&sys_block.join("loop0").join("size"), "0\n");
+
+        let disks = disks_in(&sys_block, &udev_data).unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(disks.len(), 1);
+
+        let disk = &disks[0];
+        assert_eq!(disk.path, PathBuf::from("/dev/sda"));
+        assert_eq!(disk.size, 2048 * 512);
+        assert_eq!(disk.sector_size, Some(4096));
+        assert_eq!(disk.bus_type.as_deref(), Some("ata"));
+        assert_eq!(disk.model.as_deref(), Some("Foo SSD"));
+        assert_eq!(disk.serial.as_deref(), Some("1337"));
+        assert_eq!(disk.partition_table, Some(PartitionTable::Gpt));
+        assert_eq!(disk.partition_table_id.as_deref(), Some("8a8f2ab5-24ba-4e3d-8bd3-3e5bfb3c26dd"));
+
+        assert_eq!(disk.partitions.len(), 2);
+
+        let partition = &disk.partitions[0];
+        assert_eq!(partition.path, Some(PathBuf::from("/dev/sda1")));
+        assert_eq!(partition.number, Some(1));
+        assert_eq!(partition.offset, 34 * 512);
+        assert_eq!(partition.size, 512 * 512);
+        assert_eq!(partition.guid.as_deref(), Some("4f2b6a1c-0d6e-4a55-9d38-1b3c3a1f7e21"));
+        assert_eq!(partition.name.as_deref(), Some("EFI"));
+        assert_eq!(partition.fs_hint.as_deref(), Some("vfat"));
+
+        let partition = &disk.partitions[1];
+        assert_eq!(partition.number, Some(2));
+        assert_eq!(partition.offset, 546 * 512);
+        assert_eq!(partition.fs_hint.as_deref(), Some("ext4"));
+    }
+
+    #[test]
+    fn disks_in_no_udev() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let sys_block = tempdir.path().join("block");
+
+        let vda = sys_block.join("vda");
+        write(&vda.join("dev"), "254:0\n");
+        write(&vda.join("size"), "16\n");
+        write(&vda.join("serial"), "foo\n");
+        std::fs::create_dir(vda.join("device"))
+            .unwrap();
+
+        let disks = disks_in(&sys_block, &tempdir.path().join("udev")).unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(disks.len(), 1);
+
+        let disk = &disks[0];
+        assert_eq!(disk.path, PathBuf::from("/dev/vda"));
+        assert_eq!(disk.size, 16 * 512);
+        assert_eq!(disk.sector_size, None);
+        assert_eq!(disk.serial.as_deref(), Some("foo"));
+        assert_eq!(disk.partition_table, None);
+        assert!(disk.partitions.is_empty());
+    }
+
+    #[test]
+    fn parse_udev_data_properties() {
+        let properties = parse_udev_data("\
+S:disk/by-uuid/foo
+E:ID_FS_TYPE=vfat
+E:ID_FS_LABEL=foo=bar
+G:systemd
+");
+
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties["ID_FS_TYPE"], "vfat");
+        assert_eq!(properties["ID_FS_LABEL"], "foo=bar");
+    }
+
+    #[test]
+    fn dev_path_with_slash() {
+        let path = dev_path(Path::new("/sys/block/cciss!c0d0"));
+        assert_eq!(path, PathBuf::from("/dev/cciss/c0d0"));
+    }
+
+    /// Writes the given content to the file, creating parent folders.
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap())
+            .unwrap();
+        std::fs::write(path, content)
+            .unwrap();
+    }
+}
@@ -0,0 +1,520 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+
+use crate::disk::{Disk, Partition, PartitionTable};
+
+/// Returns an iterator over all disks on the system.
+pub fn disks() -> std::io::Result<impl Iterator<Item = std::io::Result<Disk>>> {
+    let keys = Keys::new()?;
+
+    let mut disks = Vec::new();
+    let mut partitions = HashMap::<String, Vec<Partition>>::new();
+
+    // Both disks and partitions are represented as media objects in the I/O
+    // Registry [1]. Disks are the "whole" ones and partitions are their
+    // descendants.
+    //
+    // [1]: https://developer.apple.com/documentation/iokit/iomedia
+    for media in medias()? {
+        // SAFETY: The object is valid as it has just been returned by iterator.
+        let props = unsafe { Props::of(media.0) }?;
+
+        // SAFETY: The dictionary is valid (ensured by the wrapper).
+        let Some(bsd_name) = (unsafe { dict_string(props.0 .0, &keys.bsd_name) }) else {
+            continue;
+        };
+
+        // SAFETY: The dictionary is valid (ensured by the wrapper).
+        if unsafe { dict_bool(props.0 .0, &keys.whole) } == Some(true) {
+            // SAFETY: The media object and the dictionary are valid.
+            disks.push(unsafe { disk(media.0, &props, bsd_name, &keys) });
+        } else if let Some(disk_name) = parent_name(&bsd_name) {
+            // SAFETY: The dictionary is valid (ensured by the wrapper).
+            let partition = unsafe { partition(&props, &bsd_name, &keys) };
+            partitions.entry(String::from(disk_name)).or_default().push(partition);
+        }
+    }
+
+    for disk in &mut disks {
+        let name = disk.path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if let Some(mut partitions) = partitions.remove(&name) {
+            partitions.sort_by_key(|partition| partition.number);
+            disk.partitions = partitions;
+        }
+    }
+    disks.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(disks.into_iter().map(Ok))
+}
+
+/// Collects information about the whole disk media.
+///
+/// # Safety
+///
+/// `media` must be a valid `IOMedia` object and `props` must be its properties.
+unsafe fn disk(media: u32, props: &Props, bsd_name: String, keys: &Keys) -> Disk {
+    // SAFETY: The dictionary is valid as required by the caller.
+    let (size, sector_size, content) = unsafe {(
+        dict_u64(props.0 .0, &keys.size).unwrap_or(0),
+        dict_u64(props.0 .0, &keys.block_size)
+            .and_then(|block_size| u32::try_from(block_size).ok()),
+        dict_string(props.0 .0, &keys.content),
+    )};
+
+    // Whole disks have their partitioning scheme as their content [1].
+    //
+    // [1]: https://developer.apple.com/documentation/kernel/kiomediacontentkey
+    let partition_table = match content.as_deref() {
+        Some("GUID_partition_scheme") => Some(PartitionTable::Gpt),
+        Some("FDisk_partition_scheme") => Some(PartitionTable::Mbr),
+        _ => None,
+    };
+
+    // Hardware details are not properties of the media but of the device that
+    // provides it, so we need to search its ancestors.
+    //
+    // SAFETY: The media object is valid as required by the caller.
+    let (device, protocol) = unsafe {(
+        search(media, &keys.device_characteristics),
+        search(media, &keys.protocol_characteristics),
+    )};
+
+    // SAFETY: Searched objects are verified to be dictionaries.
+    let (model, serial, bus_type) = unsafe {(
+        device.as_ref().and_then(|device| dict_string(device.0, &keys.product_name)),
+        device.as_ref().and_then(|device| dict_string(device.0, &keys.serial_number)),
+        protocol.as_ref().and_then(|protocol| dict_string(protocol.0, &keys.physical_interconnect)),
+    )};
+
+    Disk {
+        path: std::path::Path::new("/dev").join(bsd_name),
+        size,
+        sector_size,
+        bus_type,
+        model: model.map(|model| String::from(model.trim())),
+        serial: serial.map(|serial| String::from(serial.trim())),
+        partition_table,
+        partition_table_id: None,
+        partitions: Vec::new(),
+    }
+}
+
+/// Collects information about the partition media.
+///
+/// # Safety
+///
+/// `props` must be properties of a valid `IOMedia` object.
+unsafe fn partition(props: &Props, bsd_name: &str, keys: &Keys) -> Partition {
+    // SAFETY: The dictionary is valid as required by the caller.
+    unsafe {
+        // Partitions have their partition type as their content (a GUID in case
+        // of GPT partitions).
+        let type_id = dict_string(props.0 .0, &keys.content)
+            .map(|type_id| type_id.to_ascii_lowercase());
+
+        Partition {
+            path: Some(std::path::Path::new("/dev").join(bsd_name)),
+            number: dict_u64(props.0 .0, &keys.partition_id)
+                .and_then(|number| u32::try_from(number).ok()),
+            offset: dict_u64(props.0 .0, &keys.base).unwrap_or(0),
+            size: dict_u64(props.0 .0, &keys.size).unwrap_or(0),
+            guid: dict_string(props.0 .0, &keys.uuid)
+                .map(|guid| guid.to_ascii_lowercase()),
+            name: None,
+            fs_hint: type_id.as_deref()
+                .and_then(crate::disk::fs_hint)
+                .map(String::from),
+            type_id,
+        }
+    }
+}
+
+/// Returns the BSD name of the disk the partition with the given name is on.
+///
+/// Partitions are named after their disk with an `s` and the partition number
+/// appended (e.g. `disk0s1` is the first partition of `disk0`).
+fn parent_name(bsd_name: &str) -> Option<&str> {
+    let (disk_name, number) = bsd_name.rsplit_once('s')?;
+    if number.is_empty() || !number.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(disk_name)
+}
+
+/// Returns all media objects registered in the I/O Registry.
+fn medias() -> std::io::Result<Vec<Object>> {
+    // SAFETY: We pass a null-terminated class name. The returned dictionary is
+    // consumed by the call below, so we do not release it.
+    let matching = unsafe {
+        IOServiceMatching(c"IOMedia".as_ptr())
+    };
+    if matching.is_null() {
+        return Err(std::io::Error::other("failed to create media matching dictionary"));
+    }
+
+    let mut iter = 0;
+
+    // SAFETY: We pass the default main port, a valid matching dictionary and a
+    // valid location for the iterator. We verify the result below.
+    let status = unsafe {
+        IOServiceGetMatchingServices(K_IO_MAIN_PORT_DEFAULT, matching, &mut iter)
+    };
+    if status != KERN_SUCCESS {
+        return Err(std::io::Error::other(format! {
+            "failed to obtain media objects (status: {status:#x})"
+        }));
+    }
+    let iter = Object(iter);
+
+    let mut medias = Vec::new();
+    loop {
+        // SAFETY: The iterator is valid. The returned object is owned by us
+        // and released when the wrapper is dropped.
+        let media = unsafe { IOIteratorNext(iter.0) };
+        if media == 0 {
+            break;
+        }
+
+        medias.push(Object(media));
+    }
+
+    Ok(medias)
+}
+
+/// Searches ancestors of the registry entry for a dictionary property.
+///
+/// # Safety
+///
+/// `entry` must be a valid registry entry object.
+unsafe fn search(entry: u32, key: &Owned) -> Option<Owned> {
+    // SAFETY: The entry is valid as required by the caller and the plane name
+    // is null-terminated. The returned value (if any) is owned by us.
+    let value = unsafe {
+        IORegistryEntrySearchCFProperty(
+            entry,
+            c"IOService".as_ptr(),
+            key.0,
+            std::ptr::null(),
+            K_IO_REGISTRY_ITERATE_RECURSIVELY | K_IO_REGISTRY_ITERATE_PARENTS,
+        )
+    };
+    if value.is_null() {
+        return None;
+    }
+    let value = Owned(value);
+
+    // SAFETY: The value is a valid object (it was checked for null above).
+    if unsafe { CFGetTypeID(value.0) != CFDictionaryGetTypeID() } {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// Properties of a registry entry.
+struct Props(Owned);
+
+impl Props {
+
+    /// Obtains properties of the given registry entry.
+    ///
+    /// # Safety
+    ///
+    /// `entry` must be a valid registry entry object.
+    unsafe fn of(entry: u32) -> std::io::Result<Props> {
+        let mut props = std::ptr::null();
+
+        // SAFETY: The entry is valid as required by the caller. On success,
+        // the dictionary is owned by us.
+        let status = unsafe {
+            IORegistryEntryCreateCFProperties(entry, &mut props, std::ptr::null(), 0)
+        };
+        if status != KERN_SUCCESS || props.is_null() {
+            return Err(std::io::Error::other(format! {
+                "failed to obtain media properties (status: {status:#x})"
+            }));
+        }
+
+        Ok(Props(Owned(props)))
+    }
+}
+
+/// Returns the string value for the given key of the dictionary.
+///
+/// `None` is returned if there is no such key or the value is not a string.
+///
+/// # Safety
+///
+/// `dict` must be a valid `CFDictionary` object.
+unsafe fn dict_string(dict: *const c_void, key: &Owned) -> Option<String> {
+    // SAFETY: The dictionary is valid as required by the caller and the key is
+    // a valid string. The value (if any) is owned by the dictionary.
+    let value = unsafe { CFDictionaryGetValue(dict, key.0) };
+    if value.is_null() || unsafe { CFGetTypeID(value) != CFStringGetTypeID() } {
+        return None;
+    }
+
+    // SAFETY: We verified that the value is a string.
+    let len = unsafe { CFStringGetLength(value) };
+    // SAFETY: This is a pure computation without any preconditions.
+    let size = unsafe { CFStringGetMaximumSizeForEncoding(len, K_CF_STRING_ENCODING_UTF8) } + 1;
+
+    let mut buf = vec![0u8; size as usize];
+
+    // SAFETY: We pass a buffer that is big enough to hold the string in UTF-8
+    // together with the null terminator.
+    let status = unsafe {
+        CFStringGetCString(value, buf.as_mut_ptr().cast(), size, K_CF_STRING_ENCODING_UTF8)
+    };
+    if status == 0 {
+        return None;
+    }
+
+    let len = buf.iter().position(|byte| *byte == 0).unwrap_or(buf.len());
+    buf.truncate(len);
+
+    String::from_utf8(buf).ok()
+}
+
+/// Returns the integer value for the given key of the dictionary.
+///
+/// `None` is returned if there is no such key or the value is not a number.
+///
+/// # Safety
+///
+/// `dict` must be a valid `CFDictionary` object.
+unsafe fn dict_u64(dict: *const c_void, key: &Owned) -> Option<u64> {
+    // SAFETY: The dictionary is valid as required by the caller and the key is
+    // a valid string. The value (if any) is owned by the dictionary.
+    let value = unsafe { CFDictionaryGetValue(dict, key.0) };
+    if value.is_null() || unsafe { CFGetTypeID(value) != CFNumberGetTypeID() } {
+        return None;
+    }
+
+    let mut result = 0i64;
+
+    // SAFETY: We verified that the value is a number and we pass a pointer to
+    // a 64-bit integer as required for the requested number type.
+    let status = unsafe {
+        CFNumberGetValue(value, K_CF_NUMBER_SINT64_TYPE, (&mut result as *mut i64).cast())
+    };
+    if status == 0 {
+        return None;
+    }
+
+    u64::try_from(result).ok()
+}
+
+/// Returns the boolean value for the given key of the dictionary.
+///
+/// `None` is returned if there is no such key or the value is not a boolean.
+///
+/// # Safety
+///
+/// `dict` must be a valid `CFDictionary` object.
+unsafe fn dict_bool(dict: *const c_void, key: &Owned) -> Option<bool> {
+    // SAFETY: The dictionary is valid as required by the caller and the key is
+    // a valid string. The value (if any) is owned by the dictionary.
+    let value = unsafe { CFDictionaryGetValue(dict, key.0) };
+    if value.is_null() || unsafe { CFGetTypeID(value) != CFBooleanGetTypeID() } {
+        return None;
+    }
+
+    // SAFETY: We verified that the value is a boolean.
+    Some(unsafe { CFBooleanGetValue(value) } != 0)
+}
+
+/// Keys of the media properties that we are interested in.
+struct Keys {
+    bsd_name: Owned,
+    whole: Owned,
+    size: Owned,
+    block_size: Owned,
+    content: Owned,
+    base: Owned,
+    partition_id: Owned,
+    uuid: Owned,
+    device_characteristics: Owned,
+    protocol_characteristics: Owned,
+    product_name: Owned,
+    serial_number: Owned,
+    physical_interconnect: Owned,
+}
+
+impl Keys {
+
+    /// Creates Core Foundation strings for all the keys.
+    fn new() -> std::io::Result<Keys> {
+        Ok(Keys {
+            bsd_name: cf_string("BSD Name")?,
+            whole: cf_string("Whole")?,
+            size: cf_string("Size")?,
+            block_size: cf_string("Preferred Block Size")?,
+            content: cf_string("Content")?,
+            base: cf_string("Base")?,
+            partition_id: cf_string("Partition ID")?,
+            uuid: cf_string("UUID")?,
+            device_characteristics: cf_string("Device Characteristics")?,
+            protocol_characteristics: cf_string("Protocol Characteristics")?,
+            product_name: cf_string("Product Name")?,
+            serial_number: cf_string("Serial Number")?,
+            physical_interconnect: cf_string("Physical Interconnect")?,
+        })
+    }
+}
+
+/// Creates a Core Foundation string from the given Rust string.
+fn cf_string(string: &str) -> std::io::Result<Owned> {
+    // SAFETY: We pass a valid buffer together with its length. The returned
+    // string is owned by us and released when the wrapper is dropped.
+    let result = unsafe {
+        CFStringCreateWithBytes(
+            std::ptr::null(),
+            string.as_ptr(),
+            string.len() as isize,
+            K_CF_STRING_ENCODING_UTF8,
+            0,
+        )
+    };
+    if result.is_null() {
+        return Err(std::io::Error::other("failed to create string"));
+    }
+
+    Ok(Owned(result))
+}
+
+/// Owned Core Foundation object released when dropped.
+struct Owned(*const c_void);
+
+impl Drop for Owned {
+
+    fn drop(&mut self) {
+        // SAFETY: The object is owned by us and was checked for null when the
+        // wrapper was created.
+        unsafe {
+            CFRelease(self.0);
+        }
+    }
+}
+
+/// Owned I/O Kit object released when dropped.
+struct Object(u32);
+
+impl Drop for Object {
+
+    fn drop(&mut self) {
+        // SAFETY: The object is owned by us and was checked for null when the
+        // wrapper was created.
+        unsafe {
+            IOObjectRelease(self.0);
+        }
+    }
+}
+
+/// Identifier of the UTF-8 encoding (`kCFStringEncodingUTF8`).
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x08000100;
+
+/// Identifier of the 64-bit signed integer number type (`kCFNumberSInt64Type`).
+const K_CF_NUMBER_SINT64_TYPE: isize = 4;
+
+/// Default port for communicating with I/O Kit (`kIOMainPortDefault`).
+const K_IO_MAIN_PORT_DEFAULT: u32 = 0;
+
+/// Status code of successful kernel calls (`KERN_SUCCESS`).
+const KERN_SUCCESS: i32 = 0;
+
+/// Option to search the registry recursively (`kIORegistryIterateRecursively`).
+const K_IO_REGISTRY_ITERATE_RECURSIVELY: u32 = 0x00000001;
+
+/// Option to search parents of the registry entry (`kIORegistryIterateParents`).
+const K_IO_REGISTRY_ITERATE_PARENTS: u32 = 0x00000002;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+    fn CFGetTypeID(cf: *const c_void) -> usize;
+    fn CFBooleanGetTypeID() -> usize;
+    fn CFDictionaryGetTypeID() -> usize;
+    fn CFNumberGetTypeID() -> usize;
+    fn CFStringGetTypeID() -> usize;
+    fn CFBooleanGetValue(boolean: *const c_void) -> u8;
+    fn CFDictionaryGetValue(dict: *const c_void, key: *const c_void) -> *const c_void;
+    fn CFNumberGetValue(number: *const c_void, r#type: isize, value: *mut c_void) -> u8;
+    fn CFStringCreateWithBytes(
+        alloc: *const c_void,
+        bytes: *const u8,
+        len: isize,
+        encoding: u32,
+        is_external: u8,
+    ) -> *const c_void;
+    fn CFStringGetLength(string: *const c_void) -> isize;
+    fn CFStringGetMaximumSizeForEncoding(len: isize, encoding: u32) -> isize;
+    fn CFStringGetCString(
+        string: *const c_void,
+        buf: *mut std::ffi::c_char,
+        size: isize,
+        encoding: u32,
+    ) -> u8;
+}
+
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IOServiceMatching(name: *const std::ffi::c_char) -> *const c_void;
+    fn IOServiceGetMatchingServices(
+        main_port: u32,
+        matching: *const c_void,
+        iter: *mut u32,
+    ) -> i32;
+    fn IOIteratorNext(iter: u32) -> u32;
+    fn IOObjectRelease(object: u32) -> i32;
+    fn IORegistryEntryCreateCFProperties(
+        entry: u32,
+        props: *mut *const c_void,
+        alloc: *const c_void,
+        options: u32,
+    ) -> i32;
+    fn IORegistryEntrySearchCFProperty(
+        entry: u32,
+        plane: *const std::ffi::c_char,
+        key: *const c_void,
+        alloc: *const c_void,
+        options: u32,
+    ) -> *const c_void;
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn disks_boot_disk() {
+        let disks = disks().unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        // There is always at least the disk the system booted from.
+        assert!(!disks.is_empty());
+    }
+
+    #[test]
+    fn parent_name_partition() {
+        assert_eq!(parent_name("disk0s1"), Some("disk0"));
+        assert_eq!(parent_name("disk12s34"), Some("disk12"));
+    }
+
+    #[test]
+    fn parent_name_disk() {
+        assert_eq!(parent_name("disk0"), None);
+        assert_eq!(parent_name("disks"), None);
+    }
+}
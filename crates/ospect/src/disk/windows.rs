@@ -0,0 +1,463 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::Foundation::*;
+use windows_sys::Win32::Storage::FileSystem::*;
+use windows_sys::Win32::System::Ioctl::*;
+
+use crate::disk::{Disk, Partition, PartitionTable};
+
+/// Limit on physical drive numbers that we try to open.
+///
+/// Drive numbers are not guaranteed to be contiguous (e.g. a drive might have
+/// been removed), so we cannot stop at the first drive that does not exist.
+const MAX_DRIVE_COUNT: u32 = 64;
+
+/// Returns an iterator over all disks on the system.
+pub fn disks() -> std::io::Result<impl Iterator<Item = std::io::Result<Disk>>> {
+    let disks = (0..MAX_DRIVE_COUNT)
+        .filter_map(|number| {
+            let path = std::path::PathBuf::from(format!("\\\\.\\PhysicalDrive{number}"));
+            match disk(path) {
+                Ok(disk) => Some(Ok(disk)),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+                Err(error) => Some(Err(error)),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(disks.into_iter())
+}
+
+/// Collects information about the physical drive at the given path.
+fn disk(path: std::path::PathBuf) -> std::io::Result<Disk> {
+    let handle = open(&path)?;
+
+    let geometry = geometry(&handle)?;
+    let descriptor = descriptor(&handle)?;
+    let layout = layout(&handle)?;
+
+    Ok(Disk {
+        path,
+        size: u64::try_from(geometry.DiskSize).unwrap_or(0),
+        sector_size: Some(geometry.Geometry.BytesPerSector),
+        bus_type: bus_type(descriptor.bus_type).map(String::from),
+        model: descriptor.product,
+        serial: descriptor.serial,
+        partition_table: layout.partition_table,
+        partition_table_id: layout.partition_table_id,
+        partitions: layout.partitions,
+    })
+}
+
+/// Opens the physical drive at the given path for querying its properties.
+fn open(path: &std::path::Path) -> std::io::Result<Handle> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    let path = path.as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+
+    // SAFETY: We pass a null-terminated path of the drive to open [1]. We do
+    // not ask for any access rights: this is enough to query properties of the
+    // drive and does not require administrator privileges.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-createfilew
+    let handle = unsafe {
+        CreateFileW(
+            path.as_ptr(),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null(),
+            OPEN_EXISTING,
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(Handle(handle))
+}
+
+/// Obtains the geometry of the drive.
+fn geometry(handle: &Handle) -> std::io::Result<DISK_GEOMETRY_EX> {
+    // SAFETY: The structure consists of integers only, so all-zero bytes is a
+    // valid value.
+    let mut geometry = unsafe { std::mem::zeroed::<DISK_GEOMETRY_EX>() };
+
+    // SAFETY: The buffer is big enough to hold the fixed part of the structure
+    // and we are not interested in the data that follows it [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_get_drive_geometry_ex
+    unsafe {
+        ioctl(
+            handle,
+            IOCTL_DISK_GET_DRIVE_GEOMETRY_EX,
+            &[],
+            std::slice::from_raw_parts_mut(
+                (&mut geometry as *mut DISK_GEOMETRY_EX).cast::<u8>(),
+                std::mem::size_of::<DISK_GEOMETRY_EX>(),
+            ),
+        )
+    }?;
+
+    Ok(geometry)
+}
+
+/// Properties of the drive reported by the storage driver.
+struct Descriptor {
+    /// Type of the bus the drive is attached through.
+    bus_type: STORAGE_BUS_TYPE,
+    /// Product identifier (model) of the drive.
+    product: Option<String>,
+    /// Serial number of the drive.
+    serial: Option<String>,
+}
+
+/// Obtains properties of the drive reported by the storage driver.
+fn descriptor(handle: &Handle) -> std::io::Result<Descriptor> {
+    /// Size of the buffer for the descriptor and strings that follow it.
+    const BUF_SIZE: usize = 4096;
+
+    let query = STORAGE_PROPERTY_QUERY {
+        PropertyId: StorageDeviceProperty,
+        QueryType: PropertyStandardQuery,
+        AdditionalParameters: [0],
+    };
+
+    // SAFETY: The query consists of integers only and is fully initialized.
+    let query = unsafe {
+        std::slice::from_raw_parts(
+            (&query as *const STORAGE_PROPERTY_QUERY).cast::<u8>(),
+            std::mem::size_of::<STORAGE_PROPERTY_QUERY>(),
+        )
+    };
+
+    // The buffer is made of 64-bit integers so that it is properly aligned for
+    // the descriptor structure.
+    let mut buf = vec![0u64; BUF_SIZE / std::mem::size_of::<u64>()];
+
+    // SAFETY: The buffer is valid for writes of `BUF_SIZE` bytes. Strings that
+    // do not fit are truncated by the driver [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_storage_query_property
+    let buf = unsafe {
+        let buf = std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), BUF_SIZE);
+        let len = ioctl(handle, IOCTL_STORAGE_QUERY_PROPERTY, query, buf)?;
+        &buf[..len]
+    };
+    if buf.len() < std::mem::size_of::<STORAGE_DEVICE_DESCRIPTOR>() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "storage device descriptor too short",
+        ));
+    }
+
+    // SAFETY: The buffer is properly aligned and we verified that it is big
+    // enough to hold the descriptor.
+    let descriptor = unsafe {
+        std::ptr::read(buf.as_ptr().cast::<STORAGE_DEVICE_DESCRIPTOR>())
+    };
+
+    Ok(Descriptor {
+        bus_type: descriptor.BusType,
+        product: descriptor_string(buf, descriptor.ProductIdOffset),
+        serial: descriptor_string(buf, descriptor.SerialNumberOffset),
+    })
+}
+
+/// Reads a null-terminated string at the given offset of the descriptor.
+///
+/// Offset of zero means that the string is not available.
+fn descriptor_string(buf: &[u8], offset: u32) -> Option<String> {
+    if offset == 0 {
+        return None;
+    }
+
+    let string = buf.get(offset as usize..)?;
+    let len = string.iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(string.len());
+
+    // Drivers tend to pad the strings with spaces.
+    let string = String::from_utf8_lossy(&string[..len]);
+    Some(String::from(string.trim()))
+        .filter(|string| !string.is_empty())
+}
+
+/// Returns a human-friendly name of the given bus type.
+fn bus_type(bus_type: STORAGE_BUS_TYPE) -> Option<&'static str> {
+    #[allow(non_upper_case_globals)]
+    let name = match bus_type {
+        BusTypeScsi => "scsi",
+        BusTypeAtapi => "atapi",
+        BusTypeAta => "ata",
+        BusType1394 => "ieee1394",
+        BusTypeSsa => "ssa",
+        BusTypeFibre => "fibre",
+        BusTypeUsb => "usb",
+        BusTypeRAID => "raid",
+        BusTypeiScsi => "iscsi",
+        BusTypeSas => "sas",
+        BusTypeSata => "sata",
+        BusTypeSd => "sd",
+        BusTypeMmc => "mmc",
+        BusTypeVirtual => "virtual",
+        BusTypeFileBackedVirtual => "file_backed_virtual",
+        BusTypeSpaces => "spaces",
+        BusTypeNvme => "nvme",
+        BusTypeSCM => "scm",
+        BusTypeUfs => "ufs",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Partitioning of the drive.
+struct Layout {
+    /// Type of the partition table.
+    partition_table: Option<PartitionTable>,
+    /// Identifier of the partition table.
+    partition_table_id: Option<String>,
+    /// Partitions of the drive.
+    partitions: Vec<Partition>,
+}
+
+/// Obtains partitioning of the drive.
+fn layout(handle: &Handle) -> std::io::Result<Layout> {
+    /// Number of partition entries that we allocate the buffer for initially.
+    const INITIAL_PARTITION_COUNT: usize = 16;
+
+    let buf_size = |partition_count: usize| {
+        std::mem::size_of::<DRIVE_LAYOUT_INFORMATION_EX>() +
+        std::mem::size_of::<PARTITION_INFORMATION_EX>() * (partition_count - 1)
+    };
+
+    // The buffer is made of 64-bit integers so that it is properly aligned for
+    // the layout structure.
+    let mut partition_count = INITIAL_PARTITION_COUNT;
+    let mut buf = Vec::<u64>::new();
+    loop {
+        let size = buf_size(partition_count);
+        buf.resize(size.div_ceil(std::mem::size_of::<u64>()), 0);
+
+        // SAFETY: The buffer is valid for writes of `size` bytes.
+        let result = unsafe {
+            let buf = std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), size);
+            ioctl(handle, IOCTL_DISK_GET_DRIVE_LAYOUT_EX, &[], buf)
+        };
+
+        // The call fails if the buffer cannot hold all partition entries, in
+        // which case we retry with a bigger one [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ni-winioctl-ioctl_disk_get_drive_layout_ex
+        match result {
+            Ok(_) => break,
+            Err(error) if error.raw_os_error() == Some(ERROR_INSUFFICIENT_BUFFER as i32) => {
+                partition_count *= 2;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    // SAFETY: The call succeeded, so the buffer is filled with the layout.
+    let layout = unsafe {
+        &*buf.as_ptr().cast::<DRIVE_LAYOUT_INFORMATION_EX>()
+    };
+
+    // SAFETY: The layout is followed by as many entries as it declares (up to
+    // the number of entries the buffer was allocated for).
+    let entries = unsafe {
+        std::slice::from_raw_parts(
+            layout.PartitionEntry.as_ptr(),
+            usize::min(layout.PartitionCount as usize, partition_count),
+        )
+    };
+
+    let (partition_table, partition_table_id) = match layout.PartitionStyle as PARTITION_STYLE {
+        PARTITION_STYLE_MBR => {
+            // SAFETY: The union holds the MBR variant for MBR drives.
+            let signature = unsafe { layout.Anonymous.Mbr.Signature };
+            (Some(PartitionTable::Mbr), Some(format!("{signature:08x}")))
+        }
+        PARTITION_STYLE_GPT => {
+            // SAFETY: The union holds the GPT variant for GPT drives.
+            let disk_id = unsafe { layout.Anonymous.Gpt.DiskId };
+            (Some(PartitionTable::Gpt), Some(guid(&disk_id)))
+        }
+        _ => (None, None),
+    };
+
+    let partitions = entries.iter()
+        .filter_map(partition)
+        .collect();
+
+    Ok(Layout {
+        partition_table,
+        partition_table_id,
+        partitions,
+    })
+}
+
+/// Converts the partition entry of the layout to a partition.
+///
+/// `None` is returned for unused entries.
+fn partition(entry: &PARTITION_INFORMATION_EX) -> Option<Partition> {
+    let mut partition = Partition {
+        // Partitions do not have their own device paths on Windows (volumes
+        // do, but they are not necessarily backed by a single partition).
+        path: None,
+        number: Some(entry.PartitionNumber),
+        offset: u64::try_from(entry.StartingOffset).ok()?,
+        size: u64::try_from(entry.PartitionLength).ok()?,
+        ..Partition::default()
+    };
+
+    match entry.PartitionStyle {
+        PARTITION_STYLE_MBR => {
+            // SAFETY: The union holds the MBR variant for MBR partitions.
+            let mbr = unsafe { entry.Anonymous.Mbr };
+
+            // MBR layouts always have (at least) four entries with the unused
+            // ones having zero type [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-partition_information_mbr
+            if mbr.PartitionType == 0 {
+                return None;
+            }
+
+            partition.type_id = Some(format!("{:#04x}", mbr.PartitionType));
+        }
+        PARTITION_STYLE_GPT => {
+            // SAFETY: The union holds the GPT variant for GPT partitions.
+            let gpt = unsafe { entry.Anonymous.Gpt };
+
+            let name_len = gpt.Name.iter()
+                .position(|char| *char == 0)
+                .unwrap_or(gpt.Name.len());
+
+            partition.type_id = Some(guid(&gpt.PartitionType));
+            partition.guid = Some(guid(&gpt.PartitionId));
+            partition.name = Some(String::from_utf16_lossy(&gpt.Name[..name_len]))
+                .filter(|name| !name.is_empty());
+        }
+        _ => (),
+    }
+
+    partition.fs_hint = partition.type_id.as_deref()
+        .and_then(crate::disk::fs_hint)
+        .map(String::from);
+
+    Some(partition)
+}
+
+/// Formats the given GUID in its canonical lowercase form.
+fn guid(guid: &windows_sys::core::GUID) -> String {
+    let data4 = guid.data4;
+    format! {
+        "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        guid.data1, guid.data2, guid.data3,
+        data4[0], data4[1],
+        data4[2], data4[3], data4[4], data4[5], data4[6], data4[7],
+    }
+}
+
+/// Sends the given control code to the device and returns the output length.
+///
+/// # Safety
+///
+/// The input and output buffers must be valid for the control code (e.g. they
+/// must be properly aligned for the structures the code expects).
+unsafe fn ioctl(
+    handle: &Handle,
+    code: u32,
+    input: &[u8],
+    output: &mut [u8],
+) -> std::io::Result<usize> {
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    let mut len = 0;
+
+    // SAFETY: The handle is valid and the buffers are valid as required by the
+    // caller. We do not use overlapped I/O.
+    let status = unsafe {
+        DeviceIoControl(
+            handle.0,
+            code,
+            input.as_ptr().cast(),
+            input.len() as u32,
+            output.as_mut_ptr().cast(),
+            output.len() as u32,
+            &mut len,
+            std::ptr::null_mut(),
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(len as usize)
+}
+
+/// RAII wrapper around generic handles.
+struct Handle(HANDLE);
+
+impl Drop for Handle {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and we close it only once [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn disks_system_drive() {
+        let disks = disks().unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        // There is always at least the drive the system booted from.
+        assert!(!disks.is_empty());
+        assert!(disks.iter().all(|disk| disk.sector_size.is_some()));
+    }
+
+    #[test]
+    fn descriptor_string_ok() {
+        let buf = b"\0\0\0\0foo   \0bar";
+        assert_eq!(descriptor_string(buf, 4).as_deref(), Some("foo"));
+        assert_eq!(descriptor_string(buf, 11).as_deref(), Some("bar"));
+    }
+
+    #[test]
+    fn descriptor_string_missing() {
+        let buf = b"foo\0";
+        assert_eq!(descriptor_string(buf, 0), None);
+        assert_eq!(descriptor_string(buf, 42), None);
+    }
+
+    #[test]
+    fn guid_canonical() {
+        let guid = guid(&windows_sys::core::GUID {
+            data1: 0xc12a7328,
+            data2: 0xf81f,
+            data3: 0x11d2,
+            data4: [0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b],
+        });
+        assert_eq!(guid, "c12a7328-f81f-11d2-ba4b-00a0c93ec93b");
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about the DNS client on Windows.
+//!
+//! On Windows the resolver configuration is not kept in plain text files and
+//! resolved names are cached by the DNS client service. On other systems the
+//! configuration lives in files like `/etc/resolv.conf` that can be read
+//! directly and there is no system-wide cache to inspect, so there is nothing
+//! to expose here.
+
+mod windows;
+
+mod sys {
+    pub use crate::dns::windows::*;
+}
+
+/// Network parameters of the DNS client.
+#[derive(Clone, Debug)]
+pub struct NetworkParams {
+    /// Name of the domain the machine belongs to.
+    domain_name: String,
+    /// Addresses of the configured DNS servers.
+    servers: Vec<std::net::IpAddr>,
+}
+
+impl NetworkParams {
+
+    /// Returns the name of the domain the machine belongs to.
+    ///
+    /// The name is empty if the machine does not belong to any domain.
+    pub fn domain_name(&self) -> &str {
+        &self.domain_name
+    }
+
+    /// Returns addresses of the configured DNS servers.
+    ///
+    /// Note that only IPv4 servers are reported.
+    pub fn servers(&self) -> impl Iterator<Item = std::net::IpAddr> + '_ {
+        self.servers.iter().copied()
+    }
+}
+
+/// An entry of the DNS client cache.
+#[derive(Clone, Debug)]
+pub struct CacheEntry {
+    /// Name that the entry is for.
+    name: std::ffi::OsString,
+    /// Type of the cached record (e.g. 1 for `A` or 28 for `AAAA`).
+    record_type: u16,
+}
+
+impl CacheEntry {
+
+    /// Returns the name that the entry is for.
+    pub fn name(&self) -> &std::ffi::OsStr {
+        &self.name
+    }
+
+    /// Returns the type of the cached record.
+    ///
+    /// The type is a [numeric DNS resource record type][1], e.g. 1 for `A` or
+    /// 28 for `AAAA` records.
+    ///
+    /// [1]: https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-4
+    pub fn record_type(&self) -> u16 {
+        self.record_type
+    }
+}
+
+/// Returns network parameters of the DNS client.
+///
+/// # Errors
+///
+/// This function will fail if the parameters could not be queried.
+///
+/// # Examples
+///
+/// ```
+/// let params = ospect::dns::network_params()
+///     .unwrap();
+///
+/// for server in params.servers() {
+///     println!("{server}");
+/// }
+/// ```
+pub fn network_params() -> std::io::Result<NetworkParams> {
+    self::sys::network_params()
+}
+
+/// Returns an iterator over entries of the DNS client cache.
+///
+/// # Errors
+///
+/// This function will fail if the cache could not be queried (e.g. because
+/// the DNS client service is not running).
+///
+/// # Examples
+///
+/// ```
+/// let entries = ospect::dns::cache_entries()
+///     .unwrap();
+///
+/// for entry in entries {
+///     println!("{} ({})", entry.name().to_string_lossy(), entry.record_type());
+/// }
+/// ```
+pub fn cache_entries() -> std::io::Result<impl Iterator<Item = CacheEntry>> {
+    self::sys::cache_entries()
+}
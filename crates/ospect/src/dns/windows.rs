@@ -0,0 +1,218 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::Foundation::*;
+use windows_sys::Win32::NetworkManagement::Dns::*;
+use windows_sys::Win32::NetworkManagement::IpHelper::*;
+use windows_sys::Win32::System::LibraryLoader::*;
+
+use crate::dns::{CacheEntry, NetworkParams};
+
+/// Returns network parameters of the DNS client.
+pub fn network_params() -> std::io::Result<NetworkParams> {
+    let mut buf_len = 0;
+
+    // SAFETY: We call the function without a buffer to learn its required
+    // size as described in the official docs [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/iphlpapi/nf-iphlpapi-getnetworkparams
+    let code = unsafe {
+        GetNetworkParams(std::ptr::null_mut(), &mut buf_len)
+    };
+    if code != ERROR_BUFFER_OVERFLOW {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    // We use a buffer of `u64` to guarantee proper alignment of the structure
+    // (which contains pointers).
+    let mut buf = vec![0u64; (buf_len as usize).div_ceil(8)];
+
+    // SAFETY: We pass a buffer of the size returned by the previous call. The
+    // parameters cannot really change between the calls but if it happens, we
+    // just fail below.
+    let code = unsafe {
+        GetNetworkParams(buf.as_mut_ptr().cast::<FIXED_INFO_W2KSP1>(), &mut buf_len)
+    };
+    if code != NO_ERROR {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    // SAFETY: The call succeeded, so the buffer now holds a valid structure.
+    let info = unsafe {
+        &*buf.as_ptr().cast::<FIXED_INFO_W2KSP1>()
+    };
+
+    let domain_name = parse_cstr(&info.DomainName);
+
+    let mut servers = Vec::new();
+
+    let mut server = Some(&info.DnsServerList);
+    while let Some(server_info) = server {
+        let addr = parse_cstr(&server_info.IpAddress.String);
+
+        // The first entry of the list is empty if there are no servers.
+        if !addr.is_empty() {
+            let addr = addr.parse()
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+            servers.push(addr);
+        }
+
+        // SAFETY: Entries of the list point either to next entries within the
+        // buffer or are null.
+        server = unsafe { server_info.Next.as_ref() };
+    }
+
+    Ok(NetworkParams {
+        domain_name,
+        servers,
+    })
+}
+
+/// Returns an iterator over entries of the DNS client cache.
+pub fn cache_entries() -> std::io::Result<impl Iterator<Item = CacheEntry>> {
+    let get_cache_data_table = load_get_cache_data_table()?;
+
+    let mut table = std::ptr::null_mut::<DNS_CACHE_ENTRY>();
+
+    // SAFETY: We pass a pointer to which the function stores the head of the
+    // list of entries, that we free below.
+    let status = unsafe {
+        get_cache_data_table(&mut table)
+    };
+    // The function returns an error if the cache is empty as well, so there is
+    // no way to tell these two cases apart (other than checking the last error
+    // that is not always set).
+    if status == FALSE {
+        return Ok(Vec::new().into_iter());
+    }
+
+    let mut entries = Vec::new();
+
+    let mut entry = table;
+    while !entry.is_null() {
+        // SAFETY: The entry is not null and all the entries in the list are
+        // valid until we free them.
+        let entry_ref = unsafe { &*entry };
+
+        if !entry_ref.name.is_null() {
+            // SAFETY: The name is a non-null and null-terminated wide string.
+            let name = unsafe {
+                let len = (0..).take_while(|&i| *entry_ref.name.add(i) != 0).count();
+                std::slice::from_raw_parts(entry_ref.name, len)
+            };
+
+            use std::os::windows::ffi::OsStringExt as _;
+            entries.push(CacheEntry {
+                name: std::ffi::OsString::from_wide(name),
+                record_type: entry_ref.record_type,
+            });
+        }
+
+        let next = entry_ref.next;
+
+        // SAFETY: Both the name and the entry are allocated by the DNS client
+        // library and freed exactly once.
+        unsafe {
+            DnsFree(entry_ref.name.cast(), DnsFreeFlat);
+            DnsFree(entry.cast(), DnsFreeFlat);
+        }
+
+        entry = next;
+    }
+
+    Ok(entries.into_iter())
+}
+
+/// Entry of the DNS client cache returned by `DnsGetCacheDataTable`.
+///
+/// The function is not documented and so the layout of the structure is not
+/// officially specified. It is however stable across all the supported Windows
+/// versions and used by many tools (e.g. `ipconfig /displaydns` uses a similar
+/// mechanism under the hood).
+#[repr(C)]
+struct DNS_CACHE_ENTRY {
+    next: *mut DNS_CACHE_ENTRY,
+    name: *mut u16,
+    record_type: u16,
+    data_len: u16,
+    flags: u32,
+}
+
+/// Signature of the `DnsGetCacheDataTable` function.
+type DnsGetCacheDataTable = unsafe extern "system" fn(*mut *mut DNS_CACHE_ENTRY) -> BOOL;
+
+/// Loads the `DnsGetCacheDataTable` function from the DNS client library.
+///
+/// The function is not part of the public API (and not part of import
+/// libraries), so it has to be resolved at runtime.
+fn load_get_cache_data_table() -> std::io::Result<DnsGetCacheDataTable> {
+    // SAFETY: We pass a null-terminated library name and restrict the search
+    // to the system directory so that no other library can be picked up [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/libloaderapi/nf-libloaderapi-loadlibraryexw
+    let module = unsafe {
+        LoadLibraryExW(
+            windows_sys::w!("dnsapi.dll"),
+            std::ptr::null_mut(),
+            LOAD_LIBRARY_SEARCH_SYSTEM32,
+        )
+    };
+    if module.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: We pass a valid module handle and a null-terminated name. Note
+    // that we never free the library: it is used by the system anyway and the
+    // function pointer has to remain valid.
+    let proc = unsafe {
+        GetProcAddress(module, windows_sys::s!("DnsGetCacheDataTable"))
+    };
+    let Some(proc) = proc else {
+        return Err(std::io::Error::last_os_error());
+    };
+
+    // SAFETY: The exported function has the declared signature.
+    Ok(unsafe {
+        std::mem::transmute::<unsafe extern "system" fn() -> isize, DnsGetCacheDataTable>(proc)
+    })
+}
+
+/// Converts a null-terminated ANSI string buffer to a Rust string.
+fn parse_cstr(buf: &[i8]) -> String {
+    let bytes = buf.iter()
+        .map(|&byte| byte as u8)
+        .take_while(|&byte| byte != 0)
+        .collect::<Vec<u8>>();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn network_params_ok() {
+        assert!(network_params().is_ok());
+    }
+
+    #[test]
+    fn cache_entries_ok() {
+        for entry in cache_entries().unwrap() {
+            assert!(!entry.name().is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_cstr_ok() {
+        let mut buf = [0i8; 16];
+        for (i, byte) in b"example.com".iter().enumerate() {
+            buf[i] = *byte as i8;
+        }
+
+        assert_eq!(parse_cstr(&buf), "example.com");
+    }
+}
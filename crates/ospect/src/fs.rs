@@ -17,6 +17,9 @@ pub mod macos;
 #[cfg(target_os = "windows")]
 pub mod windows;
 
+#[cfg(target_family = "unix")]
+mod unix;
+
 mod sys {
     #[cfg(target_os = "linux")]
     pub use crate::fs::linux::*;
@@ -197,6 +200,139 @@ pub struct Mount {
     pub path: std::path::PathBuf,
     /// Type of the mounted filesystem (e.g. `ext4`, `ramfs`, `proc`).
     pub fs_type: String,
+    /// Options the filesystem is mounted with (e.g. `rw`, `nosuid`).
+    pub options: Vec<String>,
+    /// Whether the filesystem is mounted as read-only.
+    pub read_only: bool,
+}
+
+impl Mount {
+
+    /// Returns whether the mounted filesystem is a pseudo filesystem.
+    ///
+    /// Pseudo filesystems (like `proc` or `sysfs` on Linux) do not store any
+    /// data on a device and instead they expose various kernel interfaces.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mounts = ospect::fs::mounts()
+    ///     .unwrap()
+    ///     .filter_map(Result::ok);
+    ///
+    /// for mount in mounts.filter(|mount| !mount.is_pseudo()) {
+    ///     println!("{}", mount.path.display());
+    /// }
+    /// ```
+    pub fn is_pseudo(&self) -> bool {
+        PSEUDO_FS_TYPES.contains(&self.fs_type.as_str())
+    }
+
+    /// Returns whether the mounted filesystem is a network filesystem.
+    ///
+    /// Data of network filesystems (like NFS or SMB) is stored on a remote
+    /// host, so accessing it can be slow and it can change or disappear at any
+    /// time without the local system noticing.
+    ///
+    /// Note that FUSE filesystems are recognized only if they are known to be
+    /// backed by a network (e.g. `fuse.sshfs`).
+    pub fn is_network(&self) -> bool {
+        NETWORK_FS_TYPES.iter()
+            .any(|fs_type| self.fs_type.eq_ignore_ascii_case(fs_type))
+    }
+}
+
+/// Types of known pseudo filesystems.
+const PSEUDO_FS_TYPES: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devfs",
+    "devpts",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Types of known network filesystems.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "9p",
+    "acfs",
+    "afpfs",
+    "afs",
+    "ceph",
+    "cifs",
+    "fuse.glusterfs",
+    "fuse.s3fs",
+    "fuse.sshfs",
+    "gfs2",
+    "glusterfs",
+    "gpfs",
+    "lustre",
+    "ncpfs",
+    "nfs",
+    "nfs4",
+    "smb3",
+    "smbfs",
+    "webdav",
+];
+
+/// Information about space available on a filesystem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Space {
+    /// Total size of the filesystem (in bytes).
+    pub total: u64,
+    /// Number of free bytes on the filesystem.
+    pub free: u64,
+    /// Number of free bytes available to the current (unprivileged) user.
+    ///
+    /// This can be less than [`free`](Space::free) because of reserved blocks
+    /// or quotas.
+    pub available: u64,
+}
+
+/// Returns information about space on the filesystem the given path is on.
+///
+/// The exact behaviour is system specific:
+///
+///   * On Linux and macOS it uses the [`statvfs`][1] call.
+///   * On Windows it uses the [`GetDiskFreeSpaceExW`][2] call.
+///
+/// [1]: https://man7.org/linux/man-pages/man3/statvfs.3.html
+/// [2]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdiskfreespaceexw
+///
+/// # Errors
+///
+/// This function will fail if the path does not exist or if the information
+/// cannot be obtained for other reasons (e.g. insufficient permissions).
+///
+/// # Examples
+///
+/// ```
+/// let tempdir = std::env::temp_dir();
+///
+/// let space = ospect::fs::space(&tempdir)
+///     .unwrap();
+/// assert!(space.free <= space.total);
+/// ```
+pub fn space<P>(path: P) -> std::io::Result<Space>
+where
+    P: AsRef<Path>,
+{
+    self::sys::space(path)
 }
 
 /// Returns an iterator over mounted filesystems information.
@@ -225,6 +361,24 @@ mod tests {
         assert!(ext_attrs(&tempdir.path().join("foo")).is_err());
     }
 
+    #[test]
+    fn mount_is_network() {
+        let mount = |fs_type: &str| Mount {
+            name: String::from("foo"),
+            path: std::path::PathBuf::from("/foo"),
+            fs_type: String::from(fs_type),
+            options: Vec::new(),
+            read_only: false,
+        };
+
+        assert!(mount("nfs4").is_network());
+        assert!(mount("cifs").is_network());
+        assert!(mount("fuse.sshfs").is_network());
+        assert!(!mount("ext4").is_network());
+        assert!(!mount("fuse.gocryptfs").is_network());
+        assert!(!mount("proc").is_network());
+    }
+
     #[cfg(all(target_os = "linux", feature = "test-setfattr"))]
     #[test]
     fn ext_attrs_multiple_values() {
@@ -186,6 +186,14 @@ where
     Ok(buf)
 }
 
+/// Returns information about space on the filesystem the given path is on.
+pub fn space<P>(path: P) -> std::io::Result<crate::fs::Space>
+where
+    P: AsRef<Path>,
+{
+    crate::fs::unix::space(path)
+}
+
 /// Returns an iterator over mounted filesystems information.
 pub fn mounts() -> std::io::Result<impl Iterator<Item = std::io::Result<Mount>>> {
     // We try to parse `/proc/mounts`, but if it does not exist we fallback to
@@ -223,19 +231,31 @@ impl<R: std::io::Read> Mounts<R> {
     fn parse_buf(&self) -> std::io::Result<Mount> {
         let mut cols = self.buf.split(' ');
 
-        // There is more data in the file but we don't care for the time being
-        // and only "parse" the first three columns.
+        // There is more data in the file (dump frequency and fsck order) but
+        // we don't care for the time being and only "parse" the first four
+        // columns.
         let name = cols.next()
             .ok_or_else(|| std::io::ErrorKind::InvalidData)?;
         let path = cols.next()
             .ok_or_else(|| std::io::ErrorKind::InvalidData)?;
         let fs_type = cols.next()
             .ok_or_else(|| std::io::ErrorKind::InvalidData)?;
+        let options = cols.next()
+            .ok_or(std::io::ErrorKind::InvalidData)?
+            .trim_end()
+            .split(',')
+            .filter(|option| !option.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        let read_only = options.iter().any(|option| option == "ro");
 
         Ok(Mount {
             name: name.into(),
             path: path.into(),
             fs_type: fs_type.into(),
+            options,
+            read_only,
         })
     }
 }
@@ -393,6 +413,17 @@ pub(crate) mod tests {
         };
     }
 
+    #[test]
+    fn mounts_missing_options() {
+        const MTAB: &'static str = "\
+/dev/foobar / ext4
+        ";
+
+        let mut mounts = Mounts::new(MTAB.as_bytes());
+
+        assert!(mounts.next().unwrap().is_err());
+    }
+
     #[test]
     fn mounts_empty_mtab() {
         const MTAB: &'static str = "\
@@ -409,7 +440,7 @@ pub(crate) mod tests {
 sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
 proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
 /dev/foobar / ext4 rw,relatime 0 0
-/dev/quux /usr/quux ext4 rw,relatime 0 0
+/dev/quux /usr/quux ext4 ro,relatime 0 0
         ";
 
         let mut mounts = Mounts::new(MTAB.as_bytes());
@@ -418,6 +449,9 @@ proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
         assert_eq!(mount.name, "sysfs");
         assert_eq!(mount.path, Path::new("/sys"));
         assert_eq!(mount.fs_type, "sysfs");
+        assert_eq!(mount.options, ["rw", "nosuid", "nodev", "noexec", "relatime"]);
+        assert!(!mount.read_only);
+        assert!(mount.is_pseudo());
 
         let mount = mounts.next().unwrap().unwrap();
         assert_eq!(mount.name, "proc");
@@ -428,11 +462,14 @@ proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
         assert_eq!(mount.name, "/dev/foobar");
         assert_eq!(mount.path, Path::new("/"));
         assert_eq!(mount.fs_type, "ext4");
+        assert!(!mount.is_pseudo());
 
         let mount = mounts.next().unwrap().unwrap();
         assert_eq!(mount.name, "/dev/quux");
         assert_eq!(mount.path, Path::new("/usr/quux"));
         assert_eq!(mount.fs_type, "ext4");
+        assert_eq!(mount.options, ["ro", "relatime"]);
+        assert!(mount.read_only);
 
         assert!(mounts.next().is_none());
     }
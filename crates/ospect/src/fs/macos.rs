@@ -164,6 +164,14 @@ where
     Ok(buf)
 }
 
+/// Returns information about space on the filesystem the given path is on.
+pub fn space<P>(path: P) -> std::io::Result<crate::fs::Space>
+where
+    P: AsRef<Path>,
+{
+    crate::fs::unix::space(path)
+}
+
 /// Returns an iterator over mounted filesystems information.
 pub fn mounts() -> std::io::Result<impl Iterator<Item = std::io::Result<Mount>>> {
     // SAFETY: We do the first call to `getfsstat` with null-pointer only to get
@@ -225,10 +233,36 @@ pub fn mounts() -> std::io::Result<impl Iterator<Item = std::io::Result<Mount>>>
 
         use std::os::unix::ffi::OsStrExt as _;
 
+        // Unlike on Linux, options are not available as strings and we need to
+        // reconstruct them from the flags. We use the names that the `mount`
+        // utility uses [1].
+        //
+        // [1]: https://developer.apple.com/library/archive/documentation/System/Conceptual/ManPages_iPhoneOS/man8/mount.8.html
+        const OPTIONS: &[(libc::c_int, &str)] = &[
+            (libc::MNT_SYNCHRONOUS, "sync"),
+            (libc::MNT_NOEXEC, "noexec"),
+            (libc::MNT_NOSUID, "nosuid"),
+            (libc::MNT_NODEV, "nodev"),
+            (libc::MNT_LOCAL, "local"),
+            (libc::MNT_DONTBROWSE, "nobrowse"),
+            (libc::MNT_AUTOMOUNTED, "automounted"),
+            (libc::MNT_JOURNALED, "journaled"),
+            (libc::MNT_NOATIME, "noatime"),
+        ];
+
+        let read_only = statfs.f_flags & libc::MNT_RDONLY as u32 != 0;
+
+        let mut options = vec![String::from(if read_only { "ro" } else { "rw" })];
+        options.extend(OPTIONS.iter()
+            .filter(|(flag, _)| statfs.f_flags & *flag as u32 != 0)
+            .map(|(_, name)| String::from(*name)));
+
         Ok(Mount {
             name: name.into_owned(),
             path: PathBuf::from(OsStr::from_bytes(path)),
             fs_type: fs_type.into_owned(),
+            options,
+            read_only,
         })
     }))
 }
@@ -0,0 +1,77 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+/// Returns information about space on the filesystem the given path is on.
+pub fn space<P>(path: P) -> std::io::Result<crate::fs::Space>
+where
+    P: AsRef<Path>,
+{
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    let mut stat = std::mem::MaybeUninit::uninit();
+
+    // SAFETY: We pass a valid null-terminated path and a buffer for the result
+    // as described in the documentation [1]. We verify the result below.
+    //
+    // [1]: https://man7.org/linux/man-pages/man3/statvfs.3.html
+    let code = unsafe {
+        libc::statvfs(path.as_ptr(), stat.as_mut_ptr())
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the structure has been filled.
+    let stat = unsafe {
+        stat.assume_init()
+    };
+
+    // Block counts are expressed in the units of the fragment size (not the
+    // block size) [1]. The exact types of the fields vary between platforms,
+    // hence the conversions (that are no-ops on some of them).
+    //
+    // [1]: https://pubs.opengroup.org/onlinepubs/9699919799/basedefs/sys_statvfs.h.html
+    #[allow(clippy::useless_conversion)]
+    let space = {
+        let frag_size = u64::from(stat.f_frsize);
+
+        crate::fs::Space {
+            total: u64::from(stat.f_blocks).saturating_mul(frag_size),
+            free: u64::from(stat.f_bfree).saturating_mul(frag_size),
+            available: u64::from(stat.f_bavail).saturating_mul(frag_size),
+        }
+    };
+
+    Ok(space)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn space_root() {
+        let space = space("/")
+            .unwrap();
+
+        assert!(space.total > 0);
+        assert!(space.free <= space.total);
+        assert!(space.available <= space.free);
+    }
+
+    #[test]
+    fn space_non_existing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(space(tempdir.path().join("foo")).is_err());
+    }
+}
@@ -30,6 +30,46 @@ where
     Err(std::io::ErrorKind::Unsupported.into())
 }
 
+/// Returns information about space on the filesystem the given path is on.
+pub fn space<P>(path: P) -> std::io::Result<crate::fs::Space>
+where
+    P: AsRef<Path>,
+{
+    use std::os::windows::ffi::OsStrExt as _;
+
+    let path_wide = path.as_ref().as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+
+    let mut available = 0;
+    let mut total = 0;
+    let mut free = 0;
+
+    // SAFETY: We pass a valid null-terminated path and pointers to integers
+    // for the results as described in the documentation [1]. We verify the
+    // status below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getdiskfreespaceexw
+    let status = unsafe {
+        windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW(
+            path_wide.as_ptr(),
+            &mut available,
+            &mut total,
+            &mut free,
+        )
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(crate::fs::Space {
+        total,
+        free,
+        available,
+    })
+}
+
 /// Returns an iterator over mounted filesystems information.
 pub fn mounts() -> std::io::Result<impl Iterator<Item = std::io::Result<Mount>>> {
     // We have a choice here: we can have a fully lazy iterator by combining
@@ -56,10 +96,13 @@ pub fn mounts() -> std::io::Result<impl Iterator<Item = std::io::Result<Mount>>>
             let name_len = name_buf.iter().position(|tchar| *tchar == 0)
                 .expect("volume name not null-terminated");
 
-            let fs_type_buf = volume_fs_type(&name_buf)?;
+            let (fs_type_buf, fs_flags) = volume_fs_info(&name_buf)?;
             let fs_type_len = fs_type_buf.iter().position(|tchar| *tchar == 0)
                 .expect("volume filesystem name not null-terminated");
 
+            use windows_sys::Win32::System::SystemServices::FILE_READ_ONLY_VOLUME;
+            let read_only = fs_flags & FILE_READ_ONLY_VOLUME != 0;
+
             for mount_point in VolumeMountPoints::new(&name_buf)? {
                 results.push(Ok(Mount {
                     name: OsString::from_wide(&name_buf[0..name_len])
@@ -67,6 +110,10 @@ pub fn mounts() -> std::io::Result<impl Iterator<Item = std::io::Result<Mount>>>
                     path: mount_point,
                     fs_type: OsString::from_wide(&fs_type_buf[0..fs_type_len])
                         .to_string_lossy().into_owned(),
+                    // Windows has no notion of mount options, so the read-only
+                    // flag is the only one we can report.
+                    options: vec![String::from(if read_only { "ro" } else { "rw" })],
+                    read_only,
                 }));
             }
 
@@ -353,18 +400,21 @@ impl Iterator for VolumeMountPoints {
     }
 }
 
-/// Returns filesystem type for the given volume name.
-fn volume_fs_type(name_buf: &VolumeNameBuf) -> std::io::Result<VolumeFsTypeBuf> {
+/// Returns filesystem type and filesystem flags for the given volume name.
+fn volume_fs_info(name_buf: &VolumeNameBuf) -> std::io::Result<(VolumeFsTypeBuf, u32)> {
     // TODO(rust-lang/rust#96097): Refactor with `MaybeUninit` once support
     // for arrays is stabilized.
     let mut fs_type_buf = {
         [0; (windows_sys::Win32::Foundation::MAX_PATH + 1) as usize]
     };
 
+    let mut fs_flags = 0;
+
     // SAFETY: This is just a call to the unsafe function as described in
     // the documentation [1]. As root path we pass the volume name and then
-    // only the buffer for filesystem type along with its size. All other
-    // values we leave empty (they are optional) as we are not interested.
+    // only the buffer for filesystem flags and filesystem type along with its
+    // size. All other values we leave empty (they are optional) as we are not
+    // interested.
     //
     // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-getvolumeinformationw
     let status = unsafe {
@@ -373,7 +423,7 @@ fn volume_fs_type(name_buf: &VolumeNameBuf) -> std::io::Result<VolumeFsTypeBuf>
             std::ptr::null_mut(), 0, // Volume name.
             std::ptr::null_mut(), // Volume serial number.
             std::ptr::null_mut(), // Component length limit.
-            std::ptr::null_mut(), // Filesystem flags.
+            &mut fs_flags,
             fs_type_buf.as_mut_ptr(), fs_type_buf.len() as u32,
         )
     };
@@ -381,5 +431,5 @@ fn volume_fs_type(name_buf: &VolumeNameBuf) -> std::io::Result<VolumeFsTypeBuf>
         return Err(std::io::Error::last_os_error());
     }
 
-    Ok(fs_type_buf)
+    Ok((fs_type_buf, fs_flags))
 }
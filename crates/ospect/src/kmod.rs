@@ -0,0 +1,139 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about loaded kernel modules.
+//!
+//! Depending on the system these are called differently: kernel modules on
+//! Linux, kernel extensions on macOS and device drivers on Windows.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+mod sys {
+    #[cfg(target_os = "linux")]
+    pub use crate::kmod::linux::*;
+
+    #[cfg(target_os = "macos")]
+    pub use crate::kmod::macos::*;
+
+    #[cfg(target_os = "windows")]
+    pub use crate::kmod::windows::*;
+}
+
+/// Information about a loaded kernel module.
+#[derive(Clone, Debug, Default)]
+pub struct Module {
+    /// Name of the module.
+    name: std::ffi::OsString,
+    /// Size of the module in memory (in bytes), if known.
+    size: Option<u64>,
+    /// Number of references to the module, if known.
+    ref_count: Option<u64>,
+    /// Address at which the module is loaded, if known.
+    base_address: Option<u64>,
+    /// Path to the file that the module was loaded from, if known.
+    path: Option<std::path::PathBuf>,
+    /// Version of the module, if known.
+    version: Option<String>,
+    /// Checksum of the source files the module was built from.
+    #[cfg(target_os = "linux")]
+    srcversion: Option<String>,
+    /// Flags denoting the ways in which the module taints the kernel.
+    #[cfg(target_os = "linux")]
+    taint: Option<String>,
+}
+
+impl Module {
+
+    /// Returns the name of the module.
+    ///
+    /// On Linux this is the name as used by `modprobe` (e.g. `ext4`), on macOS
+    /// the bundle identifier of the extension (e.g. `com.apple.iokit.IOUSBFamily`)
+    /// and on Windows the file name of the driver (e.g. `ACPI.sys`).
+    pub fn name(&self) -> &std::ffi::OsStr {
+        &self.name
+    }
+
+    /// Returns the size of the module in memory (in bytes), if known.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Returns the number of references to the module, if known.
+    ///
+    /// Modules with non-zero number of references cannot be unloaded.
+    pub fn ref_count(&self) -> Option<u64> {
+        self.ref_count
+    }
+
+    /// Returns the address at which the module is loaded, if known.
+    ///
+    /// Note that systems tend to hide kernel addresses from unprivileged users
+    /// in which case this is not available.
+    pub fn base_address(&self) -> Option<u64> {
+        self.base_address
+    }
+
+    /// Returns the path to the file that the module was loaded from, if known.
+    ///
+    /// On Linux this is the path to the module file that would be loaded now
+    /// (according to `modules.dep`), which in principle does not have to be the
+    /// file that was actually loaded.
+    pub fn path(&self) -> Option<&std::path::Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the version of the module, if known.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Returns the checksum of the source files the module was built from.
+    #[cfg(target_os = "linux")]
+    pub fn srcversion(&self) -> Option<&str> {
+        self.srcversion.as_deref()
+    }
+
+    /// Returns flags denoting the ways in which the module taints the kernel.
+    ///
+    /// See the [kernel documentation][1] for the meaning of particular flags
+    /// (e.g. `O` for out-of-tree modules or `E` for unsigned ones).
+    ///
+    /// [1]: https://docs.kernel.org/admin-guide/tainted-kernels.html
+    #[cfg(target_os = "linux")]
+    pub fn taint(&self) -> Option<&str> {
+        self.taint.as_deref()
+    }
+}
+
+/// Returns an iterator over all kernel modules loaded on the system.
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to enumerate the modules
+/// (e.g. because of insufficient permissions). Each yielded item can also be
+/// an error if the information for a particular module was not available or
+/// was malformed.
+///
+/// # Examples
+///
+/// ```
+/// let modules = ospect::kmod::modules()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for module in modules {
+///     println!("{}", module.name().to_string_lossy());
+/// }
+/// ```
+pub fn modules() -> std::io::Result<impl Iterator<Item = std::io::Result<Module>>> {
+    self::sys::modules()
+}
@@ -0,0 +1,272 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::kmod::Module;
+
+/// Path to the file listing loaded kernel modules.
+const PROC_MODULES_PATH: &str = "/proc/modules";
+
+/// Path to the directory with information about loaded modules in sysfs.
+const SYS_MODULE_PATH: &str = "/sys/module";
+
+/// Path to the directory with modules of all installed kernels.
+const LIB_MODULES_PATH: &str = "/lib/modules";
+
+/// Returns an iterator over all kernel modules loaded on the system.
+pub fn modules() -> std::io::Result<Modules> {
+    let file = match std::fs::File::open(PROC_MODULES_PATH) {
+        Ok(file) => Some(std::io::BufReader::new(file)),
+        // Kernels built without support for loadable modules do not have the
+        // file at all, so there is nothing to list.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error),
+    };
+
+    // Paths are just an addition to the core information, so we do not want
+    // to fail if they are not available.
+    let paths = crate::os::kernel_version()
+        .and_then(|version| {
+            let dir_path = Path::new(LIB_MODULES_PATH).join(version);
+            let file = std::fs::File::open(dir_path.join("modules.dep"))?;
+            parse_modules_dep(std::io::BufReader::new(file), &dir_path)
+        })
+        .unwrap_or_default();
+
+    Ok(Modules {
+        lines: file.map(std::io::BufRead::lines),
+        paths,
+    })
+}
+
+/// Iterator over kernel modules loaded on the system.
+pub struct Modules {
+    /// Lines of the `/proc/modules` file (if it exists).
+    lines: Option<std::io::Lines<std::io::BufReader<std::fs::File>>>,
+    /// Paths to module files indexed by module names.
+    paths: HashMap<OsString, PathBuf>,
+}
+
+impl Iterator for Modules {
+
+    type Item = std::io::Result<Module>;
+
+    fn next(&mut self) -> Option<std::io::Result<Module>> {
+        let line = match self.lines.as_mut()?.next()? {
+            Ok(line) => line,
+            Err(error) => return Some(Err(error)),
+        };
+
+        let mut module = match parse_proc_module(&line) {
+            Ok(module) => module,
+            Err(error) => return Some(Err(error)),
+        };
+
+        module.path = self.paths.get(&module.name).cloned();
+
+        let sys_path = Path::new(SYS_MODULE_PATH).join(&module.name);
+        module.version = read_sys_attr(&sys_path.join("version"));
+        module.srcversion = read_sys_attr(&sys_path.join("srcversion"));
+        module.taint = read_sys_attr(&sys_path.join("taint"));
+
+        Some(Ok(module))
+    }
+}
+
+/// Parses a single line of the `/proc/modules` file.
+///
+/// Lines have the `name size refcount deps state address [taint]` format, e.g.
+/// `ext4 1036288 2 - Live 0xffffffffc0a1e000`.
+fn parse_proc_module(line: &str) -> std::io::Result<Module> {
+    let mut fields = line.split_ascii_whitespace();
+
+    let mut next_field = || fields.next().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, format! {
+            "truncated module entry: {line:?}"
+        })
+    });
+
+    let name = next_field()?;
+
+    let size = next_field()?.parse()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    // The reference count is `-` for modules that cannot be unloaded.
+    let ref_count = next_field()?.parse().ok();
+
+    let _deps = next_field()?;
+    let _state = next_field()?;
+
+    // The address is zero if kernel pointers are hidden from us.
+    let base_address = next_field()?.strip_prefix("0x")
+        .and_then(|address| u64::from_str_radix(address, 16).ok())
+        .filter(|address| *address != 0);
+
+    Ok(Module {
+        name: OsString::from(name),
+        size: Some(size),
+        ref_count,
+        base_address,
+        ..Module::default()
+    })
+}
+
+/// Parses the `modules.dep` file into paths of module files.
+///
+/// Relative paths are resolved against `dir_path` (the directory with modules
+/// of the running kernel).
+fn parse_modules_dep<R>(reader: R, dir_path: &Path) -> std::io::Result<HashMap<OsString, PathBuf>>
+where
+    R: std::io::BufRead,
+{
+    let mut paths = HashMap::new();
+
+    for line in reader.lines() {
+        let line = line?;
+
+        let Some((path, _)) = line.split_once(':') else {
+            continue;
+        };
+        let path = Path::new(path);
+
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        // Module files can be compressed (e.g. `ext4.ko.zst`). Dashes in file
+        // names are replaced with underscores in module names.
+        let Some((name, _)) = file_name.split_once(".ko") else {
+            continue;
+        };
+        let name = name.replace('-', "_");
+
+        paths.insert(OsString::from(name), dir_path.join(path));
+    }
+
+    Ok(paths)
+}
+
+/// Reads a (single-line) module attribute from sysfs.
+///
+/// `None` is returned if the attribute does not exist or is empty.
+fn read_sys_attr(path: &Path) -> Option<String> {
+    let value = std::fs::read_to_string(path).ok()?;
+    let value = value.trim();
+
+    if value.is_empty() {
+        None
+    } else {
+        Some(String::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn modules_ok() {
+        // The system might not support loadable modules at all, but listing
+        // should not fail anyway.
+        for module in modules().unwrap() {
+            let module = module.unwrap();
+            assert!(!module.name().is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_proc_module_fixture() {
+        let proc_modules = "\
+nf_tables 266240 0 - Live 0x0000000000000000
+xt_conntrack 16384 1 - Live 0x0000000000000000
+nf_conntrack 172032 2 xt_conntrack,nf_nat, Live 0x0000000000000000
+ext4 1036288 2 - Live 0x0000000000000000
+crc16 12288 1 ext4, Live 0x0000000000000000
+";
+
+        let names = proc_modules.lines()
+            .map(|line| parse_proc_module(line).unwrap().name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["nf_tables", "xt_conntrack", "nf_conntrack", "ext4", "crc16"]);
+    }
+
+    #[test]
+    fn parse_proc_module_ok() {
+        let module = parse_proc_module("ext4 1036288 2 - Live 0xffffffffc0a1e000")
+            .unwrap();
+
+        assert_eq!(module.name(), "ext4");
+        assert_eq!(module.size(), Some(1036288));
+        assert_eq!(module.ref_count(), Some(2));
+        assert_eq!(module.base_address(), Some(0xffffffffc0a1e000));
+    }
+
+    #[test]
+    fn parse_proc_module_deps_and_taint() {
+        let module = parse_proc_module("vboxnetflt 32768 0 vboxdrv, Live 0xffffffffc0f2c000 (OE)")
+            .unwrap();
+
+        assert_eq!(module.name(), "vboxnetflt");
+        assert_eq!(module.size(), Some(32768));
+        assert_eq!(module.ref_count(), Some(0));
+    }
+
+    #[test]
+    fn parse_proc_module_hidden_address() {
+        let module = parse_proc_module("crc16 12288 1 ext4, Live 0x0000000000000000")
+            .unwrap();
+
+        assert_eq!(module.base_address(), None);
+    }
+
+    #[test]
+    fn parse_proc_module_no_ref_count() {
+        let module = parse_proc_module("foo 4096 - - Live 0xffffffffc0000000")
+            .unwrap();
+
+        assert_eq!(module.ref_count(), None);
+    }
+
+    #[test]
+    fn parse_proc_module_truncated() {
+        let error = parse_proc_module("ext4 1036288 2")
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_proc_module_invalid_size() {
+        let error = parse_proc_module("ext4 foo 2 - Live 0xffffffffc0a1e000")
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_modules_dep_ok() {
+        let modules_dep = b"\
+kernel/fs/ext4/ext4.ko.zst: kernel/lib/crc16.ko.zst kernel/fs/mbcache.ko.zst
+kernel/lib/crc16.ko.zst:
+kernel/drivers/hid/hid-generic.ko:
+/opt/dkms/vboxdrv.ko.xz:
+";
+
+        let dir_path = Path::new("/lib/modules/6.1.0-18-amd64");
+        let paths = parse_modules_dep(&modules_dep[..], dir_path)
+            .unwrap();
+
+        assert_eq!(paths.len(), 4);
+        assert_eq!(paths[&OsString::from("ext4")], dir_path.join("kernel/fs/ext4/ext4.ko.zst"));
+        assert_eq!(paths[&OsString::from("crc16")], dir_path.join("kernel/lib/crc16.ko.zst"));
+        assert_eq!(paths[&OsString::from("hid_generic")], dir_path.join("kernel/drivers/hid/hid-generic.ko"));
+        assert_eq!(paths[&OsString::from("vboxdrv")], PathBuf::from("/opt/dkms/vboxdrv.ko.xz"));
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::c_void;
+
+use crate::kmod::Module;
+
+/// Returns an iterator over all kernel modules loaded on the system.
+pub fn modules() -> std::io::Result<impl Iterator<Item = std::io::Result<Module>>> {
+    // SAFETY: Passing null pointers requests information about all loaded
+    // extensions with all the keys [1]. The returned dictionary is owned by
+    // us and released when the wrapper is dropped.
+    //
+    // [1]: https://developer.apple.com/documentation/kernel/1576174-oskextcopyloadedkextinfo
+    let info = unsafe {
+        OSKextCopyLoadedKextInfo(std::ptr::null(), std::ptr::null())
+    };
+    if info.is_null() {
+        return Err(std::io::Error::other("failed to obtain loaded kernel extensions"));
+    }
+    let info = Owned(info);
+
+    // SAFETY: The dictionary is valid (it was checked for null above).
+    let count = unsafe { CFDictionaryGetCount(info.0) };
+
+    let mut values = vec![std::ptr::null(); count as usize];
+
+    // SAFETY: The dictionary is valid and the buffer for values has exactly as
+    // many elements as there are entries in the dictionary. We do not care
+    // about keys (they are bundle identifiers which are also part of values)
+    // and we are allowed to pass null for them.
+    unsafe {
+        CFDictionaryGetKeysAndValues(info.0, std::ptr::null_mut(), values.as_mut_ptr());
+    }
+
+    let keys = Keys::new()?;
+
+    // SAFETY: Values are owned by the dictionary which outlives this call.
+    let modules = values.into_iter()
+        .map(|value| unsafe { module(value, &keys) })
+        .collect::<Vec<_>>();
+
+    Ok(modules.into_iter())
+}
+
+/// Converts the information dictionary about a single extension to a module.
+///
+/// # Safety
+///
+/// `value` must be a valid Core Foundation object.
+unsafe fn module(value: *const c_void, keys: &Keys) -> std::io::Result<Module> {
+    // SAFETY: The value is a valid object as required by the caller.
+    if unsafe { CFGetTypeID(value) != CFDictionaryGetTypeID() } {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "kernel extension information is not a dictionary",
+        ));
+    }
+
+    // SAFETY: We verified that the value is a dictionary.
+    let Some(name) = (unsafe { dict_string(value, &keys.identifier) }) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "kernel extension without bundle identifier",
+        ));
+    };
+
+    // SAFETY: We verified that the value is a dictionary.
+    unsafe {
+        let path = dict_string(value, &keys.executable_path)
+            .or_else(|| dict_string(value, &keys.path));
+
+        Ok(Module {
+            name: name.into(),
+            size: dict_u64(value, &keys.load_size),
+            ref_count: dict_u64(value, &keys.retain_count),
+            // The address is zero if kernel pointers are hidden from us.
+            base_address: dict_u64(value, &keys.load_address)
+                .filter(|address| *address != 0),
+            path: path.map(std::path::PathBuf::from),
+            version: dict_string(value, &keys.version),
+        })
+    }
+}
+
+/// Returns the string value for the given key of the dictionary.
+///
+/// `None` is returned if there is no such key or the value is not a string.
+///
+/// # Safety
+///
+/// `dict` must be a valid `CFDictionary` object.
+unsafe fn dict_string(dict: *const c_void, key: &Owned) -> Option<String> {
+    // SAFETY: The dictionary is valid as required by the caller and the key is
+    // a valid string. The value (if any) is owned by the dictionary.
+    let value = unsafe { CFDictionaryGetValue(dict, key.0) };
+    if value.is_null() || unsafe { CFGetTypeID(value) != CFStringGetTypeID() } {
+        return None;
+    }
+
+    // SAFETY: We verified that the value is a string.
+    let len = unsafe { CFStringGetLength(value) };
+    // SAFETY: This is a pure computation without any preconditions.
+    let size = unsafe { CFStringGetMaximumSizeForEncoding(len, K_CF_STRING_ENCODING_UTF8) } + 1;
+
+    let mut buf = vec![0u8; size as usize];
+
+    // SAFETY: We pass a buffer that is big enough to hold the string in UTF-8
+    // together with the null terminator.
+    let status = unsafe {
+        CFStringGetCString(value, buf.as_mut_ptr().cast(), size, K_CF_STRING_ENCODING_UTF8)
+    };
+    if status == 0 {
+        return None;
+    }
+
+    let len = buf.iter().position(|byte| *byte == 0).unwrap_or(buf.len());
+    buf.truncate(len);
+
+    String::from_utf8(buf).ok()
+}
+
+/// Returns the integer value for the given key of the dictionary.
+///
+/// `None` is returned if there is no such key or the value is not a number.
+///
+/// # Safety
+///
+/// `dict` must be a valid `CFDictionary` object.
+unsafe fn dict_u64(dict: *const c_void, key: &Owned) -> Option<u64> {
+    // SAFETY: The dictionary is valid as required by the caller and the key is
+    // a valid string. The value (if any) is owned by the dictionary.
+    let value = unsafe { CFDictionaryGetValue(dict, key.0) };
+    if value.is_null() || unsafe { CFGetTypeID(value) != CFNumberGetTypeID() } {
+        return None;
+    }
+
+    let mut result = 0i64;
+
+    // SAFETY: We verified that the value is a number and we pass a pointer to
+    // a 64-bit integer as required for the requested number type.
+    let status = unsafe {
+        CFNumberGetValue(value, K_CF_NUMBER_SINT64_TYPE, (&mut result as *mut i64).cast())
+    };
+    if status == 0 {
+        return None;
+    }
+
+    // Addresses are reported as signed integers (that is the widest integer
+    // type Core Foundation supports), so we reinterpret them.
+    Some(result as u64)
+}
+
+/// Keys of the extension information dictionaries that we are interested in.
+struct Keys {
+    identifier: Owned,
+    version: Owned,
+    path: Owned,
+    executable_path: Owned,
+    load_address: Owned,
+    load_size: Owned,
+    retain_count: Owned,
+}
+
+impl Keys {
+
+    /// Creates Core Foundation strings for all the keys.
+    fn new() -> std::io::Result<Keys> {
+        Ok(Keys {
+            identifier: cf_string("CFBundleIdentifier")?,
+            version: cf_string("CFBundleVersion")?,
+            path: cf_string("OSBundlePath")?,
+            executable_path: cf_string("OSBundleExecutablePath")?,
+            load_address: cf_string("OSBundleLoadAddress")?,
+            load_size: cf_string("OSBundleLoadSize")?,
+            retain_count: cf_string("OSBundleRetainCount")?,
+        })
+    }
+}
+
+/// Creates a Core Foundation string from the given Rust string.
+fn cf_string(string: &str) -> std::io::Result<Owned> {
+    // SAFETY: We pass a valid buffer together with its length. The returned
+    // string is owned by us and released when the wrapper is dropped.
+    let result = unsafe {
+        CFStringCreateWithBytes(
+            std::ptr::null(),
+            string.as_ptr(),
+            string.len() as isize,
+            K_CF_STRING_ENCODING_UTF8,
+            0,
+        )
+    };
+    if result.is_null() {
+        return Err(std::io::Error::other("failed to create string"));
+    }
+
+    Ok(Owned(result))
+}
+
+/// Owned Core Foundation object released when dropped.
+struct Owned(*const c_void);
+
+impl Drop for Owned {
+
+    fn drop(&mut self) {
+        // SAFETY: The object is owned by us and was checked for null when the
+        // wrapper was created.
+        unsafe {
+            CFRelease(self.0);
+        }
+    }
+}
+
+/// Identifier of the UTF-8 encoding (`kCFStringEncodingUTF8`).
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x08000100;
+
+/// Identifier of the 64-bit signed integer number type (`kCFNumberSInt64Type`).
+const K_CF_NUMBER_SINT64_TYPE: isize = 4;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+    fn CFGetTypeID(cf: *const c_void) -> usize;
+    fn CFDictionaryGetTypeID() -> usize;
+    fn CFNumberGetTypeID() -> usize;
+    fn CFStringGetTypeID() -> usize;
+    fn CFDictionaryGetCount(dict: *const c_void) -> isize;
+    fn CFDictionaryGetKeysAndValues(
+        dict: *const c_void,
+        keys: *mut *const c_void,
+        values: *mut *const c_void,
+    );
+    fn CFDictionaryGetValue(dict: *const c_void, key: *const c_void) -> *const c_void;
+    fn CFNumberGetValue(number: *const c_void, r#type: isize, value: *mut c_void) -> u8;
+    fn CFStringCreateWithBytes(
+        alloc: *const c_void,
+        bytes: *const u8,
+        len: isize,
+        encoding: u32,
+        is_external: u8,
+    ) -> *const c_void;
+    fn CFStringGetLength(string: *const c_void) -> isize;
+    fn CFStringGetMaximumSizeForEncoding(len: isize, encoding: u32) -> isize;
+    fn CFStringGetCString(
+        string: *const c_void,
+        buf: *mut std::ffi::c_char,
+        size: isize,
+        encoding: u32,
+    ) -> u8;
+}
+
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn OSKextCopyLoadedKextInfo(
+        identifiers: *const c_void,
+        keys: *const c_void,
+    ) -> *const c_void;
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn modules_ok() {
+        let modules = modules().unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        // The kernel itself is reported as one of the extensions.
+        assert!(modules.iter().any(|module| module.name() == "com.apple.kpi.bsd"));
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::{OsStrExt as _, OsStringExt as _};
+
+use windows_sys::Win32::Foundation::*;
+use windows_sys::Win32::System::ProcessStatus::*;
+
+use crate::kmod::Module;
+
+/// Returns an iterator over all kernel modules loaded on the system.
+pub fn modules() -> std::io::Result<impl Iterator<Item = std::io::Result<Module>>> {
+    // There are usually a few hundred drivers loaded, so we start with a buffer
+    // that should be more than enough and grow it if it is not.
+    let mut bases = vec![std::ptr::null_mut(); 1024];
+
+    loop {
+        let size = (bases.len() * std::mem::size_of::<*mut std::ffi::c_void>()) as u32;
+        let mut size_needed = 0;
+
+        // SAFETY: We pass a buffer together with its size in bytes [1]. The
+        // function will not write past it and reports how many bytes it needs.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-enumdevicedrivers
+        let status = unsafe {
+            EnumDeviceDrivers(bases.as_mut_ptr(), size, &mut size_needed)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let count = size_needed as usize / std::mem::size_of::<*mut std::ffi::c_void>();
+        if size_needed <= size {
+            bases.truncate(count);
+            break;
+        }
+
+        // More drivers could have been loaded in the meantime, so we leave some
+        // room for them.
+        bases.resize(count + 64, std::ptr::null_mut());
+    }
+
+    Ok(bases.into_iter().map(module))
+}
+
+/// Returns information about the driver loaded at the given base address.
+fn module(base: *mut std::ffi::c_void) -> std::io::Result<Module> {
+    // Unprivileged callers get all-zero addresses, which makes it impossible to
+    // ask about the drivers.
+    if base.is_null() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "driver base address not available",
+        ));
+    }
+
+    // SAFETY: We pass a base address obtained from `EnumDeviceDrivers` [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getdevicedriverbasenamew
+    let name = query_string(|buf, len| unsafe {
+        GetDeviceDriverBaseNameW(base, buf, len)
+    })?;
+
+    // SAFETY: We pass a base address obtained from `EnumDeviceDrivers` [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getdevicedriverfilenamew
+    let path = query_string(|buf, len| unsafe {
+        GetDeviceDriverFileNameW(base, buf, len)
+    }).ok().map(|path| std::path::PathBuf::from(normalize_path(&path)));
+
+    Ok(Module {
+        name,
+        base_address: Some(base as u64),
+        path,
+        ..Module::default()
+    })
+}
+
+/// Calls a function filling a wide string buffer until the buffer is big
+/// enough to fit the whole string.
+///
+/// The function is expected to behave like `GetDeviceDriverBaseNameW`: return
+/// the number of characters written (truncating the string if needed) or zero
+/// in case of an error.
+fn query_string<F>(mut func: F) -> std::io::Result<OsString>
+where
+    F: FnMut(*mut u16, u32) -> u32,
+{
+    let mut buf = vec![0u16; MAX_PATH as usize];
+
+    loop {
+        let len = func(buf.as_mut_ptr(), buf.len() as u32) as usize;
+        if len == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // If the string fills the whole buffer, it might have been truncated.
+        if len < buf.len() {
+            return Ok(OsString::from_wide(&buf[..len]));
+        }
+
+        buf.resize(buf.len() * 2, 0);
+    }
+}
+
+/// Converts a driver path as reported by the kernel to a Win32 path.
+///
+/// Driver paths are usually either relative to the `\SystemRoot\` object (e.g.
+/// `\SystemRoot\System32\drivers\ACPI.sys`) or are DOS device paths (e.g.
+/// `\??\C:\Windows\System32\drivers\foo.sys`).
+fn normalize_path(path: &OsStr) -> OsString {
+    let wide = path.encode_wide().collect::<Vec<u16>>();
+
+    let strip = |prefix: &str| -> Option<&[u16]> {
+        let prefix = prefix.encode_utf16().collect::<Vec<u16>>();
+        if wide.len() < prefix.len() {
+            return None;
+        }
+
+        let (head, tail) = wide.split_at(prefix.len());
+        if String::from_utf16_lossy(head).eq_ignore_ascii_case(&String::from_utf16_lossy(&prefix)) {
+            Some(tail)
+        } else {
+            None
+        }
+    };
+
+    if let Some(rest) = strip("\\??\\") {
+        return OsString::from_wide(rest);
+    }
+
+    if let Some(rest) = strip("\\SystemRoot\\") {
+        let mut result = std::env::var_os("SystemRoot")
+            .unwrap_or_else(|| OsString::from("C:\\Windows"));
+        result.push("\\");
+        result.push(OsString::from_wide(rest));
+        return result;
+    }
+
+    path.to_os_string()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn modules_ok() {
+        let modules = modules().unwrap()
+            // Without sufficient privileges we cannot query the drivers.
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+
+        for module in &modules {
+            assert!(!module.name().is_empty());
+        }
+
+        // The kernel image itself is listed as one of the drivers.
+        if !modules.is_empty() {
+            assert!(modules.iter().any(|module| {
+                module.name().eq_ignore_ascii_case("ntoskrnl.exe")
+            }));
+        }
+    }
+
+    #[test]
+    fn normalize_path_dos_device() {
+        assert_eq! {
+            normalize_path(OsStr::new("\\??\\C:\\Program Files\\Foo\\foo.sys")),
+            OsString::from("C:\\Program Files\\Foo\\foo.sys")
+        };
+    }
+
+    #[test]
+    fn normalize_path_system_root() {
+        let mut expected = std::env::var_os("SystemRoot").unwrap();
+        expected.push("\\System32\\drivers\\ACPI.sys");
+
+        assert_eq! {
+            normalize_path(OsStr::new("\\SystemRoot\\System32\\drivers\\ACPI.sys")),
+            expected
+        };
+    }
+
+    #[test]
+    fn normalize_path_other() {
+        assert_eq! {
+            normalize_path(OsStr::new("C:\\Windows\\System32\\drivers\\foo.sys")),
+            OsString::from("C:\\Windows\\System32\\drivers\\foo.sys")
+        };
+    }
+}
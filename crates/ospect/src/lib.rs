@@ -5,7 +5,18 @@
 
 mod libc;
 
+pub mod disk;
+#[cfg(target_family = "windows")]
+pub mod dns;
 pub mod fs;
+pub mod kmod;
 pub mod net;
 pub mod os;
+#[cfg(target_family = "windows")]
+pub mod pipe;
 pub mod proc;
+#[cfg(target_family = "windows")]
+pub mod service;
+#[cfg(target_family = "windows")]
+pub mod token;
+pub mod user;
@@ -291,6 +291,18 @@ mod macos {
         pub fi_type: i32,
     }
 
+    // https://opensource.apple.com/source/xnu/xnu-1228.0.2/bsd/sys/proc_info.h.auto.html
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    pub struct vnode_fdinfowithpath {
+        pub pfi: proc_fileinfo,
+        pub pvip: libc::vnode_info_path,
+    }
+
+    // https://opensource.apple.com/source/xnu/xnu-1228.0.2/bsd/sys/fcntl.h.auto.html
+    pub const FREAD: u32 = 0x0001;
+    pub const FWRITE: u32 = 0x0002;
+
     // https://opensource.apple.com/source/xnu/xnu-1228.0.2/bsd/sys/proc_info.h.auto.html
     #[derive(Clone, Copy)]
     #[repr(C)]
@@ -525,7 +537,82 @@ mod macos {
         pub kp_proc: extern_proc,
         pub kp_eproc: eproc,
     }
+
+    pub const VM_REGION_BASIC_INFO_64: libc::c_int = 9;
+
+    // The original header wraps the definition in `#pragma pack(4)`.
+    #[derive(Clone, Copy)]
+    #[repr(C, packed(4))]
+    pub struct vm_region_basic_info_64 {
+        /// Current protection of the region.
+        pub protection: libc::vm_prot_t,
+        /// Maximum protection of the region.
+        pub max_protection: libc::vm_prot_t,
+        /// Inheritance attribute of the region.
+        pub inheritance: libc::vm_inherit_t,
+        /// Whether the region is shared.
+        pub shared: libc::boolean_t,
+        /// Whether the region is reserved.
+        pub reserved: libc::boolean_t,
+        /// Offset into the object that the region maps.
+        pub offset: libc::memory_object_offset_t,
+        /// Expected paging behaviour of the region.
+        pub behavior: libc::c_int,
+        /// Number of times the region is wired by the user.
+        pub user_wired_count: libc::c_ushort,
+    }
+
+    pub const VM_REGION_BASIC_INFO_COUNT_64: libc::mach_msg_type_number_t = {
+        (std::mem::size_of::<vm_region_basic_info_64>() / std::mem::size_of::<libc::c_int>()) as u32
+    };
+
+    extern "C" {
+
+        /// Returns information about the first memory region at or after the
+        /// given address in the address space of the task.
+        pub fn mach_vm_region(
+            target_task: libc::vm_map_t,
+            address: *mut libc::mach_vm_address_t,
+            size: *mut libc::mach_vm_size_t,
+            flavor: libc::c_int,
+            info: *mut libc::c_int,
+            info_count: *mut libc::mach_msg_type_number_t,
+            object_name: *mut libc::mach_port_t,
+        ) -> libc::kern_return_t;
+
+        /// Reads memory of the task into a buffer of the calling task.
+        pub fn mach_vm_read_overwrite(
+            target_task: libc::vm_map_t,
+            address: libc::mach_vm_address_t,
+            size: libc::mach_vm_size_t,
+            data: libc::mach_vm_address_t,
+            out_size: *mut libc::mach_vm_size_t,
+        ) -> libc::kern_return_t;
+
+        /// Releases a user reference to the port right.
+        pub fn mach_port_deallocate(
+            task: libc::mach_port_t,
+            name: libc::mach_port_t,
+        ) -> libc::kern_return_t;
+    }
+}
+
+#[cfg(target_family = "unix")]
+mod unix {
+
+    extern "C" {
+
+        /// Initializes timezone conversion information.
+        ///
+        /// See the [man page][1] for more details.
+        ///
+        /// [1]: https://man7.org/linux/man-pages/man3/tzset.3.html
+        pub fn tzset();
+    }
 }
 
 #[cfg(target_os = "macos")]
 pub use self::macos::*;
+
+#[cfg(target_family = "unix")]
+pub use self::unix::*;
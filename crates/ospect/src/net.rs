@@ -59,22 +59,51 @@ impl From<[u8; 6]> for MacAddr {
 pub struct Interface {
     /// A name of this interface as reported by the system.
     name: std::ffi::OsString,
-    /// IP addresses associated with this interface.
-    ip_addrs: Vec<std::net::IpAddr>,
+    /// An index of this interface as reported by the system (if known).
+    index: Option<u32>,
+    /// Addresses associated with this interface.
+    addrs: Vec<InterfaceAddr>,
     /// The MAC address associated with this interface.
     mac_addr: Option<MacAddr>,
+    /// The maximum transmission unit of this interface (if known).
+    mtu: Option<u32>,
+    /// Status flags of this interface.
+    flags: InterfaceFlags,
 }
 
 impl Interface {
 
+    /// Creates a new interface with the given name and no other information.
+    fn new(name: std::ffi::OsString) -> Interface {
+        Interface {
+            name,
+            index: None,
+            addrs: Vec::new(),
+            mac_addr: None,
+            mtu: None,
+            flags: InterfaceFlags::default(),
+        }
+    }
+
     /// Returns the name of this interface as reported by the system.
     pub fn name(&self) -> &std::ffi::OsStr {
         self.name.as_os_str()
     }
 
+    /// Returns the index of this interface as reported by the system.
+    pub fn index(&self) -> Option<u32> {
+        self.index
+    }
+
+    /// Returns the addresses (together with their prefixes) associated with
+    /// this interface.
+    pub fn addrs(&self) -> impl Iterator<Item = &InterfaceAddr> + '_ {
+        self.addrs.iter()
+    }
+
     /// Returns the IP addresses associated with this interface.
     pub fn ip_addrs(&self) -> impl Iterator<Item = &std::net::IpAddr> + '_ {
-        self.ip_addrs.iter()
+        self.addrs().map(|addr| &addr.ip_addr)
     }
 
     /// Returns the IPv4 addresses associated with this interface.
@@ -97,6 +126,126 @@ impl Interface {
     pub fn mac_addr(&self) -> Option<&MacAddr> {
         self.mac_addr.as_ref()
     }
+
+    /// Returns the maximum transmission unit (in bytes) of this interface.
+    pub fn mtu(&self) -> Option<u32> {
+        self.mtu
+    }
+
+    /// Returns whether the interface is up (administratively enabled).
+    pub fn is_up(&self) -> bool {
+        self.flags.up
+    }
+
+    /// Returns whether the interface is operational (e.g. has a carrier).
+    pub fn is_running(&self) -> bool {
+        self.flags.running
+    }
+
+    /// Returns whether the interface is a loopback interface.
+    pub fn is_loopback(&self) -> bool {
+        self.flags.loopback
+    }
+}
+
+/// Status flags of a network interface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct InterfaceFlags {
+    /// Whether the interface is up (administratively enabled).
+    up: bool,
+    /// Whether the interface is operational.
+    running: bool,
+    /// Whether the interface is a loopback interface.
+    loopback: bool,
+}
+
+/// An address associated with a network interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InterfaceAddr {
+    /// The IP address itself.
+    ip_addr: std::net::IpAddr,
+    /// The length (in bits) of the network prefix of the address (if known).
+    prefix_len: Option<u8>,
+}
+
+impl InterfaceAddr {
+
+    /// Returns the IP address.
+    pub fn ip_addr(&self) -> std::net::IpAddr {
+        self.ip_addr
+    }
+
+    /// Returns the length (in bits) of the network prefix of the address.
+    pub fn prefix_len(&self) -> Option<u8> {
+        self.prefix_len
+    }
+
+    /// Returns the scope in which the address is valid.
+    pub fn scope(&self) -> AddrScope {
+        use std::net::IpAddr::*;
+
+        if self.ip_addr.is_loopback() {
+            return AddrScope::Host;
+        }
+
+        let is_link_local = match self.ip_addr {
+            V4(ipv4_addr) => ipv4_addr.is_link_local(),
+            // `fe80::/10` as defined in RFC 4291 [1].
+            //
+            // [1]: https://www.rfc-editor.org/rfc/rfc4291#section-2.5.6
+            V6(ipv6_addr) => ipv6_addr.segments()[0] & 0xffc0 == 0xfe80,
+        };
+
+        if is_link_local {
+            AddrScope::Link
+        } else {
+            AddrScope::Global
+        }
+    }
+}
+
+impl From<std::net::IpAddr> for InterfaceAddr {
+
+    fn from(ip_addr: std::net::IpAddr) -> InterfaceAddr {
+        InterfaceAddr {
+            ip_addr,
+            prefix_len: None,
+        }
+    }
+}
+
+/// A scope in which an interface address is valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddrScope {
+    /// The address is valid only within the host (i.e. a loopback address).
+    Host,
+    /// The address is valid only on the link it is assigned to.
+    Link,
+    /// The address is valid everywhere.
+    Global,
+}
+
+/// Counts the prefix length (number of leading one bits) of the given netmask.
+///
+/// [`None`] is returned if the netmask is not contiguous.
+#[cfg(target_family = "unix")]
+fn prefix_len(netmask: &[u8]) -> Option<u8> {
+    let mut len = 0;
+    let mut bits = netmask.iter()
+        .flat_map(|octet| (0..8).rev().map(move |i| octet & (1 << i) != 0));
+
+    for bit in &mut bits {
+        if !bit {
+            break;
+        }
+        len += 1;
+    }
+
+    if bits.any(|bit| bit) {
+        return None;
+    }
+
+    Some(len)
 }
 
 /// Collects information about available network interfaces.
@@ -631,11 +780,230 @@ pub fn all_connections() -> std::io::Result<impl Iterator<Item = std::io::Result
     Ok(tcp.chain(udp))
 }
 
+/// An entry of the neighbor cache (ARP for IPv4 and NDP for IPv6).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Neighbor {
+    /// IP address of the neighbor.
+    ip_addr: std::net::IpAddr,
+    /// MAC address of the neighbor (if resolved).
+    mac_addr: Option<MacAddr>,
+    /// Index of the interface through which the neighbor is reachable.
+    iface_index: u32,
+    /// State of the entry.
+    state: NeighborState,
+}
+
+impl Neighbor {
+
+    /// Returns the IP address of the neighbor.
+    pub fn ip_addr(&self) -> std::net::IpAddr {
+        self.ip_addr
+    }
+
+    /// Returns the MAC address of the neighbor.
+    ///
+    /// The address is not available for entries that are not resolved (yet).
+    pub fn mac_addr(&self) -> Option<&MacAddr> {
+        self.mac_addr.as_ref()
+    }
+
+    /// Returns the index of the interface through which the neighbor is
+    /// reachable.
+    pub fn iface_index(&self) -> u32 {
+        self.iface_index
+    }
+
+    /// Returns the state of the entry.
+    pub fn state(&self) -> NeighborState {
+        self.state
+    }
+}
+
+/// A state of a neighbor cache entry.
+///
+/// The states follow the neighbor unreachability detection of RFC 4861 [1] as
+/// all the supported systems use them (for ARP as well). Not all systems report
+/// all the states.
+///
+/// [1]: https://www.rfc-editor.org/rfc/rfc4861#section-7.3.2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NeighborState {
+    /// The state is not known.
+    Unknown,
+    /// Address resolution is in progress.
+    Incomplete,
+    /// The neighbor is known to be reachable.
+    Reachable,
+    /// The neighbor is no longer known to be reachable.
+    Stale,
+    /// Waiting before sending a reachability probe.
+    Delay,
+    /// Reachability probes are being sent.
+    Probe,
+    /// Address resolution failed.
+    Failed,
+    /// The entry is static (configured manually).
+    Permanent,
+}
+
+/// An entry of the routing table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Route {
+    /// Destination network address of the route.
+    dest_addr: std::net::IpAddr,
+    /// Length (in bits) of the destination network prefix.
+    dest_prefix_len: u8,
+    /// Address of the next hop (if the destination is not directly attached).
+    gateway: Option<std::net::IpAddr>,
+    /// Index of the outgoing interface (if known).
+    iface_index: Option<u32>,
+    /// Metric (preference) of the route (if known).
+    metric: Option<u32>,
+}
+
+impl Route {
+
+    /// Returns the destination network address of the route.
+    pub fn dest_addr(&self) -> std::net::IpAddr {
+        self.dest_addr
+    }
+
+    /// Returns the length (in bits) of the destination network prefix.
+    ///
+    /// Default routes have prefix length of zero.
+    pub fn dest_prefix_len(&self) -> u8 {
+        self.dest_prefix_len
+    }
+
+    /// Returns the address of the next hop.
+    ///
+    /// Routes to directly attached networks have no gateway.
+    pub fn gateway(&self) -> Option<std::net::IpAddr> {
+        self.gateway
+    }
+
+    /// Returns the index of the outgoing interface.
+    pub fn iface_index(&self) -> Option<u32> {
+        self.iface_index
+    }
+
+    /// Returns the metric (preference) of the route.
+    ///
+    /// Lower metrics are preferred. The meaning of particular values is system
+    /// specific.
+    pub fn metric(&self) -> Option<u32> {
+        self.metric
+    }
+}
+
+/// Returns an iterator over entries of the neighbor (ARP and NDP) cache.
+///
+/// # Errors
+///
+/// This function will fail if there was some kind of issue (e.g. insufficient
+/// permissions to make certain system calls) during information collection.
+/// Each yielded item can also be an error if the information for a particular
+/// entry was malformed.
+///
+/// # Examples
+///
+/// ```
+/// let neighbors = ospect::net::neighbors()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for neighbor in neighbors {
+///     println!("{} ({:?})", neighbor.ip_addr(), neighbor.state());
+/// }
+/// ```
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    self::sys::neighbors()
+}
+
+/// Returns an iterator over entries of the (IPv4 and IPv6) routing table.
+///
+/// # Errors
+///
+/// This function will fail if there was some kind of issue (e.g. insufficient
+/// permissions to make certain system calls) during information collection.
+/// Each yielded item can also be an error if the information for a particular
+/// entry was malformed.
+///
+/// # Examples
+///
+/// ```
+/// let routes = ospect::net::routes()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for route in routes {
+///     println!("{}/{}", route.dest_addr(), route.dest_prefix_len());
+/// }
+/// ```
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    self::sys::routes()
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
 
+    #[test]
+    fn interface_addr_scope_host() {
+        let addr = InterfaceAddr::from(std::net::IpAddr::from([127, 0, 0, 1]));
+        assert_eq!(addr.scope(), AddrScope::Host);
+
+        let addr = InterfaceAddr::from(std::net::IpAddr::from(std::net::Ipv6Addr::LOCALHOST));
+        assert_eq!(addr.scope(), AddrScope::Host);
+    }
+
+    #[test]
+    fn interface_addr_scope_link() {
+        let addr = InterfaceAddr::from(std::net::IpAddr::from([169, 254, 1, 1]));
+        assert_eq!(addr.scope(), AddrScope::Link);
+
+        let ipv6_addr = "fe80::1ff:fe23:4567:890a".parse::<std::net::Ipv6Addr>()
+            .unwrap();
+        let addr = InterfaceAddr::from(std::net::IpAddr::from(ipv6_addr));
+        assert_eq!(addr.scope(), AddrScope::Link);
+    }
+
+    #[test]
+    fn interface_addr_scope_global() {
+        let addr = InterfaceAddr::from(std::net::IpAddr::from([8, 8, 8, 8]));
+        assert_eq!(addr.scope(), AddrScope::Global);
+
+        let ipv6_addr = "2001:db8::1".parse::<std::net::Ipv6Addr>()
+            .unwrap();
+        let addr = InterfaceAddr::from(std::net::IpAddr::from(ipv6_addr));
+        assert_eq!(addr.scope(), AddrScope::Global);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn prefix_len_ipv4() {
+        assert_eq!(prefix_len(&[255, 255, 255, 0]), Some(24));
+        assert_eq!(prefix_len(&[255, 255, 240, 0]), Some(20));
+        assert_eq!(prefix_len(&[255, 255, 255, 255]), Some(32));
+        assert_eq!(prefix_len(&[0, 0, 0, 0]), Some(0));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn prefix_len_ipv6() {
+        let mut netmask = [0; 16];
+        netmask[..8].fill(0xff);
+
+        assert_eq!(prefix_len(&netmask), Some(64));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn prefix_len_non_contiguous() {
+        assert_eq!(prefix_len(&[255, 0, 255, 0]), None);
+    }
+
     // TODO(@panhania): Add tests where we connect to the TCP listener to check
     // TCP for an established collection state (not only for "listening" as we
     // do now).
@@ -4,6 +4,8 @@
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
 mod conn;
+mod netlink;
+mod table;
 
 use super::*;
 
@@ -54,6 +56,32 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
         // the `continue` statements in the code below.
         addr_iter = addr.ifa_next;
 
+        // SAFETY: `ifa_name` is guaranteed to point to a null-terminated string
+        // with interface name.
+        let name_cstr = unsafe {
+            std::ffi::CStr::from_ptr(addr.ifa_name)
+        };
+        let name = std::ffi::OsStr::from_bytes(name_cstr.to_bytes());
+
+        // Note that we create the interface entry before looking at the address
+        // as there might be interfaces without any address (e.g. some of the
+        // tunnel interfaces are reported with only a null one).
+        let entry = ifaces.entry(name).or_insert_with(|| {
+            let mut iface = Interface::new(name.to_os_string());
+            iface.index = index(name_cstr);
+            iface.mtu = mtu(name_cstr).ok();
+
+            iface
+        });
+
+        // Flags of all the entries for the same interface should be the same
+        // so we can just overwrite them.
+        entry.flags = InterfaceFlags {
+            up: addr.ifa_flags & libc::IFF_UP as libc::c_uint != 0,
+            running: addr.ifa_flags & libc::IFF_RUNNING as libc::c_uint != 0,
+            loopback: addr.ifa_flags & libc::IFF_LOOPBACK as libc::c_uint != 0,
+        };
+
         // SAFETY: `ifa_ddr` is not guaranteed to be not null, so we have to
         // verify it. But if it is not null, it is guaranteed to point to valid
         // address instance.
@@ -62,17 +90,10 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
             None => continue,
         };
 
-        // SAFETY: `ifa_name` is guaranteed to point to a null-terminated string
-        // with interface name.
-        let name = std::ffi::OsStr::from_bytes(unsafe {
-            std::ffi::CStr::from_ptr(addr.ifa_name)
-        }.to_bytes());
-
-        let entry = ifaces.entry(name).or_insert(Interface {
-            name: name.to_os_string(),
-            ip_addrs: Vec::new(),
-            mac_addr: None,
-        });
+        // SAFETY: Similarly to `ifa_addr`, `ifa_netmask` is not guaranteed to
+        // be not null but if it is not, it is guaranteed to point to a valid
+        // address of the same family as `ifa_addr`.
+        let netmask = unsafe { addr.ifa_netmask.as_ref() };
 
         match i32::from(family) {
             libc::AF_INET => {
@@ -91,7 +112,22 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
                 let ipv4_addr_u32 = u32::from_be(ipv4_addr_u32);
 
                 let ipv4_addr = std::net::Ipv4Addr::from(ipv4_addr_u32);
-                entry.ip_addrs.push(ipv4_addr.into());
+
+                // SAFETY: The netmask is of the same family as the address, so
+                // the cast is safe as well. The netmask bytes are in network
+                // order, which is what we want for counting the leading ones.
+                let prefix_len = netmask.and_then(|netmask| {
+                    let netmask_u32 = unsafe {
+                        *(netmask as *const libc::sockaddr as *const libc::sockaddr_in)
+                    }.sin_addr.s_addr;
+
+                    prefix_len(&netmask_u32.to_ne_bytes())
+                });
+
+                entry.addrs.push(InterfaceAddr {
+                    ip_addr: ipv4_addr.into(),
+                    prefix_len,
+                });
             }
             libc::AF_INET6 => {
                 // SAFETY: For `AF_INET6` family, it is guaranteed that the
@@ -105,7 +141,20 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
 
                 let ipv6_addr = std::net::Ipv6Addr::from(ipv6_addr_octets);
 
-                entry.ip_addrs.push(ipv6_addr.into());
+                // SAFETY: The netmask is of the same family as the address, so
+                // the cast is safe as well.
+                let prefix_len = netmask.and_then(|netmask| {
+                    let netmask_octets = unsafe {
+                        *(netmask as *const libc::sockaddr as *const libc::sockaddr_in6)
+                    }.sin6_addr.s6_addr;
+
+                    prefix_len(&netmask_octets)
+                });
+
+                entry.addrs.push(InterfaceAddr {
+                    ip_addr: ipv6_addr.into(),
+                    prefix_len,
+                });
             },
             libc::AF_PACKET => {
                 // SAFETY: For `AF_PACKET family, it is guaranteed that the
@@ -162,6 +211,70 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
     Ok(ifaces.into_iter())
 }
 
+/// Returns the index of the interface with the given name.
+fn index(name: &std::ffi::CStr) -> Option<u32> {
+    // SAFETY: `if_nametoindex` [1] expects a null-terminated string which is
+    // guaranteed by the `CStr` type. On failure, zero is returned.
+    //
+    // [1]: https://man7.org/linux/man-pages/man3/if_nametoindex.3.html
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => None,
+        index => Some(index),
+    }
+}
+
+/// Returns the maximum transmission unit of the interface with the given name.
+fn mtu(name: &std::ffi::CStr) -> std::io::Result<u32> {
+    // SAFETY: Creating a socket is safe, we verify the result below.
+    let sock = unsafe {
+        libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0)
+    };
+    if sock < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: We just created the socket and we do not share it with anything
+    // else, so it is safe to take the ownership over it (and close it when it
+    // goes out of scope).
+    let sock = unsafe {
+        <std::os::fd::OwnedFd as std::os::fd::FromRawFd>::from_raw_fd(sock)
+    };
+
+    // SAFETY: `ifreq` is a plain C struct for which all zeros are valid.
+    let mut req = unsafe {
+        std::mem::zeroed::<libc::ifreq>()
+    };
+
+    let name = name.to_bytes_with_nul();
+    if name.len() > req.ifr_name.len() {
+        return Err(std::io::ErrorKind::InvalidInput.into());
+    }
+    for (dest, src) in req.ifr_name.iter_mut().zip(name) {
+        *dest = *src as libc::c_char;
+    }
+
+    // SAFETY: We pass a valid socket and a request with the interface name set
+    // as required by `SIOCGIFMTU` [1]. We verify the result below.
+    //
+    // [1]: https://man7.org/linux/man-pages/man7/netdevice.7.html
+    let code = unsafe {
+        use std::os::fd::AsRawFd as _;
+        libc::ioctl(sock.as_raw_fd(), libc::SIOCGIFMTU as _, &mut req)
+    };
+    if code < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: `SIOCGIFMTU` call succeeded, so the `ifru_mtu` variant of the
+    // union is the one that has been filled.
+    let mtu = unsafe {
+        req.ifr_ifru.ifru_mtu
+    };
+
+    u32::try_from(mtu)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
 /// Returns an iterator over IPv4 TCP connections for the specified process.
 pub fn tcp_v4_connections(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV4>>> {
     self::conn::tcp_v4(pid)
@@ -184,22 +297,40 @@ pub fn udp_v6_connections(pid: u32) -> std::io::Result<impl Iterator<Item = std:
 
 /// Returns an iterator over IPv4 TCP connections of all processes.
 pub fn all_tcp_v4_connections() -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV4>>> {
-    crate::net::unix::all_tcp_v4_connections()
+    self::conn::all_tcp_v4()
 }
 
 /// Returns an iterator over IPv6 TCP connections of all processes.
 pub fn all_tcp_v6_connections() -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV6>>> {
-    crate::net::unix::all_tcp_v6_connections()
+    self::conn::all_tcp_v6()
 }
 
 /// Returns an iterator over IPv4 UDP connections of all processes.
 pub fn all_udp_v4_connections() -> std::io::Result<impl Iterator<Item = std::io::Result<UdpConnectionV4>>> {
-    crate::net::unix::all_udp_v4_connections()
+    self::conn::all_udp_v4()
 }
 
 /// Returns an iterator over IPv6 UDP connections of all processes.
 pub fn all_udp_v6_connections() -> std::io::Result<impl Iterator<Item = std::io::Result<UdpConnectionV6>>> {
-    crate::net::unix::all_udp_v6_connections()
+    self::conn::all_udp_v6()
+}
+
+/// Returns an iterator over entries of the neighbor cache.
+///
+/// The entries are collected through [rtnetlink][1] `RTM_GETNEIGH` requests.
+///
+/// [1]: https://man7.org/linux/man-pages/man7/rtnetlink.7.html
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    self::table::neighbors()
+}
+
+/// Returns an iterator over entries of the routing table.
+///
+/// The entries are collected through [rtnetlink][1] `RTM_GETROUTE` requests.
+///
+/// [1]: https://man7.org/linux/man-pages/man7/rtnetlink.7.html
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    self::table::routes()
 }
 
 #[cfg(test)]
@@ -228,4 +359,27 @@ mod tests {
             loopback.mac_addr(), Some(&MacAddr::from([0, 0, 0, 0, 0, 0]))
         };
     }
+
+    #[test]
+    fn interfaces_loopback_details() {
+        let loopback = interfaces().unwrap()
+            .find(|iface| iface.name() == "lo")
+            .unwrap();
+
+        assert!(loopback.is_up());
+        assert!(loopback.is_loopback());
+        assert!(loopback.index().is_some());
+        assert!(loopback.mtu().is_some_and(|mtu| mtu > 0));
+
+        let ipv4_addr = loopback.addrs()
+            .find(|addr| addr.ip_addr() == std::net::Ipv4Addr::LOCALHOST)
+            .unwrap();
+        assert_eq!(ipv4_addr.prefix_len(), Some(8));
+        assert_eq!(ipv4_addr.scope(), AddrScope::Host);
+
+        let ipv6_addr = loopback.addrs()
+            .find(|addr| addr.ip_addr() == std::net::Ipv6Addr::LOCALHOST)
+            .unwrap();
+        assert_eq!(ipv6_addr.prefix_len(), Some(128));
+    }
 }
@@ -9,7 +9,7 @@ use crate::net::*;
 pub fn tcp_v4(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV4>>> {
     let path = format!("/proc/{pid}/net/tcp");
     Ok(TcpConnections {
-        pid,
+        owners: SocketOwners::of_process(pid)?,
         iter: Connections::new(path, parse_tcp_v4_connection)?,
     }.map(|conn| Ok(TcpConnectionV4::from_inner(conn?))))
 }
@@ -18,7 +18,7 @@ pub fn tcp_v4(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<
 pub fn tcp_v6(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV6>>> {
     let path = format!("/proc/{pid}/net/tcp6");
     Ok(TcpConnections {
-        pid,
+        owners: SocketOwners::of_process(pid)?,
         iter: Connections::new(path, parse_tcp_v6_connection)?,
     }.map(|conn| Ok(TcpConnectionV6::from_inner(conn?))))
 }
@@ -27,7 +27,7 @@ pub fn tcp_v6(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<
 pub fn udp_v4(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<UdpConnectionV4>>> {
     let path = format!("/proc/{pid}/net/udp");
     Ok(UdpConnections {
-        pid,
+        owners: SocketOwners::of_process(pid)?,
         iter: Connections::new(path, parse_udp_v4_connection)?,
     }.map(|conn| Ok(UdpConnectionV4::from_inner(conn?))))
 }
@@ -36,41 +36,85 @@ pub fn udp_v4(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<
 pub fn udp_v6(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<UdpConnectionV6>>> {
     let path = format!("/proc/{pid}/net/udp6");
     Ok(UdpConnections {
-        pid,
+        owners: SocketOwners::of_process(pid)?,
         iter: Connections::new(path, parse_udp_v6_connection)?,
     }.map(|conn| Ok(UdpConnectionV6::from_inner(conn?))))
 }
 
+/// Returns an iterator over IPv4 TCP connections of all processes.
+///
+/// Note that only connections in the network namespace of the current process
+/// are yielded.
+pub fn all_tcp_v4() -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV4>>> {
+    Ok(TcpConnections {
+        owners: SocketOwners::of_all_processes()?,
+        iter: Connections::new("/proc/net/tcp", parse_tcp_v4_connection)?,
+    }.map(|conn| Ok(TcpConnectionV4::from_inner(conn?))))
+}
+
+/// Returns an iterator over IPv6 TCP connections of all processes.
+///
+/// Note that only connections in the network namespace of the current process
+/// are yielded.
+pub fn all_tcp_v6() -> std::io::Result<impl Iterator<Item = std::io::Result<TcpConnectionV6>>> {
+    Ok(TcpConnections {
+        owners: SocketOwners::of_all_processes()?,
+        iter: Connections::new("/proc/net/tcp6", parse_tcp_v6_connection)?,
+    }.map(|conn| Ok(TcpConnectionV6::from_inner(conn?))))
+}
+
+/// Returns an iterator over IPv4 UDP connections of all processes.
+///
+/// Note that only connections in the network namespace of the current process
+/// are yielded.
+pub fn all_udp_v4() -> std::io::Result<impl Iterator<Item = std::io::Result<UdpConnectionV4>>> {
+    Ok(UdpConnections {
+        owners: SocketOwners::of_all_processes()?,
+        iter: Connections::new("/proc/net/udp", parse_udp_v4_connection)?,
+    }.map(|conn| Ok(UdpConnectionV4::from_inner(conn?))))
+}
+
+/// Returns an iterator over IPv6 UDP connections of all processes.
+///
+/// Note that only connections in the network namespace of the current process
+/// are yielded.
+pub fn all_udp_v6() -> std::io::Result<impl Iterator<Item = std::io::Result<UdpConnectionV6>>> {
+    Ok(UdpConnections {
+        owners: SocketOwners::of_all_processes()?,
+        iter: Connections::new("/proc/net/udp6", parse_udp_v6_connection)?,
+    }.map(|conn| Ok(UdpConnectionV6::from_inner(conn?))))
+}
+
 // TODO(rust-lang/rust#63063): Simplify as an alias to `impl`.
-/// Iterator over UDP connections of a particular process.
+/// Iterator over UDP connections owned by known processes.
 ///
-/// Instances of this iterator can be created using the [`udp_v4_connections`]
-/// and [`udp_v6_connections`] functions.
+/// Instances of this iterator can be created using the [`udp_v4`], [`udp_v6`],
+/// [`all_udp_v4`] and [`all_udp_v6`] functions.
 ///
 /// # Errors
 ///
 /// Each item yield by the iterator can be [`ParseConnectionError`] if the
 /// connection information returned by the system was malformed.
-pub struct UdpConnections<A> {
-    /// Identifier of the process that owns the yielded connections.
-    pid: u32,
+struct UdpConnections<A> {
+    /// Processes that own sockets of the yielded connections.
+    owners: SocketOwners,
     /// Underlying iterator over UDP connections.
     iter: Connections<UdpConnectionInner<A>>,
 }
 
 // TODO(rust-lang/rust#63063): Simplify as an alias to `impl`.
-/// Iterator over TCP connections of a particular process.
+/// Iterator over TCP connections owned by known processes.
 ///
-/// Instances of this iterator can be created using the [`tcp_v4_connections`]
-/// and [`tcp_v6_connections`] functions.
+/// Instances of this iterator can be created using the [`tcp_v4`], [`tcp_v6`],
+/// [`all_tcp_v4`] and [`all_tcp_v6`] functions.
 ///
 /// # Errors
 ///
 /// Each item yield by the iterator can be [`ParseConnectionError`] if the
 /// connection information returned by the system was malformed.
 struct TcpConnections<A> {
-    /// Identifier of the process that owns the yielded connections.
-    pid: u32,
+    /// Processes that own sockets of the yielded connections.
+    owners: SocketOwners,
     /// Underlying iterator over TCP connections.
     iter: Connections<TcpConnectionInner<A>>,
 }
@@ -79,12 +123,17 @@ impl<A> Iterator for TcpConnections<A> {
     type Item = std::io::Result<TcpConnectionInner<A>>;
 
     fn next(&mut self) -> Option<std::io::Result<TcpConnectionInner<A>>> {
-        let mut conn = self.iter.next()?;
-        if let Ok(conn) =  conn.as_mut() {
-            conn.pid = self.pid;
+        loop {
+            let (mut conn, inode) = match self.iter.next()? {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+
+            if let Some(pid) = self.owners.owner(inode) {
+                conn.pid = pid;
+                return Some(Ok(conn));
+            }
         }
-
-        Some(conn)
     }
 }
 
@@ -92,13 +141,111 @@ impl<A> Iterator for UdpConnections<A> {
     type Item = std::io::Result<UdpConnectionInner<A>>;
 
     fn next(&mut self) -> Option<std::io::Result<UdpConnectionInner<A>>> {
-        let mut conn = self.iter.next()?;
-        if let Ok(conn) =  conn.as_mut() {
-            conn.pid = self.pid;
+        loop {
+            let (mut conn, inode) = match self.iter.next()? {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+
+            if let Some(pid) = self.owners.owner(inode) {
+                conn.pid = pid;
+                return Some(Ok(conn));
+            }
+        }
+    }
+}
+
+/// Mapping from socket inodes to identifiers of processes that own them.
+///
+/// Connection tables in procfs are shared by all processes within the same
+/// network namespace (so `/proc/<pid>/net/tcp` is not specific to `<pid>`).
+/// The only link between a table entry and a process is the socket inode that
+/// also appears as a `socket:[<inode>]` link in `/proc/<pid>/fd` [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man5/proc_pid_fd.5.html
+enum SocketOwners {
+    /// Sockets of a single process, connections of other ones are skipped.
+    Process {
+        /// Identifier of the process that owns the sockets.
+        pid: u32,
+        /// Inodes of sockets that the process has open.
+        inodes: std::collections::HashSet<u64>,
+    },
+    /// Sockets of all processes on the system.
+    ///
+    /// Connections that do not belong to any process (e.g. TCP connections in
+    /// the `TIME_WAIT` state) are assigned to the process `0`.
+    All(std::collections::HashMap<u64, u32>),
+}
+
+impl SocketOwners {
+
+    /// Collects inodes of sockets of the specified process.
+    fn of_process(pid: u32) -> std::io::Result<SocketOwners> {
+        Ok(SocketOwners::Process {
+            pid,
+            inodes: socket_inodes(pid)?.collect(),
+        })
+    }
+
+    /// Collects inodes of sockets of all processes on the system.
+    fn of_all_processes() -> std::io::Result<SocketOwners> {
+        let mut owners = std::collections::HashMap::new();
+
+        for pid in crate::proc::ids()? {
+            let pid = match pid {
+                Ok(pid) => pid,
+                Err(_) => continue,
+            };
+
+            // Processes can terminate while we iterate over them and we might
+            // lack permissions to inspect some of them. In both cases there
+            // is nothing we can do but skip the process: its connections are
+            // still going to be reported, just without the owner.
+            let inodes = match socket_inodes(pid) {
+                Ok(inodes) => inodes,
+                Err(_) => continue,
+            };
+
+            owners.extend(inodes.map(|inode| (inode, pid)));
         }
 
-        Some(conn)
+        Ok(SocketOwners::All(owners))
     }
+
+    /// Returns the owner of the socket with the given inode.
+    ///
+    /// [`None`] is returned if the connection should not be yielded at all.
+    fn owner(&self, inode: u64) -> Option<u32> {
+        match self {
+            SocketOwners::Process { pid, inodes } => {
+                inodes.contains(&inode).then_some(*pid)
+            }
+            SocketOwners::All(owners) => {
+                Some(owners.get(&inode).copied().unwrap_or(0))
+            }
+        }
+    }
+}
+
+/// Returns an iterator over inodes of sockets open by the specified process.
+fn socket_inodes(pid: u32) -> std::io::Result<impl Iterator<Item = u64>> {
+    let entries = std::fs::read_dir(format!("/proc/{pid}/fd"))?;
+
+    Ok(entries.filter_map(|entry| {
+        // File descriptors may be closed while we iterate over them, so we skip
+        // all the entries that we fail to read.
+        let target = std::fs::read_link(entry.ok()?.path()).ok()?;
+        parse_socket_inode(target.to_str()?)
+    }))
+}
+
+/// Parses a socket inode out of a procfs file descriptor link target.
+///
+/// Links to sockets have the `socket:[<inode>]` format, [`None`] is returned
+/// for targets that do not follow it.
+fn parse_socket_inode(string: &str) -> Option<u64> {
+    string.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
 }
 
 /// Abstract iterator over connections of a particular process.
@@ -147,16 +294,20 @@ impl<C> Connections<C> {
 }
 
 impl<C> Iterator for Connections<C> {
-    type Item = std::io::Result<C>;
+    /// Connection information together with the inode of its socket.
+    type Item = std::io::Result<(C, u64)>;
 
-    fn next(&mut self) -> Option<std::io::Result<C>> {
+    fn next(&mut self) -> Option<std::io::Result<(C, u64)>> {
         let line = match self.lines.next() {
             None => return None,
             Some(Ok(line)) => line,
             Some(Err(error)) => return Some(Err(error)),
         };
 
-        match (self.parse_connection)(&line) {
+        let conn = (self.parse_connection)(&line)
+            .and_then(|conn| Ok((conn, parse_connection_inode(&line)?)));
+
+        match conn {
             Ok(conn) => Some(Ok(conn)),
             Err(error) => Some(Err({
                 std::io::Error::new(std::io::ErrorKind::InvalidData, error)
@@ -254,6 +405,19 @@ where
     })
 }
 
+/// Parses an inode of a connection socket out of a procfs connection line.
+fn parse_connection_inode(string: &str) -> Result<u64, ParseConnectionError> {
+    // Unlike the leading columns, the trailing ones are padded with a variable
+    // amount of whitespace, so we cannot simply split on each character. The
+    // inode is the 10th column (after `sl`, addresses, state, queues, timer
+    // information, retransmits, uid and timeout).
+    let inode_str = string.split_whitespace().nth(9)
+        .ok_or(ParseConnectionError::InvalidFormat)?;
+
+    inode_str.parse()
+        .map_err(|_| ParseConnectionError::InvalidInode)
+}
+
 /// Parses an IPv4 socket address in the procfs format.
 fn parse_socket_addr_v4(string: &str) -> Result<std::net::SocketAddrV4, ParseSocketAddrError> {
     let mut parts = string.split(':');
@@ -378,6 +542,8 @@ enum ParseConnectionError {
     InvalidRemoteAddr(ParseSocketAddrError),
     /// It was not possible to parse the connection state part.
     InvalidState(ParseTcpStateError),
+    /// It was not possible to parse the socket inode part.
+    InvalidInode,
 }
 
 impl std::fmt::Display for ParseConnectionError {
@@ -397,6 +563,9 @@ impl std::fmt::Display for ParseConnectionError {
             InvalidState(error) => {
                 write!(fmt, "invalid state: {}", error)
             }
+            InvalidInode => {
+                write!(fmt, "invalid socket inode")
+            }
         }
     }
 }
@@ -613,6 +782,89 @@ mod tests {
         assert_eq!(error, ParseConnectionError::InvalidFormat);
     }
 
+    #[test]
+    fn parse_connection_inode_ok() {
+        let inode = parse_connection_inode(
+            "   1: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 24840 1 0000000000000000 100 0 0 10 0"
+        ).unwrap();
+
+        assert_eq!(inode, 24840);
+    }
+
+    #[test]
+    fn parse_connection_inode_missing() {
+        let error = parse_connection_inode("0: 00000000:0000 00000000:0000 0A")
+            .unwrap_err();
+
+        assert_eq!(error, ParseConnectionError::InvalidFormat);
+    }
+
+    #[test]
+    fn parse_connection_inode_invalid() {
+        let error = parse_connection_inode(
+            "0: 00000000:0000 00000000:0000 0A 00000000:00000000 00:00000000 00000000 0 0 foo 1 0000000000000000 100 0 0 10 0"
+        ).unwrap_err();
+
+        assert_eq!(error, ParseConnectionError::InvalidInode);
+    }
+
+    #[test]
+    fn parse_socket_inode_ok() {
+        assert_eq!(parse_socket_inode("socket:[1337]"), Some(1337));
+    }
+
+    #[test]
+    fn parse_socket_inode_not_socket() {
+        assert_eq!(parse_socket_inode("/dev/null"), None);
+        assert_eq!(parse_socket_inode("pipe:[1337]"), None);
+        assert_eq!(parse_socket_inode("socket:[foo]"), None);
+    }
+
+    #[test]
+    fn tcp_v4_other_process_connection() {
+        use std::net::Ipv4Addr;
+
+        let server = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap();
+        let server_addr = server.local_addr()
+            .unwrap();
+
+        // The connection table of the init process includes our socket but it
+        // should not be attributed to it since it is not the owner.
+        let conns = match tcp_v4(1) {
+            Ok(conns) => conns,
+            // We might not be allowed to inspect the init process at all.
+            Err(_) => return,
+        };
+
+        assert! {
+            conns
+                .filter_map(Result::ok)
+                .find(|conn| std::net::SocketAddr::from(conn.local_addr()) == server_addr)
+                .is_none()
+        };
+    }
+
+    #[test]
+    fn all_tcp_v4_local_connection() {
+        use std::net::Ipv4Addr;
+
+        let server = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap();
+        let server_addr = server.local_addr()
+            .unwrap();
+
+        let conns = all_tcp_v4()
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|conn| std::net::SocketAddr::from(conn.local_addr()) == server_addr)
+            .collect::<Vec<_>>();
+
+        assert_eq!(conns.len(), 1);
+        assert_eq!(conns[0].pid(), std::process::id());
+        assert_eq!(conns[0].state(), TcpState::Listen);
+    }
+
     #[test]
     fn parse_socket_addr_v4_zeros() {
         let addr = parse_socket_addr_v4("00000000:0000")
@@ -0,0 +1,362 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Minimal support for routing netlink [1] dump requests.
+//!
+//! [1]: https://man7.org/linux/man-pages/man7/rtnetlink.7.html
+
+use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd};
+
+/// Size of the netlink message header.
+const HEADER_LEN: usize = 16;
+
+/// Size of the routing attribute header.
+const ATTR_HEADER_LEN: usize = 4;
+
+/// Size of the buffer for receiving messages.
+///
+/// The kernel limits dump responses to a page (or 8 KiB on systems with bigger
+/// pages) per datagram, so this is more than enough.
+const RECV_BUF_LEN: usize = 32 * 1024;
+
+/// Sends a dump request of the given type and returns payloads of all replies.
+///
+/// `body` is the family-specific header of the request (e.g. `ndmsg`) and
+/// `reply_kind` is the type of messages that the kernel replies with.
+pub fn dump(kind: u16, body: &[u8], reply_kind: u16) -> std::io::Result<Vec<Vec<u8>>> {
+    // SAFETY: This is just a system call without any memory-related invariants.
+    // We check for errors below.
+    let fd = unsafe {
+        libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_ROUTE)
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: We verified that the descriptor is valid and we are its only
+    // owner, so it is fine to close it when dropped.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // We give no address to which the request is sent, which means it goes to
+    // the kernel. There is only one request per socket, so any sequence number
+    // will do.
+    const SEQ: u32 = 1;
+
+    let mut request = Vec::with_capacity(HEADER_LEN + body.len());
+    request.extend_from_slice(&((HEADER_LEN + body.len()) as u32).to_ne_bytes());
+    request.extend_from_slice(&kind.to_ne_bytes());
+    request.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    request.extend_from_slice(&SEQ.to_ne_bytes());
+    request.extend_from_slice(&0u32.to_ne_bytes());
+    request.extend_from_slice(body);
+
+    // SAFETY: We pass a valid buffer together with its length.
+    let status = unsafe {
+        libc::send(fd.as_raw_fd(), request.as_ptr().cast(), request.len(), 0)
+    };
+    if status < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut payloads = Vec::new();
+    let mut buf = vec![0u8; RECV_BUF_LEN];
+
+    loop {
+        // SAFETY: We pass a valid buffer together with its length. The kernel
+        // does not write past it.
+        let len = unsafe {
+            libc::recv(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0)
+        };
+        if len < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+        if len == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        for message in messages(&buf[..len as usize]) {
+            let message = message?;
+            if message.seq != SEQ {
+                continue;
+            }
+
+            match message.kind {
+                kind if kind == libc::NLMSG_DONE as u16 => return Ok(payloads),
+                kind if kind == libc::NLMSG_ERROR as u16 => {
+                    return Err(parse_error(message.payload));
+                }
+                kind if kind == reply_kind => payloads.push(message.payload.to_vec()),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// A single netlink message.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Message<'a> {
+    /// Type of the message.
+    pub kind: u16,
+    /// Sequence number of the message.
+    pub seq: u32,
+    /// Payload of the message (without the header).
+    pub payload: &'a [u8],
+}
+
+/// Returns an iterator over netlink messages in the given buffer.
+pub fn messages(buf: &[u8]) -> Messages<'_> {
+    Messages {
+        buf,
+    }
+}
+
+/// Iterator over netlink messages in a buffer.
+pub struct Messages<'a> {
+    /// Part of the buffer that has not been parsed yet.
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for Messages<'a> {
+
+    type Item = std::io::Result<Message<'a>>;
+
+    fn next(&mut self) -> Option<std::io::Result<Message<'a>>> {
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let Some(header) = self.buf.get(..HEADER_LEN) else {
+            self.buf = &[];
+            return Some(Err(invalid_data("truncated netlink message header")));
+        };
+
+        let len = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = u16::from_ne_bytes([header[4], header[5]]);
+        let seq = u32::from_ne_bytes([header[8], header[9], header[10], header[11]]);
+
+        if len < HEADER_LEN || len > self.buf.len() {
+            self.buf = &[];
+            return Some(Err(invalid_data("invalid netlink message length")));
+        }
+
+        let payload = &self.buf[HEADER_LEN..len];
+        self.buf = self.buf.get(align(len)..).unwrap_or(&[]);
+
+        Some(Ok(Message {
+            kind,
+            seq,
+            payload,
+        }))
+    }
+}
+
+/// Returns an iterator over routing attributes (as type and value pairs) in
+/// the given buffer.
+pub fn attrs(buf: &[u8]) -> Attrs<'_> {
+    Attrs {
+        buf,
+    }
+}
+
+/// Iterator over routing attributes in a buffer.
+pub struct Attrs<'a> {
+    /// Part of the buffer that has not been parsed yet.
+    buf: &'a [u8],
+}
+
+impl<'a> Iterator for Attrs<'a> {
+
+    type Item = std::io::Result<(u16, &'a [u8])>;
+
+    fn next(&mut self) -> Option<std::io::Result<(u16, &'a [u8])>> {
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let Some(header) = self.buf.get(..ATTR_HEADER_LEN) else {
+            self.buf = &[];
+            return Some(Err(invalid_data("truncated routing attribute header")));
+        };
+
+        let len = u16::from_ne_bytes([header[0], header[1]]) as usize;
+        let kind = u16::from_ne_bytes([header[2], header[3]]);
+
+        if len < ATTR_HEADER_LEN || len > self.buf.len() {
+            self.buf = &[];
+            return Some(Err(invalid_data("invalid routing attribute length")));
+        }
+
+        let value = &self.buf[ATTR_HEADER_LEN..len];
+        self.buf = self.buf.get(align(len)..).unwrap_or(&[]);
+
+        // The highest bits of the type are flags that we do not care about.
+        Some(Ok((kind & libc::NLA_TYPE_MASK as u16, value)))
+    }
+}
+
+/// Parses an IP address from a routing attribute value.
+pub fn parse_ip_addr(value: &[u8]) -> std::io::Result<std::net::IpAddr> {
+    if let Ok(octets) = <[u8; 4]>::try_from(value) {
+        return Ok(std::net::IpAddr::from(octets));
+    }
+    if let Ok(octets) = <[u8; 16]>::try_from(value) {
+        return Ok(std::net::IpAddr::from(octets));
+    }
+
+    Err(invalid_data("invalid IP address length"))
+}
+
+/// Parses a 32-bit integer from a routing attribute value.
+pub fn parse_u32(value: &[u8]) -> std::io::Result<u32> {
+    <[u8; 4]>::try_from(value)
+        .map(u32::from_ne_bytes)
+        .map_err(|_| invalid_data("invalid integer length"))
+}
+
+/// Converts the payload of an error message to an error.
+fn parse_error(payload: &[u8]) -> std::io::Error {
+    match payload.get(..4) {
+        // Errors are reported as negated `errno` values.
+        Some(code) => {
+            let code = i32::from_ne_bytes([code[0], code[1], code[2], code[3]]);
+            std::io::Error::from_raw_os_error(-code)
+        }
+        None => invalid_data("truncated netlink error message"),
+    }
+}
+
+/// Rounds the given length up to the netlink alignment (4 bytes).
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Creates an error for malformed netlink data.
+fn invalid_data(message: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn messages_multiple() {
+        let buf = [
+            // Header of the first message (with 3 bytes of payload).
+            0x13, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x02, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // Payload of the first message (with one byte of padding).
+            0xaa, 0xbb, 0xcc, 0x00,
+            // Header of the second message (without payload).
+            0x10, 0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let messages = messages(&buf)
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(messages, vec![
+            Message {
+                kind: 0x1c,
+                seq: 1,
+                payload: &[0xaa, 0xbb, 0xcc],
+            },
+            Message {
+                kind: 0x03,
+                seq: 2,
+                payload: &[],
+            },
+        ]);
+    }
+
+    #[test]
+    fn messages_truncated_header() {
+        let buf = [0x10, 0x00, 0x00, 0x00, 0x03, 0x00];
+
+        let mut messages = messages(&buf);
+        assert!(messages.next().unwrap().is_err());
+        assert!(messages.next().is_none());
+    }
+
+    #[test]
+    fn messages_invalid_len() {
+        let buf = [
+            0x20, 0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut messages = messages(&buf);
+        assert!(messages.next().unwrap().is_err());
+        assert!(messages.next().is_none());
+    }
+
+    #[test]
+    fn attrs_multiple() {
+        let buf = [
+            // `RTA_TABLE` with value 254.
+            0x08, 0x00, 0x0f, 0x00, 0xfe, 0x00, 0x00, 0x00,
+            // `RTA_PREF` with one byte of value and padding.
+            0x05, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // Nested `RTA_MULTIPATH` (with the nested flag set).
+            0x04, 0x00, 0x09, 0x80,
+        ];
+
+        let attrs = attrs(&buf)
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(attrs, vec![
+            (0x0f, &[0xfe, 0x00, 0x00, 0x00][..]),
+            (0x14, &[0x00][..]),
+            (0x09, &[][..]),
+        ]);
+    }
+
+    #[test]
+    fn attrs_invalid_len() {
+        let buf = [0x02, 0x00, 0x0f, 0x00];
+
+        let mut attrs = attrs(&buf);
+        assert!(attrs.next().unwrap().is_err());
+        assert!(attrs.next().is_none());
+    }
+
+    #[test]
+    fn parse_ip_addr_ok() {
+        assert_eq! {
+            parse_ip_addr(&[192, 0, 2, 1]).unwrap(),
+            std::net::IpAddr::from([192, 0, 2, 1])
+        };
+        assert_eq! {
+            parse_ip_addr(&std::net::Ipv6Addr::LOCALHOST.octets()).unwrap(),
+            std::net::IpAddr::from(std::net::Ipv6Addr::LOCALHOST)
+        };
+        assert!(parse_ip_addr(&[192, 0, 2]).is_err());
+    }
+
+    #[test]
+    fn parse_error_ok() {
+        let error = parse_error(&(-libc::EPERM).to_ne_bytes());
+        assert_eq!(error.raw_os_error(), Some(libc::EPERM));
+    }
+
+    #[test]
+    fn dump_route_ok() {
+        // `rtmsg` header with all fields (including the family) unspecified.
+        let body = [0; 12];
+
+        let payloads = dump(libc::RTM_GETROUTE, &body, libc::RTM_NEWROUTE)
+            .unwrap();
+
+        // There should be at least routes for the loopback interface.
+        assert!(!payloads.is_empty());
+    }
+}
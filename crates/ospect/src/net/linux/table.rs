@@ -0,0 +1,418 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use crate::net::*;
+
+use super::netlink;
+
+/// Size of the `ndmsg` header of neighbor messages.
+const NDMSG_LEN: usize = 12;
+
+/// Size of the `rtmsg` header of route messages.
+const RTMSG_LEN: usize = 12;
+
+/// Returns an iterator over entries of the neighbor cache.
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    // `ndmsg` header with all fields (including the family) unspecified so
+    // that entries of all families are dumped.
+    let body = [0; NDMSG_LEN];
+
+    let payloads = netlink::dump(libc::RTM_GETNEIGH, &body, libc::RTM_NEWNEIGH)?;
+    Ok(payloads.into_iter().filter_map(|payload| parse_neighbor(&payload).transpose()))
+}
+
+/// Returns an iterator over entries of the routing table.
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    // `rtmsg` header with all fields (including the family) unspecified so
+    // that routes of all families and tables are dumped.
+    let body = [0; RTMSG_LEN];
+
+    let payloads = netlink::dump(libc::RTM_GETROUTE, &body, libc::RTM_NEWROUTE)?;
+    Ok(payloads.into_iter().filter_map(|payload| parse_route(&payload).transpose()))
+}
+
+/// Parses the payload of a `RTM_NEWNEIGH` message.
+///
+/// `None` is returned for entries that are not really neighbors (e.g. entries
+/// of interfaces that do not use address resolution at all).
+fn parse_neighbor(payload: &[u8]) -> std::io::Result<Option<Neighbor>> {
+    let Some(header) = payload.get(..NDMSG_LEN) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "truncated neighbor message",
+        ));
+    };
+
+    let iface_index = u32::from_ne_bytes([header[4], header[5], header[6], header[7]]);
+    let state = u16::from_ne_bytes([header[8], header[9]]);
+
+    if state & libc::NUD_NOARP != 0 {
+        return Ok(None);
+    }
+
+    let mut ip_addr = None;
+    let mut mac_addr = None;
+
+    for attr in netlink::attrs(&payload[NDMSG_LEN..]) {
+        match attr? {
+            (libc::NDA_DST, value) => {
+                ip_addr = Some(netlink::parse_ip_addr(value)?);
+            }
+            (libc::NDA_LLADDR, value) => {
+                // Link-layer addresses of interfaces other than Ethernet-like
+                // ones (e.g. tunnels) are not MAC addresses, so we skip them.
+                mac_addr = <[u8; 6]>::try_from(value).ok().map(MacAddr::from);
+            }
+            _ => (),
+        }
+    }
+
+    let Some(ip_addr) = ip_addr else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "neighbor message without destination",
+        ));
+    };
+
+    Ok(Some(Neighbor {
+        ip_addr,
+        mac_addr,
+        iface_index,
+        state: parse_neighbor_state(state),
+    }))
+}
+
+/// Converts the `NUD_*` state of a neighbor to its system-agnostic variant.
+fn parse_neighbor_state(state: u16) -> NeighborState {
+    match state {
+        libc::NUD_INCOMPLETE => NeighborState::Incomplete,
+        libc::NUD_REACHABLE => NeighborState::Reachable,
+        libc::NUD_STALE => NeighborState::Stale,
+        libc::NUD_DELAY => NeighborState::Delay,
+        libc::NUD_PROBE => NeighborState::Probe,
+        libc::NUD_FAILED => NeighborState::Failed,
+        libc::NUD_PERMANENT => NeighborState::Permanent,
+        _ => NeighborState::Unknown,
+    }
+}
+
+/// Parses the payload of a `RTM_NEWROUTE` message.
+///
+/// `None` is returned for routes that are not part of the routing table as
+/// the user sees it: routes of the local table (that the kernel maintains for
+/// addresses of the host itself) and cached routes.
+fn parse_route(payload: &[u8]) -> std::io::Result<Option<Route>> {
+    let Some(header) = payload.get(..RTMSG_LEN) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "truncated route message",
+        ));
+    };
+
+    let family = i32::from(header[0]);
+    let dest_prefix_len = header[1];
+    let flags = u32::from_ne_bytes([header[8], header[9], header[10], header[11]]);
+
+    if flags & libc::RTM_F_CLONED != 0 {
+        return Ok(None);
+    }
+
+    // The table identifier in the header is truncated to 8 bits, the full one
+    // is given by the `RTA_TABLE` attribute (if present).
+    let mut table = u32::from(header[4]);
+
+    let mut dest_addr = None;
+    let mut gateway = None;
+    let mut iface_index = None;
+    let mut metric = None;
+
+    for attr in netlink::attrs(&payload[RTMSG_LEN..]) {
+        match attr? {
+            (libc::RTA_DST, value) => {
+                dest_addr = Some(netlink::parse_ip_addr(value)?);
+            }
+            (libc::RTA_GATEWAY, value) => {
+                gateway = Some(netlink::parse_ip_addr(value)?);
+            }
+            (libc::RTA_OIF, value) => {
+                iface_index = Some(netlink::parse_u32(value)?);
+            }
+            (libc::RTA_PRIORITY, value) => {
+                metric = Some(netlink::parse_u32(value)?);
+            }
+            (libc::RTA_TABLE, value) => {
+                table = netlink::parse_u32(value)?;
+            }
+            _ => (),
+        }
+    }
+
+    if table == u32::from(libc::RT_TABLE_LOCAL) {
+        return Ok(None);
+    }
+
+    // Default routes come without the destination attribute.
+    let dest_addr = match (dest_addr, family) {
+        (Some(dest_addr), _) => dest_addr,
+        (None, libc::AF_INET) => std::net::IpAddr::from(std::net::Ipv4Addr::UNSPECIFIED),
+        (None, libc::AF_INET6) => std::net::IpAddr::from(std::net::Ipv6Addr::UNSPECIFIED),
+        // Routes of other families (e.g. MPLS) are not supported.
+        (None, _) => return Ok(None),
+    };
+
+    Ok(Some(Route {
+        dest_addr,
+        dest_prefix_len,
+        gateway,
+        iface_index,
+        metric,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Route messages dumped on a system with a single Ethernet interface
+    /// (index 4) with IPv4 and IPv6 addresses and default routes.
+    const ROUTE_DUMP: &str = concat! {
+        "3400000018000200010000000115000002000000fe0300010000000008000f00fe000000",
+        "08000500c00002010800040004000000",
+        "3c00000018000200010000000115000002180000fe02fd010000000008000f00fe000000",
+        "08000100c000020008000700c00002020800040004000000",
+        "3c00000018000200010000000115000002080000ff02fe020000000008000f00ff000000",
+        "080001007f000000080007007f0000010800040001000000",
+        "3c00000018000200010000000115000002200000ff02fe020000000008000f00ff000000",
+        "080001007f000001080007007f0000010800040001000000",
+        "3c00000018000200010000000115000002200000ff02fd030000000008000f00ff000000",
+        "080001007fffffff080007007f0000010800040001000000",
+        "3c00000018000200010000000115000002200000ff02fe020000000008000f00ff000000",
+        "08000100c000020208000700c00002020800040004000000",
+        "3c00000018000200010000000115000002200000ff02fd030000000008000f00ff000000",
+        "08000100c00002ff08000700c00002020800040004000000",
+        "740000001800020001000000011500000a400000fe0200010000000008000f00fe000000",
+        "14000100fd00000000000000000000000000000008000600000100000800040004000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "740000001800020001000000011500000a400000fe0200010000000008000f00fe000000",
+        "14000100fe80000000000000000000000000000008000600000100000800040004000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "740000001800020001000000011500000a000000fe0300010000000008000f00fe000000",
+        "080006000004000014000500fd0000000000000000000000000000010800040004000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "740000001800020001000000011500000a800000ff0200020000000008000f00ff000000",
+        "140001000000000000000000000000000000000108000600000000000800040001000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "740000001800020001000000011500000a800000ff0200020000000008000f00ff000000",
+        "14000100fd00000000000000000000000000000208000600000000000800040004000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "740000001800020001000000011500000a800000ff0200020000000008000f00ff000000",
+        "14000100fe8000000000000000fc00fffe00000108000600000000000800040004000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "740000001800020001000000011500000a080000ff0200050000000008000f00ff000000",
+        "14000100ff00000000000000000000000000000008000600000100000800040004000000",
+        "24000c000000000000000000000000000000000000000000000000000000000000000000",
+        "0500140000000000",
+        "1400000003000200010000000115000000000000",
+    };
+
+    /// Neighbor messages dumped on the same system as [`ROUTE_DUMP`].
+    const NEIGH_DUMP: &str = concat! {
+        "4c0000001c00020001000000021500000200000001000000400000030800010000000000",
+        "0a000200000000000000000008000400000000001400030088000000624a0a00624a0a00",
+        "00000000",
+        "4c0000001c000200010000000215000002000000040000000400000108000100c0000201",
+        "0a00020002fc0000000500000800040001000000140003001ccd09001ccd0900d0c00900",
+        "00000000",
+        "580000001c00020001000000021500000a000000040000004000000514000100ff020000",
+        "0000000000000000000000160a0002003333000000160000080004000000000014000300",
+        "928c0a0022750a0022750a0000000000",
+        "580000001c00020001000000021500000a000000040000004000000514000100ff020000",
+        "0000000000000001ff0000010a0002003333ff0000010000080004000000000014000300",
+        "2d8c0a00bd740a00bd740a0000000000",
+        "1400000003000200010000000215000000000000",
+    };
+
+    /// Decodes the given hex string into bytes.
+    fn hex(string: &str) -> Vec<u8> {
+        (0..string.len()).step_by(2)
+            .map(|i| u8::from_str_radix(&string[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Parses all messages of the given kind from the given dump.
+    fn parse_dump<T>(
+        dump: &str,
+        kind: u16,
+        parse: fn(&[u8]) -> std::io::Result<Option<T>>,
+    ) -> Vec<T> {
+        let buf = hex(dump);
+
+        netlink::messages(&buf)
+            .map(Result::unwrap)
+            .filter(|message| message.kind == kind)
+            .filter_map(|message| parse(message.payload).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn neighbors_ok() {
+        // The system might not have any neighbors (e.g. it has no network) but
+        // dumping them should not fail anyway.
+        for neighbor in neighbors().unwrap() {
+            let neighbor = neighbor.unwrap();
+            assert!(!neighbor.ip_addr().is_unspecified());
+        }
+    }
+
+    #[test]
+    fn routes_ok() {
+        for route in routes().unwrap() {
+            let route = route.unwrap();
+            assert!(route.dest_prefix_len() <= 128);
+        }
+    }
+
+    #[test]
+    fn parse_neighbor_dump() {
+        let neighbors = parse_dump(NEIGH_DUMP, libc::RTM_NEWNEIGH, parse_neighbor);
+
+        // Entries of the loopback interface and IPv6 multicast addresses are
+        // not subject to address resolution and should be skipped.
+        assert_eq!(neighbors, vec![
+            Neighbor {
+                ip_addr: std::net::IpAddr::from([192, 0, 2, 1]),
+                mac_addr: Some(MacAddr::from([0x02, 0xfc, 0x00, 0x00, 0x00, 0x05])),
+                iface_index: 4,
+                state: NeighborState::Stale,
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_neighbor_truncated() {
+        let error = parse_neighbor(&[0x02, 0x00, 0x00, 0x00])
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_neighbor_without_mac_addr() {
+        let payload = hex(concat! {
+            // `ndmsg` with `AF_INET`, interface 4 and `NUD_INCOMPLETE` state.
+            "02000000", "04000000", "0100", "00", "01",
+            // `NDA_DST` with 192.0.2.3.
+            "08000100", "c0000203",
+        });
+
+        let neighbor = parse_neighbor(&payload)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(neighbor.ip_addr(), std::net::IpAddr::from([192, 0, 2, 3]));
+        assert_eq!(neighbor.mac_addr(), None);
+        assert_eq!(neighbor.state(), NeighborState::Incomplete);
+    }
+
+    #[test]
+    fn parse_neighbor_without_dst() {
+        let payload = hex(concat! {
+            // `ndmsg` with `AF_INET`, interface 4 and `NUD_REACHABLE` state.
+            "02000000", "04000000", "0200", "00", "01",
+        });
+
+        let error = parse_neighbor(&payload)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_route_dump() {
+        let routes = parse_dump(ROUTE_DUMP, libc::RTM_NEWROUTE, parse_route);
+
+        // Routes of the local table should be skipped.
+        assert_eq!(routes, vec![
+            Route {
+                dest_addr: std::net::IpAddr::from([0, 0, 0, 0]),
+                dest_prefix_len: 0,
+                gateway: Some(std::net::IpAddr::from([192, 0, 2, 1])),
+                iface_index: Some(4),
+                metric: None,
+            },
+            Route {
+                dest_addr: std::net::IpAddr::from([192, 0, 2, 0]),
+                dest_prefix_len: 24,
+                gateway: None,
+                iface_index: Some(4),
+                metric: None,
+            },
+            Route {
+                dest_addr: "fd00::".parse().unwrap(),
+                dest_prefix_len: 64,
+                gateway: None,
+                iface_index: Some(4),
+                metric: Some(256),
+            },
+            Route {
+                dest_addr: "fe80::".parse().unwrap(),
+                dest_prefix_len: 64,
+                gateway: None,
+                iface_index: Some(4),
+                metric: Some(256),
+            },
+            Route {
+                dest_addr: "::".parse().unwrap(),
+                dest_prefix_len: 0,
+                gateway: Some("fd00::1".parse().unwrap()),
+                iface_index: Some(4),
+                metric: Some(1024),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_route_truncated() {
+        let error = parse_route(&[0x02, 0x18, 0x00, 0x00])
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_route_cloned() {
+        let payload = hex(concat! {
+            // `rtmsg` for `AF_INET` host route in the main table that is
+            // flagged with `RTM_F_CLONED`.
+            "02200000", "fe000001", "00020000",
+            // `RTA_DST` with 192.0.2.3.
+            "08000100", "c0000203",
+        });
+
+        assert_eq!(parse_route(&payload).unwrap(), None);
+    }
+
+    #[test]
+    fn parse_route_invalid_attr() {
+        let payload = hex(concat! {
+            // `rtmsg` for `AF_INET` route in the main table.
+            "02180000", "fe000001", "00000000",
+            // `RTA_GATEWAY` with a truncated address.
+            "07000500", "c00002",
+        });
+
+        let error = parse_route(&payload)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -4,6 +4,7 @@
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
 mod conn;
+mod table;
 
 use super::*;
 
@@ -58,6 +59,35 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
         // the `continue` statements in the code below.
         addr_iter = addr.ifa_next;
 
+        // SAFETY: `ifa_name` is a string with interface name. While Apple docs
+        // do not mention whether it is null-terminated, it is a safe bet to
+        // assume so given the similarity to the Linux version of `getifaddrs`.
+        let name_cstr = unsafe {
+            std::ffi::CStr::from_ptr(addr.ifa_name)
+        };
+        let name = std::ffi::OsStr::from_bytes(name_cstr.to_bytes());
+
+        // As on Linux, we create the interface entry before looking at the
+        // address so that interfaces without any addresses are reported too.
+        let entry = ifaces.entry(name).or_insert_with(|| {
+            let mut iface = Interface::new(name.to_os_string());
+
+            // SAFETY: `if_nametoindex` expects a null-terminated string which
+            // is guaranteed by the `CStr` type. On failure, zero is returned.
+            iface.index = match unsafe { libc::if_nametoindex(name_cstr.as_ptr()) } {
+                0 => None,
+                index => Some(index),
+            };
+
+            iface
+        });
+
+        entry.flags = InterfaceFlags {
+            up: addr.ifa_flags & libc::IFF_UP as libc::c_uint != 0,
+            running: addr.ifa_flags & libc::IFF_RUNNING as libc::c_uint != 0,
+            loopback: addr.ifa_flags & libc::IFF_LOOPBACK as libc::c_uint != 0,
+        };
+
         // SAFETY: `ifa_ddr` is not guaranteed to be not null, so we have to
         // verify it. But if it is not null, it is guaranteed to point to valid
         // address instance.
@@ -66,18 +96,9 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
             None => continue,
         };
 
-        // SAFETY: `ifa_name` is a string with interface name. While Apple docs
-        // do not mention whether it is null-terminated, it is a safe bet to
-        // assume so given the similarity to the Linux version of `getifaddrs`.
-        let name = std::ffi::OsStr::from_bytes(unsafe {
-            std::ffi::CStr::from_ptr(addr.ifa_name)
-        }.to_bytes());
-
-        let entry = ifaces.entry(name).or_insert(Interface {
-            name: name.to_os_string(),
-            ip_addrs: Vec::new(),
-            mac_addr: None,
-        });
+        // SAFETY: The same as with `ifa_addr`: the netmask can be null but if
+        // it is not, it should be of the same family as the address.
+        let netmask = unsafe { addr.ifa_netmask.as_ref() };
 
         match i32::from(family) {
             libc::AF_INET => {
@@ -105,7 +126,21 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
                 let ipv4_addr_u32 = u32::from_be(ipv4_addr_u32);
 
                 let ipv4_addr = std::net::Ipv4Addr::from(ipv4_addr_u32);
-                entry.ip_addrs.push(ipv4_addr.into());
+
+                // SAFETY: The netmask is of the same family as the address, so
+                // the cast is safe as well.
+                let prefix_len = netmask.and_then(|netmask| {
+                    let netmask_u32 = unsafe {
+                        *(netmask as *const libc::sockaddr as *const libc::sockaddr_in)
+                    }.sin_addr.s_addr;
+
+                    prefix_len(&netmask_u32.to_ne_bytes())
+                });
+
+                entry.addrs.push(InterfaceAddr {
+                    ip_addr: ipv4_addr.into(),
+                    prefix_len,
+                });
             }
             libc::AF_INET6 => {
                 // SAFETY: For `AF_INET6` family the `ifa_addr` field is an
@@ -120,7 +155,21 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
                 }.sin6_addr.s6_addr;
 
                 let ipv6_addr = std::net::Ipv6Addr::from(ipv6_addr_octets);
-                entry.ip_addrs.push(ipv6_addr.into());
+
+                // SAFETY: The netmask is of the same family as the address, so
+                // the cast is safe as well.
+                let prefix_len = netmask.and_then(|netmask| {
+                    let netmask_octets = unsafe {
+                        *(netmask as *const libc::sockaddr as *const libc::sockaddr_in6)
+                    }.sin6_addr.s6_addr;
+
+                    prefix_len(&netmask_octets)
+                });
+
+                entry.addrs.push(InterfaceAddr {
+                    ip_addr: ipv6_addr.into(),
+                    prefix_len,
+                });
             }
             libc::AF_LINK => {
                 // SAFETY: For `AF_LINK` family the `ifa_addr` field is an
@@ -134,6 +183,14 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
                     *(addr.ifa_addr as *const libc::sockaddr_dl)
                 };
 
+                // SAFETY: For `AF_LINK` entries `ifa_data` (if not null) points
+                // to the interface statistics including its MTU [1].
+                //
+                // [1]: https://github.com/apple/darwin-xnu/blob/2ff845c2e033bd0ff64b5b6aa6063a1f8f65aa32/bsd/net/if_var.h
+                if let Some(data) = unsafe { addr.ifa_data.cast::<libc::if_data>().as_ref() } {
+                    entry.mtu = Some(data.ifi_mtu);
+                }
+
                 // Unfortunatelly, it is not uncommon to have some other non-MAC
                 // addresses with the `AF_LINK` family. We simply ignore such.
                 if sockaddr.sdl_alen != 6 {
@@ -225,6 +282,25 @@ pub fn all_udp_v6_connections() -> std::io::Result<impl Iterator<Item = std::io:
     crate::net::unix::all_udp_v6_connections()
 }
 
+/// Returns an iterator over entries of the neighbor cache.
+///
+/// The entries are collected from the `NET_RT_FLAGS` [`sysctl`][1] dump of
+/// routes with link-layer information.
+///
+/// [1]: https://developer.apple.com/library/archive/documentation/System/Conceptual/ManPages_iPhoneOS/man3/sysctl.3.html
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    self::table::neighbors()
+}
+
+/// Returns an iterator over entries of the routing table.
+///
+/// The entries are collected from the `NET_RT_DUMP` [`sysctl`][1] dump.
+///
+/// [1]: https://developer.apple.com/library/archive/documentation/System/Conceptual/ManPages_iPhoneOS/man3/sysctl.3.html
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    self::table::routes()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -0,0 +1,510 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use crate::net::*;
+
+/// Size of the `rt_msghdr` header of routing messages.
+const RT_MSGHDR_LEN: usize = std::mem::size_of::<libc::rt_msghdr>();
+
+/// Offset of the `rmx_expire` field within the `rt_msghdr` header.
+const RMX_EXPIRE_OFFSET: usize = std::mem::offset_of!(libc::rt_msghdr, rtm_rmx) +
+    std::mem::offset_of!(libc::rt_metrics, rmx_expire);
+
+/// Returns an iterator over entries of the neighbor cache.
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    let buf = dump(libc::NET_RT_FLAGS, libc::RTF_LLINFO)?;
+
+    let neighbors = messages(&buf)
+        .filter_map(|message| message.and_then(parse_neighbor).transpose())
+        .collect::<Vec<_>>();
+
+    Ok(neighbors.into_iter())
+}
+
+/// Returns an iterator over entries of the routing table.
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    let buf = dump(libc::NET_RT_DUMP, 0)?;
+
+    let routes = messages(&buf)
+        .filter_map(|message| message.and_then(parse_route).transpose())
+        .collect::<Vec<_>>();
+
+    Ok(routes.into_iter())
+}
+
+/// Dumps routing messages using the `PF_ROUTE` sysctl of the given kind.
+fn dump(kind: libc::c_int, flags: libc::c_int) -> std::io::Result<Vec<u8>> {
+    // Messages of all address families are dumped.
+    let mut mib = [libc::CTL_NET, libc::PF_ROUTE, 0, libc::AF_UNSPEC, kind, flags];
+
+    loop {
+        let mut buf_size = 0;
+
+        // SAFETY: We call the `sysctl` function as described in the FreeBSD
+        // documentation [1] (macOS's kernel derives from FreeBSD). We check for
+        // errors afterwards.
+        //
+        // This is the first call where we don't pass any buffer and we just
+        // want to estimate the size of the buffer to hold the data.
+        //
+        // [1]: https://man.freebsd.org/cgi/man.cgi?sysctl(3)
+        let code = unsafe {
+            libc::sysctl(
+                mib.as_mut_ptr(), mib.len() as libc::c_uint,
+                std::ptr::null_mut(), &mut buf_size,
+                std::ptr::null_mut(), 0,
+            )
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut buf = vec![0u8; buf_size];
+
+        // SAFETY: We pass a buffer of the size specified by the previous call
+        // together with its size. The table might have grown between the two
+        // calls in which case `ENOMEM` is returned and we just retry.
+        let code = unsafe {
+            libc::sysctl(
+                mib.as_mut_ptr(), mib.len() as libc::c_uint,
+                buf.as_mut_ptr().cast::<libc::c_void>(), &mut buf_size,
+                std::ptr::null_mut(), 0,
+            )
+        };
+        if code != 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(libc::ENOMEM) {
+                continue;
+            }
+            return Err(error);
+        }
+
+        buf.truncate(buf_size);
+        return Ok(buf);
+    }
+}
+
+/// A single routing message.
+#[derive(Debug)]
+struct Message<'a> {
+    /// Index of the interface the message refers to.
+    index: u16,
+    /// `RTF_*` flags of the route.
+    flags: libc::c_int,
+    /// Expiration time of the route (zero if it does not expire).
+    expire: i32,
+    /// Socket addresses that follow the header, indexed by `RTAX_*` values.
+    addrs: [Option<&'a [u8]>; libc::RTAX_MAX as usize],
+}
+
+/// Returns an iterator over routing messages in the given buffer.
+fn messages(buf: &[u8]) -> impl Iterator<Item = std::io::Result<Message<'_>>> {
+    let mut buf = buf;
+
+    std::iter::from_fn(move || {
+        if buf.is_empty() {
+            return None;
+        }
+
+        let len = match buf.get(..2) {
+            Some(len) => usize::from(u16::from_ne_bytes([len[0], len[1]])),
+            None => 0,
+        };
+        if len < RT_MSGHDR_LEN || len > buf.len() {
+            buf = &[];
+            return Some(Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid routing message length",
+            )));
+        }
+
+        let message = &buf[..len];
+        buf = &buf[len..];
+
+        Some(parse_message(message))
+    })
+}
+
+/// Parses a single routing message.
+fn parse_message(message: &[u8]) -> std::io::Result<Message<'_>> {
+    let int = |offset: usize| {
+        let bytes = &message[offset..offset + 4];
+        libc::c_int::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    };
+
+    let index = u16::from_ne_bytes([message[4], message[5]]);
+    let flags = int(std::mem::offset_of!(libc::rt_msghdr, rtm_flags));
+    let addrs_mask = int(std::mem::offset_of!(libc::rt_msghdr, rtm_addrs));
+    let expire = int(RMX_EXPIRE_OFFSET);
+
+    let mut addrs = [None; libc::RTAX_MAX as usize];
+    let mut buf = &message[RT_MSGHDR_LEN..];
+
+    for (i, addr) in addrs.iter_mut().enumerate() {
+        if addrs_mask & (1 << i) == 0 {
+            continue;
+        }
+
+        let Some(&len) = buf.first() else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "truncated routing message addresses",
+            ));
+        };
+        let len = usize::from(len);
+
+        // Socket addresses are padded to 4 bytes and even empty ones (e.g. the
+        // netmask of a default route) occupy the padding.
+        let padded_len = if len == 0 { 4 } else { (len + 3) & !3 };
+        if len > buf.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid socket address length",
+            ));
+        }
+
+        *addr = Some(&buf[..len]);
+        buf = buf.get(padded_len..).unwrap_or(&[]);
+    }
+
+    Ok(Message {
+        index,
+        flags,
+        expire,
+        addrs,
+    })
+}
+
+/// Parses a routing message of the `RTF_LLINFO` dump into a neighbor entry.
+///
+/// `None` is returned for messages that do not describe an IP neighbor.
+fn parse_neighbor(message: Message<'_>) -> std::io::Result<Option<Neighbor>> {
+    let Some(ip_addr) = message.addrs[libc::RTAX_DST as usize].and_then(parse_ip_addr) else {
+        return Ok(None);
+    };
+    let Some(link_addr) = message.addrs[libc::RTAX_GATEWAY as usize] else {
+        return Ok(None);
+    };
+    let Some(link_addr) = parse_link_addr(link_addr) else {
+        return Ok(None);
+    };
+
+    let mac_addr = <[u8; 6]>::try_from(link_addr.addr).ok().map(MacAddr::from);
+
+    // The system does not expose the reachability state through routing
+    // messages, so we can only tell static and unresolved entries apart.
+    let state = if message.flags & libc::RTF_STATIC != 0 || message.expire == 0 {
+        NeighborState::Permanent
+    } else if link_addr.addr.is_empty() {
+        NeighborState::Incomplete
+    } else {
+        NeighborState::Reachable
+    };
+
+    Ok(Some(Neighbor {
+        ip_addr,
+        mac_addr,
+        iface_index: u32::from(link_addr.index),
+        state,
+    }))
+}
+
+/// Parses a routing message of the routing table dump into a route.
+///
+/// `None` is returned for messages that do not describe an IP route or that
+/// describe an entry cloned from another (e.g. an entry of the neighbor cache).
+fn parse_route(message: Message<'_>) -> std::io::Result<Option<Route>> {
+    if message.flags & (libc::RTF_LLINFO | libc::RTF_WASCLONED) != 0 {
+        return Ok(None);
+    }
+
+    let Some(dest_addr) = message.addrs[libc::RTAX_DST as usize].and_then(parse_ip_addr) else {
+        return Ok(None);
+    };
+
+    let max_prefix_len = match dest_addr {
+        std::net::IpAddr::V4(_) => 32,
+        std::net::IpAddr::V6(_) => 128,
+    };
+    let dest_prefix_len = match message.addrs[libc::RTAX_NETMASK as usize] {
+        _ if message.flags & libc::RTF_HOST != 0 => max_prefix_len,
+        Some(netmask) => parse_prefix_len(netmask, dest_addr).min(max_prefix_len),
+        None => max_prefix_len,
+    };
+
+    // For directly attached networks, the gateway is a link-layer address of
+    // the interface rather than an IP address.
+    let gateway = message.addrs[libc::RTAX_GATEWAY as usize].and_then(parse_ip_addr);
+
+    Ok(Some(Route {
+        dest_addr,
+        dest_prefix_len,
+        gateway,
+        iface_index: Some(u32::from(message.index)),
+        // Routes on macOS have no metrics.
+        metric: None,
+    }))
+}
+
+/// Parses an IP address out of a `sockaddr_in` or `sockaddr_in6` structure.
+///
+/// `None` is returned for addresses of other families.
+fn parse_ip_addr(sockaddr: &[u8]) -> Option<std::net::IpAddr> {
+    match i32::from(*sockaddr.get(1)?) {
+        libc::AF_INET => {
+            let octets = <[u8; 4]>::try_from(sockaddr.get(4..8)?).ok()?;
+            Some(std::net::IpAddr::from(octets))
+        }
+        libc::AF_INET6 => {
+            let mut octets = <[u8; 16]>::try_from(sockaddr.get(8..24)?).ok()?;
+
+            // The kernel embeds the scope identifier of link-local addresses
+            // in the second 16-bit word of the address, so we need to clear it.
+            if octets[0] == 0xfe && octets[1] & 0xc0 == 0x80 ||
+               octets[0] == 0xff && octets[1] & 0x0f == 0x02 {
+                octets[2] = 0;
+                octets[3] = 0;
+            }
+
+            Some(std::net::IpAddr::from(octets))
+        }
+        _ => None,
+    }
+}
+
+/// Computes the prefix length of a netmask given as a socket address.
+///
+/// Netmasks are trimmed to their last non-zero byte (and can lack the family),
+/// so the address family is deduced from the destination address.
+fn parse_prefix_len(netmask: &[u8], dest_addr: std::net::IpAddr) -> u8 {
+    let offset = match dest_addr {
+        std::net::IpAddr::V4(_) => 4,
+        std::net::IpAddr::V6(_) => 8,
+    };
+
+    netmask.get(offset..).unwrap_or(&[]).iter()
+        .map(|byte| byte.count_ones() as u8)
+        .sum()
+}
+
+/// A link-layer address parsed out of a `sockaddr_dl` structure.
+struct LinkAddr<'a> {
+    /// Index of the interface the address belongs to.
+    index: u16,
+    /// Raw bytes of the address (empty if unresolved).
+    addr: &'a [u8],
+}
+
+/// Parses a `sockaddr_dl` structure.
+///
+/// `None` is returned for addresses of other families.
+fn parse_link_addr(sockaddr: &[u8]) -> Option<LinkAddr<'_>> {
+    if i32::from(*sockaddr.get(1)?) != libc::AF_LINK {
+        return None;
+    }
+
+    let index = u16::from_ne_bytes([*sockaddr.get(2)?, *sockaddr.get(3)?]);
+    let name_len = usize::from(*sockaddr.get(5)?);
+    let addr_len = usize::from(*sockaddr.get(6)?);
+
+    // The address follows the name of the interface in the data field.
+    let addr = sockaddr.get(8 + name_len..8 + name_len + addr_len)?;
+
+    Some(LinkAddr {
+        index,
+        addr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Builds a routing message with the given flags and socket addresses.
+    fn message(index: u16, flags: libc::c_int, expire: i32, addrs: &[(libc::c_int, &[u8])]) -> Vec<u8> {
+        let mut buf = vec![0; RT_MSGHDR_LEN];
+        buf[4..6].copy_from_slice(&index.to_ne_bytes());
+
+        let flags_offset = std::mem::offset_of!(libc::rt_msghdr, rtm_flags);
+        buf[flags_offset..flags_offset + 4].copy_from_slice(&flags.to_ne_bytes());
+
+        let mut mask = 0;
+        for (rta, sockaddr) in addrs {
+            mask |= rta;
+            buf.extend_from_slice(sockaddr);
+            buf.resize(buf.len() + (4 - sockaddr.len() % 4) % 4, 0);
+            if sockaddr.is_empty() {
+                buf.extend_from_slice(&[0; 4]);
+            }
+        }
+        let addrs_offset = std::mem::offset_of!(libc::rt_msghdr, rtm_addrs);
+        buf[addrs_offset..addrs_offset + 4].copy_from_slice(&mask.to_ne_bytes());
+        buf[RMX_EXPIRE_OFFSET..RMX_EXPIRE_OFFSET + 4].copy_from_slice(&expire.to_ne_bytes());
+
+        let len = buf.len() as u16;
+        buf[0..2].copy_from_slice(&len.to_ne_bytes());
+
+        buf
+    }
+
+    /// `sockaddr_in` with 192.0.2.1.
+    const SOCKADDR_IN: [u8; 16] = [
+        16, libc::AF_INET as u8, 0, 0, 192, 0, 2, 1,
+        0, 0, 0, 0, 0, 0, 0, 0,
+    ];
+
+    /// `sockaddr_in6` with fe80:4::1 (with the embedded scope identifier).
+    const SOCKADDR_IN6: [u8; 28] = [
+        28, libc::AF_INET6 as u8, 0, 0, 0, 0, 0, 0,
+        0xfe, 0x80, 0x00, 0x04, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 1,
+        0, 0, 0, 0,
+    ];
+
+    /// `sockaddr_dl` for the `en0` interface with index 4 and a MAC address.
+    const SOCKADDR_DL: [u8; 20] = [
+        20, libc::AF_LINK as u8, 4, 0, 6, 3, 6, 0,
+        b'e', b'n', b'0', 0x02, 0xfc, 0x00, 0x00, 0x00,
+        0x05, 0, 0, 0,
+    ];
+
+    #[test]
+    fn neighbors_ok() {
+        for neighbor in neighbors().unwrap() {
+            let neighbor = neighbor.unwrap();
+            assert!(!neighbor.ip_addr().is_unspecified());
+        }
+    }
+
+    #[test]
+    fn routes_ok() {
+        // Every system has at least a route to the loopback address.
+        assert!(routes().unwrap().any(|route| {
+            route.unwrap().dest_addr().is_loopback()
+        }));
+    }
+
+    #[test]
+    fn parse_neighbor_ok() {
+        let buf = message(4, libc::RTF_LLINFO | libc::RTF_HOST, 1200, &[
+            (libc::RTA_DST, &SOCKADDR_IN),
+            (libc::RTA_GATEWAY, &SOCKADDR_DL),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        let neighbor = parse_neighbor(message).unwrap().unwrap();
+
+        assert_eq!(neighbor.ip_addr(), std::net::IpAddr::from([192, 0, 2, 1]));
+        assert_eq!(neighbor.mac_addr(), Some(&MacAddr::from([0x02, 0xfc, 0x00, 0x00, 0x00, 0x05])));
+        assert_eq!(neighbor.iface_index(), 4);
+        assert_eq!(neighbor.state(), NeighborState::Reachable);
+    }
+
+    #[test]
+    fn parse_neighbor_incomplete() {
+        // `sockaddr_dl` for the `en0` interface without an address.
+        let sockaddr_dl = [
+            12, libc::AF_LINK as u8, 4, 0, 6, 3, 0, 0,
+            b'e', b'n', b'0', 0,
+        ];
+
+        let buf = message(4, libc::RTF_LLINFO | libc::RTF_HOST, 1200, &[
+            (libc::RTA_DST, &SOCKADDR_IN6),
+            (libc::RTA_GATEWAY, &sockaddr_dl),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        let neighbor = parse_neighbor(message).unwrap().unwrap();
+
+        assert_eq!(neighbor.ip_addr(), "fe80::1".parse::<std::net::IpAddr>().unwrap());
+        assert_eq!(neighbor.mac_addr(), None);
+        assert_eq!(neighbor.state(), NeighborState::Incomplete);
+    }
+
+    #[test]
+    fn parse_neighbor_permanent() {
+        let buf = message(4, libc::RTF_LLINFO | libc::RTF_HOST, 0, &[
+            (libc::RTA_DST, &SOCKADDR_IN),
+            (libc::RTA_GATEWAY, &SOCKADDR_DL),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        let neighbor = parse_neighbor(message).unwrap().unwrap();
+
+        assert_eq!(neighbor.state(), NeighborState::Permanent);
+    }
+
+    #[test]
+    fn parse_route_default() {
+        let buf = message(4, libc::RTF_GATEWAY | libc::RTF_STATIC, 0, &[
+            (libc::RTA_DST, &[16, libc::AF_INET as u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            (libc::RTA_GATEWAY, &SOCKADDR_IN),
+            // Netmask of default routes is empty.
+            (libc::RTA_NETMASK, &[]),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        let route = parse_route(message).unwrap().unwrap();
+
+        assert_eq!(route.dest_addr(), std::net::IpAddr::from([0, 0, 0, 0]));
+        assert_eq!(route.dest_prefix_len(), 0);
+        assert_eq!(route.gateway(), Some(std::net::IpAddr::from([192, 0, 2, 1])));
+        assert_eq!(route.iface_index(), Some(4));
+    }
+
+    #[test]
+    fn parse_route_attached() {
+        let buf = message(4, 0, 0, &[
+            (libc::RTA_DST, &[16, libc::AF_INET as u8, 0, 0, 192, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            (libc::RTA_GATEWAY, &SOCKADDR_DL),
+            // Netmask trimmed to its last non-zero byte and without family.
+            (libc::RTA_NETMASK, &[7, 0, 0, 0, 255, 255, 255]),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        let route = parse_route(message).unwrap().unwrap();
+
+        assert_eq!(route.dest_addr(), std::net::IpAddr::from([192, 0, 2, 0]));
+        assert_eq!(route.dest_prefix_len(), 24);
+        assert_eq!(route.gateway(), None);
+    }
+
+    #[test]
+    fn parse_route_host() {
+        let buf = message(1, libc::RTF_HOST, 0, &[
+            (libc::RTA_DST, &SOCKADDR_IN6),
+            (libc::RTA_GATEWAY, &SOCKADDR_DL),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        let route = parse_route(message).unwrap().unwrap();
+
+        assert_eq!(route.dest_addr(), "fe80::1".parse::<std::net::IpAddr>().unwrap());
+        assert_eq!(route.dest_prefix_len(), 128);
+    }
+
+    #[test]
+    fn parse_route_cloned() {
+        let buf = message(4, libc::RTF_LLINFO | libc::RTF_WASCLONED | libc::RTF_HOST, 1200, &[
+            (libc::RTA_DST, &SOCKADDR_IN),
+            (libc::RTA_GATEWAY, &SOCKADDR_DL),
+        ]);
+
+        let message = messages(&buf).next().unwrap().unwrap();
+        assert!(parse_route(message).unwrap().is_none());
+    }
+
+    #[test]
+    fn messages_invalid_len() {
+        let mut buf = message(4, 0, 0, &[]);
+        let len = buf.len() as u16 + 1;
+        buf[0..2].copy_from_slice(&len.to_ne_bytes());
+
+        let mut messages = messages(&buf);
+        assert!(messages.next().unwrap().is_err());
+        assert!(messages.next().is_none());
+    }
+}
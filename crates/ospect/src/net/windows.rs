@@ -6,6 +6,7 @@
 use super::*;
 
 mod conn;
+mod table;
 
 /// Collects information about available network interfaces.
 ///
@@ -117,19 +118,53 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
         // It's not the best that we have to clone `name` here to avoid borrow-
         // checker yelling at us, but considering all the cycles wasted above
         // on re-typing the string, it is a small price to pay anyway.
-        let entry = ifaces.entry(name.clone()).or_insert(Interface {
-            name: name,
-            ip_addrs: Vec::new(),
-            mac_addr: mac_addr,
+        let entry = ifaces.entry(name.clone()).or_insert_with(|| {
+            Interface::new(name)
         });
+        entry.mac_addr = mac_addr;
 
-        let mut sock_addr_iter = addr.FirstAnycastAddress;
+        // The IPv4 index is zero if IPv4 is not enabled on the interface, in
+        // which case we fall back to the IPv6 one [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/iptypes/ns-iptypes-ip_adapter_addresses_lh
+        //
+        // SAFETY: Accessing this union is safe as its variants are just
+        // alternative views of the same data.
+        entry.index = match unsafe { addr.Anonymous1.Anonymous.IfIndex } {
+            0 if addr.Ipv6IfIndex == 0 => None,
+            0 => Some(addr.Ipv6IfIndex),
+            index => Some(index),
+        };
+
+        // The MTU is set to `u32::MAX` if the value is not known.
+        if addr.Mtu != u32::MAX {
+            entry.mtu = Some(addr.Mtu);
+        }
+
+        // Windows does not distinguish between administrative and operational
+        // state in this structure, so both flags reflect the operational one.
+        let is_oper_up = addr.OperStatus == windows_sys::Win32::NetworkManagement::Ndis::IfOperStatusUp;
+        entry.flags = InterfaceFlags {
+            up: is_oper_up,
+            running: is_oper_up,
+            loopback: addr.IfType == IF_TYPE_SOFTWARE_LOOPBACK,
+        };
+
+        let mut sock_addr_iter = addr.FirstUnicastAddress;
         // SAFETY: We simply iterate on a linked list built by the system [1].
         // The list is terminated with a null node for which we check below to
         // end the iteration.
         //
         // [1]: https://learn.microsoft.com/en-us/windows/win32/api/iptypes/ns-iptypes-ip_adapter_addresses_lh
         while let Some(sock_addr) = unsafe { sock_addr_iter.as_ref() } {
+            // We advance the iterator immediately to avoid getting stuck on the
+            // `continue` statements below.
+            sock_addr_iter = sock_addr.Next;
+
+            // The prefix length is at most 128 (for IPv6), anything else would
+            // mean that the structure is malformed.
+            let prefix_len = u8::try_from(sock_addr.OnLinkPrefixLength).ok();
+
             // SAFETY: The structure is built by the operating system and so the
             // pointer (as long as it is not null, which we verify above) should
             // point to a valid address, making the dereference safe.
@@ -166,7 +201,10 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
                         ipv4_addr_octets.s_b4,
                     ]);
 
-                    entry.ip_addrs.push(ipv4_addr.into());
+                    entry.addrs.push(InterfaceAddr {
+                        ip_addr: ipv4_addr.into(),
+                        prefix_len,
+                    });
                 },
                 windows_sys::Win32::Networking::WinSock::AF_INET6 => {
                     use windows_sys::Win32::Networking::WinSock::SOCKADDR_IN6;
@@ -191,15 +229,16 @@ pub fn interfaces() -> std::io::Result<impl Iterator<Item = Interface>> {
 
                     let ipv6_addr = std::net::Ipv6Addr::from(ipv6_addr_octets);
 
-                    entry.ip_addrs.push(ipv6_addr.into());
+                    entry.addrs.push(InterfaceAddr {
+                        ip_addr: ipv6_addr.into(),
+                        prefix_len,
+                    });
                 },
                 _ => {
                     // TODO: Consider logging a warning.
                     continue
                 },
             }
-
-            sock_addr_iter = sock_addr.Next;
         }
 
         addr_iter = addr.Next;
@@ -272,6 +311,24 @@ pub fn all_udp_v6_connections() -> std::io::Result<impl Iterator<Item = std::io:
     self::conn::all_udp_v6()
 }
 
+/// Returns an iterator over entries of the neighbor cache.
+///
+/// This function is a wrapper around [`GetIpNetTable2`][1] Windows call.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/netioapi/nf-netioapi-getipnettable2
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    self::table::neighbors()
+}
+
+/// Returns an iterator over entries of the routing table.
+///
+/// This function is a wrapper around [`GetIpForwardTable2`][1] Windows call.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/netioapi/nf-netioapi-getipforwardtable2
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    self::table::routes()
+}
+
 // The official Microsoft documentation recommends "15KB" [1] as the default
 // buffer size but does not specify whether we talk about kibi- or kilo-bytes.
 // The example [2] uses literal "15000" value so we use the same thing.
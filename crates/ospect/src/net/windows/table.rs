@@ -0,0 +1,182 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::NetworkManagement::IpHelper::*;
+use windows_sys::Win32::Networking::WinSock::*;
+
+use crate::net::*;
+
+/// Returns an iterator over entries of the neighbor cache.
+pub fn neighbors() -> std::io::Result<impl Iterator<Item = std::io::Result<Neighbor>>> {
+    let mut table = std::ptr::null_mut::<MIB_IPNET_TABLE2>();
+
+    // SAFETY: We call the function as described in the official docs [1]. The
+    // table is allocated by the system and we free it once we are done.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/netioapi/nf-netioapi-getipnettable2
+    let code = unsafe {
+        GetIpNetTable2(AF_UNSPEC, &mut table)
+    };
+    if code != windows_sys::Win32::Foundation::NO_ERROR {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    let table = Table(table);
+
+    // SAFETY: The call succeeded, so the table is valid and has as many rows
+    // as its `NumEntries` field says.
+    let rows = unsafe {
+        let table = &*table.0;
+        std::slice::from_raw_parts(table.Table.as_ptr(), table.NumEntries as usize)
+    };
+
+    let neighbors = rows.iter()
+        .map(parse_neighbor)
+        .collect::<Vec<_>>();
+
+    Ok(neighbors.into_iter())
+}
+
+/// Returns an iterator over entries of the routing table.
+pub fn routes() -> std::io::Result<impl Iterator<Item = std::io::Result<Route>>> {
+    let mut table = std::ptr::null_mut::<MIB_IPFORWARD_TABLE2>();
+
+    // SAFETY: We call the function as described in the official docs [1]. The
+    // table is allocated by the system and we free it once we are done.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/netioapi/nf-netioapi-getipforwardtable2
+    let code = unsafe {
+        GetIpForwardTable2(AF_UNSPEC, &mut table)
+    };
+    if code != windows_sys::Win32::Foundation::NO_ERROR {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    let table = Table(table);
+
+    // SAFETY: The call succeeded, so the table is valid and has as many rows
+    // as its `NumEntries` field says.
+    let rows = unsafe {
+        let table = &*table.0;
+        std::slice::from_raw_parts(table.Table.as_ptr(), table.NumEntries as usize)
+    };
+
+    let routes = rows.iter()
+        .map(parse_route)
+        .collect::<Vec<_>>();
+
+    Ok(routes.into_iter())
+}
+
+/// RAII wrapper for tables allocated by the IP helper functions.
+struct Table<T>(*mut T);
+
+impl<T> Drop for Table<T> {
+
+    fn drop(&mut self) {
+        // SAFETY: The pointer was returned by one of the table functions and
+        // is freed only once.
+        unsafe {
+            FreeMibTable(self.0.cast());
+        }
+    }
+}
+
+/// Converts a row of the neighbor table to a neighbor entry.
+fn parse_neighbor(row: &MIB_IPNET_ROW2) -> std::io::Result<Neighbor> {
+    let ip_addr = parse_sockaddr_inet(&row.Address)?;
+
+    let mac_addr = match row.PhysicalAddressLength {
+        6 => {
+            let mut octets = [0; 6];
+            octets.copy_from_slice(&row.PhysicalAddress[..6]);
+            Some(MacAddr::from(octets))
+        }
+        // Entries that are not resolved have no address and entries of other
+        // (e.g. tunnel) interfaces have addresses that are not MAC ones.
+        _ => None,
+    };
+
+    // Constants of the neighbor state enum are not upper case in the bindings.
+    #[allow(non_upper_case_globals)]
+    let state = match row.State {
+        NlnsUnreachable => NeighborState::Failed,
+        NlnsIncomplete => NeighborState::Incomplete,
+        NlnsProbe => NeighborState::Probe,
+        NlnsDelay => NeighborState::Delay,
+        NlnsStale => NeighborState::Stale,
+        NlnsReachable => NeighborState::Reachable,
+        NlnsPermanent => NeighborState::Permanent,
+        _ => NeighborState::Unknown,
+    };
+
+    Ok(Neighbor {
+        ip_addr,
+        mac_addr,
+        iface_index: row.InterfaceIndex,
+        state,
+    })
+}
+
+/// Converts a row of the forwarding table to a route.
+fn parse_route(row: &MIB_IPFORWARD_ROW2) -> std::io::Result<Route> {
+    let dest_addr = parse_sockaddr_inet(&row.DestinationPrefix.Prefix)?;
+
+    // Routes to directly attached networks have the next hop unspecified.
+    let gateway = Some(parse_sockaddr_inet(&row.NextHop)?)
+        .filter(|addr| !addr.is_unspecified());
+
+    Ok(Route {
+        dest_addr,
+        dest_prefix_len: row.DestinationPrefix.PrefixLength,
+        gateway,
+        iface_index: Some(row.InterfaceIndex),
+        metric: Some(row.Metric),
+    })
+}
+
+/// Parses an IP address out of the `SOCKADDR_INET` union.
+fn parse_sockaddr_inet(sockaddr: &SOCKADDR_INET) -> std::io::Result<std::net::IpAddr> {
+    // SAFETY: The family field is shared by all the variants of the union, so
+    // it is always safe to read it. Then we read the variant it indicates.
+    unsafe {
+        match sockaddr.si_family {
+            AF_INET => {
+                let octets = sockaddr.Ipv4.sin_addr.S_un.S_un_b;
+                Ok(std::net::IpAddr::from([
+                    octets.s_b1, octets.s_b2, octets.s_b3, octets.s_b4,
+                ]))
+            }
+            AF_INET6 => {
+                Ok(std::net::IpAddr::from(sockaddr.Ipv6.sin6_addr.u.Byte))
+            }
+            family => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unexpected address family: {family}"),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn neighbors_ok() {
+        for neighbor in neighbors().unwrap() {
+            let neighbor = neighbor.unwrap();
+            assert!(!neighbor.ip_addr().is_unspecified());
+        }
+    }
+
+    #[test]
+    fn routes_loopback_exists() {
+        assert!(routes().unwrap().any(|route| {
+            route.unwrap().dest_addr().is_loopback()
+        }));
+    }
+}
@@ -51,6 +51,48 @@ pub fn installed() -> std::io::Result<std::time::SystemTime> {
     self::sys::installed()
 }
 
+/// Returns the time at which the system was booted.
+///
+/// # Errors
+///
+/// This function will return an error in case there was some error when trying
+/// to query data from the system.
+///
+/// # Examples
+///
+/// ```
+/// let time = ospect::os::booted()
+///     .unwrap();
+///
+/// assert!(time < std::time::SystemTime::now());
+/// ```
+pub fn booted() -> std::io::Result<std::time::SystemTime> {
+    self::sys::booted()
+}
+
+/// Returns the time that passed since the system was booted.
+///
+/// Unlike the difference between the current time and [`booted`], this is
+/// measured with a monotonic clock, so it is not affected by changes of the
+/// system time. The time the system was suspended is included.
+///
+/// # Errors
+///
+/// This function will return an error in case there was some error when trying
+/// to query data from the system.
+///
+/// # Examples
+///
+/// ```
+/// let uptime = ospect::os::uptime()
+///     .unwrap();
+///
+/// assert!(uptime > std::time::Duration::ZERO);
+/// ```
+pub fn uptime() -> std::io::Result<std::time::Duration> {
+    self::sys::uptime()
+}
+
 /// A list of operating systems that the library is guaranteed to run on.
 pub enum Kind {
     Linux,
@@ -79,6 +121,57 @@ pub fn version() -> std::io::Result<String> {
     self::sys::version()
 }
 
+/// Information about the release of the currently running operating system.
+#[derive(Clone, Debug)]
+pub struct Release {
+    /// Human-friendly name of the operating system (e.g. `Debian GNU/Linux`).
+    pub name: String,
+    /// Version of the operating system (e.g. `12`, `14.2.1`).
+    pub version: Option<String>,
+}
+
+/// Returns the release information of the currently running operating system.
+///
+/// The exact source of the information is system specific:
+///
+///   * On Linux it is parsed from the [`os-release`][1] file.
+///   * On macOS it is parsed from the `SystemVersion.plist` file (the same one
+///     that the `sw_vers` utility uses).
+///   * On Windows it uses the [`RtlGetVersion`][2] call.
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/os-release.html
+/// [2]: https://learn.microsoft.com/en-us/windows-hardware/drivers/ddi/wdm/nf-wdm-rtlgetversion
+///
+/// # Errors
+///
+/// This function will return an error in case there was some issue when trying
+/// to query data from the system (e.g. the `os-release` file is not present).
+///
+/// # Examples
+///
+/// ```no_run
+/// let release = ospect::os::release()
+///     .unwrap();
+///
+/// println!("{} {}", release.name, release.version.unwrap_or_default());
+/// ```
+pub fn release() -> std::io::Result<Release> {
+    self::sys::release()
+}
+
+/// Returns the kernel version of the currently running operating system.
+///
+/// On Unix systems this is the release string as reported by `uname` (e.g.
+/// `6.1.0-18-amd64`) and on Windows it is the `major.minor.build` triplet.
+///
+/// # Errors
+///
+/// This function will return an error in case there was some issue when trying
+/// to query data from the system.
+pub fn kernel_version() -> std::io::Result<String> {
+    self::sys::kernel_version()
+}
+
 /// Returns the CPU architecture of the currently running operating system.
 ///
 /// No assumptions on the specific format of this string should be made. Even
@@ -113,6 +206,43 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     self::sys::fqdn()
 }
 
+/// Information about the timezone of the currently running operating system.
+#[derive(Clone, Debug)]
+pub struct Timezone {
+    /// Name of the timezone (e.g. `Europe/Zurich`, `CET`).
+    ///
+    /// Depending on the system and its configuration, this can be a full name
+    /// from the [tz database][1], an abbreviation or a Windows-specific name
+    /// (e.g. `W. Europe Standard Time`).
+    ///
+    /// [1]: https://www.iana.org/time-zones
+    pub name: String,
+    /// Current offset from UTC (in seconds, positive values east of UTC).
+    ///
+    /// Note that this value includes daylight saving time adjustments.
+    pub utc_offset: i32,
+}
+
+/// Returns the timezone of the currently running operating system.
+///
+/// # Errors
+///
+/// This function will return an error in case there was some issue when trying
+/// to query data from the system.
+///
+/// # Examples
+///
+/// ```
+/// let timezone = ospect::os::timezone()
+///     .unwrap();
+///
+/// // No timezone is more than 14 hours away from UTC.
+/// assert!(timezone.utc_offset.abs() <= 14 * 60 * 60);
+/// ```
+pub fn timezone() -> std::io::Result<Timezone> {
+    self::sys::timezone()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -123,6 +253,38 @@ mod tests {
         assert!(!version().unwrap().is_empty());
     }
 
+    #[test]
+    fn booted_after_epoch_and_before_now() {
+        let booted = booted().unwrap();
+
+        assert!(booted > std::time::SystemTime::UNIX_EPOCH);
+        assert!(booted < std::time::SystemTime::now());
+    }
+
+    #[test]
+    fn uptime_monotonic() {
+        let uptime_1 = uptime().unwrap();
+        let uptime_2 = uptime().unwrap();
+
+        assert!(uptime_1 > std::time::Duration::ZERO);
+        assert!(uptime_1 <= uptime_2);
+    }
+
+    #[test]
+    fn release_name_not_empty() {
+        assert!(!release().unwrap().name.is_empty());
+    }
+
+    #[test]
+    fn kernel_version_not_empty() {
+        assert!(!kernel_version().unwrap().is_empty());
+    }
+
+    #[test]
+    fn timezone_name_not_empty() {
+        assert!(!timezone().unwrap().name.is_empty());
+    }
+
     #[test]
     fn arch_not_empty() {
         assert!(!arch().unwrap().is_empty());
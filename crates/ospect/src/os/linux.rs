@@ -8,6 +8,19 @@ pub fn installed() -> std::io::Result<std::time::SystemTime> {
     crate::os::unix::installed()
 }
 
+/// Returns the time at which the system was booted.
+pub fn booted() -> std::io::Result<std::time::SystemTime> {
+    let stat = std::fs::File::open("/proc/stat")?;
+    parse_boot_time(std::io::BufReader::new(stat))
+}
+
+/// Returns the time that passed since the system was booted.
+pub fn uptime() -> std::io::Result<std::time::Duration> {
+    // Unlike `CLOCK_MONOTONIC`, `CLOCK_BOOTTIME` includes the time the system
+    // was suspended.
+    crate::os::unix::clock_time(libc::CLOCK_BOOTTIME)
+}
+
 /// Returns the [`Kind`] of currently running operating system.
 ///
 /// [`Kind`]: crate::os::Kind
@@ -20,6 +33,28 @@ pub fn version() -> std::io::Result<String> {
     crate::os::unix::version()
 }
 
+/// Returns the release information of the currently running operating system.
+pub fn release() -> std::io::Result<crate::os::Release> {
+    // The `/etc/os-release` file takes precedence, but if it does not exist we
+    // should fall back to the `/usr/lib/os-release` one [1].
+    //
+    // [1]: https://www.freedesktop.org/software/systemd/man/latest/os-release.html
+    let file = match std::fs::File::open("/etc/os-release") {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            std::fs::File::open("/usr/lib/os-release")?
+        }
+        Err(error) => return Err(error),
+    };
+
+    parse_os_release(std::io::BufReader::new(file))
+}
+
+/// Returns the kernel version of the currently running operating system.
+pub fn kernel_version() -> std::io::Result<String> {
+    crate::os::unix::kernel_version()
+}
+
 /// Returns the CPU architecture of the currently running operating system.
 pub fn arch() -> std::io::Result<String> {
     crate::os::unix::arch()
@@ -34,3 +69,204 @@ pub fn hostname() -> std::io::Result<std::ffi::OsString> {
 pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     crate::os::unix::fqdn()
 }
+
+/// Returns the timezone of the currently running operating system.
+pub fn timezone() -> std::io::Result<crate::os::Timezone> {
+    crate::os::unix::timezone()
+}
+
+/// Parses boot time from the given `/proc/stat` file contents.
+///
+/// The boot time is specified as the number of seconds since the epoch on the
+/// line starting with `btime` [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man5/proc_stat.5.html
+fn parse_boot_time<R>(reader: R) -> std::io::Result<std::time::SystemTime>
+where
+    R: std::io::BufRead,
+{
+    for line in reader.lines() {
+        let line = line?;
+
+        let Some(btime) = line.strip_prefix("btime ") else {
+            continue;
+        };
+
+        let secs = btime.trim().parse::<u64>()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+        return Ok(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+    }
+
+    Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "no boot time"))
+}
+
+/// Parses release information from the given `os-release` file contents.
+///
+/// See the [documentation][1] for the details about the format.
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/os-release.html
+fn parse_os_release<R>(reader: R) -> std::io::Result<crate::os::Release>
+where
+    R: std::io::BufRead,
+{
+    let mut name = None;
+    let mut version_id = None;
+    let mut version = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = unquote_os_release_value(value);
+        match key {
+            "NAME" => name = Some(value),
+            "VERSION_ID" => version_id = Some(value),
+            "VERSION" => version = Some(value),
+            _ => (),
+        }
+    }
+
+    Ok(crate::os::Release {
+        // According to the documentation, if the name is not set, it should
+        // default to "Linux".
+        name: name.unwrap_or_else(|| String::from("Linux")),
+        // `VERSION_ID` is the one that is suitable for processing, so we use
+        // the more human-friendly `VERSION` only if it is not available.
+        version: version_id.or(version),
+    })
+}
+
+/// Removes quotes and escapes from a value of an `os-release` variable.
+fn unquote_os_release_value(value: &str) -> String {
+    let quote = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) if value.len() >= 2 && value.ends_with(quote) => quote,
+        _ => return String::from(value),
+    };
+
+    let mut result = String::new();
+
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(char) = chars.next() {
+        // Escapes are only allowed within double quotes.
+        if quote == '"' && char == '\\' {
+            if let Some(char) = chars.next() {
+                result.push(char);
+            }
+        } else {
+            result.push(char);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_boot_time_ok() {
+        let stat = b"\
+cpu  1 2 3 4 5 6 7 8 9 10
+intr 1234
+ctxt 5678
+btime 1700000000
+processes 42
+";
+
+        let booted = parse_boot_time(&stat[..])
+            .unwrap();
+
+        let expected = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000);
+        assert_eq!(booted, expected);
+    }
+
+    #[test]
+    fn parse_boot_time_missing() {
+        let stat = b"cpu  1 2 3 4 5 6 7 8 9 10\n";
+
+        assert!(parse_boot_time(&stat[..]).is_err());
+    }
+
+    #[test]
+    fn parse_boot_time_invalid() {
+        let stat = b"btime foo\n";
+
+        assert!(parse_boot_time(&stat[..]).is_err());
+    }
+
+    #[test]
+    fn parse_os_release_debian() {
+        let os_release = b"\
+PRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"
+NAME=\"Debian GNU/Linux\"
+VERSION_ID=\"12\"
+VERSION=\"12 (bookworm)\"
+VERSION_CODENAME=bookworm
+ID=debian
+";
+
+        let release = parse_os_release(&os_release[..])
+            .unwrap();
+
+        assert_eq!(release.name, "Debian GNU/Linux");
+        assert_eq!(release.version.as_deref(), Some("12"));
+    }
+
+    #[test]
+    fn parse_os_release_version_fallback() {
+        let os_release = b"\
+NAME='Foo Linux'
+VERSION=\"rolling\"
+";
+
+        let release = parse_os_release(&os_release[..])
+            .unwrap();
+
+        assert_eq!(release.name, "Foo Linux");
+        assert_eq!(release.version.as_deref(), Some("rolling"));
+    }
+
+    #[test]
+    fn parse_os_release_empty() {
+        let release = parse_os_release(&b""[..])
+            .unwrap();
+
+        assert_eq!(release.name, "Linux");
+        assert_eq!(release.version, None);
+    }
+
+    #[test]
+    fn parse_os_release_comments() {
+        let os_release = b"\
+# NAME=Foo
+NAME=Bar
+
+# VERSION_ID=1
+";
+
+        let release = parse_os_release(&os_release[..])
+            .unwrap();
+
+        assert_eq!(release.name, "Bar");
+        assert_eq!(release.version, None);
+    }
+
+    #[test]
+    fn unquote_os_release_value_escapes() {
+        assert_eq!(unquote_os_release_value(r#""foo \"bar\" \\ baz""#), r#"foo "bar" \ baz"#);
+        assert_eq!(unquote_os_release_value(r#"'foo \bar'"#), r#"foo \bar"#);
+        assert_eq!(unquote_os_release_value("foo"), "foo");
+        assert_eq!(unquote_os_release_value("\""), "\"");
+    }
+}
@@ -8,6 +8,59 @@ pub fn installed() -> std::io::Result<std::time::SystemTime> {
     crate::os::unix::installed()
 }
 
+/// Returns the time at which the system was booted.
+pub fn booted() -> std::io::Result<std::time::SystemTime> {
+    let mut mib = [libc::CTL_KERN, libc::KERN_BOOTTIME];
+
+    let mut boottime = std::mem::MaybeUninit::<libc::timeval>::uninit();
+    let mut boottime_len = std::mem::size_of::<libc::timeval>();
+
+    // SAFETY: We pass a valid MIB along with its length and a buffer of the
+    // size appropriate for the `kern.boottime` value [1]. We verify the result
+    // below.
+    //
+    // [1]: https://developer.apple.com/library/archive/documentation/System/Conceptual/ManPages_iPhoneOS/man3/sysctl.3.html
+    let code = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            boottime.as_mut_ptr().cast::<libc::c_void>(),
+            &mut boottime_len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if boottime_len != std::mem::size_of::<libc::timeval>() {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    // SAFETY: The call succeeded and we verified that the whole structure has
+    // been filled.
+    let boottime = unsafe {
+        boottime.assume_init()
+    };
+
+    let secs = u64::try_from(boottime.tv_sec)
+        .map_err(|_| std::io::ErrorKind::InvalidData)?;
+    let micros = u64::try_from(boottime.tv_usec)
+        .map_err(|_| std::io::ErrorKind::InvalidData)?;
+
+    let duration = std::time::Duration::from_secs(secs) +
+        std::time::Duration::from_micros(micros);
+
+    Ok(std::time::UNIX_EPOCH + duration)
+}
+
+/// Returns the time that passed since the system was booted.
+pub fn uptime() -> std::io::Result<std::time::Duration> {
+    // On macOS, `CLOCK_MONOTONIC` includes the time the system was asleep (as
+    // opposed to `CLOCK_UPTIME_RAW`).
+    crate::os::unix::clock_time(libc::CLOCK_MONOTONIC)
+}
+
 /// Returns the [`Kind`] of currently running operating system.
 ///
 /// [`Kind`]: crate::os::Kind
@@ -20,6 +73,25 @@ pub fn version() -> std::io::Result<String> {
     crate::os::unix::version()
 }
 
+/// Returns the release information of the currently running operating system.
+pub fn release() -> std::io::Result<crate::os::Release> {
+    let plist = std::fs::read_to_string(SYSTEM_VERSION_PLIST_PATH)?;
+
+    let name = plist_string(&plist, "ProductName")
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "no product name"))?;
+    let version = plist_string(&plist, "ProductVersion");
+
+    Ok(crate::os::Release {
+        name: String::from(name),
+        version: version.map(String::from),
+    })
+}
+
+/// Returns the kernel version of the currently running operating system.
+pub fn kernel_version() -> std::io::Result<String> {
+    crate::os::unix::kernel_version()
+}
+
 /// Returns the CPU architecture of the currently running operating system.
 pub fn arch() -> std::io::Result<String> {
     crate::os::unix::arch()
@@ -45,3 +117,71 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
 
     crate::os::unix::fqdn()
 }
+
+/// Returns the timezone of the currently running operating system.
+pub fn timezone() -> std::io::Result<crate::os::Timezone> {
+    crate::os::unix::timezone()
+}
+
+/// Path to the property list with the system version information.
+///
+/// This is the file that the `sw_vers` utility reads the information from.
+const SYSTEM_VERSION_PLIST_PATH: &str = "/System/Library/CoreServices/SystemVersion.plist";
+
+/// Extracts a string value for the given key from an XML property list.
+///
+/// This is not a real property list parser: it only supports flat dictionaries
+/// with string values and no XML entities (which is enough for the system
+/// version file).
+fn plist_string<'p>(plist: &'p str, key: &str) -> Option<&'p str> {
+    let key_tag = format!("<key>{key}</key>");
+
+    let (_, rest) = plist.split_once(&key_tag)?;
+    let rest = rest.trim_start().strip_prefix("<string>")?;
+    let (value, _) = rest.split_once("</string>")?;
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>BuildID</key>
+	<string>6F5A4E9E-1234-11EE-9C2B-2A4EE8A7E6AF</string>
+	<key>ProductBuildVersion</key>
+	<string>23C71</string>
+	<key>ProductCopyright</key>
+	<string>1983-2023 Apple Inc.</string>
+	<key>ProductName</key>
+	<string>macOS</string>
+	<key>ProductUserVisibleVersion</key>
+	<string>14.2.1</string>
+	<key>ProductVersion</key>
+	<string>14.2.1</string>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn plist_string_ok() {
+        assert_eq!(plist_string(PLIST, "ProductName"), Some("macOS"));
+        assert_eq!(plist_string(PLIST, "ProductVersion"), Some("14.2.1"));
+    }
+
+    #[test]
+    fn plist_string_missing() {
+        assert_eq!(plist_string(PLIST, "Foo"), None);
+    }
+
+    #[test]
+    fn plist_string_non_string() {
+        let plist = "<dict><key>Foo</key><integer>42</integer></dict>";
+        assert_eq!(plist_string(plist, "Foo"), None);
+    }
+}
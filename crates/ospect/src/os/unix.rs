@@ -9,6 +9,30 @@ pub fn installed() -> std::io::Result<std::time::SystemTime> {
     root_metadata.created()
 }
 
+/// Returns the current time of the given clock.
+pub fn clock_time(clock: libc::clockid_t) -> std::io::Result<std::time::Duration> {
+    let mut time = std::mem::MaybeUninit::<libc::timespec>::uninit();
+
+    // SAFETY: We pass a valid pointer to a buffer of the appropriate size. We
+    // verify the result below.
+    let code = unsafe {
+        libc::clock_gettime(clock, time.as_mut_ptr())
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the structure is initialized.
+    let time = unsafe { time.assume_init() };
+
+    let secs = u64::try_from(time.tv_sec)
+        .map_err(|_| std::io::ErrorKind::InvalidData)?;
+    let nanos = u32::try_from(time.tv_nsec)
+        .map_err(|_| std::io::ErrorKind::InvalidData)?;
+
+    Ok(std::time::Duration::new(secs, nanos))
+}
+
 /// Returns the version string of the currently running operating system.
 pub fn version() -> std::io::Result<String> {
     let uname = uname()?;
@@ -21,6 +45,18 @@ pub fn version() -> std::io::Result<String> {
     }.to_string_lossy().into_owned())
 }
 
+/// Returns the kernel version of the currently running operating system.
+pub fn kernel_version() -> std::io::Result<String> {
+    let uname = uname()?;
+
+    // SAFETY: All strings in `utsname` are guaranteed to be null-terminated.
+    // The buffer is valid for the entire scope of the function and we create
+    // an owned copy before we return, so the call is safe.
+    Ok(unsafe {
+        std::ffi::CStr::from_ptr(uname.release.as_ptr())
+    }.to_string_lossy().into_owned())
+}
+
 /// Returns the CPU architecture of the currently running operating system.
 pub fn arch() -> std::io::Result<String> {
     let uname = uname()?;
@@ -117,6 +153,98 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     Ok(fqdn)
 }
 
+/// Returns the timezone of the currently running operating system.
+pub fn timezone() -> std::io::Result<crate::os::Timezone> {
+    // SAFETY: `tzset` has no preconditions, it just initializes the timezone
+    // information used by `localtime_r` below (which, unlike `localtime`, is
+    // not required to do it on its own [1]).
+    //
+    // [1]: https://pubs.opengroup.org/onlinepubs/9699919799/functions/localtime.html
+    unsafe {
+        crate::libc::tzset();
+    }
+
+    // SAFETY: Passing null to `time` is allowed, in such case the result is
+    // only returned (and not written anywhere).
+    let now = unsafe {
+        libc::time(std::ptr::null_mut())
+    };
+
+    let mut tm = std::mem::MaybeUninit::uninit();
+
+    // SAFETY: We pass a valid pointer to the time value and a buffer for the
+    // result as described in the documentation [1]. We verify the result below.
+    //
+    // [1]: https://man7.org/linux/man-pages/man3/localtime_r.3p.html
+    let tm_ptr = unsafe {
+        libc::localtime_r(&now, tm.as_mut_ptr())
+    };
+    if tm_ptr.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the structure has been initialized.
+    let tm = unsafe {
+        tm.assume_init()
+    };
+
+    let name = match timezone_name() {
+        Some(name) => name,
+        None if tm.tm_zone.is_null() => {
+            return Err(std::io::ErrorKind::NotFound.into());
+        }
+        // SAFETY: We verified that the pointer is not null. It points to a
+        // null-terminated abbreviation [1] of static lifetime (or at least not
+        // shorter than until the next `tzset` call) and we make an owned copy
+        // of it immediately.
+        //
+        // [1]: https://man7.org/linux/man-pages/man3/tm.3type.html
+        None => unsafe {
+            std::ffi::CStr::from_ptr(tm.tm_zone)
+        }.to_string_lossy().into_owned(),
+    };
+
+    Ok(crate::os::Timezone {
+        name,
+        // The offset cannot be more than a day, so the cast is always safe even
+        // on platforms where `c_long` is 64-bit.
+        utc_offset: tm.tm_gmtoff as i32,
+    })
+}
+
+/// Returns the full name of the timezone of the currently running system.
+///
+/// The name is taken either from the `TZ` environment variable or it is derived
+/// from the `/etc/localtime` symlink (that usually points to a file within the
+/// `zoneinfo` directory). `None` is returned if neither is available.
+fn timezone_name() -> Option<String> {
+    if let Some(tz) = std::env::var_os("TZ") {
+        // Leading colon is allowed and has implementation-defined meaning [1],
+        // usually it means that the rest is a path to the timezone file.
+        //
+        // [1]: https://pubs.opengroup.org/onlinepubs/9699919799/basedefs/V1_chap08.html
+        let tz = tz.to_string_lossy();
+        let tz = tz.strip_prefix(':').unwrap_or(&tz);
+        if !tz.is_empty() {
+            return Some(String::from(tz));
+        }
+    }
+
+    let target = std::fs::read_link("/etc/localtime").ok()?;
+    zoneinfo_name(&target)
+}
+
+/// Extracts name of the timezone from the path to its `zoneinfo` file.
+fn zoneinfo_name(path: &std::path::Path) -> Option<String> {
+    let path = path.to_str()?;
+    let (_, name) = path.split_once("zoneinfo/")?;
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(String::from(name))
+}
+
 /// Returns `uname` information of the currently running operating system.
 fn uname() -> std::io::Result<libc::utsname> {
     let mut uname = std::mem::MaybeUninit::uninit();
@@ -138,3 +266,33 @@ fn uname() -> std::io::Result<libc::utsname> {
 
     Ok(uname)
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn zoneinfo_name_linux() {
+        let path = std::path::Path::new("/usr/share/zoneinfo/Europe/Zurich");
+        assert_eq!(zoneinfo_name(path).as_deref(), Some("Europe/Zurich"));
+    }
+
+    #[test]
+    fn zoneinfo_name_macos() {
+        let path = std::path::Path::new("/var/db/timezone/zoneinfo/Asia/Tokyo");
+        assert_eq!(zoneinfo_name(path).as_deref(), Some("Asia/Tokyo"));
+    }
+
+    #[test]
+    fn zoneinfo_name_relative() {
+        let path = std::path::Path::new("../usr/share/zoneinfo/Etc/UTC");
+        assert_eq!(zoneinfo_name(path).as_deref(), Some("Etc/UTC"));
+    }
+
+    #[test]
+    fn zoneinfo_name_invalid() {
+        assert_eq!(zoneinfo_name(std::path::Path::new("/etc/foo")), None);
+        assert_eq!(zoneinfo_name(std::path::Path::new("/zoneinfo/")), None);
+    }
+}
@@ -39,6 +39,31 @@ pub fn installed() -> std::io::Result<SystemTime> {
     Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(install_date_secs))
 }
 
+/// Returns the time at which the system was booted.
+pub fn booted() -> std::io::Result<SystemTime> {
+    // SAFETY: This function has no preconditions and always succeeds [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-gettickcount64
+    let uptime_millis = unsafe {
+        windows_sys::Win32::System::SystemInformation::GetTickCount64()
+    };
+
+    SystemTime::now().checked_sub(Duration::from_millis(uptime_millis))
+        .ok_or_else(|| std::io::ErrorKind::InvalidData.into())
+}
+
+/// Returns the time that passed since the system was booted.
+pub fn uptime() -> std::io::Result<Duration> {
+    // SAFETY: This function has no preconditions and always succeeds [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-gettickcount64
+    let uptime_millis = unsafe {
+        windows_sys::Win32::System::SystemInformation::GetTickCount64()
+    };
+
+    Ok(Duration::from_millis(uptime_millis))
+}
+
 /// Returns the [`Kind`] of currently running operating system.
 ///
 /// [`Kind`]: crate::os::Kind
@@ -193,6 +218,59 @@ pub fn version() -> std::io::Result<String> {
     Ok(format!("{major}.{minor}.{build}.{revision}"))
 }
 
+/// Returns the release information of the currently running operating system.
+pub fn release() -> std::io::Result<crate::os::Release> {
+    let info = rtl_version()?;
+
+    Ok(crate::os::Release {
+        name: String::from("Windows"),
+        version: Some(format! {
+            "{}.{}.{}",
+            info.dwMajorVersion, info.dwMinorVersion, info.dwBuildNumber,
+        }),
+    })
+}
+
+/// Returns the kernel version of the currently running operating system.
+pub fn kernel_version() -> std::io::Result<String> {
+    let info = rtl_version()?;
+
+    Ok(format! {
+        "{}.{}.{}",
+        info.dwMajorVersion, info.dwMinorVersion, info.dwBuildNumber,
+    })
+}
+
+/// Returns version information of the currently running operating system.
+///
+/// Unlike `GetVersionExW`, the `RtlGetVersion` function always reports the true
+/// version of the system, regardless of the application manifest.
+fn rtl_version() -> std::io::Result<windows_sys::Win32::System::SystemInformation::OSVERSIONINFOW> {
+    use windows_sys::Win32::System::SystemInformation::OSVERSIONINFOW;
+
+    // SAFETY: `OSVERSIONINFOW` is a plain C structure for which all zeroes is
+    // a valid value.
+    let mut info = unsafe {
+        std::mem::zeroed::<OSVERSIONINFOW>()
+    };
+    info.dwOSVersionInfoSize = std::mem::size_of::<OSVERSIONINFOW>() as u32;
+
+    // SAFETY: We pass a structure with the size field set as required by the
+    // documentation [1]. We verify the status below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows-hardware/drivers/ddi/wdm/nf-wdm-rtlgetversion
+    let status = unsafe {
+        windows_sys::Wdk::System::SystemServices::RtlGetVersion(&mut info)
+    };
+    if status != windows_sys::Win32::Foundation::STATUS_SUCCESS {
+        return Err(std::io::Error::other(format! {
+            "`RtlGetVersion` failure (status: {status:#x})",
+        }));
+    }
+
+    Ok(info)
+}
+
 /// Returns the CPU architecture of the currently running operating system.
 pub fn arch() -> std::io::Result<String> {
     use windows_sys::Win32::System::SystemInformation::*;
@@ -247,6 +325,49 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     computer_name(SystemInformation::ComputerNameDnsFullyQualified)
 }
 
+/// Returns the timezone of the currently running operating system.
+pub fn timezone() -> std::io::Result<crate::os::Timezone> {
+    use windows_sys::Win32::System::{
+        SystemServices::{TIME_ZONE_ID_DAYLIGHT, TIME_ZONE_ID_STANDARD},
+        Time::*,
+    };
+
+    let mut info = std::mem::MaybeUninit::uninit();
+
+    // SAFETY: We pass a buffer for the structure as described in the docs [1].
+    // We verify the result below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/timezoneapi/nf-timezoneapi-getdynamictimezoneinformation
+    let id = unsafe {
+        GetDynamicTimeZoneInformation(info.as_mut_ptr())
+    };
+    if id == TIME_ZONE_ID_INVALID {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the structure has been initialized.
+    let info = unsafe {
+        info.assume_init()
+    };
+
+    // Bias is the difference in minutes between UTC and the local time (that
+    // is, `UTC = local + bias`), so the offset has the opposite sign.
+    let bias = match id {
+        TIME_ZONE_ID_DAYLIGHT => info.Bias + info.DaylightBias,
+        TIME_ZONE_ID_STANDARD => info.Bias + info.StandardBias,
+        _ => info.Bias,
+    };
+
+    let name_len = info.TimeZoneKeyName.iter()
+        .position(|wchar| *wchar == 0)
+        .unwrap_or(info.TimeZoneKeyName.len());
+
+    Ok(crate::os::Timezone {
+        name: String::from_utf16_lossy(&info.TimeZoneKeyName[..name_len]),
+        utc_offset: -bias * 60,
+    })
+}
+
 /// Returns the name information of the currently running operating system.
 fn computer_name(
     format: windows_sys::Win32::System::SystemInformation::COMPUTER_NAME_FORMAT,
@@ -0,0 +1,94 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about named pipes on Windows.
+//!
+//! Named pipes on Windows live in a dedicated file system (mounted under the
+//! `\\.\pipe\` prefix) that is not visible to ordinary directory listings. On
+//! other systems named pipes (FIFOs) are just files that can be found on the
+//! regular file system, so there is nothing to expose here.
+
+mod windows;
+
+mod sys {
+    pub use crate::pipe::windows::*;
+}
+
+/// Information about a named pipe.
+#[derive(Clone, Debug)]
+pub struct Pipe {
+    /// Name of the pipe (relative to the `\\.\pipe\` prefix).
+    name: std::ffi::OsString,
+    /// Number of currently existing instances of the pipe.
+    instance_count: u32,
+}
+
+impl Pipe {
+
+    /// Returns the name of the pipe.
+    ///
+    /// The name is relative to the `\\.\pipe\` prefix, i.e. a pipe created as
+    /// `\\.\pipe\foo` has name `foo`. Note that pipe names can contain
+    /// backslashes, so the name is not necessarily a single path component.
+    pub fn name(&self) -> &std::ffi::OsStr {
+        &self.name
+    }
+
+    /// Returns the full path to the pipe (including the `\\.\pipe\` prefix).
+    pub fn path(&self) -> std::path::PathBuf {
+        let mut path = std::ffi::OsString::from(PREFIX);
+        path.push(&self.name);
+
+        std::path::PathBuf::from(path)
+    }
+
+    /// Returns the number of currently existing instances of the pipe.
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    /// Returns the identifier of the process that created the pipe.
+    ///
+    /// # Errors
+    ///
+    /// There is no way to query the server of a pipe without having a handle
+    /// to it, so this method connects to the pipe as a client (without asking
+    /// for read or write access). Thus, it fails if all instances of the pipe
+    /// are busy, if the pipe does not allow us to connect or if it no longer
+    /// exists.
+    ///
+    /// Be aware that the server process can observe such a connection and some
+    /// pipe servers might not react well to clients that disconnect without
+    /// saying anything.
+    pub fn server_pid(&self) -> std::io::Result<u32> {
+        self::sys::server_pid(&self.path())
+    }
+}
+
+/// Prefix under which all named pipes of the local machine are available.
+const PREFIX: &str = "\\\\.\\pipe\\";
+
+/// Returns an iterator over all named pipes on the local machine.
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to start listing the pipe
+/// file system. Each yielded item can also be an error if it was not possible
+/// to continue the listing (in which case the iterator ends).
+///
+/// # Examples
+///
+/// ```
+/// let pipes = ospect::pipe::pipes()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for pipe in pipes {
+///     println!("{}", pipe.name().to_string_lossy());
+/// }
+/// ```
+pub fn pipes() -> std::io::Result<impl Iterator<Item = std::io::Result<Pipe>>> {
+    self::sys::pipes()
+}
@@ -0,0 +1,295 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::Foundation::*;
+use windows_sys::Win32::Storage::FileSystem::*;
+
+use crate::pipe::{Pipe, PREFIX};
+
+/// Returns an iterator over all named pipes on the local machine.
+pub fn pipes() -> std::io::Result<Pipes> {
+    let pattern = format!("{PREFIX}*")
+        .encode_utf16()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+
+    // SAFETY: The find data structure consists of integers and arrays of them
+    // only, so all-zero bytes is a valid value.
+    let mut data = unsafe { std::mem::zeroed::<WIN32_FIND_DATAW>() };
+
+    // SAFETY: We pass a null-terminated pattern and a pointer to a structure
+    // to fill with the first entry [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findfirstfilew
+    let handle = unsafe {
+        FindFirstFileW(pattern.as_ptr(), &mut data)
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        let error = std::io::Error::last_os_error();
+
+        // In case there are no pipes at all (which is unlikely, but possible),
+        // the call fails and we just return an empty iterator.
+        if error.raw_os_error() == Some(ERROR_FILE_NOT_FOUND as i32) {
+            return Ok(Pipes {
+                handle: None,
+                first: None,
+            });
+        }
+
+        return Err(error);
+    }
+
+    Ok(Pipes {
+        handle: Some(FindHandle(handle)),
+        first: Some(data),
+    })
+}
+
+/// Iterator over named pipes on the local machine.
+pub struct Pipes {
+    /// Handle to the ongoing search (`None` if the search is over).
+    handle: Option<FindHandle>,
+    /// Entry returned when the search was started (if not yielded yet).
+    first: Option<WIN32_FIND_DATAW>,
+}
+
+impl Iterator for Pipes {
+
+    type Item = std::io::Result<Pipe>;
+
+    fn next(&mut self) -> Option<std::io::Result<Pipe>> {
+        if let Some(data) = self.first.take() {
+            return Some(Ok(pipe(&data)));
+        }
+
+        let handle = self.handle.as_ref()?;
+
+        // SAFETY: See the comment for `pipes`.
+        let mut data = unsafe { std::mem::zeroed::<WIN32_FIND_DATAW>() };
+
+        // SAFETY: We pass a valid search handle and a pointer to a structure to
+        // fill with the next entry [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findnextfilew
+        let status = unsafe {
+            FindNextFileW(handle.0, &mut data)
+        };
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+
+            // Either way there is nothing more we can list, so we close the
+            // search handle early.
+            self.handle = None;
+
+            if error.raw_os_error() == Some(ERROR_NO_MORE_FILES as i32) {
+                return None;
+            }
+            return Some(Err(error));
+        }
+
+        Some(Ok(pipe(&data)))
+    }
+}
+
+/// Returns the identifier of the process that created the pipe at `path`.
+pub fn server_pid(path: &std::path::Path) -> std::io::Result<u32> {
+    use std::os::windows::ffi::OsStrExt as _;
+    use windows_sys::Win32::System::Pipes::*;
+
+    let path = path.as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+
+    // SAFETY: We pass a null-terminated path of the pipe to open [1]. We ask
+    // only for reading attributes to avoid interfering with the data sent over
+    // the pipe and specify the anonymous impersonation level [2], so that the
+    // server is not able to act on our behalf.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-createfilew
+    // [2]: https://learn.microsoft.com/en-us/windows/win32/ipc/impersonating-a-named-pipe-client
+    let handle = unsafe {
+        CreateFileW(
+            path.as_ptr(),
+            FILE_READ_ATTRIBUTES,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null(),
+            OPEN_EXISTING,
+            SECURITY_SQOS_PRESENT | SECURITY_ANONYMOUS,
+            std::ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let handle = Handle(handle);
+
+    let mut pid = 0;
+
+    // SAFETY: We pass a valid handle to the client end of the pipe [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getnamedpipeserverprocessid
+    let status = unsafe {
+        GetNamedPipeServerProcessId(handle.0, &mut pid)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(pid)
+}
+
+/// Converts the given entry of the pipe file system to a pipe.
+fn pipe(data: &WIN32_FIND_DATAW) -> Pipe {
+    use std::os::windows::ffi::OsStringExt as _;
+
+    let name_len = data.cFileName.iter()
+        .position(|char| *char == 0)
+        .unwrap_or(data.cFileName.len());
+
+    Pipe {
+        name: std::ffi::OsString::from_wide(&data.cFileName[..name_len]),
+        // The pipe file system reports the current number of instances of the
+        // pipe as its size.
+        instance_count: data.nFileSizeLow,
+    }
+}
+
+/// RAII wrapper around search handles.
+struct FindHandle(HANDLE);
+
+impl Drop for FindHandle {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and we close it only once [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-findclose
+        unsafe {
+            FindClose(self.0);
+        }
+    }
+}
+
+/// RAII wrapper around generic handles.
+struct Handle(HANDLE);
+
+impl Drop for Handle {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and we close it only once [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn pipes_ok() {
+        let pipes = pipes().unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        for pipe in pipes {
+            assert!(!pipe.name().is_empty());
+        }
+    }
+
+    #[test]
+    fn pipes_created() {
+        let server = TestPipe::new("pipes_created");
+
+        let pipe = pipes().unwrap()
+            .filter_map(Result::ok)
+            .find(|pipe| pipe.name() == server.name.as_str())
+            .unwrap();
+
+        assert_eq!(pipe.instance_count(), 1);
+        assert_eq!(pipe.path(), std::path::PathBuf::from(format!("{PREFIX}{}", server.name)));
+    }
+
+    #[test]
+    fn server_pid_created() {
+        let server = TestPipe::new("server_pid_created");
+
+        let pid = server_pid(std::path::Path::new(&format!("{PREFIX}{}", server.name)))
+            .unwrap();
+
+        assert_eq!(pid, std::process::id());
+    }
+
+    #[test]
+    fn server_pid_non_existent() {
+        let path = format!("{PREFIX}rrg-test-non-existent-{}", random());
+        assert!(server_pid(std::path::Path::new(&path)).is_err());
+    }
+
+    /// Server end of a uniquely named pipe that is closed on drop.
+    struct TestPipe {
+        /// Name of the pipe (relative to the pipe prefix).
+        name: String,
+        /// Handle to the server end of the pipe.
+        handle: HANDLE,
+    }
+
+    impl TestPipe {
+
+        fn new(name: &str) -> TestPipe {
+            use windows_sys::Win32::System::Pipes::*;
+
+            let name = format!("rrg-test-{name}-{}", random());
+            let path = format!("{PREFIX}{name}")
+                .encode_utf16()
+                .chain(Some(0))
+                .collect::<Vec<u16>>();
+
+            // SAFETY: We pass a null-terminated name and allow a single
+            // instance (so that the pipe is not connected to by a stray client
+            // in the middle of the test).
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    path.as_ptr(),
+                    PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                    PIPE_TYPE_BYTE | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    0,
+                    0,
+                    0,
+                    std::ptr::null(),
+                )
+            };
+            assert_ne!(handle, INVALID_HANDLE_VALUE);
+
+            TestPipe {
+                name,
+                handle,
+            }
+        }
+    }
+
+    impl Drop for TestPipe {
+
+        fn drop(&mut self) {
+            // SAFETY: The handle is valid and we close it only once.
+            unsafe {
+                CloseHandle(self.handle);
+            }
+        }
+    }
+
+    /// Returns a random number to make test pipe names unique.
+    fn random() -> u64 {
+        use std::hash::BuildHasher as _;
+
+        std::collections::hash_map::RandomState::new().hash_one(())
+    }
+}
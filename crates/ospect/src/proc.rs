@@ -45,3 +45,398 @@ mod sys {
 pub fn ids() -> std::io::Result<impl Iterator<Item = std::io::Result<u32>>> {
     self::sys::ids()
 }
+
+/// Returns the name of the process with the specified identifier.
+///
+/// The name is a short name of the process executable (e.g. `sshd`), not the
+/// full path to it. Note that on some systems it can be truncated.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it.
+///
+/// # Examples
+///
+/// ```
+/// let name = ospect::proc::name(std::process::id())
+///     .unwrap();
+///
+/// assert!(!name.is_empty());
+/// ```
+pub fn name(pid: u32) -> std::io::Result<std::ffi::OsString> {
+    self::sys::name(pid)
+}
+
+/// Returns the identifier of the parent of the process with the specified
+/// identifier.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it.
+///
+/// # Examples
+///
+/// ```
+/// let pid = ospect::proc::parent_id(std::process::id())
+///     .unwrap();
+///
+/// assert_ne!(pid, std::process::id());
+/// ```
+pub fn parent_id(pid: u32) -> std::io::Result<u32> {
+    self::sys::parent_id(pid)
+}
+
+/// Returns the command-line arguments of the process with the specified
+/// identifier.
+///
+/// The first argument is usually (but not necessarily) the name or the path of
+/// the executable. The list can be empty, e.g. for kernel threads or processes
+/// that already exited but have not been reaped yet.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it.
+///
+/// # Examples
+///
+/// ```
+/// let args = ospect::proc::args(std::process::id())
+///     .unwrap();
+///
+/// assert!(!args.is_empty());
+/// ```
+pub fn args(pid: u32) -> std::io::Result<Vec<std::ffi::OsString>> {
+    self::sys::args(pid)
+}
+
+/// Returns the (effective) Unix identifier of the user owning the process with
+/// the specified identifier.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it.
+///
+/// # Examples
+///
+/// ```
+/// let uid = ospect::proc::uid(std::process::id())
+///     .unwrap();
+///
+/// // SAFETY: `geteuid` is always safe to call.
+/// assert_eq!(uid, unsafe { libc::geteuid() });
+/// ```
+#[cfg(target_family = "unix")]
+pub fn uid(pid: u32) -> std::io::Result<u32> {
+    self::sys::uid(pid)
+}
+
+/// Returns the security identifier of the user owning the process with the
+/// specified identifier (e.g. `S-1-5-18`).
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it.
+///
+/// # Examples
+///
+/// ```no_run
+/// let sid = ospect::proc::user_sid(std::process::id())
+///     .unwrap();
+///
+/// assert!(sid.starts_with("S-1-"));
+/// ```
+#[cfg(target_family = "windows")]
+pub fn user_sid(pid: u32) -> std::io::Result<String> {
+    self::sys::user_sid(pid)
+}
+
+/// Returns the size of resident memory of the current process (in bytes).
+///
+/// The resident memory is the part of the process memory that is currently
+/// held in RAM (i.e. not swapped out or not yet loaded).
+///
+/// # Errors
+///
+/// The function will return an error if the operating system does not allow
+/// to get the required information.
+///
+/// # Examples
+///
+/// ```
+/// let size = ospect::proc::resident_size()
+///     .unwrap();
+///
+/// assert!(size > 0);
+/// ```
+pub fn resident_size() -> std::io::Result<u64> {
+    self::sys::resident_size()
+}
+
+/// Memory region mapped into the address space of a process.
+#[derive(Clone, Debug)]
+pub struct Region {
+    /// Starting address of the region.
+    addr: u64,
+    /// Size of the region (in bytes).
+    size: u64,
+    /// Whether the region can be read.
+    readable: bool,
+    /// Whether the region can be written to.
+    writable: bool,
+    /// Whether the region can be executed.
+    executable: bool,
+    /// Whether the region is shared with other processes.
+    shared: bool,
+    /// Path to the file the region maps (if any).
+    path: Option<std::path::PathBuf>,
+}
+
+impl Region {
+
+    /// Returns the starting address of the region.
+    pub fn addr(&self) -> u64 {
+        self.addr
+    }
+
+    /// Returns the size of the region (in bytes).
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns whether the region can be read.
+    pub fn is_readable(&self) -> bool {
+        self.readable
+    }
+
+    /// Returns whether the region can be written to.
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Returns whether the region can be executed.
+    pub fn is_executable(&self) -> bool {
+        self.executable
+    }
+
+    /// Returns whether the region is shared with other processes.
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
+    /// Returns the path to the file the region maps.
+    ///
+    /// `None` is returned for anonymous regions (e.g. the heap or the stack).
+    pub fn path(&self) -> Option<&std::path::Path> {
+        self.path.as_deref()
+    }
+}
+
+/// Returns memory regions mapped into the address space of the specified
+/// process.
+///
+/// The regions are ordered by their starting addresses. Unlike [`Memory`], this
+/// does not require the memory of the process to be readable on Linux (where
+/// only its layout is inspected), so it works for more processes.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it.
+///
+/// # Examples
+///
+/// ```
+/// let regions = ospect::proc::regions(std::process::id())
+///     .unwrap();
+///
+/// assert!(regions.iter().any(|region| region.is_executable()));
+/// ```
+pub fn regions(pid: u32) -> std::io::Result<Vec<Region>> {
+    self::sys::regions(pid)
+}
+
+/// Handle to the memory of a process.
+///
+/// The handle allows to inspect the layout of the process address space and to
+/// read its contents (but not to modify it).
+pub struct Memory {
+    inner: self::sys::Memory,
+}
+
+impl Memory {
+
+    /// Opens the memory of the process with the specified identifier.
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if the process does not exist or if
+    /// the operating system does not allow to inspect its memory (which often
+    /// requires elevated privileges).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let memory = ospect::proc::Memory::open(std::process::id())
+    ///     .unwrap();
+    ///
+    /// assert!(memory.regions().unwrap().iter().any(|region| region.is_readable()));
+    /// ```
+    pub fn open(pid: u32) -> std::io::Result<Memory> {
+        Ok(Memory {
+            inner: self::sys::Memory::open(pid)?,
+        })
+    }
+
+    /// Returns memory regions currently mapped into the process address space.
+    ///
+    /// The regions are ordered by their starting addresses.
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if the layout of the address space
+    /// cannot be inspected (e.g. if the process has exited).
+    pub fn regions(&self) -> std::io::Result<Vec<Region>> {
+        self.inner.regions()
+    }
+
+    /// Reads memory of the process starting at the given address.
+    ///
+    /// Returns the number of bytes read which can be smaller than the size of
+    /// the buffer (e.g. if the end of a region was reached).
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if the memory at the given address is
+    /// not mapped or cannot be read.
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(addr, buf)
+    }
+}
+
+/// File opened by a process.
+#[derive(Clone, Debug)]
+pub struct OpenFile {
+    /// Identifier of the process that has the file open.
+    pid: u32,
+    /// Value of the file descriptor (or handle on Windows).
+    fd: u64,
+    /// Path to the file.
+    path: std::path::PathBuf,
+    /// Mode in which the file was opened (if known).
+    mode: Option<OpenMode>,
+    /// Whether the file has been deleted since it was opened.
+    deleted: bool,
+}
+
+/// Mode in which a file was opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpenMode {
+    /// The file was opened only for reading.
+    Read,
+    /// The file was opened only for writing.
+    Write,
+    /// The file was opened both for reading and writing.
+    ReadWrite,
+}
+
+impl OpenFile {
+
+    /// Returns the identifier of the process that has the file open.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the value of the file descriptor (or handle on Windows).
+    pub fn fd(&self) -> u64 {
+        self.fd
+    }
+
+    /// Returns the path to the file.
+    ///
+    /// For deleted files, this is the path the file had before it was deleted.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Returns the mode in which the file was opened (if known).
+    pub fn mode(&self) -> Option<OpenMode> {
+        self.mode
+    }
+
+    /// Returns whether the file has been deleted since it was opened.
+    ///
+    /// This is supported only on Linux, on other systems `false` is always
+    /// returned.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+}
+
+/// Returns an iterator over files opened by the process with the specified
+/// identifier.
+///
+/// Only files that have a path on a filesystem are included, i.e. sockets,
+/// pipes and other kinds of descriptors are skipped.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect it. Individual files that could
+/// not be inspected are yielded as errors as well.
+///
+/// # Examples
+///
+/// ```
+/// let _file = tempfile::tempfile()
+///     .unwrap();
+///
+/// let mut files = ospect::proc::open_files(std::process::id())
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// assert!(files.next().is_some());
+/// ```
+pub fn open_files(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<OpenFile>>> {
+    self::sys::open_files(pid)
+}
+
+/// Returns an iterator over files opened by all processes on the system.
+///
+/// This is equivalent to calling [`open_files`] for every process but it can
+/// be more efficient on some systems (e.g. on Windows all the handles are
+/// queried at once). Processes that exit in the meantime are skipped.
+///
+/// # Errors
+///
+/// The function will return an error if it is not possible to enumerate the
+/// processes. Processes and files that could not be inspected are yielded as
+/// errors.
+pub fn all_open_files() -> std::io::Result<impl Iterator<Item = std::io::Result<OpenFile>>> {
+    // There is no way to query files of all processes at once on Unix, so we
+    // have to go through them one by one.
+    #[cfg(target_family = "unix")]
+    let files = ids()?.flat_map(|pid| -> Box<dyn Iterator<Item = _>> {
+        match pid.and_then(open_files) {
+            Ok(files) => Box::new(files),
+            Err(error) if is_gone(&error) => Box::new(std::iter::empty()),
+            Err(error) => Box::new(std::iter::once(Err(error))),
+        }
+    });
+
+    #[cfg(target_family = "windows")]
+    let files = self::sys::all_open_files()?;
+
+    Ok(files)
+}
+
+/// Checks whether the given error means that the inspected process is gone.
+#[cfg(target_family = "unix")]
+fn is_gone(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::NotFound ||
+    error.raw_os_error() == Some(libc::ESRCH)
+}
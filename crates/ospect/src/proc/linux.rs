@@ -8,6 +8,307 @@ pub fn ids() -> std::io::Result<impl Iterator<Item = std::io::Result<u32>>> {
     Ids::new()
 }
 
+/// Returns the name of the process with the specified identifier.
+pub fn name(pid: u32) -> std::io::Result<std::ffi::OsString> {
+    use std::os::unix::ffi::OsStringExt as _;
+
+    // The `comm` file contains the executable name (truncated to 15 bytes by
+    // the kernel) followed by a newline character [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_comm.5.html
+    let mut name = std::fs::read(format!("/proc/{pid}/comm"))?;
+    if name.last() == Some(&b'\n') {
+        name.pop();
+    }
+
+    Ok(std::ffi::OsString::from_vec(name))
+}
+
+/// Returns the identifier of the parent of the specified process.
+pub fn parent_id(pid: u32) -> std::io::Result<u32> {
+    // The `stat` file contains the process name in parentheses followed by
+    // space-separated fields, the parent identifier being the second one [1].
+    // The name itself can contain spaces and parentheses, so we look for the
+    // last closing parenthesis.
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_stat.5.html
+    let stat = std::fs::read(format!("/proc/{pid}/stat"))?;
+
+    let fields_start = stat.iter().rposition(|byte| *byte == b')')
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+
+    let fields = std::str::from_utf8(&stat[fields_start + 1..])
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    fields.split_ascii_whitespace().nth(1)
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?
+        .parse::<u32>()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Returns the effective identifier of the user owning the specified process.
+pub fn uid(pid: u32) -> std::io::Result<u32> {
+    // The `status` file contains a line with the real, effective, saved and
+    // filesystem user identifiers of the process [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_status.5.html
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status"))?;
+
+    status.lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_ascii_whitespace().nth(1))
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?
+        .parse::<u32>()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Returns the command-line arguments of the specified process.
+pub fn args(pid: u32) -> std::io::Result<Vec<std::ffi::OsString>> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    // The `cmdline` file contains arguments separated (and terminated) by null
+    // bytes. It is empty for zombie processes and kernel threads [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_cmdline.5.html
+    let mut cmdline = std::fs::read(format!("/proc/{pid}/cmdline"))?;
+    if cmdline.last() == Some(&0) {
+        cmdline.pop();
+    }
+    if cmdline.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let args = cmdline.split(|byte| *byte == 0)
+        .map(|arg| std::ffi::OsStr::from_bytes(arg).to_os_string())
+        .collect();
+
+    Ok(args)
+}
+
+/// Returns the size of resident memory of the current process (in bytes).
+pub fn resident_size() -> std::io::Result<u64> {
+    // The `statm` file contains memory usage statistics measured in pages,
+    // the resident size is the second of them [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_statm.5.html
+    let statm = std::fs::read_to_string("/proc/self/statm")?;
+
+    let resident_pages = statm.split_ascii_whitespace().nth(1)
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?
+        .parse::<u64>()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    // SAFETY: This function is always safe to call.
+    let page_size = unsafe {
+        libc::sysconf(libc::_SC_PAGESIZE)
+    };
+    if page_size <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(resident_pages * page_size as u64)
+}
+
+/// Returns an iterator over files opened by the specified process.
+pub fn open_files(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::OpenFile>>> {
+    let entries = std::fs::read_dir(format!("/proc/{pid}/fd"))?;
+
+    Ok(entries.filter_map(move |entry| {
+        match entry.and_then(|entry| open_file(pid, &entry)) {
+            Ok(file) => file.map(Ok),
+            // The descriptor could have been closed since we listed them.
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => Some(Err(error)),
+        }
+    }))
+}
+
+/// Returns information about the file of the given `/proc/<pid>/fd` entry.
+///
+/// `None` is returned if the file descriptor does not correspond to a file on
+/// a filesystem (e.g. it is a socket or a pipe).
+fn open_file(
+    pid: u32,
+    entry: &std::fs::DirEntry,
+) -> std::io::Result<Option<crate::proc::OpenFile>> {
+    use std::os::unix::fs::MetadataExt as _;
+
+    let fd = entry.file_name().to_str()
+        .and_then(|name| name.parse::<u64>().ok())
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+
+    // Targets of descriptors that are not files on a filesystem are not paths
+    // but descriptions like `socket:[1337]` or `anon_inode:[eventfd]` [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_fd.5.html
+    let target = std::fs::read_link(entry.path())?;
+    if !target.is_absolute() {
+        return Ok(None);
+    }
+
+    // Paths of deleted files have the ` (deleted)` suffix appended. But a file
+    // can also be just named like this, so we strip the suffix only if there
+    // are no more links to the file (the metadata of the descriptor entry is
+    // the metadata of the file it points to, even if it is deleted).
+    let (path, deleted) = match strip_deleted_suffix(&target) {
+        Some(path) if std::fs::metadata(entry.path())?.nlink() == 0 => (path, true),
+        _ => (target, false),
+    };
+
+    Ok(Some(crate::proc::OpenFile {
+        pid,
+        fd,
+        path,
+        mode: open_mode(pid, fd).ok(),
+        deleted,
+    }))
+}
+
+/// Strips the suffix that the kernel appends to paths of deleted files.
+///
+/// `None` is returned if the path does not have the suffix.
+fn strip_deleted_suffix(path: &std::path::Path) -> Option<std::path::PathBuf> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let path = path.as_os_str().as_bytes().strip_suffix(b" (deleted)")?;
+    Some(std::path::PathBuf::from(std::ffi::OsStr::from_bytes(path)))
+}
+
+/// Returns the mode in which the specified file descriptor was opened.
+fn open_mode(pid: u32, fd: u64) -> std::io::Result<crate::proc::OpenMode> {
+    // The `fdinfo` file contains the flags the file was opened with as an
+    // octal number [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_fdinfo.5.html
+    let fdinfo = std::fs::read_to_string(format!("/proc/{pid}/fdinfo/{fd}"))?;
+
+    let flags = fdinfo.lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+    let flags = libc::c_int::from_str_radix(flags.trim(), 8)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    match flags & libc::O_ACCMODE {
+        libc::O_RDONLY => Ok(crate::proc::OpenMode::Read),
+        libc::O_WRONLY => Ok(crate::proc::OpenMode::Write),
+        libc::O_RDWR => Ok(crate::proc::OpenMode::ReadWrite),
+        _ => Err(std::io::ErrorKind::InvalidData.into()),
+    }
+}
+
+/// Returns memory regions mapped into the address space of the specified
+/// process.
+pub fn regions(pid: u32) -> std::io::Result<Vec<crate::proc::Region>> {
+    let maps = std::fs::read(format!("/proc/{pid}/maps"))?;
+
+    maps.split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(parse_maps_line)
+        .collect()
+}
+
+/// A Linux-specific handle to the memory of a process.
+pub struct Memory {
+    /// Identifier of the process.
+    pid: u32,
+    /// Handle to the `/proc/<pid>/mem` file.
+    file: std::fs::File,
+}
+
+impl Memory {
+
+    /// Opens the memory of the process with the specified identifier.
+    pub fn open(pid: u32) -> std::io::Result<Memory> {
+        // Opening the file requires the same permissions as attaching to the
+        // process with `ptrace` [1].
+        //
+        // [1]: https://man7.org/linux/man-pages/man5/proc_pid_mem.5.html
+        let file = std::fs::File::open(format!("/proc/{pid}/mem"))?;
+
+        Ok(Memory { pid, file })
+    }
+
+    /// Returns memory regions currently mapped into the process address space.
+    pub fn regions(&self) -> std::io::Result<Vec<crate::proc::Region>> {
+        regions(self.pid)
+    }
+
+    /// Reads memory of the process starting at the given address.
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::unix::fs::FileExt as _;
+
+        self.file.read_at(buf, addr)
+    }
+}
+
+/// Parses a single line of the `/proc/<pid>/maps` file.
+///
+/// Lines have the `start-end perms offset dev inode [path]` format [1], e.g.
+/// `7f2c4e600000-7f2c4e628000 r--p 00000000 fe:01 1835099 /usr/lib/libc.so.6`.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/proc_pid_maps.5.html
+fn parse_maps_line(line: &[u8]) -> std::io::Result<crate::proc::Region> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let invalid_data = || std::io::Error::new(std::io::ErrorKind::InvalidData, format! {
+        "malformed memory map entry: {:?}", String::from_utf8_lossy(line)
+    });
+
+    let mut rest = line;
+    let mut next_field = || -> std::io::Result<&[u8]> {
+        let start = rest.iter().position(|byte| *byte != b' ').unwrap_or(rest.len());
+        rest = &rest[start..];
+
+        let end = rest.iter().position(|byte| *byte == b' ').unwrap_or(rest.len());
+        let (field, field_rest) = rest.split_at(end);
+        rest = field_rest;
+
+        if field.is_empty() {
+            Err(invalid_data())
+        } else {
+            Ok(field)
+        }
+    };
+
+    let range = std::str::from_utf8(next_field()?)
+        .map_err(|_| invalid_data())?;
+    let (start, end) = range.split_once('-')
+        .ok_or_else(invalid_data)?;
+    let start = u64::from_str_radix(start, 16)
+        .map_err(|_| invalid_data())?;
+    let end = u64::from_str_radix(end, 16)
+        .map_err(|_| invalid_data())?;
+
+    let perms = next_field()?;
+    let [read, write, exec, share] = perms[..] else {
+        return Err(invalid_data());
+    };
+
+    let _offset = next_field()?;
+    let _dev = next_field()?;
+    let _inode = next_field()?;
+
+    // The path is separated from the inode by a variable amount of spaces and
+    // can contain spaces itself. Pseudo-paths (e.g. `[heap]` or `[stack]`) do
+    // not correspond to any file and so we treat them as anonymous regions.
+    let path = rest.trim_ascii_start();
+    let path = if path.starts_with(b"/") {
+        Some(std::path::PathBuf::from(std::ffi::OsStr::from_bytes(path)))
+    } else {
+        None
+    };
+
+    Ok(crate::proc::Region {
+        addr: start,
+        size: end.checked_sub(start).ok_or_else(invalid_data)?,
+        readable: read == b'r',
+        writable: write == b'w',
+        executable: exec == b'x',
+        shared: share == b's',
+        path,
+    })
+}
+
 /// A Linux-specific implementation of the iterator over process identifiers.
 struct Ids {
     /// An iterator over contents of the `/proc` directory.
@@ -70,3 +371,236 @@ impl Iterator for Ids {
 
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn open_files_self() {
+        use std::io::Write as _;
+
+        let mut file = tempfile::NamedTempFile::new()
+            .unwrap();
+        file.write_all(b"foo")
+            .unwrap();
+
+        let path = file.path().canonicalize()
+            .unwrap();
+
+        let open_file = open_files(std::process::id())
+            .unwrap()
+            .filter_map(Result::ok)
+            .find(|open_file| open_file.path() == path)
+            .unwrap();
+
+        assert_eq!(open_file.pid(), std::process::id());
+        assert_eq!(open_file.mode(), Some(crate::proc::OpenMode::ReadWrite));
+        assert!(!open_file.is_deleted());
+    }
+
+    #[test]
+    fn open_files_self_deleted() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().canonicalize()
+            .unwrap()
+            .join("foo");
+
+        let file = std::fs::File::create(&path)
+            .unwrap();
+        std::fs::remove_file(&path)
+            .unwrap();
+
+        let fd = file.as_raw_fd() as u64;
+
+        let open_file = open_files(std::process::id())
+            .unwrap()
+            .filter_map(Result::ok)
+            .find(|open_file| open_file.fd() == fd)
+            .unwrap();
+
+        assert_eq!(open_file.path(), path);
+        assert_eq!(open_file.mode(), Some(crate::proc::OpenMode::Write));
+        assert!(open_file.is_deleted());
+    }
+
+    #[test]
+    fn open_files_self_deleted_suffix() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().canonicalize()
+            .unwrap()
+            .join("foo (deleted)");
+
+        std::fs::File::create(&path)
+            .unwrap();
+        let file = std::fs::File::open(&path)
+            .unwrap();
+
+        let fd = file.as_raw_fd() as u64;
+
+        let open_file = open_files(std::process::id())
+            .unwrap()
+            .filter_map(Result::ok)
+            .find(|open_file| open_file.fd() == fd)
+            .unwrap();
+
+        assert_eq!(open_file.path(), path);
+        assert_eq!(open_file.mode(), Some(crate::proc::OpenMode::Read));
+        assert!(!open_file.is_deleted());
+    }
+
+    #[test]
+    fn strip_deleted_suffix_present() {
+        let path = strip_deleted_suffix("/tmp/foo (deleted)".as_ref());
+        assert_eq!(path.as_deref(), Some("/tmp/foo".as_ref()));
+    }
+
+    #[test]
+    fn strip_deleted_suffix_absent() {
+        assert_eq!(strip_deleted_suffix("/tmp/foo".as_ref()), None);
+    }
+
+    #[test]
+    fn name_self() {
+        let name = name(std::process::id())
+            .unwrap();
+
+        // Test binaries are named after the crate with a hash suffix, but the
+        // kernel truncates process names, so we only check the prefix.
+        assert!(name.to_str().unwrap().starts_with("ospect"));
+    }
+
+    #[test]
+    fn parent_id_self() {
+        let parent_id = parent_id(std::process::id())
+            .unwrap();
+
+        assert_eq!(parent_id, std::os::unix::process::parent_id());
+    }
+
+    #[test]
+    fn args_self() {
+        let args = args(std::process::id())
+            .unwrap();
+
+        let expected = std::env::args_os().collect::<Vec<_>>();
+        assert_eq!(args, expected);
+    }
+
+    #[test]
+    fn args_zombie() {
+        let mut child = std::process::Command::new("true")
+            .spawn()
+            .unwrap();
+
+        // We wait until the child exits but do not reap it yet, so it stays in
+        // the process table as a zombie.
+        // SAFETY: We pass a valid pointer to the info structure.
+        let status = unsafe {
+            let mut info = std::mem::zeroed::<libc::siginfo_t>();
+            libc::waitid(libc::P_PID, child.id(), &mut info, libc::WEXITED | libc::WNOWAIT)
+        };
+        assert_eq!(status, 0);
+
+        let args = args(child.id())
+            .unwrap();
+        assert!(args.is_empty());
+
+        child.wait()
+            .unwrap();
+    }
+
+    #[test]
+    fn resident_size_self() {
+        let size = resident_size()
+            .unwrap();
+
+        // The resident size is a multiple of the page size, which is at least
+        // 4 KiB on all supported architectures.
+        assert!(size > 0);
+        assert_eq!(size % 4096, 0);
+    }
+
+    #[test]
+    fn memory_read_self() {
+        let marker = Box::new(*b"ospect-memory-marker");
+
+        let memory = Memory::open(std::process::id())
+            .unwrap();
+
+        let mut buf = [0; 20];
+        let len = memory.read(marker.as_ptr() as u64, &mut buf)
+            .unwrap();
+
+        assert_eq!(&buf[..len], &marker[..]);
+    }
+
+    #[test]
+    fn memory_regions_self() {
+        let memory = Memory::open(std::process::id())
+            .unwrap();
+
+        let regions = memory.regions()
+            .unwrap();
+
+        // The test executable itself has to be mapped into the memory.
+        let exe_path = std::env::current_exe()
+            .unwrap();
+        assert!(regions.iter().any(|region| region.path() == Some(&exe_path)));
+    }
+
+    #[test]
+    fn parse_maps_line_file() {
+        let region = parse_maps_line(b"7f2c4e600000-7f2c4e628000 r-xp 00000000 fe:01 1835099                    /usr/lib/libc.so.6")
+            .unwrap();
+
+        assert_eq!(region.addr(), 0x7f2c4e600000);
+        assert_eq!(region.size(), 0x28000);
+        assert!(region.is_readable());
+        assert!(!region.is_writable());
+        assert!(region.is_executable());
+        assert!(!region.is_shared());
+        assert_eq!(region.path(), Some(std::path::Path::new("/usr/lib/libc.so.6")));
+    }
+
+    #[test]
+    fn parse_maps_line_path_with_spaces() {
+        let region = parse_maps_line(b"7f2c4e600000-7f2c4e628000 rw-s 00000000 fe:01 1835099 /tmp/foo bar (deleted)")
+            .unwrap();
+
+        assert!(region.is_shared());
+        assert_eq!(region.path(), Some(std::path::Path::new("/tmp/foo bar (deleted)")));
+    }
+
+    #[test]
+    fn parse_maps_line_anonymous() {
+        let region = parse_maps_line(b"55d0c5a4e000-55d0c5a6f000 rw-p 00000000 00:00 0                          [heap]")
+            .unwrap();
+
+        assert_eq!(region.addr(), 0x55d0c5a4e000);
+        assert_eq!(region.size(), 0x21000);
+        assert!(region.is_readable());
+        assert!(region.is_writable());
+        assert_eq!(region.path(), None);
+
+        let region = parse_maps_line(b"7f2c4e400000-7f2c4e500000 ---p 00000000 00:00 0")
+            .unwrap();
+
+        assert!(!region.is_readable());
+        assert_eq!(region.path(), None);
+    }
+
+    #[test]
+    fn parse_maps_line_malformed() {
+        assert!(parse_maps_line(b"7f2c4e600000-7f2c4e628000 r-xp").is_err());
+        assert!(parse_maps_line(b"7f2c4e600000 r-xp 00000000 fe:01 1835099").is_err());
+        assert!(parse_maps_line(b"7f2c4e628000-7f2c4e600000 r-xp 00000000 fe:01 0").is_err());
+    }
+}
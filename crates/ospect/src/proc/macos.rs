@@ -8,6 +8,461 @@ pub fn ids() -> std::io::Result<impl Iterator<Item = std::io::Result<u32>>> {
     Ids::new()
 }
 
+/// Returns the name of the process with the specified identifier.
+pub fn name(pid: u32) -> std::io::Result<std::ffi::OsString> {
+    use std::os::unix::ffi::OsStringExt as _;
+
+    let pid = libc::c_int::try_from(pid)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    // Process names on macOS are limited to `2 * MAXCOMLEN` bytes (where the
+    // `MAXCOMLEN` constant is 16), we add one more byte for the terminator.
+    let mut buf = vec![0u8; 2 * 16 + 1];
+
+    // SAFETY: We pass a valid buffer together with its size. The function will
+    // not write past it and returns the number of bytes written (or zero in
+    // case of an error).
+    let len = unsafe {
+        libc::proc_name(pid, buf.as_mut_ptr().cast(), buf.len() as u32)
+    };
+    if len <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    buf.truncate(len as usize);
+    Ok(std::ffi::OsString::from_vec(buf))
+}
+
+/// Returns the identifier of the parent of the specified process.
+pub fn parent_id(pid: u32) -> std::io::Result<u32> {
+    Ok(bsd_info(pid)?.pbi_ppid)
+}
+
+/// Returns the effective identifier of the user owning the specified process.
+pub fn uid(pid: u32) -> std::io::Result<u32> {
+    Ok(bsd_info(pid)?.pbi_uid)
+}
+
+/// Returns an iterator over files opened by the specified process.
+pub fn open_files(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::OpenFile>>> {
+    let pid_i32 = libc::c_int::try_from(pid)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    let fds = fds(pid_i32)?;
+
+    Ok(fds.into_iter().filter_map(move |fd| {
+        if fd.proc_fdtype != crate::libc::PROX_FDTYPE_VNODE as u32 {
+            return None;
+        }
+
+        match open_file(pid_i32, fd.proc_fd) {
+            Ok(file) => Some(Ok(file)),
+            // The descriptor could have been closed since we listed them.
+            Err(error) if error.raw_os_error() == Some(libc::EBADF) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }))
+}
+
+/// Returns all file descriptors of the specified process.
+fn fds(pid: libc::c_int) -> std::io::Result<Vec<crate::libc::proc_fdinfo>> {
+    const FDINFO_SIZE: usize = std::mem::size_of::<crate::libc::proc_fdinfo>();
+
+    // SAFETY: We call the function with a null buffer. This returns the size
+    // of the buffer that we need (or a non-positive value on error).
+    let buf_size = unsafe {
+        libc::proc_pidinfo(pid, crate::libc::PROC_PIDLISTFDS, 0, std::ptr::null_mut(), 0)
+    };
+    if buf_size <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // New descriptors can be opened between the calls, so we leave some room
+    // for them.
+    let buf_len = buf_size as usize / FDINFO_SIZE + FDS_SLACK;
+    let mut buf = Vec::<crate::libc::proc_fdinfo>::with_capacity(buf_len);
+
+    // SAFETY: We call the function as above but with an allocated buffer along
+    // with its size (in bytes).
+    let buf_size = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            crate::libc::PROC_PIDLISTFDS,
+            0,
+            buf.as_mut_ptr().cast(),
+            (buf_len * FDINFO_SIZE) as libc::c_int,
+        )
+    };
+    if buf_size <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if buf_size as usize % FDINFO_SIZE != 0 {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    // SAFETY: The call succeeded and returned the number of bytes written to
+    // the buffer, which is a multiple of the entry size (verified above).
+    unsafe {
+        buf.set_len(buf_size as usize / FDINFO_SIZE);
+    }
+
+    Ok(buf)
+}
+
+/// Returns information about the file of the given vnode file descriptor.
+fn open_file(pid: libc::c_int, fd: libc::c_int) -> std::io::Result<crate::proc::OpenFile> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let mut info = std::mem::MaybeUninit::<crate::libc::vnode_fdinfowithpath>::uninit();
+    let info_size = std::mem::size_of::<crate::libc::vnode_fdinfowithpath>() as libc::c_int;
+
+    // SAFETY: We pass a buffer for the info structure together with its size.
+    // The function returns the number of bytes written (or a non-positive
+    // value in case of an error).
+    let len = unsafe {
+        libc::proc_pidfdinfo(pid, fd, crate::libc::PROC_PIDFDVNODEPATHINFO, info.as_mut_ptr().cast(), info_size)
+    };
+    if len <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if len != info_size {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    // SAFETY: The call succeeded and filled the whole structure.
+    let info = unsafe { info.assume_init() };
+
+    // SAFETY: The path buffer is an array of arrays of bytes, so it can be
+    // viewed as a flat array of bytes of the same total size.
+    let path = unsafe {
+        std::slice::from_raw_parts(
+            info.pvip.vip_path.as_ptr().cast::<u8>(),
+            std::mem::size_of_val(&info.pvip.vip_path),
+        )
+    };
+    let path = std::ffi::CStr::from_bytes_until_nul(path)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    let openflags = info.pfi.fi_openflags;
+    let mode = match (openflags & crate::libc::FREAD != 0, openflags & crate::libc::FWRITE != 0) {
+        (true, false) => Some(crate::proc::OpenMode::Read),
+        (false, true) => Some(crate::proc::OpenMode::Write),
+        (true, true) => Some(crate::proc::OpenMode::ReadWrite),
+        (false, false) => None,
+    };
+
+    Ok(crate::proc::OpenFile {
+        pid: pid as u32,
+        fd: fd as u64,
+        path: std::path::PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes())),
+        mode,
+        deleted: false,
+    })
+}
+
+/// Number of additional entries in the buffer for file descriptors.
+const FDS_SLACK: usize = 16;
+
+/// Returns the BSD information about the specified process.
+fn bsd_info(pid: u32) -> std::io::Result<libc::proc_bsdinfo> {
+    let pid = libc::c_int::try_from(pid)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    let mut info = std::mem::MaybeUninit::<libc::proc_bsdinfo>::uninit();
+    let info_size = std::mem::size_of::<libc::proc_bsdinfo>() as libc::c_int;
+
+    // SAFETY: We pass a buffer for the info structure together with its size.
+    // The function returns the number of bytes written (or a non-positive
+    // value in case of an error).
+    let len = unsafe {
+        libc::proc_pidinfo(pid, libc::PROC_PIDTBSDINFO, 0, info.as_mut_ptr().cast(), info_size)
+    };
+    if len <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if len != info_size {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    // SAFETY: The call succeeded and filled the whole structure.
+    Ok(unsafe { info.assume_init() })
+}
+
+/// Returns the command-line arguments of the specified process.
+pub fn args(pid: u32) -> std::io::Result<Vec<std::ffi::OsString>> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let pid = libc::c_int::try_from(pid)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    let mut arg_max = 0 as libc::c_int;
+    let mut arg_max_size = std::mem::size_of::<libc::c_int>();
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+
+    // SAFETY: We call the `sysctl` function as described in the FreeBSD
+    // documentation [1] passing a buffer for a single integer.
+    //
+    // [1]: https://man.freebsd.org/cgi/man.cgi?sysctl(3)
+    let code = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(), mib.len() as libc::c_uint,
+            (&mut arg_max as *mut libc::c_int).cast(), &mut arg_max_size,
+            std::ptr::null_mut(), 0,
+        )
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0u8; arg_max as usize];
+    let mut buf_size = buf.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+
+    // SAFETY: As above, but we pass a buffer big enough to fit arguments of
+    // any process (the system-wide argument limit).
+    let code = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(), mib.len() as libc::c_uint,
+            buf.as_mut_ptr().cast(), &mut buf_size,
+            std::ptr::null_mut(), 0,
+        )
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    buf.truncate(buf_size);
+
+    Ok(parse_procargs(&buf)?
+        .into_iter()
+        .map(|arg| std::ffi::OsStr::from_bytes(arg).to_os_string())
+        .collect())
+}
+
+/// Parses arguments out of a buffer returned by the `KERN_PROCARGS2` sysctl.
+///
+/// The buffer starts with the number of arguments followed by the executable
+/// path, null padding and the null-terminated arguments themselves (which are
+/// then followed by environment variables that we are not interested in).
+fn parse_procargs(buf: &[u8]) -> std::io::Result<Vec<&[u8]>> {
+    const ARGC_SIZE: usize = std::mem::size_of::<libc::c_int>();
+
+    let argc = buf.get(..ARGC_SIZE)
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+    let argc = libc::c_int::from_ne_bytes(argc.try_into().unwrap());
+
+    let mut rest = &buf[ARGC_SIZE..];
+
+    // We skip the executable path and the padding that follows it.
+    let path_len = rest.iter().position(|byte| *byte == 0)
+        .unwrap_or(rest.len());
+    rest = &rest[path_len..];
+    let padding_len = rest.iter().position(|byte| *byte != 0)
+        .unwrap_or(rest.len());
+    rest = &rest[padding_len..];
+
+    let args = rest.split(|byte| *byte == 0)
+        .take(usize::try_from(argc).unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    Ok(args)
+}
+
+/// Returns the size of resident memory of the current process (in bytes).
+pub fn resident_size() -> std::io::Result<u64> {
+    let mut info = std::mem::MaybeUninit::<libc::mach_task_basic_info>::uninit();
+    let mut count = libc::MACH_TASK_BASIC_INFO_COUNT;
+
+    // SAFETY: We pass a buffer for the info structure together with its size
+    // (expressed in the number of integers, as expected by the function).
+    let code = unsafe {
+        #[allow(deprecated)] // `libc` suggests using `mach2` for this.
+        libc::task_info(
+            libc::mach_task_self(),
+            libc::MACH_TASK_BASIC_INFO,
+            info.as_mut_ptr().cast(),
+            &mut count,
+        )
+    };
+    if code != libc::KERN_SUCCESS {
+        return Err(kern_error(code));
+    }
+
+    // SAFETY: The call succeeded, so the structure is filled.
+    let info = unsafe { info.assume_init() };
+
+    Ok(info.resident_size)
+}
+
+/// Returns memory regions mapped into the address space of the specified
+/// process.
+pub fn regions(pid: u32) -> std::io::Result<Vec<crate::proc::Region>> {
+    // There is no way to inspect the layout without a handle to the memory.
+    Memory::open(pid)?.regions()
+}
+
+/// A macOS-specific handle to the memory of a process.
+pub struct Memory {
+    /// Identifier of the process.
+    pid: libc::c_int,
+    /// Port of the task of the process.
+    task: libc::mach_port_t,
+}
+
+impl Memory {
+
+    /// Opens the memory of the process with the specified identifier.
+    pub fn open(pid: u32) -> std::io::Result<Memory> {
+        let pid = libc::c_int::try_from(pid)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+        let mut task = std::mem::MaybeUninit::uninit();
+
+        // SAFETY: We pass a valid pointer for the task port. Getting the port
+        // of any other process requires elevated privileges (or special code
+        // signing entitlements), the function fails otherwise which is checked
+        // below.
+        let code = unsafe {
+            #[allow(deprecated)] // `libc` suggests using `mach2` for this.
+            libc::task_for_pid(libc::mach_task_self(), pid, task.as_mut_ptr())
+        };
+        if code != libc::KERN_SUCCESS {
+            return Err(kern_error(code));
+        }
+
+        Ok(Memory {
+            pid,
+            // SAFETY: The call succeeded, so the port is filled.
+            task: unsafe { task.assume_init() },
+        })
+    }
+
+    /// Returns memory regions currently mapped into the process address space.
+    pub fn regions(&self) -> std::io::Result<Vec<crate::proc::Region>> {
+        let mut regions = Vec::new();
+        let mut addr = 0;
+
+        loop {
+            let mut size = 0;
+            let mut info = std::mem::MaybeUninit::<crate::libc::vm_region_basic_info_64>::uninit();
+            let mut info_count = crate::libc::VM_REGION_BASIC_INFO_COUNT_64;
+            let mut object_name = 0;
+
+            // SAFETY: We pass a buffer for the region information along with
+            // its size (as the number of integers, not bytes). On success, the
+            // address and size are updated to match the first region at (or
+            // after) the given address.
+            let code = unsafe {
+                crate::libc::mach_vm_region(
+                    self.task,
+                    &mut addr,
+                    &mut size,
+                    crate::libc::VM_REGION_BASIC_INFO_64,
+                    info.as_mut_ptr().cast(),
+                    &mut info_count,
+                    &mut object_name,
+                )
+            };
+            match code {
+                libc::KERN_SUCCESS => (),
+                // There are no more regions after the given address.
+                libc::KERN_INVALID_ADDRESS => break,
+                _ => return Err(kern_error(code)),
+            }
+
+            // SAFETY: The call succeeded, so the information is filled.
+            let info = unsafe { info.assume_init() };
+            let protection = info.protection;
+
+            regions.push(crate::proc::Region {
+                addr,
+                size,
+                readable: protection & libc::VM_PROT_READ != 0,
+                writable: protection & libc::VM_PROT_WRITE != 0,
+                executable: protection & libc::VM_PROT_EXECUTE != 0,
+                shared: info.shared != 0,
+                path: region_path(self.pid, addr),
+            });
+
+            addr = match addr.checked_add(size) {
+                Some(addr) => addr,
+                None => break,
+            };
+        }
+
+        Ok(regions)
+    }
+
+    /// Reads memory of the process starting at the given address.
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut len = 0;
+
+        // SAFETY: We pass a buffer (as an address in our own address space)
+        // along with its size. The function will not write past it and sets
+        // the number of bytes actually read.
+        let code = unsafe {
+            crate::libc::mach_vm_read_overwrite(
+                self.task,
+                addr,
+                buf.len() as libc::mach_vm_size_t,
+                buf.as_mut_ptr() as libc::mach_vm_address_t,
+                &mut len,
+            )
+        };
+        if code != libc::KERN_SUCCESS {
+            return Err(kern_error(code));
+        }
+
+        Ok(len as usize)
+    }
+}
+
+impl Drop for Memory {
+
+    fn drop(&mut self) {
+        // SAFETY: The port was obtained by us and we no longer need it. There
+        // is not much we can do if releasing fails, so we ignore the result.
+        unsafe {
+            #[allow(deprecated)] // `libc` suggests using `mach2` for this.
+            crate::libc::mach_port_deallocate(libc::mach_task_self(), self.task);
+        }
+    }
+}
+
+/// Returns the path to the file mapped at the given address of the process.
+fn region_path(pid: libc::c_int, addr: u64) -> Option<std::path::PathBuf> {
+    use std::os::unix::ffi::OsStringExt as _;
+
+    let mut buf = vec![0u8; libc::PATH_MAX as usize];
+
+    // SAFETY: We pass a buffer along with its size. The function returns the
+    // number of bytes written or zero if the region does not map any file.
+    let len = unsafe {
+        libc::proc_regionfilename(pid, addr, buf.as_mut_ptr().cast(), buf.len() as u32)
+    };
+    if len <= 0 {
+        return None;
+    }
+    buf.truncate(len as usize);
+
+    Some(std::path::PathBuf::from(std::ffi::OsString::from_vec(buf)))
+}
+
+/// Converts a Mach error code into an I/O error.
+fn kern_error(code: libc::kern_return_t) -> std::io::Error {
+    use std::io::ErrorKind;
+
+    let kind = match code {
+        libc::KERN_INVALID_ADDRESS => ErrorKind::InvalidInput,
+        libc::KERN_PROTECTION_FAILURE => ErrorKind::PermissionDenied,
+        // `task_for_pid` fails with this generic error if we are not allowed
+        // to access the process (or if it does not exist).
+        libc::KERN_FAILURE => ErrorKind::PermissionDenied,
+        _ => ErrorKind::Other,
+    };
+
+    std::io::Error::new(kind, format!("mach error {code}"))
+}
+
 /// A macOS-specific implementation of the iterator over process identifiers.
 struct Ids {
     /// An iterator over the process metadata returned by a `sysctl` call.
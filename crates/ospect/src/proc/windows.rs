@@ -8,6 +8,1054 @@ pub fn ids() -> std::io::Result<impl Iterator<Item = std::io::Result<u32>>> {
     Ids::new()
 }
 
+/// Returns the name of the process with the specified identifier.
+pub fn name(pid: u32) -> std::io::Result<std::ffi::OsString> {
+    use std::os::windows::ffi::OsStringExt as _;
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::Threading::*,
+    };
+
+    // SAFETY: This function is safe to call with any arguments. We verify the
+    // returned handle below.
+    let handle = unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid)
+    };
+    if handle.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0u16; MAX_IMAGE_NAME_LEN];
+    let mut buf_len = buf.len() as u32;
+
+    // SAFETY: The handle is valid (verified above) and we pass a buffer along
+    // with its length (in characters). On success, the function updates the
+    // length with the number of characters written (without the terminator).
+    let status = unsafe {
+        QueryFullProcessImageNameW(handle, 0, buf.as_mut_ptr(), &mut buf_len)
+    };
+    let error = std::io::Error::last_os_error();
+
+    // SAFETY: The handle is valid and we no longer need it. There is not much
+    // we can do if closing fails, so we ignore the result.
+    unsafe {
+        CloseHandle(handle);
+    }
+
+    if status == FALSE {
+        return Err(error);
+    }
+    buf.truncate(buf_len as usize);
+
+    // The function gives us the full path to the executable from which we are
+    // interested only in the file name.
+    let path = std::path::PathBuf::from(std::ffi::OsString::from_wide(&buf));
+    match path.file_name() {
+        Some(name) => Ok(name.to_os_string()),
+        None => Err(std::io::ErrorKind::InvalidData.into()),
+    }
+}
+
+/// Returns the identifier of the parent of the specified process.
+pub fn parent_id(pid: u32) -> std::io::Result<u32> {
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::Diagnostics::ToolHelp::*,
+    };
+
+    // SAFETY: This function is safe to call with any arguments (the process
+    // identifier is ignored for process snapshots). We verify the returned
+    // handle below.
+    let snapshot = unsafe {
+        CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
+    };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: All-zero bit pattern is a valid value of the entry structure.
+    let mut entry = unsafe {
+        std::mem::zeroed::<PROCESSENTRY32W>()
+    };
+    entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+
+    // SAFETY: The snapshot handle is valid (verified above) and we pass a
+    // pointer to the entry structure with the size field set, as required.
+    let mut status = unsafe {
+        Process32FirstW(snapshot, &mut entry)
+    };
+
+    let result = loop {
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(ERROR_NO_MORE_FILES as i32) {
+                break Err(std::io::ErrorKind::NotFound.into());
+            }
+            break Err(error);
+        }
+
+        if entry.th32ProcessID == pid {
+            break Ok(entry.th32ParentProcessID);
+        }
+
+        // SAFETY: Same as with the call to `Process32FirstW` above.
+        status = unsafe {
+            Process32NextW(snapshot, &mut entry)
+        };
+    };
+
+    // SAFETY: The handle is valid and we no longer need it. There is not much
+    // we can do if closing fails, so we ignore the result.
+    unsafe {
+        CloseHandle(snapshot);
+    }
+
+    result
+}
+
+/// Returns the command-line arguments of the specified process.
+pub fn args(pid: u32) -> std::io::Result<Vec<std::ffi::OsString>> {
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::Threading::*,
+    };
+
+    // SAFETY: This function is safe to call with any arguments. We verify the
+    // returned handle below.
+    let handle = unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid)
+    };
+    if handle.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let cmdline = command_line(handle);
+
+    // SAFETY: The handle is valid and we no longer need it. There is not much
+    // we can do if closing fails, so we ignore the result.
+    unsafe {
+        CloseHandle(handle);
+    }
+
+    split_command_line(&cmdline?)
+}
+
+/// Returns the raw command line of the process with the given handle.
+fn command_line(
+    handle: windows_sys::Win32::Foundation::HANDLE,
+) -> std::io::Result<Vec<u16>> {
+    use windows_sys::{
+        Wdk::System::Threading::*,
+        Win32::Foundation::*,
+    };
+
+    let mut buf_size = 0;
+
+    // SAFETY: We call the function without a buffer to learn the size of the
+    // buffer needed to hold the command line and verify the status below.
+    let status = unsafe {
+        NtQueryInformationProcess(
+            handle,
+            ProcessCommandLineInformation,
+            std::ptr::null_mut(),
+            0,
+            &mut buf_size,
+        )
+    };
+    if status != STATUS_INFO_LENGTH_MISMATCH {
+        return Err(nt_error(status));
+    }
+
+    // The buffer contains a `UNICODE_STRING` structure (that includes a pointer
+    // to the string data placed right after it), so it has to be aligned.
+    let buf_len = (buf_size as usize).div_ceil(std::mem::size_of::<u64>());
+    let mut buf = vec![0u64; buf_len];
+
+    // SAFETY: We pass a buffer of the size specified by the previous call.
+    let status = unsafe {
+        NtQueryInformationProcess(
+            handle,
+            ProcessCommandLineInformation,
+            buf.as_mut_ptr().cast(),
+            buf_size,
+            &mut buf_size,
+        )
+    };
+    if status < 0 {
+        return Err(nt_error(status));
+    }
+
+    // SAFETY: The call succeeded and so the buffer starts with a valid
+    // `UNICODE_STRING` structure with the data pointing into the buffer.
+    let cmdline = unsafe {
+        let string = &*buf.as_ptr().cast::<UNICODE_STRING>();
+        if string.Buffer.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let len = string.Length as usize / std::mem::size_of::<u16>();
+        std::slice::from_raw_parts(string.Buffer, len).to_vec()
+    };
+
+    Ok(cmdline)
+}
+
+/// Splits the given command line into individual arguments.
+fn split_command_line(cmdline: &[u16]) -> std::io::Result<Vec<std::ffi::OsString>> {
+    use std::os::windows::ffi::OsStringExt as _;
+    use windows_sys::Win32::{
+        Foundation::*,
+        UI::Shell::*,
+    };
+
+    // `CommandLineToArgvW` returns the path to the current executable for an
+    // empty command line which is not what we want.
+    if cmdline.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut cmdline = cmdline.to_vec();
+    cmdline.push(0);
+
+    let mut argc = 0;
+
+    // SAFETY: We pass a null-terminated string. The function returns an array
+    // of pointers to null-terminated strings (or null in case of an error)
+    // that has to be freed with `LocalFree`.
+    let argv = unsafe {
+        CommandLineToArgvW(cmdline.as_ptr(), &mut argc)
+    };
+    if argv.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut args = Vec::with_capacity(argc as usize);
+    for i in 0..argc as usize {
+        // SAFETY: The array returned by the function has `argc` elements, each
+        // being a valid null-terminated string.
+        let arg = unsafe {
+            let arg = *argv.add(i);
+
+            let mut len = 0;
+            while *arg.add(len) != 0 {
+                len += 1;
+            }
+
+            std::slice::from_raw_parts(arg, len)
+        };
+
+        args.push(std::ffi::OsString::from_wide(arg));
+    }
+
+    // SAFETY: The array was allocated by `CommandLineToArgvW` and we no longer
+    // use it (the arguments have been copied above).
+    unsafe {
+        LocalFree(argv.cast());
+    }
+
+    Ok(args)
+}
+
+/// Converts an `NTSTATUS` code into an I/O error.
+fn nt_error(status: windows_sys::Win32::Foundation::NTSTATUS) -> std::io::Error {
+    // SAFETY: This function is safe to call with any status code.
+    let code = unsafe {
+        windows_sys::Win32::Foundation::RtlNtStatusToDosError(status)
+    };
+
+    std::io::Error::from_raw_os_error(code as i32)
+}
+
+/// Returns the security identifier of the user owning the specified process.
+pub fn user_sid(pid: u32) -> std::io::Result<String> {
+    use windows_sys::Win32::{
+        Foundation::*,
+        Security::*,
+        Security::Authorization::*,
+        System::Threading::*,
+    };
+
+    // SAFETY: This function is safe to call with any arguments. We verify the
+    // returned handle below.
+    let process = unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid)
+    };
+    if process.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut token = std::ptr::null_mut();
+
+    // SAFETY: The process handle is valid (verified above). On success, the
+    // token handle is written to the given location.
+    let status = unsafe {
+        OpenProcessToken(process, TOKEN_QUERY, &mut token)
+    };
+    let error = std::io::Error::last_os_error();
+
+    // SAFETY: The handle is valid and we no longer need it. There is not much
+    // we can do if closing fails, so we ignore the result.
+    unsafe {
+        CloseHandle(process);
+    }
+
+    if status == FALSE {
+        return Err(error);
+    }
+
+    // The token user information consists of a pointer to the SID followed by
+    // the SID itself, so a buffer for the structure and the largest possible
+    // SID is always enough. We use `u64` elements to have it properly aligned.
+    const BUF_SIZE: usize = std::mem::size_of::<TOKEN_USER>() + SECURITY_MAX_SID_SIZE as usize;
+    let mut buf = [0u64; BUF_SIZE.div_ceil(std::mem::size_of::<u64>())];
+    let mut buf_len = 0;
+
+    // SAFETY: The token handle is valid (verified above) and we pass a buffer
+    // along with its size (in bytes).
+    let status = unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            buf.as_mut_ptr().cast(),
+            std::mem::size_of_val(&buf) as u32,
+            &mut buf_len,
+        )
+    };
+    let error = std::io::Error::last_os_error();
+
+    // SAFETY: The handle is valid and we no longer need it. There is not much
+    // we can do if closing fails, so we ignore the result.
+    unsafe {
+        CloseHandle(token);
+    }
+
+    if status == FALSE {
+        return Err(error);
+    }
+
+    // SAFETY: The call succeeded, so the buffer starts with a valid structure
+    // and the SID it points to lies within the buffer.
+    let sid = unsafe {
+        (*buf.as_ptr().cast::<TOKEN_USER>()).User.Sid
+    };
+
+    let mut sid_str = std::ptr::null_mut();
+
+    // SAFETY: The SID is valid (see above). The string is allocated by the
+    // system and we free it below.
+    let status = unsafe {
+        ConvertSidToStringSidW(sid, &mut sid_str)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut len = 0;
+    // SAFETY: The call succeeded, so the string is valid and null-terminated.
+    while unsafe { *sid_str.add(len) } != 0 {
+        len += 1;
+    }
+    // SAFETY: We just verified that there are `len` valid characters.
+    let result = String::from_utf16(unsafe {
+        std::slice::from_raw_parts(sid_str, len)
+    });
+
+    // SAFETY: The string was allocated by `ConvertSidToStringSidW` and it has
+    // to be freed with `LocalFree` [1]. We copied it above.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertsidtostringsidw
+    unsafe {
+        LocalFree(sid_str.cast());
+    }
+
+    result.map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Returns the size of resident memory of the current process (in bytes).
+pub fn resident_size() -> std::io::Result<u64> {
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::ProcessStatus::*,
+        System::Threading::*,
+    };
+
+    let mut counters = std::mem::MaybeUninit::<PROCESS_MEMORY_COUNTERS>::uninit();
+
+    // SAFETY: The pseudo-handle of the current process is always valid and we
+    // pass a buffer for the counters together with its size.
+    let status = unsafe {
+        GetProcessMemoryInfo(
+            GetCurrentProcess(),
+            counters.as_mut_ptr(),
+            std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the counters are filled.
+    let counters = unsafe { counters.assume_init() };
+
+    // On Windows the resident memory is called the "working set".
+    Ok(counters.WorkingSetSize as u64)
+}
+
+/// Returns an iterator over files opened by the specified process.
+pub fn open_files(pid: u32) -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::OpenFile>>> {
+    // We open the process upfront so that an error is returned if it does not
+    // exist or cannot be inspected.
+    let process = open_dup_process(pid)?;
+
+    let handles = handles()?
+        .into_iter()
+        .filter(|entry| entry.pid == pid as usize)
+        .collect();
+
+    let mut files = OpenFiles::new(handles)?;
+    files.process = Some((pid as usize, Some(process)));
+
+    Ok(files)
+}
+
+/// Returns an iterator over files opened by all processes on the system.
+pub fn all_open_files() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::OpenFile>>> {
+    OpenFiles::new(handles()?)
+}
+
+/// Entry of the system handle table.
+///
+/// This is the `SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX` structure [1] which is not
+/// available in the `windows-sys` crate.
+///
+/// [1]: https://www.geoffchappell.com/studies/windows/km/ntoskrnl/api/ex/sysinfo/handle_ex.htm
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct HandleEntry {
+    object: *mut std::ffi::c_void,
+    pid: usize,
+    handle: usize,
+    granted_access: u32,
+    creator_back_trace_index: u16,
+    object_type_index: u16,
+    handle_attributes: u32,
+    reserved: u32,
+}
+
+/// Returns all the entries of the system handle table.
+fn handles() -> std::io::Result<Vec<HandleEntry>> {
+    use windows_sys::{
+        Wdk::System::SystemInformation::*,
+        Win32::Foundation::*,
+    };
+
+    /// Value of the `SystemExtendedHandleInformation` information class.
+    const SYSTEM_EXTENDED_HANDLE_INFORMATION: SYSTEM_INFORMATION_CLASS = 64;
+
+    /// Size of the `SYSTEM_HANDLE_INFORMATION_EX` header that precedes entries.
+    const HEADER_SIZE: usize = 2 * std::mem::size_of::<usize>();
+
+    let mut buf_size = DEFAULT_HANDLES_BUF_SIZE;
+    let buf = loop {
+        // The buffer contains structures with pointer-sized fields, so it has
+        // to be aligned.
+        let mut buf = vec![0u64; buf_size.div_ceil(std::mem::size_of::<u64>())];
+        let mut ret_size = 0;
+
+        // SAFETY: We pass a buffer along with its size (in bytes). The status
+        // is verified below.
+        let status = unsafe {
+            NtQuerySystemInformation(
+                SYSTEM_EXTENDED_HANDLE_INFORMATION,
+                buf.as_mut_ptr().cast(),
+                (buf.len() * std::mem::size_of::<u64>()) as u32,
+                &mut ret_size,
+            )
+        };
+        if status == STATUS_INFO_LENGTH_MISMATCH && buf_size < MAX_HANDLES_BUF_SIZE {
+            // New handles can be created between the calls, so we ask for more
+            // than what was reported.
+            buf_size = std::cmp::max(buf_size * 2, ret_size as usize + ret_size as usize / 4);
+            continue;
+        }
+        if status < 0 {
+            return Err(nt_error(status));
+        }
+
+        break buf;
+    };
+
+    // SAFETY: The call succeeded, so the buffer starts with the header: the
+    // number of entries followed by a reserved field.
+    let count = unsafe { *buf.as_ptr().cast::<usize>() };
+
+    let buf_size = buf.len() * std::mem::size_of::<u64>();
+    if HEADER_SIZE + count * std::mem::size_of::<HandleEntry>() > buf_size {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    // SAFETY: The call succeeded, so the header is followed by `count` entries
+    // (and we verified that they fit in the buffer).
+    let entries = unsafe {
+        let entries = buf.as_ptr().cast::<u8>().add(HEADER_SIZE).cast::<HandleEntry>();
+        std::slice::from_raw_parts(entries, count)
+    };
+
+    Ok(entries.to_vec())
+}
+
+/// Opens the process with the given identifier for duplicating its handles.
+fn open_dup_process(pid: u32) -> std::io::Result<std::os::windows::io::OwnedHandle> {
+    use std::os::windows::io::FromRawHandle as _;
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::Threading::*,
+    };
+
+    // SAFETY: This function is safe to call with any arguments. We verify the
+    // returned handle below.
+    let handle = unsafe {
+        OpenProcess(PROCESS_DUP_HANDLE, FALSE, pid)
+    };
+    if handle.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The handle is valid (verified above) and we own it.
+    Ok(unsafe { std::os::windows::io::OwnedHandle::from_raw_handle(handle) })
+}
+
+/// Iterator over files of the given entries of the system handle table.
+struct OpenFiles {
+    /// Remaining handle table entries to inspect.
+    handles: std::vec::IntoIter<HandleEntry>,
+    /// Identifier and handle of the process of the last inspected entry.
+    ///
+    /// The handle is `None` if the process could not be opened in which case
+    /// its remaining entries are skipped.
+    process: Option<(usize, Option<std::os::windows::io::OwnedHandle>)>,
+    /// Whether objects of the type with the given index are files.
+    file_types: std::collections::HashMap<u16, bool>,
+    /// Worker querying names of the objects.
+    names: NameQuery,
+    /// Mapping of volume device paths (e.g. `\Device\HarddiskVolume1`) to the
+    /// drive letters (e.g. `C:`).
+    drives: Vec<(Vec<u16>, Vec<u16>)>,
+}
+
+impl OpenFiles {
+
+    /// Creates an iterator over files of the given handle table entries.
+    fn new(handles: Vec<HandleEntry>) -> std::io::Result<OpenFiles> {
+        Ok(OpenFiles {
+            handles: handles.into_iter(),
+            process: None,
+            file_types: std::collections::HashMap::new(),
+            names: NameQuery::new()?,
+            drives: drives(),
+        })
+    }
+
+    /// Returns information about the file of the given handle table entry.
+    ///
+    /// `None` is returned if the handle is not a handle of a file on a volume
+    /// or if it cannot be inspected.
+    fn open_file(
+        &mut self,
+        process: std::os::windows::io::BorrowedHandle,
+        entry: &HandleEntry,
+    ) -> Option<crate::proc::OpenFile> {
+        use std::os::windows::io::{AsRawHandle as _, FromRawHandle as _};
+        use windows_sys::Win32::{
+            Foundation::*,
+            Storage::FileSystem::*,
+            System::Threading::*,
+        };
+
+        let mut handle = std::ptr::null_mut();
+
+        // SAFETY: The process handle is valid and opened with the permission to
+        // duplicate handles. The handle value might be already closed by the
+        // process, in which case the call simply fails.
+        let status = unsafe {
+            DuplicateHandle(
+                process.as_raw_handle(),
+                entry.handle as HANDLE,
+                GetCurrentProcess(),
+                &mut handle,
+                0,
+                FALSE,
+                DUPLICATE_SAME_ACCESS,
+            )
+        };
+        if status == FALSE {
+            return None;
+        }
+
+        // SAFETY: The call succeeded, so the handle is valid and we own it.
+        let handle = unsafe { std::os::windows::io::OwnedHandle::from_raw_handle(handle) };
+
+        let is_file = *self.file_types.entry(entry.object_type_index)
+            .or_insert_with(|| is_file(&handle));
+        if !is_file {
+            return None;
+        }
+
+        let name = match self.names.query(&handle) {
+            Some(Ok(name)) => name,
+            Some(Err(_)) => return None,
+            None => {
+                // The query is stuck (it happens e.g. for synchronous named
+                // pipes), so we abandon the worker along with the handle it
+                // uses and start a new one.
+                std::mem::forget(handle);
+                self.names = NameQuery::new().ok()?;
+                return None;
+            }
+        };
+
+        let path = dos_path(&name, &self.drives)?;
+
+        let readable = entry.granted_access & FILE_READ_DATA != 0;
+        let writable = entry.granted_access & (FILE_WRITE_DATA | FILE_APPEND_DATA) != 0;
+        let mode = match (readable, writable) {
+            (true, false) => Some(crate::proc::OpenMode::Read),
+            (false, true) => Some(crate::proc::OpenMode::Write),
+            (true, true) => Some(crate::proc::OpenMode::ReadWrite),
+            (false, false) => None,
+        };
+
+        Some(crate::proc::OpenFile {
+            pid: entry.pid as u32,
+            fd: entry.handle as u64,
+            path,
+            mode,
+            deleted: false,
+        })
+    }
+}
+
+impl Iterator for OpenFiles {
+
+    type Item = std::io::Result<crate::proc::OpenFile>;
+
+    fn next(&mut self) -> Option<std::io::Result<crate::proc::OpenFile>> {
+        use std::os::windows::io::AsHandle as _;
+
+        while let Some(entry) = self.handles.next() {
+            let process = match self.process.take() {
+                Some((pid, process)) if pid == entry.pid => process,
+                _ => match open_dup_process(entry.pid as u32) {
+                    Ok(process) => Some(process),
+                    Err(error) => {
+                        // Entries of the same process are next to each other,
+                        // so we report the error only once.
+                        self.process = Some((entry.pid, None));
+                        return Some(Err(error));
+                    }
+                },
+            };
+
+            let file = match &process {
+                Some(process) => self.open_file(process.as_handle(), &entry),
+                None => None,
+            };
+            self.process = Some((entry.pid, process));
+
+            if let Some(file) = file {
+                return Some(Ok(file));
+            }
+        }
+
+        None
+    }
+}
+
+/// Checks whether the object of the given handle is a file.
+fn is_file(handle: &std::os::windows::io::OwnedHandle) -> bool {
+    use std::os::windows::io::AsRawHandle as _;
+    use windows_sys::{
+        Wdk::Foundation::*,
+        Win32::System::WindowsProgramming::*,
+    };
+
+    let mut buf = [0u64; OBJECT_TYPE_BUF_LEN];
+    let mut ret_size = 0;
+
+    // SAFETY: The handle is valid and we pass a buffer along with its size (in
+    // bytes). Unlike name queries, type queries do not block.
+    let status = unsafe {
+        NtQueryObject(
+            handle.as_raw_handle(),
+            ObjectTypeInformation,
+            buf.as_mut_ptr().cast(),
+            std::mem::size_of_val(&buf) as u32,
+            &mut ret_size,
+        )
+    };
+    if status < 0 {
+        return false;
+    }
+
+    // SAFETY: The call succeeded, so the buffer starts with a valid structure
+    // with the type name pointing into the buffer.
+    let name = unsafe {
+        let info = &*buf.as_ptr().cast::<PUBLIC_OBJECT_TYPE_INFORMATION>();
+        unicode_string(&info.TypeName)
+    };
+
+    name == "File".encode_utf16().collect::<Vec<_>>()
+}
+
+/// Worker thread querying names of objects.
+///
+/// Name queries can block indefinitely for some objects (e.g. synchronous named
+/// pipes), so they are executed on a separate thread and abandoned after a
+/// timeout.
+struct NameQuery {
+    /// Sender of raw handles to query the names of.
+    requests: std::sync::mpsc::Sender<usize>,
+    /// Receiver of the names of the objects.
+    responses: std::sync::mpsc::Receiver<std::io::Result<Vec<u16>>>,
+}
+
+impl NameQuery {
+
+    /// Spawns a new worker.
+    fn new() -> std::io::Result<NameQuery> {
+        let (requests, requests_rx) = std::sync::mpsc::channel::<usize>();
+        let (responses_tx, responses) = std::sync::mpsc::channel();
+
+        std::thread::Builder::new()
+            .name(String::from("ospect-object-name"))
+            .spawn(move || {
+                // The loop ends once the worker is dropped (or abandoned).
+                for handle in requests_rx {
+                    if responses_tx.send(object_name(handle as _)).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(NameQuery { requests, responses })
+    }
+
+    /// Queries the name of the object of the given handle.
+    ///
+    /// `None` is returned if the query timed out. In such a case, the worker
+    /// cannot be used anymore and the handle must not be closed as the worker
+    /// might still use it.
+    fn query(
+        &self,
+        handle: &std::os::windows::io::OwnedHandle,
+    ) -> Option<std::io::Result<Vec<u16>>> {
+        use std::os::windows::io::AsRawHandle as _;
+
+        self.requests.send(handle.as_raw_handle() as usize).ok()?;
+        self.responses.recv_timeout(NAME_QUERY_TIMEOUT).ok()
+    }
+}
+
+/// Returns the name of the object of the given handle.
+fn object_name(handle: windows_sys::Win32::Foundation::HANDLE) -> std::io::Result<Vec<u16>> {
+    use windows_sys::Wdk::Foundation::*;
+
+    /// Value of the `ObjectNameInformation` information class.
+    const OBJECT_NAME_INFORMATION_CLASS: OBJECT_INFORMATION_CLASS = 1;
+
+    // Names are `UNICODE_STRING`s whose length is expressed as a 16-bit number
+    // of bytes, so this buffer is always big enough.
+    let mut buf = vec![0u64; OBJECT_NAME_BUF_LEN];
+    let mut ret_size = 0;
+
+    // SAFETY: The handle is valid and we pass a buffer along with its size (in
+    // bytes). The status is verified below.
+    let status = unsafe {
+        NtQueryObject(
+            handle,
+            OBJECT_NAME_INFORMATION_CLASS,
+            buf.as_mut_ptr().cast(),
+            (buf.len() * std::mem::size_of::<u64>()) as u32,
+            &mut ret_size,
+        )
+    };
+    if status < 0 {
+        return Err(nt_error(status));
+    }
+
+    // SAFETY: The call succeeded, so the buffer starts with a valid structure
+    // with the name pointing into the buffer.
+    Ok(unsafe {
+        let info = &*buf.as_ptr().cast::<OBJECT_NAME_INFORMATION>();
+        unicode_string(&info.Name)
+    })
+}
+
+/// Copies the given `UNICODE_STRING` into a vector.
+///
+/// # Safety
+///
+/// The string buffer must be either null or valid for its length.
+unsafe fn unicode_string(string: &windows_sys::Win32::Foundation::UNICODE_STRING) -> Vec<u16> {
+    if string.Buffer.is_null() {
+        return Vec::new();
+    }
+
+    let len = string.Length as usize / std::mem::size_of::<u16>();
+    // SAFETY: The caller guarantees that the buffer is valid.
+    unsafe { std::slice::from_raw_parts(string.Buffer, len) }.to_vec()
+}
+
+/// Returns the mapping of volume device paths to drive letters.
+fn drives() -> Vec<(Vec<u16>, Vec<u16>)> {
+    use windows_sys::Win32::Storage::FileSystem::*;
+
+    let mut drives = Vec::new();
+
+    for letter in b'A'..=b'Z' {
+        let drive = [u16::from(letter), u16::from(b':')];
+        let drive_str = [drive[0], drive[1], 0];
+
+        let mut buf = [0u16; MAX_DEVICE_PATH_LEN];
+
+        // SAFETY: We pass a null-terminated drive name and a buffer along with
+        // its length (in characters). The function returns the number of
+        // characters written or zero on error (e.g. if the drive does not
+        // exist).
+        let len = unsafe {
+            QueryDosDeviceW(drive_str.as_ptr(), buf.as_mut_ptr(), buf.len() as u32)
+        };
+        if len == 0 {
+            continue;
+        }
+
+        // The buffer can contain multiple null-terminated paths, the first one
+        // being the current mapping.
+        let device = buf.split(|char| *char == 0).next().unwrap_or_default();
+        if !device.is_empty() {
+            drives.push((device.to_vec(), drive.to_vec()));
+        }
+    }
+
+    drives
+}
+
+/// Converts the given NT object path to a DOS path (e.g. `C:\foo`).
+///
+/// `None` is returned if the path is not a path on a volume mapped to a drive
+/// or on a network share.
+fn dos_path(path: &[u16], drives: &[(Vec<u16>, Vec<u16>)]) -> Option<std::path::PathBuf> {
+    use std::os::windows::ffi::OsStringExt as _;
+
+    const SEPARATOR: u16 = b'\\' as u16;
+
+    let mut result = Vec::with_capacity(path.len());
+
+    let mup = r"\Device\Mup".encode_utf16().collect::<Vec<_>>();
+    if let Some(rest) = path.strip_prefix(&mup[..]).filter(|rest| rest.first() == Some(&SEPARATOR)) {
+        result.push(SEPARATOR);
+        result.extend_from_slice(rest);
+    } else {
+        let (rest, drive) = drives.iter().find_map(|(device, drive)| {
+            let rest = path.strip_prefix(&device[..])?;
+            (rest.first() == Some(&SEPARATOR)).then_some((rest, drive))
+        })?;
+        result.extend_from_slice(drive);
+        result.extend_from_slice(rest);
+    }
+
+    Some(std::path::PathBuf::from(std::ffi::OsString::from_wide(&result)))
+}
+
+/// Returns memory regions mapped into the address space of the specified
+/// process.
+pub fn regions(pid: u32) -> std::io::Result<Vec<crate::proc::Region>> {
+    // There is no way to inspect the layout without a handle to the memory.
+    Memory::open(pid)?.regions()
+}
+
+/// A Windows-specific handle to the memory of a process.
+pub struct Memory {
+    /// Handle to the process.
+    handle: windows_sys::Win32::Foundation::HANDLE,
+}
+
+impl Memory {
+
+    /// Opens the memory of the process with the specified identifier.
+    pub fn open(pid: u32) -> std::io::Result<Memory> {
+        use windows_sys::Win32::{
+            Foundation::*,
+            System::Threading::*,
+        };
+
+        // SAFETY: This function is safe to call with any arguments. We verify
+        // the returned handle below.
+        let handle = unsafe {
+            OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, FALSE, pid)
+        };
+        if handle.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Memory { handle })
+    }
+
+    /// Returns memory regions currently mapped into the process address space.
+    pub fn regions(&self) -> std::io::Result<Vec<crate::proc::Region>> {
+        use windows_sys::Win32::{
+            Foundation::*,
+            System::Memory::*,
+        };
+
+        let mut regions = Vec::new();
+        let mut addr = 0usize;
+
+        loop {
+            let mut info = std::mem::MaybeUninit::<MEMORY_BASIC_INFORMATION>::uninit();
+
+            // SAFETY: The handle is valid and we pass a buffer for the region
+            // information along with its size. The function returns the number
+            // of bytes written which is zero in case of an error (that happens
+            // also when we reach the end of the address space).
+            let len = unsafe {
+                VirtualQueryEx(
+                    self.handle,
+                    addr as *const std::ffi::c_void,
+                    info.as_mut_ptr(),
+                    std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+                )
+            };
+            if len == 0 {
+                let error = std::io::Error::last_os_error();
+                if error.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) {
+                    break;
+                }
+
+                return Err(error);
+            }
+
+            // SAFETY: The call succeeded, so the information is filled.
+            let info = unsafe { info.assume_init() };
+
+            // Reserved and free regions are not backed by any memory, so there
+            // is nothing to report about them.
+            if info.State == MEM_COMMIT {
+                regions.push(self.region(&info));
+            }
+
+            addr = match (info.BaseAddress as usize).checked_add(info.RegionSize) {
+                Some(addr) => addr,
+                None => break,
+            };
+        }
+
+        Ok(regions)
+    }
+
+    /// Reads memory of the process starting at the given address.
+    pub fn read(&self, addr: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        use windows_sys::Win32::{
+            Foundation::*,
+            System::Diagnostics::Debug::*,
+        };
+
+        let addr = usize::try_from(addr)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+        let mut len = 0;
+
+        // SAFETY: The handle is valid and we pass a buffer along with its size.
+        // The function will not write past it and sets the number of bytes
+        // written (even in case of a partial read).
+        let status = unsafe {
+            ReadProcessMemory(
+                self.handle,
+                addr as *const std::ffi::c_void,
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                &mut len,
+            )
+        };
+        if status == FALSE && len == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(len)
+    }
+
+    /// Converts region information returned by the system to a region.
+    fn region(
+        &self,
+        info: &windows_sys::Win32::System::Memory::MEMORY_BASIC_INFORMATION,
+    ) -> crate::proc::Region {
+        use windows_sys::Win32::System::Memory::*;
+
+        const WRITABLE: PAGE_PROTECTION_FLAGS = {
+            PAGE_READWRITE | PAGE_WRITECOPY |
+            PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY
+        };
+        const EXECUTABLE: PAGE_PROTECTION_FLAGS = {
+            PAGE_EXECUTE | PAGE_EXECUTE_READ |
+            PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY
+        };
+
+        let path = if info.Type == MEM_IMAGE || info.Type == MEM_MAPPED {
+            self.mapped_file_path(info.BaseAddress)
+        } else {
+            None
+        };
+
+        crate::proc::Region {
+            addr: info.BaseAddress as u64,
+            size: info.RegionSize as u64,
+            readable: info.Protect != 0 &&
+                info.Protect & (PAGE_NOACCESS | PAGE_GUARD) == 0 &&
+                info.Protect != PAGE_EXECUTE,
+            writable: info.Protect & WRITABLE != 0,
+            executable: info.Protect & EXECUTABLE != 0,
+            shared: info.Type == MEM_MAPPED,
+            path,
+        }
+    }
+
+    /// Returns the path to the file mapped at the given address.
+    ///
+    /// Note that the path uses the NT device notation (e.g. it starts with the
+    /// `\Device\HarddiskVolume1` prefix rather than with a drive letter).
+    fn mapped_file_path(&self, addr: *const std::ffi::c_void) -> Option<std::path::PathBuf> {
+        use std::os::windows::ffi::OsStringExt as _;
+
+        let mut buf = vec![0u16; MAX_IMAGE_NAME_LEN];
+
+        // SAFETY: The handle is valid and we pass a buffer along with its size
+        // (in characters). The function returns the number of characters
+        // written (without the terminator) or zero in case of an error.
+        let len = unsafe {
+            windows_sys::Win32::System::ProcessStatus::K32GetMappedFileNameW(
+                self.handle, addr, buf.as_mut_ptr(), buf.len() as u32,
+            )
+        };
+        if len == 0 {
+            return None;
+        }
+        buf.truncate(len as usize);
+
+        Some(std::path::PathBuf::from(std::ffi::OsString::from_wide(&buf)))
+    }
+}
+
+impl Drop for Memory {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and we no longer need it. There is not
+        // much we can do if closing fails, so we ignore the result.
+        unsafe {
+            windows_sys::Win32::Foundation::CloseHandle(self.handle);
+        }
+    }
+}
+
 /// A Windows-specific implementation of the iterator over process identifiers.
 struct Ids {
     /// An iterator over the process identifiers returned by `EnumProcesses`.
@@ -87,7 +1135,28 @@ impl Iterator for Ids {
 }
 
 /// The default capacity of the process identifiers buffer.
+/// Initial size of the buffer for the system handle table (in bytes).
+const DEFAULT_HANDLES_BUF_SIZE: usize = 1024 * 1024;
+
+/// Maximum size of the buffer for the system handle table (in bytes).
+const MAX_HANDLES_BUF_SIZE: usize = 1024 * 1024 * 1024;
+
+/// Length of the buffer for object type information (in 8-byte words).
+const OBJECT_TYPE_BUF_LEN: usize = 128;
+
+/// Length of the buffer for object names (in 8-byte words).
+const OBJECT_NAME_BUF_LEN: usize = (u16::MAX as usize + 64).div_ceil(8);
+
+/// Time after which queries of object names are abandoned.
+const NAME_QUERY_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// The maximum length (in characters) of a volume device path we support.
+const MAX_DEVICE_PATH_LEN: usize = 1024;
+
 const DEFAULT_PID_BUF_CAP: usize = 1024;
 
 /// The maximum capacity of the process identifiers buffer.
 const MAX_PID_BUF_CAP: usize = 16384;
+
+/// The maximum length (in characters) of process image paths we support.
+const MAX_IMAGE_NAME_LEN: usize = 32768;
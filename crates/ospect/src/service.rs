@@ -0,0 +1,151 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about services registered in the Windows Service Control
+//! Manager.
+//!
+//! On other systems services are just ordinary processes started by the init
+//! system and are described by its configuration files, so there is no system
+//! interface to expose here.
+
+mod windows;
+
+mod sys {
+    pub use crate::service::windows::*;
+}
+
+/// Information about a service.
+#[derive(Clone, Debug)]
+pub struct Service {
+    /// Name of the service (used as its key in the Service Control Manager).
+    name: std::ffi::OsString,
+    /// Display name of the service, if specified.
+    display_name: Option<std::ffi::OsString>,
+    /// Command line with which the service is started, if known.
+    command_line: Option<std::ffi::OsString>,
+    /// Way in which the service is started, if known.
+    start_type: Option<StartType>,
+    /// Current state of the service.
+    state: State,
+    /// Name of the account as which the service runs, if known.
+    account: Option<std::ffi::OsString>,
+    /// Identifier of the process of the service, if it is running.
+    pid: Option<u32>,
+    /// Whether the service is a device driver.
+    driver: bool,
+}
+
+impl Service {
+
+    /// Returns the name of the service.
+    pub fn name(&self) -> &std::ffi::OsStr {
+        &self.name
+    }
+
+    /// Returns the display name of the service, if specified.
+    pub fn display_name(&self) -> Option<&std::ffi::OsStr> {
+        self.display_name.as_deref()
+    }
+
+    /// Returns the command line with which the service is started, if known.
+    ///
+    /// This is the raw value of the service configuration: the path to the
+    /// executable may or may not be quoted and may be followed by arguments.
+    /// Paths to drivers are often relative to the system directory or use the
+    /// NT object namespace (e.g. `\SystemRoot\System32\drivers\foo.sys`).
+    pub fn command_line(&self) -> Option<&std::ffi::OsStr> {
+        self.command_line.as_deref()
+    }
+
+    /// Returns the way in which the service is started, if known.
+    pub fn start_type(&self) -> Option<StartType> {
+        self.start_type
+    }
+
+    /// Returns the current state of the service.
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns the name of the account as which the service runs, if known.
+    ///
+    /// For drivers this is the name of the driver object used by the kernel.
+    pub fn account(&self) -> Option<&std::ffi::OsStr> {
+        self.account.as_deref()
+    }
+
+    /// Returns the identifier of the process of the service, if it is running.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Returns whether the service is a device driver.
+    pub fn is_driver(&self) -> bool {
+        self.driver
+    }
+}
+
+/// Way in which a service is started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StartType {
+    /// Started by the system loader (drivers only).
+    Boot,
+    /// Started during kernel initialization (drivers only).
+    System,
+    /// Started automatically during system startup.
+    Auto,
+    /// Started on demand.
+    Demand,
+    /// Cannot be started.
+    Disabled,
+}
+
+/// State of a service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// The service is not running.
+    Stopped,
+    /// The service is starting.
+    StartPending,
+    /// The service is stopping.
+    StopPending,
+    /// The service is running.
+    Running,
+    /// The service is about to continue after being paused.
+    ContinuePending,
+    /// The service is about to pause.
+    PausePending,
+    /// The service is paused.
+    Paused,
+    /// The state reported by the system is not known.
+    Unknown(u32),
+}
+
+/// Returns an iterator over all services (including drivers) on the system.
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to connect to the Service
+/// Control Manager or to enumerate services. Each yielded item can also be an
+/// error if the information for a particular service was malformed.
+///
+/// Configuration of some services might not be accessible (e.g. because of
+/// insufficient permissions), in which case only the status of the service is
+/// available and the configuration methods return `None`.
+///
+/// # Examples
+///
+/// ```
+/// let services = ospect::service::services()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for service in services {
+///     println!("{}", service.name().to_string_lossy());
+/// }
+/// ```
+pub fn services() -> std::io::Result<impl Iterator<Item = std::io::Result<Service>>> {
+    self::sys::services()
+}
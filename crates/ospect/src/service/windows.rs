@@ -0,0 +1,301 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::System::Services::*;
+
+use crate::service::{Service, StartType, State};
+
+/// Returns an iterator over all services (including drivers) on the system.
+pub fn services() -> std::io::Result<impl Iterator<Item = std::io::Result<Service>>> {
+    use windows_sys::Win32::Foundation::*;
+
+    // SAFETY: We call the function as described in the documentation [1]. Null
+    // pointers mean the local machine and the default database.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winsvc/nf-winsvc-openscmanagerw
+    let manager = unsafe {
+        OpenSCManagerW(
+            std::ptr::null(),
+            std::ptr::null(),
+            SC_MANAGER_CONNECT | SC_MANAGER_ENUMERATE_SERVICE,
+        )
+    };
+    let manager = Handle::new(manager)?;
+
+    let mut services = Vec::new();
+    let mut resume_handle = 0;
+
+    // Entries contain pointers, so we use a buffer of 64-bit words to have it
+    // properly aligned.
+    let mut buf = Vec::<u64>::new();
+
+    loop {
+        let mut bytes_needed = 0;
+        let mut services_returned = 0;
+
+        // SAFETY: We pass a buffer along with its size (in bytes). If it is not
+        // big enough, the call fails with `ERROR_MORE_DATA` and tells us how
+        // many bytes are needed [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winsvc/nf-winsvc-enumservicesstatusexw
+        let status = unsafe {
+            EnumServicesStatusExW(
+                manager.0,
+                SC_ENUM_PROCESS_INFO,
+                SERVICE_WIN32 | SERVICE_DRIVER,
+                SERVICE_STATE_ALL,
+                buf.as_mut_ptr().cast(),
+                (buf.len() * std::mem::size_of::<u64>()) as u32,
+                &mut bytes_needed,
+                &mut services_returned,
+                &mut resume_handle,
+                std::ptr::null(),
+            )
+        };
+
+        let is_more_data = if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(ERROR_MORE_DATA as i32) {
+                return Err(error);
+            }
+
+            true
+        } else {
+            false
+        };
+
+        // SAFETY: The buffer contains `services_returned` entries (it is zero
+        // on the initial call with an empty buffer).
+        let entries = unsafe {
+            std::slice::from_raw_parts(
+                buf.as_ptr().cast::<ENUM_SERVICE_STATUS_PROCESSW>(),
+                services_returned as usize,
+            )
+        };
+
+        for entry in entries {
+            // SAFETY: Strings of the entry are valid null-terminated wide
+            // strings (or null pointers) as long as the buffer is alive.
+            let (name, display_name) = unsafe {(
+                wide_string(entry.lpServiceName),
+                wide_string(entry.lpDisplayName),
+            )};
+
+            let Some(name) = name else {
+                services.push(Err(std::io::ErrorKind::InvalidData.into()));
+                continue;
+            };
+
+            let status = &entry.ServiceStatusProcess;
+            let state = match status.dwCurrentState {
+                SERVICE_STOPPED => State::Stopped,
+                SERVICE_START_PENDING => State::StartPending,
+                SERVICE_STOP_PENDING => State::StopPending,
+                SERVICE_RUNNING => State::Running,
+                SERVICE_CONTINUE_PENDING => State::ContinuePending,
+                SERVICE_PAUSE_PENDING => State::PausePending,
+                SERVICE_PAUSED => State::Paused,
+                state => State::Unknown(state),
+            };
+
+            // Configuration of some services is not accessible even to admins,
+            // in which case we report just the status.
+            let config = config(&manager, &name).ok();
+
+            services.push(Ok(Service {
+                display_name,
+                command_line: config.as_ref()
+                    .and_then(|config| config.command_line.clone()),
+                start_type: config.as_ref()
+                    .and_then(|config| config.start_type),
+                state,
+                account: config
+                    .and_then(|config| config.account),
+                pid: match status.dwProcessId {
+                    0 => None,
+                    pid => Some(pid),
+                },
+                driver: status.dwServiceType & SERVICE_DRIVER != 0,
+                name,
+            }));
+        }
+
+        if !is_more_data {
+            break;
+        }
+
+        let words_needed = (bytes_needed as usize).div_ceil(std::mem::size_of::<u64>());
+        buf.resize(words_needed.max(buf.len()), 0);
+    }
+
+    Ok(services.into_iter())
+}
+
+/// Configuration of a service.
+struct Config {
+    /// Command line with which the service is started.
+    command_line: Option<std::ffi::OsString>,
+    /// Way in which the service is started.
+    start_type: Option<StartType>,
+    /// Name of the account as which the service runs.
+    account: Option<std::ffi::OsString>,
+}
+
+/// Returns configuration of the service with the given name.
+fn config(manager: &Handle, name: &std::ffi::OsStr) -> std::io::Result<Config> {
+    use std::os::windows::ffi::OsStrExt as _;
+    use windows_sys::Win32::Foundation::*;
+
+    let name = name.encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+
+    // SAFETY: We pass a valid manager handle and a null-terminated name [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winsvc/nf-winsvc-openservicew
+    let service = unsafe {
+        OpenServiceW(manager.0, name.as_ptr(), SERVICE_QUERY_CONFIG)
+    };
+    let service = Handle::new(service)?;
+
+    let mut bytes_needed = 0;
+
+    // SAFETY: We query the required buffer size by passing no buffer. Such call
+    // is expected to fail with `ERROR_INSUFFICIENT_BUFFER` [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winsvc/nf-winsvc-queryserviceconfigw
+    let status = unsafe {
+        QueryServiceConfigW(service.0, std::ptr::null_mut(), 0, &mut bytes_needed)
+    };
+    if status == FALSE {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER as i32) {
+            return Err(error);
+        }
+    }
+
+    // The configuration contains pointers, so we use a buffer of 64-bit words
+    // to have it properly aligned.
+    let words_needed = (bytes_needed as usize).div_ceil(std::mem::size_of::<u64>());
+    let mut buf = vec![0u64; words_needed.max(1)];
+
+    // SAFETY: We pass a buffer along with its size (in bytes) which is what
+    // the previous call said was needed.
+    let status = unsafe {
+        QueryServiceConfigW(
+            service.0,
+            buf.as_mut_ptr().cast(),
+            (buf.len() * std::mem::size_of::<u64>()) as u32,
+            &mut bytes_needed,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the buffer starts with the configuration.
+    let config = unsafe { &*buf.as_ptr().cast::<QUERY_SERVICE_CONFIGW>() };
+
+    let start_type = match config.dwStartType {
+        SERVICE_BOOT_START => Some(StartType::Boot),
+        SERVICE_SYSTEM_START => Some(StartType::System),
+        SERVICE_AUTO_START => Some(StartType::Auto),
+        SERVICE_DEMAND_START => Some(StartType::Demand),
+        SERVICE_DISABLED => Some(StartType::Disabled),
+        _ => None,
+    };
+
+    // SAFETY: Strings of the configuration are valid null-terminated wide
+    // strings (or null pointers) as long as the buffer is alive.
+    let (command_line, account) = unsafe {(
+        wide_string(config.lpBinaryPathName),
+        wide_string(config.lpServiceStartName),
+    )};
+
+    Ok(Config {
+        command_line,
+        start_type,
+        account,
+    })
+}
+
+/// RAII wrapper around service and Service Control Manager handles.
+struct Handle(SC_HANDLE);
+
+impl Handle {
+
+    /// Wraps the given handle, failing with the last OS error if it is null.
+    fn new(handle: SC_HANDLE) -> std::io::Result<Handle> {
+        if handle.is_null() {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(Handle(handle))
+        }
+    }
+}
+
+impl Drop for Handle {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and we close it only once [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winsvc/nf-winsvc-closeservicehandle
+        unsafe {
+            CloseServiceHandle(self.0);
+        }
+    }
+}
+
+/// Copies a (potentially null) wide string into an owned OS string. Empty
+/// strings are treated as missing.
+///
+/// # Safety
+///
+/// The pointer must be either null or point to a valid null-terminated string.
+unsafe fn wide_string(ptr: *const u16) -> Option<std::ffi::OsString> {
+    use std::os::windows::ffi::OsStringExt as _;
+
+    if ptr.is_null() {
+        return None;
+    }
+
+    let mut len = 0;
+    // SAFETY: The caller guarantees that the string is null-terminated.
+    while unsafe { *ptr.add(len) } != 0 {
+        len += 1;
+    }
+    if len == 0 {
+        return None;
+    }
+
+    // SAFETY: We just verified that there are `len` valid characters.
+    let chars = unsafe { std::slice::from_raw_parts(ptr, len) };
+    Some(std::ffi::OsString::from_wide(chars))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn services_event_log() {
+        let event_log = services().unwrap()
+            .filter_map(Result::ok)
+            .find(|service| service.name().eq_ignore_ascii_case("EventLog"))
+            .unwrap();
+
+        assert_eq!(event_log.state(), State::Running);
+        assert_eq!(event_log.start_type(), Some(StartType::Auto));
+        assert!(event_log.command_line().is_some());
+        assert!(!event_log.is_driver());
+    }
+
+    #[test]
+    fn services_drivers() {
+        let mut services = services().unwrap()
+            .filter_map(Result::ok);
+
+        assert!(services.any(|service| service.is_driver()));
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about access tokens of processes on Windows.
+//!
+//! Every process on Windows has an access token that describes its security
+//! context: the user it runs as, the groups the user belongs to, privileges
+//! held by the process and its integrity level. Other systems do not have an
+//! equivalent concept, so there is nothing to expose there.
+
+mod windows;
+
+mod sys {
+    pub use crate::token::windows::*;
+}
+
+/// Access token of a process.
+#[derive(Clone, Debug)]
+pub struct Token {
+    /// Security identifier of the user of the token.
+    user_sid: String,
+    /// Groups of the token.
+    groups: Vec<Group>,
+    /// Privileges of the token.
+    privileges: Vec<Privilege>,
+    /// Relative identifier of the mandatory integrity label of the token.
+    integrity_level: Option<u32>,
+    /// Elevation type of the token.
+    elevation_type: Option<ElevationType>,
+}
+
+impl Token {
+
+    /// Returns the security identifier of the user of the token.
+    pub fn user_sid(&self) -> &str {
+        &self.user_sid
+    }
+
+    /// Returns the groups of the token.
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    /// Returns the privileges of the token.
+    pub fn privileges(&self) -> &[Privilege] {
+        &self.privileges
+    }
+
+    /// Returns the integrity level of the token.
+    ///
+    /// The level is the relative identifier of the mandatory label of the
+    /// token (e.g. `0x2000` for the medium level or `0x3000` for the high
+    /// one). It is `None` if the token has no mandatory label.
+    pub fn integrity_level(&self) -> Option<u32> {
+        self.integrity_level
+    }
+
+    /// Returns the elevation type of the token.
+    ///
+    /// It is `None` if the system reported a type that we do not know.
+    pub fn elevation_type(&self) -> Option<ElevationType> {
+        self.elevation_type
+    }
+}
+
+/// Group of an access token.
+#[derive(Clone, Debug)]
+pub struct Group {
+    /// Security identifier of the group.
+    sid: String,
+    /// Attributes of the group (`SE_GROUP_*` flags).
+    attributes: u32,
+}
+
+impl Group {
+
+    /// Returns the security identifier of the group.
+    pub fn sid(&self) -> &str {
+        &self.sid
+    }
+
+    /// Returns the raw attributes of the group (`SE_GROUP_*` flags).
+    pub fn attributes(&self) -> u32 {
+        self.attributes
+    }
+
+    /// Returns whether the group is enabled for access checks.
+    pub fn is_enabled(&self) -> bool {
+        self.attributes & SE_GROUP_ENABLED != 0
+    }
+
+    /// Returns whether the group is used only to deny access.
+    pub fn is_deny_only(&self) -> bool {
+        self.attributes & SE_GROUP_USE_FOR_DENY_ONLY != 0
+    }
+}
+
+/// Privilege of an access token.
+#[derive(Clone, Debug)]
+pub struct Privilege {
+    /// Name of the privilege (e.g. `SeDebugPrivilege`).
+    name: String,
+    /// Attributes of the privilege (`SE_PRIVILEGE_*` flags).
+    attributes: u32,
+}
+
+impl Privilege {
+
+    /// Returns the name of the privilege (e.g. `SeDebugPrivilege`).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the raw attributes of the privilege (`SE_PRIVILEGE_*` flags).
+    pub fn attributes(&self) -> u32 {
+        self.attributes
+    }
+
+    /// Returns whether the privilege is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.attributes & SE_PRIVILEGE_ENABLED != 0
+    }
+
+    /// Returns whether the privilege is enabled by default.
+    pub fn is_enabled_by_default(&self) -> bool {
+        self.attributes & SE_PRIVILEGE_ENABLED_BY_DEFAULT != 0
+    }
+}
+
+/// Elevation type of an access token.
+///
+/// See the [documentation][1] for more details.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/winnt/ne-winnt-token_elevation_type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElevationType {
+    /// The token is not split (e.g. User Account Control is disabled or the
+    /// user is not an administrator).
+    Default,
+    /// The token is the elevated part of a split token.
+    Full,
+    /// The token is the limited part of a split token.
+    Limited,
+}
+
+/// Group attribute marking the group as enabled.
+const SE_GROUP_ENABLED: u32 = 0x00000004;
+/// Group attribute marking the group as used only to deny access.
+const SE_GROUP_USE_FOR_DENY_ONLY: u32 = 0x00000010;
+
+/// Privilege attribute marking the privilege as enabled by default.
+const SE_PRIVILEGE_ENABLED_BY_DEFAULT: u32 = 0x00000001;
+/// Privilege attribute marking the privilege as enabled.
+const SE_PRIVILEGE_ENABLED: u32 = 0x00000002;
+
+/// Returns the access token of the specified process.
+///
+/// # Errors
+///
+/// This function will fail if the process does not exist or if we are not
+/// allowed to query its token (e.g. because it is a protected process).
+///
+/// # Examples
+///
+/// ```
+/// let token = ospect::token::of_process(std::process::id())
+///     .unwrap();
+///
+/// println!("user: {}", token.user_sid());
+/// for privilege in token.privileges() {
+///     println!("privilege: {} (enabled: {})", privilege.name(), privilege.is_enabled());
+/// }
+/// ```
+pub fn of_process(pid: u32) -> std::io::Result<Token> {
+    self::sys::of_process(pid)
+}
@@ -0,0 +1,313 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::{
+    Foundation::*,
+    Security::*,
+    Security::Authorization::*,
+    System::Threading::*,
+};
+
+use crate::token::{ElevationType, Group, Privilege, Token};
+
+/// Returns the access token of the specified process.
+pub fn of_process(pid: u32) -> std::io::Result<Token> {
+    let token = open(pid)?;
+
+    let user_buf = information(&token, TokenUser)?;
+    // SAFETY: The buffer has been filled by a successful call, so it starts with
+    // a valid structure and the SID it points to lies within the buffer.
+    let user_sid = unsafe {
+        sid_string((*user_buf.as_ptr().cast::<TOKEN_USER>()).User.Sid)?
+    };
+
+    let groups_buf = information(&token, TokenGroups)?;
+    // SAFETY: The buffer has been filled by a successful call, so it starts with
+    // a valid structure followed by the declared number of entries.
+    let groups = unsafe {
+        let groups = &*groups_buf.as_ptr().cast::<TOKEN_GROUPS>();
+        std::slice::from_raw_parts(groups.Groups.as_ptr(), groups.GroupCount as usize)
+    };
+    let groups = groups.iter()
+        .map(|group| Ok(Group {
+            // SAFETY: SIDs of the entries lie within the buffer (see above).
+            sid: unsafe { sid_string(group.Sid)? },
+            attributes: group.Attributes,
+        }))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let privileges_buf = information(&token, TokenPrivileges)?;
+    // SAFETY: The buffer has been filled by a successful call, so it starts with
+    // a valid structure followed by the declared number of entries.
+    let privileges = unsafe {
+        let privileges = &*privileges_buf.as_ptr().cast::<TOKEN_PRIVILEGES>();
+        std::slice::from_raw_parts(privileges.Privileges.as_ptr(), privileges.PrivilegeCount as usize)
+    };
+    let privileges = privileges.iter()
+        .map(|privilege| Ok(Privilege {
+            name: privilege_name(&privilege.Luid)?,
+            attributes: privilege.Attributes,
+        }))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let label_buf = information(&token, TokenIntegrityLevel)?;
+    // SAFETY: The buffer has been filled by a successful call, so it starts with
+    // a valid structure and the SID it points to lies within the buffer.
+    let integrity_level = unsafe {
+        integrity_level((*label_buf.as_ptr().cast::<TOKEN_MANDATORY_LABEL>()).Label.Sid)
+    };
+
+    let mut elevation_type: TOKEN_ELEVATION_TYPE = 0;
+    let mut len = 0;
+
+    // SAFETY: The token handle is valid and we pass a buffer of the size that
+    // the information class requires. We verify the result below.
+    let status = unsafe {
+        GetTokenInformation(
+            token.0,
+            TokenElevationType,
+            (&mut elevation_type as *mut TOKEN_ELEVATION_TYPE).cast(),
+            std::mem::size_of::<TOKEN_ELEVATION_TYPE>() as u32,
+            &mut len,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    #[allow(non_upper_case_globals)]
+    let elevation_type = match elevation_type {
+        TokenElevationTypeDefault => Some(ElevationType::Default),
+        TokenElevationTypeFull => Some(ElevationType::Full),
+        TokenElevationTypeLimited => Some(ElevationType::Limited),
+        _ => None,
+    };
+
+    Ok(Token {
+        user_sid,
+        groups,
+        privileges,
+        integrity_level,
+        elevation_type,
+    })
+}
+
+/// Owned handle closed when dropped.
+struct Handle(HANDLE);
+
+impl Drop for Handle {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and owned by us. There is not much we can
+        // do if closing fails, so we ignore the result.
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// Opens the access token of the specified process for querying.
+fn open(pid: u32) -> std::io::Result<Handle> {
+    // SAFETY: This function is safe to call with any arguments. We verify the
+    // returned handle below.
+    let process = unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid)
+    };
+    if process.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+    let process = Handle(process);
+
+    let mut token = std::ptr::null_mut();
+
+    // SAFETY: The process handle is valid (verified above). On success, the
+    // token handle is written to the given location.
+    let status = unsafe {
+        OpenProcessToken(process.0, TOKEN_QUERY, &mut token)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(Handle(token))
+}
+
+/// Queries variable-length information of the given class about the token.
+///
+/// The information is returned in a buffer of `u64` elements, so that it is
+/// properly aligned for any of the token information structures.
+fn information(
+    token: &Handle,
+    class: TOKEN_INFORMATION_CLASS,
+) -> std::io::Result<Vec<u64>> {
+    let mut len = 0;
+
+    // SAFETY: The token handle is valid. We pass an empty buffer to learn the
+    // required size, so the call is expected to fail (verified below).
+    let status = unsafe {
+        GetTokenInformation(token.0, class, std::ptr::null_mut(), 0, &mut len)
+    };
+    if status == FALSE {
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER as i32) {
+            return Err(error);
+        }
+    }
+
+    let mut buf = vec![0u64; (len as usize).div_ceil(std::mem::size_of::<u64>())];
+
+    // SAFETY: The token handle is valid and we pass a buffer along with its
+    // size (in bytes). We verify the result below.
+    let status = unsafe {
+        GetTokenInformation(
+            token.0,
+            class,
+            buf.as_mut_ptr().cast(),
+            (buf.len() * std::mem::size_of::<u64>()) as u32,
+            &mut len,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(buf)
+}
+
+/// Converts the given SID to its string representation (e.g. `S-1-5-18`).
+///
+/// # Safety
+///
+/// The SID must be a pointer to a valid SID structure.
+unsafe fn sid_string(sid: PSID) -> std::io::Result<String> {
+    let mut sid_str = std::ptr::null_mut();
+
+    // SAFETY: The caller guarantees that the SID is valid. The string is
+    // allocated by the system and we free it below.
+    let status = unsafe {
+        ConvertSidToStringSidW(sid, &mut sid_str)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut len = 0;
+    // SAFETY: The call succeeded, so the string is valid and null-terminated.
+    while unsafe { *sid_str.add(len) } != 0 {
+        len += 1;
+    }
+    // SAFETY: We just verified that there are `len` valid characters.
+    let result = String::from_utf16(unsafe {
+        std::slice::from_raw_parts(sid_str, len)
+    });
+
+    // SAFETY: The string was allocated by `ConvertSidToStringSidW` and it has
+    // to be freed with `LocalFree` [1]. We copied it above.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertsidtostringsidw
+    unsafe {
+        LocalFree(sid_str.cast());
+    }
+
+    result.map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Returns the integrity level encoded in the given mandatory label SID.
+///
+/// The level is the last (and only) subauthority of the label SID.
+///
+/// # Safety
+///
+/// The SID must be a pointer to a valid SID structure.
+unsafe fn integrity_level(sid: PSID) -> Option<u32> {
+    // SAFETY: The caller guarantees that the SID is valid.
+    let count = unsafe {
+        *GetSidSubAuthorityCount(sid)
+    };
+    if count == 0 {
+        return None;
+    }
+
+    // SAFETY: The caller guarantees that the SID is valid and we verified that
+    // the subauthority index is within bounds.
+    Some(unsafe {
+        *GetSidSubAuthority(sid, u32::from(count) - 1)
+    })
+}
+
+/// Returns the name of the privilege with the given local identifier.
+fn privilege_name(luid: &LUID) -> std::io::Result<String> {
+    // Privilege names are short constants (e.g. `SeDebugPrivilege`), so this
+    // is more than enough for any of them.
+    let mut buf = [0u16; 256];
+    let mut len = buf.len() as u32;
+
+    // SAFETY: We pass a valid identifier and a buffer along with its size (in
+    // characters). We verify the result below.
+    let status = unsafe {
+        LookupPrivilegeNameW(std::ptr::null(), luid, buf.as_mut_ptr(), &mut len)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // On success, the length is updated to the number of characters written
+    // (without the terminator).
+    String::from_utf16(&buf[..len as usize])
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn of_process_self_user() {
+        let token = of_process(std::process::id())
+            .unwrap();
+
+        let user_sid = crate::proc::user_sid(std::process::id())
+            .unwrap();
+        assert_eq!(token.user_sid(), user_sid);
+    }
+
+    #[test]
+    fn of_process_self_privileges() {
+        let token = of_process(std::process::id())
+            .unwrap();
+
+        // This privilege (bypass traverse checking) is held by every user and
+        // enabled by default.
+        let privilege = token.privileges().iter()
+            .find(|privilege| privilege.name() == "SeChangeNotifyPrivilege")
+            .unwrap();
+        assert!(privilege.is_enabled());
+    }
+
+    #[test]
+    fn of_process_self_groups() {
+        let token = of_process(std::process::id())
+            .unwrap();
+
+        // Every token is a member of the "Everyone" group.
+        assert!(token.groups().iter().any(|group| group.sid() == "S-1-1-0"));
+    }
+
+    #[test]
+    fn of_process_self_integrity_level() {
+        let token = of_process(std::process::id())
+            .unwrap();
+
+        assert!(token.integrity_level().is_some());
+    }
+
+    #[test]
+    fn of_process_non_existent() {
+        // Process identifiers on Windows are multiples of 4, so this one can
+        // never be used.
+        assert!(of_process(0xFFFFFFFF).is_err());
+    }
+}
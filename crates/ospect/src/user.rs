@@ -0,0 +1,186 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about user accounts on the system.
+
+#[cfg(target_family = "unix")]
+mod unix;
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+mod sys {
+    #[cfg(target_family = "unix")]
+    pub use crate::user::unix::*;
+
+    #[cfg(target_family = "windows")]
+    pub use crate::user::windows::*;
+}
+
+/// Information about a user account.
+#[derive(Clone, Debug)]
+pub struct User {
+    /// Name of the account (used for logging in).
+    name: std::ffi::OsString,
+    /// Full (display) name of the user, if specified.
+    full_name: Option<std::ffi::OsString>,
+    /// Home (or profile) directory of the user, if known.
+    home_dir: Option<std::path::PathBuf>,
+    /// Unix identifier of the user.
+    #[cfg(target_family = "unix")]
+    uid: u32,
+    /// Unix identifier of the primary group of the user.
+    #[cfg(target_family = "unix")]
+    gid: u32,
+    /// Path to the login shell of the user.
+    #[cfg(target_family = "unix")]
+    shell: Option<std::path::PathBuf>,
+    /// Security identifier of the user in the string format, if known.
+    #[cfg(target_family = "windows")]
+    sid: Option<String>,
+    /// Time at which the user logged in for the last time, if known.
+    #[cfg(target_family = "windows")]
+    last_login_time: Option<std::time::SystemTime>,
+}
+
+impl User {
+
+    /// Returns the name of the account.
+    pub fn name(&self) -> &std::ffi::OsStr {
+        &self.name
+    }
+
+    /// Returns the full (display) name of the user, if specified.
+    pub fn full_name(&self) -> Option<&std::ffi::OsStr> {
+        self.full_name.as_deref()
+    }
+
+    /// Returns the home (or profile) directory of the user, if known.
+    pub fn home_dir(&self) -> Option<&std::path::Path> {
+        self.home_dir.as_deref()
+    }
+
+    /// Returns the Unix identifier of the user.
+    #[cfg(target_family = "unix")]
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the Unix identifier of the primary group of the user.
+    #[cfg(target_family = "unix")]
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the path to the login shell of the user, if specified.
+    #[cfg(target_family = "unix")]
+    pub fn shell(&self) -> Option<&std::path::Path> {
+        self.shell.as_deref()
+    }
+
+    /// Returns the security identifier of the user (e.g. `S-1-5-21-…-1001`).
+    #[cfg(target_family = "windows")]
+    pub fn sid(&self) -> Option<&str> {
+        self.sid.as_deref()
+    }
+
+    /// Returns the time at which the user logged in for the last time.
+    #[cfg(target_family = "windows")]
+    pub fn last_login_time(&self) -> Option<std::time::SystemTime> {
+        self.last_login_time
+    }
+}
+
+/// Returns an iterator over all local user accounts on the system.
+///
+/// On Unix this uses the name service switch (so accounts from remote sources
+/// like LDAP or Open Directory can be included as well).
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to enumerate the accounts
+/// (e.g. because of insufficient permissions). Each yielded item can also be
+/// an error if the information for a particular account was malformed.
+///
+/// # Examples
+///
+/// ```
+/// let users = ospect::user::users()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// for user in users {
+///     println!("{}", user.name().to_string_lossy());
+/// }
+/// ```
+pub fn users() -> std::io::Result<impl Iterator<Item = std::io::Result<User>>> {
+    self::sys::users()
+}
+
+/// Returns the name of the account with the given Unix identifier.
+///
+/// `None` is returned if there is no such account.
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to query the account
+/// database.
+///
+/// # Examples
+///
+/// ```
+/// let name = ospect::user::user_name(0)
+///     .unwrap();
+///
+/// assert_eq!(name.as_deref(), Some("root".as_ref()));
+/// ```
+#[cfg(target_family = "unix")]
+pub fn user_name(uid: u32) -> std::io::Result<Option<std::ffi::OsString>> {
+    self::sys::user_name(uid)
+}
+
+/// Returns the name of the group with the given Unix identifier.
+///
+/// `None` is returned if there is no such group.
+///
+/// # Errors
+///
+/// This function will fail if it was not possible to query the group database.
+///
+/// # Examples
+///
+/// ```
+/// let name = ospect::user::group_name(0)
+///     .unwrap();
+///
+/// assert!(name.is_some());
+/// ```
+#[cfg(target_family = "unix")]
+pub fn group_name(gid: u32) -> std::io::Result<Option<std::ffi::OsString>> {
+    self::sys::group_name(gid)
+}
+
+/// Returns the name of the account with the given security identifier.
+///
+/// The name is qualified with the domain of the account (e.g. `NT AUTHORITY\
+/// SYSTEM`). `None` is returned if there is no such account.
+///
+/// # Errors
+///
+/// This function will fail if the identifier is malformed or if it was not
+/// possible to query the account database.
+///
+/// # Examples
+///
+/// ```no_run
+/// let name = ospect::user::account_name("S-1-5-18")
+///     .unwrap();
+///
+/// assert!(name.is_some());
+/// ```
+#[cfg(target_family = "windows")]
+pub fn account_name(sid: &str) -> std::io::Result<Option<std::ffi::OsString>> {
+    self::sys::account_name(sid)
+}
@@ -0,0 +1,296 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use crate::user::User;
+
+/// Returns an iterator over all local user accounts on the system.
+pub fn users() -> std::io::Result<impl Iterator<Item = std::io::Result<User>>> {
+    // `getpwent` [1] keeps its cursor in a global state, so concurrent calls
+    // would interfere with each other. We guard the whole enumeration with a
+    // lock and collect the results before releasing it. The number of accounts
+    // on a system is rarely big, so this should not be a problem.
+    //
+    // Note that `getpwent_r` would not help us either: it is not available on
+    // all platforms and it still shares the cursor.
+    //
+    // [1]: https://man7.org/linux/man-pages/man3/getpwent.3.html
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _guard = LOCK.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    let mut users = Vec::new();
+
+    // SAFETY: Rewinding the database is always safe. We hold the lock, so no
+    // other thread uses the cursor.
+    unsafe {
+        libc::setpwent();
+    }
+
+    loop {
+        // `getpwent` does not distinguish between the end of the database and
+        // errors other than through `errno`, so we need to clear it first.
+        clear_errno();
+
+        // SAFETY: We hold the lock so the returned pointer is not going to be
+        // invalidated by other threads until the next call to `getpwent`. We
+        // copy all the information out of it before that.
+        let entry = match unsafe { libc::getpwent().as_ref() } {
+            Some(entry) => entry,
+            None => {
+                let error = std::io::Error::last_os_error();
+                match error.raw_os_error() {
+                    // Some implementations set `ENOENT` for the end of the
+                    // database (and some leave `errno` untouched).
+                    Some(0) | Some(libc::ENOENT) | None => (),
+                    Some(_) => users.push(Err(error)),
+                }
+                break;
+            }
+        };
+
+        // SAFETY: The entry returned by `getpwent` is valid (see above).
+        users.push(Ok(unsafe { parse_passwd(entry) }));
+    }
+
+    // SAFETY: Closing the database is always safe. We hold the lock, so no
+    // other thread uses the cursor.
+    unsafe {
+        libc::endpwent();
+    }
+
+    Ok(users.into_iter())
+}
+
+/// Converts a `passwd` entry into a user account information.
+///
+/// # Safety
+///
+/// All the non-null string pointers of `entry` must point to valid null-termi-
+/// nated strings.
+unsafe fn parse_passwd(entry: &libc::passwd) -> User {
+    // SAFETY: The caller guarantees validity of the strings.
+    let (name, gecos, home_dir, shell) = unsafe {(
+        os_string(entry.pw_name),
+        os_string(entry.pw_gecos),
+        os_string(entry.pw_dir),
+        os_string(entry.pw_shell),
+    )};
+
+    User {
+        name: name.unwrap_or_default(),
+        full_name: gecos.and_then(|gecos| parse_gecos_full_name(&gecos)),
+        home_dir: home_dir.map(std::path::PathBuf::from),
+        uid: entry.pw_uid,
+        gid: entry.pw_gid,
+        shell: shell.map(std::path::PathBuf::from),
+    }
+}
+
+/// Returns the name of the account with the given identifier.
+pub fn user_name(uid: u32) -> std::io::Result<Option<std::ffi::OsString>> {
+    let mut entry = std::mem::MaybeUninit::<libc::passwd>::uninit();
+
+    // SAFETY: We pass the entry and the buffer for its strings along with its
+    // size, as described in the documentation [1]. On success, the result is
+    // either null or points to the entry, which stays valid along with the
+    // buffer until the next call.
+    //
+    // [1]: https://man7.org/linux/man-pages/man3/getpwuid_r.3.html
+    let name = unsafe {
+        lookup(|buf, result| {
+            libc::getpwuid_r(uid, entry.as_mut_ptr(), buf.as_mut_ptr(), buf.len(), result)
+        }, |entry| os_string(entry.pw_name))
+    }?;
+
+    Ok(name.flatten())
+}
+
+/// Returns the name of the group with the given identifier.
+pub fn group_name(gid: u32) -> std::io::Result<Option<std::ffi::OsString>> {
+    let mut entry = std::mem::MaybeUninit::<libc::group>::uninit();
+
+    // SAFETY: We pass the entry and the buffer for its strings along with its
+    // size, as described in the documentation [1]. On success, the result is
+    // either null or points to the entry, which stays valid along with the
+    // buffer until the next call.
+    //
+    // [1]: https://man7.org/linux/man-pages/man3/getgrgid_r.3.html
+    let name = unsafe {
+        lookup(|buf, result| {
+            libc::getgrgid_r(gid, entry.as_mut_ptr(), buf.as_mut_ptr(), buf.len(), result)
+        }, |entry| os_string(entry.gr_name))
+    }?;
+
+    Ok(name.flatten())
+}
+
+/// Calls a reentrant lookup function of the account databases (`getpwuid_r`
+/// or `getgrgid_r`) and extracts information from the found entry.
+///
+/// The call is retried with bigger buffers as long as it reports that the one
+/// given to it was too small. `None` is returned if there is no such entry.
+///
+/// # Safety
+///
+/// On success, the call must set the result to either null or a pointer to a
+/// valid entry that stays valid until the next call.
+unsafe fn lookup<E, T, C, F>(mut call: C, extract: F) -> std::io::Result<Option<T>>
+where
+    C: FnMut(&mut [libc::c_char], &mut *mut E) -> libc::c_int,
+    F: FnOnce(&E) -> T,
+{
+    let mut buf = vec![0; DEFAULT_LOOKUP_BUF_LEN];
+
+    let entry = loop {
+        let mut result = std::ptr::null_mut();
+        match call(&mut buf, &mut result) {
+            0 => break result,
+            libc::ERANGE if buf.len() < MAX_LOOKUP_BUF_LEN => {
+                buf.resize(buf.len() * 2, 0);
+            }
+            // Some implementations report missing entries with errors instead
+            // of a null result [1].
+            //
+            // [1]: https://man7.org/linux/man-pages/man3/getpwuid_r.3.html
+            libc::ENOENT | libc::ESRCH => return Ok(None),
+            code => return Err(std::io::Error::from_raw_os_error(code)),
+        }
+    };
+
+    // SAFETY: The caller guarantees that the result is either null or valid.
+    Ok(unsafe { entry.as_ref() }.map(extract))
+}
+
+/// Copies a (potentially null) C string into an owned OS string. Empty strings
+/// are treated as missing.
+///
+/// # Safety
+///
+/// The pointer must be either null or point to a valid null-terminated string.
+unsafe fn os_string(ptr: *const libc::c_char) -> Option<std::ffi::OsString> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    if ptr.is_null() {
+        return None;
+    }
+
+    // SAFETY: The pointer is not null and the caller guarantees that it points
+    // to a valid null-terminated string.
+    let bytes = unsafe {
+        std::ffi::CStr::from_ptr(ptr)
+    }.to_bytes();
+    if bytes.is_empty() {
+        return None;
+    }
+
+    Some(std::ffi::OsStr::from_bytes(bytes).to_os_string())
+}
+
+/// Initial size of the buffer for strings of account database entries.
+const DEFAULT_LOOKUP_BUF_LEN: usize = 1024;
+
+/// Maximum size of the buffer for strings of account database entries.
+const MAX_LOOKUP_BUF_LEN: usize = 1024 * 1024;
+
+/// Extracts the full name of the user from the GECOS field.
+///
+/// The GECOS field is a comma-separated list of values where the first one is
+/// the full name of the user [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man5/passwd.5.html
+fn parse_gecos_full_name(gecos: &std::ffi::OsStr) -> Option<std::ffi::OsString> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let full_name = gecos.as_bytes().split(|byte| *byte == b',').next()?;
+    if full_name.is_empty() {
+        return None;
+    }
+
+    Some(std::ffi::OsStr::from_bytes(full_name).to_os_string())
+}
+
+/// Resets the `errno` value of the current thread to zero.
+fn clear_errno() {
+    // SAFETY: The `errno` location is always valid for the current thread.
+    unsafe {
+        #[cfg(target_os = "linux")]
+        { *libc::__errno_location() = 0; }
+        #[cfg(target_os = "macos")]
+        { *libc::__error() = 0; }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn users_current() {
+        // SAFETY: `getuid` is always safe to call.
+        let uid = unsafe { libc::getuid() };
+
+        let user = users().unwrap()
+            .filter_map(Result::ok)
+            .find(|user| user.uid() == uid);
+
+        // The current user does not need to have a database entry (e.g. when
+        // running in a container with an arbitrary uid).
+        let user = match user {
+            Some(user) => user,
+            None => return,
+        };
+
+        assert!(!user.name().is_empty());
+        if let Some(home_dir) = std::env::var_os("HOME") {
+            assert_eq!(user.home_dir(), Some(std::path::Path::new(&home_dir)));
+        }
+    }
+
+    #[test]
+    fn users_root() {
+        let root = users().unwrap()
+            .filter_map(Result::ok)
+            .find(|user| user.uid() == 0)
+            .unwrap();
+
+        assert_eq!(root.gid(), 0);
+    }
+
+    #[test]
+    fn user_name_root() {
+        let name = user_name(0).unwrap();
+        assert_eq!(name.as_deref(), Some("root".as_ref()));
+    }
+
+    #[test]
+    fn user_name_unknown() {
+        // There is no standard unused identifier, but the one right before the
+        // "no user" value (`-1`) is very unlikely to be used.
+        assert_eq!(user_name(u32::MAX - 1).unwrap(), None);
+    }
+
+    #[test]
+    fn group_name_root() {
+        assert!(group_name(0).unwrap().is_some());
+    }
+
+    #[test]
+    fn parse_gecos_full_name_only() {
+        let full_name = parse_gecos_full_name("John Doe".as_ref());
+        assert_eq!(full_name.as_deref(), Some("John Doe".as_ref()));
+    }
+
+    #[test]
+    fn parse_gecos_full_name_with_other_fields() {
+        let full_name = parse_gecos_full_name("John Doe,Room 1,555-1234,,".as_ref());
+        assert_eq!(full_name.as_deref(), Some("John Doe".as_ref()));
+    }
+
+    #[test]
+    fn parse_gecos_full_name_empty() {
+        assert_eq!(parse_gecos_full_name(",Room 1".as_ref()), None);
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use crate::user::User;
+
+/// Returns an iterator over all local user accounts on the system.
+pub fn users() -> std::io::Result<impl Iterator<Item = std::io::Result<User>>> {
+    use windows_sys::Win32::{
+        Foundation::*,
+        NetworkManagement::NetManagement::*,
+    };
+
+    let mut users = Vec::new();
+    let mut resume_handle = 0;
+
+    loop {
+        let mut buf = std::ptr::null_mut();
+        let mut entries_read = 0;
+        let mut entries_total = 0;
+
+        // SAFETY: We call the function as described in the documentation [1].
+        // The buffer is allocated by the system and we free it below.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/lmaccess/nf-lmaccess-netuserenum
+        let code = unsafe {
+            NetUserEnum(
+                std::ptr::null(),
+                3,
+                FILTER_NORMAL_ACCOUNT,
+                &mut buf,
+                MAX_PREFERRED_LENGTH,
+                &mut entries_read,
+                &mut entries_total,
+                &mut resume_handle,
+            )
+        };
+        if code != NERR_Success && code != ERROR_MORE_DATA {
+            return Err(std::io::Error::from_raw_os_error(code as i32));
+        }
+
+        // SAFETY: The call succeeded, so the buffer contains `entries_read`
+        // instances of the level 3 user information.
+        let entries = unsafe {
+            std::slice::from_raw_parts(buf.cast::<USER_INFO_3>(), entries_read as usize)
+        };
+
+        for entry in entries {
+            // SAFETY: All strings of the entry are valid null-terminated wide
+            // strings (or null pointers) as long as the buffer is alive.
+            let (name, full_name, home_dir) = unsafe {(
+                wide_string(entry.usri3_name),
+                wide_string(entry.usri3_full_name),
+                wide_string(entry.usri3_home_dir),
+            )};
+
+            let name = match name {
+                Some(name) => name,
+                None => {
+                    users.push(Err(std::io::ErrorKind::InvalidData.into()));
+                    continue;
+                }
+            };
+
+            let sid = sid(&name).ok();
+
+            // The home directory reported by `NetUserEnum` is usually empty as
+            // it is only set for accounts with explicitly assigned one. In such
+            // cases we use the profile directory of the user.
+            let home_dir = home_dir
+                .map(std::path::PathBuf::from)
+                .or_else(|| profile_dir(sid.as_deref()?).ok());
+
+            // Last logon time is expressed as the number of seconds since the
+            // epoch and zero means that it is not known [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/lmaccess/ns-lmaccess-user_info_3
+            let last_login_time = match entry.usri3_last_logon {
+                0 => None,
+                secs => {
+                    let secs = std::time::Duration::from_secs(u64::from(secs));
+                    Some(std::time::SystemTime::UNIX_EPOCH + secs)
+                }
+            };
+
+            users.push(Ok(User {
+                name,
+                full_name,
+                home_dir,
+                sid,
+                last_login_time,
+            }));
+        }
+
+        // SAFETY: The buffer was allocated by the `NetUserEnum` call and we do
+        // not use any data from it past this point.
+        unsafe {
+            NetApiBufferFree(buf.cast());
+        }
+
+        if code != ERROR_MORE_DATA {
+            break;
+        }
+    }
+
+    Ok(users.into_iter())
+}
+
+/// Returns the security identifier of the account with the given name.
+fn sid(name: &std::ffi::OsStr) -> std::io::Result<String> {
+    use std::os::windows::ffi::OsStrExt as _;
+    use windows_sys::Win32::{
+        Foundation::*,
+        Security::*,
+        Security::Authorization::*,
+    };
+
+    let name = name.encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+
+    let mut sid_buf = [0u8; SECURITY_MAX_SID_SIZE as usize];
+    let mut sid_len = sid_buf.len() as u32;
+
+    let mut domain_buf = [0u16; 256];
+    let mut domain_len = domain_buf.len() as u32;
+
+    let mut sid_use = 0;
+
+    // SAFETY: We pass buffers along with their sizes. The SID buffer is large
+    // enough to hold any SID, the domain buffer is enough for any valid domain
+    // name and if not, the call fails and we return an error.
+    let status = unsafe {
+        LookupAccountNameW(
+            std::ptr::null(),
+            name.as_ptr(),
+            sid_buf.as_mut_ptr().cast(),
+            &mut sid_len,
+            domain_buf.as_mut_ptr(),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut sid_str = std::ptr::null_mut();
+
+    // SAFETY: The SID buffer has been filled by the successful call above. The
+    // string is allocated by the system and we free it below.
+    let status = unsafe {
+        ConvertSidToStringSidW(sid_buf.as_mut_ptr().cast(), &mut sid_str)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded so the string is valid and null-terminated.
+    let result = unsafe { wide_string(sid_str) };
+
+    // SAFETY: The string was allocated by `ConvertSidToStringSidW` and it has
+    // to be freed with `LocalFree` [1]. We copied it above.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertsidtostringsidw
+    unsafe {
+        LocalFree(sid_str.cast());
+    }
+
+    result
+        .and_then(|sid| sid.into_string().ok())
+        .ok_or_else(|| std::io::ErrorKind::InvalidData.into())
+}
+
+/// Returns the domain-qualified name of the account with the given SID.
+pub fn account_name(sid: &str) -> std::io::Result<Option<std::ffi::OsString>> {
+    use std::os::windows::ffi::OsStringExt as _;
+    use windows_sys::Win32::{
+        Foundation::*,
+        Security::*,
+        Security::Authorization::*,
+    };
+
+    let sid_str = sid.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let mut sid = std::ptr::null_mut();
+
+    // SAFETY: We pass a valid null-terminated string. The SID is allocated by
+    // the system and we free it below.
+    let status = unsafe {
+        ConvertStringSidToSidW(sid_str.as_ptr(), &mut sid)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut name_buf = [0u16; 256];
+    let mut name_len = name_buf.len() as u32;
+
+    let mut domain_buf = [0u16; 256];
+    let mut domain_len = domain_buf.len() as u32;
+
+    let mut sid_use = 0;
+
+    // SAFETY: The SID is valid (verified above) and we pass buffers along with
+    // their sizes. They are enough for any valid account and domain name and
+    // if not, the call fails and we return an error.
+    let status = unsafe {
+        LookupAccountSidW(
+            std::ptr::null(),
+            sid,
+            name_buf.as_mut_ptr(),
+            &mut name_len,
+            domain_buf.as_mut_ptr(),
+            &mut domain_len,
+            &mut sid_use,
+        )
+    };
+    let error = std::io::Error::last_os_error();
+
+    // SAFETY: The SID was allocated by `ConvertStringSidToSidW` and it has to
+    // be freed with `LocalFree` [1]. We no longer need it.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertstringsidtosidw
+    unsafe {
+        LocalFree(sid.cast());
+    }
+
+    if status == FALSE {
+        if error.raw_os_error() == Some(ERROR_NONE_MAPPED as i32) {
+            return Ok(None);
+        }
+        return Err(error);
+    }
+
+    // On success, the lengths are updated to the number of characters written
+    // (without the terminator).
+    let name = &name_buf[..name_len as usize];
+    let domain = &domain_buf[..domain_len as usize];
+
+    let mut result = Vec::with_capacity(domain.len() + 1 + name.len());
+    if !domain.is_empty() {
+        result.extend_from_slice(domain);
+        result.push(u16::from(b'\\'));
+    }
+    result.extend_from_slice(name);
+
+    Ok(Some(std::ffi::OsString::from_wide(&result)))
+}
+
+/// Returns the profile directory of the account with the given SID.
+fn profile_dir(sid: &str) -> std::io::Result<std::path::PathBuf> {
+    use std::os::windows::ffi::OsStringExt as _;
+    use windows_sys::Win32::System::Registry::*;
+
+    let key = format!("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\ProfileList\\{sid}")
+        .encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let value = "ProfileImagePath"
+        .encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+
+    let mut buf = vec![0u16; MAX_PATH_LEN];
+    let mut buf_size = (buf.len() * std::mem::size_of::<u16>()) as u32;
+
+    // SAFETY: We pass a buffer along with its size (in bytes). The value is of
+    // the `REG_EXPAND_SZ` type and `RegGetValueW` expands it for us [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew
+    let code = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_SZ,
+            std::ptr::null_mut(),
+            buf.as_mut_ptr().cast(),
+            &mut buf_size,
+        )
+    };
+    if code != windows_sys::Win32::Foundation::NO_ERROR {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    // The size includes the null terminator which we want to strip.
+    let len = (buf_size as usize / std::mem::size_of::<u16>()).saturating_sub(1);
+    buf.truncate(len);
+
+    Ok(std::path::PathBuf::from(std::ffi::OsString::from_wide(&buf)))
+}
+
+/// Copies a (potentially null) wide string into an owned OS string. Empty
+/// strings are treated as missing.
+///
+/// # Safety
+///
+/// The pointer must be either null or point to a valid null-terminated string.
+unsafe fn wide_string(ptr: *const u16) -> Option<std::ffi::OsString> {
+    use std::os::windows::ffi::OsStringExt as _;
+
+    if ptr.is_null() {
+        return None;
+    }
+
+    let mut len = 0;
+    // SAFETY: The caller guarantees that the string is null-terminated.
+    while unsafe { *ptr.add(len) } != 0 {
+        len += 1;
+    }
+    if len == 0 {
+        return None;
+    }
+
+    // SAFETY: We just verified that there are `len` valid characters.
+    let chars = unsafe { std::slice::from_raw_parts(ptr, len) };
+    Some(std::ffi::OsString::from_wide(chars))
+}
+
+/// The maximum length (in characters) of a profile path we support.
+const MAX_PATH_LEN: usize = 32768;
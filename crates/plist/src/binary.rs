@@ -0,0 +1,436 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser for the binary property list format.
+//!
+//! There is no official specification of the format, the best description is
+//! available in comments of the [CoreFoundation sources][1].
+//!
+//! [1]: https://opensource.apple.com/source/CF/CF-1153.18/CFBinaryPList.c
+
+use std::collections::BTreeMap;
+
+use crate::{ParseError, ParseErrorKind, Value};
+
+/// Magic bytes that every binary property list starts with.
+pub const MAGIC: &[u8] = b"bplist00";
+
+/// Size of the trailer at the end of the binary property list.
+const TRAILER_LEN: usize = 32;
+
+/// Parses a binary property list from the given bytes.
+pub fn parse(buf: &[u8]) -> Result<Value, ParseError> {
+    let parser = Parser::new(buf)?;
+    parser.object(parser.top_object, 0)
+}
+
+/// State of the binary property list parser.
+struct Parser<'b> {
+    /// Entire input buffer.
+    buf: &'b [u8],
+    /// Size (in bytes) of entries in the offset table.
+    offset_size: usize,
+    /// Size (in bytes) of object references.
+    ref_size: usize,
+    /// Number of objects in the list.
+    num_objects: u64,
+    /// Index of the root object.
+    top_object: u64,
+    /// Offset at which the offset table starts.
+    offset_table_offset: usize,
+}
+
+impl<'b> Parser<'b> {
+
+    /// Creates a new parser for the given input reading its trailer.
+    fn new(buf: &'b [u8]) -> Result<Parser<'b>, ParseError> {
+        if buf.len() < MAGIC.len() + TRAILER_LEN {
+            return Err(ParseError::new(ParseErrorKind::UnexpectedEof, buf.len()));
+        }
+
+        let trailer_offset = buf.len() - TRAILER_LEN;
+        let trailer = &buf[trailer_offset..];
+
+        let invalid_trailer = || {
+            ParseError::new(ParseErrorKind::InvalidTrailer, trailer_offset)
+        };
+
+        // The first 6 bytes of the trailer are unused (5 are always zero and
+        // the sixth one is a "sort version" that we do not care about).
+        let offset_size = usize::from(trailer[6]);
+        let ref_size = usize::from(trailer[7]);
+        let num_objects = read_be_uint(&trailer[8..16]);
+        let top_object = read_be_uint(&trailer[16..24]);
+        let offset_table_offset = read_be_uint(&trailer[24..32]);
+
+        if !(1..=8).contains(&offset_size) || !(1..=8).contains(&ref_size) {
+            return Err(invalid_trailer());
+        }
+        if top_object >= num_objects {
+            return Err(invalid_trailer());
+        }
+
+        let offset_table_offset = usize::try_from(offset_table_offset)
+            .map_err(|_| invalid_trailer())?;
+        let offset_table_len = usize::try_from(num_objects).ok()
+            .and_then(|num_objects| num_objects.checked_mul(offset_size))
+            .ok_or_else(invalid_trailer)?;
+
+        if offset_table_offset < MAGIC.len() {
+            return Err(invalid_trailer());
+        }
+        match offset_table_offset.checked_add(offset_table_len) {
+            Some(offset_table_end) if offset_table_end <= trailer_offset => (),
+            _ => return Err(invalid_trailer()),
+        }
+
+        Ok(Parser {
+            buf,
+            offset_size,
+            ref_size,
+            num_objects,
+            top_object,
+            offset_table_offset,
+        })
+    }
+
+    /// Parses the object with the given index.
+    fn object(&self, index: u64, depth: usize) -> Result<Value, ParseError> {
+        if depth > crate::MAX_DEPTH {
+            return Err(ParseError::new(ParseErrorKind::NestingTooDeep, 0));
+        }
+
+        let offset = self.object_offset(index)?;
+
+        let marker = self.buf[offset];
+        let invalid_marker = || {
+            ParseError::new(ParseErrorKind::InvalidMarker(marker), offset)
+        };
+
+        match (marker >> 4, marker & 0x0F) {
+            (0x0, 0x8) => Ok(Value::Bool(false)),
+            (0x0, 0x9) => Ok(Value::Bool(true)),
+            (0x1, size_log2) => {
+                let (integer, _) = self.integer(offset, size_log2)?;
+                Ok(Value::Integer(integer))
+            }
+            (0x2, 0x2) => {
+                let bytes = self.bytes(offset + 1, 4)?;
+                let real = f32::from_be_bytes(bytes.try_into().unwrap());
+                Ok(Value::Real(f64::from(real)))
+            }
+            (0x2, 0x3) => {
+                let bytes = self.bytes(offset + 1, 8)?;
+                Ok(Value::Real(f64::from_be_bytes(bytes.try_into().unwrap())))
+            }
+            (0x3, 0x3) => {
+                let bytes = self.bytes(offset + 1, 8)?;
+                let secs = f64::from_be_bytes(bytes.try_into().unwrap());
+
+                let date = core_data_date(secs)
+                    .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidDate, offset))?;
+                Ok(Value::Date(date))
+            }
+            (0x4, _) => {
+                let (len, data_offset) = self.len(offset)?;
+                Ok(Value::Data(self.bytes(data_offset, len)?.to_vec()))
+            }
+            (0x5, _) => {
+                let (len, string_offset) = self.len(offset)?;
+                let bytes = self.bytes(string_offset, len)?;
+
+                // These are supposed to be ASCII strings but we are lenient and
+                // accept anything that is valid UTF-8.
+                let string = std::str::from_utf8(bytes)
+                    .map_err(|_| ParseError::new(ParseErrorKind::InvalidString, string_offset))?;
+                Ok(Value::String(String::from(string)))
+            }
+            (0x6, _) => {
+                let (len, string_offset) = self.len(offset)?;
+                let byte_len = len.checked_mul(2)
+                    .ok_or_else(|| ParseError::new(ParseErrorKind::UnexpectedEof, string_offset))?;
+                let bytes = self.bytes(string_offset, byte_len)?;
+
+                let units = bytes.chunks_exact(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                    .collect::<Vec<_>>();
+
+                let string = String::from_utf16(&units)
+                    .map_err(|_| ParseError::new(ParseErrorKind::InvalidString, string_offset))?;
+                Ok(Value::String(string))
+            }
+            (0x8, size_minus_1) => {
+                let bytes = self.bytes(offset + 1, usize::from(size_minus_1) + 1)?;
+                if bytes.len() > 8 {
+                    return Err(invalid_marker());
+                }
+                Ok(Value::Uid(read_be_uint(bytes)))
+            }
+            (0xA, _) => {
+                let (len, refs_offset) = self.len(offset)?;
+
+                let mut array = Vec::new();
+                for i in 0..len {
+                    let index = self.object_ref(refs_offset, i)?;
+                    array.push(self.object(index, depth + 1)?);
+                }
+
+                Ok(Value::Array(array))
+            }
+            (0xD, _) => {
+                let (len, refs_offset) = self.len(offset)?;
+
+                let mut dict = BTreeMap::new();
+                for i in 0..len {
+                    let key_index = self.object_ref(refs_offset, i)?;
+                    let value_index = self.object_ref(refs_offset, len + i)?;
+
+                    let key = match self.object(key_index, depth + 1)? {
+                        Value::String(key) => key,
+                        _ => return Err(ParseError::new(ParseErrorKind::InvalidKey, offset)),
+                    };
+                    dict.insert(key, self.object(value_index, depth + 1)?);
+                }
+
+                Ok(Value::Dict(dict))
+            }
+            _ => Err(invalid_marker()),
+        }
+    }
+
+    /// Returns the offset of the object with the given index.
+    fn object_offset(&self, index: u64) -> Result<usize, ParseError> {
+        if index >= self.num_objects {
+            return Err(ParseError::new(ParseErrorKind::InvalidReference, 0));
+        }
+
+        // We verified that the whole offset table fits in the buffer when the
+        // parser got created, so these computations cannot overflow.
+        let entry_offset = self.offset_table_offset + index as usize * self.offset_size;
+        let entry = &self.buf[entry_offset..entry_offset + self.offset_size];
+
+        // Objects have to be located between the header and the offset table.
+        match usize::try_from(read_be_uint(entry)) {
+            Ok(offset) if (MAGIC.len()..self.offset_table_offset).contains(&offset) => {
+                Ok(offset)
+            }
+            _ => Err(ParseError::new(ParseErrorKind::InvalidReference, entry_offset)),
+        }
+    }
+
+    /// Reads the `i`-th object reference from the list starting at `offset`.
+    fn object_ref(&self, offset: usize, i: usize) -> Result<u64, ParseError> {
+        let ref_offset = i.checked_mul(self.ref_size)
+            .and_then(|ref_offset| ref_offset.checked_add(offset))
+            .ok_or_else(|| ParseError::new(ParseErrorKind::UnexpectedEof, offset))?;
+
+        Ok(read_be_uint(self.bytes(ref_offset, self.ref_size)?))
+    }
+
+    /// Reads the length of the object with the marker at `offset`.
+    ///
+    /// Returns the length and the offset at which the object contents start.
+    fn len(&self, offset: usize) -> Result<(usize, usize), ParseError> {
+        let marker = self.buf[offset];
+
+        // Lengths that do not fit into the lower nibble of the marker byte are
+        // stored as a separate integer object that follows the marker.
+        if marker & 0x0F != 0x0F {
+            return Ok((usize::from(marker & 0x0F), offset + 1));
+        }
+
+        let int_marker = *self.bytes(offset + 1, 1)?.first().unwrap();
+        if int_marker >> 4 != 0x1 {
+            return Err(ParseError::new(ParseErrorKind::InvalidMarker(int_marker), offset + 1));
+        }
+
+        let (len, len_end) = self.integer(offset + 1, int_marker & 0x0F)?;
+        let len = usize::try_from(len)
+            .map_err(|_| ParseError::new(ParseErrorKind::InvalidInteger, offset + 1))?;
+
+        Ok((len, len_end))
+    }
+
+    /// Reads an integer object with the marker at `offset`.
+    ///
+    /// Returns the integer and the offset right after the object.
+    fn integer(&self, offset: usize, size_log2: u8) -> Result<(i128, usize), ParseError> {
+        if size_log2 > 4 {
+            return Err(ParseError::new(ParseErrorKind::InvalidInteger, offset));
+        }
+
+        let size = 1 << size_log2;
+        let bytes = self.bytes(offset + 1, size)?;
+
+        // Integers of up to 4 bytes are unsigned, 8-byte integers are signed
+        // and 16-byte integers are signed as well (they are used to represent
+        // unsigned 64-bit values that do not fit into the signed type).
+        let integer = match size {
+            1 | 2 | 4 => i128::from(read_be_uint(bytes)),
+            8 => i128::from(read_be_uint(bytes) as i64),
+            _ => i128::from_be_bytes(bytes.try_into().unwrap()),
+        };
+
+        Ok((integer, offset + 1 + size))
+    }
+
+    /// Returns `len` bytes of the object area starting at `offset`.
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'b [u8], ParseError> {
+        let eof = || ParseError::new(ParseErrorKind::UnexpectedEof, offset);
+
+        let end = offset.checked_add(len)
+            .ok_or_else(eof)?;
+        if end > self.offset_table_offset {
+            return Err(eof());
+        }
+
+        Ok(&self.buf[offset..end])
+    }
+}
+
+/// Reads a big-endian unsigned integer of at most 8 bytes.
+fn read_be_uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, byte| acc << 8 | u64::from(*byte))
+}
+
+/// Converts the number of seconds since the Core Data epoch to system time.
+fn core_data_date(secs: f64) -> Option<std::time::SystemTime> {
+    if !secs.is_finite() {
+        return None;
+    }
+
+    let epoch = std::time::UNIX_EPOCH
+        .checked_add(std::time::Duration::from_secs(crate::CORE_DATA_EPOCH_SECS))?;
+
+    let duration = std::time::Duration::try_from_secs_f64(secs.abs()).ok()?;
+    if secs >= 0.0 {
+        epoch.checked_add(duration)
+    } else {
+        epoch.checked_sub(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Generated using Python's `plistlib` from the following dictionary:
+    //
+    //     {
+    //         "Label": "com.example.foo",
+    //         "ProgramArguments": ["/usr/bin/foo", "--bar"],
+    //         "RunAtLoad": True,
+    //         "StartInterval": 3600,
+    //         "Date": datetime(2023, 11, 14, 22, 13, 20),
+    //         "Data": b"\x00\x01\x02",
+    //         "Real": 0.5,
+    //         "Unicode": "zażółć",
+    //     }
+    const PLIST: &[u8] = &[
+        0x62, 0x70, 0x6c, 0x69, 0x73, 0x74, 0x30, 0x30, 0xd8, 0x01, 0x02, 0x03,
+        0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0f, 0x10, 0x11,
+        0x12, 0x54, 0x44, 0x61, 0x74, 0x61, 0x54, 0x44, 0x61, 0x74, 0x65, 0x55,
+        0x4c, 0x61, 0x62, 0x65, 0x6c, 0x5f, 0x10, 0x10, 0x50, 0x72, 0x6f, 0x67,
+        0x72, 0x61, 0x6d, 0x41, 0x72, 0x67, 0x75, 0x6d, 0x65, 0x6e, 0x74, 0x73,
+        0x54, 0x52, 0x65, 0x61, 0x6c, 0x59, 0x52, 0x75, 0x6e, 0x41, 0x74, 0x4c,
+        0x6f, 0x61, 0x64, 0x5d, 0x53, 0x74, 0x61, 0x72, 0x74, 0x49, 0x6e, 0x74,
+        0x65, 0x72, 0x76, 0x61, 0x6c, 0x57, 0x55, 0x6e, 0x69, 0x63, 0x6f, 0x64,
+        0x65, 0x43, 0x00, 0x01, 0x02, 0x33, 0x41, 0xc5, 0x82, 0x14, 0x40, 0x00,
+        0x00, 0x00, 0x5f, 0x10, 0x0f, 0x63, 0x6f, 0x6d, 0x2e, 0x65, 0x78, 0x61,
+        0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x66, 0x6f, 0x6f, 0xa2, 0x0d, 0x0e, 0x5c,
+        0x2f, 0x75, 0x73, 0x72, 0x2f, 0x62, 0x69, 0x6e, 0x2f, 0x66, 0x6f, 0x6f,
+        0x55, 0x2d, 0x2d, 0x62, 0x61, 0x72, 0x23, 0x3f, 0xe0, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x09, 0x11, 0x0e, 0x10, 0x66, 0x00, 0x7a, 0x00, 0x61,
+        0x01, 0x7c, 0x00, 0xf3, 0x01, 0x42, 0x01, 0x07, 0x08, 0x19, 0x1e, 0x23,
+        0x29, 0x3c, 0x41, 0x4b, 0x59, 0x61, 0x65, 0x6e, 0x80, 0x83, 0x90, 0x96,
+        0x9f, 0xa0, 0xa3, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xb0,
+    ];
+
+    #[test]
+    fn parse_example() {
+        let value = parse(PLIST).unwrap();
+
+        assert_eq!(value.get("Label").and_then(Value::as_str), Some("com.example.foo"));
+        assert_eq!(value.get("RunAtLoad").and_then(Value::as_bool), Some(true));
+        assert_eq!(value.get("StartInterval").and_then(Value::as_integer), Some(3600));
+        assert_eq!(value.get("Real"), Some(&Value::Real(0.5)));
+        assert_eq!(value.get("Data"), Some(&Value::Data(vec![0x00, 0x01, 0x02])));
+        assert_eq!(value.get("Unicode").and_then(Value::as_str), Some("zażółć"));
+
+        let args = value.get("ProgramArguments").and_then(Value::as_array)
+            .unwrap();
+        assert_eq!(args, &[
+            Value::String(String::from("/usr/bin/foo")),
+            Value::String(String::from("--bar")),
+        ]);
+
+        let date = value.get("Date").and_then(Value::as_date)
+            .unwrap();
+        assert_eq! {
+            date,
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000)
+        };
+    }
+
+    #[test]
+    fn parse_truncated() {
+        for len in 0..PLIST.len() {
+            // We are only interested in not panicking here, the parser might
+            // still succeed for some prefixes where the trailer happens to be
+            // valid (which it never is for our fixture).
+            assert!(parse(&PLIST[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_invalid_offset_size() {
+        let mut buf = PLIST.to_vec();
+        let offset_size_offset = buf.len() - TRAILER_LEN + 6;
+        buf[offset_size_offset] = 0;
+
+        let error = parse(&buf).unwrap_err();
+        assert_eq!(error.kind(), ParseErrorKind::InvalidTrailer);
+    }
+
+    #[test]
+    fn parse_reference_cycle() {
+        // Single array object (marker `0xA1`) containing a reference to itself.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(MAGIC);
+        buf.extend_from_slice(&[0xA1, 0x00]);
+        buf.push(0x08);
+        buf.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        buf.extend_from_slice(&1u64.to_be_bytes());
+        buf.extend_from_slice(&0u64.to_be_bytes());
+        buf.extend_from_slice(&10u64.to_be_bytes());
+
+        let error = parse(&buf).unwrap_err();
+        assert_eq!(error.kind(), ParseErrorKind::NestingTooDeep);
+    }
+
+    #[test]
+    fn core_data_date_epoch() {
+        assert_eq! {
+            core_data_date(0.0),
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(978307200))
+        };
+    }
+
+    #[test]
+    fn core_data_date_before_epoch() {
+        assert_eq! {
+            core_data_date(-978307200.0),
+            Some(std::time::UNIX_EPOCH)
+        };
+    }
+
+    #[test]
+    fn core_data_date_nan() {
+        assert_eq!(core_data_date(f64::NAN), None);
+    }
+}
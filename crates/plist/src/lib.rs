@@ -0,0 +1,317 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A small library for parsing [property lists][1].
+//!
+//! Property lists are used extensively on macOS for storing configuration and
+//! metadata (e.g. application bundle information, launchd jobs or installation
+//! receipts). This library supports both the XML and the binary format but it
+//! is not a fully generic implementation: it only parses and does not offer any
+//! functionality for creating property lists.
+//!
+//! [1]: https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/PropertyLists/Introduction/Introduction.html
+
+mod binary;
+mod xml;
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// A value of the property list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Boolean value.
+    Bool(bool),
+    /// Integer value.
+    ///
+    /// Binary property lists can store integers up to 128 bits (both signed and
+    /// unsigned), so we use the widest available type.
+    Integer(i128),
+    /// Floating-point value.
+    Real(f64),
+    /// Unicode string.
+    String(String),
+    /// Point in time.
+    Date(SystemTime),
+    /// Arbitrary binary data.
+    Data(Vec<u8>),
+    /// Ordered sequence of values.
+    Array(Vec<Value>),
+    /// Mapping from string keys to values.
+    Dict(BTreeMap<String, Value>),
+    /// Object reference (specific to keyed archives in binary property lists).
+    Uid(u64),
+}
+
+impl Value {
+
+    /// Returns the string if the value is of the string type.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean if the value is of the boolean type.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(bool) => Some(*bool),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if the value is of the integer type.
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            _ => None,
+        }
+    }
+
+    /// Returns the time if the value is of the date type.
+    pub fn as_date(&self) -> Option<SystemTime> {
+        match self {
+            Value::Date(date) => Some(*date),
+            _ => None,
+        }
+    }
+
+    /// Returns the items if the value is of the array type.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if the value is of the dictionary type.
+    pub fn as_dict(&self) -> Option<&BTreeMap<String, Value>> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Returns the value for the given key if the value is a dictionary.
+    ///
+    /// `None` is returned both when the value is not a dictionary and when the
+    /// dictionary has no such key.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_dict()?.get(key)
+    }
+}
+
+/// Parses a property list from the given bytes.
+///
+/// The format (XML or binary) is detected automatically.
+///
+/// # Errors
+///
+/// This function will return an error if the input is not a valid property
+/// list or if it uses features not supported by this library.
+///
+/// # Examples
+///
+/// ```
+/// let value = plist::parse(br#"
+/// <?xml version="1.0" encoding="UTF-8"?>
+/// <plist version="1.0">
+/// <dict>
+///     <key>Label</key>
+///     <string>com.example.foo</string>
+/// </dict>
+/// </plist>
+/// "#).unwrap();
+///
+/// assert_eq!(value.get("Label").and_then(plist::Value::as_str), Some("com.example.foo"));
+/// ```
+pub fn parse(buf: &[u8]) -> Result<Value, ParseError> {
+    if buf.starts_with(self::binary::MAGIC) {
+        self::binary::parse(buf)
+    } else {
+        self::xml::parse(buf)
+    }
+}
+
+/// Parses a property list from the file at the given path.
+///
+/// See [`parse`] for more details.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or if its
+/// contents are not a valid property list (in which case the error is of the
+/// [`InvalidData`](std::io::ErrorKind::InvalidData) kind).
+pub fn parse_file<P>(path: P) -> std::io::Result<Value>
+where
+    P: AsRef<std::path::Path>,
+{
+    let buf = std::fs::read(path)?;
+    Ok(parse(&buf)?)
+}
+
+/// An error that might be returned when parsing property lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// A kind of the error.
+    kind: ParseErrorKind,
+    /// Byte offset in the input at which the error occurred.
+    offset: usize,
+}
+
+impl ParseError {
+
+    /// Creates a new error of the given kind at the given offset.
+    fn new(kind: ParseErrorKind, offset: usize) -> ParseError {
+        ParseError {
+            kind,
+            offset,
+        }
+    }
+
+    /// Returns the corresponding [`ParseErrorKind`] of the error.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Returns the byte offset in the input at which the error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Kinds of errors that can happen when parsing property lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The input ended before the value was complete.
+    UnexpectedEof,
+    /// The binary trailer (or offset table) is malformed.
+    InvalidTrailer,
+    /// An object of the binary list has an unknown or unsupported marker.
+    InvalidMarker(u8),
+    /// The binary list references an object that does not exist.
+    InvalidReference,
+    /// Values are nested too deeply (or there is a reference cycle).
+    NestingTooDeep,
+    /// The XML syntax is malformed.
+    InvalidSyntax,
+    /// An XML element is not a valid property list element.
+    UnexpectedElement,
+    /// A string is not valid UTF-8 (or UTF-16).
+    InvalidString,
+    /// An integer cannot be parsed.
+    InvalidInteger,
+    /// A real number cannot be parsed.
+    InvalidReal,
+    /// A date cannot be parsed or is out of range.
+    InvalidDate,
+    /// Binary data is not valid Base64.
+    InvalidData,
+    /// A dictionary key is not a string.
+    InvalidKey,
+}
+
+impl std::fmt::Display for ParseErrorKind {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ParseErrorKind::*;
+        match *self {
+            UnexpectedEof => write!(fmt, "unexpected end of input"),
+            InvalidTrailer => write!(fmt, "invalid binary trailer"),
+            InvalidMarker(marker) => write!(fmt, "invalid object marker: {marker:#04x}"),
+            InvalidReference => write!(fmt, "invalid object reference"),
+            NestingTooDeep => write!(fmt, "nesting too deep"),
+            InvalidSyntax => write!(fmt, "invalid syntax"),
+            UnexpectedElement => write!(fmt, "unexpected element"),
+            InvalidString => write!(fmt, "invalid string"),
+            InvalidInteger => write!(fmt, "invalid integer"),
+            InvalidReal => write!(fmt, "invalid real"),
+            InvalidDate => write!(fmt, "invalid date"),
+            InvalidData => write!(fmt, "invalid data"),
+            InvalidKey => write!(fmt, "invalid dictionary key"),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "{} (at offset {})", self.kind, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+impl From<ParseError> for std::io::Error {
+
+    fn from(error: ParseError) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Maximum depth of nested values that we allow.
+///
+/// Binary property lists can have reference cycles, so we need some limit to
+/// avoid infinite recursion. Real-world property lists are very shallow, so the
+/// limit should never be hit for legitimate inputs.
+const MAX_DEPTH: usize = 256;
+
+/// Number of seconds between the Unix epoch and the Core Data epoch.
+///
+/// Dates in the binary format are stored relative to 2001-01-01 00:00:00 UTC.
+const CORE_DATA_EPOCH_SECS: u64 = 978_307_200;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_detects_binary() {
+        // Binary list with a single `true` value. The offset table has a single
+        // one-byte entry pointing at offset 8 and the trailer says that there
+        // is one object, which is also the top one.
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"bplist00");
+        buf.push(0x09);
+        buf.push(0x08);
+        buf.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        buf.extend_from_slice(&1u64.to_be_bytes());
+        buf.extend_from_slice(&0u64.to_be_bytes());
+        buf.extend_from_slice(&9u64.to_be_bytes());
+
+        assert_eq!(parse(&buf), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn parse_detects_xml() {
+        let value = parse(b"<plist version=\"1.0\"><false/></plist>")
+            .unwrap();
+
+        assert_eq!(value, Value::Bool(false));
+    }
+
+    #[test]
+    fn value_get() {
+        let mut dict = BTreeMap::new();
+        dict.insert(String::from("foo"), Value::Integer(42));
+
+        let value = Value::Dict(dict);
+        assert_eq!(value.get("foo"), Some(&Value::Integer(42)));
+        assert_eq!(value.get("bar"), None);
+        assert_eq!(Value::Integer(42).get("foo"), None);
+    }
+
+    #[test]
+    fn parse_error_into_io_error() {
+        let error = parse(b"foo").unwrap_err();
+
+        let error = std::io::Error::from(error);
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,661 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser for the XML property list format.
+//!
+//! This is not a general-purpose XML parser: it only understands the subset of
+//! XML that is used by property lists as described by the [DTD][1]. Things like
+//! namespaces, custom entities or processing instructions within the document
+//! are not supported.
+//!
+//! [1]: https://www.apple.com/DTDs/PropertyList-1.0.dtd
+
+use std::collections::BTreeMap;
+
+use crate::{ParseError, ParseErrorKind, Value};
+
+/// Parses an XML property list from the given bytes.
+pub fn parse(buf: &[u8]) -> Result<Value, ParseError> {
+    // Property lists are always encoded with UTF-8. We strip the byte order
+    // mark if there is one.
+    let buf = buf.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(buf);
+    let input = std::str::from_utf8(buf)
+        .map_err(|error| ParseError::new(ParseErrorKind::InvalidString, error.valid_up_to()))?;
+
+    let mut parser = Parser {
+        input,
+        pos: 0,
+    };
+
+    parser.skip_misc()?;
+
+    let value = match parser.open_tag()? {
+        Tag::Open("plist") => {
+            let value = parser.value(0)?;
+            parser.skip_misc()?;
+            parser.close_tag("plist")?;
+            value
+        }
+        // Some tools write the root value without the `plist` element, we are
+        // lenient and accept such lists as well.
+        tag => parser.tag_value(tag, 0)?,
+    };
+
+    parser.skip_misc()?;
+    if parser.pos != parser.input.len() {
+        return Err(parser.error(ParseErrorKind::InvalidSyntax));
+    }
+
+    Ok(value)
+}
+
+/// State of the XML property list parser.
+struct Parser<'i> {
+    /// Entire input string.
+    input: &'i str,
+    /// Current position within the input.
+    pos: usize,
+}
+
+/// An opening tag of an XML element.
+#[derive(Debug)]
+enum Tag<'i> {
+    /// Tag of an element with content (e.g. `<string>`).
+    Open(&'i str),
+    /// Tag of an element without content (e.g. `<true/>`).
+    Empty(&'i str),
+}
+
+impl<'i> Parser<'i> {
+
+    /// Parses the next value.
+    fn value(&mut self, depth: usize) -> Result<Value, ParseError> {
+        self.skip_misc()?;
+        let tag = self.open_tag()?;
+        self.tag_value(tag, depth)
+    }
+
+    /// Parses the value of the element with the given opening tag.
+    fn tag_value(&mut self, tag: Tag<'i>, depth: usize) -> Result<Value, ParseError> {
+        if depth > crate::MAX_DEPTH {
+            return Err(self.error(ParseErrorKind::NestingTooDeep));
+        }
+
+        let start = self.pos;
+
+        match tag {
+            Tag::Empty("true") => Ok(Value::Bool(true)),
+            Tag::Empty("false") => Ok(Value::Bool(false)),
+            Tag::Empty("string") => Ok(Value::String(String::new())),
+            Tag::Empty("data") => Ok(Value::Data(Vec::new())),
+            Tag::Empty("array") => Ok(Value::Array(Vec::new())),
+            Tag::Empty("dict") => Ok(Value::Dict(BTreeMap::new())),
+            Tag::Open("string") => {
+                let string = self.text()?;
+                self.close_tag("string")?;
+
+                Ok(Value::String(string))
+            }
+            Tag::Open("integer") => {
+                let text = self.text()?;
+                self.close_tag("integer")?;
+
+                let integer = parse_integer(text.trim())
+                    .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidInteger, start))?;
+                Ok(Value::Integer(integer))
+            }
+            Tag::Open("real") => {
+                let text = self.text()?;
+                self.close_tag("real")?;
+
+                let real = text.trim().parse::<f64>()
+                    .map_err(|_| ParseError::new(ParseErrorKind::InvalidReal, start))?;
+                Ok(Value::Real(real))
+            }
+            Tag::Open("date") => {
+                let text = self.text()?;
+                self.close_tag("date")?;
+
+                let date = parse_date(text.trim())
+                    .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidDate, start))?;
+                Ok(Value::Date(date))
+            }
+            Tag::Open("data") => {
+                let text = self.text()?;
+                self.close_tag("data")?;
+
+                let data = decode_base64(&text)
+                    .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidData, start))?;
+                Ok(Value::Data(data))
+            }
+            Tag::Open("array") => {
+                let mut array = Vec::new();
+                loop {
+                    self.skip_misc()?;
+                    if self.rest().starts_with("</") {
+                        break;
+                    }
+                    array.push(self.value(depth + 1)?);
+                }
+                self.close_tag("array")?;
+
+                Ok(Value::Array(array))
+            }
+            Tag::Open("dict") => {
+                let mut dict = BTreeMap::new();
+                loop {
+                    self.skip_misc()?;
+                    if self.rest().starts_with("</") {
+                        break;
+                    }
+
+                    let key = match self.open_tag()? {
+                        Tag::Open("key") => {
+                            let key = self.text()?;
+                            self.close_tag("key")?;
+                            key
+                        }
+                        Tag::Empty("key") => String::new(),
+                        _ => return Err(self.error(ParseErrorKind::InvalidKey)),
+                    };
+
+                    let value = self.value(depth + 1)?;
+                    dict.insert(key, value);
+                }
+                self.close_tag("dict")?;
+
+                Ok(Value::Dict(dict))
+            }
+            _ => Err(ParseError::new(ParseErrorKind::UnexpectedElement, start)),
+        }
+    }
+
+    /// Parses an opening tag at the current position.
+    fn open_tag(&mut self) -> Result<Tag<'i>, ParseError> {
+        let rest = self.rest();
+        if !rest.starts_with('<') || rest.starts_with("</") {
+            return Err(self.error(ParseErrorKind::InvalidSyntax));
+        }
+
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+        };
+
+        let (content, empty) = match rest[1..end].strip_suffix('/') {
+            Some(content) => (content, true),
+            None => (&rest[1..end], false),
+        };
+
+        // We ignore all attributes (the only one used in practice is `version`
+        // on the root element).
+        let name = content.split_ascii_whitespace().next()
+            .ok_or_else(|| self.error(ParseErrorKind::InvalidSyntax))?;
+
+        self.pos += end + 1;
+
+        if empty {
+            Ok(Tag::Empty(name))
+        } else {
+            Ok(Tag::Open(name))
+        }
+    }
+
+    /// Parses a closing tag with the given name at the current position.
+    fn close_tag(&mut self, name: &str) -> Result<(), ParseError> {
+        let rest = self.rest();
+
+        let tag = rest.strip_prefix("</")
+            .and_then(|rest| rest.strip_prefix(name))
+            .map(|rest| rest.trim_start());
+
+        match tag {
+            Some(tag) if tag.starts_with('>') => {
+                self.pos = self.input.len() - tag.len() + 1;
+                Ok(())
+            }
+            Some(_) | None if rest.is_empty() => {
+                Err(self.error(ParseErrorKind::UnexpectedEof))
+            }
+            _ => Err(self.error(ParseErrorKind::InvalidSyntax)),
+        }
+    }
+
+    /// Parses text content (with entities decoded) at the current position.
+    fn text(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+
+        loop {
+            let rest = self.rest();
+
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>")
+                    .ok_or_else(|| self.error(ParseErrorKind::UnexpectedEof))?;
+                text.push_str(&cdata[..end]);
+                self.pos += "<![CDATA[".len() + end + "]]>".len();
+                continue;
+            }
+
+            let end = match rest.find('<') {
+                Some(end) => end,
+                None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+            };
+            if end == 0 {
+                break;
+            }
+
+            let chunk = &rest[..end];
+            let chunk_start = self.pos;
+            self.pos += end;
+
+            decode_entities(chunk, &mut text)
+                .map_err(|offset| ParseError::new(ParseErrorKind::InvalidSyntax, chunk_start + offset))?;
+        }
+
+        Ok(text)
+    }
+
+    /// Skips whitespace, comments, the XML declaration and the doctype.
+    fn skip_misc(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            let terminator = if trimmed.starts_with("<?") {
+                "?>"
+            } else if trimmed.starts_with("<!--") {
+                "-->"
+            } else if trimmed.starts_with("<!DOCTYPE") {
+                ">"
+            } else {
+                return Ok(());
+            };
+
+            match trimmed.find(terminator) {
+                Some(end) => self.pos += end + terminator.len(),
+                None => return Err(self.error(ParseErrorKind::UnexpectedEof)),
+            }
+        }
+    }
+
+    /// Returns the remaining (not yet parsed) input.
+    fn rest(&self) -> &'i str {
+        &self.input[self.pos..]
+    }
+
+    /// Creates an error of the given kind at the current position.
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(kind, self.pos)
+    }
+}
+
+/// Decodes XML entities in `chunk` appending the result to `text`.
+///
+/// In case of an error, the offset of the invalid entity is returned.
+fn decode_entities(chunk: &str, text: &mut String) -> Result<(), usize> {
+    let mut rest = chunk;
+    while let Some(start) = rest.find('&') {
+        text.push_str(&rest[..start]);
+
+        let offset = chunk.len() - rest.len() + start;
+
+        let end = rest[start..].find(';')
+            .ok_or(offset)?;
+        let entity = &rest[start + 1..start + end];
+
+        let char = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse::<u32>().ok()
+                } else {
+                    None
+                };
+
+                code.and_then(char::from_u32)
+                    .ok_or(offset)?
+            }
+        };
+        text.push(char);
+
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    Ok(())
+}
+
+/// Parses an integer in decimal or hexadecimal (`0x`-prefixed) notation.
+fn parse_integer(string: &str) -> Option<i128> {
+    let (negative, string) = match string.strip_prefix('-') {
+        Some(string) => (true, string),
+        None => (false, string.strip_prefix('+').unwrap_or(string)),
+    };
+
+    let integer = match string.strip_prefix("0x").or_else(|| string.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => string.parse::<i128>().ok()?,
+    };
+
+    Some(if negative { -integer } else { integer })
+}
+
+/// Parses an ISO 8601 date of the `YYYY-MM-DDTHH:MM:SSZ` form.
+///
+/// This is the only format that property lists use [1].
+///
+/// [1]: https://www.apple.com/DTDs/PropertyList-1.0.dtd
+fn parse_date(string: &str) -> Option<std::time::SystemTime> {
+    let bytes = string.as_bytes();
+    if bytes.len() != "YYYY-MM-DDTHH:MM:SSZ".len() {
+        return None;
+    }
+    if bytes[4] != b'-' || bytes[7] != b'-' || bytes[10] != b'T' ||
+       bytes[13] != b':' || bytes[16] != b':' || bytes[19] != b'Z' {
+        return None;
+    }
+
+    let num = |range: std::ops::Range<usize>| -> Option<u32> {
+        let part = &string[range];
+        if !part.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        part.parse().ok()
+    };
+
+    let year = num(0..4)?;
+    let month = num(5..7)?;
+    let day = num(8..10)?;
+    let hour = num(11..13)?;
+    let min = num(14..16)?;
+    let sec = num(17..19)?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    if hour > 23 || min > 59 || sec > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year as i64, month, day);
+    let secs = days * 86400 + i64::from(hour * 3600 + min * 60 + sec);
+
+    let duration = std::time::Duration::from_secs(secs.unsigned_abs());
+    if secs >= 0 {
+        std::time::UNIX_EPOCH.checked_add(duration)
+    } else {
+        std::time::UNIX_EPOCH.checked_sub(duration)
+    }
+}
+
+/// Returns the number of days since the Unix epoch for the given civil date.
+///
+/// This is an implementation of the algorithm described by Howard Hinnant [1].
+///
+/// [1]: https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (i64::from(month) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// Decodes standard Base64 (ignoring whitespace).
+fn decode_base64(string: &str) -> Option<Vec<u8>> {
+    fn sextet(byte: u8) -> Option<u32> {
+        match byte {
+            b'A'..=b'Z' => Some(u32::from(byte - b'A')),
+            b'a'..=b'z' => Some(u32::from(byte - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(byte - b'0') + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let bytes = string.bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+
+    let data = match bytes.iter().position(|byte| *byte == b'=') {
+        Some(pos) => {
+            // Padding has to appear only at the end and be at most 2 bytes.
+            if bytes.len() - pos > 2 || bytes[pos..].iter().any(|byte| *byte != b'=') {
+                return None;
+            }
+            if bytes.len() % 4 != 0 {
+                return None;
+            }
+            &bytes[..pos]
+        }
+        None => &bytes[..],
+    };
+
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut acc = 0;
+        for byte in chunk {
+            acc = acc << 6 | sextet(*byte)?;
+        }
+        acc <<= 6 * (4 - chunk.len() as u32);
+
+        let decoded = acc.to_be_bytes();
+        result.extend_from_slice(&decoded[1..chunk.len()]);
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.foo</string>
+	<!-- A comment that should be ignored. -->
+	<key>ProgramArguments</key>
+	<array>
+		<string>/usr/bin/foo</string>
+		<string>--bar &amp; &lt;baz&gt; &#x41;&#66;</string>
+	</array>
+	<key>RunAtLoad</key>
+	<true/>
+	<key>KeepAlive</key>
+	<false/>
+	<key>StartInterval</key>
+	<integer>3600</integer>
+	<key>Nice</key>
+	<integer>-5</integer>
+	<key>Real</key>
+	<real>0.5</real>
+	<key>Date</key>
+	<date>2023-11-14T22:13:20Z</date>
+	<key>Data</key>
+	<data>
+	AAEC
+	</data>
+	<key>Empty</key>
+	<string></string>
+	<key>EmptyShort</key>
+	<string/>
+	<key>EmptyDict</key>
+	<dict/>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn parse_example() {
+        let value = parse(PLIST.as_bytes()).unwrap();
+
+        assert_eq!(value.get("Label").and_then(Value::as_str), Some("com.example.foo"));
+        assert_eq!(value.get("RunAtLoad").and_then(Value::as_bool), Some(true));
+        assert_eq!(value.get("KeepAlive").and_then(Value::as_bool), Some(false));
+        assert_eq!(value.get("StartInterval").and_then(Value::as_integer), Some(3600));
+        assert_eq!(value.get("Nice").and_then(Value::as_integer), Some(-5));
+        assert_eq!(value.get("Real"), Some(&Value::Real(0.5)));
+        assert_eq!(value.get("Data"), Some(&Value::Data(vec![0x00, 0x01, 0x02])));
+        assert_eq!(value.get("Empty").and_then(Value::as_str), Some(""));
+        assert_eq!(value.get("EmptyShort").and_then(Value::as_str), Some(""));
+        assert_eq!(value.get("EmptyDict"), Some(&Value::Dict(BTreeMap::new())));
+
+        let args = value.get("ProgramArguments").and_then(Value::as_array)
+            .unwrap();
+        assert_eq!(args, &[
+            Value::String(String::from("/usr/bin/foo")),
+            Value::String(String::from("--bar & <baz> AB")),
+        ]);
+
+        let date = value.get("Date").and_then(Value::as_date)
+            .unwrap();
+        assert_eq! {
+            date,
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000)
+        };
+    }
+
+    #[test]
+    fn parse_without_plist_element() {
+        let value = parse(b"<array><integer>1</integer></array>")
+            .unwrap();
+
+        assert_eq!(value, Value::Array(vec![Value::Integer(1)]));
+    }
+
+    #[test]
+    fn parse_cdata() {
+        let value = parse(b"<plist><string>foo <![CDATA[<bar>]]> baz</string></plist>")
+            .unwrap();
+
+        assert_eq!(value.as_str(), Some("foo <bar> baz"));
+    }
+
+    #[test]
+    fn parse_truncated() {
+        // We trim the input first, as a list without the trailing newline is
+        // still a valid one.
+        let plist = PLIST.trim_end();
+
+        for len in 0..plist.len() {
+            if !plist.is_char_boundary(len) {
+                continue;
+            }
+            assert!(parse(&plist.as_bytes()[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_mismatched_tag() {
+        let error = parse(b"<plist><string>foo</integer></plist>")
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ParseErrorKind::InvalidSyntax);
+    }
+
+    #[test]
+    fn parse_unknown_element() {
+        let error = parse(b"<plist><foo>bar</foo></plist>")
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ParseErrorKind::UnexpectedElement);
+    }
+
+    #[test]
+    fn parse_non_string_key() {
+        let error = parse(b"<plist><dict><integer>1</integer><true/></dict></plist>")
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ParseErrorKind::InvalidKey);
+    }
+
+    #[test]
+    fn parse_invalid_entity() {
+        let error = parse(b"<plist><string>&foo;</string></plist>")
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ParseErrorKind::InvalidSyntax);
+    }
+
+    #[test]
+    fn parse_trailing_garbage() {
+        assert!(parse(b"<plist><true/></plist><false/>").is_err());
+    }
+
+    #[test]
+    fn parse_integer_hex() {
+        assert_eq!(parse_integer("0x1F"), Some(31));
+        assert_eq!(parse_integer("-0x1F"), Some(-31));
+        assert_eq!(parse_integer("foo"), None);
+    }
+
+    #[test]
+    fn parse_date_epoch() {
+        assert_eq!(parse_date("1970-01-01T00:00:00Z"), Some(std::time::UNIX_EPOCH));
+    }
+
+    #[test]
+    fn parse_date_leap_year() {
+        assert_eq! {
+            parse_date("2024-02-29T12:00:00Z"),
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1709208000))
+        };
+    }
+
+    #[test]
+    fn parse_date_before_epoch() {
+        assert_eq! {
+            parse_date("1969-12-31T23:59:59Z"),
+            Some(std::time::UNIX_EPOCH - std::time::Duration::from_secs(1))
+        };
+    }
+
+    #[test]
+    fn parse_date_invalid() {
+        assert_eq!(parse_date("2023-13-01T00:00:00Z"), None);
+        assert_eq!(parse_date("2023-01-01 00:00:00Z"), None);
+        assert_eq!(parse_date("2023-01-01T00:00:00"), None);
+        assert_eq!(parse_date("2023-0a-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn decode_base64_padding() {
+        assert_eq!(decode_base64("Zm9vYg=="), Some(b"foob".to_vec()));
+        assert_eq!(decode_base64("Zm9vYmE="), Some(b"fooba".to_vec()));
+        assert_eq!(decode_base64("Zm9vYmFy"), Some(b"foobar".to_vec()));
+        assert_eq!(decode_base64(""), Some(Vec::new()));
+    }
+
+    #[test]
+    fn decode_base64_whitespace() {
+        assert_eq!(decode_base64(" Zm9v\n\tYmFy "), Some(b"foobar".to_vec()));
+    }
+
+    #[test]
+    fn decode_base64_invalid() {
+        assert_eq!(decode_base64("Zm9v!"), None);
+        assert_eq!(decode_base64("Z"), None);
+        assert_eq!(decode_base64("Zm=9"), None);
+    }
+}
@@ -7,28 +7,82 @@ use std::path::PathBuf;
 
 const PROTOS: &'static [&'static str] = &[
     "../../proto/rrg.proto",
+    "../../proto/rrg/admin.proto",
     "../../proto/rrg/blob.proto",
+    "../../proto/rrg/crash.proto",
     "../../proto/rrg/fs.proto",
     "../../proto/rrg/net.proto",
     "../../proto/rrg/os.proto",
+    "../../proto/rrg/replay.proto",
+    "../../proto/rrg/schedule.proto",
+    "../../proto/rrg/signing.proto",
     "../../proto/rrg/startup.proto",
     "../../proto/rrg/winreg.proto",
+    "../../proto/rrg/yara.proto",
     "../../proto/rrg/action/execute_signed_command.proto",
+    "../../proto/rrg/action/get_agent_capabilities.proto",
+    "../../proto/rrg/action/get_agent_health.proto",
+    "../../proto/rrg/action/get_auth_config.proto",
+    "../../proto/rrg/action/get_browser_artifacts.proto",
+    "../../proto/rrg/action/get_dns_config.proto",
+    "../../proto/rrg/action/get_execution_artifacts.proto",
     "../../proto/rrg/action/get_file_contents.proto",
     "../../proto/rrg/action/get_file_hash.proto",
+    "../../proto/rrg/action/get_environment.proto",
     "../../proto/rrg/action/get_file_metadata.proto",
     "../../proto/rrg/action/get_filesystem_timeline.proto",
+    "../../proto/rrg/action/get_filesystem_timeline_delta.proto",
+    "../../proto/rrg/action/get_login_history.proto",
+    "../../proto/rrg/action/get_macos_logs.proto",
+    "../../proto/rrg/action/get_network_tables.proto",
+    "../../proto/rrg/action/get_ntfs_mft.proto",
+    "../../proto/rrg/action/get_proc_snapshot.proto",
+    "../../proto/rrg/action/get_process_token.proto",
+    "../../proto/rrg/action/get_raw_device.proto",
+    "../../proto/rrg/action/get_linux_security_config.proto",
+    "../../proto/rrg/action/get_system_logs.proto",
     "../../proto/rrg/action/get_system_metadata.proto",
     "../../proto/rrg/action/get_tcp_response.proto",
+    "../../proto/rrg/action/get_usn_journal.proto",
+    "../../proto/rrg/action/get_windows_event_logs.proto",
+    "../../proto/rrg/action/get_windows_security_config.proto",
     "../../proto/rrg/action/get_winreg_value.proto",
     "../../proto/rrg/action/grep_file_contents.proto",
+    "../../proto/rrg/action/list_autoruns.proto",
     "../../proto/rrg/action/list_connections.proto",
+    "../../proto/rrg/action/list_containers.proto",
+    "../../proto/rrg/action/list_deleted_open_files.proto",
+    "../../proto/rrg/action/list_disks.proto",
     "../../proto/rrg/action/list_interfaces.proto",
+    "../../proto/rrg/action/list_kernel_modules.proto",
+    "../../proto/rrg/action/list_linux_persistence.proto",
+    "../../proto/rrg/action/list_macos_persistence.proto",
     "../../proto/rrg/action/list_mounts.proto",
+    "../../proto/rrg/action/list_named_pipes.proto",
+    "../../proto/rrg/action/list_open_files.proto",
+    "../../proto/rrg/action/list_packages.proto",
+    "../../proto/rrg/action/list_processes.proto",
+    "../../proto/rrg/action/list_scheduled_tasks.proto",
+    "../../proto/rrg/action/list_services.proto",
+    "../../proto/rrg/action/list_shadow_copies.proto",
+    "../../proto/rrg/action/list_ssh_keys.proto",
+    "../../proto/rrg/action/list_users.proto",
     "../../proto/rrg/action/list_utmp_users.proto",
+    "../../proto/rrg/action/list_winreg.proto",
+    "../../proto/rrg/action/list_winreg_hive_file.proto",
     "../../proto/rrg/action/list_winreg_keys.proto",
     "../../proto/rrg/action/list_winreg_values.proto",
+    "../../proto/rrg/action/query_osquery.proto",
     "../../proto/rrg/action/query_wmi.proto",
+    "../../proto/rrg/action/rotate_command_verification_key.proto",
+    "../../proto/rrg/action/scan_files_yara.proto",
+    "../../proto/rrg/action/scan_process_memory_yara.proto",
+    "../../proto/rrg/action/set_agent_mode.proto",
+    "../../proto/rrg/action/sweep_indicators.proto",
+    "../../proto/rrg/action/update_agent.proto",
+    "../../proto/rrg/action/update_schedule.proto",
+    "../../proto/rrg/action/verify_system_integrity.proto",
+    "../../proto/rrg/action/watch_files.proto",
 ];
 
 fn main() {
@@ -54,4 +108,23 @@ fn main() {
         .inputs(PROTOS)
         .customize(customize)
         .run().unwrap();
+
+    // The fingerprint is computed over the descriptors rather than the source
+    // files, so that changes to comments or formatting do not affect it.
+    let descriptors = protobuf_parse::Parser::new()
+        .pure()
+        .include("../../proto")
+        .inputs(PROTOS)
+        .file_descriptor_set().unwrap();
+
+    let descriptors_bytes = protobuf::Message::write_to_bytes(&descriptors)
+        .unwrap();
+
+    use sha2::Digest as _;
+    let fingerprint = sha2::Sha256::digest(&descriptors_bytes);
+
+    std::fs::write(outdir.join("fingerprint.rs"), format! {
+        "pub const FINGERPRINT: [u8; 32] = {:?};\n",
+        fingerprint.as_slice(),
+    }).unwrap();
 }
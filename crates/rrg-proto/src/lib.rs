@@ -8,6 +8,12 @@ pub mod path;
 
 include!(concat!(env!("OUT_DIR"), "/proto/mod.rs"));
 
+// SHA-256 digest of the serialized descriptors of all the compiled-in protos.
+//
+// Two builds with the same fingerprint are guaranteed to agree on all message
+// definitions, so it can be used to detect drift between agent and server.
+include!(concat!(env!("OUT_DIR"), "/fingerprint.rs"));
+
 impl From<ospect::os::Kind> for os::Type {
 
     fn from(kind: ospect::os::Kind) -> os::Type {
@@ -106,9 +112,12 @@ impl From<ospect::fs::Mount> for fs::Mount {
 
     fn from(mount: ospect::fs::Mount) -> fs::Mount {
         let mut proto = fs::Mount::default();
+        proto.set_pseudo(mount.is_pseudo());
         proto.set_name(mount.name);
         proto.set_path(mount.path.into());
         proto.set_fs_type(mount.fs_type);
+        proto.set_options(mount.options);
+        proto.set_read_only(mount.read_only);
 
         proto
     }
@@ -240,6 +249,23 @@ impl From<ospect::net::TcpState> for net::TcpState {
     }
 }
 
+impl From<ospect::net::NeighborState> for get_network_tables::NeighborState {
+
+    fn from(state: ospect::net::NeighborState) -> get_network_tables::NeighborState {
+        use ospect::net::NeighborState::*;
+        match state {
+            Unknown => get_network_tables::NeighborState::UNKNOWN,
+            Incomplete => get_network_tables::NeighborState::INCOMPLETE,
+            Reachable => get_network_tables::NeighborState::REACHABLE,
+            Stale => get_network_tables::NeighborState::STALE,
+            Delay => get_network_tables::NeighborState::DELAY,
+            Probe => get_network_tables::NeighborState::PROBE,
+            Failed => get_network_tables::NeighborState::FAILED,
+            Permanent => get_network_tables::NeighborState::PERMANENT,
+        }
+    }
+}
+
 impl From<ospect::net::TcpConnectionV4> for net::TcpConnection {
 
     fn from(conn: ospect::net::TcpConnectionV4) -> net::TcpConnection {
@@ -340,10 +366,52 @@ impl From<ospect::net::Interface> for net::Interface {
             .collect::<Vec<_>>();
         proto.set_ip_addresses(ip_addrs.into());
 
+        if let Some(index) = iface.index() {
+            proto.set_index(index);
+        }
+        if let Some(mtu) = iface.mtu() {
+            proto.set_mtu(mtu);
+        }
+        proto.set_up(iface.is_up());
+        proto.set_running(iface.is_running());
+        proto.set_loopback(iface.is_loopback());
+
+        let addrs = iface.addrs()
+            .map(|addr| net::InterfaceAddress::from(*addr))
+            .collect::<Vec<_>>();
+        proto.set_addresses(addrs);
+
+        proto
+    }
+}
+
+impl From<ospect::net::InterfaceAddr> for net::InterfaceAddress {
+
+    fn from(addr: ospect::net::InterfaceAddr) -> net::InterfaceAddress {
+        let mut proto = net::InterfaceAddress::default();
+        proto.set_ip_address(addr.ip_addr().into());
+        if let Some(prefix_len) = addr.prefix_len() {
+            proto.set_prefix_length(u32::from(prefix_len));
+        }
+        proto.set_scope(addr.scope().into());
+
         proto
     }
 }
 
+impl From<ospect::net::AddrScope> for net::AddressScope {
+
+    fn from(scope: ospect::net::AddrScope) -> net::AddressScope {
+        use ospect::net::AddrScope::*;
+
+        match scope {
+            Host => net::AddressScope::HOST,
+            Link => net::AddressScope::LINK,
+            Global => net::AddressScope::GLOBAL,
+        }
+    }
+}
+
 #[cfg(target_os = "windows")]
 impl From<::winreg::PredefinedKey> for self::winreg::PredefinedKey {
 
@@ -389,6 +457,32 @@ impl TryFrom<self::winreg::PredefinedKey> for ::winreg::PredefinedKey {
     }
 }
 
+#[cfg(target_os = "windows")]
+impl From<self::winreg::View> for ::winreg::View {
+
+    fn from(view: self::winreg::View) -> ::winreg::View {
+        use self::winreg::View::*;
+
+        match view {
+            DEFAULT => ::winreg::View::Default,
+            WOW64_64KEY => ::winreg::View::Registry64,
+            WOW64_32KEY => ::winreg::View::Registry32,
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl From<::winreg::View> for self::winreg::View {
+
+    fn from(view: ::winreg::View) -> self::winreg::View {
+        match view {
+            ::winreg::View::Default => self::winreg::View::DEFAULT,
+            ::winreg::View::Registry64 => self::winreg::View::WOW64_64KEY,
+            ::winreg::View::Registry32 => self::winreg::View::WOW64_32KEY,
+        }
+    }
+}
+
 #[cfg(target_os = "windows")]
 impl From<::winreg::Value> for self::winreg::Value {
 
@@ -429,6 +523,41 @@ impl From<::winreg::Value> for self::winreg::Value {
     }
 }
 
+impl From<::winreg::hive::Value> for self::winreg::Value {
+
+    fn from(value: ::winreg::hive::Value) -> self::winreg::Value {
+        let mut proto = self::winreg::Value::default();
+        proto.set_name(value.name);
+
+        match value.data {
+            ::winreg::hive::ValueData::None => {},
+            ::winreg::hive::ValueData::Bytes(bytes) => {
+                proto.set_bytes(bytes);
+            }
+            ::winreg::hive::ValueData::String(string) => {
+                proto.set_string(string);
+            }
+            ::winreg::hive::ValueData::ExpandString(string) => {
+                proto.set_expand_string(string);
+            }
+            ::winreg::hive::ValueData::MultiString(strings) => {
+                proto.mut_multi_string().set_values(strings);
+            }
+            ::winreg::hive::ValueData::Link(string) => {
+                proto.set_link(string);
+            }
+            ::winreg::hive::ValueData::U32(int) => {
+                proto.set_uint32(int);
+            }
+            ::winreg::hive::ValueData::U64(int) => {
+                proto.set_uint64(int);
+            }
+        }
+
+        proto
+    }
+}
+
 impl From<rrg::log::Level> for log::LevelFilter {
 
     fn from(level: rrg::log::Level) -> log::LevelFilter {
@@ -644,25 +773,24 @@ pub fn secs(time: std::time::SystemTime) -> Result<u64, TimeConversionError> {
     Ok(duration.as_secs())
 }
 
-// TODO(@panhania): Upgrade to version 3.2.0 of `protobuf` that supports
-// `From<SystemTime>` conversion of Protocol Buffers `Timestamp`.
 /// Converts [`SystemTime`] to a Protocol Buffers `Timestamp` message.
 ///
+/// Times before the Unix epoch (that can appear e.g. in timestamps parsed from
+/// corrupted files) are represented with negative seconds.
+///
 /// # Examples
 ///
 /// ```
 /// let timestamp = rrg_proto::into_timestamp(std::time::SystemTime::now());
 /// assert!(timestamp.seconds > 0);
+///
+/// let time = std::time::UNIX_EPOCH - std::time::Duration::from_millis(1500);
+/// let timestamp = rrg_proto::into_timestamp(time);
+/// assert_eq!(timestamp.seconds, -2);
+/// assert_eq!(timestamp.nanos, 500_000_000);
 /// ```
 pub fn into_timestamp(time: std::time::SystemTime) -> protobuf::well_known_types::timestamp::Timestamp {
-    let since_epoch = time.duration_since(std::time::UNIX_EPOCH)
-        .expect("pre-epoch time");
-
-    let mut proto = protobuf::well_known_types::timestamp::Timestamp::default();
-    proto.nanos = since_epoch.subsec_nanos() as i32;
-    proto.seconds = since_epoch.as_secs() as i64;
-
-    proto
+    protobuf::well_known_types::timestamp::Timestamp::from(time)
 }
 
 /// Converts a protobuf [`Duration`] message to [`std::time::Duration`].
@@ -708,6 +836,36 @@ pub fn try_from_duration(
     Ok(duration_secs + duration_nanos)
 }
 
+/// Converts a protobuf [`Timestamp`] message to [`std::time::SystemTime`].
+///
+/// Timestamps before the Unix epoch are not supported and are reported as
+/// errors (with the same kinds as negative durations).
+///
+/// [`Timestamp`]: protobuf::well_known_types::timestamp::Timestamp
+///
+/// # Examples
+///
+/// ```
+/// let mut proto = protobuf::well_known_types::timestamp::Timestamp::default();
+/// proto.seconds = 1337;
+///
+/// let time = rrg_proto::try_from_timestamp(proto)
+///     .unwrap();
+/// assert_eq!(time, std::time::UNIX_EPOCH + std::time::Duration::from_secs(1337));
+/// ```
+pub fn try_from_timestamp(
+    timestamp: protobuf::well_known_types::timestamp::Timestamp,
+) -> Result<std::time::SystemTime, ParseDurationError>
+{
+    let since_epoch = protobuf::well_known_types::duration::Duration {
+        seconds: timestamp.seconds,
+        nanos: timestamp.nanos,
+        ..Default::default()
+    };
+
+    Ok(std::time::UNIX_EPOCH + try_from_duration(since_epoch)?)
+}
+
 /// Error type for cases when parsing a protobuf [`Duration`] messages.
 ///
 /// [`Duration`]: protobuf::well_known_types::Duration
@@ -0,0 +1,11 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities shared by RRG tests and benchmarks.
+//!
+//! Nothing here is meant to be used by the agent itself: the functions are
+//! simple and panic-free but not optimized or hardened in any way.
+
+pub mod tree;
@@ -0,0 +1,104 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Builders of synthetic directory trees.
+//!
+//! All the builders create the tree inside the given (existing) root folder
+//! and return the number of created filesystem entries, i.e. the number of
+//! entries that a recursive walk of the root is expected to yield.
+
+use std::path::Path;
+
+/// Creates a flat tree with `file_count` empty files directly under `root`.
+pub fn wide(root: &Path, file_count: usize) -> std::io::Result<usize> {
+    for idx in 0..file_count {
+        std::fs::File::create(root.join(format!("file{idx}")))?;
+    }
+
+    Ok(file_count)
+}
+
+/// Creates a chain of `depth` nested folders with an empty file in each.
+pub fn deep(root: &Path, depth: usize) -> std::io::Result<usize> {
+    let mut path = root.to_path_buf();
+    for _ in 0..depth {
+        // We keep the names short so that we do not hit path length limits
+        // too quickly.
+        path.push("d");
+        std::fs::create_dir(&path)?;
+        std::fs::File::create(path.join("f"))?;
+    }
+
+    Ok(2 * depth)
+}
+
+/// Creates `dir_count` folders with `file_count` files of `file_len` bytes.
+pub fn many_small_files(
+    root: &Path,
+    dir_count: usize,
+    file_count: usize,
+    file_len: usize,
+) -> std::io::Result<usize> {
+    let content = vec![0xf0; file_len];
+
+    for dir_idx in 0..dir_count {
+        let dir = root.join(format!("dir{dir_idx}"));
+        std::fs::create_dir(&dir)?;
+
+        for file_idx in 0..file_count {
+            std::fs::write(dir.join(format!("file{file_idx}")), &content)?;
+        }
+    }
+
+    Ok(dir_count * (file_count + 1))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Counts all the entries under the given folder recursively.
+    fn count(path: &Path) -> usize {
+        std::fs::read_dir(path).unwrap()
+            .map(Result::unwrap)
+            .map(|entry| match entry.file_type().unwrap().is_dir() {
+                true => 1 + count(&entry.path()),
+                false => 1,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn wide_count() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert_eq!(wide(tempdir.path(), 42).unwrap(), 42);
+        assert_eq!(count(tempdir.path()), 42);
+    }
+
+    #[test]
+    fn deep_count() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert_eq!(deep(tempdir.path(), 16).unwrap(), 32);
+        assert_eq!(count(tempdir.path()), 32);
+    }
+
+    #[test]
+    fn many_small_files_count() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert_eq!(many_small_files(tempdir.path(), 4, 8, 16).unwrap(), 36);
+        assert_eq!(count(tempdir.path()), 36);
+
+        let metadata = std::fs::metadata(tempdir.path().join("dir3").join("file7"))
+            .unwrap();
+        assert_eq!(metadata.len(), 16);
+    }
+}
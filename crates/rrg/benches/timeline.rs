@@ -0,0 +1,132 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Benchmarks of the filesystem timeline pipeline.
+//!
+//! Throughput of all the benchmarks is reported in entries per second, so that
+//! numbers for trees of different shapes are comparable. To run them:
+//!
+//! ```text
+//! cargo bench -p rrg --bench timeline --features test-fake_session
+//! ```
+
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+/// Builds a timeline action request for the given root folder.
+fn request(root: &Path) -> rrg::Request {
+    use protobuf::Message as _;
+
+    let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+    args.mut_root().set_raw_bytes(rrg_proto::path::into_bytes(root.to_path_buf()));
+
+    let mut proto = rrg_proto::rrg::Request::new();
+    proto.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+    proto.mut_args().value = args.write_to_bytes()
+        .unwrap();
+
+    rrg::Request::try_from(proto)
+        .unwrap()
+}
+
+/// Collects entries of the given folder in the timeline wire format.
+fn entries(root: &Path) -> Vec<rrg_proto::get_filesystem_timeline::Entry> {
+    use rrg_proto::convert::FromLossy as _;
+
+    rrg::fs::walk_dir(root).unwrap()
+        .map(Result::unwrap)
+        .map(rrg_proto::get_filesystem_timeline::Entry::from_lossy)
+        .collect()
+}
+
+fn handle(c: &mut Criterion) {
+    type Builder = fn(&Path) -> std::io::Result<usize>;
+
+    let trees: [(&str, Builder); 3] = [
+        ("wide", |root| rrg_test_support::tree::wide(root, 10_000)),
+        ("deep", |root| rrg_test_support::tree::deep(root, 256)),
+        ("many_small_files", |root| {
+            rrg_test_support::tree::many_small_files(root, 100, 100, 64)
+        }),
+    ];
+
+    let mut group = c.benchmark_group("handle");
+    for (name, build) in trees {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let entry_count = build(tempdir.path())
+            .unwrap();
+
+        group.throughput(Throughput::Elements(entry_count as u64));
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                rrg::session::FakeSession::new,
+                |mut session| {
+                    rrg::action::dispatch(&mut session, request(tempdir.path()))
+                        .unwrap();
+                    session
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn from_lossy(c: &mut Criterion) {
+    use rrg_proto::convert::FromLossy as _;
+
+    let tempdir = tempfile::tempdir()
+        .unwrap();
+    rrg_test_support::tree::many_small_files(tempdir.path(), 100, 100, 64)
+        .unwrap();
+
+    let entries = rrg::fs::walk_dir(tempdir.path()).unwrap()
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("from_lossy");
+    group.throughput(Throughput::Elements(entries.len() as u64));
+    group.bench_function("many_small_files", |b| {
+        b.iter_batched(
+            || entries.iter().map(|entry| rrg::fs::Entry {
+                path: entry.path.clone(),
+                metadata: entry.metadata.clone(),
+            }).collect::<Vec<_>>(),
+            |entries| {
+                entries.into_iter()
+                    .map(rrg_proto::get_filesystem_timeline::Entry::from_lossy)
+                    .collect::<Vec<_>>()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn gzchunked_encode(c: &mut Criterion) {
+    let tempdir = tempfile::tempdir()
+        .unwrap();
+    rrg_test_support::tree::many_small_files(tempdir.path(), 100, 100, 64)
+        .unwrap();
+
+    let entries = entries(tempdir.path());
+
+    let mut group = c.benchmark_group("gzchunked_encode");
+    group.throughput(Throughput::Elements(entries.len() as u64));
+    group.bench_function("many_small_files", |b| {
+        b.iter(|| {
+            rrg::gzchunked::encode(entries.iter().cloned())
+                .map(Result::unwrap)
+                .map(|part| part.len())
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, handle, from_lossy, gzchunked_encode);
+criterion_main!(benches);
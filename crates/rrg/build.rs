@@ -0,0 +1,89 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+fn main() {
+    // Reproducible builds can fix the build time through the standard variable
+    // [1], otherwise we use the current time.
+    //
+    // [1]: https://reproducible-builds.org/docs/source-date-epoch/
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let build_time = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(build_time) => build_time,
+        Err(_) => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("system time before the Unix epoch")
+            .as_secs()
+            .to_string(),
+    };
+
+    println!("cargo:rustc-env=RRG_BUILD_TIME={build_time}");
+
+    // Builds from source archives have no Git metadata available, so we allow
+    // the commit to be specified explicitly. If it is not known at all, the
+    // variable is not set and the commit is simply not reported.
+    println!("cargo:rerun-if-env-changed=RRG_GIT_COMMIT");
+
+    let commit = match std::env::var("RRG_GIT_COMMIT") {
+        Ok(commit) => Some(commit),
+        Err(_) => git_commit(),
+    };
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=RRG_GIT_COMMIT={commit}");
+    }
+
+    let rustc = std::env::var("RUSTC")
+        .unwrap_or_else(|_| String::from("rustc"));
+    let rustc_version = std::process::Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_default();
+
+    println!("cargo:rustc-env=RRG_RUSTC_VERSION={rustc_version}");
+
+    let target = std::env::var("TARGET")
+        .expect("no target");
+
+    println!("cargo:rustc-env=RRG_TARGET={target}");
+
+    // Unlike `CARGO_FEATURE_*` variables, this one preserves the original names
+    // of the features (with dashes and lowercase letters).
+    let features = std::env::var("CARGO_CFG_FEATURE")
+        .unwrap_or_default();
+
+    println!("cargo:rustc-env=RRG_FEATURES={features}");
+}
+
+/// Returns the hash of the Git commit the agent is built from (if available).
+fn git_commit() -> Option<String> {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+    };
+
+    // The build script has to be rerun whenever the commit changes: both when
+    // a different branch is checked out and when a new commit is made on the
+    // current one.
+    let head_path = git(&["rev-parse", "--git-path", "HEAD"])?;
+    println!("cargo:rerun-if-changed={head_path}");
+    if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        if let Some(branch_path) = git(&["rev-parse", "--git-path", &branch]) {
+            println!("cargo:rerun-if-changed={branch_path}");
+        }
+    }
+
+    git(&["rev-parse", "HEAD"])
+}
@@ -33,6 +33,9 @@ pub mod get_filesystem_timeline;
 #[cfg(feature = "action-get_tcp_response")]
 pub mod get_tcp_response;
 
+#[cfg(feature = "action-list_processes")]
+pub mod list_processes;
+
 #[cfg(feature = "action-list_connections")]
 pub mod list_connections;
 
@@ -60,6 +63,147 @@ pub mod query_wmi;
 #[cfg(feature = "action-execute_signed_command")]
 pub mod execute_signed_command;
 
+#[cfg(feature = "action-list_users")]
+pub mod list_users;
+
+#[cfg(feature = "action-list_packages")]
+pub mod list_packages;
+
+#[cfg(feature = "action-list_scheduled_tasks")]
+pub mod list_scheduled_tasks;
+
+#[cfg(feature = "action-list_services")]
+pub mod list_services;
+
+#[cfg(feature = "action-get_system_logs")]
+pub mod get_system_logs;
+
+#[cfg(feature = "action-get_windows_event_logs")]
+pub mod get_windows_event_logs;
+
+#[cfg(feature = "action-list_winreg")]
+pub mod list_winreg;
+
+#[cfg(feature = "action-list_winreg_hive_file")]
+pub mod list_winreg_hive_file;
+
+#[cfg(feature = "action-list_named_pipes")]
+pub mod list_named_pipes;
+
+#[cfg(feature = "action-get_execution_artifacts")]
+pub mod get_execution_artifacts;
+
+#[cfg(feature = "action-list_macos_persistence")]
+pub mod list_macos_persistence;
+
+#[cfg(feature = "action-get_macos_logs")]
+pub mod get_macos_logs;
+
+#[cfg(feature = "action-get_auth_config")]
+pub mod get_auth_config;
+
+#[cfg(feature = "action-list_ssh_keys")]
+pub mod list_ssh_keys;
+
+#[cfg(feature = "action-list_kernel_modules")]
+pub mod list_kernel_modules;
+
+#[cfg(feature = "action-get_network_tables")]
+pub mod get_network_tables;
+
+#[cfg(feature = "action-get_dns_config")]
+pub mod get_dns_config;
+
+#[cfg(feature = "action-scan_files_yara")]
+pub mod scan_files_yara;
+
+#[cfg(feature = "action-scan_process_memory_yara")]
+pub mod scan_process_memory_yara;
+
+#[cfg(feature = "action-query_osquery")]
+pub mod query_osquery;
+
+#[cfg(feature = "action-update_agent")]
+pub mod update_agent;
+
+#[cfg(feature = "action-rotate_command_verification_key")]
+pub mod rotate_command_verification_key;
+
+#[cfg(feature = "action-get_agent_health")]
+pub mod get_agent_health;
+
+#[cfg(feature = "action-get_ntfs_mft")]
+pub mod get_ntfs_mft;
+
+#[cfg(feature = "action-get_usn_journal")]
+pub mod get_usn_journal;
+
+#[cfg(feature = "action-list_shadow_copies")]
+pub mod list_shadow_copies;
+
+#[cfg(feature = "action-get_proc_snapshot")]
+pub mod get_proc_snapshot;
+
+#[cfg(feature = "action-list_containers")]
+pub mod list_containers;
+
+#[cfg(feature = "action-get_agent_capabilities")]
+pub mod get_agent_capabilities;
+
+#[cfg(feature = "action-update_schedule")]
+pub mod update_schedule;
+
+#[cfg(feature = "action-list_open_files")]
+pub mod list_open_files;
+
+#[cfg(feature = "action-get_browser_artifacts")]
+pub mod get_browser_artifacts;
+
+#[cfg(feature = "action-set_agent_mode")]
+pub mod set_agent_mode;
+
+#[cfg(feature = "action-list_deleted_open_files")]
+pub mod list_deleted_open_files;
+
+#[cfg(feature = "action-verify_system_integrity")]
+pub mod verify_system_integrity;
+
+#[cfg(feature = "action-watch_files")]
+pub mod watch_files;
+
+#[cfg(feature = "action-get_process_token")]
+pub mod get_process_token;
+
+#[cfg(feature = "action-list_disks")]
+pub mod list_disks;
+
+#[cfg(feature = "action-get_raw_device")]
+pub mod get_raw_device;
+
+#[cfg(feature = "action-list_autoruns")]
+pub mod list_autoruns;
+
+#[cfg(feature = "action-list_linux_persistence")]
+pub mod list_linux_persistence;
+
+#[cfg(feature = "action-get_filesystem_timeline_delta")]
+pub mod get_filesystem_timeline_delta;
+
+#[cfg(feature = "action-get_windows_security_config")]
+pub mod get_windows_security_config;
+
+#[cfg(feature = "action-get_linux_security_config")]
+pub mod get_linux_security_config;
+
+#[cfg(feature = "action-sweep_indicators")]
+pub mod sweep_indicators;
+
+#[cfg(feature = "action-get_environment")]
+pub mod get_environment;
+
+#[cfg(feature = "action-get_login_history")]
+pub mod get_login_history;
+
 use log::info;
 
 /// Dispatches the given `request` to an appropriate action handler.
@@ -85,6 +229,15 @@ where
 
     info!("dispatching request '{request_id}': {action}");
 
+    // We check the policy before even looking at the arguments, so that denied
+    // actions are not exposed to any (potentially malicious) input.
+    crate::policy::check_action(session.args(), action)?;
+
+    // Handlers not aware of dry runs would execute the request for real.
+    if session.is_dry_run() && !supports_dry_run(action) {
+        return Err(crate::session::Error::unsupported_dry_run(action));
+    }
+
     let result = match request.action() {
         #[cfg(feature = "action-get_system_metadata")]
         GetSystemMetadata => {
@@ -110,6 +263,10 @@ where
         GetTcpResponse => {
             handle(session, request, self::get_tcp_response::handle)
         }
+        #[cfg(feature = "action-list_processes")]
+        ListProcesses => {
+            handle(session, request, self::list_processes::handle)
+        }
         #[cfg(feature = "action-list_connections")]
         ListConnections => {
             handle(session, request, self::list_connections::handle)
@@ -146,6 +303,194 @@ where
         ExecuteSignedCommand => {
             handle(session, request, self::execute_signed_command::handle)
         }
+        #[cfg(feature = "action-list_users")]
+        ListUsers => {
+            handle(session, request, self::list_users::handle)
+        }
+        #[cfg(feature = "action-list_packages")]
+        ListPackages => {
+            handle(session, request, self::list_packages::handle)
+        }
+        #[cfg(feature = "action-list_scheduled_tasks")]
+        ListScheduledTasks => {
+            handle(session, request, self::list_scheduled_tasks::handle)
+        }
+        #[cfg(feature = "action-list_services")]
+        ListServices => {
+            handle(session, request, self::list_services::handle)
+        }
+        #[cfg(feature = "action-get_system_logs")]
+        GetSystemLogs => {
+            handle(session, request, self::get_system_logs::handle)
+        }
+        #[cfg(feature = "action-get_windows_event_logs")]
+        GetWindowsEventLogs => {
+            handle(session, request, self::get_windows_event_logs::handle)
+        }
+        #[cfg(feature = "action-list_winreg")]
+        ListWinreg => {
+            handle(session, request, self::list_winreg::handle)
+        }
+        #[cfg(feature = "action-list_winreg_hive_file")]
+        ListWinregHiveFile => {
+            handle(session, request, self::list_winreg_hive_file::handle)
+        }
+        #[cfg(feature = "action-list_named_pipes")]
+        ListNamedPipes => {
+            handle(session, request, self::list_named_pipes::handle)
+        }
+        #[cfg(feature = "action-get_execution_artifacts")]
+        GetExecutionArtifacts => {
+            handle(session, request, self::get_execution_artifacts::handle)
+        }
+        #[cfg(feature = "action-list_macos_persistence")]
+        ListMacosPersistence => {
+            handle(session, request, self::list_macos_persistence::handle)
+        }
+        #[cfg(feature = "action-get_macos_logs")]
+        GetMacosLogs => {
+            handle(session, request, self::get_macos_logs::handle)
+        }
+        #[cfg(feature = "action-get_auth_config")]
+        GetAuthConfig => {
+            handle(session, request, self::get_auth_config::handle)
+        }
+        #[cfg(feature = "action-list_ssh_keys")]
+        ListSshKeys => {
+            handle(session, request, self::list_ssh_keys::handle)
+        }
+        #[cfg(feature = "action-list_kernel_modules")]
+        ListKernelModules => {
+            handle(session, request, self::list_kernel_modules::handle)
+        }
+        #[cfg(feature = "action-get_network_tables")]
+        GetNetworkTables => {
+            handle(session, request, self::get_network_tables::handle)
+        }
+        #[cfg(feature = "action-get_dns_config")]
+        GetDnsConfig => {
+            handle(session, request, self::get_dns_config::handle)
+        }
+        #[cfg(feature = "action-scan_files_yara")]
+        ScanFilesYara => {
+            handle(session, request, self::scan_files_yara::handle)
+        }
+        #[cfg(feature = "action-scan_process_memory_yara")]
+        ScanProcessMemoryYara => {
+            handle(session, request, self::scan_process_memory_yara::handle)
+        }
+        #[cfg(feature = "action-query_osquery")]
+        QueryOsquery => {
+            handle(session, request, self::query_osquery::handle)
+        }
+        #[cfg(feature = "action-update_agent")]
+        UpdateAgent => {
+            handle(session, request, self::update_agent::handle)
+        }
+        #[cfg(feature = "action-rotate_command_verification_key")]
+        RotateCommandVerificationKey => {
+            handle(session, request, self::rotate_command_verification_key::handle)
+        }
+        #[cfg(feature = "action-get_agent_health")]
+        GetAgentHealth => {
+            handle(session, request, self::get_agent_health::handle)
+        }
+        #[cfg(feature = "action-get_ntfs_mft")]
+        GetNtfsMft => {
+            handle(session, request, self::get_ntfs_mft::handle)
+        }
+        #[cfg(feature = "action-get_usn_journal")]
+        GetUsnJournal => {
+            handle(session, request, self::get_usn_journal::handle)
+        }
+        #[cfg(feature = "action-list_shadow_copies")]
+        ListShadowCopies => {
+            handle(session, request, self::list_shadow_copies::handle)
+        }
+        #[cfg(feature = "action-get_proc_snapshot")]
+        GetProcSnapshot => {
+            handle(session, request, self::get_proc_snapshot::handle)
+        }
+        #[cfg(feature = "action-list_containers")]
+        ListContainers => {
+            handle(session, request, self::list_containers::handle)
+        }
+        #[cfg(feature = "action-get_agent_capabilities")]
+        GetAgentCapabilities => {
+            handle(session, request, self::get_agent_capabilities::handle)
+        }
+        #[cfg(feature = "action-update_schedule")]
+        UpdateSchedule => {
+            handle(session, request, self::update_schedule::handle)
+        }
+        #[cfg(feature = "action-list_open_files")]
+        ListOpenFiles => {
+            handle(session, request, self::list_open_files::handle)
+        }
+        #[cfg(feature = "action-get_browser_artifacts")]
+        GetBrowserArtifacts => {
+            handle(session, request, self::get_browser_artifacts::handle)
+        }
+        #[cfg(feature = "action-set_agent_mode")]
+        SetAgentMode => {
+            handle(session, request, self::set_agent_mode::handle)
+        }
+        #[cfg(feature = "action-list_deleted_open_files")]
+        ListDeletedOpenFiles => {
+            handle(session, request, self::list_deleted_open_files::handle)
+        }
+        #[cfg(feature = "action-verify_system_integrity")]
+        VerifySystemIntegrity => {
+            handle(session, request, self::verify_system_integrity::handle)
+        }
+        #[cfg(feature = "action-watch_files")]
+        WatchFiles => {
+            handle(session, request, self::watch_files::handle)
+        }
+        #[cfg(feature = "action-get_process_token")]
+        GetProcessToken => {
+            handle(session, request, self::get_process_token::handle)
+        }
+        #[cfg(feature = "action-list_disks")]
+        ListDisks => {
+            handle(session, request, self::list_disks::handle)
+        }
+        #[cfg(feature = "action-get_raw_device")]
+        GetRawDevice => {
+            handle(session, request, self::get_raw_device::handle)
+        }
+        #[cfg(feature = "action-list_autoruns")]
+        ListAutoruns => {
+            handle(session, request, self::list_autoruns::handle)
+        }
+        #[cfg(feature = "action-list_linux_persistence")]
+        ListLinuxPersistence => {
+            handle(session, request, self::list_linux_persistence::handle)
+        }
+        #[cfg(feature = "action-get_filesystem_timeline_delta")]
+        GetFilesystemTimelineDelta => {
+            handle(session, request, self::get_filesystem_timeline_delta::handle)
+        }
+        #[cfg(feature = "action-get_windows_security_config")]
+        GetWindowsSecurityConfig => {
+            handle(session, request, self::get_windows_security_config::handle)
+        }
+        #[cfg(feature = "action-get_linux_security_config")]
+        GetLinuxSecurityConfig => {
+            handle(session, request, self::get_linux_security_config::handle)
+        }
+        #[cfg(feature = "action-sweep_indicators")]
+        SweepIndicators => {
+            handle(session, request, self::sweep_indicators::handle)
+        }
+        #[cfg(feature = "action-get_environment")]
+        GetEnvironment => {
+            handle(session, request, self::get_environment::handle)
+        }
+        #[cfg(feature = "action-get_login_history")]
+        GetLoginHistory => {
+            handle(session, request, self::get_login_history::handle)
+        }
         // We allow `unreachable_patterns` because otherwise we get a warning if
         // we compile with all the actions enabled.
         #[allow(unreachable_patterns)]
@@ -159,6 +504,188 @@ where
     result
 }
 
+/// Returns the version of arguments of the given action supported by the agent.
+///
+/// The version of an action should be bumped every time its arguments gain a
+/// new field whose meaning would be lost if the field was silently dropped by
+/// an agent that does not know about it. The server can then declare a minimum
+/// version in the request and agents that are too old reject it instead of
+/// executing the action with only some of the arguments.
+///
+/// `None` is returned if the action is not supported by the agent at all.
+pub fn version(action: crate::request::Action) -> Option<u32> {
+    use crate::request::Action::*;
+
+    // Every action supported by `dispatch` should have a version here.
+    match action {
+        #[cfg(feature = "action-get_system_metadata")]
+        GetSystemMetadata => Some(1),
+        #[cfg(feature = "action-get_file_metadata")]
+        GetFileMetadata => Some(1),
+        #[cfg(feature = "action-get_file_contents")]
+        GetFileContents => Some(1),
+        #[cfg(feature = "action-grep_file_contents")]
+        GrepFileContents => Some(1),
+        #[cfg(feature = "action-get_filesystem_timeline")]
+        GetFilesystemTimeline => Some(2),
+        #[cfg(feature = "action-get_tcp_response")]
+        GetTcpResponse => Some(1),
+        #[cfg(feature = "action-list_processes")]
+        ListProcesses => Some(1),
+        #[cfg(feature = "action-list_connections")]
+        ListConnections => Some(1),
+        #[cfg(feature = "action-list_interfaces")]
+        ListInterfaces => Some(1),
+        #[cfg(feature = "action-list_mounts")]
+        ListMounts => Some(1),
+        #[cfg(feature = "action-list_utmp_users")]
+        ListUtmpUsers => Some(1),
+        #[cfg(feature = "action-get_winreg_value")]
+        GetWinregValue => Some(1),
+        #[cfg(feature = "action-list_winreg_values")]
+        ListWinregValues => Some(1),
+        #[cfg(feature = "action-list_winreg_keys")]
+        ListWinregKeys => Some(1),
+        #[cfg(feature = "action-query_wmi")]
+        QueryWmi => Some(1),
+        #[cfg(feature = "action-execute_signed_command")]
+        ExecuteSignedCommand => Some(1),
+        #[cfg(feature = "action-list_users")]
+        ListUsers => Some(1),
+        #[cfg(feature = "action-list_packages")]
+        ListPackages => Some(1),
+        #[cfg(feature = "action-list_scheduled_tasks")]
+        ListScheduledTasks => Some(1),
+        #[cfg(feature = "action-list_services")]
+        ListServices => Some(1),
+        #[cfg(feature = "action-get_system_logs")]
+        GetSystemLogs => Some(1),
+        #[cfg(feature = "action-get_windows_event_logs")]
+        GetWindowsEventLogs => Some(1),
+        #[cfg(feature = "action-list_winreg")]
+        ListWinreg => Some(1),
+        #[cfg(feature = "action-list_winreg_hive_file")]
+        ListWinregHiveFile => Some(1),
+        #[cfg(feature = "action-list_named_pipes")]
+        ListNamedPipes => Some(1),
+        #[cfg(feature = "action-get_execution_artifacts")]
+        GetExecutionArtifacts => Some(1),
+        #[cfg(feature = "action-list_macos_persistence")]
+        ListMacosPersistence => Some(1),
+        #[cfg(feature = "action-get_macos_logs")]
+        GetMacosLogs => Some(1),
+        #[cfg(feature = "action-get_auth_config")]
+        GetAuthConfig => Some(1),
+        #[cfg(feature = "action-list_ssh_keys")]
+        ListSshKeys => Some(1),
+        #[cfg(feature = "action-list_kernel_modules")]
+        ListKernelModules => Some(1),
+        #[cfg(feature = "action-get_network_tables")]
+        GetNetworkTables => Some(1),
+        #[cfg(feature = "action-get_dns_config")]
+        GetDnsConfig => Some(1),
+        #[cfg(feature = "action-scan_files_yara")]
+        ScanFilesYara => Some(1),
+        #[cfg(feature = "action-scan_process_memory_yara")]
+        ScanProcessMemoryYara => Some(1),
+        #[cfg(feature = "action-query_osquery")]
+        QueryOsquery => Some(1),
+        #[cfg(feature = "action-update_agent")]
+        UpdateAgent => Some(1),
+        #[cfg(feature = "action-rotate_command_verification_key")]
+        RotateCommandVerificationKey => Some(1),
+        #[cfg(feature = "action-get_agent_health")]
+        GetAgentHealth => Some(1),
+        #[cfg(feature = "action-get_ntfs_mft")]
+        GetNtfsMft => Some(1),
+        #[cfg(feature = "action-get_usn_journal")]
+        GetUsnJournal => Some(1),
+        #[cfg(feature = "action-list_shadow_copies")]
+        ListShadowCopies => Some(1),
+        #[cfg(feature = "action-get_proc_snapshot")]
+        GetProcSnapshot => Some(1),
+        #[cfg(feature = "action-list_containers")]
+        ListContainers => Some(1),
+        #[cfg(feature = "action-get_agent_capabilities")]
+        GetAgentCapabilities => Some(1),
+        #[cfg(feature = "action-update_schedule")]
+        UpdateSchedule => Some(1),
+        #[cfg(feature = "action-list_open_files")]
+        ListOpenFiles => Some(1),
+        #[cfg(feature = "action-get_browser_artifacts")]
+        GetBrowserArtifacts => Some(1),
+        #[cfg(feature = "action-set_agent_mode")]
+        SetAgentMode => Some(1),
+        #[cfg(feature = "action-list_deleted_open_files")]
+        ListDeletedOpenFiles => Some(1),
+        #[cfg(feature = "action-verify_system_integrity")]
+        VerifySystemIntegrity => Some(1),
+        #[cfg(feature = "action-watch_files")]
+        WatchFiles => Some(1),
+        #[cfg(feature = "action-get_process_token")]
+        GetProcessToken => Some(1),
+        #[cfg(feature = "action-list_disks")]
+        ListDisks => Some(1),
+        #[cfg(feature = "action-get_raw_device")]
+        GetRawDevice => Some(1),
+        #[cfg(feature = "action-list_autoruns")]
+        ListAutoruns => Some(1),
+        #[cfg(feature = "action-list_linux_persistence")]
+        ListLinuxPersistence => Some(1),
+        #[cfg(feature = "action-get_filesystem_timeline_delta")]
+        GetFilesystemTimelineDelta => Some(1),
+        #[cfg(feature = "action-get_windows_security_config")]
+        GetWindowsSecurityConfig => Some(1),
+        #[cfg(feature = "action-get_linux_security_config")]
+        GetLinuxSecurityConfig => Some(1),
+        #[cfg(feature = "action-sweep_indicators")]
+        SweepIndicators => Some(1),
+        #[cfg(feature = "action-get_environment")]
+        GetEnvironment => Some(1),
+        #[cfg(feature = "action-get_login_history")]
+        GetLoginHistory => Some(1),
+        // We allow `unreachable_patterns` because otherwise we get a warning if
+        // we compile with all the actions enabled.
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Checks whether the given action supports being executed as a dry run.
+///
+/// Handlers of such actions check [`Session::is_dry_run`] and if it is set, they
+/// only validate the arguments and reply with a [`crate::dry_run::Estimate`] of
+/// the cost of the request. Dry runs of other actions are rejected.
+///
+/// [`Session::is_dry_run`]: crate::session::Session::is_dry_run
+pub fn supports_dry_run(action: crate::request::Action) -> bool {
+    use crate::request::Action::*;
+
+    match action {
+        #[cfg(feature = "action-get_file_contents")]
+        GetFileContents => true,
+        #[cfg(feature = "action-get_filesystem_timeline")]
+        GetFilesystemTimeline => true,
+        #[cfg(feature = "action-scan_files_yara")]
+        ScanFilesYara => true,
+        // See the comment in `dispatch` on `unreachable_patterns`.
+        #[allow(unreachable_patterns)]
+        _ => false,
+    }
+}
+
+/// Returns versions of arguments of all the actions supported by the agent.
+///
+/// See [`version`] for more details.
+pub fn versions() -> Vec<(crate::request::Action, u32)> {
+    use protobuf::Enum as _;
+
+    rrg_proto::rrg::Action::VALUES.iter()
+        .filter_map(|action| crate::request::Action::try_from(*action).ok())
+        .filter_map(|action| Some((action, version(action)?)))
+        .collect()
+}
+
 /// Handles a `request` using the specified `handler`.
 ///
 /// This method will attempt to interpret request arguments for the specific
@@ -167,12 +694,214 @@ where
 /// # Errors
 ///
 /// This function will return an error if the request arguments cannot be parsed
-/// for the specific action or if the action execution fails.
+/// for the specific action, if they refer to paths that the policy does not
+/// allow to access, or if the action execution fails.
 fn handle<S, A, H>(session: &mut S, request: crate::Request, handler: H) -> crate::session::Result<()>
 where
     S: crate::session::Session,
     A: crate::request::Args,
     H: FnOnce(&mut S, A) -> crate::session::Result<()>,
 {
-    Ok(handler(session, request.args()?)?)
+    let action = request.action();
+    let args = request.args::<A>()?;
+
+    for path in args.paths() {
+        crate::policy::check_path(session.args(), action, path)?;
+    }
+
+    Ok(handler(session, args)?)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn request<M>(action: rrg_proto::rrg::Action, args: M) -> crate::Request
+    where
+        M: protobuf::Message,
+    {
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_action(action);
+        proto.mut_args().value = args.write_to_bytes()
+            .unwrap();
+
+        crate::Request::try_from(proto)
+            .unwrap()
+    }
+
+    fn session(
+        allowed_actions: Vec<crate::request::Action>,
+        denied_actions: Vec<crate::request::Action>,
+        denied_paths: Vec<crate::policy::PathRule>,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            allowed_actions,
+            denied_actions,
+            denied_paths,
+            ..Default::default()
+        })
+    }
+
+    #[cfg(all(
+        feature = "action-get_filesystem_timeline",
+        feature = "action-get_system_metadata",
+    ))]
+    #[test]
+    fn dispatch_denied_action() {
+        use crate::request::Action;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = session(vec![], vec![Action::GetFilesystemTimeline], vec![]);
+
+        let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+        args.set_root(tempdir.path().to_path_buf().into());
+
+        let error = dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE,
+            args,
+        )).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::PolicyDenied);
+        assert_eq!(session.reply_count(), 0);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+
+        dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_SYSTEM_METADATA,
+            protobuf::well_known_types::empty::Empty::new(),
+        )).unwrap();
+        assert_eq!(session.reply_count(), 1);
+    }
+
+    #[cfg(all(
+        feature = "action-get_filesystem_timeline",
+        feature = "action-get_system_metadata",
+    ))]
+    #[test]
+    fn dispatch_dry_run() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = session(vec![], vec![], vec![]);
+        session.set_dry_run();
+
+        let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+        args.set_root(tempdir.path().to_path_buf().into());
+
+        dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE,
+            args,
+        )).unwrap();
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+
+        let error = dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_SYSTEM_METADATA,
+            protobuf::well_known_types::empty::Empty::new(),
+        )).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::UnsupportedAction);
+        assert_eq!(error.code(), crate::session::ErrorCode::Unsupported);
+        assert_eq!(session.reply_count(), 1);
+    }
+
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[test]
+    fn version_get_filesystem_timeline() {
+        // This is pinned so that changes to the timeline arguments come with
+        // a deliberate decision about bumping the version.
+        assert_eq!(version(crate::request::Action::GetFilesystemTimeline), Some(2));
+    }
+
+    #[test]
+    fn versions_all_supported() {
+        for (action, action_version) in versions() {
+            assert_eq!(version(action), Some(action_version));
+        }
+    }
+
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[test]
+    fn dispatch_denied_action_invalid_args() {
+        use crate::request::Action;
+
+        let mut session = session(vec![], vec![Action::GetFilesystemTimeline], vec![]);
+
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+        proto.mut_args().value = b"\xff\xff\xff".to_vec();
+
+        let request = crate::Request::try_from(proto)
+            .unwrap();
+
+        // Arguments are not even parsed, so the policy error takes precedence.
+        let error = dispatch(&mut session, request)
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::PolicyDenied);
+    }
+
+    #[cfg(all(
+        feature = "action-get_filesystem_timeline",
+        feature = "action-get_system_metadata",
+    ))]
+    #[test]
+    fn dispatch_not_allowed_action() {
+        use crate::request::Action;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = session(vec![Action::GetSystemMetadata], vec![], vec![]);
+
+        let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+        args.set_root(tempdir.path().to_path_buf().into());
+
+        let error = dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE,
+            args,
+        )).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::PolicyDenied);
+
+        dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_SYSTEM_METADATA,
+            protobuf::well_known_types::empty::Empty::new(),
+        )).unwrap();
+        assert_eq!(session.reply_count(), 1);
+    }
+
+    #[cfg(feature = "action-get_file_metadata")]
+    #[test]
+    fn dispatch_denied_path() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::write(tempdir_path.join("secret"), b"")
+            .unwrap();
+        std::fs::write(tempdir_path.join("public"), b"")
+            .unwrap();
+
+        let rule = format!("get_file_metadata={}", tempdir_path.join("secret").display());
+        let mut session = session(vec![], vec![], vec![rule.parse().unwrap()]);
+
+        let mut args = rrg_proto::get_file_metadata::Args::new();
+        args.set_path(tempdir_path.join("secret").into());
+
+        let error = dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_FILE_METADATA,
+            args,
+        )).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::PolicyDenied);
+        assert_eq!(session.reply_count(), 0);
+
+        let mut args = rrg_proto::get_file_metadata::Args::new();
+        args.set_path(tempdir_path.join("public").into());
+
+        dispatch(&mut session, request(
+            rrg_proto::rrg::Action::GET_FILE_METADATA,
+            args,
+        )).unwrap();
+        assert_eq!(session.reply_count(), 1);
+    }
 }
@@ -12,6 +12,11 @@ const MAX_STDERR_SIZE: usize = 512 * 1024; // 512 KiB.
 
 const COMMAND_EXECUTION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
 
+/// Period of time within which the number of executed commands is limited.
+const RATE_LIMIT_PERIOD: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+mod audit;
+
 /// Arguments of the `execute_signed_command` action.
 pub struct Args {
     raw_command: Vec<u8>,
@@ -24,12 +29,16 @@ pub struct Args {
     stdin: Vec<u8>,
     ed25519_signature: ed25519_dalek::Signature,
     timeout: std::time::Duration,
+    /// Identity to run the command as (if different than the agent one).
+    run_as: Option<crate::run_as::RunAs>,
 }
 
 /// Result of the `execute_signed_command` action.
 pub struct Item {
     /// Exit status of the command subprocess.
     exit_status: std::process::ExitStatus,
+    /// Time it took the command subprocess to finish (or to be killed).
+    runtime: std::time::Duration,
     /// Standard output of the command executiom.
     stdout: Vec<u8>,
     /// Wheather standard output is truncated.
@@ -65,29 +74,139 @@ impl std::fmt::Display for CommandExecutionError {
 
 impl std::error::Error for CommandExecutionError {}
 
+/// An error indicating that the rate limit is set without an audit log.
+#[derive(Debug)]
+struct MissingAuditLogError;
+
+impl std::fmt::Display for MissingAuditLogError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing audit log to enforce the command rate limit")
+    }
+}
+
+impl std::error::Error for MissingAuditLogError {}
+
+/// An error indicating that too many commands were executed recently.
+#[derive(Debug)]
+struct RateLimitError {
+    /// Maximum number of commands allowed to execute within the period.
+    limit: u32,
+}
+
+impl std::fmt::Display for RateLimitError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "executed {} commands within the last {}",
+            self.limit,
+            humantime::format_duration(RATE_LIMIT_PERIOD),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
 /// Handles invocations of the `execute_signed_command` action.
 pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
-    use std::io::{Read as _, Write as _};
     use crate::request::ParseArgsError;
 
-    match session.args().command_verification_key {
-        Some(key) => key
-            .verify_strict(&args.raw_command, &args.ed25519_signature)
-            .map_err(|error| ParseArgsError::invalid_field("raw_command", error))?,
-        None => return Err(crate::session::Error::action(MissingCommandVerificationKeyError)),
+    let keys = match &session.args().command_verification_key_store {
+        Some(path) => {
+            crate::signing::KeySet::load(session.args().command_verification_key, path)
+                .map_err(crate::session::Error::action)?
+        }
+        None => crate::signing::KeySet::new(session.args().command_verification_key),
     };
 
-    let mut command_process = std::process::Command::new(&args.path)
+    let now = std::time::SystemTime::now();
+    if keys.trusted_keys(now).next().is_none() {
+        return Err(crate::session::Error::action(MissingCommandVerificationKeyError));
+    }
+    let signer = keys.verify(&args.raw_command, &args.ed25519_signature, now)
+        .map_err(|error| ParseArgsError::invalid_field("raw_command", error))?;
+
+    let mut audit_log = match &session.args().command_audit_log {
+        Some(path) => Some(audit::Log::open(path).map_err(crate::session::Error::action)?),
+        None => None,
+    };
+
+    // The number of executions is determined from the audit log, so that the
+    // limit cannot be circumvented by restarting the agent.
+    if let Some(limit) = session.args().command_rate_limit {
+        let Some(audit_log) = &audit_log else {
+            return Err(crate::session::Error::action_unavailable(MissingAuditLogError));
+        };
+
+        let since = now.checked_sub(RATE_LIMIT_PERIOD)
+            .unwrap_or(std::time::UNIX_EPOCH);
+        if audit_log.started_since(since) >= limit as usize {
+            return Err(crate::session::Error::rate_limited(RateLimitError { limit }));
+        }
+    }
+
+    let execution = audit::Execution {
+        request_id: session.request_id(),
+        command_sha256: {
+            use sha2::Digest as _;
+            sha2::Sha256::digest(&args.raw_command).into()
+        },
+        signer,
+    };
+
+    // The entry about the execution has to be persisted before the command is
+    // spawned, so that there is a trace of it even if the agent crashes.
+    if let Some(audit_log) = &mut audit_log {
+        audit_log.started(&execution)
+            .map_err(crate::session::Error::action)?;
+    }
+
+    let result = execute(args);
+
+    if let Some(audit_log) = &mut audit_log {
+        match &result {
+            Ok(item) => audit_log.finished(&execution, item.exit_status),
+            Err(_) => audit_log.failed(&execution),
+        }.map_err(crate::session::Error::action)?;
+    }
+
+    session.reply(result?)?;
+
+    Ok(())
+}
+
+/// Executes the command specified in the given arguments.
+fn execute(args: Args) -> crate::session::Result<Item> {
+    use std::io::{Read as _, Write as _};
+
+    let mut command = std::process::Command::new(&args.path);
+    command
         .stdin(std::process::Stdio::piped())
         .args(args.args)
         .env_clear()
         .envs(args.env)
         .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
+        .stderr(std::process::Stdio::piped());
+
+    // We start the command in its own process group, so that on timeout we can
+    // kill all the processes it spawned as well. Otherwise, any descendant that
+    // inherited the output pipes would keep them open and we would wait for it
+    // to finish (possibly forever) when reading the output.
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::process::CommandExt as _;
+        command.process_group(0);
+    }
+
+    if let Some(run_as) = &args.run_as {
+        log::info!("running '{}' as {run_as:?}", args.path.display());
+    }
+
+    let mut command_process = crate::run_as::spawn(command, args.run_as.as_ref())
         .map_err(crate::session::Error::action)?;
 
     let command_start_time = std::time::Instant::now();
@@ -185,30 +304,31 @@ where
     });
 
     log::info!("starting '{}' (timeout: {:?})", args.path.display(), args.timeout);
-    loop {
+    let exit_status = loop {
         let time_elapsed = command_start_time.elapsed();
         let time_left = args.timeout.saturating_sub(time_elapsed);
 
-        if time_left.is_zero() {
-            break;
-        }
-
         match command_process.try_wait() {
-            Ok(None) => {
+            Ok(Some(exit_status)) => break exit_status,
+            Ok(None) if !time_left.is_zero() => {
                 std::thread::sleep(std::cmp::min(COMMAND_EXECUTION_CHECK_INTERVAL, time_left));
             }
-            _ => break,
-        }
-    }
-    // Either the process has exited, then kill doesn't do anything,
-    // or we kill the process.
-    command_process
-        .kill()
-        .map_err(crate::session::Error::action)?;
+            Ok(None) => {
+                log::info!("killing '{}' (timeout exceeded)", args.path.display());
 
-    let exit_status = command_process
-        .wait()
-        .map_err(crate::session::Error::action)?;
+                // Note that the process is not reaped yet at this point (we do
+                // it only below), so its identifier (which is also identifier
+                // of the process group) cannot be reused by anything else.
+                kill(&mut command_process)
+                    .map_err(crate::session::Error::action)?;
+
+                break command_process.wait()
+                    .map_err(crate::session::Error::action)?;
+            }
+            Err(error) => return Err(crate::session::Error::action(error)),
+        }
+    };
+    let runtime = command_start_time.elapsed();
 
     if let Some(writer) = writer {
         match writer.join() {
@@ -240,8 +360,9 @@ where
         Err(error) => std::panic::resume_unwind(error),
     };
 
-    session.reply(Item {
+    Ok(Item {
         exit_status,
+        runtime,
         // Note that we will return `truncated_std*` bit even if the output was
         // exactly `MAX_STD*_SIZE`. However, because this constant is an agent
         // implementation detail we might have as well set it to be 1 more than
@@ -251,11 +372,37 @@ where
         truncated_stderr: stderr.len() == MAX_STDERR_SIZE,
         stdout,
         stderr,
-    })?;
+    })
+}
+
+/// Kills the given command subprocess together with its process group.
+#[cfg(target_family = "unix")]
+fn kill(process: &mut crate::run_as::Child) -> std::io::Result<()> {
+    let pgid = libc::pid_t::try_from(process.id())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    // SAFETY: This is just a system call without any memory-related invariants.
+    // Negated identifier means that the signal is sent to the whole group [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man2/kill.2.html
+    let code = unsafe {
+        libc::kill(-pgid, libc::SIGKILL)
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
 
     Ok(())
 }
 
+/// Kills the given command subprocess.
+///
+/// Unlike on Unix, processes spawned by the subprocess are not killed.
+#[cfg(target_family = "windows")]
+fn kill(process: &mut crate::run_as::Child) -> std::io::Result<()> {
+    process.kill()
+}
+
 impl crate::request::Args for Args {
     type Proto = rrg_proto::execute_signed_command::Args;
 
@@ -273,7 +420,7 @@ impl crate::request::Args for Args {
             rrg_proto::execute_signed_command::Command::parse_from_bytes(&raw_command)
                 .map_err(|error| ParseArgsError::invalid_field("command", error))?;
 
-        let path = std::path::PathBuf::try_from(command.take_path())
+        let path = crate::path::from_proto(command.take_path())
             .map_err(|error| ParseArgsError::invalid_field("command path", error))?;
 
         let stdin = match command.unsigned_stdin_allowed() {
@@ -284,6 +431,11 @@ impl crate::request::Args for Args {
         let timeout = std::time::Duration::try_from(proto.take_timeout())
             .map_err(|error| ParseArgsError::invalid_field("timeout", error))?;
 
+        // The identity is part of the signed command, so it is covered by the
+        // signature verified before the command is executed.
+        let run_as = crate::run_as::RunAs::from_proto(command.take_run_as())
+            .map_err(|error| ParseArgsError::invalid_field("command run_as", error))?;
+
         Ok(Args {
             raw_command,
             path,
@@ -292,8 +444,13 @@ impl crate::request::Args for Args {
             ed25519_signature,
             stdin,
             timeout,
+            run_as,
         })
     }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.path.as_path()]
+    }
 }
 
 impl crate::response::Item for Item {
@@ -315,6 +472,8 @@ impl crate::response::Item for Item {
             }
         }
 
+        proto.set_runtime(self.runtime.into());
+
         proto.set_stdout(self.stdout);
         proto.set_stdout_truncated(self.truncated_stdout);
 
@@ -334,11 +493,8 @@ mod tests {
 
     fn prepare_session(verification_key: ed25519_dalek::VerifyingKey) -> crate::session::FakeSession {
         crate::session::FakeSession::with_args(crate::args::Args {
-            heartbeat_rate: std::time::Duration::from_secs(0),
             command_verification_key: Some(verification_key),
-            verbosity: log::LevelFilter::Debug,
-            log_to_stdout: false,
-            log_to_file: None,
+            ..Default::default()
         })
     }
 
@@ -360,6 +516,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from(b""),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -390,6 +547,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from(b""),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -419,6 +577,7 @@ mod tests {
             stdin: "Hello, world!".as_bytes().to_vec(),
             ed25519_signature,
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -448,6 +607,7 @@ mod tests {
             ed25519_signature,
             stdin: "Hello, world!".as_bytes().to_vec(),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -480,6 +640,7 @@ mod tests {
             stdin: vec![0xFF; 2 * 1024 * 1024],
             ed25519_signature,
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -513,6 +674,7 @@ mod tests {
             stdin: vec![0xFF; 2 * 1024 * 1024],
             ed25519_signature,
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -547,6 +709,7 @@ mod tests {
             stdin: Vec::default(),
             ed25519_signature,
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -580,6 +743,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from(b""),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -612,6 +776,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from(b""),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
         handle(&mut session, args).unwrap();
         assert_eq!(session.reply_count(), 1);
@@ -653,6 +818,326 @@ mod tests {
         assert!(handle(&mut session, args).is_err());
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_tampered_command() {
+        use protobuf::Message as _;
+        use crate::request::Args as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let marker_path = tempdir.path().join("marker");
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_session(signing_key.verifying_key());
+
+        let mut command = rrg_proto::execute_signed_command::Command::new();
+        command.set_path(std::path::PathBuf::from("true").into());
+
+        let signature = signing_key.sign(&command.write_to_bytes().unwrap());
+
+        // We replace the signed command with a different one that leaves a
+        // trace if it is ever executed.
+        let mut command = rrg_proto::execute_signed_command::Command::new();
+        command.set_path(std::path::PathBuf::from("touch").into());
+        command.mut_args().push(marker_path.display().to_string());
+
+        let mut args_proto = rrg_proto::execute_signed_command::Args::new();
+        args_proto.set_command_ed25519_signature(signature.to_vec());
+        args_proto.set_command(command.write_to_bytes().unwrap());
+        args_proto.mut_timeout().seconds = 5;
+
+        let args = Args::from_proto(args_proto)
+            .unwrap();
+
+        assert!(handle(&mut session, args).is_err());
+        assert_eq!(session.reply_count(), 0);
+        assert!(!marker_path.exists());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_run_as_own_uid() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_session(signing_key.verifying_key());
+
+        let raw_command = Vec::default();
+        let ed25519_signature = signing_key.sign(&raw_command);
+
+        // SAFETY: These functions are always safe to call.
+        let (uid, gid) = unsafe {
+            (libc::geteuid(), libc::getegid())
+        };
+
+        // We cannot drop to a different user without being root, but dropping
+        // to our own one still exercises the whole mechanism.
+        let args = Args {
+            raw_command,
+            path: "id".into(),
+            args: vec![String::from("-u")],
+            env: std::collections::HashMap::new(),
+            ed25519_signature,
+            stdin: Vec::from(b""),
+            timeout: std::time::Duration::from_secs(5),
+            run_as: Some(crate::run_as::RunAs::Unix {
+                uid,
+                gid,
+                groups: Vec::new(),
+            }),
+        };
+        handle(&mut session, args).unwrap();
+        assert_eq!(session.reply_count(), 1);
+        let item = session.reply::<Item>(0);
+
+        assert!(item.exit_status.success());
+        assert_eq!(item.stdout, format!("{uid}\n").as_bytes());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_tampered_run_as() {
+        use protobuf::Message as _;
+        use crate::request::Args as _;
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_session(signing_key.verifying_key());
+
+        let mut command = rrg_proto::execute_signed_command::Command::new();
+        command.set_path(std::path::PathBuf::from("true").into());
+        command.mut_run_as().set_unix_uid(65534);
+        command.mut_run_as().set_unix_gid(65534);
+
+        let signature = signing_key.sign(&command.write_to_bytes().unwrap());
+
+        // We remove the identity from the signed command, which should make it
+        // run with the agent one if the signature did not cover it.
+        command.clear_run_as();
+
+        let mut args_proto = rrg_proto::execute_signed_command::Args::new();
+        args_proto.set_command_ed25519_signature(signature.to_vec());
+        args_proto.set_command(command.write_to_bytes().unwrap());
+        args_proto.mut_timeout().seconds = 5;
+
+        let args = Args::from_proto(args_proto)
+            .unwrap();
+        assert!(args.run_as.is_none());
+
+        assert!(handle(&mut session, args).is_err());
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_rotated_key() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let store_path = tempdir.path().join("keys");
+
+        let initial_signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let new_signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let mut keys = crate::signing::KeySet::new(None);
+        keys.rotate(
+            crate::signing::VerificationKey::new(new_signing_key.verifying_key()),
+            Some(initial_signing_key.verifying_key()),
+        ).unwrap();
+        keys.save(&store_path)
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            command_verification_key: Some(initial_signing_key.verifying_key()),
+            command_verification_key_store: Some(store_path),
+            ..Default::default()
+        });
+
+        let args = |signing_key: &ed25519_dalek::SigningKey| {
+            let raw_command = Vec::default();
+            let ed25519_signature = signing_key.sign(&raw_command);
+
+            Args {
+                raw_command,
+                path: "true".into(),
+                args: Vec::new(),
+                env: std::collections::HashMap::new(),
+                ed25519_signature,
+                stdin: Vec::from(b""),
+                timeout: std::time::Duration::from_secs(5),
+                run_as: None,
+            }
+        };
+
+        // The initial key was revoked by the rotation so it must not work even
+        // though it is still given in the agent arguments.
+        assert!(handle(&mut session, args(&initial_signing_key)).is_err());
+        assert_eq!(session.reply_count(), 0);
+
+        handle(&mut session, args(&new_signing_key))
+            .unwrap();
+        assert_eq!(session.reply_count(), 1);
+    }
+
+    fn prepare_audited_session(
+        verification_key: ed25519_dalek::VerifyingKey,
+        audit_log: Option<&std::path::Path>,
+        rate_limit: Option<u32>,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            command_verification_key: Some(verification_key),
+            command_audit_log: audit_log.map(std::path::Path::to_path_buf),
+            command_rate_limit: rate_limit,
+            ..Default::default()
+        })
+    }
+
+    fn signed_args(signing_key: &ed25519_dalek::SigningKey, path: &str) -> Args {
+        use protobuf::Message as _;
+
+        let mut command = rrg_proto::execute_signed_command::Command::new();
+        command.set_path(std::path::PathBuf::from(path).into());
+
+        let raw_command = command.write_to_bytes()
+            .unwrap();
+        let ed25519_signature = signing_key.sign(&raw_command);
+
+        Args {
+            raw_command,
+            path: path.into(),
+            args: Vec::new(),
+            env: std::collections::HashMap::new(),
+            ed25519_signature,
+            stdin: Vec::from(b""),
+            timeout: std::time::Duration::from_secs(5),
+            run_as: None,
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_audit_log() {
+        use rrg_proto::execute_signed_command::audit_entry::Event;
+        use sha2::Digest as _;
+        use crate::session::Session as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let audit_log_path = tempdir.path().join("audit");
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_audited_session(
+            signing_key.verifying_key(),
+            Some(&audit_log_path),
+            None,
+        );
+
+        handle(&mut session, signed_args(&signing_key, "true"))
+            .unwrap();
+        handle(&mut session, signed_args(&signing_key, "false"))
+            .unwrap();
+        handle(&mut session, signed_args(&signing_key, "/foo/bar/baz"))
+            .unwrap_err();
+        assert_eq!(session.reply_count(), 2);
+
+        let audit_log = audit::Log::open(&audit_log_path)
+            .unwrap();
+        let entries = audit_log.entries();
+
+        let events = entries.iter()
+            .map(|entry| entry.event())
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec! {
+            Event::STARTED, Event::FINISHED,
+            Event::STARTED, Event::FINISHED,
+            Event::STARTED, Event::FAILED,
+        });
+
+        assert_eq!(entries[1].exit_code(), 0);
+        assert_eq!(entries[3].exit_code(), 1);
+
+        let command_sha256 = sha2::Sha256::digest(&signed_args(&signing_key, "false").raw_command);
+        assert_eq!(entries[2].command_sha256(), &command_sha256[..]);
+        assert_eq!(entries[3].command_sha256(), &command_sha256[..]);
+
+        for entry in entries {
+            assert_eq!(entry.flow_id(), session.request_id().flow_id());
+            assert_eq!(entry.request_id(), session.request_id().request_id());
+            assert_eq!(entry.signer_ed25519_public_key(), signing_key.verifying_key().to_bytes());
+            assert!(entry.has_time());
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_invalid_signature_not_audited() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let audit_log_path = tempdir.path().join("audit");
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let other_signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_audited_session(
+            signing_key.verifying_key(),
+            Some(&audit_log_path),
+            None,
+        );
+
+        handle(&mut session, signed_args(&other_signing_key, "true"))
+            .unwrap_err();
+        assert!(!audit_log_path.exists());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_rate_limit() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let audit_log_path = tempdir.path().join("audit");
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_audited_session(
+            signing_key.verifying_key(),
+            Some(&audit_log_path),
+            Some(2),
+        );
+
+        handle(&mut session, signed_args(&signing_key, "true"))
+            .unwrap();
+        handle(&mut session, signed_args(&signing_key, "true"))
+            .unwrap();
+
+        let error = handle(&mut session, signed_args(&signing_key, "true"))
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::RateLimited);
+        assert_eq!(session.reply_count(), 2);
+
+        // The limit should hold across restarts (simulated with a new session)
+        // as executions are counted from the log.
+        let mut session = prepare_audited_session(
+            signing_key.verifying_key(),
+            Some(&audit_log_path),
+            Some(2),
+        );
+
+        let error = handle(&mut session, signed_args(&signing_key, "true"))
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::RateLimited);
+        assert_eq!(session.reply_count(), 0);
+
+        let audit_log = audit::Log::open(&audit_log_path)
+            .unwrap();
+        assert_eq!(audit_log.entries().len(), 4);
+    }
+
+    #[test]
+    fn handle_rate_limit_without_audit_log() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_audited_session(signing_key.verifying_key(), None, Some(1));
+
+        let error = handle(&mut session, signed_args(&signing_key, "true"))
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionUnavailable);
+        assert_eq!(session.reply_count(), 0);
+    }
+
     #[cfg(target_family = "unix")]
     #[test]
     fn handle_truncate_output() {
@@ -670,6 +1155,7 @@ mod tests {
             ed25519_signature,
             stdin: ("A".repeat(MAX_STDOUT_SIZE) + "truncated").into_bytes(),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
 
         handle(&mut session, args).unwrap();
@@ -708,6 +1194,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from("ABCD\r\n".repeat(MAX_STDOUT_SIZE)),
             timeout: std::time::Duration::from_secs(5),
+            run_as: None,
         };
 
         handle(&mut session, args).unwrap();
@@ -745,6 +1232,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from(b""),
             timeout,
+            run_as: None,
         };
 
         handle(&mut session, args).unwrap();
@@ -759,6 +1247,44 @@ mod tests {
         assert_eq!(item.stdout, b"");
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_kill_if_timeout_process_group() {
+        let timeout = std::time::Duration::from_secs(1);
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut session = prepare_session(signing_key.verifying_key());
+
+        let raw_command = Vec::default();
+        let ed25519_signature = signing_key.sign(&raw_command);
+
+        // The background process inherits the output pipes of the shell, so if
+        // it was not killed together with the shell, we would wait for it.
+        let args = Args {
+            raw_command,
+            path: "sh".into(),
+            args: ["-c", "sleep 60 & sleep 60"]
+                .into_iter().map(String::from).collect(),
+            env: std::collections::HashMap::new(),
+            ed25519_signature,
+            stdin: Vec::from(b""),
+            timeout,
+            run_as: None,
+        };
+
+        let start_time = std::time::Instant::now();
+        handle(&mut session, args).unwrap();
+        assert!(start_time.elapsed() < std::time::Duration::from_secs(30));
+
+        assert_eq!(session.reply_count(), 1);
+        let item = session.reply::<Item>(0);
+
+        use std::os::unix::process::ExitStatusExt as _;
+
+        assert_eq!(item.exit_status.signal(), Some(libc::SIGKILL));
+        assert!(item.runtime >= timeout);
+    }
+
     #[cfg(target_family = "unix")]
     #[test]
     fn handle_kill_if_timeout_large_stdin() {
@@ -783,6 +1309,7 @@ mod tests {
             ed25519_signature,
             stdin: vec![0xFF; 2 * 1024 * 1024],
             timeout,
+            run_as: None,
         };
 
         handle(&mut session, args).unwrap();
@@ -819,6 +1346,7 @@ mod tests {
             ed25519_signature,
             stdin: Vec::from(b""),
             timeout,
+            run_as: None,
         };
 
         handle(&mut session, args).unwrap();
@@ -857,6 +1385,7 @@ mod tests {
             ed25519_signature,
             stdin: vec![0xFF; 2 * 1024 * 1024],
             timeout,
+            run_as: None,
         };
 
         handle(&mut session, args).unwrap();
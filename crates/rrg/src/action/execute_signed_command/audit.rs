@@ -0,0 +1,364 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Local audit log of executed signed commands.
+//!
+//! The log is an append-only file of length-delimited `AuditEntry` messages.
+//! Every entry contains a digest of the one preceding it, so modifications of
+//! the log (other than appending properly chained entries) are detected when
+//! the log is opened.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use rrg_proto::execute_signed_command::{AuditEntry, audit_entry::Event};
+
+/// Details of a command execution that is recorded in the log.
+pub struct Execution {
+    /// Identifier of the request to execute the command.
+    pub request_id: crate::RequestId,
+    /// SHA-256 digest of the serialized command.
+    pub command_sha256: [u8; 32],
+    /// Key that the command was signed with.
+    pub signer: ed25519_dalek::VerifyingKey,
+}
+
+/// Handle to an opened audit log.
+pub struct Log {
+    /// File the log is stored in.
+    file: std::fs::File,
+    /// Entries of the log (including those appended through this handle).
+    entries: Vec<AuditEntry>,
+    /// SHA-256 digest of the last serialized entry in the log (if any).
+    last_entry_sha256: Option<[u8; 32]>,
+}
+
+impl Log {
+
+    /// Opens the log at the given path, creating it if it does not exist.
+    ///
+    /// All entries of the log are read and the chain of their digests is
+    /// verified. Logs that fail verification are reported as errors of the
+    /// [`std::io::ErrorKind::InvalidData`] kind.
+    pub fn open<P>(path: P) -> std::io::Result<Log>
+    where
+        P: AsRef<Path>,
+    {
+        use std::io::Read as _;
+        use protobuf::Message as _;
+
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+
+        let mut entries = Vec::new();
+        let mut last_entry_sha256: Option<[u8; 32]> = None;
+
+        let mut input = protobuf::CodedInputStream::from_bytes(&buf);
+        while !input.eof().map_err(invalid_data)? {
+            let len = input.read_raw_varint32()
+                .map_err(invalid_data)?;
+            let raw_entry = input.read_raw_bytes(len)
+                .map_err(invalid_data)?;
+
+            let entry = AuditEntry::parse_from_bytes(&raw_entry)
+                .map_err(invalid_data)?;
+
+            let previous_entry_sha256 = match &last_entry_sha256 {
+                Some(sha256) => &sha256[..],
+                None => &[][..],
+            };
+            if entry.previous_entry_sha256() != previous_entry_sha256 {
+                return Err(invalid_data(BrokenChainError {
+                    entry_index: entries.len(),
+                }));
+            }
+
+            last_entry_sha256 = Some(sha256(&raw_entry));
+            entries.push(entry);
+        }
+
+        Ok(Log {
+            file,
+            entries,
+            last_entry_sha256,
+        })
+    }
+
+    /// Returns all entries of the log.
+    #[cfg(test)]
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of executions started at or after the given time.
+    pub fn started_since(&self, time: SystemTime) -> usize {
+        self.entries.iter()
+            .filter(|entry| entry.event() == Event::STARTED)
+            .filter(|entry| match rrg_proto::try_from_timestamp(entry.time().clone()) {
+                Ok(entry_time) => entry_time >= time,
+                // Entries with malformed time are counted as recent ones, so
+                // that they cannot be used to circumvent the rate limit.
+                Err(_) => true,
+            })
+            .count()
+    }
+
+    /// Records that the execution is about to be started.
+    pub fn started(&mut self, execution: &Execution) -> std::io::Result<()> {
+        self.append(entry(execution, Event::STARTED))
+    }
+
+    /// Records that the execution finished with the given exit status.
+    pub fn finished(
+        &mut self,
+        execution: &Execution,
+        exit_status: std::process::ExitStatus,
+    ) -> std::io::Result<()> {
+        let mut entry = entry(execution, Event::FINISHED);
+        if let Some(exit_code) = exit_status.code() {
+            entry.set_exit_code(exit_code);
+        }
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::process::ExitStatusExt as _;
+
+            if let Some(exit_signal) = exit_status.signal() {
+                entry.set_exit_signal(exit_signal);
+            }
+        }
+
+        self.append(entry)
+    }
+
+    /// Records that the execution failed.
+    pub fn failed(&mut self, execution: &Execution) -> std::io::Result<()> {
+        self.append(entry(execution, Event::FAILED))
+    }
+
+    /// Appends the given entry to the log and syncs it to the disk.
+    fn append(&mut self, mut entry: AuditEntry) -> std::io::Result<()> {
+        use std::io::Write as _;
+        use protobuf::Message as _;
+
+        if let Some(last_entry_sha256) = self.last_entry_sha256 {
+            entry.set_previous_entry_sha256(last_entry_sha256.to_vec());
+        }
+
+        let raw_entry = entry.write_to_bytes()
+            .map_err(invalid_data)?;
+
+        let mut buf = Vec::new();
+        let mut output = protobuf::CodedOutputStream::vec(&mut buf);
+        output.write_raw_varint32(raw_entry.len() as u32)
+            .and_then(|()| output.write_raw_bytes(&raw_entry))
+            .and_then(|()| output.flush())
+            .map_err(invalid_data)?;
+        drop(output);
+
+        // We write the whole entry at once to make it less likely for a crash
+        // to leave a partial entry (which would break the log).
+        self.file.write_all(&buf)?;
+        self.file.sync_data()?;
+
+        self.last_entry_sha256 = Some(sha256(&raw_entry));
+        self.entries.push(entry);
+
+        Ok(())
+    }
+}
+
+/// Creates a new log entry of the given event for the given execution.
+fn entry(execution: &Execution, event: Event) -> AuditEntry {
+    let mut entry = AuditEntry::new();
+    entry.set_event(event);
+    entry.set_time(rrg_proto::into_timestamp(SystemTime::now()));
+    entry.set_flow_id(execution.request_id.flow_id());
+    entry.set_request_id(execution.request_id.request_id());
+    entry.set_command_sha256(execution.command_sha256.to_vec());
+    entry.set_signer_ed25519_public_key(execution.signer.to_bytes().to_vec());
+
+    entry
+}
+
+/// Computes the SHA-256 digest of the given data.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    use sha2::Digest as _;
+
+    sha2::Sha256::digest(data).into()
+}
+
+/// Wraps the given error into an I/O error of the `InvalidData` kind.
+fn invalid_data<E>(error: E) -> std::io::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+/// An error indicating that an entry of the log is not chained to the previous.
+#[derive(Debug)]
+struct BrokenChainError {
+    /// Index of the first entry with a mismatched digest.
+    entry_index: usize,
+}
+
+impl std::fmt::Display for BrokenChainError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "audit log entry #{} does not match the previous one", self.entry_index)
+    }
+}
+
+impl std::error::Error for BrokenChainError {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn execution() -> Execution {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        Execution {
+            request_id: crate::RequestId::new(0xf00, 0xba7),
+            command_sha256: sha256(b"foo"),
+            signer: signing_key.verifying_key(),
+        }
+    }
+
+    #[test]
+    fn open_not_existing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let log = Log::open(tempdir.path().join("audit"))
+            .unwrap();
+        assert!(log.entries().is_empty());
+        assert!(tempdir.path().join("audit").exists());
+    }
+
+    #[test]
+    fn append_and_open() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("audit");
+
+        let execution = execution();
+
+        let mut log = Log::open(&path)
+            .unwrap();
+        log.started(&execution)
+            .unwrap();
+        log.failed(&execution)
+            .unwrap();
+        drop(log);
+
+        let mut log = Log::open(&path)
+            .unwrap();
+        log.started(&execution)
+            .unwrap();
+        drop(log);
+
+        let log = Log::open(&path)
+            .unwrap();
+
+        let events = log.entries().iter()
+            .map(|entry| entry.event())
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![Event::STARTED, Event::FAILED, Event::STARTED]);
+
+        let entry = &log.entries()[0];
+        assert_eq!(entry.flow_id(), 0xf00);
+        assert_eq!(entry.request_id(), 0xba7);
+        assert_eq!(entry.command_sha256(), sha256(b"foo"));
+        assert_eq!(entry.signer_ed25519_public_key(), execution.signer.to_bytes());
+        assert!(entry.previous_entry_sha256().is_empty());
+    }
+
+    #[test]
+    fn open_tampered() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("audit");
+
+        let execution = Execution {
+            command_sha256: [0xaa; 32],
+            ..execution()
+        };
+
+        let mut log = Log::open(&path)
+            .unwrap();
+        log.started(&execution)
+            .unwrap();
+        log.failed(&execution)
+            .unwrap();
+        drop(log);
+
+        // We modify the command digest of the first entry: it stays valid on
+        // its own, but the digest in the second entry no longer matches it.
+        let mut buf = std::fs::read(&path)
+            .unwrap();
+        let offset = buf.windows(32).position(|window| window == [0xaa; 32])
+            .unwrap();
+        buf[offset] = 0xbb;
+        std::fs::write(&path, buf)
+            .unwrap();
+
+        let error = Log::open(&path).err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn open_truncated() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("audit");
+
+        let mut log = Log::open(&path)
+            .unwrap();
+        log.started(&execution())
+            .unwrap();
+        drop(log);
+
+        let buf = std::fs::read(&path)
+            .unwrap();
+        std::fs::write(&path, &buf[..buf.len() - 1])
+            .unwrap();
+
+        let error = Log::open(&path).err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn started_since() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let execution = execution();
+
+        let mut log = Log::open(tempdir.path().join("audit"))
+            .unwrap();
+
+        let time = SystemTime::now();
+        log.started(&execution)
+            .unwrap();
+        log.failed(&execution)
+            .unwrap();
+        log.started(&execution)
+            .unwrap();
+
+        assert_eq!(log.started_since(time - std::time::Duration::from_secs(60)), 2);
+        assert_eq!(log.started_since(SystemTime::now() + std::time::Duration::from_secs(60)), 0);
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the agent capabilities action.
+
+/// A result of the `get_agent_capabilities` action.
+struct Item {
+    /// Versions of arguments of the actions supported by the agent.
+    action_versions: Vec<(crate::request::Action, u32)>,
+}
+
+/// Handles invocations of the `get_agent_capabilities` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    session.reply(Item {
+        action_versions: crate::action::versions(),
+    })?;
+
+    Ok(())
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_agent_capabilities::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        for (action, version) in self.action_versions {
+            let action_version = crate::startup::action_version_into_proto(action, version);
+            proto.mut_action_versions().push(action_version);
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_includes_self() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+        let item = session.reply::<Item>(0);
+
+        assert! {
+            item.action_versions.iter().any(|(action, version)| {
+                *action == crate::request::Action::GetAgentCapabilities && *version >= 1
+            })
+        };
+    }
+
+    #[test]
+    fn handle_only_supported() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.reply::<Item>(0);
+        for (action, version) in &item.action_versions {
+            assert_eq!(crate::action::version(*action), Some(*version));
+        }
+    }
+}
@@ -0,0 +1,612 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+mod exe;
+
+/// Disk space below which the state partition is reported as running low.
+const LOW_DISK_SPACE: u64 = 64 * 1024 * 1024;
+
+/// Memory usage above which the agent process is reported as bloated.
+const HIGH_MEMORY_USAGE: u64 = 1024 * 1024 * 1024;
+
+/// Kinds of checks that the agent performs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Check {
+    /// Version of the agent and details of its build.
+    AgentVersion,
+    /// Digest of the configuration the agent has been started with.
+    Configuration,
+    /// Whether the directories the agent keeps its state in are writable.
+    StateDirectories,
+    /// Whether the system clock is not behind the build time.
+    Clock,
+    /// Disk space available on the state partition.
+    DiskSpace,
+    /// Memory used by the agent process.
+    MemoryUsage,
+    /// Whether scanning with YARA rules is available.
+    Yara,
+    /// Whether running osquery queries is available.
+    Osquery,
+    /// Whether querying WMI is available.
+    Wmi,
+    /// Mode in which the agent handles requests.
+    AgentMode,
+    /// Digest of the agent executable and indicators of tampering with it.
+    AgentBinary,
+}
+
+/// Outcome of a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    /// Everything is as expected.
+    Ok,
+    /// Something is not as expected but the agent can still work.
+    Warn,
+    /// Something is wrong and the agent might not work correctly.
+    Fail,
+}
+
+/// A result of the `get_agent_health` action.
+struct Item {
+    /// Check that has been performed.
+    check: Check,
+    /// Outcome of the check.
+    status: Status,
+    /// Human-readable details of the outcome.
+    detail: String,
+}
+
+impl Item {
+
+    /// Creates a new item for the given check.
+    fn new<D: Into<String>>(check: Check, status: Status, detail: D) -> Item {
+        Item {
+            check,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Handles invocations of the `get_agent_health` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    for item in checks(session.args()) {
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Sends results of all checks to the agent health sink.
+///
+/// This function should be called once at the agent startup, so that problems
+/// with the agent are reported even if no flow requests them.
+pub fn startup(args: &crate::args::Args) {
+    for item in checks(args) {
+        if item.status != Status::Ok {
+            log::warn!("health check {:?} not ok: {}", item.check, item.detail);
+        }
+
+        crate::response::Parcel::new(crate::Sink::AgentHealth, item)
+            .send_unaccounted();
+    }
+}
+
+/// Performs all the checks using the given agent arguments and returns them.
+///
+/// Unlike with [`startup`], the results are not sent to the server.
+pub fn run(args: &crate::args::Args) -> Vec<rrg_proto::get_agent_health::Result> {
+    use crate::response::Item as _;
+
+    checks(args).into_iter()
+        .map(Item::into_proto)
+        .collect()
+}
+
+/// Performs all the checks using the given agent arguments.
+fn checks(args: &crate::args::Args) -> Vec<Item> {
+    let state_dirs = state_dirs(args);
+
+    vec! {
+        check_agent_version(),
+        check_configuration(args),
+        check_state_directories(&state_dirs),
+        check_clock(std::time::SystemTime::now()),
+        check_disk_space(&state_dirs),
+        check_memory_usage(),
+        check_yara(),
+        check_osquery(args),
+        check_wmi(),
+        check_agent_mode(crate::mode::current(), crate::mode::queued_count()),
+        check_agent_binary(),
+    }
+}
+
+/// Returns directories in which the agent keeps its state.
+fn state_dirs(args: &crate::args::Args) -> Vec<PathBuf> {
+    let state_paths = [
+        &args.command_verification_key_store,
+        &args.command_audit_log,
+        &args.log_to_file,
+    ];
+
+    let mut state_dirs = state_paths.into_iter()
+        .flatten()
+        .filter_map(|path| path.parent())
+        .map(|path| match path {
+            // Relative paths with a single component have an empty parent.
+            path if path.as_os_str().is_empty() => PathBuf::from("."),
+            path => path.to_path_buf(),
+        })
+        .collect::<Vec<_>>();
+    state_dirs.extend(args.state_dir.iter().cloned());
+    state_dirs.extend(crate::crash::spool_dir(args));
+    state_dirs.sort();
+    state_dirs.dedup();
+
+    state_dirs
+}
+
+/// Reports the version of the agent and details of its build.
+fn check_agent_version() -> Item {
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+
+    Item::new(Check::AgentVersion, Status::Ok, format! {
+        "{} {} ({}-{}, {profile}, built at {})",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        humantime::format_rfc3339(crate::version::build_time()),
+    })
+}
+
+/// Reports the digest of the configuration the agent has been started with.
+///
+/// See [`crate::config::digest`] for details what the digest is computed of.
+fn check_configuration(args: &crate::args::Args) -> Item {
+    let digest = crate::config::digest(args);
+
+    Item::new(Check::Configuration, Status::Ok, format!("sha256:{}", hex(&digest)))
+}
+
+/// Verifies that all the given state directories are writable.
+fn check_state_directories(state_dirs: &[PathBuf]) -> Item {
+    if state_dirs.is_empty() {
+        return Item::new(Check::StateDirectories, Status::Ok, "no state directories configured");
+    }
+
+    let mut failures = Vec::new();
+    for state_dir in state_dirs {
+        if let Err(error) = check_writable(state_dir) {
+            failures.push(format!("'{}' not writable: {error}", state_dir.display()));
+        }
+    }
+
+    if failures.is_empty() {
+        let state_dirs = state_dirs.iter()
+            .map(|state_dir| format!("'{}'", state_dir.display()))
+            .collect::<Vec<_>>();
+
+        Item::new(Check::StateDirectories, Status::Ok, format! {
+            "writable: {}", state_dirs.join(", "),
+        })
+    } else {
+        Item::new(Check::StateDirectories, Status::Fail, failures.join("; "))
+    }
+}
+
+/// Verifies that a file can be created in the given directory.
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let path = dir.join(format!(".rrg-health-{}", std::process::id()));
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+
+    std::fs::remove_file(&path)
+}
+
+/// Verifies that the given time is not before the time the agent was built.
+///
+/// A clock that is behind is likely misconfigured and would make all the
+/// timestamps reported by the agent misleading.
+fn check_clock(now: std::time::SystemTime) -> Item {
+    let build_time = crate::version::build_time();
+
+    if now < build_time {
+        Item::new(Check::Clock, Status::Fail, format! {
+            "system time {} is before build time {}",
+            humantime::format_rfc3339(now),
+            humantime::format_rfc3339(build_time),
+        })
+    } else {
+        Item::new(Check::Clock, Status::Ok, format! {
+            "system time {}", humantime::format_rfc3339(now),
+        })
+    }
+}
+
+/// Reports disk space available on the partition of the state directories.
+///
+/// If no state directories are configured, the partition of the agent
+/// executable is checked instead.
+fn check_disk_space(state_dirs: &[PathBuf]) -> Item {
+    let path = match state_dirs.first() {
+        Some(state_dir) => state_dir.clone(),
+        None => match std::env::current_exe() {
+            Ok(path) => path,
+            Err(error) => {
+                return Item::new(Check::DiskSpace, Status::Fail, format! {
+                    "failed to obtain agent's path: {error}",
+                });
+            }
+        },
+    };
+
+    let space = match ospect::fs::space(&path) {
+        Ok(space) => space,
+        Err(error) => {
+            return Item::new(Check::DiskSpace, Status::Fail, format! {
+                "failed to obtain space information for '{}': {error}",
+                path.display(),
+            });
+        }
+    };
+
+    let status = if space.available < LOW_DISK_SPACE {
+        Status::Warn
+    } else {
+        Status::Ok
+    };
+
+    Item::new(Check::DiskSpace, status, format! {
+        "{} bytes available on '{}'", space.available, path.display(),
+    })
+}
+
+/// Reports memory used by the agent process.
+fn check_memory_usage() -> Item {
+    let resident_size = match ospect::proc::resident_size() {
+        Ok(resident_size) => resident_size,
+        Err(error) => {
+            return Item::new(Check::MemoryUsage, Status::Fail, format! {
+                "failed to obtain resident memory size: {error}",
+            });
+        }
+    };
+
+    let status = if resident_size > HIGH_MEMORY_USAGE {
+        Status::Warn
+    } else {
+        Status::Ok
+    };
+
+    Item::new(Check::MemoryUsage, status, format! {
+        "{resident_size} bytes resident",
+    })
+}
+
+/// Reports whether scanning with YARA rules is available.
+fn check_yara() -> Item {
+    if cfg!(any(
+        feature = "action-scan_files_yara",
+        feature = "action-scan_process_memory_yara",
+    )) {
+        Item::new(Check::Yara, Status::Ok, "available")
+    } else {
+        Item::new(Check::Yara, Status::Ok, "not built in")
+    }
+}
+
+/// Reports whether running osquery queries is available.
+#[cfg(feature = "action-query_osquery")]
+fn check_osquery(args: &crate::args::Args) -> Item {
+    match crate::action::query_osquery::osquery_path(args.osquery_path.as_deref()) {
+        Ok(path) => Item::new(Check::Osquery, Status::Ok, format! {
+            "available at '{}'", path.display(),
+        }),
+        // Agent is built with osquery support, so it is likely expected to
+        // work on this machine.
+        Err(error) => Item::new(Check::Osquery, Status::Warn, error.to_string()),
+    }
+}
+
+/// Reports whether running osquery queries is available.
+#[cfg(not(feature = "action-query_osquery"))]
+fn check_osquery(_: &crate::args::Args) -> Item {
+    Item::new(Check::Osquery, Status::Ok, "not built in")
+}
+
+/// Reports whether querying WMI is available.
+fn check_wmi() -> Item {
+    if !cfg!(target_os = "windows") {
+        Item::new(Check::Wmi, Status::Ok, "not supported on this system")
+    } else if cfg!(feature = "action-query_wmi") {
+        Item::new(Check::Wmi, Status::Ok, "available")
+    } else {
+        Item::new(Check::Wmi, Status::Ok, "not built in")
+    }
+}
+
+/// Reports the mode in which the agent handles requests.
+///
+/// The agent not handling requests as usual is not a failure as it has been
+/// requested to do so, but it is worth a warning.
+fn check_agent_mode(mode: crate::mode::Mode, queued_count: usize) -> Item {
+    use crate::mode::Mode;
+
+    match mode {
+        Mode::Normal => Item::new(Check::AgentMode, Status::Ok, "normal"),
+        Mode::Paused => Item::new(Check::AgentMode, Status::Warn, format! {
+            "paused with {queued_count} queued requests"
+        }),
+        Mode::Drain => Item::new(Check::AgentMode, Status::Warn, "drain"),
+    }
+}
+
+/// Reports the digest of the agent executable and indicators of tampering.
+///
+/// Mismatches with the expected digest and the executable being replaced on
+/// disk after the agent started are reported as warnings: they are worth the
+/// attention of defenders but do not prevent the agent from working.
+fn check_agent_binary() -> Item {
+    let exe = match exe::inspect() {
+        Ok(exe) => exe,
+        Err(error) => {
+            return Item::new(Check::AgentBinary, Status::Warn, format! {
+                "failed to inspect agent executable: {error}",
+            });
+        }
+    };
+
+    let mut status = Status::Ok;
+    let mut details = vec! {
+        format!("sha256:{} of '{}'", hex(&exe.sha256), exe.path.display()),
+    };
+
+    match exe.verification {
+        exe::Verification::Unavailable => {
+            details.push(String::from("no expected digest embedded"));
+        }
+        exe::Verification::Match => {
+            details.push(String::from("matches expected digest"));
+        }
+        exe::Verification::Mismatch { expected, actual } => {
+            status = Status::Warn;
+            details.push(format! {
+                "expected digest sha256:{} does not match sha256:{}",
+                hex(&expected), hex(&actual),
+            });
+        }
+    }
+
+    if let Some(modified) = exe.modified {
+        details.push(format!("modified at {}", humantime::format_rfc3339(modified)));
+    }
+    if let Some(changed) = exe.changed {
+        details.push(format!("changed at {}", humantime::format_rfc3339(changed)));
+    }
+    if exe.immutable == Some(true) {
+        details.push(String::from("immutable"));
+    }
+    if exe.replaced == Some(true) {
+        status = Status::Warn;
+        details.push(String::from("replaced on disk since the agent started"));
+    }
+
+    Item::new(Check::AgentBinary, status, details.join(", "))
+}
+
+/// Formats the given bytes as a lowercase hexadecimal string.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_agent_health::Result;
+
+    fn into_proto(self) -> rrg_proto::get_agent_health::Result {
+        let mut proto = rrg_proto::get_agent_health::Result::new();
+        proto.set_check(self.check.into());
+        proto.set_status(self.status.into());
+        proto.set_detail(self.detail);
+
+        proto
+    }
+}
+
+impl From<Check> for rrg_proto::get_agent_health::Check {
+
+    fn from(check: Check) -> rrg_proto::get_agent_health::Check {
+        use rrg_proto::get_agent_health::Check::*;
+
+        match check {
+            Check::AgentVersion => AGENT_VERSION,
+            Check::Configuration => CONFIGURATION,
+            Check::StateDirectories => STATE_DIRECTORIES,
+            Check::Clock => CLOCK,
+            Check::DiskSpace => DISK_SPACE,
+            Check::MemoryUsage => MEMORY_USAGE,
+            Check::Yara => YARA,
+            Check::Osquery => OSQUERY,
+            Check::Wmi => WMI,
+            Check::AgentMode => AGENT_MODE,
+            Check::AgentBinary => AGENT_BINARY,
+        }
+    }
+}
+
+impl From<Status> for rrg_proto::get_agent_health::Status {
+
+    fn from(status: Status) -> rrg_proto::get_agent_health::Status {
+        use rrg_proto::get_agent_health::Status::*;
+
+        match status {
+            Status::Ok => OK,
+            Status::Warn => WARN,
+            Status::Fail => FAIL,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_all_checks() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let checks = session.replies::<Item>()
+            .map(|item| item.check)
+            .collect::<Vec<_>>();
+
+        assert_eq!(checks, vec! {
+            Check::AgentVersion,
+            Check::Configuration,
+            Check::StateDirectories,
+            Check::Clock,
+            Check::DiskSpace,
+            Check::MemoryUsage,
+            Check::Yara,
+            Check::Osquery,
+            Check::Wmi,
+            Check::AgentMode,
+            Check::AgentBinary,
+        });
+    }
+
+    #[test]
+    fn handle_agent_version() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.check == Check::AgentVersion)
+            .unwrap();
+
+        assert_eq!(item.status, Status::Ok);
+
+        let version = item.detail.split(' ').nth(1)
+            .unwrap();
+        assert_eq!(version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn check_agent_mode_paused() {
+        let item = check_agent_mode(crate::mode::Mode::Paused, 42);
+        assert_eq!(item.status, Status::Warn);
+        assert!(item.detail.contains("42"));
+    }
+
+    #[test]
+    fn check_state_directories_writable() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let item = check_state_directories(&[tempdir.path().to_path_buf()]);
+        assert_eq!(item.status, Status::Ok);
+
+        // The probe file should not be left behind.
+        assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn check_state_directories_not_existing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let item = check_state_directories(&[
+            tempdir.path().to_path_buf(),
+            tempdir.path().join("foo"),
+        ]);
+        assert_eq!(item.status, Status::Fail);
+        assert!(item.detail.contains("foo"));
+    }
+
+    #[test]
+    fn check_clock_before_build() {
+        let item = check_clock(std::time::UNIX_EPOCH);
+        assert_eq!(item.status, Status::Fail);
+
+        let item = check_clock(std::time::SystemTime::now());
+        assert_eq!(item.status, Status::Ok);
+    }
+
+    #[test]
+    fn check_disk_space_state_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let item = check_disk_space(&[tempdir.path().to_path_buf()]);
+        assert_ne!(item.status, Status::Fail);
+    }
+
+    #[test]
+    fn check_agent_binary_sha256() {
+        use sha2::Digest as _;
+
+        let item = check_agent_binary();
+        assert_eq!(item.status, Status::Ok);
+
+        let data = std::fs::read(std::env::current_exe().unwrap())
+            .unwrap();
+        let sha256 = hex(&sha2::Sha256::digest(&data));
+        assert!(item.detail.starts_with(&format!("sha256:{sha256} ")));
+    }
+
+    #[test]
+    fn check_memory_usage_ok() {
+        let item = check_memory_usage();
+        assert_eq!(item.status, Status::Ok);
+    }
+
+    #[test]
+    fn state_dirs_from_args() {
+        use argh::FromArgs as _;
+
+        let mut args = crate::args::Args::from_args(&["rrg"], &[])
+            .unwrap();
+        args.command_audit_log = Some(PathBuf::from("/foo/bar/audit"));
+        args.log_to_file = Some(PathBuf::from("/foo/bar/log"));
+        args.command_verification_key_store = Some(PathBuf::from("keys"));
+
+        assert_eq!(state_dirs(&args), vec! {
+            PathBuf::from("/foo/bar"),
+            PathBuf::from("."),
+        });
+    }
+
+    #[test]
+    fn state_dirs_from_args_state_dir() {
+        use argh::FromArgs as _;
+
+        let mut args = crate::args::Args::from_args(&["rrg"], &[])
+            .unwrap();
+        args.state_dir = Some(PathBuf::from("/foo/state"));
+
+        assert_eq!(state_dirs(&args), vec! {
+            PathBuf::from("/foo/state"),
+            PathBuf::from("/foo/state/crash"),
+        });
+    }
+}
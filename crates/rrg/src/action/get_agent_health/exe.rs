@@ -0,0 +1,342 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Integrity indicators of the agent executable.
+//!
+//! An executable cannot contain a digest of itself, so the expected digest is
+//! embedded into a slot after the build: the slot consists of a fixed marker
+//! followed by 32 zero bytes and the packaging step computes SHA-256 of the
+//! executable (with the slot as built) and writes it over the zero bytes. The
+//! agent verifies the digest the same way: by hashing its image with the bytes
+//! following the marker zeroed.
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Length of the marker that starts the expected digest slot.
+const MARKER_LEN: usize = 16;
+
+/// Length of the expected digest slot (the marker followed by the digest).
+const SLOT_LEN: usize = MARKER_LEN + 32;
+
+/// Slot for the expected SHA-256 digest of the agent executable.
+///
+/// See the [module documentation](self) for how the slot is filled.
+static EXPECTED_SHA256_SLOT: [u8; SLOT_LEN] = {
+    let marker = b"rrg-exe-sha256:\0";
+
+    let mut slot = [0; SLOT_LEN];
+    let mut i = 0;
+    while i < MARKER_LEN {
+        slot[i] = marker[i];
+        i += 1;
+    }
+
+    slot
+};
+
+/// Integrity indicators of the agent executable.
+#[derive(Debug)]
+pub struct Exe {
+    /// Path to the executable.
+    pub path: PathBuf,
+    /// SHA-256 digest of the executable image the agent is running from.
+    pub sha256: [u8; 32],
+    /// Outcome of verifying the image against the expected digest.
+    pub verification: Verification,
+    /// Time at which the executable was last modified.
+    pub modified: Option<SystemTime>,
+    /// Time at which the metadata of the executable was last changed.
+    pub changed: Option<SystemTime>,
+    /// Whether the executable has the immutable flag set (Linux only).
+    pub immutable: Option<bool>,
+    /// Whether the executable on disk is no longer the running image.
+    ///
+    /// This is `None` on systems where this cannot be determined.
+    pub replaced: Option<bool>,
+}
+
+/// Outcome of verifying the executable against the expected digest.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// No expected digest has been embedded into the executable.
+    Unavailable,
+    /// The executable matches the expected digest.
+    Match,
+    /// The executable does not match the expected digest.
+    Mismatch {
+        /// Digest embedded into the executable.
+        expected: [u8; 32],
+        /// Digest computed of the executable image.
+        actual: [u8; 32],
+    },
+}
+
+/// Collects integrity indicators of the agent executable.
+///
+/// On Linux, the executable is read through `/proc/self/exe`, so the digest is
+/// computed of the running image even if the file has been replaced on disk.
+pub fn inspect() -> std::io::Result<Exe> {
+    use sha2::Digest as _;
+
+    let image = image()?;
+
+    let mut data = std::fs::read(&image.image_path)?;
+    let sha256 = sha2::Sha256::digest(&data).into();
+
+    // SAFETY: The reference to the static is valid and properly aligned. The
+    // read is volatile since the slot is filled after the build and we do not
+    // want the compiler to assume its value.
+    let slot = unsafe {
+        std::ptr::read_volatile(&EXPECTED_SHA256_SLOT)
+    };
+    let verification = verify(&mut data, &slot);
+
+    let metadata = std::fs::metadata(&image.image_path)?;
+
+    Ok(Exe {
+        immutable: immutable(&image.path),
+        modified: metadata.modified().ok(),
+        changed: changed(&metadata),
+        path: image.path,
+        sha256,
+        verification,
+        replaced: image.replaced,
+    })
+}
+
+/// Location of the running executable image.
+struct Image {
+    /// Path to the executable on disk.
+    path: PathBuf,
+    /// Path through which the running image can be read.
+    image_path: PathBuf,
+    /// Whether the file at `path` is no longer the running image.
+    replaced: Option<bool>,
+}
+
+/// Locates the running executable image.
+#[cfg(target_os = "linux")]
+fn image() -> std::io::Result<Image> {
+    use std::os::unix::ffi::OsStrExt as _;
+    use std::os::unix::fs::MetadataExt as _;
+
+    let image_path = PathBuf::from("/proc/self/exe");
+
+    // The link reports the path the image was executed from with a " (deleted)"
+    // suffix if the file has been unlinked since [1].
+    //
+    // [1]: https://man7.org/linux/man-pages/man5/proc_pid_exe.5.html
+    let link = std::fs::read_link(&image_path)?;
+    let path = match link.as_os_str().as_bytes().strip_suffix(b" (deleted)") {
+        Some(path) => PathBuf::from(std::ffi::OsStr::from_bytes(path)),
+        None => link,
+    };
+
+    // Statting the link gives us the inode of the running image. Writing to a
+    // running executable is not allowed, so the only way to change what is on
+    // disk is to put a different inode at the path.
+    let image_metadata = std::fs::metadata(&image_path)?;
+    let replaced = match std::fs::metadata(&path) {
+        Ok(metadata) => {
+            metadata.dev() != image_metadata.dev() ||
+            metadata.ino() != image_metadata.ino()
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => true,
+        Err(error) => return Err(error),
+    };
+
+    Ok(Image {
+        path,
+        image_path,
+        replaced: Some(replaced),
+    })
+}
+
+/// Locates the running executable image.
+#[cfg(not(target_os = "linux"))]
+fn image() -> std::io::Result<Image> {
+    // There is no portable way of reading the running image, so we read the
+    // module path and cannot tell whether it has been replaced.
+    let path = std::env::current_exe()?;
+
+    Ok(Image {
+        image_path: path.clone(),
+        path,
+        replaced: None,
+    })
+}
+
+/// Verifies the given executable data against the expected digest slot.
+///
+/// The slot within the data is zeroed in the process.
+fn verify(data: &mut [u8], slot: &[u8; SLOT_LEN]) -> Verification {
+    use sha2::Digest as _;
+
+    let (marker, expected) = slot.split_at(MARKER_LEN);
+    if expected.iter().all(|byte| *byte == 0) {
+        return Verification::Unavailable;
+    }
+
+    // If the marker is missing, the data is hashed as it is and the digest is
+    // not going to match.
+    if let Some(offset) = data.windows(MARKER_LEN).position(|window| window == marker) {
+        let start = offset + MARKER_LEN;
+        let end = usize::min(start + 32, data.len());
+        data[start..end].fill(0);
+    }
+
+    let actual = <[u8; 32]>::from(sha2::Sha256::digest(&*data));
+    let expected = <[u8; 32]>::try_from(expected)
+        .expect("invalid digest length");
+
+    if actual == expected {
+        Verification::Match
+    } else {
+        Verification::Mismatch {
+            expected,
+            actual,
+        }
+    }
+}
+
+/// Returns the time at which metadata of the file was last changed.
+#[cfg(target_family = "unix")]
+fn changed(metadata: &std::fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt as _;
+
+    let secs = u64::try_from(metadata.ctime()).ok()?;
+    let nanos = u32::try_from(metadata.ctime_nsec()).ok()?;
+
+    Some(std::time::UNIX_EPOCH + std::time::Duration::new(secs, nanos))
+}
+
+/// Returns the time at which metadata of the file was last changed.
+#[cfg(target_family = "windows")]
+fn changed(_: &std::fs::Metadata) -> Option<SystemTime> {
+    // The change time is not exposed through the standard library on Windows.
+    None
+}
+
+/// Returns whether the file at the given path has the immutable flag set.
+#[cfg(target_os = "linux")]
+fn immutable(path: &std::path::Path) -> Option<bool> {
+    /// Flag of files that cannot be modified, unlinked or renamed [1].
+    ///
+    /// [1]: https://man7.org/linux/man-pages/man2/ioctl_iflags.2.html
+    const FS_IMMUTABLE_FL: u32 = 0x00000010;
+
+    match ospect::fs::linux::flags(path) {
+        Ok(flags) => Some(flags & FS_IMMUTABLE_FL != 0),
+        Err(error) => {
+            // Not all filesystems support flags, so this is not worth more than
+            // a debug message.
+            log::debug!("failed to get flags of '{}': {error}", path.display());
+            None
+        }
+    }
+}
+
+/// Returns whether the file at the given path has the immutable flag set.
+#[cfg(not(target_os = "linux"))]
+fn immutable(_: &std::path::Path) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn inspect_sha256() {
+        use sha2::Digest as _;
+
+        let exe = inspect()
+            .unwrap();
+
+        let data = std::fs::read(std::env::current_exe().unwrap())
+            .unwrap();
+        assert_eq!(exe.sha256, <[u8; 32]>::from(sha2::Sha256::digest(&data)));
+    }
+
+    #[test]
+    fn inspect_path() {
+        let exe = inspect()
+            .unwrap();
+
+        assert_eq!(exe.path, std::env::current_exe().unwrap());
+        assert!(exe.modified.is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn inspect_not_replaced() {
+        let exe = inspect()
+            .unwrap();
+
+        assert_eq!(exe.replaced, Some(false));
+    }
+
+    #[test]
+    fn inspect_unavailable() {
+        // Test binaries are never patched with the expected digest.
+        let exe = inspect()
+            .unwrap();
+
+        assert_eq!(exe.verification, Verification::Unavailable);
+    }
+
+    #[test]
+    fn verify_match() {
+        use sha2::Digest as _;
+
+        let mut data = b"foo".to_vec();
+        data.extend_from_slice(&test_slot([0; 32]));
+        data.extend_from_slice(b"bar");
+
+        // This is what the packaging step does: hash the data with the empty
+        // slot and then fill the slot with the digest.
+        let expected = <[u8; 32]>::from(sha2::Sha256::digest(&data));
+        data[3 + MARKER_LEN..3 + SLOT_LEN].copy_from_slice(&expected);
+
+        assert_eq!(verify(&mut data, &test_slot(expected)), Verification::Match);
+    }
+
+    #[test]
+    fn verify_mismatch() {
+        let mut data = b"foo".to_vec();
+        data.extend_from_slice(&test_slot([0xff; 32]));
+
+        let verification = verify(&mut data, &test_slot([0xff; 32]));
+        assert!(matches!(verification, Verification::Mismatch { expected, .. } if expected == [0xff; 32]));
+    }
+
+    #[test]
+    fn verify_missing_marker() {
+        let mut data = b"foobar".to_vec();
+
+        let verification = verify(&mut data, &test_slot([0xff; 32]));
+        assert!(matches!(verification, Verification::Mismatch { .. }));
+    }
+
+    #[test]
+    fn verify_unavailable() {
+        let mut data = b"foobar".to_vec();
+
+        assert_eq!(verify(&mut data, &test_slot([0; 32])), Verification::Unavailable);
+    }
+
+    /// Returns a slot with a test marker followed by the given digest.
+    ///
+    /// A different marker than the real one is used so that the data of tests
+    /// cannot be confused with the actual slot.
+    fn test_slot(digest: [u8; 32]) -> [u8; SLOT_LEN] {
+        let mut slot = [0; SLOT_LEN];
+        slot[..MARKER_LEN].copy_from_slice(b"rrg-test-marker\0");
+        slot[MARKER_LEN..].copy_from_slice(&digest);
+        slot
+    }
+}
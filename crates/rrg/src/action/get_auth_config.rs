@@ -0,0 +1,326 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+#[cfg(target_family = "unix")]
+mod passwd;
+
+#[cfg(target_family = "unix")]
+mod sshd;
+
+#[cfg(target_family = "unix")]
+mod sudoers;
+
+#[cfg(target_family = "unix")]
+use std::path::{Path, PathBuf};
+
+/// Path to the file with user accounts.
+#[cfg(target_family = "unix")]
+const PASSWD_PATH: &str = "/etc/passwd";
+
+/// Path to the file with groups.
+#[cfg(target_family = "unix")]
+const GROUP_PATH: &str = "/etc/group";
+
+/// Paths to files with password hashes.
+///
+/// These are never read, we only verify whether they can be opened so that
+/// missing permissions are reported.
+#[cfg(target_family = "unix")]
+const SHADOW_PATHS: &[&str] = &[
+    "/etc/shadow",
+    "/etc/gshadow",
+    // Used by macOS and BSDs instead of the shadow file.
+    "/etc/master.passwd",
+];
+
+/// Path to the main sudoers file.
+#[cfg(target_family = "unix")]
+const SUDOERS_PATH: &str = "/etc/sudoers";
+
+/// Path to the main SSH daemon configuration file.
+#[cfg(target_family = "unix")]
+const SSHD_CONFIG_PATH: &str = "/etc/ssh/sshd_config";
+
+/// A result of the `get_auth_config` action.
+#[cfg(target_family = "unix")]
+#[derive(Debug)]
+enum Item {
+    /// User account defined in the passwd file.
+    User(User),
+    /// Group defined in the group file.
+    Group(Group),
+    /// Rule defined in sudoers.
+    SudoRule(SudoRule),
+    /// Effective option of the SSH daemon configuration.
+    SshdOption(SshdOption),
+    /// Configuration file that exists but could not be read.
+    UnreadableFile {
+        /// Path to the file.
+        path: PathBuf,
+        /// Error that occurred when opening or reading the file.
+        error: std::io::Error,
+    },
+}
+
+/// User account defined in the passwd file.
+#[cfg(target_family = "unix")]
+#[derive(Debug, PartialEq, Eq)]
+struct User {
+    /// Name of the user.
+    name: String,
+    /// Numeric identifier of the user.
+    uid: u32,
+    /// Numeric identifier of the primary group of the user.
+    gid: u32,
+    /// Free-form information about the user.
+    gecos: String,
+    /// Home directory of the user.
+    home: PathBuf,
+    /// Login shell of the user.
+    shell: PathBuf,
+}
+
+/// Group defined in the group file.
+#[cfg(target_family = "unix")]
+#[derive(Debug, PartialEq, Eq)]
+struct Group {
+    /// Name of the group.
+    name: String,
+    /// Numeric identifier of the group.
+    gid: u32,
+    /// Names of supplementary members of the group.
+    members: Vec<String>,
+}
+
+/// Rule (for a single command) defined in sudoers.
+#[cfg(target_family = "unix")]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SudoRule {
+    /// Path to the file in which the rule is defined.
+    path: PathBuf,
+    /// Number of the line (starting from 1) at which the rule is defined.
+    line: usize,
+    /// Users that the rule applies to.
+    users: Vec<String>,
+    /// Hosts on which the rule applies.
+    hosts: Vec<String>,
+    /// Users as which the command can be run.
+    run_as_users: Vec<String>,
+    /// Groups as which the command can be run.
+    run_as_groups: Vec<String>,
+    /// Tags and options of the command.
+    tags: Vec<String>,
+    /// Command allowed or denied by the rule.
+    command: String,
+}
+
+/// Effective option of the SSH daemon configuration.
+#[cfg(target_family = "unix")]
+#[derive(Debug, PartialEq, Eq)]
+struct SshdOption {
+    /// Path to the file in which the option is defined.
+    path: PathBuf,
+    /// Number of the line (starting from 1) at which the option is defined.
+    line: usize,
+    /// Keyword of the option.
+    keyword: String,
+    /// Value of the option as it was written.
+    value: String,
+    /// Criteria of the `Match` block the option is defined within.
+    match_criteria: Option<String>,
+}
+
+/// Handles invocations of the `get_auth_config` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut items = Vec::new();
+
+    self::passwd::users(Path::new(PASSWD_PATH), &mut items);
+    self::passwd::groups(Path::new(GROUP_PATH), &mut items);
+
+    for path in SHADOW_PATHS {
+        probe_file(Path::new(path), &mut items);
+    }
+
+    self::sudoers::rules(Path::new(SUDOERS_PATH), &mut items);
+    self::sshd::options(Path::new(SSHD_CONFIG_PATH), &mut items);
+
+    for item in items {
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `get_auth_config` action.
+#[cfg(not(target_family = "unix"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Reads the configuration file at the given path.
+///
+/// `None` is returned if the file does not exist or cannot be read. In the
+/// latter case, the failure is recorded as an item.
+#[cfg(target_family = "unix")]
+fn read_file(path: &Path, items: &mut Vec<Item>) -> Option<String> {
+    match std::fs::read(path) {
+        Ok(content) => Some(String::from_utf8_lossy(&content).into_owned()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => {
+            items.push(Item::UnreadableFile {
+                path: path.to_path_buf(),
+                error,
+            });
+            None
+        }
+    }
+}
+
+/// Verifies whether the file at the given path can be opened (without reading
+/// it) and records it as an item if it cannot.
+#[cfg(target_family = "unix")]
+fn probe_file(path: &Path, items: &mut Vec<Item>) {
+    match std::fs::File::open(path) {
+        Ok(_) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => items.push(Item::UnreadableFile {
+            path: path.to_path_buf(),
+            error,
+        }),
+    }
+}
+
+#[cfg(target_family = "unix")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_auth_config::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_auth_config::Result::new();
+
+        match self {
+            Item::User(user) => {
+                let user_proto = proto.mut_user();
+                user_proto.set_name(user.name);
+                user_proto.set_uid(user.uid);
+                user_proto.set_gid(user.gid);
+                user_proto.set_gecos(user.gecos);
+                user_proto.set_home(user.home.into());
+                user_proto.set_shell(user.shell.into());
+            }
+            Item::Group(group) => {
+                let group_proto = proto.mut_group();
+                group_proto.set_name(group.name);
+                group_proto.set_gid(group.gid);
+                group_proto.set_members(group.members);
+            }
+            Item::SudoRule(rule) => {
+                let rule_proto = proto.mut_sudo_rule();
+                rule_proto.set_path(crate::path::into_proto(rule.path));
+                rule_proto.set_line(rule.line as u32);
+                rule_proto.set_users(rule.users);
+                rule_proto.set_hosts(rule.hosts);
+                rule_proto.set_run_as_users(rule.run_as_users);
+                rule_proto.set_run_as_groups(rule.run_as_groups);
+                rule_proto.set_tags(rule.tags);
+                rule_proto.set_command(rule.command);
+            }
+            Item::SshdOption(option) => {
+                let option_proto = proto.mut_sshd_option();
+                option_proto.set_path(crate::path::into_proto(option.path));
+                option_proto.set_line(option.line as u32);
+                option_proto.set_keyword(option.keyword);
+                option_proto.set_value(option.value);
+                if let Some(match_criteria) = option.match_criteria {
+                    option_proto.set_match_criteria(match_criteria);
+                }
+            }
+            Item::UnreadableFile { path, error } => {
+                let file_proto = proto.mut_unreadable_file();
+                file_proto.set_path(crate::path::into_proto(path));
+                file_proto.set_permission_denied(error.kind() == std::io::ErrorKind::PermissionDenied);
+                file_proto.set_error(error.to_string());
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "unix")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        // Every Unix system has at least the root user.
+        assert!(session.replies::<Item>().any(|item| match item {
+            Item::User(user) => user.uid == 0,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn read_file_not_found() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut items = Vec::new();
+        assert!(read_file(&tempdir.path().join("foo"), &mut items).is_none());
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn read_file_unreadable() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // Reading a directory fails with an error other than "not found".
+        let mut items = Vec::new();
+        assert!(read_file(tempdir.path(), &mut items).is_none());
+        assert!(matches!(&items[..], [Item::UnreadableFile { path, .. }] if path == tempdir.path()));
+    }
+
+    #[test]
+    fn probe_file_permission_denied() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("shadow");
+        std::fs::write(&path, b"root:$6$secret:19000:0:99999:7:::\n")
+            .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o000))
+            .unwrap();
+
+        let mut items = Vec::new();
+        probe_file(&path, &mut items);
+
+        // Root can open the file regardless of its permissions, in which case
+        // nothing is reported (and the file is not read either way).
+        if std::fs::File::open(&path).is_ok() {
+            assert!(items.is_empty());
+        } else {
+            assert!(matches! {
+                &items[..],
+                [Item::UnreadableFile { error, .. }] if error.kind() == std::io::ErrorKind::PermissionDenied
+            });
+        }
+    }
+}
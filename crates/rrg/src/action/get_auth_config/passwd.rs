@@ -0,0 +1,190 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use super::{Group, Item, User};
+
+/// Collects items for users defined in the passwd file at the given path.
+pub fn users(path: &Path, items: &mut Vec<Item>) {
+    let Some(content) = super::read_file(path, items) else {
+        return;
+    };
+
+    for (line, fields) in records(&content) {
+        match parse_user(&fields) {
+            Some(user) => items.push(Item::User(user)),
+            None => log::warn!("invalid passwd entry at '{}:{line}'", path.display()),
+        }
+    }
+}
+
+/// Collects items for groups defined in the group file at the given path.
+pub fn groups(path: &Path, items: &mut Vec<Item>) {
+    let Some(content) = super::read_file(path, items) else {
+        return;
+    };
+
+    for (line, fields) in records(&content) {
+        match parse_group(&fields) {
+            Some(group) => items.push(Item::Group(group)),
+            None => log::warn!("invalid group entry at '{}:{line}'", path.display()),
+        }
+    }
+}
+
+/// Returns colon-separated records of the given file along with the numbers of
+/// lines (starting from 1) at which they are defined.
+///
+/// Comments, empty lines and NIS compatibility entries (starting with `+` or
+/// `-`) are skipped.
+fn records(content: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    content.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with(['#', '+', '-'])
+        })
+        .map(|(idx, line)| (idx + 1, line.split(':').collect()))
+}
+
+/// Parses a user from fields of a passwd record.
+///
+/// The password field is deliberately ignored.
+fn parse_user(fields: &[&str]) -> Option<User> {
+    let [name, _, uid, gid, gecos, home, shell] = fields else {
+        return None;
+    };
+
+    Some(User {
+        name: String::from(*name),
+        uid: uid.parse().ok()?,
+        gid: gid.parse().ok()?,
+        gecos: String::from(*gecos),
+        home: PathBuf::from(home),
+        shell: PathBuf::from(shell),
+    })
+}
+
+/// Parses a group from fields of a group record.
+///
+/// The password field is deliberately ignored.
+fn parse_group(fields: &[&str]) -> Option<Group> {
+    let [name, _, gid, members] = fields else {
+        return None;
+    };
+
+    Some(Group {
+        name: String::from(*name),
+        gid: gid.parse().ok()?,
+        members: members.split(',')
+            .map(str::trim)
+            .filter(|member| !member.is_empty())
+            .map(String::from)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn users_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("passwd");
+        std::fs::write(&path, b"\
+# Local users.
+root:x:0:0:root:/root:/bin/bash
+alice:$1$oldstylehash:1000:1000:Alice,,,:/home/alice:/bin/zsh
+
++@netgroup::::::
+bob:x:1001:100
+nobody:x:65534:65534:nobody:/nonexistent:/usr/sbin/nologin
+").unwrap();
+
+        let mut items = Vec::new();
+        users(&path, &mut items);
+
+        let users = items.into_iter()
+            .map(|item| match item {
+                Item::User(user) => user,
+                _ => panic!("unexpected item: {item:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(users, vec![
+            User {
+                name: String::from("root"),
+                uid: 0,
+                gid: 0,
+                gecos: String::from("root"),
+                home: PathBuf::from("/root"),
+                shell: PathBuf::from("/bin/bash"),
+            },
+            User {
+                name: String::from("alice"),
+                uid: 1000,
+                gid: 1000,
+                gecos: String::from("Alice,,,"),
+                home: PathBuf::from("/home/alice"),
+                shell: PathBuf::from("/bin/zsh"),
+            },
+            User {
+                name: String::from("nobody"),
+                uid: 65534,
+                gid: 65534,
+                gecos: String::from("nobody"),
+                home: PathBuf::from("/nonexistent"),
+                shell: PathBuf::from("/usr/sbin/nologin"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn groups_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("group");
+        std::fs::write(&path, b"\
+root:x:0:
+sudo:x:27:alice,bob
+users:x:100:bob
+invalid:x:foo:
+").unwrap();
+
+        let mut items = Vec::new();
+        groups(&path, &mut items);
+
+        let groups = items.into_iter()
+            .map(|item| match item {
+                Item::Group(group) => group,
+                _ => panic!("unexpected item: {item:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups, vec![
+            Group {
+                name: String::from("root"),
+                gid: 0,
+                members: vec![],
+            },
+            Group {
+                name: String::from("sudo"),
+                gid: 27,
+                members: vec![String::from("alice"), String::from("bob")],
+            },
+            Group {
+                name: String::from("users"),
+                gid: 100,
+                members: vec![String::from("bob")],
+            },
+        ]);
+    }
+}
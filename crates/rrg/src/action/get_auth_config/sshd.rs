@@ -0,0 +1,347 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::{Item, SshdOption};
+
+/// Maximum depth of nested includes that we follow.
+///
+/// This is the same limit as the one used by the daemon.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Keywords (in lowercase) that can be given multiple times with all values
+/// being effective.
+const MULTI_VALUED_KEYWORDS: &[&str] = &[
+    "acceptenv",
+    "allowgroups",
+    "allowusers",
+    "denygroups",
+    "denyusers",
+    "hostcertificate",
+    "hostkey",
+    "listenaddress",
+    "port",
+    "subsystem",
+];
+
+/// Collects items for effective options of the SSH daemon configuration file
+/// at the given path and all the files it includes.
+pub fn options(path: &Path, items: &mut Vec<Item>) {
+    let mut state = State {
+        // Relative includes are resolved against the directory of the main
+        // configuration file (regardless of which file they are in).
+        base_dir_path: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        match_criteria: None,
+        seen: HashSet::new(),
+    };
+
+    file_options(path, 0, &mut state, items);
+}
+
+/// State of the configuration parser shared across included files.
+struct State {
+    /// Directory against which relative includes are resolved.
+    base_dir_path: PathBuf,
+    /// Criteria of the current `Match` block (if any).
+    match_criteria: Option<String>,
+    /// Keywords (in lowercase) already given in particular `Match` blocks.
+    seen: HashSet<(Option<String>, String)>,
+}
+
+/// Collects items for effective options in the given configuration file.
+///
+/// `depth` is the number of includes that led to this file.
+fn file_options(path: &Path, depth: usize, state: &mut State, items: &mut Vec<Item>) {
+    if depth > MAX_INCLUDE_DEPTH {
+        log::warn!("sshd include depth exceeded at '{}'", path.display());
+        return;
+    }
+
+    let Some(content) = super::read_file(path, items) else {
+        return;
+    };
+
+    // `Match` blocks do not extend beyond the file they are defined in.
+    let match_criteria = state.match_criteria.clone();
+
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((keyword, value)) = split_option(line) else {
+            log::warn!("invalid sshd option at '{}:{}'", path.display(), idx + 1);
+            continue;
+        };
+
+        let keyword_lower = keyword.to_ascii_lowercase();
+        match keyword_lower.as_str() {
+            "match" => {
+                state.match_criteria = Some(String::from(value));
+                continue;
+            }
+            "include" => {
+                for pattern in value.split_whitespace() {
+                    let pattern = state.base_dir_path.join(pattern);
+                    for include_path in include_paths(&pattern) {
+                        file_options(&include_path, depth + 1, state, items);
+                    }
+                }
+                continue;
+            }
+            _ => (),
+        }
+
+        if !MULTI_VALUED_KEYWORDS.contains(&keyword_lower.as_str()) {
+            let key = (state.match_criteria.clone(), keyword_lower);
+            if !state.seen.insert(key) {
+                // The daemon uses the first value, this one is ignored.
+                continue;
+            }
+        }
+
+        items.push(Item::SshdOption(SshdOption {
+            path: path.to_path_buf(),
+            line: idx + 1,
+            keyword: String::from(keyword),
+            value: String::from(value),
+            match_criteria: state.match_criteria.clone(),
+        }));
+    }
+
+    state.match_criteria = match_criteria;
+}
+
+/// Splits the given (trimmed) line into the keyword and the value.
+///
+/// Both `Keyword value` and `Keyword=value` forms are supported.
+fn split_option(line: &str) -> Option<(&str, &str)> {
+    let (keyword, value) = line.split_once(|char: char| char.is_whitespace() || char == '=')?;
+
+    let value = value.trim_start();
+    // The equals sign may be surrounded by whitespace.
+    let value = value.strip_prefix('=').unwrap_or(value).trim();
+
+    if keyword.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    Some((keyword, value))
+}
+
+/// Returns paths to files matching the given include pattern.
+///
+/// Only the last component of the pattern can contain wildcards (which is what
+/// the daemon configuration files use in practice).
+fn include_paths(pattern: &Path) -> Vec<PathBuf> {
+    let Some(name_pattern) = pattern.file_name().and_then(|name| name.to_str()) else {
+        return vec![pattern.to_path_buf()];
+    };
+    if !name_pattern.contains(['*', '?']) {
+        return vec![pattern.to_path_buf()];
+    }
+
+    let dir_path = pattern.parent().unwrap_or(Path::new(""));
+
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list sshd include directory '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(error) => {
+                log::warn!("failed to read sshd include directory entry: {error}");
+                None
+            }
+        })
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // Like with shell globs, hidden files have to be matched explicitly.
+            (!name.starts_with('.') || name_pattern.starts_with('.')) &&
+            glob_match(name_pattern.as_bytes(), name.as_bytes())
+        })
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths
+}
+
+/// Checks whether the given name matches the given wildcard pattern.
+///
+/// The pattern can contain `*` (matching any sequence of characters) and `?`
+/// (matching any single character).
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some((b'*', pattern_rest)), _) => {
+            glob_match(pattern_rest, name) ||
+            (!name.is_empty() && glob_match(pattern, &name[1..]))
+        }
+        (Some((b'?', pattern_rest)), Some((_, name_rest))) => {
+            glob_match(pattern_rest, name_rest)
+        }
+        (Some((pattern_char, pattern_rest)), Some((name_char, name_rest))) => {
+            pattern_char == name_char && glob_match(pattern_rest, name_rest)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn sshd_options(items: Vec<Item>) -> Vec<SshdOption> {
+        items.into_iter()
+            .map(|item| match item {
+                Item::SshdOption(option) => option,
+                _ => panic!("unexpected item: {item:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_option_ok() {
+        assert_eq!(split_option("Port 22"), Some(("Port", "22")));
+        assert_eq!(split_option("Port=22"), Some(("Port", "22")));
+        assert_eq!(split_option("Port = 22"), Some(("Port", "22")));
+        assert_eq!(split_option("Banner \"/etc/issue net\""), Some(("Banner", "\"/etc/issue net\"")));
+        assert_eq!(split_option("Port"), None);
+        assert_eq!(split_option("Port ="), None);
+    }
+
+    #[test]
+    fn glob_match_ok() {
+        assert!(glob_match(b"*.conf", b"50-cloud-init.conf"));
+        assert!(glob_match(b"*", b"foo"));
+        assert!(glob_match(b"f?o", b"foo"));
+        assert!(!glob_match(b"*.conf", b"foo.conf.bak"));
+        assert!(!glob_match(b"f?o", b"fo"));
+    }
+
+    #[test]
+    fn options_first_wins() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("sshd_config");
+        std::fs::write(&path, b"\
+# Global options.
+Port 22
+Port 2222
+PermitRootLogin no
+permitrootlogin yes
+PasswordAuthentication=yes
+InvalidLine
+
+Match User backup
+    PasswordAuthentication no
+    PasswordAuthentication yes
+    ForceCommand /usr/local/bin/backup
+
+Match Group sftp Address 10.0.0.0/8
+    PermitRootLogin prohibit-password
+").unwrap();
+
+        let mut items = Vec::new();
+        options(&path, &mut items);
+
+        let options = sshd_options(items).into_iter()
+            .map(|option| (option.line, option.keyword, option.value, option.match_criteria))
+            .collect::<Vec<_>>();
+
+        assert_eq!(options, vec![
+            (2, String::from("Port"), String::from("22"), None),
+            (3, String::from("Port"), String::from("2222"), None),
+            (4, String::from("PermitRootLogin"), String::from("no"), None),
+            (6, String::from("PasswordAuthentication"), String::from("yes"), None),
+            (10, String::from("PasswordAuthentication"), String::from("no"), Some(String::from("User backup"))),
+            (12, String::from("ForceCommand"), String::from("/usr/local/bin/backup"), Some(String::from("User backup"))),
+            (15, String::from("PermitRootLogin"), String::from("prohibit-password"), Some(String::from("Group sftp Address 10.0.0.0/8"))),
+        ]);
+    }
+
+    #[test]
+    fn options_includes() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("sshd_config"), b"\
+Include sshd_config.d/*.conf
+PermitRootLogin yes
+X11Forwarding yes
+").unwrap();
+
+        std::fs::create_dir(tempdir.path().join("sshd_config.d"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("sshd_config.d").join("50-cloud-init.conf"), b"\
+PasswordAuthentication no
+").unwrap();
+        std::fs::write(tempdir.path().join("sshd_config.d").join("10-hardening.conf"), b"\
+PermitRootLogin no
+Match User alice
+    X11Forwarding no
+").unwrap();
+        std::fs::write(tempdir.path().join("sshd_config.d").join("99-disabled.conf.bak"), b"\
+PermitRootLogin yes
+").unwrap();
+
+        let mut items = Vec::new();
+        options(&tempdir.path().join("sshd_config"), &mut items);
+
+        let options = sshd_options(items);
+        assert_eq!(options.len(), 4);
+
+        assert_eq!(options[0].path, tempdir.path().join("sshd_config.d").join("10-hardening.conf"));
+        assert_eq!(options[0].keyword, "PermitRootLogin");
+        assert_eq!(options[0].value, "no");
+
+        assert_eq!(options[1].keyword, "X11Forwarding");
+        assert_eq!(options[1].match_criteria.as_deref(), Some("User alice"));
+
+        assert_eq!(options[2].path, tempdir.path().join("sshd_config.d").join("50-cloud-init.conf"));
+        assert_eq!(options[2].keyword, "PasswordAuthentication");
+        assert_eq!(options[2].match_criteria, None);
+
+        // The `Match` block ends with the included file, so this is a global
+        // option again.
+        assert_eq!(options[3].path, tempdir.path().join("sshd_config"));
+        assert_eq!(options[3].line, 3);
+        assert_eq!(options[3].keyword, "X11Forwarding");
+        assert_eq!(options[3].value, "yes");
+        assert_eq!(options[3].match_criteria, None);
+    }
+
+    #[test]
+    fn options_include_not_found() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("sshd_config");
+        std::fs::write(&path, b"\
+Include /nonexistent/sshd_config.d/*.conf missing.conf
+UsePAM yes
+").unwrap();
+
+        let mut items = Vec::new();
+        options(&path, &mut items);
+
+        let options = sshd_options(items);
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].keyword, "UsePAM");
+    }
+}
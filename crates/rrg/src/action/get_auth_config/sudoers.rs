@@ -0,0 +1,857 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::{Item, SudoRule};
+
+/// Maximum depth of nested includes that we follow.
+///
+/// Sudo itself gives up at a similar depth, this also guards us against files
+/// that (directly or not) include themselves.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Tags that can precede commands in user specifications.
+const TAGS: &[&str] = &[
+    "EXEC", "NOEXEC",
+    "FOLLOW", "NOFOLLOW",
+    "INTERCEPT", "NOINTERCEPT",
+    "LOG_INPUT", "NOLOG_INPUT",
+    "LOG_OUTPUT", "NOLOG_OUTPUT",
+    "MAIL", "NOMAIL",
+    "PASSWD", "NOPASSWD",
+    "SETENV", "NOSETENV",
+];
+
+/// Options (in the `OPTION=value` form) that can precede commands in user
+/// specifications.
+const OPTIONS: &[&str] = &[
+    "APPARMOR_PROFILE",
+    "CHROOT",
+    "CWD",
+    "LIMITPRIVS",
+    "NOTAFTER",
+    "NOTBEFORE",
+    "PRIVS",
+    "ROLE",
+    "TIMEOUT",
+    "TYPE",
+];
+
+/// Algorithms that can be used for command digests (e.g. `sha256:...`).
+const DIGESTS: &[&str] = &["sha224", "sha256", "sha384", "sha512"];
+
+/// Keywords that start alias definitions.
+const ALIASES: &[&str] = &[
+    "Cmd_Alias",
+    "Cmnd_Alias",
+    "Host_Alias",
+    "Runas_Alias",
+    "User_Alias",
+];
+
+/// Collects items for rules defined in the sudoers file at the given path and
+/// in all the files it includes.
+pub fn rules(path: &Path, items: &mut Vec<Item>) {
+    file_rules(path, 0, items);
+}
+
+/// Collects items for rules defined in the given sudoers file.
+///
+/// `depth` is the number of includes that led to this file.
+fn file_rules(path: &Path, depth: usize, items: &mut Vec<Item>) {
+    if depth > MAX_INCLUDE_DEPTH {
+        log::warn!("sudoers include depth exceeded at '{}'", path.display());
+        return;
+    }
+
+    let Some(content) = super::read_file(path, items) else {
+        return;
+    };
+
+    for (line, text) in logical_lines(&content) {
+        let text = text.trim();
+
+        if let Some(include) = parse_include(text) {
+            match include {
+                Include::File(include_path) => {
+                    file_rules(&resolve(path, include_path), depth + 1, items);
+                }
+                Include::Dir(include_path) => {
+                    for file_path in include_dir_paths(&resolve(path, include_path)) {
+                        file_rules(&file_path, depth + 1, items);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let text = strip_comment(text).trim();
+        if text.is_empty() || is_defaults(text) || is_alias(text) {
+            continue;
+        }
+
+        let rules = match parse_user_spec(text) {
+            Ok(rules) => rules,
+            Err(error) => {
+                log::warn!("invalid sudoers entry at '{}:{line}': {error}", path.display());
+                continue;
+            }
+        };
+
+        for rule in rules {
+            items.push(Item::SudoRule(SudoRule {
+                path: path.to_path_buf(),
+                line,
+                ..rule
+            }));
+        }
+    }
+}
+
+/// Returns logical lines of the given sudoers file along with the numbers of
+/// lines (starting from 1) at which they start.
+///
+/// Lines ending with a backslash are continued on the next line.
+fn logical_lines(content: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+
+    for (idx, line) in content.lines().enumerate() {
+        let (line_num, text) = current.get_or_insert_with(|| (idx + 1, String::new()));
+
+        match line.strip_suffix('\\') {
+            Some(line) => {
+                text.push_str(line);
+                text.push(' ');
+            }
+            None => {
+                text.push_str(line);
+                lines.push((*line_num, std::mem::take(text)));
+                current = None;
+            }
+        }
+    }
+
+    // The file may end with a dangling continuation.
+    if let Some(line) = current {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Include directive of a sudoers file.
+#[derive(Debug, PartialEq, Eq)]
+enum Include<'a> {
+    /// Include of a single file.
+    File(&'a str),
+    /// Include of all files in a directory.
+    Dir(&'a str),
+}
+
+/// Parses an include directive from the given (trimmed) line.
+///
+/// `None` is returned if the line is not an include directive.
+fn parse_include(line: &str) -> Option<Include<'_>> {
+    let line = line.strip_prefix(['#', '@'])?;
+
+    let (keyword, path) = line.split_once(char::is_whitespace)?;
+    let path = path.trim();
+    let path = path.strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+        .unwrap_or(path);
+
+    match keyword {
+        "include" => Some(Include::File(path)),
+        "includedir" => Some(Include::Dir(path)),
+        _ => None,
+    }
+}
+
+/// Resolves the path of an include relative to the including file.
+fn resolve(path: &Path, include_path: &str) -> PathBuf {
+    match path.parent() {
+        Some(parent) => parent.join(include_path),
+        None => PathBuf::from(include_path),
+    }
+}
+
+/// Returns paths of files included by an `includedir` directive.
+///
+/// Like sudo, we skip files with names that contain a dot or end with a tilde
+/// (so that backups and package manager leftovers are not picked up).
+fn include_dir_paths(dir_path: &Path) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list sudoers directory '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(error) => {
+                log::warn!("failed to read sudoers directory entry: {error}");
+                None
+            }
+        })
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            !name.contains('.') && !name.ends_with('~')
+        })
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| !file_type.is_dir()))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths
+}
+
+/// Strips the comment (if any) from the given line.
+///
+/// A hash followed by a digit is not a comment but a numeric user or group
+/// identifier (e.g. `#1000`).
+fn strip_comment(line: &str) -> &str {
+    let mut chars = line.char_indices().peekable();
+    let mut quoted = false;
+
+    while let Some((idx, char)) = chars.next() {
+        match char {
+            '\\' => {
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            '#' if !quoted => match chars.peek() {
+                Some((_, next)) if next.is_ascii_digit() => (),
+                _ => return &line[..idx],
+            },
+            _ => (),
+        }
+    }
+
+    line
+}
+
+/// Checks whether the given line is a `Defaults` entry.
+fn is_defaults(line: &str) -> bool {
+    // `Defaults` can be immediately followed by a binding, e.g. `Defaults:bob`.
+    line.strip_prefix("Defaults")
+        .is_some_and(|rest| rest.starts_with([' ', '\t', ':', '@', '>', '!']))
+}
+
+/// Checks whether the given line is an alias definition.
+fn is_alias(line: &str) -> bool {
+    line.split_once(char::is_whitespace)
+        .is_some_and(|(keyword, _)| ALIASES.contains(&keyword))
+}
+
+/// Token of a sudoers line.
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    /// Word with quotes and escapes already resolved.
+    Word(String),
+    /// One of the special characters (`,`, `=`, `:`, `(` or `)`).
+    Punct(char),
+}
+
+/// Splits the given line into tokens along with their spans in the line.
+fn tokenize(line: &str) -> Result<Vec<(Token, Range<usize>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some(&(start, char)) = chars.peek() {
+        match char {
+            _ if char.is_whitespace() => {
+                chars.next();
+            }
+            ',' | '=' | ':' | '(' | ')' => {
+                chars.next();
+                tokens.push((Token::Punct(char), start..start + 1));
+            }
+            '"' => {
+                chars.next();
+
+                let mut word = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((idx, '"')) => break idx + 1,
+                        Some((_, '\\')) => word.extend(chars.next().map(|(_, char)| char)),
+                        Some((_, char)) => word.push(char),
+                        None => return Err(ParseError::UnterminatedQuote),
+                    }
+                };
+
+                tokens.push((Token::Word(word), start..end));
+            }
+            _ => {
+                let mut word = String::new();
+                let mut end = start;
+
+                while let Some(&(idx, char)) = chars.peek() {
+                    if char.is_whitespace() || matches!(char, ',' | '=' | ':' | '(' | ')' | '"') {
+                        break;
+                    }
+                    chars.next();
+                    end = idx + char.len_utf8();
+
+                    if char == '\\' {
+                        if let Some((idx, char)) = chars.next() {
+                            word.push(char);
+                            end = idx + char.len_utf8();
+                        }
+                    } else {
+                        word.push(char);
+                    }
+                }
+
+                tokens.push((Token::Word(word), start..end));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a user specification into rules (one for each command).
+///
+/// The returned rules have no path and line set.
+fn parse_user_spec(line: &str) -> Result<Vec<SudoRule>, ParseError> {
+    let tokens = tokenize(line)?;
+    let mut parser = Parser {
+        line,
+        tokens: &tokens,
+        pos: 0,
+    };
+
+    let mut rules = Vec::new();
+
+    let users = parser.list()?;
+    if users.is_empty() {
+        return Err(parser.unexpected());
+    }
+
+    loop {
+        let hosts = parser.list()?;
+        if hosts.is_empty() {
+            return Err(parser.unexpected());
+        }
+        parser.expect('=')?;
+
+        // Run-as lists, tags and options carry over to subsequent commands of
+        // the same host specification until they are overridden.
+        let mut run_as_users = Vec::new();
+        let mut run_as_groups = Vec::new();
+        let mut tags = Vec::<String>::new();
+
+        loop {
+            if parser.eat('(') {
+                run_as_users = parser.list()?;
+                run_as_groups = if parser.eat(':') {
+                    parser.list()?
+                } else {
+                    Vec::new()
+                };
+                parser.expect(')')?;
+            }
+
+            loop {
+                if let Some(tag) = parser.keyword(TAGS, ':') {
+                    let base = tag.strip_prefix("NO").unwrap_or(tag);
+                    tags.retain(|other| other.strip_prefix("NO").unwrap_or(other) != base);
+                    tags.push(String::from(tag));
+                } else if let Some(option) = parser.keyword(OPTIONS, '=') {
+                    let value = parser.word().ok_or_else(|| parser.unexpected())?;
+                    let prefix = format!("{option}=");
+                    tags.retain(|other| !other.starts_with(&prefix));
+                    tags.push(format!("{prefix}{value}"));
+                } else {
+                    break;
+                }
+            }
+
+            rules.push(SudoRule {
+                users: users.clone(),
+                hosts: hosts.clone(),
+                run_as_users: run_as_users.clone(),
+                run_as_groups: run_as_groups.clone(),
+                tags: tags.clone(),
+                command: parser.command()?,
+                ..SudoRule::default()
+            });
+
+            if !parser.eat(',') {
+                break;
+            }
+        }
+
+        if parser.is_done() {
+            break;
+        }
+        parser.expect(':')?;
+    }
+
+    Ok(rules)
+}
+
+/// Parser of tokens of a single user specification.
+struct Parser<'a> {
+    /// Line that the tokens come from.
+    line: &'a str,
+    /// Tokens of the line.
+    tokens: &'a [(Token, Range<usize>)],
+    /// Position of the current token.
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+
+    /// Checks whether all the tokens have been consumed.
+    fn is_done(&self) -> bool {
+        self.pos == self.tokens.len()
+    }
+
+    /// Returns the token at the given offset from the current position.
+    fn peek(&self, offset: usize) -> Option<&'a Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| token)
+    }
+
+    /// Consumes the given punctuation character if it is the current token.
+    fn eat(&mut self, punct: char) -> bool {
+        if self.peek(0) == Some(&Token::Punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consumes the given punctuation character or fails.
+    fn expect(&mut self, punct: char) -> Result<(), ParseError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Consumes the current token if it is a word.
+    fn word(&mut self) -> Option<&'a str> {
+        match self.peek(0) {
+            Some(Token::Word(word)) => {
+                self.pos += 1;
+                Some(word)
+            }
+            _ => None,
+        }
+    }
+
+    /// Consumes one of the given keywords if it is followed by the given
+    /// punctuation character (which is consumed as well).
+    fn keyword(&mut self, keywords: &[&'static str], punct: char) -> Option<&'static str> {
+        let Some(Token::Word(word)) = self.peek(0) else {
+            return None;
+        };
+        if self.peek(1) != Some(&Token::Punct(punct)) {
+            return None;
+        }
+
+        let keyword = keywords.iter().find(|keyword| *keyword == word)?;
+        self.pos += 2;
+        Some(keyword)
+    }
+
+    /// Consumes a comma-separated list of words.
+    ///
+    /// Negations written with a space (e.g. `! root`) are joined with the word
+    /// they negate.
+    fn list(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut items = Vec::new();
+
+        loop {
+            let mut item = String::new();
+            while let Some(word) = self.word() {
+                item.push_str(word);
+                if !word.chars().all(|char| char == '!') {
+                    break;
+                }
+            }
+
+            if item.is_empty() {
+                if items.is_empty() {
+                    return Ok(items);
+                }
+                return Err(self.unexpected());
+            }
+            items.push(item);
+
+            if !self.eat(',') {
+                return Ok(items);
+            }
+        }
+    }
+
+    /// Consumes a command (with arguments), returning it as it was written.
+    fn command(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+
+        // Digests are separated from the command with a colon which otherwise
+        // ends the command specification.
+        if let Some(Token::Word(word)) = self.peek(0) {
+            if DIGESTS.contains(&word.as_str()) && self.peek(1) == Some(&Token::Punct(':')) {
+                self.pos += 2;
+            }
+        }
+
+        while let Some(token) = self.peek(0) {
+            if matches!(token, Token::Punct(',' | ':')) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+
+        let span_start = self.tokens[start].1.start;
+        let span_end = self.tokens[self.pos - 1].1.end;
+        Ok(String::from(&self.line[span_start..span_end]))
+    }
+
+    /// Returns an error for the current token.
+    fn unexpected(&self) -> ParseError {
+        match self.tokens.get(self.pos) {
+            Some((_, span)) => ParseError::UnexpectedToken(String::from(&self.line[span.clone()])),
+            None => ParseError::UnexpectedEnd,
+        }
+    }
+}
+
+/// Error that can occur when parsing a user specification.
+#[derive(Debug, PartialEq, Eq)]
+enum ParseError {
+    /// The line contains a quote that is not closed.
+    UnterminatedQuote,
+    /// The line contains a token that is not valid at its position.
+    UnexpectedToken(String),
+    /// The line ends prematurely.
+    UnexpectedEnd,
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+
+        match self {
+            UnterminatedQuote => write!(fmt, "unterminated quote"),
+            UnexpectedToken(token) => write!(fmt, "unexpected token '{token}'"),
+            UnexpectedEnd => write!(fmt, "unexpected end of line"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| String::from(*item)).collect()
+    }
+
+    fn sudo_rules(items: Vec<Item>) -> Vec<SudoRule> {
+        items.into_iter()
+            .map(|item| match item {
+                Item::SudoRule(rule) => rule,
+                _ => panic!("unexpected item: {item:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn logical_lines_continuation() {
+        let lines = logical_lines("\
+foo
+bar \\
+  baz \\
+  quux
+norf
+");
+
+        assert_eq!(lines, vec![
+            (1, String::from("foo")),
+            (2, String::from("bar    baz    quux")),
+            (5, String::from("norf")),
+        ]);
+    }
+
+    #[test]
+    fn parse_include_ok() {
+        assert_eq!(parse_include("#include /etc/sudoers.local"), Some(Include::File("/etc/sudoers.local")));
+        assert_eq!(parse_include("@include \"foo bar\""), Some(Include::File("foo bar")));
+        assert_eq!(parse_include("#includedir /etc/sudoers.d"), Some(Include::Dir("/etc/sudoers.d")));
+        assert_eq!(parse_include("@includedir sudoers.d"), Some(Include::Dir("sudoers.d")));
+    }
+
+    #[test]
+    fn parse_include_comment() {
+        assert_eq!(parse_include("# include files below"), None);
+        assert_eq!(parse_include("#includes are processed later"), None);
+        assert_eq!(parse_include("root ALL=(ALL) ALL"), None);
+    }
+
+    #[test]
+    fn strip_comment_ok() {
+        assert_eq!(strip_comment("root ALL=(ALL) ALL # admin"), "root ALL=(ALL) ALL ");
+        assert_eq!(strip_comment("#1000 ALL=(#0) ALL"), "#1000 ALL=(#0) ALL");
+        assert_eq!(strip_comment("bob ALL=/bin/echo \\# \"#\""), "bob ALL=/bin/echo \\# \"#\"");
+    }
+
+    #[test]
+    fn is_defaults_ok() {
+        assert!(is_defaults("Defaults env_reset"));
+        assert!(is_defaults("Defaults:bob !requiretty"));
+        assert!(is_defaults("Defaults!/bin/ls log_output"));
+        assert!(!is_defaults("DefaultsUser ALL=(ALL) ALL"));
+    }
+
+    #[test]
+    fn parse_user_spec_simple() {
+        let rules = parse_user_spec("root ALL=(ALL:ALL) ALL")
+            .unwrap();
+
+        assert_eq!(rules, vec![SudoRule {
+            users: strings(&["root"]),
+            hosts: strings(&["ALL"]),
+            run_as_users: strings(&["ALL"]),
+            run_as_groups: strings(&["ALL"]),
+            command: String::from("ALL"),
+            ..SudoRule::default()
+        }]);
+    }
+
+    #[test]
+    fn parse_user_spec_no_run_as() {
+        let rules = parse_user_spec("%wheel ALL = /usr/bin/id")
+            .unwrap();
+
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].users, strings(&["%wheel"]));
+        assert!(rules[0].run_as_users.is_empty());
+        assert!(rules[0].run_as_groups.is_empty());
+        assert_eq!(rules[0].command, "/usr/bin/id");
+    }
+
+    #[test]
+    fn parse_user_spec_multiple_commands() {
+        let rules = parse_user_spec("\
+alice, bob, ! mallory ALL = (root) NOPASSWD: /usr/bin/apt update, \
+(www-data) PASSWD: NOEXEC: /usr/bin/vim /var/www/index.html, /bin/ls -l /srv")
+            .unwrap();
+
+        assert_eq!(rules.len(), 3);
+
+        assert_eq!(rules[0].users, strings(&["alice", "bob", "!mallory"]));
+        assert_eq!(rules[0].run_as_users, strings(&["root"]));
+        assert_eq!(rules[0].tags, strings(&["NOPASSWD"]));
+        assert_eq!(rules[0].command, "/usr/bin/apt update");
+
+        assert_eq!(rules[1].run_as_users, strings(&["www-data"]));
+        assert_eq!(rules[1].tags, strings(&["PASSWD", "NOEXEC"]));
+        assert_eq!(rules[1].command, "/usr/bin/vim /var/www/index.html");
+
+        // Run-as list and tags carry over from the previous command.
+        assert_eq!(rules[2].run_as_users, strings(&["www-data"]));
+        assert_eq!(rules[2].tags, strings(&["PASSWD", "NOEXEC"]));
+        assert_eq!(rules[2].command, "/bin/ls -l /srv");
+    }
+
+    #[test]
+    fn parse_user_spec_multiple_hosts() {
+        let rules = parse_user_spec("bob web1, web2 = (: adm) /bin/cat /var/log/syslog : db = ALL")
+            .unwrap();
+
+        assert_eq!(rules.len(), 2);
+
+        assert_eq!(rules[0].hosts, strings(&["web1", "web2"]));
+        assert!(rules[0].run_as_users.is_empty());
+        assert_eq!(rules[0].run_as_groups, strings(&["adm"]));
+        assert_eq!(rules[0].command, "/bin/cat /var/log/syslog");
+
+        // Run-as lists do not carry over to other host specifications.
+        assert_eq!(rules[1].hosts, strings(&["db"]));
+        assert!(rules[1].run_as_groups.is_empty());
+        assert_eq!(rules[1].command, "ALL");
+    }
+
+    #[test]
+    fn parse_user_spec_options_and_digest() {
+        let rules = parse_user_spec("\
+carol ALL = CWD=/tmp TIMEOUT=1h sha256:0123abcd /usr/bin/make, !/usr/bin/su")
+            .unwrap();
+
+        assert_eq!(rules.len(), 2);
+
+        assert_eq!(rules[0].tags, strings(&["CWD=/tmp", "TIMEOUT=1h"]));
+        assert_eq!(rules[0].command, "sha256:0123abcd /usr/bin/make");
+        assert_eq!(rules[1].command, "!/usr/bin/su");
+    }
+
+    #[test]
+    fn parse_user_spec_escaped_command() {
+        let rules = parse_user_spec("dave ALL = /bin/echo a\\,b\\:c, /bin/true")
+            .unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].command, "/bin/echo a\\,b\\:c");
+        assert_eq!(rules[1].command, "/bin/true");
+    }
+
+    #[test]
+    fn parse_user_spec_quoted_user() {
+        let rules = parse_user_spec("\"%domain users\" ALL = ALL")
+            .unwrap();
+
+        assert_eq!(rules[0].users, strings(&["%domain users"]));
+    }
+
+    #[test]
+    fn parse_user_spec_invalid() {
+        assert_eq!(parse_user_spec("alice ALL"), Err(ParseError::UnexpectedEnd));
+        assert_eq!(parse_user_spec("alice ALL = (root"), Err(ParseError::UnexpectedEnd));
+        assert_eq!(parse_user_spec("alice = ALL"), Err(ParseError::UnexpectedToken(String::from("="))));
+        assert_eq!(parse_user_spec("alice ALL = ALL, "), Err(ParseError::UnexpectedEnd));
+        assert_eq!(parse_user_spec("\"alice ALL = ALL"), Err(ParseError::UnterminatedQuote));
+    }
+
+    #[test]
+    fn rules_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("sudoers");
+        std::fs::write(&path, b"\
+# This file MUST be edited with the 'visudo' command as root.
+Defaults        env_reset
+Defaults:bob    !requiretty
+
+User_Alias      ADMINS = alice, \\
+                         bob
+Cmnd_Alias      SHUTDOWN = /sbin/shutdown, /sbin/reboot
+
+root    ALL=(ALL:ALL) ALL
+ADMINS  ALL = (root) NOPASSWD: SHUTDOWN, \\
+              /usr/bin/systemctl restart nginx # web server
+this is not valid
+%sudo   ALL=(ALL:ALL) ALL
+").unwrap();
+
+        let mut items = Vec::new();
+        rules(&path, &mut items);
+
+        let rules = sudo_rules(items);
+        assert_eq!(rules.len(), 4);
+
+        assert_eq!(rules[0].path, path);
+        assert_eq!(rules[0].line, 9);
+        assert_eq!(rules[0].users, strings(&["root"]));
+
+        assert_eq!(rules[1].line, 10);
+        assert_eq!(rules[1].users, strings(&["ADMINS"]));
+        assert_eq!(rules[1].tags, strings(&["NOPASSWD"]));
+        assert_eq!(rules[1].command, "SHUTDOWN");
+
+        assert_eq!(rules[2].line, 10);
+        assert_eq!(rules[2].tags, strings(&["NOPASSWD"]));
+        assert_eq!(rules[2].command, "/usr/bin/systemctl restart nginx");
+
+        assert_eq!(rules[3].line, 13);
+        assert_eq!(rules[3].users, strings(&["%sudo"]));
+    }
+
+    #[test]
+    fn rules_includes() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("sudoers"), b"\
+root ALL=(ALL) ALL
+#include sudoers.local
+@includedir sudoers.d
+").unwrap();
+
+        std::fs::write(tempdir.path().join("sudoers.local"), b"\
+alice ALL=(ALL) ALL
+").unwrap();
+
+        std::fs::create_dir(tempdir.path().join("sudoers.d"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("sudoers.d").join("20-carol"), b"\
+carol ALL=(ALL) ALL
+").unwrap();
+        std::fs::write(tempdir.path().join("sudoers.d").join("10-bob"), b"\
+bob ALL=(ALL) ALL
+").unwrap();
+        std::fs::write(tempdir.path().join("sudoers.d").join("10-bob~"), b"\
+mallory ALL=(ALL) ALL
+").unwrap();
+        std::fs::write(tempdir.path().join("sudoers.d").join("README.md"), b"\
+mallory ALL=(ALL) ALL
+").unwrap();
+
+        let mut items = Vec::new();
+        rules(&tempdir.path().join("sudoers"), &mut items);
+
+        let rules = sudo_rules(items);
+        let users = rules.iter()
+            .map(|rule| (rule.users[0].as_str(), rule.path.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(users, vec![
+            ("root", tempdir.path().join("sudoers")),
+            ("alice", tempdir.path().join("sudoers.local")),
+            ("bob", tempdir.path().join("sudoers.d").join("10-bob")),
+            ("carol", tempdir.path().join("sudoers.d").join("20-carol")),
+        ]);
+    }
+
+    #[test]
+    fn rules_include_cycle() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("sudoers");
+        std::fs::write(&path, b"\
+root ALL=(ALL) ALL
+#include sudoers
+").unwrap();
+
+        let mut items = Vec::new();
+        rules(&path, &mut items);
+
+        assert_eq!(items.len(), MAX_INCLUDE_DEPTH + 1);
+    }
+
+    #[test]
+    fn rules_not_found() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut items = Vec::new();
+        rules(&tempdir.path().join("sudoers"), &mut items);
+
+        assert!(items.is_empty());
+    }
+}
@@ -0,0 +1,578 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the browser artifacts action.
+//!
+//! Browsers keep their databases open (and locked) while running, so they are
+//! never read in place: they are copied to a temporary file first which is then
+//! opened as immutable. This means that changes not yet checkpointed from the
+//! write-ahead log are not visible, which is a price for not interfering with
+//! the running browser.
+
+mod chromium;
+mod firefox;
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::warn;
+
+/// Directories (relative to the home directory) with browser profiles.
+#[cfg(target_os = "linux")]
+const PROFILES_DIRS: &[(Browser, &str)] = &[
+    (Browser::Chrome, ".config/google-chrome"),
+    (Browser::Chromium, ".config/chromium"),
+    (Browser::Edge, ".config/microsoft-edge"),
+    (Browser::Firefox, ".mozilla/firefox"),
+];
+
+/// Directories (relative to the home directory) with browser profiles.
+#[cfg(target_os = "macos")]
+const PROFILES_DIRS: &[(Browser, &str)] = &[
+    (Browser::Chrome, "Library/Application Support/Google/Chrome"),
+    (Browser::Chromium, "Library/Application Support/Chromium"),
+    (Browser::Edge, "Library/Application Support/Microsoft Edge"),
+    (Browser::Firefox, "Library/Application Support/Firefox/Profiles"),
+];
+
+/// Directories (relative to the home directory) with browser profiles.
+#[cfg(target_family = "windows")]
+const PROFILES_DIRS: &[(Browser, &str)] = &[
+    (Browser::Chrome, "AppData\\Local\\Google\\Chrome\\User Data"),
+    (Browser::Chromium, "AppData\\Local\\Chromium\\User Data"),
+    (Browser::Edge, "AppData\\Local\\Microsoft\\Edge\\User Data"),
+    (Browser::Firefox, "AppData\\Roaming\\Mozilla\\Firefox\\Profiles"),
+];
+
+/// Arguments of the `get_browser_artifacts` action.
+pub struct Args {
+    /// Whether to collect visits from the browsing history.
+    history: bool,
+    /// Whether to collect installed extensions.
+    extensions: bool,
+}
+
+/// A result of the `get_browser_artifacts` action.
+#[derive(Debug)]
+struct Item {
+    /// Name of the user whose profile the artifact was collected from.
+    user: String,
+    /// Browser to which the profile belongs.
+    browser: Browser,
+    /// Path to the profile directory.
+    profile_path: PathBuf,
+    /// Collected artifact.
+    artifact: Artifact,
+}
+
+/// Browser that artifacts are collected from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Browser {
+    Chrome,
+    Chromium,
+    Edge,
+    Firefox,
+}
+
+/// Artifact collected from a browser profile.
+#[derive(Debug, PartialEq, Eq)]
+enum Artifact {
+    /// Visit of a page from the browsing history.
+    Visit(Visit),
+    /// Installed extension.
+    Extension(Extension),
+}
+
+/// Visit of a page from the browsing history.
+#[derive(Debug, PartialEq, Eq)]
+struct Visit {
+    /// URL of the visited page.
+    url: String,
+    /// Title of the visited page (if known).
+    title: Option<String>,
+    /// Time at which the page was visited (if known).
+    time: Option<SystemTime>,
+}
+
+/// Extension installed in a browser profile.
+#[derive(Debug, PartialEq, Eq)]
+struct Extension {
+    /// Identifier of the extension.
+    id: String,
+    /// Name of the extension (if known).
+    name: Option<String>,
+    /// Version of the extension (if known).
+    version: Option<String>,
+}
+
+impl Browser {
+
+    /// Returns the name of the file that every profile of the browser has.
+    ///
+    /// This is used to tell profile directories from other directories that
+    /// browsers keep next to them.
+    fn profile_marker(self) -> &'static str {
+        match self {
+            Browser::Chrome | Browser::Chromium | Browser::Edge => chromium::HISTORY_FILE_NAME,
+            Browser::Firefox => firefox::PLACES_FILE_NAME,
+        }
+    }
+}
+
+/// Handles invocations of the `get_browser_artifacts` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let users = ospect::user::users()
+        .map_err(crate::session::Error::action)?;
+
+    for user in users {
+        let user = match user {
+            Ok(user) => user,
+            Err(error) => {
+                warn!("failed to obtain user information: {error}");
+                continue;
+            }
+        };
+
+        let Some(home_dir) = user.home_dir() else {
+            continue;
+        };
+
+        let user_name = user.name().to_string_lossy().into_owned();
+
+        for (browser, profiles_dir) in PROFILES_DIRS {
+            for profile_path in profiles(&home_dir.join(profiles_dir), *browser) {
+                for artifact in artifacts(*browser, &profile_path, &args) {
+                    session.reply(Item {
+                        user: user_name.clone(),
+                        browser: *browser,
+                        profile_path: profile_path.clone(),
+                        artifact,
+                    })?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns paths to all profiles of the browser in the given directory.
+fn profiles(profiles_dir: &Path, browser: Browser) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(profiles_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            warn!("failed to list profiles in '{}': {error}", profiles_dir.display());
+            return Vec::new();
+        }
+    };
+
+    let mut profiles = entries
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry.path()),
+            Err(error) => {
+                warn!("failed to list profile in '{}': {error}", profiles_dir.display());
+                None
+            }
+        })
+        .filter(|path| path.join(browser.profile_marker()).is_file())
+        .collect::<Vec<_>>();
+    profiles.sort();
+
+    profiles
+}
+
+/// Collects artifacts requested by the arguments from the given profile.
+///
+/// Failures to collect artifacts of some kind are only logged so that they do
+/// not prevent collecting the other ones.
+fn artifacts(browser: Browser, profile_path: &Path, args: &Args) -> Vec<Artifact> {
+    let mut artifacts = Vec::new();
+
+    if args.history {
+        let visits = match browser {
+            Browser::Chrome | Browser::Chromium | Browser::Edge => {
+                query(&profile_path.join(chromium::HISTORY_FILE_NAME), chromium::visits)
+            }
+            Browser::Firefox => {
+                query(&profile_path.join(firefox::PLACES_FILE_NAME), firefox::visits)
+            }
+        };
+
+        match visits {
+            Ok(visits) => artifacts.extend(visits.into_iter().map(Artifact::Visit)),
+            Err(error) => {
+                warn!("failed to collect history of '{}': {error}", profile_path.display());
+            }
+        }
+    }
+
+    if args.extensions {
+        let extensions = match browser {
+            Browser::Chrome | Browser::Chromium | Browser::Edge => {
+                chromium::extensions(profile_path)
+            }
+            Browser::Firefox => firefox::extensions(profile_path),
+        };
+
+        match extensions {
+            Ok(extensions) => artifacts.extend(extensions.into_iter().map(Artifact::Extension)),
+            Err(error) => {
+                warn!("failed to collect extensions of '{}': {error}", profile_path.display());
+            }
+        }
+    }
+
+    artifacts
+}
+
+/// Runs the given query against a copy of the SQLite database at `path`.
+fn query<T, F>(path: &Path, query: F) -> Result<Vec<T>, QueryError>
+where
+    F: FnOnce(&rusqlite::Connection) -> rusqlite::Result<Vec<T>>,
+{
+    use rusqlite::OpenFlags;
+
+    let copy = TempCopy::new(path)
+        .map_err(QueryError::Copy)?;
+
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY |
+        OpenFlags::SQLITE_OPEN_URI |
+        OpenFlags::SQLITE_OPEN_NO_MUTEX;
+
+    // The copy is not going to change while we read it, so it is safe to open
+    // it as immutable. This way SQLite does not try to lock it or to look for
+    // the journal of the original database.
+    let conn = rusqlite::Connection::open_with_flags(copy.uri()?, flags)
+        .map_err(QueryError::Sqlite)?;
+
+    query(&conn).map_err(QueryError::Sqlite)
+}
+
+/// Temporary copy of a file that is deleted once dropped.
+struct TempCopy {
+    /// Path to the copy.
+    path: PathBuf,
+}
+
+impl TempCopy {
+
+    /// Copies the file at the given path to a new temporary file.
+    ///
+    /// The file is opened for reading in the same way as all other files read
+    /// by the agent, which on Windows means that it can be opened even if the
+    /// browser holds it open for writing.
+    fn new(path: &Path) -> std::io::Result<TempCopy> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let mut file = crate::fs::open_file(path, crate::fs::SymlinkPolicy::Follow)?;
+
+        let copy = TempCopy {
+            path: std::env::temp_dir().join(format! {
+                "rrg-browser-{}-{}.sqlite",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+            }),
+        };
+
+        let mut copy_file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&copy.path)?;
+        std::io::copy(&mut file, &mut copy_file)?;
+
+        Ok(copy)
+    }
+
+    /// Returns the SQLite URI that opens the copy as an immutable database.
+    ///
+    /// See the [SQLite documentation][1] for details on the format.
+    ///
+    /// [1]: https://www.sqlite.org/uri.html
+    fn uri(&self) -> Result<String, QueryError> {
+        let path = self.path.to_str()
+            .ok_or(QueryError::Uri)?;
+
+        let mut uri = String::from("file:");
+        if cfg!(target_family = "windows") {
+            uri.push('/');
+        }
+
+        for char in path.chars() {
+            match char {
+                '\\' if cfg!(target_family = "windows") => uri.push('/'),
+                '?' | '#' | '%' => uri.push_str(&format!("%{:02X}", u32::from(char))),
+                _ => uri.push(char),
+            }
+        }
+
+        Ok(format!("{uri}?immutable=1"))
+    }
+}
+
+impl Drop for TempCopy {
+
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_file(&self.path) {
+            warn!("failed to remove temporary copy '{}': {error}", self.path.display());
+        }
+    }
+}
+
+/// An error that can occur when querying a browser database.
+#[derive(Debug)]
+enum QueryError {
+    /// The database could not be copied.
+    Copy(std::io::Error),
+    /// The path to the copy cannot be expressed as an SQLite URI.
+    Uri,
+    /// The copy could not be opened or queried.
+    Sqlite(rusqlite::Error),
+}
+
+impl std::fmt::Display for QueryError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            QueryError::Copy(error) => write!(fmt, "failed to copy database: {error}"),
+            QueryError::Uri => write!(fmt, "non-Unicode temporary path"),
+            QueryError::Sqlite(error) => write!(fmt, "failed to query database: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QueryError::Copy(error) => Some(error),
+            QueryError::Uri => None,
+            QueryError::Sqlite(error) => Some(error),
+        }
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_browser_artifacts::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use rrg_proto::get_browser_artifacts::Kind as Proto;
+
+        if proto.kinds.is_empty() {
+            return Ok(Args {
+                history: true,
+                extensions: true,
+            });
+        }
+
+        let mut args = Args {
+            history: false,
+            extensions: false,
+        };
+
+        for kind in proto.kinds {
+            match kind.enum_value() {
+                Ok(Proto::HISTORY) => args.history = true,
+                Ok(Proto::EXTENSIONS) => args.extensions = true,
+                Ok(Proto::UNSPECIFIED) | Err(_) => {
+                    return Err(crate::request::ParseArgsError::invalid_field("kinds", KindError {
+                        value: kind.value(),
+                    }));
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_browser_artifacts::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::get_browser_artifacts::Browser as Proto;
+
+        let mut proto = rrg_proto::get_browser_artifacts::Result::new();
+        proto.set_user(self.user);
+        proto.set_browser(match self.browser {
+            Browser::Chrome => Proto::CHROME,
+            Browser::Chromium => Proto::CHROMIUM,
+            Browser::Edge => Proto::EDGE,
+            Browser::Firefox => Proto::FIREFOX,
+        });
+        proto.set_profile_path(crate::path::into_proto(self.profile_path));
+
+        match self.artifact {
+            Artifact::Visit(visit) => {
+                let visit_proto = proto.mut_visit();
+                visit_proto.set_url(visit.url);
+                if let Some(title) = visit.title {
+                    visit_proto.set_title(title);
+                }
+                if let Some(time) = visit.time {
+                    visit_proto.set_time(rrg_proto::into_timestamp(time));
+                }
+            }
+            Artifact::Extension(extension) => {
+                let extension_proto = proto.mut_extension();
+                extension_proto.set_id(extension.id);
+                if let Some(name) = extension.name {
+                    extension_proto.set_name(name);
+                }
+                if let Some(version) = extension.version {
+                    extension_proto.set_version(version);
+                }
+            }
+        }
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an unknown kind.
+#[derive(Debug)]
+struct KindError {
+    /// Raw value of the kind.
+    value: i32,
+}
+
+impl std::fmt::Display for KindError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown browser artifact kind: {}", self.value)
+    }
+}
+
+impl std::error::Error for KindError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn profiles_with_marker() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("Default"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("Default").join("History"), b"")
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("Crashpad"))
+            .unwrap();
+
+        assert_eq! {
+            profiles(tempdir.path(), Browser::Chrome),
+            vec![tempdir.path().join("Default")],
+        };
+        assert!(profiles(tempdir.path(), Browser::Firefox).is_empty());
+    }
+
+    #[test]
+    fn profiles_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(profiles(&tempdir.path().join("foo"), Browser::Chrome).is_empty());
+    }
+
+    #[test]
+    fn artifacts_extensions_only() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // The history file is not a valid database, so if it was queried, the
+        // failure would be only logged.
+        std::fs::write(tempdir.path().join("History"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("extensions.json"), br#"{
+            "addons": [{ "id": "foo@example.com", "type": "extension" }]
+        }"#).unwrap();
+
+        let args = Args {
+            history: false,
+            extensions: true,
+        };
+
+        assert_eq!(artifacts(Browser::Firefox, tempdir.path(), &args), vec![
+            Artifact::Extension(Extension {
+                id: String::from("foo@example.com"),
+                name: None,
+                version: None,
+            }),
+        ]);
+    }
+
+    #[test]
+    fn query_removes_copy() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.sqlite");
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE foo (bar INTEGER); INSERT INTO foo VALUES (42);")
+            .unwrap();
+
+        let mut copy_path = None;
+        let values = query(&path, |conn| {
+            let path = conn.path()
+                .unwrap();
+            copy_path = Some(PathBuf::from(path));
+
+            conn.prepare("SELECT bar FROM foo")?
+                .query_map([], |row| row.get::<_, i64>(0))?
+                .collect()
+        }).unwrap();
+
+        assert_eq!(values, vec![42]);
+
+        let copy_path = copy_path.unwrap();
+        assert_ne!(copy_path, path);
+        assert!(!copy_path.exists());
+    }
+
+    #[test]
+    fn query_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.sqlite");
+        assert!(matches! {
+            query(&path, |_| Ok(Vec::<()>::new())),
+            Err(QueryError::Copy(_))
+        });
+    }
+
+    #[test]
+    fn args_from_proto_empty() {
+        use crate::request::Args as _;
+
+        let args = Args::from_proto(rrg_proto::get_browser_artifacts::Args::new())
+            .unwrap();
+        assert!(args.history);
+        assert!(args.extensions);
+    }
+
+    #[test]
+    fn args_from_proto_unspecified() {
+        use crate::request::Args as _;
+        use rrg_proto::get_browser_artifacts::Kind;
+
+        let mut proto = rrg_proto::get_browser_artifacts::Args::new();
+        proto.kinds.push(Kind::UNSPECIFIED.into());
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
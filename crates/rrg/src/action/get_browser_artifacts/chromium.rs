@@ -0,0 +1,315 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parsers of artifacts of Chromium-based browsers (Chrome, Chromium, Edge).
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use super::{Extension, Visit};
+
+/// Name of the history database file in the profile directory.
+pub const HISTORY_FILE_NAME: &str = "History";
+
+/// Name of the directory with unpacked extensions in the profile directory.
+const EXTENSIONS_DIR_NAME: &str = "Extensions";
+
+/// Number of seconds between the WebKit epoch (1601-01-01) and the Unix one.
+const WEBKIT_EPOCH_OFFSET_SECS: u64 = 11_644_473_600;
+
+/// Returns all visits from the history database.
+///
+/// Only the columns known to be present in all versions of the schema are
+/// selected, so that new columns do not break the query.
+pub fn visits(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<Visit>> {
+    let mut stmt = conn.prepare(r"
+        SELECT urls.url, urls.title, visits.visit_time
+          FROM visits
+          JOIN urls ON urls.id = visits.url
+      ORDER BY visits.visit_time
+    ")?;
+
+    let visits = stmt.query_map([], |row| {
+        Ok(Visit {
+            url: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.filter(|title| !title.is_empty()),
+            time: row.get::<_, Option<i64>>(2)?.and_then(webkit_time),
+        })
+    })?;
+
+    visits.collect()
+}
+
+/// Returns all extensions unpacked in the given profile directory.
+///
+/// Every extension has a directory named after its identifier with a directory
+/// for each of its versions with the manifest inside.
+pub fn extensions(profile_path: &Path) -> std::io::Result<Vec<Extension>> {
+    let extensions_path = profile_path.join(EXTENSIONS_DIR_NAME);
+
+    let ids = match std::fs::read_dir(&extensions_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let mut extensions = Vec::new();
+
+    for id in ids {
+        let id = id?;
+        if !id.file_type()?.is_dir() {
+            continue;
+        }
+
+        for version in std::fs::read_dir(id.path())? {
+            let version = version?;
+            if !version.file_type()?.is_dir() {
+                continue;
+            }
+
+            extensions.push(extension(
+                &id.file_name().to_string_lossy(),
+                &version.path(),
+            ));
+        }
+    }
+
+    extensions.sort_by(|lhs, rhs| (&lhs.id, &lhs.version).cmp(&(&rhs.id, &rhs.version)));
+
+    Ok(extensions)
+}
+
+/// Returns information about the extension unpacked at the given path.
+///
+/// If the manifest cannot be read, only the identifier and the version (from
+/// the directory name) are reported.
+fn extension(id: &str, path: &Path) -> Extension {
+    // Names of version directories have the `<version>_<n>` format.
+    let dir_version = path.file_name()
+        .map(|name| name.to_string_lossy())
+        .map(|name| match name.rsplit_once('_') {
+            Some((version, _)) => String::from(version),
+            None => name.into_owned(),
+        });
+
+    let manifest = match json::parse_file(path.join("manifest.json")) {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            log::warn!("failed to read manifest of extension '{id}': {error}");
+            return Extension {
+                id: String::from(id),
+                name: None,
+                version: dir_version,
+            };
+        }
+    };
+
+    let name = manifest.get("name")
+        .and_then(json::Value::as_str)
+        .map(|name| localize(path, &manifest, name));
+
+    let version = manifest.get("version")
+        .and_then(json::Value::as_str)
+        .map(String::from)
+        .or(dir_version);
+
+    Extension {
+        id: String::from(id),
+        name,
+        version,
+    }
+}
+
+/// Resolves the given string of the manifest to its default locale version.
+///
+/// Localizable strings have the `__MSG_<key>__` format where the key refers to
+/// the message in the `messages.json` file of the locale (keys are matched
+/// case-insensitively). If the string cannot be resolved, it is returned as it
+/// is.
+fn localize(path: &Path, manifest: &json::Value, string: &str) -> String {
+    let Some(key) = string.strip_prefix("__MSG_").and_then(|key| key.strip_suffix("__")) else {
+        return String::from(string);
+    };
+
+    let Some(locale) = manifest.get("default_locale").and_then(json::Value::as_str) else {
+        return String::from(string);
+    };
+
+    let messages_path = path.join("_locales").join(locale).join("messages.json");
+    let messages = match json::parse_file(&messages_path) {
+        Ok(messages) => messages,
+        Err(error) => {
+            log::warn!("failed to read '{}': {error}", messages_path.display());
+            return String::from(string);
+        }
+    };
+
+    messages.as_object()
+        .and_then(|messages| {
+            messages.iter().find(|(name, _)| name.eq_ignore_ascii_case(key))
+        })
+        .and_then(|(_, message)| message.get("message"))
+        .and_then(json::Value::as_str)
+        .map(String::from)
+        .unwrap_or_else(|| String::from(string))
+}
+
+/// Converts a WebKit timestamp (microseconds since 1601-01-01) to system time.
+///
+/// Zero means that the time is not known.
+fn webkit_time(micros: i64) -> Option<SystemTime> {
+    let micros = u64::try_from(micros).ok().filter(|micros| *micros != 0)?;
+    let since_webkit_epoch = Duration::from_micros(micros);
+
+    let since_unix_epoch = since_webkit_epoch
+        .checked_sub(Duration::from_secs(WEBKIT_EPOCH_OFFSET_SECS))?;
+
+    SystemTime::UNIX_EPOCH.checked_add(since_unix_epoch)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Creates a history database fixture at the given path.
+    ///
+    /// The schema is trimmed down to the parts we use but it has some extra
+    /// columns to verify that unknown ones are ignored.
+    fn history_fixture(path: &Path) {
+        let conn = rusqlite::Connection::open(path)
+            .unwrap();
+
+        conn.execute_batch(r"
+            CREATE TABLE urls (
+                id INTEGER PRIMARY KEY,
+                url LONGVARCHAR,
+                title LONGVARCHAR,
+                visit_count INTEGER DEFAULT 0 NOT NULL,
+                hidden INTEGER DEFAULT 0 NOT NULL
+            );
+            CREATE TABLE visits (
+                id INTEGER PRIMARY KEY,
+                url INTEGER NOT NULL,
+                visit_time INTEGER NOT NULL,
+                from_visit INTEGER,
+                transition INTEGER DEFAULT 0 NOT NULL,
+                is_known_to_sync BOOLEAN DEFAULT FALSE NOT NULL
+            );
+            INSERT INTO urls (id, url, title, visit_count)
+                VALUES (1, 'https://example.com/', 'Example', 2),
+                       (2, 'https://example.org/', '', 1);
+            INSERT INTO visits (id, url, visit_time)
+                VALUES (1, 1, 13348540800000000),
+                       (2, 2, 13348540801000000),
+                       (3, 1, 0);
+        ").unwrap();
+    }
+
+    #[test]
+    fn visits_fixture() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join(HISTORY_FILE_NAME);
+        history_fixture(&path);
+
+        let visits = super::super::query(&path, visits)
+            .unwrap();
+
+        // 13348540800000000 is 2024-01-01 00:00:00 UTC.
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+
+        assert_eq!(visits, vec![
+            Visit {
+                url: String::from("https://example.com/"),
+                title: Some(String::from("Example")),
+                time: None,
+            },
+            Visit {
+                url: String::from("https://example.com/"),
+                title: Some(String::from("Example")),
+                time: Some(time),
+            },
+            Visit {
+                url: String::from("https://example.org/"),
+                title: None,
+                time: Some(time + Duration::from_secs(1)),
+            },
+        ]);
+    }
+
+    #[test]
+    fn visits_missing_table() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join(HISTORY_FILE_NAME);
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE urls (id INTEGER PRIMARY KEY, url LONGVARCHAR);")
+            .unwrap();
+
+        assert!(super::super::query(&path, visits).is_err());
+    }
+
+    #[test]
+    fn extensions_fixture() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let foo_path = tempdir.path().join("Extensions").join("foo").join("1.2.3_0");
+        std::fs::create_dir_all(foo_path.join("_locales").join("en"))
+            .unwrap();
+        std::fs::write(foo_path.join("manifest.json"), br#"{
+            "name": "__MSG_appName__",
+            "version": "1.2.3",
+            "default_locale": "en",
+            "manifest_version": 3
+        }"#).unwrap();
+        std::fs::write(foo_path.join("_locales").join("en").join("messages.json"), br#"{
+            "APPNAME": { "message": "Foo Extension" }
+        }"#).unwrap();
+
+        let bar_path = tempdir.path().join("Extensions").join("bar").join("4.5_0");
+        std::fs::create_dir_all(&bar_path)
+            .unwrap();
+
+        let extensions = extensions(tempdir.path())
+            .unwrap();
+
+        assert_eq!(extensions, vec![
+            Extension {
+                id: String::from("bar"),
+                name: None,
+                version: Some(String::from("4.5")),
+            },
+            Extension {
+                id: String::from("foo"),
+                name: Some(String::from("Foo Extension")),
+                version: Some(String::from("1.2.3")),
+            },
+        ]);
+    }
+
+    #[test]
+    fn extensions_none() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(extensions(tempdir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn webkit_time_epoch() {
+        assert_eq!(webkit_time(11_644_473_600_000_000), Some(SystemTime::UNIX_EPOCH));
+    }
+
+    #[test]
+    fn webkit_time_unknown() {
+        assert_eq!(webkit_time(0), None);
+        assert_eq!(webkit_time(-1), None);
+    }
+}
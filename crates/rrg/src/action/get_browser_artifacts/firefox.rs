@@ -0,0 +1,212 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parsers of artifacts of Firefox.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use super::{Extension, Visit};
+
+/// Name of the history (and bookmarks) database file in the profile directory.
+pub const PLACES_FILE_NAME: &str = "places.sqlite";
+
+/// Name of the file with installed add-ons in the profile directory.
+const EXTENSIONS_FILE_NAME: &str = "extensions.json";
+
+/// Returns all visits from the places database.
+///
+/// Only the columns known to be present in all versions of the schema are
+/// selected, so that new columns do not break the query.
+pub fn visits(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<Visit>> {
+    let mut stmt = conn.prepare(r"
+        SELECT moz_places.url, moz_places.title, moz_historyvisits.visit_date
+          FROM moz_historyvisits
+          JOIN moz_places ON moz_places.id = moz_historyvisits.place_id
+      ORDER BY moz_historyvisits.visit_date
+    ")?;
+
+    let visits = stmt.query_map([], |row| {
+        Ok(Visit {
+            url: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.filter(|title| !title.is_empty()),
+            time: row.get::<_, Option<i64>>(2)?.and_then(prtime),
+        })
+    })?;
+
+    visits.collect()
+}
+
+/// Returns all extensions installed in the given profile directory.
+///
+/// Add-ons of other types (e.g. themes or dictionaries) are not reported.
+pub fn extensions(profile_path: &Path) -> std::io::Result<Vec<Extension>> {
+    let addons = match json::parse_file(profile_path.join(EXTENSIONS_FILE_NAME)) {
+        Ok(addons) => addons,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let addons = addons.get("addons")
+        .and_then(json::Value::as_array)
+        .ok_or(std::io::ErrorKind::InvalidData)?;
+
+    let mut extensions = Vec::new();
+
+    for addon in addons {
+        if addon.get("type").and_then(json::Value::as_str) != Some("extension") {
+            continue;
+        }
+
+        let Some(id) = addon.get("id").and_then(json::Value::as_str) else {
+            log::warn!("add-on without identifier in '{}'", profile_path.display());
+            continue;
+        };
+
+        extensions.push(Extension {
+            id: String::from(id),
+            name: addon.get("defaultLocale")
+                .and_then(|locale| locale.get("name"))
+                .and_then(json::Value::as_str)
+                .map(String::from),
+            version: addon.get("version")
+                .and_then(json::Value::as_str)
+                .map(String::from),
+        });
+    }
+
+    Ok(extensions)
+}
+
+/// Converts a PRTime timestamp (microseconds since the Unix epoch) to system
+/// time.
+///
+/// Zero means that the time is not known.
+fn prtime(micros: i64) -> Option<SystemTime> {
+    let micros = u64::try_from(micros).ok().filter(|micros| *micros != 0)?;
+
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_micros(micros))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Creates a places database fixture at the given path.
+    ///
+    /// The schema is trimmed down to the parts we use but it has some extra
+    /// columns to verify that unknown ones are ignored.
+    fn places_fixture(path: &Path) {
+        let conn = rusqlite::Connection::open(path)
+            .unwrap();
+
+        conn.execute_batch(r"
+            CREATE TABLE moz_places (
+                id INTEGER PRIMARY KEY,
+                url LONGVARCHAR,
+                title LONGVARCHAR,
+                rev_host LONGVARCHAR,
+                visit_count INTEGER DEFAULT 0,
+                frecency INTEGER DEFAULT -1 NOT NULL
+            );
+            CREATE TABLE moz_historyvisits (
+                id INTEGER PRIMARY KEY,
+                from_visit INTEGER,
+                place_id INTEGER,
+                visit_date INTEGER,
+                visit_type INTEGER,
+                session INTEGER
+            );
+            INSERT INTO moz_places (id, url, title, visit_count)
+                VALUES (1, 'https://example.com/', 'Example', 1),
+                       (2, 'https://example.org/', NULL, 1);
+            INSERT INTO moz_historyvisits (id, place_id, visit_date, visit_type)
+                VALUES (1, 2, 1704067201000000, 1),
+                       (2, 1, 1704067200000000, 1);
+        ").unwrap();
+    }
+
+    #[test]
+    fn visits_fixture() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join(PLACES_FILE_NAME);
+        places_fixture(&path);
+
+        let visits = super::super::query(&path, visits)
+            .unwrap();
+
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+
+        assert_eq!(visits, vec![
+            Visit {
+                url: String::from("https://example.com/"),
+                title: Some(String::from("Example")),
+                time: Some(time),
+            },
+            Visit {
+                url: String::from("https://example.org/"),
+                title: None,
+                time: Some(time + Duration::from_secs(1)),
+            },
+        ]);
+    }
+
+    #[test]
+    fn extensions_fixture() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join(EXTENSIONS_FILE_NAME), br#"{
+            "schemaVersion": 36,
+            "addons": [
+                {
+                    "id": "foo@example.com",
+                    "type": "extension",
+                    "version": "1.2.3",
+                    "defaultLocale": { "name": "Foo Extension" },
+                    "active": true
+                },
+                {
+                    "id": "default-theme@mozilla.org",
+                    "type": "theme",
+                    "version": "1.3"
+                }
+            ]
+        }"#).unwrap();
+
+        let extensions = extensions(tempdir.path())
+            .unwrap();
+
+        assert_eq!(extensions, vec![
+            Extension {
+                id: String::from("foo@example.com"),
+                name: Some(String::from("Foo Extension")),
+                version: Some(String::from("1.2.3")),
+            },
+        ]);
+    }
+
+    #[test]
+    fn extensions_none() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(extensions(tempdir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn extensions_malformed() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join(EXTENSIONS_FILE_NAME), b"{\"addons\": 42}")
+            .unwrap();
+
+        assert!(extensions(tempdir.path()).is_err());
+    }
+}
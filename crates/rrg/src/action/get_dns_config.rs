@@ -0,0 +1,416 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+/// Path to the resolver configuration file.
+#[cfg(target_family = "unix")]
+const RESOLV_CONF_PATH: &str = "/etc/resolv.conf";
+
+/// Registry key (relative to `HKEY_LOCAL_MACHINE`) with TCP/IP parameters.
+#[cfg(target_family = "windows")]
+const TCPIP_PARAMETERS_KEY: &str = "SYSTEM\\CurrentControlSet\\Services\\Tcpip\\Parameters";
+
+/// A result of the `get_dns_config` action.
+#[derive(Debug)]
+enum Item {
+    /// Entry of the hosts file.
+    HostsEntry(HostsEntry),
+    /// DNS server that the resolver is configured to use.
+    Nameserver(std::net::IpAddr),
+    /// Domain appended to names that are not fully qualified.
+    SearchDomain(String),
+    /// Entry of the DNS client cache.
+    #[cfg(target_family = "windows")]
+    CacheEntry(ospect::dns::CacheEntry),
+}
+
+/// Entry of the hosts file.
+#[derive(Debug, PartialEq, Eq)]
+struct HostsEntry {
+    /// Path to the hosts file.
+    path: PathBuf,
+    /// Number of the line (starting from 1) at which the entry is defined.
+    line: usize,
+    /// IP address that the hostnames resolve to.
+    ip_addr: std::net::IpAddr,
+    /// Hostnames (the canonical name followed by aliases) of the entry.
+    hostnames: Vec<String>,
+}
+
+/// Resolver configuration parsed from `resolv.conf`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ResolvConf {
+    /// Addresses of configured DNS servers.
+    nameservers: Vec<std::net::IpAddr>,
+    /// Domains appended to names that are not fully qualified.
+    search_domains: Vec<String>,
+}
+
+/// Handles invocations of the `get_dns_config` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let hosts_path = hosts_path();
+    match std::fs::read(&hosts_path) {
+        Ok(content) => {
+            let content = String::from_utf8_lossy(&content);
+            for entry in parse_hosts(&content, &hosts_path) {
+                session.reply(Item::HostsEntry(entry))?;
+            }
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => {
+            log::error!("failed to read hosts file {}: {error}", hosts_path.display());
+        }
+    }
+
+    let resolv_conf = resolv_conf()
+        .map_err(crate::session::Error::action)?;
+
+    for nameserver in resolv_conf.nameservers {
+        session.reply(Item::Nameserver(nameserver))?;
+    }
+    for search_domain in resolv_conf.search_domains {
+        session.reply(Item::SearchDomain(search_domain))?;
+    }
+
+    #[cfg(target_family = "windows")]
+    {
+        let entries = ospect::dns::cache_entries()
+            .map_err(crate::session::Error::action)?;
+
+        for entry in entries {
+            session.reply(Item::CacheEntry(entry))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the path to the hosts file.
+#[cfg(target_family = "unix")]
+fn hosts_path() -> PathBuf {
+    PathBuf::from("/etc/hosts")
+}
+
+/// Returns the path to the hosts file.
+#[cfg(target_family = "windows")]
+fn hosts_path() -> PathBuf {
+    let system_root = std::env::var_os("SystemRoot")
+        .unwrap_or_else(|| std::ffi::OsString::from("C:\\Windows"));
+
+    PathBuf::from(system_root).join("System32\\drivers\\etc\\hosts")
+}
+
+/// Returns the resolver configuration.
+#[cfg(target_family = "unix")]
+fn resolv_conf() -> std::io::Result<ResolvConf> {
+    match std::fs::read(RESOLV_CONF_PATH) {
+        Ok(content) => Ok(parse_resolv_conf(&String::from_utf8_lossy(&content))),
+        // Without the file, the resolver uses the local server and the domain
+        // part of the hostname. We report only what is configured explicitly.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            Ok(ResolvConf::default())
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the resolver configuration.
+#[cfg(target_family = "windows")]
+fn resolv_conf() -> std::io::Result<ResolvConf> {
+    use std::ffi::OsStr;
+
+    let params = ospect::dns::network_params()?;
+
+    let search_list = winreg::PredefinedKey::LocalMachine
+        .open(OsStr::new(TCPIP_PARAMETERS_KEY))
+        .and_then(|key| key.value_data(OsStr::new("SearchList")));
+
+    let search_list = match search_list {
+        Ok(winreg::ValueData::String(search_list)) => {
+            search_list.to_string_lossy().into_owned()
+        }
+        Ok(_) => String::new(),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+
+    let mut search_domains = search_list.split([',', ' '])
+        .filter(|domain| !domain.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    // If there is no explicit search list, the system uses the primary domain.
+    if search_domains.is_empty() && !params.domain_name().is_empty() {
+        search_domains.push(String::from(params.domain_name()));
+    }
+
+    Ok(ResolvConf {
+        nameservers: params.servers().collect(),
+        search_domains,
+    })
+}
+
+/// Parses entries of the hosts file [1].
+///
+/// Each line consists of an IP address followed by one or more hostnames and
+/// everything after a `#` character is a comment. Lines that are not valid
+/// entries are skipped.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/hosts.5.html
+fn parse_hosts(content: &str, path: &Path) -> Vec<HostsEntry> {
+    let mut entries = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = match line.split_once('#') {
+            Some((line, _)) => line,
+            None => line,
+        };
+
+        let mut fields = line.split_ascii_whitespace();
+
+        let Some(ip_addr) = fields.next() else {
+            continue;
+        };
+        let Some(ip_addr) = parse_ip_addr(ip_addr) else {
+            log::debug!("invalid hosts address at line {}: {ip_addr:?}", i + 1);
+            continue;
+        };
+
+        let hostnames = fields.map(String::from).collect::<Vec<_>>();
+        if hostnames.is_empty() {
+            log::debug!("hosts entry without hostnames at line {}", i + 1);
+            continue;
+        }
+
+        entries.push(HostsEntry {
+            path: path.to_path_buf(),
+            line: i + 1,
+            ip_addr,
+            hostnames,
+        });
+    }
+
+    entries
+}
+
+/// Parses the resolver configuration file [1].
+///
+/// The `domain` and `search` keywords are mutually exclusive and the last one
+/// of them wins.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/resolv.conf.5.html
+#[cfg(any(target_family = "unix", test))]
+fn parse_resolv_conf(content: &str) -> ResolvConf {
+    let mut resolv_conf = ResolvConf::default();
+
+    for line in content.lines() {
+        // Both `#` and `;` start comments but only at the beginning of a line.
+        if line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        let mut fields = line.split_ascii_whitespace();
+        match fields.next() {
+            Some("nameserver") => {
+                let Some(addr) = fields.next() else {
+                    continue;
+                };
+                match parse_ip_addr(addr) {
+                    Some(addr) => resolv_conf.nameservers.push(addr),
+                    None => log::debug!("invalid nameserver address: {addr:?}"),
+                }
+            }
+            Some("domain") => {
+                resolv_conf.search_domains = fields.next()
+                    .map(String::from)
+                    .into_iter()
+                    .collect();
+            }
+            Some("search") => {
+                resolv_conf.search_domains = fields
+                    .map(String::from)
+                    .collect();
+            }
+            _ => (),
+        }
+    }
+
+    resolv_conf
+}
+
+/// Parses an IP address, ignoring the IPv6 zone identifier (e.g. `%eth0`) if
+/// there is one.
+fn parse_ip_addr(string: &str) -> Option<std::net::IpAddr> {
+    let addr = match string.split_once('%') {
+        Some((addr, _)) => addr,
+        None => string,
+    };
+
+    addr.parse().ok()
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_dns_config::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_dns_config::Result::new();
+
+        match self {
+            Item::HostsEntry(entry) => {
+                let entry_proto = proto.mut_hosts_entry();
+                entry_proto.set_path(crate::path::into_proto(entry.path));
+                entry_proto.set_line(entry.line as u32);
+                entry_proto.set_ip_address(entry.ip_addr.into());
+                entry_proto.set_hostnames(entry.hostnames);
+            }
+            Item::Nameserver(ip_addr) => {
+                proto.mut_nameserver().set_ip_address(ip_addr.into());
+            }
+            Item::SearchDomain(name) => {
+                proto.mut_search_domain().set_name(name);
+            }
+            #[cfg(target_family = "windows")]
+            Item::CacheEntry(entry) => {
+                let entry_proto = proto.mut_cache_entry();
+                entry_proto.set_name(entry.name().to_string_lossy().into_owned());
+                entry_proto.set_record_type(u32::from(entry.record_type()));
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+    }
+
+    #[test]
+    fn parse_hosts_fixture() {
+        let hosts = "\
+# Static table lookup for hostnames.
+127.0.0.1	localhost
+127.0.1.1	foo.example.com	foo # The machine itself.
+
+::1		localhost ip6-localhost ip6-loopback
+fe80::1%lo0	localhost
+ff02::1		ip6-allnodes
+192.0.2.1
+not-an-address	bar
+  # 192.0.2.2 commented-out
+";
+
+        let path = Path::new("/etc/hosts");
+        let entries = parse_hosts(hosts, path);
+
+        assert_eq!(entries, vec![
+            HostsEntry {
+                path: path.to_path_buf(),
+                line: 2,
+                ip_addr: std::net::IpAddr::from([127, 0, 0, 1]),
+                hostnames: vec![String::from("localhost")],
+            },
+            HostsEntry {
+                path: path.to_path_buf(),
+                line: 3,
+                ip_addr: std::net::IpAddr::from([127, 0, 1, 1]),
+                hostnames: vec![String::from("foo.example.com"), String::from("foo")],
+            },
+            HostsEntry {
+                path: path.to_path_buf(),
+                line: 5,
+                ip_addr: std::net::IpAddr::from(std::net::Ipv6Addr::LOCALHOST),
+                hostnames: vec![
+                    String::from("localhost"),
+                    String::from("ip6-localhost"),
+                    String::from("ip6-loopback"),
+                ],
+            },
+            HostsEntry {
+                path: path.to_path_buf(),
+                line: 6,
+                ip_addr: "fe80::1".parse().unwrap(),
+                hostnames: vec![String::from("localhost")],
+            },
+            HostsEntry {
+                path: path.to_path_buf(),
+                line: 7,
+                ip_addr: "ff02::1".parse().unwrap(),
+                hostnames: vec![String::from("ip6-allnodes")],
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_hosts_windows_line_endings() {
+        let hosts = "127.0.0.1 localhost\r\n::1 localhost\r\n";
+
+        let entries = parse_hosts(hosts, Path::new("hosts"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].hostnames, vec![String::from("localhost")]);
+    }
+
+    #[test]
+    fn parse_resolv_conf_fixture() {
+        let resolv_conf = "\
+# Generated by NetworkManager
+; Another kind of comment.
+search corp.example.com example.com
+nameserver 192.0.2.53
+nameserver 2001:db8::53
+nameserver fe80::1%eth0
+nameserver not-an-address
+options edns0 trust-ad
+";
+
+        assert_eq!(parse_resolv_conf(resolv_conf), ResolvConf {
+            nameservers: vec![
+                std::net::IpAddr::from([192, 0, 2, 53]),
+                "2001:db8::53".parse().unwrap(),
+                "fe80::1".parse().unwrap(),
+            ],
+            search_domains: vec![
+                String::from("corp.example.com"),
+                String::from("example.com"),
+            ],
+        });
+    }
+
+    #[test]
+    fn parse_resolv_conf_last_domain_wins() {
+        let resolv_conf = "\
+search foo.example.com bar.example.com
+domain example.com
+";
+
+        assert_eq! {
+            parse_resolv_conf(resolv_conf).search_domains,
+            vec![String::from("example.com")]
+        };
+    }
+
+    #[test]
+    fn parse_resolv_conf_empty() {
+        assert_eq!(parse_resolv_conf(""), ResolvConf::default());
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn cache_entries_ok() {
+        assert!(ospect::dns::cache_entries().is_ok());
+    }
+}
@@ -0,0 +1,626 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the environment audit action.
+//!
+//! Besides the variables themselves, every `PATH` variable is decomposed into
+//! the directories it consists of, since hijacking one of them (or putting a
+//! writable directory in front of the system ones) is a common way for malware
+//! to persist.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_family = "unix")]
+mod unix;
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// Arguments of the `get_environment` action.
+pub struct Args {
+    /// Names of binaries to look up in the directories of every `PATH`.
+    binary_names: Vec<String>,
+    /// Whether the action is allowed to call external programs.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    allow_external_commands: bool,
+}
+
+/// A result of the `get_environment` action.
+#[derive(Debug)]
+enum Item {
+    /// An environment variable.
+    Variable(Variable),
+    /// A directory listed in a `PATH` variable.
+    PathEntry(PathEntry),
+    /// The first match of a binary in the directories of a `PATH` variable.
+    BinaryMatch(BinaryMatch),
+}
+
+/// Scope in which an environment variable is defined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scope {
+    /// Environment of the agent process.
+    Process,
+    /// System-level environment inherited by processes of all users.
+    System,
+}
+
+/// An environment variable.
+#[derive(Debug)]
+struct Variable {
+    /// Scope in which the variable is defined.
+    scope: Scope,
+    /// Source (file, registry key or command) that defines the variable.
+    source: String,
+    /// Name of the variable.
+    name: OsString,
+    /// Value of the variable.
+    value: OsString,
+}
+
+/// A directory listed in a `PATH` variable.
+#[derive(Debug)]
+struct PathEntry {
+    /// Scope in which the `PATH` variable is defined.
+    scope: Scope,
+    /// Source from which the `PATH` variable was collected.
+    source: String,
+    /// Position of the directory in the `PATH` variable.
+    index: usize,
+    /// Path to the directory.
+    path: PathBuf,
+    /// Whether the path exists.
+    exists: bool,
+    /// Whether the path is a directory.
+    is_dir: bool,
+    /// Whether the directory can be written to by a non-administrative user.
+    writable_by_non_admin: Option<bool>,
+}
+
+/// The first match of a binary in the directories of a `PATH` variable.
+#[derive(Debug)]
+struct BinaryMatch {
+    /// Scope in which the `PATH` variable is defined.
+    scope: Scope,
+    /// Source from which the `PATH` variable was collected.
+    source: String,
+    /// Name of the binary as given in the arguments.
+    name: String,
+    /// Path to the matching executable.
+    path: PathBuf,
+    /// Position of the directory containing the match in the `PATH` variable.
+    index: usize,
+}
+
+/// Handles invocations of the `get_environment` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut variables = std::env::vars_os()
+        .map(|(name, value)| Variable {
+            scope: Scope::Process,
+            source: String::new(),
+            name,
+            value,
+        })
+        .collect::<Vec<_>>();
+
+    #[cfg(target_os = "linux")]
+    variables.extend(linux::variables());
+
+    #[cfg(target_os = "macos")]
+    variables.extend(macos::variables(args.allow_external_commands));
+
+    #[cfg(target_family = "windows")]
+    variables.extend(windows::variables());
+
+    for variable in variables {
+        let items = if is_path(&variable.name) {
+            audit_path(&variable, &args.binary_names)
+        } else {
+            Vec::new()
+        };
+
+        session.reply(Item::Variable(variable))?;
+        for item in items {
+            session.reply(item)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether the variable with the given name is a `PATH` variable.
+fn is_path(name: &OsStr) -> bool {
+    // Names of environment variables are case-insensitive on Windows and the
+    // system one is usually spelled as `Path` there.
+    if cfg!(target_family = "windows") {
+        name.eq_ignore_ascii_case("PATH")
+    } else {
+        name == "PATH"
+    }
+}
+
+/// Decomposes the given `PATH` variable into items describing its directories.
+///
+/// For every directory, a [`PathEntry`] item is returned. For every binary
+/// name, a [`BinaryMatch`] item is returned for the first directory that has a
+/// matching executable (if any).
+fn audit_path(variable: &Variable, binary_names: &[String]) -> Vec<Item> {
+    let mut items = Vec::new();
+
+    let dirs = split_path(&variable.value);
+
+    for (index, dir) in &dirs {
+        let metadata = std::fs::metadata(dir);
+
+        let writable_by_non_admin = match &metadata {
+            Ok(metadata) if metadata.is_dir() => match writable_by_non_admin(dir) {
+                Ok(writable) => Some(writable),
+                Err(error) => {
+                    log::warn!("failed to evaluate access to '{}': {}", dir.display(), error);
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        items.push(Item::PathEntry(PathEntry {
+            scope: variable.scope,
+            source: variable.source.clone(),
+            index: *index,
+            path: dir.clone(),
+            exists: metadata.is_ok(),
+            is_dir: metadata.as_ref().is_ok_and(std::fs::Metadata::is_dir),
+            writable_by_non_admin,
+        }));
+    }
+
+    for name in binary_names {
+        let candidates = candidate_names(name);
+
+        let binary_match = dirs.iter().find_map(|(index, dir)| {
+            candidates.iter()
+                .map(|candidate| dir.join(candidate))
+                .find(|path| is_executable(path))
+                .map(|path| (*index, path))
+        });
+
+        if let Some((index, path)) = binary_match {
+            items.push(Item::BinaryMatch(BinaryMatch {
+                scope: variable.scope,
+                source: variable.source.clone(),
+                name: name.clone(),
+                path,
+                index,
+            }));
+        }
+    }
+
+    items
+}
+
+/// Splits the value of a `PATH` variable into indexed directories.
+///
+/// On Unix, empty entries stand for the current working directory and so they
+/// are reported as `.`. Entries that reference other variables cannot be
+/// resolved outside of a shell and are skipped (without affecting indices of
+/// the remaining entries).
+#[cfg(target_family = "unix")]
+fn split_path(value: &OsStr) -> Vec<(usize, PathBuf)> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    std::env::split_paths(value)
+        .enumerate()
+        .filter(|(_, dir)| !dir.as_os_str().as_bytes().contains(&b'$'))
+        .map(|(index, dir)| {
+            if dir.as_os_str().is_empty() {
+                (index, PathBuf::from("."))
+            } else {
+                (index, dir)
+            }
+        })
+        .collect()
+}
+
+/// Splits the value of a `PATH` variable into indexed directories.
+///
+/// References to environment variables (e.g. `%SystemRoot%`) are expanded
+/// first as the system variables are usually defined with them. Empty entries
+/// are ignored by Windows and so they are skipped.
+#[cfg(target_family = "windows")]
+fn split_path(value: &OsStr) -> Vec<(usize, PathBuf)> {
+    let value = match winreg::expand_string(value) {
+        Ok(value) => value,
+        Err(error) => {
+            log::warn!("failed to expand {value:?}: {error}");
+            value.to_os_string()
+        }
+    };
+
+    std::env::split_paths(&value)
+        .enumerate()
+        .filter(|(_, dir)| !dir.as_os_str().is_empty())
+        .collect()
+}
+
+/// Returns names of files that the binary with the given name can have.
+#[cfg(target_family = "unix")]
+fn candidate_names(name: &str) -> Vec<OsString> {
+    vec![OsString::from(name)]
+}
+
+/// Returns names of files that the binary with the given name can have.
+///
+/// Names that have no extension are extended with each of the extensions of
+/// executables listed in `PATHEXT` (in the order they are listed).
+#[cfg(target_family = "windows")]
+fn candidate_names(name: &str) -> Vec<OsString> {
+    /// Value of `PATHEXT` used if the variable is not defined.
+    const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+    if Path::new(name).extension().is_some() {
+        return vec![OsString::from(name)];
+    }
+
+    let pathext = std::env::var("PATHEXT")
+        .unwrap_or_else(|_| String::from(DEFAULT_PATHEXT));
+
+    pathext.split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| OsString::from(format!("{name}{ext}")))
+        .collect()
+}
+
+/// Checks whether the file at the given path is an executable.
+#[cfg(target_family = "unix")]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Checks whether the file at the given path is an executable.
+#[cfg(target_family = "windows")]
+fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+/// Checks whether the given directory can be written to by a non-admin user.
+fn writable_by_non_admin(path: &Path) -> std::io::Result<bool> {
+    #[cfg(target_family = "unix")]
+    return unix::writable_by_non_admin(path);
+
+    #[cfg(target_family = "windows")]
+    return windows::writable_by_non_admin(path);
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_environment::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            binary_names: proto.take_binary_names(),
+            allow_external_commands: proto.allow_external_commands(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_environment::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_environment::Result::new();
+
+        match self {
+            Item::Variable(variable) => {
+                let variable_proto = proto.mut_variable();
+                variable_proto.set_scope(variable.scope.into());
+                variable_proto.set_source(variable.source);
+                variable_proto.set_name(variable.name.to_string_lossy().into_owned());
+                variable_proto.set_value(variable.value.to_string_lossy().into_owned());
+            }
+            Item::PathEntry(entry) => {
+                let entry_proto = proto.mut_path_entry();
+                entry_proto.set_scope(entry.scope.into());
+                entry_proto.set_source(entry.source);
+                entry_proto.set_index(entry.index as u32);
+                entry_proto.set_path(crate::path::into_proto(entry.path));
+                entry_proto.set_exists(entry.exists);
+                entry_proto.set_is_dir(entry.is_dir);
+                if let Some(writable_by_non_admin) = entry.writable_by_non_admin {
+                    entry_proto.set_writable_by_non_admin(writable_by_non_admin);
+                }
+            }
+            Item::BinaryMatch(binary_match) => {
+                let match_proto = proto.mut_binary_match();
+                match_proto.set_scope(binary_match.scope.into());
+                match_proto.set_source(binary_match.source);
+                match_proto.set_name(binary_match.name);
+                match_proto.set_path(crate::path::into_proto(binary_match.path));
+                match_proto.set_index(binary_match.index as u32);
+            }
+        }
+
+        proto
+    }
+}
+
+impl From<Scope> for rrg_proto::get_environment::Scope {
+
+    fn from(scope: Scope) -> rrg_proto::get_environment::Scope {
+        use rrg_proto::get_environment::Scope as Proto;
+
+        match scope {
+            Scope::Process => Proto::PROCESS,
+            Scope::System => Proto::SYSTEM,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn path_variable(dirs: &[&Path]) -> Variable {
+        Variable {
+            scope: Scope::System,
+            source: String::from("/etc/environment"),
+            name: OsString::from("PATH"),
+            value: std::env::join_paths(dirs).unwrap(),
+        }
+    }
+
+    fn path_entries(items: &[Item]) -> Vec<&PathEntry> {
+        items.iter()
+            .filter_map(|item| match item {
+                Item::PathEntry(entry) => Some(entry),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn binary_matches(items: &[Item]) -> Vec<&BinaryMatch> {
+        items.iter()
+            .filter_map(|item| match item {
+                Item::BinaryMatch(binary_match) => Some(binary_match),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[cfg(target_family = "unix")]
+    fn create_executable(path: &Path) {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        std::fs::write(path, b"#!/bin/sh\n")
+            .unwrap();
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+    }
+
+    #[cfg(target_family = "windows")]
+    fn create_executable(path: &Path) {
+        std::fs::write(path, b"MZ")
+            .unwrap();
+    }
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args {
+            binary_names: vec![],
+            allow_external_commands: false,
+        }).is_ok());
+
+        let process_variable_count = session.replies::<Item>()
+            .filter(|item| matches!(item, Item::Variable(variable) if variable.scope == Scope::Process))
+            .count();
+        assert_eq!(process_variable_count, std::env::vars_os().count());
+    }
+
+    #[test]
+    fn is_path_ok() {
+        assert!(is_path(OsStr::new("PATH")));
+        assert!(!is_path(OsStr::new("PATHEXT")));
+        assert!(!is_path(OsStr::new("HOME")));
+    }
+
+    #[test]
+    fn audit_path_entries() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let dir = tempdir.path().join("bin");
+        std::fs::create_dir(&dir)
+            .unwrap();
+
+        let file = tempdir.path().join("file");
+        std::fs::write(&file, b"")
+            .unwrap();
+
+        let missing = tempdir.path().join("missing");
+
+        let variable = path_variable(&[&dir, &file, &missing]);
+        let items = audit_path(&variable, &[]);
+
+        let entries = path_entries(&items);
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].scope, Scope::System);
+        assert_eq!(entries[0].source, "/etc/environment");
+        assert_eq!(entries[0].index, 0);
+        assert_eq!(entries[0].path, dir);
+        assert!(entries[0].exists);
+        assert!(entries[0].is_dir);
+        assert!(entries[0].writable_by_non_admin.is_some());
+
+        assert_eq!(entries[1].index, 1);
+        assert_eq!(entries[1].path, file);
+        assert!(entries[1].exists);
+        assert!(!entries[1].is_dir);
+        assert_eq!(entries[1].writable_by_non_admin, None);
+
+        assert_eq!(entries[2].index, 2);
+        assert_eq!(entries[2].path, missing);
+        assert!(!entries[2].exists);
+        assert!(!entries[2].is_dir);
+        assert_eq!(entries[2].writable_by_non_admin, None);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn audit_path_writable() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let private = tempdir.path().join("private");
+        std::fs::create_dir(&private)
+            .unwrap();
+        std::fs::set_permissions(&private, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        let public = tempdir.path().join("public");
+        std::fs::create_dir(&public)
+            .unwrap();
+        std::fs::set_permissions(&public, std::fs::Permissions::from_mode(0o1777))
+            .unwrap();
+
+        let variable = path_variable(&[&private, &public]);
+        let items = audit_path(&variable, &[]);
+
+        let entries = path_entries(&items);
+        assert_eq!(entries.len(), 2);
+
+        // Directories owned by non-root users are always writable by non-admins
+        // (as their owners can change the permissions), so the first directory
+        // is writable only if the test is not run as root.
+        //
+        // SAFETY: `geteuid` is always successful.
+        let is_root = unsafe { libc::geteuid() } == 0;
+        assert_eq!(entries[0].writable_by_non_admin, Some(!is_root));
+        assert_eq!(entries[1].writable_by_non_admin, Some(true));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn audit_path_empty_entry() {
+        let variable = Variable {
+            scope: Scope::Process,
+            source: String::new(),
+            name: OsString::from("PATH"),
+            value: OsString::from("/usr/bin::$HOME/bin:/bin"),
+        };
+
+        let items = audit_path(&variable, &[]);
+
+        let entries = path_entries(&items)
+            .into_iter()
+            .map(|entry| (entry.index, entry.path.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec![
+            (0, PathBuf::from("/usr/bin")),
+            (1, PathBuf::from(".")),
+            (3, PathBuf::from("/bin")),
+        ]);
+    }
+
+    #[test]
+    fn audit_path_binary_matches() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let first = tempdir.path().join("first");
+        std::fs::create_dir(&first)
+            .unwrap();
+        let second = tempdir.path().join("second");
+        std::fs::create_dir(&second)
+            .unwrap();
+
+        let foo = if cfg!(target_family = "windows") { "foo.exe" } else { "foo" };
+        create_executable(&first.join(foo));
+        create_executable(&second.join(foo));
+        create_executable(&second.join("bar.cmd"));
+
+        let variable = path_variable(&[&first, &second]);
+        let items = audit_path(&variable, &[
+            String::from(foo),
+            String::from("bar.cmd"),
+            String::from("quux"),
+        ]);
+
+        let matches = binary_matches(&items);
+        assert_eq!(matches.len(), 2);
+
+        assert_eq!(matches[0].scope, Scope::System);
+        assert_eq!(matches[0].source, "/etc/environment");
+        assert_eq!(matches[0].name, foo);
+        assert_eq!(matches[0].path, first.join(foo));
+        assert_eq!(matches[0].index, 0);
+
+        assert_eq!(matches[1].name, "bar.cmd");
+        assert_eq!(matches[1].path, second.join("bar.cmd"));
+        assert_eq!(matches[1].index, 1);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn audit_path_binary_not_executable() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let first = tempdir.path().join("first");
+        std::fs::create_dir(&first)
+            .unwrap();
+        std::fs::write(first.join("python"), b"")
+            .unwrap();
+
+        let second = tempdir.path().join("second");
+        std::fs::create_dir(&second)
+            .unwrap();
+        create_executable(&second.join("python"));
+
+        let variable = path_variable(&[&first, &second]);
+        let items = audit_path(&variable, &[String::from("python")]);
+
+        let matches = binary_matches(&items);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, second.join("python"));
+        assert_eq!(matches[0].index, 1);
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn audit_path_binary_pathext() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        create_executable(&tempdir.path().join("python.exe"));
+
+        let variable = path_variable(&[tempdir.path()]);
+        let items = audit_path(&variable, &[String::from("python")]);
+
+        let matches = binary_matches(&items);
+        assert_eq!(matches.len(), 1);
+        // Paths on Windows are case-insensitive and the extension is spelled
+        // as in `PATHEXT`, so we compare them accordingly.
+        assert!(matches[0].path.as_os_str().eq_ignore_ascii_case(tempdir.path().join("python.exe")));
+    }
+}
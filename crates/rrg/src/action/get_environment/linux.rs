@@ -0,0 +1,169 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use super::{Scope, Variable};
+
+/// Path to the system-wide environment file read by `pam_env`.
+const ENVIRONMENT_PATH: &str = "/etc/environment";
+
+/// Path to the directory with scripts sourced by login shells.
+const PROFILE_D_PATH: &str = "/etc/profile.d";
+
+/// Returns variables of the system-level environment.
+///
+/// The environment is collected from `/etc/environment` and from the `export`
+/// statements of scripts in `/etc/profile.d`. The scripts are not executed, so
+/// only plain assignments are picked up.
+pub fn variables() -> Vec<Variable> {
+    let mut variables = Vec::new();
+
+    match std::fs::read_to_string(ENVIRONMENT_PATH) {
+        Ok(content) => {
+            variables.extend(into_variables(ENVIRONMENT_PATH, parse_environment(&content)));
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => log::warn!("failed to read '{ENVIRONMENT_PATH}': {error}"),
+    }
+
+    let mut scripts = match std::fs::read_dir(PROFILE_D_PATH) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sh"))
+            .collect::<Vec<_>>(),
+        Err(error) => {
+            if error.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("failed to list '{PROFILE_D_PATH}': {error}");
+            }
+            Vec::new()
+        }
+    };
+    // Scripts are sourced in the alphabetical order.
+    scripts.sort();
+
+    for script in scripts {
+        match std::fs::read_to_string(&script) {
+            Ok(content) => {
+                variables.extend(into_variables(&script, parse_exports(&content)));
+            }
+            Err(error) => log::warn!("failed to read '{}': {}", script.display(), error),
+        }
+    }
+
+    variables
+}
+
+/// Converts parsed assignments from the given source into variables.
+fn into_variables<P>(source: P, assignments: Vec<(String, String)>) -> impl Iterator<Item = Variable>
+where
+    P: AsRef<Path>,
+{
+    let source = source.as_ref().display().to_string();
+
+    assignments.into_iter().map(move |(name, value)| Variable {
+        scope: Scope::System,
+        source: source.clone(),
+        name: OsString::from(name),
+        value: OsString::from(value),
+    })
+}
+
+/// Parses the content of the `/etc/environment` file.
+///
+/// The file consists of `<name>=<value>` lines [1]. Values can be quoted and
+/// (even though `pam_env` does not require it) lines prefixed with `export` are
+/// accepted as the file is often written as if it was a shell script.
+///
+/// [1]: https://man7.org/linux/man-pages/man8/pam_env.8.html
+fn parse_environment(content: &str) -> Vec<(String, String)> {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix("export ").map(str::trim_start).unwrap_or(line))
+        .filter_map(parse_assignment)
+        .collect()
+}
+
+/// Parses `export` statements of a shell script.
+///
+/// Only statements of the `export <name>=<value>` form that take a whole line
+/// are supported, anything more involved would require a shell to evaluate.
+fn parse_exports(content: &str) -> Vec<(String, String)> {
+    content.lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix("export "))
+        .filter_map(|line| parse_assignment(line.trim_start()))
+        .collect()
+}
+
+/// Parses a single `<name>=<value>` assignment.
+///
+/// Surrounding quotes (single or double) are stripped from the value.
+fn parse_assignment(line: &str) -> Option<(String, String)> {
+    let (name, value) = line.split_once('=')?;
+
+    let is_name = !name.is_empty()
+        && !name.starts_with(|char: char| char.is_ascii_digit())
+        && name.chars().all(|char| char.is_ascii_alphanumeric() || char == '_');
+    if !is_name {
+        return None;
+    }
+
+    let value = value.trim();
+    let value = ['"', '\''].iter()
+        .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(value);
+
+    Some((String::from(name), String::from(value)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_environment_ok() {
+        let content = "\
+# System-wide environment.
+PATH=\"/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin\"
+LANG=en_US.UTF-8
+export EDITOR='vim'
+
+not an assignment
+";
+
+        assert_eq!(parse_environment(content), vec![
+            (String::from("PATH"), String::from("/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin")),
+            (String::from("LANG"), String::from("en_US.UTF-8")),
+            (String::from("EDITOR"), String::from("vim")),
+        ]);
+    }
+
+    #[test]
+    fn parse_exports_ok() {
+        let content = "\
+if [ -d /opt/foo/bin ]; then
+    export PATH=\"$PATH:/opt/foo/bin\"
+fi
+export JAVA_HOME=/usr/lib/jvm/default
+FOO=bar
+exported=no
+export 1FOO=bar
+";
+
+        assert_eq!(parse_exports(content), vec![
+            (String::from("PATH"), String::from("$PATH:/opt/foo/bin")),
+            (String::from("JAVA_HOME"), String::from("/usr/lib/jvm/default")),
+        ]);
+    }
+
+    #[test]
+    fn parse_assignment_unbalanced_quotes() {
+        assert_eq!(parse_assignment("FOO=\"bar"), Some((String::from("FOO"), String::from("\"bar"))));
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use super::{Scope, Variable};
+
+/// Path to the file with the default directories of `PATH`.
+const PATHS_PATH: &str = "/etc/paths";
+
+/// Path to the directory with files with additional directories of `PATH`.
+const PATHS_D_PATH: &str = "/etc/paths.d";
+
+/// Returns variables of the system-level environment.
+///
+/// Directories that `path_helper` [1] puts into `PATH` of login shells are
+/// reported as a `PATH` variable for every file they come from. The environment
+/// of launchd (inherited by all the processes it spawns) is collected only if
+/// external commands are allowed.
+///
+/// [1]: https://www.manpagez.com/man/8/path_helper/
+pub fn variables(allow_external_commands: bool) -> Vec<Variable> {
+    let mut variables = Vec::new();
+
+    let mut paths_files = vec![Path::new(PATHS_PATH).to_path_buf()];
+    match std::fs::read_dir(PATHS_D_PATH) {
+        Ok(entries) => {
+            let mut paths = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect::<Vec<_>>();
+            // Files are read by `path_helper` in the alphabetical order.
+            paths.sort();
+            paths_files.extend(paths);
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => log::warn!("failed to list '{PATHS_D_PATH}': {error}"),
+    }
+
+    for path in paths_files {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => variables.push(Variable {
+                scope: Scope::System,
+                source: path.display().to_string(),
+                name: OsString::from("PATH"),
+                value: OsString::from(parse_paths(&content)),
+            }),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+            Err(error) => log::warn!("failed to read '{}': {}", path.display(), error),
+        }
+    }
+
+    if allow_external_commands {
+        match launchctl_export() {
+            Ok(output) => {
+                variables.extend(parse_export(&output).into_iter().map(|(name, value)| Variable {
+                    scope: Scope::System,
+                    source: String::from("launchctl"),
+                    name: OsString::from(name),
+                    value: OsString::from(value),
+                }));
+            }
+            Err(error) => log::warn!("failed to get launchd environment: {error}"),
+        }
+    }
+
+    variables
+}
+
+/// Parses a `path_helper` file into the value of a `PATH` variable.
+///
+/// Every non-empty line of the file is a single directory.
+fn parse_paths(content: &str) -> String {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Runs `launchctl export` and returns its standard output.
+fn launchctl_export() -> std::io::Result<String> {
+    let output = std::process::Command::new("launchctl")
+        .arg("export")
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format! {
+            "launchctl export failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        }));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses the output of `launchctl export`.
+///
+/// Every line of the output has the `<name>="<value>"; export <name>;` form.
+fn parse_export(output: &str) -> Vec<(String, String)> {
+    let mut variables = Vec::new();
+
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some((name, rest)) = line.split_once('=') else {
+            log::warn!("malformed launchctl export line: {line:?}");
+            continue;
+        };
+        let Some(value) = rest.strip_suffix(&format!("; export {name};")) else {
+            log::warn!("malformed launchctl export line: {line:?}");
+            continue;
+        };
+        let value = value.strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+
+        variables.push((String::from(name), String::from(value)));
+    }
+
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_paths_ok() {
+        let content = "/usr/local/bin\n/usr/bin\n\n/bin\n";
+        assert_eq!(parse_paths(content), "/usr/local/bin:/usr/bin:/bin");
+    }
+
+    #[test]
+    fn parse_export_ok() {
+        let output = "\
+PATH=\"/usr/bin:/bin:/usr/sbin:/sbin\"; export PATH;
+TMPDIR=\"/var/folders/zz/zyxvpxvq6csfxvn_n0000000000000/T/\"; export TMPDIR;
+malformed
+";
+
+        assert_eq!(parse_export(output), vec![
+            (String::from("PATH"), String::from("/usr/bin:/bin:/usr/sbin:/sbin")),
+            (String::from("TMPDIR"), String::from("/var/folders/zz/zyxvpxvq6csfxvn_n0000000000000/T/")),
+        ]);
+    }
+}
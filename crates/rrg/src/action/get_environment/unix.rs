@@ -0,0 +1,444 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+/// Identifier of the root user.
+const ROOT_UID: u32 = 0;
+
+/// Identifiers of groups of administrative users.
+#[cfg(target_os = "linux")]
+const ADMIN_GIDS: &[u32] = &[0];
+
+/// Identifiers of groups of administrative users.
+///
+/// On macOS, these are the `wheel` and `admin` groups.
+#[cfg(target_os = "macos")]
+const ADMIN_GIDS: &[u32] = &[0, 80];
+
+/// Checks whether the given directory can be written to by a non-admin user.
+///
+/// The ownership and the permission bits of the directory are evaluated along
+/// with its access control list (if there is any). Directories owned by users
+/// other than root are considered writable as their owners can always change
+/// the permissions.
+pub fn writable_by_non_admin(path: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::MetadataExt as _;
+
+    // Access control lists are read without following symlinks, so we need to
+    // resolve the path first.
+    let path = std::fs::canonicalize(path)?;
+    let metadata = std::fs::metadata(&path)?;
+
+    if metadata.uid() != ROOT_UID {
+        return Ok(true);
+    }
+
+    let mode_writable = {
+        let group_writable = metadata.mode() & 0o020 != 0 && !ADMIN_GIDS.contains(&metadata.gid());
+        let other_writable = metadata.mode() & 0o002 != 0;
+        group_writable || other_writable
+    };
+
+    match acl::writable_by_non_admin(&path, metadata.gid())? {
+        // On Linux, the access control list subsumes the permission bits.
+        Some(acl_writable) if cfg!(target_os = "linux") => Ok(acl_writable),
+        // On macOS, entries of the list grant permissions on top of the bits.
+        Some(acl_writable) => Ok(acl_writable || mode_writable),
+        None => Ok(mode_writable),
+    }
+}
+
+/// Evaluation of POSIX access control lists [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man5/acl.5.html
+#[cfg(target_os = "linux")]
+mod acl {
+
+    use std::path::Path;
+
+    use super::{ADMIN_GIDS, ROOT_UID};
+
+    /// Name of the extended attribute with the access control list.
+    const XATTR_NAME: &str = "system.posix_acl_access";
+
+    /// Version of the extended attribute format that we understand.
+    const VERSION: u32 = 2;
+
+    const ACL_USER_OBJ: u16 = 0x01;
+    const ACL_USER: u16 = 0x02;
+    const ACL_GROUP_OBJ: u16 = 0x04;
+    const ACL_GROUP: u16 = 0x08;
+    const ACL_MASK: u16 = 0x10;
+    const ACL_OTHER: u16 = 0x20;
+
+    /// Permission to write.
+    const ACL_WRITE: u16 = 0x02;
+
+    /// Entry of an access control list.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Entry {
+        /// Type of the entry (`ACL_*` constant).
+        pub tag: u16,
+        /// Permissions granted by the entry.
+        pub perm: u16,
+        /// Identifier of the user or group of named entries.
+        pub id: u32,
+    }
+
+    /// Checks whether the access control list of the given root-owned path
+    /// grants write access to a non-admin user.
+    ///
+    /// `None` is returned if the path has no access control list (other than
+    /// the one equivalent to the permission bits).
+    pub fn writable_by_non_admin(path: &Path, gid: u32) -> std::io::Result<Option<bool>> {
+        let value = match ospect::fs::ext_attr_value(path, XATTR_NAME) {
+            Ok(value) => value,
+            Err(error) => match error.raw_os_error() {
+                Some(libc::ENODATA) | Some(libc::EOPNOTSUPP) => return Ok(None),
+                _ => return Err(error),
+            },
+        };
+
+        Ok(Some(evaluate(&parse(&value)?, gid)))
+    }
+
+    /// Parses the extended attribute with the access control list.
+    ///
+    /// The attribute consists of a 32-bit version followed by 8-byte entries
+    /// with the tag, permissions and identifier (all little-endian).
+    pub fn parse(value: &[u8]) -> std::io::Result<Vec<Entry>> {
+        use byteorder::{ByteOrder as _, LittleEndian};
+
+        let invalid_data = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+        };
+
+        if value.len() < 4 || !(value.len() - 4).is_multiple_of(8) {
+            return Err(invalid_data("malformed access control list"));
+        }
+
+        let version = LittleEndian::read_u32(&value[0..4]);
+        if version != VERSION {
+            return Err(invalid_data("unsupported access control list version"));
+        }
+
+        Ok(value[4..].chunks_exact(8).map(|entry| Entry {
+            tag: LittleEndian::read_u16(&entry[0..2]),
+            perm: LittleEndian::read_u16(&entry[2..4]),
+            id: LittleEndian::read_u32(&entry[4..8]),
+        }).collect())
+    }
+
+    /// Checks whether any of the entries grants write access to a non-admin.
+    ///
+    /// The owner of the path is assumed to be root. Permissions of named users
+    /// and all the groups are limited by the mask entry (if there is any).
+    pub fn evaluate(entries: &[Entry], gid: u32) -> bool {
+        let mask = entries.iter()
+            .find(|entry| entry.tag == ACL_MASK)
+            .map(|entry| entry.perm)
+            .unwrap_or(!0);
+
+        entries.iter().any(|entry| {
+            let writable = entry.perm & ACL_WRITE != 0;
+            match entry.tag {
+                ACL_USER_OBJ => false,
+                ACL_USER => writable && mask & ACL_WRITE != 0 && entry.id != ROOT_UID,
+                ACL_GROUP_OBJ => writable && mask & ACL_WRITE != 0 && !ADMIN_GIDS.contains(&gid),
+                ACL_GROUP => writable && mask & ACL_WRITE != 0 && !ADMIN_GIDS.contains(&entry.id),
+                ACL_OTHER => writable,
+                _ => false,
+            }
+        })
+    }
+}
+
+/// Evaluation of extended access control lists of macOS [1].
+///
+/// [1]: https://developer.apple.com/library/archive/documentation/Security/Conceptual/Security_Overview/Architecture/Architecture.html
+#[cfg(target_os = "macos")]
+mod acl {
+
+    use std::path::Path;
+
+    use super::{ADMIN_GIDS, ROOT_UID};
+
+    /// Type of extended access control lists (`ACL_TYPE_EXTENDED`).
+    const ACL_TYPE_EXTENDED: libc::c_int = 0x00000100;
+
+    /// Permissions that allow (directly or not) to create files in a directory.
+    const WRITE_PERMS: &[&str] = &[
+        "add_file",
+        "add_subdirectory",
+        "writesecurity",
+        "chown",
+    ];
+
+    #[allow(non_camel_case_types)]
+    type acl_t = *mut libc::c_void;
+
+    extern "C" {
+        fn acl_get_file(path: *const libc::c_char, r#type: libc::c_int) -> acl_t;
+        fn acl_to_text(acl: acl_t, len: *mut libc::ssize_t) -> *mut libc::c_char;
+        fn acl_free(obj: *mut libc::c_void) -> libc::c_int;
+    }
+
+    /// Checks whether the access control list of the given root-owned path
+    /// grants write access to a non-admin user.
+    ///
+    /// `None` is returned if the path has no access control list.
+    pub fn writable_by_non_admin(path: &Path, _: u32) -> std::io::Result<Option<bool>> {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+        // SAFETY: We pass a valid null-terminated path. The result is verified
+        // below and freed once we are done with it.
+        let acl = unsafe {
+            acl_get_file(path.as_ptr(), ACL_TYPE_EXTENDED)
+        };
+        if acl.is_null() {
+            let error = std::io::Error::last_os_error();
+            return match error.raw_os_error() {
+                Some(libc::ENOENT) | Some(libc::EOPNOTSUPP) => Ok(None),
+                _ => Err(error),
+            };
+        }
+
+        // SAFETY: The list is valid (we verified it above).
+        let text = unsafe {
+            acl_to_text(acl, std::ptr::null_mut())
+        };
+        let result = if text.is_null() {
+            Err(std::io::Error::last_os_error())
+        } else {
+            // SAFETY: The text is a valid null-terminated string allocated by
+            // the call above.
+            let text = unsafe {
+                std::ffi::CStr::from_ptr(text)
+            }.to_string_lossy().into_owned();
+            Ok(Some(evaluate(&text)))
+        };
+
+        // SAFETY: Both objects were allocated by the calls above and are not
+        // used after this point.
+        unsafe {
+            if !text.is_null() {
+                acl_free(text.cast());
+            }
+            acl_free(acl);
+        }
+
+        result
+    }
+
+    /// Checks whether any of the entries of the textual representation of an
+    /// access control list grants write access to a non-admin.
+    ///
+    /// Entries are lines in the `<kind>:<uuid>:<name>:<id>:<allow|deny>:<perms>`
+    /// format (optionally followed by inheritance flags). Deny entries are not
+    /// taken into account, so the result errs on the side of reporting paths
+    /// as writable.
+    pub fn evaluate(text: &str) -> bool {
+        text.lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with("!#"))
+            .filter_map(|line| {
+                let fields = line.split(':').collect::<Vec<_>>();
+                match fields.as_slice() {
+                    [kind, _, _, id, "allow", perms, ..] => Some((*kind, id.parse::<u32>().ok(), *perms)),
+                    _ => None,
+                }
+            })
+            .any(|(kind, id, perms)| {
+                let non_admin = match (kind, id) {
+                    ("user", Some(uid)) => uid != ROOT_UID,
+                    ("group", Some(gid)) => !ADMIN_GIDS.contains(&gid),
+                    // Principals that cannot be resolved are not known to be
+                    // administrative ones.
+                    _ => true,
+                };
+                non_admin && perms.split(',').any(|perm| WRITE_PERMS.contains(&perm))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn writable_by_non_admin_world_writable() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::set_permissions(tempdir.path(), std::fs::Permissions::from_mode(0o777))
+            .unwrap();
+
+        assert!(writable_by_non_admin(tempdir.path()).unwrap());
+    }
+
+    #[test]
+    fn writable_by_non_admin_symlink() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let target = tempdir.path().join("target");
+        std::fs::create_dir(&target)
+            .unwrap();
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o777))
+            .unwrap();
+
+        let link = tempdir.path().join("link");
+        std::os::unix::fs::symlink(&target, &link)
+            .unwrap();
+
+        assert!(writable_by_non_admin(&link).unwrap());
+    }
+
+    #[test]
+    fn writable_by_non_admin_missing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(writable_by_non_admin(&tempdir.path().join("missing")).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    mod linux {
+
+        use super::super::acl::*;
+
+        fn value(entries: &[(u16, u16, u32)]) -> Vec<u8> {
+            let mut value = 2u32.to_le_bytes().to_vec();
+            for (tag, perm, id) in entries {
+                value.extend(tag.to_le_bytes());
+                value.extend(perm.to_le_bytes());
+                value.extend(id.to_le_bytes());
+            }
+            value
+        }
+
+        #[test]
+        fn parse_ok() {
+            let entries = parse(&value(&[
+                (0x01, 0o7, u32::MAX),
+                (0x02, 0o6, 1000),
+            ])).unwrap();
+
+            assert_eq!(entries, vec![
+                Entry { tag: 0x01, perm: 0o7, id: u32::MAX },
+                Entry { tag: 0x02, perm: 0o6, id: 1000 },
+            ]);
+        }
+
+        #[test]
+        fn parse_invalid_version() {
+            let mut value = value(&[]);
+            value[0] = 1;
+
+            assert!(parse(&value).is_err());
+        }
+
+        #[test]
+        fn parse_truncated() {
+            let mut value = value(&[(0x01, 0o7, u32::MAX)]);
+            value.pop();
+
+            assert!(parse(&value).is_err());
+        }
+
+        #[test]
+        fn evaluate_named_user() {
+            let entries = parse(&value(&[
+                (0x01, 0o7, u32::MAX),
+                (0x02, 0o7, 1000),
+                (0x04, 0o5, u32::MAX),
+                (0x10, 0o7, u32::MAX),
+                (0x20, 0o5, u32::MAX),
+            ])).unwrap();
+
+            // Mode bits of such a directory could be `0o775` with the group
+            // being root and so not writable by a non-admin.
+            assert!(evaluate(&entries, 0));
+        }
+
+        #[test]
+        fn evaluate_named_user_masked() {
+            let entries = parse(&value(&[
+                (0x01, 0o7, u32::MAX),
+                (0x02, 0o7, 1000),
+                (0x04, 0o5, u32::MAX),
+                (0x10, 0o5, u32::MAX),
+                (0x20, 0o5, u32::MAX),
+            ])).unwrap();
+
+            assert!(!evaluate(&entries, 0));
+        }
+
+        #[test]
+        fn evaluate_named_root_group() {
+            let entries = parse(&value(&[
+                (0x01, 0o7, u32::MAX),
+                (0x04, 0o5, u32::MAX),
+                (0x08, 0o7, 0),
+                (0x10, 0o7, u32::MAX),
+                (0x20, 0o5, u32::MAX),
+            ])).unwrap();
+
+            assert!(!evaluate(&entries, 0));
+        }
+
+        #[test]
+        fn evaluate_owning_group() {
+            let entries = parse(&value(&[
+                (0x01, 0o7, u32::MAX),
+                (0x04, 0o7, u32::MAX),
+                (0x20, 0o5, u32::MAX),
+            ])).unwrap();
+
+            assert!(!evaluate(&entries, 0));
+            assert!(evaluate(&entries, 1000));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    mod macos {
+
+        use super::super::acl::*;
+
+        #[test]
+        fn evaluate_non_admin_user() {
+            let text = "\
+!#acl 1
+user:FFFFEEEE-DDDD-CCCC-BBBB-AAAA000001F5:jdoe:501:allow:list,add_file,search
+";
+            assert!(evaluate(text));
+        }
+
+        #[test]
+        fn evaluate_admin_group() {
+            let text = "\
+!#acl 1
+group:ABCDEFAB-CDEF-ABCD-EFAB-CDEF00000050:admin:80:allow:list,add_file,search
+";
+            assert!(!evaluate(text));
+        }
+
+        #[test]
+        fn evaluate_deny() {
+            let text = "\
+!#acl 1
+group:ABCDEFAB-CDEF-ABCD-EFAB-CDEF0000000C:everyone:12:deny:delete
+";
+            assert!(!evaluate(text));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::OsStr;
+use std::path::Path;
+
+use windows_sys::Win32::Foundation::*;
+use windows_sys::Win32::Security::Authorization::*;
+use windows_sys::Win32::Security::*;
+use windows_sys::Win32::Storage::FileSystem::{
+    FILE_ADD_FILE, FILE_ADD_SUBDIRECTORY, WRITE_DAC, WRITE_OWNER,
+};
+
+use super::{Scope, Variable};
+
+/// Registry key (relative to `HKEY_LOCAL_MACHINE`) of the system environment.
+const ENVIRONMENT_KEY: &str = r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+
+/// String representation of the SID of the `TrustedInstaller` service.
+const TRUSTED_INSTALLER_SID: &str = "S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464";
+
+/// Well-known groups that every non-admin user belongs to.
+const NON_ADMIN_SIDS: [WELL_KNOWN_SID_TYPE; 4] = [
+    WinWorldSid,
+    WinAuthenticatedUserSid,
+    WinBuiltinUsersSid,
+    WinInteractiveSid,
+];
+
+/// Access rights that allow (directly or not) to create files in a directory.
+const WRITE_RIGHTS: u32 = FILE_ADD_FILE
+    | FILE_ADD_SUBDIRECTORY
+    | WRITE_DAC
+    | WRITE_OWNER
+    | GENERIC_WRITE
+    | GENERIC_ALL;
+
+/// Returns variables of the system-level environment.
+///
+/// The variables are stored in the registry, values of `REG_EXPAND_SZ` type are
+/// reported without expanding them.
+pub fn variables() -> Vec<Variable> {
+    let source = format!(r"HKEY_LOCAL_MACHINE\{ENVIRONMENT_KEY}");
+
+    let info = winreg::PredefinedKey::LocalMachine
+        .open(OsStr::new(ENVIRONMENT_KEY))
+        .and_then(|key| key.info());
+    let info = match info {
+        Ok(info) => info,
+        Err(error) => {
+            log::warn!("failed to open '{source}': {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut variables = Vec::new();
+
+    for value in info.values() {
+        match value {
+            Ok(winreg::Value {
+                name,
+                data: winreg::ValueData::String(value) | winreg::ValueData::ExpandString(value),
+            }) => variables.push(Variable {
+                scope: Scope::System,
+                source: source.clone(),
+                name,
+                value,
+            }),
+            Ok(value) => log::debug!("unexpected environment value type: {value:?}"),
+            Err(error) => log::warn!("failed to read value of '{source}': {error}"),
+        }
+    }
+
+    variables
+}
+
+/// Checks whether the given directory can be written to by a non-admin user.
+///
+/// The effective rights granted by the DACL of the directory are evaluated for
+/// the groups every non-admin user belongs to. Directories owned by principals
+/// other than administrators, `SYSTEM` or `TrustedInstaller` are considered
+/// writable as their owners can always change the DACL.
+pub fn writable_by_non_admin(path: &Path) -> std::io::Result<bool> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    let path_wide = path.as_os_str().encode_wide()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+
+    let mut owner = std::ptr::null_mut();
+    let mut dacl = std::ptr::null_mut();
+    let mut descriptor = std::ptr::null_mut();
+
+    // SAFETY: We pass a null-terminated path and valid locations for the
+    // results. On success, the owner and DACL point into the descriptor that
+    // we free below.
+    let status = unsafe {
+        GetNamedSecurityInfoW(
+            path_wide.as_ptr(),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION,
+            &mut owner,
+            std::ptr::null_mut(),
+            &mut dacl,
+            std::ptr::null_mut(),
+            &mut descriptor,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return Err(std::io::Error::from_raw_os_error(status as i32));
+    }
+
+    let result = evaluate(owner, dacl);
+
+    // SAFETY: The descriptor was allocated by the call above and neither it
+    // nor the pointers into it are used after this point.
+    unsafe {
+        LocalFree(descriptor);
+    }
+
+    result
+}
+
+/// Checks whether the given owner and DACL allow writes by a non-admin user.
+fn evaluate(owner: PSID, dacl: *mut ACL) -> std::io::Result<bool> {
+    // A null DACL grants full access to everyone.
+    if dacl.is_null() {
+        return Ok(true);
+    }
+
+    if !owner.is_null() && !is_admin(owner)? {
+        return Ok(true);
+    }
+
+    for sid_type in NON_ADMIN_SIDS {
+        // SIDs consist of 32-bit values, so the buffer has to be aligned as
+        // such.
+        let mut sid = [0u32; SECURITY_MAX_SID_SIZE as usize / 4];
+        let mut sid_len = SECURITY_MAX_SID_SIZE;
+
+        // SAFETY: We pass a buffer along with its size. We verify the result
+        // below.
+        let status = unsafe {
+            CreateWellKnownSid(sid_type, std::ptr::null_mut(), sid.as_mut_ptr().cast(), &mut sid_len)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: The structure is plain data for which zeroes are valid.
+        let mut trustee = unsafe {
+            std::mem::zeroed::<TRUSTEE_W>()
+        };
+        // SAFETY: The SID is valid and outlives the trustee.
+        unsafe {
+            BuildTrusteeWithSidW(&mut trustee, sid.as_mut_ptr().cast());
+        }
+
+        let mut rights = 0;
+
+        // SAFETY: The DACL and the trustee are valid. We verify the result
+        // below.
+        let status = unsafe {
+            GetEffectiveRightsFromAclW(dacl, &trustee, &mut rights)
+        };
+        if status != ERROR_SUCCESS {
+            return Err(std::io::Error::from_raw_os_error(status as i32));
+        }
+
+        if rights & WRITE_RIGHTS != 0 {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Checks whether the given SID belongs to an administrative principal.
+fn is_admin(sid: PSID) -> std::io::Result<bool> {
+    // SAFETY: The SID is valid (as guaranteed by the caller).
+    let is_well_known_admin = unsafe {
+        IsWellKnownSid(sid, WinBuiltinAdministratorsSid) != FALSE ||
+        IsWellKnownSid(sid, WinLocalSystemSid) != FALSE
+    };
+    if is_well_known_admin {
+        return Ok(true);
+    }
+
+    let trusted_installer_sid_wide = TRUSTED_INSTALLER_SID.encode_utf16()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+    let mut trusted_installer_sid = std::ptr::null_mut();
+
+    // SAFETY: We pass a null-terminated SID string. The SID is allocated by the
+    // system and we free it below.
+    let status = unsafe {
+        ConvertStringSidToSidW(trusted_installer_sid_wide.as_ptr(), &mut trusted_installer_sid)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: Both SIDs are valid.
+    let is_trusted_installer = unsafe {
+        EqualSid(sid, trusted_installer_sid) != FALSE
+    };
+
+    // SAFETY: The SID was allocated by `ConvertStringSidToSidW` and it has to
+    // be freed with `LocalFree` [1]. It is no longer used after the call.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertstringsidtosidw
+    unsafe {
+        LocalFree(trusted_installer_sid);
+    }
+
+    Ok(is_trusted_installer)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn variables_path() {
+        let variables = variables();
+
+        assert! {
+            variables.iter().any(|variable| variable.name.eq_ignore_ascii_case("Path"))
+        };
+    }
+
+    #[test]
+    fn writable_by_non_admin_system_root() {
+        let system_root = std::env::var_os("SystemRoot")
+            .unwrap();
+
+        assert!(!writable_by_non_admin(&Path::new(&system_root).join("System32")).unwrap());
+    }
+}
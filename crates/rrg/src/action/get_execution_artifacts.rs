@@ -0,0 +1,452 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the execution artifacts action.
+//!
+//! Parsers of the artifacts are platform-independent (so that they can be
+//! tested against fixtures everywhere), but artifacts are collected only on
+//! Windows.
+
+#[cfg(any(target_family = "windows", test))]
+mod prefetch;
+
+#[cfg(any(target_family = "windows", test))]
+mod shimcache;
+
+#[cfg(any(target_family = "windows", test))]
+mod xpress;
+
+/// Path to the system directory used if it cannot be read from the environment.
+#[cfg(target_family = "windows")]
+const DEFAULT_SYSTEM_ROOT: &str = "C:\\Windows";
+
+/// Registry key (relative to `HKEY_LOCAL_MACHINE`) of the shimcache value.
+#[cfg(target_family = "windows")]
+const SHIMCACHE_KEY: &str = "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\AppCompatCache";
+
+/// Name of the registry value with the shimcache.
+#[cfg(target_family = "windows")]
+const SHIMCACHE_VALUE: &str = "AppCompatCache";
+
+/// Arguments of the `get_execution_artifacts` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Whether to collect artifacts from prefetch files.
+    prefetch: bool,
+    /// Whether to collect artifacts from the shimcache.
+    shimcache: bool,
+}
+
+/// A result of the `get_execution_artifacts` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+enum Item {
+    /// Artifact collected from a prefetch file.
+    Prefetch {
+        /// Path to the prefetch file.
+        path: std::path::PathBuf,
+        /// Information parsed from the prefetch file.
+        prefetch: Prefetch,
+    },
+    /// Artifact collected from the shimcache.
+    ShimcacheEntry(ShimcacheEntry),
+}
+
+/// Information parsed from a prefetch file.
+#[cfg(any(target_family = "windows", test))]
+#[derive(Debug)]
+struct Prefetch {
+    /// Version of the prefetch file format.
+    version: u32,
+    /// Name of the executable.
+    executable_name: String,
+    /// Hash of the path to the executable.
+    hash: u32,
+    /// Number of times the executable was run.
+    run_count: u32,
+    /// Times at which the executable was last run (the most recent first).
+    last_run_times: Vec<std::time::SystemTime>,
+    /// Paths of files loaded by the executable shortly after it started.
+    file_names: Vec<String>,
+}
+
+/// Information parsed from a shimcache entry.
+#[cfg(any(target_family = "windows", test))]
+#[derive(Debug, PartialEq, Eq)]
+struct ShimcacheEntry {
+    /// Position of the entry in the cache.
+    position: u32,
+    /// Path to the file the entry is for.
+    path: String,
+    /// Last modification time of the file (if recorded).
+    last_modification_time: Option<std::time::SystemTime>,
+}
+
+/// Handles invocations of the `get_execution_artifacts` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    // Failure to collect from one of the sources should not prevent us from
+    // collecting from the other, so we report it only once we are done.
+    let mut error = None;
+
+    if args.prefetch {
+        if let Err(prefetch_error) = handle_prefetch(session)? {
+            log::error!("failed to collect prefetch files: {prefetch_error}");
+            error = Some(prefetch_error);
+        }
+    }
+
+    if args.shimcache {
+        if let Err(shimcache_error) = handle_shimcache(session)? {
+            log::error!("failed to collect shimcache: {shimcache_error}");
+            error = error.or(Some(shimcache_error));
+        }
+    }
+
+    match error {
+        Some(error) => Err(crate::session::Error::action(error)),
+        None => Ok(()),
+    }
+}
+
+/// Collects artifacts from all prefetch files of the system.
+///
+/// The outer result is a session error, the inner one is a failure to list the
+/// prefetch directory. Failures of individual files are only logged.
+#[cfg(target_family = "windows")]
+fn handle_prefetch<S>(session: &mut S) -> crate::session::Result<Result<(), CollectError>>
+where
+    S: crate::session::Session,
+{
+    let system_root = std::env::var_os("SystemRoot")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::path::PathBuf::from(DEFAULT_SYSTEM_ROOT));
+    let prefetch_path = system_root.join("Prefetch");
+
+    let entries = match std::fs::read_dir(&prefetch_path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            return Ok(Err(CollectError {
+                source: Source::Prefetch,
+                error: error.into(),
+            }));
+        }
+    };
+
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read prefetch directory entry: {error}");
+                continue;
+            }
+        };
+
+        let is_prefetch = path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pf"));
+        if !is_prefetch {
+            continue;
+        }
+
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(error) => {
+                log::warn!("failed to read prefetch file '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        let prefetch = match self::prefetch::parse(&data) {
+            Ok(prefetch) => prefetch,
+            Err(error) => {
+                log::warn!("failed to parse prefetch file '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        session.reply(Item::Prefetch {
+            path,
+            prefetch,
+        })?;
+    }
+
+    Ok(Ok(()))
+}
+
+/// Collects artifacts from the shimcache.
+///
+/// The outer result is a session error, the inner one is a failure to read or
+/// parse the cache.
+#[cfg(target_family = "windows")]
+fn handle_shimcache<S>(session: &mut S) -> crate::session::Result<Result<(), CollectError>>
+where
+    S: crate::session::Session,
+{
+    let data = match read_shimcache() {
+        Ok(data) => data,
+        Err(error) => {
+            return Ok(Err(CollectError {
+                source: Source::Shimcache,
+                error: error.into(),
+            }));
+        }
+    };
+
+    let entries = match self::shimcache::parse(&data) {
+        Ok(entries) => entries,
+        Err(error) => {
+            return Ok(Err(CollectError {
+                source: Source::Shimcache,
+                error: error.into(),
+            }));
+        }
+    };
+
+    for entry in entries {
+        session.reply(Item::ShimcacheEntry(entry))?;
+    }
+
+    Ok(Ok(()))
+}
+
+/// Reads the raw shimcache from the registry.
+#[cfg(target_family = "windows")]
+fn read_shimcache() -> std::io::Result<Vec<u8>> {
+    let key = winreg::PredefinedKey::LocalMachine
+        .open(std::ffi::OsStr::new(SHIMCACHE_KEY))?;
+
+    match key.value_data(std::ffi::OsStr::new(SHIMCACHE_VALUE))? {
+        winreg::ValueData::Bytes(data) => Ok(data),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "shimcache value is not binary",
+        )),
+    }
+}
+
+/// Source of execution artifacts.
+#[cfg(target_family = "windows")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// Prefetch files.
+    Prefetch,
+    /// The shimcache.
+    Shimcache,
+}
+
+/// An error indicating that it was not possible to collect from a source.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct CollectError {
+    /// Source from which the collection failed.
+    source: Source,
+    /// Underlying cause of the failure.
+    error: Box<dyn std::error::Error + Send + Sync>,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for CollectError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.source {
+            Source::Prefetch => write!(fmt, "prefetch: {}", self.error),
+            Source::Shimcache => write!(fmt, "shimcache: {}", self.error),
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for CollectError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// Handles invocations of the `get_execution_artifacts` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_execution_artifacts::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use rrg_proto::get_execution_artifacts::Source as Proto;
+
+        if proto.sources.is_empty() {
+            return Ok(Args {
+                prefetch: true,
+                shimcache: true,
+            });
+        }
+
+        let mut args = Args {
+            prefetch: false,
+            shimcache: false,
+        };
+
+        for source in proto.sources {
+            match source.enum_value() {
+                Ok(Proto::PREFETCH) => args.prefetch = true,
+                Ok(Proto::SHIMCACHE) => args.shimcache = true,
+                Ok(Proto::UNKNOWN) | Err(_) => {
+                    return Err(crate::request::ParseArgsError::invalid_field("sources", SourceError {
+                        value: source.value(),
+                    }));
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_execution_artifacts::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::get_execution_artifacts::Source;
+
+        let mut proto = rrg_proto::get_execution_artifacts::Result::new();
+
+        match self {
+            Item::Prefetch { path, prefetch } => {
+                proto.set_source(Source::PREFETCH);
+
+                let prefetch_proto = proto.mut_prefetch();
+                prefetch_proto.set_path(crate::path::into_proto(path));
+                prefetch_proto.set_format_version(prefetch.version);
+                prefetch_proto.set_executable_name(prefetch.executable_name);
+                prefetch_proto.set_hash(prefetch.hash);
+                prefetch_proto.set_run_count(prefetch.run_count);
+                prefetch_proto.last_run_times = prefetch.last_run_times.into_iter()
+                    .map(rrg_proto::into_timestamp)
+                    .collect();
+                prefetch_proto.file_names = prefetch.file_names;
+            }
+            Item::ShimcacheEntry(entry) => {
+                proto.set_source(Source::SHIMCACHE);
+
+                let entry_proto = proto.mut_shimcache_entry();
+                entry_proto.set_position(entry.position);
+                entry_proto.set_path(entry.path);
+                if let Some(time) = entry.last_modification_time {
+                    entry_proto.set_last_modification_time(rrg_proto::into_timestamp(time));
+                }
+            }
+        }
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an unknown source.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct SourceError {
+    /// Raw value of the source.
+    value: i32,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for SourceError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown execution artifact source: {}", self.value)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for SourceError {
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_shimcache() {
+        let args = Args {
+            prefetch: false,
+            shimcache: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let entries = session.replies::<Item>()
+            .map(|item| match item {
+                Item::ShimcacheEntry(entry) => entry,
+                item => panic!("unexpected item: {item:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        for (position, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.position as usize, position);
+            assert!(!entry.path.is_empty());
+        }
+    }
+
+    #[test]
+    fn item_into_proto_prefetch() {
+        use crate::response::Item as _;
+
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200);
+
+        let item = Item::Prefetch {
+            path: std::path::PathBuf::from("C:\\Windows\\Prefetch\\CMD.EXE-0BD30981.pf"),
+            prefetch: Prefetch {
+                version: 30,
+                executable_name: String::from("CMD.EXE"),
+                hash: 0x0BD30981,
+                run_count: 42,
+                last_run_times: vec![time],
+                file_names: vec![String::from("\\VOLUME{01d9}\\WINDOWS\\SYSTEM32\\CMD.EXE")],
+            },
+        };
+
+        let proto = item.into_proto();
+        assert_eq!(proto.source(), rrg_proto::get_execution_artifacts::Source::PREFETCH);
+        assert_eq!(proto.prefetch().format_version(), 30);
+        assert_eq!(proto.prefetch().executable_name(), "CMD.EXE");
+        assert_eq!(proto.prefetch().hash(), 0x0BD30981);
+        assert_eq!(proto.prefetch().run_count(), 42);
+        assert_eq!(proto.prefetch().last_run_times.len(), 1);
+        assert_eq!(proto.prefetch().last_run_times[0].seconds, 1_704_067_200);
+        assert_eq!(proto.prefetch().file_names.len(), 1);
+    }
+
+    #[test]
+    fn item_into_proto_shimcache_entry() {
+        use crate::response::Item as _;
+
+        let item = Item::ShimcacheEntry(ShimcacheEntry {
+            position: 3,
+            path: String::from("C:\\Windows\\explorer.exe"),
+            last_modification_time: None,
+        });
+
+        let proto = item.into_proto();
+        assert_eq!(proto.source(), rrg_proto::get_execution_artifacts::Source::SHIMCACHE);
+        assert_eq!(proto.shimcache_entry().position(), 3);
+        assert_eq!(proto.shimcache_entry().path(), "C:\\Windows\\explorer.exe");
+        assert!(!proto.shimcache_entry().has_last_modification_time());
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser of prefetch files.
+//!
+//! There is no official documentation of the format, the layout here follows
+//! the community documentation of the [libscca] project.
+//!
+//! [libscca]: https://github.com/libyal/libscca/blob/main/documentation/Windows%20Prefetch%20File%20(PF)%20format.asciidoc
+
+use super::Prefetch;
+
+/// Signature of uncompressed prefetch files.
+const SIGNATURE: &[u8; 4] = b"SCCA";
+
+/// Signature of compressed prefetch files (used since Windows 10).
+const COMPRESSED_SIGNATURE: &[u8; 3] = b"MAM";
+
+/// Identifier of the LZ77+Huffman compression format.
+///
+/// See the documentation of [`RtlCompressBuffer`][1] for all the formats.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows-hardware/drivers/ddi/ntifs/nf-ntifs-rtlcompressbuffer
+const COMPRESSION_FORMAT_XPRESS_HUFF: u8 = 0x04;
+
+/// Maximum size of decompressed prefetch files that we are willing to handle.
+///
+/// Real prefetch files rarely exceed a few hundreds of kilobytes.
+const MAX_DECOMPRESSED_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Size of the file header (the same for all format versions).
+const HEADER_SIZE: usize = 84;
+
+/// Parses the given contents of a prefetch file (compressed or not).
+pub fn parse(data: &[u8]) -> Result<Prefetch, ParseError> {
+    if data.starts_with(COMPRESSED_SIGNATURE) {
+        parse_uncompressed(&decompress(data)?)
+    } else {
+        parse_uncompressed(data)
+    }
+}
+
+/// Decompresses the given contents of a compressed prefetch file.
+fn decompress(data: &[u8]) -> Result<Vec<u8>, ParseError> {
+    // The byte after the signature has the compression format in the lower
+    // bits and the highest bit tells whether a checksum follows the size.
+    let flags = *data.get(3).ok_or(ParseError::Truncated)?;
+
+    let format = flags & 0x0F;
+    if format != COMPRESSION_FORMAT_XPRESS_HUFF {
+        return Err(ParseError::UnsupportedCompression(format));
+    }
+
+    let size = u32_at(data, 4)?;
+    if size > MAX_DECOMPRESSED_SIZE {
+        return Err(ParseError::TooLarge(size));
+    }
+
+    // We do not verify the checksum: the decompressor has to deal with any
+    // input anyway and the parsed data is validated on its own.
+    let header_size = if flags & 0x80 != 0 { 12 } else { 8 };
+    let compressed = data.get(header_size..).ok_or(ParseError::Truncated)?;
+
+    super::xpress::decompress(compressed, size as usize)
+        .map_err(ParseError::Decompress)
+}
+
+/// Parses the given contents of an uncompressed prefetch file.
+fn parse_uncompressed(data: &[u8]) -> Result<Prefetch, ParseError> {
+    if data.len() < HEADER_SIZE {
+        return Err(ParseError::Truncated);
+    }
+    if &data[4..8] != SIGNATURE {
+        return Err(ParseError::InvalidSignature);
+    }
+
+    let version = u32_at(data, 0)?;
+
+    // Offsets of the run information depend on the version of the format
+    // (and the size of the file information section in case of version 30).
+    let (last_run_time_offsets, run_count_offset) = match version {
+        17 => (120..128, 144),
+        23 => (128..136, 152),
+        26 | 30 | 31 => {
+            // The file information section is either 224 or 216 bytes. There
+            // is no explicit size, but it is followed by the file metrics.
+            let run_count_offset = match u32_at(data, HEADER_SIZE)? {
+                300 => 200,
+                _ => 208,
+            };
+
+            (128..192, run_count_offset)
+        }
+        _ => return Err(ParseError::UnsupportedVersion(version)),
+    };
+
+    let last_run_times = data.get(last_run_time_offsets)
+        .ok_or(ParseError::Truncated)?
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        // Unused slots are zeroed.
+        .filter(|ticks| *ticks != 0)
+        .map(winreg::time::from_file_time)
+        .collect();
+
+    let file_names_offset = u32_at(data, 100)? as usize;
+    let file_names_size = u32_at(data, 104)? as usize;
+    let file_names = data.get(file_names_offset..)
+        .and_then(|data| data.get(..file_names_size))
+        .ok_or(ParseError::Truncated)?;
+
+    Ok(Prefetch {
+        version,
+        executable_name: utf16_strings(&data[16..76]).into_iter().next().unwrap_or_default(),
+        hash: u32_at(data, 76)?,
+        run_count: u32_at(data, run_count_offset)?,
+        last_run_times,
+        file_names: utf16_strings(file_names),
+    })
+}
+
+/// Splits the given data into null-terminated UTF-16 strings.
+///
+/// Empty strings are skipped and so is the incomplete last character (if the
+/// data has odd length).
+fn utf16_strings(data: &[u8]) -> Vec<String> {
+    let chars = data.chunks_exact(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+        .collect::<Vec<_>>();
+
+    chars.split(|char| *char == 0)
+        .filter(|string| !string.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect()
+}
+
+/// Reads a little-endian 32-bit integer at the given offset.
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bytes = data.get(offset..offset + 4)
+        .ok_or(ParseError::Truncated)?;
+
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// An error that might occur when parsing a prefetch file.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file ended prematurely.
+    Truncated,
+    /// The file does not have the prefetch signature.
+    InvalidSignature,
+    /// The file uses an unknown version of the format.
+    UnsupportedVersion(u32),
+    /// The file is compressed with an unsupported format.
+    UnsupportedCompression(u8),
+    /// The decompressed file would be larger than we are willing to handle.
+    TooLarge(u32),
+    /// The compressed file is malformed.
+    Decompress(super::xpress::Error),
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+
+        match self {
+            Truncated => write!(fmt, "truncated prefetch file"),
+            InvalidSignature => write!(fmt, "invalid prefetch signature"),
+            UnsupportedVersion(version) => {
+                write!(fmt, "unsupported prefetch version: {version}")
+            }
+            UnsupportedCompression(format) => {
+                write!(fmt, "unsupported prefetch compression: {format:#x}")
+            }
+            TooLarge(size) => {
+                write!(fmt, "prefetch file too large: {size} bytes")
+            }
+            Decompress(_) => write!(fmt, "failed to decompress prefetch file"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Decompress(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Returns the time `secs` seconds after 2024-01-01 (used by fixtures).
+    fn time(secs: u64) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200 + secs)
+    }
+
+    const FILE_NAMES: [&str; 3] = [
+        "\\VOLUME{01d9f0e1a2b3c4d5-1234abcd}\\WINDOWS\\SYSTEM32\\NTDLL.DLL",
+        "\\VOLUME{01d9f0e1a2b3c4d5-1234abcd}\\WINDOWS\\SYSTEM32\\KERNEL32.DLL",
+        "\\VOLUME{01d9f0e1a2b3c4d5-1234abcd}\\WINDOWS\\SYSTEM32\\CMD.EXE",
+    ];
+
+    #[test]
+    fn parse_v17() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v17.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.version, 17);
+        assert_eq!(prefetch.executable_name, "CMD.EXE");
+        assert_eq!(prefetch.hash, 0x0BD30981);
+        assert_eq!(prefetch.run_count, 3);
+        assert_eq!(prefetch.last_run_times, vec![time(17)]);
+        assert_eq!(prefetch.file_names, FILE_NAMES);
+    }
+
+    #[test]
+    fn parse_v23() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v23.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.version, 23);
+        assert_eq!(prefetch.executable_name, "CMD.EXE");
+        assert_eq!(prefetch.run_count, 5);
+        assert_eq!(prefetch.last_run_times, vec![time(23)]);
+        assert_eq!(prefetch.file_names, FILE_NAMES);
+    }
+
+    #[test]
+    fn parse_v26() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v26.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.version, 26);
+        assert_eq!(prefetch.executable_name, "CMD.EXE");
+        assert_eq!(prefetch.run_count, 7);
+        assert_eq!(prefetch.last_run_times, vec![time(260), time(250), time(240)]);
+        assert_eq!(prefetch.file_names, FILE_NAMES);
+    }
+
+    #[test]
+    fn parse_v30() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v30.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.version, 30);
+        assert_eq!(prefetch.run_count, 42);
+        assert_eq! {
+            prefetch.last_run_times,
+            (0..8).map(|i| time(300 - 10 * i)).collect::<Vec<_>>()
+        };
+        assert_eq!(prefetch.file_names, FILE_NAMES);
+    }
+
+    #[test]
+    fn parse_v30_short_file_information() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v30_216.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.version, 30);
+        assert_eq!(prefetch.run_count, 9);
+        assert_eq!(prefetch.last_run_times, vec![time(302)]);
+    }
+
+    #[test]
+    fn parse_v30_compressed() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v30_mam.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.version, 30);
+        assert_eq!(prefetch.executable_name, "CMD.EXE");
+        assert_eq!(prefetch.hash, 0x0BD30981);
+        assert_eq!(prefetch.run_count, 42);
+        assert_eq!(prefetch.last_run_times.len(), 8);
+        assert_eq!(prefetch.last_run_times[0], time(300));
+
+        // The fixture is big enough to span multiple compressed blocks.
+        assert_eq!(prefetch.file_names.len(), 2003);
+        assert_eq!(prefetch.file_names[..3], FILE_NAMES);
+        assert_eq! {
+            prefetch.file_names[2002],
+            "\\VOLUME{01d9f0e1a2b3c4d5-1234abcd}\\PROGRAM FILES\\FOO\\LIB01999.DLL"
+        };
+    }
+
+    #[test]
+    fn parse_v30_compressed_checksum() {
+        let prefetch = parse(include_bytes!("../../../testdata/prefetch_v30_mam_crc.pf"))
+            .unwrap();
+
+        assert_eq!(prefetch.executable_name, "NOTEPAD.EXE");
+        assert_eq!(prefetch.hash, 0xD8414F97);
+        assert_eq!(prefetch.run_count, 1);
+        assert_eq!(prefetch.last_run_times, vec![time(301)]);
+    }
+
+    #[test]
+    fn parse_invalid_signature() {
+        let mut data = include_bytes!("../../../testdata/prefetch_v30.pf").to_vec();
+        data[4..8].copy_from_slice(b"ABCD");
+
+        assert!(matches!(parse(&data), Err(ParseError::InvalidSignature)));
+    }
+
+    #[test]
+    fn parse_unsupported_version() {
+        let mut data = include_bytes!("../../../testdata/prefetch_v30.pf").to_vec();
+        data[0..4].copy_from_slice(&42_u32.to_le_bytes());
+
+        assert!(matches!(parse(&data), Err(ParseError::UnsupportedVersion(42))));
+    }
+
+    #[test]
+    fn parse_unsupported_compression() {
+        let mut data = include_bytes!("../../../testdata/prefetch_v30_mam.pf").to_vec();
+        // LZNT1 (used by NTFS compression).
+        data[3] = 0x02;
+
+        assert!(matches!(parse(&data), Err(ParseError::UnsupportedCompression(0x02))));
+    }
+
+    #[test]
+    fn parse_too_large() {
+        let mut data = include_bytes!("../../../testdata/prefetch_v30_mam.pf").to_vec();
+        data[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(parse(&data), Err(ParseError::TooLarge(u32::MAX))));
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let data = include_bytes!("../../../testdata/prefetch_v30.pf");
+
+        for len in 0..data.len() {
+            // File names are placed before the volume information (that we do
+            // not parse), so there is no error for the last few truncations.
+            if len >= data.len() - 104 {
+                break;
+            }
+
+            assert!(parse(&data[..len]).is_err(), "len: {len}");
+        }
+    }
+
+    #[test]
+    fn parse_compressed_truncated() {
+        let data = include_bytes!("../../../testdata/prefetch_v30_mam.pf");
+
+        assert!(parse(&data[..data.len() / 2]).is_err());
+        assert!(parse(&data[..8]).is_err());
+        assert!(parse(&data[..3]).is_err());
+    }
+}
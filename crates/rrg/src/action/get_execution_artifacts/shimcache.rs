@@ -0,0 +1,299 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser of the Application Compatibility Cache (shimcache).
+//!
+//! The cache is stored as a single binary registry value with a layout that
+//! differs between Windows versions. Only layouts used since Windows 8 are
+//! supported. There is no official documentation of them, the layout here
+//! follows the Mandiant whitepaper "Leveraging the Application Compatibility
+//! Cache in Forensic Investigations" and the parsers that followed it.
+
+use super::ShimcacheEntry;
+
+/// Size of the header of the Windows 8 and 8.1 layout.
+const WIN8_HEADER_SIZE: u32 = 0x80;
+
+/// Size of the header of the Windows 10 layout.
+const WIN10_HEADER_SIZE: u32 = 0x30;
+
+/// Size of the header of the Windows 10 layout since the Creators Update.
+const WIN10_CREATORS_HEADER_SIZE: u32 = 0x34;
+
+/// Signature of entries of the Windows 8 layout.
+const WIN80_ENTRY_SIGNATURE: &[u8] = b"00ts";
+
+/// Signature of entries of the Windows 8.1 and Windows 10 layouts.
+const WIN81_ENTRY_SIGNATURE: &[u8] = b"10ts";
+
+/// Size of the entry header (signature, checksum and size of the entry data).
+const ENTRY_HEADER_SIZE: usize = 12;
+
+/// Parses the given raw value of the cache.
+pub fn parse(data: &[u8]) -> Result<Vec<ShimcacheEntry>, ParseError> {
+    // The layout can be determined from the first field, which is the size of
+    // the header (that is otherwise mostly statistics) for all the supported
+    // layouts.
+    let header_size = u32_at(data, 0)?;
+    let layout = match header_size {
+        WIN8_HEADER_SIZE => Layout::Win8,
+        WIN10_HEADER_SIZE | WIN10_CREATORS_HEADER_SIZE => Layout::Win10,
+        _ => return Err(ParseError::UnsupportedFormat(header_size)),
+    };
+
+    let mut offset = header_size as usize;
+
+    // Windows 8 and 8.1 layouts are the same except for the signature, so we
+    // accept whichever is used by the first entry.
+    let signature = match (layout, data.get(offset..offset + 4)) {
+        (_, None) => return Ok(Vec::new()),
+        (Layout::Win8, Some(WIN80_ENTRY_SIGNATURE)) => WIN80_ENTRY_SIGNATURE,
+        (_, Some(WIN81_ENTRY_SIGNATURE)) => WIN81_ENTRY_SIGNATURE,
+        (_, Some(_)) => return Err(ParseError::InvalidEntry(offset)),
+    };
+
+    let mut entries = Vec::new();
+
+    while offset + ENTRY_HEADER_SIZE <= data.len() {
+        if &data[offset..offset + 4] != signature {
+            return Err(ParseError::InvalidEntry(offset));
+        }
+
+        let entry_size = u32_at(data, offset + 8)? as usize;
+        let entry = data.get(offset + ENTRY_HEADER_SIZE..)
+            .and_then(|data| data.get(..entry_size))
+            .ok_or(ParseError::Truncated)?;
+
+        let mut entry = Reader {
+            data: entry,
+            offset: 0,
+        };
+
+        let path_len = usize::from(entry.u16()?);
+        let path = entry.utf16(path_len)?;
+
+        if layout == Layout::Win8 {
+            // Package name of Windows Store apps (which are not files, so we
+            // do not have any use for it) followed by insertion and shim flags.
+            let package_len = usize::from(entry.u16()?);
+            entry.bytes(package_len)?;
+            entry.bytes(8)?;
+        }
+
+        let last_modification_time = match entry.u64()? {
+            0 => None,
+            ticks => Some(winreg::time::from_file_time(ticks)),
+        };
+
+        entries.push(ShimcacheEntry {
+            position: entries.len() as u32,
+            path,
+            last_modification_time,
+        });
+
+        offset += ENTRY_HEADER_SIZE + entry_size;
+    }
+
+    Ok(entries)
+}
+
+/// Layout of the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// Layout used by Windows 8 and 8.1.
+    Win8,
+    /// Layout used by Windows 10 and 11.
+    Win10,
+}
+
+/// Cursor over data of an individual cache entry.
+struct Reader<'a> {
+    /// Data of the entry.
+    data: &'a [u8],
+    /// Offset of the next unread byte.
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+
+    /// Reads the next `len` bytes.
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        let bytes = self.data.get(self.offset..self.offset + len)
+            .ok_or(ParseError::Truncated)?;
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    /// Reads the next little-endian 16-bit integer.
+    fn u16(&mut self) -> Result<u16, ParseError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    /// Reads the next little-endian 64-bit integer.
+    fn u64(&mut self) -> Result<u64, ParseError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Reads the next UTF-16 string of `len` bytes.
+    fn utf16(&mut self, len: usize) -> Result<String, ParseError> {
+        let chars = self.bytes(len)?
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect::<Vec<_>>();
+
+        Ok(String::from_utf16_lossy(&chars))
+    }
+}
+
+/// Reads a little-endian 32-bit integer at the given offset.
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bytes = data.get(offset..offset + 4)
+        .ok_or(ParseError::Truncated)?;
+
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// An error that might occur when parsing the cache.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The cache ended prematurely.
+    Truncated,
+    /// The cache uses an unsupported layout (identified by its first field).
+    UnsupportedFormat(u32),
+    /// The entry at the given offset does not have the expected signature.
+    InvalidEntry(usize),
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+
+        match self {
+            Truncated => write!(fmt, "truncated shimcache"),
+            UnsupportedFormat(signature) => {
+                write!(fmt, "unsupported shimcache format: {signature:#x}")
+            }
+            InvalidEntry(offset) => {
+                write!(fmt, "invalid shimcache entry at offset {offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Returns the time `secs` seconds after 2024-01-01 (used by fixtures).
+    fn time(secs: u64) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200 + secs)
+    }
+
+    fn assert_fixture_entries(entries: &[ShimcacheEntry]) {
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[0].position, 0);
+        assert_eq!(entries[0].path, "\\??\\C:\\Windows\\system32\\cmd.exe");
+        assert_eq!(entries[0].last_modification_time, Some(time(1000)));
+
+        assert_eq!(entries[1].position, 1);
+        assert_eq!(entries[1].path, "\\??\\C:\\Program Files\\Foo\\foo.exe");
+        assert_eq!(entries[1].last_modification_time, Some(time(2000)));
+
+        assert_eq!(entries[2].position, 2);
+        assert_eq!(entries[2].path, "\\??\\C:\\Users\\Ünicode\\bar.exe");
+        assert_eq!(entries[2].last_modification_time, Some(time(3000)));
+
+        assert_eq!(entries[3].position, 3);
+        assert_eq!(entries[3].path, "SYSVOL\\Windows\\explorer.exe");
+        assert_eq!(entries[3].last_modification_time, None);
+    }
+
+    #[test]
+    fn parse_win80() {
+        let entries = parse(include_bytes!("../../../testdata/shimcache_win80.bin"))
+            .unwrap();
+
+        assert_fixture_entries(&entries);
+    }
+
+    #[test]
+    fn parse_win81() {
+        let entries = parse(include_bytes!("../../../testdata/shimcache_win81.bin"))
+            .unwrap();
+
+        assert_fixture_entries(&entries);
+    }
+
+    #[test]
+    fn parse_win10() {
+        let entries = parse(include_bytes!("../../../testdata/shimcache_win10_v1.bin"))
+            .unwrap();
+
+        assert_fixture_entries(&entries);
+    }
+
+    #[test]
+    fn parse_win10_creators() {
+        let entries = parse(include_bytes!("../../../testdata/shimcache_win10.bin"))
+            .unwrap();
+
+        assert_fixture_entries(&entries);
+    }
+
+    #[test]
+    fn parse_empty() {
+        let mut data = vec![0; WIN10_CREATORS_HEADER_SIZE as usize];
+        data[0..4].copy_from_slice(&WIN10_CREATORS_HEADER_SIZE.to_le_bytes());
+
+        assert_eq!(parse(&data), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_unsupported_format() {
+        // Signature of the Windows 7 layout.
+        let data = 0xBADC0FEE_u32.to_le_bytes();
+
+        assert_eq!(parse(&data), Err(ParseError::UnsupportedFormat(0xBADC0FEE)));
+    }
+
+    #[test]
+    fn parse_win10_with_win80_signature() {
+        let mut data = include_bytes!("../../../testdata/shimcache_win10.bin").to_vec();
+        let offset = WIN10_CREATORS_HEADER_SIZE as usize;
+        data[offset..offset + 4].copy_from_slice(WIN80_ENTRY_SIGNATURE);
+
+        assert_eq!(parse(&data), Err(ParseError::InvalidEntry(offset)));
+    }
+
+    #[test]
+    fn parse_invalid_entry() {
+        let mut data = include_bytes!("../../../testdata/shimcache_win81.bin").to_vec();
+        let offset = WIN8_HEADER_SIZE as usize;
+        let entry_size = u32_at(&data, offset + 8).unwrap() as usize;
+
+        // Corrupt the signature of the second entry.
+        let second_offset = offset + ENTRY_HEADER_SIZE + entry_size;
+        data[second_offset] = b'X';
+
+        assert_eq!(parse(&data), Err(ParseError::InvalidEntry(second_offset)));
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let data = include_bytes!("../../../testdata/shimcache_win10.bin");
+
+        // Trailing bytes that do not fit an entry header are ignored, so only
+        // cuts in the middle of an entry are errors.
+        assert_eq!(parse(&data[..2]), Err(ParseError::Truncated));
+        assert_eq!(parse(&data[..data.len() - 1]), Err(ParseError::Truncated));
+    }
+}
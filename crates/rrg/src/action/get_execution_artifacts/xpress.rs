@@ -0,0 +1,396 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Decompressor of the LZ77+Huffman (also known as Xpress Huffman) format.
+//!
+//! See the [MS-XCA] specification for the details about the format.
+//!
+//! [MS-XCA]: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-xca
+
+/// Number of bytes of decompressed data in a single block.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Number of bytes of the Huffman code table at the beginning of each block.
+const TABLE_SIZE: usize = 256;
+
+/// Number of symbols of the Huffman code (literals and match descriptors).
+const SYMBOL_COUNT: usize = 2 * TABLE_SIZE;
+
+/// Maximum length (in bits) of an individual Huffman code.
+const MAX_CODE_LEN: u32 = 15;
+
+/// Decompresses data in the LZ77+Huffman format.
+///
+/// The format does not store the size of the decompressed data, so it has to
+/// be known upfront (e.g. from a header of the container of the data).
+pub fn decompress(input: &[u8], size: usize) -> Result<Vec<u8>, Error> {
+    let mut input = Input::new(input);
+
+    // We do not trust `size` with the initial allocation, as it usually comes
+    // from the same untrusted source as the compressed data.
+    let mut output = Vec::with_capacity(std::cmp::min(size, BLOCK_SIZE));
+
+    while output.len() < size {
+        let table = Table::new(input.bytes(TABLE_SIZE)?)
+            .map_err(|kind| Error { kind, offset: input.pos })?;
+
+        input.start_bits()?;
+
+        let block_end = std::cmp::min(output.len() + BLOCK_SIZE, size);
+        while output.len() < block_end {
+            let symbol = table.symbols[input.peek_bits(MAX_CODE_LEN) as usize];
+            input.consume_bits(u32::from(table.lens[usize::from(symbol)]))?;
+
+            if let Ok(literal) = u8::try_from(symbol) {
+                output.push(literal);
+                continue;
+            }
+
+            let symbol = symbol - 256;
+            let offset_bit_len = u32::from(symbol >> 4);
+
+            let mut match_len = usize::from(symbol & 0b1111);
+            if match_len == 15 {
+                match_len = usize::from(input.byte()?);
+                if match_len == 255 {
+                    let offset = input.pos;
+                    match_len = usize::from(input.u16()?);
+                    if match_len < 15 {
+                        return Err(Error {
+                            kind: ErrorKind::InvalidMatchLen,
+                            offset,
+                        });
+                    }
+                    match_len -= 15;
+                }
+                match_len += 15;
+            }
+            match_len += 3;
+
+            let match_offset = (input.peek_bits(offset_bit_len) | (1 << offset_bit_len)) as usize;
+            input.consume_bits(offset_bit_len)?;
+
+            let Some(match_start) = output.len().checked_sub(match_offset) else {
+                return Err(Error {
+                    kind: ErrorKind::InvalidMatchOffset,
+                    offset: input.pos,
+                });
+            };
+
+            // The match can overlap with the bytes it produces (which is how
+            // runs are encoded), so we have to copy byte by byte.
+            for index in match_start..match_start + match_len {
+                output.push(output[index]);
+            }
+        }
+    }
+
+    // The last match could have produced more bytes than we need.
+    output.truncate(size);
+
+    Ok(output)
+}
+
+/// Decoding table of the Huffman code of a single block.
+struct Table {
+    /// Lengths of codes of all symbols.
+    lens: [u8; SYMBOL_COUNT],
+    /// Symbols indexed by the next (most significant) 15 bits of input.
+    symbols: Vec<u16>,
+}
+
+impl Table {
+
+    /// Creates a decoding table from the raw table at the beginning of a block.
+    fn new(raw: &[u8]) -> Result<Table, ErrorKind> {
+        // Each byte stores 4-bit code lengths of two consecutive symbols (with
+        // the lower bits used for the even one).
+        let mut lens = [0; SYMBOL_COUNT];
+        for (index, byte) in raw.iter().enumerate() {
+            lens[2 * index] = byte & 0b1111;
+            lens[2 * index + 1] = byte >> 4;
+        }
+
+        // The code is canonical: codes are assigned in the order of increasing
+        // lengths (and symbol values for codes of the same length), so that
+        // symbols occupy consecutive ranges of the table.
+        let mut symbols = vec![0; 1 << MAX_CODE_LEN];
+        let mut symbols_len = 0;
+        for code_len in 1..=MAX_CODE_LEN as u8 {
+            for (symbol, len) in lens.iter().enumerate() {
+                if *len != code_len {
+                    continue;
+                }
+
+                let count = 1 << (MAX_CODE_LEN - u32::from(code_len));
+                let Some(entries) = symbols.get_mut(symbols_len..symbols_len + count) else {
+                    return Err(ErrorKind::InvalidTable);
+                };
+                entries.fill(symbol as u16);
+                symbols_len += count;
+            }
+        }
+
+        // Incomplete codes would leave some bit sequences without a symbol.
+        if symbols_len != symbols.len() {
+            return Err(ErrorKind::InvalidTable);
+        }
+
+        Ok(Table {
+            lens,
+            symbols,
+        })
+    }
+}
+
+/// Compressed input of the decompressor.
+struct Input<'a> {
+    /// Raw compressed data.
+    data: &'a [u8],
+    /// Position in the compressed data of the next unread byte.
+    pos: usize,
+    /// Buffered bits (on the most significant positions).
+    bits: u32,
+    /// Number of buffered bits beyond the 16 that are always available.
+    extra_bits_len: i32,
+}
+
+impl<'a> Input<'a> {
+
+    /// Number of bytes that can be read as bits past the end of the data.
+    ///
+    /// The bit buffer is refilled eagerly, so it can ask for (at most) two
+    /// words past the last one used by the compressed data. Such words do not
+    /// affect the output, so we allow them to be missing and use zeros.
+    const MAX_PADDING: usize = 4;
+
+    fn new(data: &'a [u8]) -> Input<'a> {
+        Input {
+            data,
+            pos: 0,
+            bits: 0,
+            extra_bits_len: 0,
+        }
+    }
+
+    /// Reads the next `len` bytes.
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let Some(bytes) = self.data.get(self.pos..self.pos + len) else {
+            return Err(Error {
+                kind: ErrorKind::Truncated,
+                offset: self.pos,
+            });
+        };
+        self.pos += len;
+
+        Ok(bytes)
+    }
+
+    /// Reads the next byte.
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Reads the next little-endian 16-bit integer.
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Reads the next little-endian 16-bit word of bits.
+    fn word(&mut self) -> Result<u32, Error> {
+        if self.pos + 2 > self.data.len() + Self::MAX_PADDING {
+            return Err(Error {
+                kind: ErrorKind::Truncated,
+                offset: self.pos,
+            });
+        }
+
+        let lo = self.data.get(self.pos).copied().unwrap_or(0);
+        let hi = self.data.get(self.pos + 1).copied().unwrap_or(0);
+        self.pos += 2;
+
+        Ok(u32::from(u16::from_le_bytes([lo, hi])))
+    }
+
+    /// Fills the bit buffer at the beginning of a block.
+    fn start_bits(&mut self) -> Result<(), Error> {
+        self.bits = self.word()? << 16;
+        self.bits |= self.word()?;
+        self.extra_bits_len = 16;
+
+        Ok(())
+    }
+
+    /// Returns the next `len` (at most 16) bits without consuming them.
+    fn peek_bits(&self, len: u32) -> u32 {
+        match len {
+            0 => 0,
+            len => self.bits >> (32 - len),
+        }
+    }
+
+    /// Consumes the next `len` (at most 16) bits.
+    fn consume_bits(&mut self, len: u32) -> Result<(), Error> {
+        self.bits <<= len;
+        self.extra_bits_len -= len as i32;
+
+        if self.extra_bits_len < 0 {
+            self.bits |= self.word()? << -self.extra_bits_len;
+            self.extra_bits_len += 16;
+        }
+
+        Ok(())
+    }
+}
+
+/// An error that might occur when decompressing data.
+#[derive(Debug)]
+pub struct Error {
+    /// Type of the error.
+    kind: ErrorKind,
+    /// Offset in the compressed data at which the error occurred.
+    offset: usize,
+}
+
+/// Type of an error that might occur when decompressing data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    /// The compressed data ended prematurely.
+    Truncated,
+    /// The Huffman code table of a block does not describe a complete code.
+    InvalidTable,
+    /// Extended length of a match was too small.
+    InvalidMatchLen,
+    /// Offset of a match pointed before the beginning of the output.
+    InvalidMatchOffset,
+}
+
+impl std::fmt::Display for Error {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ErrorKind::*;
+
+        match self.kind {
+            Truncated => write!(fmt, "truncated input")?,
+            InvalidTable => write!(fmt, "invalid Huffman table")?,
+            InvalidMatchLen => write!(fmt, "invalid match length")?,
+            InvalidMatchOffset => write!(fmt, "invalid match offset")?,
+        }
+
+        write!(fmt, " at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for Error {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn decompress_blocks() {
+        let input = include_bytes!("../../../testdata/xpress_huffman.bin");
+
+        // See the fixture generator for the origin of this data: it spans
+        // multiple blocks and includes a long run (encoded with the extended
+        // match length).
+        let expected = (0..150_000_u64)
+            .map(|i| match i {
+                70_000..71_000 => 0,
+                i => ((i * i / 7 + i / 300) % 251) as u8,
+            })
+            .collect::<Vec<u8>>();
+
+        assert_eq!(decompress(input, expected.len()).unwrap(), expected);
+    }
+
+    #[test]
+    fn decompress_prefix() {
+        let input = include_bytes!("../../../testdata/xpress_huffman.bin");
+
+        let output = decompress(input, 1024).unwrap();
+        assert_eq!(output.len(), 1024);
+        assert_eq!(output[..], decompress(input, 150_000).unwrap()[..1024]);
+    }
+
+    #[test]
+    fn decompress_empty() {
+        assert_eq!(decompress(b"", 0).unwrap(), b"");
+    }
+
+    #[test]
+    fn decompress_truncated() {
+        let input = include_bytes!("../../../testdata/xpress_huffman.bin");
+
+        let error = decompress(&input[..input.len() / 2], 150_000).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Truncated);
+
+        let error = decompress(&input[..TABLE_SIZE - 1], 150_000).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Truncated);
+    }
+
+    #[test]
+    fn decompress_incomplete_table() {
+        // Only two symbols with codes of length 2 (half of the code space).
+        let mut input = vec![0; TABLE_SIZE + 4];
+        input[0] = 0x22;
+
+        let error = decompress(&input, 1).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidTable);
+    }
+
+    #[test]
+    fn decompress_oversubscribed_table() {
+        // Three symbols with codes of length 1.
+        let mut input = vec![0; TABLE_SIZE + 4];
+        input[0] = 0x11;
+        input[1] = 0x01;
+
+        let error = decompress(&input, 1).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidTable);
+    }
+
+    #[test]
+    fn decompress_literals() {
+        // Symbols `a` and `b` with codes `0` and `1` respectively.
+        let mut input = vec![0; TABLE_SIZE];
+        input[usize::from(b'a') / 2] = 0x10;
+        input[usize::from(b'b') / 2] |= 0x01;
+        input.extend_from_slice(&0b0110_0000_0000_0000_u16.to_le_bytes());
+        input.extend_from_slice(&0_u16.to_le_bytes());
+
+        assert_eq!(decompress(&input, 4).unwrap(), b"abba");
+    }
+
+    #[test]
+    fn decompress_invalid_offset() {
+        // Literal `a` with code `0` and match (length 3, offset 1) with code
+        // `1`: the match at the very beginning has nothing to refer to.
+        let mut input = vec![0; TABLE_SIZE];
+        input[usize::from(b'a') / 2] = 0x10;
+        input[256 / 2] = 0x01;
+        input.extend_from_slice(&0b1000_0000_0000_0000_u16.to_le_bytes());
+        input.extend_from_slice(&0_u16.to_le_bytes());
+
+        let error = decompress(&input, 4).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidMatchOffset);
+    }
+
+    #[test]
+    fn decompress_run() {
+        // Literal `a` with code `0` and match (length 3, offset 1) with code
+        // `1`: the match after the literal repeats it.
+        let mut input = vec![0; TABLE_SIZE];
+        input[usize::from(b'a') / 2] = 0x10;
+        input[256 / 2] = 0x01;
+        input.extend_from_slice(&0b0100_0000_0000_0000_u16.to_le_bytes());
+        input.extend_from_slice(&0_u16.to_le_bytes());
+
+        assert_eq!(decompress(&input, 4).unwrap(), b"aaaa");
+    }
+}
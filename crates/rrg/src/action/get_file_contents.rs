@@ -15,6 +15,51 @@ pub struct Args {
     offset: u64,
     /// Number of bytes to read from the file.
     len: usize,
+    /// Policy on symlinks in the path to the file.
+    symlink_policy: crate::fs::SymlinkPolicy,
+    /// Parameters of the upload if it should be resumable.
+    resumable: Option<Resumable>,
+}
+
+/// Parameters of a resumable upload.
+struct Resumable {
+    /// Number of bytes of each chunk of the upload.
+    chunk_len: usize,
+    /// Parameters of the interrupted upload to resume (if any).
+    resume: Option<Resume>,
+}
+
+/// Parameters of an interrupted upload to resume.
+struct Resume {
+    /// SHA-256 digest of the manifest of the interrupted upload.
+    manifest_sha256: [u8; 32],
+    /// Index of the first chunk that might not have been received.
+    from_chunk: u64,
+    /// Bitmap of chunks received already.
+    received_chunks: Vec<u8>,
+}
+
+impl Resumable {
+
+    /// Checks whether the chunk at the given index has been received already.
+    fn is_received(&self, index: u64) -> bool {
+        let Some(resume) = &self.resume else {
+            return false;
+        };
+
+        if index < resume.from_chunk {
+            return true;
+        }
+
+        let Ok(byte_index) = usize::try_from(index / 8) else {
+            return false;
+        };
+
+        match resume.received_chunks.get(byte_index) {
+            Some(byte) => byte & (1 << (index % 8)) != 0,
+            None => false,
+        }
+    }
 }
 
 /// Result of the `get_file_contents` action.
@@ -23,8 +68,77 @@ pub struct Item {
     offset: u64,
     /// Number of bytes of the file part sent to the blob sink.
     len: usize,
-    /// SHA-256 digest of the file part sent to the blob sink.
-    blob_sha256: [u8; 32],
+    /// Digests of the file part sent to the blob sink.
+    blob_digests: crate::blob::Digests,
+}
+
+/// Manifest of a resumable upload (sent as the first result).
+pub struct Manifest {
+    /// Byte offset of the file part.
+    offset: u64,
+    /// Number of bytes of the file part.
+    len: u64,
+    /// Number of bytes of each chunk.
+    chunk_len: usize,
+    /// SHA-256 digests of all the chunks.
+    chunk_sha256s: Vec<[u8; 32]>,
+}
+
+impl Manifest {
+
+    /// Computes the manifest of the file part by reading it in full.
+    fn compute(
+        file: &mut std::fs::File,
+        offset: u64,
+        len: usize,
+        chunk_len: usize,
+    ) -> std::io::Result<Manifest> {
+        use std::io::Seek as _;
+        use sha2::Digest as _;
+
+        file.seek(std::io::SeekFrom::Start(offset))?;
+
+        let mut manifest = Manifest {
+            offset,
+            len: 0,
+            chunk_len,
+            chunk_sha256s: Vec::new(),
+        };
+
+        let mut len_left = len;
+
+        while len_left > 0 {
+            let chunk = read_chunk(file, std::cmp::min(len_left, chunk_len))?;
+            if chunk.is_empty() {
+                break;
+            }
+
+            manifest.chunk_sha256s.push(sha2::Sha256::digest(&chunk).into());
+            manifest.len += chunk.len() as u64;
+            len_left -= chunk.len();
+
+            if chunk.len() < chunk_len {
+                break;
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Computes the SHA-256 digest of the manifest.
+    fn sha256(&self) -> [u8; 32] {
+        use sha2::Digest as _;
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(self.offset.to_le_bytes());
+        hasher.update(self.len.to_le_bytes());
+        hasher.update((self.chunk_len as u64).to_le_bytes());
+        for chunk_sha256 in &self.chunk_sha256s {
+            hasher.update(chunk_sha256);
+        }
+
+        hasher.finalize().into()
+    }
 }
 
 /// Handle invocations of the `get_file_contents` action.
@@ -33,11 +147,18 @@ where
     S: crate::session::Session,
 {
     use std::io::{Read as _, Seek as _};
-    use sha2::Digest as _;
 
-    let mut file = std::fs::File::open(args.path)
+    let mut file = crate::fs::open_file(&args.path, args.symlink_policy)
         .map_err(crate::session::Error::action)?;
 
+    if session.is_dry_run() {
+        return session.reply(estimate(&file, &args)?);
+    }
+
+    if let Some(resumable) = &args.resumable {
+        return handle_resumable(session, file, &args, resumable);
+    }
+
     let mut offset = args.offset;
     let mut len_left = args.len;
 
@@ -45,6 +166,10 @@ where
         .map_err(crate::session::Error::action)?;
 
     loop {
+        // We do not read more of the file while blobs sent so far are still
+        // waiting to be delivered (so that they do not pile up in memory).
+        crate::session::wait_for_sink(session, crate::Sink::Blob)?;
+
         let mut buf = vec![0; std::cmp::min(len_left, MAX_BLOB_LEN)];
 
         let len_read = file.read(&mut buf[..])
@@ -57,13 +182,14 @@ where
         buf.truncate(len_read);
 
         let blob = crate::blob::Blob::from(buf);
-        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
 
-        session.send(crate::Sink::Blob, blob)?;
+        let blob_digests = session.send_blob(blob)?
+            .wait()
+            .map_err(crate::session::Error::action)?;
         session.reply(Item {
             offset,
             len: len_read,
-            blob_sha256,
+            blob_digests,
         })?;
 
         offset += len_read as u64;
@@ -73,6 +199,134 @@ where
     Ok(())
 }
 
+/// Handles a resumable upload of the file part.
+///
+/// The manifest is computed in a separate pass over the file, so that a
+/// resumed upload can be verified to be of the same contents before any of
+/// the chunks are sent.
+fn handle_resumable<S>(
+    session: &mut S,
+    mut file: std::fs::File,
+    args: &Args,
+    resumable: &Resumable,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::Seek as _;
+
+    let manifest = Manifest::compute(&mut file, args.offset, args.len, resumable.chunk_len)
+        .map_err(crate::session::Error::action)?;
+
+    if let Some(resume) = &resumable.resume {
+        if manifest.sha256() != resume.manifest_sha256 {
+            return Err(crate::session::Error::action(ModifiedError {
+                path: args.path.clone(),
+            }));
+        }
+    }
+
+    let chunk_sha256s = manifest.chunk_sha256s.clone();
+    session.reply(manifest)?;
+
+    for (index, chunk_sha256) in chunk_sha256s.iter().enumerate() {
+        if resumable.is_received(index as u64) {
+            continue;
+        }
+
+        // Chunks are big, so we check the limits (and whether the agent is
+        // shutting down) before reading each one of them.
+        session.check_limits()?;
+        crate::session::wait_for_sink(session, crate::Sink::Blob)?;
+
+        let offset = args.offset + index as u64 * resumable.chunk_len as u64;
+        file.seek(std::io::SeekFrom::Start(offset))
+            .map_err(crate::session::Error::action)?;
+
+        let chunk = read_chunk(&mut file, resumable.chunk_len)
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(chunk);
+        if blob.digest(crate::blob::DigestAlgorithm::Sha256) != *chunk_sha256 {
+            return Err(crate::session::Error::action(ModifiedError {
+                path: args.path.clone(),
+            }));
+        }
+
+        let len = blob.as_bytes().len();
+        let blob_digests = blob.digests(crate::blob::digest_algorithms(session.args()));
+
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            offset,
+            len,
+            blob_digests,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Estimates the cost of getting the contents of the given file.
+///
+/// The estimate is based on the size of the file reported by its metadata, no
+/// contents are read.
+fn estimate(
+    file: &std::fs::File,
+    args: &Args,
+) -> crate::session::Result<crate::dry_run::Estimate> {
+    let metadata = file.metadata()
+        .map_err(crate::session::Error::action)?;
+
+    let mut notes = Vec::new();
+    if !metadata.is_file() {
+        // Special files (e.g. the ones in `/proc`) often report zero size but
+        // still have contents.
+        notes.push(String::from("not a regular file, size might be inaccurate"));
+    }
+
+    let len = std::cmp::min(
+        metadata.len().saturating_sub(args.offset),
+        args.len as u64,
+    );
+    let chunk_len = match &args.resumable {
+        Some(resumable) => resumable.chunk_len as u64,
+        None => MAX_BLOB_LEN as u64,
+    };
+
+    let mut estimate = crate::dry_run::Estimate {
+        notes,
+        ..Default::default()
+    };
+
+    for index in 0..len.div_ceil(chunk_len) {
+        if args.resumable.as_ref().is_some_and(|resumable| resumable.is_received(index)) {
+            continue;
+        }
+
+        estimate.item_count += 1;
+        estimate.bytes += std::cmp::min(chunk_len, len - index * chunk_len);
+    }
+    // Resumable uploads start with the manifest.
+    if args.resumable.is_some() {
+        estimate.item_count += 1;
+    }
+
+    Ok(estimate)
+}
+
+/// Reads a chunk of at most `len` bytes from the current position of the file.
+///
+/// Unlike a single read, the chunk is shorter only at the end of the file.
+fn read_chunk(file: &mut std::fs::File, len: usize) -> std::io::Result<Vec<u8>> {
+    use std::io::Read as _;
+
+    let mut chunk = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut chunk)?;
+
+    Ok(chunk)
+}
+
 impl crate::request::Args for Args {
 
     type Proto = rrg_proto::get_file_contents::Args;
@@ -80,7 +334,7 @@ impl crate::request::Args for Args {
     fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
         use crate::request::ParseArgsError;
 
-        let path = PathBuf::try_from(proto.take_path())
+        let path = crate::path::from_proto(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
         let len = match proto.length() {
@@ -93,12 +347,44 @@ impl crate::request::Args for Args {
             len => len as usize,
         };
 
+        let resume = match proto.resume_manifest_sha256() {
+            [] if proto.resume_from_chunk() != 0 || !proto.received_chunks().is_empty() => {
+                return Err(ParseArgsError::invalid_field("resume_manifest_sha256", MissingManifestError));
+            }
+            [] => None,
+            manifest_sha256 => Some(Resume {
+                manifest_sha256: <[u8; 32]>::try_from(manifest_sha256)
+                    .map_err(|error| ParseArgsError::invalid_field("resume_manifest_sha256", error))?,
+                from_chunk: proto.resume_from_chunk(),
+                received_chunks: proto.take_received_chunks(),
+            }),
+        };
+
+        let resumable = if proto.resumable() || resume.is_some() {
+            Some(Resumable {
+                chunk_len: MAX_BLOB_LEN,
+                resume,
+            })
+        } else {
+            None
+        };
+
         Ok(Args {
             path,
             offset: proto.offset(),
             len,
+            symlink_policy: if proto.deny_symlinks() {
+                crate::fs::SymlinkPolicy::Deny
+            } else {
+                crate::fs::SymlinkPolicy::Follow
+            },
+            resumable,
         })
     }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.path.as_path()]
+    }
 }
 
 impl crate::response::Item for Item {
@@ -109,7 +395,30 @@ impl crate::response::Item for Item {
         let mut proto = Self::Proto::default();
         proto.set_offset(self.offset);
         proto.set_length(self.len as u64);
-        proto.set_blob_sha256(self.blob_sha256.into());
+        if let Some(blob_sha256) = self.blob_digests.sha256 {
+            proto.set_blob_sha256(blob_sha256.into());
+        }
+        if let Some(blob_blake3) = self.blob_digests.blake3 {
+            proto.set_blob_blake3(blob_blake3.into());
+        }
+
+        proto
+    }
+}
+
+impl crate::response::Item for Manifest {
+
+    type Proto = rrg_proto::get_file_contents::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut manifest = rrg_proto::get_file_contents::Manifest::default();
+        manifest.set_sha256(self.sha256().to_vec());
+        manifest.set_length(self.len);
+        manifest.set_chunk_length(self.chunk_len as u64);
+        manifest.set_chunk_sha256s(self.chunk_sha256s.into_iter().map(Vec::from).collect());
+
+        let mut proto = Self::Proto::default();
+        proto.set_manifest(manifest);
 
         proto
     }
@@ -135,6 +444,37 @@ impl std::fmt::Display for LenError {
 impl std::error::Error for LenError {
 }
 
+/// An error indicating that resume arguments were given without a manifest.
+#[derive(Debug)]
+struct MissingManifestError;
+
+impl std::fmt::Display for MissingManifestError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing manifest digest of the upload to resume")
+    }
+}
+
+impl std::error::Error for MissingManifestError {
+}
+
+/// An error indicating that the file was modified during a resumable upload.
+#[derive(Debug)]
+struct ModifiedError {
+    /// Path to the modified file.
+    path: PathBuf,
+}
+
+impl std::fmt::Display for ModifiedError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "file '{}' modified since the upload started", self.path.display())
+    }
+}
+
+impl std::error::Error for ModifiedError {
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -152,6 +492,8 @@ mod tests {
             path: tempdir.path().join("foo"),
             offset: 0,
             len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -162,6 +504,72 @@ mod tests {
         assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
     }
 
+    #[test]
+    fn handle_dry_run() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), vec![0xAB; MAX_BLOB_LEN + 42])
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_dry_run();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+
+        let estimate = session.reply::<crate::dry_run::Estimate>(0);
+        assert_eq!(estimate.item_count, 2);
+        assert_eq!(estimate.bytes, MAX_BLOB_LEN as u64 + 42);
+    }
+
+    #[test]
+    fn handle_dry_run_resumed() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: Some(Resumable {
+                chunk_len: 4,
+                resume: Some(Resume {
+                    manifest_sha256: [0; 32],
+                    from_chunk: 1,
+                    received_chunks: vec![],
+                }),
+            }),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_dry_run();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+
+        let estimate = session.reply::<crate::dry_run::Estimate>(0);
+        // The manifest and the two chunks that have not been received yet.
+        assert_eq!(estimate.item_count, 3);
+        assert_eq!(estimate.bytes, 6);
+    }
+
     #[test]
     fn handle_small_file_all() {
         let tempdir = tempfile::tempdir()
@@ -174,6 +582,8 @@ mod tests {
             path: tempdir.path().join("foo"),
             offset: 0,
             len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -192,6 +602,48 @@ mod tests {
         assert_eq!(blob.as_bytes(), b"0123456789");
     }
 
+    #[test]
+    fn handle_sink_pressure() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), vec![0xf0; 3 * MAX_BLOB_LEN])
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
+        };
+
+        // The sink becomes congested after the first blob is sent and clears
+        // after being checked a few times.
+        let checks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut session = crate::session::FakeSession::new();
+        session.simulate_sink_pressure(crate::Sink::Blob, 1024, 0, {
+            let checks = checks.clone();
+            move || {
+                checks.set(checks.get() + 1);
+                match checks.get() {
+                    2..=4 => 4096,
+                    _ => 0,
+                }
+            }
+        });
+
+        handle(&mut session, args)
+            .unwrap();
+
+        // While stalled, the action should keep heartbeating without sending
+        // anything new.
+        assert_eq!(session.heartbeats(), &[1, 1, 1]);
+
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 3);
+        assert_eq!(session.reply_count(), 3);
+    }
+
     #[test]
     fn handle_small_file_from_offset() {
         let tempdir = tempfile::tempdir()
@@ -204,6 +656,8 @@ mod tests {
             path: tempdir.path().join("foo"),
             offset: 5,
             len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -234,6 +688,8 @@ mod tests {
             path: tempdir.path().join("foo"),
             offset: 0,
             len: 5,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -260,6 +716,8 @@ mod tests {
             path: PathBuf::from("/dev/zero"),
             offset: 0,
             len: MAX_BLOB_LEN * 2 + 1337,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -300,6 +758,8 @@ mod tests {
             path: PathBuf::from("/dev/zero"),
             offset: 0xb33f,
             len: MAX_BLOB_LEN + 1337,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -316,4 +776,303 @@ mod tests {
         assert_eq!(item.offset, 0xb33f + MAX_BLOB_LEN as u64);
         assert_eq!(item.len, 1337);
     }
+
+    #[test]
+    fn handle_default_digests() {
+        use crate::blob::DigestAlgorithm;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        let sha256 = blob.digest(DigestAlgorithm::Sha256);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.blob_digests.sha256, Some(sha256));
+        assert_eq!(item.blob_digests.blake3, None);
+    }
+
+    #[test]
+    fn handle_blake3_digests() {
+        use crate::blob::DigestAlgorithm;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
+        };
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            blob_digests: vec![DigestAlgorithm::Blake3],
+            ..Default::default()
+        });
+        handle(&mut session, args)
+            .unwrap();
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        let blake3 = blob.digest(DigestAlgorithm::Blake3);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.blob_digests.sha256, None);
+        assert_eq!(item.blob_digests.blake3, Some(blake3));
+    }
+
+    #[test]
+    fn item_into_proto_blake3_only() {
+        use crate::response::Item as _;
+
+        let item = Item {
+            offset: 0,
+            len: 3,
+            blob_digests: crate::blob::Digests {
+                sha256: None,
+                blake3: Some([0x42; 32]),
+            },
+        };
+
+        let proto = item.into_proto();
+        assert!(proto.blob_sha256().is_empty());
+        assert_eq!(proto.blob_blake3(), [0x42; 32]);
+    }
+
+    /// Runs a resumable upload of the given file in chunks of 4 bytes.
+    ///
+    /// If `interrupt_after` is given, the session fails after sending the given
+    /// number of chunks (as if the transport was interrupted).
+    fn handle_resumable_chunks(
+        path: &std::path::Path,
+        resume: Option<Resume>,
+        interrupt_after: Option<usize>,
+    ) -> (crate::session::FakeSession, bool) {
+        let args = Args {
+            path: path.to_path_buf(),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: Some(Resumable {
+                chunk_len: 4,
+                resume,
+            }),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        if let Some(interrupt_after) = interrupt_after {
+            // Limits are checked once before every chunk, so we simulate the
+            // interrupt as a limit that is crossed after the given number of
+            // checks.
+            let check_count = std::cell::Cell::new(0);
+            session.simulate_memory_usage(interrupt_after, move || {
+                check_count.set(check_count.get() + 1);
+                check_count.get()
+            });
+        }
+
+        let is_ok = handle(&mut session, args).is_ok();
+
+        (session, is_ok)
+    }
+
+    /// Returns offsets of the chunks sent in a resumable upload.
+    fn chunk_offsets(session: &crate::session::FakeSession) -> Vec<u64> {
+        (1..session.reply_count())
+            .map(|id| session.reply::<Item>(id).offset)
+            .collect()
+    }
+
+    #[test]
+    fn handle_resumable_manifest() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let (session, is_ok) = handle_resumable_chunks(&tempdir.path().join("foo"), None, None);
+        assert!(is_ok);
+
+        let manifest = session.reply::<Manifest>(0);
+        assert_eq!(manifest.len, 10);
+        assert_eq!(manifest.chunk_sha256s, vec![
+            <[u8; 32]>::from(sha2::Sha256::digest(b"0123")),
+            <[u8; 32]>::from(sha2::Sha256::digest(b"4567")),
+            <[u8; 32]>::from(sha2::Sha256::digest(b"89")),
+        ]);
+
+        assert_eq!(chunk_offsets(&session), vec![0, 4, 8]);
+
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| blob.as_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(blobs, vec![&b"0123"[..], &b"4567"[..], &b"89"[..]]);
+    }
+
+    #[test]
+    fn handle_resumable_interrupted() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789abcdefghij")
+            .unwrap();
+
+        let (session, is_ok) = handle_resumable_chunks(&tempdir.path().join("foo"), None, Some(2));
+        assert!(!is_ok);
+        assert_eq!(chunk_offsets(&session), vec![0, 4]);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 2);
+
+        let manifest_sha256 = session.reply::<Manifest>(0).sha256();
+
+        // Chunk #3 is marked as received as well (e.g. because it arrived out
+        // of order before the interrupt).
+        let resume = Resume {
+            manifest_sha256,
+            from_chunk: 2,
+            received_chunks: vec![0b0000_1000],
+        };
+
+        let (session, is_ok) = handle_resumable_chunks(&tempdir.path().join("foo"), Some(resume), None);
+        assert!(is_ok);
+        assert_eq!(chunk_offsets(&session), vec![8, 16]);
+
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| blob.as_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(blobs, vec![&b"89ab"[..], &b"ghij"[..]]);
+    }
+
+    #[test]
+    fn handle_resumable_modified() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let (session, _) = handle_resumable_chunks(&tempdir.path().join("foo"), None, Some(1));
+        let manifest_sha256 = session.reply::<Manifest>(0).sha256();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789!")
+            .unwrap();
+
+        let resume = Resume {
+            manifest_sha256,
+            from_chunk: 1,
+            received_chunks: Vec::new(),
+        };
+
+        let (session, is_ok) = handle_resumable_chunks(&tempdir.path().join("foo"), Some(resume), None);
+        assert!(!is_ok);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[test]
+    fn args_from_proto_resume_without_manifest() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_file_contents::Args::new();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.set_resume_from_chunk(1);
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_resume_implies_resumable() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_file_contents::Args::new();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.set_resume_manifest_sha256(vec![0; 32]);
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+        assert!(args.resumable.is_some_and(|resumable| resumable.resume.is_some()));
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlinked_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("dir"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("dir").join("foo"), b"0123456789")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.path().join("dir"), tempdir.path().join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("link").join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            resumable: None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"0123456789");
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlinked_dir_denied() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.join("dir"))
+            .unwrap();
+        std::fs::write(tempdir.join("dir").join("foo"), b"0123456789")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.join("dir"), tempdir.join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link").join("foo"),
+            offset: 0,
+            len: usize::MAX,
+            symlink_policy: crate::fs::SymlinkPolicy::Deny,
+            resumable: None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
 }
@@ -16,6 +16,12 @@ pub struct Args {
     sha1: bool,
     /// Whether to collect SHA-256 digest of the file contents.
     sha256: bool,
+    /// Policy on symlinks in the root path and paths of digested files.
+    symlink_policy: crate::fs::SymlinkPolicy,
+    /// Tolerance for modification and access times lying in the future.
+    future_time_tolerance: std::time::Duration,
+    /// Unicode normalization form to apply to reported paths.
+    normalize_unicode: crate::path::Normalization,
 }
 
 /// Result of the `get_file_metadata` action.
@@ -27,6 +33,12 @@ struct Item {
     /// Extended attributes of the file.
     #[cfg(target_family = "unix")]
     ext_attrs: Vec<ospect::fs::ExtAttr>,
+    /// Name of the user owning the file (if it could be resolved).
+    #[cfg(target_family = "unix")]
+    owner_name: Option<std::ffi::OsString>,
+    /// Name of the group owning the file (if it could be resolved).
+    #[cfg(target_family = "unix")]
+    group_name: Option<std::ffi::OsString>,
     // TODO(@panhania): Add support for file flags (also known as attributes).
     //
     // Collection of them is already implemented in the `ospect` crate, but it
@@ -39,8 +51,39 @@ struct Item {
     // attributes!) and on macOS it is called "flags".
     /// Path to the file pointed by a symlink (if available).
     symlink: Option<PathBuf>,
+    /// Target of the symlink (if the file is a symlink that is not broken).
+    symlink_target: Option<SymlinkTarget>,
     /// Digest of the file contents.
     digest: Digest,
+    /// Snapshot of the agent clock taken when the collection started.
+    collection_clock: crate::clock::Snapshot,
+    /// Whether the modification or access time lies in the future.
+    anomalous_time: bool,
+}
+
+impl Item {
+
+    /// Normalizes all the paths of the item to the given form.
+    ///
+    /// This should be done only once the item is about to be sent, as the
+    /// normalized paths might not point to the actual files anymore.
+    fn normalize_unicode(&mut self, normalization: crate::path::Normalization) {
+        self.path = normalization.apply(std::mem::take(&mut self.path));
+        if let Some(symlink) = self.symlink.take() {
+            self.symlink = Some(normalization.apply(symlink));
+        }
+        if let Some(symlink_target) = &mut self.symlink_target {
+            symlink_target.path = normalization.apply(std::mem::take(&mut symlink_target.path));
+        }
+    }
+}
+
+/// Information about a file that a symlink points to.
+struct SymlinkTarget {
+    /// Canonical path to the target.
+    path: PathBuf,
+    /// Metadata of the target.
+    metadata: std::fs::Metadata,
 }
 
 /// Handles invocations of the `get_file_metadata` action.
@@ -55,7 +98,13 @@ where
         return Err(crate::session::Error::action(error));
     }
 
-    let metadata = args.path.symlink_metadata()
+    let mmap_threshold = session.args().mmap_threshold;
+
+    // Files with times in the future are flagged relative to the snapshot, see
+    // the [`crate::clock`] module for more details.
+    let collection_clock = crate::clock::Snapshot::now();
+
+    let metadata = crate::fs::symlink_metadata(&args.path, args.symlink_policy)
         .map_err(crate::session::Error::action)?;
 
     #[cfg(target_family = "unix")]
@@ -75,13 +124,19 @@ where
         path = canonicalize_parent(&args.path);
         symlink = Some(std::fs::read_link(&args.path));
     } else {
-        path = args.path.canonicalize();
+        path = canonicalize(&args.path);
         symlink = None;
     };
 
     let path = path.map_err(crate::session::Error::action)?;
     let symlink = symlink.transpose().map_err(crate::session::Error::action)?;
 
+    let symlink_target = if metadata.is_symlink() {
+        resolve_symlink(&args.path, args.symlink_policy)
+    } else {
+        None
+    };
+
     // We log warnings here instead of the `digest` method to avoid repeated
     // messages for (potential) child files.
     if args.md5 && !cfg!(feature = "action-get_file_metadata-md5") {
@@ -94,19 +149,45 @@ where
         log::warn!("SHA-256 digest requested but not supported");
     }
 
-    session.reply(Item {
+    #[cfg(target_family = "unix")]
+    let (owner_name, group_name) = owner_names(&metadata);
+
+    let anomalous_time = collection_clock.is_anomalous(&metadata, args.future_time_tolerance);
+
+    let mut item = Item {
         path: path.clone(),
         metadata,
         #[cfg(target_family = "unix")]
         ext_attrs,
+        #[cfg(target_family = "unix")]
+        owner_name,
+        #[cfg(target_family = "unix")]
+        group_name,
         symlink,
-        digest: digest(&args.path, &args),
-    })?;
+        symlink_target,
+        digest: digest(&args.path, &args, mmap_threshold),
+        collection_clock: collection_clock.clone(),
+        anomalous_time,
+    };
+    item.normalize_unicode(args.normalize_unicode);
+    session.reply(item)?;
 
     if args.max_depth > 0 {
+        // Walked paths have to be checked against the policy before they are
+        // opened, while the session is also used to send the replies, so we
+        // need to share it.
+        let session = std::cell::RefCell::new(session);
+
         for entry in crate::fs::walk_dir(&path)
             .map_err(crate::session::Error::action)?
             .with_max_depth(args.max_depth)
+            .filter_entry(|entry| match session.borrow().check_path(&entry.path) {
+                Ok(()) => true,
+                Err(error) => {
+                    log::warn!("skipping '{}': {error}", entry.path.display());
+                    false
+                }
+            })
         {
             let entry = match entry {
                 Ok(entry) => entry,
@@ -155,22 +236,94 @@ where
                 None
             };
 
-            let digest = digest(&entry.path, &args);
+            let symlink_target = if entry.metadata.is_symlink() {
+                resolve_symlink(&entry.path, args.symlink_policy)
+            } else {
+                None
+            };
+
+            let digest = digest(&entry.path, &args, mmap_threshold);
+
+            #[cfg(target_family = "unix")]
+            let (owner_name, group_name) = owner_names(&entry.metadata);
+
+            let anomalous_time = collection_clock.is_anomalous(&entry.metadata, args.future_time_tolerance);
 
-            session.reply(Item {
+            let mut item = Item {
                 path: entry.path,
                 metadata: entry.metadata,
                 #[cfg(target_family = "unix")]
                 ext_attrs,
+                #[cfg(target_family = "unix")]
+                owner_name,
+                #[cfg(target_family = "unix")]
+                group_name,
                 symlink,
+                symlink_target,
                 digest,
-            })?;
+                collection_clock: collection_clock.clone(),
+                anomalous_time,
+            };
+            item.normalize_unicode(args.normalize_unicode);
+            session.borrow_mut().reply(item)?;
         }
     }
 
     Ok(())
 }
 
+/// Resolves names of the user and the group owning the file with the given
+/// metadata.
+#[cfg(target_family = "unix")]
+fn owner_names(
+    metadata: &std::fs::Metadata,
+) -> (Option<std::ffi::OsString>, Option<std::ffi::OsString>) {
+    use std::os::unix::fs::MetadataExt as _;
+
+    let resolver = crate::os::resolver();
+    (resolver.user_name(metadata.uid()), resolver.group_name(metadata.gid()))
+}
+
+/// Collects information about the file that the given symlink points to.
+///
+/// This is done on a best-effort basis: `None` is returned if the symlink is
+/// broken (or cannot be resolved for other reasons) and if symlinks are not to
+/// be followed according to the given policy.
+fn resolve_symlink(
+    path: &Path,
+    symlink_policy: crate::fs::SymlinkPolicy,
+) -> Option<SymlinkTarget> {
+    if symlink_policy == crate::fs::SymlinkPolicy::Deny {
+        return None;
+    }
+
+    // Canonicalization resolves the path component by component, so it deals
+    // with relative targets and chains of symlinks.
+    let target_path = match canonicalize(path) {
+        Ok(target_path) => target_path,
+        Err(error) => {
+            log::debug!("failed to resolve symlink '{}': {error}", path.display());
+            return None;
+        }
+    };
+
+    let target_metadata = match std::fs::metadata(&target_path) {
+        Ok(target_metadata) => target_metadata,
+        Err(error) => {
+            log::debug! {
+                "failed to collect metadata of symlink target '{}': {error}",
+                target_path.display(),
+            };
+            return None;
+        }
+    };
+
+    Some(SymlinkTarget {
+        path: target_path,
+        metadata: target_metadata,
+    })
+}
+
 /// Record with digest information of the file contents.
 #[derive(Default)]
 struct Digest {
@@ -186,20 +339,30 @@ struct Digest {
 }
 
 /// Computes the digest record of the file contents using requested algorithms.
-fn digest(path: &Path, args: &Args) -> Digest {
+///
+/// Regular files at least `mmap_threshold` bytes big are memory-mapped rather
+/// than read through a buffer.
+fn digest(path: &Path, args: &Args, mmap_threshold: Option<u64>) -> Digest {
     if !(args.md5 || args.sha1 || args.sha256) {
         // If no digests were requested, we do not need to read the file.
         return Digest::default();
     }
 
-    let mut file = match std::fs::File::open(path) {
-        Ok(file) => std::io::BufReader::new(file),
+    let file = match crate::fs::open_file(path, args.symlink_policy) {
+        Ok(file) => file,
         Err(error) => {
             log::error!("failed to open '{}' for digest: {error}", path.display());
             return Digest::default();
         }
     };
 
+    // Only regular files can be mapped and have meaningful length, others we
+    // read until the end.
+    let (len, mmap_threshold) = match file.metadata() {
+        Ok(metadata) if metadata.is_file() => (metadata.len(), mmap_threshold),
+        _ => (u64::MAX, None),
+    };
+
     #[cfg(feature = "action-get_file_metadata-md5")]
     let mut md5_hasher = if args.md5 {
         Some(<md5::Md5 as md5::Digest>::new())
@@ -221,17 +384,9 @@ fn digest(path: &Path, args: &Args) -> Digest {
         None
     };
 
-    loop {
-        use std::io::BufRead as _;
-
-        let buf = match file.fill_buf() {
-            Ok(buf) if buf.is_empty() => break,
-            Ok(buf) => buf,
-            Err(error) => {
-                log::error!("failed to read content of '{}' for digest: {error}", path.display());
-                return Digest::default();
-            }
-        };
+    let result = crate::mmap::read_chunks(&file, path, len, mmap_threshold, |buf| {
+        // Depending on the enabled features, the buffer might not be used.
+        let _ = buf;
 
         #[cfg(feature = "action-get_file_metadata-md5")]
         if let Some(ref mut md5_hasher) = md5_hasher {
@@ -247,9 +402,10 @@ fn digest(path: &Path, args: &Args) -> Digest {
         if let Some(ref mut sha256_hasher) = sha256_hasher {
             <_ as sha2::Digest>::update(sha256_hasher, buf);
         }
-
-        let buf_len = buf.len();
-        file.consume(buf_len);
+    });
+    if let Err(error) = result {
+        log::error!("failed to read content of '{}' for digest: {error}", path.display());
+        return Digest::default();
     }
 
     Digest {
@@ -269,17 +425,35 @@ impl crate::request::Args for Args {
     fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
         use crate::request::ParseArgsError;
 
-        let path = PathBuf::try_from(proto.take_path())
+        let path = crate::path::from_proto(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
+        let future_time_tolerance = if proto.has_future_time_tolerance() {
+            std::time::Duration::try_from(proto.take_future_time_tolerance())
+                .map_err(|error| ParseArgsError::invalid_field("future_time_tolerance", error))?
+        } else {
+            crate::clock::DEFAULT_FUTURE_TOLERANCE
+        };
+
         Ok(Args {
             path,
             max_depth: proto.max_depth(),
             md5: proto.md5(),
             sha1: proto.sha1(),
             sha256: proto.sha256(),
+            symlink_policy: if proto.deny_symlinks() {
+                crate::fs::SymlinkPolicy::Deny
+            } else {
+                crate::fs::SymlinkPolicy::Follow
+            },
+            future_time_tolerance,
+            normalize_unicode: proto.normalize_unicode().into(),
         })
     }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.path.as_path()]
+    }
 }
 
 impl crate::response::Item for Item {
@@ -288,7 +462,14 @@ impl crate::response::Item for Item {
 
     fn into_proto(self) -> Self::Proto {
         let mut proto = rrg_proto::get_file_metadata::Result::default();
-        proto.set_path(self.path.into());
+        proto.set_path(crate::path::into_proto(self.path));
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+
+            proto.set_unix_uid(self.metadata.uid());
+            proto.set_unix_gid(self.metadata.gid());
+        }
         proto.set_metadata(self.metadata.into());
 
         #[cfg(target_family = "unix")]
@@ -296,11 +477,25 @@ impl crate::response::Item for Item {
             for ext_attr in self.ext_attrs {
                 proto.mut_ext_attrs().push(ext_attr.into());
             }
+            if let Some(owner_name) = self.owner_name {
+                proto.set_unix_owner_name(owner_name.to_string_lossy().into_owned());
+            }
+            if let Some(group_name) = self.group_name {
+                proto.set_unix_group_name(group_name.to_string_lossy().into_owned());
+            }
         }
 
         if let Some(symlink) = self.symlink {
             proto.set_symlink(symlink.into());
         }
+        if let Some(symlink_target) = self.symlink_target {
+            let symlink_target_proto = proto.mut_symlink_target();
+            symlink_target_proto.set_path(crate::path::into_proto(symlink_target.path));
+            symlink_target_proto.set_metadata(symlink_target.metadata.into());
+        }
+
+        proto.set_collection_clock(self.collection_clock.into());
+        proto.set_anomalous_time(self.anomalous_time);
 
         #[cfg(feature = "action-get_file_metadata-md5")]
         if let Some(md5) = self.digest.md5 {
@@ -319,6 +514,25 @@ impl crate::response::Item for Item {
     }
 }
 
+/// Returns the canonical, absolute form of the path.
+///
+/// This is equivalent to [`std::fs::canonicalize`] except for paths in the
+/// Windows device namespace (e.g. files in volume shadow copies). The system
+/// resolves canonical paths to drive letters that such devices do not have, so
+/// these paths are returned as they are.
+fn canonicalize<P>(path: P) -> std::io::Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    if crate::fs::split_device_path(path).is_some() {
+        return Ok(path.to_path_buf());
+    }
+
+    path.canonicalize()
+}
+
 /// Returns the canonical, absolute form of the path.
 ///
 /// This is similar to [`std::fs::canonicalize`] but modifies only the dirname
@@ -339,7 +553,7 @@ where
         }
     };
 
-    let mut canonicalized = parent.canonicalize()?;
+    let mut canonicalized = canonicalize(parent)?;
 
     match path.file_name() {
         Some(file_name) => canonicalized.push(file_name),
@@ -371,6 +585,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -385,6 +602,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -407,6 +627,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -417,6 +640,220 @@ mod tests {
         let item = session.reply::<Item>(0);
         assert_eq!(item.path, tempdir.join("foo"));
         assert_eq!(item.metadata.is_file(), true);
+        assert!(!item.anomalous_time);
+    }
+
+    #[cfg(feature = "action-get_file_metadata-sha256")]
+    #[test]
+    fn handle_normalize_unicode() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        // Some filesystems (e.g. APFS) do not allow names that differ only in
+        // normalization within the same folder, so we put them in separate
+        // ones.
+        std::fs::create_dir(tempdir.join("nfc"))
+            .unwrap();
+        std::fs::create_dir(tempdir.join("nfd"))
+            .unwrap();
+        std::fs::write(tempdir.join("nfc").join("caf\u{e9}"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.join("nfd").join("cafe\u{301}"), b"foo")
+            .unwrap();
+
+        for (normalize_unicode, name) in [
+            (crate::path::Normalization::Nfc, "caf\u{e9}"),
+            (crate::path::Normalization::Nfd, "cafe\u{301}"),
+        ] {
+            let args = Args {
+                path: tempdir.clone(),
+                max_depth: 2,
+                md5: false,
+                sha1: false,
+                sha256: true,
+                symlink_policy: crate::fs::SymlinkPolicy::Follow,
+                future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+                normalize_unicode,
+            };
+
+            let mut session = crate::session::FakeSession::new();
+            assert!(handle(&mut session, args).is_ok());
+
+            let items_by_path = session.replies::<Item>()
+                .map(|item| (item.path.clone(), item))
+                .collect::<std::collections::HashMap<_, _>>();
+
+            // Files have to be still opened through their actual paths for the
+            // digests to be computed.
+            let nfc_item = &items_by_path[&tempdir.join("nfc").join(name)];
+            assert!(nfc_item.digest.sha256.is_some());
+            let nfd_item = &items_by_path[&tempdir.join("nfd").join(name)];
+            assert!(nfd_item.digest.sha256.is_some());
+        }
+    }
+
+    #[test]
+    fn handle_max_depth_denied_paths() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.join("secret"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("secret").join("foo"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("bar"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.clone(),
+            max_depth: 2,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            denied_paths: vec![tempdir.join("secret").display().to_string().parse().unwrap()],
+            ..Default::default()
+        });
+        session.set_action(crate::request::Action::GetFileMetadata);
+        assert!(handle(&mut session, args).is_ok());
+
+        let mut paths = session.replies::<Item>()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(paths, vec![tempdir.clone(), tempdir.join("bar")]);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_anomalous_time() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::File::create(tempdir.join("foo"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("bar"))
+            .unwrap();
+        crate::clock::tests::set_future_mtime(&tempdir.join("bar"));
+
+        let args = Args {
+            path: tempdir.clone(),
+            max_depth: 1,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let mut items = session.replies::<Item>().collect::<Vec<_>>();
+        items.sort_by_key(|item| item.path.clone());
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].path, tempdir);
+        assert!(!items[0].anomalous_time);
+        assert_eq!(items[1].path, tempdir.join("bar"));
+        assert!(items[1].anomalous_time);
+        assert_eq!(items[2].path, tempdir.join("foo"));
+        assert!(!items[2].anomalous_time);
+
+        // All the items should carry the same snapshot of the clock.
+        let realtime = |item: &Item| {
+            rrg_proto::os::ClockSnapshot::from(item.collection_clock.clone())
+                .take_realtime()
+        };
+        assert!(items.iter().all(|item| realtime(item) == realtime(items[0])));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_regular_file_owner_names() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::File::create(tempdir.join("foo"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("foo").to_path_buf(),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // SAFETY: `geteuid` and `getegid` are always safe to call.
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.owner_name, ospect::user::user_name(uid).unwrap());
+        assert_eq!(item.group_name, ospect::user::group_name(gid).unwrap());
+    }
+
+    // macOS filesystems do not allow file names that are not valid UTF-8.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_regular_file_non_utf8_name() {
+        use std::os::unix::ffi::OsStrExt as _;
+        use crate::request::Args as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        let path = tempdir.join(std::ffi::OsStr::from_bytes(b"foo\xff\xfe"));
+        std::fs::File::create(&path)
+            .unwrap();
+
+        let mut args = rrg_proto::get_file_metadata::Args::new();
+        args.mut_path().set_raw_bytes(path.as_os_str().as_bytes().to_vec());
+
+        let args = Args::from_proto(args)
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path.as_os_str().as_bytes(), path.as_os_str().as_bytes());
+        assert!(item.metadata.is_file());
+    }
+
+    #[test]
+    fn args_from_proto_embedded_nul() {
+        use crate::request::Args as _;
+
+        let mut args = rrg_proto::get_file_metadata::Args::new();
+        args.mut_path().set_raw_bytes(b"/etc/passwd\0.txt".to_vec());
+
+        assert!(Args::from_proto(args).is_err());
     }
 
     #[cfg(target_family = "unix")]
@@ -438,6 +875,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -451,6 +891,217 @@ mod tests {
         assert_eq!(item.symlink, Some(tempdir.join("file")));
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink_target() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::write(tempdir.join("file"), b"foobar")
+            .unwrap();
+        std::os::unix::fs::symlink("file", tempdir.join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link"),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert!(item.metadata.is_symlink());
+        assert_eq!(item.symlink, Some(PathBuf::from("file")));
+
+        let symlink_target = item.symlink_target.as_ref().unwrap();
+        assert_eq!(symlink_target.path, tempdir.join("file"));
+        assert!(symlink_target.metadata.is_file());
+        assert_eq!(symlink_target.metadata.len(), 6);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink_target_broken() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::os::unix::fs::symlink(tempdir.join("file"), tempdir.join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link"),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert!(item.metadata.is_symlink());
+        assert_eq!(item.symlink, Some(tempdir.join("file")));
+        assert!(item.symlink_target.is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink_target_chain() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::write(tempdir.join("file"), b"foobar")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.join("file"), tempdir.join("link1"))
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.join("link1"), tempdir.join("link2"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link2"),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert!(item.metadata.is_symlink());
+        assert_eq!(item.symlink, Some(tempdir.join("link1")));
+
+        let symlink_target = item.symlink_target.as_ref().unwrap();
+        assert_eq!(symlink_target.path, tempdir.join("file"));
+        assert!(symlink_target.metadata.is_file());
+        assert_eq!(symlink_target.metadata.len(), 6);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink_denied() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::File::create(tempdir.join("file"))
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.join("file"), tempdir.join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link"),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Deny,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlinked_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.join("dir"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("dir").join("file"))
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.join("dir"), tempdir.join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link").join("file"),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.join("dir").join("file"));
+        assert!(item.metadata.is_file());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlinked_dir_denied() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.join("dir"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("dir").join("file"))
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.join("dir"), tempdir.join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("link").join("file"),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Deny,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
     #[cfg(feature = "test-setfattr")]
     #[cfg(target_os = "linux")]
     #[test]
@@ -474,6 +1125,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -510,6 +1164,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -542,6 +1199,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -582,6 +1242,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -627,6 +1290,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -684,6 +1350,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -746,6 +1415,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -787,6 +1459,9 @@ mod tests {
             md5: true,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -821,6 +1496,9 @@ mod tests {
             md5: true,
             sha1: false,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -865,6 +1543,9 @@ mod tests {
             md5: false,
             sha1: true,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -899,6 +1580,9 @@ mod tests {
             md5: false,
             sha1: true,
             sha256: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -943,6 +1627,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: true,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -979,6 +1666,9 @@ mod tests {
             md5: false,
             sha1: false,
             sha256: true,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
         };
 
         let mut session = crate::session::FakeSession::new();
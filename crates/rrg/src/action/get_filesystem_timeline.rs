@@ -5,6 +5,9 @@
 
 //! A handler and associated types for the timeline action.
 
+#[cfg(target_family = "unix")]
+mod anomaly;
+
 use std::path::PathBuf;
 
 use rrg_proto::convert::FromLossy;
@@ -12,14 +15,63 @@ use rrg_proto::convert::FromLossy;
 /// Arguments of the `get_filesystem_timeline` action.
 pub struct Args {
     root: PathBuf,
+    /// Whether to encode entry paths relative to their parent folders.
+    delta_paths: bool,
+    /// Whether to resolve names of users and groups owning the files.
+    resolve_owner_names: bool,
+    /// Tolerance for modification and access times lying in the future.
+    future_time_tolerance: std::time::Duration,
+    /// Unicode normalization form to apply to reported paths.
+    normalize_unicode: crate::path::Normalization,
+    /// Options of inline type detection of files (if requested).
+    inline_magic: Option<InlineMagic>,
+    /// Options of flagging anomalies of entries (if requested).
+    anomaly_flags: Option<AnomalyFlags>,
+}
+
+/// Options of flagging anomalies of timeline entries.
+struct AnomalyFlags {
+    /// Directories in which setuid binaries are expected (defaults if empty).
+    #[cfg_attr(not(target_family = "unix"), allow(dead_code))]
+    setuid_prefixes: Vec<PathBuf>,
 }
 
+/// Options of inline type detection of timeline entries.
+struct InlineMagic {
+    /// Size of the biggest file for which the type is detected.
+    max_file_size: u64,
+    /// Total number of bytes that can be read for type detection.
+    byte_budget: u64,
+}
+
+/// Default size of the biggest file for which the type is detected.
+const DEFAULT_INLINE_MAGIC_MAX_FILE_SIZE: u64 = 16 * 1024 * 1024; // 16 MiB.
+
+/// Default number of bytes that can be read for type detection.
+const DEFAULT_INLINE_MAGIC_BYTE_BUDGET: u64 = 64 * 1024 * 1024; // 64 MiB.
+
 /// Result of the `get_filesystem_timeline` action.
 pub struct Item {
-    /// SHA-256 digest of the timeline batch sent to the blob sink.
-    blob_sha256: [u8; 32],
+    /// Digests of the timeline batch sent to the blob sink.
+    blob_digests: crate::blob::Digests,
     // Number of entries in the batch sent to the blob sink.
     entry_count: usize,
+    /// Upper directory of the overlayfs mount the root lies in (if any).
+    overlay_upper_dir: Option<PathBuf>,
+    /// Total length of full paths of entries in the batch.
+    full_path_len: u64,
+    /// Total length of paths of entries in the batch as they were encoded.
+    encoded_path_len: u64,
+    /// Snapshot of the agent clock taken when the collection started.
+    collection_clock: crate::clock::Snapshot,
+    /// Total number of bytes read for type detection of entries in the batch.
+    magic_read_len: u64,
+    /// Number of entries in the batch skipped because of the byte budget.
+    magic_skipped_count: u64,
+    /// Whether the collection stopped early (because of a limit).
+    truncated: bool,
+    /// Number of entries in the batch with each kind of anomaly.
+    anomaly_counts: Vec<(rrg_proto::get_filesystem_timeline::Anomaly, u64)>,
 }
 
 /// Handles requests for the timeline action.
@@ -27,7 +79,35 @@ pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
-    use sha2::Digest as _;
+    // Entries with times in the future are flagged relative to the snapshot
+    // (rather than to the time each of them was collected at), see the
+    // [`crate::clock`] module for more details.
+    let collection_clock = crate::clock::Snapshot::now();
+
+    if session.is_dry_run() {
+        return session.reply(estimate(&args)?);
+    }
+
+    // Nothing is collected if the deadline passed while the request waited to
+    // be handled (it would be aborted on the first entry anyway).
+    session.check_deadline()?;
+
+    // If the root lies in the upper directory of an overlay filesystem (e.g.
+    // the writable layer of a container), files in the timeline are the ones
+    // changed by the overlay and not the complete view of its filesystem.
+    let overlay_upper_dir = overlay_upper_dir(&args.root);
+    if let Some(ref upper_dir) = overlay_upper_dir {
+        log::info!("timeline root is in overlay upper directory '{}'", upper_dir.display());
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    if args.resolve_owner_names {
+        log::warn!("owner name resolution requested but not supported");
+    }
+    #[cfg(not(target_family = "unix"))]
+    if args.anomaly_flags.is_some() {
+        log::warn!("anomaly flagging requested but not supported");
+    }
 
     // `entry_count` keeps track of the number of entries that are included in
     // each batch. Each time the `entries` iterator (defined below) yields an
@@ -40,8 +120,44 @@ where
     // when we process batches.
     let entry_count = std::cell::Cell::new(0);
 
-    let entries = crate::fs::walk_dir(&args.root)
-        .map_err(crate::session::Error::action)?
+    // Similarly to `entry_count`, paths are encoded relative to the entries of
+    // the same batch, so the encoder is shared and reset after every batch.
+    let path_encoder = std::cell::RefCell::new(PathEncoder::new(args.delta_paths));
+
+    // The byte budget for type detection spans the whole collection but the
+    // statistics are reported per batch, so it is shared and reset similarly.
+    let magic_detector = args.inline_magic.as_ref()
+        .map(|inline_magic| std::cell::RefCell::new(MagicDetector::new(inline_magic)));
+
+    // Anomalies are counted per batch as well.
+    #[cfg(target_family = "unix")]
+    let anomaly_detector = args.anomaly_flags.as_ref()
+        .map(|anomaly_flags| anomaly_flags.setuid_prefixes.clone())
+        .map(|setuid_prefixes| std::cell::RefCell::new(anomaly::Detector::new(setuid_prefixes)));
+
+    // The session is borrowed mutably when sending batches, so we need our own
+    // copy of the redactor for the `entries` iterator.
+    let redactor = session.redactor().clone();
+
+    // Walking big filesystems can take a lot of resources, so we check limits
+    // of the session while iterating over the entries. Once they are crossed,
+    // the iterator stops (so that the entries collected so far are flushed as
+    // the last batch marked as truncated) and `limits_error` is set. Because
+    // the session is also used to send the batches, we need to share it
+    // through a cell. The same goes for checking walked paths against the
+    // policy before they are opened.
+    let session = std::cell::RefCell::new(session);
+    let limits_error = std::cell::Cell::new(None);
+    let truncated = std::cell::Cell::new(false);
+
+    let entries = crate::fs::walk_dir(&args.root)?
+        .filter_entry(|entry| match session.borrow().check_path(&entry.path) {
+            Ok(()) => true,
+            Err(error) => {
+                log::warn!("skipping '{}': {error}", entry.path.display());
+                false
+            }
+        })
         .filter_map(|entry| match entry {
             Ok(entry) => Some(entry),
             Err(error) => {
@@ -49,30 +165,388 @@ where
                 None
             }
         })
+        .map_while(|entry| match session.borrow().check_limits() {
+            Ok(()) => Some(entry),
+            Err(error) => {
+                limits_error.set(Some(error));
+                truncated.set(true);
+                None
+            }
+        })
         .inspect(|_| {
             entry_count.set(entry_count.get() + 1);
         })
-        .map(rrg_proto::get_filesystem_timeline::Entry::from_lossy);
+        .map(|mut entry| {
+            let is_dir = entry.metadata.is_dir();
+            let is_anomalous = collection_clock.is_anomalous(&entry.metadata, args.future_time_tolerance);
+
+            // The path has to be the original one for the file to be opened.
+            let magic_type = magic_detector.as_ref()
+                .and_then(|magic_detector| magic_detector.borrow_mut().detect(&entry));
+            #[cfg(target_family = "unix")]
+            let anomaly_flags = anomaly_detector.as_ref()
+                .map(|anomaly_detector| anomaly_detector.borrow_mut().detect(&entry));
+
+            // The walker opens directories before yielding them, so the path
+            // is no longer needed for accessing the entry and can be altered.
+            entry.path = args.normalize_unicode.apply(entry.path);
+
+            let mut entry = rrg_proto::get_filesystem_timeline::Entry::from_lossy(entry);
+            if is_anomalous {
+                entry.set_anomalous_time(true);
+            }
+            if let Some(magic_type) = magic_type {
+                entry.set_magic_type(String::from(magic_type));
+            }
+            #[cfg(target_family = "unix")]
+            if args.resolve_owner_names {
+                resolve_owner_names(&mut entry);
+            }
+            #[cfg(target_family = "unix")]
+            match anomaly_flags {
+                Some(0) | None => (),
+                Some(anomaly_flags) => entry.set_anomaly_flags(anomaly_flags),
+            }
+
+            // Entries are sent as blobs that the session does not look into,
+            // so we have to redact them ourselves.
+            if let std::borrow::Cow::Owned(path) = redactor.redact_path(entry.path()) {
+                entry.set_path(path);
+            }
+
+            // Paths have to be encoded after redaction, so that the decoded
+            // paths are exactly the redacted ones.
+            path_encoder.borrow_mut().encode(&mut entry, is_dir);
+            entry
+        });
 
     for batch in crate::gzchunked::encode(entries) {
         let batch = batch
             .map_err(crate::session::Error::action)?;
 
         let blob = crate::blob::Blob::from(batch);
-        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
 
-        session.send(crate::Sink::Blob, blob)?;
+        // The encoder does not poll more entries than end up in the batch, so
+        // the encoder state corresponds to the entries of this batch only.
+        let mut path_encoder = path_encoder.borrow_mut();
+        let mut magic_detector = magic_detector.as_ref()
+            .map(|magic_detector| magic_detector.borrow_mut());
+        #[cfg(target_family = "unix")]
+        let mut anomaly_detector = anomaly_detector.as_ref()
+            .map(|anomaly_detector| anomaly_detector.borrow_mut());
+        #[cfg(target_family = "unix")]
+        let anomaly_counts = anomaly_detector.as_ref()
+            .map_or(Vec::new(), |anomaly_detector| anomaly_detector.counts());
+        #[cfg(not(target_family = "unix"))]
+        let anomaly_counts = Vec::new();
+
+        let mut session = session.borrow_mut();
+
+        // Entries of the next batch are not collected until this one is sent,
+        // so waiting here pauses the walk while the sink is congested.
+        crate::session::wait_for_sink(&mut **session, crate::Sink::Blob)?;
+
+        let blob_digests = session.send_blob(blob)?
+            .wait()
+            .map_err(crate::session::Error::action)?;
         session.reply(Item {
-            blob_sha256,
+            blob_digests,
             entry_count: entry_count.get(),
+            overlay_upper_dir: overlay_upper_dir.clone(),
+            full_path_len: path_encoder.full_path_len,
+            encoded_path_len: path_encoder.encoded_path_len,
+            collection_clock: collection_clock.clone(),
+            magic_read_len: magic_detector.as_ref()
+                .map_or(0, |magic_detector| magic_detector.read_len),
+            magic_skipped_count: magic_detector.as_ref()
+                .map_or(0, |magic_detector| magic_detector.skipped_count),
+            truncated: truncated.get(),
+            anomaly_counts,
         })?;
 
         entry_count.set(0);
+        path_encoder.reset();
+        if let Some(magic_detector) = &mut magic_detector {
+            magic_detector.reset();
+        }
+        #[cfg(target_family = "unix")]
+        if let Some(anomaly_detector) = &mut anomaly_detector {
+            anomaly_detector.reset();
+        }
+    }
+
+    if let Some(error) = limits_error.take() {
+        log::warn!("aborting timeline collection: {error}");
+        return Err(error);
     }
 
     Ok(())
 }
 
+/// Estimates the cost of collecting the timeline with the given arguments.
+///
+/// Only the top-level entries of the root are counted, so the estimate is just
+/// a lower bound of the number of entries in the timeline.
+fn estimate(args: &Args) -> crate::session::Result<crate::dry_run::Estimate> {
+    let item_count = std::fs::read_dir(&args.root)
+        .map_err(crate::session::Error::action)?
+        .count() as u64;
+
+    let mut notes = Vec::new();
+    if overlay_upper_dir(&args.root).is_some() {
+        notes.push(String::from("root is in an overlay upper directory"));
+    }
+    #[cfg(not(target_family = "unix"))]
+    if args.resolve_owner_names {
+        notes.push(String::from("owner name resolution not supported"));
+    }
+    #[cfg(not(target_family = "unix"))]
+    if args.anomaly_flags.is_some() {
+        notes.push(String::from("anomaly flagging not supported"));
+    }
+
+    Ok(crate::dry_run::Estimate {
+        item_count,
+        // Only the metadata of entries is collected and the size of batches
+        // cannot be told without walking the whole tree.
+        bytes: 0,
+        notes,
+    })
+}
+
+/// Encoder of timeline entry paths within a single batch.
+///
+/// With delta encoding enabled, paths of entries whose parent folder is an
+/// earlier entry of the batch are replaced with the index of that entry and the
+/// remaining path bytes. Otherwise, paths are left intact and only their total
+/// length is measured.
+struct PathEncoder {
+    /// Whether delta encoding is enabled.
+    delta: bool,
+    /// Indices of folder entries encoded so far in the batch by their paths.
+    dirs: std::collections::HashMap<Vec<u8>, u64>,
+    /// Number of entries encoded so far in the batch.
+    entry_count: u64,
+    /// Total length of full paths of entries encoded so far in the batch.
+    full_path_len: u64,
+    /// Total length of encoded paths of entries encoded so far in the batch.
+    encoded_path_len: u64,
+}
+
+impl PathEncoder {
+
+    /// Creates a new encoder for the first batch.
+    fn new(delta: bool) -> PathEncoder {
+        PathEncoder {
+            delta,
+            dirs: std::collections::HashMap::new(),
+            entry_count: 0,
+            full_path_len: 0,
+            encoded_path_len: 0,
+        }
+    }
+
+    /// Encodes the path of the given entry (the next one in the batch).
+    fn encode(
+        &mut self,
+        entry: &mut rrg_proto::get_filesystem_timeline::Entry,
+        is_dir: bool,
+    ) {
+        use rrg_proto::get_filesystem_timeline::PathEncoding;
+
+        let index = self.entry_count;
+        self.entry_count += 1;
+
+        let path_len = entry.path().len() as u64;
+        self.full_path_len += path_len;
+
+        if !self.delta {
+            self.encoded_path_len += path_len;
+            return;
+        }
+
+        if index == 0 {
+            entry.set_path_encoding(PathEncoding::DELTA);
+        }
+
+        let path = entry.take_path();
+
+        let separator = std::path::MAIN_SEPARATOR as u8;
+        let parent_index = path.iter().rposition(|byte| *byte == separator)
+            .and_then(|pos| Some((pos, *self.dirs.get(&path[..pos])?)));
+
+        if is_dir {
+            self.dirs.insert(path.clone(), index);
+        }
+
+        match parent_index {
+            Some((pos, parent_index)) => {
+                self.encoded_path_len += (path.len() - pos) as u64;
+                entry.set_parent_index(parent_index);
+                entry.set_path_suffix(path[pos..].to_vec());
+            }
+            None => {
+                self.encoded_path_len += path_len;
+                entry.set_path(path);
+            }
+        }
+    }
+
+    /// Resets the encoder state before the next batch.
+    fn reset(&mut self) {
+        self.dirs.clear();
+        self.entry_count = 0;
+        self.full_path_len = 0;
+        self.encoded_path_len = 0;
+    }
+}
+
+/// Detector of types of timeline entries within a byte budget.
+struct MagicDetector {
+    /// Size of the biggest file for which the type is detected.
+    max_file_size: u64,
+    /// Number of bytes that can still be read for type detection.
+    budget_left: u64,
+    /// Total number of bytes read for entries of the current batch.
+    read_len: u64,
+    /// Number of entries of the current batch skipped because of the budget.
+    skipped_count: u64,
+}
+
+impl MagicDetector {
+
+    /// Creates a new detector with the full budget.
+    fn new(inline_magic: &InlineMagic) -> MagicDetector {
+        MagicDetector {
+            max_file_size: inline_magic.max_file_size,
+            budget_left: inline_magic.byte_budget,
+            read_len: 0,
+            skipped_count: 0,
+        }
+    }
+
+    /// Detects the type of the given entry (if it qualifies for detection).
+    fn detect(&mut self, entry: &crate::fs::Entry) -> Option<&'static str> {
+        let size = entry.metadata.len();
+        if !entry.metadata.is_file() || size == 0 || size > self.max_file_size {
+            return None;
+        }
+
+        // We charge the budget with the biggest read up front, so that it is
+        // never exceeded even if the file grows in the meantime.
+        let max_read_len = std::cmp::min(size, crate::magic::HEADER_LEN as u64);
+        if self.budget_left < max_read_len {
+            self.skipped_count += 1;
+            return None;
+        }
+
+        let header = match crate::magic::read_header(&entry.path) {
+            Ok(header) => header,
+            Err(error) => {
+                log::debug!("failed to read header of '{}': {}", entry.path.display(), error);
+                return None;
+            }
+        };
+
+        self.budget_left -= header.len() as u64;
+        self.read_len += header.len() as u64;
+
+        crate::magic::detect(&header)
+    }
+
+    /// Resets the statistics of the detector before the next batch.
+    fn reset(&mut self) {
+        self.read_len = 0;
+        self.skipped_count = 0;
+    }
+}
+
+/// Returns the overlayfs upper directory that the given path lies in (if any).
+#[cfg(target_os = "linux")]
+fn overlay_upper_dir(path: &std::path::Path) -> Option<PathBuf> {
+    let mounts = match ospect::fs::mounts() {
+        Ok(mounts) => mounts,
+        Err(error) => {
+            log::warn!("failed to list mounts: {error}");
+            return None;
+        }
+    };
+
+    let path = std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf());
+
+    find_overlay_upper_dir(&path, mounts.filter_map(Result::ok))
+}
+
+/// Returns the overlayfs upper directory that the given path lies in (if any).
+#[cfg(not(target_os = "linux"))]
+fn overlay_upper_dir(_: &std::path::Path) -> Option<PathBuf> {
+    None
+}
+
+/// Finds the upper directory among the given mounts that contains the path.
+#[cfg(target_os = "linux")]
+fn find_overlay_upper_dir<I>(path: &std::path::Path, mounts: I) -> Option<PathBuf>
+where
+    I: Iterator<Item = ospect::fs::Mount>,
+{
+    mounts
+        .filter(|mount| mount.fs_type == "overlay")
+        .filter_map(|mount| {
+            mount.options.iter().find_map(|option| {
+                option.strip_prefix("upperdir=").map(PathBuf::from)
+            })
+        })
+        .find(|upper_dir| path.starts_with(upper_dir))
+}
+
+/// Sets names of the user and the group owning the file of the given entry.
+#[cfg(target_family = "unix")]
+fn resolve_owner_names(entry: &mut rrg_proto::get_filesystem_timeline::Entry) {
+    let resolver = crate::os::resolver();
+
+    let uid = entry.unix_uid_unsigned.and_then(|uid| u32::try_from(uid).ok());
+    if let Some(name) = uid.and_then(|uid| resolver.user_name(uid)) {
+        entry.set_unix_owner_name(name.to_string_lossy().into_owned());
+    }
+
+    let gid = entry.unix_gid_unsigned.and_then(|gid| u32::try_from(gid).ok());
+    if let Some(name) = gid.and_then(|gid| resolver.group_name(gid)) {
+        entry.set_unix_group_name(name.to_string_lossy().into_owned());
+    }
+}
+
+/// Identifiers of the device and the owners of a file.
+#[cfg(target_family = "unix")]
+struct UnixIds {
+    /// Identifier of the device containing the file.
+    dev: u64,
+    /// Identifier of the user owning the file.
+    uid: u32,
+    /// Identifier of the group owning the file.
+    gid: u32,
+}
+
+/// Sets identifier fields of the given entry.
+///
+/// Unsigned fields are always set. Legacy signed fields are set only if the
+/// identifier fits them, otherwise the entry is marked as having them omitted.
+#[cfg(target_family = "unix")]
+fn set_unix_ids(entry: &mut rrg_proto::get_filesystem_timeline::Entry, ids: UnixIds) {
+    entry.set_unix_dev_unsigned(ids.dev);
+    entry.set_unix_uid_unsigned(u64::from(ids.uid));
+    entry.set_unix_gid_unsigned(u64::from(ids.gid));
+
+    // User and group identifiers are 32-bit on all supported platforms, so
+    // only device identifiers can be out of range of the signed fields.
+    entry.set_unix_uid(i64::from(ids.uid));
+    entry.set_unix_gid(i64::from(ids.gid));
+    match i64::try_from(ids.dev) {
+        Ok(dev) => entry.set_unix_dev(dev),
+        Err(_) => entry.set_unix_legacy_ids_omitted(true),
+    }
+}
+
 impl crate::request::Args for Args {
 
     type Proto = rrg_proto::get_filesystem_timeline::Args;
@@ -80,13 +554,58 @@ impl crate::request::Args for Args {
     fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
         use crate::request::ParseArgsError;
 
-        let root = PathBuf::try_from(proto.take_root())
+        let root = crate::path::from_proto(proto.take_root())
             .map_err(|error| ParseArgsError::invalid_field("root", error))?;
 
+        let future_time_tolerance = if proto.has_future_time_tolerance() {
+            std::time::Duration::try_from(proto.take_future_time_tolerance())
+                .map_err(|error| ParseArgsError::invalid_field("future_time_tolerance", error))?
+        } else {
+            crate::clock::DEFAULT_FUTURE_TOLERANCE
+        };
+
+        let inline_magic = if proto.inline_magic() {
+            Some(InlineMagic {
+                max_file_size: match proto.inline_magic_max_file_size() {
+                    0 => DEFAULT_INLINE_MAGIC_MAX_FILE_SIZE,
+                    max_file_size => max_file_size,
+                },
+                byte_budget: match proto.inline_magic_byte_budget() {
+                    0 => DEFAULT_INLINE_MAGIC_BYTE_BUDGET,
+                    byte_budget => byte_budget,
+                },
+            })
+        } else {
+            None
+        };
+
+        let anomaly_flags = if proto.anomaly_flags() {
+            let setuid_prefixes = proto.take_setuid_prefixes().into_iter()
+                .map(crate::path::from_proto)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| ParseArgsError::invalid_field("setuid_prefixes", error))?;
+
+            Some(AnomalyFlags {
+                setuid_prefixes,
+            })
+        } else {
+            None
+        };
+
         Ok(Args {
             root: root,
+            delta_paths: proto.delta_paths(),
+            resolve_owner_names: proto.resolve_owner_names(),
+            future_time_tolerance,
+            normalize_unicode: proto.normalize_unicode().into(),
+            inline_magic,
+            anomaly_flags,
         })
     }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.root.as_path()]
+    }
 }
 
 impl crate::response::Item for Item {
@@ -95,8 +614,28 @@ impl crate::response::Item for Item {
 
     fn into_proto(self) -> Self::Proto {
         let mut proto = Self::Proto::default();
-        proto.set_blob_sha256(self.blob_sha256.into());
+        if let Some(blob_sha256) = self.blob_digests.sha256 {
+            proto.set_blob_sha256(blob_sha256.into());
+        }
+        if let Some(blob_blake3) = self.blob_digests.blake3 {
+            proto.set_blob_blake3(blob_blake3.into());
+        }
         proto.set_entry_count(self.entry_count as u64);
+        if let Some(overlay_upper_dir) = self.overlay_upper_dir {
+            proto.set_overlay_upper_dir(crate::path::into_proto(overlay_upper_dir));
+        }
+        proto.set_full_path_len(self.full_path_len);
+        proto.set_encoded_path_len(self.encoded_path_len);
+        proto.set_collection_clock(self.collection_clock.into());
+        proto.set_magic_read_len(self.magic_read_len);
+        proto.set_magic_skipped_count(self.magic_skipped_count);
+        proto.set_truncated(self.truncated);
+        for (anomaly, count) in self.anomaly_counts {
+            let mut anomaly_count = rrg_proto::get_filesystem_timeline::AnomalyCount::new();
+            anomaly_count.set_anomaly(anomaly);
+            anomaly_count.set_count(count);
+            proto.mut_anomaly_counts().push(anomaly_count);
+        }
 
         proto
     }
@@ -106,7 +645,7 @@ impl FromLossy<crate::fs::Entry> for rrg_proto::get_filesystem_timeline::Entry {
 
     fn from_lossy(entry: crate::fs::Entry) -> Self {
         let mut proto = Self::default();
-        proto.set_path(rrg_proto::path::into_bytes(entry.path));
+        proto.set_path(crate::path::into_bytes(entry.path));
         proto.set_size(entry.metadata.len());
 
         fn nanos(time: std::time::SystemTime) -> Option<i64> {
@@ -134,15 +673,11 @@ impl FromLossy<crate::fs::Entry> for rrg_proto::get_filesystem_timeline::Entry {
 
             proto.set_unix_mode(i64::from(entry.metadata.mode()));
             proto.set_unix_ino(entry.metadata.ino());
-            if let Some(dev) = i64::try_from(entry.metadata.dev()).ok() {
-                proto.set_unix_dev(dev);
-            }
-            if let Some(uid) = i64::try_from(entry.metadata.uid()).ok() {
-                proto.set_unix_uid(uid);
-            }
-            if let Some(gid) = i64::try_from(entry.metadata.gid()).ok() {
-                proto.set_unix_gid(gid);
-            }
+            set_unix_ids(&mut proto, UnixIds {
+                dev: entry.metadata.dev(),
+                uid: entry.metadata.uid(),
+                gid: entry.metadata.gid(),
+            });
             proto.set_ctime_nanos(entry.metadata.ctime_nsec());
         }
 
@@ -163,26 +698,74 @@ mod tests {
 
     use super::*;
 
+    fn args(root: &std::path::Path) -> Args {
+        Args {
+            root: root.to_path_buf(),
+            delta_paths: false,
+            resolve_owner_names: false,
+            future_time_tolerance: crate::clock::DEFAULT_FUTURE_TOLERANCE,
+            normalize_unicode: crate::path::Normalization::None,
+            inline_magic: None,
+            anomaly_flags: None,
+        }
+    }
+
     #[test]
     fn handle_non_existent_path() {
         let tempdir = tempfile::tempdir().unwrap();
 
-        let request = Args {
-            root: tempdir.path().join("foo")
-        };
+        let request = args(&tempdir.path().join("foo"));
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_err());
     }
 
+    #[test]
+    fn handle_non_existent_path_code() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let request = args(&tempdir.path().join("foo"));
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, request).unwrap_err();
+        assert_eq!(error.code(), crate::session::ErrorCode::NotFound);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_inaccessible_path_code() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        // Permissions are not enforced for the superuser.
+        // SAFETY: The call has no preconditions and cannot fail.
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::set_permissions(&root, std::fs::Permissions::from_mode(0o000))
+            .unwrap();
+
+        let request = args(&root);
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, request).unwrap_err();
+
+        // Restore the permissions so that the directory can be cleaned up.
+        std::fs::set_permissions(&root, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        assert_eq!(error.code(), crate::session::ErrorCode::PermissionDenied);
+    }
+
     #[test]
     fn handle_empty_dir() {
         let tempdir = tempfile::tempdir().unwrap();
         let tempdir_path = tempdir.path().to_path_buf();
 
-        let request = Args {
-            root: tempdir_path.clone(),
-        };
+        let request = args(&tempdir_path);
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -198,9 +781,7 @@ mod tests {
         std::fs::File::create(tempdir.path().join("b")).unwrap();
         std::fs::File::create(tempdir.path().join("c")).unwrap();
 
-        let request = Args {
-            root: tempdir.path().to_path_buf(),
-        };
+        let request = args(tempdir.path());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -214,6 +795,224 @@ mod tests {
         assert_eq!(path(&entries[2]), Some(tempdir.path().join("c")));
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_resolve_owner_names() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("a")).unwrap();
+
+        let request = Args {
+            resolve_owner_names: true,
+            ..args(tempdir.path())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+
+        // SAFETY: `geteuid` is always safe to call.
+        let uid = unsafe { libc::geteuid() };
+        let name = ospect::user::user_name(uid).unwrap()
+            .map(|name| name.to_string_lossy().into_owned());
+        assert_eq!(entries[0].unix_owner_name, name);
+    }
+
+    #[test]
+    fn handle_owner_names_not_resolved_by_default() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("a")).unwrap();
+
+        let request = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].unix_owner_name, None);
+        assert_eq!(entries[0].unix_group_name, None);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_anomalous_time() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("a")).unwrap();
+        std::fs::File::create(tempdir.path().join("b")).unwrap();
+        crate::clock::tests::set_future_mtime(&tempdir.path().join("b"));
+
+        let request = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].has_anomalous_time());
+        assert!(entries[1].anomalous_time());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_anomalous_time_within_tolerance() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("a")).unwrap();
+        crate::clock::tests::set_future_mtime(&tempdir.path().join("a"));
+
+        let request = Args {
+            future_time_tolerance: std::time::Duration::from_secs(7 * 24 * 60 * 60),
+            ..args(tempdir.path())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].has_anomalous_time());
+    }
+
+    #[test]
+    fn handle_collection_clock() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("a")).unwrap();
+
+        let request = args(tempdir.path());
+
+        let before = std::time::SystemTime::now();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let proto = session.reply::<Item>(0).collection_clock.clone();
+        let proto = rrg_proto::os::ClockSnapshot::from(proto);
+
+        let realtime = rrg_proto::try_from_timestamp(proto.realtime().clone())
+            .unwrap();
+        assert!(realtime >= before);
+        assert!(realtime <= std::time::SystemTime::now());
+        assert!(proto.has_uptime());
+    }
+
+    #[test]
+    fn handle_memory_watermark_exceeded() {
+        let tempdir = tempfile::tempdir().unwrap();
+        for i in 0..100 {
+            std::fs::File::create(tempdir.path().join(i.to_string())).unwrap();
+        }
+
+        let request = args(tempdir.path());
+
+        // We simulate memory usage that grows with every check, so that the
+        // watermark is crossed after a few entries.
+        let checks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut session = crate::session::FakeSession::new();
+        session.simulate_memory_usage(10, {
+            let checks = checks.clone();
+            move || {
+                checks.set(checks.get() + 1);
+                checks.get()
+            }
+        });
+
+        let error = handle(&mut session, request)
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::ResourceExhausted);
+
+        // Entries collected before the watermark was crossed should still be
+        // sent as complete batches.
+        assert!(session.reply_count() > 0);
+        assert_eq!(entries(&session).len(), 10);
+    }
+
+    #[test]
+    fn handle_deadline_passed() {
+        let tempdir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            std::fs::File::create(tempdir.path().join(i.to_string())).unwrap();
+        }
+
+        let request = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_deadline(std::time::Instant::now() - std::time::Duration::from_secs(1));
+
+        let error = handle(&mut session, request)
+            .unwrap_err();
+        assert_eq!(error.code(), crate::session::ErrorCode::DeadlineExceeded);
+
+        assert_eq!(session.reply_count(), 0);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[test]
+    fn handle_deadline_during_collection() {
+        let tempdir = tempfile::tempdir().unwrap();
+        for i in 0..100 {
+            std::fs::File::create(tempdir.path().join(i.to_string())).unwrap();
+        }
+
+        let request = args(tempdir.path());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(500);
+
+        // Limits are checked for every entry, so to make the deadline pass at
+        // a deterministic point we wait for it while checking the fifth entry.
+        let checks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut session = crate::session::FakeSession::new();
+        session.set_deadline(deadline);
+        session.simulate_memory_usage(usize::MAX, {
+            let checks = checks.clone();
+            move || {
+                checks.set(checks.get() + 1);
+                if checks.get() == 5 {
+                    std::thread::sleep(deadline.saturating_duration_since(std::time::Instant::now()));
+                }
+                0
+            }
+        });
+
+        let error = handle(&mut session, request)
+            .unwrap_err();
+        assert_eq!(error.code(), crate::session::ErrorCode::DeadlineExceeded);
+
+        assert_eq!(entries(&session).len(), 5);
+
+        let last_id = session.reply_count() - 1;
+        assert!(session.reply::<Item>(last_id).truncated);
+    }
+
+    #[test]
+    fn handle_sink_pressure() {
+        let tempdir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            std::fs::File::create(tempdir.path().join(i.to_string())).unwrap();
+        }
+
+        let request = args(tempdir.path());
+
+        // The sink is congested when the first batch is about to be sent and
+        // clears after being checked a few times.
+        let checks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut session = crate::session::FakeSession::new();
+        session.simulate_sink_pressure(crate::Sink::Blob, 1024, 0, {
+            let checks = checks.clone();
+            move || {
+                checks.set(checks.get() + 1);
+                if checks.get() <= 3 { 4096 } else { 0 }
+            }
+        });
+
+        assert!(handle(&mut session, request).is_ok());
+
+        assert_eq!(session.heartbeats(), &[0, 0, 0]);
+        assert_eq!(entries(&session).len(), 10);
+    }
+
     #[test]
     fn handle_dir_with_nested_dirs() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -221,9 +1020,7 @@ mod tests {
 
         std::fs::create_dir_all(tempdir_path.join("a").join("b")).unwrap();
 
-        let request = Args {
-            root: tempdir_path.clone(),
-        };
+        let request = args(&tempdir_path);
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -236,6 +1033,197 @@ mod tests {
         assert_eq!(path(&entries[1]), Some(tempdir_path.join("a").join("b")));
     }
 
+    #[test]
+    fn handle_many_small_files() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let entry_count = rrg_test_support::tree::many_small_files(tempdir.path(), 64, 64, 16)
+            .unwrap();
+
+        let request = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        assert_eq!(entries(&session).len(), entry_count);
+
+        // Every batch should decode on its own to as many entries as the reply
+        // that accompanies it claims.
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob);
+        for (blob, item) in blobs.zip(session.replies::<Item>()) {
+            let batch = crate::gzchunked::decode(std::iter::once(blob.as_bytes()))
+                .map(Result::<rrg_proto::get_filesystem_timeline::Entry, _>::unwrap);
+            assert_eq!(batch.count(), item.entry_count);
+        }
+    }
+
+    #[test]
+    fn handle_delta_paths() {
+        use rrg_proto::get_filesystem_timeline::PathEncoding;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        rrg_test_support::tree::deep(tempdir.path(), 32)
+            .unwrap();
+        rrg_test_support::tree::many_small_files(tempdir.path(), 16, 16, 0)
+            .unwrap();
+
+        let mut full_session = crate::session::FakeSession::new();
+        assert!(handle(&mut full_session, args(tempdir.path())).is_ok());
+
+        let mut delta_session = crate::session::FakeSession::new();
+        assert!(handle(&mut delta_session, Args {
+            delta_paths: true,
+            ..args(tempdir.path())
+        }).is_ok());
+
+        let full_entries = entries(&full_session);
+
+        let mut delta_entries = Vec::new();
+        for blob in delta_session.parcels::<crate::blob::Blob>(crate::Sink::Blob) {
+            let batch = crate::gzchunked::decode(std::iter::once(blob.as_bytes()))
+                .map(Result::unwrap)
+                .collect::<Vec<rrg_proto::get_filesystem_timeline::Entry>>();
+
+            assert_eq!(batch[0].path_encoding(), PathEncoding::DELTA);
+            assert!(batch.iter().any(|entry| entry.has_parent_index()));
+            delta_entries.extend(resolve_paths(batch));
+        }
+
+        assert_eq!(full_entries.len(), delta_entries.len());
+        for (mut full_entry, mut delta_entry) in full_entries.into_iter().zip(delta_entries) {
+            use protobuf::Message as _;
+
+            // Folders are accessed by the first walk, so their access times
+            // can differ between the two.
+            full_entry.clear_atime_nanos();
+            delta_entry.clear_atime_nanos();
+
+            assert_eq! {
+                full_entry.write_to_bytes().unwrap(),
+                delta_entry.write_to_bytes().unwrap(),
+            };
+        }
+
+        let full_item = full_session.reply::<Item>(0);
+        assert_eq!(full_item.full_path_len, full_item.encoded_path_len);
+
+        let delta_item = delta_session.reply::<Item>(0);
+        assert_eq!(delta_item.full_path_len, full_item.full_path_len);
+        assert!(delta_item.encoded_path_len < delta_item.full_path_len / 2);
+    }
+
+    #[test]
+    fn path_encoder_reset() {
+        let dir = std::env::temp_dir();
+        let file = dir.join("foo");
+
+        fn entry(path: &std::path::Path) -> rrg_proto::get_filesystem_timeline::Entry {
+            let mut entry = rrg_proto::get_filesystem_timeline::Entry::new();
+            entry.set_path(crate::path::into_bytes(path.to_path_buf()));
+            entry
+        }
+
+        let mut encoder = PathEncoder::new(true);
+
+        let mut dir_entry = entry(&dir);
+        encoder.encode(&mut dir_entry, true);
+        assert!(!dir_entry.has_parent_index());
+
+        let mut file_entry = entry(&file);
+        encoder.encode(&mut file_entry, false);
+        assert_eq!(file_entry.parent_index(), 0);
+        assert!(!file_entry.has_path());
+
+        encoder.reset();
+
+        // The parent folder is not part of the new batch, so the full path is
+        // expected.
+        let mut file_entry = entry(&file);
+        encoder.encode(&mut file_entry, false);
+        assert!(!file_entry.has_parent_index());
+        assert_eq!(file_entry.path(), crate::path::into_bytes(file));
+    }
+
+    /// Replaces delta-encoded paths of the given batch with full paths.
+    fn resolve_paths(
+        mut batch: Vec<rrg_proto::get_filesystem_timeline::Entry>,
+    ) -> Vec<rrg_proto::get_filesystem_timeline::Entry> {
+        let mut paths = Vec::<Vec<u8>>::new();
+        for entry in &mut batch {
+            if entry.has_parent_index() {
+                let mut path = paths[entry.parent_index() as usize].clone();
+                path.extend(entry.take_path_suffix());
+
+                entry.clear_parent_index();
+                entry.set_path(path);
+            }
+            entry.clear_path_encoding();
+
+            paths.push(entry.path().to_vec());
+        }
+
+        batch
+    }
+
+    // Redaction patterns are matched against raw path bytes, so the test uses
+    // Unix-style paths.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_redacted_paths() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir_path = tempdir.path().to_path_buf();
+
+        std::fs::create_dir_all(tempdir_path.join("home").join("alice")).unwrap();
+        std::fs::create_dir_all(tempdir_path.join("etc")).unwrap();
+        std::fs::File::create(tempdir_path.join("home").join("alice").join("a")).unwrap();
+        std::fs::File::create(tempdir_path.join("home").join("alice").join("b")).unwrap();
+        std::fs::File::create(tempdir_path.join("etc").join("passwd")).unwrap();
+
+        let pattern = format! {
+            "^{}/home/([^/]+)",
+            regex::escape(&tempdir_path.display().to_string()),
+        };
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            redacted_paths: vec![pattern.parse().unwrap()],
+            ..Default::default()
+        });
+
+        let request = args(&tempdir_path);
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        let paths = entries.iter()
+            .map(|entry| path(entry).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths.len(), 6);
+        assert!(paths.contains(&tempdir_path.join("home")));
+        assert!(paths.contains(&tempdir_path.join("etc")));
+        assert!(paths.contains(&tempdir_path.join("etc").join("passwd")));
+
+        let mut redacted_paths = paths.iter()
+            .filter_map(|path| path.strip_prefix(tempdir_path.join("home")).ok())
+            .filter(|path| !path.as_os_str().is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(redacted_paths.len(), 3);
+
+        let token = redacted_paths[0].components().next().unwrap();
+        let token = token.as_os_str().to_str().unwrap();
+        assert!(token.starts_with('<') && token.ends_with('>'));
+
+        redacted_paths.sort();
+        assert_eq! {
+            redacted_paths,
+            vec! {
+                std::path::Path::new(token),
+                &std::path::Path::new(token).join("a"),
+                &std::path::Path::new(token).join("b"),
+            },
+        };
+
+        assert!(!paths.iter().any(|path| path.ends_with("alice")));
+    }
+
     // Symlinking is supported only on Unix-like systems.
     #[cfg(target_family = "unix")]
     #[test]
@@ -249,9 +1237,7 @@ mod tests {
         std::fs::create_dir(&dir_path).unwrap();
         std::os::unix::fs::symlink(&dir_path, &symlink_path).unwrap();
 
-        let request = Args {
-            root: root_path.clone(),
-        };
+        let request = args(&root_path);
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -275,9 +1261,7 @@ mod tests {
         std::fs::File::create(&file_path_1).unwrap();
         std::fs::File::create(&file_path_2).unwrap();
 
-        let request = Args {
-            root: root_path.clone(),
-        };
+        let request = args(&root_path);
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -295,14 +1279,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn handle_normalize_unicode() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root_path = tempdir.path().to_path_buf();
+
+        // Some filesystems (e.g. APFS) do not allow names that differ only in
+        // normalization within the same folder, so we put them in separate
+        // ones.
+        std::fs::create_dir(root_path.join("nfc")).unwrap();
+        std::fs::create_dir(root_path.join("nfd")).unwrap();
+        std::fs::write(root_path.join("nfc").join("caf\u{e9}"), b"foo").unwrap();
+        std::fs::write(root_path.join("nfd").join("cafe\u{301}"), b"foo").unwrap();
+
+        for (normalize_unicode, name) in [
+            (crate::path::Normalization::Nfc, "caf\u{e9}"),
+            (crate::path::Normalization::Nfd, "cafe\u{301}"),
+        ] {
+            let request = Args {
+                normalize_unicode,
+                ..args(&root_path)
+            };
+
+            let mut session = crate::session::FakeSession::new();
+            assert!(handle(&mut session, request).is_ok());
+
+            let mut entries = entries(&session);
+            entries.sort_by_key(|entry| entry.path().to_owned());
+
+            assert_eq!(entries.len(), 4);
+            assert_eq!(path(&entries[1]), Some(root_path.join("nfc").join(name)));
+            assert_eq!(path(&entries[3]), Some(root_path.join("nfd").join(name)));
+
+            // Metadata is still collected through the actual paths.
+            assert_eq!(entries[1].size(), 3);
+            assert_eq!(entries[3].size(), 3);
+        }
+    }
+
     #[test]
     fn handle_file_metadata() {
         let tempdir = tempfile::tempdir().unwrap();
         std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
 
-        let request = Args {
-            root: tempdir.path().to_path_buf(),
-        };
+        let request = args(tempdir.path());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -322,13 +1342,50 @@ mod tests {
             assert_eq!(mode & libc::S_IFMT, libc::S_IFREG);
 
             let uid = unsafe { libc::getuid() };
-            assert_eq!(entries[0].unix_uid(), uid.into());
+            assert_eq!(entries[0].unix_uid(), i64::from(uid));
 
             let gid = unsafe { libc::getgid() };
-            assert_eq!(entries[0].unix_gid(), gid.into());
+            assert_eq!(entries[0].unix_gid(), i64::from(gid));
+
+            assert_eq!(entries[0].unix_uid_unsigned(), u64::from(uid));
+            assert_eq!(entries[0].unix_gid_unsigned(), u64::from(gid));
+            assert_eq!(entries[0].unix_dev(), entries[0].unix_dev_unsigned() as i64);
+            assert!(!entries[0].unix_legacy_ids_omitted());
         }
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn set_unix_ids_large_uid() {
+        let mut entry = rrg_proto::get_filesystem_timeline::Entry::default();
+        set_unix_ids(&mut entry, UnixIds {
+            dev: 42,
+            uid: 4294967294,
+            gid: 4294967293,
+        });
+
+        assert_eq!(entry.unix_uid_unsigned(), 4294967294);
+        assert_eq!(entry.unix_gid_unsigned(), 4294967293);
+        assert_eq!(entry.unix_uid(), 4294967294);
+        assert_eq!(entry.unix_gid(), 4294967293);
+        assert!(!entry.unix_legacy_ids_omitted());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn set_unix_ids_large_dev() {
+        let mut entry = rrg_proto::get_filesystem_timeline::Entry::default();
+        set_unix_ids(&mut entry, UnixIds {
+            dev: u64::MAX,
+            uid: 1337,
+            gid: 1337,
+        });
+
+        assert_eq!(entry.unix_dev_unsigned(), u64::MAX);
+        assert!(!entry.has_unix_dev());
+        assert!(entry.unix_legacy_ids_omitted());
+    }
+
     #[test]
     fn handle_hardlink_metadata() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -340,9 +1397,7 @@ mod tests {
         std::fs::File::create(&file_path).unwrap();
         std::fs::hard_link(&file_path, &hardlink_path).unwrap();
 
-        let request = Args {
-            root: root_path.clone(),
-        };
+        let request = args(&root_path);
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -386,9 +1441,7 @@ mod tests {
         };
         assert!(status > 0);
 
-        let request = Args {
-            root: temp_dir.path().to_path_buf(),
-        };
+        let request = args(temp_dir.path());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -401,6 +1454,245 @@ mod tests {
         assert_eq!(attributes & FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_HIDDEN);
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn find_overlay_upper_dir_inside() {
+        let mounts = [
+            mount("/dev/sda1", "/", "ext4", &["rw"]),
+            mount("overlay", "/var/lib/docker/overlay2/f00/merged", "overlay", &[
+                "rw",
+                "lowerdir=/var/lib/docker/overlay2/l/BA4:/var/lib/docker/overlay2/l/BA5",
+                "upperdir=/var/lib/docker/overlay2/f00/diff",
+                "workdir=/var/lib/docker/overlay2/f00/work",
+            ]),
+        ];
+
+        let path = std::path::Path::new("/var/lib/docker/overlay2/f00/diff/etc");
+        assert_eq! {
+            find_overlay_upper_dir(path, mounts.into_iter()),
+            Some(PathBuf::from("/var/lib/docker/overlay2/f00/diff"))
+        };
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn find_overlay_upper_dir_outside() {
+        let mounts = [
+            mount("/dev/sda1", "/", "ext4", &["rw"]),
+            mount("overlay", "/var/lib/docker/overlay2/f00/merged", "overlay", &[
+                "rw",
+                "lowerdir=/var/lib/docker/overlay2/l/BA4",
+                "upperdir=/var/lib/docker/overlay2/f00/diff",
+            ]),
+        ];
+
+        // Files in the merged view are not in the upper directory itself.
+        let path = std::path::Path::new("/var/lib/docker/overlay2/f00/merged/etc");
+        assert_eq!(find_overlay_upper_dir(path, mounts.into_iter()), None);
+    }
+
+    #[test]
+    fn handle_no_overlay_upper_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+
+        let args = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.overlay_upper_dir, None);
+    }
+
+    #[test]
+    fn handle_inline_magic() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("elf"), b"\x7fELF\x02\x01\x01\x00")
+            .unwrap();
+        std::fs::write(tempdir.path().join("large"), {
+            let mut content = b"\x7fELF\x02\x01\x01\x00".to_vec();
+            content.resize(1024 * 1024, 0);
+            content
+        }).unwrap();
+        std::fs::write(tempdir.path().join("text"), b"lorem ipsum")
+            .unwrap();
+
+        let args = Args {
+            inline_magic: Some(InlineMagic {
+                max_file_size: 1024,
+                byte_budget: DEFAULT_INLINE_MAGIC_BYTE_BUDGET,
+            }),
+            ..args(tempdir.path())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("elf")));
+        assert_eq!(entries[0].magic_type.as_deref(), Some("elf"));
+        assert_eq!(path(&entries[1]), Some(tempdir.path().join("large")));
+        assert_eq!(entries[1].magic_type, None);
+        assert_eq!(path(&entries[2]), Some(tempdir.path().join("text")));
+        assert_eq!(entries[2].magic_type, None);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.magic_read_len, 8 + 11);
+        assert_eq!(item.magic_skipped_count, 0);
+    }
+
+    #[test]
+    fn handle_inline_magic_byte_budget() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        for name in ["a", "b", "c", "d"] {
+            std::fs::write(tempdir.path().join(name), vec![0xAB; 1024])
+                .unwrap();
+        }
+
+        let args = Args {
+            inline_magic: Some(InlineMagic {
+                max_file_size: DEFAULT_INLINE_MAGIC_MAX_FILE_SIZE,
+                byte_budget: 2 * crate::magic::HEADER_LEN as u64 + 1,
+            }),
+            ..args(tempdir.path())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.magic_read_len, 2 * crate::magic::HEADER_LEN as u64);
+        assert_eq!(item.magic_skipped_count, 2);
+    }
+
+    #[test]
+    fn handle_inline_magic_not_requested() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("elf"), b"\x7fELF\x02\x01\x01\x00")
+            .unwrap();
+
+        let args = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].magic_type, None);
+        assert_eq!(session.reply::<Item>(0).magic_read_len, 0);
+    }
+
+    #[test]
+    fn handle_dry_run() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("foo"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo").join("bar"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("baz"), b"")
+            .unwrap();
+
+        let args = args(tempdir.path());
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_dry_run();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+
+        let estimate = session.reply::<crate::dry_run::Estimate>(0);
+        assert_eq!(estimate.item_count, 2);
+        assert_eq!(estimate.bytes, 0);
+    }
+
+    #[test]
+    fn handle_dry_run_root_not_existing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = args(&tempdir.path().join("foo"));
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_dry_run();
+        assert!(handle(&mut session, args).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_anomaly_flags() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        use rrg_proto::get_filesystem_timeline::Anomaly;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("setuid"), b"")
+            .unwrap();
+        std::fs::set_permissions(tempdir.path().join("setuid"), std::fs::Permissions::from_mode(0o4777))
+            .unwrap();
+
+        std::fs::write(tempdir.path().join(".hidden"), b"")
+            .unwrap();
+        std::fs::set_permissions(tempdir.path().join(".hidden"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        let args = Args {
+            anomaly_flags: Some(AnomalyFlags {
+                setuid_prefixes: vec![],
+            }),
+            ..args(tempdir.path())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join(".hidden")));
+        assert_eq!(entries[0].anomaly_flags, Some(Anomaly::HIDDEN_EXECUTABLE as u32));
+        assert_eq!(path(&entries[1]), Some(tempdir.path().join("setuid")));
+        assert_eq! {
+            entries[1].anomaly_flags,
+            Some(Anomaly::WORLD_WRITABLE_SETUID as u32 | Anomaly::SETUID_OUTSIDE_STANDARD_DIRS as u32)
+        };
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.anomaly_counts, vec![
+            (Anomaly::WORLD_WRITABLE_SETUID, 1),
+            (Anomaly::SETUID_OUTSIDE_STANDARD_DIRS, 1),
+            (Anomaly::HIDDEN_EXECUTABLE, 1),
+        ]);
+    }
+
+    /// Constructs a mount entry with the given properties.
+    #[cfg(target_os = "linux")]
+    fn mount(name: &str, path: &str, fs_type: &str, options: &[&str]) -> ospect::fs::Mount {
+        ospect::fs::Mount {
+            name: String::from(name),
+            path: PathBuf::from(path),
+            fs_type: String::from(fs_type),
+            options: options.iter().map(|option| String::from(*option)).collect(),
+            read_only: false,
+        }
+    }
+
     /// Retrieves timeline entries from the given session object.
     fn entries(
         session: &crate::session::FakeSession,
@@ -418,7 +1710,7 @@ mod tests {
 
         let total_entry_count = session.replies::<Item>()
             .map(|item| item.entry_count)
-            .sum();
+            .sum::<usize>();
 
         assert_eq!(entries.len(), total_entry_count);
 
@@ -429,6 +1721,6 @@ mod tests {
     fn path(
         entry: &rrg_proto::get_filesystem_timeline::Entry,
     ) -> Option<PathBuf> {
-        rrg_proto::path::from_bytes(entry.path().to_owned()).ok()
+        crate::path::from_bytes(entry.path().to_owned()).ok()
     }
 }
@@ -0,0 +1,339 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Detection of suspicious ownership and permissions of timeline entries.
+//!
+//! All the rules are evaluated using only the path and the metadata of the
+//! entry that the walk collects anyway, so flagging anomalies does not require
+//! any additional I/O.
+
+use std::path::{Path, PathBuf};
+
+use rrg_proto::get_filesystem_timeline::Anomaly;
+
+/// All the anomalies in the order in which they are reported.
+const ANOMALIES: [Anomaly; 5] = [
+    Anomaly::WORLD_WRITABLE_SETUID,
+    Anomaly::SETUID_OUTSIDE_STANDARD_DIRS,
+    Anomaly::ROOT_OWNED_IN_HOME,
+    Anomaly::HIDDEN_EXECUTABLE,
+    Anomaly::DEVICE_OUTSIDE_DEV,
+];
+
+/// Directories in which setuid binaries are expected by default.
+const DEFAULT_SETUID_PREFIXES: [&str; 10] = [
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib64",
+    "/usr/bin",
+    "/usr/sbin",
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/libexec",
+    "/usr/local/bin",
+];
+
+/// Directories containing home directories of users.
+const HOME_PARENTS: [&str; 2] = [
+    "/home",
+    // Home directories on macOS.
+    "/Users",
+];
+
+/// Detector of anomalies of timeline entries.
+pub struct Detector {
+    /// Directories in which setuid binaries are expected.
+    setuid_prefixes: Vec<PathBuf>,
+    /// Number of entries with each of the anomalies (in order of [`ANOMALIES`])
+    /// detected in the current batch.
+    counts: [u64; ANOMALIES.len()],
+}
+
+impl Detector {
+
+    /// Creates a new detector with the given directories of setuid binaries.
+    ///
+    /// If no directories are given, the default ones are used.
+    pub fn new(setuid_prefixes: Vec<PathBuf>) -> Detector {
+        let setuid_prefixes = if setuid_prefixes.is_empty() {
+            DEFAULT_SETUID_PREFIXES.iter().map(PathBuf::from).collect()
+        } else {
+            setuid_prefixes
+        };
+
+        Detector {
+            setuid_prefixes,
+            counts: [0; ANOMALIES.len()],
+        }
+    }
+
+    /// Detects anomalies of the given entry and returns them as bit flags.
+    pub fn detect(&mut self, entry: &crate::fs::Entry) -> u32 {
+        use std::os::unix::fs::MetadataExt as _;
+
+        let flags = flags(
+            &entry.path,
+            entry.metadata.mode(),
+            entry.metadata.uid(),
+            &self.setuid_prefixes,
+        );
+
+        for (count, anomaly) in self.counts.iter_mut().zip(ANOMALIES) {
+            if flags & anomaly as u32 != 0 {
+                *count += 1;
+            }
+        }
+
+        flags
+    }
+
+    /// Returns the number of entries with each anomaly in the current batch.
+    ///
+    /// Anomalies not detected for any entry are omitted.
+    pub fn counts(&self) -> Vec<(Anomaly, u64)> {
+        ANOMALIES.into_iter()
+            .zip(self.counts)
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    /// Resets the counts of the detector before the next batch.
+    pub fn reset(&mut self) {
+        self.counts = [0; ANOMALIES.len()];
+    }
+}
+
+/// Computes anomaly flags of a file with the given path, mode and owner.
+// Mode constants are `u16` on macOS, so the casts are not always a no-op.
+#[allow(clippy::unnecessary_cast)]
+fn flags(path: &Path, mode: u32, uid: u32, setuid_prefixes: &[PathBuf]) -> u32 {
+    let file_type = mode & libc::S_IFMT as u32;
+    let is_file = file_type == libc::S_IFREG as u32;
+    let is_device = file_type == libc::S_IFBLK as u32 || file_type == libc::S_IFCHR as u32;
+    let is_setuid = mode & libc::S_ISUID as u32 != 0;
+
+    let mut flags = 0;
+
+    if is_file && is_setuid && mode & libc::S_IWOTH as u32 != 0 {
+        flags |= Anomaly::WORLD_WRITABLE_SETUID as u32;
+    }
+
+    if is_file && is_setuid {
+        let parent = path.parent().unwrap_or(path);
+        if !setuid_prefixes.iter().any(|prefix| parent.starts_with(prefix)) {
+            flags |= Anomaly::SETUID_OUTSIDE_STANDARD_DIRS as u32;
+        }
+    }
+
+    // Home directories themselves can be owned by the superuser in unusual
+    // setups, so only entries within them are considered.
+    if uid == 0 && HOME_PARENTS.iter().any(|home_parent| is_within_home(path, home_parent)) {
+        flags |= Anomaly::ROOT_OWNED_IN_HOME as u32;
+    }
+
+    // Symlinks have all permission bits set, so we consider regular files only.
+    if is_file && mode & 0o111 != 0 && is_hidden(path) {
+        flags |= Anomaly::HIDDEN_EXECUTABLE as u32;
+    }
+
+    if is_device && !path.starts_with("/dev") {
+        flags |= Anomaly::DEVICE_OUTSIDE_DEV as u32;
+    }
+
+    flags
+}
+
+/// Checks whether the path lies within a home directory in the given parent.
+fn is_within_home(path: &Path, home_parent: &str) -> bool {
+    match path.strip_prefix(home_parent) {
+        // The first component is the home directory itself.
+        Ok(path) => path.components().count() > 1,
+        Err(_) => false,
+    }
+}
+
+/// Checks whether the final component of the path is hidden.
+fn is_hidden(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    match path.file_name() {
+        Some(name) => name.as_bytes().starts_with(b"."),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Mode constants are `u16` on macOS, so the casts are not always a no-op.
+    #[allow(clippy::unnecessary_cast)]
+    const S_IFREG: u32 = libc::S_IFREG as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const S_IFDIR: u32 = libc::S_IFDIR as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const S_IFLNK: u32 = libc::S_IFLNK as u32;
+    #[allow(clippy::unnecessary_cast)]
+    const S_IFBLK: u32 = libc::S_IFBLK as u32;
+
+    fn default_prefixes() -> Vec<PathBuf> {
+        Detector::new(Vec::new()).setuid_prefixes
+    }
+
+    #[test]
+    fn flags_regular() {
+        let path = Path::new("/usr/bin/ls");
+        assert_eq!(flags(path, S_IFREG | 0o755, 0, &default_prefixes()), 0);
+    }
+
+    #[test]
+    fn flags_setuid_standard() {
+        let path = Path::new("/usr/bin/sudo");
+        assert_eq!(flags(path, S_IFREG | 0o4755, 0, &default_prefixes()), 0);
+    }
+
+    #[test]
+    fn flags_world_writable_setuid() {
+        let path = Path::new("/usr/bin/sudo");
+        assert_eq! {
+            flags(path, S_IFREG | 0o4777, 0, &default_prefixes()),
+            Anomaly::WORLD_WRITABLE_SETUID as u32
+        };
+    }
+
+    #[test]
+    fn flags_setuid_outside_standard_dirs() {
+        let path = Path::new("/tmp/sh");
+        assert_eq! {
+            flags(path, S_IFREG | 0o4755, 0, &default_prefixes()),
+            Anomaly::SETUID_OUTSIDE_STANDARD_DIRS as u32
+        };
+    }
+
+    #[test]
+    fn flags_setuid_custom_prefixes() {
+        let prefixes = vec![PathBuf::from("/opt/foo")];
+
+        let path = Path::new("/opt/foo/bin/bar");
+        assert_eq!(flags(path, S_IFREG | 0o4755, 0, &prefixes), 0);
+
+        let path = Path::new("/usr/bin/sudo");
+        assert_eq! {
+            flags(path, S_IFREG | 0o4755, 0, &prefixes),
+            Anomaly::SETUID_OUTSIDE_STANDARD_DIRS as u32
+        };
+    }
+
+    #[test]
+    fn flags_setuid_prefix_not_component() {
+        let path = Path::new("/binary/sh");
+        assert_eq! {
+            flags(path, S_IFREG | 0o4755, 0, &default_prefixes()),
+            Anomaly::SETUID_OUTSIDE_STANDARD_DIRS as u32
+        };
+    }
+
+    #[test]
+    fn flags_root_owned_in_home() {
+        let path = Path::new("/home/alice/.bashrc");
+        assert_eq! {
+            flags(path, S_IFREG | 0o644, 0, &default_prefixes()),
+            Anomaly::ROOT_OWNED_IN_HOME as u32
+        };
+
+        let path = Path::new("/Users/alice/Library");
+        assert_eq! {
+            flags(path, S_IFDIR | 0o755, 0, &default_prefixes()),
+            Anomaly::ROOT_OWNED_IN_HOME as u32
+        };
+    }
+
+    #[test]
+    fn flags_root_owned_home_dir() {
+        let path = Path::new("/home/alice");
+        assert_eq!(flags(path, S_IFDIR | 0o755, 0, &default_prefixes()), 0);
+    }
+
+    #[test]
+    fn flags_user_owned_in_home() {
+        let path = Path::new("/home/alice/.bashrc");
+        assert_eq!(flags(path, S_IFREG | 0o644, 1000, &default_prefixes()), 0);
+    }
+
+    #[test]
+    fn flags_hidden_executable() {
+        let path = Path::new("/tmp/.x");
+        assert_eq! {
+            flags(path, S_IFREG | 0o700, 1000, &default_prefixes()),
+            Anomaly::HIDDEN_EXECUTABLE as u32
+        };
+    }
+
+    #[test]
+    fn flags_hidden_symlink() {
+        let path = Path::new("/tmp/.x");
+        assert_eq!(flags(path, S_IFLNK | 0o777, 1000, &default_prefixes()), 0);
+    }
+
+    #[test]
+    fn flags_device_outside_dev() {
+        let path = Path::new("/tmp/sda");
+        assert_eq! {
+            flags(path, S_IFBLK | 0o660, 0, &default_prefixes()),
+            Anomaly::DEVICE_OUTSIDE_DEV as u32
+        };
+
+        let path = Path::new("/dev/sda");
+        assert_eq!(flags(path, S_IFBLK | 0o660, 0, &default_prefixes()), 0);
+    }
+
+    #[test]
+    fn detector_counts() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let setuid_path = tempdir.path().join("setuid");
+        std::fs::write(&setuid_path, b"")
+            .unwrap();
+        std::fs::set_permissions(&setuid_path, std::fs::Permissions::from_mode(0o4777))
+            .unwrap();
+
+        let hidden_path = tempdir.path().join(".hidden");
+        std::fs::write(&hidden_path, b"")
+            .unwrap();
+        std::fs::set_permissions(&hidden_path, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        let mut detector = Detector::new(Vec::new());
+
+        let flags = detector.detect(&crate::fs::Entry {
+            metadata: std::fs::symlink_metadata(&setuid_path).unwrap(),
+            path: setuid_path,
+        });
+        assert_eq! {
+            flags,
+            Anomaly::WORLD_WRITABLE_SETUID as u32 | Anomaly::SETUID_OUTSIDE_STANDARD_DIRS as u32
+        };
+
+        let flags = detector.detect(&crate::fs::Entry {
+            metadata: std::fs::symlink_metadata(&hidden_path).unwrap(),
+            path: hidden_path,
+        });
+        assert_eq!(flags, Anomaly::HIDDEN_EXECUTABLE as u32);
+
+        assert_eq!(detector.counts(), vec![
+            (Anomaly::WORLD_WRITABLE_SETUID, 1),
+            (Anomaly::SETUID_OUTSIDE_STANDARD_DIRS, 1),
+            (Anomaly::HIDDEN_EXECUTABLE, 1),
+        ]);
+
+        detector.reset();
+        assert_eq!(detector.counts(), vec![]);
+    }
+}
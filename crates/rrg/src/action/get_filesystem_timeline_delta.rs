@@ -0,0 +1,592 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the timeline delta action.
+//!
+//! Repeated timelines of the same root consist mostly of entries that did not
+//! change in the meantime. Instead of sending all of them again, this action
+//! takes a manifest of the previous timeline (hashes of paths and metadata of
+//! its entries) and sends only entries that are new, changed or deleted.
+
+use std::path::PathBuf;
+
+use rrg_proto::convert::FromLossy;
+use rrg_proto::get_filesystem_timeline_delta::Change;
+
+/// Arguments of the `get_filesystem_timeline_delta` action.
+pub struct Args {
+    /// Root directory to get the timeline delta of.
+    root: PathBuf,
+    /// Entries of the baseline manifest (sorted by path hashes).
+    baseline: Vec<ManifestEntry>,
+}
+
+/// Entry of a timeline manifest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ManifestEntry {
+    /// Hash of the path of the entry (see [`path_hash`]).
+    path_hash: u64,
+    /// Hash of the metadata of the entry (see [`metadata_hash`]).
+    metadata_hash: u64,
+}
+
+/// Result of the `get_filesystem_timeline_delta` action.
+pub struct Item {
+    /// Digests of the delta batch sent to the blob sink.
+    blob_digests: crate::blob::Digests,
+    /// Number of entries in the batch sent to the blob sink.
+    entry_count: usize,
+    /// Whether the collection stopped early (because of a limit).
+    truncated: bool,
+}
+
+/// Handles invocations of the `get_filesystem_timeline_delta` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    session.check_deadline()?;
+
+    // Entries of the baseline that we encounter during the walk are marked so
+    // that the remaining ones can be reported as deleted afterwards.
+    let visited = std::cell::RefCell::new(vec![false; args.baseline.len()]);
+
+    // Similarly to the `get_filesystem_timeline` action, counts and limits are
+    // shared between the `entries` iterator and the batch loop, see comments
+    // there for more details.
+    let entry_count = std::cell::Cell::new(0);
+
+    let redactor = session.redactor().clone();
+
+    let session = std::cell::RefCell::new(session);
+    let limits_error = std::cell::Cell::new(None);
+    let truncated = std::cell::Cell::new(false);
+
+    let entries = crate::fs::walk_dir(&args.root)?
+        .filter_entry(|entry| match session.borrow().check_path(&entry.path) {
+            Ok(()) => true,
+            Err(error) => {
+                log::warn!("skipping '{}': {error}", entry.path.display());
+                false
+            }
+        })
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(error) => {
+                log::warn!("failed to obtain directory entry: {}", error);
+                None
+            }
+        })
+        .map_while(|entry| match session.borrow().check_limits() {
+            Ok(()) => Some(entry),
+            Err(error) => {
+                limits_error.set(Some(error));
+                truncated.set(true);
+                None
+            }
+        })
+        .filter_map(|entry| {
+            let mut entry = rrg_proto::get_filesystem_timeline::Entry::from_lossy(entry);
+
+            // Hashes are computed before redaction, so that they do not depend
+            // on the redaction configuration of the agent.
+            let path_hash = path_hash(entry.path());
+            let metadata_hash = metadata_hash(&entry);
+
+            let index = args.baseline
+                .binary_search_by_key(&path_hash, |baseline| baseline.path_hash);
+
+            let change = match index {
+                Ok(index) => {
+                    visited.borrow_mut()[index] = true;
+                    if args.baseline[index].metadata_hash == metadata_hash {
+                        return None;
+                    }
+                    Change::CHANGED
+                }
+                Err(_) => Change::NEW,
+            };
+
+            // Entries are sent as blobs that the session does not look into,
+            // so we have to redact them ourselves.
+            if let std::borrow::Cow::Owned(path) = redactor.redact_path(entry.path()) {
+                entry.set_path(path);
+            }
+
+            let mut delta_entry = rrg_proto::get_filesystem_timeline_delta::Entry::new();
+            delta_entry.set_change(change);
+            delta_entry.set_entry(entry);
+            delta_entry.set_path_hash(path_hash);
+            delta_entry.set_metadata_hash(metadata_hash);
+            Some(delta_entry)
+        });
+
+    // Deleted entries can be determined only once the walk is complete, so
+    // this iterator has to be lazy: it is polled only after all the entries
+    // above have been yielded.
+    let deleted_entries = std::iter::once(()).flat_map(|()| {
+        // If the walk was stopped early, we cannot tell entries that have been
+        // deleted from the ones that were not visited.
+        if truncated.get() {
+            return Vec::new();
+        }
+
+        args.baseline.iter()
+            .zip(visited.borrow().iter())
+            .filter(|(_, visited)| !**visited)
+            .map(|(baseline, _)| {
+                let mut delta_entry = rrg_proto::get_filesystem_timeline_delta::Entry::new();
+                delta_entry.set_change(Change::DELETED);
+                delta_entry.set_path_hash(baseline.path_hash);
+                delta_entry
+            })
+            .collect()
+    });
+
+    let entries = entries.chain(deleted_entries)
+        .inspect(|_| {
+            entry_count.set(entry_count.get() + 1);
+        });
+
+    for batch in crate::gzchunked::encode(entries) {
+        let batch = batch
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(batch);
+
+        let mut session = session.borrow_mut();
+
+        // Entries of the next batch are not collected until this one is sent,
+        // so waiting here pauses the walk while the sink is congested.
+        crate::session::wait_for_sink(&mut **session, crate::Sink::Blob)?;
+
+        let blob_digests = session.send_blob(blob)?
+            .wait()
+            .map_err(crate::session::Error::action)?;
+        session.reply(Item {
+            blob_digests,
+            entry_count: entry_count.get(),
+            truncated: truncated.get(),
+        })?;
+
+        entry_count.set(0);
+    }
+
+    if let Some(error) = limits_error.take() {
+        log::warn!("aborting timeline delta collection: {error}");
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Computes the hash of the given entry path.
+///
+/// The hash is the first 8 bytes (as a little-endian integer) of the SHA-256
+/// digest of the path bytes (as encoded by [`crate::path::into_bytes`]).
+fn path_hash(path: &[u8]) -> u64 {
+    use sha2::Digest as _;
+
+    truncate(sha2::Sha256::digest(path).into())
+}
+
+/// Computes the hash of the metadata of the given timeline entry.
+///
+/// The hash is the first 8 bytes (as a little-endian integer) of the SHA-256
+/// digest of the fields listed below, each encoded as a little-endian 8-byte
+/// integer (0 if the field is not set):
+///
+///   * `size`,
+///   * `unix_mode`,
+///   * `unix_ino`,
+///   * `unix_uid_unsigned`,
+///   * `unix_gid_unsigned`,
+///   * `mtime_nanos`,
+///   * `ctime_nanos`,
+///   * `btime_nanos`,
+///   * `windows_attributes`.
+///
+/// Access time is excluded as it changes whenever the file is read. This
+/// definition is a part of the protocol (servers compute the manifest from
+/// timelines on their own), so it must not change.
+fn metadata_hash(entry: &rrg_proto::get_filesystem_timeline::Entry) -> u64 {
+    use sha2::Digest as _;
+
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(entry.size().to_le_bytes());
+    hasher.update(entry.unix_mode().to_le_bytes());
+    hasher.update(entry.unix_ino().to_le_bytes());
+    hasher.update(entry.unix_uid_unsigned().to_le_bytes());
+    hasher.update(entry.unix_gid_unsigned().to_le_bytes());
+    hasher.update(entry.mtime_nanos().to_le_bytes());
+    hasher.update(entry.ctime_nanos().to_le_bytes());
+    hasher.update(entry.btime_nanos().to_le_bytes());
+    hasher.update(entry.windows_attributes().to_le_bytes());
+
+    truncate(hasher.finalize().into())
+}
+
+/// Truncates the given SHA-256 digest to a 64-bit hash.
+fn truncate(digest: [u8; 32]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+
+    u64::from_le_bytes(bytes)
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_filesystem_timeline_delta::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let root = crate::path::from_proto(proto.take_root())
+            .map_err(|error| ParseArgsError::invalid_field("root", error))?;
+
+        let chunks = proto.baseline_manifest().iter()
+            .map(|chunk| chunk.as_slice());
+
+        use rrg_proto::get_filesystem_timeline_delta::ManifestEntry as ManifestEntryProto;
+
+        let mut baseline = crate::gzchunked::decode::<_, ManifestEntryProto>(chunks)
+            .map(|entry| entry.map(ManifestEntry::from))
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(|error| ParseArgsError::invalid_field("baseline_manifest", error))?;
+
+        // The manifest is expected to be sorted already but we cannot rely on
+        // that (and sorting a sorted vector is cheap anyway).
+        baseline.sort_unstable_by_key(|entry| entry.path_hash);
+        baseline.dedup_by_key(|entry| entry.path_hash);
+
+        Ok(Args {
+            root,
+            baseline,
+        })
+    }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.root.as_path()]
+    }
+}
+
+impl From<rrg_proto::get_filesystem_timeline_delta::ManifestEntry> for ManifestEntry {
+
+    fn from(proto: rrg_proto::get_filesystem_timeline_delta::ManifestEntry) -> ManifestEntry {
+        ManifestEntry {
+            path_hash: proto.path_hash(),
+            metadata_hash: proto.metadata_hash(),
+        }
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_filesystem_timeline_delta::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        if let Some(blob_sha256) = self.blob_digests.sha256 {
+            proto.set_blob_sha256(blob_sha256.into());
+        }
+        if let Some(blob_blake3) = self.blob_digests.blake3 {
+            proto.set_blob_blake3(blob_blake3.into());
+        }
+        proto.set_entry_count(self.entry_count as u64);
+        proto.set_truncated(self.truncated);
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_non_existent_root() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            root: tempdir.path().join("foo"),
+            baseline: Vec::new(),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, args)
+            .unwrap_err();
+        assert_eq!(error.code(), crate::session::ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn handle_empty_baseline() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("foo"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo").join("bar"), b"bar")
+            .unwrap();
+
+        let args = Args {
+            root: tempdir.path().to_path_buf(),
+            baseline: Vec::new(),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.entry.path().to_vec());
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].change(), Change::NEW);
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("foo")));
+        assert_eq!(entries[1].change(), Change::NEW);
+        assert_eq!(path(&entries[1]), Some(tempdir.path().join("foo").join("bar")));
+    }
+
+    #[test]
+    fn handle_unchanged() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        rrg_test_support::tree::many_small_files(tempdir.path(), 4, 4, 16)
+            .unwrap();
+
+        let baseline = manifest(tempdir.path());
+        assert!(!baseline.is_empty());
+
+        let args = Args {
+            root: tempdir.path().to_path_buf(),
+            baseline,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert!(entries(&session).is_empty());
+    }
+
+    #[test]
+    fn handle_mutations() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let root = tempdir.path();
+
+        std::fs::create_dir(root.join("a"))
+            .unwrap();
+        std::fs::write(root.join("a").join("foo"), b"foo")
+            .unwrap();
+        std::fs::write(root.join("a").join("bar"), b"bar")
+            .unwrap();
+        std::fs::create_dir(root.join("b"))
+            .unwrap();
+        std::fs::write(root.join("b").join("baz"), b"baz")
+            .unwrap();
+        std::fs::create_dir(root.join("c"))
+            .unwrap();
+        std::fs::write(root.join("c").join("quux"), b"quux")
+            .unwrap();
+
+        let baseline = manifest(root);
+
+        // Modifying a file does not affect its parent folder but creating or
+        // deleting one does.
+        std::fs::write(root.join("a").join("foo"), b"foofoo")
+            .unwrap();
+        std::fs::remove_file(root.join("b").join("baz"))
+            .unwrap();
+        std::fs::write(root.join("c").join("norf"), b"norf")
+            .unwrap();
+
+        let args = Args {
+            root: root.to_path_buf(),
+            baseline,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let entries = entries(&session);
+
+        let mut changes = entries.iter()
+            .filter(|entry| entry.change() != Change::DELETED)
+            .map(|entry| (entry.change(), path(entry).unwrap()))
+            .collect::<Vec<_>>();
+        changes.sort_by(|(_, path_a), (_, path_b)| path_a.cmp(path_b));
+
+        assert_eq!(changes, vec![
+            (Change::CHANGED, root.join("a").join("foo")),
+            (Change::CHANGED, root.join("b")),
+            (Change::CHANGED, root.join("c")),
+            (Change::NEW, root.join("c").join("norf")),
+        ]);
+
+        let deleted = entries.iter()
+            .filter(|entry| entry.change() == Change::DELETED)
+            .map(|entry| entry.path_hash())
+            .collect::<Vec<_>>();
+
+        let baz_path = crate::path::into_bytes(root.join("b").join("baz"));
+        assert_eq!(deleted, vec![path_hash(&baz_path)]);
+    }
+
+    #[test]
+    fn handle_truncated_no_deleted() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        for i in 0..10 {
+            std::fs::write(tempdir.path().join(i.to_string()), b"")
+                .unwrap();
+        }
+
+        let args = Args {
+            root: tempdir.path().to_path_buf(),
+            baseline: vec![ManifestEntry {
+                path_hash: path_hash(b"/foo/bar"),
+                metadata_hash: 0,
+            }],
+        };
+
+        // We simulate memory usage that grows with every check, so that the
+        // watermark is crossed after a few entries.
+        let checks = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut session = crate::session::FakeSession::new();
+        session.simulate_memory_usage(5, {
+            let checks = checks.clone();
+            move || {
+                checks.set(checks.get() + 1);
+                checks.get()
+            }
+        });
+
+        let error = handle(&mut session, args)
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::ResourceExhausted);
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 5);
+        assert!(entries.iter().all(|entry| entry.change() == Change::NEW));
+
+        let last_id = session.reply_count() - 1;
+        assert!(session.reply::<Item>(last_id).truncated);
+    }
+
+    #[test]
+    fn metadata_hash_stable() {
+        let mut entry = rrg_proto::get_filesystem_timeline::Entry::new();
+        entry.set_size(1337);
+        entry.set_unix_mode(0o100644);
+        entry.set_mtime_nanos(1_700_000_000_000_000_000);
+
+        // The hash is a part of the protocol, so it cannot change silently.
+        assert_eq!(metadata_hash(&entry), 0x5229e78c6a2e08aa);
+    }
+
+    #[test]
+    fn metadata_hash_ignores_atime() {
+        let mut entry = rrg_proto::get_filesystem_timeline::Entry::new();
+        entry.set_size(1337);
+        entry.set_atime_nanos(1_700_000_000_000_000_000);
+
+        let hash = metadata_hash(&entry);
+
+        entry.set_atime_nanos(1_800_000_000_000_000_000);
+        assert_eq!(metadata_hash(&entry), hash);
+    }
+
+    #[test]
+    fn path_hash_stable() {
+        assert_eq!(path_hash(b"/foo/bar"), 0xeaf3f86bad965da0);
+    }
+
+    #[test]
+    fn args_from_proto_unsorted_manifest() {
+        use crate::request::Args as _;
+
+        let manifest_entries = [(3, 30), (1, 10), (2, 20), (1, 10)]
+            .into_iter()
+            .map(|(path_hash, metadata_hash)| {
+                let mut proto = rrg_proto::get_filesystem_timeline_delta::ManifestEntry::new();
+                proto.set_path_hash(path_hash);
+                proto.set_metadata_hash(metadata_hash);
+                proto
+            });
+
+        let mut proto = rrg_proto::get_filesystem_timeline_delta::Args::new();
+        proto.set_root(crate::path::into_proto(PathBuf::from("/")));
+        for chunk in crate::gzchunked::encode(manifest_entries) {
+            proto.mut_baseline_manifest().push(chunk.unwrap());
+        }
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+
+        assert_eq!(args.baseline, vec![
+            ManifestEntry { path_hash: 1, metadata_hash: 10 },
+            ManifestEntry { path_hash: 2, metadata_hash: 20 },
+            ManifestEntry { path_hash: 3, metadata_hash: 30 },
+        ]);
+    }
+
+    #[test]
+    fn args_from_proto_invalid_manifest() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_filesystem_timeline_delta::Args::new();
+        proto.set_root(crate::path::into_proto(PathBuf::from("/")));
+        proto.mut_baseline_manifest().push(b"foobar".to_vec());
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    /// Builds a manifest of the full timeline of the given root.
+    fn manifest(root: &std::path::Path) -> Vec<ManifestEntry> {
+        let args = Args {
+            root: root.to_path_buf(),
+            baseline: Vec::new(),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let mut manifest = entries(&session).into_iter()
+            .map(|entry| ManifestEntry {
+                path_hash: entry.path_hash(),
+                metadata_hash: entry.metadata_hash(),
+            })
+            .collect::<Vec<_>>();
+        manifest.sort_by_key(|entry| entry.path_hash);
+
+        manifest
+    }
+
+    /// Retrieves entries of the delta sent by the action.
+    fn entries(
+        session: &crate::session::FakeSession,
+    ) -> Vec<rrg_proto::get_filesystem_timeline_delta::Entry> {
+        assert_eq!(session.parcel_count(crate::Sink::Blob), session.reply_count());
+
+        let chunks = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(crate::blob::Blob::as_bytes);
+
+        crate::gzchunked::decode(chunks)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    /// Retrieves the path of the given delta entry.
+    fn path(
+        entry: &rrg_proto::get_filesystem_timeline_delta::Entry,
+    ) -> Option<PathBuf> {
+        crate::path::from_bytes(entry.entry.path().to_owned()).ok()
+    }
+}
@@ -0,0 +1,451 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the Linux security configuration action.
+//!
+//! The status of every supported security subsystem is always reported, even
+//! if the subsystem is not present on the system, so that the server can tell
+//! missing subsystems apart from ones that were not collected.
+
+#[cfg(target_os = "linux")]
+mod apparmor;
+
+#[cfg(target_os = "linux")]
+mod audit;
+
+#[cfg(target_os = "linux")]
+mod lockdown;
+
+#[cfg(target_os = "linux")]
+mod selinux;
+
+/// Arguments of the `get_linux_security_config` action.
+#[cfg(target_os = "linux")]
+pub struct Args {
+    /// Whether the action is allowed to call external programs.
+    allow_external_commands: bool,
+}
+
+/// A result of the `get_linux_security_config` action.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+enum Item {
+    /// Status of the audit subsystem.
+    AuditStatus(AuditStatus),
+    /// Rule (as printed by `auditctl -l`) loaded into the audit subsystem.
+    AuditRule(String),
+    /// Status of SELinux.
+    SelinuxStatus(SelinuxStatus),
+    /// Status of AppArmor.
+    ApparmorStatus(ApparmorStatus),
+    /// Profile loaded into AppArmor.
+    ApparmorProfile(ApparmorProfile),
+    /// Status of the kernel lockdown.
+    LockdownStatus(LockdownStatus),
+}
+
+/// Presence of a security subsystem on the system.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Presence {
+    /// It was not possible to determine whether the subsystem is present.
+    #[default]
+    Unknown,
+    /// The subsystem is not supported by the kernel or is not enabled.
+    NotPresent,
+    /// The subsystem is present and enabled.
+    Present,
+}
+
+/// Status of the audit subsystem.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+struct AuditStatus {
+    /// Presence of the audit subsystem.
+    presence: Presence,
+    /// Error that occurred when collecting the status.
+    error: Option<std::io::Error>,
+    /// Mode of the audit subsystem.
+    mode: Option<AuditMode>,
+    /// Action taken by the kernel on critical audit errors.
+    failure_action: Option<AuditFailureAction>,
+    /// Identifier of the process of the audit daemon (0 if it does not run).
+    daemon_pid: u32,
+    /// Maximum number of messages per second (0 if unlimited).
+    rate_limit: u32,
+    /// Maximum number of messages waiting to be consumed by the daemon.
+    backlog_limit: u32,
+    /// Number of messages lost since the system was booted.
+    lost: u32,
+    /// Number of messages waiting to be consumed by the daemon.
+    backlog: u32,
+    /// Whether the loaded audit rules were listed.
+    rules_listed: bool,
+}
+
+/// Mode of the audit subsystem.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AuditMode {
+    /// Auditing is disabled.
+    Disabled,
+    /// Auditing is enabled.
+    Enabled,
+    /// Auditing is enabled and its configuration cannot be changed.
+    Locked,
+}
+
+/// Action taken by the kernel on critical audit errors.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AuditFailureAction {
+    /// Nothing is done.
+    Silent,
+    /// A message is written to the kernel log.
+    Printk,
+    /// The kernel panics.
+    Panic,
+}
+
+/// Status of SELinux.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+struct SelinuxStatus {
+    /// Presence of SELinux.
+    presence: Presence,
+    /// Error that occurred when collecting the status.
+    error: Option<std::io::Error>,
+    /// Mode of SELinux.
+    mode: Option<SelinuxMode>,
+    /// Name of the policy configured to be loaded.
+    policy_name: Option<String>,
+    /// Version of the loaded policy.
+    policy_version: Option<u32>,
+    /// Whether the loaded policy supports multi-level security.
+    mls: bool,
+}
+
+/// Mode of SELinux.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SelinuxMode {
+    /// Policy violations are only logged.
+    Permissive,
+    /// Policy violations are denied.
+    Enforcing,
+}
+
+/// Status of AppArmor.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+struct ApparmorStatus {
+    /// Presence of AppArmor.
+    presence: Presence,
+    /// Error that occurred when collecting the status.
+    error: Option<std::io::Error>,
+}
+
+/// Profile loaded into AppArmor.
+#[cfg(target_os = "linux")]
+#[derive(Debug, PartialEq, Eq)]
+struct ApparmorProfile {
+    /// Name of the profile.
+    name: String,
+    /// Mode of the profile (`None` if not known).
+    mode: Option<ApparmorMode>,
+}
+
+/// Mode of an AppArmor profile.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ApparmorMode {
+    /// Policy violations are denied.
+    Enforce,
+    /// Policy violations are only logged.
+    Complain,
+    /// Policy violations kill the process.
+    Kill,
+    /// The profile does not confine the process.
+    Unconfined,
+}
+
+/// Status of the kernel lockdown.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default)]
+struct LockdownStatus {
+    /// Presence of the kernel lockdown.
+    presence: Presence,
+    /// Error that occurred when collecting the status.
+    error: Option<std::io::Error>,
+    /// Mode of the kernel lockdown.
+    mode: Option<LockdownMode>,
+}
+
+/// Mode of the kernel lockdown.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LockdownMode {
+    /// The kernel is not locked down.
+    None,
+    /// Features allowing modifications of the running kernel are disabled.
+    Integrity,
+    /// Features allowing extraction of confidential information are disabled.
+    Confidentiality,
+}
+
+/// Handles invocations of the `get_linux_security_config` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut items = Vec::new();
+    items.extend(self::audit::items(args.allow_external_commands));
+    items.extend(self::selinux::items());
+    items.extend(self::apparmor::items());
+    items.extend(self::lockdown::items());
+
+    for item in items {
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `get_linux_security_config` action.
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_os = "linux")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_linux_security_config::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            allow_external_commands: proto.allow_external_commands(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<Presence> for rrg_proto::get_linux_security_config::Presence {
+
+    fn from(presence: Presence) -> rrg_proto::get_linux_security_config::Presence {
+        use rrg_proto::get_linux_security_config::Presence as Proto;
+
+        match presence {
+            Presence::Unknown => Proto::UNKNOWN,
+            Presence::NotPresent => Proto::NOT_PRESENT,
+            Presence::Present => Proto::PRESENT,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<AuditMode> for rrg_proto::get_linux_security_config::AuditMode {
+
+    fn from(mode: AuditMode) -> rrg_proto::get_linux_security_config::AuditMode {
+        use rrg_proto::get_linux_security_config::AuditMode as Proto;
+
+        match mode {
+            AuditMode::Disabled => Proto::AUDIT_DISABLED,
+            AuditMode::Enabled => Proto::AUDIT_ENABLED,
+            AuditMode::Locked => Proto::AUDIT_LOCKED,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<AuditFailureAction> for rrg_proto::get_linux_security_config::AuditFailureAction {
+
+    fn from(action: AuditFailureAction) -> rrg_proto::get_linux_security_config::AuditFailureAction {
+        use rrg_proto::get_linux_security_config::AuditFailureAction as Proto;
+
+        match action {
+            AuditFailureAction::Silent => Proto::SILENT,
+            AuditFailureAction::Printk => Proto::PRINTK,
+            AuditFailureAction::Panic => Proto::PANIC,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<SelinuxMode> for rrg_proto::get_linux_security_config::SelinuxMode {
+
+    fn from(mode: SelinuxMode) -> rrg_proto::get_linux_security_config::SelinuxMode {
+        use rrg_proto::get_linux_security_config::SelinuxMode as Proto;
+
+        match mode {
+            SelinuxMode::Permissive => Proto::PERMISSIVE,
+            SelinuxMode::Enforcing => Proto::ENFORCING,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<ApparmorMode> for rrg_proto::get_linux_security_config::ApparmorMode {
+
+    fn from(mode: ApparmorMode) -> rrg_proto::get_linux_security_config::ApparmorMode {
+        use rrg_proto::get_linux_security_config::ApparmorMode as Proto;
+
+        match mode {
+            ApparmorMode::Enforce => Proto::ENFORCE,
+            ApparmorMode::Complain => Proto::COMPLAIN,
+            ApparmorMode::Kill => Proto::KILL,
+            ApparmorMode::Unconfined => Proto::UNCONFINED,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<LockdownMode> for rrg_proto::get_linux_security_config::LockdownMode {
+
+    fn from(mode: LockdownMode) -> rrg_proto::get_linux_security_config::LockdownMode {
+        use rrg_proto::get_linux_security_config::LockdownMode as Proto;
+
+        match mode {
+            LockdownMode::None => Proto::NONE,
+            LockdownMode::Integrity => Proto::INTEGRITY,
+            LockdownMode::Confidentiality => Proto::CONFIDENTIALITY,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_linux_security_config::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_linux_security_config::Result::new();
+
+        match self {
+            Item::AuditStatus(status) => {
+                let status_proto = proto.mut_audit_status();
+                status_proto.set_presence(status.presence.into());
+                if let Some(error) = status.error {
+                    status_proto.set_error(error.to_string());
+                }
+                if let Some(mode) = status.mode {
+                    status_proto.set_mode(mode.into());
+                }
+                if let Some(failure_action) = status.failure_action {
+                    status_proto.set_failure_action(failure_action.into());
+                }
+                status_proto.set_daemon_pid(status.daemon_pid);
+                status_proto.set_rate_limit(status.rate_limit);
+                status_proto.set_backlog_limit(status.backlog_limit);
+                status_proto.set_lost(status.lost);
+                status_proto.set_backlog(status.backlog);
+                status_proto.set_rules_listed(status.rules_listed);
+            }
+            Item::AuditRule(rule) => {
+                proto.mut_audit_rule().set_rule(rule);
+            }
+            Item::SelinuxStatus(status) => {
+                let status_proto = proto.mut_selinux_status();
+                status_proto.set_presence(status.presence.into());
+                if let Some(error) = status.error {
+                    status_proto.set_error(error.to_string());
+                }
+                if let Some(mode) = status.mode {
+                    status_proto.set_mode(mode.into());
+                }
+                if let Some(policy_name) = status.policy_name {
+                    status_proto.set_policy_name(policy_name);
+                }
+                if let Some(policy_version) = status.policy_version {
+                    status_proto.set_policy_version(policy_version);
+                }
+                status_proto.set_mls(status.mls);
+            }
+            Item::ApparmorStatus(status) => {
+                let status_proto = proto.mut_apparmor_status();
+                status_proto.set_presence(status.presence.into());
+                if let Some(error) = status.error {
+                    status_proto.set_error(error.to_string());
+                }
+            }
+            Item::ApparmorProfile(profile) => {
+                let profile_proto = proto.mut_apparmor_profile();
+                profile_proto.set_name(profile.name);
+                if let Some(mode) = profile.mode {
+                    profile_proto.set_mode(mode.into());
+                }
+            }
+            Item::LockdownStatus(status) => {
+                let status_proto = proto.mut_lockdown_status();
+                status_proto.set_presence(status.presence.into());
+                if let Some(error) = status.error {
+                    status_proto.set_error(error.to_string());
+                }
+                if let Some(mode) = status.mode {
+                    status_proto.set_mode(mode.into());
+                }
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_classifies_all_subsystems() {
+        let mut session = crate::session::FakeSession::new();
+
+        let args = Args {
+            allow_external_commands: false,
+        };
+        assert!(handle(&mut session, args).is_ok());
+
+        let mut audit_count = 0;
+        let mut selinux_count = 0;
+        let mut apparmor_count = 0;
+        let mut lockdown_count = 0;
+
+        for item in session.replies::<Item>() {
+            match item {
+                Item::AuditStatus(status) => {
+                    audit_count += 1;
+                    // Rules are listed only with `auditctl`.
+                    assert!(!status.rules_listed);
+                }
+                Item::AuditRule(_) => panic!("unexpected audit rule"),
+                Item::SelinuxStatus(status) => {
+                    selinux_count += 1;
+                    if status.presence == Presence::Present {
+                        assert!(status.mode.is_some() || status.error.is_some());
+                    }
+                }
+                Item::ApparmorStatus(_) => apparmor_count += 1,
+                Item::ApparmorProfile(_) => (),
+                Item::LockdownStatus(status) => {
+                    lockdown_count += 1;
+                    if status.presence == Presence::Present {
+                        assert!(status.mode.is_some() || status.error.is_some());
+                    }
+                }
+            }
+        }
+
+        assert_eq!(audit_count, 1);
+        assert_eq!(selinux_count, 1);
+        assert_eq!(apparmor_count, 1);
+        assert_eq!(lockdown_count, 1);
+    }
+}
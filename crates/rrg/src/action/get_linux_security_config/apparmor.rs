@@ -0,0 +1,132 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{ApparmorMode, ApparmorProfile, ApparmorStatus, Item, Presence};
+
+/// Path to the file telling whether AppArmor is enabled.
+///
+/// The file exists only if the kernel is built with AppArmor.
+const ENABLED_PATH: &str = "/sys/module/apparmor/parameters/enabled";
+
+/// Path to the file listing the loaded profiles.
+const PROFILES_PATH: &str = "/sys/kernel/security/apparmor/profiles";
+
+/// Returns items with the status of AppArmor and its loaded profiles.
+pub fn items() -> Vec<Item> {
+    let mut status = ApparmorStatus::default();
+    let mut items = Vec::new();
+
+    status.presence = match std::fs::read_to_string(ENABLED_PATH) {
+        Ok(content) if content.trim() == "Y" => Presence::Present,
+        Ok(_) => Presence::NotPresent,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Presence::NotPresent,
+        Err(error) => {
+            status.error = Some(error);
+            Presence::Unknown
+        }
+    };
+
+    if status.presence == Presence::Present {
+        match std::fs::read_to_string(PROFILES_PATH) {
+            Ok(content) => {
+                items.extend(parse(&content).into_iter().map(Item::ApparmorProfile));
+            }
+            Err(error) => status.error = Some(error),
+        }
+    }
+
+    items.insert(0, Item::ApparmorStatus(status));
+    items
+}
+
+/// Parses the list of loaded profiles.
+///
+/// Every line of the list describes a single profile in the `<name> (<mode>)`
+/// format. Profile names can contain spaces, so the mode is split off from the
+/// end of the line. Profiles in modes that we do not know about are reported
+/// without a mode.
+fn parse(content: &str) -> Vec<ApparmorProfile> {
+    let mut profiles = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some((name, mode)) = line.strip_suffix(')').and_then(|line| line.rsplit_once(" (")) else {
+            log::warn!("malformed AppArmor profile: {line:?}");
+            continue;
+        };
+
+        let mode = match mode {
+            "enforce" => Some(ApparmorMode::Enforce),
+            "complain" => Some(ApparmorMode::Complain),
+            "kill" => Some(ApparmorMode::Kill),
+            "unconfined" => Some(ApparmorMode::Unconfined),
+            _ => None,
+        };
+
+        profiles.push(ApparmorProfile {
+            name: String::from(name),
+            mode,
+        });
+    }
+
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_fixture() {
+        let profiles = parse(include_str!("../../../testdata/apparmor_profiles"));
+
+        assert_eq!(profiles.len(), 12);
+
+        assert_eq!(profiles[0], ApparmorProfile {
+            name: String::from("snap.firefox.hook.post-refresh"),
+            mode: Some(ApparmorMode::Enforce),
+        });
+        assert_eq!(profiles[3], ApparmorProfile {
+            name: String::from("/usr/sbin/cupsd//third_party"),
+            mode: Some(ApparmorMode::Enforce),
+        });
+        assert_eq!(profiles[4], ApparmorProfile {
+            name: String::from("/usr/lib/cups/backend/cups-pdf"),
+            mode: Some(ApparmorMode::Complain),
+        });
+        assert_eq!(profiles[7], ApparmorProfile {
+            name: String::from("Discord"),
+            mode: Some(ApparmorMode::Unconfined),
+        });
+        assert_eq!(profiles[8], ApparmorProfile {
+            name: String::from("/opt/My App/bin/app"),
+            mode: Some(ApparmorMode::Kill),
+        });
+        assert_eq!(profiles[11], ApparmorProfile {
+            name: String::from("nvidia_modprobe"),
+            mode: None,
+        });
+    }
+
+    #[test]
+    fn parse_malformed() {
+        let profiles = parse("foo\nbar (enforce)\n(complain\n");
+
+        assert_eq!(profiles, vec![ApparmorProfile {
+            name: String::from("bar"),
+            mode: Some(ApparmorMode::Enforce),
+        }]);
+    }
+
+    #[test]
+    fn parse_empty() {
+        assert!(parse("").is_empty());
+    }
+}
@@ -0,0 +1,427 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd};
+
+use super::{AuditFailureAction, AuditMode, AuditStatus, Item, Presence};
+
+/// Type of the netlink message requesting (and carrying) the audit status.
+///
+/// See `include/uapi/linux/audit.h` in the kernel sources.
+const AUDIT_GET: u16 = 1000;
+
+/// Size of the netlink message header.
+const HEADER_LEN: usize = 16;
+
+/// Size of the part of `struct audit_status` that we parse.
+///
+/// The structure has been extended over time, but the first 8 fields (each a
+/// 32-bit integer) have been there since the beginning.
+const STATUS_LEN: usize = 8 * std::mem::size_of::<u32>();
+
+/// Time after which we give up waiting for the status from the kernel.
+const RECV_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Returns items with the status of the audit subsystem and its loaded rules.
+///
+/// The status is queried from the kernel over netlink. If that fails (e.g. we
+/// are running in a network namespace other than the initial one) and external
+/// commands are allowed, `auditctl` is used instead. Rules are listed only with
+/// `auditctl` and so only if external commands are allowed.
+pub fn items(allow_external_commands: bool) -> Vec<Item> {
+    let mut status = match query_status() {
+        Ok(status) => status,
+        // The kernel does not support the audit netlink protocol if it is built
+        // without audit support.
+        Err(error) if error.raw_os_error() == Some(libc::EPROTONOSUPPORT) => AuditStatus {
+            presence: Presence::NotPresent,
+            ..AuditStatus::default()
+        },
+        Err(error) if allow_external_commands => match auditctl_status() {
+            Ok(status) => status,
+            Err(auditctl_error) => {
+                log::warn!("failed to get audit status with auditctl: {auditctl_error}");
+                AuditStatus {
+                    error: Some(error),
+                    ..AuditStatus::default()
+                }
+            }
+        },
+        Err(error) => AuditStatus {
+            error: Some(error),
+            ..AuditStatus::default()
+        },
+    };
+
+    let mut items = Vec::new();
+
+    if allow_external_commands && status.presence == Presence::Present {
+        match auditctl_rules() {
+            Ok(rules) => {
+                status.rules_listed = true;
+                items.extend(rules.into_iter().map(Item::AuditRule));
+            }
+            Err(error) => {
+                status.error.get_or_insert(error);
+            }
+        }
+    }
+
+    items.insert(0, Item::AuditStatus(status));
+    items
+}
+
+/// Queries the audit status from the kernel over netlink.
+fn query_status() -> std::io::Result<AuditStatus> {
+    // SAFETY: This is just a system call without any memory-related invariants.
+    // We check for errors below.
+    let fd = unsafe {
+        libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_AUDIT)
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: We verified that the descriptor is valid and we are its only
+    // owner, so it is fine to close it when dropped.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // The kernel sends the reply from a separate thread, so we should not wait
+    // for it indefinitely in case something goes wrong.
+    let timeout = libc::timeval {
+        tv_sec: RECV_TIMEOUT.as_secs() as libc::time_t,
+        tv_usec: 0,
+    };
+    // SAFETY: We pass a valid pointer to the option value together with its
+    // size.
+    let status = unsafe {
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            (&timeout as *const libc::timeval).cast(),
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+    if status < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // There is only one request per socket, so any sequence number will do.
+    const SEQ: u32 = 1;
+
+    let mut request = Vec::with_capacity(HEADER_LEN);
+    request.extend_from_slice(&(HEADER_LEN as u32).to_ne_bytes());
+    request.extend_from_slice(&AUDIT_GET.to_ne_bytes());
+    request.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16).to_ne_bytes());
+    request.extend_from_slice(&SEQ.to_ne_bytes());
+    request.extend_from_slice(&0u32.to_ne_bytes());
+
+    // SAFETY: We pass a valid buffer together with its length.
+    let status = unsafe {
+        libc::send(fd.as_raw_fd(), request.as_ptr().cast(), request.len(), 0)
+    };
+    if status < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0u8; 8 * 1024];
+
+    loop {
+        // SAFETY: We pass a valid buffer together with its length. The kernel
+        // does not write past it.
+        let len = unsafe {
+            libc::recv(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0)
+        };
+        if len < 0 {
+            let error = std::io::Error::last_os_error();
+            match error.kind() {
+                std::io::ErrorKind::Interrupted => continue,
+                std::io::ErrorKind::WouldBlock => {
+                    return Err(std::io::ErrorKind::TimedOut.into());
+                }
+                _ => return Err(error),
+            }
+        }
+        if len == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut messages = &buf[..len as usize];
+        while let Some((kind, seq, payload, rest)) = split_message(messages) {
+            messages = rest;
+
+            if seq != SEQ {
+                continue;
+            }
+
+            match kind {
+                AUDIT_GET => return parse_status(payload),
+                kind if kind == libc::NLMSG_ERROR as u16 => {
+                    let Some(code) = payload.get(..4) else {
+                        return Err(invalid_data("truncated netlink error"));
+                    };
+                    let code = i32::from_ne_bytes([code[0], code[1], code[2], code[3]]);
+                    // A zero code is just an acknowledgement of the request,
+                    // the status comes in a separate message.
+                    if code != 0 {
+                        return Err(std::io::Error::from_raw_os_error(-code));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Splits the first netlink message off the given buffer.
+///
+/// The message is returned as its type, sequence number, payload and the rest
+/// of the buffer. `None` is returned if the buffer does not contain a complete
+/// message.
+fn split_message(buf: &[u8]) -> Option<(u16, u32, &[u8], &[u8])> {
+    let header = buf.get(..HEADER_LEN)?;
+
+    let len = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let kind = u16::from_ne_bytes([header[4], header[5]]);
+    let seq = u32::from_ne_bytes([header[8], header[9], header[10], header[11]]);
+
+    let payload = buf.get(HEADER_LEN..len)?;
+    // Messages are aligned to 4 bytes.
+    let rest = buf.get(len.next_multiple_of(4)..).unwrap_or(&[]);
+
+    Some((kind, seq, payload, rest))
+}
+
+/// Parses the audit status from the payload of an `AUDIT_GET` reply.
+///
+/// The payload is `struct audit_status` (in the native byte order).
+fn parse_status(payload: &[u8]) -> std::io::Result<AuditStatus> {
+    let Some(payload) = payload.get(..STATUS_LEN) else {
+        return Err(invalid_data("truncated audit status"));
+    };
+
+    let mut fields = payload.chunks_exact(4)
+        .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+    let mut field = || fields.next().unwrap_or(0);
+
+    // The first field is a mask of fields to set, meaningless in replies.
+    let _ = field();
+
+    Ok(AuditStatus {
+        presence: Presence::Present,
+        mode: mode(field()),
+        failure_action: failure_action(field()),
+        daemon_pid: field(),
+        rate_limit: field(),
+        backlog_limit: field(),
+        lost: field(),
+        backlog: field(),
+        ..AuditStatus::default()
+    })
+}
+
+/// Gets the audit status using `auditctl -s`.
+fn auditctl_status() -> std::io::Result<AuditStatus> {
+    let output = auditctl("-s")?;
+    Ok(parse_auditctl_status(&output))
+}
+
+/// Parses the audit status printed by `auditctl -s`.
+///
+/// Every line of the output is a `<name> <value>` pair. Fields that we do not
+/// know about are ignored.
+fn parse_auditctl_status(output: &str) -> AuditStatus {
+    let mut status = AuditStatus {
+        presence: Presence::Present,
+        ..AuditStatus::default()
+    };
+
+    for line in output.lines() {
+        let Some((name, value)) = line.trim().split_once(' ') else {
+            continue;
+        };
+        let Ok(value) = value.trim().parse::<u32>() else {
+            continue;
+        };
+
+        match name {
+            "enabled" => status.mode = mode(value),
+            "failure" => status.failure_action = failure_action(value),
+            "pid" => status.daemon_pid = value,
+            "rate_limit" => status.rate_limit = value,
+            "backlog_limit" => status.backlog_limit = value,
+            "lost" => status.lost = value,
+            "backlog" => status.backlog = value,
+            _ => (),
+        }
+    }
+
+    status
+}
+
+/// Lists the loaded audit rules using `auditctl -l`.
+fn auditctl_rules() -> std::io::Result<Vec<String>> {
+    let output = auditctl("-l")?;
+    Ok(parse_auditctl_rules(&output))
+}
+
+/// Parses the loaded audit rules printed by `auditctl -l`.
+///
+/// Every line of the output is a single rule, unless there are no rules at all
+/// in which case `No rules` is printed.
+fn parse_auditctl_rules(output: &str) -> Vec<String> {
+    output.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && *line != "No rules")
+        .map(String::from)
+        .collect()
+}
+
+/// Runs `auditctl` with the given flag and returns its standard output.
+fn auditctl(flag: &str) -> std::io::Result<String> {
+    let output = std::process::Command::new("auditctl")
+        .arg(flag)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format! {
+            "auditctl {flag} failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        }));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Maps the `enabled` field of the audit status to the audit mode.
+fn mode(enabled: u32) -> Option<AuditMode> {
+    match enabled {
+        0 => Some(AuditMode::Disabled),
+        1 => Some(AuditMode::Enabled),
+        2 => Some(AuditMode::Locked),
+        _ => None,
+    }
+}
+
+/// Maps the `failure` field of the audit status to the failure action.
+fn failure_action(failure: u32) -> Option<AuditFailureAction> {
+    match failure {
+        0 => Some(AuditFailureAction::Silent),
+        1 => Some(AuditFailureAction::Printk),
+        2 => Some(AuditFailureAction::Panic),
+        _ => None,
+    }
+}
+
+/// Creates an error indicating that the kernel replied with malformed data.
+fn invalid_data(message: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_status_ok() {
+        let mut payload = Vec::new();
+        // mask, enabled, failure, pid, rate_limit, backlog_limit, lost, backlog.
+        for field in [0u32, 2, 1, 612, 100, 8192, 3, 7] {
+            payload.extend_from_slice(&field.to_ne_bytes());
+        }
+        // Newer kernels send more fields that we do not parse.
+        payload.extend_from_slice(&[0; 12]);
+
+        let status = parse_status(&payload)
+            .unwrap();
+        assert_eq!(status.presence, Presence::Present);
+        assert_eq!(status.mode, Some(AuditMode::Locked));
+        assert_eq!(status.failure_action, Some(AuditFailureAction::Printk));
+        assert_eq!(status.daemon_pid, 612);
+        assert_eq!(status.rate_limit, 100);
+        assert_eq!(status.backlog_limit, 8192);
+        assert_eq!(status.lost, 3);
+        assert_eq!(status.backlog, 7);
+    }
+
+    #[test]
+    fn parse_status_truncated() {
+        let error = parse_status(&[0; STATUS_LEN - 1])
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn split_message_ok() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(HEADER_LEN as u32 + 2).to_ne_bytes());
+        buf.extend_from_slice(&AUDIT_GET.to_ne_bytes());
+        buf.extend_from_slice(&0u16.to_ne_bytes());
+        buf.extend_from_slice(&42u32.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(&[0xAB, 0xCD, 0x00, 0x00]);
+        buf.extend_from_slice(&[0xFF; HEADER_LEN]);
+
+        let (kind, seq, payload, rest) = split_message(&buf)
+            .unwrap();
+        assert_eq!(kind, AUDIT_GET);
+        assert_eq!(seq, 42);
+        assert_eq!(payload, &[0xAB, 0xCD]);
+        assert_eq!(rest, &[0xFF; HEADER_LEN]);
+    }
+
+    #[test]
+    fn split_message_truncated() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(HEADER_LEN as u32 + 32).to_ne_bytes());
+        buf.extend_from_slice(&[0; HEADER_LEN - 4]);
+
+        assert!(split_message(&buf).is_none());
+    }
+
+    #[test]
+    fn parse_auditctl_status_ok() {
+        let status = parse_auditctl_status("\
+enabled 1
+failure 2
+pid 1337
+rate_limit 0
+backlog_limit 8192
+lost 0
+backlog 4
+backlog_wait_time 60000
+backlog_wait_time_actual 0
+loginuid_immutable 0 unlocked
+");
+        assert_eq!(status.presence, Presence::Present);
+        assert_eq!(status.mode, Some(AuditMode::Enabled));
+        assert_eq!(status.failure_action, Some(AuditFailureAction::Panic));
+        assert_eq!(status.daemon_pid, 1337);
+        assert_eq!(status.rate_limit, 0);
+        assert_eq!(status.backlog_limit, 8192);
+        assert_eq!(status.backlog, 4);
+    }
+
+    #[test]
+    fn parse_auditctl_rules_ok() {
+        let rules = parse_auditctl_rules("\
+-w /etc/passwd -p wa -k identity
+-a always,exit -F arch=b64 -S execve -F key=exec
+");
+        assert_eq!(rules, vec![
+            "-w /etc/passwd -p wa -k identity",
+            "-a always,exit -F arch=b64 -S execve -F key=exec",
+        ]);
+    }
+
+    #[test]
+    fn parse_auditctl_rules_none() {
+        assert!(parse_auditctl_rules("No rules\n").is_empty());
+    }
+}
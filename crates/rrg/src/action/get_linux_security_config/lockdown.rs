@@ -0,0 +1,97 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{Item, LockdownMode, LockdownStatus, Presence};
+
+/// Path to the file with the kernel lockdown mode.
+///
+/// See the [documentation][1] for more details.
+///
+/// [1]: https://man7.org/linux/man-pages/man7/kernel_lockdown.7.html
+const LOCKDOWN_PATH: &str = "/sys/kernel/security/lockdown";
+
+/// Returns an item with the status of the kernel lockdown.
+///
+/// The file exists only if the kernel is built with the lockdown module (and
+/// the security filesystem is mounted), so the lockdown is considered to be
+/// not present without it.
+pub fn items() -> Vec<Item> {
+    let status = match std::fs::read_to_string(LOCKDOWN_PATH) {
+        Ok(content) => match parse(&content) {
+            Some(mode) => LockdownStatus {
+                presence: Presence::Present,
+                mode: Some(mode),
+                ..LockdownStatus::default()
+            },
+            None => LockdownStatus {
+                presence: Presence::Present,
+                error: Some(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("malformed lockdown mode: {content:?}"),
+                )),
+                ..LockdownStatus::default()
+            },
+        },
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => LockdownStatus {
+            presence: Presence::NotPresent,
+            ..LockdownStatus::default()
+        },
+        Err(error) => LockdownStatus {
+            presence: Presence::Unknown,
+            error: Some(error),
+            ..LockdownStatus::default()
+        },
+    };
+
+    vec![Item::LockdownStatus(status)]
+}
+
+/// Parses the content of the kernel lockdown file.
+///
+/// The file lists all the supported modes with the active one in brackets,
+/// e.g. `none [integrity] confidentiality`.
+fn parse(content: &str) -> Option<LockdownMode> {
+    let active = content.split_whitespace()
+        .find_map(|mode| mode.strip_prefix('[')?.strip_suffix(']'))?;
+
+    match active {
+        "none" => Some(LockdownMode::None),
+        "integrity" => Some(LockdownMode::Integrity),
+        "confidentiality" => Some(LockdownMode::Confidentiality),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_fixture() {
+        let content = include_str!("../../../testdata/lockdown");
+        assert_eq!(parse(content), Some(LockdownMode::Integrity));
+    }
+
+    #[test]
+    fn parse_none() {
+        assert_eq!(parse("[none] integrity confidentiality\n"), Some(LockdownMode::None));
+    }
+
+    #[test]
+    fn parse_confidentiality() {
+        assert_eq!(parse("none integrity [confidentiality]\n"), Some(LockdownMode::Confidentiality));
+    }
+
+    #[test]
+    fn parse_no_active() {
+        assert_eq!(parse("none integrity confidentiality\n"), None);
+    }
+
+    #[test]
+    fn parse_unknown_mode() {
+        assert_eq!(parse("none [paranoid]\n"), None);
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use super::{Item, Presence, SelinuxMode, SelinuxStatus};
+
+/// Path to the SELinux filesystem.
+///
+/// The filesystem is mounted only if SELinux is enabled, otherwise the path
+/// might not exist at all or be just an empty directory.
+const SELINUXFS_PATH: &str = "/sys/fs/selinux";
+
+/// Path to the SELinux configuration file.
+const CONFIG_PATH: &str = "/etc/selinux/config";
+
+/// Returns an item with the status of SELinux.
+pub fn items() -> Vec<Item> {
+    let selinuxfs = Path::new(SELINUXFS_PATH);
+
+    let mut status = SelinuxStatus::default();
+
+    match std::fs::read_to_string(selinuxfs.join("enforce")) {
+        Ok(content) => {
+            status.presence = Presence::Present;
+            status.mode = match content.trim() {
+                "0" => Some(SelinuxMode::Permissive),
+                "1" => Some(SelinuxMode::Enforcing),
+                _ => None,
+            };
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            status.presence = Presence::NotPresent;
+            return vec![Item::SelinuxStatus(status)];
+        }
+        Err(error) => {
+            status.error = Some(error);
+            return vec![Item::SelinuxStatus(status)];
+        }
+    }
+
+    match std::fs::read_to_string(selinuxfs.join("policyvers")) {
+        Ok(content) => status.policy_version = content.trim().parse().ok(),
+        Err(error) => status.error = Some(error),
+    }
+
+    match std::fs::read_to_string(selinuxfs.join("mls")) {
+        Ok(content) => status.mls = content.trim() == "1",
+        Err(error) => status.error = Some(error),
+    }
+
+    match std::fs::read_to_string(CONFIG_PATH) {
+        Ok(content) => status.policy_name = parse_policy_name(&content),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => status.error = Some(error),
+    }
+
+    vec![Item::SelinuxStatus(status)]
+}
+
+/// Parses the name of the policy from the SELinux configuration file.
+///
+/// The configuration is a list of `<key>=<value>` lines where the policy name
+/// is given by the `SELINUXTYPE` key [1]. If the key is given more than once,
+/// the last occurrence wins.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/selinux_config.5.html
+fn parse_policy_name(content: &str) -> Option<String> {
+    content.lines()
+        .rev()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "SELINUXTYPE")
+        .map(|(_, value)| String::from(value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_policy_name_ok() {
+        let content = "\
+# This file controls the state of SELinux on the system.
+SELINUX=enforcing
+# SELINUXTYPE=mls
+SELINUXTYPE=targeted
+";
+        assert_eq!(parse_policy_name(content).as_deref(), Some("targeted"));
+    }
+
+    #[test]
+    fn parse_policy_name_missing() {
+        assert_eq!(parse_policy_name("SELINUX=permissive\n"), None);
+    }
+}
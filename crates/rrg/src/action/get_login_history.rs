@@ -0,0 +1,275 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the login history action.
+//!
+//! The history is read from the `utmp` files (the current logins, the log of
+//! logins and logouts and the log of failed login attempts), see the
+//! [`crate::utmp`] module for the details.
+
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+/// Paths to the files with records to collect.
+#[cfg(target_os = "linux")]
+const PATHS: [&str; 3] = [
+    "/var/log/wtmp",
+    "/var/run/utmp",
+    "/var/log/btmp",
+];
+
+/// Arguments of the `get_login_history` action.
+#[cfg(target_os = "linux")]
+pub struct Args {
+    /// Time since which the records should be collected.
+    since: Option<std::time::SystemTime>,
+    /// Maximum number of records to collect (if limited).
+    max_records: Option<u64>,
+    /// Whether to also collect records from rotated files.
+    include_rotated: bool,
+    /// Layout of the records in the files (picked for each file if not
+    /// specified).
+    layout: Option<crate::utmp::Layout>,
+}
+
+/// A result of the `get_login_history` action.
+#[cfg(target_os = "linux")]
+pub struct Item {
+    /// Path to the file from which the record was read.
+    path: PathBuf,
+    /// The record itself.
+    record: crate::utmp::Record,
+}
+
+/// Handles invocations of the `get_login_history` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut paths = Vec::new();
+    for path in PATHS {
+        let path = Path::new(path);
+        paths.push(path.to_path_buf());
+        if args.include_rotated {
+            paths.extend(rotated_paths(path));
+        }
+    }
+
+    let mut record_count = 0;
+
+    for path in paths {
+        let records = match crate::utmp::open(&path, args.layout) {
+            Ok(records) => records,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to open '{}': {}", path.display(), error);
+                continue;
+            }
+        };
+
+        for record in records {
+            let record = match record {
+                Ok(record) => record,
+                Err(error) => {
+                    log::warn!("failed to read '{}': {}", path.display(), error);
+                    break;
+                }
+            };
+
+            if args.since.is_some_and(|since| record.time < since) {
+                continue;
+            }
+            if args.max_records.is_some_and(|max_records| record_count >= max_records) {
+                return Ok(());
+            }
+
+            session.reply(Item {
+                path: path.clone(),
+                record,
+            })?;
+            record_count += 1;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Returns paths to uncompressed rotated versions of the given file.
+///
+/// Rotated files have a numeric suffix (e.g. `wtmp.1`) and are returned from
+/// the most recent one.
+#[cfg(target_os = "linux")]
+fn rotated_paths(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to list '{}': {}", dir.display(), error);
+            return Vec::new();
+        }
+    };
+
+    let mut rotated = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let entry_name = entry.file_name();
+            let suffix = entry_name.to_str()?
+                .strip_prefix(name.to_str()?)?
+                .strip_prefix('.')?;
+            let index = suffix.parse::<u32>().ok()?;
+            Some((index, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    rotated.sort();
+
+    rotated.into_iter().map(|(_, path)| path).collect()
+}
+
+#[cfg(target_os = "linux")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_login_history::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+        use rrg_proto::get_login_history::Layout as Proto;
+
+        let since = if proto.has_since() {
+            let since = rrg_proto::try_from_timestamp(proto.take_since())
+                .map_err(|error| ParseArgsError::invalid_field("since", error))?;
+            Some(since)
+        } else {
+            None
+        };
+
+        let max_records = match proto.max_records() {
+            0 => None,
+            max_records => Some(max_records),
+        };
+
+        let layout = match proto.layout() {
+            Proto::NATIVE => None,
+            Proto::TIME32 => Some(crate::utmp::Layout::Time32),
+            Proto::TIME64 => Some(crate::utmp::Layout::Time64),
+        };
+
+        Ok(Args {
+            since,
+            max_records,
+            include_rotated: proto.include_rotated(),
+            layout,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_login_history::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let record = self.record;
+
+        let mut proto = rrg_proto::get_login_history::Result::new();
+        proto.set_path(self.path.into());
+        proto.set_type(record.r#type.into());
+        proto.set_raw_type(i32::from(record.raw_type));
+        proto.set_user(record.user.to_string_lossy().into_owned());
+        proto.set_tty(record.tty.to_string_lossy().into_owned());
+        proto.set_host(record.host.to_string_lossy().into_owned());
+        match record.ip_addr {
+            Some(std::net::IpAddr::V4(ip_addr)) => proto.set_ip_address(ip_addr.into()),
+            Some(std::net::IpAddr::V6(ip_addr)) => proto.set_ip_address(ip_addr.into()),
+            None => (),
+        }
+        proto.set_pid(record.pid);
+        proto.set_time(rrg_proto::into_timestamp(record.time));
+
+        proto
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<crate::utmp::RecordType> for rrg_proto::get_login_history::RecordType {
+
+    fn from(r#type: crate::utmp::RecordType) -> rrg_proto::get_login_history::RecordType {
+        use crate::utmp::RecordType;
+        use rrg_proto::get_login_history::RecordType as Proto;
+
+        match r#type {
+            RecordType::RunLvl => Proto::RUN_LVL,
+            RecordType::BootTime => Proto::BOOT_TIME,
+            RecordType::NewTime => Proto::NEW_TIME,
+            RecordType::OldTime => Proto::OLD_TIME,
+            RecordType::InitProcess => Proto::INIT_PROCESS,
+            RecordType::LoginProcess => Proto::LOGIN_PROCESS,
+            RecordType::UserProcess => Proto::USER_PROCESS,
+            RecordType::DeadProcess => Proto::DEAD_PROCESS,
+            RecordType::Accounting => Proto::ACCOUNTING,
+            RecordType::Unknown => Proto::UNKNOWN,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn rotated_paths_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        for name in ["wtmp", "wtmp.1", "wtmp.2", "wtmp.10", "wtmp.2.gz", "btmp.1", "wtmpx"] {
+            std::fs::write(tempdir.path().join(name), b"")
+                .unwrap();
+        }
+
+        assert_eq!(rotated_paths(&tempdir.path().join("wtmp")), vec![
+            tempdir.path().join("wtmp.1"),
+            tempdir.path().join("wtmp.2"),
+            tempdir.path().join("wtmp.10"),
+        ]);
+    }
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args {
+            since: None,
+            max_records: None,
+            include_rotated: true,
+            layout: None,
+        }).is_ok());
+    }
+
+    #[test]
+    fn handle_max_records() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args {
+            since: None,
+            max_records: Some(1),
+            include_rotated: true,
+            layout: None,
+        }).is_ok());
+
+        assert!(session.reply_count() <= 1);
+    }
+}
@@ -0,0 +1,829 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the macOS unified log action.
+//!
+//! The unified log is stored in undocumented `tracev3` files that reference
+//! format strings stored in separate files (and in the shared cache), so
+//! instead of parsing them on our own we query the log through `log show`.
+
+#[cfg(target_os = "macos")]
+use rrg_proto::convert::FromLossy;
+
+/// Path to the `log` executable.
+#[cfg(target_os = "macos")]
+const LOG_PATH: &str = "/usr/bin/log";
+
+/// Maximum allowed length (in bytes) of the predicate.
+#[cfg(target_os = "macos")]
+const MAX_PREDICATE_LEN: usize = 4096;
+
+/// Arguments of the `get_macos_logs` action.
+#[cfg(target_os = "macos")]
+pub struct Args {
+    /// Time since which the entries should be collected.
+    start: Option<std::time::SystemTime>,
+    /// Time until which the entries should be collected.
+    end: Option<std::time::SystemTime>,
+    /// Predicate that the entries have to match.
+    predicate: Option<String>,
+    /// Maximum number of entries to collect.
+    max_entries: usize,
+}
+
+/// Result of the `get_macos_logs` action.
+#[cfg(target_os = "macos")]
+pub struct Item {
+    /// SHA-256 digest of the entry batch sent to the blob sink.
+    blob_sha256: [u8; 32],
+    /// Number of entries in the batch sent to the blob sink.
+    entry_count: usize,
+}
+
+/// Handles invocations of the `get_macos_logs` action.
+#[cfg(target_os = "macos")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut command = std::process::Command::new(LOG_PATH);
+    command
+        .arg("show")
+        .arg("--style").arg("ndjson")
+        // By default only messages of the default level and above are shown,
+        // we include everything and let the predicate decide.
+        .arg("--info")
+        .arg("--debug")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        // We do not read the standard error while processing the output and
+        // so piping it could block `log` if it reports a lot of errors.
+        .stderr(std::process::Stdio::null());
+
+    if let Some(start) = args.start {
+        command.arg("--start").arg(timespec(start));
+    }
+    if let Some(end) = args.end {
+        command.arg("--end").arg(timespec(round_up(end)));
+    }
+    // The command is executed directly and not through a shell, so the value
+    // is passed verbatim as a single argument (it is validated when parsing
+    // the arguments anyway).
+    if let Some(predicate) = &args.predicate {
+        command.arg("--predicate").arg(predicate);
+    }
+
+    let mut child = command.spawn()
+        .map_err(crate::session::Error::action)?;
+
+    let stdout = child.stdout.take()
+        .expect("no stdout pipe");
+
+    let result = send_entries(session, std::io::BufReader::new(stdout), args.max_entries);
+
+    let entry_count = match result {
+        Ok(entry_count) => entry_count,
+        Err(error) => {
+            if let Err(error) = child.kill() {
+                log::warn!("failed to kill `log`: {error}");
+            }
+            if let Err(error) = child.wait() {
+                log::warn!("failed to wait for `log`: {error}");
+            }
+
+            return Err(error);
+        }
+    };
+
+    // If we collected all the entries we wanted, `log` might still be trying
+    // to output more of them, so we stop it (and ignore its status).
+    if entry_count == args.max_entries {
+        if let Err(error) = child.kill() {
+            log::warn!("failed to kill `log`: {error}");
+        }
+        child.wait()
+            .map_err(crate::session::Error::action)?;
+
+        return Ok(());
+    }
+
+    let status = child.wait()
+        .map_err(crate::session::Error::action)?;
+    if !status.success() {
+        return Err(crate::session::Error::action(std::io::Error::other(format! {
+            "`log` failed ({status})",
+        })));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Sends batches of log entries printed as NDJSON to the given reader.
+///
+/// Returns the total number of entries sent.
+#[cfg(target_os = "macos")]
+fn send_entries<S, R>(
+    session: &mut S,
+    ndjson: R,
+    max_entries: usize,
+) -> crate::session::Result<usize>
+where
+    S: crate::session::Session,
+    R: std::io::BufRead,
+{
+    use sha2::Digest as _;
+
+    // See the filesystem timeline action for why `entry_count` has to be a
+    // cell. The same applies to `error`, which is set once the output becomes
+    // unreadable (and at which point the iterator stops).
+    let entry_count = std::cell::Cell::new(0);
+    let error = std::cell::Cell::new(None);
+
+    let entries = Reader::new(ndjson)
+        .map_while(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                error.set(Some(err));
+                None
+            }
+        })
+        .take(max_entries)
+        .inspect(|_| {
+            entry_count.set(entry_count.get() + 1);
+        })
+        .map(rrg_proto::get_macos_logs::Entry::from_lossy);
+
+    let mut total_entry_count = 0;
+
+    for batch in crate::gzchunked::encode(entries) {
+        let batch = batch
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(batch);
+        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            blob_sha256,
+            entry_count: entry_count.get(),
+        })?;
+
+        total_entry_count += entry_count.get();
+        entry_count.set(0);
+    }
+
+    if let Some(error) = error.take() {
+        return Err(crate::session::Error::action(error));
+    }
+
+    Ok(total_entry_count)
+}
+
+/// Individual entry of the unified log as printed by `log`.
+#[cfg(target_os = "macos")]
+struct Entry {
+    /// Line with the entry (without the trailing newline).
+    line: String,
+    /// Parsed JSON object of the entry.
+    object: json::Value,
+}
+
+#[cfg(target_os = "macos")]
+impl Entry {
+
+    /// Returns the string value of the given field of the entry.
+    fn get_str(&self, name: &str) -> Option<&str> {
+        self.object.get(name).and_then(json::Value::as_str)
+    }
+
+    /// Returns the integer value of the given field of the entry.
+    fn get_integer(&self, name: &str) -> Option<i128> {
+        self.object.get(name).and_then(json::Value::as_integer)
+    }
+}
+
+/// Iterator over log entries printed by `log` in the NDJSON style.
+#[cfg(target_os = "macos")]
+struct Reader<R> {
+    /// Underlying reader with the output.
+    inner: R,
+    /// Buffer for the line that is currently being read.
+    buf: Vec<u8>,
+}
+
+#[cfg(target_os = "macos")]
+impl<R: std::io::BufRead> Reader<R> {
+
+    /// Creates a new reader over the given output.
+    fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Reads the next entry if there is any.
+    fn read_entry(&mut self) -> std::io::Result<Option<Entry>> {
+        loop {
+            self.buf.clear();
+            if self.inner.read_until(b'\n', &mut self.buf)? == 0 {
+                return Ok(None);
+            }
+
+            let line = self.buf.trim_ascii();
+
+            // `log` can print informational lines (e.g. about filtering) that
+            // are not JSON objects, we just skip them.
+            if !line.starts_with(b"{") {
+                continue;
+            }
+
+            let object = json::parse(line)?;
+
+            // The output ends with an object with the number of entries (that
+            // we do not need). Any actual entry always has a timestamp.
+            if object.get("timestamp").is_none() {
+                continue;
+            }
+
+            let line = String::from_utf8(line.to_vec())
+                .map_err(std::io::Error::other)?;
+
+            return Ok(Some(Entry {
+                line,
+                object,
+            }));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl<R: std::io::BufRead> Iterator for Reader<R> {
+
+    type Item = std::io::Result<Entry>;
+
+    fn next(&mut self) -> Option<std::io::Result<Entry>> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Formats the given time as a `log` date specification.
+#[cfg(target_os = "macos")]
+fn timespec(time: std::time::SystemTime) -> String {
+    // `log` accepts only `YYYY-MM-DD HH:MM:SS` dates (optionally followed by
+    // the UTC offset), which is RFC 3339 with a different separator.
+    let rfc3339 = humantime::format_rfc3339_seconds(time).to_string();
+    let rfc3339 = rfc3339.trim_end_matches('Z');
+
+    match rfc3339.split_once('T') {
+        Some((date, time)) => format!("{date} {time}+0000"),
+        None => format!("{rfc3339}+0000"),
+    }
+}
+
+/// Rounds the given time up to the nearest full second.
+#[cfg(target_os = "macos")]
+fn round_up(time: std::time::SystemTime) -> std::time::SystemTime {
+    // Arguments are verified not to be before the epoch, so the fallback is
+    // never going to be used.
+    let since_epoch = time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    if since_epoch.subsec_nanos() == 0 {
+        time
+    } else {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(since_epoch.as_secs() + 1)
+    }
+}
+
+/// Parses a timestamp as printed by `log` into microseconds since the epoch.
+///
+/// The timestamps have the `YYYY-MM-DD HH:MM:SS.ffffff+HHMM` format.
+#[cfg(target_os = "macos")]
+fn parse_timestamp(string: &str) -> Option<i64> {
+    let (date, time) = string.split_once(' ')?;
+
+    let mut date = date.splitn(3, '-');
+    let year = date.next()?.parse::<i64>().ok()?;
+    let month = date.next()?.parse::<u32>().ok()?;
+    let day = date.next()?.parse::<u32>().ok()?;
+
+    let offset_index = time.rfind(['+', '-'])?;
+    let (time, offset) = time.split_at(offset_index);
+
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+
+    let mut time = time.splitn(3, ':');
+    let hour = time.next()?.parse::<i64>().ok()?;
+    let minute = time.next()?.parse::<i64>().ok()?;
+    let second = time.next()?.parse::<i64>().ok()?;
+
+    let micros = match fraction {
+        // We pad (or truncate) the fraction to exactly six digits.
+        Some(fraction) if fraction.bytes().all(|byte| byte.is_ascii_digit()) => {
+            let fraction = format!("{:0<6}", &fraction[..fraction.len().min(6)]);
+            fraction.parse::<i64>().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+
+    let offset_sign = if offset.starts_with('-') { -1 } else { 1 };
+    let offset = offset[1..].replace(':', "");
+    if offset.len() != 4 {
+        return None;
+    }
+    let offset_hours = offset[..2].parse::<i64>().ok()?;
+    let offset_minutes = offset[2..].parse::<i64>().ok()?;
+    let offset_secs = offset_sign * (offset_hours * 3600 + offset_minutes * 60);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second - offset_secs;
+
+    secs.checked_mul(1_000_000)?.checked_add(micros)
+}
+
+/// Converts a proleptic Gregorian date to the number of days since the epoch.
+///
+/// See the [original][1] description of the algorithm for more details.
+///
+/// [1]: https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+#[cfg(target_os = "macos")]
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// Validates that the given predicate can be safely passed to `log`.
+///
+/// The predicate is never interpreted by a shell, but we still reject values
+/// that `log` could misinterpret as options or that could mess up its argument
+/// parsing (and anything that is not printable in general).
+#[cfg(target_os = "macos")]
+fn validate_predicate(predicate: &str) -> Result<(), PredicateError> {
+    if predicate.len() > MAX_PREDICATE_LEN {
+        return Err(PredicateError::TooLong(predicate.len()));
+    }
+    if predicate.trim_start().starts_with('-') {
+        return Err(PredicateError::Option);
+    }
+    if let Some(char) = predicate.chars().find(|char| char.is_control()) {
+        return Err(PredicateError::InvalidChar(char));
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_macos_logs::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let start = if proto.has_start() {
+            let start = rrg_proto::try_from_timestamp(proto.take_start())
+                .map_err(|error| ParseArgsError::invalid_field("start", error))?;
+            Some(start)
+        } else {
+            None
+        };
+
+        let end = if proto.has_end() {
+            let end = rrg_proto::try_from_timestamp(proto.take_end())
+                .map_err(|error| ParseArgsError::invalid_field("end", error))?;
+            Some(end)
+        } else {
+            None
+        };
+
+        let predicate = match proto.take_predicate() {
+            predicate if predicate.is_empty() => None,
+            predicate => {
+                validate_predicate(&predicate)
+                    .map_err(|error| ParseArgsError::invalid_field("predicate", error))?;
+                Some(predicate)
+            }
+        };
+
+        let max_entries = match proto.max_entries() {
+            0 => usize::MAX,
+            max_entries => usize::try_from(max_entries).unwrap_or(usize::MAX),
+        };
+
+        Ok(Args {
+            start,
+            end,
+            predicate,
+            max_entries,
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_macos_logs::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_blob_sha256(self.blob_sha256.into());
+        proto.set_entry_count(self.entry_count as u64);
+
+        proto
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl FromLossy<Entry> for rrg_proto::get_macos_logs::Entry {
+
+    fn from_lossy(entry: Entry) -> Self {
+        let mut proto = Self::default();
+
+        if let Some(micros) = entry.get_str("timestamp").and_then(parse_timestamp) {
+            proto.set_timestamp_micros(micros);
+        }
+
+        let process_id = entry.get_integer("processID")
+            .and_then(|pid| u32::try_from(pid).ok());
+        if let Some(process_id) = process_id {
+            proto.set_process_id(process_id);
+        }
+        if let Some(path) = entry.get_str("processImagePath") {
+            proto.set_process_image_path(String::from(path));
+        }
+
+        let thread_id = entry.get_integer("threadID")
+            .and_then(|tid| u64::try_from(tid).ok());
+        if let Some(thread_id) = thread_id {
+            proto.set_thread_id(thread_id);
+        }
+        if let Some(path) = entry.get_str("senderImagePath") {
+            proto.set_sender_image_path(String::from(path));
+        }
+
+        // Entries that are not logged to any subsystem have empty strings here
+        // rather than no values at all.
+        if let Some(subsystem) = entry.get_str("subsystem").filter(|s| !s.is_empty()) {
+            proto.set_subsystem(String::from(subsystem));
+        }
+        if let Some(category) = entry.get_str("category").filter(|c| !c.is_empty()) {
+            proto.set_category(String::from(category));
+        }
+
+        if let Some(event_type) = entry.get_str("eventType") {
+            proto.set_event_type(String::from(event_type));
+        }
+        if let Some(message_type) = entry.get_str("messageType") {
+            proto.set_message_type(String::from(message_type));
+        }
+        if let Some(message) = entry.get_str("eventMessage") {
+            proto.set_event_message(String::from(message));
+        }
+
+        proto.set_json(entry.line);
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an invalid predicate.
+#[cfg(target_os = "macos")]
+#[derive(Debug)]
+enum PredicateError {
+    /// The predicate is longer than allowed (the value is its length).
+    TooLong(usize),
+    /// The predicate looks like a command-line option.
+    Option,
+    /// The predicate contains a disallowed (e.g. control) character.
+    InvalidChar(char),
+}
+
+#[cfg(target_os = "macos")]
+impl std::fmt::Display for PredicateError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use PredicateError::*;
+
+        match self {
+            TooLong(len) => write! {
+                fmt,
+                "predicate too long ({len} bytes, at most {MAX_PREDICATE_LEN} allowed)"
+            },
+            Option => write!(fmt, "predicate starts with '-'"),
+            InvalidChar(char) => write!(fmt, "predicate contains invalid character {char:?}"),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl std::error::Error for PredicateError {
+}
+
+#[cfg(target_os = "macos")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_os_log_marker() {
+        use std::hash::BuildHasher as _;
+
+        let marker = format! {
+            "rrg-test-marker-{:016x}",
+            std::collections::hash_map::RandomState::new().hash_one(()),
+        };
+
+        let start = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        os_log(&marker);
+
+        // The entry might not be immediately visible to `log`, so we retry for
+        // a while before giving up.
+        for _ in 0..10 {
+            let args = Args {
+                start: Some(start),
+                end: None,
+                predicate: Some(format!("eventMessage CONTAINS \"{marker}\"")),
+                max_entries: 1,
+            };
+
+            let mut session = crate::session::FakeSession::new();
+            handle(&mut session, args)
+                .unwrap();
+
+            let entries = entries(&session);
+            if let Some(entry) = entries.first() {
+                assert_eq!(entry.event_message(), marker);
+                assert_eq!(entry.process_id(), std::process::id());
+                assert!(entry.timestamp_micros() > 0);
+                return;
+            }
+
+            std::thread::sleep(std::time::Duration::from_secs(1));
+        }
+
+        panic!("marker not found in the unified log");
+    }
+
+    #[test]
+    fn handle_max_entries() {
+        let args = Args {
+            start: Some(std::time::SystemTime::now() - std::time::Duration::from_secs(3600)),
+            end: None,
+            predicate: None,
+            max_entries: 3,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert!(entries(&session).len() <= 3);
+    }
+
+    #[test]
+    fn send_entries_ndjson() {
+        let ndjson = br#"{"timestamp":"2024-01-01 01:00:00.123456+0100","processID":42,"processImagePath":"\/usr\/libexec\/foo","threadID":1337,"senderImagePath":"\/usr\/lib\/libfoo.dylib","subsystem":"com.example.foo","category":"network","eventType":"logEvent","messageType":"Error","eventMessage":"connection failed: z\u0105b"}
+{"timestamp":"2024-01-01 00:00:01-0000","processID":1,"subsystem":"","category":"","eventType":"activityCreateEvent","eventMessage":"bar"}
+{"count":2,"finished":1}
+"#;
+
+        let mut session = crate::session::FakeSession::new();
+        let entry_count = send_entries(&mut session, &ndjson[..], usize::MAX)
+            .unwrap();
+        assert_eq!(entry_count, 2);
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].timestamp_micros(), 1_704_067_200_123_456);
+        assert_eq!(entries[0].process_id(), 42);
+        assert_eq!(entries[0].process_image_path(), "/usr/libexec/foo");
+        assert_eq!(entries[0].thread_id(), 1337);
+        assert_eq!(entries[0].sender_image_path(), "/usr/lib/libfoo.dylib");
+        assert_eq!(entries[0].subsystem(), "com.example.foo");
+        assert_eq!(entries[0].category(), "network");
+        assert_eq!(entries[0].event_type(), "logEvent");
+        assert_eq!(entries[0].message_type(), "Error");
+        assert_eq!(entries[0].event_message(), "connection failed: ząb");
+        assert!(entries[0].json().starts_with(r#"{"timestamp":"2024-01-01 01:00:00.123456+0100""#));
+
+        assert_eq!(entries[1].timestamp_micros(), 1_704_067_201_000_000);
+        assert!(!entries[1].has_subsystem());
+        assert!(!entries[1].has_category());
+        assert!(!entries[1].has_message_type());
+        assert_eq!(entries[1].event_message(), "bar");
+    }
+
+    #[test]
+    fn send_entries_max_entries() {
+        let ndjson = br#"{"timestamp":"2024-01-01 00:00:00+0000","eventMessage":"foo"}
+{"timestamp":"2024-01-01 00:00:01+0000","eventMessage":"bar"}
+{"timestamp":"2024-01-01 00:00:02+0000","eventMessage":"baz"}
+"#;
+
+        let mut session = crate::session::FakeSession::new();
+        let entry_count = send_entries(&mut session, &ndjson[..], 2)
+            .unwrap();
+        assert_eq!(entry_count, 2);
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].event_message(), "foo");
+        assert_eq!(entries[1].event_message(), "bar");
+    }
+
+    #[test]
+    fn send_entries_skips_non_objects() {
+        let ndjson = b"Filtering the log data using \"eventMessage CONTAINS \\\"foo\\\"\"\n\
+{\"timestamp\":\"2024-01-01 00:00:00+0000\",\"eventMessage\":\"foo\"}\n\
+\n";
+
+        let mut session = crate::session::FakeSession::new();
+        let entry_count = send_entries(&mut session, &ndjson[..], usize::MAX)
+            .unwrap();
+        assert_eq!(entry_count, 1);
+    }
+
+    #[test]
+    fn send_entries_invalid_json() {
+        let ndjson = br#"{"timestamp":"2024-01-01 00:00:00+0000","eventMessage":"foo"}
+{"timestamp":"2024-01-01 00:00:01+0000","eventMess
+"#;
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(send_entries(&mut session, &ndjson[..], usize::MAX).is_err());
+    }
+
+    #[test]
+    fn timespec_utc() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200);
+        assert_eq!(timespec(time), "2024-01-01 00:00:00+0000");
+    }
+
+    #[test]
+    fn round_up_fraction() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_500);
+        assert_eq!(round_up(time), std::time::UNIX_EPOCH + std::time::Duration::from_secs(2));
+
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1);
+        assert_eq!(round_up(time), time);
+    }
+
+    #[test]
+    fn parse_timestamp_offsets() {
+        assert_eq!(parse_timestamp("1970-01-01 00:00:00.000000+0000"), Some(0));
+        assert_eq!(parse_timestamp("1970-01-01 01:00:00.000001+0100"), Some(1));
+        assert_eq!(parse_timestamp("1969-12-31 19:00:00-0500"), Some(0));
+        assert_eq!(parse_timestamp("2024-02-29 12:00:00.5+00:00"), Some(1_709_208_000_500_000));
+    }
+
+    #[test]
+    fn parse_timestamp_invalid() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("2024-01-01"), None);
+        assert_eq!(parse_timestamp("2024-13-01 00:00:00+0000"), None);
+        assert_eq!(parse_timestamp("2024-01-01 00:00:00.x+0000"), None);
+        assert_eq!(parse_timestamp("2024-01-01 00:00:00+01"), None);
+    }
+
+    #[test]
+    fn args_predicate_ok() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_macos_logs::Args::new();
+        proto.set_predicate(String::from("process == \"sshd\" AND eventMessage CONTAINS[c] \"failed; rm -rf /\""));
+
+        let args = Args::from_proto(proto).unwrap();
+        assert!(args.predicate.is_some());
+    }
+
+    #[test]
+    fn args_predicate_option() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_macos_logs::Args::new();
+        proto.set_predicate(String::from("--archive=/tmp/foo.logarchive"));
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn args_predicate_control_char() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_macos_logs::Args::new();
+        proto.set_predicate(String::from("process == \"foo\"\n--debug"));
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn args_predicate_too_long() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_macos_logs::Args::new();
+        proto.set_predicate("a".repeat(MAX_PREDICATE_LEN + 1));
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    /// Logs the given message to the unified log using `os_log`.
+    fn os_log(message: &str) {
+        // `os_log` is a macro that serializes the arguments into a buffer and
+        // calls the function below. The buffer starts with a summary byte and
+        // the number of arguments, followed by the arguments (each with its
+        // own descriptor byte and size).
+        extern "C" {
+            static __dso_handle: std::ffi::c_void;
+
+            fn _os_log_impl(
+                dso: *const std::ffi::c_void,
+                log: libc::os_log_t,
+                r#type: libc::os_log_type_t,
+                format: *const std::ffi::c_char,
+                buf: *const u8,
+                size: u32,
+            );
+        }
+
+        /// Summary flag indicating that there are non-scalar arguments.
+        const HAS_NON_SCALAR: u8 = 0x02;
+        /// Descriptor of a public string argument.
+        const PUBLIC_STRING: u8 = 0x22;
+
+        // The format string needs to be a part of the binary for `log` to be
+        // able to resolve it later.
+        static FORMAT: &std::ffi::CStr = c"%{public}s";
+
+        let message = std::ffi::CString::new(message)
+            .unwrap();
+
+        let mut buf = vec![HAS_NON_SCALAR, 1, PUBLIC_STRING, 8];
+        buf.extend_from_slice(&(message.as_ptr() as u64).to_ne_bytes());
+
+        // SAFETY: Subsystem and category names are valid C strings, so is the
+        // format string (which is a part of our binary) and the buffer
+        // describes the format arguments as expected by it.
+        unsafe {
+            let log = libc::os_log_create(c"com.google.rrg".as_ptr(), c"test".as_ptr());
+            _os_log_impl(
+                &__dso_handle,
+                log,
+                libc::OS_LOG_TYPE_DEFAULT,
+                FORMAT.as_ptr(),
+                buf.as_ptr(),
+                buf.len() as u32,
+            );
+        }
+    }
+
+    /// Retrieves all entries sent to the blob sink in the given session.
+    fn entries(
+        session: &crate::session::FakeSession,
+    ) -> Vec<rrg_proto::get_macos_logs::Entry> {
+        let blob_count = session.parcel_count(crate::Sink::Blob);
+        let reply_count = session.reply_count();
+        assert_eq!(blob_count, reply_count);
+
+        let chunks = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(crate::blob::Blob::as_bytes);
+
+        let entries = crate::gzchunked::decode(chunks)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let total_entry_count = session.replies::<Item>()
+            .map(|item| item.entry_count)
+            .sum();
+
+        assert_eq!(entries.len(), total_entry_count);
+
+        entries
+    }
+}
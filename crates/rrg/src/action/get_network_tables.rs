@@ -0,0 +1,179 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+
+/// A result of the `get_network_tables` action.
+enum Item {
+    /// Entry of the neighbor cache.
+    Neighbor {
+        /// Information about the entry.
+        neighbor: ospect::net::Neighbor,
+        /// Name of the interface the entry belongs to (if known).
+        iface_name: Option<OsString>,
+    },
+    /// Entry of the routing table.
+    Route {
+        /// Information about the entry.
+        route: ospect::net::Route,
+        /// Name of the outgoing interface of the route (if known).
+        iface_name: Option<OsString>,
+    },
+}
+
+/// Handles invocations of the `get_network_tables` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    // Interface names are only an addition to the tables, so we do not fail
+    // if they cannot be collected.
+    let iface_names = match ospect::net::interfaces() {
+        Ok(ifaces) => ifaces
+            .filter_map(|iface| Some((iface.index()?, iface.name().to_os_string())))
+            .collect::<HashMap<_, _>>(),
+        Err(error) => {
+            log::warn!("failed to collect interface names: {error}");
+            HashMap::new()
+        }
+    };
+
+    let neighbors = ospect::net::neighbors()
+        .map_err(crate::session::Error::action)?;
+
+    for neighbor in neighbors {
+        let neighbor = match neighbor {
+            Ok(neighbor) => neighbor,
+            Err(error) => {
+                log::warn!("failed to parse neighbor entry: {error}");
+                continue;
+            }
+        };
+
+        session.reply(Item::Neighbor {
+            iface_name: iface_names.get(&neighbor.iface_index()).cloned(),
+            neighbor,
+        })?;
+    }
+
+    let routes = ospect::net::routes()
+        .map_err(crate::session::Error::action)?;
+
+    for route in routes {
+        let route = match route {
+            Ok(route) => route,
+            Err(error) => {
+                log::warn!("failed to parse route entry: {error}");
+                continue;
+            }
+        };
+
+        session.reply(Item::Route {
+            iface_name: route.iface_index()
+                .and_then(|iface_index| iface_names.get(&iface_index))
+                .cloned(),
+            route,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_network_tables::Result;
+
+    fn into_proto(self) -> rrg_proto::get_network_tables::Result {
+        let mut proto = rrg_proto::get_network_tables::Result::new();
+
+        match self {
+            Item::Neighbor { neighbor, iface_name } => {
+                let neighbor_proto = proto.mut_neighbor();
+                neighbor_proto.set_ip_address(neighbor.ip_addr().into());
+                if let Some(mac_addr) = neighbor.mac_addr() {
+                    neighbor_proto.set_mac_address((*mac_addr).into());
+                }
+                neighbor_proto.set_interface_index(neighbor.iface_index());
+                if let Some(iface_name) = iface_name {
+                    neighbor_proto.set_interface_name(iface_name.to_string_lossy().into_owned());
+                }
+                neighbor_proto.set_state(neighbor.state().into());
+            }
+            Item::Route { route, iface_name } => {
+                let route_proto = proto.mut_route();
+                route_proto.set_destination(route.dest_addr().into());
+                route_proto.set_destination_prefix_len(u32::from(route.dest_prefix_len()));
+                if let Some(gateway) = route.gateway() {
+                    route_proto.set_gateway(gateway.into());
+                }
+                if let Some(iface_index) = route.iface_index() {
+                    route_proto.set_interface_index(iface_index);
+                }
+                if let Some(iface_name) = iface_name {
+                    route_proto.set_interface_name(iface_name.to_string_lossy().into_owned());
+                }
+                if let Some(metric) = route.metric() {
+                    route_proto.set_metric(metric);
+                }
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        // Every system has at least a route to the loopback address (although
+        // on Linux it lives in the local table that is not reported).
+        #[cfg(not(target_os = "linux"))]
+        assert! {
+            session.replies::<Item>().any(|item| match item {
+                Item::Route { route, .. } => route.dest_addr().is_loopback(),
+                Item::Neighbor { .. } => false,
+            })
+        };
+
+        for item in session.replies::<Item>() {
+            if let Item::Route { route, .. } = item {
+                assert!(route.dest_prefix_len() <= 128);
+            }
+        }
+    }
+
+    #[test]
+    fn into_proto_route_without_gateway() {
+        use crate::response::Item as _;
+
+        let route = ospect::net::routes()
+            .unwrap()
+            .filter_map(Result::ok)
+            .find(|route| route.gateway().is_none());
+
+        // The system might have no directly attached networks (the loopback
+        // interface is not reported everywhere).
+        let Some(route) = route else {
+            return;
+        };
+
+        let proto = Item::Route {
+            route,
+            iface_name: Some(OsString::from("foo0")),
+        }.into_proto();
+
+        assert!(proto.has_route());
+        assert!(!proto.route().has_gateway());
+        assert_eq!(proto.route().interface_name(), "foo0");
+    }
+}
@@ -0,0 +1,445 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+// Raw volumes can be opened only on Windows but the export logic itself works
+// with any seekable reader and so it is available (and tested) everywhere.
+#![cfg_attr(not(target_family = "windows"), allow(dead_code))]
+
+use crate::ntfs;
+
+/// Limit on the size of individual MFT part blob sent to the blob sink.
+const MAX_BLOB_LEN: u64 = 2 * 1024 * 1024; // 2 MiB.
+
+/// Number of bytes read from the beginning of the volume to get the boot sector.
+///
+/// Reads from raw volumes have to be aligned to the sector size, which we do
+/// not know before parsing the boot sector, so we read as much as the biggest
+/// supported sector.
+const BOOT_SECTOR_READ_LEN: usize = 4096;
+
+/// Arguments of the `get_ntfs_mft` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Drive letter of the volume to export the MFT of.
+    volume: char,
+}
+
+/// Result of the `get_ntfs_mft` action.
+struct Item {
+    /// Byte offset of the MFT part sent to the blob sink.
+    offset: u64,
+    /// Number of bytes of the MFT part sent to the blob sink.
+    len: u64,
+    /// SHA-256 digest of the MFT part sent to the blob sink.
+    blob_sha256: [u8; 32],
+}
+
+/// Handles invocations of the `get_ntfs_mft` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::os::windows::fs::OpenOptionsExt as _;
+    use windows_sys::Win32::Storage::FileSystem::*;
+    use crate::os::windows::privileges::{with_privilege, SE_BACKUP_NAME};
+
+    let path = format!("\\\\.\\{}:", args.volume);
+
+    // The volume is mounted and used by the system, so we have to allow others
+    // to keep reading and writing it.
+    let open = || {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(&path)
+    };
+
+    let volume = with_privilege(SE_BACKUP_NAME, open)
+        .map_err(crate::session::Error::action)?
+        .map_err(|error| crate::session::Error::action(OpenError {
+            path,
+            error,
+        }))?;
+
+    export(session, volume)
+}
+
+/// Handles invocations of the `get_ntfs_mft` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Exports the MFT of the NTFS volume readable through the given reader.
+fn export<S, R>(session: &mut S, mut volume: R) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+    R: std::io::Read + std::io::Seek,
+{
+    use sha2::Digest as _;
+
+    let boot_sector_buf = read_at(&mut volume, 0, BOOT_SECTOR_READ_LEN)
+        .map_err(crate::session::Error::action)?;
+    let boot_sector = ntfs::BootSector::parse(&boot_sector_buf)
+        .map_err(crate::session::Error::action)?;
+
+    log::info! {
+        "exporting MFT at cluster {} (cluster size: {}, record size: {})",
+        boot_sector.mft_lcn,
+        boot_sector.bytes_per_cluster,
+        boot_sector.bytes_per_mft_record,
+    };
+
+    // The first record of the MFT describes the MFT itself. Note that records
+    // can be smaller than sectors but we still need to read whole sectors.
+    let mft_offset = boot_sector.mft_lcn * boot_sector.bytes_per_cluster;
+    let record_read_len = std::cmp::max(
+        boot_sector.bytes_per_mft_record,
+        boot_sector.bytes_per_sector,
+    );
+
+    let mut record = read_at(&mut volume, mft_offset, record_read_len as usize)
+        .map_err(crate::session::Error::action)?;
+    record.truncate(boot_sector.bytes_per_mft_record as usize);
+
+    ntfs::apply_fixups(&mut record)
+        .map_err(crate::session::Error::action)?;
+    let data = ntfs::parse_data_attribute(&record)
+        .map_err(crate::session::Error::action)?;
+
+    let runs_len = data.runs.iter()
+        .map(|run| run.len * boot_sector.bytes_per_cluster)
+        .sum::<u64>();
+    if runs_len < data.size {
+        // This can happen if the MFT is so fragmented that its runs do not fit
+        // in a single record and are continued through an attribute list. We
+        // still export the part that we know of.
+        log::warn! {
+            "MFT runs cover only {runs_len} out of {} bytes",
+            data.size,
+        };
+    }
+
+    let mut offset = 0;
+
+    for run in data.runs {
+        if offset >= data.size {
+            break;
+        }
+
+        let lcn = run.lcn
+            .ok_or_else(|| crate::session::Error::action(SparseRunError))?;
+
+        let run_offset = lcn * boot_sector.bytes_per_cluster;
+        let run_len = run.len * boot_sector.bytes_per_cluster;
+
+        let mut run_pos = 0;
+        while run_pos < run_len && offset < data.size {
+            session.check_limits()?;
+
+            // We read whole clusters to keep the reads aligned and only then
+            // cut the part of the last cluster that is past the MFT size.
+            let read_len = std::cmp::min(MAX_BLOB_LEN, run_len - run_pos);
+            let mut buf = read_at(&mut volume, run_offset + run_pos, read_len as usize)
+                .map_err(crate::session::Error::action)?;
+            run_pos += read_len;
+
+            let len = std::cmp::min(read_len, data.size - offset);
+            buf.truncate(len as usize);
+
+            let blob = crate::blob::Blob::from(buf);
+            let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+
+            session.send(crate::Sink::Blob, blob)?;
+            session.reply(Item {
+                offset,
+                len,
+                blob_sha256,
+            })?;
+
+            offset += len;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads exactly `len` bytes at the given offset of the given reader.
+fn read_at<R>(reader: &mut R, offset: u64, len: usize) -> std::io::Result<Vec<u8>>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    reader.seek(std::io::SeekFrom::Start(offset))?;
+
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_ntfs_mft::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let volume = proto.volume();
+        let volume = volume.strip_suffix(':').unwrap_or(volume);
+
+        let volume = match volume.as_bytes() {
+            [] => 'C',
+            [letter] if letter.is_ascii_alphabetic() => {
+                char::from(letter.to_ascii_uppercase())
+            }
+            _ => {
+                return Err(ParseArgsError::invalid_field("volume", VolumeError {
+                    volume: proto.volume().to_string(),
+                }));
+            }
+        };
+
+        Ok(Args {
+            volume,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_ntfs_mft::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_offset(self.offset);
+        proto.set_length(self.len);
+        proto.set_blob_sha256(self.blob_sha256.into());
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an invalid volume.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct VolumeError {
+    volume: String,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for VolumeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "invalid volume drive letter: {:?}", self.volume)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for VolumeError {
+}
+
+/// An error indicating that opening the raw volume failed.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct OpenError {
+    /// Path to the volume that was opened.
+    path: String,
+    /// Underlying error returned by the system.
+    error: std::io::Error,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for OpenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Raw volumes can be opened only with elevated privileges, so we make
+        // it explicit in such cases.
+        if self.error.kind() == std::io::ErrorKind::PermissionDenied {
+            write! {
+                fmt,
+                "access to volume '{}' denied (reading it requires \
+                 administrator privileges): {}",
+                self.path, self.error
+            }
+        } else {
+            write!(fmt, "failed to open volume '{}': {}", self.path, self.error)
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for OpenError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error indicating that the MFT has sparse runs.
+///
+/// The MFT is never sparse on a consistent volume.
+#[derive(Debug)]
+struct SparseRunError;
+
+impl std::fmt::Display for SparseRunError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unexpected sparse run in MFT data")
+    }
+}
+
+impl std::error::Error for SparseRunError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Size of clusters of the fixture volumes.
+    const CLUSTER_SIZE: usize = 4096;
+
+    /// Builds a fixture volume with the MFT data stored in the given runs.
+    ///
+    /// The MFT starts at cluster 4 and `runs` is the encoded data run list of
+    /// the MFT (which should start there as well). Clusters are filled with
+    /// their index so that it is easy to verify what was exported.
+    fn volume(runs: &[u8], mft_size: u64, cluster_count: usize) -> std::io::Cursor<Vec<u8>> {
+        let mut image = (0..cluster_count)
+            .flat_map(|cluster| std::iter::repeat_n(cluster as u8, CLUSTER_SIZE))
+            .collect::<Vec<u8>>();
+
+        let boot_sector = ntfs::tests::boot_sector(512, 8, 4, -10);
+        image[..CLUSTER_SIZE].fill(0);
+        image[..boot_sector.len()].copy_from_slice(&boot_sector);
+
+        let record = ntfs::tests::mft_record(runs, mft_size);
+        let mft_offset = 4 * CLUSTER_SIZE;
+        image[mft_offset..mft_offset + record.len()].copy_from_slice(&record);
+
+        std::io::Cursor::new(image)
+    }
+
+    #[test]
+    fn export_single_run() {
+        use sha2::Digest as _;
+
+        // Two clusters at cluster 4.
+        let volume = volume(&[0x11, 0x02, 0x04, 0x00], 8192, 8);
+
+        let mut session = crate::session::FakeSession::new();
+        export(&mut session, volume.clone())
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 0);
+        assert_eq!(item.len, 8192);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), &volume.get_ref()[4 * CLUSTER_SIZE..6 * CLUSTER_SIZE]);
+
+        let blob_sha256 = <[u8; 32]>::from(sha2::Sha256::digest(blob.as_bytes()));
+        assert_eq!(item.blob_sha256, blob_sha256);
+    }
+
+    #[test]
+    fn export_multiple_runs() {
+        let volume = volume(&[
+            // Two clusters at cluster 4.
+            0x11, 0x02, 0x04,
+            // One cluster at cluster 10 (truncated to the MFT size).
+            0x11, 0x01, 0x06,
+            0x00,
+        ], 8192 + 1000, 12);
+
+        let mut session = crate::session::FakeSession::new();
+        export(&mut session, volume)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 2);
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.offset, 8192);
+        assert_eq!(item.len, 1000);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 1);
+        assert_eq!(blob.as_bytes(), &[10; 1000][..]);
+    }
+
+    #[test]
+    fn export_multiple_chunks() {
+        let cluster_count = 2 * MAX_BLOB_LEN as usize / CLUSTER_SIZE;
+        let mft_size = (cluster_count * CLUSTER_SIZE) as u64;
+
+        let volume = volume(&[
+            0x12,
+            cluster_count as u8, (cluster_count >> 8) as u8,
+            0x04,
+            0x00,
+        ], mft_size, 4 + cluster_count);
+
+        let mut session = crate::session::FakeSession::new();
+        export(&mut session, volume)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+
+        let item_1 = session.reply::<Item>(0);
+        assert_eq!(item_1.offset, 0);
+        assert_eq!(item_1.len, MAX_BLOB_LEN);
+
+        let item_2 = session.reply::<Item>(1);
+        assert_eq!(item_2.offset, MAX_BLOB_LEN);
+        assert_eq!(item_2.len, MAX_BLOB_LEN);
+    }
+
+    #[test]
+    fn export_sparse_run() {
+        let volume = volume(&[
+            0x11, 0x02, 0x04,
+            0x01, 0x02,
+            0x00,
+        ], 4 * CLUSTER_SIZE as u64, 8);
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(export(&mut session, volume).is_err());
+    }
+
+    #[test]
+    fn export_not_ntfs() {
+        let volume = std::io::Cursor::new(vec![0; 8 * CLUSTER_SIZE]);
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(export(&mut session, volume).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn args_volume() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_ntfs_mft::Args::new();
+        assert_eq!(Args::from_proto(proto.clone()).unwrap().volume, 'C');
+
+        proto.set_volume(String::from("d:"));
+        assert_eq!(Args::from_proto(proto.clone()).unwrap().volume, 'D');
+
+        proto.set_volume(String::from("DE"));
+        assert!(Args::from_proto(proto.clone()).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the procfs snapshot action.
+
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+
+/// Paths to the procfs files collected if not specified otherwise.
+#[cfg(target_os = "linux")]
+const DEFAULT_PATHS: &[&str] = &[
+    "/proc/modules",
+    "/proc/mounts",
+    "/proc/net/tcp",
+    "/proc/net/tcp6",
+    "/proc/sys/kernel/tainted",
+];
+
+/// Names of per-process files collected for each of the requested processes.
+#[cfg(target_os = "linux")]
+const PROCESS_FILE_NAMES: &[&str] = &[
+    "cmdline",
+    "status",
+];
+
+/// Maximum number of bytes collected from a single file if not specified.
+#[cfg(target_os = "linux")]
+const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024; // 1 MiB.
+
+/// Arguments of the `get_proc_snapshot` action.
+#[cfg(target_os = "linux")]
+pub struct Args {
+    /// Paths to the procfs files to collect.
+    paths: Vec<PathBuf>,
+    /// Maximum number of bytes to collect from a single file.
+    max_file_size: u64,
+}
+
+/// Result of the `get_proc_snapshot` action.
+#[cfg(target_os = "linux")]
+struct Item {
+    /// Manifest of all the files in the bundle.
+    entries: Vec<ManifestEntry>,
+    /// SHA-256 digests of the bundle parts sent to the blob sink.
+    blob_sha256s: Vec<[u8; 32]>,
+}
+
+/// Description of a single file in the bundle.
+#[cfg(target_os = "linux")]
+struct ManifestEntry {
+    /// Path to the collected file.
+    path: PathBuf,
+    /// Collected contents of the file (or an error if it could not be read).
+    contents: std::io::Result<Contents>,
+}
+
+/// Collected contents of a single file.
+#[cfg(target_os = "linux")]
+struct Contents {
+    /// Number of bytes of the file in the bundle.
+    len: u64,
+    /// SHA-256 digest of the file contents in the bundle.
+    sha256: [u8; 32],
+    /// Whether the contents were truncated.
+    truncated: bool,
+}
+
+/// Handles invocations of the `get_proc_snapshot` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use sha2::Digest as _;
+
+    let mut entries = Vec::new();
+    let mut files = Vec::new();
+
+    for path in args.paths {
+        session.check_limits()?;
+
+        let (data, truncated) = match read(&path, args.max_file_size) {
+            Ok(result) => result,
+            Err(error) => {
+                log::warn!("failed to read '{}': {error}", path.display());
+                entries.push(ManifestEntry {
+                    path,
+                    contents: Err(error),
+                });
+                continue;
+            }
+        };
+
+        entries.push(ManifestEntry {
+            path: path.clone(),
+            contents: Ok(Contents {
+                len: data.len() as u64,
+                sha256: sha2::Sha256::digest(&data).into(),
+                truncated,
+            }),
+        });
+
+        let mut file = rrg_proto::get_proc_snapshot::File::new();
+        file.set_path(crate::path::into_proto(path));
+        file.set_contents(data);
+        files.push(file);
+    }
+
+    let mut blob_sha256s = Vec::new();
+
+    for batch in crate::gzchunked::encode(files.into_iter()) {
+        let batch = batch
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(batch);
+        blob_sha256s.push(sha2::Sha256::digest(blob.as_bytes()).into());
+
+        session.send(crate::Sink::Blob, blob)?;
+    }
+
+    session.reply(Item {
+        entries,
+        blob_sha256s,
+    })?;
+
+    Ok(())
+}
+
+/// Handles invocations of the `get_proc_snapshot` action.
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Reads up to `max_len` bytes of the file at the given path.
+///
+/// Files in procfs report their size as zero (their contents are generated on
+/// the fly), so we read them until the end instead of relying on metadata. The
+/// returned flag indicates whether the file was longer than `max_len`.
+#[cfg(target_os = "linux")]
+fn read(path: &std::path::Path, max_len: u64) -> std::io::Result<(Vec<u8>, bool)> {
+    use std::io::Read as _;
+
+    let file = std::fs::File::open(path)?;
+
+    // We read one byte more than allowed to know whether there is more.
+    let mut buf = Vec::new();
+    file.take(max_len.saturating_add(1)).read_to_end(&mut buf)?;
+
+    let truncated = buf.len() as u64 > max_len;
+    buf.truncate(max_len as usize);
+
+    Ok((buf, truncated))
+}
+
+#[cfg(target_os = "linux")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_proc_snapshot::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let mut paths = Vec::new();
+
+        for path in proto.take_paths() {
+            let path = crate::path::from_proto(path)
+                .map_err(|error| ParseArgsError::invalid_field("paths", error))?;
+
+            if !is_proc_path(&path) {
+                return Err(ParseArgsError::invalid_field("paths", ProcPathError {
+                    path,
+                }));
+            }
+
+            paths.push(path);
+        }
+
+        for pid in proto.pids() {
+            for name in PROCESS_FILE_NAMES {
+                paths.push(PathBuf::from(format!("/proc/{pid}/{name}")));
+            }
+        }
+
+        if paths.is_empty() {
+            paths.extend(DEFAULT_PATHS.iter().map(PathBuf::from));
+        }
+
+        let max_file_size = match proto.max_file_size() {
+            0 => DEFAULT_MAX_FILE_SIZE,
+            max_file_size => max_file_size,
+        };
+
+        Ok(Args {
+            paths,
+            max_file_size,
+        })
+    }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        self.paths.iter()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+/// Checks whether the given path is an absolute path within `/proc`.
+///
+/// Parent directory components are not allowed, so that the path cannot
+/// lexically escape `/proc`.
+#[cfg(target_os = "linux")]
+fn is_proc_path(path: &std::path::Path) -> bool {
+    use std::path::Component;
+
+    let mut components = path.components();
+    if components.next() != Some(Component::RootDir) {
+        return false;
+    }
+    if components.next() != Some(Component::Normal("proc".as_ref())) {
+        return false;
+    }
+
+    components.all(|component| component != Component::ParentDir)
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_proc_snapshot::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+
+        for entry in self.entries {
+            let mut entry_proto = rrg_proto::get_proc_snapshot::ManifestEntry::new();
+            entry_proto.set_path(crate::path::into_proto(entry.path));
+
+            match entry.contents {
+                Ok(contents) => {
+                    entry_proto.set_length(contents.len);
+                    entry_proto.set_sha256(contents.sha256.to_vec());
+                    entry_proto.set_truncated(contents.truncated);
+                }
+                Err(error) => {
+                    entry_proto.set_error(error.to_string());
+                }
+            }
+
+            proto.mut_entries().push(entry_proto);
+        }
+
+        for blob_sha256 in self.blob_sha256s {
+            proto.mut_blob_sha256s().push(blob_sha256.to_vec());
+        }
+
+        proto
+    }
+}
+
+/// An error indicating that a requested path is not within `/proc`.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct ProcPathError {
+    path: PathBuf,
+}
+
+#[cfg(target_os = "linux")]
+impl std::fmt::Display for ProcPathError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "path '{}' is not within /proc", self.path.display())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::error::Error for ProcPathError {
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Decodes all files from the bundle sent by the session.
+    fn bundle(session: &crate::session::FakeSession) -> Vec<rrg_proto::get_proc_snapshot::File> {
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| blob.as_bytes());
+
+        crate::gzchunked::decode(blobs)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn handle_current_process() {
+        use sha2::Digest as _;
+
+        let pid = std::process::id();
+
+        let args = Args {
+            paths: vec! {
+                PathBuf::from(format!("/proc/{pid}/cmdline")),
+                PathBuf::from(format!("/proc/{pid}/status")),
+            },
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+        let item = session.reply::<Item>(0);
+
+        let files = bundle(&session);
+        assert_eq!(files.len(), 2);
+        assert_eq!(item.entries.len(), 2);
+
+        for (entry, file) in item.entries.iter().zip(files.iter()) {
+            let path = crate::path::from_proto(file.path().clone())
+                .unwrap();
+            assert_eq!(entry.path, path);
+
+            let contents = entry.contents.as_ref()
+                .unwrap();
+            assert_eq!(contents.len, file.contents().len() as u64);
+            assert_eq!(contents.sha256, <[u8; 32]>::from(sha2::Sha256::digest(file.contents())));
+            assert!(!contents.truncated);
+        }
+
+        // Procfs advertises no size but we should still get the contents.
+        let status = String::from_utf8_lossy(files[1].contents());
+        assert!(status.contains(&format!("Pid:\t{pid}\n")));
+
+        let blob_sha256s = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| <[u8; 32]>::from(sha2::Sha256::digest(blob.as_bytes())))
+            .collect::<Vec<_>>();
+        assert_eq!(item.blob_sha256s, blob_sha256s);
+    }
+
+    #[test]
+    fn handle_truncated() {
+        let args = Args {
+            paths: vec![PathBuf::from("/proc/self/status")],
+            max_file_size: 4,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        let contents = item.entries[0].contents.as_ref()
+            .unwrap();
+        assert_eq!(contents.len, 4);
+        assert!(contents.truncated);
+
+        let files = bundle(&session);
+        assert_eq!(files[0].contents(), b"Name");
+    }
+
+    #[test]
+    fn handle_missing_file() {
+        let args = Args {
+            paths: vec! {
+                PathBuf::from("/proc/rrg-non-existent"),
+                PathBuf::from("/proc/self/status"),
+            },
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.entries.len(), 2);
+        assert!(item.entries[0].contents.is_err());
+        assert!(item.entries[1].contents.is_ok());
+
+        // Only files that were read are in the bundle.
+        let files = bundle(&session);
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn args_default() {
+        use crate::request::Args as _;
+
+        let proto = rrg_proto::get_proc_snapshot::Args::new();
+        let args = Args::from_proto(proto)
+            .unwrap();
+
+        assert_eq!(args.paths.len(), DEFAULT_PATHS.len());
+        assert_eq!(args.max_file_size, DEFAULT_MAX_FILE_SIZE);
+    }
+
+    #[test]
+    fn args_pids() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_proc_snapshot::Args::new();
+        proto.mut_pids().push(42);
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+
+        assert_eq!(args.paths, vec! {
+            PathBuf::from("/proc/42/cmdline"),
+            PathBuf::from("/proc/42/status"),
+        });
+    }
+
+    #[test]
+    fn args_path_outside_proc() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_proc_snapshot::Args::new();
+        proto.mut_paths().push(PathBuf::from("/etc/passwd").into());
+        assert!(Args::from_proto(proto).is_err());
+
+        let mut proto = rrg_proto::get_proc_snapshot::Args::new();
+        proto.mut_paths().push(PathBuf::from("/proc/../etc/passwd").into());
+        assert!(Args::from_proto(proto).is_err());
+
+        let mut proto = rrg_proto::get_proc_snapshot::Args::new();
+        proto.mut_paths().push(PathBuf::from("proc/modules").into());
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
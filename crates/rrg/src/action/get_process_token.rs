@@ -0,0 +1,239 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Arguments of the `get_process_token` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Identifiers of processes to get the tokens of.
+    pids: Vec<u32>,
+}
+
+/// A result of the `get_process_token` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct Item {
+    /// Identifier of the process.
+    pid: u32,
+    /// Token of the process (or an error if it could not be obtained).
+    token: std::io::Result<Token>,
+}
+
+/// Access token of a process along with resolved details.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct Token {
+    /// Token as reported by the system.
+    token: ospect::token::Token,
+    /// Name of the user of the token (if resolved).
+    user_name: Option<std::ffi::OsString>,
+}
+
+/// Handles invocations of the `get_process_token` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let pids = if args.pids.is_empty() {
+        vec![std::process::id()]
+    } else {
+        args.pids
+    };
+
+    for pid in pids {
+        let token = match ospect::token::of_process(pid) {
+            Ok(token) => {
+                let user_name = match ospect::user::account_name(token.user_sid()) {
+                    Ok(user_name) => user_name,
+                    Err(error) => {
+                        log::warn! {
+                            "failed to resolve name of '{}': {error}",
+                            token.user_sid(),
+                        };
+                        None
+                    }
+                };
+
+                Ok(Token {
+                    token,
+                    user_name,
+                })
+            }
+            Err(error) => {
+                log::warn!("failed to obtain token of process {pid}: {error}");
+                Err(error)
+            }
+        };
+
+        session.reply(Item {
+            pid,
+            token,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `get_process_token` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_process_token::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            pids: proto.pids().to_vec(),
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_process_token::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_process_token::Result::new();
+        proto.set_pid(self.pid);
+
+        match self.token {
+            Ok(token) => proto.set_token(token.into()),
+            Err(error) => proto.set_error(error.to_string()),
+        }
+
+        proto
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl From<Token> for rrg_proto::get_process_token::Token {
+
+    fn from(token: Token) -> rrg_proto::get_process_token::Token {
+        use rrg_proto::get_process_token::{ElevationType, IntegrityLevel};
+
+        let mut proto = rrg_proto::get_process_token::Token::new();
+        proto.set_user_sid(token.token.user_sid().to_string());
+        if let Some(user_name) = token.user_name {
+            proto.set_user_name(user_name.to_string_lossy().into_owned());
+        }
+
+        for group in token.token.groups() {
+            let mut group_proto = rrg_proto::get_process_token::Group::new();
+            group_proto.set_sid(group.sid().to_string());
+            group_proto.set_attributes(group.attributes());
+            group_proto.set_enabled(group.is_enabled());
+            group_proto.set_deny_only(group.is_deny_only());
+
+            proto.mut_groups().push(group_proto);
+        }
+
+        for privilege in token.token.privileges() {
+            let mut privilege_proto = rrg_proto::get_process_token::Privilege::new();
+            privilege_proto.set_name(privilege.name().to_string());
+            privilege_proto.set_attributes(privilege.attributes());
+            privilege_proto.set_enabled(privilege.is_enabled());
+            privilege_proto.set_enabled_by_default(privilege.is_enabled_by_default());
+
+            proto.mut_privileges().push(privilege_proto);
+        }
+
+        if let Some(rid) = token.token.integrity_level() {
+            // Integrity levels are ordered ranges of relative identifiers [1],
+            // so we classify values that are not exactly the well-known ones
+            // into the range they fall into.
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/secauthz/well-known-sids
+            proto.set_integrity_level(match rid {
+                0x0000..0x1000 => IntegrityLevel::INTEGRITY_LEVEL_UNTRUSTED,
+                0x1000..0x2000 => IntegrityLevel::INTEGRITY_LEVEL_LOW,
+                0x2000..0x2100 => IntegrityLevel::INTEGRITY_LEVEL_MEDIUM,
+                0x2100..0x3000 => IntegrityLevel::INTEGRITY_LEVEL_MEDIUM_PLUS,
+                0x3000..0x4000 => IntegrityLevel::INTEGRITY_LEVEL_HIGH,
+                0x4000..0x5000 => IntegrityLevel::INTEGRITY_LEVEL_SYSTEM,
+                0x5000.. => IntegrityLevel::INTEGRITY_LEVEL_PROTECTED_PROCESS,
+            });
+            proto.set_integrity_level_rid(rid);
+        }
+
+        proto.set_elevation_type(match token.token.elevation_type() {
+            Some(ospect::token::ElevationType::Default) => {
+                ElevationType::ELEVATION_TYPE_DEFAULT
+            }
+            Some(ospect::token::ElevationType::Full) => {
+                ElevationType::ELEVATION_TYPE_FULL
+            }
+            Some(ospect::token::ElevationType::Limited) => {
+                ElevationType::ELEVATION_TYPE_LIMITED
+            }
+            None => ElevationType::ELEVATION_TYPE_UNKNOWN,
+        });
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_self() {
+        let args = Args {
+            pids: vec![],
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.pid, std::process::id());
+
+        let token = item.token.as_ref().unwrap();
+
+        let user_sid = ospect::proc::user_sid(std::process::id())
+            .unwrap();
+        assert_eq!(token.token.user_sid(), user_sid);
+        assert!(token.user_name.is_some());
+
+        assert! {
+            token.token.privileges().iter()
+                .any(|privilege| privilege.name() == "SeChangeNotifyPrivilege")
+        };
+    }
+
+    #[test]
+    fn handle_non_existent() {
+        // Process identifiers on Windows are multiples of 4, so this one can
+        // never be used.
+        let args = Args {
+            pids: vec![std::process::id(), 0xFFFFFFFF],
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 2);
+
+        let item = session.reply::<Item>(0);
+        assert!(item.token.is_ok());
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.pid, 0xFFFFFFFF);
+        assert!(item.token.is_err());
+    }
+}
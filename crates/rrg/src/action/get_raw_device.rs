@@ -0,0 +1,963 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Imaging of raw block devices and volumes.
+//!
+//! Images of whole disks take hours, so they are always resumable: the first
+//! result is a manifest describing the imaged range and an interrupted image
+//! can be continued by issuing the request again with the digest of the
+//! manifest and the chunks that were received (in the same way as resumable
+//! uploads of the `get_file_contents` action).
+//!
+//! Unlike with files, the manifest does not include digests of the chunks: the
+//! device is likely in use while being imaged and its contents change all the
+//! time, so an image of a live device is never consistent anyway.
+
+mod alloc;
+
+use std::path::PathBuf;
+
+/// Default (and maximum) number of bytes of each chunk of the image.
+const MAX_CHUNK_LEN: u64 = 2 * 1024 * 1024; // 2 MiB.
+
+/// Alignment of offsets and lengths of reads from the device.
+///
+/// Reads from raw devices have to be aligned to the sector size. We do not
+/// know it upfront, so we align to the biggest sector size in common use.
+const ALIGN: u64 = 4096;
+
+/// Arguments of the `get_raw_device` action.
+pub struct Args {
+    /// Path to the device to image.
+    path: PathBuf,
+    /// Offset from which to image the device.
+    offset: u64,
+    /// Number of bytes of the device to image (until the end if not set).
+    len: Option<u64>,
+    /// Number of bytes of each chunk of the image.
+    chunk_len: u64,
+    /// Limit on the number of bytes read from the device per second.
+    bandwidth_limit: Option<u64>,
+    /// Whether to skip ranges not allocated by the filesystem of the device.
+    skip_unallocated: bool,
+    /// Parameters of the interrupted image to resume (if any).
+    resume: Option<Resume>,
+}
+
+/// Parameters of an interrupted image to resume.
+struct Resume {
+    /// SHA-256 digest of the manifest of the interrupted image.
+    manifest_sha256: [u8; 32],
+    /// Index of the first chunk that might not have been received.
+    from_chunk: u64,
+    /// Bitmap of chunks received already.
+    received_chunks: Vec<u8>,
+}
+
+impl Resume {
+
+    /// Checks whether the chunk at the given index has been received already.
+    fn is_received(&self, index: u64) -> bool {
+        if index < self.from_chunk {
+            return true;
+        }
+
+        let Ok(byte_index) = usize::try_from(index / 8) else {
+            return false;
+        };
+
+        match self.received_chunks.get(byte_index) {
+            Some(byte) => byte & (1 << (index % 8)) != 0,
+            None => false,
+        }
+    }
+}
+
+/// Result of the `get_raw_device` action.
+struct Item {
+    /// Byte offset (from the start of the device) of the chunk.
+    offset: u64,
+    /// Number of bytes of the chunk.
+    len: u64,
+    /// SHA-256 digest of the chunk sent to the blob sink (if any).
+    blob_sha256: Option<[u8; 32]>,
+    /// Whether some sectors of the chunk could not be read.
+    read_error: bool,
+    /// Whether the chunk was skipped because it is not allocated.
+    unallocated: bool,
+}
+
+/// Manifest of the image (sent as the first result).
+struct Manifest {
+    /// Byte offset of the imaged range.
+    offset: u64,
+    /// Number of bytes of the imaged range.
+    len: u64,
+    /// Number of bytes of each chunk.
+    chunk_len: u64,
+    /// Size of the whole device.
+    device_size: u64,
+}
+
+impl Manifest {
+
+    /// Computes the SHA-256 digest of the manifest.
+    fn sha256(&self) -> [u8; 32] {
+        use sha2::Digest as _;
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(self.offset.to_le_bytes());
+        hasher.update(self.len.to_le_bytes());
+        hasher.update(self.chunk_len.to_le_bytes());
+        hasher.update(self.device_size.to_le_bytes());
+
+        hasher.finalize().into()
+    }
+}
+
+/// Handles invocations of the `get_raw_device` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut device = open(&args.path)
+        .map_err(|error| crate::session::Error::action(OpenError {
+            path: args.path.clone(),
+            error,
+        }))?;
+
+    let device_size = device_size(&mut device)
+        .map_err(crate::session::Error::action)?;
+
+    image(session, device, device_size, &args)
+}
+
+/// Images the device readable through the given reader.
+fn image<S, R>(
+    session: &mut S,
+    mut device: R,
+    device_size: u64,
+    args: &Args,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+    R: std::io::Read + std::io::Seek,
+{
+    use sha2::Digest as _;
+
+    let len_left = device_size.saturating_sub(args.offset);
+    let manifest = Manifest {
+        offset: args.offset,
+        len: args.len.map_or(len_left, |len| std::cmp::min(len, len_left)),
+        chunk_len: args.chunk_len,
+        device_size,
+    };
+
+    if let Some(resume) = &args.resume {
+        if manifest.sha256() != resume.manifest_sha256 {
+            return Err(crate::session::Error::action(ModifiedError {
+                path: args.path.clone(),
+            }));
+        }
+    }
+
+    let offset = manifest.offset;
+    let len = manifest.len;
+    session.reply(manifest)?;
+
+    let mut allocation = if args.skip_unallocated {
+        match alloc::detect(&mut device) {
+            Ok(Some(allocation)) => Some(allocation),
+            Ok(None) => {
+                log::info!("no supported filesystem on '{}'", args.path.display());
+                None
+            }
+            Err(error) => {
+                log::warn!("failed to read allocation of '{}': {error}", args.path.display());
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut throttle = args.bandwidth_limit.map(Throttle::new);
+
+    for index in 0..len.div_ceil(args.chunk_len) {
+        if args.resume.as_ref().is_some_and(|resume| resume.is_received(index)) {
+            continue;
+        }
+
+        // Chunks are big, so we check the limits (and whether the agent is
+        // shutting down) before reading each one of them.
+        session.check_limits()?;
+
+        let chunk_offset = offset + index * args.chunk_len;
+        let chunk_len = std::cmp::min(args.chunk_len, offset + len - chunk_offset);
+
+        if let Some(allocation) = &mut allocation {
+            match allocation.is_allocated(&mut device, chunk_offset, chunk_len) {
+                Ok(true) => (),
+                Ok(false) => {
+                    session.reply(Item {
+                        offset: chunk_offset,
+                        len: chunk_len,
+                        blob_sha256: None,
+                        read_error: false,
+                        unallocated: true,
+                    })?;
+                    continue;
+                }
+                // We cannot tell whether the chunk is allocated, so we image it
+                // to be on the safe side.
+                Err(error) => {
+                    log::warn!("failed to check allocation at {chunk_offset}: {error}");
+                }
+            }
+        }
+
+        let (buf, read_error) = read_chunk(&mut device, chunk_offset, chunk_len as usize)
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(buf);
+        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            offset: chunk_offset,
+            len: chunk_len,
+            blob_sha256: Some(blob_sha256),
+            read_error,
+            unallocated: false,
+        })?;
+
+        if let Some(throttle) = &mut throttle {
+            std::thread::sleep(throttle.record(chunk_len, std::time::Instant::now()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a chunk of `len` bytes at the given offset of the device.
+///
+/// If the chunk cannot be read in full, it is read again in aligned blocks
+/// with the blocks that cannot be read (e.g. bad sectors) filled with zeros.
+/// In such cases the returned flag is set.
+fn read_chunk<R>(device: &mut R, offset: u64, len: usize) -> std::io::Result<(Vec<u8>, bool)>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let mut buf = vec![0; len];
+
+    device.seek(std::io::SeekFrom::Start(offset))?;
+    if device.read_exact(&mut buf).is_ok() {
+        return Ok((buf, false));
+    }
+
+    buf.fill(0);
+
+    let mut read_error = false;
+    for (index, block) in buf.chunks_mut(ALIGN as usize).enumerate() {
+        let block_offset = offset + index as u64 * ALIGN;
+
+        device.seek(std::io::SeekFrom::Start(block_offset))?;
+        if let Err(error) = device.read_exact(block) {
+            log::warn!("failed to read block at {block_offset}: {error}");
+            block.fill(0);
+            read_error = true;
+        }
+    }
+
+    Ok((buf, read_error))
+}
+
+/// Limiter of the rate at which the device is read.
+struct Throttle {
+    /// Maximum number of bytes per second.
+    bytes_per_sec: u64,
+    /// Moment at which the reading started.
+    start: std::time::Instant,
+    /// Number of bytes read so far.
+    bytes: u64,
+}
+
+impl Throttle {
+
+    /// Creates a new throttle with the given limit starting now.
+    fn new(bytes_per_sec: u64) -> Throttle {
+        Throttle {
+            bytes_per_sec,
+            start: std::time::Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Records the given number of bytes as read and returns how long to wait
+    /// before reading more to stay within the limit.
+    fn record(&mut self, len: u64, now: std::time::Instant) -> std::time::Duration {
+        self.bytes += len;
+
+        let due = std::time::Duration::from_secs_f64(self.bytes as f64 / self.bytes_per_sec as f64);
+        due.saturating_sub(now.saturating_duration_since(self.start))
+    }
+}
+
+/// Opens the device at the given path for reading.
+#[cfg(target_family = "unix")]
+fn open(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    std::fs::File::open(path)
+}
+
+/// Opens the device at the given path for reading.
+#[cfg(target_family = "windows")]
+fn open(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    use std::os::windows::fs::OpenOptionsExt as _;
+    use windows_sys::Win32::Storage::FileSystem::*;
+    use crate::os::windows::privileges::{with_privilege, SE_BACKUP_NAME};
+
+    // Devices are mounted and used by the system, so we have to allow others
+    // to keep reading and writing them.
+    let open = || {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)
+    };
+
+    with_privilege(SE_BACKUP_NAME, open)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::PermissionDenied, error))?
+}
+
+/// Returns the size of the given device (in bytes).
+#[cfg(target_family = "unix")]
+fn device_size(device: &mut std::fs::File) -> std::io::Result<u64> {
+    use std::io::Seek as _;
+
+    // Block devices report zero length in their metadata, but seeking to their
+    // end works (and also works for regular files).
+    let size = device.seek(std::io::SeekFrom::End(0))?;
+    device.seek(std::io::SeekFrom::Start(0))?;
+
+    Ok(size)
+}
+
+/// Returns the size of the given device (in bytes).
+#[cfg(target_family = "windows")]
+fn device_size(device: &mut std::fs::File) -> std::io::Result<u64> {
+    use std::os::windows::io::AsRawHandle as _;
+    use windows_sys::Win32::Foundation::FALSE;
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+    use windows_sys::Win32::System::Ioctl::*;
+
+    let metadata = device.metadata()?;
+    if metadata.is_file() {
+        return Ok(metadata.len());
+    }
+
+    // SAFETY: The structure is a plain integer for which zero is a valid value.
+    let mut info = unsafe {
+        std::mem::zeroed::<GET_LENGTH_INFORMATION>()
+    };
+    let mut info_len = 0;
+
+    // SAFETY: The handle is valid for the lifetime of the file and we pass the
+    // output buffer with its correct size. We verify the result below.
+    let status = unsafe {
+        DeviceIoControl(
+            device.as_raw_handle(),
+            IOCTL_DISK_GET_LENGTH_INFO,
+            std::ptr::null(),
+            0,
+            &mut info as *mut GET_LENGTH_INFORMATION as *mut std::ffi::c_void,
+            std::mem::size_of::<GET_LENGTH_INFORMATION>() as u32,
+            &mut info_len,
+            std::ptr::null_mut(),
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    u64::try_from(info.Length)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_raw_device::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let path = crate::path::from_proto(proto.take_path())
+            .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+        if proto.offset() % ALIGN != 0 {
+            return Err(ParseArgsError::invalid_field("offset", AlignError {
+                value: proto.offset(),
+            }));
+        }
+        if proto.length() % ALIGN != 0 {
+            return Err(ParseArgsError::invalid_field("length", AlignError {
+                value: proto.length(),
+            }));
+        }
+
+        let chunk_len = match proto.chunk_length() {
+            0 => MAX_CHUNK_LEN,
+            len if len % ALIGN != 0 => {
+                return Err(ParseArgsError::invalid_field("chunk_length", AlignError {
+                    value: len,
+                }));
+            }
+            len if len > MAX_CHUNK_LEN => {
+                return Err(ParseArgsError::invalid_field("chunk_length", ChunkLenError {
+                    len,
+                }));
+            }
+            len => len,
+        };
+
+        let resume = match proto.resume_manifest_sha256() {
+            [] if proto.resume_from_chunk() != 0 || !proto.received_chunks().is_empty() => {
+                return Err(ParseArgsError::invalid_field("resume_manifest_sha256", MissingManifestError));
+            }
+            [] => None,
+            manifest_sha256 => Some(Resume {
+                manifest_sha256: <[u8; 32]>::try_from(manifest_sha256)
+                    .map_err(|error| ParseArgsError::invalid_field("resume_manifest_sha256", error))?,
+                from_chunk: proto.resume_from_chunk(),
+                received_chunks: proto.take_received_chunks(),
+            }),
+        };
+
+        Ok(Args {
+            path,
+            offset: proto.offset(),
+            len: match proto.length() {
+                0 => None,
+                len => Some(len),
+            },
+            chunk_len,
+            bandwidth_limit: match proto.bandwidth_limit() {
+                0 => None,
+                limit => Some(limit),
+            },
+            skip_unallocated: proto.skip_unallocated(),
+            resume,
+        })
+    }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.path.as_path()]
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_raw_device::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_offset(self.offset);
+        proto.set_length(self.len);
+        if let Some(blob_sha256) = self.blob_sha256 {
+            proto.set_blob_sha256(blob_sha256.to_vec());
+        }
+        proto.set_read_error(self.read_error);
+        proto.set_unallocated(self.unallocated);
+
+        proto
+    }
+}
+
+impl crate::response::Item for Manifest {
+
+    type Proto = rrg_proto::get_raw_device::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut manifest = rrg_proto::get_raw_device::Manifest::default();
+        manifest.set_sha256(self.sha256().to_vec());
+        manifest.set_offset(self.offset);
+        manifest.set_length(self.len);
+        manifest.set_chunk_length(self.chunk_len);
+        manifest.set_device_size(self.device_size);
+
+        let mut proto = Self::Proto::default();
+        proto.set_manifest(manifest);
+
+        proto
+    }
+}
+
+/// An error indicating that opening the device failed.
+#[derive(Debug)]
+struct OpenError {
+    /// Path to the device that was opened.
+    path: PathBuf,
+    /// Underlying error returned by the system.
+    error: std::io::Error,
+}
+
+impl std::fmt::Display for OpenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Raw devices can be opened only with elevated privileges, so we make
+        // it explicit in such cases.
+        if self.error.kind() == std::io::ErrorKind::PermissionDenied {
+            write! {
+                fmt,
+                "access to device '{}' denied (reading it requires \
+                 administrator privileges): {}",
+                self.path.display(), self.error
+            }
+        } else {
+            write!(fmt, "failed to open device '{}': {}", self.path.display(), self.error)
+        }
+    }
+}
+
+impl std::error::Error for OpenError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error indicating that an offset or a length is not properly aligned.
+#[derive(Debug)]
+struct AlignError {
+    value: u64,
+}
+
+impl std::fmt::Display for AlignError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "value {} is not a multiple of {}", self.value, ALIGN)
+    }
+}
+
+impl std::error::Error for AlignError {
+}
+
+/// An error indicating that the action was invoked with invalid chunk length.
+#[derive(Debug)]
+struct ChunkLenError {
+    len: u64,
+}
+
+impl std::fmt::Display for ChunkLenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "provided chunk length ({}) is bigger than allowed ({})",
+            self.len, MAX_CHUNK_LEN
+        }
+    }
+}
+
+impl std::error::Error for ChunkLenError {
+}
+
+/// An error indicating that resume arguments were given without a manifest.
+#[derive(Debug)]
+struct MissingManifestError;
+
+impl std::fmt::Display for MissingManifestError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing manifest digest of the image to resume")
+    }
+}
+
+impl std::error::Error for MissingManifestError {
+}
+
+/// An error indicating that the device changed since the image started.
+#[derive(Debug)]
+struct ModifiedError {
+    /// Path to the modified device.
+    path: PathBuf,
+}
+
+impl std::fmt::Display for ModifiedError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "device '{}' (or the imaged range) changed since the image started",
+            self.path.display()
+        }
+    }
+}
+
+impl std::error::Error for ModifiedError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Returns arguments for imaging the whole device in chunks of 8 KiB.
+    fn args() -> Args {
+        Args {
+            path: PathBuf::from("/dev/foo"),
+            offset: 0,
+            len: None,
+            chunk_len: 2 * ALIGN,
+            bandwidth_limit: None,
+            skip_unallocated: false,
+            resume: None,
+        }
+    }
+
+    /// Returns device data with each 4 KiB block filled with its index.
+    fn data(block_count: usize) -> Vec<u8> {
+        (0..block_count)
+            .flat_map(|block| std::iter::repeat_n(block as u8, ALIGN as usize))
+            .collect()
+    }
+
+    /// Reconstructs the image from the results and blobs of the session.
+    ///
+    /// Unallocated chunks are filled with zeros.
+    fn reconstruct(session: &crate::session::FakeSession, image: &mut [u8]) {
+        let mut blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob);
+
+        for id in 1..session.reply_count() {
+            let item = session.reply::<Item>(id);
+            let range = item.offset as usize..(item.offset + item.len) as usize;
+
+            if item.unallocated {
+                image[range].fill(0);
+            } else {
+                let blob = blobs.next()
+                    .unwrap();
+                image[range].copy_from_slice(blob.as_bytes());
+            }
+        }
+
+        assert!(blobs.next().is_none());
+    }
+
+    /// Reader that fails reads of the block at the given offset.
+    struct BadBlockReader {
+        inner: std::io::Cursor<Vec<u8>>,
+        bad_block_offset: u64,
+    }
+
+    impl std::io::Read for BadBlockReader {
+
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let pos = self.inner.position();
+            if pos <= self.bad_block_offset && self.bad_block_offset < pos + buf.len() as u64 {
+                return Err(std::io::Error::other("bad sector"));
+            }
+
+            self.inner.read(buf)
+        }
+    }
+
+    impl std::io::Seek for BadBlockReader {
+
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn handle_loop_file() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+        std::fs::write(tempfile.path(), data(7))
+            .unwrap();
+
+        let args = Args {
+            path: tempfile.path().to_path_buf(),
+            ..args()
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let manifest = session.reply::<Manifest>(0);
+        assert_eq!(manifest.len, 7 * ALIGN);
+        assert_eq!(manifest.device_size, 7 * ALIGN);
+        assert_eq!(session.reply_count(), 1 + 4);
+
+        let mut image = vec![0xFF; 7 * ALIGN as usize];
+        reconstruct(&session, &mut image);
+        assert_eq!(image, data(7));
+    }
+
+    #[test]
+    fn image_chunk_digests() {
+        use sha2::Digest as _;
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(data(4)), 4 * ALIGN, &args())
+            .unwrap();
+
+        for id in 1..session.reply_count() {
+            let item = session.reply::<Item>(id);
+            let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, id - 1);
+
+            let blob_sha256 = <[u8; 32]>::from(sha2::Sha256::digest(blob.as_bytes()));
+            assert_eq!(item.blob_sha256, Some(blob_sha256));
+            assert!(!item.read_error);
+            assert!(!item.unallocated);
+        }
+    }
+
+    #[test]
+    fn image_offset_len() {
+        let args = Args {
+            offset: ALIGN,
+            len: Some(3 * ALIGN),
+            ..args()
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(data(8)), 8 * ALIGN, &args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1 + 2);
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.offset, ALIGN);
+        assert_eq!(item.len, 2 * ALIGN);
+
+        let item = session.reply::<Item>(2);
+        assert_eq!(item.offset, 3 * ALIGN);
+        assert_eq!(item.len, ALIGN);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 1);
+        assert_eq!(blob.as_bytes(), &data(4)[3 * ALIGN as usize..]);
+    }
+
+    #[test]
+    fn image_len_past_end() {
+        let args = Args {
+            offset: 2 * ALIGN,
+            len: Some(16 * ALIGN),
+            ..args()
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(data(4)), 4 * ALIGN, &args)
+            .unwrap();
+
+        assert_eq!(session.reply::<Manifest>(0).len, 2 * ALIGN);
+        assert_eq!(session.reply_count(), 1 + 1);
+    }
+
+    #[test]
+    fn image_bad_block() {
+        let device = BadBlockReader {
+            inner: std::io::Cursor::new(data(6)),
+            bad_block_offset: 3 * ALIGN,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, device, 6 * ALIGN, &args())
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1 + 3);
+        assert!(!session.reply::<Item>(1).read_error);
+        assert!(session.reply::<Item>(2).read_error);
+        assert!(!session.reply::<Item>(3).read_error);
+
+        let mut image = vec![0xFF; 6 * ALIGN as usize];
+        reconstruct(&session, &mut image);
+
+        let mut expected = data(6);
+        expected[3 * ALIGN as usize..4 * ALIGN as usize].fill(0);
+        assert_eq!(image, expected);
+    }
+
+    #[test]
+    fn image_skip_unallocated() {
+        let cluster = alloc::tests::CLUSTER_SIZE;
+        let volume = alloc::tests::volume(16, &[0, 1, 2, 3, 4, 5, 6, 7, 10, 15], 0xAB);
+
+        let args = Args {
+            chunk_len: cluster as u64,
+            skip_unallocated: true,
+            ..args()
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(volume.clone()), volume.len() as u64, &args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1 + 16);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 10);
+        assert!(session.reply::<Item>(1 + 9).unallocated);
+        assert!(!session.reply::<Item>(1 + 10).unallocated);
+        assert_eq!(session.reply::<Item>(1 + 9).blob_sha256, None);
+
+        let mut image = vec![0xFF; volume.len()];
+        reconstruct(&session, &mut image);
+
+        let mut expected = volume.clone();
+        for cluster_index in [8, 9, 11, 12, 13, 14] {
+            expected[cluster_index * cluster..(cluster_index + 1) * cluster].fill(0);
+        }
+        assert_eq!(image, expected);
+    }
+
+    #[test]
+    fn image_skip_unallocated_not_ntfs() {
+        let args = Args {
+            skip_unallocated: true,
+            ..args()
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(data(4)), 4 * ALIGN, &args)
+            .unwrap();
+
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 2);
+
+        let mut image = vec![0xFF; 4 * ALIGN as usize];
+        reconstruct(&session, &mut image);
+        assert_eq!(image, data(4));
+    }
+
+    #[test]
+    fn image_resume() {
+        let mut session = crate::session::FakeSession::new();
+
+        // Limits are checked once before every chunk, so we simulate the
+        // interrupt as a limit that is crossed after the second check.
+        let check_count = std::cell::Cell::new(0);
+        session.simulate_memory_usage(2, move || {
+            check_count.set(check_count.get() + 1);
+            check_count.get()
+        });
+
+        assert!(image(&mut session, std::io::Cursor::new(data(10)), 10 * ALIGN, &args()).is_err());
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 2);
+
+        let mut image_data = vec![0xFF; 10 * ALIGN as usize];
+        reconstruct(&session, &mut image_data);
+
+        // Chunk #3 is marked as received as well, so we fill it in ourselves
+        // as if it arrived out of order before the interrupt.
+        image_data[6 * ALIGN as usize..8 * ALIGN as usize]
+            .copy_from_slice(&data(10)[6 * ALIGN as usize..8 * ALIGN as usize]);
+
+        let args = Args {
+            resume: Some(Resume {
+                manifest_sha256: session.reply::<Manifest>(0).sha256(),
+                from_chunk: 2,
+                received_chunks: vec![0b0000_1000],
+            }),
+            ..args()
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(data(10)), 10 * ALIGN, &args)
+            .unwrap();
+
+        let offsets = (1..session.reply_count())
+            .map(|id| session.reply::<Item>(id).offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![4 * ALIGN, 8 * ALIGN]);
+
+        reconstruct(&session, &mut image_data);
+        assert_eq!(image_data, data(10));
+    }
+
+    #[test]
+    fn image_deadline_passed() {
+        let mut session = crate::session::FakeSession::new();
+        session.set_deadline(std::time::Instant::now() - std::time::Duration::from_secs(1));
+
+        let error = image(&mut session, std::io::Cursor::new(data(4)), 4 * ALIGN, &args())
+            .unwrap_err();
+        assert_eq!(error.code(), crate::session::ErrorCode::DeadlineExceeded);
+
+        // The manifest is still sent, so that the image can be resumed.
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[test]
+    fn image_resume_modified() {
+        let mut session = crate::session::FakeSession::new();
+        image(&mut session, std::io::Cursor::new(data(4)), 4 * ALIGN, &args())
+            .unwrap();
+
+        let args = Args {
+            resume: Some(Resume {
+                manifest_sha256: session.reply::<Manifest>(0).sha256(),
+                from_chunk: 1,
+                received_chunks: Vec::new(),
+            }),
+            ..args()
+        };
+
+        // The device got bigger in the meantime.
+        let mut session = crate::session::FakeSession::new();
+        assert!(image(&mut session, std::io::Cursor::new(data(6)), 6 * ALIGN, &args).is_err());
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[test]
+    fn throttle_delay() {
+        let start = std::time::Instant::now();
+        let mut throttle = Throttle {
+            bytes_per_sec: 1024,
+            start,
+            bytes: 0,
+        };
+
+        let delay = throttle.record(2048, start + std::time::Duration::from_millis(500));
+        assert_eq!(delay, std::time::Duration::from_millis(1500));
+
+        let delay = throttle.record(1024, start + std::time::Duration::from_secs(5));
+        assert_eq!(delay, std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn args_from_proto_unaligned() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_raw_device::Args::new();
+        proto.set_path(PathBuf::from("/dev/foo").into());
+        proto.set_offset(512);
+        assert!(Args::from_proto(proto.clone()).is_err());
+
+        proto.set_offset(0);
+        proto.set_chunk_length(1000);
+        assert!(Args::from_proto(proto.clone()).is_err());
+
+        proto.set_chunk_length(2 * MAX_CHUNK_LEN);
+        assert!(Args::from_proto(proto.clone()).is_err());
+
+        proto.set_chunk_length(0);
+        let args = Args::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.chunk_len, MAX_CHUNK_LEN);
+        assert_eq!(args.len, None);
+    }
+
+    #[test]
+    fn args_from_proto_resume_without_manifest() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_raw_device::Args::new();
+        proto.set_path(PathBuf::from("/dev/foo").into());
+        proto.set_resume_from_chunk(1);
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
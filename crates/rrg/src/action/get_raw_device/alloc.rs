@@ -0,0 +1,256 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Allocation status of ranges of volumes with supported filesystems.
+//!
+//! Currently only NTFS is supported: allocated clusters are tracked by the
+//! `$Bitmap` system file with one bit per cluster. The bitmap is not loaded in
+//! full (it is 32 MiB for a 1 TiB volume with 4 KiB clusters), instead only the
+//! clusters of the bitmap covering the queried ranges are read.
+
+use crate::ntfs;
+
+/// Number of bytes read from the beginning of the volume to get the boot sector.
+///
+/// See the comment for the same constant of the `get_ntfs_mft` action.
+const BOOT_SECTOR_READ_LEN: usize = 4096;
+
+/// Allocation map of a volume.
+pub struct Allocation {
+    /// Number of bytes in a single cluster of the volume.
+    bytes_per_cluster: u64,
+    /// Runs of clusters occupied by the `$Bitmap` data.
+    bitmap_runs: Vec<ntfs::DataRun>,
+    /// Size of the `$Bitmap` data in bytes.
+    bitmap_size: u64,
+    /// Last cluster of the bitmap that was read (with its index).
+    cached: Option<(u64, Vec<u8>)>,
+}
+
+/// Detects a supported filesystem on the volume and returns its allocation map.
+///
+/// `None` is returned if the volume does not have a supported filesystem.
+pub fn detect<R>(volume: &mut R) -> std::io::Result<Option<Allocation>>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let boot_sector_buf = match read_at(volume, 0, BOOT_SECTOR_READ_LEN) {
+        Ok(buf) => buf,
+        // The volume is too small to have any filesystem we know.
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let Ok(boot_sector) = ntfs::BootSector::parse(&boot_sector_buf) else {
+        return Ok(None);
+    };
+
+    // The first run of the MFT always covers the system files, so we can get
+    // to the record of `$Bitmap` directly. Records can be smaller than sectors
+    // and reads have to be aligned, so we read the whole sector around it.
+    let record_offset = boot_sector.mft_lcn * boot_sector.bytes_per_cluster +
+        ntfs::MFT_RECORD_BITMAP * boot_sector.bytes_per_mft_record;
+    let read_offset = record_offset - record_offset % boot_sector.bytes_per_sector;
+    let read_len = (record_offset - read_offset + boot_sector.bytes_per_mft_record)
+        .next_multiple_of(boot_sector.bytes_per_sector);
+
+    let buf = read_at(volume, read_offset, read_len as usize)?;
+    let record_start = (record_offset - read_offset) as usize;
+    let mut record = buf[record_start..record_start + boot_sector.bytes_per_mft_record as usize]
+        .to_vec();
+
+    ntfs::apply_fixups(&mut record)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    let data = ntfs::parse_data_attribute(&record)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    Ok(Some(Allocation {
+        bytes_per_cluster: boot_sector.bytes_per_cluster,
+        bitmap_runs: data.runs,
+        bitmap_size: data.size,
+        cached: None,
+    }))
+}
+
+impl Allocation {
+
+    /// Checks whether any cluster of the given range of the volume is in use.
+    ///
+    /// Clusters not covered by the bitmap (e.g. the backup boot sector at the
+    /// very end of the volume) are considered to be in use.
+    pub fn is_allocated<R>(
+        &mut self,
+        volume: &mut R,
+        offset: u64,
+        len: u64,
+    ) -> std::io::Result<bool>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
+        if len == 0 {
+            return Ok(false);
+        }
+
+        let first_cluster = offset / self.bytes_per_cluster;
+        let last_cluster = (offset + len - 1) / self.bytes_per_cluster;
+
+        let mut cluster = first_cluster;
+        while cluster <= last_cluster {
+            let byte_index = cluster / 8;
+            if byte_index >= self.bitmap_size {
+                return Ok(true);
+            }
+
+            // Whole bytes of the bitmap with no bits set are very common, so we
+            // skip to the next byte in such cases.
+            let byte = self.bitmap_byte(volume, byte_index)?;
+            if byte == 0 {
+                cluster = (byte_index + 1) * 8;
+                continue;
+            }
+            if byte & (1 << (cluster % 8)) != 0 {
+                return Ok(true);
+            }
+
+            cluster += 1;
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the byte of the bitmap at the given index.
+    fn bitmap_byte<R>(&mut self, volume: &mut R, index: u64) -> std::io::Result<u8>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
+        let vcn = index / self.bytes_per_cluster;
+
+        match &self.cached {
+            Some((cached_vcn, cluster)) if *cached_vcn == vcn => {}
+            _ => {
+                let cluster = self.read_bitmap_cluster(volume, vcn)?;
+                self.cached = Some((vcn, cluster));
+            }
+        }
+
+        let (_, cluster) = self.cached.as_ref()
+            .expect("no cached bitmap cluster");
+
+        Ok(cluster[(index % self.bytes_per_cluster) as usize])
+    }
+
+    /// Reads the cluster of the bitmap data with the given index.
+    fn read_bitmap_cluster<R>(&self, volume: &mut R, vcn: u64) -> std::io::Result<Vec<u8>>
+    where
+        R: std::io::Read + std::io::Seek,
+    {
+        let mut run_vcn = 0;
+
+        for run in &self.bitmap_runs {
+            if vcn < run_vcn + run.len {
+                // The bitmap is never sparse on a consistent volume. If it is,
+                // we err on the side of imaging too much.
+                let Some(lcn) = run.lcn else {
+                    return Ok(vec![0xFF; self.bytes_per_cluster as usize]);
+                };
+
+                let offset = (lcn + vcn - run_vcn) * self.bytes_per_cluster;
+                return read_at(volume, offset, self.bytes_per_cluster as usize);
+            }
+
+            run_vcn += run.len;
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("bitmap cluster {vcn} not covered by data runs"),
+        ))
+    }
+}
+
+/// Reads exactly `len` bytes at the given offset of the given reader.
+fn read_at<R>(reader: &mut R, offset: u64, len: usize) -> std::io::Result<Vec<u8>>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    reader.seek(std::io::SeekFrom::Start(offset))?;
+
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    /// Size of clusters of the fixture volumes.
+    pub const CLUSTER_SIZE: usize = 4096;
+
+    /// Builds a fixture NTFS volume with the given clusters in use.
+    ///
+    /// The MFT starts at cluster 4 and the bitmap is stored at cluster 2. Data
+    /// clusters (other than the ones with NTFS structures) are filled with the
+    /// given byte.
+    pub fn volume(cluster_count: usize, allocated: &[usize], fill: u8) -> Vec<u8> {
+        let mut image = vec![fill; cluster_count * CLUSTER_SIZE];
+        image[..8 * CLUSTER_SIZE].fill(0);
+
+        let boot_sector = ntfs::tests::boot_sector(512, 8, 4, -10);
+        image[..boot_sector.len()].copy_from_slice(&boot_sector);
+
+        let bitmap_size = cluster_count.div_ceil(8) as u64;
+        let record = ntfs::tests::mft_record(&[0x11, 0x01, 0x02, 0x00], bitmap_size);
+        let record_offset = 4 * CLUSTER_SIZE + ntfs::MFT_RECORD_BITMAP as usize * 1024;
+        image[record_offset..record_offset + record.len()].copy_from_slice(&record);
+
+        for cluster in allocated {
+            image[2 * CLUSTER_SIZE + cluster / 8] |= 1 << (cluster % 8);
+        }
+
+        image
+    }
+
+    #[test]
+    fn detect_not_ntfs() {
+        let mut volume = std::io::Cursor::new(vec![0xAB; 16 * CLUSTER_SIZE]);
+        assert!(detect(&mut volume).unwrap().is_none());
+    }
+
+    #[test]
+    fn detect_too_small() {
+        let mut volume = std::io::Cursor::new(vec![0; 1024]);
+        assert!(detect(&mut volume).unwrap().is_none());
+    }
+
+    #[test]
+    fn is_allocated_clusters() {
+        let mut volume = std::io::Cursor::new(volume(64, &[0, 1, 2, 4, 5, 20, 63], 0xFF));
+
+        let mut allocation = detect(&mut volume).unwrap()
+            .unwrap();
+
+        let cluster = CLUSTER_SIZE as u64;
+        assert!(allocation.is_allocated(&mut volume, 0, cluster).unwrap());
+        assert!(!allocation.is_allocated(&mut volume, 3 * cluster, cluster).unwrap());
+        assert!(!allocation.is_allocated(&mut volume, 8 * cluster, 12 * cluster).unwrap());
+        assert!(allocation.is_allocated(&mut volume, 8 * cluster, 13 * cluster).unwrap());
+        assert!(!allocation.is_allocated(&mut volume, 21 * cluster, 42 * cluster).unwrap());
+        assert!(allocation.is_allocated(&mut volume, 63 * cluster, cluster).unwrap());
+    }
+
+    #[test]
+    fn is_allocated_past_bitmap() {
+        let mut volume = std::io::Cursor::new(volume(64, &[], 0xFF));
+
+        let mut allocation = detect(&mut volume).unwrap()
+            .unwrap();
+
+        let cluster = CLUSTER_SIZE as u64;
+        assert!(!allocation.is_allocated(&mut volume, 32 * cluster, 32 * cluster).unwrap());
+        assert!(allocation.is_allocated(&mut volume, 64 * cluster, cluster).unwrap());
+    }
+}
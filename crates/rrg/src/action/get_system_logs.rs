@@ -0,0 +1,465 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the system logs action.
+
+#[cfg(target_os = "linux")]
+mod export;
+
+#[cfg(target_os = "linux")]
+use rrg_proto::convert::FromLossy;
+
+/// The lowest syslog priority (`debug`).
+#[cfg(target_os = "linux")]
+const MAX_PRIORITY: u32 = 7;
+
+/// Arguments of the `get_system_logs` action.
+#[cfg(target_os = "linux")]
+pub struct Args {
+    /// Time since which the entries should be collected.
+    since: Option<std::time::SystemTime>,
+    /// Time until which the entries should be collected.
+    until: Option<std::time::SystemTime>,
+    /// Name of the unit to collect the entries of.
+    unit: Option<String>,
+    /// Lowest priority of the entries to collect (as a syslog number).
+    max_priority: Option<u32>,
+    /// Maximum number of entries to collect.
+    max_entries: usize,
+}
+
+/// Result of the `get_system_logs` action.
+#[cfg(target_os = "linux")]
+pub struct Item {
+    /// SHA-256 digest of the entry batch sent to the blob sink.
+    blob_sha256: [u8; 32],
+    /// Number of entries in the batch sent to the blob sink.
+    entry_count: usize,
+}
+
+/// Handles invocations of the `get_system_logs` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut command = std::process::Command::new("journalctl");
+    command
+        .arg("--output=export")
+        .arg("--no-pager")
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        // We do not read the standard error while processing the output and
+        // so piping it could block `journalctl` if it reports a lot of errors.
+        .stderr(std::process::Stdio::null());
+
+    if let Some(since) = args.since {
+        command.arg(format!("--since={}", timespec(since)));
+    }
+    if let Some(until) = args.until {
+        command.arg(format!("--until={}", timespec(until)));
+    }
+    if let Some(unit) = &args.unit {
+        command.arg(format!("--unit={unit}"));
+    }
+    if let Some(max_priority) = args.max_priority {
+        command.arg(format!("--priority={max_priority}"));
+    }
+
+    let mut child = command.spawn()
+        .map_err(crate::session::Error::action)?;
+
+    let stdout = child.stdout.take()
+        .expect("no stdout pipe");
+
+    let result = send_entries(session, std::io::BufReader::new(stdout), args.max_entries);
+
+    let entry_count = match result {
+        Ok(entry_count) => entry_count,
+        Err(error) => {
+            if let Err(error) = child.kill() {
+                log::warn!("failed to kill `journalctl`: {error}");
+            }
+            if let Err(error) = child.wait() {
+                log::warn!("failed to wait for `journalctl`: {error}");
+            }
+
+            return Err(error);
+        }
+    };
+
+    // If we collected all the entries we wanted, `journalctl` might still be
+    // trying to output more of them, so we stop it (and ignore its status).
+    if entry_count == args.max_entries {
+        if let Err(error) = child.kill() {
+            log::warn!("failed to kill `journalctl`: {error}");
+        }
+        child.wait()
+            .map_err(crate::session::Error::action)?;
+
+        return Ok(());
+    }
+
+    let status = child.wait()
+        .map_err(crate::session::Error::action)?;
+    if !status.success() {
+        return Err(crate::session::Error::action(std::io::Error::other(format! {
+            "`journalctl` failed ({status})",
+        })));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Sends batches of journal entries exported to the given reader.
+///
+/// Returns the total number of entries sent.
+#[cfg(target_os = "linux")]
+fn send_entries<S, R>(
+    session: &mut S,
+    export: R,
+    max_entries: usize,
+) -> crate::session::Result<usize>
+where
+    S: crate::session::Session,
+    R: std::io::BufRead,
+{
+    use sha2::Digest as _;
+
+    // See the filesystem timeline action for why `entry_count` has to be a
+    // cell. The same applies to `error`, which is set once the export stream
+    // becomes unreadable (and at which point the iterator stops).
+    let entry_count = std::cell::Cell::new(0);
+    let error = std::cell::Cell::new(None);
+
+    let entries = export::Reader::new(export)
+        .map_while(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                error.set(Some(err));
+                None
+            }
+        })
+        .take(max_entries)
+        .inspect(|_| {
+            entry_count.set(entry_count.get() + 1);
+        })
+        .map(rrg_proto::get_system_logs::Entry::from_lossy);
+
+    let mut total_entry_count = 0;
+
+    for batch in crate::gzchunked::encode(entries) {
+        let batch = batch
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(batch);
+        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            blob_sha256,
+            entry_count: entry_count.get(),
+        })?;
+
+        total_entry_count += entry_count.get();
+        entry_count.set(0);
+    }
+
+    if let Some(error) = error.take() {
+        return Err(crate::session::Error::action(error));
+    }
+
+    Ok(total_entry_count)
+}
+
+/// Formats the given time as a `journalctl` timestamp specification.
+#[cfg(target_os = "linux")]
+fn timespec(time: std::time::SystemTime) -> String {
+    // Arguments are verified not to be before the epoch, so the fallback is
+    // never going to be used.
+    let since_epoch = time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    format!("@{}.{:06}", since_epoch.as_secs(), since_epoch.subsec_micros())
+}
+
+#[cfg(target_os = "linux")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_system_logs::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let since = if proto.has_since() {
+            let since = rrg_proto::try_from_timestamp(proto.take_since())
+                .map_err(|error| ParseArgsError::invalid_field("since", error))?;
+            Some(since)
+        } else {
+            None
+        };
+
+        let until = if proto.has_until() {
+            let until = rrg_proto::try_from_timestamp(proto.take_until())
+                .map_err(|error| ParseArgsError::invalid_field("until", error))?;
+            Some(until)
+        } else {
+            None
+        };
+
+        let unit = match proto.take_unit() {
+            unit if unit.is_empty() => None,
+            unit => Some(unit),
+        };
+
+        let max_priority = match proto.max_priority {
+            Some(priority) if priority > MAX_PRIORITY => {
+                return Err(ParseArgsError::invalid_field("max_priority", PriorityError {
+                    priority,
+                }));
+            }
+            max_priority => max_priority,
+        };
+
+        let max_entries = match proto.max_entries() {
+            0 => usize::MAX,
+            max_entries => usize::try_from(max_entries).unwrap_or(usize::MAX),
+        };
+
+        Ok(Args {
+            since,
+            until,
+            unit,
+            max_priority,
+            max_entries,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_system_logs::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_blob_sha256(self.blob_sha256.into());
+        proto.set_entry_count(self.entry_count as u64);
+
+        proto
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl FromLossy<export::Entry> for rrg_proto::get_system_logs::Entry {
+
+    fn from_lossy(entry: export::Entry) -> Self {
+        let mut proto = Self::default();
+
+        let realtime_micros = entry.get_str("__REALTIME_TIMESTAMP")
+            .and_then(|micros| micros.parse().ok());
+        if let Some(realtime_micros) = realtime_micros {
+            proto.set_realtime_micros(realtime_micros);
+        }
+
+        if let Some(unit) = entry.get_str("_SYSTEMD_UNIT") {
+            proto.set_unit(String::from(unit));
+        }
+
+        let pid = entry.get_str("_PID")
+            .and_then(|pid| pid.parse().ok());
+        if let Some(pid) = pid {
+            proto.set_pid(pid);
+        }
+
+        let priority = entry.get_str("PRIORITY")
+            .and_then(|priority| priority.parse().ok());
+        if let Some(priority) = priority {
+            proto.set_priority(priority);
+        }
+
+        // Messages are supposed to be human-readable but nothing prevents them
+        // from being binary. The raw value is still available among fields.
+        if let Some(message) = entry.get("MESSAGE") {
+            proto.set_message(String::from_utf8_lossy(message).into_owned());
+        }
+
+        for (name, value) in entry.fields {
+            proto.mut_fields().entry(name).or_insert(value);
+        }
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with invalid priority.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct PriorityError {
+    priority: u32,
+}
+
+#[cfg(target_os = "linux")]
+impl std::fmt::Display for PriorityError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "provided priority ({}) is lower than allowed ({})",
+            self.priority, MAX_PRIORITY
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::error::Error for PriorityError {
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Serializes a binary field with the given name and value.
+    fn binary_field(name: &str, value: &[u8]) -> Vec<u8> {
+        let mut field = Vec::new();
+        field.extend_from_slice(name.as_bytes());
+        field.push(b'\n');
+        field.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        field.extend_from_slice(value);
+        field.push(b'\n');
+        field
+    }
+
+    #[test]
+    fn handle_max_entries() {
+        let args = Args {
+            since: None,
+            until: None,
+            unit: None,
+            max_priority: None,
+            max_entries: 1,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // The system might not have any journal entries (e.g. in containers),
+        // so we only verify the limit.
+        assert!(entries(&session).len() <= 1);
+    }
+
+    #[test]
+    fn send_entries_binary() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"\
+__REALTIME_TIMESTAMP=1342540861416351
+_SYSTEMD_UNIT=foo.service
+_PID=1337
+PRIORITY=3
+");
+        export.extend(binary_field("MESSAGE", b"foo\nbar\xff"));
+        export.extend(binary_field("FOO_BLOB", b"\x00\x01\x02"));
+        export.extend_from_slice(b"\n");
+        export.extend_from_slice(b"MESSAGE=baz\n\n");
+
+        let mut session = crate::session::FakeSession::new();
+        let entry_count = send_entries(&mut session, &export[..], usize::MAX)
+            .unwrap();
+        assert_eq!(entry_count, 2);
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].realtime_micros(), 1342540861416351);
+        assert_eq!(entries[0].unit(), "foo.service");
+        assert_eq!(entries[0].pid(), 1337);
+        assert_eq!(entries[0].priority(), 3);
+        assert_eq!(entries[0].message(), "foo\nbar\u{FFFD}");
+        assert_eq!(entries[0].fields()["MESSAGE"], b"foo\nbar\xff");
+        assert_eq!(entries[0].fields()["FOO_BLOB"], b"\x00\x01\x02");
+
+        assert!(!entries[1].has_realtime_micros());
+        assert!(!entries[1].has_unit());
+        assert_eq!(entries[1].message(), "baz");
+    }
+
+    #[test]
+    fn send_entries_max_entries() {
+        let export = b"MESSAGE=foo\n\nMESSAGE=bar\n\nMESSAGE=baz\n\n";
+
+        let mut session = crate::session::FakeSession::new();
+        let entry_count = send_entries(&mut session, &export[..], 2)
+            .unwrap();
+        assert_eq!(entry_count, 2);
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message(), "foo");
+        assert_eq!(entries[1].message(), "bar");
+    }
+
+    #[test]
+    fn send_entries_truncated() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"MESSAGE=foo\n\n");
+        export.extend_from_slice(b"MESSAGE\n");
+        export.extend_from_slice(&1024u64.to_le_bytes());
+        export.extend_from_slice(b"bar");
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(send_entries(&mut session, &export[..], usize::MAX).is_err());
+    }
+
+    #[test]
+    fn timespec_micros() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_micros(1_342_540_861_000_042);
+        assert_eq!(timespec(time), "@1342540861.000042");
+    }
+
+    #[test]
+    fn args_invalid_priority() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_system_logs::Args::new();
+        proto.set_max_priority(8);
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    /// Retrieves all entries sent to the blob sink in the given session.
+    fn entries(
+        session: &crate::session::FakeSession,
+    ) -> Vec<rrg_proto::get_system_logs::Entry> {
+        let blob_count = session.parcel_count(crate::Sink::Blob);
+        let reply_count = session.reply_count();
+        assert_eq!(blob_count, reply_count);
+
+        let chunks = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(crate::blob::Blob::as_bytes);
+
+        let entries = crate::gzchunked::decode(chunks)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let total_entry_count = session.replies::<Item>()
+            .map(|item| item.entry_count)
+            .sum::<usize>();
+
+        assert_eq!(entries.len(), total_entry_count);
+
+        entries
+    }
+}
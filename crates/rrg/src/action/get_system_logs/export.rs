@@ -0,0 +1,311 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser of the systemd journal export format.
+//!
+//! See the [documentation][1] for details about the format.
+//!
+//! [1]: https://systemd.io/JOURNAL_EXPORT_FORMATS/#journal-export-format
+
+use std::io::{BufRead, Read as _};
+
+/// Individual entry of the journal.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// All fields (names and values) of the entry in the order of export.
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl Entry {
+
+    /// Returns the value of the first field with the given name.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.fields.iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Returns the value of the first field with the given name as a string.
+    ///
+    /// Values that are not valid UTF-8 are treated as missing.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        std::str::from_utf8(self.get(name)?).ok()
+    }
+}
+
+/// Streaming reader of journal entries in the export format.
+pub struct Reader<R> {
+    /// Underlying reader of the exported entries.
+    inner: R,
+    /// Whether an error was encountered (after which we do not proceed).
+    failed: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+
+    /// Creates a new reader of entries exported to the given reader.
+    pub fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            failed: false,
+        }
+    }
+
+    /// Reads the next entry or returns `None` if there are no more entries.
+    fn read_entry(&mut self) -> std::io::Result<Option<Entry>> {
+        let mut entry = Entry::default();
+
+        loop {
+            let mut line = Vec::new();
+            if self.inner.read_until(b'\n', &mut line)? == 0 {
+                // The last entry is supposed to be terminated with an empty
+                // line, but we are not strict about it.
+                if entry.fields.is_empty() {
+                    return Ok(None);
+                } else {
+                    return Ok(Some(entry));
+                }
+            }
+
+            if line.pop() != Some(b'\n') {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+
+            // An empty line terminates an entry. We skip additional empty lines
+            // as they would yield empty entries.
+            if line.is_empty() {
+                if entry.fields.is_empty() {
+                    continue;
+                } else {
+                    return Ok(Some(entry));
+                }
+            }
+
+            // Text fields are serialized as `NAME=value` lines whereas binary
+            // fields have the name on a separate line and the value follows
+            // (prefixed with its length).
+            let (name, value) = match line.iter().position(|byte| *byte == b'=') {
+                Some(index) => {
+                    let value = line.split_off(index + 1);
+                    line.pop();
+                    (line, value)
+                }
+                None => {
+                    let value = self.read_binary_value()?;
+                    (line, value)
+                }
+            };
+
+            let name = String::from_utf8(name)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+            entry.fields.push((name, value));
+        }
+    }
+
+    /// Reads a value of a binary field (the part following its name line).
+    ///
+    /// The value is prefixed with a 64-bit little-endian length and terminated
+    /// by a newline.
+    fn read_binary_value(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut len_buf = [0; std::mem::size_of::<u64>()];
+        self.inner.read_exact(&mut len_buf)?;
+        let len = u64::from_le_bytes(len_buf);
+
+        // We do not preallocate the buffer using the length as it comes from
+        // the input and could be arbitrarily large.
+        let mut value = Vec::new();
+        (&mut self.inner).take(len).read_to_end(&mut value)?;
+        if value.len() as u64 != len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut newline_buf = [0; 1];
+        self.inner.read_exact(&mut newline_buf)?;
+        if newline_buf != [b'\n'] {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "binary field value not terminated with a newline",
+            ));
+        }
+
+        Ok(value)
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+
+    type Item = std::io::Result<Entry>;
+
+    fn next(&mut self) -> Option<std::io::Result<Entry>> {
+        if self.failed {
+            return None;
+        }
+
+        // Once we fail, we lose track of where the entries are in the stream,
+        // so there is no point in continuing.
+        let result = self.read_entry().transpose();
+        if let Some(Err(_)) = result {
+            self.failed = true;
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Serializes a binary field with the given name and value.
+    fn binary_field(name: &str, value: &[u8]) -> Vec<u8> {
+        let mut field = Vec::new();
+        field.extend_from_slice(name.as_bytes());
+        field.push(b'\n');
+        field.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        field.extend_from_slice(value);
+        field.push(b'\n');
+        field
+    }
+
+    #[test]
+    fn reader_empty() {
+        let mut reader = Reader::new(&b""[..]);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn reader_text_fields() {
+        let export = b"\
+__CURSOR=s=739ad463348b4ceca5a9e69c95a3c93f;i=4ece7;b=6c7c6013a8674c9b9d9d5d4b3d1b5a8e
+__REALTIME_TIMESTAMP=1342540861416351
+_PID=1
+_SYSTEMD_UNIT=init.scope
+PRIORITY=6
+MESSAGE=Started Foo Bar = Baz.
+
+";
+
+        let entries = Reader::new(&export[..])
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let entry = &entries[0];
+        assert_eq!(entry.fields.len(), 6);
+        assert_eq!(entry.get_str("__REALTIME_TIMESTAMP"), Some("1342540861416351"));
+        assert_eq!(entry.get_str("_PID"), Some("1"));
+        assert_eq!(entry.get_str("_SYSTEMD_UNIT"), Some("init.scope"));
+        assert_eq!(entry.get_str("PRIORITY"), Some("6"));
+        assert_eq!(entry.get_str("MESSAGE"), Some("Started Foo Bar = Baz."));
+        assert_eq!(entry.get("SYSLOG_IDENTIFIER"), None);
+    }
+
+    #[test]
+    fn reader_binary_fields() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"__REALTIME_TIMESTAMP=1342540861421465\n");
+        export.extend(binary_field("MESSAGE", b"foo\nbar"));
+        export.extend(binary_field("BLOB", b"\x00\xff\n\n\x01"));
+        export.extend_from_slice(b"_PID=42\n");
+        export.extend_from_slice(b"\n");
+
+        let entries = Reader::new(&export[..])
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+
+        let entry = &entries[0];
+        assert_eq!(entry.get("MESSAGE"), Some(&b"foo\nbar"[..]));
+        assert_eq!(entry.get("BLOB"), Some(&b"\x00\xff\n\n\x01"[..]));
+        assert_eq!(entry.get_str("BLOB"), None);
+        assert_eq!(entry.get_str("_PID"), Some("42"));
+    }
+
+    #[test]
+    fn reader_binary_field_empty() {
+        let mut export = Vec::new();
+        export.extend(binary_field("FOO", b""));
+        export.extend_from_slice(b"\n");
+
+        let entries = Reader::new(&export[..])
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries, vec![Entry {
+            fields: vec![(String::from("FOO"), Vec::new())],
+        }]);
+    }
+
+    #[test]
+    fn reader_many_entries() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"MESSAGE=foo\n\n");
+        export.extend(binary_field("MESSAGE", b"bar\n"));
+        export.extend_from_slice(b"\n\n");
+        export.extend_from_slice(b"MESSAGE=baz\n");
+
+        let entries = Reader::new(&export[..])
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].get("MESSAGE"), Some(&b"foo"[..]));
+        assert_eq!(entries[1].get("MESSAGE"), Some(&b"bar\n"[..]));
+        assert_eq!(entries[2].get("MESSAGE"), Some(&b"baz"[..]));
+    }
+
+    #[test]
+    fn reader_repeated_fields() {
+        let export = b"FOO=bar\nFOO=baz\n\n";
+
+        let entries = Reader::new(&export[..])
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries[0].fields.len(), 2);
+        assert_eq!(entries[0].get_str("FOO"), Some("bar"));
+    }
+
+    #[test]
+    fn reader_binary_field_truncated() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"MESSAGE\n");
+        export.extend_from_slice(&1337u64.to_le_bytes());
+        export.extend_from_slice(b"foo");
+
+        let mut reader = Reader::new(&export[..]);
+
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn reader_binary_field_huge_len() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"MESSAGE\n");
+        export.extend_from_slice(&u64::MAX.to_le_bytes());
+        export.extend_from_slice(b"foo\n\n");
+
+        let error = Reader::new(&export[..]).next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn reader_binary_field_no_newline() {
+        let mut export = Vec::new();
+        export.extend_from_slice(b"MESSAGE\n");
+        export.extend_from_slice(&3u64.to_le_bytes());
+        export.extend_from_slice(b"fooX\n");
+
+        let error = Reader::new(&export[..]).next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reader_text_field_truncated() {
+        let error = Reader::new(&b"MESSAGE=foo"[..]).next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
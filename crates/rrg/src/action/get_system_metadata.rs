@@ -18,6 +18,20 @@ struct Item {
     fqdn: Option<std::ffi::OsString>,
     /// Estimated time at which the operating system was installed.
     installed: Option<std::time::SystemTime>,
+    /// Release information of the operating system the agent is running on.
+    release: Option<ospect::os::Release>,
+    /// Kernel version of the operating system the agent is running on.
+    kernel_version: Option<String>,
+    /// Time at which the operating system was booted.
+    booted: Option<std::time::SystemTime>,
+    /// Time on the agent at the moment the metadata was collected.
+    agent_time: std::time::SystemTime,
+    /// Timezone of the operating system the agent is running on.
+    timezone: Option<ospect::os::Timezone>,
+    /// Version of the agent itself.
+    agent_version: crate::startup::Version,
+    /// Information about the build of the agent itself.
+    agent_build: crate::version::Build,
 }
 
 impl Item {
@@ -60,6 +74,35 @@ impl Item {
             }
         };
 
+        let release = match ospect::os::release() {
+            Ok(release) => Some(release),
+            Err(error) => {
+                error!("failed to collect system release: {error}");
+                None
+            }
+        };
+        let kernel_version = match ospect::os::kernel_version() {
+            Ok(kernel_version) => Some(kernel_version),
+            Err(error) => {
+                error!("failed to collect system kernel version: {error}");
+                None
+            }
+        };
+        let booted = match ospect::os::booted() {
+            Ok(booted) => Some(booted),
+            Err(error) => {
+                error!("failed to collect system boot time: {error}");
+                None
+            }
+        };
+        let timezone = match ospect::os::timezone() {
+            Ok(timezone) => Some(timezone),
+            Err(error) => {
+                error!("failed to collect system timezone: {error}");
+                None
+            }
+        };
+
         Ok(Item {
             kind: ospect::os::kind(),
             version,
@@ -67,6 +110,13 @@ impl Item {
             hostname,
             fqdn,
             installed,
+            release,
+            kernel_version,
+            booted,
+            agent_time: std::time::SystemTime::now(),
+            timezone,
+            agent_version: crate::startup::Version::from_cargo(),
+            agent_build: crate::version::Build::current(),
         })
     }
 }
@@ -95,6 +145,25 @@ impl crate::response::Item for Item {
         if let Some(installed) = self.installed {
             proto.set_install_time(into_timestamp(installed));
         }
+        if let Some(release) = self.release {
+            proto.set_os_name(release.name);
+            if let Some(version) = release.version {
+                proto.set_os_version(version);
+            }
+        }
+        if let Some(kernel_version) = self.kernel_version {
+            proto.set_kernel_version(kernel_version);
+        }
+        if let Some(booted) = self.booted {
+            proto.set_boot_time(into_timestamp(booted));
+        }
+        proto.set_agent_time(into_timestamp(self.agent_time));
+        if let Some(timezone) = self.timezone {
+            proto.set_timezone_name(timezone.name);
+            proto.set_timezone_utc_offset(timezone.utc_offset);
+        }
+        proto.set_agent_version(self.agent_version.into());
+        proto.set_agent_build(self.agent_build.into());
 
         proto
     }
@@ -112,3 +181,57 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_hostname_not_empty() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        let hostname = item.hostname.as_ref()
+            .expect("no hostname");
+        assert!(!hostname.is_empty());
+    }
+
+    #[test]
+    fn handle_boot_time_after_epoch_and_in_past() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.reply::<Item>(0);
+        let booted = item.booted
+            .expect("no boot time");
+        assert!(booted > std::time::SystemTime::UNIX_EPOCH);
+        assert!(booted < item.agent_time);
+        assert!(item.agent_time <= std::time::SystemTime::now());
+    }
+
+    #[test]
+    fn handle_kernel_version_not_empty() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.reply::<Item>(0);
+        let kernel_version = item.kernel_version.as_ref()
+            .expect("no kernel version");
+        assert!(!kernel_version.is_empty());
+    }
+
+    #[test]
+    fn handle_agent_build() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert!(!item.agent_build.rustc_version.is_empty());
+        assert!(!item.agent_build.target.is_empty());
+        assert!(item.agent_build.features.contains(&"action-get_system_metadata"));
+    }
+}
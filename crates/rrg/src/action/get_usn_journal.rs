@@ -0,0 +1,614 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the NTFS change journal action.
+
+// Record parsing does not depend on the system and so it is tested everywhere
+// but it is used only on Windows.
+#[cfg_attr(not(target_family = "windows"), allow(dead_code))]
+mod usn;
+
+/// Size of the output buffer used for reading the journal.
+#[cfg(target_family = "windows")]
+const READ_BUF_LEN: usize = 64 * 1024; // 64 KiB.
+
+/// Arguments of the `get_usn_journal` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Drive letter of the volume to read the journal of.
+    volume: char,
+    /// Update sequence number to start reading the journal from.
+    start_usn: i64,
+    /// Identifier of the journal instance that `start_usn` refers to.
+    journal_id: Option<u64>,
+}
+
+/// Result of the `get_usn_journal` action.
+#[cfg(target_family = "windows")]
+pub struct Item {
+    /// SHA-256 digest of the record batch sent to the blob sink (if any).
+    blob_sha256: Option<[u8; 32]>,
+    /// Number of records in the batch sent to the blob sink.
+    record_count: usize,
+    /// Update sequence number to continue reading the journal from.
+    next_usn: i64,
+    /// Identifier of the journal instance that was read.
+    journal_id: u64,
+}
+
+/// Handles invocations of the `get_usn_journal` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use sha2::Digest as _;
+    use crate::os::windows::privileges::{with_privilege, SE_BACKUP_NAME};
+
+    let path = format!("\\\\.\\{}:", args.volume);
+
+    let volume = with_privilege(SE_BACKUP_NAME, || open_volume(&path))
+        .map_err(crate::session::Error::action)?
+        .map_err(|error| crate::session::Error::action(OpenError {
+            path,
+            error,
+        }))?;
+
+    let journal = sys::query_journal(&volume)
+        .map_err(crate::session::Error::action)?;
+
+    if let Some(journal_id) = args.journal_id {
+        if journal_id != journal.id {
+            return Err(crate::session::Error::action(JournalIdError {
+                expected: journal_id,
+                actual: journal.id,
+            }));
+        }
+    }
+
+    let start_usn = if args.start_usn == 0 {
+        journal.first_usn
+    } else if args.start_usn < journal.first_usn {
+        // The records have been purged from the journal in the meantime, the
+        // best that we can do is to start from the oldest available one.
+        log::warn! {
+            "USN {} no longer in the journal, starting from {}",
+            args.start_usn, journal.first_usn,
+        };
+        journal.first_usn
+    } else {
+        args.start_usn
+    };
+
+    let records = Records {
+        volume: &volume,
+        journal_id: journal.id,
+        next_usn: start_usn,
+        // We do not want to chase a journal that is constantly being written
+        // to, so we read only up to the state at the time of the query.
+        end_usn: journal.next_usn,
+        pending: std::collections::VecDeque::new(),
+    };
+
+    // See the filesystem timeline action for why `record_count` has to be a
+    // cell. The same applies to `next_usn` (which is the cursor after the last
+    // record consumed by the iterator), `error` (which is set if reading the
+    // journal fails), `limits_error` and the session.
+    let record_count = std::cell::Cell::new(0);
+    let next_usn = std::cell::Cell::new(start_usn);
+    let error = std::cell::Cell::new(None);
+    let limits_error = std::cell::Cell::new(None);
+    let session = std::cell::RefCell::new(session);
+
+    let records = records
+        .map_while(|record| match record {
+            Ok(record) => Some(record),
+            Err(err) => {
+                error.set(Some(err));
+                None
+            }
+        })
+        .map_while(|record| match session.borrow().check_limits() {
+            Ok(()) => Some(record),
+            Err(error) => {
+                limits_error.set(Some(error));
+                None
+            }
+        })
+        .inspect(|(_, record_next_usn)| {
+            record_count.set(record_count.get() + 1);
+            next_usn.set(*record_next_usn);
+        })
+        .map(|(record, _)| rrg_proto::get_usn_journal::Record::from(record));
+
+    let mut sent_any = false;
+
+    for batch in crate::gzchunked::encode(records) {
+        let batch = batch
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(batch);
+        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+
+        let mut session = session.borrow_mut();
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            blob_sha256: Some(blob_sha256),
+            record_count: record_count.get(),
+            next_usn: next_usn.get(),
+            journal_id: journal.id,
+        })?;
+
+        record_count.set(0);
+        sent_any = true;
+    }
+
+    // Even if there were no new records, the server still needs to know where
+    // to continue from in the next invocation.
+    if !sent_any {
+        session.borrow_mut().reply(Item {
+            blob_sha256: None,
+            record_count: 0,
+            next_usn: next_usn.get(),
+            journal_id: journal.id,
+        })?;
+    }
+
+    if let Some(error) = limits_error.take() {
+        log::warn!("aborting change journal collection: {error}");
+        return Err(error);
+    }
+
+    if let Some(error) = error.take() {
+        return Err(crate::session::Error::action(error));
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `get_usn_journal` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Opens the raw volume at the given path for querying its change journal.
+#[cfg(target_family = "windows")]
+fn open_volume(path: &str) -> std::io::Result<std::fs::File> {
+    use std::os::windows::fs::OpenOptionsExt as _;
+    use windows_sys::Win32::Storage::FileSystem::*;
+
+    // The volume is mounted and used by the system, so we have to allow others
+    // to keep reading and writing it.
+    std::fs::OpenOptions::new()
+        .read(true)
+        .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+/// Iterator over records of the change journal.
+///
+/// Each record is paired with the update sequence number to continue reading
+/// the journal from once the record has been processed.
+#[cfg(target_family = "windows")]
+struct Records<'a> {
+    /// Volume to read the journal of.
+    volume: &'a std::fs::File,
+    /// Identifier of the journal instance to read.
+    journal_id: u64,
+    /// Update sequence number to continue reading the journal from.
+    next_usn: i64,
+    /// Update sequence number at which to stop reading the journal.
+    end_usn: i64,
+    /// Records read from the journal but not yet yielded.
+    pending: std::collections::VecDeque<(usn::Record, i64)>,
+}
+
+#[cfg(target_family = "windows")]
+impl<'a> Iterator for Records<'a> {
+
+    type Item = std::io::Result<(usn::Record, i64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.next_usn >= self.end_usn {
+                return None;
+            }
+
+            let buf = match sys::read_journal(self.volume, self.journal_id, self.next_usn) {
+                Ok(buf) => buf,
+                Err(error) => return Some(Err(error)),
+            };
+
+            let records = match usn::parse_records(&buf) {
+                Ok(records) => records,
+                Err(error) => {
+                    use std::io::{Error, ErrorKind};
+                    return Some(Err(Error::new(ErrorKind::InvalidData, error)));
+                }
+            };
+
+            // If there are no more records, the journal returns just the next
+            // update sequence number.
+            if records.records.is_empty() {
+                return None;
+            }
+
+            // The cursor after each record is the number of the record that
+            // follows it and, for the last one, the number returned by the
+            // journal.
+            let cursors = records.records.iter()
+                .skip(1)
+                .map(|record| record.usn)
+                .chain(std::iter::once(records.next_usn))
+                .collect::<Vec<_>>();
+
+            self.pending.extend(records.records.into_iter().zip(cursors));
+
+            self.next_usn = records.next_usn;
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_usn_journal::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let volume = proto.volume();
+        let volume = volume.strip_suffix(':').unwrap_or(volume);
+
+        let volume = match volume.as_bytes() {
+            [] => 'C',
+            [letter] if letter.is_ascii_alphabetic() => {
+                char::from(letter.to_ascii_uppercase())
+            }
+            _ => {
+                return Err(ParseArgsError::invalid_field("volume", VolumeError {
+                    volume: proto.volume().to_string(),
+                }));
+            }
+        };
+
+        let start_usn = i64::try_from(proto.start_usn())
+            .map_err(|error| ParseArgsError::invalid_field("start_usn", error))?;
+
+        let journal_id = match proto.journal_id() {
+            0 => None,
+            journal_id => Some(journal_id),
+        };
+
+        Ok(Args {
+            volume,
+            start_usn,
+            journal_id,
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_usn_journal::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        if let Some(blob_sha256) = self.blob_sha256 {
+            proto.set_blob_sha256(blob_sha256.into());
+        }
+        proto.set_record_count(self.record_count as u64);
+        // Update sequence numbers are never negative in a valid journal.
+        proto.set_next_usn(self.next_usn.max(0) as u64);
+        proto.set_journal_id(self.journal_id);
+
+        proto
+    }
+}
+
+impl From<usn::Record> for rrg_proto::get_usn_journal::Record {
+
+    fn from(record: usn::Record) -> Self {
+        let mut proto = Self::default();
+        proto.set_usn(record.usn.max(0) as u64);
+        proto.set_file_reference_number(record.file_reference_number);
+        proto.set_parent_file_reference_number(record.parent_file_reference_number);
+        if let Ok(nanos) = rrg_proto::nanos(record.timestamp) {
+            if let Ok(nanos) = i64::try_from(nanos) {
+                proto.set_timestamp_nanos(nanos);
+            }
+        }
+        proto.set_reason(record.reason);
+        proto.set_file_attributes(record.file_attributes);
+        proto.set_file_name(record.file_name.to_string_lossy().into_owned());
+        proto.set_major_version(u32::from(record.major_version));
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an invalid volume.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct VolumeError {
+    volume: String,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for VolumeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "invalid volume drive letter: {:?}", self.volume)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for VolumeError {
+}
+
+/// An error indicating that opening the raw volume failed.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct OpenError {
+    /// Path to the volume that was opened.
+    path: String,
+    /// Underlying error returned by the system.
+    error: std::io::Error,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for OpenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.error.kind() == std::io::ErrorKind::PermissionDenied {
+            write! {
+                fmt,
+                "access to volume '{}' denied (reading its change journal \
+                 requires administrator privileges): {}",
+                self.path, self.error
+            }
+        } else {
+            write!(fmt, "failed to open volume '{}': {}", self.path, self.error)
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for OpenError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error indicating that the journal instance is not the expected one.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct JournalIdError {
+    /// Identifier of the journal instance that was expected.
+    expected: u64,
+    /// Identifier of the current journal instance.
+    actual: u64,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for JournalIdError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "journal was recreated (expected id {:#x}, actual id {:#x})",
+            self.expected, self.actual
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for JournalIdError {
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    use std::os::windows::io::AsRawHandle as _;
+
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::IO::*,
+        System::Ioctl::*,
+    };
+
+    /// State of the change journal of a volume.
+    pub struct Journal {
+        /// Identifier of the journal instance.
+        pub id: u64,
+        /// Update sequence number of the oldest available record.
+        pub first_usn: i64,
+        /// Update sequence number of the next record to be written.
+        pub next_usn: i64,
+    }
+
+    /// Queries the state of the change journal of the given volume.
+    pub fn query_journal(volume: &std::fs::File) -> std::io::Result<Journal> {
+        // SAFETY: The structure consists of plain integers for which zero is a
+        // valid value.
+        let mut data = unsafe {
+            std::mem::zeroed::<USN_JOURNAL_DATA_V0>()
+        };
+        let mut data_len = 0;
+
+        // SAFETY: The handle is valid for the lifetime of the file and we pass
+        // the output buffer with its correct size. We verify the result below.
+        let status = unsafe {
+            DeviceIoControl(
+                volume.as_raw_handle(),
+                FSCTL_QUERY_USN_JOURNAL,
+                std::ptr::null(),
+                0,
+                &mut data as *mut USN_JOURNAL_DATA_V0 as *mut std::ffi::c_void,
+                std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+                &mut data_len,
+                std::ptr::null_mut(),
+            )
+        };
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(ERROR_JOURNAL_NOT_ACTIVE as i32) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "change journal is not active on the volume",
+                ));
+            }
+            return Err(error);
+        }
+
+        Ok(Journal {
+            id: data.UsnJournalID,
+            first_usn: data.FirstUsn,
+            next_usn: data.NextUsn,
+        })
+    }
+
+    /// Reads a buffer of journal records starting at the given number.
+    ///
+    /// The returned buffer is in the format expected by [`super::usn::parse_records`].
+    pub fn read_journal(
+        volume: &std::fs::File,
+        journal_id: u64,
+        start_usn: i64,
+    ) -> std::io::Result<Vec<u8>> {
+        let input = READ_USN_JOURNAL_DATA_V1 {
+            StartUsn: start_usn,
+            ReasonMask: u32::MAX,
+            ReturnOnlyOnClose: 0,
+            // We do not want to wait for new records.
+            Timeout: 0,
+            BytesToWaitFor: 0,
+            UsnJournalID: journal_id,
+            MinMajorVersion: 2,
+            MaxMajorVersion: 3,
+        };
+
+        // Records in the output are 8-byte aligned, so we use a buffer of
+        // `u64` values to keep them aligned in memory as well.
+        let mut buf = vec![0u64; super::READ_BUF_LEN / std::mem::size_of::<u64>()];
+        let mut buf_len = 0;
+
+        // SAFETY: The handle is valid for the lifetime of the file and we pass
+        // the input and output buffers with their correct sizes. We verify the
+        // result below.
+        let status = unsafe {
+            DeviceIoControl(
+                volume.as_raw_handle(),
+                FSCTL_READ_USN_JOURNAL,
+                &input as *const READ_USN_JOURNAL_DATA_V1 as *const std::ffi::c_void,
+                std::mem::size_of::<READ_USN_JOURNAL_DATA_V1>() as u32,
+                buf.as_mut_ptr() as *mut std::ffi::c_void,
+                super::READ_BUF_LEN as u32,
+                &mut buf_len,
+                std::ptr::null_mut(),
+            )
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let buf = buf.into_iter()
+            .flat_map(u64::to_le_bytes)
+            .take(buf_len as usize)
+            .collect();
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn args_volume() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_usn_journal::Args::new();
+        assert_eq!(Args::from_proto(proto.clone()).unwrap().volume, 'C');
+
+        proto.set_volume(String::from("d:"));
+        assert_eq!(Args::from_proto(proto.clone()).unwrap().volume, 'D');
+
+        proto.set_volume(String::from("DE"));
+        assert!(Args::from_proto(proto.clone()).is_err());
+    }
+
+    #[test]
+    fn args_journal_id() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::get_usn_journal::Args::new();
+        assert_eq!(Args::from_proto(proto.clone()).unwrap().journal_id, None);
+
+        proto.set_journal_id(0xf00);
+        assert_eq!(Args::from_proto(proto.clone()).unwrap().journal_id, Some(0xf00));
+    }
+
+    #[cfg(feature = "test-admin")]
+    #[test]
+    fn handle_file_create() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let volume = tempdir.path().to_str().unwrap()
+            .chars().next().unwrap();
+
+        let journal = sys::query_journal(&open_volume(&format!("\\\\.\\{volume}:")).unwrap())
+            .unwrap();
+
+        let name = format! {
+            "rrg-test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH).unwrap()
+                .as_nanos(),
+        };
+        std::fs::write(tempdir.path().join(&name), b"")
+            .unwrap();
+
+        let args = Args {
+            volume,
+            start_usn: journal.next_usn,
+            journal_id: Some(journal.id),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = session.replies::<Item>().collect::<Vec<_>>();
+        assert!(items.iter().all(|item| item.journal_id == journal.id));
+        assert!(items.last().unwrap().next_usn > journal.next_usn);
+
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| blob.as_bytes());
+
+        let records = crate::gzchunked::decode(blobs)
+            .map(Result::unwrap)
+            .collect::<Vec<rrg_proto::get_usn_journal::Record>>();
+
+        // USN_REASON_FILE_CREATE
+        assert!(records.iter().any(|record| {
+            record.file_name() == name && record.reason() & 0x100 != 0
+        }));
+    }
+}
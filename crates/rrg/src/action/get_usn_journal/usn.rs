@@ -0,0 +1,400 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parsing of change journal records returned by `FSCTL_READ_USN_JOURNAL`.
+//!
+//! See the documentation of [`USN_RECORD_V2`] and [`USN_RECORD_V3`] for the
+//! details of the record layouts.
+//!
+//! [`USN_RECORD_V2`]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-usn_record_v2
+//! [`USN_RECORD_V3`]: https://learn.microsoft.com/en-us/windows/win32/api/winioctl/ns-winioctl-usn_record_v3
+
+/// Size of the common header of all record versions.
+const HEADER_LEN: usize = 8;
+
+/// A single record of the change journal.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Major version of the record.
+    pub major_version: u16,
+    /// Update sequence number of the record.
+    pub usn: i64,
+    /// Reference number of the file that changed.
+    ///
+    /// This is 8 bytes long for version 2 records and 16 bytes long for version
+    /// 3 records.
+    pub file_reference_number: Vec<u8>,
+    /// Reference number of the directory containing the file that changed.
+    pub parent_file_reference_number: Vec<u8>,
+    /// Time of the change.
+    pub timestamp: std::time::SystemTime,
+    /// Bitmask of reasons for the change.
+    pub reason: u32,
+    /// Attributes of the file that changed.
+    pub file_attributes: u32,
+    /// Name of the file that changed.
+    pub file_name: std::ffi::OsString,
+}
+
+/// Records parsed from a single output buffer of `FSCTL_READ_USN_JOURNAL`.
+#[derive(Debug)]
+pub struct Records {
+    /// Update sequence number to continue reading the journal from.
+    pub next_usn: i64,
+    /// Records contained in the buffer.
+    pub records: Vec<Record>,
+}
+
+/// Parses the output buffer of `FSCTL_READ_USN_JOURNAL`.
+///
+/// The buffer starts with the update sequence number of the record following
+/// the returned ones and is followed by the records themselves. Records of
+/// unsupported versions are skipped.
+pub fn parse_records(buf: &[u8]) -> Result<Records, ParseError> {
+    let next_usn = i64::from_le_bytes(array(buf, 0)?);
+
+    let mut records = Vec::new();
+
+    let mut pos = 8;
+    while pos < buf.len() {
+        let header = buf.get(pos..pos + HEADER_LEN)
+            .ok_or(ParseError::InputTooShort)?;
+
+        let len = u32::from_le_bytes(array(header, 0)?) as usize;
+        if len < HEADER_LEN {
+            return Err(ParseError::InvalidRecordLen(len));
+        }
+
+        let record = buf.get(pos..pos + len)
+            .ok_or(ParseError::InputTooShort)?;
+        pos += len;
+
+        match u16::from_le_bytes(array(header, 4)?) {
+            2 => records.push(parse_record_v2(record)?),
+            3 => records.push(parse_record_v3(record)?),
+            version => {
+                log::warn!("skipping change journal record of version {version}");
+            }
+        }
+    }
+
+    Ok(Records {
+        next_usn,
+        records,
+    })
+}
+
+/// Parses a single `USN_RECORD_V2` record.
+fn parse_record_v2(buf: &[u8]) -> Result<Record, ParseError> {
+    Ok(Record {
+        major_version: 2,
+        file_reference_number: buf.get(8..16)
+            .ok_or(ParseError::InputTooShort)?.to_vec(),
+        parent_file_reference_number: buf.get(16..24)
+            .ok_or(ParseError::InputTooShort)?.to_vec(),
+        usn: i64::from_le_bytes(array(buf, 24)?),
+        timestamp: timestamp(i64::from_le_bytes(array(buf, 32)?)),
+        reason: u32::from_le_bytes(array(buf, 40)?),
+        file_attributes: u32::from_le_bytes(array(buf, 52)?),
+        file_name: file_name(buf, 56)?,
+    })
+}
+
+/// Parses a single `USN_RECORD_V3` record.
+fn parse_record_v3(buf: &[u8]) -> Result<Record, ParseError> {
+    Ok(Record {
+        major_version: 3,
+        file_reference_number: buf.get(8..24)
+            .ok_or(ParseError::InputTooShort)?.to_vec(),
+        parent_file_reference_number: buf.get(24..40)
+            .ok_or(ParseError::InputTooShort)?.to_vec(),
+        usn: i64::from_le_bytes(array(buf, 40)?),
+        timestamp: timestamp(i64::from_le_bytes(array(buf, 48)?)),
+        reason: u32::from_le_bytes(array(buf, 56)?),
+        file_attributes: u32::from_le_bytes(array(buf, 68)?),
+        file_name: file_name(buf, 72)?,
+    })
+}
+
+/// Parses the file name described by the length and offset at `pos`.
+///
+/// The name is stored as UTF-16 and its length is given in bytes.
+fn file_name(buf: &[u8], pos: usize) -> Result<std::ffi::OsString, ParseError> {
+    let len = usize::from(u16::from_le_bytes(array(buf, pos)?));
+    let offset = usize::from(u16::from_le_bytes(array(buf, pos + 2)?));
+
+    let name_buf = buf.get(offset..offset + len)
+        .ok_or(ParseError::InvalidFileName)?;
+    if name_buf.len() % 2 != 0 {
+        return Err(ParseError::InvalidFileName);
+    }
+
+    let name_wide = name_buf.chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+
+    // File names on Windows do not have to be valid UTF-16, so we decode them
+    // lossily (on Windows we could preserve them using `OsStringExt` but the
+    // parsing here should work the same everywhere).
+    Ok(char::decode_utf16(name_wide)
+        .map(|char| char.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>()
+        .into())
+}
+
+/// Converts the given `FILETIME` value of a record to a system time.
+fn timestamp(ticks: i64) -> std::time::SystemTime {
+    // Negative timestamps are not valid `FILETIME` values, we clamp them to
+    // the earliest representable time.
+    winreg::time::from_file_time(u64::try_from(ticks).unwrap_or(0))
+}
+
+/// Reads a fixed-size byte array at the given offset.
+fn array<const N: usize>(buf: &[u8], offset: usize) -> Result<[u8; N], ParseError> {
+    let bytes = buf.get(offset..offset + N)
+        .ok_or(ParseError::InputTooShort)?;
+
+    let mut array = [0; N];
+    array.copy_from_slice(bytes);
+
+    Ok(array)
+}
+
+/// An error that can occur when parsing change journal records.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ended before the record was fully parsed.
+    InputTooShort,
+    /// The record has an invalid length.
+    InvalidRecordLen(usize),
+    /// The file name of the record is out of bounds or malformed.
+    InvalidFileName,
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::InputTooShort => write!(fmt, "input too short"),
+            ParseError::InvalidRecordLen(len) => {
+                write!(fmt, "invalid record length: {len}")
+            }
+            ParseError::InvalidFileName => write!(fmt, "invalid file name"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// `FILETIME` value of 2024-01-01 00:00:00 UTC.
+    const TIMESTAMP_TICKS: i64 = 133_485_408_000_000_000;
+
+    /// System time corresponding to [`TIMESTAMP_TICKS`].
+    fn timestamp_time() -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200)
+    }
+
+    /// Encodes the given name as UTF-16 bytes.
+    fn wide(name: &str) -> Vec<u8> {
+        name.encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    /// Builds a fixture `USN_RECORD_V2` record.
+    fn record_v2(usn: i64, frn: u64, parent_frn: u64, reason: u32, name: &str) -> Vec<u8> {
+        let name = wide(name);
+        let len = (60 + name.len()).next_multiple_of(8);
+
+        let mut buf = vec![0; len];
+        buf[0..4].copy_from_slice(&(len as u32).to_le_bytes());
+        buf[4..6].copy_from_slice(&2u16.to_le_bytes());
+        buf[8..16].copy_from_slice(&frn.to_le_bytes());
+        buf[16..24].copy_from_slice(&parent_frn.to_le_bytes());
+        buf[24..32].copy_from_slice(&usn.to_le_bytes());
+        buf[32..40].copy_from_slice(&TIMESTAMP_TICKS.to_le_bytes());
+        buf[40..44].copy_from_slice(&reason.to_le_bytes());
+        buf[52..56].copy_from_slice(&0x20u32.to_le_bytes());
+        buf[56..58].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        buf[58..60].copy_from_slice(&60u16.to_le_bytes());
+        buf[60..60 + name.len()].copy_from_slice(&name);
+        buf
+    }
+
+    /// Builds a fixture `USN_RECORD_V3` record.
+    fn record_v3(usn: i64, frn: u128, parent_frn: u128, reason: u32, name: &str) -> Vec<u8> {
+        let name = wide(name);
+        let len = (76 + name.len()).next_multiple_of(8);
+
+        let mut buf = vec![0; len];
+        buf[0..4].copy_from_slice(&(len as u32).to_le_bytes());
+        buf[4..6].copy_from_slice(&3u16.to_le_bytes());
+        buf[8..24].copy_from_slice(&frn.to_le_bytes());
+        buf[24..40].copy_from_slice(&parent_frn.to_le_bytes());
+        buf[40..48].copy_from_slice(&usn.to_le_bytes());
+        buf[48..56].copy_from_slice(&TIMESTAMP_TICKS.to_le_bytes());
+        buf[56..60].copy_from_slice(&reason.to_le_bytes());
+        buf[68..72].copy_from_slice(&0x10u32.to_le_bytes());
+        buf[72..74].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        buf[74..76].copy_from_slice(&76u16.to_le_bytes());
+        buf[76..76 + name.len()].copy_from_slice(&name);
+        buf
+    }
+
+    /// Builds a fixture output buffer with the given records.
+    fn output(next_usn: i64, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = next_usn.to_le_bytes().to_vec();
+        for record in records {
+            buf.extend_from_slice(record);
+        }
+        buf
+    }
+
+    #[test]
+    fn parse_records_empty() {
+        let records = parse_records(&output(0x1000, &[]))
+            .unwrap();
+
+        assert_eq!(records.next_usn, 0x1000);
+        assert!(records.records.is_empty());
+    }
+
+    #[test]
+    fn parse_records_v2() {
+        let buf = output(0x2000, &[
+            record_v2(0x1000, 0x0001_0000_0000_1234, 0x0005_0000_0000_0005, 0x100, "foo.txt"),
+        ]);
+
+        let records = parse_records(&buf)
+            .unwrap();
+
+        assert_eq!(records.next_usn, 0x2000);
+        assert_eq!(records.records, vec![Record {
+            major_version: 2,
+            usn: 0x1000,
+            file_reference_number: 0x0001_0000_0000_1234u64.to_le_bytes().to_vec(),
+            parent_file_reference_number: 0x0005_0000_0000_0005u64.to_le_bytes().to_vec(),
+            timestamp: timestamp_time(),
+            reason: 0x100,
+            file_attributes: 0x20,
+            file_name: "foo.txt".into(),
+        }]);
+    }
+
+    #[test]
+    fn parse_records_v3() {
+        let buf = output(0x2000, &[
+            record_v3(0x1000, 0xABCD_0000_0000_0000_1234, 0x5, 0x80000100, "bar"),
+        ]);
+
+        let records = parse_records(&buf)
+            .unwrap();
+
+        assert_eq!(records.records, vec![Record {
+            major_version: 3,
+            usn: 0x1000,
+            file_reference_number: 0xABCD_0000_0000_0000_1234u128.to_le_bytes().to_vec(),
+            parent_file_reference_number: 0x5u128.to_le_bytes().to_vec(),
+            timestamp: timestamp_time(),
+            reason: 0x80000100,
+            file_attributes: 0x10,
+            file_name: "bar".into(),
+        }]);
+    }
+
+    #[test]
+    fn parse_records_mixed_versions() {
+        let buf = output(0x3000, &[
+            record_v2(0x1000, 1, 5, 0x100, "foo"),
+            record_v3(0x1050, 2, 5, 0x200, "bar"),
+            record_v2(0x10A0, 3, 5, 0x400, "baz"),
+        ]);
+
+        let records = parse_records(&buf)
+            .unwrap();
+
+        let summary = records.records.iter()
+            .map(|record| (record.major_version, record.usn, record.file_name.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            (2, 0x1000, "foo".into()),
+            (3, 0x1050, "bar".into()),
+            (2, 0x10A0, "baz".into()),
+        ]);
+    }
+
+    #[test]
+    fn parse_records_unknown_version() {
+        let mut record_v4 = vec![0; 16];
+        record_v4[0..4].copy_from_slice(&16u32.to_le_bytes());
+        record_v4[4..6].copy_from_slice(&4u16.to_le_bytes());
+
+        let buf = output(0x3000, &[
+            record_v4,
+            record_v2(0x1010, 1, 5, 0x100, "foo"),
+        ]);
+
+        let records = parse_records(&buf)
+            .unwrap();
+
+        assert_eq!(records.records.len(), 1);
+        assert_eq!(records.records[0].usn, 0x1010);
+    }
+
+    #[test]
+    fn parse_records_non_ascii_name() {
+        let buf = output(0x2000, &[
+            record_v2(0x1000, 1, 5, 0x100, "zażółć.txt"),
+        ]);
+
+        let records = parse_records(&buf)
+            .unwrap();
+
+        assert_eq!(records.records[0].file_name, "zażółć.txt");
+    }
+
+    #[test]
+    fn parse_records_truncated_record() {
+        let mut buf = output(0x2000, &[
+            record_v2(0x1000, 1, 5, 0x100, "foo"),
+        ]);
+        buf.truncate(buf.len() - 8);
+
+        assert_eq!(parse_records(&buf).unwrap_err(), ParseError::InputTooShort);
+    }
+
+    #[test]
+    fn parse_records_invalid_len() {
+        let mut record = record_v2(0x1000, 1, 5, 0x100, "foo");
+        record[0..4].copy_from_slice(&0u32.to_le_bytes());
+
+        assert_eq! {
+            parse_records(&output(0x2000, &[record])).unwrap_err(),
+            ParseError::InvalidRecordLen(0)
+        };
+    }
+
+    #[test]
+    fn parse_records_invalid_name() {
+        let mut record = record_v2(0x1000, 1, 5, 0x100, "foo");
+        // The name length points past the end of the record.
+        record[56..58].copy_from_slice(&0x100u16.to_le_bytes());
+
+        assert_eq! {
+            parse_records(&output(0x2000, &[record])).unwrap_err(),
+            ParseError::InvalidFileName
+        };
+    }
+
+    #[test]
+    fn parse_records_no_next_usn() {
+        assert_eq!(parse_records(&[0x00, 0x10]).unwrap_err(), ParseError::InputTooShort);
+    }
+}
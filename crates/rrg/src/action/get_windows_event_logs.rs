@@ -0,0 +1,522 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the Windows Event Log action.
+
+#[cfg(target_family = "windows")]
+mod event_data;
+
+/// Arguments of the `get_windows_event_logs` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Name of the channel to collect the events from.
+    channel: String,
+    /// XPath expression to filter the events with.
+    xpath: Option<String>,
+    /// Time since which the events should be collected.
+    since: Option<std::time::SystemTime>,
+    /// Time until which the events should be collected.
+    until: Option<std::time::SystemTime>,
+    /// Maximum number of events to collect.
+    max_events: usize,
+    /// Whether to parse the `EventData` section of the events.
+    parse_event_data: bool,
+}
+
+/// Result of the `get_windows_event_logs` action.
+#[cfg(target_family = "windows")]
+pub struct Item {
+    /// SHA-256 digest of the event batch sent to the blob sink.
+    blob_sha256: [u8; 32],
+    /// Number of events in the batch sent to the blob sink.
+    event_count: usize,
+    /// Whether the collection stopped early (because of a limit).
+    truncated: bool,
+}
+
+/// Handles invocations of the `get_windows_event_logs` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use sha2::Digest as _;
+    use crate::os::windows::privileges::{with_privilege, SE_SECURITY_NAME};
+
+    // Querying big channels is expensive, so we do not even start if the
+    // deadline passed while the request waited to be handled.
+    session.check_deadline()?;
+
+    // The `Security` channel can be read only with the security privilege that
+    // is disabled by default (even for administrators). Other channels do not
+    // need it and so we do not fail if it cannot be enabled.
+    let events = if args.channel.eq_ignore_ascii_case("Security") {
+        with_privilege(SE_SECURITY_NAME, || winevt::query(&query(&args)))
+            .map_err(crate::session::Error::action)?
+    } else {
+        winevt::query(&query(&args))
+    };
+
+    let events = events
+        .map_err(|error| crate::session::Error::action(QueryError {
+            channel: args.channel.clone(),
+            error,
+        }))?;
+
+    // See the filesystem timeline action for why `event_count` has to be a
+    // cell. The same applies to `error`, which is set if we fail to fetch the
+    // events (and at which point the iterator stops), and to `limits_error`
+    // and the session (which is used to check limits while fetching events).
+    let event_count = std::cell::Cell::new(0);
+    let error = std::cell::Cell::new(None);
+    let limits_error = std::cell::Cell::new(None);
+    let truncated = std::cell::Cell::new(false);
+    let session = std::cell::RefCell::new(session);
+
+    let events = events
+        .map_while(|event| match event {
+            Ok(event) => Some(event),
+            Err(err) => {
+                error.set(Some(err));
+                None
+            }
+        })
+        .filter_map(|event| match event.render_xml() {
+            Ok(xml) => Some(xml),
+            Err(error) => {
+                log::warn!("failed to render event: {error}");
+                None
+            }
+        })
+        .take(args.max_events)
+        .map_while(|xml| match session.borrow().check_limits() {
+            Ok(()) => Some(xml),
+            Err(error) => {
+                limits_error.set(Some(error));
+                truncated.set(true);
+                None
+            }
+        })
+        .inspect(|_| {
+            event_count.set(event_count.get() + 1);
+        })
+        .map(|xml| {
+            let mut proto = rrg_proto::get_windows_event_logs::Event::default();
+
+            if args.parse_event_data {
+                for (index, data) in event_data::parse(&xml).into_iter().enumerate() {
+                    let name = data.name.unwrap_or_else(|| index.to_string());
+                    proto.mut_event_data().insert(name, data.value);
+                }
+            }
+
+            proto.set_xml(xml);
+            proto
+        });
+
+    for batch in crate::gzchunked::encode(events) {
+        let batch = batch
+            .map_err(crate::session::Error::action)?;
+
+        let blob = crate::blob::Blob::from(batch);
+        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+
+        let mut session = session.borrow_mut();
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            blob_sha256,
+            event_count: event_count.get(),
+            truncated: truncated.get(),
+        })?;
+
+        event_count.set(0);
+    }
+
+    if let Some(error) = limits_error.take() {
+        log::warn!("aborting event log collection: {error}");
+        return Err(error);
+    }
+
+    if let Some(error) = error.take() {
+        return Err(crate::session::Error::action(QueryError {
+            channel: args.channel,
+            error,
+        }));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_family = "windows"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Builds a structured XML query corresponding to the given arguments.
+///
+/// See the [documentation][1] for details about the query format.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/wes/queryschema-schema
+#[cfg(target_family = "windows")]
+fn query(args: &Args) -> String {
+    let channel = escape(&args.channel);
+
+    let mut query = String::new();
+    query.push_str("<QueryList>");
+    query.push_str(&format!("<Query Id=\"0\" Path=\"{channel}\">"));
+    query.push_str(&format! {
+        "<Select Path=\"{channel}\">{}</Select>",
+        escape(args.xpath.as_deref().unwrap_or("*")),
+    });
+
+    // Arbitrary XPath expressions cannot be easily combined with the time
+    // range conditions, so instead we suppress events outside of the range.
+    if let Some(since) = args.since {
+        query.push_str(&format! {
+            "<Suppress Path=\"{channel}\">{}</Suppress>",
+            escape(&format!("*[System[TimeCreated[@SystemTime < '{}']]]", timestamp(since))),
+        });
+    }
+    if let Some(until) = args.until {
+        query.push_str(&format! {
+            "<Suppress Path=\"{channel}\">{}</Suppress>",
+            escape(&format!("*[System[TimeCreated[@SystemTime > '{}']]]", timestamp(until))),
+        });
+    }
+
+    query.push_str("</Query>");
+    query.push_str("</QueryList>");
+
+    query
+}
+
+/// Escapes special XML characters in the given string.
+#[cfg(target_family = "windows")]
+fn escape(string: &str) -> String {
+    let mut result = String::with_capacity(string.len());
+
+    for char in string.chars() {
+        match char {
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '&' => result.push_str("&amp;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(char),
+        }
+    }
+
+    result
+}
+
+/// Formats the given time as an ISO 8601 UTC timestamp with milliseconds.
+///
+/// Arguments are verified not to be before the epoch, so such times are never
+/// going to be formatted.
+#[cfg(target_family = "windows")]
+fn timestamp(time: std::time::SystemTime) -> String {
+    let since_epoch = time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+
+    format! {
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        since_epoch.subsec_millis(),
+    }
+}
+
+/// Converts the number of days since the epoch to a proleptic Gregorian date.
+///
+/// See the [original][1] description of the algorithm for more details.
+///
+/// [1]: https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+#[cfg(target_family = "windows")]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_windows_event_logs::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let since = if proto.has_since() {
+            let since = rrg_proto::try_from_timestamp(proto.take_since())
+                .map_err(|error| ParseArgsError::invalid_field("since", error))?;
+            Some(since)
+        } else {
+            None
+        };
+
+        let until = if proto.has_until() {
+            let until = rrg_proto::try_from_timestamp(proto.take_until())
+                .map_err(|error| ParseArgsError::invalid_field("until", error))?;
+            Some(until)
+        } else {
+            None
+        };
+
+        let xpath = match proto.take_xpath() {
+            xpath if xpath.is_empty() => None,
+            xpath => Some(xpath),
+        };
+
+        let max_events = match proto.max_events() {
+            0 => usize::MAX,
+            max_events => usize::try_from(max_events).unwrap_or(usize::MAX),
+        };
+
+        Ok(Args {
+            channel: proto.take_channel(),
+            xpath,
+            since,
+            until,
+            max_events,
+            parse_event_data: proto.parse_event_data(),
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_windows_event_logs::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_blob_sha256(self.blob_sha256.into());
+        proto.set_event_count(self.event_count as u64);
+        proto.set_truncated(self.truncated);
+
+        proto
+    }
+}
+
+/// An error indicating that querying events of a channel failed.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct QueryError {
+    /// Name of the channel that was queried.
+    channel: String,
+    /// Underlying error returned by the system.
+    error: std::io::Error,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for QueryError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Some channels (most notably `Security`) can be read only with elevated
+        // privileges, so we make it explicit in such cases.
+        if self.error.kind() == std::io::ErrorKind::PermissionDenied {
+            write! {
+                fmt,
+                "access to channel '{}' denied (reading it might require \
+                 administrator privileges): {}",
+                self.channel, self.error
+            }
+        } else {
+            write!(fmt, "failed to query channel '{}': {}", self.channel, self.error)
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for QueryError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(target_family = "windows")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn timestamp_epoch() {
+        assert_eq!(timestamp(std::time::UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn timestamp_millis() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(1_709_210_096_789);
+        assert_eq!(timestamp(time), "2024-02-29T12:34:56.789Z");
+    }
+
+    #[test]
+    fn query_escaped() {
+        let args = Args {
+            channel: String::from("Foo & Bar"),
+            xpath: Some(String::from("*[System[EventID < 10]]")),
+            since: None,
+            until: None,
+            max_events: usize::MAX,
+            parse_event_data: false,
+        };
+
+        assert_eq! {
+            query(&args),
+            "<QueryList>\
+             <Query Id=\"0\" Path=\"Foo &amp; Bar\">\
+             <Select Path=\"Foo &amp; Bar\">*[System[EventID &lt; 10]]</Select>\
+             </Query>\
+             </QueryList>"
+        };
+    }
+
+    #[test]
+    fn query_time_range() {
+        let args = Args {
+            channel: String::from("Application"),
+            xpath: None,
+            since: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1)),
+            until: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(2)),
+            max_events: usize::MAX,
+            parse_event_data: false,
+        };
+
+        let query = query(&args);
+        assert!(query.contains("<Select Path=\"Application\">*</Select>"));
+        assert!(query.contains("@SystemTime &lt; &apos;1970-01-01T00:00:01.000Z&apos;"));
+        assert!(query.contains("@SystemTime &gt; &apos;1970-01-01T00:00:02.000Z&apos;"));
+    }
+
+    #[test]
+    fn handle_invalid_channel() {
+        let args = Args {
+            channel: String::from("rrg-non-existent-channel"),
+            xpath: None,
+            since: None,
+            until: None,
+            max_events: usize::MAX,
+            parse_event_data: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_report_event() {
+        use windows_sys::Win32::System::EventLog::*;
+
+        // We use a unique message to find exactly the event we reported.
+        let message = format! {
+            "rrg-test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH).unwrap()
+                .as_nanos(),
+        };
+
+        let source = "rrg-test".encode_utf16().chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+        let message_wide = message.encode_utf16().chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+
+        // SAFETY: We register a source on the local machine. Sources that are
+        // not installed are logged to the `Application` channel [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-registereventsourcew
+        let handle_log = unsafe {
+            RegisterEventSourceW(std::ptr::null(), source.as_ptr())
+        };
+        assert!(!handle_log.is_null());
+
+        let strings = [message_wide.as_ptr()];
+
+        // SAFETY: We pass the valid handle and a single null-terminated string
+        // as described in the documentation [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-reporteventw
+        let status = unsafe {
+            ReportEventW(
+                handle_log,
+                EVENTLOG_INFORMATION_TYPE,
+                0,
+                1337,
+                std::ptr::null_mut(),
+                strings.len() as u16,
+                0,
+                strings.as_ptr(),
+                std::ptr::null(),
+            )
+        };
+
+        // SAFETY: We deregister the source we registered above.
+        unsafe {
+            DeregisterEventSource(handle_log);
+        }
+        assert_ne!(status, windows_sys::Win32::Foundation::FALSE);
+
+        let args = Args {
+            channel: String::from("Application"),
+            xpath: Some(format!("*[EventData[Data='{message}']]")),
+            since: None,
+            until: None,
+            max_events: usize::MAX,
+            parse_event_data: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let events = events(&session);
+        assert_eq!(events.len(), 1);
+
+        let event = &events[0];
+        assert!(event.xml().contains("rrg-test"));
+        assert!(event.xml().contains(">1337</EventID>"));
+        assert_eq!(event.event_data()["0"], message);
+    }
+
+    /// Retrieves all events sent to the blob sink in the given session.
+    fn events(
+        session: &crate::session::FakeSession,
+    ) -> Vec<rrg_proto::get_windows_event_logs::Event> {
+        let blob_count = session.parcel_count(crate::Sink::Blob);
+        let reply_count = session.reply_count();
+        assert_eq!(blob_count, reply_count);
+
+        let chunks = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(crate::blob::Blob::as_bytes);
+
+        let events = crate::gzchunked::decode(chunks)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let total_event_count = session.replies::<Item>()
+            .map(|item| item.event_count)
+            .sum();
+
+        assert_eq!(events.len(), total_event_count);
+
+        events
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for extracting the `EventData` section of rendered events.
+//!
+//! The XML rendered by the system is very regular, so instead of using a full
+//! XML parser we look only for the few elements that we care about.
+
+/// Individual data item of the `EventData` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Data {
+    /// Name of the data item (if it has one).
+    pub name: Option<String>,
+    /// Value of the data item.
+    pub value: String,
+}
+
+/// Parses data items of the `EventData` section of the given event XML.
+///
+/// Events without the section yield no items.
+pub fn parse(xml: &str) -> Vec<Data> {
+    let Some(section) = section(xml) else {
+        return Vec::new();
+    };
+
+    let mut items = Vec::new();
+    let mut rest = section;
+
+    while let Some(start) = find_tag(rest, "Data") {
+        rest = &rest[start + "<Data".len()..];
+
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let attrs = &rest[..tag_end];
+        rest = &rest[tag_end + 1..];
+
+        let name = attr(attrs, "Name").map(unescape);
+
+        // Empty items can be rendered as self-closing elements.
+        let value = if attrs.ends_with('/') {
+            String::new()
+        } else {
+            let Some(value_end) = rest.find("</Data>") else {
+                break;
+            };
+            let value = unescape(&rest[..value_end]);
+            rest = &rest[value_end + "</Data>".len()..];
+            value
+        };
+
+        items.push(Data {
+            name,
+            value,
+        });
+    }
+
+    items
+}
+
+/// Returns the content of the `EventData` section of the given event XML.
+fn section(xml: &str) -> Option<&str> {
+    let start = find_tag(xml, "EventData")?;
+    let rest = &xml[start + "<EventData".len()..];
+
+    let tag_end = rest.find('>')?;
+    if rest[..tag_end].ends_with('/') {
+        return Some("");
+    }
+    let rest = &rest[tag_end + 1..];
+
+    let end = rest.find("</EventData>")?;
+    Some(&rest[..end])
+}
+
+/// Finds the start of the first opening tag with the given name.
+fn find_tag(xml: &str, name: &str) -> Option<usize> {
+    let mut offset = 0;
+
+    while let Some(index) = xml[offset..].find('<') {
+        let start = offset + index;
+        let rest = &xml[start + 1..];
+
+        // We need to make sure that the tag is not just prefixed with the name
+        // we are looking for (e.g. `<DataItem>` when looking for `<Data>`).
+        if let Some(rest) = rest.strip_prefix(name) {
+            if rest.starts_with(['>', '/', ' ', '\t', '\r', '\n']) {
+                return Some(start);
+            }
+        }
+
+        offset = start + 1;
+    }
+
+    None
+}
+
+/// Returns the raw value of the attribute with the given name.
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+
+    loop {
+        let rest_trimmed = rest.trim_start();
+        let (attr_name, after_name) = rest_trimmed.split_once('=')?;
+        let after_name = after_name.trim_start();
+
+        let quote = after_name.chars().next()
+            .filter(|char| *char == '\'' || *char == '"')?;
+        let value_len = after_name[1..].find(quote)?;
+        let value = &after_name[1..1 + value_len];
+
+        if attr_name.trim() == name {
+            return Some(value);
+        }
+
+        rest = &after_name[1 + value_len + 1..];
+    }
+}
+
+/// Replaces XML character and entity references in the given string.
+fn unescape(string: &str) -> String {
+    let mut result = String::with_capacity(string.len());
+    let mut rest = string;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let reference = &rest[1..end];
+
+        let char = match reference {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = if let Some(hex) = reference.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = reference.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+            }
+        };
+
+        match char {
+            Some(char) => {
+                result.push(char);
+                rest = &rest[end + 1..];
+            }
+            // Invalid references are left as they are.
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_named() {
+        let xml = "\
+<Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'>\
+<System><Provider Name='Microsoft-Windows-Security-Auditing'/><EventID>4624</EventID></System>\
+<EventData>\
+<Data Name='SubjectUserSid'>S-1-5-18</Data>\
+<Data Name='TargetUserName'>foo</Data>\
+<Data Name='IpAddress'>-</Data>\
+</EventData>\
+</Event>";
+
+        assert_eq!(parse(xml), vec![
+            Data { name: Some(String::from("SubjectUserSid")), value: String::from("S-1-5-18") },
+            Data { name: Some(String::from("TargetUserName")), value: String::from("foo") },
+            Data { name: Some(String::from("IpAddress")), value: String::from("-") },
+        ]);
+    }
+
+    #[test]
+    fn parse_unnamed() {
+        let xml = "\
+<Event><System/><EventData><Data>foo</Data><Data>bar</Data><Binary>00FF</Binary></EventData></Event>";
+
+        assert_eq!(parse(xml), vec![
+            Data { name: None, value: String::from("foo") },
+            Data { name: None, value: String::from("bar") },
+        ]);
+    }
+
+    #[test]
+    fn parse_empty_items() {
+        let xml = "\
+<Event><EventData><Data Name='Foo'/><Data Name=\"Bar\"></Data></EventData></Event>";
+
+        assert_eq!(parse(xml), vec![
+            Data { name: Some(String::from("Foo")), value: String::new() },
+            Data { name: Some(String::from("Bar")), value: String::new() },
+        ]);
+    }
+
+    #[test]
+    fn parse_escaped() {
+        let xml = "\
+<Event><EventData><Data Name='A&amp;B'>&lt;foo&gt; &quot;bar&quot; &#x41;&#66; &bogus;</Data></EventData></Event>";
+
+        assert_eq!(parse(xml), vec![
+            Data { name: Some(String::from("A&B")), value: String::from("<foo> \"bar\" AB &bogus;") },
+        ]);
+    }
+
+    #[test]
+    fn parse_no_event_data() {
+        let xml = "\
+<Event><System/><UserData><Data>foo</Data></UserData></Event>";
+
+        assert_eq!(parse(xml), vec![]);
+    }
+
+    #[test]
+    fn parse_empty_event_data() {
+        assert_eq!(parse("<Event><System/><EventData/></Event>"), vec![]);
+        assert_eq!(parse("<Event><System/><EventData></EventData></Event>"), vec![]);
+    }
+
+    #[test]
+    fn parse_similar_tags() {
+        let xml = "\
+<Event><EventDataFoo><Data>foo</Data></EventDataFoo><EventData><DataItem>bar</DataItem><Data>baz</Data></EventData></Event>";
+
+        assert_eq!(parse(xml), vec![
+            Data { name: None, value: String::from("baz") },
+        ]);
+    }
+}
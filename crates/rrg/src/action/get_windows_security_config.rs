@@ -0,0 +1,395 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the Windows security configuration action.
+//!
+//! The configuration is collected in independent areas (see [`Area`]). Failure
+//! to collect one of them (e.g. because of insufficient permissions) does not
+//! fail the action: it is reported as an item and the other areas are still
+//! collected.
+
+#[cfg(target_family = "windows")]
+mod audit;
+
+#[cfg(target_family = "windows")]
+mod defender;
+
+#[cfg(target_family = "windows")]
+mod firewall;
+
+/// A result of the `get_windows_security_config` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+enum Item {
+    /// Configuration of a firewall profile.
+    FirewallProfile(FirewallProfile),
+    /// Summary of firewall rules defined in a single store.
+    FirewallRules(FirewallRules),
+    /// Status of Microsoft Defender Antivirus.
+    DefenderStatus(DefenderStatus),
+    /// Exclusions configured for Microsoft Defender Antivirus.
+    DefenderExclusions(DefenderExclusions),
+    /// Audit policy of a single audit subcategory.
+    AuditPolicy(AuditPolicy),
+    /// Area of the configuration that could not be collected.
+    AreaError {
+        /// Area that could not be collected.
+        area: Area,
+        /// Error that occurred when collecting the area.
+        error: std::io::Error,
+    },
+}
+
+/// Area of the security configuration collected by the action.
+#[cfg(target_family = "windows")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Area {
+    /// Windows Firewall profiles.
+    FirewallProfiles,
+    /// Windows Firewall rules.
+    FirewallRules,
+    /// Status of Microsoft Defender Antivirus.
+    DefenderStatus,
+    /// Exclusions of Microsoft Defender Antivirus.
+    DefenderExclusions,
+    /// Local audit policy.
+    AuditPolicy,
+}
+
+/// Windows Firewall profile.
+#[cfg(target_family = "windows")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Profile {
+    /// Used for networks with access to the domain controller.
+    Domain,
+    /// Used for networks marked as private.
+    Private,
+    /// Used for all the other networks.
+    Public,
+}
+
+/// Action taken by the firewall.
+#[cfg(target_family = "windows")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FirewallAction {
+    /// Traffic is allowed.
+    Allow,
+    /// Traffic is blocked.
+    Block,
+}
+
+/// Store in which firewall rules are defined.
+#[cfg(target_family = "windows")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RuleStore {
+    /// Rules defined locally on the machine.
+    Local,
+    /// Rules defined by Group Policy.
+    Policy,
+}
+
+/// Effective configuration of a firewall profile.
+#[cfg(target_family = "windows")]
+#[derive(Debug, PartialEq, Eq)]
+struct FirewallProfile {
+    /// Profile the configuration is of.
+    profile: Profile,
+    /// Whether the firewall is enabled for the profile.
+    enabled: bool,
+    /// Action taken for inbound traffic not matching any rule.
+    default_inbound_action: FirewallAction,
+    /// Action taken for outbound traffic not matching any rule.
+    default_outbound_action: FirewallAction,
+    /// Whether notifications about blocked programs are disabled.
+    notifications_disabled: bool,
+    /// Whether any of the settings is enforced by Group Policy.
+    policy_enforced: bool,
+}
+
+/// Summary of firewall rules defined in a single store.
+#[cfg(target_family = "windows")]
+#[derive(Debug, PartialEq, Eq)]
+struct FirewallRules {
+    /// Store the rules are defined in.
+    store: RuleStore,
+    /// Total number of rules in the store.
+    total_count: u64,
+    /// Number of enabled rules in the store.
+    enabled_count: u64,
+    /// Number of enabled rules allowing inbound traffic.
+    enabled_inbound_allow_count: u64,
+    /// Number of enabled rules blocking inbound traffic.
+    enabled_inbound_block_count: u64,
+    /// Number of enabled rules allowing outbound traffic.
+    enabled_outbound_allow_count: u64,
+    /// Number of enabled rules blocking outbound traffic.
+    enabled_outbound_block_count: u64,
+    /// Number of rules that could not be parsed.
+    malformed_count: u64,
+}
+
+/// Status of Microsoft Defender Antivirus.
+#[cfg(target_family = "windows")]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DefenderStatus {
+    /// Whether the antivirus is enabled.
+    antivirus_enabled: bool,
+    /// Whether the antimalware service is enabled.
+    antimalware_service_enabled: bool,
+    /// Whether real-time protection is enabled.
+    real_time_protection_enabled: bool,
+    /// Whether behavior monitoring is enabled.
+    behavior_monitor_enabled: bool,
+    /// Whether tamper protection is enabled.
+    tamper_protected: bool,
+    /// Age of the antivirus signatures (in days).
+    signature_age_days: Option<u32>,
+    /// Time at which the antivirus signatures were last updated.
+    signature_last_updated: Option<std::time::SystemTime>,
+    /// Version of the antivirus signatures.
+    signature_version: Option<String>,
+    /// Version of the antimalware product.
+    product_version: Option<String>,
+}
+
+/// Exclusions configured for Microsoft Defender Antivirus.
+///
+/// All exclusions are kept verbatim, exactly as they are configured.
+#[cfg(target_family = "windows")]
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DefenderExclusions {
+    /// Excluded files and directories.
+    paths: Vec<String>,
+    /// Excluded file name extensions.
+    extensions: Vec<String>,
+    /// Processes whose file activity is excluded.
+    processes: Vec<String>,
+    /// Excluded IP addresses.
+    ip_addresses: Vec<String>,
+}
+
+/// Audit policy of a single audit subcategory.
+#[cfg(target_family = "windows")]
+#[derive(Debug, PartialEq, Eq)]
+struct AuditPolicy {
+    /// Name of the category the subcategory belongs to.
+    category: String,
+    /// Name of the subcategory.
+    subcategory: String,
+    /// Identifier (GUID) of the subcategory.
+    subcategory_guid: String,
+    /// Whether successful events of the subcategory are audited.
+    audit_success: bool,
+    /// Whether failed events of the subcategory are audited.
+    audit_failure: bool,
+}
+
+/// Function collecting items of a single area of the configuration.
+#[cfg(target_family = "windows")]
+type Collect = fn() -> std::io::Result<Vec<Item>>;
+
+/// Handles invocations of the `get_windows_security_config` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let areas: [(Area, Collect); 5] = [
+        (Area::FirewallProfiles, self::firewall::profiles),
+        (Area::FirewallRules, self::firewall::rules),
+        (Area::DefenderStatus, self::defender::status),
+        (Area::DefenderExclusions, self::defender::exclusions),
+        (Area::AuditPolicy, self::audit::policies),
+    ];
+
+    for (area, collect) in areas {
+        match collect() {
+            Ok(items) => {
+                for item in items {
+                    session.reply(item)?;
+                }
+            }
+            Err(error) => {
+                log::warn!("failed to collect {area:?}: {error}");
+                session.reply(Item::AreaError { area, error })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `get_windows_security_config` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_family = "windows")]
+impl From<Area> for rrg_proto::get_windows_security_config::Area {
+
+    fn from(area: Area) -> rrg_proto::get_windows_security_config::Area {
+        use rrg_proto::get_windows_security_config::Area::*;
+
+        match area {
+            Area::FirewallProfiles => FIREWALL_PROFILES,
+            Area::FirewallRules => FIREWALL_RULES,
+            Area::DefenderStatus => DEFENDER_STATUS,
+            Area::DefenderExclusions => DEFENDER_EXCLUSIONS,
+            Area::AuditPolicy => AUDIT_POLICY,
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl From<Profile> for rrg_proto::get_windows_security_config::Profile {
+
+    fn from(profile: Profile) -> rrg_proto::get_windows_security_config::Profile {
+        use rrg_proto::get_windows_security_config::Profile::*;
+
+        match profile {
+            Profile::Domain => DOMAIN,
+            Profile::Private => PRIVATE,
+            Profile::Public => PUBLIC,
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl From<FirewallAction> for rrg_proto::get_windows_security_config::FirewallAction {
+
+    fn from(action: FirewallAction) -> rrg_proto::get_windows_security_config::FirewallAction {
+        use rrg_proto::get_windows_security_config::FirewallAction::*;
+
+        match action {
+            FirewallAction::Allow => ALLOW,
+            FirewallAction::Block => BLOCK,
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl From<RuleStore> for rrg_proto::get_windows_security_config::RuleStore {
+
+    fn from(store: RuleStore) -> rrg_proto::get_windows_security_config::RuleStore {
+        use rrg_proto::get_windows_security_config::RuleStore::*;
+
+        match store {
+            RuleStore::Local => LOCAL,
+            RuleStore::Policy => POLICY,
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_windows_security_config::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_windows_security_config::Result::new();
+
+        match self {
+            Item::FirewallProfile(profile) => {
+                let profile_proto = proto.mut_firewall_profile();
+                profile_proto.set_profile(profile.profile.into());
+                profile_proto.set_enabled(profile.enabled);
+                profile_proto.set_default_inbound_action(profile.default_inbound_action.into());
+                profile_proto.set_default_outbound_action(profile.default_outbound_action.into());
+                profile_proto.set_notifications_disabled(profile.notifications_disabled);
+                profile_proto.set_policy_enforced(profile.policy_enforced);
+            }
+            Item::FirewallRules(rules) => {
+                let rules_proto = proto.mut_firewall_rules();
+                rules_proto.set_store(rules.store.into());
+                rules_proto.set_total_count(rules.total_count);
+                rules_proto.set_enabled_count(rules.enabled_count);
+                rules_proto.set_enabled_inbound_allow_count(rules.enabled_inbound_allow_count);
+                rules_proto.set_enabled_inbound_block_count(rules.enabled_inbound_block_count);
+                rules_proto.set_enabled_outbound_allow_count(rules.enabled_outbound_allow_count);
+                rules_proto.set_enabled_outbound_block_count(rules.enabled_outbound_block_count);
+                rules_proto.set_malformed_count(rules.malformed_count);
+            }
+            Item::DefenderStatus(status) => {
+                let status_proto = proto.mut_defender_status();
+                status_proto.set_antivirus_enabled(status.antivirus_enabled);
+                status_proto.set_antimalware_service_enabled(status.antimalware_service_enabled);
+                status_proto.set_real_time_protection_enabled(status.real_time_protection_enabled);
+                status_proto.set_behavior_monitor_enabled(status.behavior_monitor_enabled);
+                status_proto.set_tamper_protected(status.tamper_protected);
+                if let Some(signature_age_days) = status.signature_age_days {
+                    status_proto.set_signature_age_days(signature_age_days);
+                }
+                if let Some(signature_last_updated) = status.signature_last_updated {
+                    status_proto.set_signature_last_updated(rrg_proto::into_timestamp(signature_last_updated));
+                }
+                if let Some(signature_version) = status.signature_version {
+                    status_proto.set_signature_version(signature_version);
+                }
+                if let Some(product_version) = status.product_version {
+                    status_proto.set_product_version(product_version);
+                }
+            }
+            Item::DefenderExclusions(exclusions) => {
+                let exclusions_proto = proto.mut_defender_exclusions();
+                exclusions_proto.set_paths(exclusions.paths);
+                exclusions_proto.set_extensions(exclusions.extensions);
+                exclusions_proto.set_processes(exclusions.processes);
+                exclusions_proto.set_ip_addresses(exclusions.ip_addresses);
+            }
+            Item::AuditPolicy(policy) => {
+                let policy_proto = proto.mut_audit_policy();
+                policy_proto.set_category(policy.category);
+                policy_proto.set_subcategory(policy.subcategory);
+                policy_proto.set_subcategory_guid(policy.subcategory_guid);
+                policy_proto.set_audit_success(policy.audit_success);
+                policy_proto.set_audit_failure(policy.audit_failure);
+            }
+            Item::AreaError { area, error } => {
+                let error_proto = proto.mut_area_error();
+                error_proto.set_area(area.into());
+                error_proto.set_permission_denied(error.kind() == std::io::ErrorKind::PermissionDenied);
+                error_proto.set_error(error.to_string());
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_defender_status() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        // Defender might be missing or disabled (e.g. on Windows Server or if
+        // a third-party antivirus is installed), in which case the area should
+        // be cleanly reported as failed rather than fail the whole action.
+        let mut statuses = session.replies::<Item>().filter(|item| match item {
+            Item::DefenderStatus(_) => true,
+            Item::AreaError { area, .. } => *area == Area::DefenderStatus,
+            _ => false,
+        });
+        assert!(statuses.next().is_some());
+        assert!(statuses.next().is_none());
+
+        // Reading the firewall configuration from the registry does not need
+        // any special permissions, so the profiles should be always there.
+        let profile_count = session.replies::<Item>()
+            .filter(|item| matches!(item, Item::FirewallProfile(_)))
+            .count();
+        assert_eq!(profile_count, 3);
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::core::GUID;
+use windows_sys::Win32::Security::Authentication::Identity::*;
+
+use super::{AuditPolicy, Item};
+
+/// Returns items with the system audit policy of all audit subcategories.
+///
+/// Querying the policy requires the `SeSecurityPrivilege` privilege which is
+/// enabled for the duration of the query.
+pub fn policies() -> std::io::Result<Vec<Item>> {
+    use crate::os::windows::privileges::{with_privilege, SE_SECURITY_NAME};
+
+    with_privilege(SE_SECURITY_NAME, query_policies)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::PermissionDenied, error))?
+}
+
+/// Queries the system audit policy of all audit subcategories.
+fn query_policies() -> std::io::Result<Vec<Item>> {
+    let mut items = Vec::new();
+
+    let mut categories = Buffer::<GUID>::new();
+    // SAFETY: This is just an FFI call as described in the docs [1]. Upon
+    // success, the buffer is allocated by the system and we own it.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/ntsecapi/nf-ntsecapi-auditenumeratecategories
+    let status = unsafe {
+        AuditEnumerateCategories(&mut categories.ptr, &mut categories.len)
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    for category in categories.as_slice() {
+        let category_name = category_name(category)?;
+
+        let mut subcategories = Buffer::<GUID>::new();
+        // SAFETY: This is just an FFI call as described in the docs [1]. Upon
+        // success, the buffer is allocated by the system and we own it.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/ntsecapi/nf-ntsecapi-auditenumeratesubcategories
+        let status = unsafe {
+            AuditEnumerateSubCategories(
+                category,
+                0,
+                &mut subcategories.ptr,
+                &mut subcategories.len,
+            )
+        };
+        if status == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        if subcategories.as_slice().is_empty() {
+            continue;
+        }
+
+        let mut policies = Buffer::<AUDIT_POLICY_INFORMATION>::new();
+        policies.len = subcategories.len;
+        // SAFETY: This is just an FFI call as described in the docs [1]. The
+        // buffer with subcategories is valid and has the given length. Upon
+        // success, the system allocates the buffer with one policy for each of
+        // the subcategories (so it has the same length) and we own it.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/ntsecapi/nf-ntsecapi-auditquerysystempolicy
+        let status = unsafe {
+            AuditQuerySystemPolicy(
+                subcategories.ptr,
+                subcategories.len,
+                &mut policies.ptr,
+            )
+        };
+        if status == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        for policy in policies.as_slice() {
+            let subcategory_guid = &policy.AuditSubCategoryGuid;
+            let (audit_success, audit_failure) = flags(policy.AuditingInformation);
+
+            items.push(Item::AuditPolicy(AuditPolicy {
+                category: category_name.clone(),
+                subcategory: subcategory_name(subcategory_guid)?,
+                subcategory_guid: guid(subcategory_guid),
+                audit_success,
+                audit_failure,
+            }));
+        }
+    }
+
+    Ok(items)
+}
+
+/// Returns the display name of the audit category with the given identifier.
+fn category_name(guid: &GUID) -> std::io::Result<String> {
+    let mut name = Buffer::<u16>::new();
+    // SAFETY: This is just an FFI call as described in the docs [1]. Upon
+    // success, the null-terminated string is allocated by the system and we
+    // own it.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/ntsecapi/nf-ntsecapi-auditlookupcategorynamew
+    let status = unsafe {
+        AuditLookupCategoryNameW(guid, &mut name.ptr)
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(name.to_string_lossy())
+}
+
+/// Returns the display name of the audit subcategory with the given identifier.
+fn subcategory_name(guid: &GUID) -> std::io::Result<String> {
+    let mut name = Buffer::<u16>::new();
+    // SAFETY: This is just an FFI call as described in the docs [1]. Upon
+    // success, the null-terminated string is allocated by the system and we
+    // own it.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/ntsecapi/nf-ntsecapi-auditlookupsubcategorynamew
+    let status = unsafe {
+        AuditLookupSubCategoryNameW(guid, &mut name.ptr)
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(name.to_string_lossy())
+}
+
+/// Splits the auditing information of a policy into success and failure flags.
+fn flags(info: u32) -> (bool, bool) {
+    let success = info & POLICY_AUDIT_EVENT_SUCCESS as u32 != 0;
+    let failure = info & POLICY_AUDIT_EVENT_FAILURE as u32 != 0;
+
+    (success, failure)
+}
+
+/// Formats the given GUID in its canonical lowercase form.
+fn guid(guid: &GUID) -> String {
+    let data4 = guid.data4;
+    format! {
+        "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        guid.data1, guid.data2, guid.data3,
+        data4[0], data4[1],
+        data4[2], data4[3], data4[4], data4[5], data4[6], data4[7],
+    }
+}
+
+/// Buffer allocated by the audit API.
+///
+/// The buffer is freed using [`AuditFree`] when dropped.
+struct Buffer<T> {
+    /// Pointer to the buffer (null if the buffer was not allocated).
+    ptr: *mut T,
+    /// Number of elements of the buffer (unused for null-terminated strings).
+    len: u32,
+}
+
+impl<T> Buffer<T> {
+
+    /// Creates a new, not yet allocated buffer.
+    fn new() -> Buffer<T> {
+        Buffer {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    /// Returns the elements of the buffer.
+    fn as_slice(&self) -> &[T] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+
+        // SAFETY: The buffer was allocated by the system with `len` elements
+        // and it stays valid until we free it on drop.
+        unsafe {
+            std::slice::from_raw_parts(self.ptr, self.len as usize)
+        }
+    }
+}
+
+impl Buffer<u16> {
+
+    /// Converts a null-terminated wide string in the buffer to a string.
+    fn to_string_lossy(&self) -> String {
+        if self.ptr.is_null() {
+            return String::new();
+        }
+
+        // SAFETY: The buffer holds a null-terminated string allocated by the
+        // system, so we can read it until the null character.
+        let wide = unsafe {
+            let mut len = 0;
+            while *self.ptr.add(len) != 0 {
+                len += 1;
+            }
+            std::slice::from_raw_parts(self.ptr, len)
+        };
+
+        String::from_utf16_lossy(wide)
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+
+        // SAFETY: The buffer was allocated by the audit API and is not used
+        // after this point.
+        unsafe {
+            AuditFree(self.ptr.cast());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn flags_none() {
+        assert_eq!(flags(0), (false, false));
+    }
+
+    #[test]
+    fn flags_success_and_failure() {
+        assert_eq!(flags(1), (true, false));
+        assert_eq!(flags(2), (false, true));
+        assert_eq!(flags(3), (true, true));
+    }
+
+    #[test]
+    fn guid_canonical() {
+        let guid = guid(&GUID {
+            data1: 0x0cce9210,
+            data2: 0x69ae,
+            data3: 0x11d9,
+            data4: [0xbe, 0xd3, 0x50, 0x50, 0x54, 0x50, 0x30, 0x30],
+        });
+        assert_eq!(guid, "0cce9210-69ae-11d9-bed3-505054503030");
+    }
+
+    #[cfg(feature = "test-admin")]
+    #[test]
+    fn policies_ok() {
+        let items = policies()
+            .unwrap();
+
+        // "Security State Change" is one of the subcategories that exist on all
+        // the systems (although its name might be localized, its GUID is not).
+        assert!(items.iter().any(|item| match item {
+            Item::AuditPolicy(policy) => {
+                policy.subcategory_guid == "0cce9210-69ae-11d9-bed3-505054503030"
+            }
+            _ => false,
+        }));
+    }
+}
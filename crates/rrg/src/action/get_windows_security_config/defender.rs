@@ -0,0 +1,191 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::OsStr;
+
+use super::{DefenderExclusions, DefenderStatus, Item};
+
+/// WMI namespace with Microsoft Defender classes.
+const NAMESPACE: &str = "root\\Microsoft\\Windows\\Defender";
+
+/// WQL query for the status of Microsoft Defender Antivirus.
+const STATUS_QUERY: &str = "\
+SELECT AntivirusEnabled, AMServiceEnabled, RealTimeProtectionEnabled, \
+       BehaviorMonitorEnabled, IsTamperProtected, AntivirusSignatureAge, \
+       AntivirusSignatureLastUpdated, AntivirusSignatureVersion, \
+       AMProductVersion \
+FROM MSFT_MpComputerStatus";
+
+/// WQL query for the exclusions of Microsoft Defender Antivirus.
+const EXCLUSIONS_QUERY: &str = "\
+SELECT ExclusionPath, ExclusionExtension, ExclusionProcess, ExclusionIpAddress \
+FROM MSFT_MpPreference";
+
+/// Returns an item with the status of Microsoft Defender Antivirus.
+pub fn status() -> std::io::Result<Vec<Item>> {
+    let row = single_row(STATUS_QUERY)?;
+    Ok(vec![Item::DefenderStatus(DefenderStatus::from_row(&row))])
+}
+
+/// Returns an item with the exclusions of Microsoft Defender Antivirus.
+pub fn exclusions() -> std::io::Result<Vec<Item>> {
+    let row = single_row(EXCLUSIONS_QUERY)?;
+    Ok(vec![Item::DefenderExclusions(DefenderExclusions::from_row(&row))])
+}
+
+/// Executes the given query in the Defender namespace and returns its only row.
+fn single_row(query: &str) -> std::io::Result<wmi::QueryRow> {
+    let query = wmi::query(query)?
+        .namespace(NAMESPACE);
+
+    let mut rows = query.rows()?;
+    match rows.next() {
+        Some(row) => row,
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no Microsoft Defender instance",
+        )),
+    }
+}
+
+impl DefenderStatus {
+
+    /// Creates a status from a `MSFT_MpComputerStatus` row.
+    ///
+    /// Missing boolean columns are assumed to be disabled features.
+    fn from_row(row: &wmi::QueryRow) -> DefenderStatus {
+        let bool = |name: &str| match row.get(OsStr::new(name)) {
+            Some(wmi::QueryValue::Bool(value)) => *value,
+            _ => false,
+        };
+        let string = |name: &str| match row.get(OsStr::new(name)) {
+            Some(wmi::QueryValue::String(value)) => Some(value.to_string_lossy().into_owned()),
+            _ => None,
+        };
+
+        // The age is declared as `uint32` but WMI might give it to us as any
+        // kind of integer.
+        let signature_age_days = match row.get(OsStr::new("AntivirusSignatureAge")) {
+            Some(wmi::QueryValue::U32(value)) => Some(*value),
+            Some(wmi::QueryValue::I32(value)) => u32::try_from(*value).ok(),
+            Some(wmi::QueryValue::U16(value)) => Some(u32::from(*value)),
+            Some(wmi::QueryValue::I16(value)) => u32::try_from(*value).ok(),
+            Some(wmi::QueryValue::U64(value)) => u32::try_from(*value).ok(),
+            Some(wmi::QueryValue::I64(value)) => u32::try_from(*value).ok(),
+            _ => None,
+        };
+
+        let signature_last_updated = match row.get(OsStr::new("AntivirusSignatureLastUpdated")) {
+            Some(wmi::QueryValue::DateTime(time)) => Some(*time),
+            _ => None,
+        };
+
+        DefenderStatus {
+            antivirus_enabled: bool("AntivirusEnabled"),
+            antimalware_service_enabled: bool("AMServiceEnabled"),
+            real_time_protection_enabled: bool("RealTimeProtectionEnabled"),
+            behavior_monitor_enabled: bool("BehaviorMonitorEnabled"),
+            tamper_protected: bool("IsTamperProtected"),
+            signature_age_days,
+            signature_last_updated,
+            signature_version: string("AntivirusSignatureVersion"),
+            product_version: string("AMProductVersion"),
+        }
+    }
+}
+
+impl DefenderExclusions {
+
+    /// Creates exclusions from a `MSFT_MpPreference` row.
+    ///
+    /// Exclusions are kept exactly as they are returned by WMI. Columns are not
+    /// set (rather than empty) if there are no exclusions of the given kind.
+    fn from_row(row: &wmi::QueryRow) -> DefenderExclusions {
+        let strings = |name: &str| match row.get(OsStr::new(name)) {
+            Some(wmi::QueryValue::Array(values)) => values.iter()
+                .filter_map(|value| match value {
+                    wmi::QueryValue::String(value) => Some(value.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .collect(),
+            Some(wmi::QueryValue::String(value)) => vec![value.to_string_lossy().into_owned()],
+            _ => Vec::new(),
+        };
+
+        DefenderExclusions {
+            paths: strings("ExclusionPath"),
+            extensions: strings("ExclusionExtension"),
+            processes: strings("ExclusionProcess"),
+            ip_addresses: strings("ExclusionIpAddress"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn status_from_row() {
+        let mut row = wmi::QueryRow::new();
+        row.insert("AntivirusEnabled".into(), wmi::QueryValue::Bool(true));
+        row.insert("AMServiceEnabled".into(), wmi::QueryValue::Bool(true));
+        row.insert("RealTimeProtectionEnabled".into(), wmi::QueryValue::Bool(false));
+        row.insert("BehaviorMonitorEnabled".into(), wmi::QueryValue::Bool(true));
+        row.insert("IsTamperProtected".into(), wmi::QueryValue::Bool(true));
+        row.insert("AntivirusSignatureAge".into(), wmi::QueryValue::U32(3));
+        row.insert("AntivirusSignatureLastUpdated".into(), wmi::QueryValue::DateTime(
+            std::time::UNIX_EPOCH,
+        ));
+        row.insert("AntivirusSignatureVersion".into(), wmi::QueryValue::String(
+            "1.421.123.0".into(),
+        ));
+        row.insert("AMProductVersion".into(), wmi::QueryValue::String(
+            "4.18.24090.11".into(),
+        ));
+
+        assert_eq!(DefenderStatus::from_row(&row), DefenderStatus {
+            antivirus_enabled: true,
+            antimalware_service_enabled: true,
+            real_time_protection_enabled: false,
+            behavior_monitor_enabled: true,
+            tamper_protected: true,
+            signature_age_days: Some(3),
+            signature_last_updated: Some(std::time::UNIX_EPOCH),
+            signature_version: Some(String::from("1.421.123.0")),
+            product_version: Some(String::from("4.18.24090.11")),
+        });
+    }
+
+    #[test]
+    fn status_from_row_empty() {
+        let row = wmi::QueryRow::new();
+        assert_eq!(DefenderStatus::from_row(&row), DefenderStatus::default());
+    }
+
+    #[test]
+    fn exclusions_from_row_verbatim() {
+        let mut row = wmi::QueryRow::new();
+        row.insert("ExclusionPath".into(), wmi::QueryValue::Array(vec![
+            wmi::QueryValue::String("C:\\Users\\*\\AppData\\Local\\Temp\\".into()),
+            wmi::QueryValue::String("%ProgramData%\\Foo".into()),
+        ]));
+        row.insert("ExclusionExtension".into(), wmi::QueryValue::Array(vec![
+            wmi::QueryValue::String(".EXE".into()),
+        ]));
+        row.insert("ExclusionProcess".into(), wmi::QueryValue::None);
+
+        assert_eq!(DefenderExclusions::from_row(&row), DefenderExclusions {
+            paths: vec![
+                String::from("C:\\Users\\*\\AppData\\Local\\Temp\\"),
+                String::from("%ProgramData%\\Foo"),
+            ],
+            extensions: vec![String::from(".EXE")],
+            processes: vec![],
+            ip_addresses: vec![],
+        });
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::{OsStr, OsString};
+
+use super::{FirewallAction, FirewallProfile, FirewallRules, Item, Profile, RuleStore};
+
+/// Key with the local firewall configuration.
+const LOCAL_KEY: &str = "SYSTEM\\CurrentControlSet\\Services\\SharedAccess\\Parameters\\FirewallPolicy";
+
+/// Key with the firewall configuration enforced by Group Policy.
+const POLICY_KEY: &str = "SOFTWARE\\Policies\\Microsoft\\WindowsFirewall";
+
+/// Profiles together with names of their subkeys in the local and policy keys.
+///
+/// Note that the private profile is called "standard" in the local key.
+const PROFILES: [(Profile, &str, &str); 3] = [
+    (Profile::Domain, "DomainProfile", "DomainProfile"),
+    (Profile::Private, "StandardProfile", "PrivateProfile"),
+    (Profile::Public, "PublicProfile", "PublicProfile"),
+];
+
+/// Firewall rule (as relevant for the summary).
+#[derive(Debug, PartialEq, Eq)]
+struct Rule {
+    /// Whether the rule is enabled.
+    enabled: bool,
+    /// Whether the rule applies to inbound (rather than outbound) traffic.
+    inbound: bool,
+    /// Action taken for traffic matching the rule.
+    action: FirewallAction,
+}
+
+/// Returns items with the effective configuration of all firewall profiles.
+pub fn profiles() -> std::io::Result<Vec<Item>> {
+    let mut items = Vec::new();
+
+    for (profile, local_name, policy_name) in PROFILES {
+        let local = winreg::PredefinedKey::LocalMachine
+            .open(&winreg::path::join(OsStr::new(LOCAL_KEY), OsStr::new(local_name)))?;
+        let policy = open_if_exists(&winreg::path::join(OsStr::new(POLICY_KEY), OsStr::new(policy_name)))?;
+
+        let mut policy_enforced = false;
+
+        // Settings defined by Group Policy take precedence over local ones. The
+        // defaults are what Windows uses if neither of them is defined [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/security/operating-system-security/network-security/windows-firewall/
+        let mut setting = |name: &str, default: u32| -> std::io::Result<u32> {
+            if let Some(policy) = &policy {
+                if let Some(value) = dword(policy, name)? {
+                    policy_enforced = true;
+                    return Ok(value);
+                }
+            }
+
+            Ok(dword(&local, name)?.unwrap_or(default))
+        };
+
+        let enabled = setting("EnableFirewall", 1)? != 0;
+        let default_inbound_action = action(setting("DefaultInboundAction", 1)?);
+        let default_outbound_action = action(setting("DefaultOutboundAction", 0)?);
+        let notifications_disabled = setting("DisableNotifications", 0)? != 0;
+
+        items.push(Item::FirewallProfile(FirewallProfile {
+            profile,
+            enabled,
+            default_inbound_action,
+            default_outbound_action,
+            notifications_disabled,
+            policy_enforced,
+        }));
+    }
+
+    Ok(items)
+}
+
+/// Returns items with summaries of firewall rules in all rule stores.
+///
+/// The policy store is skipped if no rules are defined by Group Policy.
+pub fn rules() -> std::io::Result<Vec<Item>> {
+    let mut items = Vec::new();
+
+    let stores = [
+        (RuleStore::Local, LOCAL_KEY),
+        (RuleStore::Policy, POLICY_KEY),
+    ];
+
+    for (store, key_name) in stores {
+        let key_name = winreg::path::join(OsStr::new(key_name), OsStr::new("FirewallRules"));
+        let key = match store {
+            RuleStore::Local => winreg::PredefinedKey::LocalMachine.open(&key_name)?,
+            RuleStore::Policy => match open_if_exists(&key_name)? {
+                Some(key) => key,
+                None => continue,
+            },
+        };
+
+        let mut values = Vec::new();
+        for value in key.info()?.values() {
+            let value = match value {
+                Ok(value) => value,
+                Err(error) => {
+                    log::error!("failed to list firewall rule in {key_name:?}: {error}");
+                    continue;
+                }
+            };
+
+            // Rules are always strings, values of other types are going to be
+            // counted as malformed.
+            let string = match value.data {
+                winreg::ValueData::String(string) => string,
+                _ => OsString::new(),
+            };
+            values.push(string.to_string_lossy().into_owned());
+        }
+
+        items.push(Item::FirewallRules(summarize(store, values.iter().map(String::as_str))));
+    }
+
+    Ok(items)
+}
+
+/// Summarizes the given firewall rules (in their registry format).
+fn summarize<'s, I>(store: RuleStore, rules: I) -> FirewallRules
+where
+    I: Iterator<Item = &'s str>,
+{
+    let mut summary = FirewallRules {
+        store,
+        total_count: 0,
+        enabled_count: 0,
+        enabled_inbound_allow_count: 0,
+        enabled_inbound_block_count: 0,
+        enabled_outbound_allow_count: 0,
+        enabled_outbound_block_count: 0,
+        malformed_count: 0,
+    };
+
+    for rule in rules {
+        summary.total_count += 1;
+
+        let rule = match parse_rule(rule) {
+            Some(rule) => rule,
+            None => {
+                summary.malformed_count += 1;
+                continue;
+            }
+        };
+
+        if !rule.enabled {
+            continue;
+        }
+        summary.enabled_count += 1;
+
+        match (rule.inbound, rule.action) {
+            (true, FirewallAction::Allow) => summary.enabled_inbound_allow_count += 1,
+            (true, FirewallAction::Block) => summary.enabled_inbound_block_count += 1,
+            (false, FirewallAction::Allow) => summary.enabled_outbound_allow_count += 1,
+            (false, FirewallAction::Block) => summary.enabled_outbound_block_count += 1,
+        }
+    }
+
+    summary
+}
+
+/// Parses a firewall rule in its registry format.
+///
+/// Rules are stored as strings of `|`-separated `<key>=<value>` fields preceded
+/// by the version of the format, e.g.:
+///
+/// ```text
+/// v2.30|Action=Allow|Active=TRUE|Dir=In|Protocol=6|LPort=3389|Name=RDP|
+/// ```
+///
+/// `None` is returned if the string is not a rule or if any of the fields that
+/// we need is missing.
+fn parse_rule(string: &str) -> Option<Rule> {
+    let mut fields = string.split('|');
+    if !fields.next()?.starts_with('v') {
+        return None;
+    }
+
+    let mut enabled = None;
+    let mut inbound = None;
+    let mut action = None;
+
+    for field in fields {
+        let Some((key, value)) = field.split_once('=') else {
+            continue;
+        };
+
+        match key {
+            "Active" => enabled = match value {
+                "TRUE" => Some(true),
+                "FALSE" => Some(false),
+                _ => return None,
+            },
+            "Dir" => inbound = match value {
+                "In" => Some(true),
+                "Out" => Some(false),
+                _ => return None,
+            },
+            "Action" => action = match value {
+                "Allow" => Some(FirewallAction::Allow),
+                "Block" => Some(FirewallAction::Block),
+                _ => return None,
+            },
+            _ => (),
+        }
+    }
+
+    Some(Rule {
+        enabled: enabled?,
+        inbound: inbound?,
+        action: action?,
+    })
+}
+
+/// Maps the numeric value of a default action setting to the action.
+fn action(value: u32) -> FirewallAction {
+    match value {
+        0 => FirewallAction::Allow,
+        _ => FirewallAction::Block,
+    }
+}
+
+/// Opens the given key of the local machine if it exists.
+fn open_if_exists(key_name: &OsStr) -> std::io::Result<Option<winreg::OpenKey>> {
+    match winreg::PredefinedKey::LocalMachine.open(key_name) {
+        Ok(key) => Ok(Some(key)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Reads a `REG_DWORD` value with the given name of the given key.
+///
+/// `None` is returned if the value does not exist (or is of a different type).
+fn dword(key: &winreg::OpenKey, name: &str) -> std::io::Result<Option<u32>> {
+    match key.value_data(OsStr::new(name)) {
+        Ok(winreg::ValueData::U32(value)) => Ok(Some(value)),
+        Ok(data) => {
+            log::warn!("unexpected firewall setting '{name}' data: {data:?}");
+            Ok(None)
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_rule_ok() {
+        let rule = parse_rule("v2.30|Action=Allow|Active=TRUE|Dir=In|Protocol=6|LPort=3389|Name=RDP|")
+            .unwrap();
+
+        assert_eq!(rule, Rule {
+            enabled: true,
+            inbound: true,
+            action: FirewallAction::Allow,
+        });
+    }
+
+    #[test]
+    fn parse_rule_missing_field() {
+        assert_eq!(parse_rule("v2.30|Action=Block|Dir=Out|Name=Foo|"), None);
+    }
+
+    #[test]
+    fn parse_rule_invalid_value() {
+        assert_eq!(parse_rule("v2.30|Action=Allow|Active=MAYBE|Dir=In|"), None);
+    }
+
+    #[test]
+    fn parse_rule_no_version() {
+        assert_eq!(parse_rule("Action=Allow|Active=TRUE|Dir=In|"), None);
+    }
+
+    #[test]
+    fn summarize_counts() {
+        let rules = [
+            "v2.30|Action=Allow|Active=TRUE|Dir=In|Name=Foo|",
+            "v2.30|Action=Allow|Active=TRUE|Dir=In|Name=Bar|",
+            "v2.30|Action=Block|Active=TRUE|Dir=In|Name=Baz|",
+            "v2.30|Action=Allow|Active=TRUE|Dir=Out|Name=Quux|",
+            "v2.31|Action=Block|Active=TRUE|Dir=Out|Name=Norf|",
+            "v2.30|Action=Allow|Active=FALSE|Dir=In|Name=Thud|",
+            "",
+        ];
+
+        assert_eq!(summarize(RuleStore::Policy, rules.into_iter()), FirewallRules {
+            store: RuleStore::Policy,
+            total_count: 7,
+            enabled_count: 5,
+            enabled_inbound_allow_count: 2,
+            enabled_inbound_block_count: 1,
+            enabled_outbound_allow_count: 1,
+            enabled_outbound_block_count: 1,
+            malformed_count: 1,
+        });
+    }
+
+    #[test]
+    fn action_values() {
+        assert_eq!(action(0), FirewallAction::Allow);
+        assert_eq!(action(1), FirewallAction::Block);
+    }
+
+    #[test]
+    fn profiles_ok() {
+        let items = profiles()
+            .unwrap();
+
+        let profiles = items.iter()
+            .map(|item| match item {
+                Item::FirewallProfile(profile) => profile.profile,
+                _ => panic!("unexpected item: {item:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(profiles, vec![Profile::Domain, Profile::Private, Profile::Public]);
+    }
+}
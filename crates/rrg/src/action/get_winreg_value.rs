@@ -3,6 +3,10 @@
 // Use of this source code is governed by an MIT-style license that can be found
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
+/// Default maximum number of bytes of binary data to return.
+#[cfg(target_family = "windows")]
+const DEFAULT_MAX_BYTES_LEN: usize = 64 * 1024;
+
 #[cfg(target_family = "windows")]
 /// Arguments of the `get_winreg_value` action.
 pub struct Args {
@@ -12,6 +16,10 @@ pub struct Args {
     key: std::ffi::OsString,
     /// Name of the value to get.
     value_name: std::ffi::OsString,
+    /// View of the registry to get the value from.
+    view: winreg::View,
+    /// Maximum number of bytes of binary data to return.
+    max_bytes_len: usize,
 }
 
 /// A result of the `get_winreg_value` action.
@@ -23,6 +31,10 @@ struct Item {
     key: std::ffi::OsString,
     /// Retrieved value.
     value: winreg::Value,
+    /// Data of the value with references to environment variables expanded.
+    expanded_string: Option<std::ffi::OsString>,
+    /// Whether binary data of the value was truncated.
+    truncated: bool,
 }
 
 /// Handles invocations of the `get_winreg_value` action.
@@ -41,11 +53,40 @@ pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
-    let key = args.root.open(&args.key)
-        .map_err(crate::session::Error::action)?;
+    let key = args.root.open_with_view(&args.key, args.view)
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => crate::session::Error::action(KeyNotFoundError {
+                key: args.key.clone(),
+            }),
+            _ => crate::session::Error::action(error),
+        })?;
 
-    let value_data = key.value_data(&args.value_name)
-        .map_err(crate::session::Error::action)?;
+    let mut value_data = key.value_data(&args.value_name)
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => crate::session::Error::action(ValueNotFoundError {
+                value_name: args.value_name.clone(),
+            }),
+            _ => crate::session::Error::action(error),
+        })?;
+
+    let expanded_string = match &value_data {
+        winreg::ValueData::ExpandString(string) => match winreg::expand_string(string) {
+            Ok(expanded_string) => Some(expanded_string),
+            Err(error) => {
+                log::warn!("failed to expand {string:?}: {error}");
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let mut truncated = false;
+    if let winreg::ValueData::Bytes(bytes) = &mut value_data {
+        if bytes.len() > args.max_bytes_len {
+            bytes.truncate(args.max_bytes_len);
+            truncated = true;
+        }
+    }
 
     session.reply(Item {
         root: args.root,
@@ -55,7 +96,9 @@ where
             // TODO(@panhania): Add support for case-correcting the value.
             name: args.value_name,
             data: value_data,
-        }
+        },
+        expanded_string,
+        truncated,
     })?;
 
     Ok(())
@@ -74,10 +117,26 @@ impl crate::request::Args for Args {
             crate::request::ParseArgsError::invalid_field("root", error)
         })?;
 
+        let view = match proto.view.enum_value() {
+            Ok(view) => winreg::View::from(view),
+            Err(value) => {
+                return Err(crate::request::ParseArgsError::invalid_field("view", ViewError {
+                    value,
+                }));
+            }
+        };
+
+        let max_bytes_len = match proto.max_bytes_len() {
+            0 => DEFAULT_MAX_BYTES_LEN,
+            len => usize::try_from(len).unwrap_or(usize::MAX),
+        };
+
         Ok(Args {
             root,
             key: std::ffi::OsString::from(proto.take_key()),
             value_name: std::ffi::OsString::from(proto.take_name()),
+            view,
+            max_bytes_len,
         })
     }
 }
@@ -92,11 +151,75 @@ impl crate::response::Item for Item {
         proto.set_root(self.root.into());
         proto.set_key(self.key.to_string_lossy().into_owned());
         proto.set_value(self.value.into());
+        if let Some(expanded_string) = self.expanded_string {
+            proto.set_expanded_string(expanded_string.to_string_lossy().into_owned());
+        }
+        proto.set_truncated(self.truncated);
 
         proto
     }
 }
 
+/// An error indicating that the key of the requested value does not exist.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct KeyNotFoundError {
+    /// Key that was not found.
+    key: std::ffi::OsString,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for KeyNotFoundError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "key {:?} not found", self.key)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for KeyNotFoundError {
+}
+
+/// An error indicating that the requested value does not exist in its key.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct ValueNotFoundError {
+    /// Name of the value that was not found.
+    value_name: std::ffi::OsString,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for ValueNotFoundError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "value {:?} not found", self.value_name)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for ValueNotFoundError {
+}
+
+/// An error indicating that the action was invoked with an unknown view.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct ViewError {
+    /// Raw value of the view.
+    value: i32,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for ViewError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown registry view: {}", self.value)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for ViewError {
+}
+
 #[cfg(test)]
 #[cfg(target_family = "windows")]
 mod tests {
@@ -109,6 +232,8 @@ mod tests {
             root: winreg::PredefinedKey::LocalMachine,
             key: std::ffi::OsString::from("FOOWARE\\Linux\\GNU"),
             value_name: std::ffi::OsString::from("Version"),
+            view: winreg::View::Default,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -121,6 +246,8 @@ mod tests {
             root: winreg::PredefinedKey::LocalMachine,
             key: std::ffi::OsString::from("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion"),
             value_name: std::ffi::OsString::from("CurrentType"),
+            view: winreg::View::Default,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -140,6 +267,8 @@ mod tests {
             root: winreg::PredefinedKey::LocalMachine,
             key: std::ffi::OsString::from("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion"),
             value_name: std::ffi::OsString::from("DigitalProductId"),
+            view: winreg::View::Default,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -152,4 +281,289 @@ mod tests {
         assert_eq!(item.value.name, "DigitalProductId");
         assert!(matches!(item.value.data, winreg::ValueData::Bytes(_)));
     }
+
+    #[test]
+    fn handle_key_not_found() {
+        let args = Args {
+            root: winreg::PredefinedKey::CurrentUser,
+            key: std::ffi::OsString::from("Software\\rrg-test\\non-existent"),
+            value_name: std::ffi::OsString::from("foo"),
+            view: winreg::View::Default,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, args).unwrap_err();
+
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<KeyNotFoundError>());
+    }
+
+    #[test]
+    fn handle_value_not_found() {
+        let key = TestKey::new("handle_value_not_found");
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, key.args("foo")).unwrap_err();
+
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<ValueNotFoundError>());
+    }
+
+    #[test]
+    fn handle_string_round_trip() {
+        use windows_sys::Win32::System::Registry::REG_SZ;
+
+        let key = TestKey::new("handle_string_round_trip");
+        key.set("foo", REG_SZ, &wide("bar"));
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args("foo")).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.value.data, winreg::ValueData::String("bar".into()));
+        assert_eq!(item.expanded_string, None);
+    }
+
+    #[test]
+    fn handle_expand_string_round_trip() {
+        use windows_sys::Win32::System::Registry::REG_EXPAND_SZ;
+
+        let key = TestKey::new("handle_expand_string_round_trip");
+        key.set("foo", REG_EXPAND_SZ, &wide("%SystemRoot%\\foo"));
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args("foo")).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq! {
+            item.value.data,
+            winreg::ValueData::ExpandString("%SystemRoot%\\foo".into())
+        };
+
+        let system_root = std::env::var_os("SystemRoot").unwrap();
+        assert_eq! {
+            item.expanded_string.as_deref(),
+            Some(std::path::Path::new(&system_root).join("foo").as_os_str())
+        };
+    }
+
+    #[test]
+    fn handle_multi_string_round_trip() {
+        use windows_sys::Win32::System::Registry::REG_MULTI_SZ;
+
+        let mut data = Vec::new();
+        data.extend(wide("foo"));
+        data.extend(wide("bar"));
+        data.extend(wide(""));
+
+        let key = TestKey::new("handle_multi_string_round_trip");
+        key.set("foo", REG_MULTI_SZ, &data);
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args("foo")).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq! {
+            item.value.data,
+            winreg::ValueData::MultiString(vec!["foo".into(), "bar".into()])
+        };
+    }
+
+    #[test]
+    fn handle_u32_round_trip() {
+        use windows_sys::Win32::System::Registry::REG_DWORD;
+
+        let key = TestKey::new("handle_u32_round_trip");
+        key.set("foo", REG_DWORD, &0xdeadbeef_u32.to_le_bytes());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args("foo")).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.value.data, winreg::ValueData::U32(0xdeadbeef));
+    }
+
+    #[test]
+    fn handle_u64_round_trip() {
+        use windows_sys::Win32::System::Registry::REG_QWORD;
+
+        let key = TestKey::new("handle_u64_round_trip");
+        key.set("foo", REG_QWORD, &0xcafebabe_deadbeef_u64.to_le_bytes());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args("foo")).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.value.data, winreg::ValueData::U64(0xcafebabe_deadbeef));
+    }
+
+    #[test]
+    fn handle_bytes_round_trip() {
+        use windows_sys::Win32::System::Registry::REG_BINARY;
+
+        let key = TestKey::new("handle_bytes_round_trip");
+        key.set("foo", REG_BINARY, b"\x00\x01\x02\x03");
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args("foo")).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.value.data, winreg::ValueData::Bytes(b"\x00\x01\x02\x03".to_vec()));
+        assert!(!item.truncated);
+    }
+
+    #[test]
+    fn handle_bytes_truncated() {
+        use windows_sys::Win32::System::Registry::REG_BINARY;
+
+        let key = TestKey::new("handle_bytes_truncated");
+        key.set("foo", REG_BINARY, b"\x00\x01\x02\x03");
+
+        let args = Args {
+            max_bytes_len: 2,
+            ..key.args("foo")
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.value.data, winreg::ValueData::Bytes(b"\x00\x01".to_vec()));
+        assert!(item.truncated);
+    }
+
+    #[test]
+    fn handle_view() {
+        let args = Args {
+            root: winreg::PredefinedKey::LocalMachine,
+            key: std::ffi::OsString::from("SOFTWARE\\Microsoft\\Windows\\CurrentVersion"),
+            value_name: std::ffi::OsString::from("ProgramFilesDir"),
+            view: winreg::View::Registry32,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        let winreg::ValueData::String(ref program_files) = item.value.data else {
+            panic!("unexpected data: {:?}", item.value.data);
+        };
+        // We assume that the tests are not run on 32-bit systems anymore, so
+        // the 32-bit view should point to the folder for 32-bit programs.
+        assert!(program_files.to_string_lossy().ends_with("(x86)"));
+    }
+
+    /// Encodes the given string as null-terminated UTF-16 (as bytes).
+    fn wide(string: &str) -> Vec<u8> {
+        string.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    /// Temporary key under `HKEY_CURRENT_USER\Software\rrg-test`.
+    ///
+    /// The key is deleted (along with its values) when dropped.
+    struct TestKey {
+        /// Path to the key relative to `HKEY_CURRENT_USER`.
+        path: std::ffi::OsString,
+        /// Handle to the open key.
+        raw: windows_sys::Win32::System::Registry::HKEY,
+    }
+
+    impl TestKey {
+
+        /// Creates a new test key with the given name.
+        fn new(name: &str) -> TestKey {
+            use windows_sys::Win32::System::Registry::*;
+
+            let path = std::ffi::OsString::from(format!("Software\\rrg-test\\{name}"));
+
+            let mut raw = std::ptr::null_mut();
+
+            // SAFETY: This is just an FFI call as described in the docs [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regcreatekeyexw
+            let code = unsafe {
+                RegCreateKeyExW(
+                    HKEY_CURRENT_USER,
+                    wide_os(&path).as_ptr(),
+                    0,
+                    std::ptr::null(),
+                    REG_OPTION_VOLATILE,
+                    KEY_ALL_ACCESS,
+                    std::ptr::null(),
+                    &mut raw,
+                    std::ptr::null_mut(),
+                )
+            };
+            assert_eq!(code, windows_sys::Win32::Foundation::ERROR_SUCCESS);
+
+            TestKey {
+                path,
+                raw,
+            }
+        }
+
+        /// Sets the value with the given name to the given raw data.
+        fn set(
+            &self,
+            name: &str,
+            data_type: windows_sys::Win32::System::Registry::REG_VALUE_TYPE,
+            data: &[u8],
+        ) {
+            // SAFETY: This is just an FFI call as described in the docs [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regsetvalueexw
+            let code = unsafe {
+                windows_sys::Win32::System::Registry::RegSetValueExW(
+                    self.raw,
+                    wide_os(std::ffi::OsStr::new(name)).as_ptr(),
+                    0,
+                    data_type,
+                    data.as_ptr(),
+                    data.len() as u32,
+                )
+            };
+            assert_eq!(code, windows_sys::Win32::Foundation::ERROR_SUCCESS);
+        }
+
+        /// Returns action arguments for getting the value with the given name.
+        fn args(&self, value_name: &str) -> Args {
+            Args {
+                root: winreg::PredefinedKey::CurrentUser,
+                key: self.path.clone(),
+                value_name: std::ffi::OsString::from(value_name),
+                view: winreg::View::Default,
+                max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+            }
+        }
+    }
+
+    impl Drop for TestKey {
+
+        fn drop(&mut self) {
+            use windows_sys::Win32::System::Registry::*;
+
+            // SAFETY: These are just FFI calls as described in the docs [1, 2].
+            // We do not verify the results as there is nothing we can do about
+            // failures here anyway.
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regclosekey
+            // [2]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regdeletetreew
+            unsafe {
+                RegCloseKey(self.raw);
+                RegDeleteTreeW(HKEY_CURRENT_USER, wide_os(&self.path).as_ptr());
+            }
+        }
+    }
+
+    /// Encodes the given string as a null-terminated wide string.
+    fn wide_os(string: &std::ffi::OsStr) -> Vec<u16> {
+        use std::os::windows::ffi::OsStrExt as _;
+
+        string.encode_wide().chain(std::iter::once(0)).collect()
+    }
 }
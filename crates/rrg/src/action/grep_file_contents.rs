@@ -71,7 +71,7 @@ impl crate::request::Args for Args {
     fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
         use crate::request::ParseArgsError;
 
-        let path = PathBuf::try_from(proto.take_path())
+        let path = crate::path::from_proto(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
         let regex = regex::Regex::new(proto.regex())
@@ -82,6 +82,10 @@ impl crate::request::Args for Args {
             regex,
         })
     }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.path.as_path()]
+    }
 }
 
 impl crate::response::Item for Item {
@@ -0,0 +1,961 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Sweep of registry locations commonly used for persistence.
+//!
+//! The locations are described by a static [catalog][CATALOG] rather than
+//! hardcoded in the sweep, so covering a new location is a matter of adding an
+//! entry to it (and bumping [`CATALOG_VERSION`]).
+
+/// Version of the catalog of swept locations.
+///
+/// It is reported with every result so that the server can tell which locations
+/// were covered by the agent. It should be bumped whenever the catalog changes.
+#[cfg(target_family = "windows")]
+const CATALOG_VERSION: u32 = 1;
+
+/// Catalog of the registry locations swept by the action.
+#[cfg(target_family = "windows")]
+const CATALOG: &[Location] = &[
+    Location {
+        name: "run",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run",
+        kind: Kind::AllValues,
+        wow64: true,
+    },
+    Location {
+        name: "run_once",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\RunOnce",
+        kind: Kind::AllValues,
+        wow64: true,
+    },
+    Location {
+        name: "run",
+        root: Root::Users,
+        key: "Software\\Microsoft\\Windows\\CurrentVersion\\Run",
+        kind: Kind::AllValues,
+        wow64: false,
+    },
+    Location {
+        name: "run_once",
+        root: Root::Users,
+        key: "Software\\Microsoft\\Windows\\CurrentVersion\\RunOnce",
+        kind: Kind::AllValues,
+        wow64: false,
+    },
+    Location {
+        name: "services",
+        root: Root::LocalMachine,
+        key: "SYSTEM\\CurrentControlSet\\Services",
+        kind: Kind::SubkeyValue("ImagePath"),
+        wow64: false,
+    },
+    Location {
+        name: "winlogon_shell",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon",
+        kind: Kind::Value("Shell"),
+        wow64: false,
+    },
+    Location {
+        name: "winlogon_shell",
+        root: Root::Users,
+        key: "Software\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon",
+        kind: Kind::Value("Shell"),
+        wow64: false,
+    },
+    Location {
+        name: "winlogon_userinit",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon",
+        kind: Kind::ValueList("Userinit", &[',']),
+        wow64: false,
+    },
+    Location {
+        name: "ifeo_debugger",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Image File Execution Options",
+        kind: Kind::SubkeyValue("Debugger"),
+        wow64: true,
+    },
+    Location {
+        name: "appinit_dlls",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Windows",
+        // The list can be delimited with either spaces or commas [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/dlls/secure-boot-and-appinit-dlls
+        kind: Kind::ValueList("AppInit_DLLs", &[',', ' ']),
+        wow64: true,
+    },
+    Location {
+        name: "scheduled_task_com_handler",
+        root: Root::LocalMachine,
+        key: "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Schedule\\TaskCache\\Tasks",
+        kind: Kind::TaskComHandlers,
+        wow64: false,
+    },
+];
+
+/// Registry location used for persistence.
+#[cfg(target_family = "windows")]
+struct Location {
+    /// Name of the location reported with the results.
+    name: &'static str,
+    /// Root of the key of the location.
+    root: Root,
+    /// Key of the location relative to its root.
+    key: &'static str,
+    /// Kind of values at the location defining autoruns.
+    kind: Kind,
+    /// Whether the key is redirected for 32-bit applications and both views
+    /// of the registry should be swept.
+    wow64: bool,
+}
+
+/// Root of a key of a registry location.
+#[cfg(target_family = "windows")]
+enum Root {
+    /// `HKEY_LOCAL_MACHINE`.
+    LocalMachine,
+    /// Every hive loaded under `HKEY_USERS`.
+    Users,
+}
+
+/// Kind of values at a registry location defining autoruns.
+#[cfg(target_family = "windows")]
+enum Kind {
+    /// Every value of the key is a command line.
+    AllValues,
+    /// Value with the given name is a command line.
+    Value(&'static str),
+    /// Value with the given name is a list of paths delimited with any of the
+    /// given characters.
+    ValueList(&'static str, &'static [char]),
+    /// Value with the given name of every subkey of the key is a command line.
+    SubkeyValue(&'static str),
+    /// Every subkey of the key is a scheduled task with actions that may refer
+    /// to COM handlers.
+    TaskComHandlers,
+}
+
+/// Arguments of the `list_autoruns` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Whether to skip computing digests and verifying signatures of targets.
+    skip_targets: bool,
+}
+
+/// A result of the `list_autoruns` action.
+#[cfg(target_family = "windows")]
+struct Item {
+    /// Name of the catalog location the autorun was found at.
+    location: &'static str,
+    /// Root predefined key of the key the autorun was found in.
+    root: winreg::PredefinedKey,
+    /// Key relative to `root` the autorun was found in.
+    key: std::ffi::OsString,
+    /// View of the registry in which the key was found.
+    view: winreg::View,
+    /// Value defining the autorun.
+    value: winreg::Value,
+    /// File executed by the autorun (if it could be determined).
+    target: Option<Target>,
+}
+
+/// File executed by an autorun.
+#[cfg(target_family = "windows")]
+struct Target {
+    /// Normalized path to the file.
+    path: std::path::PathBuf,
+    /// Arguments the file is executed with.
+    arguments: Option<String>,
+    /// SHA-256 digest of the file (if it was computed).
+    sha256: Option<[u8; 32]>,
+    /// Status of the Authenticode signature of the file.
+    signature: SignatureStatus,
+}
+
+/// Status of the Authenticode signature of a file.
+#[cfg(target_family = "windows")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SignatureStatus {
+    /// The signature was not verified.
+    Unverified,
+    /// The file has a valid embedded signature.
+    Signed,
+    /// The file has no embedded signature.
+    Unsigned,
+    /// The file has an embedded signature that is not valid.
+    Invalid,
+}
+
+/// Autorun found at a registry location (before its targets are resolved).
+#[cfg(target_family = "windows")]
+struct Entry {
+    /// Key relative to the root the autorun was found in.
+    key: std::ffi::OsString,
+    /// Value defining the autorun.
+    value: winreg::Value,
+    /// Command lines executed by the autorun.
+    command_lines: Vec<String>,
+}
+
+/// Handles invocations of the `list_autoruns` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Handles invocations of the `list_autoruns` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let system_root = crate::os::windows::path::system_root();
+
+    let user_hives = match user_hives() {
+        Ok(user_hives) => user_hives,
+        Err(error) => {
+            log::error!("failed to list user hives: {error}");
+            Vec::new()
+        }
+    };
+
+    for location in CATALOG {
+        let (root, keys) = match location.root {
+            Root::LocalMachine => {
+                (winreg::PredefinedKey::LocalMachine, vec![std::ffi::OsString::from(location.key)])
+            }
+            Root::Users => {
+                let keys = user_hives.iter()
+                    .map(|hive| winreg::path::join(hive.as_os_str(), std::ffi::OsStr::new(location.key)))
+                    .collect();
+                (winreg::PredefinedKey::Users, keys)
+            }
+        };
+
+        let views: &[winreg::View] = if location.wow64 {
+            &[winreg::View::Registry64, winreg::View::Registry32]
+        } else {
+            &[winreg::View::Default]
+        };
+
+        for key in keys {
+            for view in views {
+                let entries = match sweep(location, root, &key, *view) {
+                    Ok(entries) => entries,
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(error) => {
+                        log::error!("failed to sweep {key:?} ({view:?}): {error}");
+                        continue;
+                    }
+                };
+
+                for entry in entries {
+                    if entry.command_lines.is_empty() {
+                        session.reply(Item {
+                            location: location.name,
+                            root,
+                            key: entry.key,
+                            view: *view,
+                            value: entry.value,
+                            target: None,
+                        })?;
+                        continue;
+                    }
+
+                    for command_line in &entry.command_lines {
+                        let target = Target::new(command_line, &system_root, args.skip_targets);
+
+                        session.reply(Item {
+                            location: location.name,
+                            root,
+                            key: entry.key.clone(),
+                            view: *view,
+                            value: entry.value.clone(),
+                            target: Some(target),
+                        })?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns names of the user hives loaded under `HKEY_USERS`.
+#[cfg(target_family = "windows")]
+fn user_hives() -> std::io::Result<Vec<std::ffi::OsString>> {
+    let users = winreg::PredefinedKey::Users.open(std::ffi::OsStr::new(""))?;
+
+    let mut hives = Vec::new();
+    for hive in users.info()?.subkeys() {
+        let hive = hive?;
+        // Classes hives (e.g. `S-1-5-21-...-1001_Classes`) are merged into the
+        // `Software\Classes` keys of users and do not have locations we sweep.
+        if hive.to_string_lossy().ends_with("_Classes") {
+            continue;
+        }
+        hives.push(hive);
+    }
+
+    Ok(hives)
+}
+
+/// Sweeps the given key of a location for autoruns.
+#[cfg(target_family = "windows")]
+fn sweep(
+    location: &Location,
+    root: winreg::PredefinedKey,
+    key_name: &std::ffi::OsStr,
+    view: winreg::View,
+) -> std::io::Result<Vec<Entry>> {
+    let key = root.open_with_view(key_name, view)?;
+
+    let mut entries = Vec::new();
+
+    match location.kind {
+        Kind::AllValues => {
+            for value in key.info()?.values() {
+                let value = match value {
+                    Ok(value) => value,
+                    Err(error) => {
+                        log::error!("failed to list value for key {key_name:?}: {error}");
+                        continue;
+                    }
+                };
+
+                entries.push(Entry {
+                    key: key_name.to_os_string(),
+                    command_lines: command_lines(&value.data, &[]),
+                    value,
+                });
+            }
+        }
+        Kind::Value(value_name) | Kind::ValueList(value_name, _) => {
+            let separators = match location.kind {
+                Kind::ValueList(_, separators) => separators,
+                _ => &[],
+            };
+
+            let value_name = std::ffi::OsString::from(value_name);
+            let data = match key.value_data(&value_name) {
+                Ok(data) => data,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(entries);
+                }
+                Err(error) => return Err(error),
+            };
+
+            entries.push(Entry {
+                key: key_name.to_os_string(),
+                command_lines: command_lines(&data, separators),
+                value: winreg::Value {
+                    name: value_name,
+                    data,
+                },
+            });
+        }
+        Kind::SubkeyValue(value_name) => {
+            let value_name = std::ffi::OsString::from(value_name);
+
+            for_each_subkey(&key, key_name, |subkey, subkey_name| {
+                let data = match subkey.value_data(&value_name) {
+                    Ok(data) => data,
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+                    Err(error) => {
+                        log::error!("failed to get {value_name:?} of {subkey_name:?}: {error}");
+                        return;
+                    }
+                };
+
+                entries.push(Entry {
+                    key: subkey_name,
+                    command_lines: command_lines(&data, &[]),
+                    value: winreg::Value {
+                        name: value_name.clone(),
+                        data,
+                    },
+                });
+            })?;
+        }
+        Kind::TaskComHandlers => {
+            let actions_name = std::ffi::OsString::from("Actions");
+
+            for_each_subkey(&key, key_name, |subkey, subkey_name| {
+                let data = match subkey.value_data(&actions_name) {
+                    Ok(data) => data,
+                    Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+                    Err(error) => {
+                        log::error!("failed to get actions of {subkey_name:?}: {error}");
+                        return;
+                    }
+                };
+
+                let winreg::ValueData::Bytes(ref actions) = data else {
+                    return;
+                };
+
+                let clsids = task_com_handler_clsids(actions);
+                if clsids.is_empty() {
+                    return;
+                }
+
+                let command_lines = clsids.iter()
+                    .filter_map(|clsid| match com_server(clsid) {
+                        Ok(command_line) => Some(command_line),
+                        Err(error) => {
+                            log::warn!("failed to resolve COM server {clsid}: {error}");
+                            None
+                        }
+                    })
+                    .collect();
+
+                entries.push(Entry {
+                    key: subkey_name,
+                    command_lines,
+                    value: winreg::Value {
+                        name: actions_name.clone(),
+                        data,
+                    },
+                });
+            })?;
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Calls the given function for every subkey of the given key.
+///
+/// The function is called with the opened subkey and its name relative to the
+/// root of `key`. Subkeys that cannot be opened are skipped.
+#[cfg(target_family = "windows")]
+fn for_each_subkey<F>(
+    key: &winreg::OpenKey,
+    key_name: &std::ffi::OsStr,
+    mut func: F,
+) -> std::io::Result<()>
+where
+    F: FnMut(winreg::OpenKey, std::ffi::OsString),
+{
+    for subkey_name in key.info()?.subkeys() {
+        let subkey_name = match subkey_name {
+            Ok(subkey_name) => subkey_name,
+            Err(error) => {
+                log::error!("failed to list subkey for key {key_name:?}: {error}");
+                continue;
+            }
+        };
+
+        let subkey = match key.open(&subkey_name) {
+            Ok(subkey) => subkey,
+            Err(error) => {
+                log::error!("failed to open subkey {subkey_name:?} of {key_name:?}: {error}");
+                continue;
+            }
+        };
+
+        func(subkey, winreg::path::join(key_name, &subkey_name));
+    }
+
+    Ok(())
+}
+
+/// Extracts command lines from the given value data.
+///
+/// If any separators are given, string data is treated as a list delimited
+/// with these.
+#[cfg(target_family = "windows")]
+fn command_lines(data: &winreg::ValueData, separators: &[char]) -> Vec<String> {
+    let strings = match data {
+        winreg::ValueData::String(string) |
+        winreg::ValueData::ExpandString(string) => vec![string.to_string_lossy()],
+        winreg::ValueData::MultiString(strings) => {
+            strings.iter().map(|string| string.to_string_lossy()).collect()
+        }
+        _ => return Vec::new(),
+    };
+
+    let mut command_lines = Vec::new();
+    for string in strings {
+        if separators.is_empty() {
+            command_lines.push(string.trim().to_string());
+        } else {
+            command_lines.extend(string.split(separators).map(|item| item.trim().to_string()));
+        }
+    }
+    command_lines.retain(|command_line| !command_line.is_empty());
+
+    command_lines
+}
+
+/// Extracts identifiers of COM handlers from actions of a scheduled task.
+///
+/// Actions are stored in an undocumented binary format in which every action
+/// starts with a 16-bit magic number followed by a length-prefixed UTF-16 id.
+/// COM handler actions (magic `0x7777`) continue with the class identifier and
+/// length-prefixed data passed to the handler. Instead of parsing actions of
+/// all the other types, we look for COM handler actions that are consistent
+/// with the length of the data.
+#[cfg(target_family = "windows")]
+fn task_com_handler_clsids(actions: &[u8]) -> Vec<String> {
+    /// Magic number of COM handler actions.
+    const COM_HANDLER_MAGIC: [u8; 2] = 0x7777u16.to_le_bytes();
+
+    /// Reads a 32-bit length at the given offset.
+    fn read_len(actions: &[u8], offset: usize) -> Option<usize> {
+        let bytes = actions.get(offset..offset.checked_add(4)?)?;
+        usize::try_from(u32::from_le_bytes(bytes.try_into().ok()?)).ok()
+    }
+
+    let mut clsids = Vec::new();
+
+    for offset in 0..actions.len().saturating_sub(1) {
+        if actions[offset..offset + 2] != COM_HANDLER_MAGIC {
+            continue;
+        }
+
+        let clsid = (|| {
+            let id_len = read_len(actions, offset + 2)?;
+            if id_len % 2 != 0 {
+                return None;
+            }
+
+            let clsid_offset = (offset + 6).checked_add(id_len)?;
+            let clsid = actions.get(clsid_offset..clsid_offset + 16)?;
+
+            let data_offset = clsid_offset + 16;
+            let data_len = read_len(actions, data_offset)?;
+            if data_len % 2 != 0 || (data_offset + 4).checked_add(data_len)? > actions.len() {
+                return None;
+            }
+
+            Some(format_clsid(clsid.try_into().ok()?))
+        })();
+
+        if let Some(clsid) = clsid {
+            clsids.push(clsid);
+        }
+    }
+
+    clsids
+}
+
+/// Formats the given class identifier in the registry format.
+#[cfg(target_family = "windows")]
+fn format_clsid(clsid: [u8; 16]) -> String {
+    let data1 = u32::from_le_bytes([clsid[0], clsid[1], clsid[2], clsid[3]]);
+    let data2 = u16::from_le_bytes([clsid[4], clsid[5]]);
+    let data3 = u16::from_le_bytes([clsid[6], clsid[7]]);
+
+    let mut result = format!("{{{data1:08X}-{data2:04X}-{data3:04X}-");
+    for (i, byte) in clsid[8..].iter().enumerate() {
+        if i == 2 {
+            result.push('-');
+        }
+        result.push_str(&format!("{byte:02X}"));
+    }
+    result.push('}');
+
+    result
+}
+
+/// Returns the command line of the in-process server of the given COM class.
+#[cfg(target_family = "windows")]
+fn com_server(clsid: &str) -> std::io::Result<String> {
+    let key_name = format!("SOFTWARE\\Classes\\CLSID\\{clsid}\\InprocServer32");
+    let key = winreg::PredefinedKey::LocalMachine
+        .open_with_view(std::ffi::OsStr::new(&key_name), winreg::View::Registry64)?;
+
+    // The path to the server is the default (unnamed) value of the key.
+    match key.value_data(std::ffi::OsStr::new(""))? {
+        winreg::ValueData::String(string) |
+        winreg::ValueData::ExpandString(string) => Ok(string.to_string_lossy().into_owned()),
+        _ => Err(std::io::ErrorKind::InvalidData.into()),
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl Target {
+
+    /// Resolves the target of the given command line.
+    ///
+    /// Unless `skip_digest` is set, the target file is also hashed and its
+    /// signature is verified.
+    fn new(command_line: &str, system_root: &std::path::Path, skip_digest: bool) -> Target {
+        let (path, arguments) = crate::os::windows::path::split_command_line(command_line);
+        let path = crate::os::windows::path::normalize(path, system_root);
+
+        let mut target = Target {
+            path,
+            arguments: arguments.map(String::from),
+            sha256: None,
+            signature: SignatureStatus::Unverified,
+        };
+        if skip_digest {
+            return target;
+        }
+
+        match sha256(&target.path) {
+            Ok(sha256) => {
+                target.sha256 = Some(sha256);
+                target.signature = verify_signature(&target.path);
+            }
+            Err(error) => {
+                log::warn!("failed to hash {:?}: {error}", target.path);
+            }
+        }
+
+        target
+    }
+}
+
+/// Computes the SHA-256 digest of the file at the given path.
+#[cfg(target_family = "windows")]
+fn sha256(path: &std::path::Path) -> std::io::Result<[u8; 32]> {
+    use sha2::Digest as _;
+
+    let mut file = std::fs::File::open(path)?;
+
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize().into())
+}
+
+/// Verifies the embedded Authenticode signature of the file at the given path.
+///
+/// Revocation is not checked and no certificates are retrieved from the network
+/// to avoid the agent making network requests on its own.
+#[cfg(target_family = "windows")]
+fn verify_signature(path: &std::path::Path) -> SignatureStatus {
+    use std::os::windows::ffi::OsStrExt as _;
+    use windows_sys::Win32::Foundation::*;
+    use windows_sys::Win32::Security::WinTrust::*;
+
+    let path_wide = path.as_os_str().encode_wide()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+
+    let mut file_info = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: path_wide.as_ptr(),
+        hFile: std::ptr::null_mut(),
+        pgKnownSubject: std::ptr::null_mut(),
+    };
+
+    let mut data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        pPolicyCallbackData: std::ptr::null_mut(),
+        pSIPClientData: std::ptr::null_mut(),
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: WTD_CHOICE_FILE,
+        Anonymous: WINTRUST_DATA_0 {
+            pFile: &mut file_info,
+        },
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        hWVTStateData: std::ptr::null_mut(),
+        pwszURLReference: std::ptr::null_mut(),
+        dwProvFlags: WTD_CACHE_ONLY_URL_RETRIEVAL,
+        dwUIContext: 0,
+        pSignatureSettings: std::ptr::null_mut(),
+    };
+
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+
+    // SAFETY: This is just an FFI call as described in the docs [1]. All the
+    // pointers in `data` point to values that outlive the call.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/wintrust/nf-wintrust-winverifytrust
+    let status = unsafe {
+        WinVerifyTrust(
+            std::ptr::null_mut(),
+            &mut action,
+            &mut data as *mut WINTRUST_DATA as *mut std::ffi::c_void,
+        )
+    };
+
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+
+    // SAFETY: The state data has to be released with another call as described
+    // in the docs [1]. We do not verify the result as there is nothing we can
+    // do about failures here anyway.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/wintrust/nf-wintrust-winverifytrust
+    unsafe {
+        WinVerifyTrust(
+            std::ptr::null_mut(),
+            &mut action,
+            &mut data as *mut WINTRUST_DATA as *mut std::ffi::c_void,
+        );
+    }
+
+    match status {
+        0 => SignatureStatus::Signed,
+        // Files that are not executables (e.g. scripts) cannot have embedded
+        // signatures at all.
+        TRUST_E_NOSIGNATURE |
+        TRUST_E_SUBJECT_FORM_UNKNOWN |
+        TRUST_E_PROVIDER_UNKNOWN => SignatureStatus::Unsigned,
+        _ => SignatureStatus::Invalid,
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_autoruns::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            skip_targets: proto.skip_targets(),
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_autoruns::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_autoruns::Result::new();
+        proto.set_location(String::from(self.location));
+        proto.set_catalog_version(CATALOG_VERSION);
+        proto.set_root(self.root.into());
+        proto.set_key(self.key.to_string_lossy().into_owned());
+        proto.set_view(self.view.into());
+        proto.set_value(self.value.into());
+
+        if let Some(target) = self.target {
+            proto.set_target_path(crate::path::into_proto(target.path));
+            if let Some(arguments) = target.arguments {
+                proto.set_target_arguments(arguments);
+            }
+            if let Some(sha256) = target.sha256 {
+                proto.set_target_sha256(sha256.to_vec());
+            }
+            proto.set_target_signature(target.signature.into());
+        }
+
+        proto
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl From<SignatureStatus> for rrg_proto::list_autoruns::SignatureStatus {
+
+    fn from(status: SignatureStatus) -> rrg_proto::list_autoruns::SignatureStatus {
+        use rrg_proto::list_autoruns::SignatureStatus as Proto;
+
+        match status {
+            SignatureStatus::Unverified => Proto::UNVERIFIED,
+            SignatureStatus::Signed => Proto::SIGNED,
+            SignatureStatus::Unsigned => Proto::UNSIGNED,
+            SignatureStatus::Invalid => Proto::INVALID,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn command_lines_string() {
+        let data = winreg::ValueData::String("C:\\Foo\\foo.exe --bar".into());
+        assert_eq!(command_lines(&data, &[]), vec!["C:\\Foo\\foo.exe --bar"]);
+    }
+
+    #[test]
+    fn command_lines_list() {
+        let data = winreg::ValueData::String("C:\\Windows\\system32\\userinit.exe,".into());
+        assert_eq!(command_lines(&data, &[',']), vec!["C:\\Windows\\system32\\userinit.exe"]);
+
+        let data = winreg::ValueData::String("C:\\foo.dll, C:\\bar.dll C:\\baz.dll".into());
+        assert_eq! {
+            command_lines(&data, &[',', ' ']),
+            vec!["C:\\foo.dll", "C:\\bar.dll", "C:\\baz.dll"]
+        };
+    }
+
+    #[test]
+    fn command_lines_non_string() {
+        let data = winreg::ValueData::U32(1);
+        assert!(command_lines(&data, &[]).is_empty());
+    }
+
+    #[test]
+    fn format_clsid_ok() {
+        let clsid = [
+            0x78, 0x56, 0x34, 0x12, 0x34, 0x12, 0x78, 0x56,
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF,
+        ];
+        assert_eq!(format_clsid(clsid), "{12345678-1234-5678-0123-456789ABCDEF}");
+    }
+
+    #[test]
+    fn task_com_handler_clsids_ok() {
+        let clsid = [
+            0x78, 0x56, 0x34, 0x12, 0x34, 0x12, 0x78, 0x56,
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF,
+        ];
+
+        let mut actions = Vec::new();
+        // Version and context (`Author`).
+        actions.extend(3u16.to_le_bytes());
+        actions.extend(12u32.to_le_bytes());
+        actions.extend("Author".encode_utf16().flat_map(u16::to_le_bytes));
+        // COM handler action with an empty id and empty data.
+        actions.extend(0x7777u16.to_le_bytes());
+        actions.extend(0u32.to_le_bytes());
+        actions.extend(clsid);
+        actions.extend(0u32.to_le_bytes());
+
+        assert_eq! {
+            task_com_handler_clsids(&actions),
+            vec!["{12345678-1234-5678-0123-456789ABCDEF}"]
+        };
+    }
+
+    #[test]
+    fn task_com_handler_clsids_truncated() {
+        let mut actions = Vec::new();
+        actions.extend(3u16.to_le_bytes());
+        actions.extend(0x7777u16.to_le_bytes());
+        actions.extend(0u32.to_le_bytes());
+        actions.extend([0x00; 8]);
+
+        assert!(task_com_handler_clsids(&actions).is_empty());
+    }
+
+    #[test]
+    fn handle_run_planted() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let target_path = tempdir.path().join("foo.exe");
+        std::fs::write(&target_path, b"MZfoobar")
+            .unwrap();
+
+        let value_name = format!("rrg-test-{}", std::process::id());
+        let _value = RunValue::plant(&value_name, &format! {
+            "\"{}\" --bar", target_path.display()
+        });
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args {
+            skip_targets: false,
+        }).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.value.name == value_name.as_str())
+            .unwrap();
+
+        assert_eq!(item.location, "run");
+        assert_eq!(item.root, winreg::PredefinedKey::Users);
+
+        let target = item.target.as_ref()
+            .unwrap();
+        assert_eq!(target.path, target_path);
+        assert_eq!(target.arguments.as_deref(), Some("--bar"));
+        assert_eq!(target.sha256, Some(sha2::Sha256::digest(b"MZfoobar").into()));
+        assert_ne!(target.signature, SignatureStatus::Signed);
+    }
+
+    #[test]
+    fn handle_skip_targets() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args {
+            skip_targets: true,
+        }).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.location == "services")
+            .unwrap();
+
+        let target = item.target.as_ref()
+            .unwrap();
+        assert!(target.sha256.is_none());
+        assert_eq!(target.signature, SignatureStatus::Unverified);
+    }
+
+    /// Value planted under the `Run` key of the current user for testing.
+    ///
+    /// The value is deleted when dropped.
+    struct RunValue {
+        /// Name of the planted value.
+        name: Vec<u16>,
+    }
+
+    impl RunValue {
+
+        /// Plants a value with the given name and command line.
+        fn plant(name: &str, command_line: &str) -> RunValue {
+            use windows_sys::Win32::System::Registry::*;
+
+            let name = wide(name);
+            let data = wide(command_line);
+
+            // SAFETY: This is just an FFI call as described in the docs [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regsetkeyvaluew
+            let code = unsafe {
+                RegSetKeyValueW(
+                    HKEY_CURRENT_USER,
+                    wide(RUN_KEY).as_ptr(),
+                    name.as_ptr(),
+                    REG_SZ,
+                    data.as_ptr().cast(),
+                    (data.len() * std::mem::size_of::<u16>()) as u32,
+                )
+            };
+            assert_eq!(code, windows_sys::Win32::Foundation::ERROR_SUCCESS);
+
+            RunValue {
+                name,
+            }
+        }
+    }
+
+    impl Drop for RunValue {
+
+        fn drop(&mut self) {
+            use windows_sys::Win32::System::Registry::*;
+
+            // SAFETY: This is just an FFI call as described in the docs [1].
+            // We do not verify the result as there is nothing we can do about
+            // failures here anyway.
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regdeletekeyvaluew
+            unsafe {
+                RegDeleteKeyValueW(HKEY_CURRENT_USER, wide(RUN_KEY).as_ptr(), self.name.as_ptr());
+            }
+        }
+    }
+
+    /// `Run` key relative to the hive of the current user.
+    const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+    /// Encodes the given string as a null-terminated wide string.
+    fn wide(string: &str) -> Vec<u16> {
+        string.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+}
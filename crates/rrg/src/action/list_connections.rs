@@ -9,6 +9,8 @@ use log::warn;
 struct Item {
     // Information about the individual connection.
     conn: ospect::net::Connection,
+    // Name of the process that owns the connection (if known).
+    process_name: Option<std::ffi::OsString>,
 }
 
 // Handles invocations of the `list_connections` action.
@@ -19,6 +21,10 @@ where
     let conns = ospect::net::all_connections()
         .map_err(crate::session::Error::action)?;
 
+    // Processes usually own more than one connection, so we cache the names
+    // not to query the system for the same process over and over again.
+    let mut process_names = std::collections::HashMap::new();
+
     for conn in conns {
         let conn = match conn {
             Ok(conn) => conn,
@@ -28,8 +34,24 @@ where
             }
         };
 
+        let process_name = process_names.entry(conn.pid()).or_insert_with(|| {
+            // Connections not owned by any process are reported with pid 0.
+            if conn.pid() == 0 {
+                return None;
+            }
+
+            match ospect::proc::name(conn.pid()) {
+                Ok(name) => Some(name),
+                Err(error) => {
+                    warn!("failed to obtain name of process {}: {}", conn.pid(), error);
+                    None
+                }
+            }
+        }).clone();
+
         session.reply(Item {
             conn,
+            process_name,
         })?;
     }
 
@@ -43,6 +65,9 @@ impl crate::response::Item for Item {
     fn into_proto(self) -> rrg_proto::list_connections::Result {
         let mut proto = rrg_proto::list_connections::Result::new();
         proto.set_connection(self.conn.into());
+        if let Some(process_name) = self.process_name {
+            proto.set_process_name(process_name.to_string_lossy().into_owned());
+        }
 
         proto
     }
@@ -71,9 +96,34 @@ mod tests {
 
         if let ospect::net::Connection::Tcp(conn) = item.conn {
             assert_eq!(conn.state(), ospect::net::TcpState::Listen);
+            assert_eq!(conn.local_addr().port(), server_addr.port());
+            assert_eq!(conn.pid(), std::process::id());
         } else {
             panic!();
         }
+
+        let process_name = ospect::proc::name(std::process::id())
+            .unwrap();
+        assert_eq!(item.process_name, Some(process_name));
+    }
+
+    #[test]
+    fn handle_local_tcp_connection_once() {
+        use std::net::Ipv4Addr;
+
+        let server = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap();
+        let server_addr = server.local_addr()
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let count = session.replies::<Item>().filter(|item| {
+            item.conn.local_addr() == server_addr
+        }).count();
+
+        assert_eq!(count, 1);
     }
 
     #[test]
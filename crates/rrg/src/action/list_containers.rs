@@ -0,0 +1,232 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the container listing action.
+//!
+//! Containers are not enumerated through the container runtimes (which might
+//! not be reachable or even installed on the host) but are inferred from the
+//! cgroups of the running processes (see [`crate::container`]).
+
+/// A result of the `list_containers` action.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct Item {
+    /// Identifier of the container.
+    id: String,
+    /// Path of the cgroup of the container.
+    cgroup_root: String,
+    /// Identifier of the init process of the container.
+    init_pid: u32,
+    /// Number of processes running in the container.
+    process_count: usize,
+}
+
+/// A process considered for container attribution.
+#[cfg(target_os = "linux")]
+struct Process {
+    /// Identifier of the process.
+    pid: u32,
+    /// Identifier of the parent process (if known).
+    parent_pid: Option<u32>,
+    /// Path of the cgroup the process belongs to.
+    cgroup_path: String,
+}
+
+/// Handles invocations of the `list_containers` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let pids = ospect::proc::ids()
+        .map_err(crate::session::Error::action)?;
+
+    // Processes can exit at any moment, so failing to read information about
+    // some of them is expected and we just skip them.
+    let processes = pids
+        .filter_map(Result::ok)
+        .filter_map(|pid| Some(Process {
+            pid,
+            parent_pid: ospect::proc::parent_id(pid).ok(),
+            cgroup_path: crate::container::cgroup_path(pid).ok()?,
+        }));
+
+    for item in containers(processes) {
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_containers` action.
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Aggregates the given processes into containers they belong to.
+///
+/// Processes that do not belong to any container are ignored.
+#[cfg(target_os = "linux")]
+fn containers<I>(processes: I) -> Vec<Item>
+where
+    I: Iterator<Item = Process>,
+{
+    use std::collections::{BTreeMap, HashMap};
+
+    // We only keep processes that belong to some container, grouped by the
+    // container identifier.
+    let mut containers = BTreeMap::<String, (String, HashMap<u32, Option<u32>>)>::new();
+
+    for process in processes {
+        let Some(id) = crate::container::container_id(&process.cgroup_path) else {
+            continue;
+        };
+        let Some(cgroup_root) = crate::container::container_cgroup_root(&process.cgroup_path) else {
+            continue;
+        };
+
+        let (_, pids) = containers.entry(id.to_string())
+            .or_insert_with(|| (cgroup_root.to_string(), HashMap::new()));
+        pids.insert(process.pid, process.parent_pid);
+    }
+
+    containers.into_iter()
+        .filter_map(|(id, (cgroup_root, pids))| {
+            // Processes started inside the container descend from its init
+            // process, so the init process is the one with a parent outside of
+            // it. Processes started with e.g. `docker exec` have their parent
+            // outside of the container as well, but they are started after the
+            // init and so usually they have higher identifiers.
+            let init_pid = pids.iter()
+                .filter(|(_, parent_pid)| match parent_pid {
+                    Some(parent_pid) => !pids.contains_key(parent_pid),
+                    None => true,
+                })
+                .map(|(pid, _)| *pid)
+                .min()
+                .or_else(|| pids.keys().min().copied())?;
+
+            Some(Item {
+                id,
+                cgroup_root,
+                init_pid,
+                process_count: pids.len(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_containers::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_id(self.id);
+        proto.set_cgroup_root(self.cgroup_root);
+        proto.set_init_pid(self.init_pid);
+        proto.set_process_count(self.process_count as u64);
+
+        proto
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const ID_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const ID_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    fn process(pid: u32, parent_pid: u32, cgroup_path: &str) -> Process {
+        Process {
+            pid,
+            parent_pid: Some(parent_pid),
+            cgroup_path: String::from(cgroup_path),
+        }
+    }
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        // The tests might or might not run on a container host (or inside of
+        // a container), so we only verify that the reported data is sane.
+        for item in session.replies::<Item>() {
+            assert_eq!(item.id.len(), 64);
+            assert!(item.cgroup_root.contains(&item.id));
+            assert!(item.process_count > 0);
+        }
+    }
+
+    #[test]
+    fn containers_none() {
+        let items = containers([
+            process(1, 0, "/init.scope"),
+            process(100, 1, "/system.slice/sshd.service"),
+        ].into_iter());
+
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn containers_many() {
+        let items = containers([
+            process(1, 0, "/init.scope"),
+            process(10, 1, "/system.slice/containerd.service"),
+            process(100, 10, &format!("/system.slice/docker-{ID_A}.scope")),
+            process(101, 100, &format!("/system.slice/docker-{ID_A}.scope")),
+            process(200, 10, &format!("/docker/{ID_B}")),
+        ].into_iter());
+
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].id, ID_A);
+        assert_eq!(items[0].cgroup_root, format!("/system.slice/docker-{ID_A}.scope"));
+        assert_eq!(items[0].init_pid, 100);
+        assert_eq!(items[0].process_count, 2);
+
+        assert_eq!(items[1].id, ID_B);
+        assert_eq!(items[1].cgroup_root, format!("/docker/{ID_B}"));
+        assert_eq!(items[1].init_pid, 200);
+        assert_eq!(items[1].process_count, 1);
+    }
+
+    #[test]
+    fn containers_nested_cgroup() {
+        let items = containers([
+            process(100, 10, &format!("/system.slice/docker-{ID_A}.scope/init.scope")),
+            process(101, 100, &format!("/system.slice/docker-{ID_A}.scope/system.slice/foo.service")),
+        ].into_iter());
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].cgroup_root, format!("/system.slice/docker-{ID_A}.scope"));
+        assert_eq!(items[0].init_pid, 100);
+        assert_eq!(items[0].process_count, 2);
+    }
+
+    #[test]
+    fn containers_exec_process() {
+        let items = containers([
+            process(100, 10, &format!("/docker/{ID_A}")),
+            process(101, 100, &format!("/docker/{ID_A}")),
+            // Process started with `docker exec` is a child of the runtime.
+            process(300, 10, &format!("/docker/{ID_A}")),
+        ].into_iter());
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].init_pid, 100);
+        assert_eq!(items[0].process_count, 3);
+    }
+}
@@ -0,0 +1,565 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the deleted open file listing action.
+//!
+//! Deleted files stay around for as long as some process holds them open or
+//! has them mapped into memory (e.g. a running executable that deleted itself
+//! from the disk). Such files are reported by the kernel with the ` (deleted)`
+//! suffix appended to their paths in `/proc/<pid>/fd` and `/proc/<pid>/maps`.
+//! Contents of files held open can still be read through the corresponding
+//! `/proc/<pid>/fd/<fd>` entry.
+
+#[cfg(target_os = "linux")]
+use std::ffi::OsString;
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "linux")]
+use log::warn;
+
+/// Maximum size of a file to collect the contents of if not specified.
+#[cfg(target_os = "linux")]
+const DEFAULT_MAX_CONTENT_SIZE: u64 = 16 * 1024 * 1024; // 16 MiB.
+
+/// Limit on the size of individual content part blob sent to the blob sink.
+#[cfg(target_os = "linux")]
+const MAX_BLOB_LEN: usize = 2 * 1024 * 1024; // 2 MiB.
+
+/// Arguments of the `list_deleted_open_files` action.
+#[cfg(target_os = "linux")]
+pub struct Args {
+    /// Identifier of the process to list the deleted files of.
+    pid: Option<u32>,
+    /// Whether to collect contents of the deleted files held open.
+    fetch_content: bool,
+    /// Maximum size of a file to collect the contents of.
+    max_content_size: u64,
+}
+
+/// A result of the `list_deleted_open_files` action.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct Item {
+    /// Identifier of the process holding the deleted file.
+    pid: u32,
+    /// Name of the process executable (if known).
+    process_name: Option<OsString>,
+    /// How the process holds the deleted file.
+    holder: Holder,
+    /// Path the file had before it was deleted.
+    path: PathBuf,
+    /// Size of the deleted file (if known).
+    size: Option<u64>,
+    /// Collected contents of the deleted file (if requested).
+    content: Option<Content>,
+}
+
+/// Way in which a process holds a deleted file.
+#[cfg(target_os = "linux")]
+#[derive(Debug, PartialEq, Eq)]
+enum Holder {
+    /// The file is held open through the file descriptor.
+    Fd(u64),
+    /// The file is mapped into memory starting at the address.
+    Map(u64),
+}
+
+/// Contents of a deleted file sent to the blob sink.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct Content {
+    /// SHA-256 digests of the content parts sent to the blob sink.
+    blob_sha256s: Vec<[u8; 32]>,
+    /// Number of bytes of the contents sent to the blob sink.
+    len: u64,
+    /// Error that interrupted collection of the contents (if any).
+    error: Option<std::io::Error>,
+}
+
+/// Handles invocations of the `list_deleted_open_files` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::os::unix::fs::MetadataExt as _;
+
+    let pids: Box<dyn Iterator<Item = std::io::Result<u32>>> = match args.pid {
+        Some(pid) => Box::new(std::iter::once(Ok(pid))),
+        None => Box::new(ospect::proc::ids()
+            .map_err(crate::session::Error::action)?),
+    };
+
+    for pid in pids {
+        let pid = match pid {
+            Ok(pid) => pid,
+            Err(error) => {
+                warn!("failed to obtain process identifier: {error}");
+                continue;
+            }
+        };
+
+        session.check_limits()?;
+
+        let files = match ospect::proc::open_files(pid) {
+            Ok(files) => files,
+            // The process could have exited since we listed them. This is not
+            // a problem unless it was the one we were explicitly asked about.
+            Err(error) if is_gone(&error) && args.pid.is_none() => continue,
+            Err(error) => return Err(crate::session::Error::action(error)),
+        };
+
+        let process_name = ospect::proc::name(pid).ok();
+
+        for file in files {
+            let file = match file {
+                Ok(file) => file,
+                Err(error) => {
+                    warn!("failed to obtain open file of process {pid}: {error}");
+                    continue;
+                }
+            };
+
+            if !file.is_deleted() {
+                continue;
+            }
+
+            let fd_path = PathBuf::from(format!("/proc/{pid}/fd/{}", file.fd()));
+
+            // The metadata of the descriptor entry is the metadata of the file
+            // it points to (even if it is deleted).
+            let size = match std::fs::metadata(&fd_path) {
+                Ok(metadata) => Some(metadata.len()),
+                Err(error) => {
+                    warn!("failed to obtain size of '{}': {error}", fd_path.display());
+                    None
+                }
+            };
+
+            let content = match size {
+                Some(size) if args.fetch_content && size <= args.max_content_size => {
+                    Some(fetch_content(session, &fd_path, args.max_content_size)?)
+                }
+                _ => None,
+            };
+
+            session.reply(Item {
+                pid,
+                process_name: process_name.clone(),
+                holder: Holder::Fd(file.fd()),
+                path: file.path().to_path_buf(),
+                size,
+                content,
+            })?;
+        }
+
+        let regions = match ospect::proc::regions(pid) {
+            Ok(regions) => regions,
+            Err(error) if is_gone(&error) => continue,
+            Err(error) => {
+                warn!("failed to obtain memory regions of process {pid}: {error}");
+                continue;
+            }
+        };
+
+        // A single file is usually mapped into multiple consecutive regions
+        // (e.g. code and data segments of an executable), we report only the
+        // first one.
+        let mut reported_paths = std::collections::HashSet::new();
+
+        for region in regions {
+            let Some(path) = region.path().and_then(strip_deleted_suffix) else {
+                continue;
+            };
+            if reported_paths.contains(&path) {
+                continue;
+            }
+
+            // Mapped files can be inspected through the `map_files` entries [1]
+            // the same way as descriptors. But this requires elevated privileges,
+            // so if it is not possible, we trust the suffix.
+            //
+            // [1]: https://man7.org/linux/man-pages/man5/proc_pid_map_files.5.html
+            let map_files_path = format! {
+                "/proc/{pid}/map_files/{:x}-{:x}",
+                region.addr(),
+                region.addr() + region.size(),
+            };
+            let size = match std::fs::metadata(map_files_path) {
+                Ok(metadata) if metadata.nlink() > 0 => continue,
+                Ok(metadata) => Some(metadata.len()),
+                Err(_) => None,
+            };
+
+            reported_paths.insert(path.clone());
+
+            session.reply(Item {
+                pid,
+                process_name: process_name.clone(),
+                holder: Holder::Map(region.addr()),
+                path,
+                size,
+                content: None,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_deleted_open_files` action.
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Sends up to `max_len` bytes of contents of the file at the given path to
+/// the blob sink.
+///
+/// The file can disappear at any moment (e.g. if the process holding it exits)
+/// so errors are not fatal and are reported as part of the contents instead.
+/// Once the file is open, it stays readable until we are done with it.
+#[cfg(target_os = "linux")]
+fn fetch_content<S>(
+    session: &mut S,
+    path: &Path,
+    max_len: u64,
+) -> crate::session::Result<Content>
+where
+    S: crate::session::Session,
+{
+    use std::io::Read as _;
+
+    let mut content = Content {
+        blob_sha256s: Vec::new(),
+        len: 0,
+        error: None,
+    };
+
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            warn!("failed to open '{}': {error}", path.display());
+            content.error = Some(error);
+            return Ok(content);
+        }
+    };
+
+    // The file could have grown since we checked its size, so we make sure not
+    // to read more than allowed.
+    let mut file = file.take(max_len);
+
+    loop {
+        let mut chunk = Vec::with_capacity(MAX_BLOB_LEN);
+        if let Err(error) = file.by_ref().take(MAX_BLOB_LEN as u64).read_to_end(&mut chunk) {
+            warn!("failed to read '{}': {error}", path.display());
+            content.error = Some(error);
+            break;
+        }
+
+        if chunk.is_empty() {
+            break;
+        }
+
+        let blob = crate::blob::Blob::from(chunk);
+        content.blob_sha256s.push(blob.digest(crate::blob::DigestAlgorithm::Sha256));
+        content.len += blob.as_bytes().len() as u64;
+
+        session.send(crate::Sink::Blob, blob)?;
+    }
+
+    Ok(content)
+}
+
+/// Strips the suffix that the kernel appends to paths of deleted files.
+///
+/// `None` is returned if the path does not have the suffix.
+#[cfg(target_os = "linux")]
+fn strip_deleted_suffix(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let path = path.as_os_str().as_bytes().strip_suffix(b" (deleted)")?;
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(path)))
+}
+
+/// Checks whether the given error means that the inspected process is gone.
+#[cfg(target_os = "linux")]
+fn is_gone(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::NotFound ||
+    error.raw_os_error() == Some(libc::ESRCH)
+}
+
+#[cfg(target_os = "linux")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_deleted_open_files::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        let max_content_size = match proto.max_content_size() {
+            0 => DEFAULT_MAX_CONTENT_SIZE,
+            max_content_size => max_content_size,
+        };
+
+        Ok(Args {
+            pid: proto.pid,
+            fetch_content: proto.fetch_content(),
+            max_content_size,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_deleted_open_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_pid(self.pid);
+        if let Some(process_name) = self.process_name {
+            proto.set_process_name(process_name.to_string_lossy().into_owned());
+        }
+        match self.holder {
+            Holder::Fd(fd) => proto.set_fd(fd),
+            Holder::Map(addr) => proto.set_map_addr(addr),
+        }
+        proto.set_path(crate::path::into_proto(self.path));
+        if let Some(size) = self.size {
+            proto.set_size(size);
+        }
+        if let Some(content) = self.content {
+            for blob_sha256 in content.blob_sha256s {
+                proto.mut_content_blob_sha256s().push(blob_sha256.to_vec());
+            }
+            proto.set_content_length(content.len);
+            if let Some(error) = content.error {
+                proto.set_content_error(error.to_string());
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Creates a file with the given contents in a temporary directory and
+    /// deletes it, returning the handle to it and the path it had.
+    fn deleted_file(tempdir: &tempfile::TempDir, data: &[u8]) -> (std::fs::File, PathBuf) {
+        let path = tempdir.path().canonicalize()
+            .unwrap()
+            .join("foo");
+
+        std::fs::write(&path, data)
+            .unwrap();
+        let file = std::fs::File::open(&path)
+            .unwrap();
+        std::fs::remove_file(&path)
+            .unwrap();
+
+        (file, path)
+    }
+
+    #[test]
+    fn handle_fd() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let (file, path) = deleted_file(&tempdir, b"hello, world!");
+
+        let args = Args {
+            pid: Some(std::process::id()),
+            fetch_content: false,
+            max_content_size: DEFAULT_MAX_CONTENT_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let fd = file.as_raw_fd() as u64;
+
+        let item = session.replies::<Item>()
+            .find(|item| item.holder == Holder::Fd(fd))
+            .unwrap();
+        assert_eq!(item.pid, std::process::id());
+        assert!(item.process_name.is_some());
+        assert_eq!(item.path, path);
+        assert_eq!(item.size, Some(13));
+        assert!(item.content.is_none());
+
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[test]
+    fn handle_fd_fetch_content() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let (file, path) = deleted_file(&tempdir, b"hello, world!");
+
+        let args = Args {
+            pid: Some(std::process::id()),
+            fetch_content: true,
+            max_content_size: DEFAULT_MAX_CONTENT_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let fd = file.as_raw_fd() as u64;
+
+        let item = session.replies::<Item>()
+            .find(|item| item.holder == Holder::Fd(fd))
+            .unwrap();
+        assert_eq!(item.path, path);
+
+        let content = item.content.as_ref()
+            .unwrap();
+        assert_eq!(content.len, 13);
+        assert!(content.error.is_none());
+        assert_eq!(content.blob_sha256s.len(), 1);
+
+        let blob = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .find(|blob| blob.digest(crate::blob::DigestAlgorithm::Sha256) == content.blob_sha256s[0])
+            .unwrap();
+        assert_eq!(blob.as_bytes(), b"hello, world!");
+    }
+
+    #[test]
+    fn handle_fd_fetch_content_too_big() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let (file, _) = deleted_file(&tempdir, b"hello, world!");
+
+        let args = Args {
+            pid: Some(std::process::id()),
+            fetch_content: true,
+            max_content_size: 4,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let fd = file.as_raw_fd() as u64;
+
+        let item = session.replies::<Item>()
+            .find(|item| item.holder == Holder::Fd(fd))
+            .unwrap();
+        assert_eq!(item.size, Some(13));
+        assert!(item.content.is_none());
+    }
+
+    #[test]
+    fn handle_fd_not_deleted() {
+        use std::os::fd::AsRawFd as _;
+
+        let file = tempfile::NamedTempFile::new()
+            .unwrap();
+
+        let args = Args {
+            pid: Some(std::process::id()),
+            fetch_content: false,
+            max_content_size: DEFAULT_MAX_CONTENT_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let fd = file.as_file().as_raw_fd() as u64;
+        assert! {
+            !session.replies::<Item>().any(|item| item.holder == Holder::Fd(fd))
+        };
+    }
+
+    #[test]
+    fn handle_map() {
+        use std::os::fd::AsRawFd as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let (file, path) = deleted_file(&tempdir, &[0xf0; 4096]);
+
+        // SAFETY: We map the file (that is exactly one page long) for reading
+        // and verify the result below. The mapping is not accessed and it is
+        // unmapped before the end of the test.
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                4096,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        assert_ne!(addr, libc::MAP_FAILED);
+
+        // The mapping keeps the file around even without the descriptor.
+        drop(file);
+
+        let args = Args {
+            pid: Some(std::process::id()),
+            fetch_content: false,
+            max_content_size: DEFAULT_MAX_CONTENT_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        let result = handle(&mut session, args);
+
+        // SAFETY: The mapping is exactly one page long and is not used anymore.
+        assert_eq!(unsafe { libc::munmap(addr, 4096) }, 0);
+
+        result.unwrap();
+
+        let item = session.replies::<Item>()
+            .find(|item| item.holder == Holder::Map(addr as u64))
+            .unwrap();
+        assert_eq!(item.path, path);
+        assert!(item.content.is_none());
+    }
+
+    #[test]
+    fn handle_no_process() {
+        let args = Args {
+            // Process identifiers are bounded by 2^22 on Linux [1].
+            //
+            // [1]: https://man7.org/linux/man-pages/man5/proc_sys_kernel.5.html
+            pid: Some(u32::MAX),
+            fetch_content: false,
+            max_content_size: DEFAULT_MAX_CONTENT_SIZE,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn strip_deleted_suffix_present() {
+        let path = strip_deleted_suffix("/tmp/foo (deleted)".as_ref());
+        assert_eq!(path.as_deref(), Some("/tmp/foo".as_ref()));
+    }
+
+    #[test]
+    fn strip_deleted_suffix_absent() {
+        assert_eq!(strip_deleted_suffix("/tmp/foo".as_ref()), None);
+    }
+}
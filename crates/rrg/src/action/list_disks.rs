@@ -0,0 +1,154 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use log::warn;
+
+/// A result of the `list_disks` action.
+struct Item {
+    /// Information about the individual disk.
+    disk: ospect::disk::Disk,
+}
+
+/// Handles invocations of the `list_disks` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let disks = ospect::disk::disks()
+        .map_err(crate::session::Error::action)?;
+
+    for disk in disks {
+        let disk = match disk {
+            Ok(disk) => disk,
+            Err(error) => {
+                warn!("failed to obtain disk information: {}", error);
+                continue;
+            }
+        };
+
+        session.reply(Item {
+            disk,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_disks::Result;
+
+    fn into_proto(self) -> rrg_proto::list_disks::Result {
+        use rrg_proto::list_disks::PartitionTable;
+
+        let disk = self.disk;
+
+        let mut proto = rrg_proto::list_disks::Result::default();
+        proto.set_path(crate::path::into_proto(disk.path));
+        proto.set_size(disk.size);
+
+        if let Some(sector_size) = disk.sector_size {
+            proto.set_sector_size(sector_size);
+        }
+        if let Some(bus_type) = disk.bus_type {
+            proto.set_bus_type(bus_type);
+        }
+        if let Some(model) = disk.model {
+            proto.set_model(model);
+        }
+        if let Some(serial) = disk.serial {
+            proto.set_serial(serial);
+        }
+        match disk.partition_table {
+            Some(ospect::disk::PartitionTable::Mbr) => {
+                proto.set_partition_table(PartitionTable::MBR);
+            }
+            Some(ospect::disk::PartitionTable::Gpt) => {
+                proto.set_partition_table(PartitionTable::GPT);
+            }
+            None => (),
+        }
+        if let Some(partition_table_id) = disk.partition_table_id {
+            proto.set_partition_table_id(partition_table_id);
+        }
+
+        proto.set_partitions(disk.partitions.into_iter().map(partition_into_proto).collect());
+
+        proto
+    }
+}
+
+/// Converts the given partition to its protobuf representation.
+fn partition_into_proto(
+    partition: ospect::disk::Partition,
+) -> rrg_proto::list_disks::Partition {
+    let mut proto = rrg_proto::list_disks::Partition::default();
+
+    if let Some(path) = partition.path {
+        proto.set_path(crate::path::into_proto(path));
+    }
+    if let Some(number) = partition.number {
+        proto.set_number(number);
+    }
+    proto.set_offset(partition.offset);
+    proto.set_size(partition.size);
+
+    if let Some(type_id) = partition.type_id {
+        proto.set_type_id(type_id);
+    }
+    if let Some(guid) = partition.guid {
+        proto.set_guid(guid);
+    }
+    if let Some(name) = partition.name {
+        proto.set_name(name);
+    }
+    if let Some(fs_hint) = partition.fs_hint {
+        proto.set_fs_hint(fs_hint);
+    }
+
+    proto
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.disk.path.as_os_str().is_empty());
+
+            for partition in &item.disk.partitions {
+                assert!(partition.offset + partition.size <= item.disk.size);
+            }
+        }
+    }
+
+    #[test]
+    fn partition_into_proto_gpt() {
+        let partition = ospect::disk::Partition {
+            path: Some(std::path::PathBuf::from("/dev/sda1")),
+            number: Some(1),
+            offset: 1024 * 1024,
+            size: 512 * 1024 * 1024,
+            type_id: Some(String::from("c12a7328-f81f-11d2-ba4b-00a0c93ec93b")),
+            guid: Some(String::from("4f2b6a1c-0d6e-4a55-9d38-1b3c3a1f7e21")),
+            name: Some(String::from("EFI")),
+            fs_hint: Some(String::from("vfat")),
+        };
+
+        let proto = partition_into_proto(partition);
+        assert_eq!(proto.number(), 1);
+        assert_eq!(proto.offset(), 1024 * 1024);
+        assert_eq!(proto.size(), 512 * 1024 * 1024);
+        assert_eq!(proto.guid(), "4f2b6a1c-0d6e-4a55-9d38-1b3c3a1f7e21");
+        assert_eq!(proto.name(), "EFI");
+        assert_eq!(proto.fs_hint(), "vfat");
+    }
+}
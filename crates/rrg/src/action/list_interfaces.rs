@@ -62,6 +62,30 @@ mod tests {
         }
     }
 
+    #[test]
+    // Loopback interface is not available on Windows.
+    #[cfg_attr(target_family = "windows", ignore)]
+    fn handle_loopback_interface_addrs() {
+        use std::net::{Ipv4Addr, Ipv6Addr};
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>().find(|item| {
+            item.iface.ipv4_addrs().any(|addr| *addr == Ipv4Addr::LOCALHOST)
+        }).unwrap();
+
+        assert!(item.iface.is_loopback());
+        assert!(item.iface.is_up());
+        assert!(item.iface.ipv6_addrs().any(|addr| *addr == Ipv6Addr::LOCALHOST));
+
+        assert! {
+            item.iface.addrs().all(|addr| {
+                addr.scope() == ospect::net::AddrScope::Host
+            })
+        };
+    }
+
     #[test]
     fn handle_some_interface() {
         let mut session = crate::session::FakeSession::new();
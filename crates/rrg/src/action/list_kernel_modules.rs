@@ -0,0 +1,176 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use log::warn;
+
+/// Maximum size of a module file for which we compute the digest.
+///
+/// Module files are rarely bigger than a few megabytes, so anything above this
+/// is suspicious on its own and not worth reading in full.
+const MAX_SHA256_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A result of the `list_kernel_modules` action.
+struct Item {
+    /// Information about the individual module.
+    module: ospect::kmod::Module,
+    /// SHA-256 digest of the module file (if computed).
+    sha256: Option<[u8; 32]>,
+}
+
+/// Handles invocations of the `list_kernel_modules` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let modules = ospect::kmod::modules()
+        .map_err(crate::session::Error::action)?;
+
+    for module in modules {
+        let module = match module {
+            Ok(module) => module,
+            Err(error) => {
+                warn!("failed to obtain kernel module information: {}", error);
+                continue;
+            }
+        };
+
+        let sha256 = match module.path() {
+            Some(path) => match sha256(path) {
+                Ok(sha256) => sha256,
+                Err(error) => {
+                    warn!("failed to hash kernel module '{}': {}", path.display(), error);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        session.reply(Item {
+            module,
+            sha256,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Computes the SHA-256 digest of the file at the given path.
+///
+/// `None` is returned if the file does not exist (e.g. the module was removed
+/// after being loaded), is not a regular file (e.g. it is a bundle directory)
+/// or is too big.
+fn sha256(path: &std::path::Path) -> std::io::Result<Option<[u8; 32]>> {
+    use std::io::Read as _;
+    use sha2::Digest as _;
+
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+
+    let metadata = file.metadata()?;
+    if !metadata.is_file() || metadata.len() > MAX_SHA256_FILE_SIZE {
+        return Ok(None);
+    }
+
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file.take(MAX_SHA256_FILE_SIZE), &mut hasher)?;
+
+    Ok(Some(hasher.finalize().into()))
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_kernel_modules::Result;
+
+    fn into_proto(self) -> rrg_proto::list_kernel_modules::Result {
+        let mut proto = rrg_proto::list_kernel_modules::Result::default();
+        proto.set_name(self.module.name().to_string_lossy().into_owned());
+
+        if let Some(size) = self.module.size() {
+            proto.set_size(size);
+        }
+        if let Some(ref_count) = self.module.ref_count() {
+            proto.set_ref_count(ref_count);
+        }
+        if let Some(base_address) = self.module.base_address() {
+            proto.set_base_address(base_address);
+        }
+        if let Some(path) = self.module.path() {
+            proto.set_path(crate::path::into_proto(path.to_path_buf()));
+        }
+        if let Some(version) = self.module.version() {
+            proto.set_version(version.to_string());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(srcversion) = self.module.srcversion() {
+                proto.set_srcversion(srcversion.to_string());
+            }
+            if let Some(taint) = self.module.taint() {
+                proto.set_taint(taint.to_string());
+            }
+        }
+
+        if let Some(sha256) = self.sha256 {
+            proto.set_sha256(sha256.to_vec());
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.module.name().is_empty());
+        }
+    }
+
+    #[test]
+    fn sha256_file() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+        std::fs::write(tempfile.path(), b"foobar")
+            .unwrap();
+
+        let sha256 = sha256(tempfile.path())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(sha256, [
+            0xc3, 0xab, 0x8f, 0xf1, 0x37, 0x20, 0xe8, 0xad,
+            0x90, 0x47, 0xdd, 0x39, 0x46, 0x6b, 0x3c, 0x89,
+            0x74, 0xe5, 0x92, 0xc2, 0xfa, 0x38, 0x3d, 0x4a,
+            0x39, 0x60, 0x71, 0x4c, 0xae, 0xf0, 0xc4, 0xf2,
+        ]);
+    }
+
+    #[test]
+    fn sha256_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert_eq!(sha256(tempdir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn sha256_not_found() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert_eq!(sha256(&tempdir.path().join("foo.ko")).unwrap(), None);
+    }
+}
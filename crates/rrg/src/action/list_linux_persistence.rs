@@ -0,0 +1,345 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+#[cfg(target_os = "linux")]
+mod baseline;
+
+#[cfg(target_os = "linux")]
+mod pam;
+
+#[cfg(target_os = "linux")]
+mod preload;
+
+#[cfg(target_os = "linux")]
+mod profile;
+
+#[cfg(target_os = "linux")]
+mod systemd;
+
+#[cfg(target_os = "linux")]
+mod udev;
+
+/// Maximum number of bytes of files included in excerpts.
+#[cfg(target_os = "linux")]
+const MAX_EXCERPT_LEN: usize = 4 * 1024;
+
+/// Arguments of the `list_linux_persistence` action.
+#[cfg(target_os = "linux")]
+pub struct Args {
+    /// Whether the action is allowed to call external programs.
+    allow_external_commands: bool,
+}
+
+/// A result of the `list_linux_persistence` action.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+struct Item {
+    /// Location from which the item was collected.
+    location: Location,
+    /// Path to the file in which the item is defined.
+    path: std::path::PathBuf,
+    /// Location-specific identifier of the item.
+    label: String,
+    /// Origin of the definition of the item.
+    origin: Origin,
+    /// Excerpt of the raw definition of the item.
+    excerpt: String,
+    /// Path to the executable that the item runs or loads.
+    program: Option<std::path::PathBuf>,
+    /// Arguments (excluding `argv[0]`) passed to the executable.
+    args: Vec<String>,
+    /// SHA-256 digest of the executable.
+    program_sha256: Option<[u8; 32]>,
+}
+
+/// Location from which the item was collected.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Location {
+    /// Enabled systemd units.
+    SystemdUnit,
+    /// Drop-in files of systemd units.
+    SystemdDropIn,
+    /// systemd generators.
+    SystemdGenerator,
+    /// Entries of `/etc/ld.so.preload`.
+    LdSoPreload,
+    /// Modules referenced by PAM configuration.
+    PamModule,
+    /// Scripts sourced by login shells.
+    ShellProfile,
+    /// The `rc.local` script.
+    RcLocal,
+    /// Programs executed by udev rules.
+    UdevRule,
+}
+
+/// Origin of the definition of an item.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Origin {
+    /// The origin could not be determined.
+    Unknown,
+    /// Defined in a file provided by the distribution or a package.
+    Vendor,
+    /// Defined by the administrator.
+    Admin,
+    /// Defined in a file of a package modified since it was installed.
+    Modified,
+}
+
+/// Handles invocations of the `list_linux_persistence` action.
+#[cfg(target_os = "linux")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let baseline = self::baseline::Baseline::new(args.allow_external_commands);
+
+    let mut items = Vec::new();
+    items.extend(self::systemd::items());
+    items.extend(self::preload::items(&baseline));
+    items.extend(self::pam::items(&baseline));
+    items.extend(self::profile::items(&baseline));
+    items.extend(self::udev::items());
+
+    for mut item in items {
+        item.program_sha256 = item.program.as_deref().and_then(sha256);
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_linux_persistence` action.
+#[cfg(not(target_os = "linux"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+/// Determines the origin of an item from the path to the file defining it.
+///
+/// Following the convention of systemd and udev, files in `/usr` (other than
+/// `/usr/local`) and `/lib` belong to the distribution while files in `/etc`,
+/// `/run` and `/usr/local` are defined by the administrator.
+#[cfg(target_os = "linux")]
+fn origin_from_path(path: &std::path::Path) -> Origin {
+    const ADMIN_PATHS: [&str; 3] = ["/etc", "/run", "/usr/local"];
+    const VENDOR_PATHS: [&str; 3] = ["/usr", "/lib", "/lib64"];
+
+    if ADMIN_PATHS.iter().any(|prefix| path.starts_with(prefix)) {
+        Origin::Admin
+    } else if VENDOR_PATHS.iter().any(|prefix| path.starts_with(prefix)) {
+        Origin::Vendor
+    } else {
+        Origin::Unknown
+    }
+}
+
+/// Returns an excerpt of the file at the given path.
+///
+/// Only the first [`MAX_EXCERPT_LEN`] bytes of the file are included. Binary
+/// files (e.g. compiled generators) have empty excerpts.
+#[cfg(target_os = "linux")]
+fn excerpt_file(path: &std::path::Path) -> String {
+    use std::io::Read as _;
+
+    let mut buf = Vec::new();
+
+    let result = std::fs::File::open(path)
+        .and_then(|file| file.take(MAX_EXCERPT_LEN as u64).read_to_end(&mut buf));
+    if let Err(error) = result {
+        log::warn!("failed to read excerpt of '{}': {error}", path.display());
+        return String::new();
+    }
+
+    if buf.contains(&0) {
+        return String::new();
+    }
+
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Computes the SHA-256 digest of the file at the given path.
+///
+/// Errors are logged and `None` is returned in such cases (it is expected for
+/// some of the referenced files not to exist).
+#[cfg(target_os = "linux")]
+fn sha256(path: &std::path::Path) -> Option<[u8; 32]> {
+    use sha2::Digest as _;
+
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            log::warn!("failed to open '{}' for hashing: {error}", path.display());
+            return None;
+        }
+    };
+
+    let mut hasher = sha2::Sha256::new();
+    if let Err(error) = std::io::copy(&mut file, &mut hasher) {
+        log::warn!("failed to hash '{}': {error}", path.display());
+        return None;
+    }
+
+    Some(hasher.finalize().into())
+}
+
+#[cfg(target_os = "linux")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_linux_persistence::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            allow_external_commands: proto.allow_external_commands(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_linux_persistence::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_linux_persistence::Result::new();
+        proto.set_location(self.location.into());
+        proto.set_path(crate::path::into_proto(self.path));
+        proto.set_label(self.label);
+        proto.set_origin(self.origin.into());
+        proto.set_excerpt(self.excerpt);
+        if let Some(program) = self.program {
+            proto.set_program(crate::path::into_proto(program));
+        }
+        proto.set_args(self.args);
+        if let Some(program_sha256) = self.program_sha256 {
+            proto.set_program_sha256(program_sha256.to_vec());
+        }
+
+        proto
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<Location> for rrg_proto::list_linux_persistence::Location {
+
+    fn from(location: Location) -> rrg_proto::list_linux_persistence::Location {
+        use rrg_proto::list_linux_persistence::Location as Proto;
+
+        match location {
+            Location::SystemdUnit => Proto::SYSTEMD_UNIT,
+            Location::SystemdDropIn => Proto::SYSTEMD_DROP_IN,
+            Location::SystemdGenerator => Proto::SYSTEMD_GENERATOR,
+            Location::LdSoPreload => Proto::LD_SO_PRELOAD,
+            Location::PamModule => Proto::PAM_MODULE,
+            Location::ShellProfile => Proto::SHELL_PROFILE,
+            Location::RcLocal => Proto::RC_LOCAL,
+            Location::UdevRule => Proto::UDEV_RULE,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<Origin> for rrg_proto::list_linux_persistence::Origin {
+
+    fn from(origin: Origin) -> rrg_proto::list_linux_persistence::Origin {
+        use rrg_proto::list_linux_persistence::Origin as Proto;
+
+        match origin {
+            Origin::Unknown => Proto::UNKNOWN_ORIGIN,
+            Origin::Vendor => Proto::VENDOR,
+            Origin::Admin => Proto::ADMIN,
+            Origin::Modified => Proto::MODIFIED,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args {
+            allow_external_commands: false,
+        }).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.label.is_empty());
+        }
+    }
+
+    #[test]
+    fn origin_from_path_many() {
+        use std::path::Path;
+
+        assert_eq!(origin_from_path(Path::new("/etc/systemd/system/foo.service")), Origin::Admin);
+        assert_eq!(origin_from_path(Path::new("/run/systemd/system/foo.service")), Origin::Admin);
+        assert_eq!(origin_from_path(Path::new("/usr/local/lib/systemd/system/foo.service")), Origin::Admin);
+        assert_eq!(origin_from_path(Path::new("/usr/lib/systemd/system/foo.service")), Origin::Vendor);
+        assert_eq!(origin_from_path(Path::new("/lib/udev/rules.d/99-foo.rules")), Origin::Vendor);
+        assert_eq!(origin_from_path(Path::new("/opt/foo/foo.service")), Origin::Unknown);
+    }
+
+    #[test]
+    fn excerpt_file_text() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.sh");
+        std::fs::write(&path, "#!/bin/sh\necho foo\n")
+            .unwrap();
+
+        assert_eq!(excerpt_file(&path), "#!/bin/sh\necho foo\n");
+    }
+
+    #[test]
+    fn excerpt_file_truncated() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.sh");
+        std::fs::write(&path, "x".repeat(MAX_EXCERPT_LEN * 2))
+            .unwrap();
+
+        assert_eq!(excerpt_file(&path).len(), MAX_EXCERPT_LEN);
+    }
+
+    #[test]
+    fn excerpt_file_binary() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"\x7FELF\x02\x01\x01\x00")
+            .unwrap();
+
+        assert_eq!(excerpt_file(&path), "");
+    }
+
+    #[test]
+    fn sha256_ok() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"foo")
+            .unwrap();
+
+        assert_eq!(sha256(&path), Some(sha2::Sha256::digest(b"foo").into()));
+        assert_eq!(sha256(&tempdir.path().join("bar")), None);
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Baseline of configuration files installed by packages.
+//!
+//! The baseline tells files shipped by the distribution apart from the ones
+//! added or modified by the administrator (or an attacker).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::Origin;
+
+/// Path to the dpkg status database.
+const DPKG_STATUS_PATH: &str = "/var/lib/dpkg/status";
+
+/// Paths to directories where the RPM database can be located.
+const RPM_DB_PATHS: [&str; 2] = [
+    "/var/lib/rpm",
+    "/usr/lib/sysimage/rpm",
+];
+
+/// Baseline of configuration files installed by packages.
+pub enum Baseline {
+    /// Configuration files (with their MD5 digests) according to dpkg.
+    Dpkg(HashMap<PathBuf, [u8; 16]>),
+    /// Configuration files are verified with the `rpm` program.
+    Rpm,
+    /// No baseline is available.
+    None,
+}
+
+impl Baseline {
+
+    /// Loads the baseline of the package manager available on the system.
+    ///
+    /// The RPM baseline is available only if external commands are allowed.
+    pub fn new(allow_external_commands: bool) -> Baseline {
+        match std::fs::File::open(DPKG_STATUS_PATH) {
+            Ok(file) => match parse_conffiles(std::io::BufReader::new(file)) {
+                Ok(conffiles) => return Baseline::Dpkg(conffiles),
+                Err(error) => log::warn!("failed to parse dpkg status: {error}"),
+            },
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+            Err(error) => log::warn!("failed to open dpkg status: {error}"),
+        }
+
+        if allow_external_commands && RPM_DB_PATHS.iter().any(|path| Path::new(path).is_dir()) {
+            return Baseline::Rpm;
+        }
+
+        Baseline::None
+    }
+
+    /// Determines the origin of the file at the given path.
+    pub fn origin(&self, path: &Path) -> Origin {
+        match self {
+            Baseline::Dpkg(conffiles) => match conffiles.get(path) {
+                Some(expected_md5) => match md5(path) {
+                    Ok(md5) if md5 == *expected_md5 => Origin::Vendor,
+                    Ok(_) => Origin::Modified,
+                    Err(error) => {
+                        log::warn!("failed to hash '{}': {error}", path.display());
+                        Origin::Unknown
+                    }
+                },
+                // Note that files owned by packages but not marked as conffiles
+                // are also reported this way. Packages are not supposed to ship
+                // files in `/etc` other than conffiles though.
+                None => Origin::Admin,
+            },
+            Baseline::Rpm => match rpm_origin(path) {
+                Ok(origin) => origin,
+                Err(error) => {
+                    log::warn!("failed to verify '{}': {error}", path.display());
+                    Origin::Unknown
+                }
+            },
+            Baseline::None => Origin::Unknown,
+        }
+    }
+}
+
+/// Parses configuration files from the given dpkg status database contents.
+///
+/// Configuration files of every package are listed in the `Conffiles` field
+/// of its stanza, one per continuation line in the `<path> <md5>` format
+/// (optionally followed by flags like `obsolete`).
+fn parse_conffiles<R>(reader: R) -> std::io::Result<HashMap<PathBuf, [u8; 16]>>
+where
+    R: std::io::BufRead,
+{
+    let mut conffiles = HashMap::new();
+
+    let mut in_conffiles = false;
+    for line in reader.lines() {
+        let line = line?;
+
+        if !line.starts_with([' ', '\t']) {
+            in_conffiles = line.starts_with("Conffiles:");
+            continue;
+        }
+        if !in_conffiles {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let (Some(path), Some(md5)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+
+        // Files of packages that were unpacked but not configured yet have the
+        // `newconffile` placeholder instead of the digest.
+        match decode_md5(md5) {
+            Some(md5) => {
+                conffiles.insert(PathBuf::from(path), md5);
+            }
+            None => log::debug!("invalid digest of conffile '{path}': {md5}"),
+        }
+    }
+
+    Ok(conffiles)
+}
+
+/// Decodes an MD5 digest from its hexadecimal representation.
+fn decode_md5(hex: &str) -> Option<[u8; 16]> {
+    if hex.len() != 32 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut md5 = [0; 16];
+    for (i, byte) in md5.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+
+    Some(md5)
+}
+
+/// Computes the MD5 digest of the file at the given path.
+fn md5(path: &Path) -> std::io::Result<[u8; 16]> {
+    use md5::Digest as _;
+
+    let mut file = std::fs::File::open(path)?;
+
+    let mut hasher = md5::Md5::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize().into())
+}
+
+/// Determines the origin of the file at the given path using `rpm`.
+fn rpm_origin(path: &Path) -> std::io::Result<Origin> {
+    let output = std::process::Command::new("rpm")
+        .arg("--query")
+        .arg("--file")
+        .arg(path)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    // `rpm` fails if the file is not owned by any package.
+    if !output.status.success() {
+        return Ok(Origin::Admin);
+    }
+
+    // Verification fails if any of the files of the package differ from their
+    // expected state, so we do not check the status and parse the output.
+    let output = std::process::Command::new("rpm")
+        .arg("--verify")
+        .arg("--file")
+        .arg(path)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if is_rpm_modified(&String::from_utf8_lossy(&output.stdout), path) {
+        Ok(Origin::Modified)
+    } else {
+        Ok(Origin::Vendor)
+    }
+}
+
+/// Checks whether `rpm --verify` output reports the given file as modified.
+///
+/// Every line of the output describes a file that failed the verification in
+/// the `<flags> <attribute> <path>` format, where `5` in the flags means that
+/// the digest of the file differs [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man8/rpm.8.html#VERIFY_OPTIONS
+fn is_rpm_modified(output: &str, path: &Path) -> bool {
+    let path = path.to_string_lossy();
+
+    output.lines()
+        .filter(|line| line.split_whitespace().last() == Some(path.as_ref()))
+        .any(|line| {
+            line.split_whitespace().next()
+                .is_some_and(|flags| flags == "missing" || flags.contains('5'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_conffiles_ok() {
+        let status = b"\
+Package: libpam-runtime
+Status: install ok installed
+Conffiles:
+ /etc/pam.conf 87fc76f18e98ee7d3848f6b81b3391e5
+ /etc/pam.d/other 31aa7f2181889ffb00b87df4126d1701
+Description: Runtime support for the PAM library
+ Contains configuration files and directories required for
+ authentication to work on Debian systems.
+
+Package: openssh-server
+Status: install ok installed
+Conffiles:
+ /etc/pam.d/sshd 8b4c7a12b031424b2a9946881da59812
+ /etc/ssh/moduli newconffile
+ /etc/init.d/ssh 3649a6fe8c18ad1d5245fd91737de507 obsolete
+";
+
+        let conffiles = parse_conffiles(&status[..])
+            .unwrap();
+
+        assert_eq!(conffiles.len(), 4);
+        assert_eq! {
+            conffiles[Path::new("/etc/pam.d/other")],
+            decode_md5("31aa7f2181889ffb00b87df4126d1701").unwrap()
+        };
+        assert!(conffiles.contains_key(Path::new("/etc/pam.d/sshd")));
+        assert!(conffiles.contains_key(Path::new("/etc/init.d/ssh")));
+        assert!(!conffiles.contains_key(Path::new("/etc/ssh/moduli")));
+    }
+
+    #[test]
+    fn parse_conffiles_description_ignored() {
+        let status = b"\
+Package: foo
+Description: Foo
+ /etc/foo 87fc76f18e98ee7d3848f6b81b3391e5
+";
+
+        assert!(parse_conffiles(&status[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decode_md5_invalid() {
+        assert_eq!(decode_md5("newconffile"), None);
+        assert_eq!(decode_md5("zzfc76f18e98ee7d3848f6b81b3391e5"), None);
+    }
+
+    #[test]
+    fn origin_dpkg() {
+        use md5::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let vendor_path = tempdir.path().join("vendor");
+        std::fs::write(&vendor_path, b"foo")
+            .unwrap();
+
+        let modified_path = tempdir.path().join("modified");
+        std::fs::write(&modified_path, b"bar")
+            .unwrap();
+
+        let admin_path = tempdir.path().join("admin");
+        std::fs::write(&admin_path, b"baz")
+            .unwrap();
+
+        let baseline = Baseline::Dpkg(HashMap::from([
+            (vendor_path.clone(), md5::Md5::digest(b"foo").into()),
+            (modified_path.clone(), md5::Md5::digest(b"foo").into()),
+        ]));
+
+        assert_eq!(baseline.origin(&vendor_path), Origin::Vendor);
+        assert_eq!(baseline.origin(&modified_path), Origin::Modified);
+        assert_eq!(baseline.origin(&admin_path), Origin::Admin);
+    }
+
+    #[test]
+    fn is_rpm_modified_ok() {
+        let output = "\
+S.5....T.  c /etc/pam.d/sshd
+.M.......  c /etc/pam.d/login
+missing   c /etc/pam.d/su
+";
+
+        assert!(is_rpm_modified(output, Path::new("/etc/pam.d/sshd")));
+        assert!(!is_rpm_modified(output, Path::new("/etc/pam.d/login")));
+        assert!(is_rpm_modified(output, Path::new("/etc/pam.d/su")));
+        assert!(!is_rpm_modified(output, Path::new("/etc/pam.d/other")));
+    }
+}
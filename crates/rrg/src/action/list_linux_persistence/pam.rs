@@ -0,0 +1,234 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use super::baseline::Baseline;
+use super::{Item, Location};
+
+/// Path to the directory with PAM configuration of services.
+const CONFIG_DIR_PATH: &str = "/etc/pam.d";
+
+/// Paths to directories in which PAM modules given by name are looked up.
+///
+/// The actual directory is a compile-time setting of the PAM library and
+/// differs between distributions and architectures.
+const MODULE_DIR_PATHS: &[&str] = &[
+    "/lib/security",
+    "/lib64/security",
+    "/usr/lib/security",
+    "/usr/lib64/security",
+    "/lib/x86_64-linux-gnu/security",
+    "/usr/lib/x86_64-linux-gnu/security",
+    "/lib/aarch64-linux-gnu/security",
+    "/usr/lib/aarch64-linux-gnu/security",
+];
+
+/// Entry of a PAM configuration file referencing a module.
+#[derive(Debug, PartialEq, Eq)]
+struct Entry<'a> {
+    /// Number of the line (starting at 1) of the entry.
+    line_number: usize,
+    /// Line of the entry.
+    line: &'a str,
+    /// Name of or path to the module.
+    module: &'a str,
+    /// Arguments passed to the module.
+    args: Vec<&'a str>,
+}
+
+/// Returns items corresponding to modules referenced by PAM configuration.
+pub fn items(baseline: &Baseline) -> Vec<Item> {
+    let entries = match std::fs::read_dir(CONFIG_DIR_PATH) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list PAM configuration: {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut paths = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut items = Vec::new();
+
+    for path in paths {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) => {
+                log::warn!("failed to read '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        let origin = baseline.origin(&path);
+
+        for entry in parse(&content) {
+            items.push(Item {
+                location: Location::PamModule,
+                label: format!("{}:{}", path.display(), entry.line_number),
+                path: path.clone(),
+                origin,
+                excerpt: String::from(entry.line),
+                program: Some(module_path(entry.module)),
+                args: entry.args.into_iter().map(String::from).collect(),
+                program_sha256: None,
+            });
+        }
+    }
+
+    items
+}
+
+/// Parses entries referencing modules from PAM configuration of a service.
+///
+/// Every entry is in the `<type> <control> <module> <args>...` format, where
+/// control can be a bracketed list of actions containing whitespace. Entries
+/// including configuration of other services (with `include` or `substack`
+/// control or the `@include` directive) do not reference modules and are
+/// skipped. See the [documentation][1] for details about the format.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/pam.d.5.html
+fn parse(content: &str) -> Vec<Entry<'_>> {
+    let mut entries = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+
+        let Some((_, rest)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let rest = rest.trim_start();
+
+        let (control, rest) = if rest.starts_with('[') {
+            match rest.split_once(']') {
+                Some((control, rest)) => (control, rest),
+                None => continue,
+            }
+        } else {
+            match rest.split_once(char::is_whitespace) {
+                Some((control, rest)) => (control, rest),
+                None => continue,
+            }
+        };
+
+        if control == "include" || control == "substack" {
+            continue;
+        }
+
+        // Comments can also follow the entry on the same line.
+        let rest = rest.split_once('#').map_or(rest, |(rest, _)| rest);
+
+        let mut words = rest.split_whitespace();
+        let Some(module) = words.next() else {
+            continue;
+        };
+
+        entries.push(Entry {
+            line_number: index + 1,
+            line,
+            module,
+            args: words.collect(),
+        });
+    }
+
+    entries
+}
+
+/// Resolves the path to the PAM module with the given name.
+///
+/// If the module cannot be found in any of the known directories, the name is
+/// returned as it is.
+fn module_path(module: &str) -> PathBuf {
+    let path = Path::new(module);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    MODULE_DIR_PATHS.iter()
+        .map(|dir_path| Path::new(dir_path).join(path))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_ok() {
+        let content = "\
+# PAM configuration for the Secure Shell service
+
+@include common-auth
+account    required     pam_nologin.so
+session [success=ok ignore=ignore module_unknown=ignore default=bad] pam_selinux.so close
+session    required     pam_env.so user_readenv=1 envfile=/etc/default/locale # Comment.
+-session   optional     pam_systemd.so
+auth       sufficient   /opt/evil/pam_backdoor.so
+auth       include      system-auth
+";
+
+        assert_eq!(parse(content), vec![
+            Entry {
+                line_number: 4,
+                line: "account    required     pam_nologin.so",
+                module: "pam_nologin.so",
+                args: vec![],
+            },
+            Entry {
+                line_number: 5,
+                line: "session [success=ok ignore=ignore module_unknown=ignore default=bad] pam_selinux.so close",
+                module: "pam_selinux.so",
+                args: vec!["close"],
+            },
+            Entry {
+                line_number: 6,
+                line: "session    required     pam_env.so user_readenv=1 envfile=/etc/default/locale # Comment.",
+                module: "pam_env.so",
+                args: vec!["user_readenv=1", "envfile=/etc/default/locale"],
+            },
+            Entry {
+                line_number: 7,
+                line: "-session   optional     pam_systemd.so",
+                module: "pam_systemd.so",
+                args: vec![],
+            },
+            Entry {
+                line_number: 8,
+                line: "auth       sufficient   /opt/evil/pam_backdoor.so",
+                module: "/opt/evil/pam_backdoor.so",
+                args: vec![],
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse("auth\nauth required\nsession [default=bad pam_foo.so\n"), vec![]);
+    }
+
+    #[test]
+    fn module_path_absolute() {
+        assert_eq!(module_path("/opt/foo/pam_foo.so"), Path::new("/opt/foo/pam_foo.so"));
+    }
+
+    #[test]
+    fn module_path_not_found() {
+        assert_eq! {
+            module_path("pam_rrg_non_existent.so"),
+            Path::new("pam_rrg_non_existent.so")
+        };
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use super::baseline::Baseline;
+use super::{Item, Location};
+
+/// Path to the file with libraries preloaded into every process.
+const PATH: &str = "/etc/ld.so.preload";
+
+/// Returns items corresponding to libraries listed in `/etc/ld.so.preload`.
+pub fn items(baseline: &Baseline) -> Vec<Item> {
+    let path = Path::new(PATH);
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to read '{PATH}': {error}");
+            return Vec::new();
+        }
+    };
+
+    let origin = baseline.origin(path);
+
+    parse(&content).into_iter()
+        .map(|(line_number, line, library)| Item {
+            location: Location::LdSoPreload,
+            path: path.to_path_buf(),
+            label: format!("{PATH}:{line_number}"),
+            origin,
+            excerpt: String::from(line),
+            program: Some(PathBuf::from(library)),
+            args: Vec::new(),
+            program_sha256: None,
+        })
+        .collect()
+}
+
+/// Parses libraries (with line numbers and lines) from `/etc/ld.so.preload`.
+///
+/// Libraries are separated with whitespace or colons [1]. Lines starting with
+/// `#` are comments.
+///
+/// [1]: https://man7.org/linux/man-pages/man8/ld.so.8.html#FILES
+fn parse(content: &str) -> Vec<(usize, &str, &str)> {
+    let mut libraries = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+
+        libraries.extend(line.split(|char: char| char.is_whitespace() || char == ':')
+            .filter(|library| !library.is_empty())
+            .map(|library| (index + 1, line, library)));
+    }
+
+    libraries
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_ok() {
+        let content = "\
+# Comment.
+/usr/lib/libfoo.so
+
+/opt/bar/libbar.so:/opt/baz/libbaz.so /lib/libquux.so
+";
+
+        assert_eq!(parse(content), vec![
+            (2, "/usr/lib/libfoo.so", "/usr/lib/libfoo.so"),
+            (4, "/opt/bar/libbar.so:/opt/baz/libbaz.so /lib/libquux.so", "/opt/bar/libbar.so"),
+            (4, "/opt/bar/libbar.so:/opt/baz/libbaz.so /lib/libquux.so", "/opt/baz/libbaz.so"),
+            (4, "/opt/bar/libbar.so:/opt/baz/libbaz.so /lib/libquux.so", "/lib/libquux.so"),
+        ]);
+    }
+
+    #[test]
+    fn parse_empty() {
+        assert_eq!(parse(""), vec![]);
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use super::baseline::Baseline;
+use super::{Item, Location};
+
+/// Path to the directory with scripts sourced by login shells.
+const PROFILE_DIR_PATH: &str = "/etc/profile.d";
+
+/// Paths at which the `rc.local` script can be located.
+///
+/// On Red Hat based distributions `/etc/rc.local` is usually a symlink to the
+/// script in `/etc/rc.d`.
+const RC_LOCAL_PATHS: [&str; 2] = [
+    "/etc/rc.local",
+    "/etc/rc.d/rc.local",
+];
+
+/// Returns items corresponding to shell profile scripts and `rc.local`.
+pub fn items(baseline: &Baseline) -> Vec<Item> {
+    let mut items = Vec::new();
+
+    for path in profile_paths() {
+        items.push(item(Location::ShellProfile, path, baseline));
+    }
+
+    let mut rc_local_paths = std::collections::HashSet::new();
+    for path in RC_LOCAL_PATHS {
+        let path = Path::new(path);
+
+        // We resolve symlinks so that the same script is not reported twice.
+        let path = match std::fs::canonicalize(path) {
+            Ok(path) => path,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to resolve '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        if rc_local_paths.insert(path.clone()) {
+            items.push(item(Location::RcLocal, path, baseline));
+        }
+    }
+
+    items
+}
+
+/// Returns paths to all scripts in the profile directory.
+fn profile_paths() -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(PROFILE_DIR_PATH) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list '{PROFILE_DIR_PATH}': {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut paths = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+}
+
+/// Returns an item corresponding to the script at the given path.
+///
+/// Scripts are executed (or sourced) themselves, so they are also reported as
+/// the program of the item.
+fn item(location: Location, path: PathBuf, baseline: &Baseline) -> Item {
+    let label = path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    Item {
+        location,
+        label,
+        origin: baseline.origin(&path),
+        excerpt: super::excerpt_file(&path),
+        program: Some(path.clone()),
+        args: Vec::new(),
+        program_sha256: None,
+        path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn item_script() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.sh");
+        std::fs::write(&path, "export PATH=/opt/foo/bin:$PATH\n")
+            .unwrap();
+
+        let item = item(Location::ShellProfile, path.clone(), &Baseline::None);
+        assert_eq!(item.location, Location::ShellProfile);
+        assert_eq!(item.label, "foo.sh");
+        assert_eq!(item.origin, super::super::Origin::Unknown);
+        assert_eq!(item.excerpt, "export PATH=/opt/foo/bin:$PATH\n");
+        assert_eq!(item.program, Some(path));
+    }
+}
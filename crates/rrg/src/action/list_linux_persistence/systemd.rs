@@ -0,0 +1,332 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use crate::systemd::Unit;
+
+use super::{Item, Location};
+
+/// Paths to directories with system generators.
+///
+/// Generators are executed early at boot and on every reload of systemd to
+/// create units dynamically [1].
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.generator.html
+const GENERATOR_DIR_PATHS: &[&str] = &[
+    "/etc/systemd/system-generators",
+    "/run/systemd/system-generators",
+    "/usr/local/lib/systemd/system-generators",
+    "/usr/lib/systemd/system-generators",
+    "/lib/systemd/system-generators",
+];
+
+/// Returns items corresponding to enabled units, drop-ins and generators.
+pub fn items() -> Vec<Item> {
+    let mut items = Vec::new();
+
+    for (name, link_path) in crate::systemd::enabled_units() {
+        // Enabling units creates symlinks to their files, so resolving them
+        // gives the unit file that is actually used.
+        let path = match std::fs::canonicalize(&link_path) {
+            Ok(path) => path,
+            Err(error) => {
+                log::warn!("failed to resolve unit '{}': {error}", link_path.display());
+                continue;
+            }
+        };
+
+        // Masked units are symlinks to `/dev/null`, so there is nothing to run.
+        if path == Path::new("/dev/null") {
+            continue;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => items.push(unit_item(&name, path, &content)),
+            Err(error) => log::warn!("failed to read unit '{}': {error}", path.display()),
+        }
+    }
+
+    for (name, path) in crate::systemd::drop_ins() {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => items.push(drop_in_item(&name, path, &content)),
+            Err(error) => log::warn!("failed to read drop-in '{}': {error}", path.display()),
+        }
+    }
+
+    for path in generator_paths() {
+        items.push(generator_item(path));
+    }
+
+    items
+}
+
+/// Returns an item corresponding to the enabled unit with the given name.
+///
+/// Units other than services (e.g. timers) do not execute anything on their
+/// own, so for them the command of the service they activate is reported.
+fn unit_item(name: &str, path: PathBuf, content: &str) -> Item {
+    let (program, args) = match Unit::parse(content.as_bytes()) {
+        Ok(unit) => match exec_start(name, &unit) {
+            Some((unit_name, command)) => program_args(&unit_name, &command),
+            None => (None, Vec::new()),
+        },
+        Err(error) => {
+            log::warn!("failed to parse unit '{}': {error}", path.display());
+            (None, Vec::new())
+        }
+    };
+
+    Item {
+        location: Location::SystemdUnit,
+        label: String::from(name),
+        origin: super::origin_from_path(&path),
+        excerpt: super::excerpt_file(&path),
+        program,
+        args,
+        program_sha256: None,
+        path,
+    }
+}
+
+/// Returns an item corresponding to a drop-in of the unit with the given name.
+fn drop_in_item(name: &str, path: PathBuf, content: &str) -> Item {
+    let (program, args) = match Unit::parse(content.as_bytes()) {
+        Ok(unit) => match unit.get_all("Service", "ExecStart").first() {
+            Some(command) => program_args(name, command),
+            None => (None, Vec::new()),
+        },
+        Err(error) => {
+            log::warn!("failed to parse drop-in '{}': {error}", path.display());
+            (None, Vec::new())
+        }
+    };
+
+    Item {
+        location: Location::SystemdDropIn,
+        label: String::from(name),
+        origin: super::origin_from_path(&path),
+        excerpt: super::excerpt_file(&path),
+        program,
+        args,
+        program_sha256: None,
+        path,
+    }
+}
+
+/// Returns an item corresponding to the generator at the given path.
+fn generator_item(path: PathBuf) -> Item {
+    let label = path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    Item {
+        location: Location::SystemdGenerator,
+        label,
+        origin: super::origin_from_path(&path),
+        excerpt: super::excerpt_file(&path),
+        program: Some(path.clone()),
+        args: Vec::new(),
+        program_sha256: None,
+        path,
+    }
+}
+
+/// Returns paths to all generators on the system.
+fn generator_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    for dir_path in GENERATOR_DIR_PATHS {
+        let entries = match std::fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to list generators in '{dir_path}': {error}");
+                continue;
+            }
+        };
+
+        let mut dir_paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        dir_paths.sort();
+
+        paths.extend(dir_paths);
+    }
+
+    paths
+}
+
+/// Returns the main command executed when the given unit is started.
+///
+/// The command is returned together with the name of the unit that defines it
+/// (needed to expand specifiers).
+fn exec_start(name: &str, unit: &Unit) -> Option<(String, String)> {
+    let (stem, unit_type) = name.rsplit_once('.')?;
+
+    let section = match unit_type {
+        // Service units can have more than one `ExecStart=` (for oneshot ones)
+        // but the first one is always the main binary.
+        "service" => {
+            let command = unit.get_all("Service", "ExecStart").first()?.to_string();
+            return Some((String::from(name), command));
+        }
+        "timer" => "Timer",
+        "socket" => "Socket",
+        "path" => "Path",
+        _ => return None,
+    };
+
+    // By default, units activate the service with the same name. Sockets that
+    // accept connections activate instances of a template service instead [1].
+    //
+    // [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.socket.html#Accept=
+    let service_name = match unit.get(section, "Unit") {
+        Some(service_name) => String::from(service_name),
+        None if unit.get(section, "Accept") == Some("yes") => format!("{stem}@.service"),
+        None => format!("{stem}.service"),
+    };
+
+    let service_path = find_unit(&service_name)?;
+    let service = match Unit::open(&service_path) {
+        Ok(service) => service,
+        Err(error) => {
+            log::warn!("failed to parse unit '{}': {error}", service_path.display());
+            return None;
+        }
+    };
+
+    let command = service.get_all("Service", "ExecStart").first()?.to_string();
+    Some((service_name, command))
+}
+
+/// Returns the path of the unit file with the given name.
+///
+/// For instances of template units (e.g. `foo@bar.service`) without a unit
+/// file of their own, the path of the template (`foo@.service`) is returned.
+fn find_unit(name: &str) -> Option<PathBuf> {
+    if let Some(path) = crate::systemd::find_unit(name) {
+        return Some(path);
+    }
+
+    let (prefix, rest) = name.split_once('@')?;
+    let (_, unit_type) = rest.rsplit_once('.')?;
+    crate::systemd::find_unit(&format!("{prefix}@.{unit_type}"))
+}
+
+/// Splits the command of the unit with the given name into program and args.
+fn program_args(unit_name: &str, command: &str) -> (Option<PathBuf>, Vec<String>) {
+    let stripped = crate::systemd::strip_exec_prefix(command);
+    // With the `@` prefix, the second word is passed as `argv[0]` instead of
+    // the program path, so it is not an argument in our sense.
+    let has_argv0 = command[..command.len() - stripped.len()].contains('@');
+
+    let command = crate::systemd::expand_specifiers(stripped, unit_name);
+
+    let mut words = crate::systemd::split_exec(&command).into_iter();
+    let program = words.next().map(PathBuf::from);
+    if has_argv0 {
+        words.next();
+    }
+
+    (program, words.collect())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn unit_item_template() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("systemd_template.service");
+
+        let item = unit_item("backup@mnt-data.service", path.clone(), include_str! {
+            "../../../testdata/systemd_template.service"
+        });
+
+        assert_eq!(item.location, Location::SystemdUnit);
+        assert_eq!(item.label, "backup@mnt-data.service");
+        assert_eq!(item.path, path);
+        assert_eq!(item.program.as_deref(), Some(Path::new("/usr/bin/backup")));
+        assert_eq!(item.args, vec![
+            "--source", "/mnt/data",
+            "--dest", "/var/backups/mnt-data",
+            "--label", "backup of backup",
+        ]);
+        assert!(item.excerpt.contains("ExecStart="));
+    }
+
+    #[test]
+    fn unit_item_target() {
+        let item = unit_item("multi-user.target", PathBuf::from("/usr/lib/systemd/system/multi-user.target"), "\
+[Unit]
+Description=Multi-User System
+Requires=basic.target
+");
+
+        assert_eq!(item.origin, super::super::Origin::Vendor);
+        assert_eq!(item.program, None);
+        assert!(item.args.is_empty());
+    }
+
+    #[test]
+    fn drop_in_item_reset() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("systemd_drop_in.conf");
+
+        let item = drop_in_item("ssh.service", path, include_str! {
+            "../../../testdata/systemd_drop_in.conf"
+        });
+
+        assert_eq!(item.location, Location::SystemdDropIn);
+        assert_eq!(item.label, "ssh.service");
+        assert_eq!(item.program.as_deref(), Some(Path::new("/tmp/.cache/payload")));
+        assert_eq!(item.args, vec!["--connect", "10.0.0.1 443"]);
+    }
+
+    #[test]
+    fn exec_start_service() {
+        let unit = Unit::parse(&b"\
+[Service]
+ExecStart=+/usr/sbin/foo -d
+ExecStart=/usr/sbin/foo-helper
+"[..]).unwrap();
+
+        assert_eq! {
+            exec_start("foo.service", &unit),
+            Some((String::from("foo.service"), String::from("+/usr/sbin/foo -d")))
+        };
+    }
+
+    #[test]
+    fn exec_start_unknown_type() {
+        let unit = Unit::parse(&b"\
+[Mount]
+What=/dev/sda1
+Where=/mnt
+"[..]).unwrap();
+
+        assert_eq!(exec_start("mnt.mount", &unit), None);
+    }
+
+    #[test]
+    fn program_args_prefix() {
+        assert_eq! {
+            program_args("foo.service", "-@/usr/bin/foo foo --bar=%n"),
+            (Some(PathBuf::from("/usr/bin/foo")), vec![String::from("--bar=foo.service")])
+        };
+    }
+
+    #[test]
+    fn program_args_empty() {
+        assert_eq!(program_args("foo.service", ""), (None, vec![]));
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use super::{Item, Location};
+
+/// Paths to directories with udev rules.
+///
+/// The paths are ordered by priority: if rules with the same file name exist
+/// in more than one of them, the first one takes precedence [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man7/udev.7.html#RULES_FILES
+const RULES_DIR_PATHS: &[&str] = &[
+    "/etc/udev/rules.d",
+    "/run/udev/rules.d",
+    "/usr/local/lib/udev/rules.d",
+    "/usr/lib/udev/rules.d",
+    "/lib/udev/rules.d",
+];
+
+/// Paths to directories in which programs given without a path are looked up.
+const PROGRAM_DIR_PATHS: &[&str] = &[
+    "/usr/lib/udev",
+    "/lib/udev",
+];
+
+/// `RUN` assignment of a udev rule.
+#[derive(Debug, PartialEq, Eq)]
+struct Run {
+    /// Number of the line (starting at 1) of the rule.
+    line_number: usize,
+    /// Rule with the assignment (with continuation lines joined).
+    rule: String,
+    /// Command executed by the rule.
+    command: String,
+}
+
+/// Returns items corresponding to programs executed by udev rules.
+pub fn items() -> Vec<Item> {
+    let mut items = Vec::new();
+
+    for path in rules_paths() {
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) => {
+                log::warn!("failed to read udev rules '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        let origin = super::origin_from_path(&path);
+
+        for run in parse(&content) {
+            let mut words = run.command.split_whitespace();
+
+            items.push(Item {
+                location: Location::UdevRule,
+                label: format!("{}:{}", path.display(), run.line_number),
+                path: path.clone(),
+                origin,
+                program: words.next().map(program_path),
+                args: words.map(String::from).collect(),
+                excerpt: run.rule,
+                program_sha256: None,
+            });
+        }
+    }
+
+    items
+}
+
+/// Returns paths to all effective rules files on the system.
+fn rules_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut names = std::collections::HashSet::new();
+
+    for dir_path in RULES_DIR_PATHS {
+        let entries = match std::fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to list udev rules in '{dir_path}': {error}");
+                continue;
+            }
+        };
+
+        let mut dir_paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == Some(std::ffi::OsStr::new("rules")))
+            .collect::<Vec<_>>();
+        dir_paths.sort();
+
+        for path in dir_paths {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+
+            if names.insert(name.to_os_string()) {
+                paths.push(path);
+            }
+        }
+    }
+
+    paths
+}
+
+/// Parses `RUN` assignments of programs from the given udev rules.
+///
+/// Rules are comma-separated lists of `<key><operator>"<value>"` pairs where
+/// lines ending with a backslash are continued in the next line. Assignments
+/// of built-in commands (`RUN{builtin}`) are skipped. See the [documentation][1]
+/// for details about the format.
+///
+/// [1]: https://man7.org/linux/man-pages/man7/udev.7.html#RULES_FILES
+fn parse(content: &str) -> Vec<Run> {
+    let mut runs = Vec::new();
+
+    let mut lines = content.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let mut rule = String::from(line.trim());
+        while rule.ends_with('\\') {
+            rule.pop();
+            match lines.next() {
+                Some((_, next)) => rule.push_str(next.trim()),
+                None => break,
+            }
+        }
+
+        if rule.is_empty() || rule.starts_with('#') {
+            continue;
+        }
+
+        for (key, operator, value) in pairs(&rule) {
+            if key != "RUN" && key != "RUN{program}" {
+                continue;
+            }
+            if operator != "=" && operator != "+=" && operator != ":=" {
+                continue;
+            }
+
+            runs.push(Run {
+                line_number: index + 1,
+                rule: rule.clone(),
+                command: value,
+            });
+        }
+    }
+
+    runs
+}
+
+/// Splits the given rule into key, operator and (unquoted) value triples.
+fn pairs(rule: &str) -> Vec<(&str, &str, String)> {
+    let mut pairs = Vec::new();
+
+    let mut rest = rule;
+    loop {
+        rest = rest.trim_start_matches(|char: char| char == ',' || char.is_whitespace());
+        if rest.is_empty() {
+            break;
+        }
+
+        let Some(operator_start) = rest.find(['=', '!', '+', '-', ':']) else {
+            break;
+        };
+        let key = rest[..operator_start].trim();
+
+        let operator_len = match rest.as_bytes().get(operator_start + 1) {
+            Some(b'=') => 2,
+            _ => 1,
+        };
+        let operator = &rest[operator_start..operator_start + operator_len];
+        rest = rest[operator_start + operator_len..].trim_start();
+
+        let Some(quoted) = rest.strip_prefix('"') else {
+            break;
+        };
+
+        // Quotes inside of values have to be escaped with a backslash.
+        let mut value = String::new();
+        let mut end = None;
+        let mut chars = quoted.char_indices();
+        while let Some((index, char)) = chars.next() {
+            match char {
+                '\\' => match chars.next() {
+                    Some((_, '"')) => value.push('"'),
+                    Some((_, escaped)) => {
+                        value.push('\\');
+                        value.push(escaped);
+                    }
+                    None => value.push('\\'),
+                },
+                '"' => {
+                    end = Some(index);
+                    break;
+                }
+                char => value.push(char),
+            }
+        }
+
+        let Some(end) = end else {
+            break;
+        };
+
+        pairs.push((key, operator, value));
+        rest = &quoted[end + 1..];
+    }
+
+    pairs
+}
+
+/// Resolves the path to the program with the given name.
+///
+/// Programs without an absolute path are looked up in the udev directory [1].
+/// If the program cannot be found there, the name is returned as it is.
+///
+/// [1]: https://man7.org/linux/man-pages/man7/udev.7.html#OPERATORS
+fn program_path(program: &str) -> PathBuf {
+    let path = Path::new(program);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    PROGRAM_DIR_PATHS.iter()
+        .map(|dir_path| Path::new(dir_path).join(path))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_fixture() {
+        let runs = parse(include_str!("../../../testdata/udev.rules"));
+
+        assert_eq!(runs.len(), 3);
+
+        assert_eq!(runs[0].line_number, 2);
+        assert_eq!(runs[0].command, "/usr/local/bin/usb-hook --device $kernel");
+
+        assert_eq!(runs[1].line_number, 3);
+        assert_eq!(runs[1].command, "net-helper %k");
+        assert!(runs[1].rule.starts_with("ACTION==\"add\", SUBSYSTEM==\"net\","));
+
+        assert_eq!(runs[2].line_number, 6);
+        assert_eq!(runs[2].command, "/bin/sh -c 'echo \"block\" > /tmp/log'");
+    }
+
+    #[test]
+    fn pairs_operators() {
+        assert_eq!(pairs("KERNEL==\"sd*\", ENV{FOO}!=\"1\", RUN+=\"/bin/foo\", TAG-=\"bar\""), vec![
+            ("KERNEL", "==", String::from("sd*")),
+            ("ENV{FOO}", "!=", String::from("1")),
+            ("RUN", "+=", String::from("/bin/foo")),
+            ("TAG", "-=", String::from("bar")),
+        ]);
+    }
+
+    #[test]
+    fn pairs_unterminated() {
+        assert_eq!(pairs("KERNEL==\"sd*\", RUN+=\"/bin/foo"), vec![
+            ("KERNEL", "==", String::from("sd*")),
+        ]);
+    }
+
+    #[test]
+    fn program_path_absolute() {
+        assert_eq!(program_path("/usr/local/bin/foo"), Path::new("/usr/local/bin/foo"));
+    }
+
+    #[test]
+    fn program_path_not_found() {
+        assert_eq!(program_path("rrg-non-existent-helper"), Path::new("rrg-non-existent-helper"));
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+#[cfg(any(target_os = "macos", test))]
+mod bookmark;
+
+#[cfg(target_os = "macos")]
+mod cron;
+
+#[cfg(target_os = "macos")]
+mod launchd;
+
+#[cfg(any(target_os = "macos", test))]
+mod login_items;
+
+/// A result of the `list_macos_persistence` action.
+#[cfg(target_os = "macos")]
+#[derive(Debug)]
+struct Item {
+    /// Location from which the item was collected.
+    location: Location,
+    /// Path to the file in which the item is defined.
+    path: std::path::PathBuf,
+    /// Location-specific identifier of the item.
+    label: String,
+    /// Name of the user as which the item is executed.
+    user: Option<String>,
+    /// Path to the executable that the item starts.
+    program: Option<std::path::PathBuf>,
+    /// Arguments (excluding `argv[0]`) passed to the executable.
+    args: Vec<String>,
+    /// Location-specific description of when the item is started.
+    trigger: String,
+    /// Whether the item is enabled.
+    enabled: bool,
+}
+
+/// Location from which the item was collected.
+#[cfg(target_os = "macos")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Location {
+    /// Launch daemons.
+    SystemLaunchDaemon,
+    /// System-wide launch agents.
+    SystemLaunchAgent,
+    /// Per-user launch agents.
+    UserLaunchAgent,
+    /// Login items of the background task management agent.
+    LoginItem,
+    /// Entries of crontab files.
+    Crontab,
+    /// Scripts executed by `periodic`.
+    Periodic,
+}
+
+/// Handles invocations of the `list_macos_persistence` action.
+#[cfg(target_os = "macos")]
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut items = Vec::new();
+    items.extend(self::launchd::items());
+    items.extend(self::login_items::items());
+    items.extend(self::cron::items());
+
+    for item in items {
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_macos_persistence` action.
+#[cfg(not(target_os = "macos"))]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_os = "macos")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_macos_persistence::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_macos_persistence::Result::new();
+        proto.set_location(self.location.into());
+        proto.set_path(crate::path::into_proto(self.path));
+        proto.set_label(self.label);
+        if let Some(user) = self.user {
+            proto.set_user(user);
+        }
+        if let Some(program) = self.program {
+            proto.set_program(program.into());
+        }
+        proto.set_args(self.args);
+        proto.set_trigger(self.trigger);
+        proto.set_enabled(self.enabled);
+
+        proto
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl From<Location> for rrg_proto::list_macos_persistence::Location {
+
+    fn from(location: Location) -> rrg_proto::list_macos_persistence::Location {
+        use rrg_proto::list_macos_persistence::Location as Proto;
+
+        match location {
+            Location::SystemLaunchDaemon => Proto::SYSTEM_LAUNCH_DAEMON,
+            Location::SystemLaunchAgent => Proto::SYSTEM_LAUNCH_AGENT,
+            Location::UserLaunchAgent => Proto::USER_LAUNCH_AGENT,
+            Location::LoginItem => Proto::LOGIN_ITEM,
+            Location::Crontab => Proto::CRONTAB,
+            Location::Periodic => Proto::PERIODIC,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "macos")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        // There are always some system launch daemons defined on macOS.
+        let items = session.replies::<Item>().collect::<Vec<_>>();
+        assert!(items.iter().any(|item| {
+            item.location == Location::SystemLaunchDaemon
+        }));
+    }
+
+    #[test]
+    fn handle_no_empty_labels() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.label.is_empty());
+        }
+    }
+}
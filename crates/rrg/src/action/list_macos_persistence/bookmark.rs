@@ -0,0 +1,213 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser of bookmarks (serialized `NSURL` references to files).
+//!
+//! Bookmarks are not documented by Apple, the layout here follows the
+//! [description][1] from the `mac_alias` project. We only need the path of the
+//! file the bookmark points to, so nothing else is parsed.
+//!
+//! [1]: https://mac-alias.readthedocs.io/en/latest/bookmark_fmt.html
+
+/// Magic bytes at the beginning of every bookmark.
+const MAGIC: &[u8] = b"book";
+
+/// Magic value at the beginning of every table of contents.
+const TOC_MAGIC: u32 = 0xFFFF_FFFE;
+
+/// Key of the table of contents entry with path components.
+const PATH_KEY: u32 = 0x1004;
+
+/// Type of items that are UTF-8 strings.
+const STRING_TYPE: u32 = 0x0101;
+
+/// Type of items that are arrays of offsets to other items.
+const ARRAY_TYPE: u32 = 0x0601;
+
+/// Returns the path to the file that the given bookmark points to.
+pub fn path(data: &[u8]) -> Result<std::path::PathBuf, ParseError> {
+    if data.get(..MAGIC.len()) != Some(MAGIC) {
+        return Err(ParseError::InvalidMagic);
+    }
+
+    // The header is followed by the data section, all offsets that follow are
+    // relative to its beginning.
+    let header_size = u32_at(data, 12)? as usize;
+    let data = data.get(header_size..)
+        .ok_or(ParseError::Truncated)?;
+
+    let toc_offset = u32_at(data, 0)? as usize;
+    if u32_at(data, toc_offset + 4)? != TOC_MAGIC {
+        return Err(ParseError::InvalidToc(toc_offset));
+    }
+
+    // There can be more tables of contents but the first one is the one with
+    // the basic information about the target.
+    let count = u32_at(data, toc_offset + 16)? as usize;
+
+    let mut path_offset = None;
+    for index in 0..count {
+        let entry_offset = toc_offset + 20 + index * 12;
+        if u32_at(data, entry_offset)? == PATH_KEY {
+            path_offset = Some(u32_at(data, entry_offset + 4)? as usize);
+            break;
+        }
+    }
+    let path_offset = path_offset
+        .ok_or(ParseError::MissingPath)?;
+
+    let mut path = std::path::PathBuf::from("/");
+    for component_offset in item(data, path_offset, ARRAY_TYPE)?.chunks_exact(4) {
+        let component_offset = u32::from_le_bytes(component_offset.try_into().unwrap());
+        let component = item(data, component_offset as usize, STRING_TYPE)?;
+
+        path.push(String::from_utf8_lossy(component).as_ref());
+    }
+
+    Ok(path)
+}
+
+/// Returns the content of the item of the given type at the given offset.
+fn item(data: &[u8], offset: usize, expected_type: u32) -> Result<&[u8], ParseError> {
+    let len = u32_at(data, offset)? as usize;
+
+    let r#type = u32_at(data, offset + 4)?;
+    if r#type != expected_type {
+        return Err(ParseError::InvalidItem(offset));
+    }
+
+    data.get(offset + 8..)
+        .and_then(|data| data.get(..len))
+        .ok_or(ParseError::Truncated)
+}
+
+/// Reads a little-endian 32-bit integer at the given offset.
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bytes = data.get(offset..)
+        .and_then(|data| data.get(..4))
+        .ok_or(ParseError::Truncated)?;
+
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// An error that might occur when parsing a bookmark.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The bookmark ended prematurely.
+    Truncated,
+    /// The bookmark does not start with the expected magic bytes.
+    InvalidMagic,
+    /// The table of contents at the given offset is malformed.
+    InvalidToc(usize),
+    /// The item at the given offset is not of the expected type.
+    InvalidItem(usize),
+    /// The bookmark does not specify the path of the target.
+    MissingPath,
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+
+        match self {
+            Truncated => write!(fmt, "truncated bookmark"),
+            InvalidMagic => write!(fmt, "invalid bookmark magic"),
+            InvalidToc(offset) => {
+                write!(fmt, "invalid bookmark table of contents at offset {offset}")
+            }
+            InvalidItem(offset) => {
+                write!(fmt, "invalid bookmark item at offset {offset}")
+            }
+            MissingPath => write!(fmt, "bookmark without target path"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Bookmark of `/Applications/Foo.app` (with the header and a single table
+    /// of contents that has only the path entry).
+    const FOO_APP: &[u8] = &[
+        // Header: magic, size, version, header size and reserved bytes.
+        b'b', b'o', b'o', b'k', 0x88, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x04, 0x10, 0x30, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // Offset of the table of contents.
+        0x38, 0x00, 0x00, 0x00,
+        // String item: `Applications`.
+        0x0C, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00,
+        b'A', b'p', b'p', b'l', b'i', b'c', b'a', b't',
+        b'i', b'o', b'n', b's',
+        // String item: `Foo.app` (padded to 4 bytes).
+        0x07, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00,
+        b'F', b'o', b'o', b'.', b'a', b'p', b'p', 0x00,
+        // Array item with offsets of both strings.
+        0x08, 0x00, 0x00, 0x00, 0x01, 0x06, 0x00, 0x00,
+        0x04, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00,
+        // Table of contents: size, magic, identifier, next and count.
+        0x14, 0x00, 0x00, 0x00, 0xFE, 0xFF, 0xFF, 0xFF,
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00,
+        // Entry: key, offset and reserved field.
+        0x04, 0x10, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn path_ok() {
+        assert_eq!(path(FOO_APP), Ok(std::path::PathBuf::from("/Applications/Foo.app")));
+    }
+
+    #[test]
+    fn path_invalid_magic() {
+        // Alias records (used before bookmarks were introduced) are not
+        // supported.
+        let mut data = FOO_APP.to_vec();
+        data[..4].copy_from_slice(b"alis");
+
+        assert_eq!(path(&data), Err(ParseError::InvalidMagic));
+    }
+
+    #[test]
+    fn path_invalid_toc() {
+        let mut data = FOO_APP.to_vec();
+        data[0x30 + 0x38 + 4] = 0x00;
+
+        assert_eq!(path(&data), Err(ParseError::InvalidToc(0x38)));
+    }
+
+    #[test]
+    fn path_missing_path() {
+        let mut data = FOO_APP.to_vec();
+        data[0x30 + 0x38 + 20] = 0x05;
+
+        assert_eq!(path(&data), Err(ParseError::MissingPath));
+    }
+
+    #[test]
+    fn path_invalid_item() {
+        // Point the path entry at one of the strings instead of the array.
+        let mut data = FOO_APP.to_vec();
+        data[0x30 + 0x38 + 24] = 0x04;
+
+        assert_eq!(path(&data), Err(ParseError::InvalidItem(0x04)));
+    }
+
+    #[test]
+    fn path_truncated() {
+        assert_eq!(path(&FOO_APP[..FOO_APP.len() - 12]), Err(ParseError::Truncated));
+        assert_eq!(path(&FOO_APP[..8]), Err(ParseError::Truncated));
+    }
+}
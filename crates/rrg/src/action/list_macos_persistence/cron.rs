@@ -0,0 +1,254 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use crate::cron::{crontab_paths, parse_crontab, Format};
+
+use super::{Item, Location};
+
+/// Path to the system crontab file.
+const SYSTEM_CRONTAB_PATH: &str = "/etc/crontab";
+
+/// Path to the directory with per-user crontab files.
+///
+/// This is where `crontab` stores them on macOS (it is a symlink to the
+/// `/private/var/at/tabs` directory, readable only by root).
+const USER_CRONTAB_DIR_PATH: &str = "/usr/lib/cron/tabs";
+
+/// Paths to directories with scripts executed by `periodic`.
+///
+/// The first one is for scripts shipped with the system, the second one is the
+/// default value of `local_periodic` from `/etc/defaults/periodic.conf`.
+const PERIODIC_DIR_PATHS: &[&str] = &[
+    "/etc/periodic",
+    "/usr/local/etc/periodic",
+];
+
+/// Periods for which `periodic` is invoked by launchd.
+///
+/// Each period corresponds to a subdirectory of the periodic directories.
+const PERIODS: &[&str] = &["daily", "weekly", "monthly"];
+
+/// Shell that cron uses to execute commands.
+///
+/// Crontabs can override it with the `SHELL` variable but this is rare enough
+/// that we do not bother tracking assignments.
+const SHELL_PATH: &str = "/bin/sh";
+
+/// Returns items corresponding to all crontab entries and periodic scripts.
+pub fn items() -> Vec<Item> {
+    let mut items = Vec::new();
+
+    items.extend(crontab_items(Path::new(SYSTEM_CRONTAB_PATH), Format::System, None));
+
+    for path in crontab_paths(Path::new(USER_CRONTAB_DIR_PATH)) {
+        // Per-user crontab files are named after the users that own them.
+        let user = path.file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+
+        items.extend(crontab_items(&path, Format::User, user));
+    }
+
+    for dir_path in PERIODIC_DIR_PATHS {
+        for period in PERIODS {
+            items.extend(periodic_items(&Path::new(dir_path).join(period), period));
+        }
+    }
+
+    items
+}
+
+/// Returns items corresponding to entries of the crontab file at the given path.
+///
+/// `user` is used for entries that do not specify the user explicitly.
+fn crontab_items(path: &Path, format: Format, user: Option<String>) -> Vec<Item> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to open crontab '{}': {error}", path.display());
+            return Vec::new();
+        }
+    };
+
+    let entries = match parse_crontab(std::io::BufReader::new(file), format) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to parse crontab '{}': {error}", path.display());
+            return Vec::new();
+        }
+    };
+
+    entries.into_iter()
+        .map(|entry| Item {
+            location: Location::Crontab,
+            path: path.to_path_buf(),
+            label: format!("{}:{}", path.display(), entry.line),
+            user: entry.user.or_else(|| user.clone()),
+            program: Some(PathBuf::from(SHELL_PATH)),
+            args: vec![String::from("-c"), entry.command],
+            trigger: entry.schedule,
+            // Disabled entries are commented out, so everything that we parse
+            // is enabled.
+            enabled: true,
+        })
+        .collect()
+}
+
+/// Returns items corresponding to scripts in the given periodic directory.
+///
+/// `period` is the name of the period that the scripts are executed for.
+fn periodic_items(dir_path: &Path, period: &str) -> Vec<Item> {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list periodic scripts in '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut items = Vec::new();
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::warn!("failed to read periodic script entry: {error}");
+                continue;
+            }
+        };
+
+        let path = entry.path();
+
+        // We follow symlinks here as scripts are often linked from elsewhere.
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => continue,
+            Err(error) => {
+                log::warn!("failed to stat periodic script '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        items.push(Item {
+            location: Location::Periodic,
+            label: entry.file_name().to_string_lossy().into_owned(),
+            user: Some(String::from("root")),
+            program: Some(path.clone()),
+            args: Vec::new(),
+            trigger: String::from(period),
+            // `periodic` silently skips scripts that are not executable.
+            enabled: metadata.permissions().mode() & 0o111 != 0,
+            path,
+        });
+    }
+
+    items.sort_by(|a, b| a.path.cmp(&b.path));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn crontab_items_system() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("crontab");
+        std::fs::write(&path, b"\
+# /etc/crontab
+SHELL=/bin/sh
+
+*/10 * * * * root /usr/libexec/atrun
+@reboot      nobody /usr/local/bin/beacon --quiet
+").unwrap();
+
+        let items = crontab_items(&path, Format::System, None);
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].location, Location::Crontab);
+        assert_eq!(items[0].label, format!("{}:4", path.display()));
+        assert_eq!(items[0].user.as_deref(), Some("root"));
+        assert_eq!(items[0].program.as_deref(), Some(Path::new("/bin/sh")));
+        assert_eq!(items[0].args, vec!["-c", "/usr/libexec/atrun"]);
+        assert_eq!(items[0].trigger, "*/10 * * * *");
+
+        assert_eq!(items[1].user.as_deref(), Some("nobody"));
+        assert_eq!(items[1].args, vec!["-c", "/usr/local/bin/beacon --quiet"]);
+        assert_eq!(items[1].trigger, "@reboot");
+    }
+
+    #[test]
+    fn crontab_items_user() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("alice");
+        std::fs::write(&path, b"0 9 * * 1-5 open -a Mail\n")
+            .unwrap();
+
+        let items = crontab_items(&path, Format::User, Some(String::from("alice")));
+        assert_eq!(items.len(), 1);
+
+        assert_eq!(items[0].user.as_deref(), Some("alice"));
+        assert_eq!(items[0].args, vec!["-c", "open -a Mail"]);
+        assert_eq!(items[0].trigger, "0 9 * * 1-5");
+    }
+
+    #[test]
+    fn crontab_items_not_found() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let items = crontab_items(&tempdir.path().join("foo"), Format::System, None);
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn periodic_items_executable() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let enabled_path = tempdir.path().join("100.clean-logs");
+        std::fs::write(&enabled_path, b"#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&enabled_path, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        let disabled_path = tempdir.path().join("999.local");
+        std::fs::write(&disabled_path, b"#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&disabled_path, std::fs::Permissions::from_mode(0o644))
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("subdir"))
+            .unwrap();
+
+        let items = periodic_items(tempdir.path(), "weekly");
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].location, Location::Periodic);
+        assert_eq!(items[0].label, "100.clean-logs");
+        assert_eq!(items[0].program.as_deref(), Some(enabled_path.as_path()));
+        assert_eq!(items[0].trigger, "weekly");
+        assert!(items[0].enabled);
+
+        assert_eq!(items[1].label, "999.local");
+        assert!(!items[1].enabled);
+    }
+
+    #[test]
+    fn items_ok() {
+        // macOS ships with a few daily, weekly and monthly scripts.
+        assert!(items().iter().any(|item| item.location == Location::Periodic));
+    }
+}
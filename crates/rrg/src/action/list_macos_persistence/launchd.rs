@@ -0,0 +1,119 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use super::{Item, Location};
+
+/// Returns items corresponding to all launchd jobs on the system.
+pub fn items() -> Vec<Item> {
+    crate::launchd::jobs().into_iter()
+        .filter_map(|job| {
+            let path = job.path.clone()?;
+            Some(item(job, &path))
+        })
+        .collect()
+}
+
+/// Converts the given launchd job defined in the given file to an item.
+fn item(job: crate::launchd::Job, path: &Path) -> Item {
+    Item {
+        location: location(path),
+        path: path.to_path_buf(),
+        program: job.program().map(std::path::PathBuf::from),
+        args: job.args().into_iter().map(String::from).collect(),
+        trigger: job.schedule(),
+        user: job.user,
+        enabled: job.enabled,
+        label: job.label,
+    }
+}
+
+/// Determines the location of a job from the path to its definition.
+///
+/// launchd itself tells daemons and agents apart only by the directory their
+/// definitions are in, so this is what we do as well.
+fn location(path: &Path) -> Location {
+    if path.starts_with(crate::launchd::USERS_PATH) {
+        return Location::UserLaunchAgent;
+    }
+
+    let dir_name = path.parent()
+        .and_then(Path::file_name);
+
+    if dir_name == Some(std::ffi::OsStr::new("LaunchDaemons")) {
+        Location::SystemLaunchDaemon
+    } else {
+        Location::SystemLaunchAgent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn item_xml() {
+        let definition = plist::parse(include_bytes!("../../../testdata/launchd_agent.plist"))
+            .unwrap();
+        let job = crate::launchd::Job::parse(definition)
+            .unwrap();
+
+        let item = item(job, Path::new("/Users/alice/Library/LaunchAgents/com.example.sync.plist"));
+
+        assert_eq!(item.location, Location::UserLaunchAgent);
+        assert_eq!(item.label, "com.example.sync");
+        assert_eq!(item.program.as_deref(), Some(Path::new("/Users/alice/.local/bin/sync")));
+        assert_eq!(item.args, vec!["--quiet", "--config", "/Users/alice/.sync.conf"]);
+        assert_eq!(item.trigger, "StartInterval=300; RunAtLoad; KeepAlive");
+        assert_eq!(item.user, None);
+        assert!(item.enabled);
+    }
+
+    #[test]
+    fn item_binary() {
+        let definition = plist::parse(include_bytes!("../../../testdata/launchd_daemon.plist"))
+            .unwrap();
+
+        // Nested dictionaries and data blobs have to be preserved, even though
+        // we do not report them.
+        let env = definition.get("EnvironmentVariables").unwrap();
+        assert_eq!(env.get("LANG").and_then(plist::Value::as_str), Some("C"));
+        let services = definition.get("MachServices").unwrap();
+        assert!(services.get("com.example.helper.xpc").unwrap().as_dict().is_some());
+        assert!(matches!(
+            definition.get("SignatureData"),
+            Some(plist::Value::Data(data)) if data == b"\x00\xFF\x10\x20"
+        ));
+
+        let job = crate::launchd::Job::parse(definition)
+            .unwrap();
+
+        let item = item(job, Path::new("/Library/LaunchDaemons/com.example.helper.plist"));
+
+        assert_eq!(item.location, Location::SystemLaunchDaemon);
+        assert_eq!(item.label, "com.example.helper");
+        assert_eq!(item.program.as_deref(), Some(Path::new("/Library/PrivilegedHelperTools/com.example.helper")));
+        assert_eq!(item.args, vec!["--daemon"]);
+        assert_eq!(item.trigger, "RunAtLoad; KeepAlive");
+        assert_eq!(item.user.as_deref(), Some("root"));
+        assert!(!item.enabled);
+    }
+
+    #[test]
+    fn location_system_agent() {
+        let path = Path::new("/System/Library/LaunchAgents/com.apple.Finder.plist");
+        assert_eq!(location(path), Location::SystemLaunchAgent);
+    }
+
+    #[test]
+    fn items_ok() {
+        for item in items() {
+            assert!(!item.label.is_empty());
+            assert!(item.path.is_absolute());
+        }
+    }
+}
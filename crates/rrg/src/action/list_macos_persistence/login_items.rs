@@ -0,0 +1,323 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parser of login items of the background task management agent.
+//!
+//! Up until macOS 12, login items (both added by users and registered by apps
+//! through the Service Management framework) are stored in per-user files that
+//! are `NSKeyedArchiver` archives. Newer versions keep them in a system-wide
+//! database readable only by root in a different format, which we do not yet
+//! support.
+
+/// Path (relative to the home directory) of the file with login items.
+#[cfg(target_os = "macos")]
+const RELATIVE_PATH: &str = "Library/Application Support/com.apple.backgroundtaskmanagementagent/backgrounditems.btm";
+
+/// Login item stored in the background task management agent archive.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LoginItem {
+    /// Name of the item (usually the name of the application).
+    pub name: String,
+    /// Path to the item (if its bookmark could be resolved).
+    pub path: Option<std::path::PathBuf>,
+}
+
+/// Returns items corresponding to login items of all users.
+#[cfg(target_os = "macos")]
+pub fn items() -> Vec<super::Item> {
+    let users = match std::fs::read_dir(crate::launchd::USERS_PATH) {
+        Ok(users) => users,
+        Err(error) => {
+            log::warn!("failed to list users: {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut items = Vec::new();
+
+    for entry in users {
+        let home_path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read user entry: {error}");
+                continue;
+            }
+        };
+
+        let Some(user) = home_path.file_name() else {
+            continue;
+        };
+        let user = user.to_string_lossy();
+
+        let path = home_path.join(RELATIVE_PATH);
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to read login items '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        let login_items = match parse(&data) {
+            Ok(login_items) => login_items,
+            Err(error) => {
+                log::warn!("failed to parse login items '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        items.extend(login_items.into_iter().map(|login_item| super::Item {
+            location: super::Location::LoginItem,
+            path: path.clone(),
+            label: login_item.name,
+            user: Some(String::from(user.as_ref())),
+            program: login_item.path,
+            args: Vec::new(),
+            trigger: String::from("login"),
+            enabled: true,
+        }));
+    }
+
+    items
+}
+
+/// Parses login items from the given background task management archive.
+pub fn parse(data: &[u8]) -> Result<Vec<LoginItem>, ParseError> {
+    let archive = plist::parse(data)
+        .map_err(ParseError::Plist)?;
+    let archive = Archive::new(&archive)
+        .ok_or(ParseError::InvalidArchive)?;
+
+    let containers = archive.root()
+        .and_then(|root| archive.get(root, "backgroundItems"))
+        .and_then(|items| archive.get(items, "allContainers"))
+        .and_then(|containers| archive.array(containers))
+        .ok_or(ParseError::MissingContainers)?;
+
+    let mut login_items = Vec::new();
+
+    for container in containers {
+        let Some(items) = archive.get(container, "internalItems") else {
+            continue;
+        };
+        let Some(items) = archive.array(items) else {
+            continue;
+        };
+
+        for item in items {
+            let Some(name) = archive.get(item, "name").and_then(plist::Value::as_str) else {
+                log::warn!("login item without name");
+                continue;
+            };
+
+            let path = match archive.get(item, "bookmark").and_then(|value| archive.data(value)) {
+                Some(bookmark) => match super::bookmark::path(bookmark) {
+                    Ok(path) => Some(path),
+                    Err(error) => {
+                        log::warn!("invalid bookmark of login item '{name}': {error}");
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            login_items.push(LoginItem {
+                name: String::from(name),
+                path,
+            });
+        }
+    }
+
+    Ok(login_items)
+}
+
+/// Wrapper over an `NSKeyedArchiver` archive for following object references.
+///
+/// Objects of the archive are stored in a flat array and refer to each other
+/// by indices into it (encoded as UIDs). All methods of the wrapper resolve the
+/// references, so they always return actual objects.
+struct Archive<'a> {
+    /// Flat list of all the objects in the archive.
+    objects: &'a [plist::Value],
+    /// Dictionary with references to the top-level objects.
+    top: &'a plist::Value,
+}
+
+impl<'a> Archive<'a> {
+
+    /// Wraps the given property list if it is an `NSKeyedArchiver` archive.
+    fn new(value: &'a plist::Value) -> Option<Archive<'a>> {
+        if value.get("$archiver").and_then(plist::Value::as_str) != Some("NSKeyedArchiver") {
+            return None;
+        }
+
+        Some(Archive {
+            objects: value.get("$objects")?.as_array()?,
+            top: value.get("$top")?,
+        })
+    }
+
+    /// Returns the root object of the archive.
+    fn root(&self) -> Option<&'a plist::Value> {
+        self.resolve(self.top.get("root")?)
+    }
+
+    /// Returns the object referenced by the given value.
+    ///
+    /// Values that are not references are returned as they are.
+    fn resolve(&self, value: &'a plist::Value) -> Option<&'a plist::Value> {
+        match value {
+            plist::Value::Uid(uid) => self.objects.get(usize::try_from(*uid).ok()?),
+            value => Some(value),
+        }
+    }
+
+    /// Returns the object under the given key of the given dictionary object.
+    fn get(&self, object: &'a plist::Value, key: &str) -> Option<&'a plist::Value> {
+        self.resolve(object.get(key)?)
+    }
+
+    /// Returns the elements of the given `NSArray` (or plain array) object.
+    fn array(&self, object: &'a plist::Value) -> Option<Vec<&'a plist::Value>> {
+        let elements = match object {
+            plist::Value::Array(elements) => elements,
+            object => object.get("NS.objects")?.as_array()?,
+        };
+
+        elements.iter()
+            .map(|element| self.resolve(element))
+            .collect()
+    }
+
+    /// Returns the bytes of the given `NSData` (or a wrapper of it) object.
+    fn data(&self, object: &'a plist::Value) -> Option<&'a [u8]> {
+        let mut object = object;
+
+        // Data can be wrapped (e.g. by an `NSMutableData` or a bookmark object)
+        // so we unwrap a few levels and give up on anything deeper (or cyclic).
+        for _ in 0..3 {
+            match object {
+                plist::Value::Data(data) => return Some(data),
+                _ => {
+                    object = self.get(object, "NS.data")
+                        .or_else(|| self.get(object, "data"))?;
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// An error that might occur when parsing login items.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The file is not a valid property list.
+    Plist(plist::ParseError),
+    /// The property list is not an `NSKeyedArchiver` archive.
+    InvalidArchive,
+    /// The archive does not have the expected list of item containers.
+    MissingContainers,
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+
+        match self {
+            Plist(error) => write!(fmt, "invalid property list: {error}"),
+            InvalidArchive => write!(fmt, "not a keyed archive"),
+            MissingContainers => write!(fmt, "missing login item containers"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Plist(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_ok() {
+        let items = parse(include_bytes!("../../../testdata/backgrounditems.btm"))
+            .unwrap();
+
+        assert_eq!(items, vec![
+            LoginItem {
+                name: String::from("Foo Helper"),
+                path: Some(std::path::PathBuf::from("/Applications/Foo.app/Contents/Library/LoginItems/Foo Helper.app")),
+            },
+            LoginItem {
+                name: String::from("Bar"),
+                path: Some(std::path::PathBuf::from("/Users/alice/Applications/Bar.app")),
+            },
+            LoginItem {
+                name: String::from("Zażółć"),
+                path: Some(std::path::PathBuf::from("/Applications/Zażółć.app")),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_not_archive() {
+        let data = br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.foo</string>
+</dict>
+</plist>
+"#;
+
+        assert_eq!(parse(data), Err(ParseError::InvalidArchive));
+    }
+
+    #[test]
+    fn parse_missing_containers() {
+        // XML property lists cannot have references, but objects are accepted
+        // in their place, so we can use it for a minimal archive.
+        let data = br#"
+<plist version="1.0">
+<dict>
+	<key>$archiver</key>
+	<string>NSKeyedArchiver</string>
+	<key>$objects</key>
+	<array>
+		<string>$null</string>
+	</array>
+	<key>$top</key>
+	<dict>
+		<key>root</key>
+		<dict>
+			<key>version</key>
+			<integer>2</integer>
+		</dict>
+	</dict>
+</dict>
+</plist>
+"#;
+
+        assert_eq!(parse(data), Err(ParseError::MissingContainers));
+    }
+
+    #[test]
+    fn parse_invalid_plist() {
+        let data = include_bytes!("../../../testdata/backgrounditems.btm");
+
+        assert!(matches!(parse(&data[..64]), Err(ParseError::Plist(_))));
+    }
+}
@@ -7,6 +7,8 @@
 struct Item {
     // Information about the individual filesystem mount.
     mount: ospect::fs::Mount,
+    // Information about space on the mounted filesystem (if available).
+    space: Option<ospect::fs::Space>,
 }
 
 // Handles invocations of the `list_mounts` action.
@@ -26,8 +28,23 @@ where
             }
         };
 
+        // Failing to obtain space information is not critical (e.g. the mount
+        // point might not be accessible to us), we still want to report the
+        // mount itself.
+        let space = match ospect::fs::space(&mount.path) {
+            Ok(space) => Some(space),
+            Err(error) => {
+                log::warn! {
+                    "failed to obtain space information for '{}': {}",
+                    mount.path.display(), error,
+                };
+                None
+            }
+        };
+
         session.reply(Item {
             mount,
+            space,
         })?;
     }
 
@@ -42,6 +59,12 @@ impl crate::response::Item for Item {
         let mut proto = rrg_proto::list_mounts::Result::default();
         proto.set_mount(self.mount.into());
 
+        if let Some(space) = self.space {
+            proto.set_total_bytes(space.total);
+            proto.set_free_bytes(space.free);
+            proto.set_available_bytes(space.available);
+        }
+
         proto
     }
 }
@@ -58,4 +81,38 @@ mod tests {
 
         assert!(session.reply_count() > 0);
     }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_root_mount() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.mount.path == std::path::Path::new("/"))
+            .expect("no root mount");
+
+        assert!(!item.mount.is_pseudo());
+
+        let space = item.space.expect("no root mount space");
+        assert!(space.total > 0);
+        assert!(space.free <= space.total);
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn handle_system_drive_mount() {
+        let system_drive = std::env::var_os("SystemDrive")
+            .expect("no system drive");
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.mount.path.starts_with(&system_drive))
+            .expect("no system drive mount");
+
+        let space = item.space.expect("no system drive mount space");
+        assert!(space.total > 0);
+    }
 }
@@ -0,0 +1,239 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Arguments of the `list_named_pipes` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Whether to connect to pipes to resolve their server processes.
+    resolve_server_pids: bool,
+}
+
+/// A result of the `list_named_pipes` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct Item {
+    /// Name of the pipe (relative to the `\\.\pipe\` prefix).
+    name: std::ffi::OsString,
+    /// Number of currently existing instances of the pipe.
+    instance_count: u32,
+    /// Identifier of the process that created the pipe (if resolved).
+    server_pid: Option<u32>,
+}
+
+/// Handles invocations of the `list_named_pipes` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let pipes = ospect::pipe::pipes()
+        .map_err(crate::session::Error::action)?;
+
+    for pipe in pipes {
+        let pipe = match pipe {
+            Ok(pipe) => pipe,
+            Err(error) => {
+                log::error!("failed to continue listing named pipes: {error}");
+                break;
+            }
+        };
+
+        let server_pid = if args.resolve_server_pids {
+            match pipe.server_pid() {
+                Ok(pid) => Some(pid),
+                Err(error) => {
+                    log::warn! {
+                        "failed to obtain server of pipe '{}': {error}",
+                        pipe.path().display(),
+                    };
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        session.reply(Item {
+            name: pipe.name().to_os_string(),
+            instance_count: pipe.instance_count(),
+            server_pid,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_named_pipes` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_named_pipes::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            resolve_server_pids: proto.resolve_server_pids(),
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_named_pipes::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_named_pipes::Result::new();
+        proto.set_name(self.name.to_string_lossy().into_owned());
+        proto.set_instance_count(self.instance_count);
+        if let Some(server_pid) = self.server_pid {
+            proto.set_server_pid(server_pid);
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_own_pipe() {
+        let pipe = TestPipe::new("handle_own_pipe");
+
+        let args = Args {
+            resolve_server_pids: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.name == pipe.name.as_str())
+            .unwrap();
+
+        assert_eq!(item.instance_count, 1);
+        assert_eq!(item.server_pid, Some(std::process::id()));
+    }
+
+    #[test]
+    fn handle_no_server_pids() {
+        let pipe = TestPipe::new("handle_no_server_pids");
+
+        let args = Args {
+            resolve_server_pids: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.name == pipe.name.as_str())
+            .unwrap();
+
+        assert_eq!(item.server_pid, None);
+    }
+
+    #[test]
+    fn handle_busy_pipe() {
+        let pipe = TestPipe::new("handle_busy_pipe");
+
+        // We connect to the only instance of the pipe, so that the action is
+        // not able to do that. It should still report the pipe (and others).
+        let client = pipe.connect();
+
+        let args = Args {
+            resolve_server_pids: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.name == pipe.name.as_str())
+            .unwrap();
+
+        assert_eq!(item.server_pid, None);
+
+        drop(client);
+    }
+
+    /// Server end of a uniquely named single-instance pipe closed on drop.
+    struct TestPipe {
+        /// Name of the pipe (relative to the `\\.\pipe\` prefix).
+        name: String,
+        /// Handle to the server end of the pipe.
+        handle: windows_sys::Win32::Foundation::HANDLE,
+    }
+
+    impl TestPipe {
+
+        fn new(name: &str) -> TestPipe {
+            use std::hash::BuildHasher as _;
+            use windows_sys::Win32::Foundation::*;
+            use windows_sys::Win32::Storage::FileSystem::*;
+            use windows_sys::Win32::System::Pipes::*;
+
+            let random = std::collections::hash_map::RandomState::new()
+                .hash_one(());
+
+            let name = format!("rrg-test-{name}-{random}");
+
+            // SAFETY: We pass a null-terminated name of the pipe to create.
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    wide(&format!("\\\\.\\pipe\\{name}")).as_ptr(),
+                    PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                    PIPE_TYPE_BYTE | PIPE_REJECT_REMOTE_CLIENTS,
+                    1,
+                    0,
+                    0,
+                    0,
+                    std::ptr::null(),
+                )
+            };
+            assert_ne!(handle, INVALID_HANDLE_VALUE);
+
+            TestPipe {
+                name,
+                handle,
+            }
+        }
+
+        /// Connects to the pipe as a client and returns the client handle.
+        fn connect(&self) -> std::fs::File {
+            std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(format!("\\\\.\\pipe\\{}", self.name))
+                .unwrap()
+        }
+    }
+
+    impl Drop for TestPipe {
+
+        fn drop(&mut self) {
+            // SAFETY: The handle is valid and we close it only once.
+            unsafe {
+                windows_sys::Win32::Foundation::CloseHandle(self.handle);
+            }
+        }
+    }
+
+    fn wide(string: &str) -> Vec<u16> {
+        string.encode_utf16().chain(Some(0)).collect()
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the open file listing action.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+/// Arguments of the `list_open_files` action.
+pub struct Args {
+    /// Identifier of the process to list the open files of.
+    pid: Option<u32>,
+    /// Path of the file (or directory with files) to list the holders of.
+    path: Option<PathBuf>,
+}
+
+/// A result of the `list_open_files` action.
+#[derive(Debug)]
+struct Item {
+    /// Identifier of the process holding the file open.
+    pid: u32,
+    /// Name of the process executable (if known).
+    process_name: Option<OsString>,
+    /// File descriptor (or handle value on Windows) of the open file.
+    fd: u64,
+    /// Path of the open file.
+    path: PathBuf,
+    /// Mode in which the file is open (if known).
+    mode: Option<ospect::proc::OpenMode>,
+    /// Whether the file has been deleted while being open.
+    deleted: bool,
+}
+
+/// An error indicating that neither a process nor a path filter was given.
+#[derive(Debug)]
+struct MissingFilterError;
+
+impl std::fmt::Display for MissingFilterError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "neither process identifier nor path specified")
+    }
+}
+
+impl std::error::Error for MissingFilterError {}
+
+/// Handles invocations of the `list_open_files` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let files: Box<dyn Iterator<Item = std::io::Result<ospect::proc::OpenFile>>> = match args.pid {
+        Some(pid) => Box::new(ospect::proc::open_files(pid)
+            .map_err(crate::session::Error::action)?),
+        None => Box::new(ospect::proc::all_open_files()
+            .map_err(crate::session::Error::action)?),
+    };
+
+    // Processes usually hold many files open, so we cache their names instead
+    // of querying them for every file.
+    let mut process_names = HashMap::<u32, Option<OsString>>::new();
+
+    for file in files {
+        let file = match file {
+            Ok(file) => file,
+            Err(error) => {
+                warn!("failed to obtain open file: {error}");
+                continue;
+            }
+        };
+
+        if let Some(path) = &args.path {
+            if !file.path().starts_with(path) {
+                continue;
+            }
+        }
+
+        let process_name = process_names.entry(file.pid())
+            .or_insert_with(|| ospect::proc::name(file.pid()).ok())
+            .clone();
+
+        session.reply(Item {
+            pid: file.pid(),
+            process_name,
+            fd: file.fd(),
+            path: file.path().to_path_buf(),
+            mode: file.mode(),
+            deleted: file.is_deleted(),
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Normalizes the given path to the form in which open files are reported.
+///
+/// Paths of open files are reported with all symlinks resolved, so the filter
+/// has to be resolved as well to match them. On Windows, the canonical form
+/// uses the verbatim prefix which is not used by the reported paths, so the
+/// path is left as it is.
+fn normalize(path: PathBuf) -> PathBuf {
+    #[cfg(target_family = "unix")]
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+
+    path
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_open_files::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let path = if proto.has_path() {
+            let path = crate::path::from_proto(proto.take_path())
+                .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+            Some(normalize(path))
+        } else {
+            None
+        };
+
+        let pid = proto.pid;
+        if pid.is_none() && path.is_none() {
+            return Err(ParseArgsError::invalid_field("pid", MissingFilterError));
+        }
+
+        Ok(Args {
+            pid,
+            path,
+        })
+    }
+
+    fn paths(&self) -> Vec<&Path> {
+        self.path.iter()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_open_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::list_open_files::OpenMode;
+
+        let mut proto = Self::Proto::default();
+        proto.set_pid(self.pid);
+        if let Some(process_name) = self.process_name {
+            proto.set_process_name(process_name.to_string_lossy().into_owned());
+        }
+        proto.set_fd(self.fd);
+        proto.set_path(crate::path::into_proto(self.path));
+        proto.set_mode(match self.mode {
+            Some(ospect::proc::OpenMode::Read) => OpenMode::READ,
+            Some(ospect::proc::OpenMode::Write) => OpenMode::WRITE,
+            Some(ospect::proc::OpenMode::ReadWrite) => OpenMode::READ_WRITE,
+            None => OpenMode::UNKNOWN,
+        });
+        proto.set_deleted(self.deleted);
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_pid() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = normalize(tempdir.path().to_path_buf());
+
+        let _file = std::fs::File::create(tempdir_path.join("foo"))
+            .unwrap();
+
+        let args = Args {
+            pid: Some(std::process::id()),
+            path: None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // On Windows, the temporary directory path can contain short (8.3)
+        // names whereas the reported paths always use the long ones, so we
+        // match only the unique directory name and the file name.
+        let tempfile_name = Path::new(tempdir_path.file_name().unwrap()).join("foo");
+
+        let item = session.replies::<Item>()
+            .find(|item| item.path.ends_with(&tempfile_name))
+            .unwrap();
+        assert_eq!(item.pid, std::process::id());
+        assert!(item.process_name.is_some());
+        assert!(!item.deleted);
+    }
+
+    // On Windows, the temporary directory path can contain short (8.3) names
+    // which cannot match the reported paths (see above).
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_path() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = normalize(tempdir.path().to_path_buf());
+
+        let _file = std::fs::File::create(tempdir_path.join("foo"))
+            .unwrap();
+
+        let args = Args {
+            pid: None,
+            path: Some(tempdir_path.clone()),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let items = session.replies::<Item>()
+            .collect::<Vec<_>>();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].pid, std::process::id());
+        assert_eq!(items[0].path, tempdir_path.join("foo"));
+        #[cfg(target_os = "linux")]
+        assert_eq!(items[0].mode, Some(ospect::proc::OpenMode::Write));
+    }
+
+    #[test]
+    fn args_from_proto_missing_filter() {
+        use crate::request::Args as _;
+
+        let proto = rrg_proto::list_open_files::Args::new();
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
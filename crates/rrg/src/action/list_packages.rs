@@ -0,0 +1,245 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+#[cfg(target_os = "linux")]
+mod dpkg;
+
+#[cfg(target_os = "linux")]
+mod rpm;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+/// Arguments of the `list_packages` action.
+pub struct Args {
+    /// Whether it is allowed to invoke external programs.
+    allow_external_commands: bool,
+}
+
+/// A result of the `list_packages` action.
+struct Item {
+    /// Information about the individual installed package.
+    package: Package,
+}
+
+/// Information about an installed software package.
+#[derive(Debug, Default)]
+struct Package {
+    /// Name of the package.
+    name: String,
+    /// Version of the package.
+    version: Option<String>,
+    /// Architecture the package was built for.
+    arch: Option<String>,
+    /// Time at which the package was installed.
+    install_time: Option<std::time::SystemTime>,
+    /// Publisher (vendor, maintainer) of the package.
+    publisher: Option<String>,
+    /// Source from which the package information was collected.
+    source: Source,
+    /// Whether the information was collected using an external program.
+    external_command: bool,
+}
+
+/// Source from which the package information was collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Source {
+    /// The source is not known.
+    #[default]
+    Unknown,
+    /// The dpkg status database.
+    #[cfg(target_os = "linux")]
+    Dpkg,
+    /// The RPM database.
+    #[cfg(target_os = "linux")]
+    Rpm,
+    /// The `Uninstall` keys of the Windows registry.
+    #[cfg(target_os = "windows")]
+    WindowsUninstall,
+    /// Installation receipts of macOS packages.
+    #[cfg(target_os = "macos")]
+    MacosReceipt,
+    /// Application bundles of macOS.
+    #[cfg(target_os = "macos")]
+    MacosApplication,
+}
+
+/// Handles invocations of the `list_packages` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut packages = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        match self::dpkg::packages() {
+            Ok(dpkg_packages) => packages.extend(dpkg_packages),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+            Err(error) => log::warn!("failed to list dpkg packages: {error}"),
+        }
+
+        // There is no native support for reading the RPM database (it is either
+        // a Berkeley DB or an SQLite database depending on the version), so we
+        // can only use the `rpm` binary and only if we are explicitly allowed
+        // to.
+        if self::rpm::is_available() {
+            if args.allow_external_commands {
+                match self::rpm::packages() {
+                    Ok(rpm_packages) => packages.extend(rpm_packages),
+                    Err(error) => log::warn!("failed to list rpm packages: {error}"),
+                }
+            } else {
+                log::info!("skipping rpm packages (external commands not allowed)");
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // There are no external commands that we would call on macOS.
+        let _ = args.allow_external_commands;
+
+        match self::macos::receipts() {
+            Ok(receipts) => packages.extend(receipts),
+            Err(error) => log::warn!("failed to list package receipts: {error}"),
+        }
+        match self::macos::applications() {
+            Ok(applications) => packages.extend(applications),
+            Err(error) => log::warn!("failed to list applications: {error}"),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // There are no external commands that we would call on Windows.
+        let _ = args.allow_external_commands;
+
+        match self::windows::packages() {
+            Ok(windows_packages) => packages.extend(windows_packages),
+            Err(error) => log::warn!("failed to list uninstall entries: {error}"),
+        }
+    }
+
+    for package in packages {
+        session.reply(Item {
+            package,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_packages::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            allow_external_commands: proto.allow_external_commands(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_packages::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::into_timestamp;
+
+        let package = self.package;
+
+        let mut proto = rrg_proto::list_packages::Result::new();
+        proto.set_name(package.name);
+        if let Some(version) = package.version {
+            proto.set_version(version);
+        }
+        if let Some(arch) = package.arch {
+            proto.set_architecture(arch);
+        }
+        if let Some(install_time) = package.install_time {
+            proto.set_install_time(into_timestamp(install_time));
+        }
+        if let Some(publisher) = package.publisher {
+            proto.set_publisher(publisher);
+        }
+        proto.set_source(package.source.into());
+        proto.set_external_command(package.external_command);
+
+        proto
+    }
+}
+
+impl From<Source> for rrg_proto::list_packages::Source {
+
+    fn from(source: Source) -> rrg_proto::list_packages::Source {
+        use rrg_proto::list_packages::Source as Proto;
+
+        match source {
+            Source::Unknown => Proto::UNKNOWN,
+            #[cfg(target_os = "linux")]
+            Source::Dpkg => Proto::DPKG,
+            #[cfg(target_os = "linux")]
+            Source::Rpm => Proto::RPM,
+            #[cfg(target_os = "windows")]
+            Source::WindowsUninstall => Proto::WINDOWS_UNINSTALL,
+            #[cfg(target_os = "macos")]
+            Source::MacosReceipt => Proto::MACOS_RECEIPT,
+            #[cfg(target_os = "macos")]
+            Source::MacosApplication => Proto::MACOS_APPLICATION,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_no_external_commands() {
+        let args = Args {
+            allow_external_commands: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.package.name.is_empty());
+            assert!(!item.package.external_command);
+        }
+    }
+
+    #[test]
+    fn item_into_proto() {
+        use crate::response::Item as _;
+
+        let item = Item {
+            package: Package {
+                name: String::from("foo"),
+                version: Some(String::from("1.2.3")),
+                arch: None,
+                install_time: Some(std::time::UNIX_EPOCH),
+                publisher: Some(String::from("Foo Inc.")),
+                source: Source::Unknown,
+                external_command: true,
+            },
+        };
+
+        let proto = item.into_proto();
+        assert_eq!(proto.name(), "foo");
+        assert_eq!(proto.version(), "1.2.3");
+        assert_eq!(proto.architecture(), "");
+        assert_eq!(proto.install_time().seconds, 0);
+        assert_eq!(proto.publisher(), "Foo Inc.");
+        assert_eq!(proto.source(), rrg_proto::list_packages::Source::UNKNOWN);
+        assert!(proto.external_command());
+    }
+}
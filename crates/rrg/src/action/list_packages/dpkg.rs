@@ -0,0 +1,264 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{Package, Source};
+
+/// Path to the dpkg status database.
+const STATUS_PATH: &str = "/var/lib/dpkg/status";
+
+/// Path to the directory with dpkg control files of installed packages.
+const INFO_PATH: &str = "/var/lib/dpkg/info";
+
+/// Returns packages installed according to the dpkg status database.
+pub fn packages() -> std::io::Result<Vec<Package>> {
+    let file = std::fs::File::open(STATUS_PATH)?;
+
+    let mut packages = parse_status(std::io::BufReader::new(file))?;
+    for package in &mut packages {
+        package.install_time = install_time(package);
+    }
+
+    Ok(packages)
+}
+
+/// Estimates the installation time of the given dpkg package.
+///
+/// dpkg does not record installation time in the status database, so we use
+/// modification time of the file listing the package contents instead. This is
+/// the same heuristic that other tools use but note that upgrading the package
+/// updates the file as well.
+fn install_time(package: &Package) -> Option<std::time::SystemTime> {
+    let info_path = std::path::Path::new(INFO_PATH);
+
+    // For packages from foreign architectures (and since some dpkg version for
+    // all packages with `Multi-Arch: same`) the file name includes the arch.
+    let mut candidates = vec![info_path.join(format!("{}.list", package.name))];
+    if let Some(arch) = &package.arch {
+        candidates.push(info_path.join(format!("{}:{}.list", package.name, arch)));
+    }
+
+    candidates.into_iter()
+        .find_map(|path| std::fs::metadata(path).ok()?.modified().ok())
+}
+
+/// Parses packages from the given dpkg status database contents.
+///
+/// The database is a sequence of stanzas (separated with empty lines) in the
+/// [control file format][1]. Only packages that are actually installed (as
+/// opposed to e.g. removed but not purged) are returned.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/deb822.5.html
+fn parse_status<R>(reader: R) -> std::io::Result<Vec<Package>>
+where
+    R: std::io::BufRead,
+{
+    let mut packages = Vec::new();
+
+    let mut stanza = Stanza::default();
+    for line in reader.lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            packages.extend(std::mem::take(&mut stanza).into_package());
+            continue;
+        }
+
+        // Continuation lines (of multi-line fields like `Description`) start
+        // with whitespace. We don't need any of these fields.
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            log::warn!("invalid dpkg status line: {line:?}");
+            continue;
+        };
+
+        let value = Some(String::from(value.trim()));
+
+        // Field names are case-insensitive, but dpkg always writes them in the
+        // canonical form.
+        match name {
+            "Package" => stanza.package = value,
+            "Status" => stanza.status = value,
+            "Version" => stanza.version = value,
+            "Architecture" => stanza.arch = value,
+            "Maintainer" => stanza.maintainer = value,
+            _ => (),
+        }
+    }
+    packages.extend(stanza.into_package());
+
+    Ok(packages)
+}
+
+/// Fields of a single dpkg status database stanza.
+#[derive(Default)]
+struct Stanza {
+    /// Value of the `Package` field.
+    package: Option<String>,
+    /// Value of the `Status` field.
+    status: Option<String>,
+    /// Value of the `Version` field.
+    version: Option<String>,
+    /// Value of the `Architecture` field.
+    arch: Option<String>,
+    /// Value of the `Maintainer` field.
+    maintainer: Option<String>,
+}
+
+impl Stanza {
+
+    /// Converts the stanza into a package if it describes an installed one.
+    fn into_package(self) -> Option<Package> {
+        let name = self.package?;
+
+        // The status consists of three words: desired action, error flag and
+        // the actual package state [1]. We only care about the last one.
+        //
+        // [1]: https://man7.org/linux/man-pages/man1/dpkg-query.1.html
+        let state = self.status?.split_whitespace().nth(2).map(String::from);
+        if state.as_deref() != Some("installed") {
+            return None;
+        }
+
+        Some(Package {
+            name,
+            version: self.version,
+            arch: self.arch,
+            install_time: None,
+            publisher: self.maintainer,
+            source: Source::Dpkg,
+            external_command: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_status_installed() {
+        let status = b"\
+Package: libc6
+Status: install ok installed
+Priority: optional
+Section: libs
+Installed-Size: 12988
+Maintainer: GNU Libc Maintainers <debian-glibc@lists.debian.org>
+Architecture: amd64
+Multi-Arch: same
+Source: glibc
+Version: 2.36-9+deb12u4
+Depends: libgcc-s1
+Description: GNU C Library: Shared libraries
+ Contains the standard libraries that are used by nearly all programs on
+ the system.
+ .
+ This package includes shared versions of the standard C library.
+Homepage: https://www.gnu.org/software/libc/libc.html
+
+Package: zlib1g
+Status: install ok installed
+Priority: optional
+Section: libs
+Maintainer: Mark Brown <broonie@debian.org>
+Architecture: amd64
+Version: 1:1.2.13.dfsg-1
+Description: compression library - runtime
+";
+
+        let packages = parse_status(&status[..])
+            .unwrap();
+
+        assert_eq!(packages.len(), 2);
+
+        assert_eq!(packages[0].name, "libc6");
+        assert_eq!(packages[0].version.as_deref(), Some("2.36-9+deb12u4"));
+        assert_eq!(packages[0].arch.as_deref(), Some("amd64"));
+        assert_eq! {
+            packages[0].publisher.as_deref(),
+            Some("GNU Libc Maintainers <debian-glibc@lists.debian.org>")
+        };
+        assert_eq!(packages[0].source, Source::Dpkg);
+        assert!(!packages[0].external_command);
+
+        assert_eq!(packages[1].name, "zlib1g");
+        assert_eq!(packages[1].version.as_deref(), Some("1:1.2.13.dfsg-1"));
+    }
+
+    #[test]
+    fn parse_status_not_installed() {
+        let status = b"\
+Package: foo
+Status: deinstall ok config-files
+Architecture: amd64
+Version: 1.0
+
+Package: bar
+Status: install ok half-installed
+Architecture: amd64
+Version: 2.0
+
+Package: baz
+Status: install ok installed
+Architecture: all
+Version: 3.0
+";
+
+        let packages = parse_status(&status[..])
+            .unwrap();
+
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "baz");
+        assert_eq!(packages[0].arch.as_deref(), Some("all"));
+    }
+
+    #[test]
+    fn parse_status_multiple_empty_lines() {
+        let status = b"\n\n\
+Package: foo
+Status: install ok installed
+Version: 1.0
+\n\n\
+Package: bar
+Status: install ok installed
+";
+
+        let packages = parse_status(&status[..])
+            .unwrap();
+
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "foo");
+        assert_eq!(packages[1].name, "bar");
+        assert_eq!(packages[1].version, None);
+    }
+
+    #[test]
+    fn parse_status_missing_fields() {
+        let status = b"\
+Status: install ok installed
+Version: 1.0
+
+Package: foo
+Version: 1.0
+";
+
+        let packages = parse_status(&status[..])
+            .unwrap();
+
+        assert!(packages.is_empty());
+    }
+
+    #[test]
+    fn parse_status_empty() {
+        let packages = parse_status(&b""[..])
+            .unwrap();
+
+        assert!(packages.is_empty());
+    }
+}
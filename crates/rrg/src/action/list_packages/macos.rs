@@ -0,0 +1,244 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use super::{Package, Source};
+
+/// Path to the directory with installation receipts.
+const RECEIPTS_PATH: &str = "/var/db/receipts";
+
+/// Path to the directory with installed applications.
+const APPLICATIONS_PATH: &str = "/Applications";
+
+/// Returns packages installed according to the installation receipts.
+///
+/// Every package installed with the system installer leaves a receipt: a pair
+/// of a bill of materials (`.bom`) and a property list (`.plist`) file. We read
+/// only the latter as the former just lists the installed files.
+pub fn receipts() -> std::io::Result<Vec<Package>> {
+    let mut packages = Vec::new();
+
+    for entry in std::fs::read_dir(RECEIPTS_PATH)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read receipt entry: {error}");
+                continue;
+            }
+        };
+
+        if path.extension() != Some(std::ffi::OsStr::new("plist")) {
+            continue;
+        }
+
+        let receipt = match plist::parse_file(&path) {
+            Ok(receipt) => receipt,
+            Err(error) => {
+                log::warn!("failed to parse receipt '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        match parse_receipt(&receipt) {
+            Some(package) => packages.push(package),
+            None => log::warn!("invalid receipt '{}'", path.display()),
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Returns packages corresponding to application bundles.
+///
+/// Applications distributed as bundles (e.g. installed by dragging them from a
+/// disk image) leave no receipts, so we look at the bundles directly.
+pub fn applications() -> std::io::Result<Vec<Package>> {
+    let mut packages = Vec::new();
+
+    for entry in std::fs::read_dir(APPLICATIONS_PATH)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read application entry: {error}");
+                continue;
+            }
+        };
+
+        if path.extension() != Some(std::ffi::OsStr::new("app")) {
+            continue;
+        }
+
+        let info = match plist::parse_file(path.join("Contents").join("Info.plist")) {
+            Ok(info) => info,
+            Err(error) => {
+                log::warn!("failed to parse info of '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        let mut package = parse_bundle_info(&info, &path);
+        // Bundles have no record of when they were installed but creation time
+        // of the bundle directory is a good approximation.
+        package.install_time = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.created())
+            .ok();
+
+        packages.push(package);
+    }
+
+    Ok(packages)
+}
+
+/// Parses a package from the installation receipt property list.
+fn parse_receipt(receipt: &plist::Value) -> Option<Package> {
+    let name = receipt.get("PackageIdentifier")?.as_str()?;
+
+    Some(Package {
+        name: String::from(name),
+        version: receipt.get("PackageVersion")
+            .and_then(plist::Value::as_str)
+            .map(String::from),
+        install_time: receipt.get("InstallDate")
+            .and_then(plist::Value::as_date),
+        source: Source::MacosReceipt,
+        ..Package::default()
+    })
+}
+
+/// Parses a package from the bundle information property list.
+///
+/// `bundle_path` is used as a fallback name in case the property list does not
+/// specify it.
+fn parse_bundle_info(info: &plist::Value, bundle_path: &Path) -> Package {
+    let string = |key| {
+        info.get(key).and_then(plist::Value::as_str)
+            .filter(|value| !value.is_empty())
+            .map(String::from)
+    };
+
+    let name = string("CFBundleDisplayName")
+        .or_else(|| string("CFBundleName"))
+        .unwrap_or_else(|| {
+            bundle_path.file_stem().unwrap_or_default()
+                .to_string_lossy().into_owned()
+        });
+
+    // `CFBundleShortVersionString` is the user-visible version while the other
+    // one is the build number [1].
+    //
+    // [1]: https://developer.apple.com/documentation/bundleresources/information-property-list/cfbundleshortversionstring
+    let version = string("CFBundleShortVersionString")
+        .or_else(|| string("CFBundleVersion"));
+
+    Package {
+        name,
+        version,
+        source: Source::MacosApplication,
+        ..Package::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_receipt_ok() {
+        let receipt = plist::parse(br#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>InstallDate</key>
+	<date>2023-11-14T22:13:20Z</date>
+	<key>InstallPrefixPath</key>
+	<string>/</string>
+	<key>InstallProcessName</key>
+	<string>installer</string>
+	<key>PackageFileName</key>
+	<string>Foo.pkg</string>
+	<key>PackageIdentifier</key>
+	<string>com.example.foo</string>
+	<key>PackageVersion</key>
+	<string>1.2.3</string>
+</dict>
+</plist>
+"#).unwrap();
+
+        let package = parse_receipt(&receipt)
+            .unwrap();
+
+        assert_eq!(package.name, "com.example.foo");
+        assert_eq!(package.version.as_deref(), Some("1.2.3"));
+        assert_eq! {
+            package.install_time,
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000))
+        };
+        assert_eq!(package.source, Source::MacosReceipt);
+    }
+
+    #[test]
+    fn parse_receipt_no_identifier() {
+        let receipt = plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>PackageVersion</key>
+	<string>1.2.3</string>
+</dict>
+</plist>
+"#).unwrap();
+
+        assert!(parse_receipt(&receipt).is_none());
+    }
+
+    #[test]
+    fn parse_bundle_info_ok() {
+        let info = plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>CFBundleName</key>
+	<string>Foo</string>
+	<key>CFBundleDisplayName</key>
+	<string>Foo Browser</string>
+	<key>CFBundleShortVersionString</key>
+	<string>4.2</string>
+	<key>CFBundleVersion</key>
+	<string>4200.1</string>
+</dict>
+</plist>
+"#).unwrap();
+
+        let package = parse_bundle_info(&info, Path::new("/Applications/Foo.app"));
+        assert_eq!(package.name, "Foo Browser");
+        assert_eq!(package.version.as_deref(), Some("4.2"));
+        assert_eq!(package.source, Source::MacosApplication);
+    }
+
+    #[test]
+    fn parse_bundle_info_fallback() {
+        let info = plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>CFBundleVersion</key>
+	<string>17</string>
+</dict>
+</plist>
+"#).unwrap();
+
+        let package = parse_bundle_info(&info, Path::new("/Applications/Bar.app"));
+        assert_eq!(package.name, "Bar");
+        assert_eq!(package.version.as_deref(), Some("17"));
+    }
+
+    #[test]
+    fn applications_not_empty() {
+        // Every macOS installation comes with at least Safari.
+        let packages = applications()
+            .unwrap();
+
+        assert!(!packages.is_empty());
+    }
+}
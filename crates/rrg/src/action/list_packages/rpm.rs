@@ -0,0 +1,156 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{Package, Source};
+
+/// Paths to directories where the RPM database can be located.
+///
+/// Older systems store the database in `/var/lib/rpm`, newer ones moved it to
+/// `/usr/lib/sysimage/rpm` (with the old path being a symlink).
+const DB_PATHS: &[&str] = &[
+    "/var/lib/rpm",
+    "/usr/lib/sysimage/rpm",
+];
+
+/// Format of the `rpm` output with fields separated with tabs.
+///
+/// Missing values are reported by `rpm` as `(none)`.
+const QUERY_FORMAT: &str = concat! {
+    "%{NAME}\\t",
+    "%|EPOCH?{%{EPOCH}:}:{}|%{VERSION}-%{RELEASE}\\t",
+    "%{ARCH}\\t",
+    "%{INSTALLTIME}\\t",
+    "%{VENDOR}\\n",
+};
+
+/// Returns whether the system has an RPM database.
+pub fn is_available() -> bool {
+    DB_PATHS.iter().any(|path| std::path::Path::new(path).is_dir())
+}
+
+/// Returns packages installed according to the `rpm` program.
+pub fn packages() -> std::io::Result<Vec<Package>> {
+    let output = std::process::Command::new("rpm")
+        .arg("--query")
+        .arg("--all")
+        .arg("--queryformat")
+        .arg(QUERY_FORMAT)
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format! {
+            "`rpm` failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        }));
+    }
+
+    Ok(parse_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses packages from the `rpm` output in the [`QUERY_FORMAT`] format.
+fn parse_output(output: &str) -> Vec<Package> {
+    let mut packages = Vec::new();
+
+    for line in output.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split('\t');
+
+        let name = fields.next();
+        let version = fields.next();
+        let arch = fields.next();
+        let install_time = fields.next();
+        let vendor = fields.next();
+
+        let (Some(name), Some(version), Some(arch), Some(install_time), Some(vendor)) =
+            (name, version, arch, install_time, vendor)
+        else {
+            log::warn!("invalid rpm output line: {line:?}");
+            continue;
+        };
+
+        /// Returns `None` if the field is not set.
+        fn field(value: &str) -> Option<String> {
+            match value {
+                "" | "(none)" => None,
+                _ => Some(String::from(value)),
+            }
+        }
+
+        let install_time = install_time.parse::<u64>().ok()
+            .map(|secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+
+        packages.push(Package {
+            name: String::from(name),
+            version: field(version),
+            arch: field(arch),
+            install_time,
+            publisher: field(vendor),
+            source: Source::Rpm,
+            external_command: true,
+        });
+    }
+
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_output_ok() {
+        let output = "\
+bash\t5.2.15-3.fc38\tx86_64\t1700000000\tFedora Project
+gpg-pubkey\t18b8e74c-62f2920f\t(none)\t1700000001\t(none)
+perl-Carp\t1.52-490.fc38\tnoarch\t1700000002\tFedora Project
+openssl-libs\t1:3.0.9-2.fc38\tx86_64\t1700000003\tFedora Project
+";
+
+        let packages = parse_output(output);
+        assert_eq!(packages.len(), 4);
+
+        assert_eq!(packages[0].name, "bash");
+        assert_eq!(packages[0].version.as_deref(), Some("5.2.15-3.fc38"));
+        assert_eq!(packages[0].arch.as_deref(), Some("x86_64"));
+        assert_eq! {
+            packages[0].install_time,
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000))
+        };
+        assert_eq!(packages[0].publisher.as_deref(), Some("Fedora Project"));
+        assert_eq!(packages[0].source, Source::Rpm);
+        assert!(packages[0].external_command);
+
+        assert_eq!(packages[1].name, "gpg-pubkey");
+        assert_eq!(packages[1].arch, None);
+        assert_eq!(packages[1].publisher, None);
+
+        assert_eq!(packages[3].version.as_deref(), Some("1:3.0.9-2.fc38"));
+    }
+
+    #[test]
+    fn parse_output_invalid_lines() {
+        let output = "\
+foo
+bar\t1.0-1\tx86_64\tbaz\tQuux Inc.
+";
+
+        let packages = parse_output(output);
+        assert_eq!(packages.len(), 1);
+
+        assert_eq!(packages[0].name, "bar");
+        assert_eq!(packages[0].install_time, None);
+    }
+
+    #[test]
+    fn parse_output_empty() {
+        assert!(parse_output("").is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::ffi::OsStr;
+
+use super::{Package, Source};
+
+/// Registry keys (relative to a hive) with uninstall entries of applications.
+///
+/// The first one is for native applications, the second one is for 32-bit ones
+/// running on a 64-bit system [1]. Note that we do not need to specify the view
+/// explicitly (with `KEY_WOW64_32KEY`): the agent is a 64-bit process and so it
+/// sees the redirected key under its real name.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/winprog64/registry-redirector
+const UNINSTALL_KEYS: &[&str] = &[
+    "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+];
+
+/// Returns packages listed in the uninstall keys of the registry.
+///
+/// Both machine-wide entries and per-user entries (of users whose hives are
+/// currently loaded) are considered.
+pub fn packages() -> std::io::Result<Vec<Package>> {
+    let mut packages = Vec::new();
+
+    for key_name in UNINSTALL_KEYS {
+        let key = match winreg::PredefinedKey::LocalMachine.open(OsStr::new(key_name)) {
+            Ok(key) => key,
+            // The `WOW6432Node` key does not exist on 32-bit systems.
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+
+        packages.extend(uninstall_key_packages(&key));
+    }
+
+    let users = winreg::PredefinedKey::Users.info()?;
+    for sid in users.subkeys() {
+        let sid = match sid {
+            Ok(sid) => sid,
+            Err(error) => {
+                log::warn!("failed to list user hive: {error}");
+                continue;
+            }
+        };
+
+        for key_name in UNINSTALL_KEYS {
+            let key_name = winreg::path::join(sid.as_os_str(), OsStr::new(key_name));
+
+            let key = match winreg::PredefinedKey::Users.open(&key_name) {
+                Ok(key) => key,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => {
+                    log::warn!("failed to open uninstall key {key_name:?}: {error}");
+                    continue;
+                }
+            };
+
+            packages.extend(uninstall_key_packages(&key));
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Returns packages for all entries of the given uninstall key.
+fn uninstall_key_packages(key: &winreg::OpenKey) -> Vec<Package> {
+    let mut packages = Vec::new();
+
+    let info = match key.info() {
+        Ok(info) => info,
+        Err(error) => {
+            log::warn!("failed to query uninstall key: {error}");
+            return packages;
+        }
+    };
+
+    for entry_name in info.subkeys() {
+        let entry_name = match entry_name {
+            Ok(entry_name) => entry_name,
+            Err(error) => {
+                log::warn!("failed to list uninstall entry: {error}");
+                continue;
+            }
+        };
+
+        let entry = match key.open(&entry_name) {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::warn!("failed to open uninstall entry {entry_name:?}: {error}");
+                continue;
+            }
+        };
+
+        let value = |name: &str| -> Option<String> {
+            match entry.value_data(OsStr::new(name)).ok()? {
+                winreg::ValueData::String(string) |
+                winreg::ValueData::ExpandString(string) => {
+                    Some(string.to_string_lossy().into_owned())
+                }
+                _ => None,
+            }
+        };
+
+        // Entries without a display name are not shown in the "Programs and
+        // Features" panel (they are usually components of other packages), so
+        // we skip them as well.
+        if let Some(package) = parse_entry(value) {
+            packages.push(package);
+        }
+    }
+
+    packages
+}
+
+/// Parses a package from values of an uninstall entry.
+///
+/// `value` should return string data of the value with the given name (if there
+/// is such value) and the list of possible values is documented [here][1].
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/msi/uninstall-registry-key
+fn parse_entry<F>(value: F) -> Option<Package>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let non_empty = |name| value(name).filter(|value| !value.is_empty());
+
+    Some(Package {
+        name: non_empty("DisplayName")?,
+        version: non_empty("DisplayVersion"),
+        arch: None,
+        install_time: non_empty("InstallDate").as_deref().and_then(parse_install_date),
+        publisher: non_empty("Publisher"),
+        source: Source::WindowsUninstall,
+        external_command: false,
+    })
+}
+
+/// Parses installation date in the `YYYYMMDD` format.
+///
+/// Installation dates have no time information, so midnight (UTC) is used.
+fn parse_install_date(date: &str) -> Option<std::time::SystemTime> {
+    if date.len() != 8 || !date.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let date = format!("{}-{}-{}T00:00:00Z", &date[0..4], &date[4..6], &date[6..8]);
+    humantime::parse_rfc3339(&date).ok()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Parses the values of a single key from a `.reg` export.
+    ///
+    /// Only string values (`"Name"="Data"`) are supported.
+    fn parse_reg_export(export: &str) -> std::collections::HashMap<String, String> {
+        let mut values = std::collections::HashMap::new();
+
+        for line in export.lines() {
+            let Some((name, data)) = line.split_once("\"=\"") else {
+                continue;
+            };
+
+            let name = name.trim_start_matches('"');
+            let data = data.trim_end_matches('"').replace("\\\\", "\\");
+            values.insert(String::from(name), data);
+        }
+
+        values
+    }
+
+    #[test]
+    fn parse_entry_ok() {
+        let values = parse_reg_export(r#"
+Windows Registry Editor Version 5.00
+
+[HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\7-Zip]
+"DisplayName"="7-Zip 23.01 (x64)"
+"DisplayVersion"="23.01"
+"DisplayIcon"="C:\\Program Files\\7-Zip\\7zFM.exe"
+"InstallDate"="20231114"
+"InstallLocation"="C:\\Program Files\\7-Zip\\"
+"Publisher"="Igor Pavlov"
+"UninstallString"="\"C:\\Program Files\\7-Zip\\Uninstall.exe\""
+"NoModify"=dword:00000001
+"#);
+
+        let package = parse_entry(|name| values.get(name).cloned())
+            .unwrap();
+
+        assert_eq!(package.name, "7-Zip 23.01 (x64)");
+        assert_eq!(package.version.as_deref(), Some("23.01"));
+        assert_eq!(package.publisher.as_deref(), Some("Igor Pavlov"));
+        assert_eq! {
+            package.install_time,
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1699920000))
+        };
+        assert_eq!(package.source, Source::WindowsUninstall);
+    }
+
+    #[test]
+    fn parse_entry_no_display_name() {
+        let values = parse_reg_export(r#"
+[HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\{90160000-008C-0000-1000-0000000FF1CE}]
+"DisplayVersion"="16.0.4266.1001"
+"Publisher"="Microsoft Corporation"
+"#);
+
+        assert!(parse_entry(|name| values.get(name).cloned()).is_none());
+    }
+
+    #[test]
+    fn parse_entry_empty_fields() {
+        let values = parse_reg_export(r#"
+[HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\Foo]
+"DisplayName"="Foo"
+"DisplayVersion"=""
+"InstallDate"="invalid"
+"#);
+
+        let package = parse_entry(|name| values.get(name).cloned())
+            .unwrap();
+
+        assert_eq!(package.name, "Foo");
+        assert_eq!(package.version, None);
+        assert_eq!(package.install_time, None);
+        assert_eq!(package.publisher, None);
+    }
+
+    #[test]
+    fn parse_install_date_invalid() {
+        assert_eq!(parse_install_date("2023111"), None);
+        assert_eq!(parse_install_date("2023-11-1"), None);
+        assert_eq!(parse_install_date("20231314"), None);
+    }
+
+    #[test]
+    fn packages_ok() {
+        assert!(packages().is_ok());
+    }
+}
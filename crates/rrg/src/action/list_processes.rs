@@ -0,0 +1,403 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the process listing action.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime};
+
+use log::warn;
+
+/// Default interval between consecutive samples of the process table.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Arguments of the `list_processes` action.
+pub struct Args {
+    /// Duration for which the process table should be sampled (zero to list
+    /// processes only once).
+    duration: Duration,
+    /// Interval between consecutive samples of the process table.
+    interval: Duration,
+}
+
+/// A result of the `list_processes` action.
+#[derive(Debug)]
+struct Item {
+    /// Identifier of the process.
+    pid: u32,
+    /// Identifier of the parent process (if known).
+    parent_pid: Option<u32>,
+    /// Name of the process executable (if known).
+    name: Option<std::ffi::OsString>,
+    /// Command-line arguments of the process (if known).
+    args: Option<Vec<std::ffi::OsString>>,
+    /// Identifier of the user owning the process (if known).
+    #[cfg(target_family = "unix")]
+    uid: Option<u32>,
+    /// Security identifier of the user owning the process (if known).
+    #[cfg(target_family = "windows")]
+    user_sid: Option<String>,
+    /// Name of the user owning the process (if known).
+    user_name: Option<std::ffi::OsString>,
+    /// Time at which the process was first observed.
+    first_seen_time: SystemTime,
+    /// Time at which the process was last observed.
+    last_seen_time: SystemTime,
+    /// Identifiers of namespaces the process belongs to.
+    #[cfg(target_os = "linux")]
+    namespaces: crate::container::Namespaces,
+    /// Path of the cgroup the process belongs to (if known).
+    #[cfg(target_os = "linux")]
+    cgroup_path: Option<String>,
+}
+
+/// Handles invocations of the `list_processes` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let start = Instant::now();
+    let start_time = SystemTime::now();
+
+    let pids = self::pids()
+        .map_err(crate::session::Error::action)?;
+
+    if args.duration.is_zero() {
+        for pid in pids {
+            session.reply(Item::new(pid, start_time))?;
+        }
+
+        return Ok(());
+    }
+
+    // Processes running at the start are not reported. We forget about them
+    // once they are gone, so a new process that happens to reuse their pid
+    // is reported as expected.
+    let mut initial_pids = pids;
+
+    // Processes that we observed since the start and that are still running.
+    // Together with the initial processes, the number of tracked processes is
+    // never greater than the number of distinct processes on the system.
+    let mut observed = HashMap::<u32, Item>::new();
+
+    loop {
+        let time_left = args.duration.saturating_sub(start.elapsed());
+        if time_left.is_zero() {
+            break;
+        }
+        std::thread::sleep(std::cmp::min(args.interval, time_left));
+
+        session.check_limits()?;
+
+        let time = SystemTime::now();
+        let pids = self::pids()
+            .map_err(crate::session::Error::action)?;
+
+        initial_pids.retain(|pid| pids.contains(pid));
+
+        // Processes that are gone will not be observed again, so we can report
+        // them right away instead of keeping them until the end.
+        let gone_pids = observed.keys()
+            .filter(|pid| !pids.contains(pid))
+            .copied()
+            .collect::<Vec<_>>();
+        for pid in gone_pids {
+            if let Some(item) = observed.remove(&pid) {
+                session.reply(item)?;
+            }
+        }
+
+        for pid in pids {
+            if initial_pids.contains(&pid) {
+                continue;
+            }
+
+            observed.entry(pid)
+                .and_modify(|item| item.last_seen_time = time)
+                .or_insert_with(|| Item::new(pid, time));
+        }
+    }
+
+    for item in observed.into_values() {
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Returns identifiers of all processes currently running on the system.
+fn pids() -> std::io::Result<HashSet<u32>> {
+    let mut pids = HashSet::new();
+
+    for pid in ospect::proc::ids()? {
+        match pid {
+            Ok(pid) => {
+                pids.insert(pid);
+            }
+            Err(error) => {
+                warn!("failed to obtain process identifier: {error}");
+            }
+        }
+    }
+
+    Ok(pids)
+}
+
+impl Item {
+
+    /// Creates an item for the process with the given identifier observed at
+    /// the given time.
+    ///
+    /// Processes can exit at any moment, so failures to obtain information
+    /// about them are expected and the missing details are simply left out.
+    fn new(pid: u32, time: SystemTime) -> Item {
+        let resolver = crate::os::resolver();
+
+        #[cfg(target_family = "unix")]
+        let uid = ospect::proc::uid(pid).ok();
+        #[cfg(target_family = "unix")]
+        let user_name = uid.and_then(|uid| resolver.user_name(uid));
+
+        #[cfg(target_family = "windows")]
+        let user_sid = ospect::proc::user_sid(pid).ok();
+        #[cfg(target_family = "windows")]
+        let user_name = user_sid.as_deref().and_then(|sid| resolver.account_name(sid));
+
+        Item {
+            pid,
+            parent_pid: ospect::proc::parent_id(pid).ok(),
+            name: ospect::proc::name(pid).ok(),
+            args: ospect::proc::args(pid).ok(),
+            #[cfg(target_family = "unix")]
+            uid,
+            #[cfg(target_family = "windows")]
+            user_sid,
+            user_name,
+            first_seen_time: time,
+            last_seen_time: time,
+            #[cfg(target_os = "linux")]
+            namespaces: crate::container::namespaces(pid),
+            #[cfg(target_os = "linux")]
+            cgroup_path: crate::container::cgroup_path(pid).ok(),
+        }
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_processes::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let duration = if proto.has_duration() {
+            Duration::try_from(proto.take_duration())
+                .map_err(|error| ParseArgsError::invalid_field("duration", error))?
+        } else {
+            Duration::ZERO
+        };
+
+        let interval = if proto.has_interval() {
+            Duration::try_from(proto.take_interval())
+                .map_err(|error| ParseArgsError::invalid_field("interval", error))?
+        } else {
+            DEFAULT_INTERVAL
+        };
+
+        Ok(Args {
+            duration,
+            interval: if interval.is_zero() { DEFAULT_INTERVAL } else { interval },
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_processes::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_pid(self.pid);
+        if let Some(parent_pid) = self.parent_pid {
+            proto.set_parent_pid(parent_pid);
+        }
+        if let Some(name) = self.name {
+            proto.set_name(name.to_string_lossy().into_owned());
+        }
+        if let Some(args) = self.args {
+            proto.set_args(args.into_iter().map(|arg| {
+                arg.to_string_lossy().into_owned()
+            }).collect());
+        }
+        #[cfg(target_family = "unix")]
+        if let Some(uid) = self.uid {
+            proto.set_unix_uid(uid);
+        }
+        #[cfg(target_family = "windows")]
+        if let Some(user_sid) = self.user_sid {
+            proto.set_windows_user_sid(user_sid);
+        }
+        if let Some(user_name) = self.user_name {
+            proto.set_user_name(user_name.to_string_lossy().into_owned());
+        }
+        proto.set_first_seen_time(rrg_proto::into_timestamp(self.first_seen_time));
+        proto.set_last_seen_time(rrg_proto::into_timestamp(self.last_seen_time));
+        #[cfg(target_os = "linux")]
+        {
+            proto.set_namespaces(self.namespaces.into());
+            if let Some(cgroup_path) = self.cgroup_path {
+                if let Some(container_id) = crate::container::container_id(&cgroup_path) {
+                    proto.set_container_id(container_id.to_string());
+                }
+                proto.set_cgroup_path(cgroup_path);
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_once() {
+        let args = Args {
+            duration: Duration::ZERO,
+            interval: DEFAULT_INTERVAL,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.pid == std::process::id())
+            .unwrap();
+
+        let parent_pid = ospect::proc::parent_id(std::process::id())
+            .unwrap();
+        assert_eq!(item.parent_pid, Some(parent_pid));
+        assert_eq!(item.first_seen_time, item.last_seen_time);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_once_owner() {
+        let args = Args {
+            duration: Duration::ZERO,
+            interval: DEFAULT_INTERVAL,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.pid == std::process::id())
+            .unwrap();
+
+        // SAFETY: `geteuid` is always safe to call.
+        let uid = unsafe { libc::geteuid() };
+        assert_eq!(item.uid, Some(uid));
+        assert_eq!(item.user_name, ospect::user::user_name(uid).unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_once_namespaces() {
+        let args = Args {
+            duration: Duration::ZERO,
+            interval: DEFAULT_INTERVAL,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.replies::<Item>()
+            .find(|item| item.pid == std::process::id())
+            .unwrap();
+
+        assert_eq!(item.namespaces, crate::container::namespaces(std::process::id()));
+        assert!(item.namespaces.pid.is_some());
+        assert!(item.cgroup_path.is_some());
+    }
+
+    #[test]
+    fn handle_sampling_no_initial_processes() {
+        let args = Args {
+            duration: Duration::from_millis(50),
+            interval: Duration::from_millis(10),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // The current process was running since the start, so it should not be
+        // reported.
+        assert! {
+            !session.replies::<Item>().any(|item| item.pid == std::process::id())
+        };
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_sampling_short_lived_processes() {
+        let args = Args {
+            duration: Duration::from_millis(1000),
+            interval: Duration::from_millis(10),
+        };
+
+        let spawner = std::thread::spawn(|| {
+            std::thread::sleep(Duration::from_millis(100));
+
+            let mut children = Vec::new();
+            for _ in 0..5 {
+                let child = std::process::Command::new("true")
+                    .spawn()
+                    .unwrap();
+                children.push(child);
+            }
+
+            // Children that exited but were not waited for yet remain in the
+            // process table, so we give the sampler some time to notice them
+            // before reaping.
+            std::thread::sleep(Duration::from_millis(100));
+
+            children.into_iter()
+                .map(|mut child| {
+                    let pid = child.id();
+                    child.wait().unwrap();
+                    pid
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let child_pids = spawner.join()
+            .unwrap();
+
+        let items = session.replies::<Item>()
+            .filter(|item| child_pids.contains(&item.pid))
+            .collect::<Vec<_>>();
+        assert!(!items.is_empty());
+
+        for item in items {
+            assert_eq!(item.parent_pid, Some(std::process::id()));
+            assert!(item.first_seen_time <= item.last_seen_time);
+        }
+
+        // Each process should be reported only once.
+        let mut pids = session.replies::<Item>()
+            .map(|item| item.pid)
+            .collect::<Vec<_>>();
+        let pids_count = pids.len();
+        pids.sort();
+        pids.dedup();
+        assert_eq!(pids.len(), pids_count);
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+#[cfg(target_os = "linux")]
+mod cron;
+
+#[cfg(target_os = "linux")]
+mod systemd;
+
+#[cfg(target_os = "macos")]
+mod launchd;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+/// A result of the `list_scheduled_tasks` action.
+struct Item {
+    /// Information about the individual scheduled task.
+    task: Task,
+}
+
+/// Information about a scheduled task.
+#[derive(Debug, Default)]
+struct Task {
+    /// Source from which the task was collected.
+    source: Source,
+    /// Source-specific identifier of the task.
+    identifier: String,
+    /// Command that the task executes.
+    command: String,
+    /// Source-specific description of when the task is executed.
+    schedule: String,
+    /// Name of the user as which the task is executed.
+    user: Option<String>,
+    /// Whether the task is enabled.
+    enabled: bool,
+    /// Author of the task.
+    author: Option<String>,
+}
+
+/// Source from which the task was collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Source {
+    /// The source is not known.
+    #[default]
+    Unknown,
+    /// Crontab files.
+    #[cfg(target_os = "linux")]
+    Crontab,
+    /// Timer units of systemd.
+    #[cfg(target_os = "linux")]
+    SystemdTimer,
+    /// Launch agents and daemons of launchd.
+    #[cfg(target_os = "macos")]
+    Launchd,
+    /// Windows Task Scheduler.
+    #[cfg(target_os = "windows")]
+    WindowsTaskScheduler,
+}
+
+/// Handles invocations of the `list_scheduled_tasks` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut tasks = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        tasks.extend(self::cron::tasks());
+        tasks.extend(self::systemd::timers());
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        tasks.extend(self::launchd::tasks());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let windows_tasks = self::windows::tasks()
+            .map_err(crate::session::Error::action)?;
+
+        tasks.extend(windows_tasks);
+    }
+
+    for task in tasks {
+        session.reply(Item {
+            task,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_scheduled_tasks::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let task = self.task;
+
+        let mut proto = rrg_proto::list_scheduled_tasks::Result::new();
+        proto.set_source(task.source.into());
+        proto.set_identifier(task.identifier);
+        proto.set_command(task.command);
+        proto.set_schedule(task.schedule);
+        if let Some(user) = task.user {
+            proto.set_user(user);
+        }
+        proto.set_enabled(task.enabled);
+        if let Some(author) = task.author {
+            proto.set_author(author);
+        }
+
+        proto
+    }
+}
+
+impl From<Source> for rrg_proto::list_scheduled_tasks::Source {
+
+    fn from(source: Source) -> rrg_proto::list_scheduled_tasks::Source {
+        use rrg_proto::list_scheduled_tasks::Source as Proto;
+
+        match source {
+            Source::Unknown => Proto::UNKNOWN,
+            #[cfg(target_os = "linux")]
+            Source::Crontab => Proto::CRONTAB,
+            #[cfg(target_os = "linux")]
+            Source::SystemdTimer => Proto::SYSTEMD_TIMER,
+            #[cfg(target_os = "macos")]
+            Source::Launchd => Proto::LAUNCHD,
+            #[cfg(target_os = "windows")]
+            Source::WindowsTaskScheduler => Proto::WINDOWS_TASK_SCHEDULER,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.task.identifier.is_empty());
+        }
+    }
+
+    #[test]
+    fn item_into_proto() {
+        use crate::response::Item as _;
+
+        let item = Item {
+            task: Task {
+                source: Source::Unknown,
+                identifier: String::from("foo"),
+                command: String::from("/usr/bin/foo --bar"),
+                schedule: String::from("@daily"),
+                user: Some(String::from("root")),
+                enabled: true,
+                author: None,
+            },
+        };
+
+        let proto = item.into_proto();
+        assert_eq!(proto.source(), rrg_proto::list_scheduled_tasks::Source::UNKNOWN);
+        assert_eq!(proto.identifier(), "foo");
+        assert_eq!(proto.command(), "/usr/bin/foo --bar");
+        assert_eq!(proto.schedule(), "@daily");
+        assert_eq!(proto.user(), "root");
+        assert!(proto.enabled());
+        assert_eq!(proto.author(), "");
+    }
+}
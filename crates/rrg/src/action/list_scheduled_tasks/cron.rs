@@ -0,0 +1,99 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use crate::cron::{crontab_paths, parse_crontab, Format};
+
+use super::{Source, Task};
+
+/// Path to the system crontab file.
+const SYSTEM_CRONTAB_PATH: &str = "/etc/crontab";
+
+/// Path to the directory with additional system crontab files.
+const SYSTEM_CRONTAB_DIR_PATH: &str = "/etc/cron.d";
+
+/// Paths to directories with per-user crontab files.
+///
+/// The first one is used by Debian and derivatives, the second one by Red Hat
+/// and derivatives.
+const USER_CRONTAB_DIR_PATHS: &[&str] = &[
+    "/var/spool/cron/crontabs",
+    "/var/spool/cron",
+];
+
+/// Returns tasks defined in all the crontab files on the system.
+pub fn tasks() -> Vec<Task> {
+    let mut tasks = Vec::new();
+
+    tasks.extend(crontab_tasks(Path::new(SYSTEM_CRONTAB_PATH), Format::System, None));
+
+    for path in crontab_paths(Path::new(SYSTEM_CRONTAB_DIR_PATH)) {
+        tasks.extend(crontab_tasks(&path, Format::System, None));
+    }
+
+    for dir_path in USER_CRONTAB_DIR_PATHS {
+        for path in crontab_paths(Path::new(dir_path)) {
+            // Per-user crontab files are named after the users that own them.
+            let user = path.file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+
+            tasks.extend(crontab_tasks(&path, Format::User, user));
+        }
+    }
+
+    tasks
+}
+
+/// Returns tasks defined in the crontab file at the given path.
+///
+/// `user` is used for entries that do not specify the user explicitly.
+fn crontab_tasks(path: &Path, format: Format, user: Option<String>) -> Vec<Task> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to open crontab '{}': {error}", path.display());
+            return Vec::new();
+        }
+    };
+
+    let entries = match parse_crontab(std::io::BufReader::new(file), format) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to parse crontab '{}': {error}", path.display());
+            return Vec::new();
+        }
+    };
+
+    entries.into_iter()
+        .map(|entry| Task {
+            source: Source::Crontab,
+            identifier: format!("{}:{}", path.display(), entry.line),
+            command: entry.command,
+            schedule: entry.schedule,
+            user: entry.user.or_else(|| user.clone()),
+            // Disabled entries are commented out, so everything that we parse
+            // is enabled.
+            enabled: true,
+            author: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn tasks_ok() {
+        for task in tasks() {
+            assert_eq!(task.source, Source::Crontab);
+            assert!(!task.command.is_empty());
+            assert!(task.user.is_some());
+        }
+    }
+}
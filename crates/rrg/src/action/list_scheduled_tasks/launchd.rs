@@ -0,0 +1,161 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{Source, Task};
+
+/// Returns tasks corresponding to all launchd jobs on the system.
+pub fn tasks() -> Vec<Task> {
+    crate::launchd::jobs().into_iter()
+        .map(task)
+        .collect()
+}
+
+/// Converts the given launchd job to the normalized task representation.
+fn task(job: crate::launchd::Job) -> Task {
+    Task {
+        source: Source::Launchd,
+        command: job.command(),
+        schedule: job.schedule(),
+        user: job.user,
+        enabled: job.enabled,
+        identifier: job.label,
+        author: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn task_binary() {
+        // Generated with Python's `plistlib` (using the `FMT_BINARY` format)
+        // from the following dictionary:
+        //
+        //     {
+        //         "Label": "com.example.updater",
+        //         "ProgramArguments": ["/usr/local/bin/updater", "--check"],
+        //         "StartCalendarInterval": {"Hour": 3, "Minute": 15},
+        //         "RunAtLoad": True,
+        //         "UserName": "root",
+        //     }
+        const JOB: &[u8] = &[
+            0x62, 0x70, 0x6c, 0x69, 0x73, 0x74, 0x30, 0x30, 0xd5, 0x01, 0x02, 0x03,
+            0x04, 0x05, 0x06, 0x07, 0x0a, 0x0b, 0x10, 0x55, 0x4c, 0x61, 0x62, 0x65,
+            0x6c, 0x5f, 0x10, 0x10, 0x50, 0x72, 0x6f, 0x67, 0x72, 0x61, 0x6d, 0x41,
+            0x72, 0x67, 0x75, 0x6d, 0x65, 0x6e, 0x74, 0x73, 0x59, 0x52, 0x75, 0x6e,
+            0x41, 0x74, 0x4c, 0x6f, 0x61, 0x64, 0x5f, 0x10, 0x15, 0x53, 0x74, 0x61,
+            0x72, 0x74, 0x43, 0x61, 0x6c, 0x65, 0x6e, 0x64, 0x61, 0x72, 0x49, 0x6e,
+            0x74, 0x65, 0x72, 0x76, 0x61, 0x6c, 0x58, 0x55, 0x73, 0x65, 0x72, 0x4e,
+            0x61, 0x6d, 0x65, 0x5f, 0x10, 0x13, 0x63, 0x6f, 0x6d, 0x2e, 0x65, 0x78,
+            0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x75, 0x70, 0x64, 0x61, 0x74, 0x65,
+            0x72, 0xa2, 0x08, 0x09, 0x5f, 0x10, 0x16, 0x2f, 0x75, 0x73, 0x72, 0x2f,
+            0x6c, 0x6f, 0x63, 0x61, 0x6c, 0x2f, 0x62, 0x69, 0x6e, 0x2f, 0x75, 0x70,
+            0x64, 0x61, 0x74, 0x65, 0x72, 0x57, 0x2d, 0x2d, 0x63, 0x68, 0x65, 0x63,
+            0x6b, 0x09, 0xd2, 0x0c, 0x0d, 0x0e, 0x0f, 0x54, 0x48, 0x6f, 0x75, 0x72,
+            0x56, 0x4d, 0x69, 0x6e, 0x75, 0x74, 0x65, 0x10, 0x03, 0x10, 0x0f, 0x54,
+            0x72, 0x6f, 0x6f, 0x74, 0x08, 0x13, 0x19, 0x2c, 0x36, 0x4e, 0x57, 0x6d,
+            0x70, 0x89, 0x91, 0x92, 0x97, 0x9c, 0xa3, 0xa5, 0xa7, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0xac,
+        ];
+
+        let job = crate::launchd::Job::parse(plist::parse(JOB).unwrap())
+            .unwrap();
+
+        let task = task(job);
+
+        assert_eq!(task.source, Source::Launchd);
+        assert_eq!(task.identifier, "com.example.updater");
+        assert_eq!(task.command, "/usr/local/bin/updater --check");
+        assert_eq!(task.schedule, "StartCalendarInterval={Hour=3, Minute=15}; RunAtLoad");
+        assert_eq!(task.user.as_deref(), Some("root"));
+        assert!(task.enabled);
+    }
+
+    #[test]
+    fn task_program() {
+        let job = plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.agent</string>
+	<key>Program</key>
+	<string>/opt/example/agent</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>agent</string>
+		<string>--daemon</string>
+	</array>
+	<key>StartInterval</key>
+	<integer>3600</integer>
+	<key>KeepAlive</key>
+	<dict>
+		<key>SuccessfulExit</key>
+		<false/>
+	</dict>
+	<key>Disabled</key>
+	<true/>
+</dict>
+</plist>
+"#).unwrap();
+
+        let task = task(crate::launchd::Job::parse(job).unwrap());
+
+        assert_eq!(task.identifier, "com.example.agent");
+        assert_eq!(task.command, "/opt/example/agent --daemon");
+        assert_eq!(task.schedule, "StartInterval=3600; KeepAlive");
+        assert_eq!(task.user, None);
+        assert!(!task.enabled);
+    }
+
+    #[test]
+    fn task_calendar_intervals() {
+        let job = plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.backup</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>/usr/local/bin/backup</string>
+	</array>
+	<key>StartCalendarInterval</key>
+	<array>
+		<dict>
+			<key>Weekday</key>
+			<integer>1</integer>
+		</dict>
+		<dict>
+			<key>Day</key>
+			<integer>15</integer>
+			<key>Hour</key>
+			<integer>12</integer>
+		</dict>
+	</array>
+	<key>WatchPaths</key>
+	<array>
+		<string>/etc/hosts</string>
+	</array>
+</dict>
+</plist>
+"#).unwrap();
+
+        let task = task(crate::launchd::Job::parse(job).unwrap());
+
+        assert_eq! {
+            task.schedule,
+            "StartCalendarInterval={Weekday=1}; StartCalendarInterval={Day=15, Hour=12}; WatchPaths=/etc/hosts"
+        };
+    }
+
+    #[test]
+    fn tasks_not_empty() {
+        // There are always some system daemons defined on macOS.
+        assert!(!tasks().is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use crate::systemd::Unit;
+
+use super::{Source, Task};
+
+/// Settings of the `[Timer]` section that define when the timer elapses.
+///
+/// See the [documentation][1] for details.
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.timer.html
+const TIMER_SCHEDULE_KEYS: &[&str] = &[
+    "OnActiveSec",
+    "OnBootSec",
+    "OnStartupSec",
+    "OnUnitActiveSec",
+    "OnUnitInactiveSec",
+    "OnCalendar",
+];
+
+/// Returns tasks corresponding to all timer units on the system.
+pub fn timers() -> Vec<Task> {
+    let mut timers = Vec::new();
+
+    for (name, path) in crate::systemd::units("timer") {
+        match timer(&name, &path) {
+            Ok(timer) => timers.push(timer),
+            Err(error) => {
+                log::warn!("failed to read timer '{}': {error}", path.display());
+            }
+        }
+    }
+
+    timers
+}
+
+/// Returns a task corresponding to the timer unit at the given path.
+fn timer(name: &str, path: &Path) -> std::io::Result<Task> {
+    let timer = Unit::open(path)?;
+
+    // By default, a timer activates the service with the same name [1].
+    //
+    // [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.timer.html#Unit=
+    let service_name = match timer.get("Timer", "Unit") {
+        Some(service_name) => String::from(service_name),
+        None => format!("{}.service", name.trim_end_matches(".timer")),
+    };
+
+    let service = match crate::systemd::find_unit(&service_name) {
+        Some(service_path) => Some(Unit::open(service_path)?),
+        None => {
+            log::warn!("no service '{service_name}' for timer '{name}'");
+            None
+        }
+    };
+
+    let command = match &service {
+        Some(service) => service.get_all("Service", "ExecStart").into_iter()
+            .map(crate::systemd::strip_exec_prefix)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => String::new(),
+    };
+
+    let user = service.as_ref()
+        .and_then(|service| service.get("Service", "User"))
+        .map(String::from);
+
+    Ok(Task {
+        source: Source::SystemdTimer,
+        identifier: String::from(name),
+        command,
+        schedule: timer_schedule(&timer),
+        user,
+        enabled: crate::systemd::is_enabled(name),
+        author: None,
+    })
+}
+
+/// Returns the description of when the given timer unit elapses.
+fn timer_schedule(timer: &Unit) -> String {
+    let mut schedule = Vec::new();
+
+    for key in TIMER_SCHEDULE_KEYS {
+        for value in timer.get_all("Timer", key) {
+            schedule.push(format!("{key}={value}"));
+        }
+    }
+
+    schedule.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn timer_schedule_many() {
+        let unit = Unit::parse(&b"\
+[Timer]
+OnBootSec=15min
+OnUnitActiveSec=1w
+AccuracySec=1h
+"[..]).unwrap();
+
+        assert_eq!(timer_schedule(&unit), "OnBootSec=15min; OnUnitActiveSec=1w");
+    }
+
+    #[test]
+    fn timers_ok() {
+        for timer in timers() {
+            assert_eq!(timer.source, Source::SystemdTimer);
+            assert!(timer.identifier.ends_with(".timer"));
+        }
+    }
+}
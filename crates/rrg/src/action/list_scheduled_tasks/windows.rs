@@ -0,0 +1,168 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{Source, Task};
+
+/// Returns tasks registered in the Windows Task Scheduler.
+pub fn tasks() -> std::io::Result<Vec<Task>> {
+    let scheduler = taskschd::scheduler()?;
+
+    let mut tasks = Vec::new();
+
+    for task in scheduler.tasks()? {
+        match task {
+            Ok(task) => tasks.push(convert_task(task)),
+            Err(error) => log::warn!("failed to read scheduled task: {error}"),
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Converts a Task Scheduler task to the normalized task representation.
+fn convert_task(task: taskschd::Task) -> Task {
+    let command = task.actions.iter()
+        .map(action_command)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let schedule = task.triggers.iter()
+        .map(trigger_schedule)
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    Task {
+        source: Source::WindowsTaskScheduler,
+        identifier: task.path.to_string_lossy().into_owned(),
+        command,
+        schedule,
+        // Tasks can be configured to run either as a specific user or as any
+        // member of a specific group.
+        user: task.user.or(task.group)
+            .map(|user| user.to_string_lossy().into_owned()),
+        enabled: task.enabled,
+        author: task.author
+            .map(|author| author.to_string_lossy().into_owned()),
+    }
+}
+
+/// Returns the description of what the given action executes.
+fn action_command(action: &taskschd::Action) -> String {
+    match action {
+        taskschd::Action::Exec { path, arguments, .. } => match arguments {
+            Some(arguments) => {
+                format!("{} {}", path.to_string_lossy(), arguments.to_string_lossy())
+            }
+            None => path.to_string_lossy().into_owned(),
+        },
+        taskschd::Action::ComHandler { class_id, data } => match data {
+            Some(data) => {
+                format!("COM handler {} {}", class_id.to_string_lossy(), data.to_string_lossy())
+            }
+            None => format!("COM handler {}", class_id.to_string_lossy()),
+        },
+        taskschd::Action::SendEmail => String::from("send email"),
+        taskschd::Action::ShowMessage => String::from("show message"),
+        taskschd::Action::Unknown(kind) => format!("unknown action ({kind})"),
+    }
+}
+
+/// Returns the description of when the given trigger starts a task.
+fn trigger_schedule(trigger: &taskschd::Trigger) -> String {
+    use taskschd::TriggerKind::*;
+
+    let kind = match trigger.kind {
+        Event => String::from("event"),
+        Time => String::from("time"),
+        Daily => String::from("daily"),
+        Weekly => String::from("weekly"),
+        Monthly => String::from("monthly"),
+        MonthlyDayOfWeek => String::from("monthly (day of week)"),
+        Idle => String::from("idle"),
+        Registration => String::from("registration"),
+        Boot => String::from("boot"),
+        Logon => String::from("logon"),
+        SessionStateChange => String::from("session state change"),
+        Custom => String::from("custom"),
+        Unknown(kind) => format!("unknown ({kind})"),
+    };
+
+    let mut details = Vec::new();
+    if let Some(start) = &trigger.start {
+        details.push(format!("from {}", start.to_string_lossy()));
+    }
+    if let Some(end) = &trigger.end {
+        details.push(format!("until {}", end.to_string_lossy()));
+    }
+    if !trigger.enabled {
+        details.push(String::from("disabled"));
+    }
+
+    if details.is_empty() {
+        kind
+    } else {
+        format!("{kind} ({})", details.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn convert_task_ok() {
+        let task = convert_task(taskschd::Task {
+            name: "Foo".into(),
+            path: "\\Example\\Foo".into(),
+            enabled: true,
+            author: Some("Example Corp.".into()),
+            user: None,
+            group: Some("S-1-5-32-545".into()),
+            triggers: vec![
+                taskschd::Trigger {
+                    kind: taskschd::TriggerKind::Daily,
+                    start: Some("2023-11-14T03:00:00".into()),
+                    end: None,
+                    enabled: true,
+                },
+                taskschd::Trigger {
+                    kind: taskschd::TriggerKind::Logon,
+                    start: None,
+                    end: None,
+                    enabled: false,
+                },
+            ],
+            actions: vec![
+                taskschd::Action::Exec {
+                    path: "C:\\Example\\foo.exe".into(),
+                    arguments: Some("--bar".into()),
+                    working_dir: None,
+                },
+                taskschd::Action::ComHandler {
+                    class_id: "{01234567-89AB-CDEF-0123-456789ABCDEF}".into(),
+                    data: None,
+                },
+            ],
+        });
+
+        assert_eq!(task.source, Source::WindowsTaskScheduler);
+        assert_eq!(task.identifier, "\\Example\\Foo");
+        assert_eq! {
+            task.command,
+            "C:\\Example\\foo.exe --bar\nCOM handler {01234567-89AB-CDEF-0123-456789ABCDEF}"
+        };
+        assert_eq!(task.schedule, "daily (from 2023-11-14T03:00:00); logon (disabled)");
+        assert_eq!(task.user.as_deref(), Some("S-1-5-32-545"));
+        assert_eq!(task.author.as_deref(), Some("Example Corp."));
+        assert!(task.enabled);
+    }
+
+    #[test]
+    fn tasks_not_empty() {
+        // Every Windows installation comes with a lot of built-in tasks.
+        assert!(!tasks().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+#[cfg(target_os = "linux")]
+mod systemd;
+
+#[cfg(target_os = "linux")]
+mod sysv;
+
+#[cfg(target_os = "macos")]
+mod launchd;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+/// Arguments of the `list_services` action.
+pub struct Args {
+    /// Whether it is allowed to invoke external programs.
+    allow_external_commands: bool,
+}
+
+/// A result of the `list_services` action.
+struct Item {
+    /// Information about the individual service.
+    service: Service,
+}
+
+/// Information about a system service (daemon).
+#[derive(Debug, Default)]
+struct Service {
+    /// Source from which the service was collected.
+    source: Source,
+    /// Source-specific name of the service.
+    name: String,
+    /// Human-readable name or description of the service.
+    display_name: Option<String>,
+    /// Unquoted path to the executable of the service.
+    binary_path: Option<std::path::PathBuf>,
+    /// Arguments with which the executable is invoked.
+    arguments: Option<String>,
+    /// Way in which the service is started.
+    start_type: StartType,
+    /// Current runtime state of the service.
+    state: State,
+    /// Name of the account as which the service runs.
+    user: Option<String>,
+}
+
+/// Source from which the service was collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Source {
+    /// The source is not known.
+    #[default]
+    Unknown,
+    /// Service units of systemd.
+    #[cfg(target_os = "linux")]
+    Systemd,
+    /// SysV init scripts.
+    #[cfg(target_os = "linux")]
+    SysvInit,
+    /// Launch daemons and agents of launchd.
+    #[cfg(target_os = "macos")]
+    Launchd,
+    /// Windows Service Control Manager.
+    #[cfg(target_os = "windows")]
+    WindowsServiceControlManager,
+}
+
+/// Way in which a service is started.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum StartType {
+    /// The start type is not known.
+    #[default]
+    Unknown,
+    /// Started by the system loader.
+    #[cfg(target_os = "windows")]
+    Boot,
+    /// Started during kernel initialization.
+    #[cfg(target_os = "windows")]
+    System,
+    /// Started automatically during system startup.
+    Automatic,
+    /// Started only on demand.
+    Manual,
+    /// Cannot be started.
+    Disabled,
+}
+
+/// Runtime state of a service.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    /// The state is not known.
+    #[default]
+    Unknown,
+    /// The service is not running.
+    Stopped,
+    /// The service is starting.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    StartPending,
+    /// The service is stopping.
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    StopPending,
+    /// The service is running.
+    Running,
+    /// The service is about to continue after being paused.
+    #[cfg(target_os = "windows")]
+    ContinuePending,
+    /// The service is about to pause.
+    #[cfg(target_os = "windows")]
+    PausePending,
+    /// The service is paused.
+    #[cfg(target_os = "windows")]
+    Paused,
+    /// The service is not running because it has failed.
+    #[cfg(target_os = "linux")]
+    Failed,
+}
+
+/// Handles invocations of the `list_services` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut services = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut systemd_services = self::systemd::services();
+
+        // There is no native way of getting the runtime state of units other
+        // than talking to systemd over D-Bus, so we can only ask `systemctl`
+        // and only if we are explicitly allowed to.
+        if args.allow_external_commands {
+            match self::systemd::states() {
+                Ok(states) => {
+                    for service in &mut systemd_services {
+                        if let Some(state) = states.get(&service.name) {
+                            service.state = *state;
+                        }
+                    }
+                }
+                Err(error) => log::warn!("failed to query systemd units: {error}"),
+            }
+        } else {
+            log::info!("skipping systemd unit states (external commands not allowed)");
+        }
+
+        // systemd ignores init scripts for which there is a native unit with
+        // the same name, so we skip them as well.
+        let systemd_names = systemd_services.iter()
+            .map(|service| service.name.clone())
+            .collect::<std::collections::HashSet<_>>();
+
+        let sysv_services = self::sysv::services().into_iter()
+            .filter(|service| !systemd_names.contains(&format!("{}.service", service.name)));
+
+        services.extend(systemd_services);
+        services.extend(sysv_services);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let mut launchd_services = self::launchd::services();
+
+        // The runtime state of jobs is known only to launchd itself, so we can
+        // only ask `launchctl` and only if we are explicitly allowed to.
+        if args.allow_external_commands {
+            match self::launchd::states() {
+                Ok(states) => {
+                    for service in &mut launchd_services {
+                        service.state = states.get(&service.name)
+                            .copied()
+                            .unwrap_or(State::Stopped);
+                    }
+                }
+                Err(error) => log::warn!("failed to query launchd jobs: {error}"),
+            }
+        } else {
+            log::info!("skipping launchd job states (external commands not allowed)");
+        }
+
+        services.extend(launchd_services);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // There are no external commands that we would call on Windows.
+        let _ = args.allow_external_commands;
+
+        let windows_services = self::windows::services()
+            .map_err(crate::session::Error::action)?;
+
+        services.extend(windows_services);
+    }
+
+    for service in services {
+        session.reply(Item {
+            service,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Splits the given command line into the executable path and the arguments.
+///
+/// The path can be quoted (with either single or double quotes) in which case
+/// the quotes are stripped. Otherwise, the path ends at the first whitespace.
+#[cfg(target_os = "linux")]
+fn split_command(command: &str) -> (&str, Option<&str>) {
+    let command = command.trim();
+
+    let quote = command.chars().next()
+        .filter(|char| *char == '"' || *char == '\'');
+
+    let (path, rest) = match quote {
+        Some(quote) => {
+            let quoted = &command[1..];
+            match quoted.find(quote) {
+                Some(index) => (&quoted[..index], &quoted[index + 1..]),
+                None => (quoted, ""),
+            }
+        }
+        None => match command.find(char::is_whitespace) {
+            Some(index) => (&command[..index], &command[index..]),
+            None => (command, ""),
+        },
+    };
+
+    let args = rest.trim();
+    if args.is_empty() {
+        (path, None)
+    } else {
+        (path, Some(args))
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_services::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            allow_external_commands: proto.allow_external_commands(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_services::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let service = self.service;
+
+        let mut proto = rrg_proto::list_services::Result::new();
+        proto.set_source(service.source.into());
+        proto.set_name(service.name);
+        if let Some(display_name) = service.display_name {
+            proto.set_display_name(display_name);
+        }
+        if let Some(binary_path) = service.binary_path {
+            proto.set_binary_path(crate::path::into_proto(binary_path));
+        }
+        if let Some(arguments) = service.arguments {
+            proto.set_arguments(arguments);
+        }
+        proto.set_start_type(service.start_type.into());
+        proto.set_state(service.state.into());
+        if let Some(user) = service.user {
+            proto.set_user(user);
+        }
+
+        proto
+    }
+}
+
+impl From<Source> for rrg_proto::list_services::Source {
+
+    fn from(source: Source) -> rrg_proto::list_services::Source {
+        use rrg_proto::list_services::Source as Proto;
+
+        match source {
+            Source::Unknown => Proto::UNKNOWN,
+            #[cfg(target_os = "linux")]
+            Source::Systemd => Proto::SYSTEMD,
+            #[cfg(target_os = "linux")]
+            Source::SysvInit => Proto::SYSV_INIT,
+            #[cfg(target_os = "macos")]
+            Source::Launchd => Proto::LAUNCHD,
+            #[cfg(target_os = "windows")]
+            Source::WindowsServiceControlManager => Proto::WINDOWS_SERVICE_CONTROL_MANAGER,
+        }
+    }
+}
+
+impl From<StartType> for rrg_proto::list_services::StartType {
+
+    fn from(start_type: StartType) -> rrg_proto::list_services::StartType {
+        use rrg_proto::list_services::StartType as Proto;
+
+        match start_type {
+            StartType::Unknown => Proto::UNKNOWN_START_TYPE,
+            #[cfg(target_os = "windows")]
+            StartType::Boot => Proto::BOOT,
+            #[cfg(target_os = "windows")]
+            StartType::System => Proto::SYSTEM,
+            StartType::Automatic => Proto::AUTOMATIC,
+            StartType::Manual => Proto::MANUAL,
+            StartType::Disabled => Proto::DISABLED,
+        }
+    }
+}
+
+impl From<State> for rrg_proto::list_services::State {
+
+    fn from(state: State) -> rrg_proto::list_services::State {
+        use rrg_proto::list_services::State as Proto;
+
+        match state {
+            State::Unknown => Proto::UNKNOWN_STATE,
+            State::Stopped => Proto::STOPPED,
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            State::StartPending => Proto::START_PENDING,
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            State::StopPending => Proto::STOP_PENDING,
+            State::Running => Proto::RUNNING,
+            #[cfg(target_os = "windows")]
+            State::ContinuePending => Proto::CONTINUE_PENDING,
+            #[cfg(target_os = "windows")]
+            State::PausePending => Proto::PAUSE_PENDING,
+            #[cfg(target_os = "windows")]
+            State::Paused => Proto::PAUSED,
+            #[cfg(target_os = "linux")]
+            State::Failed => Proto::FAILED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_no_external_commands() {
+        let args = Args {
+            allow_external_commands: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        for item in session.replies::<Item>() {
+            assert!(!item.service.name.is_empty());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn split_command_unquoted() {
+        assert_eq!(split_command("/usr/sbin/sshd -D"), ("/usr/sbin/sshd", Some("-D")));
+        assert_eq!(split_command("/usr/sbin/cron"), ("/usr/sbin/cron", None));
+        assert_eq!(split_command("  /bin/foo   --bar  "), ("/bin/foo", Some("--bar")));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn split_command_quoted() {
+        assert_eq! {
+            split_command("\"C:\\Program Files\\Foo\\foo.exe\" --bar \"baz\""),
+            ("C:\\Program Files\\Foo\\foo.exe", Some("--bar \"baz\""))
+        };
+        assert_eq!(split_command("'/opt/foo bar/foo'"), ("/opt/foo bar/foo", None));
+        assert_eq!(split_command("\"/opt/foo"), ("/opt/foo", None));
+    }
+
+    #[test]
+    fn item_into_proto() {
+        use crate::response::Item as _;
+
+        let item = Item {
+            service: Service {
+                source: Source::Unknown,
+                name: String::from("foo"),
+                display_name: Some(String::from("Foo Service")),
+                binary_path: Some(std::path::PathBuf::from("/usr/bin/foo")),
+                arguments: Some(String::from("--bar")),
+                start_type: StartType::Automatic,
+                state: State::Running,
+                user: None,
+            },
+        };
+
+        let mut proto = item.into_proto();
+        assert_eq!(proto.source(), rrg_proto::list_services::Source::UNKNOWN);
+        assert_eq!(proto.name(), "foo");
+        assert_eq!(proto.display_name(), "Foo Service");
+        assert_eq! {
+            crate::path::from_proto(proto.take_binary_path()).unwrap(),
+            std::path::PathBuf::from("/usr/bin/foo")
+        };
+        assert_eq!(proto.arguments(), "--bar");
+        assert_eq!(proto.start_type(), rrg_proto::list_services::StartType::AUTOMATIC);
+        assert_eq!(proto.state(), rrg_proto::list_services::State::RUNNING);
+        assert_eq!(proto.user(), "");
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+
+use super::{Service, Source, StartType, State};
+
+/// Returns services corresponding to all launchd jobs on the system.
+pub fn services() -> Vec<Service> {
+    crate::launchd::jobs().into_iter()
+        .map(service)
+        .collect()
+}
+
+/// Returns runtime states of all jobs loaded into launchd.
+///
+/// The states are obtained from the `launchctl` program and the map keys are
+/// labels of the jobs. Jobs that are not loaded are not included.
+pub fn states() -> std::io::Result<HashMap<String, State>> {
+    let output = std::process::Command::new("launchctl")
+        .arg("list")
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format! {
+            "`launchctl` failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        }));
+    }
+
+    Ok(parse_states(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Converts the given launchd job to the normalized service representation.
+fn service(job: crate::launchd::Job) -> Service {
+    let definition = &job.definition;
+
+    // Jobs are loaded at startup (or login for agents) but are started only if
+    // they ask for it, otherwise launchd waits for them to be requested.
+    let is_started = definition.get("RunAtLoad").and_then(plist::Value::as_bool) == Some(true) ||
+        matches!(definition.get("KeepAlive"), Some(plist::Value::Bool(true) | plist::Value::Dict(_)));
+
+    let start_type = if !job.enabled {
+        StartType::Disabled
+    } else if is_started {
+        StartType::Automatic
+    } else {
+        StartType::Manual
+    };
+
+    let args = job.args();
+
+    Service {
+        source: Source::Launchd,
+        display_name: None,
+        binary_path: job.program().map(std::path::PathBuf::from),
+        arguments: if args.is_empty() {
+            None
+        } else {
+            Some(args.join(" "))
+        },
+        start_type,
+        state: State::Unknown,
+        name: job.label,
+        user: job.user,
+    }
+}
+
+/// Parses runtime states of jobs from the output of `launchctl list`.
+///
+/// The output has a header line followed by lines with tab-separated PID (or
+/// `-` if the job is not running), last exit status and label.
+fn parse_states(output: &str) -> HashMap<String, State> {
+    let mut states = HashMap::new();
+
+    for line in output.lines().skip(1) {
+        let mut fields = line.split('\t');
+
+        let (Some(pid), Some(_), Some(label)) = (fields.next(), fields.next(), fields.next()) else {
+            log::warn!("invalid launchctl output line: {line:?}");
+            continue;
+        };
+
+        let state = if pid == "-" {
+            State::Stopped
+        } else {
+            State::Running
+        };
+
+        states.insert(String::from(label), state);
+    }
+
+    states
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn service_run_at_load() {
+        let job = crate::launchd::Job::parse(plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.daemon</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>/usr/local/bin/daemon</string>
+		<string>--foo</string>
+	</array>
+	<key>RunAtLoad</key>
+	<true/>
+</dict>
+</plist>
+"#).unwrap()).unwrap();
+
+        let service = service(job);
+        assert_eq!(service.source, Source::Launchd);
+        assert_eq!(service.name, "com.example.daemon");
+        assert_eq!(service.binary_path.as_deref(), Some(std::path::Path::new("/usr/local/bin/daemon")));
+        assert_eq!(service.arguments.as_deref(), Some("--foo"));
+        assert_eq!(service.start_type, StartType::Automatic);
+    }
+
+    #[test]
+    fn service_disabled() {
+        let job = crate::launchd::Job::parse(plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.agent</string>
+	<key>Program</key>
+	<string>/opt/example/agent</string>
+	<key>Disabled</key>
+	<true/>
+</dict>
+</plist>
+"#).unwrap()).unwrap();
+
+        let service = service(job);
+        assert_eq!(service.arguments, None);
+        assert_eq!(service.start_type, StartType::Disabled);
+    }
+
+    #[test]
+    fn parse_states_ok() {
+        let output = "\
+PID\tStatus\tLabel
+-\t0\tcom.apple.foo
+123\t0\tcom.apple.bar
+-\t-9\tcom.example.baz
+";
+
+        let states = parse_states(output);
+        assert_eq!(states.len(), 3);
+        assert_eq!(states["com.apple.foo"], State::Stopped);
+        assert_eq!(states["com.apple.bar"], State::Running);
+        assert_eq!(states["com.example.baz"], State::Stopped);
+    }
+
+    #[test]
+    fn services_not_empty() {
+        // There are always some system daemons defined on macOS.
+        assert!(!services().is_empty());
+    }
+}
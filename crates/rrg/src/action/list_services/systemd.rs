@@ -0,0 +1,214 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::systemd::Unit;
+
+use super::{Service, Source, StartType, State};
+
+/// Returns services corresponding to all service units on the system.
+pub fn services() -> Vec<Service> {
+    let mut services = Vec::new();
+
+    for (name, path) in crate::systemd::units("service") {
+        match service(&name, &path) {
+            Ok(service) => services.push(service),
+            Err(error) => {
+                log::warn!("failed to read service '{}': {error}", path.display());
+            }
+        }
+    }
+
+    services
+}
+
+/// Returns runtime states of all service units known to systemd.
+///
+/// The states are obtained from the `systemctl` program and the map keys are
+/// names of the units.
+pub fn states() -> std::io::Result<HashMap<String, State>> {
+    let output = std::process::Command::new("systemctl")
+        .arg("list-units")
+        .arg("--type=service")
+        .arg("--all")
+        .arg("--output=json")
+        .arg("--no-pager")
+        .stdin(std::process::Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format! {
+            "`systemctl` failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        }));
+    }
+
+    Ok(parse_states(&json::parse(&output.stdout)?))
+}
+
+/// Returns a service corresponding to the service unit at the given path.
+fn service(name: &str, path: &Path) -> std::io::Result<Service> {
+    // Masked units are symlinks to `/dev/null`, so there is nothing to parse.
+    if crate::systemd::is_masked(path) {
+        return Ok(Service {
+            source: Source::Systemd,
+            name: String::from(name),
+            start_type: StartType::Disabled,
+            ..Default::default()
+        });
+    }
+
+    let unit = Unit::open(path)?;
+
+    // Service units can have more than one `ExecStart=` (for `Type=oneshot`)
+    // but the first one is always the main binary.
+    let (binary_path, arguments) = match unit.get_all("Service", "ExecStart").first() {
+        Some(command) => {
+            let command = crate::systemd::strip_exec_prefix(command);
+
+            let (binary_path, arguments) = super::split_command(command);
+            (Some(std::path::PathBuf::from(binary_path)), arguments.map(String::from))
+        }
+        None => (None, None),
+    };
+
+    // Units that are not enabled can still be started on demand: either
+    // manually, through socket activation or as dependencies of other units.
+    let start_type = if crate::systemd::is_enabled(name) {
+        StartType::Automatic
+    } else {
+        StartType::Manual
+    };
+
+    Ok(Service {
+        source: Source::Systemd,
+        name: String::from(name),
+        display_name: unit.get("Unit", "Description").map(String::from),
+        binary_path,
+        arguments,
+        start_type,
+        state: State::Unknown,
+        user: unit.get("Service", "User").map(String::from),
+    })
+}
+
+/// Parses runtime states of units from the JSON output of `systemctl`.
+fn parse_states(output: &json::Value) -> HashMap<String, State> {
+    let mut states = HashMap::new();
+
+    for unit in output.as_array().unwrap_or_default() {
+        let Some(name) = unit.get("unit").and_then(json::Value::as_str) else {
+            log::warn!("systemd unit without a name");
+            continue;
+        };
+
+        let active = unit.get("active").and_then(json::Value::as_str);
+        let sub = unit.get("sub").and_then(json::Value::as_str);
+
+        // Active units of `Type=oneshot` that already finished are reported
+        // as `exited`: there is no process running for them anymore.
+        let state = match (active, sub) {
+            (Some("active"), Some("exited")) => State::Stopped,
+            (Some("active" | "reloading"), _) => State::Running,
+            (Some("inactive"), _) => State::Stopped,
+            (Some("activating"), _) => State::StartPending,
+            (Some("deactivating"), _) => State::StopPending,
+            (Some("failed"), _) => State::Failed,
+            _ => State::Unknown,
+        };
+
+        states.insert(String::from(name), state);
+    }
+
+    states
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_states_many() {
+        let output = json::parse(br#"[
+            {"unit":"cron.service","load":"loaded","active":"active","sub":"running","description":"Regular background program processing daemon"},
+            {"unit":"apt-daily.service","load":"loaded","active":"inactive","sub":"dead","description":"Daily apt download activities"},
+            {"unit":"networking.service","load":"loaded","active":"active","sub":"exited","description":"Raise network interfaces"},
+            {"unit":"foo.service","load":"loaded","active":"failed","sub":"failed","description":"Foo"},
+            {"unit":"bar.service","load":"loaded","active":"activating","sub":"start-pre","description":"Bar"}
+        ]"#).unwrap();
+
+        let states = parse_states(&output);
+        assert_eq!(states.len(), 5);
+        assert_eq!(states["cron.service"], State::Running);
+        assert_eq!(states["apt-daily.service"], State::Stopped);
+        assert_eq!(states["networking.service"], State::Stopped);
+        assert_eq!(states["foo.service"], State::Failed);
+        assert_eq!(states["bar.service"], State::StartPending);
+    }
+
+    #[test]
+    fn parse_states_invalid() {
+        let output = json::parse(br#"[{"active":"active"}, {"unit":"foo.service"}]"#)
+            .unwrap();
+
+        let states = parse_states(&output);
+        assert_eq!(states.len(), 1);
+        assert_eq!(states["foo.service"], State::Unknown);
+    }
+
+    #[test]
+    fn service_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.service");
+        std::fs::write(&path, b"\
+[Unit]
+Description=Foo daemon
+
+[Service]
+ExecStart=-\"/opt/foo bar/food\" --config /etc/foo.conf
+User=foo
+").unwrap();
+
+        let service = service("foo.service", &path)
+            .unwrap();
+
+        assert_eq!(service.source, Source::Systemd);
+        assert_eq!(service.name, "foo.service");
+        assert_eq!(service.display_name.as_deref(), Some("Foo daemon"));
+        assert_eq!(service.binary_path.as_deref(), Some(Path::new("/opt/foo bar/food")));
+        assert_eq!(service.arguments.as_deref(), Some("--config /etc/foo.conf"));
+        assert_eq!(service.user.as_deref(), Some("foo"));
+    }
+
+    #[test]
+    fn service_masked() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo.service");
+        std::os::unix::fs::symlink("/dev/null", &path)
+            .unwrap();
+
+        let service = service("foo.service", &path)
+            .unwrap();
+
+        assert_eq!(service.start_type, StartType::Disabled);
+        assert_eq!(service.binary_path, None);
+    }
+
+    #[test]
+    fn services_ok() {
+        for service in services() {
+            assert_eq!(service.source, Source::Systemd);
+            assert!(service.name.ends_with(".service"));
+        }
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use super::{Service, Source, StartType, State};
+
+/// Paths to directories with init scripts.
+///
+/// The first one is used by Debian and derivatives, the second one by Red Hat
+/// and derivatives (where the first one is usually a symlink to it).
+const INIT_DIR_PATHS: &[&str] = &[
+    "/etc/init.d",
+    "/etc/rc.d/init.d",
+];
+
+/// Paths to directories with init script links of multi-user runlevels.
+///
+/// Scripts started in the given runlevel are symlinked there with the name
+/// prefixed with `S` and a two-digit sequence number.
+const RC_DIR_PATHS: &[&str] = &[
+    "/etc/rc2.d",
+    "/etc/rc3.d",
+    "/etc/rc4.d",
+    "/etc/rc5.d",
+    "/etc/rc.d/rc2.d",
+    "/etc/rc.d/rc3.d",
+    "/etc/rc.d/rc4.d",
+    "/etc/rc.d/rc5.d",
+];
+
+/// Returns services corresponding to all init scripts on the system.
+pub fn services() -> Vec<Service> {
+    let started = started_names();
+
+    let mut services = Vec::new();
+    let mut dir_paths = std::collections::HashSet::new();
+
+    for dir_path in INIT_DIR_PATHS {
+        // The directories are often symlinked to each other, so we make sure
+        // that we do not report the same scripts twice.
+        match std::fs::canonicalize(dir_path) {
+            Ok(dir_path) => {
+                if !dir_paths.insert(dir_path) {
+                    continue;
+                }
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to resolve '{dir_path}': {error}");
+                continue;
+            }
+        }
+
+        for path in script_paths(Path::new(dir_path)) {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let name = name.to_string_lossy().into_owned();
+
+            let display_name = match std::fs::File::open(&path) {
+                Ok(file) => short_description(std::io::BufReader::new(file)),
+                Err(error) => {
+                    log::warn!("failed to open init script '{}': {error}", path.display());
+                    None
+                }
+            };
+
+            let start_type = if started.contains(&name) {
+                StartType::Automatic
+            } else {
+                StartType::Manual
+            };
+
+            services.push(Service {
+                source: Source::SysvInit,
+                name,
+                display_name,
+                binary_path: Some(path),
+                arguments: None,
+                start_type,
+                state: State::Unknown,
+                user: None,
+            });
+        }
+    }
+
+    services
+}
+
+/// Returns paths to all executable init scripts in the given directory.
+fn script_paths(dir_path: &Path) -> Vec<PathBuf> {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to list init scripts in '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut paths = Vec::new();
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::warn!("failed to read init script entry: {error}");
+                continue;
+            }
+        };
+
+        // Init script directories contain also some non-scripts like README
+        // files or the `.depend.*` files used by `insserv`.
+        let metadata = match std::fs::metadata(entry.path()) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                log::warn!("failed to stat init script '{}': {error}", entry.path().display());
+                continue;
+            }
+        };
+        if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+            continue;
+        }
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        paths.push(entry.path());
+    }
+
+    paths.sort();
+    paths
+}
+
+/// Returns names of init scripts started in any of the multi-user runlevels.
+fn started_names() -> std::collections::HashSet<String> {
+    let mut names = std::collections::HashSet::new();
+
+    for dir_path in RC_DIR_PATHS {
+        let entries = match std::fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to list runlevel links in '{dir_path}': {error}");
+                continue;
+            }
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            if let Some(name) = started_name(&entry.file_name().to_string_lossy()) {
+                names.insert(String::from(name));
+            }
+        }
+    }
+
+    names
+}
+
+/// Returns the script name if the given runlevel link name starts it.
+///
+/// Such names have the `S<NN><name>` format (e.g. `S01ssh`).
+fn started_name(link_name: &str) -> Option<&str> {
+    let rest = link_name.strip_prefix('S')?;
+
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if digits != 2 {
+        return None;
+    }
+
+    Some(&rest[digits..]).filter(|name| !name.is_empty())
+}
+
+/// Returns the short description from the LSB header of the given script.
+///
+/// See the [specification][1] for details about the header format.
+///
+/// [1]: https://refspecs.linuxbase.org/LSB_3.1.1/LSB-Core-generic/LSB-Core-generic/initscrcomconv.html
+fn short_description<R>(script: R) -> Option<String>
+where
+    R: std::io::BufRead,
+{
+    let mut is_header = false;
+
+    for line in script.lines() {
+        // Init scripts are not required to be valid UTF-8, we just give up on
+        // those that are not.
+        let line = line.ok()?;
+        let line = line.trim();
+
+        match line {
+            "### BEGIN INIT INFO" => is_header = true,
+            "### END INIT INFO" => return None,
+            _ if is_header => {
+                let description = line.strip_prefix('#')
+                    .map(str::trim_start)
+                    .and_then(|line| line.strip_prefix("Short-Description:"))
+                    .map(str::trim);
+
+                if let Some(description) = description {
+                    return Some(String::from(description));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn started_name_ok() {
+        assert_eq!(started_name("S01ssh"), Some("ssh"));
+        assert_eq!(started_name("S20cron"), Some("cron"));
+    }
+
+    #[test]
+    fn started_name_invalid() {
+        assert_eq!(started_name("K01ssh"), None);
+        assert_eq!(started_name("S1ssh"), None);
+        assert_eq!(started_name("S01"), None);
+        assert_eq!(started_name("README"), None);
+    }
+
+    #[test]
+    fn short_description_ok() {
+        let script = b"\
+#!/bin/sh
+### BEGIN INIT INFO
+# Provides:          ssh sshd
+# Required-Start:    $remote_fs $syslog
+# Default-Start:     2 3 4 5
+# Short-Description: OpenBSD Secure Shell server
+### END INIT INFO
+";
+
+        assert_eq! {
+            short_description(&script[..]).as_deref(),
+            Some("OpenBSD Secure Shell server")
+        };
+    }
+
+    #[test]
+    fn short_description_none() {
+        let script = b"\
+#!/bin/sh
+# Short-Description: not in the header
+### BEGIN INIT INFO
+# Provides:          foo
+### END INIT INFO
+";
+
+        assert_eq!(short_description(&script[..]), None);
+    }
+
+    #[test]
+    fn services_ok() {
+        for service in services() {
+            assert_eq!(service.source, Source::SysvInit);
+            assert!(service.binary_path.is_some());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::Path;
+
+use super::{Service, Source, StartType, State};
+
+/// Returns services registered in the Windows Service Control Manager.
+pub fn services() -> std::io::Result<Vec<Service>> {
+    let system_root = crate::os::windows::path::system_root();
+
+    let mut services = Vec::new();
+
+    for service in ospect::service::services()? {
+        match service {
+            Ok(service) => services.push(convert_service(service, &system_root)),
+            Err(error) => log::warn!("failed to read service: {error}"),
+        }
+    }
+
+    Ok(services)
+}
+
+/// Converts a Service Control Manager service to the normalized representation.
+fn convert_service(service: ospect::service::Service, system_root: &Path) -> Service {
+    use ospect::service::State::*;
+
+    let state = match service.state() {
+        Stopped => State::Stopped,
+        StartPending => State::StartPending,
+        StopPending => State::StopPending,
+        Running => State::Running,
+        ContinuePending => State::ContinuePending,
+        PausePending => State::PausePending,
+        Paused => State::Paused,
+        Unknown(state) => {
+            log::warn!("unknown state of service {:?}: {state}", service.name());
+            State::Unknown
+        }
+    };
+
+    let start_type = match service.start_type() {
+        Some(ospect::service::StartType::Boot) => StartType::Boot,
+        Some(ospect::service::StartType::System) => StartType::System,
+        Some(ospect::service::StartType::Auto) => StartType::Automatic,
+        Some(ospect::service::StartType::Demand) => StartType::Manual,
+        Some(ospect::service::StartType::Disabled) => StartType::Disabled,
+        None => StartType::Unknown,
+    };
+
+    let (binary_path, arguments) = match service.command_line() {
+        Some(command_line) => {
+            let command_line = command_line.to_string_lossy();
+
+            let (path, arguments) = crate::os::windows::path::split_command_line(&command_line);
+            (Some(crate::os::windows::path::normalize(path, system_root)), arguments.map(String::from))
+        }
+        None => (None, None),
+    };
+
+    Service {
+        source: Source::WindowsServiceControlManager,
+        name: service.name().to_string_lossy().into_owned(),
+        display_name: service.display_name()
+            .map(|display_name| display_name.to_string_lossy().into_owned()),
+        binary_path,
+        arguments,
+        start_type,
+        state,
+        user: service.account()
+            .map(|account| account.to_string_lossy().into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn services_event_log() {
+        let services = services()
+            .unwrap();
+
+        let event_log = services.iter()
+            .find(|service| service.name.eq_ignore_ascii_case("EventLog"))
+            .unwrap();
+
+        assert_eq!(event_log.source, Source::WindowsServiceControlManager);
+        assert_eq!(event_log.state, State::Running);
+        assert_eq!(event_log.start_type, StartType::Automatic);
+
+        let binary_path = event_log.binary_path.as_ref()
+            .unwrap();
+        assert!(binary_path.ends_with("svchost.exe"));
+        assert!(binary_path.is_absolute());
+    }
+}
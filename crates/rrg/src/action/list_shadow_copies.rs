@@ -0,0 +1,169 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the volume shadow copy listing action.
+//!
+//! Files in the listed shadow copies can be then accessed by other file actions
+//! using paths in the device namespace (see [`crate::fs::split_device_path`]).
+
+/// WQL query listing all shadow copies together with the columns we need.
+#[cfg(target_family = "windows")]
+const QUERY: &str = "SELECT ID, DeviceObject, VolumeName, InstallDate FROM Win32_ShadowCopy";
+
+/// A result of the `list_shadow_copies` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct Item {
+    /// Identifier (GUID) of the shadow copy.
+    id: String,
+    /// Path to the device of the shadow copy.
+    device_path: std::path::PathBuf,
+    /// Volume GUID path of the volume the shadow copy was taken of.
+    original_volume: String,
+    /// Time at which the shadow copy was created.
+    creation_time: Option<std::time::SystemTime>,
+}
+
+/// Handles invocations of the `list_shadow_copies` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let query = wmi::query(QUERY)
+        .map_err(crate::session::Error::action)?;
+
+    let rows = query.rows()
+        .map_err(crate::session::Error::action)?;
+
+    for row in rows {
+        let row = row
+            .map_err(crate::session::Error::action)?;
+
+        let item = match Item::from_row(&row) {
+            Some(item) => item,
+            None => {
+                log::warn!("skipping malformed shadow copy row: {row:?}");
+                continue;
+            }
+        };
+
+        session.reply(item)?;
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_shadow_copies` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_family = "windows")]
+impl Item {
+
+    /// Creates an item from a `Win32_ShadowCopy` row.
+    ///
+    /// `None` is returned if the row does not have the required columns.
+    fn from_row(row: &wmi::QueryRow) -> Option<Item> {
+        fn string(row: &wmi::QueryRow, name: &str) -> Option<std::ffi::OsString> {
+            match row.get(std::ffi::OsStr::new(name))? {
+                wmi::QueryValue::String(value) => Some(value.clone()),
+                _ => None,
+            }
+        }
+
+        let creation_time = match row.get(std::ffi::OsStr::new("InstallDate")) {
+            Some(wmi::QueryValue::DateTime(time)) => Some(*time),
+            _ => None,
+        };
+
+        Some(Item {
+            id: string(row, "ID")?.to_string_lossy().into_owned(),
+            device_path: std::path::PathBuf::from(string(row, "DeviceObject")?),
+            original_volume: string(row, "VolumeName")?.to_string_lossy().into_owned(),
+            creation_time,
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_shadow_copies::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_id(self.id);
+        proto.set_device_path(crate::path::into_proto(self.device_path));
+        proto.set_original_volume(self.original_volume);
+        if let Some(creation_time) = self.creation_time {
+            proto.set_creation_time(rrg_proto::into_timestamp(creation_time));
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn item_from_row() {
+        let mut row = wmi::QueryRow::new();
+        row.insert("ID".into(), wmi::QueryValue::String("{F00}".into()));
+        row.insert("DeviceObject".into(), wmi::QueryValue::String(
+            "\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1".into(),
+        ));
+        row.insert("VolumeName".into(), wmi::QueryValue::String(
+            "\\\\?\\Volume{BA7}\\".into(),
+        ));
+        row.insert("InstallDate".into(), wmi::QueryValue::DateTime(
+            std::time::UNIX_EPOCH,
+        ));
+
+        let item = Item::from_row(&row)
+            .unwrap();
+        assert_eq!(item.id, "{F00}");
+        assert!(crate::fs::split_device_path(&item.device_path).is_some());
+        assert_eq!(item.original_volume, "\\\\?\\Volume{BA7}\\");
+        assert_eq!(item.creation_time, Some(std::time::UNIX_EPOCH));
+    }
+
+    #[test]
+    fn item_from_row_missing_column() {
+        let mut row = wmi::QueryRow::new();
+        row.insert("ID".into(), wmi::QueryValue::String("{F00}".into()));
+
+        assert!(Item::from_row(&row).is_none());
+    }
+
+    #[cfg(feature = "test-admin")]
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        // There might be no shadow copies on the system at all, in which case
+        // the action should just succeed without any replies.
+        for item in session.replies::<Item>() {
+            assert!(crate::fs::split_device_path(&item.device_path).is_some());
+
+            // The root of the shadow copy should be accessible through the
+            // device path.
+            let mut root = item.device_path.clone().into_os_string();
+            root.push("\\");
+            assert!(std::fs::metadata(&root).unwrap().is_dir());
+        }
+    }
+}
@@ -0,0 +1,636 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+/// Files (relative to the home directory) that keys are collected from.
+///
+/// `authorized_keys2` is deprecated but is still read by the daemon unless the
+/// configuration says otherwise, which makes it a good place to hide a key in.
+const FILES: &[(&str, FileType)] = &[
+    ("authorized_keys", FileType::AuthorizedKeys),
+    ("authorized_keys2", FileType::AuthorizedKeys),
+    ("known_hosts", FileType::KnownHosts),
+];
+
+/// Prefixes of key types.
+///
+/// Options of authorized keys never start with any of these, so this is how
+/// we tell whether a line has options or not.
+const KEY_TYPE_PREFIXES: &[&str] = &["ssh-", "ecdsa-", "sk-", "rsa-sha2-"];
+
+/// A result of the `list_ssh_keys` action.
+struct Item {
+    /// Name of the user that owns the file.
+    user: String,
+    /// Path to the file.
+    path: PathBuf,
+    /// Type of the file.
+    file_type: FileType,
+    /// Number of the line (starting from 1) of the entry.
+    line: usize,
+    /// Entry defined at the line.
+    entry: Entry,
+}
+
+/// Type of the file that a key was found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileType {
+    /// File with keys that can be used to log in as the user.
+    AuthorizedKeys,
+    /// File with public keys of hosts that the user connected to.
+    KnownHosts,
+}
+
+/// Entry of a file with keys.
+#[derive(Debug, PartialEq, Eq)]
+enum Entry {
+    /// Successfully parsed key.
+    Key(Key),
+    /// Line that could not be parsed.
+    Malformed {
+        /// Reason why the line could not be parsed.
+        error: ParseError,
+        /// Content of the line.
+        content: String,
+    },
+}
+
+/// Public key defined in an `authorized_keys` or `known_hosts` file.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Key {
+    /// Options of the key (`authorized_keys` only).
+    options: Vec<String>,
+    /// Marker of the key (`known_hosts` only).
+    marker: Option<String>,
+    /// Host patterns that the key is associated with (`known_hosts` only).
+    hosts: Vec<String>,
+    /// Whether the host names are hashed.
+    hashed_hosts: bool,
+    /// Type of the key (e.g. `ssh-ed25519`).
+    r#type: String,
+    /// Raw key blob.
+    blob: Vec<u8>,
+    /// Comment of the key.
+    comment: String,
+}
+
+/// Handles invocations of the `list_ssh_keys` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let users = ospect::user::users()
+        .map_err(crate::session::Error::action)?;
+
+    for user in users {
+        let user = match user {
+            Ok(user) => user,
+            Err(error) => {
+                warn!("failed to obtain user information: {}", error);
+                continue;
+            }
+        };
+
+        let Some(home_dir) = user.home_dir() else {
+            continue;
+        };
+
+        for item in user_items(&user.name().to_string_lossy(), home_dir) {
+            session.reply(item)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns items for all keys of the given user.
+fn user_items(user: &str, home_dir: &Path) -> Vec<Item> {
+    let mut items = Vec::new();
+
+    for (name, file_type) in FILES {
+        let path = home_dir.join(".ssh").join(name);
+
+        let content = match std::fs::read(&path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                warn!("failed to read '{}': {}", path.display(), error);
+                continue;
+            }
+        };
+
+        for (idx, line) in String::from_utf8_lossy(&content).lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let key = match file_type {
+                FileType::AuthorizedKeys => parse_authorized_key(line),
+                FileType::KnownHosts => parse_known_host(line),
+            };
+
+            let entry = match key {
+                Ok(key) => Entry::Key(key),
+                Err(error) => Entry::Malformed {
+                    error,
+                    content: String::from(line),
+                },
+            };
+
+            items.push(Item {
+                user: String::from(user),
+                path: path.clone(),
+                file_type: *file_type,
+                line: idx + 1,
+                entry,
+            });
+        }
+    }
+
+    items
+}
+
+/// Parses a (non-empty and non-comment) line of an `authorized_keys` file.
+///
+/// The line has the `[options] type blob [comment]` format.
+fn parse_authorized_key(line: &str) -> Result<Key, ParseError> {
+    let (options, rest) = if KEY_TYPE_PREFIXES.iter().any(|prefix| line.starts_with(prefix)) {
+        (Vec::new(), line)
+    } else {
+        split_options(line)?
+    };
+
+    let (r#type, blob, comment) = parse_key(rest)?;
+
+    Ok(Key {
+        options,
+        r#type,
+        blob,
+        comment,
+        ..Key::default()
+    })
+}
+
+/// Parses a (non-empty and non-comment) line of a `known_hosts` file.
+///
+/// The line has the `[marker] hosts type blob [comment]` format.
+fn parse_known_host(line: &str) -> Result<Key, ParseError> {
+    let (marker, rest) = match split_token(line) {
+        (marker @ ("@cert-authority" | "@revoked"), rest) => (Some(String::from(marker)), rest),
+        (marker, _) if marker.starts_with('@') => {
+            return Err(ParseError::InvalidMarker(String::from(marker)));
+        }
+        _ => (None, line),
+    };
+
+    let (hosts, rest) = split_token(rest);
+    if hosts.is_empty() {
+        return Err(ParseError::MissingHosts);
+    }
+
+    let (r#type, blob, comment) = parse_key(rest)?;
+
+    Ok(Key {
+        marker,
+        // Hashed entries have the `|1|salt|hash` format (with both the salt
+        // and the hash Base64-encoded).
+        hashed_hosts: hosts.starts_with('|'),
+        hosts: hosts.split(',').map(String::from).collect(),
+        r#type,
+        blob,
+        comment,
+        ..Key::default()
+    })
+}
+
+/// Parses the `type blob [comment]` part of a line.
+fn parse_key(line: &str) -> Result<(String, Vec<u8>, String), ParseError> {
+    let (r#type, rest) = split_token(line);
+    let (blob, comment) = split_token(rest);
+    if r#type.is_empty() || blob.is_empty() {
+        return Err(ParseError::MissingKey);
+    }
+
+    let blob = decode_base64(blob)
+        .ok_or(ParseError::InvalidBase64)?;
+
+    // The blob starts with the key type (as a length-prefixed string), which
+    // has to agree with the one given explicitly.
+    let blob_type = blob.get(..4)
+        .map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        .and_then(|len| blob.get(4..4 + len))
+        .ok_or(ParseError::InvalidBlob)?;
+    if blob_type != r#type.as_bytes() {
+        return Err(ParseError::TypeMismatch {
+            r#type: String::from(r#type),
+            blob_type: String::from_utf8_lossy(blob_type).into_owned(),
+        });
+    }
+
+    Ok((String::from(r#type), blob, String::from(comment.trim())))
+}
+
+/// Splits the first whitespace-separated token from the given string.
+fn split_token(string: &str) -> (&str, &str) {
+    let string = string.trim_start();
+    match string.find([' ', '\t']) {
+        Some(idx) => (&string[..idx], string[idx..].trim_start()),
+        None => (string, ""),
+    }
+}
+
+/// Splits the comma-separated options from the given `authorized_keys` line.
+///
+/// Options can have quoted values (e.g. `command="echo foo"`) in which case
+/// whitespace and commas do not end them.
+fn split_options(line: &str) -> Result<(Vec<String>, &str), ParseError> {
+    let mut options = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut chars = line.char_indices();
+
+    let end = loop {
+        match chars.next() {
+            Some((_, '\\')) if quoted => {
+                chars.next();
+            }
+            Some((_, '"')) => quoted = !quoted,
+            Some((idx, ',')) if !quoted => {
+                options.push(String::from(&line[start..idx]));
+                start = idx + 1;
+            }
+            Some((idx, ' ' | '\t')) if !quoted => break idx,
+            Some(_) => (),
+            None if quoted => return Err(ParseError::UnterminatedQuote),
+            None => break line.len(),
+        }
+    };
+    options.push(String::from(&line[start..end]));
+
+    Ok((options, &line[end..]))
+}
+
+/// Decodes standard Base64 (with optional padding).
+fn decode_base64(string: &str) -> Option<Vec<u8>> {
+    fn sextet(byte: u8) -> Option<u32> {
+        match byte {
+            b'A'..=b'Z' => Some(u32::from(byte - b'A')),
+            b'a'..=b'z' => Some(u32::from(byte - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(byte - b'0') + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let data = string.trim_end_matches('=').as_bytes();
+    if string.len() - data.len() > 2 || data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut acc = 0;
+        for byte in chunk {
+            acc = acc << 6 | sextet(*byte)?;
+        }
+        acc <<= 6 * (4 - chunk.len() as u32);
+
+        let decoded = acc.to_be_bytes();
+        result.extend_from_slice(&decoded[1..chunk.len()]);
+    }
+
+    Some(result)
+}
+
+/// Encodes the given data as standard Base64 without padding.
+///
+/// This is the encoding that OpenSSH uses for fingerprints.
+fn encode_base64_unpadded(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut acc = [0; 4];
+        acc[1..1 + chunk.len()].copy_from_slice(chunk);
+        let acc = u32::from_be_bytes(acc);
+
+        for idx in 0..chunk.len() + 1 {
+            let sextet = (acc >> (18 - 6 * idx)) & 0x3F;
+            result.push(char::from(ALPHABET[sextet as usize]));
+        }
+    }
+
+    result
+}
+
+/// Returns the SHA-256 fingerprint of the given key blob in the OpenSSH format.
+fn sha256_fingerprint(blob: &[u8]) -> String {
+    use sha2::Digest as _;
+
+    format!("SHA256:{}", encode_base64_unpadded(&sha2::Sha256::digest(blob)))
+}
+
+/// Error that can occur when parsing a line of a file with keys.
+#[derive(Debug, PartialEq, Eq)]
+enum ParseError {
+    /// The options contain a quote that is not closed.
+    UnterminatedQuote,
+    /// The line has an unknown marker.
+    InvalidMarker(String),
+    /// The line has no host patterns.
+    MissingHosts,
+    /// The line has no key type or blob.
+    MissingKey,
+    /// The key blob is not valid Base64.
+    InvalidBase64,
+    /// The key blob does not start with the key type.
+    InvalidBlob,
+    /// The key type does not agree with the one in the key blob.
+    TypeMismatch {
+        /// Key type given explicitly.
+        r#type: String,
+        /// Key type in the key blob.
+        blob_type: String,
+    },
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ParseError::*;
+
+        match self {
+            UnterminatedQuote => write!(fmt, "unterminated quote in options"),
+            InvalidMarker(marker) => write!(fmt, "invalid marker '{marker}'"),
+            MissingHosts => write!(fmt, "missing host patterns"),
+            MissingKey => write!(fmt, "missing key type or blob"),
+            InvalidBase64 => write!(fmt, "key blob is not valid Base64"),
+            InvalidBlob => write!(fmt, "key blob is truncated"),
+            TypeMismatch { r#type, blob_type } => {
+                write!(fmt, "key type '{type}' does not match blob type '{blob_type}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_ssh_keys::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_ssh_keys::Result::new();
+        proto.set_user(self.user);
+        proto.set_path(crate::path::into_proto(self.path));
+        proto.set_file_type(self.file_type.into());
+        proto.set_line(self.line as u32);
+
+        match self.entry {
+            Entry::Key(key) => {
+                let key_proto = proto.mut_key();
+                key_proto.set_options(key.options);
+                if let Some(marker) = key.marker {
+                    key_proto.set_marker(marker);
+                }
+                key_proto.set_hosts(key.hosts);
+                key_proto.set_hashed_hosts(key.hashed_hosts);
+                key_proto.set_type(key.r#type);
+                key_proto.set_sha256_fingerprint(sha256_fingerprint(&key.blob));
+                key_proto.set_blob(key.blob);
+                key_proto.set_comment(key.comment);
+            }
+            Entry::Malformed { error, content } => {
+                let malformed_proto = proto.mut_malformed_line();
+                malformed_proto.set_error(error.to_string());
+                malformed_proto.set_content(content);
+            }
+        }
+
+        proto
+    }
+}
+
+impl From<FileType> for rrg_proto::list_ssh_keys::FileType {
+
+    fn from(file_type: FileType) -> rrg_proto::list_ssh_keys::FileType {
+        use rrg_proto::list_ssh_keys::FileType as Proto;
+
+        match file_type {
+            FileType::AuthorizedKeys => Proto::AUTHORIZED_KEYS,
+            FileType::KnownHosts => Proto::KNOWN_HOSTS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const ED25519: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIFGVM+O287vlsxOCrgDU0Y1cIlKn/lsQyQCsB/FrKcUX";
+    const ED25519_FINGERPRINT: &str = "SHA256:nB4x0q9v/1Bn0zo7oip86hab4SLp/MOpHjvNfWqICVg";
+
+    const ECDSA: &str = "ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBOoEYdFez449P1NJsEbRn21LvUPNHirtScwCofqMdiOM3g6/Pf2UQzRmStr+RJZ8tAeDtvNk1PLK5mcT7m4iUIM=";
+    const ECDSA_FINGERPRINT: &str = "SHA256:p5GHF01NrS/gMDf8yqchbZUygSQ3wI1qHbLX/BvdCIU";
+
+    #[test]
+    fn handle_ok() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+    }
+
+    #[test]
+    fn parse_authorized_key_simple() {
+        let key = parse_authorized_key(&format!("{ED25519} alice@laptop"))
+            .unwrap();
+
+        assert!(key.options.is_empty());
+        assert_eq!(key.r#type, "ssh-ed25519");
+        assert_eq!(key.comment, "alice@laptop");
+        assert_eq!(sha256_fingerprint(&key.blob), ED25519_FINGERPRINT);
+    }
+
+    #[test]
+    fn parse_authorized_key_no_comment() {
+        let key = parse_authorized_key(ECDSA)
+            .unwrap();
+
+        assert_eq!(key.r#type, "ecdsa-sha2-nistp256");
+        assert_eq!(key.comment, "");
+        assert_eq!(sha256_fingerprint(&key.blob), ECDSA_FINGERPRINT);
+    }
+
+    #[test]
+    fn parse_authorized_key_options() {
+        let line = format!("from=\"10.0.0.0/8,192.168.1.1\",command=\"/bin/echo \\\"hi there\\\"\",no-pty {ED25519} backup key");
+        let key = parse_authorized_key(&line)
+            .unwrap();
+
+        assert_eq!(key.options, vec![
+            String::from("from=\"10.0.0.0/8,192.168.1.1\""),
+            String::from("command=\"/bin/echo \\\"hi there\\\"\""),
+            String::from("no-pty"),
+        ]);
+        assert_eq!(key.r#type, "ssh-ed25519");
+        assert_eq!(key.comment, "backup key");
+    }
+
+    #[test]
+    fn parse_authorized_key_unterminated_quote() {
+        let line = format!("command=\"/bin/true {ED25519}");
+        assert_eq!(parse_authorized_key(&line), Err(ParseError::UnterminatedQuote));
+    }
+
+    #[test]
+    fn parse_authorized_key_missing_blob() {
+        assert_eq!(parse_authorized_key("ssh-ed25519"), Err(ParseError::MissingKey));
+        assert_eq!(parse_authorized_key("no-pty ssh-ed25519"), Err(ParseError::MissingKey));
+    }
+
+    #[test]
+    fn parse_authorized_key_invalid_base64() {
+        assert_eq!(parse_authorized_key("ssh-ed25519 AAAA$$$$"), Err(ParseError::InvalidBase64));
+    }
+
+    #[test]
+    fn parse_authorized_key_type_mismatch() {
+        let blob = ED25519.split_once(' ').unwrap().1;
+        assert_eq! {
+            parse_authorized_key(&format!("ssh-rsa {blob}")),
+            Err(ParseError::TypeMismatch {
+                r#type: String::from("ssh-rsa"),
+                blob_type: String::from("ssh-ed25519"),
+            })
+        };
+    }
+
+    #[test]
+    fn parse_known_host_plain() {
+        let key = parse_known_host(&format!("github.com,140.82.121.4 {ECDSA}"))
+            .unwrap();
+
+        assert_eq!(key.marker, None);
+        assert_eq!(key.hosts, vec!["github.com", "140.82.121.4"]);
+        assert!(!key.hashed_hosts);
+        assert_eq!(key.r#type, "ecdsa-sha2-nistp256");
+    }
+
+    #[test]
+    fn parse_known_host_hashed() {
+        let line = format!("|1|exyaX5eCv488fYM1AIQg0HPwDfw=|ml9E4qHYoIk3IxPImFWyqlDthwg= {ECDSA}");
+        let key = parse_known_host(&line)
+            .unwrap();
+
+        assert_eq!(key.hosts, vec!["|1|exyaX5eCv488fYM1AIQg0HPwDfw=|ml9E4qHYoIk3IxPImFWyqlDthwg="]);
+        assert!(key.hashed_hosts);
+        assert_eq!(sha256_fingerprint(&key.blob), ECDSA_FINGERPRINT);
+    }
+
+    #[test]
+    fn parse_known_host_marker() {
+        let key = parse_known_host(&format!("@cert-authority *.example.com {ED25519} ca"))
+            .unwrap();
+
+        assert_eq!(key.marker.as_deref(), Some("@cert-authority"));
+        assert_eq!(key.hosts, vec!["*.example.com"]);
+        assert_eq!(key.comment, "ca");
+
+        assert_eq! {
+            parse_known_host(&format!("@trusted *.example.com {ED25519}")),
+            Err(ParseError::InvalidMarker(String::from("@trusted")))
+        };
+    }
+
+    #[test]
+    fn parse_known_host_missing_key() {
+        assert_eq!(parse_known_host("example.com"), Err(ParseError::MissingKey));
+    }
+
+    #[test]
+    fn base64_roundtrip() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"] {
+            let encoded = encode_base64_unpadded(data);
+            assert!(!encoded.contains('='));
+            assert_eq!(decode_base64(&encoded).as_deref(), Some(data));
+        }
+
+        assert_eq!(encode_base64_unpadded(b"foobar"), "Zm9vYmFy");
+        assert_eq!(decode_base64("Zm9vYg=="), Some(b"foob".to_vec()));
+        assert_eq!(decode_base64("Zm9vYg==="), None);
+        assert_eq!(decode_base64("Zm9vY"), None);
+    }
+
+    #[test]
+    fn user_items_homedir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let ssh_dir = tempdir.path().join(".ssh");
+        std::fs::create_dir(&ssh_dir)
+            .unwrap();
+
+        std::fs::write(ssh_dir.join("authorized_keys"), format!("\
+# Keys allowed to log in.
+{ED25519} alice@laptop
+
+restrict,command=\"/usr/local/bin/backup\" {ECDSA} backup
+ssh-ed25519 this-is-not-a-key
+").as_bytes()).unwrap();
+
+        std::fs::write(ssh_dir.join("authorized_keys2"), format!("\
+{ECDSA} legacy
+").as_bytes()).unwrap();
+
+        std::fs::write(ssh_dir.join("known_hosts"), format!("\
+|1|exyaX5eCv488fYM1AIQg0HPwDfw=|ml9E4qHYoIk3IxPImFWyqlDthwg= {ECDSA}
+@revoked
+").as_bytes()).unwrap();
+
+        let items = user_items("alice", tempdir.path());
+        assert_eq!(items.len(), 6);
+
+        assert!(items.iter().all(|item| item.user == "alice"));
+
+        assert_eq!(items[0].path, ssh_dir.join("authorized_keys"));
+        assert_eq!(items[0].file_type, FileType::AuthorizedKeys);
+        assert_eq!(items[0].line, 2);
+        assert!(matches!(&items[0].entry, Entry::Key(key) if key.comment == "alice@laptop"));
+
+        assert_eq!(items[1].line, 4);
+        assert!(matches!(&items[1].entry, Entry::Key(key) if key.options.len() == 2));
+
+        assert_eq!(items[2].line, 5);
+        assert_eq!(items[2].entry, Entry::Malformed {
+            error: ParseError::InvalidBase64,
+            content: String::from("ssh-ed25519 this-is-not-a-key"),
+        });
+
+        assert_eq!(items[3].path, ssh_dir.join("authorized_keys2"));
+        assert_eq!(items[3].file_type, FileType::AuthorizedKeys);
+        assert!(matches!(&items[3].entry, Entry::Key(key) if key.comment == "legacy"));
+
+        assert_eq!(items[4].path, ssh_dir.join("known_hosts"));
+        assert_eq!(items[4].file_type, FileType::KnownHosts);
+        assert!(matches!(&items[4].entry, Entry::Key(key) if key.hashed_hosts));
+
+        assert_eq!(items[5].line, 2);
+        assert!(matches!(&items[5].entry, Entry::Malformed { error: ParseError::MissingHosts, .. }));
+    }
+
+    #[test]
+    fn user_items_no_ssh_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(user_items("bob", tempdir.path()).is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use log::warn;
+
+/// A result of the `list_users` action.
+struct Item {
+    /// Information about the individual user account.
+    user: ospect::user::User,
+    /// Time at which the user logged in for the last time (if known).
+    last_login_time: Option<std::time::SystemTime>,
+}
+
+/// Handles invocations of the `list_users` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let users = ospect::user::users()
+        .map_err(crate::session::Error::action)?;
+
+    #[cfg(target_os = "linux")]
+    let last_login_times = match crate::utmp::open(WTMP_PATH, None) {
+        Ok(records) => match last_login_times(records) {
+            Ok(last_login_times) => last_login_times,
+            Err(error) => {
+                warn!("failed to parse login records: {}", error);
+                std::collections::HashMap::new()
+            }
+        },
+        Err(error) => {
+            warn!("failed to open login records: {}", error);
+            std::collections::HashMap::new()
+        }
+    };
+
+    for user in users {
+        let user = match user {
+            Ok(user) => user,
+            Err(error) => {
+                warn!("failed to obtain user information: {}", error);
+                continue;
+            }
+        };
+
+        #[cfg(target_os = "linux")]
+        let last_login_time = last_login_times.get(user.name()).copied();
+
+        #[cfg(target_os = "windows")]
+        let last_login_time = user.last_login_time();
+
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        let last_login_time = None;
+
+        session.reply(Item {
+            user,
+            last_login_time,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Path to the file with the history of user logins.
+#[cfg(target_os = "linux")]
+const WTMP_PATH: &str = "/var/log/wtmp";
+
+/// Collects times of the most recent login of each user from `utmp` records.
+#[cfg(target_os = "linux")]
+fn last_login_times<I>(
+    records: I,
+) -> std::io::Result<std::collections::HashMap<std::ffi::OsString, std::time::SystemTime>>
+where
+    I: Iterator<Item = std::io::Result<crate::utmp::Record>>,
+{
+    let mut result = std::collections::HashMap::new();
+
+    for record in records {
+        let record = record?;
+
+        if record.r#type != crate::utmp::RecordType::UserProcess {
+            continue;
+        }
+        if record.user.is_empty() {
+            continue;
+        }
+
+        match result.get_mut(&record.user) {
+            Some(last_time) if *last_time >= record.time => (),
+            Some(last_time) => *last_time = record.time,
+            None => {
+                result.insert(record.user, record.time);
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_users::Result;
+
+    fn into_proto(self) -> rrg_proto::list_users::Result {
+        let mut proto = rrg_proto::list_users::Result::default();
+        proto.set_username(self.user.name().to_string_lossy().into_owned());
+
+        if let Some(full_name) = self.user.full_name() {
+            proto.set_full_name(full_name.to_string_lossy().into_owned());
+        }
+        if let Some(home_dir) = self.user.home_dir() {
+            proto.set_home_dir(home_dir.to_path_buf().into());
+        }
+
+        #[cfg(target_family = "unix")]
+        {
+            proto.set_uid(self.user.uid());
+            proto.set_gid(self.user.gid());
+            if let Some(shell) = self.user.shell() {
+                proto.set_shell(shell.to_path_buf().into());
+            }
+        }
+
+        #[cfg(target_family = "windows")]
+        if let Some(sid) = self.user.sid() {
+            proto.set_sid(sid.to_string());
+        }
+
+        if let Some(last_login_time) = self.last_login_time {
+            proto.set_last_login_time(rrg_proto::into_timestamp(last_login_time));
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_some_user() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        assert!(session.reply_count() > 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_current_user() {
+        // SAFETY: `getuid` is always safe to call.
+        let uid = unsafe { libc::getuid() };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>().find(|item| {
+            item.user.uid() == uid
+        });
+
+        // The current user does not need to have a database entry (e.g. when
+        // running in a container with an arbitrary uid).
+        let item = match item {
+            Some(item) => item,
+            None => return,
+        };
+
+        if let Some(home_dir) = std::env::var_os("HOME") {
+            assert_eq!(item.user.home_dir(), Some(std::path::Path::new(&home_dir)));
+        }
+    }
+
+    /// Serializes the given `utmp` record to bytes.
+    #[cfg(target_os = "linux")]
+    fn utmp_record(r#type: libc::c_short, user: &str, secs: i64) -> Vec<u8> {
+        // SAFETY: `utmpx` is a plain C struct for which all zeros are valid.
+        let mut record = unsafe {
+            std::mem::zeroed::<libc::utmpx>()
+        };
+        record.ut_type = r#type;
+        record.ut_tv.tv_sec = secs as _;
+        for (dest, src) in record.ut_user.iter_mut().zip(user.bytes()) {
+            *dest = src as libc::c_char;
+        }
+
+        // SAFETY: The record is a properly initialized plain C struct, so it
+        // is safe to view it as bytes.
+        unsafe {
+            std::slice::from_raw_parts(
+                (&record as *const libc::utmpx).cast::<u8>(),
+                std::mem::size_of::<libc::utmpx>(),
+            )
+        }.to_vec()
+    }
+
+    /// Parses `utmp` records from the given bytes in the native layout.
+    #[cfg(target_os = "linux")]
+    fn utmp_records(data: &[u8]) -> crate::utmp::Records<&[u8]> {
+        crate::utmp::Records::new(data, crate::utmp::Layout::native())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn last_login_times_empty() {
+        let times = last_login_times(utmp_records(b""))
+            .unwrap();
+
+        assert!(times.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn last_login_times_latest() {
+        let mut data = Vec::new();
+        data.extend(utmp_record(libc::USER_PROCESS, "foo", 1_000));
+        data.extend(utmp_record(libc::USER_PROCESS, "bar", 2_000));
+        data.extend(utmp_record(libc::USER_PROCESS, "foo", 3_000));
+        data.extend(utmp_record(libc::USER_PROCESS, "bar", 1_500));
+
+        let times = last_login_times(utmp_records(&data))
+            .unwrap();
+
+        assert_eq! {
+            times[std::ffi::OsStr::new("foo")],
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(3_000)
+        };
+        assert_eq! {
+            times[std::ffi::OsStr::new("bar")],
+            std::time::UNIX_EPOCH + std::time::Duration::from_secs(2_000)
+        };
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn last_login_times_non_user_process() {
+        let mut data = Vec::new();
+        data.extend(utmp_record(libc::BOOT_TIME, "reboot", 1_000));
+        data.extend(utmp_record(libc::DEAD_PROCESS, "foo", 1_000));
+
+        let times = last_login_times(utmp_records(&data))
+            .unwrap();
+
+        assert!(times.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn last_login_times_truncated() {
+        let mut data = Vec::new();
+        data.extend(utmp_record(libc::USER_PROCESS, "foo", 1_000));
+
+        let truncated = utmp_record(libc::USER_PROCESS, "bar", 2_000);
+        data.extend(&truncated[..truncated.len() / 2]);
+
+        let times = last_login_times(utmp_records(&data))
+            .unwrap();
+
+        assert_eq!(times.len(), 1);
+        assert!(times.contains_key(std::ffi::OsStr::new("foo")));
+    }
+}
@@ -25,57 +25,20 @@ pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
-    let mut file = std::fs::File::open(&args.path)
+    let records = crate::utmp::open(&args.path, None)
         .map_err(crate::session::Error::action)?;
 
     let mut usernames = std::collections::HashSet::new();
 
-    loop {
-        use std::io::Read as _;
-
-        let mut buf = [0u8; std::mem::size_of::<libc::utmpx>()];
-        match file.read_exact(&mut buf) {
-            Ok(()) => (),
-            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
-                break;
-            }
-            Err(error) => {
-                return Err(crate::session::Error::action(error));
-            }
-        }
-
-        const TYPE_OFFSET: usize = std::mem::offset_of!(libc::utmpx, ut_type);
-        const TYPE_SIZE: usize = std::mem::size_of::<libc::c_short>();
-
-        let type_bytes = <_>::try_from(&buf[TYPE_OFFSET..TYPE_OFFSET + TYPE_SIZE])
-            .expect("invalid type of utmp");
-        // According to [1], `utmp` uses litle-endian byte order. However, it
-        // might be the case that it actually uses native-endian. This should be
-        // verified and adjusted if needed.
-        //
-        // [1]: https://github.com/libyal/dtformats/blob/main/documentation/Utmp%20login%20records%20format.asciidoc
-        let r#type = libc::c_short::from_le_bytes(type_bytes);
+    for record in records {
+        let record = record
+            .map_err(crate::session::Error::action)?;
 
-        if r#type != libc::USER_PROCESS {
+        if record.r#type != crate::utmp::RecordType::UserProcess {
             continue;
         }
 
-        const USER_OFFSET: usize = std::mem::offset_of!(libc::utmpx, ut_user);
-
-        let user = std::ffi::CStr::from_bytes_until_nul(&buf[USER_OFFSET..])
-            .map_err(|error| {
-                std::io::Error::new(std::io::ErrorKind::InvalidData, error)
-            })
-            .map_err(crate::session::Error::action)?;
-
-        use std::os::unix::ffi::OsStrExt as _;
-        let user = std::ffi::OsStr::from_bytes(user.to_bytes());
-
-        // TODO: https://github.com/rust-lang/rust/issues/60896 - Refactor once
-        //``hash_set_entry` is stabilized.
-        if !usernames.contains(user) {
-            usernames.insert(user.to_owned());
-        }
+        usernames.insert(record.user);
     }
 
     for username in usernames {
@@ -104,7 +67,7 @@ impl crate::request::Args for Args {
     fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
         use crate::request::ParseArgsError;
 
-        let path = std::path::PathBuf::try_from(proto.take_path())
+        let path = crate::path::from_proto(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
         Ok(Args {
@@ -135,10 +98,9 @@ mod tests {
     use super::*;
 
     #[test]
-    // Looks like `utmp` entries can have different size depending on the
-    // platform (e.g. on `aarch64` it has a size of 400 bytes) and so `x86_64`
-    // samples will not work there.
-    #[cfg_attr(not(target_arch = "x86_64"), ignore)]
+    // The samples are in the little-endian byte order (the layout is detected
+    // from the file size, so they work regardless of the native layout).
+    #[cfg_attr(target_endian = "big", ignore)]
     fn handle_custom_utmp_file() {
         use std::io::Write as _;
 
@@ -0,0 +1,598 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Default maximum number of bytes of binary data to return for each value.
+#[cfg(target_family = "windows")]
+const DEFAULT_MAX_BYTES_LEN: usize = 64 * 1024;
+
+/// Arguments of the `list_winreg` action.
+#[cfg(target_family = "windows")]
+pub struct Args {
+    /// Root predefined key of the key to list.
+    root: winreg::PredefinedKey,
+    /// Key relative to `root` to list.
+    key: std::ffi::OsString,
+    /// View of the registry to list the key in.
+    view: winreg::View,
+    /// Limit on the depth of recursion when visiting subkeys.
+    max_depth: u32,
+    /// Maximum number of bytes of binary data to return for each value.
+    max_bytes_len: usize,
+}
+
+/// A result of the `list_winreg` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct Item {
+    /// Root predefined key of the listed entry.
+    root: winreg::PredefinedKey,
+    /// Key relative to `root` that was listed.
+    key: std::ffi::OsString,
+    /// Subkey relative to `root` and `key` of the listed entry.
+    subkey: std::ffi::OsString,
+    /// Listed entry.
+    entry: Entry,
+}
+
+/// An entry listed by the `list_winreg` action.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+enum Entry {
+    /// Subkey of the listed key.
+    Subkey {
+        /// Time at which the subkey (or any of its values) was last modified.
+        last_write_time: std::time::SystemTime,
+        /// Class of the subkey.
+        class: std::ffi::OsString,
+    },
+    /// Value of the listed key or one of its subkeys.
+    Value {
+        /// Listed value.
+        value: winreg::Value,
+        /// Whether binary data of the value was truncated.
+        truncated: bool,
+    },
+}
+
+/// Handles invocations of the `list_winreg` action.
+#[cfg(target_family = "windows")]
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    // The default value of 0 is treated as 1 (see the documentation of the
+    // `max_depth` field in the Protocol Buffers definition).
+    let max_depth = std::cmp::max(args.max_depth, 1);
+
+    /// Single "stack frame" of the recursive listing procedure.
+    struct PendingKey {
+        /// Name of the key relative to the key from which we started.
+        key_rel_name: std::ffi::OsString,
+        /// Current depth of the recursive walk.
+        depth: u32,
+    }
+
+    // `pending_keys` represents our recursion stack. Unlike the other registry
+    // actions, we keep only names of the keys on the stack (rather than open
+    // handles) and open them only once they are visited. This way walking very
+    // wide keys does not require us to keep thousands of handles open.
+    let mut pending_keys = Vec::new();
+    pending_keys.push(PendingKey {
+        key_rel_name: std::ffi::OsString::new(),
+        depth: 0,
+    });
+
+    while let Some(PendingKey { key_rel_name, depth }) = pending_keys.pop() {
+        let key_name = winreg::path::join(&args.key, &key_rel_name);
+
+        let key = match args.root.open_with_view(&key_name, args.view) {
+            Ok(key) => key,
+            // We fail only if the key that we start with cannot be opened. In
+            // the other cases the subkey might have been deleted in the mean
+            // time or we might not have access to it, so we just carry on.
+            Err(error) if depth == 0 => {
+                return Err(crate::session::Error::action(error));
+            }
+            Err(error) => {
+                log::error!("failed to open subkey {key_name:?}: {error}");
+                continue;
+            }
+        };
+
+        let key_info = match key.info() {
+            Ok(key_info) => key_info,
+            Err(error) if depth == 0 => {
+                return Err(crate::session::Error::action(error));
+            }
+            Err(error) => {
+                log::error!("failed to query info of subkey {key_name:?}: {error}");
+                continue;
+            }
+        };
+
+        if depth > 0 {
+            session.reply(Item {
+                root: args.root,
+                key: args.key.clone(),
+                subkey: key_rel_name.clone(),
+                entry: Entry::Subkey {
+                    last_write_time: key_info.last_write_time(),
+                    class: key_info.class().to_os_string(),
+                },
+            })?;
+        }
+
+        for value in key_info.values() {
+            let mut value = match value {
+                Ok(value) => value,
+                Err(error) => {
+                    log::error!("failed to list value of key {key_name:?}: {error}");
+                    continue;
+                }
+            };
+
+            let mut truncated = false;
+            if let winreg::ValueData::Bytes(bytes) = &mut value.data {
+                if bytes.len() > args.max_bytes_len {
+                    bytes.truncate(args.max_bytes_len);
+                    truncated = true;
+                }
+            }
+
+            session.reply(Item {
+                root: args.root,
+                key: args.key.clone(),
+                subkey: key_rel_name.clone(),
+                entry: Entry::Value {
+                    value,
+                    truncated,
+                },
+            })?;
+        }
+
+        if depth < max_depth {
+            for subkey_name in key_info.subkeys() {
+                let subkey_name = match subkey_name {
+                    Ok(subkey_name) => subkey_name,
+                    Err(error) => {
+                        log::error!("failed to list subkey of key {key_name:?}: {error}");
+                        continue;
+                    }
+                };
+
+                pending_keys.push(PendingKey {
+                    key_rel_name: winreg::path::join(&key_rel_name, &subkey_name),
+                    depth: depth + 1,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles invocations of the `list_winreg` action.
+#[cfg(target_family = "unix")]
+pub fn handle<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Error, ErrorKind};
+    Err(crate::session::Error::action(Error::from(ErrorKind::Unsupported)))
+}
+
+#[cfg(target_family = "windows")]
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_winreg::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        let root = match proto.root.enum_value() {
+            Ok(root) => winreg::PredefinedKey::try_from(root),
+            Err(value) => Err(rrg_proto::ParseWinregPredefinedKeyError { value }),
+        }.map_err(|error| {
+            crate::request::ParseArgsError::invalid_field("root", error)
+        })?;
+
+        let view = match proto.view.enum_value() {
+            Ok(view) => winreg::View::from(view),
+            Err(value) => {
+                return Err(crate::request::ParseArgsError::invalid_field("view", ViewError {
+                    value,
+                }));
+            }
+        };
+
+        let max_bytes_len = match proto.max_bytes_len() {
+            0 => DEFAULT_MAX_BYTES_LEN,
+            len => usize::try_from(len).unwrap_or(usize::MAX),
+        };
+
+        Ok(Args {
+            root,
+            key: std::ffi::OsString::from(proto.take_key()),
+            view,
+            max_depth: proto.max_depth(),
+            max_bytes_len,
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_winreg::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_winreg::Result::new();
+        proto.set_root(self.root.into());
+        proto.set_key(self.key.to_string_lossy().into_owned());
+        proto.set_subkey(self.subkey.to_string_lossy().into_owned());
+
+        match self.entry {
+            Entry::Subkey { last_write_time, class } => {
+                let subkey_info = proto.mut_subkey_info();
+                subkey_info.set_last_write_time(rrg_proto::into_timestamp(last_write_time));
+                subkey_info.set_class(class.to_string_lossy().into_owned());
+            }
+            Entry::Value { value, truncated } => {
+                proto.set_value(value.into());
+                proto.set_truncated(truncated);
+            }
+        }
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an unknown view.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct ViewError {
+    /// Raw value of the view.
+    value: i32,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for ViewError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown registry view: {}", self.value)
+    }
+}
+
+#[cfg(target_family = "windows")]
+impl std::error::Error for ViewError {
+}
+
+#[cfg(test)]
+#[cfg(target_family = "windows")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_non_existent() {
+        let args = Args {
+            root: winreg::PredefinedKey::CurrentUser,
+            key: std::ffi::OsString::from("Software\\rrg-test\\non-existent"),
+            view: winreg::View::Default,
+            max_depth: 0,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_tree() {
+        use windows_sys::Win32::System::Registry::*;
+
+        let key = TestKey::new("list_winreg_handle_tree");
+        key.set("", "foo", REG_SZ, &wide("bar"));
+        key.create("a", "");
+        key.set("a", "quux", REG_DWORD, &42_u32.to_le_bytes());
+        key.create("a\\b", "");
+        key.set("a\\b", "norf", REG_BINARY, b"\x00\x01\x02");
+        key.create("c", "rrg-class");
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args(2)).is_ok());
+
+        let mut subkeys = session.replies::<Item>()
+            .filter_map(|item| match &item.entry {
+                Entry::Subkey { class, .. } => Some((item.subkey.clone(), class.clone())),
+                Entry::Value { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        subkeys.sort();
+
+        assert_eq!(subkeys, vec![
+            ("a".into(), "".into()),
+            ("a\\b".into(), "".into()),
+            ("c".into(), "rrg-class".into()),
+        ]);
+
+        let mut values = session.replies::<Item>()
+            .filter_map(|item| match &item.entry {
+                Entry::Value { value, .. } => Some((item.subkey.clone(), value.clone())),
+                Entry::Subkey { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        values.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        assert_eq!(values, vec![
+            ("".into(), winreg::Value {
+                name: "foo".into(),
+                data: winreg::ValueData::String("bar".into()),
+            }),
+            ("a".into(), winreg::Value {
+                name: "quux".into(),
+                data: winreg::ValueData::U32(42),
+            }),
+            ("a\\b".into(), winreg::Value {
+                name: "norf".into(),
+                data: winreg::ValueData::Bytes(b"\x00\x01\x02".to_vec()),
+            }),
+        ]);
+
+        for item in session.replies::<Item>() {
+            assert_eq!(item.root, winreg::PredefinedKey::CurrentUser);
+            assert_eq!(item.key, key.path);
+        }
+    }
+
+    #[test]
+    fn handle_subkey_last_write_time() {
+        let key = TestKey::new("list_winreg_handle_subkey_last_write_time");
+        key.create("a", "");
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args(1)).is_ok());
+
+        let item = session.reply::<Item>(0);
+        let Entry::Subkey { last_write_time, .. } = item.entry else {
+            panic!("unexpected entry: {:?}", item.entry);
+        };
+
+        let elapsed = last_write_time.elapsed().unwrap();
+        assert!(elapsed < std::time::Duration::from_secs(60 * 60));
+    }
+
+    #[test]
+    fn handle_max_depth() {
+        let key = TestKey::new("list_winreg_handle_max_depth");
+        key.create("a", "");
+        key.create("a\\b", "");
+        key.create("a\\b\\c", "");
+
+        let subkeys = |max_depth| {
+            let mut session = crate::session::FakeSession::new();
+            assert!(handle(&mut session, key.args(max_depth)).is_ok());
+
+            let mut subkeys = session.replies::<Item>()
+                .map(|item| item.subkey.clone())
+                .collect::<Vec<_>>();
+            subkeys.sort();
+            subkeys
+        };
+
+        assert_eq!(subkeys(0), vec!["a"]);
+        assert_eq!(subkeys(1), vec!["a"]);
+        assert_eq!(subkeys(2), vec!["a", "a\\b"]);
+        assert_eq!(subkeys(3), vec!["a", "a\\b", "a\\b\\c"]);
+        assert_eq!(subkeys(4), vec!["a", "a\\b", "a\\b\\c"]);
+    }
+
+    #[test]
+    fn handle_max_depth_values() {
+        use windows_sys::Win32::System::Registry::*;
+
+        let key = TestKey::new("list_winreg_handle_max_depth_values");
+        key.create("a", "");
+        key.set("a", "foo", REG_DWORD, &1_u32.to_le_bytes());
+        key.create("a\\b", "");
+        key.set("a\\b", "bar", REG_DWORD, &2_u32.to_le_bytes());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, key.args(1)).is_ok());
+
+        let values = session.replies::<Item>()
+            .filter_map(|item| match &item.entry {
+                Entry::Value { value, .. } => Some(value.name.clone()),
+                Entry::Subkey { .. } => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(values, vec!["foo"]);
+    }
+
+    #[test]
+    fn handle_bytes_truncated() {
+        use windows_sys::Win32::System::Registry::REG_BINARY;
+
+        let key = TestKey::new("list_winreg_handle_bytes_truncated");
+        key.set("", "foo", REG_BINARY, b"\x00\x01\x02\x03");
+        key.set("", "bar", REG_BINARY, b"\x00\x01");
+
+        let args = Args {
+            max_bytes_len: 2,
+            ..key.args(1)
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let mut values = session.replies::<Item>()
+            .filter_map(|item| match &item.entry {
+                Entry::Value { value, truncated } => {
+                    Some((value.name.clone(), value.data.clone(), *truncated))
+                }
+                Entry::Subkey { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        values.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+        assert_eq!(values, vec![
+            ("bar".into(), winreg::ValueData::Bytes(b"\x00\x01".to_vec()), false),
+            ("foo".into(), winreg::ValueData::Bytes(b"\x00\x01".to_vec()), true),
+        ]);
+    }
+
+    #[test]
+    fn handle_view() {
+        let args = Args {
+            root: winreg::PredefinedKey::LocalMachine,
+            key: std::ffi::OsString::from("SOFTWARE\\Microsoft\\Windows\\CurrentVersion"),
+            view: winreg::View::Registry32,
+            max_depth: 1,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let program_files = session.replies::<Item>()
+            .find_map(|item| match &item.entry {
+                Entry::Value { value, .. } if item.subkey.is_empty() && value.name == "ProgramFilesDir" => {
+                    Some(value.data.clone())
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        let winreg::ValueData::String(program_files) = program_files else {
+            panic!("unexpected data: {program_files:?}");
+        };
+        // We assume that the tests are not run on 32-bit systems anymore, so
+        // the 32-bit view should point to the folder for 32-bit programs.
+        assert!(program_files.to_string_lossy().ends_with("(x86)"));
+    }
+
+    /// Encodes the given string as null-terminated UTF-16 (as bytes).
+    fn wide(string: &str) -> Vec<u8> {
+        string.encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    /// Temporary key under `HKEY_CURRENT_USER\Software\rrg-test`.
+    ///
+    /// The key is deleted (along with its subkeys and values) when dropped.
+    struct TestKey {
+        /// Path to the key relative to `HKEY_CURRENT_USER`.
+        path: std::ffi::OsString,
+    }
+
+    impl TestKey {
+
+        /// Creates a new test key with the given name.
+        fn new(name: &str) -> TestKey {
+            let path = std::ffi::OsString::from(format!("Software\\rrg-test\\{name}"));
+            create_key(&path, "");
+
+            TestKey {
+                path,
+            }
+        }
+
+        /// Creates a subkey with the given path and class.
+        fn create(&self, subkey: &str, class: &str) {
+            create_key(&winreg::path::join(self.path.as_os_str(), std::ffi::OsStr::new(subkey)), class);
+        }
+
+        /// Sets the value with the given name of the given subkey.
+        fn set(
+            &self,
+            subkey: &str,
+            name: &str,
+            data_type: windows_sys::Win32::System::Registry::REG_VALUE_TYPE,
+            data: &[u8],
+        ) {
+            let path = winreg::path::join(self.path.as_os_str(), std::ffi::OsStr::new(subkey));
+
+            // SAFETY: This is just an FFI call as described in the docs [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regsetkeyvaluew
+            let code = unsafe {
+                windows_sys::Win32::System::Registry::RegSetKeyValueW(
+                    windows_sys::Win32::System::Registry::HKEY_CURRENT_USER,
+                    wide_os(&path).as_ptr(),
+                    wide_os(std::ffi::OsStr::new(name)).as_ptr(),
+                    data_type,
+                    data.as_ptr().cast(),
+                    data.len() as u32,
+                )
+            };
+            assert_eq!(code, windows_sys::Win32::Foundation::ERROR_SUCCESS);
+        }
+
+        /// Returns action arguments for listing the key to the given depth.
+        fn args(&self, max_depth: u32) -> Args {
+            Args {
+                root: winreg::PredefinedKey::CurrentUser,
+                key: self.path.clone(),
+                view: winreg::View::Default,
+                max_depth,
+                max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+            }
+        }
+    }
+
+    impl Drop for TestKey {
+
+        fn drop(&mut self) {
+            use windows_sys::Win32::System::Registry::*;
+
+            // SAFETY: This is just an FFI call as described in the docs [1].
+            // We do not verify the result as there is nothing we can do about
+            // failures here anyway.
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regdeletetreew
+            unsafe {
+                RegDeleteTreeW(HKEY_CURRENT_USER, wide_os(&self.path).as_ptr());
+            }
+        }
+    }
+
+    /// Creates a volatile key with the given path under `HKEY_CURRENT_USER`.
+    fn create_key(path: &std::ffi::OsStr, class: &str) {
+        use windows_sys::Win32::System::Registry::*;
+
+        let mut raw = std::ptr::null_mut();
+
+        // SAFETY: These are just FFI calls as described in the docs [1, 2].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regcreatekeyexw
+        // [2]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regclosekey
+        let code = unsafe {
+            RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                wide_os(path).as_ptr(),
+                0,
+                wide_os(std::ffi::OsStr::new(class)).as_ptr(),
+                REG_OPTION_VOLATILE,
+                KEY_ALL_ACCESS,
+                std::ptr::null(),
+                &mut raw,
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(code, windows_sys::Win32::Foundation::ERROR_SUCCESS);
+
+        unsafe {
+            RegCloseKey(raw);
+        }
+    }
+
+    /// Encodes the given string as a null-terminated wide string.
+    fn wide_os(string: &std::ffi::OsStr) -> Vec<u16> {
+        use std::os::windows::ffi::OsStrExt as _;
+
+        string.encode_wide().chain(std::iter::once(0)).collect()
+    }
+}
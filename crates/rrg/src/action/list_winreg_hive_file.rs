@@ -0,0 +1,473 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::PathBuf;
+
+/// Default maximum number of bytes of binary data to return for each value.
+const DEFAULT_MAX_BYTES_LEN: usize = 64 * 1024;
+
+/// Arguments of the `list_winreg_hive_file` action.
+pub struct Args {
+    /// Path to the hive file to list.
+    path: PathBuf,
+    /// Key relative to the root of the hive to list.
+    key: String,
+    /// Limit on the depth of recursion when visiting subkeys.
+    max_depth: u32,
+    /// Maximum number of bytes of binary data to return for each value.
+    max_bytes_len: usize,
+}
+
+/// A result of the `list_winreg_hive_file` action.
+#[derive(Debug)]
+struct Item {
+    /// Key relative to the root of the hive that was listed.
+    key: String,
+    /// Subkey relative to `key` of the listed entry.
+    subkey: String,
+    /// Listed entry.
+    entry: Entry,
+}
+
+/// An entry listed by the `list_winreg_hive_file` action.
+#[derive(Debug)]
+enum Entry {
+    /// Subkey of the listed key.
+    Subkey {
+        /// Time at which the subkey (or any of its values) was last modified.
+        last_write_time: std::time::SystemTime,
+        /// Class of the subkey.
+        class: String,
+    },
+    /// Value of the listed key or one of its subkeys.
+    Value {
+        /// Listed value.
+        value: winreg::hive::Value,
+        /// Whether binary data of the value was truncated.
+        truncated: bool,
+    },
+}
+
+/// Handles invocations of the `list_winreg_hive_file` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let hive = winreg::hive::Hive::open(&args.path)
+        .map_err(|error| crate::session::Error::action(OpenError {
+            path: args.path.clone(),
+            error,
+        }))?;
+
+    if hive.is_dirty() {
+        log::warn! {
+            "hive {} is dirty, changes in its transaction logs are ignored",
+            args.path.display(),
+        };
+    }
+
+    let key = hive.root()
+        .and_then(|root| root.open(&args.key))
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => crate::session::Error::action(KeyNotFoundError {
+                key: args.key.clone(),
+            }),
+            _ => crate::session::Error::action(error),
+        })?;
+
+    // The default value of 0 is treated as 1 (see the documentation of the
+    // `max_depth` field in the Protocol Buffers definition).
+    let max_depth = std::cmp::max(args.max_depth, 1);
+
+    /// Single "stack frame" of the recursive listing procedure.
+    struct PendingKey<'h> {
+        /// Key to visit.
+        key: winreg::hive::Key<'h>,
+        /// Name of the `key` relative to the key from which we started.
+        key_rel_name: String,
+        /// Current depth of the recursive walk.
+        depth: u32,
+    }
+
+    // `pending_keys` represents our recursion stack. We implement the walk this
+    // way rather than using the traditional recursion to avoid stack overflow
+    // issues in case of deep (or maliciously crafted) hives.
+    let mut pending_keys = Vec::new();
+    pending_keys.push(PendingKey {
+        key,
+        key_rel_name: String::new(),
+        depth: 0,
+    });
+
+    while let Some(PendingKey { key, key_rel_name, depth }) = pending_keys.pop() {
+        if depth > 0 {
+            let class = key.class()
+                .inspect_err(|error| {
+                    log::error!("failed to read class of subkey {key_rel_name:?}: {error}");
+                })
+                .unwrap_or_default();
+
+            session.reply(Item {
+                key: args.key.clone(),
+                subkey: key_rel_name.clone(),
+                entry: Entry::Subkey {
+                    last_write_time: key.last_write_time(),
+                    class,
+                },
+            })?;
+        }
+
+        // Hives can be truncated or otherwise corrupted, so we do not fail if
+        // some part of the hive cannot be parsed and list as much as we can.
+        match key.values() {
+            Ok(values) => for value in values {
+                let mut value = match value {
+                    Ok(value) => value,
+                    Err(error) => {
+                        log::error!("failed to read value of key {key_rel_name:?}: {error}");
+                        continue;
+                    }
+                };
+
+                let mut truncated = false;
+                if let winreg::hive::ValueData::Bytes(bytes) = &mut value.data {
+                    if bytes.len() > args.max_bytes_len {
+                        bytes.truncate(args.max_bytes_len);
+                        truncated = true;
+                    }
+                }
+
+                session.reply(Item {
+                    key: args.key.clone(),
+                    subkey: key_rel_name.clone(),
+                    entry: Entry::Value {
+                        value,
+                        truncated,
+                    },
+                })?;
+            },
+            Err(error) => {
+                log::error!("failed to read values of key {key_rel_name:?}: {error}");
+            }
+        }
+
+        if depth < max_depth {
+            let subkeys = match key.subkeys() {
+                Ok(subkeys) => subkeys,
+                Err(error) => {
+                    log::error!("failed to read subkeys of key {key_rel_name:?}: {error}");
+                    continue;
+                }
+            };
+
+            for subkey in subkeys {
+                let subkey = match subkey {
+                    Ok(subkey) => subkey,
+                    Err(error) => {
+                        log::error!("failed to read subkey of key {key_rel_name:?}: {error}");
+                        continue;
+                    }
+                };
+
+                let subkey_rel_name = winreg::path::join(&key_rel_name, &subkey.name());
+                pending_keys.push(PendingKey {
+                    key: subkey,
+                    key_rel_name: subkey_rel_name.to_string_lossy().into_owned(),
+                    depth: depth + 1,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_winreg_hive_file::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let path = crate::path::from_proto(proto.take_path())
+            .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+        let max_bytes_len = match proto.max_bytes_len() {
+            0 => DEFAULT_MAX_BYTES_LEN,
+            len => usize::try_from(len).unwrap_or(usize::MAX),
+        };
+
+        Ok(Args {
+            path,
+            key: proto.take_key(),
+            max_depth: proto.max_depth(),
+            max_bytes_len,
+        })
+    }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        vec![self.path.as_path()]
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_winreg_hive_file::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_winreg_hive_file::Result::new();
+        proto.set_key(self.key);
+        proto.set_subkey(self.subkey);
+
+        match self.entry {
+            Entry::Subkey { last_write_time, class } => {
+                let subkey_info = proto.mut_subkey_info();
+                subkey_info.set_last_write_time(rrg_proto::into_timestamp(last_write_time));
+                subkey_info.set_class(class);
+            }
+            Entry::Value { value, truncated } => {
+                proto.set_value(value.into());
+                proto.set_truncated(truncated);
+            }
+        }
+
+        proto
+    }
+}
+
+/// An error indicating that the hive file could not be opened.
+#[derive(Debug)]
+struct OpenError {
+    /// Path to the hive file.
+    path: PathBuf,
+    /// Underlying error.
+    error: std::io::Error,
+}
+
+impl std::fmt::Display for OpenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "failed to open hive {}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for OpenError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error indicating that the requested key does not exist in the hive.
+#[derive(Debug)]
+struct KeyNotFoundError {
+    /// Key that was not found.
+    key: String,
+}
+
+impl std::fmt::Display for KeyNotFoundError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "key {:?} not found", self.key)
+    }
+}
+
+impl std::error::Error for KeyNotFoundError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Path to a small hive file used for testing.
+    ///
+    /// See tests of the [`winreg::hive`] module for a description of its
+    /// contents.
+    fn small_hive_path() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../winreg/testdata/small.hive")
+    }
+
+    fn args(key: &str, max_depth: u32) -> Args {
+        Args {
+            path: small_hive_path(),
+            key: String::from(key),
+            max_depth,
+            max_bytes_len: DEFAULT_MAX_BYTES_LEN,
+        }
+    }
+
+    fn subkeys(session: &crate::session::FakeSession) -> Vec<String> {
+        let mut subkeys = session.replies::<Item>()
+            .filter_map(|item| match item.entry {
+                Entry::Subkey { .. } => Some(item.subkey.clone()),
+                Entry::Value { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        subkeys.sort();
+        subkeys
+    }
+
+    #[test]
+    fn handle_non_existent_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo.hive"),
+            ..args("", 1)
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, args).unwrap_err();
+
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<OpenError>());
+    }
+
+    #[test]
+    fn handle_invalid_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("foo.hive");
+        std::fs::write(&path, vec![0; 8192])
+            .unwrap();
+
+        let args = Args {
+            path,
+            ..args("", 1)
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_key_not_found() {
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, args("Foo\\Norf", 1)).unwrap_err();
+
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<KeyNotFoundError>());
+    }
+
+    #[test]
+    fn handle_root() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args("", 1)).is_ok());
+
+        assert_eq!(subkeys(&session), vec!["Bar", "Foo", "Ünicode"]);
+
+        let default_value = session.replies::<Item>()
+            .find_map(|item| match &item.entry {
+                Entry::Value { value, .. } if item.subkey.is_empty() => Some(value.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(default_value.name, "");
+        assert_eq!(default_value.data, winreg::hive::ValueData::String(String::from("root")));
+    }
+
+    #[test]
+    fn handle_max_depth() {
+        let subkeys_at = |max_depth| {
+            let mut session = crate::session::FakeSession::new();
+            assert!(handle(&mut session, args("Foo", max_depth)).is_ok());
+            subkeys(&session)
+        };
+
+        assert_eq!(subkeys_at(0), vec!["Baz"]);
+        assert_eq!(subkeys_at(1), vec!["Baz"]);
+        assert_eq!(subkeys_at(2), vec!["Baz", "Baz\\Quux"]);
+        assert_eq!(subkeys_at(3), vec!["Baz", "Baz\\Quux"]);
+    }
+
+    #[test]
+    fn handle_subkey_info() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args("", 1)).is_ok());
+
+        let (last_write_time, class) = session.replies::<Item>()
+            .find_map(|item| match &item.entry {
+                Entry::Subkey { last_write_time, class } if item.subkey == "Foo" => {
+                    Some((*last_write_time, class.clone()))
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        // 2024-01-01T00:00:01Z.
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_201);
+        assert_eq!(last_write_time, time);
+        assert_eq!(class, "FooClass");
+    }
+
+    #[test]
+    fn handle_values_nested() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args("Foo", 2)).is_ok());
+
+        let value = session.replies::<Item>()
+            .find_map(|item| match &item.entry {
+                Entry::Value { value, .. } if item.subkey == "Baz\\Quux" => Some(value.clone()),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(value.name, "Depth");
+        assert_eq!(value.data, winreg::hive::ValueData::U32(3));
+    }
+
+    #[test]
+    fn handle_bytes_truncated() {
+        let args = Args {
+            max_bytes_len: 1024,
+            ..args("Foo", 1)
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let values = session.replies::<Item>()
+            .filter_map(|item| match &item.entry {
+                Entry::Value { value, truncated } => Some((value.name.as_str(), &value.data, *truncated)),
+                Entry::Subkey { .. } => None,
+            })
+            .collect::<Vec<_>>();
+
+        let big = values.iter().find(|(name, _, _)| *name == "Big").unwrap();
+        assert!(matches!(big.1, winreg::hive::ValueData::Bytes(bytes) if bytes.len() == 1024));
+        assert!(big.2);
+
+        let binary = values.iter().find(|(name, _, _)| *name == "Binary").unwrap();
+        assert!(matches!(binary.1, winreg::hive::ValueData::Bytes(bytes) if bytes.len() == 16));
+        assert!(!binary.2);
+    }
+
+    #[test]
+    fn handle_truncated_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("foo.hive");
+
+        let data = std::fs::read(small_hive_path())
+            .unwrap();
+        std::fs::write(&path, &data[..data.len() / 2])
+            .unwrap();
+
+        let args = Args {
+            path,
+            ..args("", 3)
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // We should still be able to list the keys that are stored at the very
+        // beginning of the hive.
+        assert!(subkeys(&session).contains(&String::from("Foo")));
+    }
+}
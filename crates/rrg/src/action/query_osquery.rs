@@ -0,0 +1,655 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Paths at which osquery installers put the osquery shell executable.
+///
+/// These are used only if the path is not given in the agent arguments.
+#[cfg(target_os = "linux")]
+const DEFAULT_OSQUERY_PATHS: &[&str] = &[
+    "/usr/bin/osqueryi",
+    "/opt/osquery/bin/osqueryi",
+    "/usr/local/bin/osqueryi",
+];
+
+/// Paths at which osquery installers put the osquery shell executable.
+///
+/// These are used only if the path is not given in the agent arguments.
+#[cfg(target_os = "macos")]
+const DEFAULT_OSQUERY_PATHS: &[&str] = &[
+    "/usr/local/bin/osqueryi",
+    "/opt/osquery/lib/osquery.app/Contents/MacOS/osqueryd",
+];
+
+/// Paths at which osquery installers put the osquery shell executable.
+///
+/// These are used only if the path is not given in the agent arguments.
+#[cfg(target_os = "windows")]
+const DEFAULT_OSQUERY_PATHS: &[&str] = &[
+    "C:\\Program Files\\osquery\\osqueryi.exe",
+];
+
+/// Default timeout of the query execution.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default limit on the size of the query output.
+const DEFAULT_MAX_OUTPUT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Limit on the size of the error output of osquery that we keep.
+const MAX_STDERR_SIZE: u64 = 4 * 1024;
+
+/// How often to check whether osquery has finished.
+const CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Arguments of the `query_osquery` action.
+pub struct Args {
+    /// SQL query to run.
+    query: String,
+    /// Timeout of the query execution.
+    timeout: Duration,
+    /// Maximum size of the query output.
+    max_output_size: u64,
+    /// Identity to run osquery as (if different than the agent one).
+    run_as: Option<crate::run_as::RunAs>,
+}
+
+/// A result of the `query_osquery` action.
+#[derive(Debug)]
+enum Item {
+    /// Single row of the query result.
+    Row(BTreeMap<String, String>),
+    /// Summary of the query execution.
+    Summary {
+        /// Number of rows that the query returned.
+        row_count: u64,
+        /// Version of osquery that executed the query.
+        osquery_version: String,
+    },
+}
+
+/// An error indicating that osquery is not available on the system.
+#[derive(Debug)]
+struct UnavailableError {
+    /// Path at which we looked for osquery (if it was configured).
+    path: Option<PathBuf>,
+}
+
+impl std::fmt::Display for UnavailableError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(fmt, "osquery unavailable at '{}'", path.display()),
+            None => write!(fmt, "osquery unavailable"),
+        }
+    }
+}
+
+impl std::error::Error for UnavailableError {
+}
+
+/// An error that can occur when running osquery.
+#[derive(Debug)]
+enum Error {
+    /// Failed to run osquery or to collect its output.
+    Io(std::io::Error),
+    /// Osquery did not finish within the timeout.
+    Timeout(Duration),
+    /// Osquery produced more output than allowed.
+    OutputLimitExceeded(u64),
+    /// Osquery finished with an error.
+    Failed {
+        /// Exit status of osquery.
+        exit_status: std::process::ExitStatus,
+        /// Error output of osquery (possibly truncated).
+        stderr: String,
+    },
+    /// Osquery produced output that is not valid JSON.
+    Json(json::ParseError),
+    /// Osquery produced output that does not describe rows.
+    InvalidOutput,
+}
+
+impl std::fmt::Display for Error {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Io(error) => {
+                write!(fmt, "failed to run osquery: {error}")
+            }
+            Error::Timeout(timeout) => {
+                write!(fmt, "osquery did not finish within {timeout:?}")
+            }
+            Error::OutputLimitExceeded(limit) => {
+                write!(fmt, "osquery output exceeded {limit} bytes")
+            }
+            Error::Failed { exit_status, stderr } => {
+                write!(fmt, "osquery failed ({exit_status}): {stderr}")
+            }
+            Error::Json(error) => {
+                write!(fmt, "invalid osquery output: {error}")
+            }
+            Error::InvalidOutput => {
+                write!(fmt, "osquery output is not an array of rows")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// An error indicating that logon credentials were given in unsigned arguments.
+#[derive(Debug)]
+struct CredentialsError;
+
+impl std::fmt::Display for CredentialsError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "logon credentials are allowed only in signed commands")
+    }
+}
+
+impl std::error::Error for CredentialsError {
+}
+
+impl From<std::io::Error> for Error {
+
+    fn from(error: std::io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+/// Handles invocations of the `query_osquery` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let path = osquery_path(session.args().osquery_path.as_deref())?;
+
+    // The query is passed to osquery directly as a single argument, there is
+    // no shell involved that could interpret any part of it.
+    let output = match run(&path, &["--json".as_ref(), args.query.as_ref()], &args) {
+        Ok(output) => output,
+        // The executable might have been removed after we located it.
+        Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Err(crate::session::Error::action_unavailable(UnavailableError {
+                path: Some(path),
+            }));
+        }
+        Err(error) => return Err(crate::session::Error::action(error)),
+    };
+    let rows = parse_rows(&output)
+        .map_err(crate::session::Error::action)?;
+
+    let version = run(&path, &["--version".as_ref()], &args)
+        .map_err(crate::session::Error::action)?;
+    let osquery_version = parse_version(&version);
+
+    let row_count = rows.len() as u64;
+    for row in rows {
+        session.reply(Item::Row(row))?;
+    }
+
+    session.reply(Item::Summary {
+        row_count,
+        osquery_version,
+    })?;
+
+    Ok(())
+}
+
+/// Locates the osquery shell executable.
+///
+/// If the path is configured, only that path is considered. Otherwise, the
+/// default installation paths are tried.
+pub(crate) fn osquery_path(configured_path: Option<&Path>) -> crate::session::Result<PathBuf> {
+    if let Some(path) = configured_path {
+        if path.is_file() {
+            return Ok(path.to_path_buf());
+        }
+
+        return Err(crate::session::Error::action_unavailable(UnavailableError {
+            path: Some(path.to_path_buf()),
+        }));
+    }
+
+    DEFAULT_OSQUERY_PATHS.iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+        .ok_or_else(|| crate::session::Error::action_unavailable(UnavailableError {
+            path: None,
+        }))
+}
+
+/// Runs osquery with the given arguments and returns its standard output.
+fn run(path: &Path, osquery_args: &[&std::ffi::OsStr], args: &Args) -> Result<Vec<u8>, Error> {
+    use std::io::Read as _;
+
+    let mut command = std::process::Command::new(path);
+    command
+        .args(osquery_args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let mut process = crate::run_as::spawn(command, args.run_as.as_ref())?;
+
+    let stdout = process.stdout.take()
+        .expect("no stdout pipe");
+    let stderr = process.stderr.take()
+        .expect("no stderr pipe");
+
+    // We read one byte more than the limit to know whether it was exceeded.
+    // Once that happens, we close the pipe which makes osquery fail on next
+    // write (instead of blocking forever on a full pipe).
+    let max_output_size = args.max_output_size;
+    let reader_stdout = std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        stdout.take(max_output_size.saturating_add(1)).read_to_end(&mut buf)?;
+        Ok(buf)
+    });
+
+    // Error output can only be used for diagnostics, so we keep just the first
+    // part of it but consume everything so that osquery is not blocked.
+    let reader_stderr = std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let mut stderr_limited = stderr.take(MAX_STDERR_SIZE);
+        stderr_limited.read_to_end(&mut buf)?;
+        std::io::copy(&mut stderr_limited.into_inner(), &mut std::io::sink())?;
+        Ok(buf)
+    });
+
+    let start_time = std::time::Instant::now();
+    let exit_status = loop {
+        if let Some(exit_status) = process.try_wait()? {
+            break Some(exit_status);
+        }
+
+        let time_left = args.timeout.saturating_sub(start_time.elapsed());
+        if time_left.is_zero() {
+            process.kill()?;
+            process.wait()?;
+            break None;
+        }
+
+        std::thread::sleep(std::cmp::min(CHECK_INTERVAL, time_left));
+    };
+
+    let stdout = match reader_stdout.join() {
+        Ok(stdout) => stdout?,
+        Err(error) => std::panic::resume_unwind(error),
+    };
+    let stderr = match reader_stderr.join() {
+        Ok(stderr) => stderr?,
+        Err(error) => std::panic::resume_unwind(error),
+    };
+
+    let Some(exit_status) = exit_status else {
+        return Err(Error::Timeout(args.timeout));
+    };
+    if stdout.len() as u64 > args.max_output_size {
+        return Err(Error::OutputLimitExceeded(args.max_output_size));
+    }
+    if !exit_status.success() {
+        return Err(Error::Failed {
+            exit_status,
+            stderr: String::from_utf8_lossy(&stderr).trim().to_string(),
+        });
+    }
+
+    Ok(stdout)
+}
+
+/// Parses rows from the JSON output of osquery.
+///
+/// Osquery reports all values as strings, but in case some other type is used
+/// we convert it to a string as well (with `null` being an empty string).
+fn parse_rows(output: &[u8]) -> Result<Vec<BTreeMap<String, String>>, Error> {
+    let value = json::parse(output)
+        .map_err(Error::Json)?;
+
+    let rows = value.as_array()
+        .ok_or(Error::InvalidOutput)?;
+
+    rows.iter().map(|row| {
+        let row = row.as_object()
+            .ok_or(Error::InvalidOutput)?;
+
+        row.iter().map(|(column, value)| {
+            let value = match value {
+                json::Value::Null => String::new(),
+                json::Value::Bool(bool) => bool.to_string(),
+                json::Value::Integer(integer) => integer.to_string(),
+                json::Value::Real(real) => real.to_string(),
+                json::Value::String(string) => string.clone(),
+                json::Value::Array(_) | json::Value::Object(_) => {
+                    return Err(Error::InvalidOutput);
+                }
+            };
+
+            Ok((column.clone(), value))
+        }).collect()
+    }).collect()
+}
+
+/// Parses the version from the output of `osqueryi --version`.
+///
+/// The output has the `osqueryi version 5.12.1` format. If it does not, the
+/// whole output is returned instead.
+fn parse_version(output: &[u8]) -> String {
+    let output = String::from_utf8_lossy(output);
+    let output = output.trim();
+
+    match output.rsplit_once(" version ") {
+        Some((_, version)) => version.to_string(),
+        None => output.to_string(),
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::query_osquery::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let timeout = if proto.has_timeout() {
+            Duration::try_from(proto.take_timeout())
+                .map_err(|error| ParseArgsError::invalid_field("timeout", error))?
+        } else {
+            DEFAULT_TIMEOUT
+        };
+
+        let max_output_size = match proto.max_output_size() {
+            0 => DEFAULT_MAX_OUTPUT_SIZE,
+            max_output_size => max_output_size,
+        };
+
+        // Unlike signed commands, arguments of this action can be tampered with
+        // in transit, so we do not accept any credentials here.
+        if proto.run_as().has_windows_logon() {
+            return Err(ParseArgsError::invalid_field("run_as", CredentialsError));
+        }
+
+        let run_as = crate::run_as::RunAs::from_proto(proto.take_run_as())
+            .map_err(|error| ParseArgsError::invalid_field("run_as", error))?;
+
+        Ok(Args {
+            query: proto.take_query(),
+            timeout,
+            max_output_size,
+            run_as,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::query_osquery::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::query_osquery::Result::new();
+
+        match self {
+            Item::Row(columns) => {
+                proto.mut_row().set_columns(columns.into_iter().collect());
+            }
+            Item::Summary { row_count, osquery_version } => {
+                let summary_proto = proto.mut_summary();
+                summary_proto.set_row_count(row_count);
+                summary_proto.set_osquery_version(osquery_version);
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args(query: &str) -> Args {
+        Args {
+            query: String::from(query),
+            timeout: DEFAULT_TIMEOUT,
+            max_output_size: DEFAULT_MAX_OUTPUT_SIZE,
+            run_as: None,
+        }
+    }
+
+    fn session(osquery_path: &Path) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            osquery_path: Some(osquery_path.to_path_buf()),
+            ..Default::default()
+        })
+    }
+
+    /// Creates a fake osquery executable that runs the given shell script on
+    /// queries (and that reports a fixed version).
+    #[cfg(target_family = "unix")]
+    fn stub_osquery(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let path = dir.join("osqueryi");
+        std::fs::write(&path, format! {
+            "#!/bin/sh\nif [ \"$1\" = \"--version\" ]; then echo 'osqueryi version 5.12.1'; exit 0; fi\n{script}\n"
+        }).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        path
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_rows() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = stub_osquery(tempdir.path(), r#"cat <<'EOF'
+[
+  {"name":"init","pid":"1"},
+  {"name":"sshd","pid":"1337"}
+]
+EOF"#);
+
+        let mut session = session(&path);
+        assert!(handle(&mut session, args("SELECT name, pid FROM processes")).is_ok());
+
+        assert_eq!(session.reply_count(), 3);
+
+        let Item::Row(row) = session.reply::<Item>(0) else {
+            panic!("unexpected item");
+        };
+        assert_eq!(row["name"], "init");
+        assert_eq!(row["pid"], "1");
+
+        let Item::Row(row) = session.reply::<Item>(1) else {
+            panic!("unexpected item");
+        };
+        assert_eq!(row["name"], "sshd");
+        assert_eq!(row["pid"], "1337");
+
+        let Item::Summary { row_count, osquery_version } = session.reply::<Item>(2) else {
+            panic!("unexpected item");
+        };
+        assert_eq!(*row_count, 2);
+        assert_eq!(osquery_version, "5.12.1");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_query_not_through_shell() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let query_path = tempdir.path().join("query");
+        let marker_path = tempdir.path().join("marker");
+
+        // The stub records the query it received verbatim.
+        let path = stub_osquery(tempdir.path(), &format! {
+            "printf '%s' \"$2\" > '{}'; echo '[]'", query_path.display()
+        });
+
+        let query = format!("SELECT 1; $(touch {0}) `touch {0}`", marker_path.display());
+
+        let mut session = session(&path);
+        assert!(handle(&mut session, args(&query)).is_ok());
+
+        assert_eq!(std::fs::read_to_string(&query_path).unwrap(), query);
+        assert!(!marker_path.exists());
+
+        assert_eq!(session.reply_count(), 1);
+        assert!(matches! {
+            session.reply::<Item>(0),
+            Item::Summary { row_count: 0, .. }
+        });
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_failure() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = stub_osquery(tempdir.path(), "echo 'no such table: foo' >&2; exit 1");
+
+        let mut session = session(&path);
+        let error = handle(&mut session, args("SELECT * FROM foo"))
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionFailure);
+        assert!(error.to_string().contains("no such table: foo"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_timeout() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = stub_osquery(tempdir.path(), "exec sleep 60");
+
+        let mut args = args("SELECT 1");
+        args.timeout = Duration::from_millis(100);
+
+        let mut session = session(&path);
+        let error = handle(&mut session, args)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionFailure);
+        assert!(error.to_string().contains("did not finish"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_output_limit_exceeded() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = stub_osquery(tempdir.path(), r#"echo '[{"foo":"0123456789"}]'"#);
+
+        let mut args = args("SELECT 1");
+        args.max_output_size = 8;
+
+        let mut session = session(&path);
+        let error = handle(&mut session, args)
+            .unwrap_err();
+
+        assert!(error.to_string().contains("exceeded 8 bytes"));
+    }
+
+    #[test]
+    fn handle_unavailable() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = session(&tempdir.path().join("osqueryi"));
+        let error = handle(&mut session, args("SELECT 1"))
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionUnavailable);
+        assert!(error.to_string().contains("osquery unavailable"));
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_run_as_own_uid() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = stub_osquery(tempdir.path(), r#"echo "[{\"uid\":\"$(id -u)\"}]""#);
+
+        // SAFETY: These functions are always safe to call.
+        let (uid, gid) = unsafe {
+            (libc::geteuid(), libc::getegid())
+        };
+
+        let mut args = args("SELECT uid FROM processes");
+        args.run_as = Some(crate::run_as::RunAs::Unix {
+            uid,
+            gid,
+            groups: Vec::new(),
+        });
+
+        let mut session = session(&path);
+        assert!(handle(&mut session, args).is_ok());
+
+        let Item::Row(row) = session.reply::<Item>(0) else {
+            panic!("unexpected item");
+        };
+        assert_eq!(row["uid"], uid.to_string());
+    }
+
+    #[test]
+    fn args_run_as_credentials() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::query_osquery::Args::new();
+        proto.set_query(String::from("SELECT 1"));
+        proto.mut_run_as().mut_windows_logon().set_user_name(String::from("foo"));
+        proto.mut_run_as().mut_windows_logon().set_password(String::from("bar"));
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn parse_rows_invalid() {
+        assert!(matches!(parse_rows(b"[{\"foo\":"), Err(Error::Json(_))));
+        assert!(matches!(parse_rows(b"{\"foo\":\"bar\"}"), Err(Error::InvalidOutput)));
+        assert!(matches!(parse_rows(b"[\"foo\"]"), Err(Error::InvalidOutput)));
+    }
+
+    #[test]
+    fn parse_rows_non_string_values() {
+        let rows = parse_rows(br#"[{"a":1,"b":null,"c":true,"d":"x"}]"#)
+            .unwrap();
+
+        assert_eq!(rows[0]["a"], "1");
+        assert_eq!(rows[0]["b"], "");
+        assert_eq!(rows[0]["c"], "true");
+        assert_eq!(rows[0]["d"], "x");
+    }
+
+    #[test]
+    fn parse_version_ok() {
+        assert_eq!(parse_version(b"osqueryi version 5.12.1\n"), "5.12.1");
+        assert_eq!(parse_version(b"5.12.1\n"), "5.12.1");
+    }
+}
@@ -3,6 +3,18 @@
 // Use of this source code is governed by an MIT-style license that can be found
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
+/// Namespace to run queries in if not specified otherwise.
+#[cfg(target_family = "windows")]
+const DEFAULT_NAMESPACE: &str = "root\\cimv2";
+
+/// Timeout for polling query results if not specified otherwise.
+#[cfg(target_family = "windows")]
+const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Maximum number of rows to return if not specified otherwise.
+#[cfg(target_family = "windows")]
+const DEFAULT_MAX_ROW_COUNT: usize = 10_000;
+
 /// Arguments of the `query_wmi` action.
 #[cfg(target_family = "windows")]
 pub struct Args {
@@ -10,6 +22,12 @@ pub struct Args {
     ///
     /// [1]: https://learn.microsoft.com/en-us/windows/win32/wmisdk/wql-sql-for-wmi
     query: std::ffi::OsString,
+    /// WMI namespace to run the query in.
+    namespace: std::ffi::OsString,
+    /// Timeout after which polling for query results is aborted.
+    timeout: std::time::Duration,
+    /// Maximum number of rows to return.
+    max_row_count: usize,
 }
 
 /// A result of the `query_wmi` action.
@@ -26,7 +44,9 @@ where
     S: crate::session::Session,
 {
     let query = wmi::query(&args.query)
-        .map_err(crate::session::Error::action)?;
+        .map_err(crate::session::Error::action)?
+        .namespace(&args.namespace)
+        .timeout(args.timeout);
 
     let rows = query.rows()
         .map_err(crate::session::Error::action)?;
@@ -52,10 +72,19 @@ where
     }
 
     let mut status: Status = Status::None;
+    let mut row_count = 0;
 
     for row in rows {
         let row = match row {
             Ok(row) => row,
+            // Unlike with other errors, we fail the action on timeouts even if
+            // there were some rows already reported: the result is incomplete
+            // and the caller should know about it.
+            Err(error) if error.kind() == std::io::ErrorKind::TimedOut => {
+                return Err(crate::session::Error::action(TimeoutError {
+                    timeout: args.timeout,
+                }));
+            }
             Err(error) => {
                 log::error!("failed to obtain WMI query row: {}", error);
                 // If there were no rows at all so far we keep the error in case
@@ -70,7 +99,13 @@ where
             }
         };
 
+        if row_count == args.max_row_count {
+            log::warn!("WMI query row limit ({}) reached", args.max_row_count);
+            break;
+        }
+
         session.reply(Item { row })?;
+        row_count += 1;
         // We reported a row, so we unconditionally change the status. Even if
         // there was an error, we will not fail the action at this point.
         status = Status::Some;
@@ -101,8 +136,30 @@ impl crate::request::Args for Args {
     type Proto = rrg_proto::query_wmi::Args;
 
     fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let namespace = match proto.take_namespace() {
+            namespace if namespace.is_empty() => String::from(DEFAULT_NAMESPACE),
+            namespace => namespace,
+        };
+
+        let timeout = if proto.timeout.is_some() {
+            std::time::Duration::try_from(proto.take_timeout())
+                .map_err(|error| ParseArgsError::invalid_field("timeout", error))?
+        } else {
+            DEFAULT_TIMEOUT
+        };
+
+        let max_row_count = match proto.max_row_count() {
+            0 => DEFAULT_MAX_ROW_COUNT,
+            count => usize::try_from(count).unwrap_or(usize::MAX),
+        };
+
         Ok(Args {
             query: std::ffi::OsString::from(proto.take_query()),
+            namespace: std::ffi::OsString::from(namespace),
+            timeout,
+            max_row_count,
         })
     }
 }
@@ -117,56 +174,92 @@ impl crate::response::Item for Item {
 
         for (name, value) in self.row {
             let proto_name = name.to_string_lossy().into_owned();
-            let mut proto_value = rrg_proto::query_wmi::Value::new();
+            proto.mut_row().insert(proto_name, value_into_proto(value));
+        }
 
-            match value {
-                wmi::QueryValue::None => (),
-                wmi::QueryValue::Bool(bool) => {
-                    proto_value.set_bool(bool)
-                }
-                wmi::QueryValue::U8(u8) => {
-                    proto_value.set_uint(u64::from(u8))
-                }
-                wmi::QueryValue::I8(i8) => {
-                    proto_value.set_int(i64::from(i8))
-                }
-                wmi::QueryValue::U16(u16) => {
-                    proto_value.set_uint(u64::from(u16))
-                }
-                wmi::QueryValue::I16(i16) => {
-                    proto_value.set_int(i64::from(i16))
-                }
-                wmi::QueryValue::U32(u32) => {
-                    proto_value.set_uint(u64::from(u32))
-                }
-                wmi::QueryValue::I32(i32) => {
-                    proto_value.set_int(i64::from(i32))
-                }
-                wmi::QueryValue::U64(u64) => {
-                    proto_value.set_uint(u64)
-                }
-                wmi::QueryValue::I64(i64) => {
-                    proto_value.set_int(i64)
-                }
-                wmi::QueryValue::F32(f32) => {
-                    proto_value.set_float(f32)
-                }
-                wmi::QueryValue::F64(f64) => {
-                    proto_value.set_double(f64)
-                }
-                wmi::QueryValue::String(string) => {
-                    proto_value.set_string(string.to_string_lossy().into_owned())
-                }
-                wmi::QueryValue::Unsupported(_) => (),
-            }
+        proto
+    }
+}
 
-            proto.mut_row().insert(proto_name, proto_value);
+/// Converts the given WMI query value into its Protocol Buffers representation.
+#[cfg(target_family = "windows")]
+fn value_into_proto(value: wmi::QueryValue) -> rrg_proto::query_wmi::Value {
+    let mut proto = rrg_proto::query_wmi::Value::new();
+
+    match value {
+        wmi::QueryValue::None => (),
+        wmi::QueryValue::Bool(bool) => {
+            proto.set_bool(bool)
+        }
+        wmi::QueryValue::U8(u8) => {
+            proto.set_uint(u64::from(u8))
+        }
+        wmi::QueryValue::I8(i8) => {
+            proto.set_int(i64::from(i8))
+        }
+        wmi::QueryValue::U16(u16) => {
+            proto.set_uint(u64::from(u16))
+        }
+        wmi::QueryValue::I16(i16) => {
+            proto.set_int(i64::from(i16))
+        }
+        wmi::QueryValue::U32(u32) => {
+            proto.set_uint(u64::from(u32))
+        }
+        wmi::QueryValue::I32(i32) => {
+            proto.set_int(i64::from(i32))
+        }
+        wmi::QueryValue::U64(u64) => {
+            proto.set_uint(u64)
+        }
+        wmi::QueryValue::I64(i64) => {
+            proto.set_int(i64)
+        }
+        wmi::QueryValue::F32(f32) => {
+            proto.set_float(f32)
         }
+        wmi::QueryValue::F64(f64) => {
+            proto.set_double(f64)
+        }
+        wmi::QueryValue::String(string) => {
+            proto.set_string(string.to_string_lossy().into_owned())
+        }
+        wmi::QueryValue::DateTime(time) => {
+            proto.set_datetime(rrg_proto::into_timestamp(time))
+        }
+        wmi::QueryValue::Array(values) => {
+            let values = values.into_iter()
+                .map(value_into_proto)
+                .collect();
 
-        proto
+            proto.mut_array().set_values(values)
+        }
+        wmi::QueryValue::Unsupported(_) => (),
+    }
+
+    proto
+}
+
+/// An error indicating that polling for query results timed out.
+#[cfg(target_family = "windows")]
+#[derive(Debug)]
+struct TimeoutError {
+    /// Timeout that elapsed.
+    timeout: std::time::Duration,
+}
+
+#[cfg(target_family = "windows")]
+impl std::fmt::Display for TimeoutError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "WMI query timed out after {:?}", self.timeout)
     }
 }
 
+#[cfg(target_family = "windows")]
+impl std::error::Error for TimeoutError {
+}
+
 #[cfg(test)]
 #[cfg(target_family = "windows")]
 mod tests {
@@ -183,6 +276,9 @@ mod tests {
                 VALUES
                   ('Foo', '1.3.3.7')
             ".into(),
+            namespace: DEFAULT_NAMESPACE.into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: DEFAULT_MAX_ROW_COUNT,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -201,6 +297,9 @@ mod tests {
                 WHERE
                   FreePhysicalMemory < 0
             ".into(),
+            namespace: DEFAULT_NAMESPACE.into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: DEFAULT_MAX_ROW_COUNT,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -219,10 +318,126 @@ mod tests {
                 WHERE
                   FreePhysicalMemory >= 0
             ".into(),
+            namespace: DEFAULT_NAMESPACE.into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: DEFAULT_MAX_ROW_COUNT,
         };
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, args).is_ok());
         assert_eq!(session.reply_count(), 1);
     }
+
+    #[test]
+    fn handle_win32_process_name() {
+        let args = Args {
+            query: "SELECT Name FROM Win32_Process".into(),
+            namespace: DEFAULT_NAMESPACE.into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: DEFAULT_MAX_ROW_COUNT,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let exe_path = std::env::current_exe()
+            .unwrap();
+        let exe_name = exe_path.file_name()
+            .unwrap();
+
+        assert! {
+            session.replies::<Item>().any(|item| {
+                match item.row.get(std::ffi::OsStr::new("Name")) {
+                    Some(wmi::QueryValue::String(name)) => name == exe_name,
+                    _ => false,
+                }
+            })
+        };
+    }
+
+    #[test]
+    fn handle_namespace() {
+        let args = Args {
+            query: "SELECT * FROM __Namespace".into(),
+            namespace: "root".into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: DEFAULT_MAX_ROW_COUNT,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert! {
+            session.replies::<Item>().any(|item| {
+                match item.row.get(std::ffi::OsStr::new("Name")) {
+                    Some(wmi::QueryValue::String(name)) => name.eq_ignore_ascii_case("cimv2"),
+                    _ => false,
+                }
+            })
+        };
+    }
+
+    #[test]
+    fn handle_invalid_namespace() {
+        let args = Args {
+            query: "SELECT * FROM Win32_OperatingSystem".into(),
+            namespace: "root\\rrg-non-existent".into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: DEFAULT_MAX_ROW_COUNT,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_max_row_count() {
+        let args = Args {
+            query: "SELECT Name FROM Win32_Process".into(),
+            namespace: DEFAULT_NAMESPACE.into(),
+            timeout: DEFAULT_TIMEOUT,
+            max_row_count: 2,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+        assert_eq!(session.reply_count(), 2);
+    }
+
+    #[test]
+    fn handle_timeout() {
+        let args = Args {
+            query: "SELECT * FROM CIM_DataFile".into(),
+            namespace: DEFAULT_NAMESPACE.into(),
+            timeout: std::time::Duration::ZERO,
+            max_row_count: usize::MAX,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, args).unwrap_err();
+
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<TimeoutError>());
+    }
+
+    #[test]
+    fn value_into_proto_datetime() {
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_200);
+
+        let proto = value_into_proto(wmi::QueryValue::DateTime(time));
+        assert_eq!(proto.datetime().seconds, 1_704_067_200);
+    }
+
+    #[test]
+    fn value_into_proto_array() {
+        let proto = value_into_proto(wmi::QueryValue::Array(vec![
+            wmi::QueryValue::U32(42),
+            wmi::QueryValue::String("foo".into()),
+        ]));
+
+        let values = proto.array().values();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].uint(), 42);
+        assert_eq!(values[1].string(), "foo");
+    }
 }
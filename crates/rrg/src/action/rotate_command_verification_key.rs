@@ -0,0 +1,372 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use crate::signing::{KeySet, VerificationKey};
+
+/// Context that serialized rotations are prefixed with when signing them.
+///
+/// Rotations are signed with the same keys as commands to execute, so without
+/// it a signed command that happens to parse as a rotation could be applied.
+pub const SIGNATURE_CONTEXT: &[u8] = b"rrg.rotate_command_verification_key\0";
+
+/// Arguments of the `rotate_command_verification_key` action.
+pub struct Args {
+    /// Serialized rotation message (verified against the signature).
+    raw_rotation: Vec<u8>,
+    /// Signature of the serialized rotation message.
+    ed25519_signature: ed25519_dalek::Signature,
+    /// New key to trust.
+    new_key: VerificationKey,
+    /// Key to revoke (if any).
+    revoked_key: Option<ed25519_dalek::VerifyingKey>,
+}
+
+/// Result of the `rotate_command_verification_key` action.
+pub struct Item {
+    /// Keys trusted after the rotation.
+    trusted_keys: Vec<ed25519_dalek::VerifyingKey>,
+    /// Keys revoked after the rotation.
+    revoked_keys: Vec<ed25519_dalek::VerifyingKey>,
+}
+
+/// An error indicating that the agent has no key store to persist keys in.
+#[derive(Debug)]
+struct MissingKeyStoreError;
+
+impl std::fmt::Display for MissingKeyStoreError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing command verification key store")
+    }
+}
+
+impl std::error::Error for MissingKeyStoreError {}
+
+/// Handles invocations of the `rotate_command_verification_key` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use crate::request::ParseArgsError;
+
+    let store_path = match &session.args().command_verification_key_store {
+        Some(path) => path.clone(),
+        None => return Err(crate::session::Error::action_unavailable(MissingKeyStoreError)),
+    };
+
+    let mut keys = KeySet::load(session.args().command_verification_key, &store_path)
+        .map_err(crate::session::Error::action)?;
+
+    // Rotations have to be signed with one of the keys that are trusted right
+    // now, so a rotation signed with a revoked key is never applied.
+    let now = std::time::SystemTime::now();
+    let message = [SIGNATURE_CONTEXT, &args.raw_rotation].concat();
+    keys.verify(&message, &args.ed25519_signature, now)
+        .map_err(|error| ParseArgsError::invalid_field("rotation", error))?;
+
+    keys.rotate(args.new_key, args.revoked_key)
+        .map_err(crate::session::Error::action)?;
+
+    log::info!("persisting command verification keys at '{}'", store_path.display());
+    keys.save(&store_path)
+        .map_err(crate::session::Error::action)?;
+
+    session.reply(Item {
+        trusted_keys: keys.trusted_keys(now).copied().collect(),
+        revoked_keys: keys.revoked_keys().copied().collect(),
+    })?;
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::rotate_command_verification_key::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+        use protobuf::Message as _;
+
+        let raw_signature = proto.take_rotation_ed25519_signature();
+
+        let ed25519_signature = ed25519_dalek::Signature::try_from(&raw_signature[..])
+            .map_err(|error| ParseArgsError::invalid_field("rotation_ed25519_signature", error))?;
+
+        let raw_rotation = proto.take_rotation();
+        let mut rotation =
+            rrg_proto::rotate_command_verification_key::Rotation::parse_from_bytes(&raw_rotation)
+                .map_err(|error| ParseArgsError::invalid_field("rotation", error))?;
+
+        let new_key = VerificationKey::try_from(rotation.take_new_key())
+            .map_err(|error| ParseArgsError::invalid_field("rotation new_key", error))?;
+
+        let revoked_key = match rotation.revoked_ed25519_public_key() {
+            [] => None,
+            bytes => Some(crate::signing::parse_key(bytes).map_err(|error| {
+                ParseArgsError::invalid_field("rotation revoked_ed25519_public_key", error)
+            })?),
+        };
+
+        Ok(Args {
+            raw_rotation,
+            ed25519_signature,
+            new_key,
+            revoked_key,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::rotate_command_verification_key::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::rotate_command_verification_key::Result::new();
+        for key in self.trusted_keys {
+            proto.mut_trusted_ed25519_public_keys().push(key.to_bytes().to_vec());
+        }
+        for key in self.revoked_keys {
+            proto.mut_revoked_ed25519_public_keys().push(key.to_bytes().to_vec());
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ed25519_dalek::Signer as _;
+
+    use super::*;
+
+    fn session(
+        initial_key: ed25519_dalek::VerifyingKey,
+        store_path: &std::path::Path,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            command_verification_key: Some(initial_key),
+            command_verification_key_store: Some(store_path.to_path_buf()),
+            ..Default::default()
+        })
+    }
+
+    fn raw_rotation(
+        new_key: ed25519_dalek::VerifyingKey,
+        revoked_key: Option<ed25519_dalek::VerifyingKey>,
+    ) -> Vec<u8> {
+        use protobuf::Message as _;
+
+        let mut rotation = rrg_proto::rotate_command_verification_key::Rotation::new();
+        rotation.mut_new_key().set_ed25519_public_key(new_key.to_bytes().to_vec());
+        if let Some(revoked_key) = revoked_key {
+            rotation.set_revoked_ed25519_public_key(revoked_key.to_bytes().to_vec());
+        }
+
+        rotation.write_to_bytes()
+            .unwrap()
+    }
+
+    fn args(
+        signing_key: &ed25519_dalek::SigningKey,
+        new_key: ed25519_dalek::VerifyingKey,
+        revoked_key: Option<ed25519_dalek::VerifyingKey>,
+    ) -> Args {
+        use crate::request::Args as _;
+
+        let raw_rotation = raw_rotation(new_key, revoked_key);
+        let signature = signing_key.sign(&[SIGNATURE_CONTEXT, &raw_rotation].concat());
+
+        let mut args = rrg_proto::rotate_command_verification_key::Args::new();
+        args.set_rotation_ed25519_signature(signature.to_vec());
+        args.set_rotation(raw_rotation);
+
+        Args::from_proto(args)
+            .unwrap()
+    }
+
+    fn signing_key() -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn handle_rotation() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let store_path = tempdir.path().join("keys");
+
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+
+        let mut session = session(initial_signing_key.verifying_key(), &store_path);
+        handle(&mut session, args(&initial_signing_key, new_signing_key.verifying_key(), None))
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.trusted_keys, vec! {
+            initial_signing_key.verifying_key(),
+            new_signing_key.verifying_key(),
+        });
+        assert!(item.revoked_keys.is_empty());
+
+        // We simulate a restart with a fresh session: the new key should still
+        // be trusted and able to perform further rotations.
+        let newer_signing_key = signing_key();
+
+        let mut session = self::session(initial_signing_key.verifying_key(), &store_path);
+        handle(&mut session, args(&new_signing_key, newer_signing_key.verifying_key(), None))
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.trusted_keys.len(), 3);
+    }
+
+    #[test]
+    fn handle_revocation() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let store_path = tempdir.path().join("keys");
+
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+
+        let mut session = session(initial_signing_key.verifying_key(), &store_path);
+        handle(&mut session, args(
+            &new_signing_key,
+            new_signing_key.verifying_key(),
+            None,
+        )).unwrap_err();
+
+        handle(&mut session, args(
+            &initial_signing_key,
+            new_signing_key.verifying_key(),
+            Some(initial_signing_key.verifying_key()),
+        )).unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.trusted_keys, vec![new_signing_key.verifying_key()]);
+        assert_eq!(item.revoked_keys, vec![initial_signing_key.verifying_key()]);
+
+        // After a restart the revoked key must not be trusted, even though it
+        // is still given in the agent arguments.
+        let mut session = self::session(initial_signing_key.verifying_key(), &store_path);
+        assert!(handle(&mut session, args(
+            &initial_signing_key,
+            signing_key().verifying_key(),
+            Some(new_signing_key.verifying_key()),
+        )).is_err());
+        assert_eq!(session.reply_count(), 0);
+
+        let keys = KeySet::load(Some(initial_signing_key.verifying_key()), &store_path)
+            .unwrap();
+        let trusted_keys = keys.trusted_keys(std::time::SystemTime::now())
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(trusted_keys, vec![new_signing_key.verifying_key()]);
+    }
+
+    #[test]
+    fn handle_revoked_key_re_added() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let store_path = tempdir.path().join("keys");
+
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+
+        let mut session = session(initial_signing_key.verifying_key(), &store_path);
+        handle(&mut session, args(
+            &initial_signing_key,
+            new_signing_key.verifying_key(),
+            Some(initial_signing_key.verifying_key()),
+        )).unwrap();
+
+        assert!(handle(&mut session, args(
+            &new_signing_key,
+            initial_signing_key.verifying_key(),
+            None,
+        )).is_err());
+        assert_eq!(session.reply_count(), 1);
+    }
+
+    #[test]
+    fn handle_untrusted_signature() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let store_path = tempdir.path().join("keys");
+
+        let initial_signing_key = signing_key();
+        let untrusted_signing_key = signing_key();
+
+        let mut session = session(initial_signing_key.verifying_key(), &store_path);
+        assert!(handle(&mut session, args(
+            &untrusted_signing_key,
+            untrusted_signing_key.verifying_key(),
+            Some(initial_signing_key.verifying_key()),
+        )).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+        assert!(!store_path.exists());
+    }
+
+    #[test]
+    fn handle_signature_without_context() {
+        use crate::request::Args as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let store_path = tempdir.path().join("keys");
+
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+
+        // Commands are signed as they are, so this is what a signed command
+        // that parses as a rotation looks like.
+        let raw_rotation = raw_rotation(new_signing_key.verifying_key(), None);
+        let mut args = rrg_proto::rotate_command_verification_key::Args::new();
+        args.set_rotation_ed25519_signature(initial_signing_key.sign(&raw_rotation).to_vec());
+        args.set_rotation(raw_rotation);
+
+        let mut session = session(initial_signing_key.verifying_key(), &store_path);
+        assert!(handle(&mut session, Args::from_proto(args).unwrap()).is_err());
+
+        assert_eq!(session.reply_count(), 0);
+        assert!(!store_path.exists());
+    }
+
+    #[test]
+    fn handle_missing_key_store() {
+        let signing_key = signing_key();
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            command_verification_key: Some(signing_key.verifying_key()),
+            ..Default::default()
+        });
+
+        let error = handle(&mut session, args(&signing_key, signing_key.verifying_key(), None))
+            .unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionUnavailable);
+    }
+
+    #[test]
+    fn args_from_proto_invalid_new_key() {
+        use crate::request::Args as _;
+        use protobuf::Message as _;
+
+        let signing_key = signing_key();
+
+        let mut rotation = rrg_proto::rotate_command_verification_key::Rotation::new();
+        rotation.mut_new_key().set_ed25519_public_key(b"foo".to_vec());
+
+        let raw_rotation = rotation.write_to_bytes()
+            .unwrap();
+
+        let mut args = rrg_proto::rotate_command_verification_key::Args::new();
+        args.set_rotation_ed25519_signature(signing_key.sign(&raw_rotation).to_vec());
+        args.set_rotation(raw_rotation);
+
+        assert!(Args::from_proto(args).is_err());
+    }
+}
@@ -0,0 +1,659 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+/// Default limit on the size of files to scan.
+const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Arguments of the `scan_files_yara` action.
+pub struct Args {
+    /// Compiled rules to scan files with.
+    rules: yara_x::Rules,
+    /// Paths to files to scan.
+    paths: Vec<PathBuf>,
+    /// Glob patterns of paths to files to scan.
+    path_globs: Vec<PathBuf>,
+    /// Maximum size of a file to scan.
+    max_file_size: u64,
+    /// Timeout of scanning a single file.
+    timeout: Option<std::time::Duration>,
+    /// Unicode normalization form to apply to reported paths.
+    normalize_unicode: crate::path::Normalization,
+}
+
+/// A result of the `scan_files_yara` action.
+#[derive(Debug)]
+enum Item {
+    /// Rule that matched a file.
+    RuleMatch(RuleMatch),
+    /// File that was not (fully) scanned.
+    SkippedFile {
+        /// Path to the file.
+        path: PathBuf,
+        /// Reason why the file was skipped.
+        reason: SkipReason,
+        /// Size of the file (if known).
+        size: Option<u64>,
+        /// Description of the error that occurred (if any).
+        error: Option<String>,
+    },
+}
+
+impl Item {
+
+    /// Normalizes the path of the file the item refers to.
+    fn normalize_unicode(&mut self, normalization: crate::path::Normalization) {
+        let path = match self {
+            Item::RuleMatch(rule_match) => &mut rule_match.path,
+            Item::SkippedFile { path, .. } => path,
+        };
+
+        *path = normalization.apply(std::mem::take(path));
+    }
+}
+
+/// Rule that matched a file.
+#[derive(Debug)]
+struct RuleMatch {
+    /// Path to the file.
+    path: PathBuf,
+    /// SHA-256 digest of the file contents.
+    sha256: [u8; 32],
+    /// Details of the match.
+    rule: crate::yara::RuleMatch,
+}
+
+/// Reason why a file was skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SkipReason {
+    /// The file is bigger than the limit.
+    TooLarge,
+    /// The scan did not finish within the timeout.
+    Timeout,
+    /// The file could not be read or scanned.
+    Error,
+}
+
+/// Handles invocations of the `scan_files_yara` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut paths = args.paths;
+    paths.extend(crate::glob::expand_allowed(&*session, &args.path_globs));
+
+    // The rules are compiled when parsing the arguments, so all that is left
+    // to validate the request is to resolve the files to scan.
+    if session.is_dry_run() {
+        return session.reply(estimate(&paths, args.max_file_size));
+    }
+
+    let mut scanner = crate::yara::scanner(&args.rules, args.timeout);
+    let mmap_threshold = session.args().mmap_threshold;
+
+    for path in paths {
+        for mut item in scan_file(&mut scanner, &path, args.max_file_size, mmap_threshold) {
+            item.normalize_unicode(args.normalize_unicode);
+            session.reply(item)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Estimates the cost of scanning the files at the given paths.
+///
+/// The estimate is based on the metadata of the files, no contents are read.
+fn estimate(paths: &[PathBuf], max_file_size: u64) -> crate::dry_run::Estimate {
+    let mut estimate = crate::dry_run::Estimate {
+        item_count: paths.len() as u64,
+        ..Default::default()
+    };
+
+    let mut too_large_count = 0;
+    let mut error_count = 0;
+
+    for path in paths {
+        match std::fs::metadata(path) {
+            Ok(metadata) if !metadata.is_file() => error_count += 1,
+            Ok(metadata) if metadata.len() > max_file_size => too_large_count += 1,
+            Ok(metadata) => estimate.bytes += metadata.len(),
+            Err(_) => error_count += 1,
+        }
+    }
+
+    if too_large_count > 0 {
+        estimate.notes.push(format!("{too_large_count} files too large to scan"));
+    }
+    if error_count > 0 {
+        estimate.notes.push(format!("{error_count} files not readable"));
+    }
+
+    estimate
+}
+
+/// Scans the file at the given path and returns the resulting items.
+fn scan_file(
+    scanner: &mut yara_x::Scanner<'_>,
+    path: &Path,
+    max_file_size: u64,
+    mmap_threshold: Option<u64>,
+) -> Vec<Item> {
+    let skipped = |reason, size, error: Option<String>| Item::SkippedFile {
+        path: path.to_path_buf(),
+        reason,
+        size,
+        error,
+    };
+
+    let data = match read_file(path, max_file_size, mmap_threshold) {
+        Ok(ReadFile::Data(data)) => data,
+        Ok(ReadFile::TooLarge(size)) => {
+            return vec![skipped(SkipReason::TooLarge, Some(size), None)];
+        }
+        Err(error) => {
+            warn!("failed to read '{}' for scanning: {}", path.display(), error);
+            return vec![skipped(SkipReason::Error, None, Some(error.to_string()))];
+        }
+    };
+    let size = Some(data.len() as u64);
+
+    let results = match scanner.scan(&data) {
+        Ok(results) => results,
+        Err(yara_x::ScanError::Timeout) => {
+            return vec![skipped(SkipReason::Timeout, size, None)];
+        }
+        Err(error) => {
+            warn!("failed to scan '{}': {}", path.display(), error);
+            return vec![skipped(SkipReason::Error, size, Some(error.to_string()))];
+        }
+    };
+
+    let mut rules = results.matching_rules().peekable();
+    if rules.peek().is_none() {
+        return Vec::new();
+    }
+
+    let sha256 = {
+        use sha2::Digest as _;
+        sha2::Sha256::digest(&*data).into()
+    };
+
+    rules.map(|rule| Item::RuleMatch(RuleMatch {
+        path: path.to_path_buf(),
+        sha256,
+        rule: crate::yara::RuleMatch::from(rule),
+    })).collect()
+}
+
+/// Outcome of reading a file to scan.
+enum ReadFile {
+    /// Contents of the file.
+    Data(Contents),
+    /// The file is bigger than the limit (with its actual size).
+    TooLarge(u64),
+}
+
+/// Contents of a file to scan.
+enum Contents {
+    /// Contents read into a buffer.
+    Buffered(Vec<u8>),
+    /// Contents mapped into memory.
+    Mapped(memmap2::Mmap),
+}
+
+impl std::ops::Deref for Contents {
+
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Contents::Buffered(data) => data,
+            Contents::Mapped(map) => map,
+        }
+    }
+}
+
+/// Reads the whole file at the given path unless it exceeds the size limit.
+///
+/// The limit is verified against the data actually read rather than only the
+/// metadata so that files growing while being read are not a problem.
+///
+/// Files at least `mmap_threshold` bytes big are memory-mapped instead. The
+/// mapping is bounded by the length of the file at the time it is created, so
+/// growth of the file is ignored.
+fn read_file(
+    path: &Path,
+    max_file_size: u64,
+    mmap_threshold: Option<u64>,
+) -> std::io::Result<ReadFile> {
+    use std::io::Read as _;
+
+    let file = std::fs::File::open(path)?;
+
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a regular file"));
+    }
+    if metadata.len() > max_file_size {
+        return Ok(ReadFile::TooLarge(metadata.len()));
+    }
+
+    if crate::mmap::should_map(path, metadata.len(), mmap_threshold) {
+        match crate::mmap::map(&file, metadata.len()) {
+            Ok(map) => return Ok(ReadFile::Data(Contents::Mapped(map))),
+            Err(error) => {
+                warn!("failed to map '{}', falling back to reading: {error}", path.display());
+            }
+        }
+    }
+
+    let mut data = Vec::with_capacity(metadata.len() as usize);
+    file.take(max_file_size.saturating_add(1)).read_to_end(&mut data)?;
+
+    if data.len() as u64 > max_file_size {
+        return Ok(ReadFile::TooLarge(data.len() as u64));
+    }
+
+    Ok(ReadFile::Data(Contents::Buffered(data)))
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::scan_files_yara::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let rules_compiled = if proto.has_rules_compiled() {
+            Some(proto.take_rules_compiled())
+        } else {
+            None
+        };
+        let rules = crate::yara::parse_rules(proto.rules_source(), rules_compiled)?;
+
+        let paths = proto.take_paths().into_iter()
+            .map(crate::path::from_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("paths", error))?;
+
+        let mut path_globs = Vec::new();
+        match proto.take_path_glob() {
+            path_glob if path_glob.is_empty() => (),
+            path_glob => path_globs.push(PathBuf::from(path_glob)),
+        }
+        path_globs.extend(proto.take_path_globs().into_iter().map(PathBuf::from));
+
+        let max_file_size = match proto.max_file_size() {
+            0 => DEFAULT_MAX_FILE_SIZE,
+            max_file_size => max_file_size,
+        };
+
+        let timeout = if proto.has_timeout() {
+            let timeout = std::time::Duration::try_from(proto.take_timeout())
+                .map_err(|error| ParseArgsError::invalid_field("timeout", error))?;
+            Some(timeout)
+        } else {
+            None
+        };
+
+        Ok(Args {
+            rules,
+            paths,
+            path_globs,
+            max_file_size,
+            timeout,
+            normalize_unicode: proto.normalize_unicode().into(),
+        })
+    }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        // Note that only the glob itself is checked here, the paths it expands
+        // to are checked during the expansion.
+        self.paths.iter()
+            .chain(self.path_globs.iter())
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::scan_files_yara::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::scan_files_yara::skipped_file::Reason;
+
+        let mut proto = rrg_proto::scan_files_yara::Result::new();
+
+        match self {
+            Item::RuleMatch(rule_match) => {
+                proto.set_path(crate::path::into_proto(rule_match.path));
+
+                let match_proto = proto.mut_rule_match();
+                match_proto.set_sha256(rule_match.sha256.to_vec());
+                match_proto.set_rule(rule_match.rule.into());
+            }
+            Item::SkippedFile { path, reason, size, error } => {
+                proto.set_path(crate::path::into_proto(path));
+
+                let skipped_proto = proto.mut_skipped_file();
+                skipped_proto.set_reason(match reason {
+                    SkipReason::TooLarge => Reason::TOO_LARGE,
+                    SkipReason::Timeout => Reason::TIMEOUT,
+                    SkipReason::Error => Reason::ERROR,
+                });
+                if let Some(size) = size {
+                    skipped_proto.set_size(size);
+                }
+                if let Some(error) = error {
+                    skipped_proto.set_error(error);
+                }
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const RULES_SOURCE: &str = r#"
+rule magic : foo bar {
+    strings:
+        $magic = "RRG-MAGIC-STRING"
+    condition:
+        $magic
+}
+"#;
+
+    fn args(paths: Vec<PathBuf>) -> Args {
+        Args {
+            rules: yara_x::compile(RULES_SOURCE).unwrap(),
+            paths,
+            path_globs: vec![],
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            timeout: None,
+            normalize_unicode: crate::path::Normalization::None,
+        }
+    }
+
+    #[test]
+    fn handle_match_offsets() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"0123456789RRG-MAGIC-STRING...RRG-MAGIC-STRING")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(vec![path.clone()])).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let Item::RuleMatch(rule_match) = session.reply::<Item>(0) else {
+            panic!("unexpected item");
+        };
+        assert_eq!(rule_match.path, path);
+        assert_eq!(rule_match.rule.name, "magic");
+        assert_eq!(rule_match.rule.tags, vec!["foo", "bar"]);
+        assert!(!rule_match.rule.string_matches_truncated);
+
+        let offsets = rule_match.rule.string_matches.iter()
+            .map(|string_match| string_match.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![10, 29]);
+
+        for string_match in &rule_match.rule.string_matches {
+            assert_eq!(string_match.identifier, "$magic");
+            assert_eq!(string_match.len, 16);
+            assert_eq!(string_match.data, b"RRG-MAGIC-STRING");
+        }
+
+        use sha2::Digest as _;
+        let sha256: [u8; 32] = sha2::Sha256::digest(std::fs::read(&path).unwrap()).into();
+        assert_eq!(rule_match.sha256, sha256);
+    }
+
+    #[test]
+    fn handle_dry_run() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"RRG-MAGIC-STRING")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar"), vec![0xAB; 1024])
+            .unwrap();
+
+        let mut args = args(vec![tempdir.path().join("nonexistent")]);
+        args.path_globs = vec![tempdir.path().join("*")];
+        args.max_file_size = 512;
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_dry_run();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let estimate = session.reply::<crate::dry_run::Estimate>(0);
+        assert_eq!(estimate.item_count, 3);
+        assert_eq!(estimate.bytes, 16);
+        assert_eq!(estimate.notes, vec![
+            "1 files too large to scan",
+            "1 files not readable",
+        ]);
+    }
+
+    #[test]
+    fn scan_file_mapped() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"0123456789RRG-MAGIC-STRING...RRG-MAGIC-STRING")
+            .unwrap();
+
+        let args = args(vec![path.clone()]);
+        let mut scanner = crate::yara::scanner(&args.rules, args.timeout);
+
+        let mapped = scan_file(&mut scanner, &path, args.max_file_size, Some(0));
+        let buffered = scan_file(&mut scanner, &path, args.max_file_size, None);
+        assert_eq!(mapped.len(), 1);
+        assert_eq!(buffered.len(), 1);
+
+        let (Item::RuleMatch(mapped), Item::RuleMatch(buffered)) = (&mapped[0], &buffered[0]) else {
+            panic!("unexpected items");
+        };
+        assert_eq!(mapped.sha256, buffered.sha256);
+
+        let offsets = |rule_match: &RuleMatch| {
+            rule_match.rule.string_matches.iter()
+                .map(|string_match| string_match.offset)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offsets(mapped), vec![10, 29]);
+        assert_eq!(offsets(buffered), vec![10, 29]);
+    }
+
+    #[test]
+    fn handle_no_match() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"nothing to see here")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(vec![path])).is_ok());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_too_large() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let small_path = tempdir.path().join("small");
+        std::fs::write(&small_path, b"RRG-MAGIC-STRING")
+            .unwrap();
+
+        let large_path = tempdir.path().join("large");
+        std::fs::write(&large_path, b"RRG-MAGIC-STRING 0123456789")
+            .unwrap();
+
+        let mut args = args(vec![small_path.clone(), large_path.clone()]);
+        args.max_file_size = 20;
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 2);
+        assert!(matches! {
+            session.reply::<Item>(0),
+            Item::RuleMatch(RuleMatch { path, .. }) if *path == small_path
+        });
+        assert!(matches! {
+            session.reply::<Item>(1),
+            Item::SkippedFile {
+                path,
+                reason: SkipReason::TooLarge,
+                size: Some(27),
+                ..
+            } if *path == large_path
+        });
+    }
+
+    #[test]
+    fn handle_not_found() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(vec![path.clone()])).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+        assert!(matches! {
+            session.reply::<Item>(0),
+            Item::SkippedFile { path: item_path, reason: SkipReason::Error, .. } if *item_path == path
+        });
+    }
+
+    #[test]
+    fn handle_path_glob() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("a"))
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("b"))
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("a").join("foo.bin"), b"RRG-MAGIC-STRING")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("bar.bin"), b"RRG-MAGIC-STRING")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("bar.txt"), b"RRG-MAGIC-STRING")
+            .unwrap();
+
+        let mut args = args(vec![]);
+        args.path_globs = vec![tempdir.path().join("*").join("*.bin")];
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let paths = session.replies::<Item>()
+            .map(|item| match item {
+                Item::RuleMatch(rule_match) => rule_match.path.clone(),
+                _ => panic!("unexpected item"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec! {
+            tempdir.path().join("a").join("foo.bin"),
+            tempdir.path().join("b").join("bar.bin"),
+        });
+    }
+
+    #[test]
+    fn handle_path_glob_denied_paths() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("a"))
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("b"))
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("a").join("foo.bin"), b"RRG-MAGIC-STRING")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("bar.bin"), b"RRG-MAGIC-STRING")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("baz.bin"), b"RRG-MAGIC-STRING")
+            .unwrap();
+
+        let mut args = args(vec![]);
+        args.path_globs = vec![tempdir.path().join("*").join("*.bin")];
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            denied_paths: vec! {
+                tempdir.path().join("a").display().to_string().parse().unwrap(),
+                tempdir.path().join("b").join("baz.bin").display().to_string().parse().unwrap(),
+            },
+            ..Default::default()
+        });
+        session.set_action(crate::request::Action::ScanFilesYara);
+        assert!(handle(&mut session, args).is_ok());
+
+        let paths = session.replies::<Item>()
+            .map(|item| match item {
+                Item::RuleMatch(rule_match) => rule_match.path.clone(),
+                _ => panic!("unexpected item"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![tempdir.path().join("b").join("bar.bin")]);
+    }
+
+    #[test]
+    fn from_proto_rules_compiled() {
+        use crate::request::Args as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"RRG-MAGIC-STRING")
+            .unwrap();
+
+        let rules = yara_x::compile(RULES_SOURCE)
+            .unwrap();
+
+        let mut proto = rrg_proto::scan_files_yara::Args::new();
+        proto.set_rules_compiled(rules.serialize().unwrap());
+        proto.mut_paths().push(path.clone().into());
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.max_file_size, DEFAULT_MAX_FILE_SIZE);
+        assert_eq!(args.timeout, None);
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+        assert!(matches!(session.reply::<Item>(0), Item::RuleMatch(_)));
+    }
+}
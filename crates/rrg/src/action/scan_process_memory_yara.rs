@@ -0,0 +1,552 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use log::warn;
+
+/// Default limit on the size of regions to scan.
+const DEFAULT_MAX_REGION_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Arguments of the `scan_process_memory_yara` action.
+pub struct Args {
+    /// Compiled rules to scan process memory with.
+    rules: yara_x::Rules,
+    /// Processes to scan.
+    pids: Pids,
+    /// Whether to scan the agent process itself.
+    include_self: bool,
+    /// Whether to scan only private anonymous regions.
+    private_only: bool,
+    /// Maximum size of a region to scan.
+    max_region_size: u64,
+    /// Timeout of scanning a single region.
+    timeout: Option<std::time::Duration>,
+}
+
+/// Set of processes to scan.
+#[derive(Debug, PartialEq, Eq)]
+enum Pids {
+    /// All processes on the system.
+    All,
+    /// Only processes with the given identifiers.
+    Some(Vec<u32>),
+}
+
+/// A result of the `scan_process_memory_yara` action.
+#[derive(Debug)]
+enum Item {
+    /// Rule that matched a region of the process memory.
+    RegionMatch {
+        /// Identifier of the process.
+        pid: u32,
+        /// Region that the rule matched.
+        region: ospect::proc::Region,
+        /// Details of the match.
+        rule: crate::yara::RuleMatch,
+    },
+    /// Process that was not (fully) scanned.
+    SkippedProcess {
+        /// Identifier of the process.
+        pid: u32,
+        /// Reason why the process was skipped.
+        reason: ProcessSkipReason,
+        /// Error that occurred (if any).
+        error: Option<std::io::Error>,
+    },
+    /// Region of the process memory that was not (fully) scanned.
+    SkippedRegion {
+        /// Identifier of the process.
+        pid: u32,
+        /// Region that was skipped.
+        region: ospect::proc::Region,
+        /// Reason why the region was skipped.
+        reason: RegionSkipReason,
+        /// Description of the error that occurred (if any).
+        error: Option<String>,
+    },
+}
+
+/// Reason why a process was skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProcessSkipReason {
+    /// The process exited before or during the scan.
+    Exited,
+    /// The process is the agent itself.
+    Agent,
+    /// The process memory could not be accessed.
+    Error,
+}
+
+/// Reason why a region was skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RegionSkipReason {
+    /// The region is bigger than the limit.
+    TooLarge,
+    /// The scan did not finish within the timeout.
+    Timeout,
+    /// The region could not be read or scanned.
+    Error,
+}
+
+/// Handles invocations of the `scan_process_memory_yara` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let pids = match &args.pids {
+        Pids::All => ospect::proc::ids()
+            .map_err(crate::session::Error::action)?
+            .filter_map(|pid| match pid {
+                Ok(pid) => Some(pid),
+                Err(error) => {
+                    warn!("failed to obtain process identifier: {}", error);
+                    None
+                }
+            })
+            .collect(),
+        Pids::Some(pids) => pids.clone(),
+    };
+
+    let mut scanner = crate::yara::scanner(&args.rules, args.timeout);
+
+    for pid in pids {
+        if pid == std::process::id() && !args.include_self {
+            session.reply(Item::SkippedProcess {
+                pid,
+                reason: ProcessSkipReason::Agent,
+                error: None,
+            })?;
+            continue;
+        }
+
+        scan_process(session, &mut scanner, pid, &args)?;
+    }
+
+    Ok(())
+}
+
+/// Scans memory of the process with the given identifier.
+fn scan_process<S>(
+    session: &mut S,
+    scanner: &mut yara_x::Scanner<'_>,
+    pid: u32,
+    args: &Args,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let skipped_process = |error: std::io::Error| Item::SkippedProcess {
+        pid,
+        reason: if process_exists(pid) {
+            ProcessSkipReason::Error
+        } else {
+            ProcessSkipReason::Exited
+        },
+        error: Some(error),
+    };
+
+    let memory = match ospect::proc::Memory::open(pid) {
+        Ok(memory) => memory,
+        Err(error) => return session.reply(skipped_process(error)),
+    };
+
+    let regions = match memory.regions() {
+        Ok(regions) => regions,
+        Err(error) => return session.reply(skipped_process(error)),
+    };
+
+    for region in regions {
+        if !region.is_readable() {
+            continue;
+        }
+        if args.private_only && (region.is_shared() || region.path().is_some()) {
+            continue;
+        }
+
+        if region.size() > args.max_region_size {
+            session.reply(Item::SkippedRegion {
+                pid,
+                region,
+                reason: RegionSkipReason::TooLarge,
+                error: None,
+            })?;
+            continue;
+        }
+
+        let data = match read_region(&memory, &region) {
+            Ok(data) => data,
+            Err(error) => {
+                // A failure to read is the first sign of the process having
+                // exited in the middle of the scan, in which case there is no
+                // point in trying the remaining regions.
+                if !process_exists(pid) || memory.regions().is_ok_and(|regions| regions.is_empty()) {
+                    return session.reply(Item::SkippedProcess {
+                        pid,
+                        reason: ProcessSkipReason::Exited,
+                        error: Some(error),
+                    });
+                }
+
+                session.reply(Item::SkippedRegion {
+                    pid,
+                    region,
+                    reason: RegionSkipReason::Error,
+                    error: Some(error.to_string()),
+                })?;
+                continue;
+            }
+        };
+
+        let results = match scanner.scan(&data) {
+            Ok(results) => results,
+            Err(yara_x::ScanError::Timeout) => {
+                session.reply(Item::SkippedRegion {
+                    pid,
+                    region,
+                    reason: RegionSkipReason::Timeout,
+                    error: None,
+                })?;
+                continue;
+            }
+            Err(error) => {
+                warn!("failed to scan region at {:#x} of process {}: {}", region.addr(), pid, error);
+                session.reply(Item::SkippedRegion {
+                    pid,
+                    region,
+                    reason: RegionSkipReason::Error,
+                    error: Some(error.to_string()),
+                })?;
+                continue;
+            }
+        };
+
+        for rule in results.matching_rules() {
+            session.reply(Item::RegionMatch {
+                pid,
+                region: region.clone(),
+                rule: crate::yara::RuleMatch::from(rule),
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the whole memory region of the process.
+fn read_region(
+    memory: &ospect::proc::Memory,
+    region: &ospect::proc::Region,
+) -> std::io::Result<Vec<u8>> {
+    let mut buf = vec![0; region.size() as usize];
+    let mut offset = 0;
+
+    while offset < buf.len() {
+        let len = memory.read(region.addr() + offset as u64, &mut buf[offset..])?;
+        if len == 0 {
+            break;
+        }
+
+        offset += len;
+    }
+
+    // Nothing can be read from processes that are gone (but not necessarily
+    // reaped yet).
+    if offset == 0 && !buf.is_empty() {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    buf.truncate(offset);
+
+    Ok(buf)
+}
+
+/// Verifies whether the process with the given identifier is still running.
+fn process_exists(pid: u32) -> bool {
+    match ospect::proc::ids() {
+        Ok(mut pids) => pids.any(|id| id.is_ok_and(|id| id == pid)),
+        // If we cannot tell, it is safer to assume the process is there so
+        // that the actual error is reported.
+        Err(_) => true,
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::scan_process_memory_yara::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let rules_compiled = if proto.has_rules_compiled() {
+            Some(proto.take_rules_compiled())
+        } else {
+            None
+        };
+        let rules = crate::yara::parse_rules(proto.rules_source(), rules_compiled)?;
+
+        let pids = if proto.all_processes() {
+            Pids::All
+        } else {
+            Pids::Some(proto.take_pids())
+        };
+
+        let max_region_size = match proto.max_region_size() {
+            0 => DEFAULT_MAX_REGION_SIZE,
+            max_region_size => max_region_size,
+        };
+
+        let timeout = if proto.has_timeout() {
+            let timeout = std::time::Duration::try_from(proto.take_timeout())
+                .map_err(|error| ParseArgsError::invalid_field("timeout", error))?;
+            Some(timeout)
+        } else {
+            None
+        };
+
+        Ok(Args {
+            rules,
+            pids,
+            include_self: proto.include_self(),
+            private_only: proto.private_only(),
+            max_region_size,
+            timeout,
+        })
+    }
+}
+
+/// Converts a region to its protobuf representation.
+fn region_proto(region: ospect::proc::Region) -> rrg_proto::scan_process_memory_yara::Region {
+    let mut proto = rrg_proto::scan_process_memory_yara::Region::new();
+    proto.set_address(region.addr());
+    proto.set_size(region.size());
+    if let Some(path) = region.path() {
+        proto.set_path(crate::path::into_proto(path.to_path_buf()));
+    }
+
+    proto
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::scan_process_memory_yara::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::scan_process_memory_yara::{skipped_process, skipped_region};
+
+        let mut proto = rrg_proto::scan_process_memory_yara::Result::new();
+
+        match self {
+            Item::RegionMatch { pid, region, rule } => {
+                proto.set_pid(pid);
+
+                let match_proto = proto.mut_region_match();
+                match_proto.set_region(region_proto(region));
+                match_proto.set_rule(rule.into());
+            }
+            Item::SkippedProcess { pid, reason, error } => {
+                proto.set_pid(pid);
+
+                let skipped_proto = proto.mut_skipped_process();
+                skipped_proto.set_reason(match reason {
+                    ProcessSkipReason::Exited => skipped_process::Reason::EXITED,
+                    ProcessSkipReason::Agent => skipped_process::Reason::AGENT,
+                    ProcessSkipReason::Error => skipped_process::Reason::ERROR,
+                });
+                if let Some(error) = error {
+                    skipped_proto.set_error(error.to_string());
+                }
+            }
+            Item::SkippedRegion { pid, region, reason, error } => {
+                proto.set_pid(pid);
+
+                let skipped_proto = proto.mut_skipped_region();
+                skipped_proto.set_region(region_proto(region));
+                skipped_proto.set_reason(match reason {
+                    RegionSkipReason::TooLarge => skipped_region::Reason::TOO_LARGE,
+                    RegionSkipReason::Timeout => skipped_region::Reason::TIMEOUT,
+                    RegionSkipReason::Error => skipped_region::Reason::ERROR,
+                });
+                if let Some(error) = error {
+                    skipped_proto.set_error(error);
+                }
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Rule matching the marker that test processes keep in their memory.
+    const RULES_SOURCE: &str = r#"
+rule marker {
+    strings:
+        $marker = "RRG-MEMORY-MARKER"
+    condition:
+        $marker
+}
+"#;
+
+    fn args(pids: Pids) -> Args {
+        Args {
+            rules: yara_x::compile(RULES_SOURCE).unwrap(),
+            pids,
+            include_self: false,
+            private_only: false,
+            max_region_size: DEFAULT_MAX_REGION_SIZE,
+            timeout: None,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_child_heap() {
+        use std::io::BufRead as _;
+
+        // The marker is concatenated at runtime so that it does not appear in
+        // the command line (which lands on the stack) but only on the heap of
+        // the shell.
+        let mut child = std::process::Command::new("sh")
+            .arg("-c")
+            .arg("x=RRG-MEMORY; x=\"${x}-MARKER\"; echo ready; read y")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut line = String::new();
+        std::io::BufReader::new(child.stdout.as_mut().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        assert_eq!(line, "ready\n");
+
+        let mut args = args(Pids::Some(vec![child.id()]));
+        args.private_only = true;
+
+        let mut session = crate::session::FakeSession::new();
+        let result = handle(&mut session, args);
+
+        drop(child.stdin.take());
+        child.wait()
+            .unwrap();
+
+        assert!(result.is_ok());
+
+        let mut matches = session.replies::<Item>().filter_map(|item| match item {
+            Item::RegionMatch { pid, region, rule } => Some((*pid, region, rule)),
+            _ => None,
+        });
+
+        let (pid, region, rule) = matches.next()
+            .expect("no matches");
+        assert_eq!(pid, child.id());
+        assert_eq!(rule.name, "marker");
+
+        assert!(region.is_writable());
+        assert!(!region.is_shared());
+        assert_eq!(region.path(), None);
+
+        let string_match = &rule.string_matches[0];
+        assert_eq!(string_match.data, b"RRG-MEMORY-MARKER");
+        assert!(string_match.offset + string_match.len <= region.size());
+        assert!(region.addr() + string_match.offset > 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_self_included() {
+        let marker = ["RRG-MEMORY", "-MARKER"].concat().into_bytes();
+
+        let mut args = args(Pids::Some(vec![std::process::id()]));
+        args.include_self = true;
+        args.private_only = true;
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let marker_addr = marker.as_ptr() as u64;
+
+        // The marker can be in memory multiple times (e.g. in the buffers of
+        // the scanner), but one of the matches has to be the one we created.
+        assert!(session.replies::<Item>().any(|item| match item {
+            Item::RegionMatch { region, rule, .. } => {
+                rule.string_matches.iter().any(|string_match| {
+                    region.addr() + string_match.offset == marker_addr
+                })
+            }
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn handle_self_skipped() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(Pids::Some(vec![std::process::id()]))).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+        assert!(matches! {
+            session.reply::<Item>(0),
+            Item::SkippedProcess { reason: ProcessSkipReason::Agent, .. }
+        });
+    }
+
+    #[test]
+    fn handle_exited() {
+        let mut child = std::process::Command::new(if cfg!(target_family = "windows") {
+            "cmd"
+        } else {
+            "true"
+        });
+        if cfg!(target_family = "windows") {
+            child.args(["/c", "exit"]);
+        }
+
+        let mut child = child.spawn()
+            .unwrap();
+        child.wait()
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(Pids::Some(vec![child.id()]))).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+        assert!(matches! {
+            session.reply::<Item>(0),
+            Item::SkippedProcess { reason: ProcessSkipReason::Exited, .. }
+        });
+    }
+
+    #[test]
+    fn from_proto_all_processes() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::scan_process_memory_yara::Args::new();
+        proto.set_rules_source(String::from(RULES_SOURCE));
+        proto.set_all_processes(true);
+        proto.mut_pids().push(1337);
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.pids, Pids::All);
+        assert_eq!(args.max_region_size, DEFAULT_MAX_REGION_SIZE);
+        assert!(!args.include_self);
+    }
+
+    #[test]
+    fn from_proto_rules_source_invalid() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::scan_process_memory_yara::Args::new();
+        proto.set_rules_source(String::from("rule foo { condition: bar }"));
+
+        let error = match Args::from_proto(proto) {
+            Ok(_) => panic!("unexpected success"),
+            Err(error) => error,
+        };
+        assert!(error.to_string().contains("unknown identifier `bar`"));
+    }
+}
@@ -0,0 +1,239 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the agent mode change action.
+
+/// Arguments of the `set_agent_mode` action.
+pub struct Args {
+    /// Serialized mode change message (verified against the signature).
+    raw_mode_change: Vec<u8>,
+    /// Signature of the serialized mode change message.
+    ed25519_signature: ed25519_dalek::Signature,
+    /// Mode to switch the agent to.
+    mode: crate::mode::Mode,
+}
+
+/// Result of the `set_agent_mode` action.
+pub struct Item {
+    /// Mode the agent was in before the change.
+    previous_mode: crate::mode::Mode,
+}
+
+/// Handles invocations of the `set_agent_mode` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use crate::request::ParseArgsError;
+
+    // The mode can stop the agent from doing any work, so the change has to be
+    // approved in the same way as signed commands.
+    let keys = match &session.args().command_verification_key_store {
+        Some(store_path) => {
+            crate::signing::KeySet::load(session.args().command_verification_key, store_path)
+                .map_err(crate::session::Error::action)?
+        }
+        None => crate::signing::KeySet::new(session.args().command_verification_key),
+    };
+
+    keys.verify(&args.raw_mode_change, &args.ed25519_signature, std::time::SystemTime::now())
+        .map_err(|error| ParseArgsError::invalid_field("mode_change", error))?;
+
+    log::info!("switching to {} mode", args.mode);
+    crate::mode::persist(session.args(), args.mode)
+        .map_err(crate::session::Error::action)?;
+
+    // The mode is switched by the main loop once the request is handled.
+    crate::mode::request_change(args.mode);
+
+    session.reply(Item {
+        previous_mode: crate::mode::current(),
+    })?;
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::set_agent_mode::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let raw_signature = proto.take_mode_change_ed25519_signature();
+
+        let ed25519_signature = ed25519_dalek::Signature::try_from(&raw_signature[..])
+            .map_err(|error| ParseArgsError::invalid_field("mode_change_ed25519_signature", error))?;
+
+        let raw_mode_change = proto.take_mode_change();
+        let mode_change = <rrg_proto::set_agent_mode::ModeChange as protobuf::Message>::parse_from_bytes(&raw_mode_change)
+            .map_err(|error| ParseArgsError::invalid_field("mode_change", error))?;
+
+        let mode = mode_change.mode.enum_value()
+            .map_err(|value| ParseArgsError::invalid_field("mode_change", ModeError { value }))?;
+
+        Ok(Args {
+            raw_mode_change,
+            ed25519_signature,
+            mode: mode.into(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::set_agent_mode::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::set_agent_mode::Result::new();
+        proto.set_previous_mode(self.previous_mode.into());
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with an unknown mode.
+#[derive(Debug)]
+struct ModeError {
+    /// Raw value of the mode.
+    value: i32,
+}
+
+impl std::fmt::Display for ModeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown agent mode: {}", self.value)
+    }
+}
+
+impl std::error::Error for ModeError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ed25519_dalek::Signer as _;
+
+    use crate::session::Session as _;
+
+    use crate::mode::{Admission, Gate, Mode};
+
+    use super::*;
+
+    fn session(
+        initial_key: ed25519_dalek::VerifyingKey,
+        state_dir: &std::path::Path,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            command_verification_key: Some(initial_key),
+            state_dir: Some(state_dir.to_path_buf()),
+            ..Default::default()
+        })
+    }
+
+    fn args(signing_key: &ed25519_dalek::SigningKey, mode: Mode) -> Args {
+        use crate::request::Args as _;
+        use protobuf::Message as _;
+
+        let mut mode_change = rrg_proto::set_agent_mode::ModeChange::new();
+        mode_change.set_mode(mode.into());
+
+        let raw_mode_change = mode_change.write_to_bytes()
+            .unwrap();
+
+        let mut args = rrg_proto::set_agent_mode::Args::new();
+        args.set_mode_change_ed25519_signature(signing_key.sign(&raw_mode_change).to_vec());
+        args.set_mode_change(raw_mode_change);
+
+        Args::from_proto(args)
+            .unwrap()
+    }
+
+    fn signing_key() -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng)
+    }
+
+    fn request(request_id: u64, action: crate::request::Action) -> crate::Request {
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_flow_id(0x1337);
+        proto.set_request_id(request_id);
+        proto.set_action(action.into());
+
+        crate::Request::try_from(proto)
+            .unwrap()
+    }
+
+    #[test]
+    fn handle_persist() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let signing_key = signing_key();
+
+        let mut session = session(signing_key.verifying_key(), tempdir.path());
+        assert!(handle(&mut session, args(&signing_key, Mode::Drain)).is_ok());
+        assert_eq!(crate::mode::load(session.args()), Mode::Drain);
+
+        assert!(handle(&mut session, args(&signing_key, Mode::Normal)).is_ok());
+        assert_eq!(crate::mode::load(session.args()), Mode::Normal);
+    }
+
+    #[test]
+    fn handle_untrusted_signature() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = session(signing_key().verifying_key(), tempdir.path());
+        assert!(handle(&mut session, args(&signing_key(), Mode::Paused)).is_err());
+        assert_eq!(crate::mode::load(session.args()), Mode::Normal);
+    }
+
+    #[test]
+    fn handle_pause_resume_queued() {
+        use crate::request::Action;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let signing_key = signing_key();
+
+        let mut session = session(signing_key.verifying_key(), tempdir.path());
+        let mut gate = Gate::new(crate::mode::load(session.args()));
+
+        // The main loop applies changes of the mode from the state directory
+        // (this way tests running in parallel do not interfere).
+        assert!(handle(&mut session, args(&signing_key, Mode::Paused)).is_ok());
+        gate.set_mode(crate::mode::load(session.args()));
+
+        assert!(matches!(gate.admit(request(1, Action::ListProcesses)), Admission::Queued));
+        assert!(matches!(gate.admit(request(2, Action::ListMounts)), Admission::Queued));
+        assert!(matches!(gate.admit(request(3, Action::SetAgentMode)), Admission::Dispatch(_)));
+        assert!(gate.next().is_none());
+
+        assert!(handle(&mut session, args(&signing_key, Mode::Normal)).is_ok());
+        gate.set_mode(crate::mode::load(session.args()));
+
+        let request_ids = std::iter::from_fn(|| gate.next())
+            .map(|admission| match admission {
+                Admission::Dispatch(request) => request.id().request_id(),
+                _ => panic!("unexpected admission"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(request_ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn args_from_proto_invalid_mode_change() {
+        use crate::request::Args as _;
+
+        let signing_key = signing_key();
+
+        let mut args = rrg_proto::set_agent_mode::Args::new();
+        args.set_mode_change_ed25519_signature(signing_key.sign(b"\xff\xff").to_vec());
+        args.set_mode_change(b"\xff\xff".to_vec());
+
+        assert!(Args::from_proto(args).is_err());
+    }
+}
@@ -0,0 +1,533 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+
+/// Default limit on the size of files to hash.
+const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Default limit on the total number of bytes read for hashing.
+const DEFAULT_BYTE_BUDGET: u64 = 1024 * 1024 * 1024;
+
+/// Arguments of the `sweep_indicators` action.
+pub struct Args {
+    /// Glob patterns of paths to files to sweep.
+    path_globs: Vec<PathBuf>,
+    /// SHA-256 digests of contents of known bad files.
+    sha256s: HashSet<[u8; 32]>,
+    /// Regular expressions matching names of known bad files.
+    name_regexes: Vec<regex::Regex>,
+    /// Maximum size of a file to hash.
+    max_file_size: u64,
+    /// Total number of bytes that can be read for hashing.
+    byte_budget: u64,
+    /// Time after which the sweep is stopped.
+    timeout: Option<std::time::Duration>,
+}
+
+/// A result of the `sweep_indicators` action.
+#[derive(Debug)]
+struct Item {
+    /// Path to the file that matched an indicator.
+    path: PathBuf,
+    /// Metadata of the file.
+    metadata: std::fs::Metadata,
+    /// SHA-256 digest of the file contents (if the file was hashed).
+    sha256: Option<[u8; 32]>,
+    /// Indicator that the file matched.
+    indicator: Indicator,
+}
+
+/// Indicator that a file matched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Indicator {
+    /// Digest of a known bad file that the file contents hashed to.
+    Sha256([u8; 32]),
+    /// Regular expression that the file name matched.
+    NameRegex(String),
+}
+
+/// Handles invocations of the `sweep_indicators` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let deadline = args.timeout.map(|timeout| std::time::Instant::now() + timeout);
+
+    let paths = crate::glob::expand_allowed(&*session, &args.path_globs);
+
+    let mut budget_left = args.byte_budget;
+    let mut unhashed_count = 0;
+
+    for path in paths {
+        session.check_limits()?;
+
+        if deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+            warn!("sweep timed out, stopping before '{}'", path.display());
+            break;
+        }
+
+        // Glob expansion does not follow symlinks and neither do we, so that
+        // the sweep does not escape the directories it was asked to look at.
+        let metadata = match std::fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                debug!("failed to stat '{}': {}", path.display(), error);
+                continue;
+            }
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        let mut indicators = Vec::new();
+
+        if let Some(name) = path.file_name() {
+            let name = name.to_string_lossy();
+            indicators.extend(args.name_regexes.iter()
+                .filter(|name_regex| name_regex.is_match(&name))
+                .map(|name_regex| Indicator::NameRegex(String::from(name_regex.as_str()))));
+        }
+
+        // There is no point in reading the file if it cannot match any digest
+        // and there is no name hit to attach the digest to. Files that are too
+        // big are not hashed at all.
+        let sha256 = if (args.sha256s.is_empty() && indicators.is_empty()) ||
+            metadata.len() > args.max_file_size
+        {
+            None
+        } else if metadata.len() > budget_left {
+            unhashed_count += 1;
+            None
+        } else {
+            // We charge the budget with the size of the file up front, so that
+            // it is never exceeded even if the file grows in the meantime.
+            budget_left -= metadata.len();
+
+            match hash_file(&path, metadata.len()) {
+                Ok(sha256) => sha256,
+                Err(error) => {
+                    warn!("failed to hash '{}': {}", path.display(), error);
+                    None
+                }
+            }
+        };
+
+        if let Some(sha256) = sha256 {
+            if args.sha256s.contains(&sha256) {
+                indicators.insert(0, Indicator::Sha256(sha256));
+            }
+        }
+
+        for indicator in indicators {
+            session.reply(Item {
+                path: path.clone(),
+                metadata: metadata.clone(),
+                sha256,
+                indicator,
+            })?;
+        }
+    }
+
+    if unhashed_count > 0 {
+        warn!("byte budget exhausted, {unhashed_count} files not hashed");
+    }
+
+    Ok(())
+}
+
+/// Computes the SHA-256 digest of the file at the given path.
+///
+/// The file is expected to be exactly `len` bytes long and no more than that
+/// is read. If the file turns out to be of a different size (e.g. because it
+/// was modified in the meantime), no digest is returned.
+fn hash_file(path: &Path, len: u64) -> std::io::Result<Option<[u8; 32]>> {
+    use sha2::Digest as _;
+    use std::io::Read as _;
+
+    let file = std::fs::File::open(path)?;
+
+    let mut hasher = sha2::Sha256::new();
+    let read_len = std::io::copy(&mut (&file).take(len), &mut hasher)?;
+
+    if read_len != len || file.metadata()?.len() != len {
+        debug!("'{}' changed while being hashed", path.display());
+        return Ok(None);
+    }
+
+    Ok(Some(hasher.finalize().into()))
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::sweep_indicators::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let path_globs = proto.take_path_globs().into_iter()
+            .map(crate::path::from_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("path_globs", error))?;
+
+        let sha256s = proto.take_sha256s().into_iter()
+            .map(|sha256| <[u8; 32]>::try_from(sha256.as_slice()))
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("sha256s", error))?;
+
+        let name_regexes = proto.take_name_regexes().into_iter()
+            .map(|name_regex| regex::Regex::new(&name_regex))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("name_regexes", error))?;
+
+        let max_file_size = match proto.max_file_size() {
+            0 => DEFAULT_MAX_FILE_SIZE,
+            max_file_size => max_file_size,
+        };
+
+        let byte_budget = match proto.byte_budget() {
+            0 => DEFAULT_BYTE_BUDGET,
+            byte_budget => byte_budget,
+        };
+
+        let timeout = if proto.has_timeout() {
+            let timeout = std::time::Duration::try_from(proto.take_timeout())
+                .map_err(|error| ParseArgsError::invalid_field("timeout", error))?;
+            Some(timeout)
+        } else {
+            None
+        };
+
+        Ok(Args {
+            path_globs,
+            sha256s,
+            name_regexes,
+            max_file_size,
+            byte_budget,
+            timeout,
+        })
+    }
+
+    fn paths(&self) -> Vec<&std::path::Path> {
+        // Note that only the glob itself is checked here, the paths it expands
+        // to are checked during the expansion.
+        self.path_globs.iter()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::sweep_indicators::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::sweep_indicators::Result::new();
+        proto.set_path(crate::path::into_proto(self.path));
+        proto.set_metadata(self.metadata.into());
+
+        if let Some(sha256) = self.sha256 {
+            proto.set_sha256(sha256.to_vec());
+        }
+
+        match self.indicator {
+            Indicator::Sha256(sha256) => {
+                proto.set_sha256_match(sha256.to_vec());
+            }
+            Indicator::NameRegex(name_regex) => {
+                proto.set_name_regex_match(name_regex);
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const BAD_CONTENT: &[u8] = b"RRG-KNOWN-BAD-CONTENT";
+
+    fn sha256(content: &[u8]) -> [u8; 32] {
+        use sha2::Digest as _;
+        sha2::Sha256::digest(content).into()
+    }
+
+    fn args(path_glob: PathBuf) -> Args {
+        Args {
+            path_globs: vec![path_glob],
+            sha256s: HashSet::from([sha256(BAD_CONTENT)]),
+            name_regexes: vec![regex::Regex::new(r"^evil.*\.exe$").unwrap()],
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            byte_budget: DEFAULT_BYTE_BUDGET,
+            timeout: None,
+        }
+    }
+
+    #[test]
+    fn handle_hits() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let hash_path = tempdir.path().join("innocent.txt");
+        std::fs::write(&hash_path, BAD_CONTENT)
+            .unwrap();
+
+        let name_path = tempdir.path().join("evil-tool.exe");
+        std::fs::write(&name_path, b"foo")
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("clean.txt"), b"bar")
+            .unwrap();
+        std::fs::write(tempdir.path().join("not-evil.exe"), b"baz")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(tempdir.path().join("*"))).is_ok());
+
+        let items = session.replies::<Item>().collect::<Vec<_>>();
+        assert_eq!(items.len(), 2);
+
+        let hash_hits = items.iter()
+            .filter(|item| matches!(item.indicator, Indicator::Sha256(_)))
+            .collect::<Vec<_>>();
+        assert_eq!(hash_hits.len(), 1);
+        assert_eq!(hash_hits[0].path, hash_path);
+        assert_eq!(hash_hits[0].indicator, Indicator::Sha256(sha256(BAD_CONTENT)));
+        assert_eq!(hash_hits[0].sha256, Some(sha256(BAD_CONTENT)));
+        assert_eq!(hash_hits[0].metadata.len(), BAD_CONTENT.len() as u64);
+
+        let name_hits = items.iter()
+            .filter(|item| matches!(item.indicator, Indicator::NameRegex(_)))
+            .collect::<Vec<_>>();
+        assert_eq!(name_hits.len(), 1);
+        assert_eq!(name_hits[0].path, name_path);
+        assert_eq!(name_hits[0].indicator, Indicator::NameRegex(String::from(r"^evil.*\.exe$")));
+        assert_eq!(name_hits[0].sha256, Some(sha256(b"foo")));
+    }
+
+    #[test]
+    fn handle_both_hits_same_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("evil.exe");
+        std::fs::write(&path, BAD_CONTENT)
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(tempdir.path().join("*.exe"))).is_ok());
+
+        let indicators = session.replies::<Item>()
+            .map(|item| item.indicator.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(indicators, vec![
+            Indicator::Sha256(sha256(BAD_CONTENT)),
+            Indicator::NameRegex(String::from(r"^evil.*\.exe$")),
+        ]);
+    }
+
+    #[test]
+    fn handle_max_file_size() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("innocent.txt"), BAD_CONTENT)
+            .unwrap();
+        std::fs::write(tempdir.path().join("evil.exe"), BAD_CONTENT)
+            .unwrap();
+
+        let mut args = args(tempdir.path().join("*"));
+        args.max_file_size = BAD_CONTENT.len() as u64 - 1;
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // Too big files are not hashed but their names are still matched.
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.path().join("evil.exe"));
+        assert_eq!(item.indicator, Indicator::NameRegex(String::from(r"^evil.*\.exe$")));
+        assert_eq!(item.sha256, None);
+    }
+
+    #[test]
+    fn handle_byte_budget() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("a.txt"), BAD_CONTENT)
+            .unwrap();
+        std::fs::write(tempdir.path().join("b.txt"), BAD_CONTENT)
+            .unwrap();
+        std::fs::write(tempdir.path().join("c.txt"), BAD_CONTENT)
+            .unwrap();
+
+        let mut args = args(tempdir.path().join("*.txt"));
+        args.byte_budget = 2 * BAD_CONTENT.len() as u64 + 1;
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let paths = session.replies::<Item>()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![
+            tempdir.path().join("a.txt"),
+            tempdir.path().join("b.txt"),
+        ]);
+    }
+
+    #[test]
+    fn handle_timeout() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("evil.exe"), BAD_CONTENT)
+            .unwrap();
+
+        let mut args = args(tempdir.path().join("*"));
+        args.timeout = Some(std::time::Duration::ZERO);
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_deadline_passed() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("evil.exe"), BAD_CONTENT)
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        session.set_deadline(std::time::Instant::now() - std::time::Duration::from_secs(1));
+
+        assert!(handle(&mut session, args(tempdir.path().join("*"))).is_err());
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_denied_paths() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("a"))
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("b"))
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("a").join("evil.exe"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("evil.exe"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("evil-secret.exe"), b"foo")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::with_args(crate::args::Args {
+            denied_paths: vec! {
+                tempdir.path().join("a").display().to_string().parse().unwrap(),
+                tempdir.path().join("b").join("evil-secret.exe").display().to_string().parse().unwrap(),
+            },
+            ..Default::default()
+        });
+        session.set_action(crate::request::Action::SweepIndicators);
+        assert!(handle(&mut session, args(tempdir.path().join("*").join("*"))).is_ok());
+
+        let paths = session.replies::<Item>()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec![tempdir.path().join("b").join("evil.exe")]);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let target = tempdir.path().join("target");
+        std::fs::create_dir(&target)
+            .unwrap();
+        std::fs::write(target.join("file"), BAD_CONTENT)
+            .unwrap();
+
+        let sweep = tempdir.path().join("sweep");
+        std::fs::create_dir(&sweep)
+            .unwrap();
+        std::os::unix::fs::symlink(target.join("file"), sweep.join("evil.exe"))
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args(sweep.join("*"))).is_ok());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::sweep_indicators::Args::new();
+        proto.mut_path_globs().push(crate::path::into_proto(PathBuf::from("/tmp/*")));
+        proto.mut_sha256s().push(sha256(BAD_CONTENT).to_vec());
+        proto.mut_sha256s().push(sha256(BAD_CONTENT).to_vec());
+        proto.mut_name_regexes().push(String::from(r"^evil\.exe$"));
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.path_globs, vec![PathBuf::from("/tmp/*")]);
+        assert_eq!(args.sha256s, HashSet::from([sha256(BAD_CONTENT)]));
+        assert_eq!(args.name_regexes.len(), 1);
+        assert_eq!(args.max_file_size, DEFAULT_MAX_FILE_SIZE);
+        assert_eq!(args.byte_budget, DEFAULT_BYTE_BUDGET);
+        assert_eq!(args.timeout, None);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn args_from_proto_non_utf8_path_glob() {
+        use std::os::unix::ffi::OsStrExt as _;
+        use crate::request::Args as _;
+
+        let path_glob = PathBuf::from(std::ffi::OsStr::from_bytes(b"/tmp/\xff*"));
+
+        let mut proto = rrg_proto::sweep_indicators::Args::new();
+        proto.mut_path_globs().push(crate::path::into_proto(path_glob.clone()));
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.path_globs, vec![path_glob]);
+    }
+
+    #[test]
+    fn args_from_proto_invalid_sha256() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::sweep_indicators::Args::new();
+        proto.mut_sha256s().push(vec![0xAB; 31]);
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_invalid_name_regex() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::sweep_indicators::Args::new();
+        proto.mut_name_regexes().push(String::from("(foo"));
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
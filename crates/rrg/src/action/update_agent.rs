@@ -0,0 +1,564 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::{Path, PathBuf};
+
+/// Maximum size of the new agent executable.
+///
+/// Agent builds are well below this limit, so anything larger is much more
+/// likely to be a mistake than a legitimate update.
+const MAX_BINARY_SIZE: usize = 128 * 1024 * 1024; // 128 MiB.
+
+/// Arguments of the `update_agent` action.
+pub struct Args {
+    /// Serialized update message (verified against the signature).
+    raw_update: Vec<u8>,
+    /// Signature of the serialized update message.
+    ed25519_signature: ed25519_dalek::Signature,
+    /// Expected SHA-256 digest of the new agent executable.
+    binary_sha256: [u8; 32],
+    /// Version of the new agent.
+    version: String,
+    /// Contents of the new agent executable.
+    binary: Vec<u8>,
+    /// Whether to only stage the new executable.
+    dry_run: bool,
+}
+
+/// Result of the `update_agent` action.
+pub struct Item {
+    /// Version of the staged agent.
+    version: String,
+    /// Path to which the new executable was written before the swap.
+    staged_path: PathBuf,
+    /// Whether the agent is going to restart with the new executable.
+    restarting: bool,
+}
+
+/// An error indicating that the update verification key is missing.
+#[derive(Debug)]
+struct MissingUpdateVerificationKeyError;
+
+impl std::fmt::Display for MissingUpdateVerificationKeyError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing update verification key")
+    }
+}
+
+impl std::error::Error for MissingUpdateVerificationKeyError {}
+
+/// An error indicating that the executable does not match the signed digest.
+#[derive(Debug)]
+struct DigestMismatchError {
+    /// Digest of the executable as specified in the signed update.
+    expected: [u8; 32],
+    /// Digest of the executable that was actually sent.
+    actual: [u8; 32],
+}
+
+impl std::fmt::Display for DigestMismatchError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "executable SHA-256 mismatch (expected ")?;
+        for byte in self.expected {
+            write!(fmt, "{byte:02x}")?;
+        }
+        write!(fmt, ", got ")?;
+        for byte in self.actual {
+            write!(fmt, "{byte:02x}")?;
+        }
+        write!(fmt, ")")
+    }
+}
+
+impl std::error::Error for DigestMismatchError {}
+
+/// An error indicating that the executable is empty or too large.
+#[derive(Debug)]
+struct BinarySizeError {
+    /// Size of the executable that was sent.
+    size: usize,
+}
+
+impl std::fmt::Display for BinarySizeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.size == 0 {
+            write!(fmt, "empty executable")
+        } else {
+            write!(fmt, "executable too large ({} bytes, limit: {MAX_BINARY_SIZE} bytes)", self.size)
+        }
+    }
+}
+
+impl std::error::Error for BinarySizeError {}
+
+/// Handles invocations of the `update_agent` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let exe_path = std::env::current_exe()
+        .map_err(crate::session::Error::action)?;
+
+    update(session, args, &exe_path)
+}
+
+/// Replaces the executable at `exe_path` with the one given in `args`.
+///
+/// Nothing is written to the filesystem unless both the signature and the
+/// digest of the new executable are verified.
+fn update<S>(session: &mut S, args: Args, exe_path: &Path) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use sha2::Digest as _;
+    use crate::request::ParseArgsError;
+
+    match session.args().update_verification_key {
+        Some(key) => key
+            .verify_strict(&args.raw_update, &args.ed25519_signature)
+            .map_err(|error| ParseArgsError::invalid_field("update", error))?,
+        None => return Err(crate::session::Error::action(MissingUpdateVerificationKeyError)),
+    };
+
+    let binary_sha256 = <[u8; 32]>::from(sha2::Sha256::digest(&args.binary));
+    if binary_sha256 != args.binary_sha256 {
+        return Err(crate::session::Error::action(DigestMismatchError {
+            expected: args.binary_sha256,
+            actual: binary_sha256,
+        }));
+    }
+
+    let staged_path = path_with_suffix(exe_path, ".new");
+
+    log::info!("staging agent {} at '{}'", args.version, staged_path.display());
+    stage(&args.binary, exe_path, &staged_path)
+        .map_err(crate::session::Error::action)?;
+
+    if !args.dry_run {
+        log::info!("swapping '{}' with '{}'", exe_path.display(), staged_path.display());
+        swap(exe_path, &staged_path)
+            .map_err(crate::session::Error::action)?;
+    }
+
+    session.reply(Item {
+        version: args.version,
+        staged_path,
+        restarting: !args.dry_run,
+    })?;
+
+    if !args.dry_run {
+        crate::request_restart();
+    }
+
+    Ok(())
+}
+
+/// Writes the new executable to the staging path.
+///
+/// The staged file is given the same permissions as the current executable. If
+/// staging fails, the partially written file is removed.
+fn stage(binary: &[u8], exe_path: &Path, staged_path: &Path) -> std::io::Result<()> {
+    use std::io::Write as _;
+
+    // There might be a leftover from an earlier (failed) update. We do not want
+    // to reuse it as it may have been modified in the meantime.
+    match std::fs::remove_file(staged_path) {
+        Ok(()) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => return Err(error),
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(staged_path)?;
+
+    let result = (|| {
+        file.write_all(binary)?;
+        file.sync_all()?;
+
+        let permissions = std::fs::metadata(exe_path)?.permissions();
+        std::fs::set_permissions(staged_path, permissions)
+    })();
+
+    if let Err(error) = result {
+        drop(file);
+        if let Err(error) = std::fs::remove_file(staged_path) {
+            log::warn!("failed to remove '{}': {error}", staged_path.display());
+        }
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Replaces the current executable with the staged one.
+///
+/// On Unix the staged file is just renamed over the current executable (which
+/// does not affect the running process).
+#[cfg(target_family = "unix")]
+fn swap(exe_path: &Path, staged_path: &Path) -> std::io::Result<()> {
+    std::fs::rename(staged_path, exe_path)
+}
+
+/// Replaces the current executable with the staged one.
+///
+/// Windows does not allow to replace the executable of a running process but
+/// it allows to rename it. Thus, we move the current executable out of the way
+/// first and schedule it for removal on the next reboot.
+#[cfg(target_family = "windows")]
+fn swap(exe_path: &Path, staged_path: &Path) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    let old_path = path_with_suffix(exe_path, ".old");
+
+    // The executable of the previous update is no longer in use (as otherwise
+    // we would not be running), so it can be removed right away.
+    match std::fs::remove_file(&old_path) {
+        Ok(()) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => return Err(error),
+    }
+
+    std::fs::rename(exe_path, &old_path)?;
+    if let Err(error) = std::fs::rename(staged_path, exe_path) {
+        // We want to leave the agent as it was, so we try to restore the old
+        // executable.
+        if let Err(error) = std::fs::rename(&old_path, exe_path) {
+            log::error!("failed to restore '{}': {error}", exe_path.display());
+        }
+        return Err(error);
+    }
+
+    let old_path_wide = old_path.as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+
+    // SAFETY: The path is a valid null-terminated wide string and we are not
+    // specifying the target path (which means that the file will be deleted).
+    // See the documentation [1] for more details.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-movefileexw
+    let status = unsafe {
+        windows_sys::Win32::Storage::FileSystem::MoveFileExW(
+            old_path_wide.as_ptr(),
+            std::ptr::null(),
+            windows_sys::Win32::Storage::FileSystem::MOVEFILE_DELAY_UNTIL_REBOOT,
+        )
+    };
+    if status == 0 {
+        // The update itself is already in place, the old executable is just
+        // going to stay around until the next update.
+        let error = std::io::Error::last_os_error();
+        log::warn!("failed to schedule removal of '{}': {error}", old_path.display());
+    }
+
+    Ok(())
+}
+
+/// Returns the given path with the suffix appended to its last component.
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(suffix);
+
+    PathBuf::from(path)
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::update_agent::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+        use protobuf::Message as _;
+
+        let raw_signature = proto.take_update_ed25519_signature();
+
+        let ed25519_signature = ed25519_dalek::Signature::try_from(&raw_signature[..])
+            .map_err(|error| ParseArgsError::invalid_field("update_ed25519_signature", error))?;
+
+        let raw_update = proto.take_update();
+        let mut update = rrg_proto::update_agent::Update::parse_from_bytes(&raw_update)
+            .map_err(|error| ParseArgsError::invalid_field("update", error))?;
+
+        let binary_sha256 = <[u8; 32]>::try_from(update.binary_sha256())
+            .map_err(|error| ParseArgsError::invalid_field("update binary_sha256", error))?;
+
+        let binary = proto.take_binary();
+        if binary.is_empty() || binary.len() > MAX_BINARY_SIZE {
+            return Err(ParseArgsError::invalid_field("binary", BinarySizeError {
+                size: binary.len(),
+            }));
+        }
+
+        Ok(Args {
+            raw_update,
+            ed25519_signature,
+            binary_sha256,
+            version: update.take_version(),
+            binary,
+            dry_run: proto.dry_run(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::update_agent::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::update_agent::Result::new();
+        proto.set_version(self.version);
+        proto.set_staged_path(crate::path::into_proto(self.staged_path));
+        proto.set_restarting(self.restarting);
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ed25519_dalek::Signer as _;
+    use sha2::Digest as _;
+
+    use super::*;
+
+    fn session(verification_key: ed25519_dalek::VerifyingKey) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            update_verification_key: Some(verification_key),
+            ..Default::default()
+        })
+    }
+
+    fn args(
+        signing_key: &ed25519_dalek::SigningKey,
+        binary: &[u8],
+        binary_sha256: &[u8],
+        dry_run: bool,
+    ) -> Args {
+        use crate::request::Args as _;
+        use protobuf::Message as _;
+
+        let mut update = rrg_proto::update_agent::Update::new();
+        update.set_binary_sha256(binary_sha256.to_vec());
+        update.set_version(String::from("1.2.3"));
+
+        let raw_update = update.write_to_bytes()
+            .unwrap();
+
+        let mut args = rrg_proto::update_agent::Args::new();
+        args.set_update_ed25519_signature(signing_key.sign(&raw_update).to_vec());
+        args.set_update(raw_update);
+        args.set_binary(binary.to_vec());
+        args.set_dry_run(dry_run);
+
+        Args::from_proto(args)
+            .unwrap()
+    }
+
+    fn exe(tempdir: &tempfile::TempDir) -> PathBuf {
+        let exe_path = tempdir.path().join("rrg");
+        std::fs::write(&exe_path, b"old")
+            .unwrap();
+
+        exe_path
+    }
+
+    #[test]
+    fn handle_invalid_signature() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+        let other_signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+
+        let binary = b"new";
+        let args = args(&other_signing_key, binary, &sha2::Sha256::digest(binary), false);
+
+        let mut session = session(signing_key.verifying_key());
+        assert!(update(&mut session, args, &exe_path).is_err());
+        assert_eq!(session.reply_count(), 0);
+
+        assert_eq!(std::fs::read(&exe_path).unwrap(), b"old");
+        assert!(!path_with_suffix(&exe_path, ".new").exists());
+    }
+
+    #[test]
+    fn handle_missing_verification_key() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+
+        let binary = b"new";
+        let args = args(&signing_key, binary, &sha2::Sha256::digest(binary), false);
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(update(&mut session, args, &exe_path).is_err());
+
+        assert_eq!(std::fs::read(&exe_path).unwrap(), b"old");
+    }
+
+    #[test]
+    fn handle_digest_mismatch() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+
+        let args = args(&signing_key, b"new", &sha2::Sha256::digest(b"other"), false);
+
+        let mut session = session(signing_key.verifying_key());
+        assert!(update(&mut session, args, &exe_path).is_err());
+        assert_eq!(session.reply_count(), 0);
+
+        assert_eq!(std::fs::read(&exe_path).unwrap(), b"old");
+        assert!(!path_with_suffix(&exe_path, ".new").exists());
+    }
+
+    #[test]
+    fn handle_dry_run() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+
+        let binary = b"new";
+        let args = args(&signing_key, binary, &sha2::Sha256::digest(binary), true);
+
+        let mut session = session(signing_key.verifying_key());
+        update(&mut session, args, &exe_path)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.version, "1.2.3");
+        assert_eq!(item.staged_path, path_with_suffix(&exe_path, ".new"));
+        assert!(!item.restarting);
+
+        assert_eq!(std::fs::read(&exe_path).unwrap(), b"old");
+        assert_eq!(std::fs::read(&item.staged_path).unwrap(), b"new");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_dry_run_permissions() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+        std::fs::set_permissions(&exe_path, std::fs::Permissions::from_mode(0o750))
+            .unwrap();
+
+        let binary = b"new";
+        let args = args(&signing_key, binary, &sha2::Sha256::digest(binary), true);
+
+        let mut session = session(signing_key.verifying_key());
+        update(&mut session, args, &exe_path)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        let metadata = std::fs::metadata(&item.staged_path)
+            .unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o750);
+    }
+
+    #[test]
+    fn handle_dry_run_stale_staged() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+        std::fs::write(path_with_suffix(&exe_path, ".new"), b"stale and longer")
+            .unwrap();
+
+        let binary = b"new";
+        let args = args(&signing_key, binary, &sha2::Sha256::digest(binary), true);
+
+        let mut session = session(signing_key.verifying_key());
+        update(&mut session, args, &exe_path)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(std::fs::read(&item.staged_path).unwrap(), b"new");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_swap() {
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let exe_path = exe(&tempdir);
+
+        let binary = b"new";
+        let args = args(&signing_key, binary, &sha2::Sha256::digest(binary), false);
+
+        let mut session = session(signing_key.verifying_key());
+        update(&mut session, args, &exe_path)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.version, "1.2.3");
+        assert!(item.restarting);
+
+        assert_eq!(std::fs::read(&exe_path).unwrap(), b"new");
+        assert!(!item.staged_path.exists());
+    }
+
+    #[test]
+    fn args_from_proto_empty_binary() {
+        use crate::request::Args as _;
+        use protobuf::Message as _;
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let mut update = rrg_proto::update_agent::Update::new();
+        update.set_binary_sha256(sha2::Sha256::digest(b"").to_vec());
+
+        let raw_update = update.write_to_bytes()
+            .unwrap();
+
+        let mut args = rrg_proto::update_agent::Args::new();
+        args.set_update_ed25519_signature(signing_key.sign(&raw_update).to_vec());
+        args.set_update(raw_update);
+
+        assert!(Args::from_proto(args).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_invalid_sha256() {
+        use crate::request::Args as _;
+        use protobuf::Message as _;
+
+        let signing_key = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+
+        let mut update = rrg_proto::update_agent::Update::new();
+        update.set_binary_sha256(b"foo".to_vec());
+
+        let raw_update = update.write_to_bytes()
+            .unwrap();
+
+        let mut args = rrg_proto::update_agent::Args::new();
+        args.set_update_ed25519_signature(signing_key.sign(&raw_update).to_vec());
+        args.set_update(raw_update);
+        args.set_binary(b"new".to_vec());
+
+        assert!(Args::from_proto(args).is_err());
+    }
+}
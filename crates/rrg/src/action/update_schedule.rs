@@ -0,0 +1,212 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the schedule update action.
+
+/// Arguments of the `update_schedule` action.
+pub struct Args {
+    /// Serialized schedule message (verified against the signature).
+    raw_schedule: Vec<u8>,
+    /// Signature of the serialized schedule message.
+    ed25519_signature: ed25519_dalek::Signature,
+    /// New schedule to follow.
+    schedule: crate::schedule::Schedule,
+}
+
+/// Result of the `update_schedule` action.
+pub struct Item {
+    /// Identifiers of the entries of the new schedule.
+    entry_ids: Vec<String>,
+}
+
+/// An error indicating that the agent has no state directory to persist the
+/// schedule in.
+#[derive(Debug)]
+struct MissingStateDirError;
+
+impl std::fmt::Display for MissingStateDirError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing state directory")
+    }
+}
+
+impl std::error::Error for MissingStateDirError {}
+
+/// Handles invocations of the `update_schedule` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use crate::request::ParseArgsError;
+
+    let schedule_path = match crate::schedule::path(session.args()) {
+        Some(path) => path,
+        None => return Err(crate::session::Error::action_unavailable(MissingStateDirError)),
+    };
+
+    // Schedules are verified with the same keys as signed commands, because
+    // scheduled actions are executed without any further approval.
+    let keys = match &session.args().command_verification_key_store {
+        Some(store_path) => {
+            crate::signing::KeySet::load(session.args().command_verification_key, store_path)
+                .map_err(crate::session::Error::action)?
+        }
+        None => crate::signing::KeySet::new(session.args().command_verification_key),
+    };
+
+    keys.verify(&args.raw_schedule, &args.ed25519_signature, std::time::SystemTime::now())
+        .map_err(|error| ParseArgsError::invalid_field("schedule", error))?;
+
+    log::info!("persisting schedule at '{}'", schedule_path.display());
+    crate::state::write_atomic(&schedule_path, &args.raw_schedule)
+        .map_err(crate::session::Error::action)?;
+
+    // The schedule is picked up by the main loop once the request is handled.
+    crate::schedule::request_reload();
+
+    session.reply(Item {
+        entry_ids: args.schedule.entry_ids().map(String::from).collect(),
+    })?;
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::update_schedule::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let raw_signature = proto.take_schedule_ed25519_signature();
+
+        let ed25519_signature = ed25519_dalek::Signature::try_from(&raw_signature[..])
+            .map_err(|error| ParseArgsError::invalid_field("schedule_ed25519_signature", error))?;
+
+        let raw_schedule = proto.take_schedule();
+        let schedule = crate::schedule::Schedule::parse(&raw_schedule)
+            .map_err(|error| ParseArgsError::invalid_field("schedule", error))?;
+
+        Ok(Args {
+            raw_schedule,
+            ed25519_signature,
+            schedule,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::update_schedule::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::update_schedule::Result::new();
+        proto.set_entry_ids(self.entry_ids);
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ed25519_dalek::Signer as _;
+
+    use crate::session::Session as _;
+
+    use super::*;
+
+    fn session(
+        initial_key: ed25519_dalek::VerifyingKey,
+        state_dir: Option<&std::path::Path>,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            command_verification_key: Some(initial_key),
+            state_dir: state_dir.map(std::path::Path::to_path_buf),
+            ..Default::default()
+        })
+    }
+
+    fn args(signing_key: &ed25519_dalek::SigningKey, entry_ids: &[&str]) -> Args {
+        use crate::request::Args as _;
+        use protobuf::Message as _;
+
+        let mut schedule = rrg_proto::schedule::Schedule::new();
+        for entry_id in entry_ids {
+            let mut entry = rrg_proto::schedule::Entry::new();
+            entry.set_id(String::from(*entry_id));
+            entry.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+            entry.mut_interval().seconds = 3600;
+            schedule.mut_entries().push(entry);
+        }
+
+        let raw_schedule = schedule.write_to_bytes()
+            .unwrap();
+
+        let mut args = rrg_proto::update_schedule::Args::new();
+        args.set_schedule_ed25519_signature(signing_key.sign(&raw_schedule).to_vec());
+        args.set_schedule(raw_schedule);
+
+        Args::from_proto(args)
+            .unwrap()
+    }
+
+    fn signing_key() -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn handle_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let signing_key = signing_key();
+
+        let mut session = session(signing_key.verifying_key(), Some(tempdir.path()));
+        assert!(handle(&mut session, args(&signing_key, &["foo", "bar"])).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.entry_ids, vec!["foo", "bar"]);
+
+        let schedule = crate::schedule::load(session.args())
+            .unwrap();
+        assert_eq!(schedule.entry_ids().collect::<Vec<_>>(), vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn handle_untrusted_signature() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = session(signing_key().verifying_key(), Some(tempdir.path()));
+        assert!(handle(&mut session, args(&signing_key(), &["foo"])).is_err());
+
+        let schedule = crate::schedule::load(session.args())
+            .unwrap();
+        assert_eq!(schedule.entry_ids().count(), 0);
+    }
+
+    #[test]
+    fn handle_missing_state_dir() {
+        let signing_key = signing_key();
+
+        let mut session = session(signing_key.verifying_key(), None);
+        assert!(handle(&mut session, args(&signing_key, &["foo"])).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_invalid_schedule() {
+        use crate::request::Args as _;
+
+        let signing_key = signing_key();
+
+        let mut args = rrg_proto::update_schedule::Args::new();
+        args.set_schedule_ed25519_signature(signing_key.sign(b"\xff\xff").to_vec());
+        args.set_schedule(b"\xff\xff".to_vec());
+
+        assert!(Args::from_proto(args).is_err());
+    }
+}
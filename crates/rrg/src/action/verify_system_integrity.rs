@@ -0,0 +1,619 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the system integrity verification action.
+//!
+//! The action verifies files on the endpoint against a manifest of their
+//! expected digests (and optionally sizes and modes) pushed by the server, so
+//! that known-good baselines of critical files can be checked without having
+//! to download any of them.
+
+use std::path::{Path, PathBuf};
+
+/// Number of files verified at the same time if not specified.
+const DEFAULT_MAX_PARALLELISM: usize = 4;
+
+/// Upper bound on the number of files verified at the same time.
+const MAX_MAX_PARALLELISM: usize = 16;
+
+/// Arguments of the `verify_system_integrity` action.
+pub struct Args {
+    /// Manifest with the expected state of the files to verify.
+    manifest: Vec<ManifestEntry>,
+    /// Whether to report files that match the manifest as well.
+    full_report: bool,
+    /// Policy on symlinks in the paths to the files.
+    symlink_policy: crate::fs::SymlinkPolicy,
+    /// Maximum number of files verified at the same time.
+    max_parallelism: usize,
+}
+
+/// Expected state of a single file.
+struct ManifestEntry {
+    /// Path to the file.
+    path: PathBuf,
+    /// Expected SHA-256 digest of the file contents.
+    sha256: [u8; 32],
+    /// Expected size of the file (if specified).
+    size: Option<u64>,
+    /// Expected permission bits of the file (if specified).
+    #[cfg_attr(not(target_family = "unix"), allow(dead_code))]
+    unix_mode: Option<u32>,
+}
+
+/// A result of the `verify_system_integrity` action.
+#[derive(Debug)]
+struct Item {
+    /// Path to the verified file.
+    path: PathBuf,
+    /// Outcome of the verification.
+    outcome: Outcome,
+}
+
+/// Outcome of the verification of a single file.
+#[derive(Debug)]
+enum Outcome {
+    /// The file exists and was verified against the manifest.
+    Found {
+        /// Actual state of the file.
+        actual: Box<Actual>,
+        /// Aspects in which the file does not match the manifest.
+        mismatch: Mismatch,
+    },
+    /// The file does not exist.
+    Missing,
+    /// The file could not be verified.
+    Error(std::io::Error),
+}
+
+impl Outcome {
+
+    /// Checks whether the file matches the manifest.
+    fn is_match(&self) -> bool {
+        match self {
+            Outcome::Found { mismatch, .. } => !mismatch.any(),
+            Outcome::Missing | Outcome::Error(_) => false,
+        }
+    }
+}
+
+/// Actual state of a verified file.
+#[derive(Debug)]
+struct Actual {
+    /// SHA-256 digest of the file contents.
+    sha256: [u8; 32],
+    /// Metadata of the file.
+    metadata: std::fs::Metadata,
+    /// Linux extended file flags (if available).
+    #[cfg(target_os = "linux")]
+    linux_flags: Option<u32>,
+}
+
+/// Aspects in which a file does not match the manifest.
+#[derive(Debug)]
+struct Mismatch {
+    /// Whether the contents do not match the expected digest.
+    content: bool,
+    /// Whether the size does not match the expected one.
+    size: bool,
+    /// Whether the permission bits do not match the expected ones.
+    mode: bool,
+}
+
+impl Mismatch {
+
+    /// Checks whether there is a mismatch in any of the aspects.
+    fn any(&self) -> bool {
+        self.content || self.size || self.mode
+    }
+}
+
+/// Handles invocations of the `verify_system_integrity` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    // Workers pick manifest entries one by one (so that a few big files do not
+    // hold up the rest) and send outcomes back to us as only we can reply.
+    let next_index = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel::<(usize, Outcome)>();
+
+    std::thread::scope(|scope| {
+        for worker_index in 0..args.max_parallelism {
+            let sender = sender.clone();
+            let next_index = &next_index;
+            let stop = &stop;
+            let args = &args;
+
+            let worker = move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = args.manifest.get(index) else {
+                        break;
+                    };
+
+                    let outcome = verify(entry, args.symlink_policy);
+                    if sender.send((index, outcome)).is_err() {
+                        break;
+                    }
+                }
+            };
+
+            std::thread::Builder::new()
+                .name(format!("verify-integrity-{worker_index}"))
+                .spawn_scoped(scope, worker)
+                .map_err(crate::session::Error::action)?;
+        }
+
+        // Workers hold their own senders, so the channel is closed once all of
+        // them are done.
+        drop(sender);
+
+        let result = reply_in_order(session, &args, receiver);
+        if result.is_err() {
+            stop.store(true, Ordering::Relaxed);
+        }
+
+        result
+    })
+}
+
+/// Replies with the outcomes received from the workers.
+///
+/// Outcomes arrive in arbitrary order, but they are replied with in the order
+/// of the manifest so that results of the action are deterministic.
+fn reply_in_order<S>(
+    session: &mut S,
+    args: &Args,
+    receiver: std::sync::mpsc::Receiver<(usize, Outcome)>,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut pending = std::collections::BTreeMap::new();
+    let mut next_index = 0;
+
+    for (index, outcome) in receiver {
+        pending.insert(index, outcome);
+
+        while let Some(outcome) = pending.remove(&next_index) {
+            let path = args.manifest[next_index].path.clone();
+            next_index += 1;
+
+            if !args.full_report && outcome.is_match() {
+                continue;
+            }
+
+            session.reply(Item {
+                path,
+                outcome,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies the file described by the given manifest entry.
+fn verify(entry: &ManifestEntry, symlink_policy: crate::fs::SymlinkPolicy) -> Outcome {
+    use sha2::Digest as _;
+
+    let mut file = match crate::fs::open_file(&entry.path, symlink_policy) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Outcome::Missing;
+        }
+        Err(error) => return Outcome::Error(error),
+    };
+
+    // We query metadata of the opened file (rather than the path), so that it
+    // describes the same file that we hash.
+    let metadata = match file.metadata() {
+        Ok(metadata) => metadata,
+        Err(error) => return Outcome::Error(error),
+    };
+
+    let mut hasher = sha2::Sha256::new();
+    if let Err(error) = std::io::copy(&mut file, &mut hasher) {
+        return Outcome::Error(error);
+    }
+    let sha256 = <[u8; 32]>::from(hasher.finalize());
+
+    let mismatch = Mismatch {
+        content: sha256 != entry.sha256,
+        size: entry.size.is_some_and(|size| size != metadata.len()),
+        #[cfg(target_family = "unix")]
+        mode: entry.unix_mode.is_some_and(|mode| mode != unix_mode(&metadata)),
+        #[cfg(not(target_family = "unix"))]
+        mode: false,
+    };
+
+    let actual = Actual {
+        sha256,
+        metadata,
+        // Flags are just an additional piece of information, so we do not want
+        // to fail the verification if they are not available (e.g. on a file
+        // system that does not support them).
+        #[cfg(target_os = "linux")]
+        linux_flags: ospect::fs::linux::flags(&entry.path).ok(),
+    };
+
+    Outcome::Found {
+        actual: Box::new(actual),
+        mismatch,
+    }
+}
+
+/// Returns the permission bits (including the setuid, setgid and sticky bits)
+/// of a file with the given metadata.
+#[cfg(target_family = "unix")]
+fn unix_mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt as _;
+
+    metadata.permissions().mode() & 0o7777
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::verify_system_integrity::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let mut manifest = Vec::new();
+
+        for mut entry in proto.take_manifest() {
+            let path = crate::path::from_proto(entry.take_path())
+                .map_err(|error| ParseArgsError::invalid_field("manifest", error))?;
+
+            let sha256 = <[u8; 32]>::try_from(entry.sha256())
+                .map_err(|error| ParseArgsError::invalid_field("manifest", error))?;
+
+            manifest.push(ManifestEntry {
+                path,
+                sha256,
+                size: entry.size,
+                unix_mode: entry.unix_mode,
+            });
+        }
+
+        let max_parallelism = match proto.max_parallelism() {
+            0 => DEFAULT_MAX_PARALLELISM,
+            max_parallelism => std::cmp::min(max_parallelism as usize, MAX_MAX_PARALLELISM),
+        };
+
+        Ok(Args {
+            manifest,
+            full_report: proto.full_report(),
+            symlink_policy: if proto.deny_symlinks() {
+                crate::fs::SymlinkPolicy::Deny
+            } else {
+                crate::fs::SymlinkPolicy::Follow
+            },
+            max_parallelism,
+        })
+    }
+
+    fn paths(&self) -> Vec<&Path> {
+        self.manifest.iter()
+            .map(|entry| entry.path.as_path())
+            .collect()
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::verify_system_integrity::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::verify_system_integrity::Status;
+
+        let mut proto = Self::Proto::default();
+        proto.set_path(crate::path::into_proto(self.path));
+
+        match self.outcome {
+            Outcome::Found { actual, mismatch } => {
+                proto.set_status(if mismatch.any() {
+                    Status::MISMATCH
+                } else {
+                    Status::MATCH
+                });
+                proto.set_content_mismatch(mismatch.content);
+                proto.set_size_mismatch(mismatch.size);
+                proto.set_mode_mismatch(mismatch.mode);
+
+                proto.set_sha256(actual.sha256.to_vec());
+                #[cfg(target_family = "unix")]
+                proto.set_unix_mode(unix_mode(&actual.metadata));
+                #[cfg(target_os = "linux")]
+                if let Some(linux_flags) = actual.linux_flags {
+                    proto.set_linux_flags(linux_flags);
+                }
+                proto.set_metadata(actual.metadata.into());
+            }
+            Outcome::Missing => {
+                proto.set_status(Status::MISSING);
+            }
+            Outcome::Error(error) => {
+                proto.set_status(Status::ERROR);
+                proto.set_error(error.to_string());
+            }
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Returns the SHA-256 digest of the given data.
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        use sha2::Digest as _;
+        sha2::Sha256::digest(data).into()
+    }
+
+    /// Returns arguments verifying the given manifest.
+    fn args(manifest: Vec<ManifestEntry>) -> Args {
+        Args {
+            manifest,
+            full_report: false,
+            symlink_policy: crate::fs::SymlinkPolicy::Follow,
+            max_parallelism: DEFAULT_MAX_PARALLELISM,
+        }
+    }
+
+    #[test]
+    fn handle_match() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo")
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("foo"),
+            sha256: sha256(b"foo"),
+            size: Some(3),
+            unix_mode: None,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(manifest))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_match_full_report() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo")
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("foo"),
+            sha256: sha256(b"foo"),
+            size: Some(3),
+            unix_mode: None,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, Args {
+            full_report: true,
+            ..args(manifest)
+        }).unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.path().join("foo"));
+        assert!(item.outcome.is_match());
+    }
+
+    #[test]
+    fn handle_content_mismatch() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"bar")
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("foo"),
+            sha256: sha256(b"foo"),
+            size: Some(3),
+            unix_mode: None,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(manifest))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        let Outcome::Found { actual, mismatch } = &item.outcome else {
+            panic!("unexpected outcome: {:?}", item.outcome);
+        };
+        assert!(mismatch.content);
+        assert!(!mismatch.size);
+        assert!(!mismatch.mode);
+        assert_eq!(actual.sha256, sha256(b"bar"));
+        assert_eq!(actual.metadata.len(), 3);
+    }
+
+    #[test]
+    fn handle_size_mismatch() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foobar")
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("foo"),
+            sha256: sha256(b"foo"),
+            size: Some(3),
+            unix_mode: None,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(manifest))
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        let Outcome::Found { mismatch, .. } = &item.outcome else {
+            panic!("unexpected outcome: {:?}", item.outcome);
+        };
+        assert!(mismatch.content);
+        assert!(mismatch.size);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_mode_mismatch() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo")
+            .unwrap();
+        std::fs::set_permissions(tempdir.path().join("foo"), std::fs::Permissions::from_mode(0o4755))
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("foo"),
+            sha256: sha256(b"foo"),
+            size: Some(3),
+            unix_mode: Some(0o755),
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(manifest))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        let Outcome::Found { mismatch, .. } = &item.outcome else {
+            panic!("unexpected outcome: {:?}", item.outcome);
+        };
+        assert!(!mismatch.content);
+        assert!(!mismatch.size);
+        assert!(mismatch.mode);
+    }
+
+    #[test]
+    fn handle_missing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("foo"),
+            sha256: sha256(b"foo"),
+            size: None,
+            unix_mode: None,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(manifest))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert!(matches!(item.outcome, Outcome::Missing));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink_denied() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.path().join("foo"), tempdir.path().join("bar"))
+            .unwrap();
+
+        let manifest = vec![ManifestEntry {
+            path: tempdir.path().join("bar"),
+            sha256: sha256(b"foo"),
+            size: None,
+            unix_mode: None,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, Args {
+            symlink_policy: crate::fs::SymlinkPolicy::Deny,
+            ..args(manifest)
+        }).unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert!(matches!(item.outcome, Outcome::Error(_)));
+    }
+
+    #[test]
+    fn handle_many_in_order() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut manifest = Vec::new();
+        for i in 0..64 {
+            let path = tempdir.path().join(i.to_string());
+            std::fs::write(&path, i.to_string())
+                .unwrap();
+
+            // Every other file is expected to have different contents.
+            let expected = if i % 2 == 0 {
+                i.to_string()
+            } else {
+                String::from("foo")
+            };
+
+            manifest.push(ManifestEntry {
+                path,
+                sha256: sha256(expected.as_bytes()),
+                size: None,
+                unix_mode: None,
+            });
+        }
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(manifest))
+            .unwrap();
+
+        let paths = session.replies::<Item>()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+
+        let expected_paths = (0..64)
+            .filter(|i| i % 2 == 1)
+            .map(|i| tempdir.path().join(i.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, expected_paths);
+    }
+
+    #[test]
+    fn args_from_proto_invalid_sha256() {
+        use crate::request::Args as _;
+
+        let mut entry = rrg_proto::verify_system_integrity::ManifestEntry::new();
+        entry.set_path(PathBuf::from("/foo").into());
+        entry.set_sha256(vec![0; 16]);
+
+        let mut proto = rrg_proto::verify_system_integrity::Args::new();
+        proto.mut_manifest().push(entry);
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
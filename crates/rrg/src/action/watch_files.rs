@@ -0,0 +1,526 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! A handler and associated types for the file watching action.
+//!
+//! Events are collected using the native notification interface of each
+//! platform: inotify on Linux, FSEvents on macOS and `ReadDirectoryChangesW` on
+//! Windows. Each of them delivers events with different granularity, so the
+//! exact sequence of events for the same operation varies between platforms.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+mod sys {
+    #[cfg(target_os = "linux")]
+    pub use super::linux::*;
+
+    #[cfg(target_os = "macos")]
+    pub use super::macos::*;
+
+    #[cfg(target_os = "windows")]
+    pub use super::windows::*;
+}
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Maximum duration for which paths can be watched.
+const MAX_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum number of paths that can be watched at once.
+///
+/// This is the limit of handles that can be waited on at once on Windows.
+const MAX_PATH_COUNT: usize = 64;
+
+/// Maximum time to wait for events before checking the session limits.
+///
+/// This bounds how long it takes for the action to notice that it should be
+/// cancelled (e.g. because the agent is shutting down).
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Arguments of the `watch_files` action.
+pub struct Args {
+    /// Paths to the files or directories to watch.
+    paths: Vec<PathBuf>,
+    /// Kinds of events to report (all if empty).
+    event_kinds: Vec<EventKind>,
+    /// Duration for which the paths should be watched.
+    duration: Duration,
+}
+
+/// Kind of a filesystem event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EventKind {
+    /// A file was created.
+    Create,
+    /// A file was deleted.
+    Delete,
+    /// Contents of a file were modified.
+    Modify,
+    /// Metadata of a file were modified.
+    Attrib,
+    /// A file was renamed (the event carries the old name).
+    RenameFrom,
+    /// A file was renamed (the event carries the new name).
+    RenameTo,
+}
+
+/// Filesystem event that occurred on one of the watched paths.
+#[derive(Debug)]
+struct Event {
+    /// Path to the file the event concerns.
+    path: PathBuf,
+    /// Kind of the event.
+    kind: EventKind,
+    /// Identifier pairing the two events of a single rename (if any).
+    cookie: Option<u32>,
+    /// Time at which the event was observed.
+    time: SystemTime,
+}
+
+/// Notification delivered by the platform-specific watcher.
+#[derive(Debug)]
+enum Notification {
+    /// An event occurred on one of the watched paths.
+    Event(Event),
+    /// The kernel event queue overflowed and some events were dropped.
+    Overflow,
+}
+
+/// Summary of the watch (sent as the last result of the action).
+#[derive(Debug)]
+struct Summary {
+    /// Number of events reported.
+    event_count: u64,
+    /// Number of times the kernel event queue overflowed.
+    overflow_count: u64,
+}
+
+/// Handles invocations of the `watch_files` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let deadline = Instant::now() + args.duration;
+
+    let mut watcher = sys::Watcher::new(&args.paths)
+        .map_err(crate::session::Error::action)?;
+
+    let mut summary = Summary {
+        event_count: 0,
+        overflow_count: 0,
+    };
+
+    loop {
+        let time_left = deadline.saturating_duration_since(Instant::now());
+        if time_left.is_zero() {
+            break;
+        }
+
+        session.check_limits()?;
+
+        let notifications = watcher.poll(std::cmp::min(time_left, POLL_INTERVAL))
+            .map_err(crate::session::Error::action)?;
+
+        for notification in notifications {
+            match notification {
+                Notification::Event(event) => {
+                    if !args.event_kinds.is_empty() && !args.event_kinds.contains(&event.kind) {
+                        continue;
+                    }
+
+                    summary.event_count += 1;
+                    session.reply(event)?;
+                }
+                Notification::Overflow => {
+                    log::warn!("file watch event queue overflowed");
+                    summary.overflow_count += 1;
+                }
+            }
+        }
+    }
+
+    session.reply(summary)?;
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::watch_files::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let mut paths = Vec::new();
+        for path in proto.take_paths() {
+            let path = crate::path::from_proto(path)
+                .map_err(|error| ParseArgsError::invalid_field("paths", error))?;
+
+            paths.push(path);
+        }
+
+        if paths.len() > MAX_PATH_COUNT {
+            return Err(ParseArgsError::invalid_field("paths", PathCountError {
+                count: paths.len(),
+            }));
+        }
+
+        let mut event_kinds = Vec::new();
+        for event_type in proto.event_types() {
+            use rrg_proto::watch_files::EventType::*;
+
+            let event_type = match event_type.enum_value() {
+                Ok(event_type) => event_type,
+                Err(value) => {
+                    return Err(ParseArgsError::invalid_field("event_types", EventTypeError {
+                        value,
+                    }));
+                }
+            };
+
+            event_kinds.push(match event_type {
+                CREATE => EventKind::Create,
+                DELETE => EventKind::Delete,
+                MODIFY => EventKind::Modify,
+                ATTRIB => EventKind::Attrib,
+                RENAME_FROM => EventKind::RenameFrom,
+                RENAME_TO => EventKind::RenameTo,
+                UNKNOWN => {
+                    return Err(ParseArgsError::invalid_field("event_types", EventTypeError {
+                        value: protobuf::Enum::value(&event_type),
+                    }));
+                }
+            });
+        }
+
+        let duration = if proto.has_duration() {
+            Duration::try_from(proto.take_duration())
+                .map_err(|error| ParseArgsError::invalid_field("duration", error))?
+        } else {
+            Duration::ZERO
+        };
+
+        if duration.is_zero() || duration > MAX_DURATION {
+            return Err(ParseArgsError::invalid_field("duration", DurationError {
+                duration,
+            }));
+        }
+
+        Ok(Args {
+            paths,
+            event_kinds,
+            duration,
+        })
+    }
+
+    fn paths(&self) -> Vec<&Path> {
+        self.paths.iter()
+            .map(PathBuf::as_path)
+            .collect()
+    }
+}
+
+impl crate::response::Item for Event {
+
+    type Proto = rrg_proto::watch_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        use rrg_proto::watch_files::EventType;
+
+        let mut event = rrg_proto::watch_files::Event::new();
+        event.set_path(crate::path::into_proto(self.path));
+        event.set_type(match self.kind {
+            EventKind::Create => EventType::CREATE,
+            EventKind::Delete => EventType::DELETE,
+            EventKind::Modify => EventType::MODIFY,
+            EventKind::Attrib => EventType::ATTRIB,
+            EventKind::RenameFrom => EventType::RENAME_FROM,
+            EventKind::RenameTo => EventType::RENAME_TO,
+        });
+        if let Some(cookie) = self.cookie {
+            event.set_cookie(cookie);
+        }
+        event.set_time(rrg_proto::into_timestamp(self.time));
+
+        let mut proto = Self::Proto::default();
+        proto.set_event(event);
+
+        proto
+    }
+}
+
+impl crate::response::Item for Summary {
+
+    type Proto = rrg_proto::watch_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut summary = rrg_proto::watch_files::Summary::new();
+        summary.set_event_count(self.event_count);
+        summary.set_overflow_count(self.overflow_count);
+
+        let mut proto = Self::Proto::default();
+        proto.set_summary(summary);
+
+        proto
+    }
+}
+
+/// An error indicating that too many paths were requested to be watched.
+#[derive(Debug)]
+struct PathCountError {
+    count: usize,
+}
+
+impl std::fmt::Display for PathCountError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "too many paths ({}, at most {MAX_PATH_COUNT} allowed)", self.count)
+    }
+}
+
+impl std::error::Error for PathCountError {
+}
+
+/// An error indicating that an unknown event type was requested.
+#[derive(Debug)]
+struct EventTypeError {
+    value: i32,
+}
+
+impl std::fmt::Display for EventTypeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown event type: {}", self.value)
+    }
+}
+
+impl std::error::Error for EventTypeError {
+}
+
+/// An error indicating that the watch duration is missing or too long.
+#[derive(Debug)]
+struct DurationError {
+    duration: Duration,
+}
+
+impl std::fmt::Display for DurationError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "invalid watch duration {} (must be positive and at most {})",
+            humantime::format_duration(self.duration),
+            humantime::format_duration(MAX_DURATION),
+        }
+    }
+}
+
+impl std::error::Error for DurationError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Runs the action on the given path while performing the given operations
+    /// on a separate thread.
+    fn watch<F>(path: &Path, event_kinds: Vec<EventKind>, f: F) -> crate::session::FakeSession
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let args = Args {
+            paths: vec![path.to_path_buf()],
+            event_kinds,
+            duration: Duration::from_secs(2),
+        };
+
+        // The watch has to be established before the operations are done, so
+        // we give the action some time to start.
+        let operations = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(500));
+            f();
+        });
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        operations.join()
+            .unwrap();
+
+        session
+    }
+
+    /// Returns paths, kinds and cookies of the events reported by the session.
+    fn events(session: &crate::session::FakeSession) -> Vec<(PathBuf, EventKind, Option<u32>)> {
+        // The last reply is always the summary.
+        (0..session.reply_count() - 1)
+            .map(|id| session.reply::<Event>(id))
+            .map(|event| (event.path.clone(), event.kind, event.cookie))
+            .collect()
+    }
+
+    /// Returns the canonical path of the given temporary directory.
+    ///
+    /// Some platforms (e.g. macOS) report events with symlinks resolved.
+    fn canonical_tempdir_path(tempdir: &tempfile::TempDir) -> PathBuf {
+        tempdir.path().canonicalize()
+            .unwrap()
+    }
+
+    #[test]
+    fn handle_create() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = canonical_tempdir_path(&tempdir);
+
+        let path = tempdir_path.join("foo");
+        let session = watch(&tempdir_path, vec![EventKind::Create], {
+            let path = path.clone();
+            move || std::fs::write(path, b"foo").unwrap()
+        });
+        let events = events(&session);
+
+        assert!(events.contains(&(path, EventKind::Create, None)));
+    }
+
+    #[test]
+    fn handle_summary() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = canonical_tempdir_path(&tempdir);
+
+        let session = watch(&tempdir_path, vec![], {
+            let tempdir_path = tempdir_path.clone();
+            move || std::fs::write(tempdir_path.join("foo"), b"foo").unwrap()
+        });
+        let events = events(&session);
+
+        let summary = session.reply::<Summary>(session.reply_count() - 1);
+        assert_eq!(summary.event_count, events.len() as u64);
+        assert_eq!(summary.overflow_count, 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_create_rename_delete() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = canonical_tempdir_path(&tempdir);
+
+        let session = watch(&tempdir_path, vec![], {
+            let tempdir_path = tempdir_path.clone();
+            move || {
+                std::fs::File::create(tempdir_path.join("foo"))
+                    .unwrap();
+                std::fs::rename(tempdir_path.join("foo"), tempdir_path.join("bar"))
+                    .unwrap();
+                std::fs::remove_file(tempdir_path.join("bar"))
+                    .unwrap();
+            }
+        });
+        let events = events(&session);
+
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0], (tempdir_path.join("foo"), EventKind::Create, None));
+        assert_eq!(events[1].0, tempdir_path.join("foo"));
+        assert_eq!(events[1].1, EventKind::RenameFrom);
+        assert_eq!(events[2].0, tempdir_path.join("bar"));
+        assert_eq!(events[2].1, EventKind::RenameTo);
+        assert_eq!(events[3], (tempdir_path.join("bar"), EventKind::Delete, None));
+
+        // Both events of the rename should have the same cookie.
+        assert!(events[1].2.is_some());
+        assert_eq!(events[1].2, events[2].2);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_event_kinds() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = canonical_tempdir_path(&tempdir);
+
+        let session = watch(&tempdir_path, vec![EventKind::Delete], {
+            let tempdir_path = tempdir_path.clone();
+            move || {
+                std::fs::write(tempdir_path.join("foo"), b"foo")
+                    .unwrap();
+                std::fs::remove_file(tempdir_path.join("foo"))
+                    .unwrap();
+            }
+        });
+        let events = events(&session);
+
+        assert_eq!(events, vec![(tempdir_path.join("foo"), EventKind::Delete, None)]);
+    }
+
+    #[test]
+    fn handle_duration() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            paths: vec![tempdir.path().to_path_buf()],
+            event_kinds: vec![],
+            duration: Duration::from_millis(300),
+        };
+
+        let start = Instant::now();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_millis(300) + 2 * POLL_INTERVAL);
+    }
+
+    #[test]
+    fn handle_not_existing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            paths: vec![tempdir.path().join("foo")],
+            event_kinds: vec![],
+            duration: Duration::from_millis(100),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_no_duration() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::watch_files::Args::new();
+        proto.mut_paths().push(PathBuf::from("/tmp").into());
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_duration_too_long() {
+        use crate::request::Args as _;
+
+        let mut proto = rrg_proto::watch_files::Args::new();
+        proto.mut_paths().push(PathBuf::from("/tmp").into());
+        proto.set_duration((MAX_DURATION + Duration::from_secs(1)).into());
+
+        assert!(Args::from_proto(proto).is_err());
+    }
+}
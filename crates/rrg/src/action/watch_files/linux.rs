@@ -0,0 +1,312 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Watching files using the inotify interface [1].
+//!
+//! [1]: https://man7.org/linux/man-pages/man7/inotify.7.html
+
+use std::collections::HashMap;
+use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd};
+use std::path::PathBuf;
+
+use super::{Event, EventKind, Notification};
+
+/// Mask of inotify events that we register for.
+const MASK: u32 = libc::IN_CREATE |
+    libc::IN_DELETE |
+    libc::IN_DELETE_SELF |
+    libc::IN_MODIFY |
+    libc::IN_ATTRIB |
+    libc::IN_MOVED_FROM |
+    libc::IN_MOVED_TO |
+    libc::IN_MOVE_SELF;
+
+/// Size of the fixed part of the `inotify_event` structure.
+const EVENT_HEADER_LEN: usize = std::mem::size_of::<libc::inotify_event>();
+
+/// Size of the buffer for reading events.
+///
+/// A single event takes at most `EVENT_HEADER_LEN + NAME_MAX + 1` bytes, so the
+/// buffer fits many of them.
+const BUF_LEN: usize = 64 * 1024;
+
+/// Watcher of filesystem events on a set of paths.
+pub struct Watcher {
+    /// The inotify instance.
+    fd: OwnedFd,
+    /// Watched paths indexed by their watch descriptors.
+    paths: HashMap<libc::c_int, PathBuf>,
+    /// Buffer for reading events.
+    buf: Vec<u8>,
+}
+
+impl Watcher {
+
+    /// Starts watching the given paths.
+    pub fn new(paths: &[PathBuf]) -> std::io::Result<Watcher> {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        // SAFETY: This function is always safe to call. We verify the result
+        // below.
+        let fd = unsafe {
+            libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC)
+        };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: We verified that the descriptor is valid and it is owned by
+        // nobody else.
+        let fd = unsafe {
+            OwnedFd::from_raw_fd(fd)
+        };
+
+        let mut watched_paths = HashMap::new();
+
+        for path in paths {
+            let path_c = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+
+            // SAFETY: We pass a valid descriptor and a valid null-terminated
+            // path string. We verify the result below.
+            let wd = unsafe {
+                libc::inotify_add_watch(fd.as_raw_fd(), path_c.as_ptr(), MASK)
+            };
+            if wd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            watched_paths.insert(wd, path.clone());
+        }
+
+        Ok(Watcher {
+            fd,
+            paths: watched_paths,
+            buf: vec![0; BUF_LEN],
+        })
+    }
+
+    /// Waits up to the given timeout for notifications and returns them.
+    pub fn poll(&mut self, timeout: std::time::Duration) -> std::io::Result<Vec<Notification>> {
+        let mut pollfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = libc::c_int::try_from(timeout.as_millis())
+            .unwrap_or(libc::c_int::MAX);
+
+        // SAFETY: We pass a pointer to a single valid `pollfd` structure. We
+        // verify the result below.
+        let code = unsafe {
+            libc::poll(&mut pollfd, 1, timeout_ms)
+        };
+        if code < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(Vec::new());
+            }
+            return Err(error);
+        }
+        if code == 0 {
+            return Ok(Vec::new());
+        }
+
+        let time = std::time::SystemTime::now();
+
+        // SAFETY: We pass a valid descriptor and a buffer of the given size.
+        // We verify the result below.
+        let len = unsafe {
+            libc::read(self.fd.as_raw_fd(), self.buf.as_mut_ptr().cast(), self.buf.len())
+        };
+        if len < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::WouldBlock ||
+               error.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(Vec::new());
+            }
+            return Err(error);
+        }
+
+        Ok(parse_events(&self.buf[..len as usize], &self.paths, time))
+    }
+}
+
+/// Parses raw inotify events read from the inotify instance.
+///
+/// Events are `inotify_event` structures (a fixed header followed by a null-
+/// padded name of the given length) laid out one after another.
+fn parse_events(
+    mut buf: &[u8],
+    paths: &HashMap<libc::c_int, PathBuf>,
+    time: std::time::SystemTime,
+) -> Vec<Notification> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let mut notifications = Vec::new();
+
+    while buf.len() >= EVENT_HEADER_LEN {
+        let field = |offset: usize| -> [u8; 4] {
+            buf[offset..offset + 4].try_into()
+                .expect("invalid field length")
+        };
+
+        let wd = libc::c_int::from_ne_bytes(field(0));
+        let mask = u32::from_ne_bytes(field(4));
+        let cookie = u32::from_ne_bytes(field(8));
+        let name_len = u32::from_ne_bytes(field(12)) as usize;
+
+        let Some(name) = buf.get(EVENT_HEADER_LEN..EVENT_HEADER_LEN + name_len) else {
+            log::error!("truncated inotify event");
+            break;
+        };
+        buf = &buf[EVENT_HEADER_LEN + name_len..];
+
+        if mask & libc::IN_Q_OVERFLOW != 0 {
+            notifications.push(Notification::Overflow);
+            continue;
+        }
+
+        let kind = if mask & libc::IN_CREATE != 0 {
+            EventKind::Create
+        } else if mask & (libc::IN_DELETE | libc::IN_DELETE_SELF) != 0 {
+            EventKind::Delete
+        } else if mask & libc::IN_MODIFY != 0 {
+            EventKind::Modify
+        } else if mask & libc::IN_ATTRIB != 0 {
+            EventKind::Attrib
+        } else if mask & (libc::IN_MOVED_FROM | libc::IN_MOVE_SELF) != 0 {
+            EventKind::RenameFrom
+        } else if mask & libc::IN_MOVED_TO != 0 {
+            EventKind::RenameTo
+        } else {
+            // Other events (e.g. `IN_IGNORED` sent when a watch is removed) are
+            // not interesting to us.
+            continue;
+        };
+
+        let Some(path) = paths.get(&wd) else {
+            log::warn!("inotify event for unknown watch descriptor {wd}");
+            continue;
+        };
+
+        // The name is present only for events concerning children of watched
+        // directories and is padded with null bytes.
+        let name = match name.iter().position(|byte| *byte == 0) {
+            Some(name_len) => &name[..name_len],
+            None => name,
+        };
+        let path = if name.is_empty() {
+            path.clone()
+        } else {
+            path.join(std::ffi::OsStr::from_bytes(name))
+        };
+
+        notifications.push(Notification::Event(Event {
+            path,
+            kind,
+            cookie: if cookie == 0 { None } else { Some(cookie) },
+            time,
+        }));
+    }
+
+    notifications
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Serializes an `inotify_event` structure with the given fields.
+    fn raw_event(wd: libc::c_int, mask: u32, cookie: u32, name: &[u8]) -> Vec<u8> {
+        // Names are padded to a multiple of 16 bytes (as the kernel does).
+        let name_len = if name.is_empty() { 0 } else { (name.len() / 16 + 1) * 16 };
+
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&wd.to_ne_bytes());
+        raw.extend_from_slice(&mask.to_ne_bytes());
+        raw.extend_from_slice(&cookie.to_ne_bytes());
+        raw.extend_from_slice(&(name_len as u32).to_ne_bytes());
+        raw.extend_from_slice(name);
+        raw.resize(EVENT_HEADER_LEN + name_len, 0);
+
+        raw
+    }
+
+    #[test]
+    fn parse_events_child() {
+        let paths = HashMap::from([(1, PathBuf::from("/tmp/foo"))]);
+
+        let raw = raw_event(1, libc::IN_CREATE, 0, b"bar");
+        let notifications = parse_events(&raw, &paths, std::time::SystemTime::now());
+
+        assert_eq!(notifications.len(), 1);
+        let Notification::Event(event) = &notifications[0] else {
+            panic!("unexpected notification: {:?}", notifications[0]);
+        };
+        assert_eq!(event.path, PathBuf::from("/tmp/foo/bar"));
+        assert_eq!(event.kind, EventKind::Create);
+        assert_eq!(event.cookie, None);
+    }
+
+    #[test]
+    fn parse_events_self() {
+        let paths = HashMap::from([(1, PathBuf::from("/tmp/foo"))]);
+
+        let raw = raw_event(1, libc::IN_DELETE_SELF, 0, b"");
+        let notifications = parse_events(&raw, &paths, std::time::SystemTime::now());
+
+        assert_eq!(notifications.len(), 1);
+        let Notification::Event(event) = &notifications[0] else {
+            panic!("unexpected notification: {:?}", notifications[0]);
+        };
+        assert_eq!(event.path, PathBuf::from("/tmp/foo"));
+        assert_eq!(event.kind, EventKind::Delete);
+    }
+
+    #[test]
+    fn parse_events_rename() {
+        let paths = HashMap::from([(1, PathBuf::from("/tmp"))]);
+
+        let mut raw = raw_event(1, libc::IN_MOVED_FROM, 42, b"foo");
+        raw.extend(raw_event(1, libc::IN_MOVED_TO, 42, b"bar"));
+        let notifications = parse_events(&raw, &paths, std::time::SystemTime::now());
+
+        assert_eq!(notifications.len(), 2);
+        let Notification::Event(from) = &notifications[0] else {
+            panic!("unexpected notification: {:?}", notifications[0]);
+        };
+        let Notification::Event(to) = &notifications[1] else {
+            panic!("unexpected notification: {:?}", notifications[1]);
+        };
+        assert_eq!(from.path, PathBuf::from("/tmp/foo"));
+        assert_eq!(from.kind, EventKind::RenameFrom);
+        assert_eq!(from.cookie, Some(42));
+        assert_eq!(to.path, PathBuf::from("/tmp/bar"));
+        assert_eq!(to.kind, EventKind::RenameTo);
+        assert_eq!(to.cookie, Some(42));
+    }
+
+    #[test]
+    fn parse_events_overflow() {
+        let paths = HashMap::new();
+
+        let raw = raw_event(-1, libc::IN_Q_OVERFLOW, 0, b"");
+        let notifications = parse_events(&raw, &paths, std::time::SystemTime::now());
+
+        assert_eq!(notifications.len(), 1);
+        assert!(matches!(notifications[0], Notification::Overflow));
+    }
+
+    #[test]
+    fn parse_events_ignored() {
+        let paths = HashMap::from([(1, PathBuf::from("/tmp/foo"))]);
+
+        let raw = raw_event(1, libc::IN_IGNORED, 0, b"");
+        let notifications = parse_events(&raw, &paths, std::time::SystemTime::now());
+
+        assert!(notifications.is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Watching files using the FSEvents interface [1].
+//!
+//! FSEvents watches directory trees, so individual files are watched through
+//! their parent directories and events concerning other files are filtered out.
+//!
+//! [1]: https://developer.apple.com/documentation/coreservices/file_system_events
+
+use std::ffi::{c_char, c_void};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+
+use super::{Event, EventKind, Notification};
+
+/// Latency (in seconds) with which FSEvents delivers events.
+const LATENCY: f64 = 0.05;
+
+/// Watcher of filesystem events on a set of paths.
+pub struct Watcher {
+    /// The event stream (`FSEventStreamRef`).
+    stream: *mut c_void,
+    /// Dispatch queue on which the stream callback is invoked.
+    queue: *mut c_void,
+    /// State shared with the stream callback.
+    shared: Arc<Shared>,
+}
+
+/// State shared between the watcher and the stream callback.
+struct Shared {
+    /// Directories watched (with all their descendants).
+    dirs: Vec<PathBuf>,
+    /// Individual files watched.
+    files: Vec<PathBuf>,
+    /// State updated by the stream callback.
+    state: Mutex<State>,
+    /// Condition variable signalled when new notifications arrive.
+    condvar: Condvar,
+}
+
+/// State updated by the stream callback.
+#[derive(Default)]
+struct State {
+    /// Notifications not yet returned by the watcher.
+    notifications: Vec<Notification>,
+    /// Event identifier and cookie of the last rename that was not paired.
+    rename: Option<(u64, u32)>,
+    /// Cookie to assign to the next rename.
+    next_cookie: u32,
+}
+
+impl Shared {
+
+    /// Checks whether an event for the given path should be reported.
+    fn is_watched(&self, path: &std::path::Path) -> bool {
+        self.dirs.iter().any(|dir| path.starts_with(dir)) ||
+        self.files.iter().any(|file| path == file)
+    }
+}
+
+impl Watcher {
+
+    /// Starts watching the given paths.
+    pub fn new(paths: &[PathBuf]) -> std::io::Result<Watcher> {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+
+        for path in paths {
+            // FSEvents happily accepts paths that do not exist, so we check for
+            // them explicitly. Events are reported with symlinks resolved, so
+            // we resolve them in watched paths as well.
+            let path = path.canonicalize()?;
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+
+        let mut stream_paths = dirs.clone();
+        stream_paths.extend(files.iter().filter_map(|file| file.parent()).map(PathBuf::from));
+
+        let shared = Arc::new(Shared {
+            dirs,
+            files,
+            state: Mutex::new(State::default()),
+            condvar: Condvar::new(),
+        });
+
+        let stream_paths = cf_array_of_paths(&stream_paths)?;
+
+        let context = FSEventStreamContext {
+            version: 0,
+            info: Arc::as_ptr(&shared) as *mut c_void,
+            retain: None,
+            release: None,
+            copy_description: None,
+        };
+
+        // SAFETY: We pass a valid callback, context and array of paths. The
+        // `info` pointer stays valid for as long as the stream exists because
+        // the watcher holds a reference to the shared state and the stream is
+        // released when the watcher is dropped. The context and the array are
+        // copied (or retained) by the stream. We verify the result below.
+        let stream = unsafe {
+            FSEventStreamCreate(
+                std::ptr::null(),
+                callback,
+                &context,
+                stream_paths.0,
+                K_FS_EVENT_STREAM_EVENT_ID_SINCE_NOW,
+                LATENCY,
+                K_FS_EVENT_STREAM_CREATE_FLAG_NO_DEFER |
+                K_FS_EVENT_STREAM_CREATE_FLAG_FILE_EVENTS,
+            )
+        };
+        if stream.is_null() {
+            return Err(std::io::Error::other("failed to create event stream"));
+        }
+
+        // SAFETY: The label is a valid null-terminated string and the queue is
+        // serial (null attributes). We verify the result below.
+        let queue = unsafe {
+            dispatch_queue_create(b"rrg.watch_files\0".as_ptr().cast(), std::ptr::null())
+        };
+        if queue.is_null() {
+            // SAFETY: The stream was created above and was not started.
+            unsafe {
+                FSEventStreamRelease(stream);
+            }
+            return Err(std::io::Error::other("failed to create dispatch queue"));
+        }
+
+        let watcher = Watcher {
+            stream,
+            queue,
+            shared,
+        };
+
+        // SAFETY: Both the stream and the queue are valid.
+        unsafe {
+            FSEventStreamSetDispatchQueue(watcher.stream, watcher.queue);
+        }
+
+        // SAFETY: The stream is valid and scheduled on the queue.
+        if unsafe { FSEventStreamStart(watcher.stream) } == 0 {
+            return Err(std::io::Error::other("failed to start event stream"));
+        }
+
+        Ok(watcher)
+    }
+
+    /// Waits up to the given timeout for notifications and returns them.
+    pub fn poll(&mut self, timeout: std::time::Duration) -> std::io::Result<Vec<Notification>> {
+        let state = self.shared.state.lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let (mut state, _) = self.shared.condvar
+            .wait_timeout_while(state, timeout, |state| state.notifications.is_empty())
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        Ok(std::mem::take(&mut state.notifications))
+    }
+}
+
+impl Drop for Watcher {
+
+    fn drop(&mut self) {
+        // SAFETY: The stream and the queue are valid. Stopping and invalidating
+        // the stream guarantees that the callback is not invoked anymore, so
+        // the shared state can be safely dropped afterwards. Stopping a stream
+        // that failed to start is allowed.
+        unsafe {
+            FSEventStreamStop(self.stream);
+            FSEventStreamInvalidate(self.stream);
+            FSEventStreamRelease(self.stream);
+            dispatch_release(self.queue);
+        }
+    }
+}
+
+/// Callback invoked by the event stream with a batch of events.
+extern "C" fn callback(
+    _stream: *const c_void,
+    info: *mut c_void,
+    event_count: usize,
+    event_paths: *mut c_void,
+    event_flags: *const u32,
+    event_ids: *const u64,
+) {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    // SAFETY: The `info` pointer is the pointer to the shared state that lives
+    // for as long as the stream (see `Watcher::new`).
+    let shared = unsafe { &*(info as *const Shared) };
+
+    // SAFETY: Without the `kFSEventStreamCreateFlagUseCFTypes` flag, the paths
+    // are an array of C strings. All the arrays have `event_count` elements.
+    let (event_paths, event_flags, event_ids) = unsafe {(
+        std::slice::from_raw_parts(event_paths as *const *const c_char, event_count),
+        std::slice::from_raw_parts(event_flags, event_count),
+        std::slice::from_raw_parts(event_ids, event_count),
+    )};
+
+    let time = std::time::SystemTime::now();
+
+    let mut state = shared.state.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    for ((path, flags), id) in event_paths.iter().zip(event_flags).zip(event_ids) {
+        let flags = *flags;
+
+        if flags & (K_FS_EVENT_STREAM_EVENT_FLAG_USER_DROPPED | K_FS_EVENT_STREAM_EVENT_FLAG_KERNEL_DROPPED) != 0 {
+            state.notifications.push(Notification::Overflow);
+            continue;
+        }
+
+        // SAFETY: Paths are valid null-terminated strings (see above).
+        let path = unsafe { std::ffi::CStr::from_ptr(*path) };
+        let path = PathBuf::from(std::ffi::OsStr::from_bytes(path.to_bytes()));
+        if !shared.is_watched(&path) {
+            continue;
+        }
+
+        // A single event can carry multiple flags if the changes of the file
+        // were coalesced, we report them in the order they likely happened.
+        let mut kinds = Vec::new();
+        if flags & K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_CREATED != 0 {
+            kinds.push(EventKind::Create);
+        }
+        if flags & K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_MODIFIED != 0 {
+            kinds.push(EventKind::Modify);
+        }
+        if flags & K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_ATTRIB != 0 {
+            kinds.push(EventKind::Attrib);
+        }
+        if flags & K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_RENAMED != 0 {
+            // Both the old and the new name are reported as renames, the only
+            // way to tell them apart is to check which one exists.
+            if path.symlink_metadata().is_ok() {
+                kinds.push(EventKind::RenameTo);
+            } else {
+                kinds.push(EventKind::RenameFrom);
+            }
+        }
+        if flags & K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_REMOVED != 0 {
+            kinds.push(EventKind::Delete);
+        }
+
+        for kind in kinds {
+            let cookie = match kind {
+                EventKind::RenameFrom => {
+                    let cookie = state.next_cookie;
+                    state.next_cookie = state.next_cookie.wrapping_add(1);
+                    state.rename = Some((*id, cookie));
+                    Some(cookie)
+                }
+                // The two events of a rename have consecutive identifiers.
+                EventKind::RenameTo => match state.rename.take() {
+                    Some((rename_id, cookie)) if rename_id + 1 == *id => Some(cookie),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            state.notifications.push(Notification::Event(Event {
+                path: path.clone(),
+                kind,
+                cookie,
+                time,
+            }));
+        }
+    }
+
+    shared.condvar.notify_all();
+}
+
+/// Owned Core Foundation object released when dropped.
+struct Owned(*const c_void);
+
+impl Drop for Owned {
+
+    fn drop(&mut self) {
+        // SAFETY: The object is owned by us and was checked for null when the
+        // wrapper was created.
+        unsafe {
+            CFRelease(self.0);
+        }
+    }
+}
+
+/// Creates a Core Foundation array of strings with the given paths.
+fn cf_array_of_paths(paths: &[PathBuf]) -> std::io::Result<Owned> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let mut strings = Vec::new();
+    for path in paths {
+        let bytes = path.as_os_str().as_bytes();
+
+        // SAFETY: We pass a valid buffer with its length. We verify the result
+        // below.
+        let string = unsafe {
+            CFStringCreateWithBytes(
+                std::ptr::null(),
+                bytes.as_ptr(),
+                bytes.len() as isize,
+                K_CF_STRING_ENCODING_UTF8,
+                0,
+            )
+        };
+        if string.is_null() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format! {
+                "path '{}' is not valid UTF-8", path.display(),
+            }));
+        }
+
+        strings.push(Owned(string));
+    }
+
+    let values = strings.iter()
+        .map(|string| string.0)
+        .collect::<Vec<_>>();
+
+    // SAFETY: We pass a valid array of Core Foundation objects with its length
+    // and the standard callbacks (that retain the strings, so it is fine that
+    // we release ours afterwards). We verify the result below.
+    let array = unsafe {
+        CFArrayCreate(
+            std::ptr::null(),
+            values.as_ptr(),
+            values.len() as isize,
+            &kCFTypeArrayCallBacks,
+        )
+    };
+    if array.is_null() {
+        return Err(std::io::Error::other("failed to create array of paths"));
+    }
+
+    Ok(Owned(array))
+}
+
+/// Context of an event stream (`FSEventStreamContext`).
+#[repr(C)]
+struct FSEventStreamContext {
+    version: isize,
+    info: *mut c_void,
+    retain: Option<extern "C" fn(*const c_void) -> *const c_void>,
+    release: Option<extern "C" fn(*const c_void)>,
+    copy_description: Option<extern "C" fn(*const c_void) -> *const c_void>,
+}
+
+/// Callback of an event stream (`FSEventStreamCallback`).
+type FSEventStreamCallback = extern "C" fn(
+    *const c_void,
+    *mut c_void,
+    usize,
+    *mut c_void,
+    *const u32,
+    *const u64,
+);
+
+/// Identifier of the UTF-8 encoding (`kCFStringEncodingUTF8`).
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x08000100;
+
+/// Request to receive only events after the stream creation.
+const K_FS_EVENT_STREAM_EVENT_ID_SINCE_NOW: u64 = 0xFFFFFFFFFFFFFFFF;
+
+/// Request to deliver events without waiting for the latency to pass.
+const K_FS_EVENT_STREAM_CREATE_FLAG_NO_DEFER: u32 = 0x00000002;
+/// Request to receive events about individual files (not only directories).
+const K_FS_EVENT_STREAM_CREATE_FLAG_FILE_EVENTS: u32 = 0x00000010;
+
+/// Events were dropped because the user-space buffer overflowed.
+const K_FS_EVENT_STREAM_EVENT_FLAG_USER_DROPPED: u32 = 0x00000002;
+/// Events were dropped because the kernel buffer overflowed.
+const K_FS_EVENT_STREAM_EVENT_FLAG_KERNEL_DROPPED: u32 = 0x00000004;
+/// The file was created.
+const K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_CREATED: u32 = 0x00000100;
+/// The file was removed.
+const K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_REMOVED: u32 = 0x00000200;
+/// The file was renamed.
+const K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_RENAMED: u32 = 0x00000800;
+/// Contents of the file were modified.
+const K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_MODIFIED: u32 = 0x00001000;
+/// Metadata of the file were modified (inode metadata, Finder information,
+/// ownership or extended attributes).
+const K_FS_EVENT_STREAM_EVENT_FLAG_ITEM_ATTRIB: u32 = 0x00000400 | 0x00002000 | 0x00004000 | 0x00008000;
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFTypeArrayCallBacks: c_void;
+
+    fn CFRelease(cf: *const c_void);
+    fn CFArrayCreate(
+        alloc: *const c_void,
+        values: *const *const c_void,
+        count: isize,
+        callbacks: *const c_void,
+    ) -> *const c_void;
+    fn CFStringCreateWithBytes(
+        alloc: *const c_void,
+        bytes: *const u8,
+        len: isize,
+        encoding: u32,
+        is_external: u8,
+    ) -> *const c_void;
+}
+
+#[link(name = "CoreServices", kind = "framework")]
+extern "C" {
+    fn FSEventStreamCreate(
+        allocator: *const c_void,
+        callback: FSEventStreamCallback,
+        context: *const FSEventStreamContext,
+        paths: *const c_void,
+        since_when: u64,
+        latency: f64,
+        flags: u32,
+    ) -> *mut c_void;
+    fn FSEventStreamSetDispatchQueue(stream: *mut c_void, queue: *mut c_void);
+    fn FSEventStreamStart(stream: *mut c_void) -> u8;
+    fn FSEventStreamStop(stream: *mut c_void);
+    fn FSEventStreamInvalidate(stream: *mut c_void);
+    fn FSEventStreamRelease(stream: *mut c_void);
+}
+
+extern "C" {
+    fn dispatch_queue_create(label: *const c_char, attr: *const c_void) -> *mut c_void;
+    fn dispatch_release(object: *mut c_void);
+}
@@ -0,0 +1,389 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Watching files using the `ReadDirectoryChangesW` function [1].
+//!
+//! `ReadDirectoryChangesW` works only on directories, so individual files are
+//! watched through their parent directories and events concerning other files
+//! are filtered out.
+//!
+//! [1]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-readdirectorychangesw
+
+use std::ffi::OsString;
+use std::os::windows::io::{AsRawHandle as _, FromRawHandle as _, OwnedHandle};
+use std::path::PathBuf;
+
+use windows_sys::Win32::{
+    Foundation::*,
+    Storage::FileSystem::*,
+    System::IO::*,
+    System::Threading::*,
+};
+
+use super::{Event, EventKind, Notification};
+
+/// Size (in DWORDs) of the buffer for reading events of a single directory.
+///
+/// Notification buffers must be DWORD-aligned, hence we express the size in
+/// DWORDs rather than in bytes.
+const BUF_LEN: usize = 16 * 1024;
+
+/// Filter of changes that we register for.
+const FILTER: u32 = FILE_NOTIFY_CHANGE_FILE_NAME |
+    FILE_NOTIFY_CHANGE_DIR_NAME |
+    FILE_NOTIFY_CHANGE_ATTRIBUTES |
+    FILE_NOTIFY_CHANGE_SIZE |
+    FILE_NOTIFY_CHANGE_LAST_WRITE |
+    FILE_NOTIFY_CHANGE_SECURITY;
+
+/// Watcher of filesystem events on a set of paths.
+pub struct Watcher {
+    /// Watched directories.
+    dirs: Vec<Dir>,
+    /// Cookie of the last rename for which only the old name was reported.
+    rename: Option<u32>,
+    /// Cookie to assign to the next rename.
+    next_cookie: u32,
+}
+
+/// Directory watched with a pending `ReadDirectoryChangesW` call.
+struct Dir {
+    /// Path to the directory.
+    path: PathBuf,
+    /// Name of the only child of the directory to report events for (if the
+    /// directory is watched on behalf of a single file).
+    name: Option<OsString>,
+    /// Handle to the directory.
+    handle: OwnedHandle,
+    /// Event signalled when the pending call completes.
+    event: OwnedHandle,
+    /// Overlapped structure of the pending call.
+    ///
+    /// It is boxed as the system holds a pointer to it until the call completes.
+    overlapped: Box<OVERLAPPED>,
+    /// Buffer for reading events.
+    ///
+    /// It is boxed as the system holds a pointer to it until the call completes.
+    buf: Box<[u32]>,
+    /// Whether there is a pending call (false if the directory can no longer
+    /// be watched, e.g. because it was deleted).
+    active: bool,
+}
+
+impl Watcher {
+
+    /// Starts watching the given paths.
+    pub fn new(paths: &[PathBuf]) -> std::io::Result<Watcher> {
+        let mut dirs = Vec::new();
+
+        for path in paths {
+            let (dir_path, name) = if std::fs::metadata(path)?.is_dir() {
+                (path.clone(), None)
+            } else {
+                match (path.parent(), path.file_name()) {
+                    (Some(parent), Some(name)) => {
+                        (parent.to_path_buf(), Some(name.to_os_string()))
+                    }
+                    _ => (path.clone(), None),
+                }
+            };
+
+            let mut dir = Dir::open(dir_path, name)?;
+            dir.arm()?;
+
+            dirs.push(dir);
+        }
+
+        Ok(Watcher {
+            dirs,
+            rename: None,
+            next_cookie: 0,
+        })
+    }
+
+    /// Waits up to the given timeout for notifications and returns them.
+    pub fn poll(&mut self, timeout: std::time::Duration) -> std::io::Result<Vec<Notification>> {
+        let events = self.dirs.iter()
+            .filter(|dir| dir.active)
+            .map(|dir| dir.event.as_raw_handle())
+            .collect::<Vec<_>>();
+
+        if events.is_empty() {
+            // There is nothing to wait for anymore, but the action is supposed
+            // to last until its deadline.
+            std::thread::sleep(timeout);
+            return Ok(Vec::new());
+        }
+
+        let timeout_ms = u32::try_from(timeout.as_millis())
+            .unwrap_or(u32::MAX - 1);
+
+        // SAFETY: We pass a valid array of event handles with its length (that
+        // is within limits as the number of paths is bounded by the action).
+        // We verify the result below.
+        let status = unsafe {
+            WaitForMultipleObjects(events.len() as u32, events.as_ptr(), FALSE, timeout_ms)
+        };
+        if status == WAIT_TIMEOUT {
+            return Ok(Vec::new());
+        }
+        if status == WAIT_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let time = std::time::SystemTime::now();
+
+        let mut notifications = Vec::new();
+
+        for dir in &mut self.dirs {
+            if !dir.active {
+                continue;
+            }
+
+            let mut len = 0;
+            // SAFETY: The handle and the overlapped structure are valid and
+            // belong to the same pending call. We verify the result below.
+            let status = unsafe {
+                GetOverlappedResult(dir.handle.as_raw_handle(), &*dir.overlapped, &mut len, FALSE)
+            };
+            if status == FALSE {
+                let error = std::io::Error::last_os_error();
+                match error.raw_os_error().map(|code| code as u32) {
+                    Some(ERROR_IO_INCOMPLETE) => continue,
+                    Some(ERROR_NOTIFY_ENUM_DIR) => {
+                        notifications.push(Notification::Overflow);
+                    }
+                    _ => {
+                        log::warn! {
+                            "failed to watch '{}': {error}",
+                            dir.path.display(),
+                        };
+                        dir.active = false;
+                        continue;
+                    }
+                }
+            } else if len == 0 {
+                // Zero-length result means that the buffer was too small to
+                // fit all the changes and they were dropped.
+                notifications.push(Notification::Overflow);
+            } else {
+                for (kind, name) in parse_events(&dir.buf, len as usize) {
+                    if let Some(ref dir_name) = dir.name {
+                        if *dir_name != name {
+                            continue;
+                        }
+                    }
+
+                    let cookie = match kind {
+                        EventKind::RenameFrom => {
+                            let cookie = self.next_cookie;
+                            self.next_cookie = self.next_cookie.wrapping_add(1);
+                            self.rename = Some(cookie);
+                            Some(cookie)
+                        }
+                        EventKind::RenameTo => self.rename.take(),
+                        _ => None,
+                    };
+
+                    notifications.push(Notification::Event(Event {
+                        path: dir.path.join(name),
+                        kind,
+                        cookie,
+                        time,
+                    }));
+                }
+            }
+
+            if let Err(error) = dir.arm() {
+                log::warn! {
+                    "failed to watch '{}': {error}",
+                    dir.path.display(),
+                };
+                dir.active = false;
+            }
+        }
+
+        Ok(notifications)
+    }
+}
+
+impl Dir {
+
+    /// Opens the given directory for watching.
+    fn open(path: PathBuf, name: Option<OsString>) -> std::io::Result<Dir> {
+        use std::os::windows::ffi::OsStrExt as _;
+
+        let path_wide = path.as_os_str().encode_wide()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+
+        // SAFETY: We pass a valid null-terminated path string. We verify the
+        // result below.
+        let handle = unsafe {
+            CreateFileW(
+                path_wide.as_ptr(),
+                FILE_LIST_DIRECTORY,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: We verified that the handle is valid and it is owned by
+        // nobody else.
+        let handle = unsafe {
+            OwnedHandle::from_raw_handle(handle)
+        };
+
+        // SAFETY: This function is safe to call with null attributes and name.
+        // We verify the result below.
+        let event = unsafe {
+            CreateEventW(std::ptr::null(), TRUE, FALSE, std::ptr::null())
+        };
+        if event.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: We verified that the handle is valid and it is owned by
+        // nobody else.
+        let event = unsafe {
+            OwnedHandle::from_raw_handle(event)
+        };
+
+        // SAFETY: The structure consists of plain integers and pointers for
+        // which zero is a valid value.
+        let overlapped = Box::new(unsafe {
+            std::mem::zeroed::<OVERLAPPED>()
+        });
+
+        Ok(Dir {
+            path,
+            name,
+            handle,
+            event,
+            overlapped,
+            buf: vec![0; BUF_LEN].into_boxed_slice(),
+            active: false,
+        })
+    }
+
+    /// Issues a new `ReadDirectoryChangesW` call for the directory.
+    fn arm(&mut self) -> std::io::Result<()> {
+        self.overlapped.hEvent = self.event.as_raw_handle();
+
+        // SAFETY: We pass a valid directory handle, a DWORD-aligned buffer with
+        // its size (in bytes) and an overlapped structure with a valid event.
+        // Both the buffer and the overlapped structure are heap-allocated and
+        // outlive the call (see `Drop`). We verify the result below.
+        let status = unsafe {
+            ReadDirectoryChangesW(
+                self.handle.as_raw_handle(),
+                self.buf.as_mut_ptr().cast(),
+                (self.buf.len() * std::mem::size_of::<u32>()) as u32,
+                FALSE,
+                FILTER,
+                std::ptr::null_mut(),
+                &mut *self.overlapped,
+                None,
+            )
+        };
+        if status == FALSE {
+            self.active = false;
+            return Err(std::io::Error::last_os_error());
+        }
+
+        self.active = true;
+        Ok(())
+    }
+}
+
+impl Drop for Dir {
+
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+
+        // SAFETY: The handle and the overlapped structure are valid. We wait
+        // for the cancelled call to complete, so that the system no longer
+        // holds pointers to the buffer and the overlapped structure when they
+        // are freed.
+        unsafe {
+            if CancelIoEx(self.handle.as_raw_handle(), &*self.overlapped) != FALSE {
+                let mut len = 0;
+                GetOverlappedResult(self.handle.as_raw_handle(), &*self.overlapped, &mut len, TRUE);
+            }
+        }
+    }
+}
+
+/// Parses `FILE_NOTIFY_INFORMATION` records returned by the system.
+///
+/// Records are variable-length structures (a fixed header followed by a wide
+/// string name) linked by offsets to the next record.
+fn parse_events(buf: &[u32], len: usize) -> Vec<(EventKind, OsString)> {
+    use std::os::windows::ffi::OsStringExt as _;
+
+    // SAFETY: Every `u32` can be viewed as four bytes.
+    let buf = unsafe {
+        std::slice::from_raw_parts(buf.as_ptr().cast::<u8>(), buf.len() * std::mem::size_of::<u32>())
+    };
+    let buf = &buf[..std::cmp::min(len, buf.len())];
+
+    let field = |offset: usize| -> Option<u32> {
+        let bytes = buf.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes(bytes.try_into().expect("invalid field length")))
+    };
+
+    let mut events = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let (Some(next), Some(action), Some(name_len)) = (
+            field(offset),
+            field(offset + 4),
+            field(offset + 8),
+        ) else {
+            log::error!("truncated change notification");
+            break;
+        };
+
+        let name_offset = offset + 12;
+        let Some(name) = buf.get(name_offset..name_offset + name_len as usize) else {
+            log::error!("truncated change notification name");
+            break;
+        };
+        let name = name.chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+            .collect::<Vec<u16>>();
+
+        let kind = match action {
+            FILE_ACTION_ADDED => Some(EventKind::Create),
+            FILE_ACTION_REMOVED => Some(EventKind::Delete),
+            FILE_ACTION_MODIFIED => Some(EventKind::Modify),
+            FILE_ACTION_RENAMED_OLD_NAME => Some(EventKind::RenameFrom),
+            FILE_ACTION_RENAMED_NEW_NAME => Some(EventKind::RenameTo),
+            _ => {
+                log::warn!("unexpected change notification action: {action}");
+                None
+            }
+        };
+        if let Some(kind) = kind {
+            events.push((kind, OsString::from_wide(&name)));
+        }
+
+        if next == 0 {
+            break;
+        }
+        offset += next as usize;
+    }
+
+    events
+}
@@ -0,0 +1,662 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Local admin endpoint of the agent.
+//!
+//! Operators on the machine can ask the agent what it is doing without going
+//! through the server. If the agent is started with the `--admin-socket` flag,
+//! it serves read-only queries on a Unix domain socket (or a named pipe on
+//! Windows) at the given path: the status of the agent (requests being handled
+//! at the moment, basic metrics, digest of the configuration and the number of
+//! spooled crash reports) and results of its health checks.
+//!
+//! The protocol is deliberately minimal: both queries and answers are the
+//! messages defined in `rrg/admin.proto` prefixed with their length encoded as
+//! a 64-bit big-endian integer.
+//!
+//! Access to the endpoint is controlled solely by the operating system: on Unix
+//! the socket is accessible only to the user the agent runs as (note that the
+//! permissions are restricted only after the socket is created, so it should be
+//! placed in a directory not accessible to others) and on Windows the pipe is
+//! accessible only to the system, administrators and the pipe owner.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::{error, info, warn};
+
+/// Maximum length of a query message accepted by the endpoint.
+const MAX_REQUEST_LEN: u64 = 64 * 1024; // 64 KiB.
+
+/// Requests that are being handled at the moment.
+static RUNNING_REQUESTS: Mutex<Vec<RunningRequest>> = Mutex::new(Vec::new());
+
+/// Number of requests handled since the agent was started.
+static REQUESTS_HANDLED: AtomicU64 = AtomicU64::new(0);
+
+/// Time at which the agent was started.
+static STARTUP_TIME: std::sync::OnceLock<std::time::SystemTime> = std::sync::OnceLock::new();
+
+/// Spawns the admin endpoint if the agent has been configured with one.
+///
+/// This function should be called only once, at the beginning of the process
+/// lifetime.
+pub fn init(args: &crate::reload::Handle) {
+    STARTUP_TIME.get_or_init(std::time::SystemTime::now);
+
+    let Some(path) = args.load().admin_socket.clone() else {
+        return;
+    };
+
+    let listener = match sys::Listener::bind(&path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("failed to bind admin endpoint at '{}': {error}", path.display());
+            return;
+        }
+    };
+    info!("serving admin queries at '{}'", path.display());
+
+    let args = args.clone();
+    let result = std::thread::Builder::new()
+        .name(String::from("admin"))
+        .spawn(move || serve(listener, &args));
+    if let Err(error) = result {
+        error!("failed to spawn admin endpoint: {error}");
+    }
+}
+
+/// A guard marking a request as being handled for the admin endpoint.
+///
+/// The request is no longer reported as running once the guard is dropped.
+pub struct RequestGuard {
+    /// Progress of the request shared with the list of running requests.
+    progress: Arc<Progress>,
+}
+
+impl RequestGuard {
+
+    /// Marks the request with the given identifier as being handled.
+    pub fn new(request_id: crate::RequestId, action: crate::request::Action) -> RequestGuard {
+        let progress = Arc::new(Progress::default());
+
+        running_requests().push(RunningRequest {
+            request_id,
+            action,
+            start_time: std::time::SystemTime::now(),
+            progress: Arc::clone(&progress),
+        });
+
+        RequestGuard { progress }
+    }
+
+    /// Records that a reply has been sent within the request.
+    pub fn record_reply(&self) {
+        self.progress.replies_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the given number of bytes has been sent within the request.
+    pub fn record_network_bytes(&self, network_bytes_sent: u64) {
+        self.progress.network_bytes_sent.fetch_add(network_bytes_sent, Ordering::Relaxed);
+    }
+}
+
+impl Drop for RequestGuard {
+
+    fn drop(&mut self) {
+        running_requests().retain(|request| !Arc::ptr_eq(&request.progress, &self.progress));
+        REQUESTS_HANDLED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Request that is being handled at the moment.
+struct RunningRequest {
+    /// Identifier of the request.
+    request_id: crate::RequestId,
+    /// Action invoked by the request.
+    action: crate::request::Action,
+    /// Time at which the agent started handling the request.
+    start_time: std::time::SystemTime,
+    /// Progress of the request updated by its session.
+    progress: Arc<Progress>,
+}
+
+/// Progress of a request that is being handled.
+#[derive(Default)]
+struct Progress {
+    /// Number of replies sent so far.
+    replies_sent: AtomicU64,
+    /// Number of bytes sent so far.
+    network_bytes_sent: AtomicU64,
+}
+
+/// Returns the list of requests that are being handled at the moment.
+fn running_requests() -> std::sync::MutexGuard<'static, Vec<RunningRequest>> {
+    // The list is always left in a consistent state, so it is fine to use it
+    // even if some other thread panicked while holding the lock.
+    RUNNING_REQUESTS.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Serves connections accepted by the given listener one after another.
+fn serve(listener: sys::Listener, args: &crate::reload::Handle) {
+    loop {
+        let stream = match listener.accept() {
+            Ok(stream) => stream,
+            Err(error) => {
+                error!("failed to accept admin connection: {error}");
+                continue;
+            }
+        };
+
+        if let Err(error) = serve_connection(stream, args) {
+            warn!("failed to serve admin connection: {error}");
+        }
+    }
+}
+
+/// Serves queries sent over the given connection until it is closed.
+fn serve_connection<S>(mut stream: S, args: &crate::reload::Handle) -> std::io::Result<()>
+where
+    S: std::io::Read + std::io::Write,
+{
+    while let Some(request) = read_request(&mut stream)? {
+        let response = query(&args.load(), request);
+        write_response(&mut stream, &response)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a single length-prefixed query from the given stream.
+///
+/// `None` is returned if the stream is closed before the next query.
+fn read_request<R>(stream: &mut R) -> std::io::Result<Option<rrg_proto::admin::Request>>
+where
+    R: std::io::Read,
+{
+    use protobuf::Message as _;
+
+    let mut len_buf = [0; std::mem::size_of::<u64>()];
+    match stream.read_exact(&mut len_buf) {
+        Ok(()) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+
+    let len = u64::from_be_bytes(len_buf);
+    if len > MAX_REQUEST_LEN {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format! {
+            "query too long ({len} bytes)",
+        }));
+    }
+
+    let mut buf = vec![0; len as usize];
+    stream.read_exact(&mut buf)?;
+
+    let request = rrg_proto::admin::Request::parse_from_bytes(&buf)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    Ok(Some(request))
+}
+
+/// Writes a single length-prefixed answer to the given stream.
+fn write_response<W>(stream: &mut W, response: &rrg_proto::admin::Response) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    use protobuf::Message as _;
+
+    let buf = response.write_to_bytes()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    stream.write_all(&(buf.len() as u64).to_be_bytes())?;
+    stream.write_all(&buf)?;
+    stream.flush()
+}
+
+/// Executes the given query against the agent with the given arguments.
+fn query(
+    args: &crate::args::Args,
+    request: rrg_proto::admin::Request,
+) -> rrg_proto::admin::Response {
+    use rrg_proto::admin::Query;
+
+    let mut response = rrg_proto::admin::Response::new();
+    match request.query() {
+        Query::STATUS => response.set_status(status(args)),
+        #[cfg(feature = "action-get_agent_health")]
+        Query::HEALTH_CHECK => {
+            response.set_health_checks(crate::action::get_agent_health::run(args));
+        }
+        #[cfg(not(feature = "action-get_agent_health"))]
+        Query::HEALTH_CHECK => {
+            response.set_error(String::from("health checks not available"));
+        }
+        Query::UNKNOWN => response.set_error(String::from("unknown query")),
+    }
+
+    response
+}
+
+/// Returns the current status of the agent with the given arguments.
+fn status(args: &crate::args::Args) -> rrg_proto::admin::Status {
+    use rrg_proto::into_timestamp;
+
+    let mut proto = rrg_proto::admin::Status::new();
+
+    for request in running_requests().iter() {
+        let mut request_proto = rrg_proto::admin::RunningRequest::new();
+        request_proto.set_flow_id(request.request_id.flow_id());
+        request_proto.set_request_id(request.request_id.request_id());
+        request_proto.set_action(request.action.into());
+        request_proto.set_start_time(into_timestamp(request.start_time));
+        request_proto.set_replies_sent(request.progress.replies_sent.load(Ordering::Relaxed));
+        request_proto.set_network_bytes_sent(request.progress.network_bytes_sent.load(Ordering::Relaxed));
+
+        proto.mut_running_requests().push(request_proto);
+    }
+
+    let metrics = proto.mut_metrics();
+    if let Some(startup_time) = STARTUP_TIME.get() {
+        metrics.set_agent_startup_time(into_timestamp(*startup_time));
+    }
+    metrics.set_requests_handled(REQUESTS_HANDLED.load(Ordering::Relaxed));
+    metrics.set_memory_allocated(crate::resource::memory_allocated() as u64);
+    metrics.set_requests_queued(crate::mode::queued_count() as u64);
+
+    proto.set_config_sha256(crate::config::digest(args).to_vec());
+    proto.set_mode(crate::mode::current().into());
+
+    match crate::crash::spool_depth(args) {
+        Ok(depth) => proto.set_crash_spool_depth(depth as u64),
+        Err(error) => warn!("failed to count spooled crash reports: {error}"),
+    }
+
+    proto
+}
+
+#[cfg(target_family = "unix")]
+mod sys {
+
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    /// Time after which idle connections to the socket are dropped.
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+    /// Listener of connections to the Unix domain socket.
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+
+        /// Binds the socket at the given path accessible only to the owner.
+        pub fn bind(path: &Path) -> std::io::Result<Listener> {
+            use std::os::unix::fs::{FileTypeExt as _, PermissionsExt as _};
+
+            // A socket left by a previous instance of the agent would make the
+            // binding fail, so we remove it (but nothing else).
+            match std::fs::symlink_metadata(path) {
+                Ok(metadata) if metadata.file_type().is_socket() => {
+                    std::fs::remove_file(path)?;
+                }
+                Ok(_) | Err(_) => (),
+            }
+
+            let listener = UnixListener::bind(path)?;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+            Ok(Listener(listener))
+        }
+
+        /// Waits for the next connection to the socket.
+        pub fn accept(&self) -> std::io::Result<UnixStream> {
+            let (stream, _) = self.0.accept()?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+
+            Ok(stream)
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    use std::os::windows::io::FromRawHandle as _;
+    use std::path::Path;
+
+    use windows_sys::Win32::{
+        Foundation::*,
+        Security::*,
+        Security::Authorization::*,
+        Storage::FileSystem::*,
+        System::Pipes::*,
+    };
+
+    /// Security descriptor of the pipe in the SDDL format.
+    ///
+    /// The pipe is accessible only to the system, administrators and its owner
+    /// (i.e. the user the agent runs as).
+    const SDDL: &str = "D:P(A;;GA;;;SY)(A;;GA;;;BA)(A;;GA;;;OW)";
+
+    // TODO: Drop idle connections (pipes opened without overlapped I/O do not
+    // support timeouts, so for now a client can block the endpoint until it
+    // disconnects).
+
+    /// Size of the input and output buffers of the pipe (in bytes).
+    const BUF_SIZE: u32 = 64 * 1024;
+
+    /// Listener of connections to the named pipe.
+    pub struct Listener {
+        /// Name of the pipe as a null-terminated wide string.
+        name: Vec<u16>,
+        /// Security descriptor that every instance of the pipe is created with.
+        security_descriptor: PSECURITY_DESCRIPTOR,
+        /// Instance of the pipe created upfront that waits for a connection.
+        pending: std::cell::Cell<Option<std::fs::File>>,
+    }
+
+    // SAFETY: The security descriptor is owned by the listener and is never
+    // mutated once created, so it can be used from any thread.
+    unsafe impl Send for Listener {}
+
+    impl Listener {
+
+        /// Creates the first instance of the named pipe with the given path.
+        pub fn bind(path: &Path) -> std::io::Result<Listener> {
+            use std::os::windows::ffi::OsStrExt as _;
+
+            let name = path.as_os_str().encode_wide()
+                .chain(std::iter::once(0))
+                .collect::<Vec<u16>>();
+
+            let sddl = SDDL.encode_utf16()
+                .chain(std::iter::once(0))
+                .collect::<Vec<u16>>();
+
+            let mut security_descriptor = std::ptr::null_mut();
+            // SAFETY: The descriptor string is a valid null-terminated wide
+            // string and the output pointer is valid. We verify the result
+            // below and free the descriptor once the listener is dropped.
+            let status = unsafe {
+                ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                    sddl.as_ptr(),
+                    SDDL_REVISION_1,
+                    &mut security_descriptor,
+                    std::ptr::null_mut(),
+                )
+            };
+            if status == FALSE {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            let listener = Listener {
+                name,
+                security_descriptor,
+                pending: std::cell::Cell::new(None),
+            };
+
+            // The first instance fails to be created if some other process
+            // already owns a pipe with the same name (so that nobody can
+            // impersonate the agent).
+            let pipe = listener.create(FILE_FLAG_FIRST_PIPE_INSTANCE)?;
+            listener.pending.set(Some(pipe));
+
+            Ok(listener)
+        }
+
+        /// Waits for the next connection to the pipe.
+        pub fn accept(&self) -> std::io::Result<std::fs::File> {
+            use std::os::windows::io::AsRawHandle as _;
+
+            let pipe = match self.pending.take() {
+                Some(pipe) => pipe,
+                None => self.create(0)?,
+            };
+
+            // SAFETY: The handle is a valid named pipe handle opened without
+            // overlapped I/O. We verify the result below.
+            let status = unsafe {
+                ConnectNamedPipe(pipe.as_raw_handle(), std::ptr::null_mut())
+            };
+            // A client might have connected between the pipe creation and the
+            // call above which is reported as an error but is not a problem.
+            if status == FALSE {
+                let error = std::io::Error::last_os_error();
+                if error.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+                    return Err(error);
+                }
+            }
+
+            Ok(pipe)
+        }
+
+        /// Creates a new instance of the pipe with the given extra flags.
+        fn create(&self, flags: u32) -> std::io::Result<std::fs::File> {
+            let security_attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: self.security_descriptor,
+                bInheritHandle: FALSE,
+            };
+
+            // SAFETY: The name is a valid null-terminated wide string and the
+            // security attributes are valid for the duration of the call. We
+            // verify the result below.
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    self.name.as_ptr(),
+                    PIPE_ACCESS_DUPLEX | flags,
+                    PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                    PIPE_UNLIMITED_INSTANCES,
+                    BUF_SIZE,
+                    BUF_SIZE,
+                    0,
+                    &security_attributes,
+                )
+            };
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            // SAFETY: The handle is a valid handle that we exclusively own. The
+            // standard library reads from and writes to it as from any other
+            // file (and reports closed pipes as the end of the stream).
+            Ok(unsafe { std::fs::File::from_raw_handle(handle) })
+        }
+    }
+
+    impl Drop for Listener {
+
+        fn drop(&mut self) {
+            // SAFETY: The descriptor has been allocated by the system with
+            // `LocalAlloc` and is not used after this point.
+            unsafe {
+                LocalFree(self.security_descriptor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn handle() -> crate::reload::Handle {
+        use argh::FromArgs as _;
+
+        let args = crate::args::Args::from_args(&["rrg"], &[])
+            .unwrap();
+
+        crate::reload::Handle::new(args)
+    }
+
+    fn request(query: rrg_proto::admin::Query) -> rrg_proto::admin::Request {
+        let mut request = rrg_proto::admin::Request::new();
+        request.set_query(query);
+
+        request
+    }
+
+    /// Sends the given query over the given stream and reads the answer.
+    fn roundtrip<S>(stream: &mut S, query: rrg_proto::admin::Query) -> rrg_proto::admin::Response
+    where
+        S: std::io::Read + std::io::Write,
+    {
+        use protobuf::Message as _;
+
+        let buf = request(query).write_to_bytes()
+            .unwrap();
+        stream.write_all(&(buf.len() as u64).to_be_bytes())
+            .unwrap();
+        stream.write_all(&buf)
+            .unwrap();
+
+        let mut len_buf = [0; 8];
+        stream.read_exact(&mut len_buf)
+            .unwrap();
+        let mut buf = vec![0; u64::from_be_bytes(len_buf) as usize];
+        stream.read_exact(&mut buf)
+            .unwrap();
+
+        rrg_proto::admin::Response::parse_from_bytes(&buf)
+            .unwrap()
+    }
+
+    #[test]
+    fn read_request_empty() {
+        let mut stream = std::io::Cursor::new(Vec::<u8>::new());
+        assert!(read_request(&mut stream).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_request_too_long() {
+        let mut stream = std::io::Cursor::new((MAX_REQUEST_LEN + 1).to_be_bytes().to_vec());
+        assert!(read_request(&mut stream).is_err());
+    }
+
+    #[test]
+    fn query_unknown() {
+        let response = query(&handle().load(), request(rrg_proto::admin::Query::UNKNOWN));
+        assert!(!response.error().is_empty());
+    }
+
+    #[test]
+    fn query_status_running_request() {
+        let request_id = crate::RequestId::new(0xf00, 0x51a);
+        let guard = RequestGuard::new(request_id, crate::request::Action::GetSystemMetadata);
+        guard.record_reply();
+        guard.record_network_bytes(1337);
+
+        let response = query(&handle().load(), request(rrg_proto::admin::Query::STATUS));
+        let running = response.status().running_requests().iter()
+            .find(|request| request.flow_id() == 0xf00 && request.request_id() == 0x51a)
+            .unwrap();
+        assert_eq!(running.action(), rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+        assert_eq!(running.replies_sent(), 1);
+        assert_eq!(running.network_bytes_sent(), 1337);
+        assert_eq!(response.status().config_sha256().len(), 32);
+
+        drop(guard);
+
+        let response = query(&handle().load(), request(rrg_proto::admin::Query::STATUS));
+        assert!(!response.status().running_requests().iter()
+            .any(|request| request.flow_id() == 0xf00 && request.request_id() == 0x51a));
+    }
+
+    #[cfg(feature = "action-get_agent_health")]
+    #[test]
+    fn query_health_check() {
+        let response = query(&handle().load(), request(rrg_proto::admin::Query::HEALTH_CHECK));
+        assert!(response.error().is_empty());
+        assert!(!response.health_checks().is_empty());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn serve_socket() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("admin.sock");
+
+        let listener = sys::Listener::bind(&path)
+            .unwrap();
+
+        use std::os::unix::fs::PermissionsExt as _;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let handle = handle();
+        std::thread::spawn(move || serve(listener, &handle));
+
+        let request_id = crate::RequestId::new(0xf00, 0x50c);
+        let _guard = RequestGuard::new(request_id, crate::request::Action::GetFileContents);
+
+        let mut stream = std::os::unix::net::UnixStream::connect(&path)
+            .unwrap();
+
+        let response = roundtrip(&mut stream, rrg_proto::admin::Query::STATUS);
+        let running = response.status().running_requests().iter()
+            .find(|request| request.flow_id() == 0xf00 && request.request_id() == 0x50c)
+            .unwrap();
+        assert_eq!(running.action(), rrg_proto::rrg::Action::GET_FILE_CONTENTS);
+
+        // The connection can be used for more than one query.
+        let response = roundtrip(&mut stream, rrg_proto::admin::Query::UNKNOWN);
+        assert!(!response.error().is_empty());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn bind_stale_socket() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("admin.sock");
+
+        drop(sys::Listener::bind(&path).unwrap());
+        assert!(sys::Listener::bind(&path).is_ok());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn bind_not_socket() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("admin.sock");
+        std::fs::write(&path, b"")
+            .unwrap();
+
+        assert!(sys::Listener::bind(&path).is_err());
+        assert!(path.exists());
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn serve_pipe() {
+        let path = std::path::PathBuf::from(format! {
+            r"\\.\pipe\rrg-admin-test-{}", std::process::id(),
+        });
+
+        let listener = sys::Listener::bind(&path)
+            .unwrap();
+
+        let handle = handle();
+        std::thread::spawn(move || serve(listener, &handle));
+
+        let request_id = crate::RequestId::new(0xf00, 0x91e);
+        let _guard = RequestGuard::new(request_id, crate::request::Action::GetFileContents);
+
+        let mut stream = std::fs::File::options()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+
+        let response = roundtrip(&mut stream, rrg_proto::admin::Query::STATUS);
+        assert!(response.status().running_requests().iter()
+            .any(|request| request.flow_id() == 0xf00 && request.request_id() == 0x91e));
+    }
+}
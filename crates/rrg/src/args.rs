@@ -57,6 +57,233 @@ pub struct Args {
        description="verification key for signed commands",
        from_str_fn(parse_verfication_key))]
     pub command_verification_key: Option<ed25519_dalek::VerifyingKey>,
+
+    /// Path to the file with the rotated set of command verification keys.
+    #[argh(option,
+           long="command-verification-key-store",
+           arg_name="PATH",
+           description="path to the file with rotated command verification keys")]
+    pub command_verification_key_store: Option<std::path::PathBuf>,
+
+    /// Path to the local audit log of executed signed commands.
+    #[argh(option,
+           long="command-audit-log",
+           arg_name="PATH",
+           description="path to the audit log of executed signed commands")]
+    pub command_audit_log: Option<std::path::PathBuf>,
+
+    /// Maximum number of signed commands executed within an hour.
+    #[argh(option,
+           long="command-rate-limit",
+           arg_name="COUNT",
+           description="maximum number of signed commands executed per hour")]
+    pub command_rate_limit: Option<u32>,
+
+    /// The public key for verifying agent updates.
+    #[argh(option,
+       long="update-verification-key",
+       arg_name="KEY",
+       description="verification key for agent updates",
+       from_str_fn(parse_verfication_key))]
+    pub update_verification_key: Option<ed25519_dalek::VerifyingKey>,
+
+    /// Path to the osquery shell executable.
+    #[argh(option,
+           long="osquery-path",
+           arg_name="PATH",
+           description="path to the osqueryi executable")]
+    pub osquery_path: Option<std::path::PathBuf>,
+
+    /// Actions that the agent is allowed to execute (all if empty).
+    #[argh(option,
+           long="allow-action",
+           arg_name="ACTION",
+           description="action that the agent is allowed to execute (repeatable)")]
+    pub allowed_actions: Vec<crate::request::Action>,
+
+    /// Actions that the agent is not allowed to execute.
+    #[argh(option,
+           long="deny-action",
+           arg_name="ACTION",
+           description="action that the agent is not allowed to execute (repeatable)")]
+    pub denied_actions: Vec<crate::request::Action>,
+
+    /// Path prefixes that the agent actions are not allowed to access.
+    #[argh(option,
+           long="deny-path",
+           arg_name="[ACTION=]PATH",
+           description="path prefix that actions are not allowed to access (repeatable)")]
+    pub denied_paths: Vec<crate::policy::PathRule>,
+
+    /// Patterns of path parts to redact in results sent to the server.
+    #[argh(option,
+           long="redact-path",
+           arg_name="REGEX",
+           description="pattern of path parts to redact in results (repeatable)")]
+    pub redacted_paths: Vec<crate::redact::Pattern>,
+
+    /// URL of the HTTPS endpoint to upload large blobs to.
+    #[argh(option,
+           long="blob-upload-url",
+           arg_name="URL",
+           description="URL of the HTTPS endpoint to upload large blobs to")]
+    pub blob_upload_url: Option<String>,
+
+    /// SHA-256 digests of public keys that the blob upload endpoint can use.
+    #[argh(option,
+           long="blob-upload-pin",
+           arg_name="SHA256",
+           description="SHA-256 digest of a pinned blob upload endpoint key (repeatable)",
+           from_str_fn(parse_sha256))]
+    pub blob_upload_pins: Vec<[u8; 32]>,
+
+    /// Minimum size of blobs to upload to the HTTPS endpoint.
+    #[argh(option,
+           long="blob-upload-threshold",
+           arg_name="SIZE",
+           default="1024 * 1024",
+           description="minimum size (in bytes) of blobs to upload to the HTTPS endpoint")]
+    pub blob_upload_threshold: usize,
+
+    /// Amount of queued data above which blob production is paused.
+    #[argh(option,
+           long="sink-pressure-high-watermark",
+           arg_name="SIZE",
+           description="amount (in bytes) of data queued for a sink above which actions pause sending")]
+    pub sink_pressure_high_watermark: Option<usize>,
+
+    /// Amount of queued data below which paused blob production is resumed.
+    #[argh(option,
+           long="sink-pressure-low-watermark",
+           arg_name="SIZE",
+           description="amount (in bytes) of data queued for a sink below which paused actions resume (defaults to half of the high watermark)")]
+    pub sink_pressure_low_watermark: Option<usize>,
+
+    /// Algorithms to compute digests of blobs sent to the server with.
+    #[argh(option,
+           long="blob-digest",
+           arg_name="ALGORITHM",
+           description="algorithm to compute digests of blobs with: sha256 (default) or blake3 (repeatable)")]
+    pub blob_digests: Vec<crate::blob::DigestAlgorithm>,
+
+    /// Minimum size of files to memory-map when hashing or scanning them.
+    #[argh(option,
+           long="mmap-threshold",
+           arg_name="SIZE",
+           description="minimum size (in bytes) of files to memory-map when hashing or scanning")]
+    pub mmap_threshold: Option<u64>,
+
+    /// Minimum size of serialized results to compress before sending.
+    #[argh(option,
+           long="reply-compression-threshold",
+           arg_name="SIZE",
+           description="minimum size (in bytes) of results to compress with zstd")]
+    pub reply_compression_threshold: Option<usize>,
+
+    /// Maximum amount of memory the agent process is allowed to use.
+    #[argh(option,
+           long="memory-limit",
+           arg_name="SIZE",
+           description="maximum amount of memory (in bytes) the agent can use")]
+    pub memory_limit: Option<usize>,
+
+    /// Maximum total size of files written to the scratch directory of a request.
+    #[argh(option,
+           long="temp-dir-size-limit",
+           arg_name="SIZE",
+           description="maximum total size (in bytes) of scratch files of a single request")]
+    pub temp_dir_size_limit: Option<u64>,
+
+    /// Determines whether to run the agent with lowered scheduling priority.
+    #[argh(switch,
+           long="low-priority",
+           description="whether to run with lowered scheduling priority")]
+    pub low_priority: bool,
+
+    /// File with requests to execute without connecting to the server.
+    #[argh(option,
+           long="offline",
+           arg_name="PATH",
+           description="execute requests from the given file without connecting to the server")]
+    pub offline: Option<std::path::PathBuf>,
+
+    /// Directory to write results of requests executed offline to.
+    #[argh(option,
+           long="offline-output",
+           arg_name="PATH",
+           description="directory to write results of offline requests to")]
+    pub offline_output: Option<std::path::PathBuf>,
+
+    /// The public key to encrypt results of requests executed offline to.
+    #[argh(option,
+           long="offline-encryption-key",
+           arg_name="KEY",
+           description="public key to encrypt results of offline requests to",
+           from_str_fn(parse_encryption_key))]
+    pub offline_encryption_key: Option<x25519_dalek::PublicKey>,
+
+    /// Directory in which the agent keeps its state.
+    #[argh(option,
+           long="state-dir",
+           arg_name="PATH",
+           description="directory to keep the agent state in")]
+    pub state_dir: Option<std::path::PathBuf>,
+
+    /// Directory to spool reports about agent crashes to.
+    #[argh(option,
+           long="crash-spool-dir",
+           arg_name="PATH",
+           description="directory to spool crash reports to")]
+    pub crash_spool_dir: Option<std::path::PathBuf>,
+
+    /// Time given to in-flight requests to finish once shutdown is requested.
+    #[argh(option,
+           long="shutdown-grace-period",
+           arg_name="DURATION",
+           default="::std::time::Duration::from_secs(5)",
+           description="time given to in-flight requests to finish on shutdown",
+           from_str_fn(parse_duration))]
+    pub shutdown_grace_period: Duration,
+
+    /// File with the schedule of actions to execute periodically.
+    #[argh(option,
+           long="schedule",
+           arg_name="PATH",
+           description="file with the schedule of actions to execute periodically")]
+    pub schedule: Option<std::path::PathBuf>,
+
+    /// Configuration file to read further arguments from.
+    #[argh(option,
+           long="config",
+           arg_name="PATH",
+           description="configuration file to read arguments from")]
+    pub config: Option<std::path::PathBuf>,
+
+    /// Determines whether to only validate the configuration and exit.
+    #[argh(switch,
+           long="check-config",
+           description="whether to only validate the configuration and exit")]
+    pub check_config: bool,
+
+    /// Path of the socket (or named pipe on Windows) to serve admin queries on.
+    #[argh(option,
+           long="admin-socket",
+           arg_name="PATH",
+           description="socket (named pipe on Windows) to serve local admin queries on")]
+    pub admin_socket: Option<std::path::PathBuf>,
+
+    /// Determines whether to record handled requests in the state directory.
+    #[argh(switch,
+           long="record-requests",
+           description="whether to record handled requests for replaying them later")]
+    pub record_requests: bool,
+
+    /// Recording of a request to execute again and compare with the recording.
+    #[argh(option,
+           long="replay",
+           arg_name="PATH",
+           description="replay the recorded request and report divergences from the recording")]
+    pub replay: Option<std::path::PathBuf>,
 }
 
 /// Parses command-line arguments.
@@ -65,13 +292,46 @@ pub struct Args {
 /// creating instances of [`Args`]. Ideally, it should be called only once in
 /// the entire lifetime of the agent.
 ///
+/// Arguments not given on the command line are read from the environment and
+/// the configuration file (if specified), see the [`config`] module for more
+/// details. If the configuration is not valid, the process exits. It also exits
+/// once the configuration is validated if only that was asked for.
+///
 /// [`Args`]: struct.Args.html
+/// [`config`]: crate::config
 pub fn from_env_args() -> Args {
-    argh::from_env()
+    let mut args: Args = argh::from_env();
+
+    // Logging is not initialized yet, so problems are reported to the standard
+    // error (as is the case with invalid command-line arguments).
+    let config = match &args.config {
+        Some(path) => crate::config::load(path),
+        None => Ok(crate::config::Config::default()),
+    };
+    let result = config.and_then(|mut config| {
+        config.override_from_env(|var| std::env::var(var).ok());
+        for warning in config.warnings() {
+            eprintln!("warning: {warning}");
+        }
+
+        config.apply(&mut args)
+    });
+
+    if let Err(error) = result {
+        eprintln!("invalid configuration: {error}");
+        std::process::exit(1);
+    }
+
+    if args.check_config {
+        println!("configuration is valid");
+        std::process::exit(0);
+    }
+
+    args
 }
 
 /// Parses a human-friendly duration description to a `Duration` object.
-fn parse_duration(value: &str) -> Result<Duration, String> {
+pub(crate) fn parse_duration(value: &str) -> Result<Duration, String> {
     humantime::parse_duration(value).map_err(|error| error.to_string())
 }
 
@@ -113,12 +373,76 @@ impl std::fmt::Display for DecodeHexError {
     }
 }
 
+/// Parses a SHA-256 digest from hex data given as string.
+pub(crate) fn parse_sha256(sha256: &str) -> Result<[u8; 32], String> {
+    let bytes = decode_hex(sha256).map_err(|error| error.to_string())?;
+    <[u8; 32]>::try_from(&bytes[..]).map_err(|_| format!("invalid SHA-256 digest length: {}", bytes.len()))
+}
+
 /// Parses a ed25519 verification key from hex data given as string to a `VerifyingKey` object.
-fn parse_verfication_key(key: &str) -> Result<ed25519_dalek::VerifyingKey, String> {
+pub(crate) fn parse_verfication_key(key: &str) -> Result<ed25519_dalek::VerifyingKey, String> {
     let bytes = decode_hex(key).map_err(|error| error.to_string())?;
     ed25519_dalek::VerifyingKey::try_from(&bytes[..]).map_err(|error| error.to_string())
 }
 
+/// Parses a X25519 public key from hex data given as string to a `PublicKey` object.
+fn parse_encryption_key(key: &str) -> Result<x25519_dalek::PublicKey, String> {
+    let bytes = decode_hex(key).map_err(|error| error.to_string())?;
+    let bytes = <[u8; 32]>::try_from(&bytes[..])
+        .map_err(|_| format!("invalid encryption key length: {}", bytes.len()))?;
+    Ok(x25519_dalek::PublicKey::from(bytes))
+}
+
+#[cfg(any(test, feature = "test-fake_session"))]
+impl Default for Args {
+
+    /// Returns agent arguments suitable for tests.
+    ///
+    /// All the optional features are disabled. Tests that need some of them
+    /// should override the relevant fields only.
+    fn default() -> Args {
+        Args {
+            heartbeat_rate: std::time::Duration::from_secs(0),
+            command_verification_key: None,
+            command_verification_key_store: None,
+            command_audit_log: None,
+            command_rate_limit: None,
+            update_verification_key: None,
+            verbosity: log::LevelFilter::Debug,
+            log_to_stdout: false,
+            log_to_file: None,
+            osquery_path: None,
+            allowed_actions: Vec::new(),
+            denied_actions: Vec::new(),
+            denied_paths: Vec::new(),
+            redacted_paths: Vec::new(),
+            blob_upload_url: None,
+            blob_upload_pins: Vec::new(),
+            blob_upload_threshold: 0,
+            sink_pressure_high_watermark: None,
+            sink_pressure_low_watermark: None,
+            blob_digests: Vec::new(),
+            mmap_threshold: None,
+            reply_compression_threshold: None,
+            memory_limit: None,
+            temp_dir_size_limit: None,
+            low_priority: false,
+            offline: None,
+            offline_output: None,
+            offline_encryption_key: None,
+            state_dir: None,
+            crash_spool_dir: None,
+            shutdown_grace_period: std::time::Duration::from_secs(5),
+            schedule: None,
+            config: None,
+            check_config: false,
+            admin_socket: None,
+            record_requests: false,
+            replay: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -149,7 +473,149 @@ mod test {
 
     #[test]
     fn decode_hex_emtpy() {
-        assert_eq!(decode_hex("").unwrap(), vec![]);
+        assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn args_policy() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[
+            "--deny-action", "execute_signed_command",
+            "--deny-action", "get_filesystem_timeline",
+            "--deny-path", "get_file_contents=/etc/shadow",
+        ]).unwrap();
+
+        assert_eq!(args.denied_actions, vec! {
+            crate::request::Action::ExecuteSignedCommand,
+            crate::request::Action::GetFilesystemTimeline,
+        });
+        assert_eq!(args.denied_paths, vec! {
+            "get_file_contents=/etc/shadow".parse().unwrap(),
+        });
+        assert!(args.allowed_actions.is_empty());
+    }
+
+    #[test]
+    fn args_policy_unknown_action() {
+        use argh::FromArgs as _;
+
+        assert!(Args::from_args(&["rrg"], &["--allow-action", "foo"]).is_err());
+    }
+
+    #[test]
+    fn args_redact_path_invalid() {
+        use argh::FromArgs as _;
+
+        assert!(Args::from_args(&["rrg"], &["--redact-path", "^/home/([^/]+)"]).is_ok());
+        assert!(Args::from_args(&["rrg"], &["--redact-path", "^/home/(["]).is_err());
+    }
+
+    #[test]
+    fn args_blob_upload() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[
+            "--blob-upload-url", "https://example.com/upload",
+            "--blob-upload-pin", &"ab".repeat(32),
+            "--blob-upload-threshold", "1337",
+        ]).unwrap();
+
+        assert_eq!(args.blob_upload_url.as_deref(), Some("https://example.com/upload"));
+        assert_eq!(args.blob_upload_pins, vec![[0xab; 32]]);
+        assert_eq!(args.blob_upload_threshold, 1337);
+
+        assert!(Args::from_args(&["rrg"], &["--blob-upload-pin", "abcd"]).is_err());
+    }
+
+    #[test]
+    fn args_sink_pressure_watermarks() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[
+            "--sink-pressure-high-watermark", "8388608",
+            "--sink-pressure-low-watermark", "1048576",
+        ]).unwrap();
+
+        assert_eq!(args.sink_pressure_high_watermark, Some(8388608));
+        assert_eq!(args.sink_pressure_low_watermark, Some(1048576));
+    }
+
+    #[test]
+    fn args_reply_compression_threshold() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[])
+            .unwrap();
+        assert_eq!(args.reply_compression_threshold, None);
+
+        let args = Args::from_args(&["rrg"], &["--reply-compression-threshold", "1337"])
+            .unwrap();
+        assert_eq!(args.reply_compression_threshold, Some(1337));
+    }
+
+    #[test]
+    fn args_resource_limits() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[])
+            .unwrap();
+        assert_eq!(args.memory_limit, None);
+        assert!(!args.low_priority);
+
+        let args = Args::from_args(&["rrg"], &["--memory-limit", "1337", "--low-priority"])
+            .unwrap();
+        assert_eq!(args.memory_limit, Some(1337));
+        assert!(args.low_priority);
+    }
+
+    #[test]
+    fn args_offline() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[
+            "--offline", "/tmp/requests",
+            "--offline-output", "/tmp/output",
+        ]).unwrap();
+        assert_eq!(args.offline, Some(std::path::PathBuf::from("/tmp/requests")));
+        assert_eq!(args.offline_output, Some(std::path::PathBuf::from("/tmp/output")));
+        assert!(args.offline_encryption_key.is_none());
+
+        let args = Args::from_args(&["rrg"], &[
+            "--offline", "/tmp/requests",
+            "--offline-encryption-key", &"ab".repeat(32),
+        ]).unwrap();
+        assert_eq!(args.offline_encryption_key.map(|key| key.to_bytes()), Some([0xab; 32]));
+
+        assert!(Args::from_args(&["rrg"], &["--offline-encryption-key", "abcd"]).is_err());
+    }
+
+    #[test]
+    fn args_shutdown_grace_period() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[])
+            .unwrap();
+        assert_eq!(args.shutdown_grace_period, Duration::from_secs(5));
+
+        let args = Args::from_args(&["rrg"], &["--shutdown-grace-period", "1m"])
+            .unwrap();
+        assert_eq!(args.shutdown_grace_period, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn args_config() {
+        use argh::FromArgs as _;
+
+        let args = Args::from_args(&["rrg"], &[])
+            .unwrap();
+        assert_eq!(args.config, None);
+        assert!(!args.check_config);
+
+        let args = Args::from_args(&["rrg"], &["--config", "/etc/rrg.toml", "--check-config"])
+            .unwrap();
+        assert_eq!(args.config, Some(std::path::PathBuf::from("/etc/rrg.toml")));
+        assert!(args.check_config);
     }
 
     quickcheck! {
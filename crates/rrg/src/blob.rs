@@ -6,7 +6,12 @@
 // Binary data object.
 pub struct Blob {
     // Binary data that the blob represents.
-    data: Vec<u8>,
+    //
+    // The data is shared with the digest worker while digests of the blob are
+    // computed in the background (see [`Blob::digests_pending`]).
+    data: std::sync::Arc<Vec<u8>>,
+    // Channel that disconnects once the digest worker releases the data.
+    released: Option<std::sync::mpsc::Receiver<()>>,
 }
 
 impl Blob {
@@ -15,13 +20,232 @@ impl Blob {
     pub fn as_bytes(&self) -> &[u8] {
         self.data.as_slice()
     }
+
+    /// Computes the digest of the blob data using the given algorithm.
+    pub fn digest(&self, algorithm: DigestAlgorithm) -> [u8; 32] {
+        match algorithm {
+            DigestAlgorithm::Sha256 => {
+                use sha2::Digest as _;
+                sha2::Sha256::digest(self.as_bytes()).into()
+            }
+            DigestAlgorithm::Blake3 => {
+                blake3::hash(self.as_bytes()).into()
+            }
+        }
+    }
+
+    /// Computes digests of the blob data using all the given algorithms.
+    pub fn digests(&self, algorithms: &[DigestAlgorithm]) -> Digests {
+        let mut digests = Digests::default();
+        for algorithm in algorithms {
+            match algorithm {
+                DigestAlgorithm::Sha256 => {
+                    digests.sha256 = Some(self.digest(DigestAlgorithm::Sha256));
+                }
+                DigestAlgorithm::Blake3 => {
+                    digests.blake3 = Some(self.digest(DigestAlgorithm::Blake3));
+                }
+            }
+        }
+
+        digests
+    }
+
+    /// Starts computing digests of the blob data in the background.
+    ///
+    /// Digests are computed by a worker thread shared by all requests, so the
+    /// caller can meanwhile send the blob and obtain the digests through the
+    /// returned handle only once it is about to reply with them.
+    ///
+    /// Note that the blob can be converted to its protobuf representation only
+    /// once the worker is done with it, so that the data does not need to be
+    /// copied.
+    pub fn digests_pending(&mut self, algorithms: &[DigestAlgorithm]) -> PendingDigests {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let (released_sender, released_receiver) = std::sync::mpsc::channel();
+
+        let job = DigestJob {
+            data: std::sync::Arc::clone(&self.data),
+            algorithms: algorithms.to_vec(),
+            sender,
+            released: released_sender,
+        };
+
+        match digest_worker() {
+            Some(worker) if worker.send(job).is_ok() => {
+                self.released = Some(released_receiver);
+
+                PendingDigests {
+                    inner: PendingDigestsInner::Pending(receiver),
+                }
+            }
+            // The worker could not be spawned, so we fall back to computing the
+            // digests right away.
+            _ => PendingDigests {
+                inner: PendingDigestsInner::Ready(self.digests(algorithms)),
+            },
+        }
+    }
+}
+
+/// Handle to digests of a blob that are computed in the background.
+pub struct PendingDigests {
+    inner: PendingDigestsInner,
+}
+
+enum PendingDigestsInner {
+    /// Digests were computed already.
+    Ready(Digests),
+    /// Digests are being computed by the worker.
+    Pending(std::sync::mpsc::Receiver<Digests>),
+}
+
+impl PendingDigests {
+
+    /// Waits for the digests to be computed and returns them.
+    pub fn wait(self) -> Result<Digests, DigestWorkerError> {
+        match self.inner {
+            PendingDigestsInner::Ready(digests) => Ok(digests),
+            PendingDigestsInner::Pending(receiver) => receiver.recv()
+                .map_err(|_| DigestWorkerError),
+        }
+    }
+}
+
+/// The error type for cases when the digest worker fails to deliver digests.
+#[derive(Debug, Clone)]
+pub struct DigestWorkerError;
+
+impl std::fmt::Display for DigestWorkerError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "digest worker disconnected")
+    }
+}
+
+impl std::error::Error for DigestWorkerError {
+}
+
+/// Request to the digest worker to compute digests of blob data.
+struct DigestJob {
+    /// Blob data to compute the digests of.
+    data: std::sync::Arc<Vec<u8>>,
+    /// Algorithms to compute the digests with.
+    algorithms: Vec<DigestAlgorithm>,
+    /// Channel to send the computed digests through.
+    sender: std::sync::mpsc::Sender<Digests>,
+    /// Channel to drop once the blob data is released.
+    released: std::sync::mpsc::Sender<()>,
+}
+
+/// Returns the channel to the digest worker (spawning it on first use).
+///
+/// `None` is returned if the worker could not be spawned.
+fn digest_worker() -> Option<&'static std::sync::mpsc::Sender<DigestJob>> {
+    static WORKER: std::sync::OnceLock<Option<std::sync::mpsc::Sender<DigestJob>>> = std::sync::OnceLock::new();
+
+    WORKER.get_or_init(|| {
+        let (sender, receiver) = std::sync::mpsc::channel::<DigestJob>();
+
+        let result = std::thread::Builder::new()
+            .name(String::from("blob-digest"))
+            .spawn(move || {
+                for job in receiver {
+                    let blob = Blob {
+                        data: job.data,
+                        released: None,
+                    };
+                    let digests = blob.digests(&job.algorithms);
+
+                    // The data has to be released before the channel is
+                    // disconnected, as the owner of the blob might be waiting
+                    // for that to take the data over.
+                    drop(blob);
+                    drop(job.released);
+
+                    // The requester might have given up on the digests (e.g.
+                    // because sending the blob failed), which is fine.
+                    let _ = job.sender.send(digests);
+                }
+            });
+
+        match result {
+            Ok(_) => Some(sender),
+            Err(error) => {
+                log::error!("failed to spawn blob digest worker: {error}");
+                None
+            }
+        }
+    }).as_ref()
+}
+
+/// Algorithm used for computing digests of blobs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// [SHA-256](https://en.wikipedia.org/wiki/SHA-2).
+    Sha256,
+    /// [BLAKE3](https://github.com/BLAKE3-team/BLAKE3).
+    Blake3,
+}
+
+impl std::str::FromStr for DigestAlgorithm {
+
+    type Err = ParseDigestAlgorithmError;
+
+    fn from_str(name: &str) -> Result<DigestAlgorithm, ParseDigestAlgorithmError> {
+        match name {
+            "sha256" => Ok(DigestAlgorithm::Sha256),
+            "blake3" => Ok(DigestAlgorithm::Blake3),
+            _ => Err(ParseDigestAlgorithmError {
+                name: String::from(name),
+            }),
+        }
+    }
+}
+
+/// The error type for cases when parsing a digest algorithm name fails.
+#[derive(Debug, Clone)]
+pub struct ParseDigestAlgorithmError {
+    /// Name that failed to parse.
+    name: String,
+}
+
+impl std::fmt::Display for ParseDigestAlgorithmError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown digest algorithm '{}'", self.name)
+    }
+}
+
+impl std::error::Error for ParseDigestAlgorithmError {
+}
+
+/// Returns digest algorithms to compute for blobs as configured by the user.
+///
+/// If no algorithms were configured, only SHA-256 digests are computed.
+pub fn digest_algorithms(args: &crate::args::Args) -> &[DigestAlgorithm] {
+    if args.blob_digests.is_empty() {
+        &[DigestAlgorithm::Sha256]
+    } else {
+        &args.blob_digests
+    }
+}
+
+/// Digests of blob data (for algorithms they were computed with).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Digests {
+    /// SHA-256 digest of the blob data.
+    pub sha256: Option<[u8; 32]>,
+    /// BLAKE3 digest of the blob data.
+    pub blake3: Option<[u8; 32]>,
 }
 
 impl From<Vec<u8>> for Blob {
 
     fn from(data: Vec<u8>) -> Blob {
         Blob {
-            data,
+            data: std::sync::Arc::new(data),
+            released: None,
         }
     }
 }
@@ -32,8 +256,128 @@ impl crate::response::Item for Blob {
 
     fn into_proto(self) -> Self::Proto {
         let mut proto = Self::Proto::default();
-        proto.set_data(self.data);
+
+        // The data is shared while the worker is computing digests of the
+        // blob. Blobs can be big, so rather than copying the data we wait for
+        // the worker to release it (the channel never yields any messages, it
+        // just gets disconnected).
+        if let Some(released) = self.released {
+            let _ = released.recv();
+        }
+
+        // The worker has released the data at this point, so it should never
+        // be copied.
+        let data = std::sync::Arc::try_unwrap(self.data)
+            .unwrap_or_else(|data| data.as_ref().clone());
+        proto.set_data(data);
 
         proto
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const SHA256_ABC: [u8; 32] = [
+        0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea,
+        0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+        0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c,
+        0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    ];
+
+    const BLAKE3_ABC: [u8; 32] = [
+        0x64, 0x37, 0xb3, 0xac, 0x38, 0x46, 0x51, 0x33,
+        0xff, 0xb6, 0x3b, 0x75, 0x27, 0x3a, 0x8d, 0xb5,
+        0x48, 0xc5, 0x58, 0x46, 0x5d, 0x79, 0xdb, 0x03,
+        0xfd, 0x35, 0x9c, 0x6c, 0xd5, 0xbd, 0x9d, 0x85,
+    ];
+
+    #[test]
+    fn digest_sha256() {
+        let blob = Blob::from(b"abc".to_vec());
+        assert_eq!(blob.digest(DigestAlgorithm::Sha256), SHA256_ABC);
+    }
+
+    #[test]
+    fn digest_blake3() {
+        let blob = Blob::from(b"abc".to_vec());
+        assert_eq!(blob.digest(DigestAlgorithm::Blake3), BLAKE3_ABC);
+    }
+
+    #[test]
+    fn digests_all() {
+        let blob = Blob::from(b"abc".to_vec());
+
+        let digests = blob.digests(&[DigestAlgorithm::Sha256, DigestAlgorithm::Blake3]);
+        assert_eq!(digests.sha256, Some(SHA256_ABC));
+        assert_eq!(digests.blake3, Some(BLAKE3_ABC));
+    }
+
+    #[test]
+    fn digests_blake3_only() {
+        let blob = Blob::from(b"abc".to_vec());
+
+        let digests = blob.digests(&[DigestAlgorithm::Blake3]);
+        assert_eq!(digests.sha256, None);
+        assert_eq!(digests.blake3, Some(BLAKE3_ABC));
+    }
+
+    #[test]
+    fn digests_pending() {
+        let mut blob = Blob::from(b"abc".to_vec());
+
+        let digests = blob.digests_pending(&[DigestAlgorithm::Sha256, DigestAlgorithm::Blake3])
+            .wait().unwrap();
+        assert_eq!(digests.sha256, Some(SHA256_ABC));
+        assert_eq!(digests.blake3, Some(BLAKE3_ABC));
+    }
+
+    #[test]
+    fn digests_pending_concurrent() {
+        let threads = (0..8u8).map(|thread_index| {
+            std::thread::spawn(move || {
+                let mut blobs = (0..32u8)
+                    .map(|blob_index| Blob::from(vec![thread_index ^ blob_index; 1024 * 1024]))
+                    .collect::<Vec<_>>();
+
+                let pending = blobs.iter_mut()
+                    .map(|blob| blob.digests_pending(&[DigestAlgorithm::Sha256]))
+                    .collect::<Vec<_>>();
+
+                for (blob, pending) in blobs.iter().zip(pending) {
+                    assert_eq!(pending.wait().unwrap(), blob.digests(&[DigestAlgorithm::Sha256]));
+                }
+            })
+        }).collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join()
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn into_proto_while_pending() {
+        use crate::response::Item as _;
+
+        let mut blob = Blob::from(vec![0xAB; 1024 * 1024]);
+        let pending = blob.digests_pending(&[DigestAlgorithm::Sha256]);
+
+        let data_ptr = blob.as_bytes().as_ptr();
+        let proto = blob.into_proto();
+
+        // The data must have been taken over rather than copied.
+        assert_eq!(proto.data().as_ptr(), data_ptr);
+        assert_eq!(proto.data(), &vec![0xAB; 1024 * 1024][..]);
+        assert!(pending.wait().unwrap().sha256.is_some());
+    }
+
+    #[test]
+    fn digest_algorithm_from_str() {
+        assert_eq!("sha256".parse::<DigestAlgorithm>().unwrap(), DigestAlgorithm::Sha256);
+        assert_eq!("blake3".parse::<DigestAlgorithm>().unwrap(), DigestAlgorithm::Blake3);
+        assert!("md5".parse::<DigestAlgorithm>().is_err());
+    }
+}
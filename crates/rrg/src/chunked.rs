@@ -90,6 +90,26 @@ where
     }
 }
 
+/// Writes a single protobuf message in the chunked format to the given writer.
+///
+/// The message is serialized directly into the writer, without buffering it
+/// in memory first. Writing consecutive messages this way yields the same
+/// output as the streaming [`encode`] function.
+///
+/// [`encode`]: fn.encode.html
+pub fn write<W, M>(writer: &mut W, msg: &M) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    M: protobuf::Message,
+{
+    use byteorder::WriteBytesExt as _;
+
+    writer.write_u64::<BigEndian>(msg.compute_size() as u64)?;
+    msg.write_to_writer(writer)?;
+
+    Ok(())
+}
+
 /// Streaming encoder for the chunked format.
 ///
 /// It implements the `Read` trait, lazily polling the underlying iterator over
@@ -115,8 +135,6 @@ where
 
     /// Pulls another message from the underlying iterator.
     fn pull(&mut self) -> std::io::Result<()> {
-        use byteorder::WriteBytesExt as _;
-
         let msg = match self.iter.next() {
             Some(msg) => msg,
             None => return Ok(()),
@@ -125,9 +143,7 @@ where
         self.cur.get_mut().clear();
         self.cur.set_position(0);
 
-        self.cur.write_u64::<BigEndian>(msg.compute_size() as u64)?;
-        msg.write_to_writer(&mut self.cur.get_mut())?;
-        self.cur.set_position(0);
+        write(self.cur.get_mut(), &msg)?;
 
         Ok(())
     }
@@ -342,6 +358,25 @@ pub mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_write_and_encode_equal() {
+        let msgs = vec! {
+            string("foo"),
+            string("bar"),
+            string("baz"),
+        };
+
+        let mut written = vec!();
+        for msg in &msgs {
+            write(&mut written, msg).unwrap();
+        }
+
+        let mut encoded = vec!();
+        std::io::copy(&mut encode(msgs.into_iter()), &mut encoded).unwrap();
+
+        assert_eq!(written, encoded);
+    }
+
     #[test]
     fn test_encode_and_decode_multiple_unit_messages() {
         let msgs = vec!(Empty::new(), Empty::new(), Empty::new());
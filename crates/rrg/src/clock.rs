@@ -0,0 +1,214 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Snapshots of the agent clock for assessing collected timestamps.
+//!
+//! Timestamps collected from the filesystem are only as trustworthy as the
+//! clock of the system that wrote them. Actions collecting them take a
+//! [`Snapshot`] of the clock at the beginning of the collection and report it
+//! along with the results, so that a skewed clock can be noticed (and the
+//! timestamps corrected) during the analysis.
+//!
+//! Timestamps lying in the future relative to the snapshot are flagged as
+//! anomalous. They are compared against the snapshot rather than the current
+//! time, so that the check stays cheap even for millions of entries.
+
+/// Default tolerance for timestamps lying in the future.
+pub const DEFAULT_FUTURE_TOLERANCE: std::time::Duration = {
+    std::time::Duration::from_secs(5 * 60)
+};
+
+/// Snapshot of the agent clock taken at a particular moment.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    /// Time of the system clock.
+    realtime: std::time::SystemTime,
+    /// Time since the system was booted (measured with a monotonic clock).
+    uptime: Option<std::time::Duration>,
+    /// Timezone the operating system is configured with.
+    timezone: Option<ospect::os::Timezone>,
+}
+
+impl Snapshot {
+
+    /// Takes a snapshot of the clock at the current moment.
+    pub fn now() -> Snapshot {
+        let uptime = match ospect::os::uptime() {
+            Ok(uptime) => Some(uptime),
+            Err(error) => {
+                log::error!("failed to collect system uptime: {error}");
+                None
+            }
+        };
+
+        let timezone = match ospect::os::timezone() {
+            Ok(timezone) => Some(timezone),
+            Err(error) => {
+                log::error!("failed to collect system timezone: {error}");
+                None
+            }
+        };
+
+        Snapshot {
+            realtime: std::time::SystemTime::now(),
+            uptime,
+            timezone,
+        }
+    }
+
+    /// Returns whether the given time is later than the snapshot by more than
+    /// the tolerance.
+    pub fn is_future(
+        &self,
+        time: std::time::SystemTime,
+        tolerance: std::time::Duration,
+    ) -> bool {
+        match time.duration_since(self.realtime) {
+            Ok(ahead) => ahead > tolerance,
+            Err(_) => false,
+        }
+    }
+
+    /// Returns whether the modification or access time of a file with the
+    /// given metadata is later than the snapshot by more than the tolerance.
+    pub fn is_anomalous(
+        &self,
+        metadata: &std::fs::Metadata,
+        tolerance: std::time::Duration,
+    ) -> bool {
+        let is_future = |time: std::io::Result<std::time::SystemTime>| {
+            time.is_ok_and(|time| self.is_future(time, tolerance))
+        };
+
+        is_future(metadata.modified()) || is_future(metadata.accessed())
+    }
+}
+
+impl From<Snapshot> for rrg_proto::os::ClockSnapshot {
+
+    fn from(snapshot: Snapshot) -> rrg_proto::os::ClockSnapshot {
+        let mut proto = rrg_proto::os::ClockSnapshot::default();
+        proto.set_realtime(rrg_proto::into_timestamp(snapshot.realtime));
+        if let Some(uptime) = snapshot.uptime {
+            proto.set_uptime(uptime.into());
+        }
+        if let Some(timezone) = snapshot.timezone {
+            proto.set_timezone_name(timezone.name);
+            proto.set_timezone_utc_offset(timezone.utc_offset);
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    const TOLERANCE: std::time::Duration = std::time::Duration::from_secs(60);
+
+    /// Sets the modification time of the given file to a day in the future.
+    #[cfg(target_family = "unix")]
+    pub fn set_future_mtime(path: &std::path::Path) {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+            .unwrap();
+
+        let future = std::time::SystemTime::now()
+            + std::time::Duration::from_secs(24 * 60 * 60);
+        let future_secs = future.duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // The access time is left intact.
+        let times = [
+            libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_OMIT,
+            },
+            libc::timespec {
+                tv_sec: future_secs as libc::time_t,
+                tv_nsec: 0,
+            },
+        ];
+
+        // SAFETY: The path is a valid null-terminated string and we pass an
+        // array of two timestamps as required. We verify the result below.
+        let code = unsafe {
+            libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), 0)
+        };
+        assert_eq!(code, 0);
+    }
+
+    #[test]
+    fn is_future_past() {
+        let snapshot = Snapshot::now();
+
+        let time = snapshot.realtime - std::time::Duration::from_secs(3600);
+        assert!(!snapshot.is_future(time, TOLERANCE));
+    }
+
+    #[test]
+    fn is_future_within_tolerance() {
+        let snapshot = Snapshot::now();
+
+        let time = snapshot.realtime + std::time::Duration::from_secs(30);
+        assert!(!snapshot.is_future(time, TOLERANCE));
+    }
+
+    #[test]
+    fn is_future_beyond_tolerance() {
+        let snapshot = Snapshot::now();
+
+        let time = snapshot.realtime + std::time::Duration::from_secs(3600);
+        assert!(snapshot.is_future(time, TOLERANCE));
+    }
+
+    #[test]
+    fn is_anomalous_fresh_file() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+
+        let snapshot = Snapshot::now();
+
+        let metadata = tempfile.as_file().metadata()
+            .unwrap();
+        assert!(!snapshot.is_anomalous(&metadata, TOLERANCE));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn is_anomalous_future_mtime() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+        set_future_mtime(tempfile.path());
+
+        let snapshot = Snapshot::now();
+
+        let metadata = tempfile.as_file().metadata()
+            .unwrap();
+        assert!(snapshot.is_anomalous(&metadata, TOLERANCE));
+    }
+
+    #[test]
+    fn into_proto() {
+        let snapshot = Snapshot {
+            realtime: std::time::UNIX_EPOCH + std::time::Duration::from_secs(1337),
+            uptime: Some(std::time::Duration::from_secs(42)),
+            timezone: Some(ospect::os::Timezone {
+                name: String::from("CET"),
+                utc_offset: 3600,
+            }),
+        };
+
+        let proto = rrg_proto::os::ClockSnapshot::from(snapshot);
+        assert_eq!(proto.realtime().seconds, 1337);
+        assert_eq!(proto.uptime().seconds, 42);
+        assert_eq!(proto.timezone_name(), "CET");
+        assert_eq!(proto.timezone_utc_offset(), 3600);
+    }
+}
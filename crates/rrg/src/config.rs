@@ -0,0 +1,890 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Configuration files of the agent.
+//!
+//! Instead of passing everything through command-line flags, the agent can be
+//! configured with a [TOML][toml] file given with the `--config` flag:
+//!
+//! ```toml
+//! state_dir = "/var/lib/rrg"
+//!
+//! [log]
+//! verbosity = "debug"
+//! to_file = "/var/log/rrg.log"
+//!
+//! [transport]
+//! heartbeat_rate = "5s"
+//! blob_upload_url = "https://example.com/upload"
+//!
+//! [policy]
+//! denied_actions = ["execute_signed_command"]
+//! denied_paths = ["get_file_contents=/etc/shadow"]
+//!
+//! [limits]
+//! memory_limit = 1073741824
+//! low_priority = true
+//!
+//! [keys]
+//! command_verification_key = "..."
+//! ```
+//!
+//! Every key can also be overridden with an environment variable named after
+//! the key with the `RRG_` prefix (e.g. `RRG_LIMITS_MEMORY_LIMIT`), which is
+//! convenient for containerized deployments. Lists in environment variables
+//! are separated with commas.
+//!
+//! The command line has the highest precedence, followed by the environment
+//! and the configuration file. Since the command line is parsed first, a flag
+//! is considered to be given if its value differs from the default one.
+//!
+//! Unknown keys are reported as warnings (so that older agents can work with
+//! newer configuration files) but invalid values of known keys are errors.
+//!
+//! The file can be changed while the agent is running and is reloaded without
+//! a restart (see the [`reload`] module for details).
+//!
+//! [`reload`]: crate::reload
+//! [toml]: https://toml.io
+
+use std::path::{Path, PathBuf};
+
+use crate::args::Args;
+
+/// A parsed configuration of the agent.
+#[derive(Default)]
+pub struct Config {
+    /// Values of the configuration keys (at most one per key).
+    entries: Vec<Entry>,
+    /// Human-readable warnings about problems found in the configuration.
+    warnings: Vec<String>,
+}
+
+/// A value of a single configuration key along with its origin.
+struct Entry {
+    /// Specification of the key.
+    key: &'static Key,
+    /// Value of the key.
+    value: Value,
+    /// Where the value comes from.
+    origin: Origin,
+}
+
+/// Loads the configuration from the file at the given path.
+pub fn load(path: &Path) -> Result<Config, Error> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|error| Error {
+            kind: ErrorKind::Read(path.to_path_buf(), error),
+        })?;
+
+    parse(&content)
+}
+
+/// Parses the configuration from the given TOML document.
+pub fn parse(content: &str) -> Result<Config, Error> {
+    let doc = toml_edit::ImDocument::parse(content)
+        .map_err(|error| Error {
+            kind: ErrorKind::Syntax(error),
+        })?;
+
+    let mut config = Config::default();
+    config.parse_table(content, "", doc.as_table())?;
+
+    Ok(config)
+}
+
+/// Computes the SHA-256 digest of the configuration of the agent.
+///
+/// The digest covers the command-line arguments the agent has been started with
+/// and the current contents of the configuration file (if any), so it changes
+/// once the file is edited.
+pub fn digest(args: &Args) -> [u8; 32] {
+    use sha2::Digest as _;
+
+    let mut hasher = sha2::Sha256::new();
+    for arg in std::env::args_os().skip(1) {
+        hasher.update(arg.as_encoded_bytes());
+        // We separate the arguments so that e.g. `--foo bar` and `--foob ar`
+        // yield different digests.
+        hasher.update([0]);
+    }
+
+    if let Some(path) = &args.config {
+        match std::fs::read(path) {
+            Ok(content) => hasher.update(content),
+            Err(error) => log::warn!("failed to read configuration for digest: {error}"),
+        }
+    }
+
+    hasher.finalize().into()
+}
+
+impl Config {
+
+    /// Returns warnings about problems found in the configuration.
+    pub fn warnings(&self) -> impl Iterator<Item = &str> {
+        self.warnings.iter().map(String::as_str)
+    }
+
+    /// Overrides configuration values with the ones from the environment.
+    ///
+    /// The `var` function is used to look up the value of the environment
+    /// variable with the given name (e.g. [`std::env::var`]).
+    pub fn override_from_env<F>(&mut self, var: F)
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        for key in KEYS {
+            let var_name = key.env_var();
+            if let Some(value) = var(&var_name) {
+                self.set(key, Value::Raw(value), Origin::Env(var_name));
+            }
+        }
+    }
+
+    /// Validates the configuration and applies it to the given arguments.
+    ///
+    /// Values of the arguments given on the command line are left intact.
+    pub fn apply(self, args: &mut Args) -> Result<(), Error> {
+        for entry in self.entries {
+            (entry.key.apply)(args, entry.value)
+                .map_err(|message| Error {
+                    kind: ErrorKind::InvalidValue {
+                        key: entry.key.name,
+                        origin: entry.origin,
+                        message,
+                    },
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the value of the given key (overriding the existing one).
+    fn set(&mut self, key: &'static Key, value: Value, origin: Origin) {
+        self.entries.retain(|entry| entry.key.name != key.name);
+        self.entries.push(Entry { key, value, origin });
+    }
+
+    /// Collects values of the keys of the given table (and its subtables).
+    fn parse_table(
+        &mut self,
+        content: &str,
+        prefix: &str,
+        table: &dyn toml_edit::TableLike,
+    ) -> Result<(), Error> {
+        for (key_name, item) in table.iter() {
+            let name = format!("{prefix}{key_name}");
+            let line = table.key(key_name)
+                .and_then(toml_edit::Key::span)
+                .map(|span| line(content, span.start));
+
+            if let Some(table) = item.as_table_like() {
+                self.parse_table(content, &format!("{name}."), table)?;
+                continue;
+            }
+
+            let key = match KEYS.iter().find(|key| key.name == name) {
+                Some(key) => key,
+                None => {
+                    self.warnings.push(match line {
+                        Some(line) => format!("unknown configuration key '{name}' (line {line})"),
+                        None => format!("unknown configuration key '{name}'"),
+                    });
+                    continue;
+                }
+            };
+
+            let origin = Origin::File(line);
+            let value = match Value::from_toml(item) {
+                Some(value) => value,
+                None => return Err(Error {
+                    kind: ErrorKind::InvalidValue {
+                        key: key.name,
+                        origin,
+                        message: String::from("unsupported value type"),
+                    },
+                }),
+            };
+
+            self.set(key, value, origin);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the (1-based) number of the line at the given byte offset.
+fn line(content: &str, offset: usize) -> usize {
+    let offset = std::cmp::min(offset, content.len());
+    content.as_bytes()[..offset].iter().filter(|byte| **byte == b'\n').count() + 1
+}
+
+/// A value of a configuration key.
+enum Value {
+    /// A string given in the configuration file.
+    String(String),
+    /// An integer given in the configuration file.
+    Integer(i64),
+    /// A boolean given in the configuration file.
+    Boolean(bool),
+    /// An array of strings given in the configuration file.
+    Strings(Vec<String>),
+    /// A raw (not yet typed) value given in the environment.
+    Raw(String),
+}
+
+impl Value {
+
+    /// Converts the given TOML item to a value (if it has a supported type).
+    fn from_toml(item: &toml_edit::Item) -> Option<Value> {
+        match item.as_value()? {
+            toml_edit::Value::String(value) => Some(Value::String(value.value().clone())),
+            toml_edit::Value::Integer(value) => Some(Value::Integer(*value.value())),
+            toml_edit::Value::Boolean(value) => Some(Value::Boolean(*value.value())),
+            toml_edit::Value::Array(array) => {
+                array.iter()
+                    .map(|value| value.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+                    .map(Value::Strings)
+            }
+            _ => None,
+        }
+    }
+
+    /// Interprets the value as a string.
+    fn into_string(self) -> Result<String, String> {
+        match self {
+            Value::String(value) | Value::Raw(value) => Ok(value),
+            _ => Err(String::from("expected a string")),
+        }
+    }
+
+    /// Interprets the value as a string and parses it into the given type.
+    fn parse<T>(self) -> Result<T, String>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.into_string()?.parse::<T>()
+            .map_err(|error| error.to_string())
+    }
+
+    /// Interprets the value as a non-negative integer of the given type.
+    fn into_integer<T>(self) -> Result<T, String>
+    where
+        T: TryFrom<i64>,
+    {
+        let value = match self {
+            Value::Integer(value) => value,
+            Value::Raw(value) => value.parse::<i64>()
+                .map_err(|_| format!("invalid integer: '{value}'"))?,
+            _ => return Err(String::from("expected an integer")),
+        };
+
+        T::try_from(value)
+            .map_err(|_| format!("integer out of range: {value}"))
+    }
+
+    /// Interprets the value as a boolean.
+    fn into_bool(self) -> Result<bool, String> {
+        match self {
+            Value::Boolean(value) => Ok(value),
+            Value::Raw(value) => match value.as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(format!("invalid boolean: '{value}'")),
+            },
+            _ => Err(String::from("expected a boolean")),
+        }
+    }
+
+    /// Interprets the value as a list of strings and parses each of them.
+    fn into_list<T, F>(self, parse: F) -> Result<Vec<T>, String>
+    where
+        F: Fn(&str) -> Result<T, String>,
+    {
+        let values = match self {
+            Value::Strings(values) => values,
+            Value::Raw(value) => {
+                value.split(',').map(str::trim).map(String::from).collect()
+            }
+            _ => return Err(String::from("expected an array of strings")),
+        };
+
+        values.iter()
+            .map(|value| parse(value))
+            .collect()
+    }
+}
+
+/// Parses the given string with its `FromStr` implementation.
+fn from_str<T>(value: &str) -> Result<T, String>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value.parse::<T>()
+        .map_err(|error| format!("'{value}': {error}"))
+}
+
+/// Sets the optional argument to the given value unless it is already set.
+fn set_option<T>(arg: &mut Option<T>, value: T) {
+    if arg.is_none() {
+        *arg = Some(value);
+    }
+}
+
+/// Sets the list argument to the given values unless it is already non-empty.
+fn set_list<T>(arg: &mut Vec<T>, values: Vec<T>) {
+    if arg.is_empty() {
+        *arg = values;
+    }
+}
+
+/// Sets the argument to the given value unless it differs from the default.
+fn set_default<T: PartialEq>(arg: &mut T, default: T, value: T) {
+    if *arg == default {
+        *arg = value;
+    }
+}
+
+/// Source a configuration value comes from.
+#[derive(Debug)]
+enum Origin {
+    /// The configuration file (at the given line, if known).
+    File(Option<usize>),
+    /// The environment variable of the given name.
+    Env(String),
+}
+
+impl std::fmt::Display for Origin {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Origin::File(Some(line)) => write!(fmt, "line {line}"),
+            Origin::File(None) => write!(fmt, "configuration file"),
+            Origin::Env(var) => write!(fmt, "environment variable {var}"),
+        }
+    }
+}
+
+/// Specification of a configuration key.
+struct Key {
+    /// Full (dotted) name of the key.
+    name: &'static str,
+    /// Validates the value and applies it to the arguments.
+    apply: fn(&mut Args, Value) -> Result<(), String>,
+}
+
+impl Key {
+
+    /// Returns the name of the environment variable overriding the key.
+    fn env_var(&self) -> String {
+        format!("RRG_{}", self.name.to_uppercase().replace('.', "_"))
+    }
+}
+
+/// All the supported configuration keys.
+const KEYS: &[Key] = &[
+    Key {
+        name: "state_dir",
+        apply: |args, value| {
+            set_option(&mut args.state_dir, PathBuf::from(value.into_string()?));
+            Ok(())
+        },
+    },
+    Key {
+        name: "log.verbosity",
+        apply: |args, value| {
+            let verbosity = value.parse::<log::LevelFilter>()?;
+            set_default(&mut args.verbosity, log::LevelFilter::Info, verbosity);
+            Ok(())
+        },
+    },
+    Key {
+        name: "log.to_stdout",
+        apply: |args, value| {
+            args.log_to_stdout |= value.into_bool()?;
+            Ok(())
+        },
+    },
+    Key {
+        name: "log.to_file",
+        apply: |args, value| {
+            set_option(&mut args.log_to_file, PathBuf::from(value.into_string()?));
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.heartbeat_rate",
+        apply: |args, value| {
+            let heartbeat_rate = crate::args::parse_duration(&value.into_string()?)?;
+            set_default(&mut args.heartbeat_rate, std::time::Duration::from_secs(5), heartbeat_rate);
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.blob_upload_url",
+        apply: |args, value| {
+            set_option(&mut args.blob_upload_url, value.into_string()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.blob_upload_pins",
+        apply: |args, value| {
+            set_list(&mut args.blob_upload_pins, value.into_list(crate::args::parse_sha256)?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.blob_upload_threshold",
+        apply: |args, value| {
+            let blob_upload_threshold = value.into_integer::<usize>()?;
+            set_default(&mut args.blob_upload_threshold, 1024 * 1024, blob_upload_threshold);
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.sink_pressure_high_watermark",
+        apply: |args, value| {
+            set_option(&mut args.sink_pressure_high_watermark, value.into_integer()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.sink_pressure_low_watermark",
+        apply: |args, value| {
+            set_option(&mut args.sink_pressure_low_watermark, value.into_integer()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "transport.reply_compression_threshold",
+        apply: |args, value| {
+            set_option(&mut args.reply_compression_threshold, value.into_integer()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "policy.allowed_actions",
+        apply: |args, value| {
+            set_list(&mut args.allowed_actions, value.into_list(from_str)?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "policy.denied_actions",
+        apply: |args, value| {
+            set_list(&mut args.denied_actions, value.into_list(from_str)?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "policy.denied_paths",
+        apply: |args, value| {
+            set_list(&mut args.denied_paths, value.into_list(from_str)?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "policy.redacted_paths",
+        apply: |args, value| {
+            set_list(&mut args.redacted_paths, value.into_list(from_str)?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "limits.memory_limit",
+        apply: |args, value| {
+            set_option(&mut args.memory_limit, value.into_integer()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "limits.temp_dir_size_limit",
+        apply: |args, value| {
+            set_option(&mut args.temp_dir_size_limit, value.into_integer()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "limits.low_priority",
+        apply: |args, value| {
+            args.low_priority |= value.into_bool()?;
+            Ok(())
+        },
+    },
+    Key {
+        name: "limits.command_rate_limit",
+        apply: |args, value| {
+            set_option(&mut args.command_rate_limit, value.into_integer()?);
+            Ok(())
+        },
+    },
+    Key {
+        name: "keys.command_verification_key",
+        apply: |args, value| {
+            let key = crate::args::parse_verfication_key(&value.into_string()?)?;
+            set_option(&mut args.command_verification_key, key);
+            Ok(())
+        },
+    },
+    Key {
+        name: "keys.command_verification_key_store",
+        apply: |args, value| {
+            set_option(&mut args.command_verification_key_store, PathBuf::from(value.into_string()?));
+            Ok(())
+        },
+    },
+    Key {
+        name: "keys.update_verification_key",
+        apply: |args, value| {
+            let key = crate::args::parse_verfication_key(&value.into_string()?)?;
+            set_option(&mut args.update_verification_key, key);
+            Ok(())
+        },
+    },
+];
+
+/// An error that might occur when loading the configuration.
+#[derive(Debug)]
+pub struct Error {
+    /// Detailed information about the error.
+    kind: ErrorKind,
+}
+
+/// Kinds of errors that might occur when loading the configuration.
+#[derive(Debug)]
+enum ErrorKind {
+    /// The configuration file could not be read.
+    Read(PathBuf, std::io::Error),
+    /// The configuration file is not a valid TOML document.
+    Syntax(toml_edit::TomlError),
+    /// A value of a configuration key is not valid.
+    InvalidValue {
+        /// Full name of the key with the invalid value.
+        key: &'static str,
+        /// Where the invalid value comes from.
+        origin: Origin,
+        /// Description of the problem with the value.
+        message: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.kind {
+            ErrorKind::Read(ref path, ref error) => {
+                write!(fmt, "failed to read '{}': {error}", path.display())
+            }
+            ErrorKind::Syntax(ref error) => {
+                write!(fmt, "invalid configuration syntax: {error}")
+            }
+            ErrorKind::InvalidValue { key, ref origin, ref message } => {
+                write!(fmt, "invalid value of '{key}' ({origin}): {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            ErrorKind::Read(_, ref error) => Some(error),
+            ErrorKind::Syntax(ref error) => Some(error),
+            ErrorKind::InvalidValue { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args(flags: &[&str]) -> Args {
+        use argh::FromArgs as _;
+
+        Args::from_args(&["rrg"], flags)
+            .unwrap()
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let error = load(&tempdir.path().join("rrg.toml"))
+            .err().unwrap();
+        assert!(matches!(error.kind, ErrorKind::Read(..)));
+    }
+
+    #[test]
+    fn load_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("rrg.toml");
+        std::fs::write(&path, "state_dir = '/var/lib/rrg'\n")
+            .unwrap();
+
+        let mut args = args(&[]);
+        load(&path).unwrap().apply(&mut args)
+            .unwrap();
+        assert_eq!(args.state_dir, Some(PathBuf::from("/var/lib/rrg")));
+    }
+
+    #[test]
+    fn parse_empty() {
+        let config = parse("")
+            .unwrap();
+        assert_eq!(config.warnings().count(), 0);
+
+        let mut args = args(&[]);
+        config.apply(&mut args)
+            .unwrap();
+        assert_eq!(args.verbosity, log::LevelFilter::Info);
+        assert_eq!(args.state_dir, None);
+    }
+
+    #[test]
+    fn parse_all_sections() {
+        let config = parse(&format!(r#"
+            state_dir = "/var/lib/rrg"
+
+            [log]
+            verbosity = "debug"
+            to_stdout = true
+            to_file = "/var/log/rrg.log"
+
+            [transport]
+            heartbeat_rate = "1m"
+            blob_upload_url = "https://example.com/upload"
+            blob_upload_pins = ["{pin}"]
+            blob_upload_threshold = 1337
+            sink_pressure_high_watermark = 8388608
+            sink_pressure_low_watermark = 2097152
+            reply_compression_threshold = 4096
+
+            [policy]
+            allowed_actions = ["get_file_metadata", "get_filesystem_timeline"]
+            denied_actions = ["execute_signed_command"]
+            denied_paths = ["get_file_contents=/etc/shadow"]
+            redacted_paths = ["^/home/([^/]+)"]
+
+            [limits]
+            memory_limit = 1073741824
+            low_priority = true
+            command_rate_limit = 10
+
+            [keys]
+            command_verification_key_store = "/var/lib/rrg/keys"
+            update_verification_key = "{key}"
+        "#, pin = "ab".repeat(32), key = verifying_key_hex()))
+            .unwrap();
+        assert_eq!(config.warnings().count(), 0);
+
+        let mut args = args(&[]);
+        config.apply(&mut args)
+            .unwrap();
+
+        assert_eq!(args.state_dir, Some(PathBuf::from("/var/lib/rrg")));
+        assert_eq!(args.verbosity, log::LevelFilter::Debug);
+        assert!(args.log_to_stdout);
+        assert_eq!(args.log_to_file, Some(PathBuf::from("/var/log/rrg.log")));
+        assert_eq!(args.heartbeat_rate, std::time::Duration::from_secs(60));
+        assert_eq!(args.blob_upload_url.as_deref(), Some("https://example.com/upload"));
+        assert_eq!(args.blob_upload_pins, vec![[0xab; 32]]);
+        assert_eq!(args.blob_upload_threshold, 1337);
+        assert_eq!(args.sink_pressure_high_watermark, Some(8388608));
+        assert_eq!(args.sink_pressure_low_watermark, Some(2097152));
+        assert_eq!(args.reply_compression_threshold, Some(4096));
+        assert_eq!(args.allowed_actions, vec! {
+            crate::request::Action::GetFileMetadata,
+            crate::request::Action::GetFilesystemTimeline,
+        });
+        assert_eq!(args.denied_actions, vec![crate::request::Action::ExecuteSignedCommand]);
+        assert_eq!(args.denied_paths, vec! {
+            "get_file_contents=/etc/shadow".parse().unwrap(),
+        });
+        assert_eq!(args.redacted_paths.len(), 1);
+        assert_eq!(args.memory_limit, Some(1073741824));
+        assert!(args.low_priority);
+        assert_eq!(args.command_rate_limit, Some(10));
+        assert_eq!(args.command_verification_key_store, Some(PathBuf::from("/var/lib/rrg/keys")));
+        assert!(args.update_verification_key.is_some());
+    }
+
+    #[test]
+    fn parse_inline_table() {
+        let config = parse("limits = { memory_limit = 1337 }")
+            .unwrap();
+
+        let mut args = args(&[]);
+        config.apply(&mut args)
+            .unwrap();
+        assert_eq!(args.memory_limit, Some(1337));
+    }
+
+    #[test]
+    fn parse_temp_dir_size_limit() {
+        let config = parse("[limits]\ntemp_dir_size_limit = 1048576\n")
+            .unwrap();
+
+        let mut args = args(&[]);
+        config.apply(&mut args)
+            .unwrap();
+        assert_eq!(args.temp_dir_size_limit, Some(1048576));
+    }
+
+    #[test]
+    fn parse_unknown_keys() {
+        let config = parse("[log]\nverbosity = 'warn'\ncolor = true\n\n[foo]\nbar = 42\n")
+            .unwrap();
+
+        let warnings = config.warnings().collect::<Vec<_>>();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("'log.color'"));
+        assert!(warnings[0].contains("line 3"));
+        assert!(warnings[1].contains("'foo.bar'"));
+        assert!(warnings[1].contains("line 6"));
+
+        // Unknown keys are ignored but known ones are still applied.
+        let mut args = args(&[]);
+        config.apply(&mut args)
+            .unwrap();
+        assert_eq!(args.verbosity, log::LevelFilter::Warn);
+    }
+
+    #[test]
+    fn parse_invalid_syntax() {
+        let error = parse("[log\nverbosity = 'debug'\n")
+            .err().unwrap();
+        assert!(matches!(error.kind, ErrorKind::Syntax(_)));
+    }
+
+    #[test]
+    fn parse_unsupported_value_type() {
+        let error = parse("\n[limits]\nmemory_limit = 13.37\n")
+            .err().unwrap();
+
+        let message = error.to_string();
+        assert!(message.contains("'limits.memory_limit'"));
+        assert!(message.contains("line 3"));
+    }
+
+    #[test]
+    fn apply_invalid_value() {
+        let config = parse("[log]\n\nverbosity = 'loud'\n")
+            .unwrap();
+
+        let error = config.apply(&mut args(&[]))
+            .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains("'log.verbosity'"));
+        assert!(message.contains("line 3"));
+    }
+
+    #[test]
+    fn apply_invalid_type() {
+        let config = parse("[limits]\nlow_priority = 'yes'\n")
+            .unwrap();
+
+        let error = config.apply(&mut args(&[]))
+            .unwrap_err();
+        assert!(error.to_string().contains("'limits.low_priority'"));
+    }
+
+    #[test]
+    fn apply_negative_integer() {
+        let config = parse("[limits]\nmemory_limit = -1\n")
+            .unwrap();
+
+        assert!(config.apply(&mut args(&[])).is_err());
+    }
+
+    #[test]
+    fn apply_unknown_action() {
+        let config = parse("[policy]\ndenied_actions = ['foo']\n")
+            .unwrap();
+
+        let error = config.apply(&mut args(&[]))
+            .unwrap_err();
+        assert!(error.to_string().contains("'policy.denied_actions'"));
+    }
+
+    #[test]
+    fn apply_command_line_precedence() {
+        let config = parse("state_dir = '/foo'\n[log]\nverbosity = 'debug'\n")
+            .unwrap();
+
+        let mut args = args(&["--state-dir", "/bar", "--verbosity", "error"]);
+        config.apply(&mut args)
+            .unwrap();
+        assert_eq!(args.state_dir, Some(PathBuf::from("/bar")));
+        assert_eq!(args.verbosity, log::LevelFilter::Error);
+    }
+
+    #[test]
+    fn override_from_env() {
+        let mut config = parse("state_dir = '/foo'\n[limits]\nmemory_limit = 1024\n")
+            .unwrap();
+        config.override_from_env(|var| match var {
+            "RRG_STATE_DIR" => Some(String::from("/bar")),
+            "RRG_LIMITS_LOW_PRIORITY" => Some(String::from("true")),
+            "RRG_POLICY_DENIED_ACTIONS" => {
+                Some(String::from("execute_signed_command, get_file_contents"))
+            }
+            _ => None,
+        });
+
+        let mut args = args(&[]);
+        config.apply(&mut args)
+            .unwrap();
+        assert_eq!(args.state_dir, Some(PathBuf::from("/bar")));
+        assert_eq!(args.memory_limit, Some(1024));
+        assert!(args.low_priority);
+        assert_eq!(args.denied_actions, vec! {
+            crate::request::Action::ExecuteSignedCommand,
+            crate::request::Action::GetFileContents,
+        });
+    }
+
+    #[test]
+    fn override_from_env_invalid_value() {
+        let mut config = Config::default();
+        config.override_from_env(|var| match var {
+            "RRG_LIMITS_MEMORY_LIMIT" => Some(String::from("a lot")),
+            _ => None,
+        });
+
+        let error = config.apply(&mut args(&[]))
+            .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains("'limits.memory_limit'"));
+        assert!(message.contains("RRG_LIMITS_MEMORY_LIMIT"));
+    }
+
+    #[test]
+    fn keys_unique() {
+        for (idx, key) in KEYS.iter().enumerate() {
+            assert!(KEYS[idx + 1..].iter().all(|other| other.name != key.name));
+        }
+    }
+
+    fn verifying_key_hex() -> String {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[0x42; 32])
+            .verifying_key();
+
+        key.to_bytes().iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
@@ -0,0 +1,361 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for attributing Linux processes to namespaces and containers.
+
+use lazy_static::lazy_static;
+
+/// Names of the namespace files in the `/proc/<pid>/ns` directory.
+const NAMESPACE_NAMES: [&str; 7] = [
+    "cgroup",
+    "ipc",
+    "mnt",
+    "net",
+    "pid",
+    "user",
+    "uts",
+];
+
+lazy_static! {
+    /// Pattern of a cgroup path component that names a container.
+    ///
+    /// Container runtimes create a cgroup per container named either with the
+    /// container identifier itself (on the `cgroupfs` driver, e.g. Docker's
+    /// `/docker/<id>` or Kubernetes' `/kubepods/.../<id>`) or with the runtime
+    /// prefix and a `.scope` suffix (on the `systemd` driver, e.g. Docker's
+    /// `docker-<id>.scope`, containerd's `cri-containerd-<id>.scope`, CRI-O's
+    /// `crio-<id>.scope` or Podman's `libpod-<id>.scope`).
+    ///
+    /// Note that CRI-O also creates `crio-conmon-<id>.scope` cgroups for the
+    /// container monitor processes which are not part of the container and so
+    /// are deliberately not matched.
+    static ref CONTAINER_ID_REGEX: regex::Regex = regex::Regex::new({
+        r"^(?:(?:docker|cri-containerd|crio|libpod)-)?([0-9a-f]{64})(?:\.scope)?$"
+    }).unwrap();
+}
+
+/// Identifiers of namespaces a process belongs to.
+///
+/// Identifiers are inode numbers of the namespace files. Processes in the same
+/// namespace have the same identifier. Identifiers of namespaces that could not
+/// be read (e.g. because of insufficient permissions) are missing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Namespaces {
+    /// Identifier of the cgroup namespace.
+    pub cgroup: Option<u64>,
+    /// Identifier of the IPC namespace.
+    pub ipc: Option<u64>,
+    /// Identifier of the mount namespace.
+    pub mnt: Option<u64>,
+    /// Identifier of the network namespace.
+    pub net: Option<u64>,
+    /// Identifier of the PID namespace.
+    pub pid: Option<u64>,
+    /// Identifier of the user namespace.
+    pub user: Option<u64>,
+    /// Identifier of the UTS namespace.
+    pub uts: Option<u64>,
+}
+
+impl From<Namespaces> for rrg_proto::list_processes::Namespaces {
+
+    fn from(namespaces: Namespaces) -> rrg_proto::list_processes::Namespaces {
+        let mut proto = rrg_proto::list_processes::Namespaces::new();
+        if let Some(id) = namespaces.cgroup {
+            proto.set_cgroup(id);
+        }
+        if let Some(id) = namespaces.ipc {
+            proto.set_ipc(id);
+        }
+        if let Some(id) = namespaces.mnt {
+            proto.set_mnt(id);
+        }
+        if let Some(id) = namespaces.net {
+            proto.set_net(id);
+        }
+        if let Some(id) = namespaces.pid {
+            proto.set_pid(id);
+        }
+        if let Some(id) = namespaces.user {
+            proto.set_user(id);
+        }
+        if let Some(id) = namespaces.uts {
+            proto.set_uts(id);
+        }
+
+        proto
+    }
+}
+
+/// Returns identifiers of namespaces the process with the given pid belongs to.
+pub fn namespaces(pid: u32) -> Namespaces {
+    let [cgroup, ipc, mnt, net, pid, user, uts] = NAMESPACE_NAMES.map(|name| {
+        let link = std::fs::read_link(format!("/proc/{pid}/ns/{name}")).ok()?;
+        parse_namespace_link(link.as_os_str().to_str()?, name)
+    });
+
+    Namespaces {
+        cgroup,
+        ipc,
+        mnt,
+        net,
+        pid,
+        user,
+        uts,
+    }
+}
+
+/// Returns the cgroup path of the process with the given pid.
+///
+/// See [`parse_cgroup`] for details on how the path is picked.
+pub fn cgroup_path(pid: u32) -> std::io::Result<String> {
+    let content = std::fs::read_to_string(format!("/proc/{pid}/cgroup"))?;
+
+    parse_cgroup(&content)
+        .map(String::from)
+        .ok_or_else(|| std::io::ErrorKind::InvalidData.into())
+}
+
+/// Extracts a container identifier from the given cgroup path.
+///
+/// Components are checked from the innermost one, so that cgroups nested
+/// within the container cgroup (e.g. `init.scope` of a container running its
+/// own systemd) are attributed to the container as well.
+pub fn container_id(cgroup_path: &str) -> Option<&str> {
+    cgroup_path.rsplit('/').find_map(|component| {
+        Some(CONTAINER_ID_REGEX.captures(component)?.get(1)?.as_str())
+    })
+}
+
+/// Returns the prefix of the given cgroup path that is the container cgroup.
+///
+/// `None` is returned if the path does not belong to any container.
+pub fn container_cgroup_root(cgroup_path: &str) -> Option<&str> {
+    let mut end = cgroup_path.len();
+
+    for component in cgroup_path.rsplit('/') {
+        if CONTAINER_ID_REGEX.is_match(component) {
+            return Some(&cgroup_path[..end]);
+        }
+        end = end.saturating_sub(component.len() + 1);
+    }
+
+    None
+}
+
+/// Parses a namespace symlink target (e.g. `net:[4026531840]`).
+fn parse_namespace_link(link: &str, name: &str) -> Option<u64> {
+    link.strip_prefix(name)?
+        .strip_prefix(":[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Picks the cgroup path out of the contents of a `/proc/<pid>/cgroup` file.
+///
+/// Each line of the file has the `<hierarchy>:<controllers>:<path>` format.
+/// With cgroup v2 there is a single `0::<path>` line, with cgroup v1 there is
+/// a line for every hierarchy and on hybrid systems both kinds are present.
+/// Because the hierarchies can differ, the first path that belongs to a
+/// container is preferred, then the cgroup v2 path and then the path of the
+/// first cgroup v1 hierarchy [1].
+///
+/// [1]: https://man7.org/linux/man-pages/man7/cgroups.7.html
+fn parse_cgroup(content: &str) -> Option<&str> {
+    let mut v1_paths = Vec::new();
+    let mut v2_path = None;
+
+    for line in content.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (Some(hierarchy), Some(controllers), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+
+        if hierarchy == "0" && controllers.is_empty() {
+            v2_path = Some(path);
+        } else {
+            v1_paths.push(path);
+        }
+    }
+
+    v2_path.iter().chain(v1_paths.iter())
+        .find(|path| container_id(path).is_some())
+        .or(v2_path.as_ref())
+        .or(v1_paths.first())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const ID: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn namespaces_self() {
+        let self_namespaces = namespaces(std::process::id());
+        assert!(self_namespaces.net.is_some());
+        assert!(self_namespaces.pid.is_some());
+        assert!(self_namespaces.mnt.is_some());
+
+        // Child processes share namespaces with the parent by default.
+        let parent_namespaces = namespaces(std::os::unix::process::parent_id());
+        if parent_namespaces.net.is_some() {
+            assert_eq!(self_namespaces.net, parent_namespaces.net);
+        }
+    }
+
+    #[test]
+    fn cgroup_path_self() {
+        let path = cgroup_path(std::process::id())
+            .unwrap();
+        assert!(path.starts_with('/'));
+    }
+
+    #[test]
+    fn parse_namespace_link_ok() {
+        assert_eq!(parse_namespace_link("net:[4026531840]", "net"), Some(4026531840));
+    }
+
+    #[test]
+    fn parse_namespace_link_wrong_name() {
+        assert_eq!(parse_namespace_link("net:[4026531840]", "pid"), None);
+    }
+
+    #[test]
+    fn parse_namespace_link_malformed() {
+        assert_eq!(parse_namespace_link("net:4026531840", "net"), None);
+        assert_eq!(parse_namespace_link("net:[foo]", "net"), None);
+    }
+
+    #[test]
+    fn parse_cgroup_v2() {
+        let content = "0::/system.slice/sshd.service\n";
+        assert_eq!(parse_cgroup(content), Some("/system.slice/sshd.service"));
+    }
+
+    #[test]
+    fn parse_cgroup_v1() {
+        let content = format! {
+            "12:pids:/docker/{ID}\n\
+             11:memory:/docker/{ID}\n\
+             1:name=systemd:/docker/{ID}\n"
+        };
+        assert_eq!(parse_cgroup(&content), Some(format!("/docker/{ID}").as_str()));
+    }
+
+    #[test]
+    fn parse_cgroup_v1_no_container() {
+        let content = "\
+            4:cpu,cpuacct:/user.slice\n\
+            1:name=systemd:/user.slice/user-1000.slice/session-1.scope\n\
+        ";
+        assert_eq!(parse_cgroup(content), Some("/user.slice"));
+    }
+
+    #[test]
+    fn parse_cgroup_hybrid() {
+        let content = format! {
+            "1:name=systemd:/system.slice/docker-{ID}.scope\n\
+             0::/init.scope\n"
+        };
+        assert_eq!(parse_cgroup(&content), Some(format!("/system.slice/docker-{ID}.scope").as_str()));
+    }
+
+    #[test]
+    fn parse_cgroup_empty() {
+        assert_eq!(parse_cgroup(""), None);
+    }
+
+    #[test]
+    fn container_id_docker_cgroupfs() {
+        assert_eq!(container_id(&format!("/docker/{ID}")), Some(ID));
+    }
+
+    #[test]
+    fn container_id_docker_systemd() {
+        assert_eq!(container_id(&format!("/system.slice/docker-{ID}.scope")), Some(ID));
+    }
+
+    #[test]
+    fn container_id_containerd_cgroupfs() {
+        let path = format!("/kubepods/burstable/pod1b2c3d4e-0000-1111-2222-333344445555/{ID}");
+        assert_eq!(container_id(&path), Some(ID));
+    }
+
+    #[test]
+    fn container_id_containerd_systemd() {
+        let path = format! {
+            "/kubepods.slice/kubepods-besteffort.slice/\
+             kubepods-besteffort-pod1b2c3d4e_0000_1111_2222_333344445555.slice/\
+             cri-containerd-{ID}.scope"
+        };
+        assert_eq!(container_id(&path), Some(ID));
+    }
+
+    #[test]
+    fn container_id_crio() {
+        let path = format! {
+            "/kubepods.slice/kubepods-pod1b2c3d4e_0000.slice/crio-{ID}.scope"
+        };
+        assert_eq!(container_id(&path), Some(ID));
+    }
+
+    #[test]
+    fn container_id_crio_conmon() {
+        let path = format! {
+            "/kubepods.slice/kubepods-pod1b2c3d4e_0000.slice/crio-conmon-{ID}.scope"
+        };
+        assert_eq!(container_id(&path), None);
+    }
+
+    #[test]
+    fn container_id_podman() {
+        let path = format! {
+            "/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{ID}.scope"
+        };
+        assert_eq!(container_id(&path), Some(ID));
+    }
+
+    #[test]
+    fn container_id_nested() {
+        let path = format!("/system.slice/docker-{ID}.scope/init.scope");
+        assert_eq!(container_id(&path), Some(ID));
+    }
+
+    #[test]
+    fn container_id_none() {
+        assert_eq!(container_id("/system.slice/sshd.service"), None);
+        assert_eq!(container_id("/"), None);
+        assert_eq!(container_id(""), None);
+    }
+
+    #[test]
+    fn container_id_short_hex() {
+        assert_eq!(container_id("/docker/0123456789ab"), None);
+    }
+
+    #[test]
+    fn container_cgroup_root_nested() {
+        let path = format!("/system.slice/docker-{ID}.scope/init.scope");
+        assert_eq! {
+            container_cgroup_root(&path),
+            Some(format!("/system.slice/docker-{ID}.scope").as_str())
+        };
+    }
+
+    #[test]
+    fn container_cgroup_root_exact() {
+        let path = format!("/docker/{ID}");
+        assert_eq!(container_cgroup_root(&path), Some(path.as_str()));
+    }
+
+    #[test]
+    fn container_cgroup_root_none() {
+        assert_eq!(container_cgroup_root("/system.slice/sshd.service"), None);
+    }
+}
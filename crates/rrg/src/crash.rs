@@ -0,0 +1,490 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Reporting of agent crashes.
+//!
+//! When the agent panics, a report about the crash is written to the spool
+//! directory (if one is specified in the agent arguments). At that point the
+//! agent cannot reliably communicate with the server, so spooled reports are
+//! delivered only once the agent is started again.
+//!
+//! Note that only panics are reported. Crashes caused by signals (e.g. invalid
+//! memory accesses in native code) terminate the process without giving us an
+//! opportunity to safely run any code.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Maximum number of crash reports kept in the spool directory.
+///
+/// Once the limit is reached, reports about subsequent crashes are dropped
+/// until the spooled ones are delivered.
+const MAX_REPORT_COUNT: usize = 8;
+
+/// Maximum length of the panic message included in the report (in bytes).
+const MAX_MESSAGE_LEN: usize = 4 * 1024;
+
+/// Maximum length of the backtrace included in the report (in bytes).
+const MAX_BACKTRACE_LEN: usize = 32 * 1024;
+
+/// Maximum size of a spooled report file accepted for delivery (in bytes).
+const MAX_REPORT_SIZE: u64 = 64 * 1024;
+
+/// Extension of spooled crash report files.
+const REPORT_EXTENSION: &str = "crash";
+
+/// Identifier of the request that is currently being handled.
+static CURRENT_REQUEST: std::sync::Mutex<Option<crate::RequestId>> =
+    std::sync::Mutex::new(None);
+
+/// Whether a panic is currently being reported.
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// Installs the panic hook spooling crash reports.
+///
+/// This function should be called only once, at the beginning of the process
+/// lifetime. The hook is installed only if the spool directory is specified in
+/// the agent arguments.
+pub fn init(args: &crate::args::Args) {
+    if let Some(spool_dir) = spool_dir(args) {
+        install(spool_dir);
+    }
+}
+
+/// Sends reports about previous crashes spooled on disk to the GRR server.
+///
+/// Reports are removed from the spool once sent, malformed reports are removed
+/// without being sent.
+pub fn deliver(args: &crate::args::Args) {
+    let Some(spool_dir) = spool_dir(args) else {
+        return;
+    };
+
+    let paths = match spooled(&spool_dir) {
+        Ok(paths) => paths,
+        Err(error) => {
+            log::error!("failed to list spooled crash reports: {error}");
+            return;
+        }
+    };
+
+    for path in paths {
+        match read(&path) {
+            Ok(report) => {
+                log::info!("sending crash report '{}'", path.display());
+                crate::response::Parcel::new(crate::Sink::CrashReport, Report(report))
+                    .send_unaccounted();
+            }
+            Err(error) => {
+                log::error!("invalid crash report '{}': {error}", path.display());
+            }
+        }
+
+        if let Err(error) = std::fs::remove_file(&path) {
+            log::error!("failed to remove crash report '{}': {error}", path.display());
+        }
+    }
+}
+
+/// Returns the number of crash reports waiting in the spool to be sent.
+pub fn spool_depth(args: &crate::args::Args) -> std::io::Result<usize> {
+    let Some(spool_dir) = spool_dir(args) else {
+        return Ok(0);
+    };
+
+    match spooled(&spool_dir) {
+        Ok(paths) => Ok(paths.len()),
+        // The spool directory is created only once the hook is installed.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the directory to spool crash reports to.
+///
+/// Unless specified explicitly, reports are spooled to a subdirectory of the
+/// agent state directory (if that one is specified).
+pub fn spool_dir(args: &crate::args::Args) -> Option<std::path::PathBuf> {
+    match (&args.crash_spool_dir, &args.state_dir) {
+        (Some(spool_dir), _) => Some(spool_dir.clone()),
+        (None, Some(state_dir)) => Some(state_dir.join("crash")),
+        (None, None) => None,
+    }
+}
+
+/// Guard marking a request as being currently handled.
+///
+/// Crash reports include the identifier of the request that was being handled
+/// while the crash happened. The request is no longer considered to be handled
+/// once the guard is dropped.
+pub struct RequestGuard {
+    _private: (),
+}
+
+impl RequestGuard {
+
+    /// Marks the request with the given identifier as being handled.
+    pub fn new(request_id: crate::RequestId) -> RequestGuard {
+        set_current_request(Some(request_id));
+        RequestGuard { _private: () }
+    }
+}
+
+impl Drop for RequestGuard {
+
+    fn drop(&mut self) {
+        set_current_request(None);
+    }
+}
+
+/// Sets the identifier of the request that is currently being handled.
+fn set_current_request(request_id: Option<crate::RequestId>) {
+    let mut current_request = CURRENT_REQUEST.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    *current_request = request_id;
+}
+
+/// Installs the panic hook spooling crash reports to the given directory.
+fn install(spool_dir: std::path::PathBuf) {
+    if let Err(error) = std::fs::create_dir_all(&spool_dir) {
+        log::error!("failed to create crash spool directory: {error}");
+    }
+
+    // We collect the operating system version upfront rather than in the hook
+    // as it involves calls to the system that we do not want to do while the
+    // process is in an unknown state.
+    let os_version = ospect::os::version()
+        .inspect_err(|error| log::error!("failed to obtain OS version: {error}"))
+        .unwrap_or_default();
+
+    let prev_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // A panic within the panic hook aborts the process immediately, so we
+        // never handle more than one panic at a time (in case e.g. some other
+        // thread panics concurrently) and ignore all the errors.
+        if !REPORTING.swap(true, Ordering::SeqCst) {
+            let report = report(info, &os_version);
+            let _ = spool(&spool_dir, &report);
+
+            REPORTING.store(false, Ordering::SeqCst);
+        }
+
+        prev_hook(info);
+    }));
+}
+
+/// Creates a crash report for the given panic.
+fn report(
+    info: &std::panic::PanicHookInfo,
+    os_version: &str,
+) -> rrg_proto::crash::CrashReport {
+    let mut proto = rrg_proto::crash::CrashReport::new();
+    proto.set_metadata(crate::startup::Metadata::from_cargo().into());
+    proto.set_crash_time(rrg_proto::into_timestamp(std::time::SystemTime::now()));
+
+    let payload = info.payload();
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("<unknown panic payload>")
+    };
+    proto.set_message(truncate(message, MAX_MESSAGE_LEN));
+
+    if let Some(location) = info.location() {
+        proto.set_location(location.to_string());
+    }
+    if let Some(thread) = std::thread::current().name() {
+        proto.set_thread(String::from(thread));
+    }
+
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    proto.set_backtrace(truncate(backtrace, MAX_BACKTRACE_LEN));
+
+    // We cannot block within the hook: if the lock is being held by the thread
+    // that panicked we would deadlock.
+    if let Ok(current_request) = CURRENT_REQUEST.try_lock() {
+        if let Some(request_id) = *current_request {
+            proto.set_flow_id(request_id.flow_id());
+            proto.set_request_id(request_id.request_id());
+        }
+    }
+
+    proto.set_os_type(ospect::os::kind().into());
+    proto.set_os_version(String::from(os_version));
+    proto.set_arch(String::from(std::env::consts::ARCH));
+
+    proto
+}
+
+/// Writes the crash report to a new file in the spool directory.
+fn spool(
+    spool_dir: &std::path::Path,
+    report: &rrg_proto::crash::CrashReport,
+) -> std::io::Result<()> {
+    use protobuf::Message as _;
+
+    if spooled(spool_dir)?.len() >= MAX_REPORT_COUNT {
+        return Err(std::io::Error::other("too many spooled crash reports"));
+    }
+
+    let bytes = report.write_to_bytes()?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let name = format!("{timestamp}-{}", std::process::id());
+
+    let path = spool_dir.join(name).with_extension(REPORT_EXTENSION);
+    crate::state::write_atomic(path, &bytes)
+}
+
+/// Returns paths of all crash reports in the spool directory.
+///
+/// The paths are sorted from the oldest to the newest report.
+fn spooled(
+    spool_dir: &std::path::Path,
+) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut paths = Vec::new();
+
+    for entry in std::fs::read_dir(spool_dir)? {
+        let path = entry?.path();
+        if path.extension() == Some(std::ffi::OsStr::new(REPORT_EXTENSION)) {
+            paths.push(path);
+        }
+    }
+
+    // Report names start with the timestamp of the crash, so sorting them by
+    // name yields them in chronological order.
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Reads a spooled crash report from the given path.
+fn read(path: &std::path::Path) -> std::io::Result<rrg_proto::crash::CrashReport> {
+    use protobuf::Message as _;
+
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_REPORT_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("report too large ({size} bytes)"),
+        ));
+    }
+
+    let bytes = std::fs::read(path)?;
+    let report = rrg_proto::crash::CrashReport::parse_from_bytes(&bytes)?;
+
+    Ok(report)
+}
+
+/// Truncates the given string to at most `max_len` bytes.
+fn truncate(mut string: String, max_len: usize) -> String {
+    if string.len() > max_len {
+        let mut len = max_len;
+        while !string.is_char_boundary(len) {
+            len -= 1;
+        }
+        string.truncate(len);
+    }
+
+    string
+}
+
+/// Crash report read from the spool directory.
+struct Report(rrg_proto::crash::CrashReport);
+
+impl crate::response::Item for Report {
+    type Proto = rrg_proto::crash::CrashReport;
+
+    fn into_proto(self) -> rrg_proto::crash::CrashReport {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Environment variable with the spool directory of the crashing process.
+    const SPOOL_DIR_ENV: &str = "RRG_TEST_CRASH_SPOOL_DIR";
+
+    /// Action handler that always panics.
+    fn handle_panic<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+    where
+        S: crate::session::Session,
+    {
+        panic!("handler panicked on purpose");
+    }
+
+    /// Runs the given test in a separate process with the given spool.
+    ///
+    /// Within the separate process, the test is expected to install the crash
+    /// hook and crash.
+    fn run_crashing(test: &str, spool_dir: &std::path::Path) {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", test, "--test-threads", "1"])
+            .env(SPOOL_DIR_ENV, spool_dir)
+            .output()
+            .unwrap();
+
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn hook_spools_report() {
+        if let Some(spool_dir) = std::env::var_os(SPOOL_DIR_ENV) {
+            install(std::path::PathBuf::from(spool_dir));
+
+            let _guard = RequestGuard::new(crate::RequestId::new(0xf00, 0xba7));
+
+            let mut session = crate::session::FakeSession::new();
+            let _ = handle_panic(&mut session, ());
+
+            unreachable!();
+        }
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        run_crashing("crash::tests::hook_spools_report", tempdir.path());
+
+        let paths = spooled(tempdir.path())
+            .unwrap();
+        assert_eq!(paths.len(), 1);
+
+        let report = read(&paths[0])
+            .unwrap();
+        assert_eq!(report.message(), "handler panicked on purpose");
+        assert!(report.location().contains("crash.rs"));
+        assert!(!report.backtrace().is_empty());
+        assert_eq!(report.flow_id(), 0xf00);
+        assert_eq!(report.request_id(), 0xba7);
+        assert_eq!(report.metadata().name(), "rrg");
+        assert!(report.has_crash_time());
+        assert_eq!(report.arch(), std::env::consts::ARCH);
+
+        // The temporary file should be renamed to the final report.
+        let entry_count = std::fs::read_dir(tempdir.path())
+            .unwrap()
+            .count();
+        assert_eq!(entry_count, 1);
+    }
+
+    #[test]
+    fn hook_spools_report_without_request() {
+        if let Some(spool_dir) = std::env::var_os(SPOOL_DIR_ENV) {
+            install(std::path::PathBuf::from(spool_dir));
+            panic!("{}", "x".repeat(2 * MAX_MESSAGE_LEN));
+        }
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        run_crashing("crash::tests::hook_spools_report_without_request", tempdir.path());
+
+        let paths = spooled(tempdir.path())
+            .unwrap();
+        assert_eq!(paths.len(), 1);
+
+        let report = read(&paths[0])
+            .unwrap();
+        assert_eq!(report.message(), "x".repeat(MAX_MESSAGE_LEN));
+        assert!(!report.has_flow_id());
+        assert!(!report.has_request_id());
+    }
+
+    #[test]
+    fn hook_spool_full() {
+        if let Some(spool_dir) = std::env::var_os(SPOOL_DIR_ENV) {
+            install(std::path::PathBuf::from(spool_dir));
+            panic!("crashed with full spool");
+        }
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        for i in 0..MAX_REPORT_COUNT {
+            let path = tempdir.path().join(format!("{i}.{REPORT_EXTENSION}"));
+            std::fs::write(path, b"").unwrap();
+        }
+
+        run_crashing("crash::tests::hook_spool_full", tempdir.path());
+
+        let paths = spooled(tempdir.path())
+            .unwrap();
+        assert_eq!(paths.len(), MAX_REPORT_COUNT);
+    }
+
+    #[test]
+    fn spooled_ignores_other_files() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("2.crash"), b"").unwrap();
+        std::fs::write(tempdir.path().join("1.crash"), b"").unwrap();
+        std::fs::write(tempdir.path().join("3.tmp"), b"").unwrap();
+
+        let paths = spooled(tempdir.path())
+            .unwrap();
+        assert_eq!(paths, vec![
+            tempdir.path().join("1.crash"),
+            tempdir.path().join("2.crash"),
+        ]);
+    }
+
+    #[test]
+    fn read_too_large() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("1.crash");
+        std::fs::write(&path, vec![0; MAX_REPORT_SIZE as usize + 1]).unwrap();
+
+        let error = read(&path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_malformed() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("1.crash");
+        std::fs::write(&path, b"\xff\xff\xff").unwrap();
+
+        assert!(read(&path).is_err());
+    }
+
+    #[test]
+    fn spool_dir_from_args() {
+        use argh::FromArgs as _;
+
+        let args = crate::args::Args::from_args(&["rrg"], &[])
+            .unwrap();
+        assert_eq!(spool_dir(&args), None);
+
+        let args = crate::args::Args::from_args(&["rrg"], &[
+            "--state-dir", "/foo",
+        ]).unwrap();
+        assert_eq!(spool_dir(&args), Some(std::path::PathBuf::from("/foo/crash")));
+
+        let args = crate::args::Args::from_args(&["rrg"], &[
+            "--state-dir", "/foo",
+            "--crash-spool-dir", "/bar",
+        ]).unwrap();
+        assert_eq!(spool_dir(&args), Some(std::path::PathBuf::from("/bar")));
+    }
+
+    #[test]
+    fn truncate_char_boundary() {
+        assert_eq!(truncate(String::from("foobar"), 3), "foo");
+        assert_eq!(truncate(String::from("foo"), 8), "foo");
+        assert_eq!(truncate(String::from("zażółć"), 3), "za");
+    }
+}
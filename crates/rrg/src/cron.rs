@@ -0,0 +1,312 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for reading crontab files.
+
+use std::path::{Path, PathBuf};
+
+/// Returns paths to all crontab files in the given directory.
+///
+/// Similarly to cron itself, files with names containing anything other than
+/// alphanumeric characters, hyphens and underscores (e.g. backups left by the
+/// package manager) are skipped.
+pub fn crontab_paths(dir_path: &Path) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list crontabs in '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut paths = Vec::new();
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::warn!("failed to read crontab entry: {error}");
+                continue;
+            }
+        };
+
+        let is_valid_name = entry.file_name().to_str()
+            .is_some_and(|name| name.bytes().all(|byte| {
+                byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'
+            }));
+        if !is_valid_name {
+            continue;
+        }
+
+        // Directories can be nested (e.g. the Debian user crontab directory is
+        // a subdirectory of the Red Hat one), so we skip them.
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_file() => (),
+            Ok(_) => continue,
+            Err(error) => {
+                log::warn!("failed to stat crontab '{}': {error}", entry.path().display());
+                continue;
+            }
+        }
+
+        paths.push(entry.path());
+    }
+
+    paths.sort();
+    paths
+}
+
+/// Format of a crontab file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// System crontab format (with the user field).
+    System,
+    /// Per-user crontab format (without the user field).
+    User,
+}
+
+/// Single job entry of a crontab file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Number of the line (starting from 1) the entry was defined at.
+    pub line: usize,
+    /// Schedule of the job (either the time fields or a special string).
+    pub schedule: String,
+    /// User as which the job is executed (only for the system format).
+    pub user: Option<String>,
+    /// Command that the job executes.
+    pub command: String,
+}
+
+/// Parses job entries from the given crontab file.
+///
+/// Environment variable assignments, comments and empty lines are skipped. See
+/// the [documentation][1] for details about the format.
+///
+/// [1]: https://man7.org/linux/man-pages/man5/crontab.5.html
+pub fn parse_crontab<R>(crontab: R, format: Format) -> std::io::Result<Vec<Entry>>
+where
+    R: std::io::BufRead,
+{
+    let mut entries = Vec::new();
+
+    for (index, line) in crontab.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') || is_env_assignment(line) {
+            continue;
+        }
+
+        match parse_entry(line, format) {
+            Some((schedule, user, command)) => entries.push(Entry {
+                line: index + 1,
+                schedule,
+                user,
+                command: String::from(command),
+            }),
+            None => log::warn!("invalid crontab entry at line {}", index + 1),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Parses a single job entry line.
+///
+/// Returns the schedule, the user (for the system format) and the command.
+fn parse_entry(line: &str, format: Format) -> Option<(String, Option<String>, &str)> {
+    let (schedule, rest) = if line.starts_with('@') {
+        let (special, rest) = split_field(line)?;
+        (String::from(special), rest)
+    } else {
+        let mut fields = Vec::with_capacity(5);
+        let mut rest = line;
+        for _ in 0..5 {
+            let (field, field_rest) = split_field(rest)?;
+            fields.push(field);
+            rest = field_rest;
+        }
+        (fields.join(" "), rest)
+    };
+
+    let (user, rest) = match format {
+        Format::System => {
+            let (user, rest) = split_field(rest)?;
+            (Some(String::from(user)), rest)
+        }
+        Format::User => (None, rest),
+    };
+
+    let command = rest.trim();
+    if command.is_empty() {
+        return None;
+    }
+
+    Some((schedule, user, command))
+}
+
+/// Checks whether the given (trimmed) line is an environment assignment.
+///
+/// Assignments have the `NAME = VALUE` form (spaces around `=` are optional)
+/// whereas job entries always start with time fields separated by spaces, so
+/// it is enough to check whether the text before `=` is a single word.
+fn is_env_assignment(line: &str) -> bool {
+    match line.split_once('=') {
+        Some((name, _)) => {
+            let name = name.trim_end();
+            !name.is_empty() && !name.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// Splits the first whitespace-separated field from the given string.
+fn split_field(string: &str) -> Option<(&str, &str)> {
+    let string = string.trim_start();
+    if string.is_empty() {
+        return None;
+    }
+
+    match string.find(char::is_whitespace) {
+        Some(index) => Some((&string[..index], &string[index..])),
+        None => Some((string, "")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_crontab_system() {
+        let crontab = b"\
+# /etc/crontab: system-wide crontab
+SHELL=/bin/sh
+PATH=/usr/local/sbin:/usr/local/bin:/sbin:/bin:/usr/sbin:/usr/bin
+
+# m h dom mon dow user  command
+17 *    * * *   root    cd / && run-parts --report /etc/cron.hourly
+25 6    * * *   root    test -x /usr/sbin/anacron || { cd / && run-parts --report /etc/cron.daily; }
+";
+
+        let entries = parse_crontab(&crontab[..], Format::System)
+            .unwrap();
+
+        assert_eq!(entries, vec![
+            Entry {
+                line: 6,
+                schedule: String::from("17 * * * *"),
+                user: Some(String::from("root")),
+                command: String::from("cd / && run-parts --report /etc/cron.hourly"),
+            },
+            Entry {
+                line: 7,
+                schedule: String::from("25 6 * * *"),
+                user: Some(String::from("root")),
+                command: String::from("test -x /usr/sbin/anacron || { cd / && run-parts --report /etc/cron.daily; }"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_crontab_user() {
+        let crontab = b"\
+MAILTO=\"\"
+*/5 * * * * /home/foo/bin/sync.sh >/dev/null 2>&1
+0 9-17 * * mon-fri echo \"work\"
+";
+
+        let entries = parse_crontab(&crontab[..], Format::User)
+            .unwrap();
+
+        assert_eq!(entries, vec![
+            Entry {
+                line: 2,
+                schedule: String::from("*/5 * * * *"),
+                user: None,
+                command: String::from("/home/foo/bin/sync.sh >/dev/null 2>&1"),
+            },
+            Entry {
+                line: 3,
+                schedule: String::from("0 9-17 * * mon-fri"),
+                user: None,
+                command: String::from("echo \"work\""),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_crontab_env_assignments() {
+        let crontab = b"\
+SHELL = /bin/bash
+  FOO=bar baz
+BAR= quux=norf
+@hourly FOO=1 /usr/bin/foo
+";
+
+        let entries = parse_crontab(&crontab[..], Format::User)
+            .unwrap();
+
+        assert_eq!(entries, vec![
+            Entry {
+                line: 4,
+                schedule: String::from("@hourly"),
+                user: None,
+                command: String::from("FOO=1 /usr/bin/foo"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_crontab_special() {
+        let crontab = b"\
+@reboot root /usr/local/bin/backdoor
+@daily  root /usr/bin/updatedb
+";
+
+        let entries = parse_crontab(&crontab[..], Format::System)
+            .unwrap();
+
+        assert_eq!(entries, vec![
+            Entry {
+                line: 1,
+                schedule: String::from("@reboot"),
+                user: Some(String::from("root")),
+                command: String::from("/usr/local/bin/backdoor"),
+            },
+            Entry {
+                line: 2,
+                schedule: String::from("@daily"),
+                user: Some(String::from("root")),
+                command: String::from("/usr/bin/updatedb"),
+            },
+        ]);
+    }
+
+    #[test]
+    fn parse_crontab_invalid_entries() {
+        let crontab = b"\
+* * * *
+* * * * * root
+@weekly root
+0 0 * * * root /bin/true
+";
+
+        let entries = parse_crontab(&crontab[..], Format::System)
+            .unwrap();
+
+        assert_eq!(entries, vec![
+            Entry {
+                line: 4,
+                schedule: String::from("0 0 * * *"),
+                user: Some(String::from("root")),
+                command: String::from("/bin/true"),
+            },
+        ]);
+    }
+}
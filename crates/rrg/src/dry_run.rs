@@ -0,0 +1,36 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Estimates of the cost of requests executed as dry runs.
+//!
+//! Requests can be marked as dry runs by the server to validate them and learn
+//! how expensive they are before executing them for real. Actions that support
+//! it (see [`crate::action::supports_dry_run`]) do only a cheap estimation pass
+//! then and reply with a single [`Estimate`].
+
+/// Estimate of the cost of a request.
+#[derive(Debug, Default)]
+pub struct Estimate {
+    /// Estimated number of items (e.g. files) the action would process.
+    pub item_count: u64,
+    /// Estimated number of bytes of data the action would read or send.
+    pub bytes: u64,
+    /// Notes about the estimate (e.g. requested features not supported).
+    pub notes: Vec<String>,
+}
+
+impl crate::response::Item for Estimate {
+
+    type Proto = rrg_proto::rrg::DryRunEstimate;
+
+    fn into_proto(self) -> rrg_proto::rrg::DryRunEstimate {
+        let mut proto = rrg_proto::rrg::DryRunEstimate::new();
+        proto.set_item_count(self.item_count);
+        proto.set_bytes(self.bytes);
+        proto.set_notes(self.notes);
+
+        proto
+    }
+}
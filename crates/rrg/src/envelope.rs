@@ -0,0 +1,500 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Envelope encryption of files written to the local disk.
+//!
+//! Results collected without a connection to the server can stay on disk for
+//! a long time, so they are encrypted to a public key of the deployment. Every
+//! file gets its own ephemeral X25519 key: a key shared with the recipient is
+//! derived from it (using HKDF-SHA256) and the data is then sealed in chunks
+//! with ChaCha20-Poly1305.
+//!
+//! The encrypted file has the following format:
+//!
+//! * magic bytes (`RRGE`) followed by a format version byte,
+//! * SHA-256 digest of the recipient public key (to identify it),
+//! * the ephemeral public key,
+//! * a sequence of chunks, each preceded by its 32-bit little-endian length.
+//!
+//! The highest bit of the chunk length marks the last chunk of the file. Chunk
+//! nonces consist of the chunk index and this marker, so chunks cannot be
+//! reordered, and a truncated file or one with data appended is detected.
+
+use std::io::{Read, Write};
+
+use chacha20poly1305::aead::{Aead as _, KeyInit as _};
+use sha2::Digest as _;
+
+/// Magic bytes at the beginning of every encrypted file.
+const MAGIC: &[u8; 4] = b"RRGE";
+
+/// Version of the encrypted file format.
+const VERSION: u8 = 1;
+
+/// Maximum size of the plaintext of a single chunk (in bytes).
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of the authentication tag appended to every chunk (in bytes).
+const TAG_SIZE: usize = 16;
+
+/// Bit of the chunk length that marks the last chunk.
+const LAST_CHUNK_FLAG: u32 = 1 << 31;
+
+/// Context string used for deriving the chunk encryption key.
+const KEY_INFO: &[u8] = b"rrg-envelope-v1";
+
+/// Writer that encrypts all the data written to it.
+///
+/// Once all the data is written, the [`Writer::finish`] method has to be called
+/// to seal the last chunk. Files of writers dropped without finishing are going
+/// to be rejected as truncated when decrypted.
+pub struct Writer<W: Write> {
+    /// Writer to write the encrypted data to.
+    inner: W,
+    /// Cipher used for sealing the chunks.
+    cipher: chacha20poly1305::ChaCha20Poly1305,
+    /// Index of the next chunk to seal.
+    chunk_index: u64,
+    /// Buffered plaintext of the next chunk.
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Writer<W> {
+
+    /// Creates a new writer encrypting data to the given `recipient` key.
+    ///
+    /// The file header is written to `inner` immediately.
+    pub fn new(
+        mut inner: W,
+        recipient: &x25519_dalek::PublicKey,
+    ) -> std::io::Result<Writer<W>> {
+        let ephemeral_secret = x25519_dalek::EphemeralSecret::random();
+        let ephemeral = x25519_dalek::PublicKey::from(&ephemeral_secret);
+
+        let shared_secret = ephemeral_secret.diffie_hellman(recipient);
+        if !shared_secret.was_contributory() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid recipient key",
+            ));
+        }
+
+        inner.write_all(MAGIC)?;
+        inner.write_all(&[VERSION])?;
+        inner.write_all(&fingerprint(recipient))?;
+        inner.write_all(ephemeral.as_bytes())?;
+
+        Ok(Writer {
+            inner,
+            cipher: cipher(&shared_secret, &ephemeral, recipient),
+            chunk_index: 0,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    /// Seals the last chunk and returns the underlying writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    /// Seals the buffered plaintext and writes it as the next chunk.
+    fn seal_chunk(&mut self, last: bool) -> std::io::Result<()> {
+        let nonce = nonce(self.chunk_index, last);
+        let chunk = self.cipher.encrypt(&nonce.into(), &self.buf[..])
+            .map_err(|_| std::io::Error::other("failed to seal a chunk"))?;
+
+        let mut len = chunk.len() as u32;
+        if last {
+            len |= LAST_CHUNK_FLAG;
+        }
+
+        self.inner.write_all(&len.to_le_bytes())?;
+        self.inner.write_all(&chunk)?;
+
+        self.chunk_index += 1;
+        self.buf.clear();
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.buf.len() == CHUNK_SIZE {
+            self.seal_chunk(false)?;
+        }
+
+        let len = std::cmp::min(buf.len(), CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // We cannot seal an incomplete chunk as it would waste space (and the
+        // chunk boundaries would leak the write pattern), so we only flush the
+        // chunks that were already sealed.
+        self.inner.flush()
+    }
+}
+
+/// Reader that decrypts data written by [`Writer`].
+///
+/// This is intended to be used by the server to process files collected by the
+/// agent. Any kind of tampering with the encrypted data is reported as an error
+/// of the [`InvalidData`] kind.
+///
+/// [`InvalidData`]: std::io::ErrorKind::InvalidData
+pub struct Reader<R: Read> {
+    /// Reader to read the encrypted data from.
+    inner: R,
+    /// Cipher used for opening the chunks.
+    cipher: chacha20poly1305::ChaCha20Poly1305,
+    /// Index of the next chunk to open.
+    chunk_index: u64,
+    /// Decrypted plaintext of the current chunk.
+    buf: Vec<u8>,
+    /// Position of the unread plaintext within the current chunk.
+    pos: usize,
+    /// Whether the last chunk has been opened.
+    done: bool,
+}
+
+impl<R: Read> Reader<R> {
+
+    /// Creates a new reader decrypting data with the given `recipient` key.
+    ///
+    /// The file header is read from `inner` immediately and an error is raised
+    /// if the file has not been encrypted to the given key.
+    pub fn new(
+        mut inner: R,
+        recipient: &x25519_dalek::StaticSecret,
+    ) -> std::io::Result<Reader<R>> {
+        let mut magic = [0; MAGIC.len()];
+        inner.read_exact(&mut magic).map_err(invalid_data_on_eof)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("invalid magic bytes"));
+        }
+
+        let mut version = [0; 1];
+        inner.read_exact(&mut version).map_err(invalid_data_on_eof)?;
+        if version[0] != VERSION {
+            return Err(invalid_data("unsupported format version"));
+        }
+
+        let recipient_public = x25519_dalek::PublicKey::from(recipient);
+
+        let mut recipient_fingerprint = [0; 32];
+        inner.read_exact(&mut recipient_fingerprint).map_err(invalid_data_on_eof)?;
+        if recipient_fingerprint != fingerprint(&recipient_public) {
+            return Err(invalid_data("encrypted to a different key"));
+        }
+
+        let mut ephemeral = [0; 32];
+        inner.read_exact(&mut ephemeral).map_err(invalid_data_on_eof)?;
+        let ephemeral = x25519_dalek::PublicKey::from(ephemeral);
+
+        let shared_secret = recipient.diffie_hellman(&ephemeral);
+        if !shared_secret.was_contributory() {
+            return Err(invalid_data("invalid ephemeral key"));
+        }
+
+        Ok(Reader {
+            inner,
+            cipher: cipher(&shared_secret, &ephemeral, &recipient_public),
+            chunk_index: 0,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+
+    /// Reads and opens the next chunk.
+    fn open_chunk(&mut self) -> std::io::Result<()> {
+        let mut len = [0; 4];
+        self.inner.read_exact(&mut len).map_err(invalid_data_on_eof)?;
+        let len = u32::from_le_bytes(len);
+
+        let last = len & LAST_CHUNK_FLAG != 0;
+        let len = (len & !LAST_CHUNK_FLAG) as usize;
+        if !(TAG_SIZE..=CHUNK_SIZE + TAG_SIZE).contains(&len) {
+            return Err(invalid_data("invalid chunk length"));
+        }
+
+        let mut chunk = vec![0; len];
+        self.inner.read_exact(&mut chunk).map_err(invalid_data_on_eof)?;
+
+        let nonce = nonce(self.chunk_index, last);
+        self.buf = self.cipher.decrypt(&nonce.into(), &chunk[..])
+            .map_err(|_| invalid_data("chunk authentication failed"))?;
+        self.pos = 0;
+
+        self.chunk_index += 1;
+
+        if last {
+            if self.inner.read(&mut [0; 1])? != 0 {
+                return Err(invalid_data("data after the last chunk"));
+            }
+            self.done = true;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+
+        let len = std::cmp::min(buf.len(), self.buf.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+/// Computes the fingerprint identifying the given recipient key.
+fn fingerprint(key: &x25519_dalek::PublicKey) -> [u8; 32] {
+    sha2::Sha256::digest(key.as_bytes()).into()
+}
+
+/// Derives the chunk cipher from the secret shared by the two parties.
+fn cipher(
+    shared_secret: &x25519_dalek::SharedSecret,
+    ephemeral: &x25519_dalek::PublicKey,
+    recipient: &x25519_dalek::PublicKey,
+) -> chacha20poly1305::ChaCha20Poly1305 {
+    let mut salt = [0; 64];
+    salt[..32].copy_from_slice(ephemeral.as_bytes());
+    salt[32..].copy_from_slice(recipient.as_bytes());
+
+    let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(&salt), shared_secret.as_bytes());
+
+    let mut key = [0; 32];
+    hkdf.expand(KEY_INFO, &mut key)
+        // This can fail only if the requested key is too long, which is not
+        // the case for a 32-byte key.
+        .expect("failed to derive a key");
+
+    chacha20poly1305::ChaCha20Poly1305::new(&key.into())
+}
+
+/// Constructs a nonce for the chunk with the given index.
+fn nonce(chunk_index: u64, last: bool) -> [u8; 12] {
+    let mut nonce = [0; 12];
+    nonce[..8].copy_from_slice(&chunk_index.to_be_bytes());
+    nonce[11] = u8::from(last);
+
+    nonce
+}
+
+/// Creates an error about malformed encrypted data.
+fn invalid_data(message: &'static str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Reports premature end of the encrypted data as truncation.
+fn invalid_data_on_eof(error: std::io::Error) -> std::io::Error {
+    match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => invalid_data("truncated data"),
+        _ => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn encrypt(data: &[u8], recipient: &x25519_dalek::PublicKey) -> Vec<u8> {
+        let mut writer = Writer::new(Vec::new(), recipient)
+            .unwrap();
+        writer.write_all(data)
+            .unwrap();
+        writer.finish()
+            .unwrap()
+    }
+
+    fn decrypt(
+        data: &[u8],
+        recipient: &x25519_dalek::StaticSecret,
+    ) -> std::io::Result<Vec<u8>> {
+        let mut reader = Reader::new(data, recipient)?;
+
+        let mut result = Vec::new();
+        reader.read_to_end(&mut result)?;
+
+        Ok(result)
+    }
+
+    fn keys() -> (x25519_dalek::StaticSecret, x25519_dalek::PublicKey) {
+        let secret = x25519_dalek::StaticSecret::random();
+        let public = x25519_dalek::PublicKey::from(&secret);
+
+        (secret, public)
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let (secret, public) = keys();
+
+        let encrypted = encrypt(b"", &public);
+        assert_eq!(decrypt(&encrypted, &secret).unwrap(), b"");
+    }
+
+    #[test]
+    fn round_trip_single_chunk() {
+        let (secret, public) = keys();
+
+        let encrypted = encrypt(b"foobar", &public);
+        assert!(!encrypted.windows(6).any(|window| window == b"foobar"));
+        assert_eq!(decrypt(&encrypted, &secret).unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn round_trip_multiple_chunks() {
+        let (secret, public) = keys();
+
+        let data = (0..(3 * CHUNK_SIZE + 1337))
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let encrypted = encrypt(&data, &public);
+        assert_eq!(decrypt(&encrypted, &secret).unwrap(), data);
+    }
+
+    #[test]
+    fn round_trip_chunk_boundary() {
+        let (secret, public) = keys();
+
+        let data = vec![0xf0; 2 * CHUNK_SIZE];
+
+        let encrypted = encrypt(&data, &public);
+        assert_eq!(decrypt(&encrypted, &secret).unwrap(), data);
+    }
+
+    #[test]
+    fn different_ephemeral_keys() {
+        let (_, public) = keys();
+
+        assert_ne!(encrypt(b"foo", &public), encrypt(b"foo", &public));
+    }
+
+    #[test]
+    fn decrypt_wrong_key() {
+        let (_, public) = keys();
+        let (other_secret, _) = keys();
+
+        let encrypted = encrypt(b"foobar", &public);
+
+        let error = decrypt(&encrypted, &other_secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decrypt_tampered_ciphertext() {
+        let (secret, public) = keys();
+
+        let data = vec![0xf0; 2 * CHUNK_SIZE + 42];
+        let encrypted = encrypt(&data, &public);
+
+        // We flip a bit in the middle of the second chunk.
+        let mut tampered = encrypted.clone();
+        tampered[encrypted.len() / 2] ^= 0x01;
+
+        let error = decrypt(&tampered, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decrypt_tampered_ephemeral_key() {
+        let (secret, public) = keys();
+
+        let mut encrypted = encrypt(b"foobar", &public);
+        encrypted[MAGIC.len() + 1 + 32] ^= 0x01;
+
+        let error = decrypt(&encrypted, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decrypt_truncated() {
+        let (secret, public) = keys();
+
+        let data = vec![0xf0; 2 * CHUNK_SIZE + 42];
+        let encrypted = encrypt(&data, &public);
+
+        // Cutting off the last chunk entirely leaves a file that consists of
+        // valid chunks only, but it should still be rejected.
+        let last_chunk_len = 4 + 42 + TAG_SIZE;
+        let truncated = &encrypted[..encrypted.len() - last_chunk_len];
+
+        let error = decrypt(truncated, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let truncated = &encrypted[..encrypted.len() - 1];
+
+        let error = decrypt(truncated, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decrypt_reordered_chunks() {
+        let (secret, public) = keys();
+
+        let data = (0..(3 * CHUNK_SIZE))
+            .map(|i| (i / CHUNK_SIZE) as u8)
+            .collect::<Vec<_>>();
+        let encrypted = encrypt(&data, &public);
+
+        let header_len = MAGIC.len() + 1 + 32 + 32;
+        let chunk_len = 4 + CHUNK_SIZE + TAG_SIZE;
+
+        let mut reordered = encrypted.clone();
+        reordered[header_len..header_len + chunk_len]
+            .copy_from_slice(&encrypted[header_len + chunk_len..header_len + 2 * chunk_len]);
+        reordered[header_len + chunk_len..header_len + 2 * chunk_len]
+            .copy_from_slice(&encrypted[header_len..header_len + chunk_len]);
+
+        let error = decrypt(&reordered, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn decrypt_appended_data() {
+        let (secret, public) = keys();
+
+        let mut encrypted = encrypt(b"foobar", &public);
+        encrypted.extend_from_slice(b"quux");
+
+        let error = decrypt(&encrypted, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn writer_unfinished() {
+        let (secret, public) = keys();
+
+        let mut writer = Writer::new(Vec::new(), &public)
+            .unwrap();
+        writer.write_all(&vec![0xf0; CHUNK_SIZE + 1])
+            .unwrap();
+        let encrypted = writer.inner.clone();
+
+        let error = decrypt(&encrypted, &secret).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -123,22 +123,25 @@ impl WalkDir {
         self
     }
 
-    #[cfg(target_family = "unix")]
-    fn is_same_dev(&self, entry: &Entry) -> bool {
-        self.dev == std::os::unix::fs::MetadataExt::dev(&entry.metadata)
+    /// Skips entries for which the given predicate does not hold.
+    ///
+    /// Unlike [`Iterator::filter`], this also skips all the descendants of the
+    /// rejected directories, without them being even opened.
+    pub fn filter_entry<P>(self, predicate: P) -> FilterEntry<P>
+    where
+        P: FnMut(&Entry) -> bool,
+    {
+        FilterEntry {
+            walk: self,
+            predicate,
+        }
     }
 
-    #[cfg(target_family = "windows")]
-    fn is_same_dev(&self, _entry: &Entry) -> bool {
-        true
-    }
-}
-
-impl std::iter::Iterator for WalkDir {
-
-    type Item = std::io::Result<Entry>;
-
-    fn next(&mut self) -> Option<std::io::Result<Entry>> {
+    /// Yields the next entry for which the given predicate holds.
+    fn next_filtered<P>(&mut self, predicate: &mut P) -> Option<std::io::Result<Entry>>
+    where
+        P: FnMut(&Entry) -> bool,
+    {
         loop {
             if let Some(entry) = self.iter.next() {
                 let entry = match entry {
@@ -146,6 +149,10 @@ impl std::iter::Iterator for WalkDir {
                     Err(error) => return Some(Err(error)),
                 };
 
+                if !predicate(&entry) {
+                    continue;
+                }
+
                 if entry.metadata.is_dir() && self.is_same_dev(&entry) && self.iter.cur_depth < self.max_depth {
                     self.pending_iters.push({
                         std::fs::read_dir(&entry.path).map(|iter| ListDir {
@@ -175,12 +182,391 @@ impl std::iter::Iterator for WalkDir {
             }
         }
     }
+
+    #[cfg(target_family = "unix")]
+    fn is_same_dev(&self, entry: &Entry) -> bool {
+        self.dev == std::os::unix::fs::MetadataExt::dev(&entry.metadata)
+    }
+
+    #[cfg(target_family = "windows")]
+    fn is_same_dev(&self, _entry: &Entry) -> bool {
+        true
+    }
+}
+
+impl std::iter::Iterator for WalkDir {
+
+    type Item = std::io::Result<Entry>;
+
+    fn next(&mut self) -> Option<std::io::Result<Entry>> {
+        self.next_filtered(&mut |_| true)
+    }
+}
+
+/// Iterator over entries in all subdirectories that satisfy a predicate.
+///
+/// The iterator can be constructed with the [`WalkDir::filter_entry`] method.
+pub struct FilterEntry<P> {
+    walk: WalkDir,
+    predicate: P,
+}
+
+impl<P> std::iter::Iterator for FilterEntry<P>
+where
+    P: FnMut(&Entry) -> bool,
+{
+    type Item = std::io::Result<Entry>;
+
+    fn next(&mut self) -> Option<std::io::Result<Entry>> {
+        self.walk.next_filtered(&mut self.predicate)
+    }
+}
+
+/// Splits a path in the Windows device namespace into the device and the rest.
+///
+/// Paths such as `\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3\Windows\foo`
+/// refer to files on devices that do not have a drive letter assigned (most
+/// notably volume shadow copies). For such paths this function returns the path
+/// to the device itself (`\\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3`)
+/// and the path relative to the root of the device (`Windows\foo`).
+///
+/// For all other paths (and on systems other than Windows) `None` is returned.
+pub fn split_device_path(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    #[cfg(target_family = "windows")]
+    {
+        use std::path::{Component, Prefix};
+
+        let mut components = path.components();
+
+        match components.next()? {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Verbatim(name) if name.eq_ignore_ascii_case("GLOBALROOT") => (),
+                _ => return None,
+            },
+            _ => return None,
+        }
+        if components.next()? != Component::RootDir {
+            return None;
+        }
+        match components.next()? {
+            Component::Normal(name) if name.eq_ignore_ascii_case("Device") => (),
+            _ => return None,
+        }
+        let device_name = match components.next()? {
+            Component::Normal(name) => name,
+            _ => return None,
+        };
+
+        let mut device = PathBuf::from("\\\\?\\GLOBALROOT\\Device");
+        device.push(device_name);
+
+        Some((device, components.as_path().to_path_buf()))
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Policy on symlinks encountered in paths of opened files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Symlinks are followed as usual.
+    #[default]
+    Follow,
+    /// Paths with a symlink in any of their components are refused.
+    ///
+    /// The path is resolved component by component, each component relative to
+    /// the already opened parent, so a component swapped with a symlink while
+    /// the path is being opened is refused as well.
+    ///
+    /// On Windows all reparse points (including junctions) count as symlinks.
+    Deny,
+}
+
+/// Opens the file at the given path for reading.
+///
+/// If the policy denies symlinks and there is a symlink anywhere in the path,
+/// an error wrapping [`SymlinkError`] is returned.
+pub fn open_file(path: &Path, policy: SymlinkPolicy) -> std::io::Result<std::fs::File> {
+    match policy {
+        SymlinkPolicy::Follow => std::fs::File::open(path),
+        SymlinkPolicy::Deny => sys::open_file_no_symlinks(path),
+    }
+}
+
+/// Queries metadata of the file at the given path without following it.
+///
+/// This works like the standard [`symlink_metadata`] function, except that if
+/// the policy denies symlinks and there is a symlink anywhere in the path (the
+/// last component included), an error wrapping [`SymlinkError`] is returned.
+///
+/// [`symlink_metadata`]: std::fs::symlink_metadata
+pub fn symlink_metadata(path: &Path, policy: SymlinkPolicy) -> std::io::Result<Metadata> {
+    match policy {
+        SymlinkPolicy::Follow => std::fs::symlink_metadata(path),
+        SymlinkPolicy::Deny => sys::metadata_no_symlinks(path),
+    }
+}
+
+/// An error indicating that a path was refused because of a symlink in it.
+#[derive(Debug)]
+pub struct SymlinkError {
+    /// Path up to and including the symlink component.
+    path: PathBuf,
+}
+
+impl std::fmt::Display for SymlinkError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "symlink not allowed at '{}'", self.path.display())
+    }
+}
+
+impl std::error::Error for SymlinkError {
+}
+
+impl From<SymlinkError> for std::io::Error {
+
+    fn from(error: SymlinkError) -> std::io::Error {
+        std::io::Error::other(error)
+    }
+}
+
+#[cfg(target_family = "unix")]
+mod sys {
+
+    use std::ffi::{CStr, CString, OsStr};
+    use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd};
+    use std::os::unix::ffi::OsStrExt as _;
+    use std::path::{Component, Path, PathBuf};
+
+    use super::SymlinkError;
+
+    /// Flags used to open intermediate directories of a path.
+    ///
+    /// On Linux we can use `O_PATH` so that directories we are allowed only to
+    /// search through (and not to list) can be opened as well.
+    #[cfg(target_os = "linux")]
+    const DIR_FLAGS: libc::c_int = libc::O_PATH | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    #[cfg(not(target_os = "linux"))]
+    const DIR_FLAGS: libc::c_int = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+
+    /// Flags used to open the last component of a path to query its metadata.
+    ///
+    /// On Linux `O_PATH` opens a symlink itself rather than failing and it has
+    /// no side effects (like blocking on FIFOs), so we can refuse symlinks
+    /// after looking at the metadata.
+    #[cfg(target_os = "linux")]
+    const METADATA_FLAGS: libc::c_int = libc::O_PATH | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    #[cfg(not(target_os = "linux"))]
+    const METADATA_FLAGS: libc::c_int = libc::O_RDONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK | libc::O_NOCTTY | libc::O_CLOEXEC;
+
+    pub fn open_file_no_symlinks(path: &Path) -> std::io::Result<std::fs::File> {
+        let (dir, name) = open_parent_no_symlinks(path)?;
+
+        let file = open_at(Some(&dir), &name, libc::O_RDONLY | libc::O_NOFOLLOW | libc::O_CLOEXEC)
+            .map_err(|error| refine_error(error, &dir, &name, path))?;
+
+        Ok(file.into())
+    }
+
+    pub fn metadata_no_symlinks(path: &Path) -> std::io::Result<std::fs::Metadata> {
+        let (dir, name) = open_parent_no_symlinks(path)?;
+
+        let file = open_at(Some(&dir), &name, METADATA_FLAGS)
+            .map_err(|error| refine_error(error, &dir, &name, path))?;
+
+        let metadata = std::fs::File::from(file).metadata()?;
+        if metadata.is_symlink() {
+            return Err(SymlinkError { path: path.to_path_buf() }.into());
+        }
+
+        Ok(metadata)
+    }
+
+    /// Opens the parent directory of the given path without following symlinks.
+    ///
+    /// The name of the last component of the path is returned alongside the
+    /// parent descriptor.
+    fn open_parent_no_symlinks(path: &Path) -> std::io::Result<(OwnedFd, CString)> {
+        if path.as_os_str().is_empty() {
+            return Err(std::io::ErrorKind::NotFound.into());
+        }
+
+        let mut components = path.components().collect::<Vec<_>>();
+        let name = match components.last() {
+            Some(Component::Normal(name)) => {
+                let name = *name;
+                components.pop();
+                name
+            }
+            _ => OsStr::new("."),
+        };
+
+        let root = if path.has_root() { c"/" } else { c"." };
+        let mut dir = open_at(None, root, DIR_FLAGS)?;
+        let mut dir_path = PathBuf::from(OsStr::from_bytes(root.to_bytes()));
+
+        for component in components {
+            let component = match component {
+                Component::Prefix(_) | Component::RootDir | Component::CurDir => continue,
+                Component::ParentDir => OsStr::new(".."),
+                Component::Normal(component) => component,
+            };
+            dir_path.push(component);
+
+            let component = c_string(component)?;
+            dir = open_at(Some(&dir), &component, DIR_FLAGS)
+                .map_err(|error| refine_error(error, &dir, &component, &dir_path))?;
+        }
+
+        Ok((dir, c_string(name)?))
+    }
+
+    /// Opens the given name relative to the given directory (or the current
+    /// working directory if no directory is given).
+    fn open_at(dir: Option<&OwnedFd>, name: &CStr, flags: libc::c_int) -> std::io::Result<OwnedFd> {
+        let dir_fd = dir.map_or(libc::AT_FDCWD, |dir| dir.as_raw_fd());
+
+        // SAFETY: The name is a valid NUL-terminated string and the descriptor
+        // is either valid for the lifetime of `dir` or `AT_FDCWD`. We verify
+        // the result below.
+        let fd = unsafe {
+            libc::openat(dir_fd, name.as_ptr(), flags)
+        };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: The descriptor has just been opened and nothing else owns it.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// Replaces errors of opening a symlink with `O_NOFOLLOW` with [`SymlinkError`].
+    ///
+    /// There is no dedicated error code for such failures (and the ones used
+    /// are also returned for other reasons), so we check whether the refused
+    /// name is really a symlink.
+    fn refine_error(
+        error: std::io::Error,
+        dir: &OwnedFd,
+        name: &CStr,
+        path: &Path,
+    ) -> std::io::Error {
+        if !matches!(error.raw_os_error(), Some(libc::ELOOP | libc::ENOTDIR | libc::EMLINK)) {
+            return error;
+        }
+
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+
+        // SAFETY: The descriptor is valid for the lifetime of `dir`, the name
+        // is a valid NUL-terminated string and the buffer is big enough to hold
+        // the result. We verify the result below.
+        let code = unsafe {
+            libc::fstatat(dir.as_raw_fd(), name.as_ptr(), stat.as_mut_ptr(), libc::AT_SYMLINK_NOFOLLOW)
+        };
+        if code != 0 {
+            return error;
+        }
+
+        // SAFETY: The call succeeded, so the buffer has been initialized.
+        let stat = unsafe { stat.assume_init() };
+        if stat.st_mode & libc::S_IFMT != libc::S_IFLNK {
+            return error;
+        }
+
+        SymlinkError { path: path.to_path_buf() }.into()
+    }
+
+    fn c_string(name: &OsStr) -> std::io::Result<CString> {
+        CString::new(name.as_bytes())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    use std::os::windows::fs::{MetadataExt as _, OpenOptionsExt as _};
+    use std::path::Path;
+
+    use windows_sys::Win32::Storage::FileSystem::*;
+
+    use super::SymlinkError;
+
+    pub fn open_file_no_symlinks(path: &Path) -> std::io::Result<std::fs::File> {
+        let _ancestors = open_ancestors_no_reparse_points(path)?;
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT)
+            .open(path)?;
+        ensure_no_reparse_point(&file.metadata()?, path)?;
+
+        Ok(file)
+    }
+
+    pub fn metadata_no_symlinks(path: &Path) -> std::io::Result<std::fs::Metadata> {
+        let _ancestors = open_ancestors_no_reparse_points(path)?;
+
+        let metadata = open_attributes(path)?.metadata()?;
+        ensure_no_reparse_point(&metadata, path)?;
+
+        Ok(metadata)
+    }
+
+    /// Opens all ancestors of the given path ensuring none is a reparse point.
+    ///
+    /// The returned handles do not allow deleting (and thus renaming) the
+    /// ancestors, so while they are held, none of them can be swapped.
+    fn open_ancestors_no_reparse_points(path: &Path) -> std::io::Result<Vec<std::fs::File>> {
+        let mut ancestors = path.ancestors()
+            .skip(1)
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .collect::<Vec<_>>();
+        ancestors.reverse();
+
+        ancestors.into_iter()
+            .map(|ancestor| {
+                let dir = open_attributes(ancestor)?;
+                ensure_no_reparse_point(&dir.metadata()?, ancestor)?;
+
+                Ok(dir)
+            })
+            .collect()
+    }
+
+    /// Opens the given path (of a file or a directory) only to read attributes.
+    fn open_attributes(path: &Path) -> std::io::Result<std::fs::File> {
+        std::fs::OpenOptions::new()
+            .access_mode(FILE_READ_ATTRIBUTES)
+            .share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+            .open(path)
+    }
+
+    fn ensure_no_reparse_point(metadata: &std::fs::Metadata, path: &Path) -> std::io::Result<()> {
+        if metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0 {
+            return Err(SymlinkError { path: path.to_path_buf() }.into());
+        }
+
+        Ok(())
+    }
 }
 
 /// Iterator over the entries in a directory.
 ///
 /// Unlike the [`ReadDir`] iterator entries, [`ListDir`] entries are guaranteed
 /// to have valid metadata objects attached.
+///
+/// Metadata of each entry is obtained with exactly one system call (`statx` on
+/// Linux and `fstatat` on other Unix-like systems, both relative to the open
+/// directory and not following symlinks) and is then carried along with the
+/// entry, so consumers should never query it again.
 pub struct ListDir {
     cur_depth: u32,
     iter: std::fs::ReadDir,
@@ -197,7 +583,7 @@ impl std::iter::Iterator for ListDir {
             None => return None,
         };
 
-        let metadata = match entry.metadata() {
+        let metadata = match entry_metadata(&entry) {
             Ok(metadata) => metadata,
             Err(error) => return Some(Err(error)),
         };
@@ -209,6 +595,25 @@ impl std::iter::Iterator for ListDir {
     }
 }
 
+/// Queries metadata of the given directory entry.
+///
+/// Metadata of all the listed entries is queried through this function, so
+/// that tests can verify that it happens exactly once per entry.
+fn entry_metadata(entry: &std::fs::DirEntry) -> std::io::Result<Metadata> {
+    #[cfg(test)]
+    ENTRY_METADATA_CALLS.with(|calls| calls.set(calls.get() + 1));
+
+    entry.metadata()
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of metadata queries issued by [`entry_metadata`] on this thread.
+    static ENTRY_METADATA_CALLS: std::cell::Cell<usize> = const {
+        std::cell::Cell::new(0)
+    };
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -444,6 +849,67 @@ mod tests {
         assert_eq!(results[0].metadata.len(), 9);
     }
 
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_metadata_not_followed() {
+        use std::os::unix::fs::MetadataExt as _;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
+        std::os::unix::fs::symlink("foo", tempdir.path().join("bar")).unwrap();
+
+        let results = walk_dir(&tempdir).unwrap()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+
+        for entry in results {
+            let metadata = std::fs::symlink_metadata(&entry.path).unwrap();
+            assert_eq!(entry.metadata.ino(), metadata.ino());
+            assert_eq!(entry.metadata.mode(), metadata.mode());
+            assert_eq!(entry.metadata.len(), metadata.len());
+        }
+    }
+
+    #[test]
+    fn walk_dir_metadata_queried_once_per_entry() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tempdir.path().join("abc").join("def")).unwrap();
+        std::fs::create_dir(tempdir.path().join("ghi")).unwrap();
+        File::create(tempdir.path().join("abc").join("jkl")).unwrap();
+        File::create(tempdir.path().join("abc").join("def").join("mno")).unwrap();
+        File::create(tempdir.path().join("pqr")).unwrap();
+
+        ENTRY_METADATA_CALLS.with(|calls| calls.set(0));
+
+        let results = walk_dir(&tempdir).unwrap()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 6);
+
+        assert_eq!(ENTRY_METADATA_CALLS.with(|calls| calls.get()), 6);
+    }
+
+    #[test]
+    fn walk_dir_filter_entry() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tempdir.path().join("abc").join("def")).unwrap();
+        std::fs::create_dir(tempdir.path().join("ghi")).unwrap();
+        File::create(tempdir.path().join("abc").join("jkl")).unwrap();
+        File::create(tempdir.path().join("ghi").join("mno")).unwrap();
+
+        let mut results = walk_dir(&tempdir).unwrap()
+            .filter_entry(|entry| entry.path != tempdir.path().join("abc"))
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        results.sort_by_key(|entry| entry.path.clone());
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].path, tempdir.path().join("ghi"));
+        assert_eq!(results[1].path, tempdir.path().join("ghi").join("mno"));
+    }
+
     #[test]
     #[should_panic]
     fn walk_dir_with_max_depth_0() {
@@ -512,4 +978,185 @@ mod tests {
         assert!(!paths.contains(&tempdir.join("a").join("b").join("c")));
         assert!(!paths.contains(&tempdir.join("a").join("b").join("c").join("d")));
     }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn split_device_path_shadow_copy() {
+        let path = Path::new("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3\\Windows\\foo.txt");
+
+        let (device, rest) = split_device_path(path)
+            .unwrap();
+        assert_eq!(device, Path::new("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3"));
+        assert_eq!(rest, Path::new("Windows\\foo.txt"));
+
+        // The split parts should yield the original path back.
+        assert_eq!(device.join(rest), path);
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn split_device_path_device_root() {
+        let path = Path::new("\\\\?\\globalroot\\device\\HarddiskVolumeShadowCopy3");
+
+        let (device, rest) = split_device_path(path)
+            .unwrap();
+        assert_eq!(device, Path::new("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3"));
+        assert_eq!(rest, Path::new(""));
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn split_device_path_other() {
+        assert!(split_device_path(Path::new("C:\\Windows\\foo.txt")).is_none());
+        assert!(split_device_path(Path::new("\\\\?\\C:\\Windows\\foo.txt")).is_none());
+        assert!(split_device_path(Path::new("\\\\?\\GLOBALROOT\\foo")).is_none());
+        assert!(split_device_path(Path::new("\\\\server\\share\\foo")).is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn split_device_path_unix() {
+        assert!(split_device_path(Path::new("/dev/sda1/foo")).is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    fn is_symlink_error(error: &std::io::Error) -> bool {
+        error.get_ref().is_some_and(|error| error.is::<SymlinkError>())
+    }
+
+    #[test]
+    fn open_file_deny_regular() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::create_dir(tempdir.join("foo")).unwrap();
+        std::fs::write(tempdir.join("foo").join("bar"), b"content").unwrap();
+
+        let mut file = open_file(&tempdir.join("foo").join("bar"), SymlinkPolicy::Deny)
+            .unwrap();
+
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut file, &mut content).unwrap();
+        assert_eq!(content, b"content");
+    }
+
+    #[test]
+    fn open_file_deny_parent_dir() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::create_dir(tempdir.join("foo")).unwrap();
+        std::fs::write(tempdir.join("bar"), b"content").unwrap();
+
+        let path = tempdir.join("foo").join("..").join("bar");
+        assert!(open_file(&path, SymlinkPolicy::Deny).is_ok());
+    }
+
+    #[test]
+    fn open_file_deny_non_existent() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        let error = open_file(&tempdir.join("foo"), SymlinkPolicy::Deny)
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn open_file_symlinked_dir() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::create_dir(tempdir.join("dir")).unwrap();
+        std::fs::write(tempdir.join("dir").join("file"), b"content").unwrap();
+        std::os::unix::fs::symlink(tempdir.join("dir"), tempdir.join("link")).unwrap();
+
+        let path = tempdir.join("link").join("file");
+        assert!(open_file(&path, SymlinkPolicy::Follow).is_ok());
+
+        let error = open_file(&path, SymlinkPolicy::Deny)
+            .unwrap_err();
+        assert!(is_symlink_error(&error));
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn open_file_symlinked_file() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::write(tempdir.join("file"), b"content").unwrap();
+        std::os::unix::fs::symlink(tempdir.join("file"), tempdir.join("link")).unwrap();
+
+        let path = tempdir.join("link");
+        assert!(open_file(&path, SymlinkPolicy::Follow).is_ok());
+
+        let error = open_file(&path, SymlinkPolicy::Deny)
+            .unwrap_err();
+        assert!(is_symlink_error(&error));
+    }
+
+    #[test]
+    fn symlink_metadata_deny_regular() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::create_dir(tempdir.join("foo")).unwrap();
+        std::fs::write(tempdir.join("foo").join("bar"), b"content").unwrap();
+
+        let metadata = symlink_metadata(&tempdir.join("foo").join("bar"), SymlinkPolicy::Deny)
+            .unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(metadata.len(), 7);
+
+        let metadata = symlink_metadata(&tempdir.join("foo"), SymlinkPolicy::Deny)
+            .unwrap();
+        assert!(metadata.is_dir());
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn symlink_metadata_symlinked_dir() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::create_dir(tempdir.join("dir")).unwrap();
+        std::fs::write(tempdir.join("dir").join("file"), b"content").unwrap();
+        std::os::unix::fs::symlink(tempdir.join("dir"), tempdir.join("link")).unwrap();
+
+        let path = tempdir.join("link").join("file");
+
+        let metadata = symlink_metadata(&path, SymlinkPolicy::Follow)
+            .unwrap();
+        assert!(metadata.is_file());
+
+        let error = symlink_metadata(&path, SymlinkPolicy::Deny)
+            .unwrap_err();
+        assert!(is_symlink_error(&error));
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn symlink_metadata_symlink() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path().canonicalize().unwrap();
+
+        std::fs::write(tempdir.join("file"), b"content").unwrap();
+        std::os::unix::fs::symlink(tempdir.join("file"), tempdir.join("link")).unwrap();
+
+        let path = tempdir.join("link");
+
+        let metadata = symlink_metadata(&path, SymlinkPolicy::Follow)
+            .unwrap();
+        assert!(metadata.is_symlink());
+
+        let error = symlink_metadata(&path, SymlinkPolicy::Deny)
+            .unwrap_err();
+        assert!(is_symlink_error(&error));
+    }
 }
@@ -0,0 +1,308 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Expansion of glob patterns into paths to existing files.
+//!
+//! Patterns expanded within a single request often share prefixes (e.g. all of
+//! them start with `C:\Users\*\AppData`), so directory listings are cached for
+//! the lifetime of the [`Expander`] and shared directories are listed once.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Default limit on the total number of cached directory entries.
+pub const DEFAULT_MAX_CACHED_ENTRIES: usize = 64 * 1024;
+
+/// Entry of a directory listing.
+pub struct Entry {
+    /// Name of the entry.
+    pub name: OsString,
+    /// Type of the entry (not following symlinks).
+    pub file_type: std::fs::FileType,
+}
+
+/// Expander of glob patterns with a cache of directory listings.
+///
+/// The cache is never invalidated, so an expander should not outlive the
+/// request it was created for.
+pub struct Expander<L> {
+    /// Function used to list directories.
+    lister: L,
+    /// Cached listings of directories.
+    listings: HashMap<PathBuf, Rc<[Entry]>>,
+    /// Total number of entries in the cached listings.
+    cached_entries: usize,
+    /// Limit on the total number of entries in the cached listings.
+    max_cached_entries: usize,
+}
+
+impl<L> Expander<L>
+where
+    L: FnMut(&Path) -> std::io::Result<Vec<Entry>>,
+{
+    /// Creates a new expander listing directories with the given function.
+    ///
+    /// Listings are cached as long as the total number of cached entries does
+    /// not exceed `max_cached_entries`. Listings that do not fit are not cached
+    /// and are obtained anew every time they are needed.
+    pub fn with_lister(lister: L, max_cached_entries: usize) -> Self {
+        Expander {
+            lister,
+            listings: HashMap::new(),
+            cached_entries: 0,
+            max_cached_entries,
+        }
+    }
+
+    /// Expands the glob pattern into paths to existing files.
+    ///
+    /// Wildcards (`*` and `?`) can be used in any component of the pattern but
+    /// they never match path separators.
+    pub fn expand(&mut self, pattern: &Path) -> Vec<PathBuf> {
+        use std::path::Component;
+
+        let mut paths = vec![PathBuf::new()];
+
+        let mut components = pattern.components().peekable();
+        while let Some(component) = components.next() {
+            let name = match component {
+                Component::Normal(name) => name,
+                _ => {
+                    for path in &mut paths {
+                        path.push(component);
+                    }
+                    continue;
+                }
+            };
+
+            let name_regex = match name.to_str() {
+                Some(name) if name.contains(['*', '?']) => regex(name),
+                _ => {
+                    for path in &mut paths {
+                        path.push(name);
+                    }
+                    continue;
+                }
+            };
+
+            let is_last = components.peek().is_none();
+
+            let mut next_paths = Vec::new();
+            for path in paths {
+                let dir_path = if path.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    path.as_path()
+                };
+
+                // Directories that do not exist or that we cannot list simply
+                // do not match anything.
+                let Ok(entries) = self.list(dir_path) else {
+                    continue;
+                };
+
+                for entry in entries.iter() {
+                    // Regular files cannot be descended into, so there is no
+                    // point in matching them against non-final components.
+                    if !is_last && entry.file_type.is_file() {
+                        continue;
+                    }
+
+                    if entry.name.to_str().is_some_and(|name| name_regex.is_match(name)) {
+                        next_paths.push(path.join(&entry.name));
+                    }
+                }
+            }
+            next_paths.sort();
+
+            paths = next_paths;
+        }
+
+        paths.retain(|path| path.is_file());
+        paths
+    }
+
+    /// Lists the directory at the given path (using the cache if possible).
+    fn list(&mut self, path: &Path) -> std::io::Result<Rc<[Entry]>> {
+        if let Some(entries) = self.listings.get(path) {
+            return Ok(entries.clone());
+        }
+
+        let entries = Rc::<[Entry]>::from((self.lister)(path)?);
+
+        // Listings that do not fit are returned as they are and the directory
+        // is going to be listed again if needed.
+        if self.cached_entries + entries.len() <= self.max_cached_entries {
+            self.cached_entries += entries.len();
+            self.listings.insert(path.to_path_buf(), entries.clone());
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Expands the glob patterns into paths that the session is allowed to access.
+///
+/// Directories that the policy of the session denies access to are not listed
+/// and paths that it denies access to are skipped. The returned paths are
+/// sorted and deduplicated.
+pub fn expand_allowed<S>(session: &S, patterns: &[PathBuf]) -> Vec<PathBuf>
+where
+    S: crate::session::Session,
+{
+    let check_path = |path: &Path| match session.check_path(path) {
+        Ok(()) => true,
+        Err(error) => {
+            log::warn!("skipping '{}': {error}", path.display());
+            false
+        }
+    };
+
+    // The expander lives only for the duration of this call, so there is no
+    // risk of listings cached by it getting stale.
+    let mut expander = Expander::with_lister(|path: &Path| {
+        if !check_path(path) {
+            return Err(std::io::ErrorKind::PermissionDenied.into());
+        }
+        list_dir(path)
+    }, DEFAULT_MAX_CACHED_ENTRIES);
+
+    let mut paths = patterns.iter()
+        .flat_map(|pattern| expander.expand(pattern))
+        .filter(|path| check_path(path))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+
+    paths
+}
+
+/// Lists entries of the directory at the given path.
+fn list_dir(path: &Path) -> std::io::Result<Vec<Entry>> {
+    std::fs::read_dir(path)?
+        .map(|entry| {
+            let entry = entry?;
+            Ok(Entry {
+                name: entry.file_name(),
+                file_type: entry.file_type()?,
+            })
+        })
+        .collect()
+}
+
+/// Converts a single component of a glob pattern to an equivalent regex.
+fn regex(pattern: &str) -> regex::Regex {
+    let mut regex = String::from("^");
+
+    for char in pattern.chars() {
+        match char {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            _ => regex.push_str(&regex::escape(char.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push('$');
+
+    // The regex consists of escaped literals and wildcards only, so it is
+    // always valid.
+    regex::Regex::new(&regex)
+        .expect("invalid glob regex")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn expand_no_wildcards() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"")
+            .unwrap();
+
+        let mut expander = Expander::with_lister(list_dir, DEFAULT_MAX_CACHED_ENTRIES);
+        assert_eq!(expander.expand(&path), vec![path]);
+        assert!(expander.expand(&tempdir.path().join("bar")).is_empty());
+    }
+
+    #[test]
+    fn expand_overlapping_lists_once() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        for user in ["alice", "bob"] {
+            let dir = tempdir.path().join(user).join("AppData");
+            std::fs::create_dir_all(&dir)
+                .unwrap();
+            std::fs::write(dir.join("foo.bin"), b"")
+                .unwrap();
+            std::fs::write(dir.join("bar.txt"), b"")
+                .unwrap();
+        }
+
+        let mut listed = Vec::new();
+        let mut expander = Expander::with_lister(|path: &Path| {
+            listed.push(path.to_path_buf());
+            list_dir(path)
+        }, DEFAULT_MAX_CACHED_ENTRIES);
+
+        let bin_paths = expander.expand(&tempdir.path().join("*").join("AppData").join("*.bin"));
+        let txt_paths = expander.expand(&tempdir.path().join("*").join("AppData").join("*.txt"));
+        drop(expander);
+
+        assert_eq!(bin_paths, vec! {
+            tempdir.path().join("alice").join("AppData").join("foo.bin"),
+            tempdir.path().join("bob").join("AppData").join("foo.bin"),
+        });
+        assert_eq!(txt_paths, vec! {
+            tempdir.path().join("alice").join("AppData").join("bar.txt"),
+            tempdir.path().join("bob").join("AppData").join("bar.txt"),
+        });
+
+        let count = |path: PathBuf| listed.iter().filter(|listed| **listed == path).count();
+        assert_eq!(count(tempdir.path().to_path_buf()), 1);
+        assert_eq!(count(tempdir.path().join("alice").join("AppData")), 1);
+        assert_eq!(count(tempdir.path().join("bob").join("AppData")), 1);
+    }
+
+    #[test]
+    fn expand_over_bound_not_cached() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo.bin"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar.bin"), b"")
+            .unwrap();
+
+        let mut listed = 0;
+        let mut expander = Expander::with_lister(|path: &Path| {
+            listed += 1;
+            list_dir(path)
+        }, 1);
+
+        let pattern = tempdir.path().join("*.bin");
+        assert_eq!(expander.expand(&pattern).len(), 2);
+        assert_eq!(expander.expand(&pattern).len(), 2);
+        drop(expander);
+
+        assert_eq!(listed, 2);
+    }
+
+    #[test]
+    fn regex_ok() {
+        let regex = regex("f?o*.bin");
+
+        assert!(regex.is_match("foo.bin"));
+        assert!(regex.is_match("fxobar.bin"));
+        assert!(!regex.is_match("foo.binx"));
+        assert!(!regex.is_match("fo.bin"));
+    }
+}
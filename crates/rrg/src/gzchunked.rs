@@ -171,13 +171,20 @@ impl Default for EncodeOpts {
 /// It implements the `Iterator` trait, lazily polling the underlying iterator
 /// over Protocol Buffers messages as more parts are needed.
 ///
+/// Messages are serialized directly into the compressor, so no more than one
+/// uncompressed message is buffered at a time. A part is finished as soon as
+/// its compressed size crosses the configured limit, so every part contains a
+/// whole number of messages and can be decoded on its own. The underlying
+/// iterator is never polled for messages that do not end up in the part being
+/// yielded, so callers can count the messages that each part contains.
+///
 /// Instances of this type can be constructed using the [`encode_with_opts`] or
 /// [`encode`] function.
 ///
 /// [`encode`]: fn.encode.html
 /// [`encode_with_opts`]: fn.encode_with_opts.html
 pub struct Encode<I> {
-    chunked: crate::chunked::Encode<I>,
+    iter: I,
     opts: EncodeOpts,
 }
 
@@ -189,27 +196,38 @@ where
     /// Creates a new encoder instance with the specified options.
     fn with_opts(iter: I, opts: EncodeOpts) -> Encode<I> {
         Encode {
-            chunked: crate::chunked::encode(iter),
-            opts: opts,
+            iter,
+            opts,
         }
     }
 
     /// Obtains the next part of the output file (if available).
     fn next_part(&mut self) -> std::io::Result<Option<Vec<u8>>> {
-        use crate::io::copy_until;
-
         let compression = self.opts.compression.0;
         let part_size = self.opts.part_size;
 
-        let mut encoder = flate2::write::GzEncoder::new(vec!(), compression);
-        let len = copy_until(&mut self.chunked, &mut encoder, |_, encoder| {
-            encoder.get_ref().len() as u64 >= part_size
-        })?;
+        let mut encoder: Option<flate2::write::GzEncoder<Vec<u8>>> = None;
+        loop {
+            if let Some(ref encoder) = encoder {
+                if encoder.get_ref().len() as u64 >= part_size {
+                    break;
+                }
+            }
+
+            let msg = match self.iter.next() {
+                Some(msg) => msg,
+                None => break,
+            };
+
+            let encoder = encoder.get_or_insert_with(|| {
+                flate2::write::GzEncoder::new(vec!(), compression)
+            });
+            crate::chunked::write(encoder, &msg)?;
+        }
 
-        if len == 0 {
-            Ok(None)
-        } else {
-            Ok(Some(encoder.finish()?))
+        match encoder {
+            Some(encoder) => Ok(Some(encoder.finish()?)),
+            None => Ok(None),
         }
     }
 }
@@ -337,6 +355,65 @@ mod tests {
         assert!(iter.all(|item| item == sample));
     }
 
+    #[test]
+    fn test_encode_parts_decode_independently() {
+        let samples = (0..32 * 1024)
+            .map(|_| bytes(rand::random::<[u8; 32]>()))
+            .collect::<Vec<_>>();
+
+        let opts = EncodeOpts {
+            compression: Compression::default(),
+            part_size: 4 * 1024,
+        };
+
+        let chunks = encode_with_opts(samples.iter().cloned(), opts)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert!(chunks.len() > 1);
+
+        let mut items = vec!();
+        for chunk in &chunks {
+            items.extend(decode::<_, BytesValue>(std::iter::once(&chunk[..]))
+                .map(Result::unwrap));
+        }
+        assert_eq!(items, samples);
+    }
+
+    #[test]
+    fn test_encode_many_tiny_items_bounded() {
+        const COUNT: usize = 1_000_000;
+        const PART_SIZE: u64 = 64 * 1024;
+
+        let pulled = std::cell::Cell::new(0);
+        let items = (0..COUNT)
+            .map(|idx| string(format!("{:x}", idx % 4096)))
+            .inspect(|_| pulled.set(pulled.get() + 1));
+
+        let opts = EncodeOpts {
+            compression: Compression::default(),
+            part_size: PART_SIZE,
+        };
+
+        let mut count = 0;
+        for chunk in encode_with_opts(items, opts) {
+            let chunk = chunk.unwrap();
+
+            // Compressors buffer some data internally, so parts can overshoot
+            // the limit but only by a small margin (and never by a whole batch
+            // of uncompressed messages).
+            assert!(chunk.len() as u64 <= 2 * PART_SIZE);
+
+            count += decode::<_, StringValue>(std::iter::once(&chunk[..]))
+                .map(Result::unwrap)
+                .count();
+
+            // No messages should have been pulled ahead of the yielded parts.
+            assert_eq!(pulled.get(), count);
+        }
+
+        assert_eq!(count, COUNT);
+    }
+
     #[test]
     fn test_encode_and_decode_with_no_compression() {
         let sample = bytes(rand::random::<[u8; 32]>());
@@ -0,0 +1,370 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for reading definitions of launchd jobs.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Paths to directories with system-wide launchd job definitions.
+///
+/// See the [documentation][1] for more details.
+///
+/// [1]: https://developer.apple.com/library/archive/documentation/MacOSX/Conceptual/BPSystemStartup/Chapters/CreatingLaunchdJobs.html
+const SYSTEM_DIR_PATHS: &[&str] = &[
+    "/Library/LaunchDaemons",
+    "/Library/LaunchAgents",
+    "/System/Library/LaunchDaemons",
+    "/System/Library/LaunchAgents",
+];
+
+/// Path to the directory with home directories of users.
+pub const USERS_PATH: &str = "/Users";
+
+/// Path to the launchd database of jobs disabled with `launchctl disable`.
+const DISABLED_PATH: &str = "/var/db/com.apple.xpc.launchd/disabled.plist";
+
+/// Definition of a launchd job.
+#[derive(Debug)]
+pub struct Job {
+    /// Label uniquely identifying the job.
+    pub label: String,
+    /// Name of the user as which the job is executed (if known).
+    pub user: Option<String>,
+    /// Whether the job is enabled.
+    pub enabled: bool,
+    /// Raw job definition (the whole property list).
+    pub definition: plist::Value,
+    /// Path to the file the job was defined in (if loaded from one).
+    pub path: Option<PathBuf>,
+}
+
+impl Job {
+
+    /// Parses a job from the given launchd job definition.
+    ///
+    /// `None` is returned if the definition does not have a label. See the
+    /// [documentation][1] for the list of all supported keys.
+    ///
+    /// [1]: https://keith.github.io/xcode-man-pages/launchd.plist.5.html
+    pub fn parse(definition: plist::Value) -> Option<Job> {
+        let label = String::from(definition.get("Label")?.as_str()?);
+
+        let user = definition.get("UserName")
+            .and_then(plist::Value::as_str)
+            .map(String::from);
+
+        let enabled = !definition.get("Disabled")
+            .and_then(plist::Value::as_bool)
+            .unwrap_or(false);
+
+        Some(Job {
+            label,
+            user,
+            enabled,
+            definition,
+            path: None,
+        })
+    }
+
+    /// Returns the path to the executable of the job.
+    ///
+    /// If both keys are specified, `Program` is the path to the executable and
+    /// `ProgramArguments` is its `argv` (including `argv[0]`). Otherwise, the
+    /// first of `ProgramArguments` is the executable.
+    pub fn program(&self) -> Option<&str> {
+        match self.definition.get("Program").and_then(plist::Value::as_str) {
+            Some(program) => Some(program),
+            None => self.program_args().into_iter().next(),
+        }
+    }
+
+    /// Returns the arguments (excluding `argv[0]`) passed to the executable.
+    pub fn args(&self) -> Vec<&str> {
+        self.program_args().into_iter()
+            .skip(1)
+            .collect()
+    }
+
+    /// Returns the command line that the job executes.
+    pub fn command(&self) -> String {
+        self.program().into_iter()
+            .chain(self.args())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns the description of when the job is started.
+    ///
+    /// If the job has many triggers, they are separated with semicolons.
+    pub fn schedule(&self) -> String {
+        let job = &self.definition;
+        let mut schedule = Vec::new();
+
+        if let Some(interval) = job.get("StartInterval").and_then(plist::Value::as_integer) {
+            schedule.push(format!("StartInterval={interval}"));
+        }
+
+        // Calendar intervals can be specified either as a single dictionary or
+        // as an array of them.
+        let calendar_intervals = match job.get("StartCalendarInterval") {
+            Some(plist::Value::Array(intervals)) => intervals.iter().collect(),
+            Some(interval) => vec![interval],
+            None => vec![],
+        };
+        for interval in calendar_intervals {
+            let Some(interval) = interval.as_dict() else {
+                continue;
+            };
+
+            let fields = interval.iter()
+                .filter_map(|(key, value)| Some(format!("{key}={}", value.as_integer()?)))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            schedule.push(format!("StartCalendarInterval={{{fields}}}"));
+        }
+
+        for key in ["WatchPaths", "QueueDirectories"] {
+            let Some(paths) = job.get(key).and_then(plist::Value::as_array) else {
+                continue;
+            };
+
+            let paths = paths.iter()
+                .filter_map(plist::Value::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            schedule.push(format!("{key}={paths}"));
+        }
+
+        if job.get("RunAtLoad").and_then(plist::Value::as_bool) == Some(true) {
+            schedule.push(String::from("RunAtLoad"));
+        }
+
+        // `KeepAlive` can be either a boolean or a dictionary with conditions.
+        match job.get("KeepAlive") {
+            Some(plist::Value::Bool(true)) | Some(plist::Value::Dict(_)) => {
+                schedule.push(String::from("KeepAlive"));
+            }
+            _ => (),
+        }
+
+        if job.get("StartOnMount").and_then(plist::Value::as_bool) == Some(true) {
+            schedule.push(String::from("StartOnMount"));
+        }
+
+        schedule.join("; ")
+    }
+
+    /// Returns all the `ProgramArguments` strings of the job.
+    fn program_args(&self) -> Vec<&str> {
+        self.definition.get("ProgramArguments")
+            .and_then(plist::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(plist::Value::as_str)
+            .collect()
+    }
+}
+
+/// Returns all launchd jobs defined on the system.
+pub fn jobs() -> Vec<Job> {
+    let disabled = disabled_overrides();
+
+    let mut jobs = Vec::new();
+
+    for dir_path in SYSTEM_DIR_PATHS {
+        jobs.extend(dir_jobs(Path::new(dir_path), None, &disabled));
+    }
+
+    let users = match std::fs::read_dir(USERS_PATH) {
+        Ok(users) => users,
+        Err(error) => {
+            log::warn!("failed to list users: {error}");
+            return jobs;
+        }
+    };
+
+    for entry in users {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read user entry: {error}");
+                continue;
+            }
+        };
+
+        let Some(user) = path.file_name() else {
+            continue;
+        };
+        let user = user.to_string_lossy();
+
+        let agents_path = path.join("Library").join("LaunchAgents");
+        jobs.extend(dir_jobs(&agents_path, Some(&user), &disabled));
+    }
+
+    jobs
+}
+
+/// Returns launchd jobs defined in the given directory.
+///
+/// `user` is used for jobs that do not specify the user explicitly.
+fn dir_jobs(
+    dir_path: &Path,
+    user: Option<&str>,
+    disabled: &HashMap<String, bool>,
+) -> Vec<Job> {
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list jobs in '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut jobs = Vec::new();
+
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read job entry: {error}");
+                continue;
+            }
+        };
+
+        if path.extension() != Some(std::ffi::OsStr::new("plist")) {
+            continue;
+        }
+
+        let definition = match plist::parse_file(&path) {
+            Ok(definition) => definition,
+            Err(error) => {
+                log::warn!("failed to parse job '{}': {error}", path.display());
+                continue;
+            }
+        };
+
+        let mut job = match Job::parse(definition) {
+            Some(job) => job,
+            None => {
+                log::warn!("invalid job '{}'", path.display());
+                continue;
+            }
+        };
+
+        if job.user.is_none() {
+            job.user = user.map(String::from);
+        }
+        job.path = Some(path);
+        if let Some(is_disabled) = disabled.get(&job.label) {
+            job.enabled = !is_disabled;
+        }
+
+        jobs.push(job);
+    }
+
+    jobs
+}
+
+/// Returns jobs explicitly enabled or disabled using `launchctl`.
+///
+/// These take precedence over the `Disabled` key of job definitions. The map
+/// values say whether the job with the given label is disabled.
+fn disabled_overrides() -> HashMap<String, bool> {
+    let overrides = match plist::parse_file(DISABLED_PATH) {
+        Ok(overrides) => overrides,
+        Err(error) => {
+            log::warn!("failed to parse disabled jobs: {error}");
+            return HashMap::new();
+        }
+    };
+
+    overrides.as_dict().into_iter()
+        .flatten()
+        .filter_map(|(label, disabled)| Some((label.clone(), disabled.as_bool()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn job_parse_program() {
+        let job = Job::parse(plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.agent</string>
+	<key>Program</key>
+	<string>/opt/example/agent</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>agent</string>
+		<string>--daemon</string>
+	</array>
+	<key>Disabled</key>
+	<true/>
+</dict>
+</plist>
+"#).unwrap()).unwrap();
+
+        assert_eq!(job.label, "com.example.agent");
+        assert_eq!(job.program(), Some("/opt/example/agent"));
+        assert_eq!(job.args(), vec!["--daemon"]);
+        assert_eq!(job.command(), "/opt/example/agent --daemon");
+        assert_eq!(job.user, None);
+        assert!(!job.enabled);
+    }
+
+    #[test]
+    fn job_parse_program_arguments() {
+        let job = Job::parse(plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.example.daemon</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>/usr/local/bin/daemon</string>
+		<string>--foo</string>
+		<string>--bar</string>
+	</array>
+	<key>UserName</key>
+	<string>nobody</string>
+</dict>
+</plist>
+"#).unwrap()).unwrap();
+
+        assert_eq!(job.program(), Some("/usr/local/bin/daemon"));
+        assert_eq!(job.args(), vec!["--foo", "--bar"]);
+        assert_eq!(job.user.as_deref(), Some("nobody"));
+        assert!(job.enabled);
+    }
+
+    #[test]
+    fn job_parse_no_label() {
+        let definition = plist::parse(br#"
+<plist version="1.0">
+<dict>
+	<key>Program</key>
+	<string>/bin/true</string>
+</dict>
+</plist>
+"#).unwrap();
+
+        assert!(Job::parse(definition).is_none());
+    }
+
+    #[test]
+    fn jobs_not_empty() {
+        // There are always some system daemons defined on macOS.
+        assert!(!jobs().is_empty());
+    }
+}
@@ -11,20 +11,144 @@ pub mod io;
 pub mod log;
 pub mod args;
 pub mod session;
+pub mod state;
+pub mod temp;
+pub mod envelope;
 
+mod admin;
 mod blob;
+mod config;
+mod crash;
 mod filter;
+mod mode;
+mod offline;
+mod os;
+mod path;
+mod policy;
+mod redact;
+mod reload;
+mod replay;
 mod request;
+mod resource;
 mod response;
+mod schedule;
+mod service;
+mod shutdown;
+mod transport;
+mod version;
+
+#[cfg(all(target_os = "linux", any(
+    feature = "action-list_linux_persistence",
+    feature = "action-list_scheduled_tasks",
+    feature = "action-list_services",
+)))]
+mod systemd;
+
+#[cfg(all(target_os = "macos", any(
+    feature = "action-list_macos_persistence",
+    feature = "action-list_scheduled_tasks",
+    feature = "action-list_services",
+)))]
+mod launchd;
+
+#[cfg(all(target_os = "linux", any(
+    feature = "action-list_containers",
+    feature = "action-list_processes",
+)))]
+mod container;
+
+#[cfg(all(target_os = "linux", any(
+    feature = "action-get_login_history",
+    feature = "action-list_users",
+    feature = "action-list_utmp_users",
+)))]
+mod utmp;
+
+#[cfg(any(
+    all(target_os = "linux", feature = "action-list_scheduled_tasks"),
+    all(target_os = "macos", feature = "action-list_macos_persistence"),
+))]
+mod cron;
 
 pub mod startup; // TODO(@panhania): Hide this module.
 
 // TODO(@panhania): Consider moving these to a separate submodule.
-#[cfg(feature = "action-get_filesystem_timeline")]
+#[cfg(any(
+    feature = "action-get_filesystem_timeline",
+    feature = "action-get_macos_logs",
+    feature = "action-get_system_logs",
+    feature = "action-get_windows_event_logs",
+))]
 pub mod chunked;
-#[cfg(feature = "action-get_filesystem_timeline")]
+#[cfg(any(
+    feature = "action-get_filesystem_timeline",
+    feature = "action-get_macos_logs",
+    feature = "action-get_system_logs",
+    feature = "action-get_windows_event_logs",
+))]
 pub mod gzchunked;
 
+#[cfg(any(
+    feature = "action-scan_files_yara",
+    feature = "action-scan_process_memory_yara",
+))]
+mod yara;
+
+#[cfg(any(
+    feature = "action-get_file_metadata",
+    feature = "action-scan_files_yara",
+))]
+mod mmap;
+
+#[cfg(any(
+    feature = "action-scan_files_yara",
+    feature = "action-sweep_indicators",
+))]
+mod glob;
+
+#[cfg(any(
+    feature = "action-get_file_metadata",
+    feature = "action-get_filesystem_timeline",
+))]
+mod clock;
+
+#[cfg(any(
+    feature = "action-get_ntfs_mft",
+    feature = "action-get_raw_device",
+))]
+// Raw volumes can be opened by `get_ntfs_mft` only on Windows.
+#[cfg_attr(not(any(
+    target_family = "windows",
+    feature = "action-get_raw_device",
+)), allow(dead_code))]
+mod ntfs;
+
+#[cfg(feature = "action-get_filesystem_timeline")]
+mod magic;
+
+#[cfg(any(
+    feature = "action-get_file_contents",
+    feature = "action-get_filesystem_timeline",
+    feature = "action-scan_files_yara",
+))]
+mod dry_run;
+
+#[cfg(any(
+    feature = "action-execute_signed_command",
+    feature = "action-query_osquery",
+))]
+mod run_as;
+
+#[cfg(any(
+    feature = "action-execute_signed_command",
+    feature = "action-rotate_command_verification_key",
+    feature = "action-update_schedule",
+    feature = "action-set_agent_mode",
+))]
+// Signed commands only need verification, rotations are a separate action.
+#[cfg_attr(not(feature = "action-rotate_command_verification_key"), allow(dead_code))]
+mod signing;
+
 pub use request::{ParseRequestError, Request, RequestId};
 pub use response::{LogBuilder, ResponseBuilder, ResponseId, Sink};
 
@@ -33,7 +157,11 @@ pub use response::{LogBuilder, ResponseBuilder, ResponseId, Sink};
 /// This function should be called only once (at the very beginning of the
 /// process lifetime).
 pub fn init(args: &crate::args::Args) {
-    log::init(args)
+    log::init(args);
+    resource::init(args);
+    crash::init(args);
+    shutdown::init(args);
+    mode::init(args);
 }
 
 /// Enters the agent's main loop and waits for messages.
@@ -48,11 +176,271 @@ pub fn init(args: &crate::args::Args) {
 /// (e.g. the Fleetspeak connection has been broken). All non-critical errors
 /// are going to be handled carefully, notifying the server about the failure if
 /// appropriate.
-pub fn listen(args: &crate::args::Args) {
+///
+/// If the agent follows a schedule (see the [`schedule`] module), requests of
+/// the schedule entries are dispatched in between the requests from the server.
+pub fn listen(args: crate::args::Args) {
+    // Requests are handled with a snapshot of the current configuration since
+    // it can be reloaded at any time (see the [`reload`] module).
+    let args = reload::Handle::new(args);
+    reload::init(&args);
+    admin::init(&args);
+
+    // Requests pass through the gate so that they are not dispatched if the
+    // agent has been paused or is being drained (see the [`mode`] module).
+    let mut gate = mode::Gate::new(mode::current());
+
+    // The main loop ticks whenever it picks a request and is considered idle
+    // while waiting for one (see the [`service`] module).
+    service::ready();
+
+    let Some(schedule) = schedule::load(&args.load()) else {
+        loop {
+            let args = args.load();
+
+            // Once shutdown is requested we do not receive any more requests.
+            if shutdown::requested() {
+                refuse_queued(&args, &mut gate);
+                shutdown::wait();
+            }
+
+            service::idle();
+            let request = Request::receive(args.heartbeat_rate);
+            service::tick();
+
+            handle(&args, &mut gate, request);
+        }
+    };
+
+    let mut scheduler = schedule::Scheduler::new(
+        schedule::SystemClock,
+        schedule,
+        args.load().state_dir.as_deref(),
+    );
+
+    // Receiving requests from the server blocks, so it is done on a separate
+    // thread and the main one waits for requests with a timeout until the next
+    // schedule entry becomes due. The receiver thread waits until the previous
+    // request is handled before it receives the next one, so that heartbeats
+    // are sent only while idling, as if the requests were received directly.
+    let (ready_sender, ready_receiver) = std::sync::mpsc::channel::<()>();
+    let (request_sender, request_receiver) = std::sync::mpsc::sync_channel(0);
+
+    let heartbeat_rate = args.load().heartbeat_rate;
+    std::thread::spawn(move || {
+        while ready_receiver.recv().is_ok() {
+            if request_sender.send(Request::receive_message(heartbeat_rate)).is_err() {
+                break;
+            }
+        }
+    });
+
+    ready_sender.send(())
+        .expect("request receiver thread terminated");
+    // Whether the receiver thread has been asked to receive the next request.
+    let mut receiving = true;
+
+    loop {
+        if shutdown::requested() {
+            refuse_queued(&args.load(), &mut gate);
+
+            // The receiver thread might be already waiting for the next request
+            // in which case we refuse the one it picks (if any).
+            if receiving {
+                if let Ok(message) = request_receiver.recv() {
+                    handle(&args.load(), &mut gate, Request::from_message(message));
+                }
+            }
+
+            shutdown::wait();
+        }
+
+        service::idle();
+        let request = match scheduler.next_run_time() {
+            Some(time) => {
+                let timeout = time.duration_since(std::time::SystemTime::now())
+                    .unwrap_or(std::time::Duration::ZERO);
+                request_receiver.recv_timeout(timeout)
+            }
+            None => request_receiver.recv()
+                .map_err(std::sync::mpsc::RecvTimeoutError::from),
+        };
+        service::tick();
+
+        use std::sync::mpsc::RecvTimeoutError::*;
+        match request {
+            Ok(message) => {
+                handle(&args.load(), &mut gate, Request::from_message(message));
+
+                // Once shutdown is requested we do not receive any more
+                // requests.
+                receiving = !shutdown::requested();
+                if receiving {
+                    ready_sender.send(())
+                        .expect("request receiver thread terminated");
+                }
+            }
+            Err(Timeout) => {
+                for request in scheduler.poll() {
+                    handle(&args.load(), &mut gate, Ok(request));
+                }
+            }
+            // The receiver thread terminates only if it panicked receiving the
+            // request (e.g. because the Fleetspeak connection was broken).
+            Err(Disconnected) => panic!("request receiver thread terminated"),
+        }
+
+        if schedule::take_reload_request() {
+            scheduler.reload(schedule::load(&args.load()).unwrap_or_default());
+        }
+    }
+}
+
+/// Refuses requests queued by the gate once shutdown is requested.
+fn refuse_queued(args: &crate::args::Args, gate: &mut mode::Gate) {
+    // Dispatching refuses requests while shutting down (see [`dispatch`]).
+    for request in gate.take_queued() {
+        dispatch(args, Ok(request));
+    }
+    gate.publish();
+}
+
+/// Handles a single request received in the agent's main loop.
+///
+/// The request is dispatched only if the gate admits it. Once it is handled,
+/// requests queued by the gate are dispatched as well if the request resumed
+/// the agent.
+fn handle(
+    args: &crate::args::Args,
+    gate: &mut mode::Gate,
+    request: Result<Request, ParseRequestError>,
+) {
+    // Requests received after shutdown was requested are refused regardless of
+    // the mode (so that the server does not wait for them to be handled).
+    if shutdown::requested() {
+        dispatch(args, request);
+        return;
+    }
+
+    match request {
+        Ok(request) => admit(args, gate.admit(request)),
+        // Invalid requests are dispatched in all modes, so that the server is
+        // notified about the error.
+        Err(error) => dispatch(args, Err(error)),
+    }
+
     loop {
-        let request = Request::receive(args.heartbeat_rate);
-        session::FleetspeakSession::dispatch(args, request);
+        if let Some(mode) = mode::take_change_request() {
+            gate.set_mode(mode);
+        }
+        gate.publish();
+
+        match gate.next() {
+            Some(admission) => admit(args, admission),
+            None => break,
+        }
+    }
+}
+
+/// Handles a decision of the gate about a request.
+fn admit(args: &crate::args::Args, admission: mode::Admission) {
+    use mode::Admission::*;
+
+    match admission {
+        Dispatch(request) => dispatch(args, Ok(request)),
+        Refuse(request_id, error) => {
+            session::FleetspeakSession::refuse(request_id, error.into());
+        }
+        Queued | Skipped => (),
+    }
+}
+
+/// Dispatches a single request admitted by the gate.
+fn dispatch(
+    args: &crate::args::Args,
+    request: Result<Request, ParseRequestError>,
+) {
+    // Once shutdown is requested we no longer accept new requests and refuse
+    // them explicitly (invalid requests are reported as usual).
+    if let (Ok(request), Err(error)) = (&request, session::check_shutdown()) {
+        session::FleetspeakSession::refuse(request.id(), error);
+        return;
     }
+
+    session::FleetspeakSession::dispatch(args, request);
+
+    // We check for pending restarts only once the request has been fully
+    // handled so that the server receives all the responses and the final
+    // status. Fleetspeak will start the agent again once we exit.
+    if RESTART_REQUESTED.load(std::sync::atomic::Ordering::SeqCst) {
+        ::log::info!("restarting the agent");
+        std::process::exit(0);
+    }
+}
+
+/// Executes requests from the given file without connecting to the server.
+///
+/// Results of the requests are written to files in the output directory (as
+/// specified in the arguments, the current one by default) and a manifest with
+/// digests of all the written files is printed to the standard output.
+pub fn offline(
+    args: &crate::args::Args,
+    requests_path: &std::path::Path,
+) -> std::io::Result<()> {
+    let output_dir = match &args.offline_output {
+        Some(output_dir) => output_dir.clone(),
+        None => std::path::PathBuf::from("."),
+    };
+
+    // The whole offline execution is treated as a single request so that the
+    // manifest is printed even if the agent is requested to shut down.
+    let _in_flight = shutdown::InFlightGuard::new();
+
+    for entry in offline::run(args, requests_path, &output_dir)? {
+        println!("{entry}");
+    }
+
+    Ok(())
+}
+
+/// Executes the request recorded in the given file again.
+///
+/// The report of the replayed execution is printed to the standard output and
+/// `false` is returned if the execution diverged from the recording (see the
+/// [`replay`] module for more details).
+pub fn replay(
+    args: &crate::args::Args,
+    recording_path: &std::path::Path,
+) -> std::io::Result<bool> {
+    let _in_flight = shutdown::InFlightGuard::new();
+
+    let report = replay::run(args, recording_path)?;
+    println!("{report}");
+
+    Ok(report.is_ok())
+}
+
+/// Whether the agent should restart after handling the current request.
+static RESTART_REQUESTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// Requests the agent to restart after handling the current request.
+#[cfg_attr(not(feature = "action-update_agent"), allow(dead_code))]
+pub(crate) fn request_restart() {
+    RESTART_REQUESTED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Runs the given agent main function under the service manager of the system.
+///
+/// On Windows, if the agent has been started by the Service Control Manager,
+/// the calling thread becomes the service control dispatcher and the function
+/// is run on the service thread. Otherwise, the function is simply called. The
+/// service manager is notified that the agent is ready once it [`listen`]s.
+pub fn service<F>(main: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    service::run(main)
 }
 
 /// Sends a system message with startup information to the GRR server.
@@ -63,3 +451,31 @@ pub fn listen(args: &crate::args::Args) {
 pub fn startup() {
     startup::startup()
 }
+
+/// Sends reports about previous crashes of the agent to the GRR server.
+///
+/// This function should be called only once at the beginning of RRG's process
+/// lifetime (after the [`startup`] information is sent). Crash reports are sent
+/// only if the crash spool directory is specified in the arguments.
+pub fn crashes(args: &crate::args::Args) {
+    crash::deliver(args)
+}
+
+/// Sends responses that could not be delivered before the previous shutdown.
+///
+/// This function should be called only once at the beginning of RRG's process
+/// lifetime (after the [`startup`] information is sent). Responses are spooled
+/// only if the crash spool directory is specified in the arguments.
+pub fn undelivered(args: &crate::args::Args) {
+    shutdown::deliver(args)
+}
+
+/// Sends results of the agent self-checks to the GRR server.
+///
+/// This function should be called only once at the beginning of RRG's process
+/// lifetime (after the [`startup`] information is sent), so that the server
+/// learns about problems with the agent before any flow is scheduled.
+#[cfg(feature = "action-get_agent_health")]
+pub fn health(args: &crate::args::Args) {
+    action::get_agent_health::startup(args)
+}
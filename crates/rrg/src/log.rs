@@ -3,18 +3,25 @@
 // Use of this source code is governed by an MIT-style license that can be found
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use log::Log;
 use lazy_static::lazy_static;
 
+/// Level at which messages are written to the standard output and files.
+static VERBOSITY: AtomicUsize = AtomicUsize::new(log::LevelFilter::Info as usize);
+
 /// Initializes the logging submodule.
 ///
 /// This function should be called only once at the beginning of the process
 /// startup.
 pub fn init(args: &crate::args::Args) {
+    set_verbosity(args.verbosity);
+
     let mut logger = Logger::default();
     if args.log_to_stdout {
         let stdout = std::io::stdout();
-        logger.stdout_logger = Some(WriterLogger::new(stdout, args.verbosity));
+        logger.stdout_logger = Some(WriterLogger::new(stdout));
     }
     if let Some(ref path) = args.log_to_file {
         let file = std::fs::OpenOptions::new()
@@ -23,7 +30,7 @@ pub fn init(args: &crate::args::Args) {
             .open(path)
             .expect("failed to open the log file");
 
-        logger.file_logger = Some(WriterLogger::new(file, args.verbosity));
+        logger.file_logger = Some(WriterLogger::new(file));
     }
 
     log::set_boxed_logger(Box::new(logger))
@@ -31,7 +38,8 @@ pub fn init(args: &crate::args::Args) {
 
     // Note that individual loggers have their own logging levels:
     //
-    //   * The standard output logger and file loggers use `args.verbosity`.
+    //   * The standard output logger and file loggers use `args.verbosity`
+    //     (which can be changed later with `set_verbosity`).
     //   * The response logger uses the level specified in the request.
     //
     // If we were to set the global max level to `args.verbosity` it would make
@@ -41,6 +49,21 @@ pub fn init(args: &crate::args::Args) {
     log::set_max_level(log::LevelFilter::Trace);
 }
 
+/// Returns the level at which messages are written to the standard output and
+/// files.
+pub fn verbosity() -> log::LevelFilter {
+    let verbosity = VERBOSITY.load(Ordering::Relaxed);
+    log::LevelFilter::iter()
+        .find(|level| *level as usize == verbosity)
+        .unwrap_or(log::LevelFilter::Trace)
+}
+
+/// Sets the level at which messages are written to the standard output and
+/// files.
+pub fn set_verbosity(verbosity: log::LevelFilter) {
+    VERBOSITY.store(verbosity as usize, Ordering::Relaxed);
+}
+
 /// [`Log`] implementation that aggregates all supported loggers.
 struct Logger {
     /// Logger instance that writes messages to standard output.
@@ -109,17 +132,14 @@ impl Log for Logger {
 struct WriterLogger<W: std::io::Write + Send + Sync> {
     /// Stream to write the log messages to.
     writer: std::sync::Mutex<W>,
-    /// Minimum level at which messages are written to the stream.
-    log_level: log::LevelFilter,
 }
 
 impl<W: std::io::Write + Send + Sync> WriterLogger<W> {
 
     /// Create a new logger for the given writable stream.
-    fn new(writer: W, log_level: log::LevelFilter) -> WriterLogger<W> {
+    fn new(writer: W) -> WriterLogger<W> {
         WriterLogger {
             writer: std::sync::Mutex::new(writer),
-            log_level,
         }
     }
 }
@@ -127,7 +147,7 @@ impl<W: std::io::Write + Send + Sync> WriterLogger<W> {
 impl<W: std::io::Write + Send + Sync> Log for WriterLogger<W> {
 
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        metadata.level() <= self.log_level
+        metadata.level() <= verbosity()
     }
 
     fn log(&self, record: &log::Record) {
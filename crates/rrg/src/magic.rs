@@ -0,0 +1,176 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Detection of file types based on their leading bytes ("magic numbers").
+//!
+//! Detection needs only a short header of the file (see [`HEADER_LEN`]), so it
+//! can be done for large numbers of files without reading them in full.
+
+/// Number of leading bytes of a file used for type detection.
+pub const HEADER_LEN: usize = 512;
+
+/// Signatures of known file types with tags they are reported with.
+///
+/// Signatures are tried in order, so more specific ones have to come first.
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x7fELF", "elf"),
+    (0, b"MZ", "pe"),
+    (0, b"\xfe\xed\xfa\xce", "mach-o"),
+    (0, b"\xfe\xed\xfa\xcf", "mach-o"),
+    (0, b"\xce\xfa\xed\xfe", "mach-o"),
+    (0, b"\xcf\xfa\xed\xfe", "mach-o"),
+    (0, b"\xca\xfe\xba\xbe", "mach-o-universal"),
+    (0, b"#!", "script"),
+    (0, b"PK\x03\x04", "zip"),
+    (0, b"PK\x05\x06", "zip"),
+    (0, b"\x1f\x8b", "gzip"),
+    (0, b"BZh", "bzip2"),
+    (0, b"\xfd7zXZ\x00", "xz"),
+    (0, b"\x28\xb5\x2f\xfd", "zstd"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7z"),
+    (0, b"Rar!\x1a\x07", "rar"),
+    (257, b"ustar", "tar"),
+    (0, b"%PDF-", "pdf"),
+    (0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "ole"),
+    (0, b"SQLite format 3\x00", "sqlite"),
+    (0, b"regf", "registry-hive"),
+    (0, b"ElfFile\x00", "evtx"),
+    (0, b"\x89PNG\r\n\x1a\n", "png"),
+    (0, b"\xff\xd8\xff", "jpeg"),
+    (0, b"GIF87a", "gif"),
+    (0, b"GIF89a", "gif"),
+    (0, b"bplist00", "plist-binary"),
+    (0, b"<?xml", "xml"),
+];
+
+/// Detects the type of a file from its leading bytes.
+///
+/// `header` should hold the first [`HEADER_LEN`] bytes of the file (or the
+/// whole file if it is shorter). `None` is returned if the type is unknown.
+pub fn detect(header: &[u8]) -> Option<&'static str> {
+    SIGNATURES.iter()
+        .find(|(offset, signature, _)| {
+            header.get(*offset..*offset + signature.len()) == Some(signature)
+        })
+        .map(|(_, _, tag)| *tag)
+}
+
+/// Reads the leading bytes of the file at the given path used for detection.
+///
+/// The file is opened without following it if it is a symlink (e.g. swapped in
+/// after the caller decided the path is a regular file) and, where the system
+/// allows it, without updating its access time.
+pub fn read_header(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Read as _;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+
+        // Non-blocking mode prevents hanging on a FIFO swapped in for the file
+        // and does not affect reads of regular files.
+        options.custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK);
+    }
+    #[cfg(target_family = "windows")]
+    {
+        use std::os::windows::fs::OpenOptionsExt as _;
+
+        options.custom_flags(windows_sys::Win32::Storage::FileSystem::FILE_FLAG_OPEN_REPARSE_POINT);
+    }
+
+    let file = options.open(path)?;
+    #[cfg(target_os = "linux")]
+    set_noatime(&file);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    file.take(HEADER_LEN as u64).read_to_end(&mut header)?;
+
+    Ok(header)
+}
+
+/// Disables access time updates for reads of the given file (if permitted).
+///
+/// This is allowed only for owners of the file (or with the `CAP_FOWNER`
+/// capability), so failures are silently ignored.
+#[cfg(target_os = "linux")]
+fn set_noatime(file: &std::fs::File) {
+    use std::os::fd::AsRawFd as _;
+
+    let fd = file.as_raw_fd();
+
+    // SAFETY: The descriptor is valid for the lifetime of the file and the
+    // `F_GETFL` and `F_SETFL` commands do not touch any memory.
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags != -1 {
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NOATIME);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn detect_elf() {
+        assert_eq!(detect(b"\x7fELF\x02\x01\x01\x00"), Some("elf"));
+    }
+
+    #[test]
+    fn detect_tar() {
+        let mut header = vec![0; HEADER_LEN];
+        header[257..262].copy_from_slice(b"ustar");
+
+        assert_eq!(detect(&header), Some("tar"));
+    }
+
+    #[test]
+    fn detect_unknown() {
+        assert_eq!(detect(b"lorem ipsum dolor sit amet"), None);
+        assert_eq!(detect(b""), None);
+    }
+
+    #[test]
+    fn read_header_short_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"#!/bin/sh\n")
+            .unwrap();
+
+        let header = read_header(&tempdir.path().join("foo"))
+            .unwrap();
+        assert_eq!(header, b"#!/bin/sh\n");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn read_header_symlink() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"#!/bin/sh\n")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.path().join("foo"), tempdir.path().join("bar"))
+            .unwrap();
+
+        assert!(read_header(&tempdir.path().join("bar")).is_err());
+    }
+
+    #[test]
+    fn read_header_long_file() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), vec![0xAB; 4 * HEADER_LEN])
+            .unwrap();
+
+        let header = read_header(&tempdir.path().join("foo"))
+            .unwrap();
+        assert_eq!(header.len(), HEADER_LEN);
+    }
+}
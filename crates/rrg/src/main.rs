@@ -3,18 +3,75 @@
 // Use of this source code is governed by an MIT-style license that can be found
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
-use log::info;
+use log::{error, info};
 
 fn main() {
     let args = rrg::args::from_env_args();
     rrg::init(&args);
 
-    info!("sending Fleetspeak startup information");
-    fleetspeak::startup(env!("CARGO_PKG_VERSION"));
+    // The lock has to be held for the whole lifetime of the process.
+    let _state_lock = match &args.state_dir {
+        Some(state_dir) => match rrg::state::lock(state_dir) {
+            Ok(lock) => {
+                // Scratch directories of requests are removed once handled, so
+                // these that are left must have been abandoned by a crash.
+                rrg::temp::purge(state_dir);
+                Some(lock)
+            }
+            Err(error @ rrg::state::LockError::Locked { .. }) => {
+                error!("another agent instance is running: {error}");
+                std::process::exit(rrg::state::LOCKED_EXIT_CODE);
+            }
+            Err(error) => {
+                error!("{error}");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
 
-    info!("sending RRG startup information");
-    rrg::startup();
+    if let Some(recording_path) = &args.replay {
+        info!("replaying request recorded in '{}'", recording_path.display());
+        match rrg::replay(&args, recording_path) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(error) => {
+                eprintln!("failed to replay request: {error}");
+                std::process::exit(1);
+            }
+        }
+    }
 
-    info!("listening for messages");
-    rrg::listen(&args);
+    if let Some(requests_path) = &args.offline {
+        info!("executing requests from '{}' offline", requests_path.display());
+        if let Err(error) = rrg::offline(&args, requests_path) {
+            eprintln!("failed to execute requests offline: {error}");
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    rrg::service(move || {
+        info!("sending Fleetspeak startup information");
+        fleetspeak::startup(env!("CARGO_PKG_VERSION"));
+
+        info!("sending RRG startup information");
+        rrg::startup();
+
+        info!("sending RRG crash reports");
+        rrg::crashes(&args);
+
+        info!("sending RRG responses undelivered before shutdown");
+        rrg::undelivered(&args);
+
+        #[cfg(feature = "action-get_agent_health")]
+        {
+            info!("sending RRG health information");
+            rrg::health(&args);
+        }
+
+        info!("listening for messages");
+        rrg::listen(args);
+    });
 }
@@ -0,0 +1,307 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for reading big files through memory mappings.
+//!
+//! Memory-mapping files avoids copying their contents through userspace
+//! buffers which speeds up hashing and scanning of multi-gigabyte files. It is
+//! used only for files at least as big as the configured threshold (so it is
+//! disabled by default) and never for files on network filesystems that can
+//! change under us at any time.
+//!
+//! Accessing mapped pages past the end of a file (e.g. because it has been
+//! truncated in the meantime) raises `SIGBUS`. Thus, files read in chunks are
+//! mapped in windows bounded by the length of the file checked just before the
+//! window is mapped (and never past the length it had when reading started),
+//! so a file that shrinks is treated as truncated at its new length. A file
+//! that shrinks while a window is being read can still fault, which is one
+//! more reason why mapping is opt-in.
+
+use std::path::Path;
+
+use log::warn;
+
+/// Maximum length of a single window of a file mapped when reading in chunks.
+const WINDOW_LEN: u64 = 64 * 1024 * 1024; // 64 MiB.
+
+/// Length of the buffer used when reading files without mapping them.
+const BUF_LEN: usize = 64 * 1024; // 64 KiB.
+
+/// Determines whether the file of the given length should be memory-mapped.
+pub fn should_map(path: &Path, len: u64, threshold: Option<u64>) -> bool {
+    let Some(threshold) = threshold else {
+        return false;
+    };
+
+    // Empty files cannot be mapped at all.
+    len > 0 && len >= threshold && !is_on_network_fs(path)
+}
+
+/// Maps the first `len` bytes of the given file into memory.
+#[cfg(feature = "action-scan_files_yara")]
+pub fn map(file: &std::fs::File, len: u64) -> std::io::Result<memmap2::Mmap> {
+    map_window(file, 0, len)
+}
+
+/// Calls the given function with consecutive chunks of the file contents.
+///
+/// The file is memory-mapped (in windows) if it is at least `threshold` bytes
+/// big and otherwise (or if mapping fails) it is read through a buffer. The
+/// file is expected to be positioned at its beginning and reading ends once
+/// `len` bytes are read or the end of the file is reached.
+pub fn read_chunks<F>(
+    file: &std::fs::File,
+    path: &Path,
+    len: u64,
+    threshold: Option<u64>,
+    mut f: F,
+) -> std::io::Result<()>
+where
+    F: FnMut(&[u8]),
+{
+    use std::io::{Read as _, Seek as _};
+
+    let mut offset = 0;
+
+    if should_map(path, len, threshold) {
+        while offset < len {
+            // The file could have been truncated since the last window, so we
+            // bound the window by its current length.
+            let cur_len = std::cmp::min(file.metadata()?.len(), len);
+            if cur_len <= offset {
+                warn!("'{}' truncated while being read", path.display());
+                return Ok(());
+            }
+            let window_len = std::cmp::min(cur_len - offset, WINDOW_LEN);
+
+            match map_window(file, offset, window_len) {
+                Ok(window) => f(&window),
+                Err(error) => {
+                    warn!("failed to map '{}', falling back to reading: {error}", path.display());
+                    break;
+                }
+            }
+
+            offset += window_len;
+        }
+
+        if offset >= len {
+            return Ok(());
+        }
+
+        (&*file).seek(std::io::SeekFrom::Start(offset))?;
+    }
+
+    let mut buf = vec![0; BUF_LEN];
+    let mut file = file.take(len - offset);
+    loop {
+        let buf_len = match file.read(&mut buf[..]) {
+            Ok(0) => return Ok(()),
+            Ok(buf_len) => buf_len,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+
+        f(&buf[..buf_len]);
+    }
+}
+
+/// Maps `len` bytes of the given file starting at `offset` into memory.
+fn map_window(file: &std::fs::File, offset: u64, len: u64) -> std::io::Result<memmap2::Mmap> {
+    let len = usize::try_from(len)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "mapping too big"))?;
+
+    // SAFETY: Mapped memory is valid only as long as the underlying file is
+    // not modified. We bound the mapping by the file length and never write
+    // through it, but others can still modify or truncate the file. This is
+    // the reason why mapping is used only for files above the configured size
+    // threshold and only if the user opted into it.
+    let map = unsafe {
+        memmap2::MmapOptions::new()
+            .offset(offset)
+            .len(len)
+            .map(file)?
+    };
+
+    #[cfg(target_family = "unix")]
+    if let Err(error) = map.advise(memmap2::Advice::Sequential) {
+        warn!("failed to advise sequential access to mapped file: {error}");
+    }
+
+    Ok(map)
+}
+
+/// Determines whether the given path lies on a network filesystem.
+///
+/// If this cannot be determined, the path is assumed to be on a network
+/// filesystem to be on the safe side.
+fn is_on_network_fs(path: &Path) -> bool {
+    #[cfg(target_family = "windows")]
+    {
+        use std::path::{Component, Prefix};
+
+        if let Some(Component::Prefix(prefix)) = path.components().next() {
+            if matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..)) {
+                return true;
+            }
+        }
+    }
+
+    let mounts = match ospect::fs::mounts() {
+        Ok(mounts) => mounts,
+        Err(error) => {
+            warn!("failed to list mounts: {error}");
+            return true;
+        }
+    };
+
+    // Canonical paths on Windows are in the verbatim form which would never
+    // match mount points, so we canonicalize on Unix only.
+    #[cfg(target_family = "unix")]
+    let path = std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf());
+
+    mounts
+        .filter_map(Result::ok)
+        .filter(|mount| path.starts_with(&mount.path))
+        .max_by_key(|mount| mount.path.components().count())
+        .is_some_and(|mount| mount.is_network())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Length of a big sparse file used in tests (more than a single window).
+    const SPARSE_LEN: u64 = 2 * WINDOW_LEN + 1337;
+
+    /// Creates a big sparse file with some data scattered across it.
+    fn sparse_file(path: &Path) -> std::fs::File {
+        use std::io::{Seek as _, Write as _};
+
+        let mut file = std::fs::File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .unwrap();
+        file.set_len(SPARSE_LEN)
+            .unwrap();
+
+        for offset in [0, 4096, WINDOW_LEN - 3, WINDOW_LEN + 42, SPARSE_LEN - 7] {
+            file.seek(std::io::SeekFrom::Start(offset))
+                .unwrap();
+            file.write_all(b"foobar")
+                .unwrap();
+        }
+
+        file.seek(std::io::SeekFrom::Start(0))
+            .unwrap();
+
+        file
+    }
+
+    fn sha256(file: &std::fs::File, path: &Path, threshold: Option<u64>) -> ([u8; 32], u64) {
+        use sha2::Digest as _;
+
+        let mut hasher = sha2::Sha256::new();
+        let mut len = 0;
+        read_chunks(file, path, SPARSE_LEN, threshold, |chunk| {
+            hasher.update(chunk);
+            len += chunk.len() as u64;
+        }).unwrap();
+
+        (hasher.finalize().into(), len)
+    }
+
+    #[test]
+    fn should_map_no_threshold() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(!should_map(tempdir.path(), 1024, None));
+    }
+
+    #[test]
+    fn should_map_below_threshold() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(!should_map(tempdir.path(), 1023, Some(1024)));
+    }
+
+    #[test]
+    fn should_map_empty() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(!should_map(tempdir.path(), 0, Some(0)));
+    }
+
+    #[test]
+    fn read_chunks_mapped_and_buffered_equal() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("sparse");
+
+        let file = sparse_file(&path);
+
+        // Tests are not expected to run on network filesystems, but even if
+        // they did, we would just compare the buffered path with itself.
+        let (mapped_sha256, mapped_len) = sha256(&file, &path, Some(0));
+
+        use std::io::Seek as _;
+        (&file).seek(std::io::SeekFrom::Start(0))
+            .unwrap();
+
+        let (buffered_sha256, buffered_len) = sha256(&file, &path, None);
+
+        assert_eq!(mapped_len, SPARSE_LEN);
+        assert_eq!(buffered_len, SPARSE_LEN);
+        assert_eq!(mapped_sha256, buffered_sha256);
+    }
+
+    #[test]
+    fn read_chunks_mapped_truncated() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("sparse");
+
+        let file = sparse_file(&path);
+
+        let mut len = 0;
+        read_chunks(&file, &path, SPARSE_LEN, Some(0), |chunk| {
+            len += chunk.len() as u64;
+
+            // We truncate the file in the middle of the next window. Mapping
+            // a window past the file end would crash the test with `SIGBUS`.
+            if len == WINDOW_LEN {
+                file.set_len(WINDOW_LEN + 1024)
+                    .unwrap();
+            }
+        }).unwrap();
+
+        assert_eq!(len, WINDOW_LEN + 1024);
+    }
+
+    #[cfg(feature = "action-scan_files_yara")]
+    #[test]
+    fn map_contents() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("foo");
+
+        std::fs::write(&path, b"foobarbaz")
+            .unwrap();
+
+        let file = std::fs::File::open(&path)
+            .unwrap();
+
+        let map = map(&file, 6)
+            .unwrap();
+        assert_eq!(&map[..], b"foobar");
+    }
+}
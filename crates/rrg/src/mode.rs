@@ -0,0 +1,509 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Modes in which the agent handles requests.
+//!
+//! During incident containment (or when the agent causes trouble on a busy
+//! machine) the server can switch the agent to a mode in which it does not
+//! start any new work:
+//!
+//! * [`Mode::Paused`] queues incoming requests until the agent is back to
+//!   normal (requests of schedule entries are skipped since they are issued
+//!   again once due).
+//! * [`Mode::Drain`] refuses incoming requests.
+//!
+//! Requests are handled one by one, so the request in flight at the time the
+//! mode changes is always finished. Changing the mode and checking the agent
+//! health are allowed in all modes, the agent also keeps heartbeating while
+//! idling as usual.
+//!
+//! The mode is decided by the [`Gate`] that the main loop consults before it
+//! dispatches a request. Sessions only request the mode to be changed (see
+//! [`request_change`]) which the main loop applies once the request is handled.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// Name of the file with the persisted mode within the state directory.
+const MODE_FILE_NAME: &str = "agent_mode";
+
+/// Maximum number of requests queued while the agent is paused.
+///
+/// Requests beyond the limit are refused, so that a long pause cannot make the
+/// agent run out of memory.
+const MAX_QUEUED_REQUESTS: usize = 256;
+
+/// Mode in which the agent handles requests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Requests are handled as usual.
+    #[default]
+    Normal,
+    /// Requests are queued until the agent is back to normal.
+    Paused,
+    /// Requests are refused until the agent is back to normal.
+    Drain,
+}
+
+/// Mode currently enforced by the main loop (for reporting).
+static CURRENT_MODE: AtomicU8 = AtomicU8::new(Mode::Normal as u8);
+
+/// Number of requests currently queued by the main loop (for reporting).
+static QUEUED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Mode that the main loop should switch to, [`NO_CHANGE`] if none.
+static REQUESTED_MODE: AtomicU8 = AtomicU8::new(NO_CHANGE);
+
+/// Value of [`REQUESTED_MODE`] meaning that no change was requested.
+const NO_CHANGE: u8 = u8::MAX;
+
+impl Mode {
+
+    /// Returns the name of the mode as persisted in the state directory.
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Paused => "paused",
+            Mode::Drain => "drain",
+        }
+    }
+
+    /// Returns the mode with the given name as persisted in the state directory.
+    fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "normal" => Some(Mode::Normal),
+            "paused" => Some(Mode::Paused),
+            "drain" => Some(Mode::Drain),
+            _ => None,
+        }
+    }
+
+    /// Returns the mode encoded as the given number (see [`Mode::as_u8`]).
+    fn from_u8(value: u8) -> Option<Mode> {
+        match value {
+            0 => Some(Mode::Normal),
+            1 => Some(Mode::Paused),
+            2 => Some(Mode::Drain),
+            _ => None,
+        }
+    }
+
+    /// Returns the mode as a number that can be stored in an atomic.
+    fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl std::fmt::Display for Mode {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+impl From<Mode> for rrg_proto::set_agent_mode::Mode {
+
+    fn from(mode: Mode) -> rrg_proto::set_agent_mode::Mode {
+        match mode {
+            Mode::Normal => Self::NORMAL,
+            Mode::Paused => Self::PAUSED,
+            Mode::Drain => Self::DRAIN,
+        }
+    }
+}
+
+impl From<rrg_proto::set_agent_mode::Mode> for Mode {
+
+    fn from(proto: rrg_proto::set_agent_mode::Mode) -> Mode {
+        use rrg_proto::set_agent_mode::Mode::*;
+
+        match proto {
+            NORMAL => Mode::Normal,
+            PAUSED => Mode::Paused,
+            DRAIN => Mode::Drain,
+        }
+    }
+}
+
+/// Restores the mode persisted in the state directory.
+///
+/// This function should be called only once, at the beginning of the process
+/// lifetime (so that the mode is reported correctly even before the main loop
+/// starts).
+pub fn init(args: &crate::args::Args) {
+    let mode = load(args);
+    if mode != Mode::Normal {
+        log::warn!("agent starting in {mode} mode");
+    }
+
+    CURRENT_MODE.store(mode.as_u8(), Ordering::Relaxed);
+}
+
+/// Returns the path to the file with the persisted mode (if any).
+fn path(args: &crate::args::Args) -> Option<std::path::PathBuf> {
+    args.state_dir.as_ref()
+        .map(|state_dir| state_dir.join(MODE_FILE_NAME))
+}
+
+/// Loads the mode persisted in the state directory.
+///
+/// If there is no state directory or no mode has been persisted yet (or it is
+/// corrupted), the agent starts in the normal mode.
+pub fn load(args: &crate::args::Args) -> Mode {
+    let Some(path) = self::path(args) else {
+        return Mode::Normal;
+    };
+
+    let name = match std::fs::read_to_string(&path) {
+        Ok(name) => name,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Mode::Normal;
+        }
+        Err(error) => {
+            log::error!("failed to read mode from '{}': {error}", path.display());
+            return Mode::Normal;
+        }
+    };
+
+    match Mode::from_name(name.trim()) {
+        Some(mode) => mode,
+        None => {
+            log::error!("invalid mode in '{}': {name:?}", path.display());
+            Mode::Normal
+        }
+    }
+}
+
+/// Persists the given mode in the state directory.
+///
+/// Nothing is persisted if the agent has no state directory (in which case the
+/// mode does not survive restarts).
+pub fn persist(args: &crate::args::Args, mode: Mode) -> std::io::Result<()> {
+    let Some(path) = self::path(args) else {
+        log::warn!("no state directory to persist the mode in");
+        return Ok(());
+    };
+
+    crate::state::write_atomic(&path, mode.name().as_bytes())
+}
+
+/// Requests the main loop to switch to the given mode.
+///
+/// The mode is switched once the current request is handled.
+#[cfg_attr(not(feature = "action-set_agent_mode"), allow(dead_code))]
+pub fn request_change(mode: Mode) {
+    REQUESTED_MODE.store(mode.as_u8(), Ordering::SeqCst);
+}
+
+/// Returns the mode that the main loop should switch to and clears the request.
+pub fn take_change_request() -> Option<Mode> {
+    Mode::from_u8(REQUESTED_MODE.swap(NO_CHANGE, Ordering::SeqCst))
+}
+
+/// Returns the mode in which the agent currently handles requests.
+pub fn current() -> Mode {
+    Mode::from_u8(CURRENT_MODE.load(Ordering::Relaxed))
+        .unwrap_or_default()
+}
+
+/// Returns the number of requests queued because the agent is paused.
+pub fn queued_count() -> usize {
+    QUEUED_COUNT.load(Ordering::Relaxed)
+}
+
+/// Checks whether the given action is allowed in all modes.
+fn is_exempt(action: crate::request::Action) -> bool {
+    use crate::request::Action::*;
+
+    matches!(action, SetAgentMode | GetAgentHealth)
+}
+
+/// Gate deciding what happens to requests in the current mode.
+pub struct Gate {
+    /// Mode that the gate enforces.
+    mode: Mode,
+    /// Requests received while paused (in the order of arrival).
+    queue: VecDeque<crate::Request>,
+}
+
+/// Decision of the [`Gate`] about a request.
+pub enum Admission {
+    /// The request should be dispatched right away.
+    Dispatch(crate::Request),
+    /// The request has been queued.
+    Queued,
+    /// The request has been dropped (it will be issued again by the schedule).
+    Skipped,
+    /// The request should be refused with the given error.
+    Refuse(crate::RequestId, RefusedError),
+}
+
+impl Gate {
+
+    /// Creates a new gate enforcing the given mode.
+    pub fn new(mode: Mode) -> Gate {
+        Gate {
+            mode,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Returns the mode that the gate enforces.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the number of queued requests.
+    pub fn queued_count(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Switches the gate to the given mode.
+    ///
+    /// Requests queued before the switch are returned by [`Gate::next`] once
+    /// the gate is not paused anymore.
+    pub fn set_mode(&mut self, mode: Mode) {
+        if mode != self.mode {
+            log::info!("switching from {} to {mode} mode", self.mode);
+        }
+        self.mode = mode;
+    }
+
+    /// Decides what should happen to the given request.
+    pub fn admit(&mut self, request: crate::Request) -> Admission {
+        if is_exempt(request.action()) {
+            return Admission::Dispatch(request);
+        }
+
+        match self.mode {
+            Mode::Normal => Admission::Dispatch(request),
+            Mode::Paused if request.schedule_id().is_some() => {
+                log::info!("skipping scheduled request '{}' while paused", request.id());
+                Admission::Skipped
+            }
+            Mode::Paused if self.queue.len() >= MAX_QUEUED_REQUESTS => {
+                Admission::Refuse(request.id(), RefusedError {
+                    mode: self.mode,
+                    queue_full: true,
+                })
+            }
+            Mode::Paused => {
+                log::info!("queueing request '{}' while paused", request.id());
+                self.queue.push_back(request);
+                Admission::Queued
+            }
+            Mode::Drain => {
+                Admission::Refuse(request.id(), RefusedError {
+                    mode: self.mode,
+                    queue_full: false,
+                })
+            }
+        }
+    }
+
+    /// Removes all the queued requests (in order of arrival) from the gate.
+    pub fn take_queued(&mut self) -> Vec<crate::Request> {
+        self.queue.drain(..).collect()
+    }
+
+    /// Returns the decision about the next queued request (if any).
+    ///
+    /// Nothing is returned while the gate is paused. Once it is not, queued
+    /// requests are dispatched (or refused when draining) in order of arrival.
+    pub fn next(&mut self) -> Option<Admission> {
+        if self.mode == Mode::Paused {
+            return None;
+        }
+
+        let request = self.queue.pop_front()?;
+        Some(self.admit(request))
+    }
+
+    /// Makes the state of the gate visible to [`current`] and [`queued_count`].
+    pub fn publish(&self) {
+        CURRENT_MODE.store(self.mode().as_u8(), Ordering::Relaxed);
+        QUEUED_COUNT.store(self.queued_count(), Ordering::Relaxed);
+    }
+}
+
+/// An error indicating that a request was refused because of the agent mode.
+#[derive(Debug)]
+pub struct RefusedError {
+    /// Mode the agent was in.
+    mode: Mode,
+    /// Whether the request could not be queued because the queue was full.
+    queue_full: bool,
+}
+
+impl std::fmt::Display for RefusedError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.queue_full {
+            write!(fmt, "agent in {} mode with full request queue", self.mode)
+        } else {
+            write!(fmt, "agent in {} mode", self.mode)
+        }
+    }
+}
+
+impl std::error::Error for RefusedError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args(state_dir: &std::path::Path) -> crate::args::Args {
+        use argh::FromArgs as _;
+
+        crate::args::Args::from_args(&["rrg"], &[
+            "--state-dir", state_dir.to_str().unwrap(),
+        ]).unwrap()
+    }
+
+    fn request(request_id: u64, action: crate::request::Action) -> crate::Request {
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_flow_id(0x1337);
+        proto.set_request_id(request_id);
+        proto.set_action(action.into());
+
+        crate::Request::try_from(proto)
+            .unwrap()
+    }
+
+    fn scheduled_request(request_id: u64) -> crate::Request {
+        let mut request = request(request_id, crate::request::Action::GetSystemMetadata);
+        request.set_schedule_id(String::from("foo"));
+
+        request
+    }
+
+    fn dispatched_id(admission: Option<Admission>) -> u64 {
+        match admission {
+            Some(Admission::Dispatch(request)) => request.id().request_id(),
+            _ => panic!("unexpected admission"),
+        }
+    }
+
+    #[test]
+    fn gate_normal_dispatch() {
+        let mut gate = Gate::new(Mode::Normal);
+        let admission = gate.admit(request(1, crate::request::Action::ListProcesses));
+        assert_eq!(dispatched_id(Some(admission)), 1);
+        assert!(gate.next().is_none());
+    }
+
+    #[test]
+    fn gate_paused_queue_until_resumed() {
+        use crate::request::Action;
+
+        let mut gate = Gate::new(Mode::Paused);
+        assert!(matches!(gate.admit(request(1, Action::ListProcesses)), Admission::Queued));
+        assert!(matches!(gate.admit(request(2, Action::ListMounts)), Admission::Queued));
+        assert_eq!(gate.queued_count(), 2);
+        assert!(gate.next().is_none());
+
+        gate.set_mode(Mode::Normal);
+        assert_eq!(dispatched_id(gate.next()), 1);
+        assert_eq!(dispatched_id(gate.next()), 2);
+        assert!(gate.next().is_none());
+    }
+
+    #[test]
+    fn gate_paused_exempt_dispatch() {
+        use crate::request::Action;
+
+        let mut gate = Gate::new(Mode::Paused);
+        let admission = gate.admit(request(1, Action::GetAgentHealth));
+        assert_eq!(dispatched_id(Some(admission)), 1);
+        let admission = gate.admit(request(2, Action::SetAgentMode));
+        assert_eq!(dispatched_id(Some(admission)), 2);
+        assert_eq!(gate.queued_count(), 0);
+    }
+
+    #[test]
+    fn gate_paused_skip_scheduled() {
+        let mut gate = Gate::new(Mode::Paused);
+        assert!(matches!(gate.admit(scheduled_request(1)), Admission::Skipped));
+        assert_eq!(gate.queued_count(), 0);
+    }
+
+    #[test]
+    fn gate_paused_queue_full() {
+        let mut gate = Gate::new(Mode::Paused);
+        for request_id in 0..MAX_QUEUED_REQUESTS as u64 {
+            let admission = gate.admit(request(request_id, crate::request::Action::ListMounts));
+            assert!(matches!(admission, Admission::Queued));
+        }
+
+        let admission = gate.admit(request(1337, crate::request::Action::ListMounts));
+        assert!(matches!(admission, Admission::Refuse(_, RefusedError { queue_full: true, .. })));
+    }
+
+    #[test]
+    fn gate_drain_refuse() {
+        use crate::request::Action;
+
+        let mut gate = Gate::new(Mode::Drain);
+        let admission = gate.admit(request(1, Action::ListProcesses));
+        assert!(matches!(admission, Admission::Refuse(_, RefusedError { mode: Mode::Drain, .. })));
+        let admission = gate.admit(request(2, Action::SetAgentMode));
+        assert_eq!(dispatched_id(Some(admission)), 2);
+    }
+
+    #[test]
+    fn gate_drain_refuse_queued() {
+        let mut gate = Gate::new(Mode::Paused);
+        assert!(matches!(gate.admit(request(1, crate::request::Action::ListMounts)), Admission::Queued));
+
+        gate.set_mode(Mode::Drain);
+        assert!(matches!(gate.next(), Some(Admission::Refuse(..))));
+        assert!(gate.next().is_none());
+    }
+
+    #[test]
+    fn gate_take_queued() {
+        let mut gate = Gate::new(Mode::Paused);
+        assert!(matches!(gate.admit(request(1, crate::request::Action::ListMounts)), Admission::Queued));
+        assert!(matches!(gate.admit(request(2, crate::request::Action::ListMounts)), Admission::Queued));
+
+        let queued = gate.take_queued();
+        assert_eq!(queued.iter().map(|request| request.id().request_id()).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(gate.queued_count(), 0);
+
+        gate.set_mode(Mode::Normal);
+        assert!(gate.next().is_none());
+    }
+
+    #[test]
+    fn load_persist() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = args(tempdir.path());
+        assert_eq!(load(&args), Mode::Normal);
+
+        persist(&args, Mode::Paused)
+            .unwrap();
+        assert_eq!(load(&args), Mode::Paused);
+
+        persist(&args, Mode::Drain)
+            .unwrap();
+        assert_eq!(load(&args), Mode::Drain);
+    }
+
+    #[test]
+    fn load_invalid() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join(MODE_FILE_NAME), b"foo")
+            .unwrap();
+
+        let args = args(tempdir.path());
+        assert_eq!(load(&args), Mode::Normal);
+    }
+}
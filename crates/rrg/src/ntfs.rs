@@ -0,0 +1,600 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parsing of the on-disk NTFS structures needed to locate system files.
+//!
+//! Only the minimum required to find the extents of system files (like `$MFT`
+//! or `$Bitmap`) is parsed: the boot sector, the update sequence fixups of MFT
+//! records, attributes of MFT records and data runs of their non-resident
+//! `$DATA` attributes.
+
+/// Size of the blocks protected by the update sequence of MFT records.
+///
+/// This is always 512 bytes, regardless of the actual sector size.
+const FIXUP_BLOCK_SIZE: usize = 512;
+
+/// Number of the MFT record of the `$Bitmap` file.
+///
+/// The `$Bitmap` file tracks which clusters of the volume are in use, one bit
+/// per cluster.
+pub const MFT_RECORD_BITMAP: u64 = 6;
+
+/// Type code of the `$DATA` attribute.
+const ATTR_TYPE_DATA: u32 = 0x80;
+
+/// Type code marking the end of the attribute list in an MFT record.
+const ATTR_TYPE_END: u32 = 0xFFFFFFFF;
+
+/// Parsed NTFS boot sector.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BootSector {
+    /// Number of bytes in a single sector.
+    pub bytes_per_sector: u64,
+    /// Number of bytes in a single cluster.
+    pub bytes_per_cluster: u64,
+    /// Logical cluster number of the first cluster of the MFT.
+    pub mft_lcn: u64,
+    /// Number of bytes in a single MFT record.
+    pub bytes_per_mft_record: u64,
+}
+
+impl BootSector {
+
+    /// Parses the boot sector from the first sector of the volume.
+    pub fn parse(buf: &[u8]) -> Result<BootSector, ParseError> {
+        if buf.len() < 512 {
+            return Err(ParseError::InputTooShort);
+        }
+        if &buf[0x03..0x0B] != b"NTFS    " {
+            return Err(ParseError::InvalidBootSector("not an NTFS volume"));
+        }
+        if buf[0x1FE..0x200] != [0x55, 0xAA] {
+            return Err(ParseError::InvalidBootSector("missing signature"));
+        }
+
+        let bytes_per_sector = u64::from(u16_le(buf, 0x0B));
+        if !bytes_per_sector.is_power_of_two() || !(256..=4096).contains(&bytes_per_sector) {
+            return Err(ParseError::InvalidBootSector("invalid sector size"));
+        }
+
+        // Values above 0x80 encode powers of two as negative exponents (this
+        // is used for clusters bigger than 64 KiB).
+        let sectors_per_cluster = match buf[0x0D] {
+            0 => return Err(ParseError::InvalidBootSector("invalid cluster size")),
+            value @ 0x01..=0x80 => u64::from(value),
+            value => 1 << (256 - u32::from(value)).min(31),
+        };
+        let bytes_per_cluster = bytes_per_sector * sectors_per_cluster;
+
+        let mft_lcn = u64_le(buf, 0x30);
+
+        // Positive values are numbers of clusters per record while negative
+        // values encode the record size in bytes as a power of two.
+        let bytes_per_mft_record = match buf[0x40] as i8 {
+            0 => return Err(ParseError::InvalidBootSector("invalid record size")),
+            value @ 1.. => u64::from(value as u8) * bytes_per_cluster,
+            value @ -31..=-1 => 1 << -value,
+            _ => return Err(ParseError::InvalidBootSector("invalid record size")),
+        };
+
+        Ok(BootSector {
+            bytes_per_sector,
+            bytes_per_cluster,
+            mft_lcn,
+            bytes_per_mft_record,
+        })
+    }
+}
+
+/// A single run of clusters of a non-resident attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataRun {
+    /// Logical cluster number of the first cluster of the run.
+    ///
+    /// Sparse runs (that do not occupy any clusters) have no cluster number.
+    pub lcn: Option<u64>,
+    /// Number of clusters in the run.
+    pub len: u64,
+}
+
+/// Parses the data run list of a non-resident attribute.
+pub fn parse_data_runs(buf: &[u8]) -> Result<Vec<DataRun>, ParseError> {
+    let mut runs = Vec::new();
+
+    let mut pos = 0;
+    let mut lcn = 0i64;
+
+    loop {
+        let header = *buf.get(pos)
+            .ok_or(ParseError::InputTooShort)?;
+        if header == 0 {
+            break;
+        }
+        pos += 1;
+
+        let len_size = usize::from(header & 0x0F);
+        let offset_size = usize::from(header >> 4);
+        if len_size == 0 || len_size > 8 || offset_size > 8 {
+            return Err(ParseError::InvalidDataRun);
+        }
+
+        let len_bytes = buf.get(pos..pos + len_size)
+            .ok_or(ParseError::InputTooShort)?;
+        pos += len_size;
+
+        let offset_bytes = buf.get(pos..pos + offset_size)
+            .ok_or(ParseError::InputTooShort)?;
+        pos += offset_size;
+
+        let len = uvarint_le(len_bytes);
+
+        if offset_size == 0 {
+            runs.push(DataRun { lcn: None, len });
+            continue;
+        }
+
+        // Offsets are relative to the cluster number of the previous run and
+        // can be negative.
+        lcn = lcn.checked_add(ivarint_le(offset_bytes))
+            .ok_or(ParseError::InvalidDataRun)?;
+
+        let lcn = u64::try_from(lcn)
+            .map_err(|_| ParseError::InvalidDataRun)?;
+
+        runs.push(DataRun { lcn: Some(lcn), len });
+    }
+
+    Ok(runs)
+}
+
+/// Applies the update sequence fixups to the given MFT record in place.
+///
+/// This verifies and restores the last two bytes of each 512-byte block of the
+/// record that were replaced by the update sequence number when the record was
+/// written to the disk.
+pub fn apply_fixups(record: &mut [u8]) -> Result<(), ParseError> {
+    if record.len() < 0x30 {
+        return Err(ParseError::InputTooShort);
+    }
+    if &record[0x00..0x04] != b"FILE" {
+        return Err(ParseError::InvalidRecord("invalid signature"));
+    }
+
+    let usa_offset = usize::from(u16_le(record, 0x04));
+    let usa_count = usize::from(u16_le(record, 0x06));
+
+    // The first entry of the update sequence array is the update sequence
+    // number itself, the following ones are the original values of the last
+    // two bytes of consecutive blocks.
+    if usa_count == 0 || (usa_count - 1) * FIXUP_BLOCK_SIZE > record.len() {
+        return Err(ParseError::InvalidRecord("invalid update sequence size"));
+    }
+    let usa = record.get(usa_offset..usa_offset + 2 * usa_count)
+        .ok_or(ParseError::InvalidRecord("invalid update sequence offset"))?
+        .to_vec();
+
+    for block in 1..usa_count {
+        let pos = block * FIXUP_BLOCK_SIZE - 2;
+        if record[pos..pos + 2] != usa[0..2] {
+            return Err(ParseError::InvalidRecord("update sequence mismatch"));
+        }
+
+        record[pos..pos + 2].copy_from_slice(&usa[2 * block..2 * block + 2]);
+    }
+
+    Ok(())
+}
+
+/// Non-resident `$DATA` attribute of an MFT record.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataAttribute {
+    /// Runs of clusters occupied by the data.
+    pub runs: Vec<DataRun>,
+    /// Actual size of the data in bytes.
+    pub size: u64,
+}
+
+/// Parses the unnamed `$DATA` attribute from the given MFT record.
+///
+/// The record is expected to have the fixups already applied.
+pub fn parse_data_attribute(record: &[u8]) -> Result<DataAttribute, ParseError> {
+    if record.len() < 0x18 {
+        return Err(ParseError::InputTooShort);
+    }
+
+    let mut pos = usize::from(u16_le(record, 0x14));
+
+    loop {
+        let attr_header = record.get(pos..pos + 0x10)
+            .ok_or(ParseError::InvalidRecord("attribute out of bounds"))?;
+
+        let attr_type = u32_le(attr_header, 0x00);
+        if attr_type == ATTR_TYPE_END {
+            return Err(ParseError::InvalidRecord("no data attribute"));
+        }
+
+        let attr_len = u32_le(attr_header, 0x04) as usize;
+        if attr_len < 0x10 {
+            return Err(ParseError::InvalidRecord("invalid attribute length"));
+        }
+
+        let attr = record.get(pos..pos + attr_len)
+            .ok_or(ParseError::InvalidRecord("attribute out of bounds"))?;
+        pos += attr_len;
+
+        let non_resident = attr[0x08] != 0;
+        let name_len = attr[0x09];
+
+        // Named `$DATA` attributes are alternate data streams, we need the
+        // unnamed (main) one.
+        if attr_type != ATTR_TYPE_DATA || name_len != 0 {
+            continue;
+        }
+        if !non_resident {
+            return Err(ParseError::InvalidRecord("resident data attribute"));
+        }
+        if attr.len() < 0x40 {
+            return Err(ParseError::InvalidRecord("invalid attribute length"));
+        }
+
+        let runs_offset = usize::from(u16_le(attr, 0x20));
+        let runs_buf = attr.get(runs_offset..)
+            .ok_or(ParseError::InvalidRecord("data runs out of bounds"))?;
+
+        return Ok(DataAttribute {
+            runs: parse_data_runs(runs_buf)?,
+            size: u64_le(attr, 0x30),
+        });
+    }
+}
+
+/// An error that can occur when parsing NTFS structures.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ended before the structure was fully parsed.
+    InputTooShort,
+    /// The boot sector is not a valid NTFS boot sector.
+    InvalidBootSector(&'static str),
+    /// The MFT record is malformed.
+    InvalidRecord(&'static str),
+    /// The data run list is malformed.
+    InvalidDataRun,
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::InputTooShort => write!(fmt, "input too short"),
+            ParseError::InvalidBootSector(reason) => {
+                write!(fmt, "invalid boot sector: {reason}")
+            }
+            ParseError::InvalidRecord(reason) => {
+                write!(fmt, "invalid MFT record: {reason}")
+            }
+            ParseError::InvalidDataRun => write!(fmt, "invalid data run"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+/// Reads a little-endian `u16` at the given offset.
+fn u16_le(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Reads a little-endian `u32` at the given offset.
+fn u32_le(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Reads a little-endian `u64` at the given offset.
+fn u64_le(buf: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&buf[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Decodes an unsigned little-endian integer of up to 8 bytes.
+fn uvarint_le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev()
+        .fold(0, |value, byte| value << 8 | u64::from(*byte))
+}
+
+/// Decodes a signed little-endian integer of up to 8 bytes.
+fn ivarint_le(bytes: &[u8]) -> i64 {
+    let value = uvarint_le(bytes);
+
+    // We sign-extend the value based on the most significant bit of the last
+    // byte.
+    match bytes.last() {
+        Some(last) if last & 0x80 != 0 && bytes.len() < 8 => {
+            (value | (u64::MAX << (8 * bytes.len()))) as i64
+        }
+        _ => value as i64,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    /// Builds a fixture boot sector with the given geometry fields.
+    pub fn boot_sector(
+        bytes_per_sector: u16,
+        sectors_per_cluster: u8,
+        mft_lcn: u64,
+        clusters_per_mft_record: i8,
+    ) -> Vec<u8> {
+        let mut buf = vec![0; 512];
+        buf[0x00..0x03].copy_from_slice(&[0xEB, 0x52, 0x90]);
+        buf[0x03..0x0B].copy_from_slice(b"NTFS    ");
+        buf[0x0B..0x0D].copy_from_slice(&bytes_per_sector.to_le_bytes());
+        buf[0x0D] = sectors_per_cluster;
+        buf[0x30..0x38].copy_from_slice(&mft_lcn.to_le_bytes());
+        buf[0x40] = clusters_per_mft_record as u8;
+        buf[0x1FE..0x200].copy_from_slice(&[0x55, 0xAA]);
+        buf
+    }
+
+    /// Builds a fixture MFT record with a `$DATA` attribute with given runs.
+    ///
+    /// The returned record is in the on-disk form (with fixups applied).
+    pub fn mft_record(runs: &[u8], size: u64) -> Vec<u8> {
+        let mut record = vec![0; 1024];
+        record[0x00..0x04].copy_from_slice(b"FILE");
+        // Update sequence array at 0x30 with 3 entries (number and 2 blocks).
+        record[0x04..0x06].copy_from_slice(&0x30u16.to_le_bytes());
+        record[0x06..0x08].copy_from_slice(&3u16.to_le_bytes());
+        // First attribute at 0x38.
+        record[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+
+        let mut pos = 0x38;
+
+        // `$STANDARD_INFORMATION` (resident, contents irrelevant).
+        record[pos..pos + 4].copy_from_slice(&0x10u32.to_le_bytes());
+        record[pos + 4..pos + 8].copy_from_slice(&0x60u32.to_le_bytes());
+        pos += 0x60;
+
+        // `$DATA` (non-resident).
+        let data_len = (0x40 + runs.len() + 1).next_multiple_of(8);
+        record[pos..pos + 4].copy_from_slice(&ATTR_TYPE_DATA.to_le_bytes());
+        record[pos + 4..pos + 8].copy_from_slice(&(data_len as u32).to_le_bytes());
+        record[pos + 0x08] = 1;
+        record[pos + 0x20..pos + 0x22].copy_from_slice(&0x40u16.to_le_bytes());
+        record[pos + 0x28..pos + 0x30].copy_from_slice(&size.next_multiple_of(4096).to_le_bytes());
+        record[pos + 0x30..pos + 0x38].copy_from_slice(&size.to_le_bytes());
+        record[pos + 0x38..pos + 0x40].copy_from_slice(&size.to_le_bytes());
+        record[pos + 0x40..pos + 0x40 + runs.len()].copy_from_slice(runs);
+        pos += data_len;
+
+        record[pos..pos + 4].copy_from_slice(&ATTR_TYPE_END.to_le_bytes());
+
+        // We simulate writing to the disk: original values of the last bytes
+        // of each block are moved to the update sequence array.
+        let usn = [0x2A, 0x00];
+        record[0x30..0x32].copy_from_slice(&usn);
+        for block in 1..3 {
+            let pos = block * FIXUP_BLOCK_SIZE - 2;
+            let original = [record[pos], record[pos + 1]];
+            record[0x30 + 2 * block..0x30 + 2 * block + 2].copy_from_slice(&original);
+            record[pos..pos + 2].copy_from_slice(&usn);
+        }
+
+        record
+    }
+
+    #[test]
+    fn boot_sector_ok() {
+        let buf = boot_sector(512, 8, 0xC0000, -10);
+
+        assert_eq!(BootSector::parse(&buf), Ok(BootSector {
+            bytes_per_sector: 512,
+            bytes_per_cluster: 4096,
+            mft_lcn: 0xC0000,
+            bytes_per_mft_record: 1024,
+        }));
+    }
+
+    #[test]
+    fn boot_sector_positive_record_size() {
+        let buf = boot_sector(4096, 1, 0x1000, 1);
+
+        let boot_sector = BootSector::parse(&buf)
+            .unwrap();
+        assert_eq!(boot_sector.bytes_per_cluster, 4096);
+        assert_eq!(boot_sector.bytes_per_mft_record, 4096);
+    }
+
+    #[test]
+    fn boot_sector_large_cluster() {
+        // 0xF8 encodes 2^8 = 256 sectors per cluster.
+        let buf = boot_sector(512, 0xF8, 0x1000, -10);
+
+        let boot_sector = BootSector::parse(&buf)
+            .unwrap();
+        assert_eq!(boot_sector.bytes_per_cluster, 128 * 1024);
+    }
+
+    #[test]
+    fn boot_sector_not_ntfs() {
+        let mut buf = boot_sector(512, 8, 0xC0000, -10);
+        buf[0x03..0x0B].copy_from_slice(b"MSDOS5.0");
+
+        assert!(matches! {
+            BootSector::parse(&buf),
+            Err(ParseError::InvalidBootSector(_))
+        });
+    }
+
+    #[test]
+    fn boot_sector_no_signature() {
+        let mut buf = boot_sector(512, 8, 0xC0000, -10);
+        buf[0x1FE] = 0x00;
+
+        assert!(matches! {
+            BootSector::parse(&buf),
+            Err(ParseError::InvalidBootSector(_))
+        });
+    }
+
+    #[test]
+    fn boot_sector_invalid_sector_size() {
+        let buf = boot_sector(1000, 8, 0xC0000, -10);
+
+        assert!(matches! {
+            BootSector::parse(&buf),
+            Err(ParseError::InvalidBootSector(_))
+        });
+    }
+
+    #[test]
+    fn boot_sector_too_short() {
+        let buf = boot_sector(512, 8, 0xC0000, -10);
+
+        assert_eq!(BootSector::parse(&buf[..256]), Err(ParseError::InputTooShort));
+    }
+
+    #[test]
+    fn data_runs_empty() {
+        assert_eq!(parse_data_runs(&[0x00]), Ok(vec![]));
+    }
+
+    #[test]
+    fn data_runs_single() {
+        // 0x18 clusters starting at cluster 0x5634.
+        let buf = [0x21, 0x18, 0x34, 0x56, 0x00];
+
+        assert_eq!(parse_data_runs(&buf), Ok(vec![
+            DataRun { lcn: Some(0x5634), len: 0x18 },
+        ]));
+    }
+
+    #[test]
+    fn data_runs_multiple() {
+        let buf = [
+            // 0x40 clusters starting at cluster 0xC0000.
+            0x31, 0x40, 0x00, 0x00, 0x0C,
+            // 0x0100 clusters starting 0x1000 clusters after the previous one.
+            0x22, 0x00, 0x01, 0x00, 0x10,
+            0x00,
+        ];
+
+        assert_eq!(parse_data_runs(&buf), Ok(vec![
+            DataRun { lcn: Some(0xC0000), len: 0x40 },
+            DataRun { lcn: Some(0xC1000), len: 0x100 },
+        ]));
+    }
+
+    #[test]
+    fn data_runs_negative_offset() {
+        let buf = [
+            // 0x10 clusters starting at cluster 0x1000.
+            0x21, 0x10, 0x00, 0x10,
+            // 0x08 clusters starting 0x10 clusters before the previous one.
+            0x11, 0x08, 0xF0,
+            0x00,
+        ];
+
+        assert_eq!(parse_data_runs(&buf), Ok(vec![
+            DataRun { lcn: Some(0x1000), len: 0x10 },
+            DataRun { lcn: Some(0x0FF0), len: 0x08 },
+        ]));
+    }
+
+    #[test]
+    fn data_runs_sparse() {
+        let buf = [
+            0x11, 0x10, 0x20,
+            // 0x30 clusters without any offset.
+            0x01, 0x30,
+            0x11, 0x08, 0x10,
+            0x00,
+        ];
+
+        assert_eq!(parse_data_runs(&buf), Ok(vec![
+            DataRun { lcn: Some(0x20), len: 0x10 },
+            DataRun { lcn: None, len: 0x30 },
+            DataRun { lcn: Some(0x30), len: 0x08 },
+        ]));
+    }
+
+    #[test]
+    fn data_runs_unterminated() {
+        assert_eq!(parse_data_runs(&[0x21, 0x18, 0x34, 0x56]), Err(ParseError::InputTooShort));
+    }
+
+    #[test]
+    fn data_runs_truncated() {
+        assert_eq!(parse_data_runs(&[0x21, 0x18, 0x34]), Err(ParseError::InputTooShort));
+    }
+
+    #[test]
+    fn data_runs_before_volume_start() {
+        assert_eq!(parse_data_runs(&[0x11, 0x08, 0xF0, 0x00]), Err(ParseError::InvalidDataRun));
+    }
+
+    #[test]
+    fn data_runs_invalid_header() {
+        assert_eq!(parse_data_runs(&[0x90, 0x00]), Err(ParseError::InvalidDataRun));
+    }
+
+    #[test]
+    fn fixups_ok() {
+        let mut record = mft_record(&[0x11, 0x04, 0x10, 0x00], 4096);
+        let pos = 2 * FIXUP_BLOCK_SIZE - 2;
+        assert_eq!(record[pos..pos + 2], [0x2A, 0x00]);
+
+        apply_fixups(&mut record)
+            .unwrap();
+        assert_eq!(record[pos..pos + 2], [0x00, 0x00]);
+    }
+
+    #[test]
+    fn fixups_mismatch() {
+        let mut record = mft_record(&[0x11, 0x04, 0x10, 0x00], 4096);
+        record[FIXUP_BLOCK_SIZE - 2] = 0xFF;
+
+        assert!(matches! {
+            apply_fixups(&mut record),
+            Err(ParseError::InvalidRecord(_))
+        });
+    }
+
+    #[test]
+    fn fixups_invalid_signature() {
+        let mut record = mft_record(&[0x11, 0x04, 0x10, 0x00], 4096);
+        record[0x00..0x04].copy_from_slice(b"BAAD");
+
+        assert!(matches! {
+            apply_fixups(&mut record),
+            Err(ParseError::InvalidRecord(_))
+        });
+    }
+
+    #[test]
+    fn data_attribute_ok() {
+        let mut record = mft_record(&[
+            0x11, 0x04, 0x10,
+            0x11, 0x02, 0x20,
+            0x00,
+        ], 5000);
+        apply_fixups(&mut record)
+            .unwrap();
+
+        assert_eq!(parse_data_attribute(&record), Ok(DataAttribute {
+            runs: vec! {
+                DataRun { lcn: Some(0x10), len: 0x04 },
+                DataRun { lcn: Some(0x30), len: 0x02 },
+            },
+            size: 5000,
+        }));
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Execution of requests without a connection to the server.
+//!
+//! In the offline mode requests are read from a file (as a sequence of length-
+//! delimited `rrg.Request` messages) and their results are written to files in
+//! the output directory. Responses and parcels are stored in the same format as
+//! they would be sent over the wire so that they can be later imported and
+//! processed by the server as if they were collected online.
+
+use sha2::Digest as _;
+
+/// An entry of the manifest describing a file written in the offline mode.
+pub struct ManifestEntry {
+    /// Path to the written file.
+    pub path: std::path::PathBuf,
+    /// SHA-256 digest of the written file.
+    pub sha256: [u8; 32],
+}
+
+impl std::fmt::Display for ManifestEntry {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // We follow the format of the `sha256sum` tool so that the manifest
+        // can be verified using it.
+        for byte in self.sha256 {
+            write!(fmt, "{byte:02x}")?;
+        }
+        write!(fmt, "  {}", self.path.display())
+    }
+}
+
+/// Executes all requests from the given file and writes results to `output_dir`.
+///
+/// The returned manifest describes all the files that were written.
+pub fn run(
+    args: &crate::args::Args,
+    requests_path: &std::path::Path,
+    output_dir: &std::path::Path,
+) -> std::io::Result<Vec<ManifestEntry>> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let mut requests_file = std::fs::File::open(requests_path)?;
+    let mut requests_input = protobuf::CodedInputStream::new(&mut requests_file);
+
+    let mut manifest = Vec::new();
+
+    while !requests_input.eof()? {
+        if crate::shutdown::requested() {
+            log::warn!("shutdown requested, skipping remaining requests");
+            break;
+        }
+
+        let request = requests_input.read_message::<rrg_proto::rrg::Request>()?;
+        let request = crate::Request::try_from(request);
+
+        let paths = crate::session::FileSession::dispatch(args, request, output_dir)?;
+        for path in paths {
+            manifest.push(manifest_entry(path)?);
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Creates a manifest entry for the file at the given path.
+fn manifest_entry(path: std::path::PathBuf) -> std::io::Result<ManifestEntry> {
+    let mut file = std::fs::File::open(&path)?;
+
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(ManifestEntry {
+        path,
+        sha256: hasher.finalize().into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args() -> crate::args::Args {
+        use argh::FromArgs as _;
+
+        crate::args::Args::from_args(&["rrg"], &[])
+            .unwrap()
+    }
+
+    /// Writes the given requests to a file in the given directory.
+    fn write_requests(
+        dir: &std::path::Path,
+        requests: &[rrg_proto::rrg::Request],
+    ) -> std::path::PathBuf {
+        use protobuf::Message as _;
+
+        let path = dir.join("requests");
+        let mut file = std::fs::File::create(&path)
+            .unwrap();
+
+        for request in requests {
+            request.write_length_delimited_to_writer(&mut file)
+                .unwrap();
+        }
+
+        path
+    }
+
+    /// Reads all length-delimited messages from the given file.
+    fn read_messages<M>(path: &std::path::Path) -> Vec<M>
+    where
+        M: protobuf::Message,
+    {
+        let mut file = std::fs::File::open(path)
+            .unwrap();
+        let mut input = protobuf::CodedInputStream::new(&mut file);
+
+        let mut messages = Vec::new();
+        while !input.eof().unwrap() {
+            messages.push(input.read_message::<M>().unwrap());
+        }
+
+        messages
+    }
+
+    #[test]
+    fn run_empty() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let requests_path = write_requests(tempdir.path(), &[]);
+        let output_dir = tempdir.path().join("output");
+
+        let manifest = run(&args(), &requests_path, &output_dir)
+            .unwrap();
+        assert!(manifest.is_empty());
+    }
+
+    #[test]
+    fn run_unknown_action() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut request = rrg_proto::rrg::Request::new();
+        request.set_flow_id(0xf00);
+        request.set_request_id(0xba7);
+        request.set_action(rrg_proto::rrg::Action::UNKNOWN);
+
+        let requests_path = write_requests(tempdir.path(), &[request]);
+        let output_dir = tempdir.path().join("output");
+
+        let manifest = run(&args(), &requests_path, &output_dir)
+            .unwrap();
+        assert_eq!(manifest.len(), 2);
+
+        let responses = read_messages::<rrg_proto::rrg::Response>(&manifest[0].path);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].flow_id(), 0xf00);
+        assert_eq!(responses[0].request_id(), 0xba7);
+        assert!(responses[0].has_status());
+        assert!(responses[0].status().has_error());
+    }
+
+    #[test]
+    fn run_encrypted() {
+        use argh::FromArgs as _;
+        use std::io::Read as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let secret = x25519_dalek::StaticSecret::random();
+        let public = x25519_dalek::PublicKey::from(&secret);
+
+        let public_hex = public.as_bytes().iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        let args = crate::args::Args::from_args(&["rrg"], &[
+            "--offline-encryption-key", &public_hex,
+        ]).unwrap();
+
+        let mut request = rrg_proto::rrg::Request::new();
+        request.set_flow_id(0xf00);
+        request.set_request_id(0xba7);
+        request.set_action(rrg_proto::rrg::Action::UNKNOWN);
+
+        let requests_path = write_requests(tempdir.path(), &[request]);
+        let output_dir = tempdir.path().join("output");
+
+        let manifest = run(&args, &requests_path, &output_dir)
+            .unwrap();
+        assert_eq!(manifest.len(), 2);
+
+        let responses_file = std::fs::File::open(&manifest[0].path)
+            .unwrap();
+        let mut responses_reader = crate::envelope::Reader::new(responses_file, &secret)
+            .unwrap();
+
+        let mut responses_bytes = Vec::new();
+        responses_reader.read_to_end(&mut responses_bytes)
+            .unwrap();
+
+        let mut responses_input = protobuf::CodedInputStream::from_bytes(&responses_bytes);
+        let response = responses_input.read_message::<rrg_proto::rrg::Response>()
+            .unwrap();
+        assert!(responses_input.eof().unwrap());
+
+        assert_eq!(response.flow_id(), 0xf00);
+        assert_eq!(response.request_id(), 0xba7);
+        assert!(response.status().has_error());
+    }
+
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[test]
+    fn run_get_filesystem_timeline() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let root = tempdir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("foo"), b"").unwrap();
+        std::fs::write(root.join("bar"), b"").unwrap();
+
+        let mut args_proto = rrg_proto::get_filesystem_timeline::Args::new();
+        args_proto.set_root(root.clone().into());
+
+        let mut request = rrg_proto::rrg::Request::new();
+        request.set_flow_id(0xf00);
+        request.set_request_id(0xba7);
+        request.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+        request.set_args(protobuf::well_known_types::any::Any::pack(&args_proto).unwrap());
+
+        let requests_path = write_requests(tempdir.path(), &[request]);
+        let output_dir = tempdir.path().join("output");
+
+        let manifest = run(&args(), &requests_path, &output_dir)
+            .unwrap();
+        assert_eq!(manifest.len(), 2);
+
+        for entry in &manifest {
+            let content = std::fs::read(&entry.path).unwrap();
+            assert_eq!(entry.sha256, <[u8; 32]>::from(sha2::Sha256::digest(&content)));
+        }
+
+        let responses_path = output_dir.join("F00-2983.responses");
+        let parcels_path = output_dir.join("F00-2983.parcels");
+        assert_eq!(manifest[0].path, responses_path);
+        assert_eq!(manifest[1].path, parcels_path);
+
+        let responses = read_messages::<rrg_proto::rrg::Response>(&responses_path);
+        let (status, replies) = responses.split_last()
+            .unwrap();
+        assert!(status.has_status());
+        assert!(!status.status().has_error());
+        assert!(!replies.is_empty());
+        assert!(replies.iter().all(|reply| reply.has_result()));
+
+        let parcels = read_messages::<rrg_proto::rrg::Parcel>(&parcels_path);
+        assert_eq!(parcels.len(), replies.len());
+        assert!(parcels.iter().all(|parcel| parcel.sink() == rrg_proto::rrg::Sink::BLOB));
+
+        // The written blobs should be decodable in exactly the same way as the
+        // ones sent while being connected to the server.
+        let chunks = parcels.iter()
+            .map(|parcel| {
+                parcel.payload().unpack::<rrg_proto::blob::Blob>()
+                    .unwrap().unwrap()
+                    .data().to_vec()
+            })
+            .collect::<Vec<_>>();
+
+        let entries = crate::gzchunked::decode(chunks.iter().map(Vec::as_slice))
+            .map(Result::unwrap)
+            .collect::<Vec<rrg_proto::get_filesystem_timeline::Entry>>();
+
+        let mut paths = entries.iter()
+            .map(|entry| crate::path::from_bytes(entry.path().to_owned()).unwrap())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(paths, vec![root.join("bar"), root.join("foo")]);
+    }
+
+    #[test]
+    fn run_get_filesystem_timeline_sampled() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let root = tempdir.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        for i in 0..1000 {
+            std::fs::write(root.join(format!("file-{i}")), b"").unwrap();
+        }
+
+        let mut args_proto = rrg_proto::get_filesystem_timeline::Args::new();
+        args_proto.set_root(root.clone().into());
+
+        let request = |request_id: u64, sample_rate: f64| {
+            let mut request = rrg_proto::rrg::Request::new();
+            request.set_flow_id(0xf00);
+            request.set_request_id(request_id);
+            request.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+            request.set_args(protobuf::well_known_types::any::Any::pack(&args_proto).unwrap());
+            request.set_sample_rate(sample_rate);
+            request
+        };
+
+        let requests_path = write_requests(tempdir.path(), &[
+            request(1, 1.0),
+            request(2, 0.5),
+            request(3, 0.5),
+            request(4, 1e-12),
+        ]);
+        let output_dir = tempdir.path().join("output");
+
+        run(&args(), &requests_path, &output_dir)
+            .unwrap();
+
+        let output = |request_id: u64| {
+            let mut responses = read_messages::<rrg_proto::rrg::Response>({
+                &output_dir.join(format!("F00-{request_id}.responses"))
+            });
+            let parcels = read_messages::<rrg_proto::rrg::Parcel>({
+                &output_dir.join(format!("F00-{request_id}.parcels"))
+            });
+
+            let status = responses.pop().unwrap()
+                .take_status();
+            assert!(!status.has_error());
+
+            // Snapshots of the clock differ between runs, so we clear them to
+            // compare the results.
+            let results = responses.into_iter()
+                .map(|mut reply| {
+                    let mut result = reply.take_result()
+                        .unpack::<rrg_proto::get_filesystem_timeline::Result>()
+                        .unwrap().unwrap();
+                    result.clear_collection_clock();
+                    result
+                })
+                .collect::<Vec<_>>();
+
+            // Every result references exactly one blob.
+            assert_eq!(parcels.len(), results.len());
+            assert_eq!(status.result_count() as usize, results.len());
+
+            (status, results)
+        };
+
+        let (status, results) = output(1);
+        assert_eq!(status.sampled_out_count(), 0);
+        let result_count = results.len();
+        assert!(result_count > 0);
+
+        let (status_2, results_2) = output(2);
+        assert_eq!(results_2.len() + status_2.sampled_out_count() as usize, result_count);
+        assert!(results_2.iter().all(|result| results.contains(result)));
+
+        // The same results should be picked when running the request again.
+        let (_, results_3) = output(3);
+        assert_eq!(results_2, results_3);
+
+        let (status_4, results_4) = output(4);
+        assert!(results_4.is_empty());
+        assert_eq!(status_4.sampled_out_count() as usize, result_count);
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Resolution of user and group names with caching.
+//!
+//! Looking names up in the account databases can be slow (e.g. it may involve
+//! a query to a remote directory service), which matters for actions that
+//! report ownership of many files or processes: most of them are owned by the
+//! same few accounts. The [`Resolver`] caches both successful and failed
+//! lookups, so that each identifier is looked up at most once (as long as it
+//! stays in the cache).
+
+#[cfg(target_family = "windows")]
+pub mod windows;
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Maximum number of entries in every cache of the resolver.
+///
+/// Once the limit is reached, the cache is cleared. This is crude but there are
+/// rarely more than a few dozens of accounts in use on a system, so it should
+/// not happen in practice.
+const MAX_CACHE_LEN: usize = 4096;
+
+/// Returns the resolver shared by all the actions.
+pub fn resolver() -> &'static Resolver {
+    static RESOLVER: Resolver = Resolver::new();
+    &RESOLVER
+}
+
+/// Resolver of user and group names that caches the results of the lookups.
+///
+/// The resolver can be safely shared between threads.
+pub struct Resolver {
+    /// Names of users with the given Unix identifiers.
+    #[cfg(target_family = "unix")]
+    user_names: Mutex<Cache<u32>>,
+    /// Names of groups with the given Unix identifiers.
+    #[cfg(target_family = "unix")]
+    group_names: Mutex<Cache<u32>>,
+    /// Names of accounts with the given security identifiers.
+    #[cfg(target_family = "windows")]
+    account_names: Mutex<Cache<String>>,
+    /// Number of lookups the resolver made (i.e. cache misses).
+    lookup_count: AtomicU64,
+}
+
+/// Cache of names of identifiers, `None` meaning that the lookup failed.
+type Cache<K> = BTreeMap<K, Option<OsString>>;
+
+impl Resolver {
+
+    /// Creates a new resolver with empty caches.
+    pub const fn new() -> Resolver {
+        Resolver {
+            #[cfg(target_family = "unix")]
+            user_names: Mutex::new(BTreeMap::new()),
+            #[cfg(target_family = "unix")]
+            group_names: Mutex::new(BTreeMap::new()),
+            #[cfg(target_family = "windows")]
+            account_names: Mutex::new(BTreeMap::new()),
+            lookup_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the name of the user with the given Unix identifier.
+    #[cfg(target_family = "unix")]
+    pub fn user_name(&self, uid: u32) -> Option<OsString> {
+        self.resolve(&self.user_names, uid, |uid| {
+            ospect::user::user_name(*uid)
+        })
+    }
+
+    /// Returns the name of the group with the given Unix identifier.
+    #[cfg(target_family = "unix")]
+    pub fn group_name(&self, gid: u32) -> Option<OsString> {
+        self.resolve(&self.group_names, gid, |gid| {
+            ospect::user::group_name(*gid)
+        })
+    }
+
+    /// Returns the name of the account with the given security identifier.
+    #[cfg(target_family = "windows")]
+    pub fn account_name(&self, sid: &str) -> Option<OsString> {
+        self.resolve(&self.account_names, String::from(sid), |sid| {
+            ospect::user::account_name(sid)
+        })
+    }
+
+    /// Returns the number of lookups the resolver made so far.
+    #[cfg(test)]
+    fn lookup_count(&self) -> u64 {
+        self.lookup_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the name of the given key from the cache or looks it up.
+    fn resolve<K, F>(&self, cache: &Mutex<Cache<K>>, key: K, lookup: F) -> Option<OsString>
+    where
+        K: Ord + std::fmt::Debug,
+        F: FnOnce(&K) -> std::io::Result<Option<OsString>>,
+    {
+        // The cache is consistent at all times, so there is no harm in using
+        // it even if some other thread panicked while holding the lock.
+        let lock = || cache.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        if let Some(name) = lock().get(&key) {
+            return name.clone();
+        }
+
+        // We do not hold the lock while looking the name up, so that threads
+        // that resolve other keys are not blocked. This means that two threads
+        // can look up the same key at the same time but this is harmless.
+        self.lookup_count.fetch_add(1, Ordering::Relaxed);
+        let name = match lookup(&key) {
+            Ok(name) => name,
+            Err(error) => {
+                log::warn!("failed to resolve name of {key:?}: {error}");
+                None
+            }
+        };
+
+        let mut cache = lock();
+        if cache.len() >= MAX_CACHE_LEN {
+            cache.clear();
+        }
+        cache.insert(key, name.clone());
+
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn user_name_current() {
+        // SAFETY: `geteuid` is always safe to call.
+        let uid = unsafe { libc::geteuid() };
+
+        let resolver = Resolver::new();
+        let name = resolver.user_name(uid);
+        assert_eq!(name, ospect::user::user_name(uid).unwrap());
+        assert_eq!(resolver.lookup_count(), 1);
+
+        assert_eq!(resolver.user_name(uid), name);
+        assert_eq!(resolver.lookup_count(), 1);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn group_name_current() {
+        // SAFETY: `getegid` is always safe to call.
+        let gid = unsafe { libc::getegid() };
+
+        let resolver = Resolver::new();
+        let name = resolver.group_name(gid);
+        assert_eq!(name, ospect::user::group_name(gid).unwrap());
+        assert_eq!(resolver.lookup_count(), 1);
+
+        assert_eq!(resolver.group_name(gid), name);
+        assert_eq!(resolver.lookup_count(), 1);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn user_name_unknown_cached() {
+        let resolver = Resolver::new();
+        assert_eq!(resolver.user_name(u32::MAX - 1), None);
+        assert_eq!(resolver.user_name(u32::MAX - 1), None);
+        assert_eq!(resolver.lookup_count(), 1);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn user_name_distinct() {
+        let resolver = Resolver::new();
+        resolver.user_name(0);
+        resolver.group_name(0);
+        resolver.user_name(u32::MAX - 1);
+        assert_eq!(resolver.lookup_count(), 3);
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn account_name_system() {
+        let resolver = Resolver::new();
+        assert!(resolver.account_name("S-1-5-18").is_some());
+        assert!(resolver.account_name("S-1-5-18").is_some());
+        assert_eq!(resolver.lookup_count(), 1);
+    }
+}
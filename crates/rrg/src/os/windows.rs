@@ -0,0 +1,14 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities specific to Windows.
+
+pub mod privileges;
+
+#[cfg(any(
+    feature = "action-list_autoruns",
+    feature = "action-list_services",
+))]
+pub mod path;
@@ -0,0 +1,242 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Normalization of executable paths found in the system configuration.
+//!
+//! Paths of executables in places like service definitions or the registry are
+//! rarely regular file paths: they use environment variables, are relative to
+//! the system directory or are specified in the NT object namespace format.
+
+use std::path::{Path, PathBuf};
+
+/// Path to the system directory used if it cannot be read from the environment.
+const DEFAULT_SYSTEM_ROOT: &str = "C:\\Windows";
+
+/// Returns the path to the system directory (e.g. `C:\Windows`).
+pub fn system_root() -> PathBuf {
+    std::env::var_os("SystemRoot")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SYSTEM_ROOT))
+}
+
+/// Normalizes the given executable path to a regular file path.
+///
+/// Paths to drivers are often specified relative to the system directory or
+/// in the NT object namespace format and some paths use environment variables,
+/// both of which we resolve here.
+pub fn normalize(path: &str, system_root: &Path) -> PathBuf {
+    let path = expand_vars(path, system_root);
+
+    // Paths in the NT object namespace can point to DOS drives with the `\??\`
+    // prefix (e.g. `\??\C:\Windows\foo.sys`).
+    let path = path.strip_prefix("\\??\\").unwrap_or(&path);
+
+    const SYSTEM_ROOT_PREFIX: &str = "\\SystemRoot\\";
+    if path.len() >= SYSTEM_ROOT_PREFIX.len() &&
+        path.is_char_boundary(SYSTEM_ROOT_PREFIX.len()) &&
+        path[..SYSTEM_ROOT_PREFIX.len()].eq_ignore_ascii_case(SYSTEM_ROOT_PREFIX)
+    {
+        return system_root.join(&path[SYSTEM_ROOT_PREFIX.len()..]);
+    }
+
+    // Paths without a drive and without a leading backslash (e.g. the common
+    // `System32\drivers\foo.sys`) are relative to the system directory.
+    let path = Path::new(path);
+    if path.is_relative() && !path.has_root() {
+        return system_root.join(path);
+    }
+
+    path.to_path_buf()
+}
+
+/// Extensions of executable files that command lines can point to.
+const EXECUTABLE_EXTENSIONS: [&str; 9] = [
+    ".exe", ".dll", ".sys", ".com", ".bat", ".cmd", ".scr", ".cpl", ".ocx",
+];
+
+/// Splits the given command line into the executable path and the arguments.
+///
+/// Command lines with spaces in the executable path are not required to be
+/// quoted: in such cases the system tries to execute every prefix ending at a
+/// space until it finds one that exists [1]. Instead of probing the filesystem
+/// (which would give results depending on where the agent runs), we consider
+/// the path to end with the first executable extension.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-createprocessw
+pub fn split_command_line(command_line: &str) -> (&str, Option<&str>) {
+    let command_line = command_line.trim();
+
+    let (path, rest) = if let Some(quoted) = command_line.strip_prefix('"') {
+        match quoted.find('"') {
+            Some(index) => (&quoted[..index], &quoted[index + 1..]),
+            None => (quoted, ""),
+        }
+    } else {
+        match executable_end(command_line) {
+            Some(end) => (&command_line[..end], &command_line[end..]),
+            None => match command_line.find(char::is_whitespace) {
+                Some(index) => (&command_line[..index], &command_line[index..]),
+                None => (command_line, ""),
+            },
+        }
+    };
+
+    let arguments = rest.trim();
+    if arguments.is_empty() {
+        (path, None)
+    } else {
+        (path, Some(arguments))
+    }
+}
+
+/// Returns the index at which the first executable extension in the given
+/// string ends (if there is any).
+fn executable_end(string: &str) -> Option<usize> {
+    let lowercase = string.to_ascii_lowercase();
+
+    for (index, _) in lowercase.match_indices('.') {
+        let rest = &lowercase[index..];
+
+        let Some(ext) = EXECUTABLE_EXTENSIONS.iter().find(|ext| rest.starts_with(*ext)) else {
+            continue;
+        };
+
+        let end = index + ext.len();
+        if string[end..].chars().next().is_none_or(char::is_whitespace) {
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+/// Expands `%NAME%` environment variables in the given string.
+///
+/// `%SystemRoot%` (and its legacy `%windir%` alias) is expanded to the given
+/// system directory. Variables that are not defined are left as they are.
+fn expand_vars(string: &str, system_root: &Path) -> String {
+    let mut result = String::new();
+    let mut rest = string;
+
+    while let Some(start) = rest.find('%') {
+        let Some(len) = rest[start + 1..].find('%') else {
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + len];
+
+        let value = if name.eq_ignore_ascii_case("SystemRoot") || name.eq_ignore_ascii_case("windir") {
+            Some(system_root.to_string_lossy().into_owned())
+        } else {
+            std::env::var(name).ok()
+        };
+
+        result.push_str(&rest[..start]);
+        match value {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn normalize_system_root() {
+        let system_root = Path::new("C:\\Windows");
+
+        assert_eq! {
+            normalize("\\SystemRoot\\System32\\drivers\\foo.sys", system_root),
+            Path::new("C:\\Windows\\System32\\drivers\\foo.sys")
+        };
+        assert_eq! {
+            normalize("%SystemRoot%\\System32\\svchost.exe", system_root),
+            Path::new("C:\\Windows\\System32\\svchost.exe")
+        };
+        assert_eq! {
+            normalize("%windir%\\foo.exe", system_root),
+            Path::new("C:\\Windows\\foo.exe")
+        };
+    }
+
+    #[test]
+    fn normalize_relative() {
+        assert_eq! {
+            normalize("System32\\drivers\\foo.sys", Path::new("C:\\Windows")),
+            Path::new("C:\\Windows\\System32\\drivers\\foo.sys")
+        };
+    }
+
+    #[test]
+    fn normalize_nt_prefix() {
+        assert_eq! {
+            normalize("\\??\\C:\\Foo\\foo.sys", Path::new("C:\\Windows")),
+            Path::new("C:\\Foo\\foo.sys")
+        };
+    }
+
+    #[test]
+    fn normalize_absolute() {
+        assert_eq! {
+            normalize("C:\\Program Files\\Foo\\foo.exe", Path::new("C:\\Windows")),
+            Path::new("C:\\Program Files\\Foo\\foo.exe")
+        };
+    }
+
+    #[test]
+    fn split_command_line_quoted() {
+        assert_eq! {
+            split_command_line("\"C:\\Program Files\\Foo\\foo.exe\" -service"),
+            ("C:\\Program Files\\Foo\\foo.exe", Some("-service"))
+        };
+        assert_eq! {
+            split_command_line("\"C:\\Foo\\foo.exe"),
+            ("C:\\Foo\\foo.exe", None)
+        };
+    }
+
+    #[test]
+    fn split_command_line_unquoted_with_spaces() {
+        assert_eq! {
+            split_command_line("C:\\Program Files\\Foo Bar\\foo.exe -k netsvcs -p"),
+            ("C:\\Program Files\\Foo Bar\\foo.exe", Some("-k netsvcs -p"))
+        };
+        assert_eq! {
+            split_command_line("C:\\Program Files\\Foo\\FOO.EXE"),
+            ("C:\\Program Files\\Foo\\FOO.EXE", None)
+        };
+        assert_eq! {
+            split_command_line("C:\\Program Files\\Foo\\foo.dll"),
+            ("C:\\Program Files\\Foo\\foo.dll", None)
+        };
+    }
+
+    #[test]
+    fn split_command_line_no_extension() {
+        assert_eq! {
+            split_command_line("C:\\Foo\\foo.exec --bar"),
+            ("C:\\Foo\\foo.exec", Some("--bar"))
+        };
+        assert_eq! {
+            split_command_line("C:\\Foo.company\\foo --bar"),
+            ("C:\\Foo.company\\foo", Some("--bar"))
+        };
+    }
+
+    #[test]
+    fn expand_vars_undefined() {
+        assert_eq! {
+            expand_vars("%RRG_UNDEFINED_VARIABLE%\\foo.exe", Path::new("C:\\Windows")),
+            "%RRG_UNDEFINED_VARIABLE%\\foo.exe"
+        };
+        assert_eq!(expand_vars("100%", Path::new("C:\\Windows")), "100%");
+    }
+}
@@ -0,0 +1,347 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Enabling privileges for the duration of privileged operations.
+//!
+//! Some operations (e.g. opening raw volumes or reading the `Security` event
+//! log) require privileges like `SeBackupPrivilege` or `SeSecurityPrivilege`.
+//! Administrators hold these but they are disabled by default and have to be
+//! enabled explicitly before they take effect.
+//!
+//! Privileges are enabled on the token of the current thread (impersonating
+//! the agent process if the thread does not have a token of its own), so that
+//! other threads are not affected, and restored once the operation completes.
+
+use windows_sys::Win32::{
+    Foundation::*,
+    Security::*,
+    System::Threading::*,
+};
+
+/// Name of the privilege required to read files regardless of their ACLs.
+pub const SE_BACKUP_NAME: &str = "SeBackupPrivilege";
+
+/// Name of the privilege required to read the security log.
+pub const SE_SECURITY_NAME: &str = "SeSecurityPrivilege";
+
+/// Name of the privilege required to assign primary tokens to processes.
+pub const SE_ASSIGNPRIMARYTOKEN_NAME: &str = "SeAssignPrimaryTokenPrivilege";
+
+/// Name of the privilege required to adjust memory quotas of processes.
+pub const SE_INCREASE_QUOTA_NAME: &str = "SeIncreaseQuotaPrivilege";
+
+/// Runs the given closure with the specified privilege enabled.
+///
+/// The previous state of the privilege is restored once the closure returns
+/// (or panics).
+///
+/// # Errors
+///
+/// This function will fail if the privilege could not be enabled, e.g. because
+/// the agent does not hold it. The error is classified as a permission denial.
+pub fn with_privilege<F, T>(name: &str, f: F) -> Result<T, PrivilegeError>
+where
+    F: FnOnce() -> T,
+{
+    let guard = Guard::enable(name)
+        .map_err(|error| PrivilegeError {
+            name: String::from(name),
+            error: std::io::Error::new(std::io::ErrorKind::PermissionDenied, error),
+        })?;
+
+    let result = f();
+    drop(guard);
+
+    Ok(result)
+}
+
+/// Guard that restores the previous state of an enabled privilege on drop.
+struct Guard {
+    /// Token of the current thread.
+    token: HANDLE,
+    /// State of the adjusted privilege before the adjustment.
+    previous: TOKEN_PRIVILEGES,
+    /// Whether the thread impersonates the process only because of the guard.
+    impersonating: bool,
+}
+
+impl Guard {
+
+    /// Enables the privilege with the given name on the current thread token.
+    fn enable(name: &str) -> std::io::Result<Guard> {
+        let name_wide = name.encode_utf16()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+
+        let mut luid = LUID {
+            LowPart: 0,
+            HighPart: 0,
+        };
+
+        // SAFETY: We pass a null-terminated privilege name and a valid location
+        // for the identifier. We verify the result below.
+        let status = unsafe {
+            LookupPrivilegeValueW(std::ptr::null(), name_wide.as_ptr(), &mut luid)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut impersonating = false;
+        let mut token = std::ptr::null_mut();
+
+        // SAFETY: The pseudo-handle of the current thread is always valid. On
+        // success, the token handle is written to the given location.
+        let status = unsafe {
+            OpenThreadToken(GetCurrentThread(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, TRUE, &mut token)
+        };
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(ERROR_NO_TOKEN as i32) {
+                return Err(error);
+            }
+
+            // The thread does not have its own token, so we give it a copy of
+            // the process one. This way adjustments do not affect other threads.
+            //
+            // SAFETY: This function is safe to call with any level. We verify
+            // the result below.
+            if unsafe { ImpersonateSelf(SecurityImpersonation) } == FALSE {
+                return Err(std::io::Error::last_os_error());
+            }
+            impersonating = true;
+
+            // SAFETY: Same as above.
+            let status = unsafe {
+                OpenThreadToken(GetCurrentThread(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, TRUE, &mut token)
+            };
+            if status == FALSE {
+                let error = std::io::Error::last_os_error();
+
+                // SAFETY: We impersonate the process (see above).
+                unsafe {
+                    RevertToSelf();
+                }
+
+                return Err(error);
+            }
+        }
+
+        // From now on, the guard takes care of cleaning up. The previous state
+        // is empty until the adjustment succeeds, so there is nothing to restore
+        // before that.
+        let mut guard = Guard {
+            token,
+            // SAFETY: The structure consists of plain integers for which zero
+            // is a valid value.
+            previous: unsafe { std::mem::zeroed() },
+            impersonating,
+        };
+
+        let new = TOKEN_PRIVILEGES {
+            PrivilegeCount: 1,
+            Privileges: [LUID_AND_ATTRIBUTES {
+                Luid: luid,
+                Attributes: SE_PRIVILEGE_ENABLED,
+            }],
+        };
+        let mut len = 0;
+
+        // SAFETY: The token handle is valid and was opened with the adjustment
+        // right. The new state contains exactly one privilege, so the previous
+        // state also fits one. We verify the result below.
+        let status = unsafe {
+            AdjustTokenPrivileges(
+                guard.token,
+                FALSE,
+                &new,
+                std::mem::size_of::<TOKEN_PRIVILEGES>() as u32,
+                &mut guard.previous,
+                &mut len,
+            )
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // The function succeeds even if the token does not hold the privilege,
+        // in which case it reports that through the last error [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/securitybaseapi/nf-securitybaseapi-adjusttokenprivileges
+        let error = std::io::Error::last_os_error();
+        if error.raw_os_error() == Some(ERROR_NOT_ALL_ASSIGNED as i32) {
+            return Err(error);
+        }
+
+        Ok(guard)
+    }
+}
+
+impl Drop for Guard {
+
+    fn drop(&mut self) {
+        // SAFETY: The token handle is valid and owned by us. The previous state
+        // is either empty or filled by the successful adjustment. There is not
+        // much we can do if restoring fails, so we only log the failure.
+        unsafe {
+            if self.previous.PrivilegeCount > 0 {
+                let status = AdjustTokenPrivileges(
+                    self.token,
+                    FALSE,
+                    &self.previous,
+                    0,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                );
+                if status == FALSE {
+                    let error = std::io::Error::last_os_error();
+                    log::error!("failed to restore privilege state: {error}");
+                }
+            }
+
+            CloseHandle(self.token);
+
+            if self.impersonating && RevertToSelf() == FALSE {
+                let error = std::io::Error::last_os_error();
+                log::error!("failed to revert impersonation: {error}");
+            }
+        }
+    }
+}
+
+/// An error indicating that a privilege could not be enabled.
+#[derive(Debug)]
+pub struct PrivilegeError {
+    /// Name of the privilege that could not be enabled.
+    name: String,
+    /// Underlying error (always a permission denial).
+    error: std::io::Error,
+}
+
+impl std::fmt::Display for PrivilegeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "failed to enable privilege '{}' (it is held only by administrators \
+             and the agent might not be running as one): {}",
+            self.name, self.error
+        }
+    }
+}
+
+impl std::error::Error for PrivilegeError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn with_privilege_backup_elevated() {
+        let token = ospect::token::of_process(std::process::id())
+            .unwrap();
+
+        let held = token.privileges().iter()
+            .any(|privilege| privilege.name() == SE_BACKUP_NAME);
+        if !held {
+            // We are not running elevated, so there is nothing to enable.
+            return;
+        }
+
+        assert_eq!(thread_privilege_enabled(SE_BACKUP_NAME), None);
+
+        let enabled = with_privilege(SE_BACKUP_NAME, || {
+            thread_privilege_enabled(SE_BACKUP_NAME)
+        }).unwrap();
+        assert_eq!(enabled, Some(true));
+
+        // The thread did not have a token before, so it should not have one
+        // after the impersonation is reverted.
+        assert_eq!(thread_privilege_enabled(SE_BACKUP_NAME), None);
+    }
+
+    #[test]
+    fn with_privilege_nested_restores() {
+        let token = ospect::token::of_process(std::process::id())
+            .unwrap();
+
+        let held = token.privileges().iter()
+            .any(|privilege| privilege.name() == SE_BACKUP_NAME);
+        if !held {
+            return;
+        }
+
+        let enabled = with_privilege(SE_SECURITY_NAME, || {
+            let inner = with_privilege(SE_BACKUP_NAME, || {
+                thread_privilege_enabled(SE_BACKUP_NAME)
+            }).unwrap();
+            assert_eq!(inner, Some(true));
+
+            thread_privilege_enabled(SE_BACKUP_NAME)
+        }).unwrap();
+        assert_eq!(enabled, Some(false));
+    }
+
+    #[test]
+    fn with_privilege_unknown() {
+        let error = with_privilege("SeFooBarPrivilege", || ())
+            .unwrap_err();
+
+        let error = crate::session::Error::action(error);
+        assert_eq!(error.code(), crate::session::ErrorCode::PermissionDenied);
+
+        assert_eq!(thread_privilege_enabled(SE_BACKUP_NAME), None);
+    }
+
+    /// Returns whether the privilege is enabled on the current thread token.
+    ///
+    /// `None` is returned if the thread does not have its own token.
+    fn thread_privilege_enabled(name: &str) -> Option<bool> {
+        let name_wide = name.encode_utf16()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+
+        let mut token = std::ptr::null_mut();
+
+        // SAFETY: The pseudo-handle of the current thread is always valid.
+        let status = unsafe {
+            OpenThreadToken(GetCurrentThread(), TOKEN_QUERY, TRUE, &mut token)
+        };
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            assert_eq!(error.raw_os_error(), Some(ERROR_NO_TOKEN as i32));
+            return None;
+        }
+
+        let mut set = PRIVILEGE_SET {
+            PrivilegeCount: 1,
+            Control: PRIVILEGE_SET_ALL_NECESSARY,
+            Privilege: [LUID_AND_ATTRIBUTES {
+                Luid: LUID {
+                    LowPart: 0,
+                    HighPart: 0,
+                },
+                Attributes: 0,
+            }],
+        };
+        let mut result = FALSE;
+
+        // SAFETY: We pass valid null-terminated name, a valid token handle and
+        // a privilege set with exactly one entry.
+        unsafe {
+            assert_ne!(LookupPrivilegeValueW(std::ptr::null(), name_wide.as_ptr(), &mut set.Privilege[0].Luid), FALSE);
+            assert_ne!(PrivilegeCheck(token, &mut set, &mut result), FALSE);
+            CloseHandle(token);
+        }
+
+        Some(result != FALSE)
+    }
+}
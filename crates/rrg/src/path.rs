@@ -0,0 +1,274 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Conversions between system paths and their wire representation.
+//!
+//! Paths are sent as raw bytes: as they are on Unix and [WTF-8][wtf8] encoded
+//! on Windows (so that unpaired surrogates survive the round trip). The only
+//! paths that are rejected are those with an embedded NUL byte, as they cannot
+//! be passed to any system call and would be silently truncated otherwise.
+//!
+//! All paths coming from and going to the server should be converted with the
+//! functions of this module.
+//!
+//! [wtf8]: https://simonsapin.github.io/wtf-8
+
+use std::path::PathBuf;
+
+/// Interprets the given bytes as a system path.
+///
+/// An error is returned if the bytes contain a NUL byte or (on Windows) if
+/// they are not a valid WTF-8 sequence.
+pub fn from_bytes(bytes: Vec<u8>) -> Result<PathBuf, ParseError> {
+    if let Some(offset) = bytes.iter().position(|byte| *byte == 0) {
+        return Err(ParseError {
+            kind: ParseErrorKind::EmbeddedNul(offset),
+        });
+    }
+
+    rrg_proto::path::from_bytes(bytes)
+        .map_err(|error| ParseError {
+            kind: ParseErrorKind::Encoding(error),
+        })
+}
+
+/// Interprets the given path message as a system path.
+///
+/// See [`from_bytes`] for the details on possible errors.
+pub fn from_proto(mut proto: rrg_proto::fs::Path) -> Result<PathBuf, ParseError> {
+    from_bytes(proto.take_raw_bytes())
+}
+
+/// Serializes the given system path to its wire representation.
+pub fn into_bytes(path: PathBuf) -> Vec<u8> {
+    rrg_proto::path::into_bytes(path)
+}
+
+/// Serializes the given system path to a path message.
+pub fn into_proto(path: PathBuf) -> rrg_proto::fs::Path {
+    let mut proto = rrg_proto::fs::Path::new();
+    proto.set_raw_bytes(into_bytes(path));
+
+    proto
+}
+
+/// Unicode normalization form to apply to paths before reporting them.
+///
+/// Some filesystems (notably the ones on macOS) store names decomposed while
+/// indicators on the server side are usually composed, so paths might have to
+/// be normalized for them to match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Paths are reported as they are.
+    #[default]
+    None,
+    /// Paths are reported in the canonical composition form (NFC).
+    Nfc,
+    /// Paths are reported in the canonical decomposition form (NFD).
+    Nfd,
+}
+
+impl Normalization {
+
+    /// Normalizes the given path to this form.
+    ///
+    /// Paths that are not valid Unicode are returned as they are. Note that the
+    /// normalized path might not point to the same file anymore, so it should
+    /// be used only for reporting.
+    pub fn apply(self, path: PathBuf) -> PathBuf {
+        use unicode_normalization::UnicodeNormalization as _;
+
+        let Some(string) = path.to_str() else {
+            return path;
+        };
+
+        match self {
+            Normalization::None => path,
+            Normalization::Nfc if unicode_normalization::is_nfc(string) => path,
+            Normalization::Nfc => PathBuf::from(string.nfc().collect::<String>()),
+            Normalization::Nfd if unicode_normalization::is_nfd(string) => path,
+            Normalization::Nfd => PathBuf::from(string.nfd().collect::<String>()),
+        }
+    }
+}
+
+impl From<rrg_proto::fs::UnicodeNormalization> for Normalization {
+
+    fn from(proto: rrg_proto::fs::UnicodeNormalization) -> Normalization {
+        use rrg_proto::fs::UnicodeNormalization::*;
+
+        match proto {
+            NONE => Normalization::None,
+            NFC => Normalization::Nfc,
+            NFD => Normalization::Nfd,
+        }
+    }
+}
+
+/// An error that might occur when parsing paths.
+#[derive(Debug)]
+pub struct ParseError {
+    /// Detailed information about the error.
+    kind: ParseErrorKind,
+}
+
+/// Kinds of errors that might occur when parsing paths.
+#[derive(Debug)]
+enum ParseErrorKind {
+    /// The path contains a NUL byte at the given offset.
+    EmbeddedNul(usize),
+    /// The path bytes are not valid for the system path encoding.
+    Encoding(rrg_proto::path::ParseError),
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.kind {
+            ParseErrorKind::EmbeddedNul(offset) => {
+                write!(fmt, "embedded NUL byte at offset {offset}")
+            }
+            ParseErrorKind::Encoding(ref error) => {
+                write!(fmt, "invalid path encoding: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            ParseErrorKind::EmbeddedNul(_) => None,
+            ParseErrorKind::Encoding(ref error) => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn from_bytes_ascii() {
+        let path = from_bytes(b"foo/bar".to_vec())
+            .unwrap();
+
+        assert_eq!(path, PathBuf::from("foo/bar"));
+    }
+
+    #[test]
+    fn from_bytes_embedded_nul() {
+        let error = from_bytes(b"foo\0bar".to_vec())
+            .unwrap_err();
+
+        assert!(matches!(error.kind, ParseErrorKind::EmbeddedNul(3)));
+    }
+
+    #[test]
+    fn from_bytes_trailing_nul() {
+        let error = from_bytes(b"foo\0".to_vec())
+            .unwrap_err();
+
+        assert!(matches!(error.kind, ParseErrorKind::EmbeddedNul(3)));
+    }
+
+    #[test]
+    fn into_bytes_from_bytes_unicode() {
+        let path = PathBuf::from("zażółć/gęślą/jaźń");
+
+        assert_eq!(from_bytes(into_bytes(path.clone())).unwrap(), path);
+    }
+
+    #[test]
+    fn into_proto_from_proto() {
+        let path = PathBuf::from("foo").join("bar");
+
+        assert_eq!(from_proto(into_proto(path.clone())).unwrap(), path);
+    }
+
+    #[test]
+    fn normalization_nfc() {
+        let path = PathBuf::from("foo").join("cafe\u{301}");
+
+        assert_eq!(Normalization::Nfc.apply(path), PathBuf::from("foo").join("caf\u{e9}"));
+    }
+
+    #[test]
+    fn normalization_nfd() {
+        let path = PathBuf::from("foo").join("caf\u{e9}");
+
+        assert_eq!(Normalization::Nfd.apply(path), PathBuf::from("foo").join("cafe\u{301}"));
+    }
+
+    #[test]
+    fn normalization_none() {
+        let path = PathBuf::from("foo").join("cafe\u{301}");
+
+        assert_eq!(Normalization::None.apply(path.clone()), path);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn normalization_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let path = PathBuf::from(std::ffi::OsStr::from_bytes(b"cafe\xcc\x81\xff"));
+
+        assert_eq!(Normalization::Nfc.apply(path.clone()), path);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn from_bytes_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let path = from_bytes(b"foo/\xff\xfe/bar".to_vec())
+            .unwrap();
+
+        assert_eq!(path.as_os_str().as_bytes(), b"foo/\xff\xfe/bar");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn into_bytes_from_bytes_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let path = PathBuf::from(std::ffi::OsStr::from_bytes(b"\x80foo\xc3"));
+
+        assert_eq!(into_bytes(path.clone()), b"\x80foo\xc3");
+        assert_eq!(from_bytes(into_bytes(path.clone())).unwrap(), path);
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn into_bytes_from_bytes_lone_surrogate() {
+        use std::os::windows::ffi::{OsStrExt as _, OsStringExt as _};
+
+        // `C:\foo\<lead surrogate>bar\<trail surrogate>`.
+        let units = [
+            0x0043, 0x003A, 0x005C, 0x0066, 0x006F, 0x006F, 0x005C,
+            0xD800, 0x0062, 0x0061, 0x0072, 0x005C, 0xDC00,
+        ];
+        let path = PathBuf::from(std::ffi::OsString::from_wide(&units));
+
+        let bytes = into_bytes(path.clone());
+        assert!(std::str::from_utf8(&bytes).is_err());
+
+        let path = from_bytes(bytes)
+            .unwrap();
+        assert_eq!(path.as_os_str().encode_wide().collect::<Vec<_>>(), units);
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn from_bytes_invalid_wtf8() {
+        let error = from_bytes(b"foo\xff".to_vec())
+            .unwrap_err();
+
+        assert!(matches!(error.kind, ParseErrorKind::Encoding(_)));
+    }
+}
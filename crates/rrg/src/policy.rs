@@ -0,0 +1,326 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Restrictions on what the agent is allowed to do.
+//!
+//! The policy is given through agent arguments and consists of:
+//!
+//!   * an allowlist of actions (if empty, all actions are allowed),
+//!   * a denylist of actions (which takes precedence over the allowlist),
+//!   * path prefixes that file actions are not allowed to access (either for
+//!     all actions or for a specific one).
+//!
+//! Actions are checked before their arguments are parsed, paths are checked
+//! against the arguments of the action before it is executed.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::request::Action;
+
+/// Path prefix that actions are not allowed to access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRule {
+    /// Action the rule applies to (or all actions if not specified).
+    action: Option<Action>,
+    /// Path prefix access to which is denied.
+    prefix: PathBuf,
+}
+
+impl PathRule {
+
+    /// Checks whether the rule denies the given action access to the path.
+    fn denies(&self, action: Action, path: &Path) -> bool {
+        if self.action.is_some_and(|rule_action| rule_action != action) {
+            return false;
+        }
+
+        // We check both the path as given and its canonical form (if it
+        // exists), so that the rule cannot be circumvented with `..` or with
+        // symlinks.
+        if normalize(path).starts_with(&self.prefix) {
+            return true;
+        }
+        if let Ok(path) = std::fs::canonicalize(path) {
+            if path.starts_with(&self.prefix) {
+                return true;
+            }
+        }
+
+        // Devices without a drive letter (e.g. volume shadow copies) contain
+        // the same files as the volumes they correspond to. We cannot easily
+        // tell which volume it is, so we conservatively apply the rule to the
+        // path relative to the device root as if it was on any volume.
+        if let Some((_, rest)) = crate::fs::split_device_path(path) {
+            if normalize(&rest).starts_with(strip_root(&self.prefix)) {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl std::str::FromStr for PathRule {
+
+    type Err = std::convert::Infallible;
+
+    /// Parses a rule in the `[ACTION=]PATH` format.
+    ///
+    /// If the part before the first `=` is not a known action name, the whole
+    /// string is considered to be a path that the rule applies to.
+    fn from_str(string: &str) -> Result<PathRule, std::convert::Infallible> {
+        if let Some((action, prefix)) = string.split_once('=') {
+            if let Ok(action) = action.parse::<Action>() {
+                return Ok(PathRule {
+                    action: Some(action),
+                    prefix: normalize(Path::new(prefix)),
+                });
+            }
+        }
+
+        Ok(PathRule {
+            action: None,
+            prefix: normalize(Path::new(string)),
+        })
+    }
+}
+
+/// Checks whether the agent policy allows to execute the given action.
+pub fn check_action(
+    args: &crate::args::Args,
+    action: Action,
+) -> Result<(), PolicyDeniedError> {
+    let allowed = args.allowed_actions.is_empty() ||
+        args.allowed_actions.contains(&action);
+
+    if !allowed || args.denied_actions.contains(&action) {
+        return Err(PolicyDeniedError {
+            action,
+            path: None,
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks whether the agent policy allows the given action to access the path.
+pub fn check_path(
+    args: &crate::args::Args,
+    action: Action,
+    path: &Path,
+) -> Result<(), PolicyDeniedError> {
+    if args.denied_paths.iter().any(|rule| rule.denies(action, path)) {
+        return Err(PolicyDeniedError {
+            action,
+            path: Some(path.to_path_buf()),
+        });
+    }
+
+    Ok(())
+}
+
+/// Normalizes the path lexically (without accessing the filesystem).
+///
+/// Current directory components are dropped and parent directory components
+/// remove the preceding component (if any).
+///
+/// Paths in the device namespace keep the device part intact, so that parent
+/// directory components cannot escape the root of the device.
+fn normalize(path: &Path) -> PathBuf {
+    if let Some((device, rest)) = crate::fs::split_device_path(path) {
+        return device.join(normalize(&rest));
+    }
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Strips the prefix (e.g. the drive letter) and the root from the path.
+fn strip_root(path: &Path) -> &Path {
+    let mut components = path.components();
+    while let Some(Component::Prefix(_) | Component::RootDir) = components.clone().next() {
+        components.next();
+    }
+
+    components.as_path()
+}
+
+/// An error indicating that something is not allowed by the agent policy.
+#[derive(Debug)]
+pub struct PolicyDeniedError {
+    /// Action that was denied.
+    action: Action,
+    /// Path access to which was denied (if the action itself is allowed).
+    path: Option<PathBuf>,
+}
+
+impl std::fmt::Display for PolicyDeniedError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write! {
+                fmt,
+                "access to '{}' denied by policy for action '{}'",
+                path.display(), self.action,
+            },
+            None => write!(fmt, "action '{}' denied by policy", self.action),
+        }
+    }
+}
+
+impl std::error::Error for PolicyDeniedError {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args() -> crate::args::Args {
+        crate::args::Args::default()
+    }
+
+    fn path_rule(string: &str) -> PathRule {
+        string.parse().unwrap()
+    }
+
+    #[test]
+    fn check_action_empty_policy() {
+        assert!(check_action(&args(), Action::GetFileContents).is_ok());
+    }
+
+    #[test]
+    fn check_action_allowlist() {
+        let mut args = args();
+        args.allowed_actions = vec![Action::GetSystemMetadata];
+
+        assert!(check_action(&args, Action::GetSystemMetadata).is_ok());
+        assert!(check_action(&args, Action::ExecuteSignedCommand).is_err());
+    }
+
+    #[test]
+    fn check_action_denylist() {
+        let mut args = args();
+        args.denied_actions = vec![Action::ExecuteSignedCommand];
+
+        assert!(check_action(&args, Action::GetSystemMetadata).is_ok());
+        assert!(check_action(&args, Action::ExecuteSignedCommand).is_err());
+    }
+
+    #[test]
+    fn check_action_denylist_precedence() {
+        let mut args = args();
+        args.allowed_actions = vec![Action::ExecuteSignedCommand];
+        args.denied_actions = vec![Action::ExecuteSignedCommand];
+
+        assert!(check_action(&args, Action::ExecuteSignedCommand).is_err());
+    }
+
+    #[test]
+    fn path_rule_from_str_action() {
+        assert_eq!(path_rule("get_file_contents=/etc/shadow"), PathRule {
+            action: Some(Action::GetFileContents),
+            prefix: PathBuf::from("/etc/shadow"),
+        });
+    }
+
+    #[test]
+    fn path_rule_from_str_no_action() {
+        assert_eq!(path_rule("/etc/shadow"), PathRule {
+            action: None,
+            prefix: PathBuf::from("/etc/shadow"),
+        });
+        assert_eq!(path_rule("/foo=bar"), PathRule {
+            action: None,
+            prefix: PathBuf::from("/foo=bar"),
+        });
+    }
+
+    #[test]
+    fn check_path_prefix() {
+        let mut args = args();
+        args.denied_paths = vec![path_rule("/etc/shadow")];
+
+        let action = Action::GetFileContents;
+        assert!(check_path(&args, action, Path::new("/etc/shadow")).is_err());
+        assert!(check_path(&args, action, Path::new("/etc/shadow/foo")).is_err());
+        assert!(check_path(&args, action, Path::new("/etc/shadow-")).is_ok());
+        assert!(check_path(&args, action, Path::new("/etc/passwd")).is_ok());
+    }
+
+    #[test]
+    fn check_path_parent_dir() {
+        let mut args = args();
+        args.denied_paths = vec![path_rule("/etc/shadow")];
+
+        let path = Path::new("/etc/foo/../shadow");
+        assert!(check_path(&args, Action::GetFileContents, path).is_err());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn check_path_symlink() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir_path = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::write(tempdir_path.join("secret"), b"")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir_path.join("secret"), tempdir_path.join("link"))
+            .unwrap();
+
+        let mut args = args();
+        args.denied_paths = vec![path_rule(&tempdir_path.join("secret").display().to_string())];
+
+        let path = tempdir_path.join("link");
+        assert!(check_path(&args, Action::GetFileContents, &path).is_err());
+    }
+
+    #[test]
+    fn check_path_specific_action() {
+        let mut args = args();
+        args.denied_paths = vec![path_rule("get_file_contents=/etc/shadow")];
+
+        let path = Path::new("/etc/shadow");
+        assert!(check_path(&args, Action::GetFileContents, path).is_err());
+        assert!(check_path(&args, Action::GetFileMetadata, path).is_ok());
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn check_path_device_path_parent_dir() {
+        let mut args = args();
+        args.denied_paths = vec![path_rule("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1")];
+
+        // Parent directory components must not escape the device root.
+        let path = Path::new("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy1\\..\\..\\foo");
+        assert!(check_path(&args, Action::GetFileContents, path).is_err());
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn check_path_shadow_copy() {
+        let mut args = args();
+        args.denied_paths = vec![path_rule("C:\\Windows\\System32\\config\\SAM")];
+
+        let action = Action::GetFileContents;
+
+        let path = Path::new("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3\\Windows\\System32\\config\\SAM");
+        assert!(check_path(&args, action, path).is_err());
+
+        let path = Path::new("\\\\?\\GLOBALROOT\\Device\\HarddiskVolumeShadowCopy3\\Windows\\System32\\config\\SYSTEM");
+        assert!(check_path(&args, action, path).is_ok());
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Redaction of user-identifying parts of paths sent to the server.
+//!
+//! Redaction is configured through agent arguments as a list of patterns (regu-
+//! lar expressions) matched against paths. Parts of paths matched by the first
+//! capture group of a pattern (or by the whole pattern if it has no groups) are
+//! replaced with a token derived from a salted hash of the original. The salt
+//! is random and generated per request, so the same original is replaced with
+//! the same token within a request but tokens are not linkable across requests.
+//!
+//! For example, with the `^/home/([^/]+)` pattern, `/home/alice/.bashrc` and
+//! `/home/alice/.profile` become `/home/<c0ffee…>/.bashrc` and
+//! `/home/<c0ffee…>/.profile` respectively (with the same token).
+
+use std::borrow::Cow;
+
+/// Number of bytes of the salted hash included in a token.
+const TOKEN_HASH_LEN: usize = 8;
+
+/// Pattern of paths parts to redact.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: regex::bytes::Regex,
+}
+
+impl std::str::FromStr for Pattern {
+
+    type Err = regex::Error;
+
+    fn from_str(string: &str) -> Result<Pattern, regex::Error> {
+        Ok(Pattern {
+            regex: regex::bytes::Regex::new(string)?,
+        })
+    }
+}
+
+/// Redactor of paths within a single request.
+#[derive(Clone)]
+pub struct Redactor {
+    /// Patterns of path parts to redact.
+    patterns: Vec<regex::bytes::Regex>,
+    /// Salt used for hashing the redacted parts.
+    salt: [u8; 16],
+}
+
+impl Redactor {
+
+    /// Creates a new redactor using the given patterns and a random salt.
+    pub fn new(patterns: &[Pattern]) -> Redactor {
+        Redactor::with_salt(patterns, random_salt())
+    }
+
+    /// Creates a new redactor using the given patterns and salt.
+    pub fn with_salt(patterns: &[Pattern], salt: [u8; 16]) -> Redactor {
+        Redactor {
+            patterns: patterns.iter()
+                .map(|pattern| pattern.regex.clone())
+                .collect(),
+            salt,
+        }
+    }
+
+    /// Redacts the given path (given as raw bytes).
+    ///
+    /// Paths not matched by any of the patterns are returned as they are.
+    pub fn redact_path<'p>(&self, path: &'p [u8]) -> Cow<'p, [u8]> {
+        let mut path = Cow::Borrowed(path);
+
+        for pattern in &self.patterns {
+            let mut redacted = Vec::new();
+            let mut last = 0;
+
+            for captures in pattern.captures_iter(&path) {
+                let part = match captures.get(1) {
+                    Some(part) => part,
+                    None => captures.get(0).expect("no implicit group"),
+                };
+                // Empty matches have nothing to redact and would only insert
+                // meaningless tokens into the path.
+                if part.is_empty() {
+                    continue;
+                }
+
+                redacted.extend_from_slice(&path[last..part.start()]);
+                redacted.extend_from_slice(&self.token(part.as_bytes()));
+                last = part.end();
+            }
+
+            if last == 0 {
+                continue;
+            }
+
+            redacted.extend_from_slice(&path[last..]);
+            path = Cow::Owned(redacted);
+        }
+
+        path
+    }
+
+    /// Redacts all paths in the given message (recursively).
+    ///
+    /// Paths are all the `rrg.fs.Path` messages: both singular and repeated
+    /// fields are supported (map values are not).
+    pub fn redact_message(&self, message: &mut dyn protobuf::MessageDyn) {
+        use protobuf::reflect::{ReflectValueBox, ReflectValueRef, RuntimeFieldType, RuntimeType};
+
+        if self.patterns.is_empty() {
+            return;
+        }
+
+        if let Some(path) = message.downcast_mut::<rrg_proto::fs::Path>() {
+            if let Cow::Owned(raw_bytes) = self.redact_path(path.raw_bytes()) {
+                path.set_raw_bytes(raw_bytes);
+            }
+            return;
+        }
+
+        for field in message.descriptor_dyn().fields() {
+            match field.runtime_field_type() {
+                RuntimeFieldType::Singular(RuntimeType::Message(_)) if field.has_field(message) => {
+                    self.redact_message(field.mut_message(message));
+                }
+                RuntimeFieldType::Repeated(RuntimeType::Message(_)) => {
+                    let mut repeated = field.mut_repeated(message);
+                    for i in 0..repeated.len() {
+                        let ReflectValueRef::Message(value) = repeated.get(i) else {
+                            continue;
+                        };
+
+                        let mut value = value.clone_box();
+                        self.redact_message(&mut *value);
+                        repeated.set(i, ReflectValueBox::Message(value));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns a token to replace the given path part with.
+    fn token(&self, part: &[u8]) -> Vec<u8> {
+        use sha2::Digest as _;
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(self.salt);
+        hasher.update(part);
+        let hash = hasher.finalize();
+
+        let mut token = Vec::with_capacity(2 + 2 * TOKEN_HASH_LEN);
+        token.push(b'<');
+        for byte in &hash[..TOKEN_HASH_LEN] {
+            token.extend_from_slice(format!("{byte:02x}").as_bytes());
+        }
+        token.push(b'>');
+
+        token
+    }
+}
+
+/// Generates a random salt.
+fn random_salt() -> [u8; 16] {
+    use std::hash::BuildHasher as _;
+
+    // We do not have a dependency providing randomness, but the standard library
+    // seeds `RandomState` keys from the operating system. Hashes made with them
+    // are unpredictable without knowing the keys, which is all we need.
+    let state = std::collections::hash_map::RandomState::new();
+
+    let mut salt = [0; 16];
+    salt[..8].copy_from_slice(&state.hash_one(0u8).to_le_bytes());
+    salt[8..].copy_from_slice(&state.hash_one(1u8).to_le_bytes());
+
+    salt
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn redactor(patterns: &[&str]) -> Redactor {
+        let patterns = patterns.iter()
+            .map(|pattern| pattern.parse().unwrap())
+            .collect::<Vec<Pattern>>();
+
+        Redactor::with_salt(&patterns, [0xf0; 16])
+    }
+
+    fn token(path: &[u8], prefix: &[u8]) -> Vec<u8> {
+        let rest = path.strip_prefix(prefix)
+            .unwrap();
+        let end = rest.iter().position(|byte| *byte == b'>')
+            .unwrap();
+
+        rest[..=end].to_vec()
+    }
+
+    #[test]
+    fn redact_path_home() {
+        let redactor = redactor(&["^/home/([^/]+)"]);
+
+        let bashrc = redactor.redact_path(b"/home/alice/.bashrc");
+        let profile = redactor.redact_path(b"/home/alice/.profile");
+
+        let token = token(&bashrc, b"/home/");
+        assert!(token.starts_with(b"<"));
+        assert!(!token.windows(5).any(|window| window == b"alice"));
+
+        assert_eq!(bashrc, [b"/home/", &token[..], b"/.bashrc"].concat());
+        assert_eq!(profile, [b"/home/", &token[..], b"/.profile"].concat());
+    }
+
+    #[test]
+    fn redact_path_different_users() {
+        let redactor = redactor(&["^/home/([^/]+)"]);
+
+        let alice = redactor.redact_path(b"/home/alice/.bashrc");
+        let bob = redactor.redact_path(b"/home/bob/.bashrc");
+
+        assert_ne!(token(&alice, b"/home/"), token(&bob, b"/home/"));
+    }
+
+    #[test]
+    fn redact_path_different_salts() {
+        let pattern = "^/home/([^/]+)".parse::<Pattern>()
+            .unwrap();
+
+        let redactor_foo = Redactor::with_salt(std::slice::from_ref(&pattern), [0xf0; 16]);
+        let redactor_bar = Redactor::with_salt(&[pattern], [0x0f; 16]);
+
+        assert_ne! {
+            redactor_foo.redact_path(b"/home/alice"),
+            redactor_bar.redact_path(b"/home/alice"),
+        };
+    }
+
+    #[test]
+    fn redact_path_not_matching() {
+        let redactor = redactor(&["^/home/([^/]+)"]);
+
+        let path = redactor.redact_path(b"/etc/passwd");
+        assert!(matches!(path, Cow::Borrowed(b"/etc/passwd")));
+    }
+
+    #[test]
+    fn redact_path_no_group() {
+        let redactor = redactor(&["alice"]);
+
+        let path = redactor.redact_path(b"/tmp/alice/alice.txt");
+        let token = token(&path, b"/tmp/");
+        assert_eq!(path, [b"/tmp/", &token[..], b"/", &token[..], b".txt"].concat());
+    }
+
+    #[test]
+    fn redact_path_no_patterns() {
+        let redactor = redactor(&[]);
+
+        let path = redactor.redact_path(b"/home/alice/.bashrc");
+        assert_eq!(path, &b"/home/alice/.bashrc"[..]);
+    }
+
+    #[test]
+    fn redact_message_nested() {
+        let redactor = redactor(&["^/home/([^/]+)"]);
+
+        let mut proto = rrg_proto::get_file_metadata::Result::new();
+        proto.set_path(std::path::PathBuf::from("/home/alice/foo").into());
+        proto.set_symlink(std::path::PathBuf::from("/home/alice/bar").into());
+
+        redactor.redact_message(&mut proto);
+
+        let path = proto.path().raw_bytes();
+        let symlink = proto.symlink().raw_bytes();
+
+        let token = token(path, b"/home/");
+        assert_eq!(path, [b"/home/", &token[..], b"/foo"].concat());
+        assert_eq!(symlink, [b"/home/", &token[..], b"/bar"].concat());
+    }
+
+    #[test]
+    fn redact_message_repeated() {
+        let redactor = redactor(&["^/home/([^/]+)"]);
+
+        let mut proto = rrg_proto::scan_files_yara::Args::new();
+        proto.mut_paths().push(std::path::PathBuf::from("/home/alice/foo").into());
+        proto.mut_paths().push(std::path::PathBuf::from("/etc/passwd").into());
+
+        redactor.redact_message(&mut proto);
+
+        let path = proto.paths()[0].raw_bytes();
+        let token = token(path, b"/home/");
+        assert_eq!(path, [b"/home/", &token[..], b"/foo"].concat());
+        assert_eq!(proto.paths()[1].raw_bytes(), b"/etc/passwd");
+    }
+}
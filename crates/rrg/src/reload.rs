@@ -0,0 +1,350 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Reloading of the agent configuration at runtime.
+//!
+//! If the agent is configured with a file (see the [`config`] module), it is
+//! reloaded on `SIGHUP` on Unix and whenever its modification time changes on
+//! Windows (where there are no signals, so the file is polled instead). The
+//! configuration is re-parsed and validated on the reloader thread and only if
+//! it is valid it replaces the current one: an invalid file is reported and
+//! the agent keeps using the previous configuration.
+//!
+//! The main loop takes a snapshot of the current configuration for every
+//! request, so a request is always handled with a consistent configuration
+//! even if it is reloaded in the meantime.
+//!
+//! Some settings are used only at startup and cannot change afterwards: the
+//! state directory, the logging destinations, the heartbeat rate and resource
+//! limits. Changes to them are reported as warnings and ignored until the
+//! agent is restarted.
+//!
+//! [`config`]: crate::config
+
+use std::sync::{Arc, RwLock};
+
+use log::{error, info, warn};
+
+use crate::args::Args;
+
+/// Interval at which the reloader checks whether to reload the configuration.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Shared handle to the current configuration of the agent.
+#[derive(Clone)]
+pub struct Handle {
+    args: Arc<RwLock<Arc<Args>>>,
+}
+
+impl Handle {
+
+    /// Creates a new handle with the given initial configuration.
+    pub fn new(args: Args) -> Handle {
+        Handle {
+            args: Arc::new(RwLock::new(Arc::new(args))),
+        }
+    }
+
+    /// Returns a snapshot of the current configuration.
+    pub fn load(&self) -> Arc<Args> {
+        // The lock is held only to clone or replace the pointer, so it cannot
+        // be poisoned by a panic in the middle of an update.
+        Arc::clone(&self.args.read().unwrap())
+    }
+
+    /// Replaces the current configuration with the given one.
+    fn store(&self, args: Args) {
+        *self.args.write().unwrap() = Arc::new(args);
+    }
+}
+
+/// Spawns the reloader of the configuration behind the given handle.
+///
+/// Nothing is done if the agent was not given a configuration file.
+pub fn init(handle: &Handle) {
+    let Some(path) = handle.load().config.clone() else {
+        return;
+    };
+
+    let mut watcher = match sys::Watcher::new(&path) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            error!("failed to watch configuration at '{}': {error}", path.display());
+            return;
+        }
+    };
+
+    let handle = handle.clone();
+    let result = std::thread::Builder::new()
+        .name(String::from("reload"))
+        .spawn(move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if watcher.changed() {
+                reload(&handle);
+            }
+        });
+    if let Err(error) = result {
+        error!("failed to spawn configuration reloader: {error}");
+    }
+}
+
+/// Reloads the configuration behind the given handle.
+fn reload(handle: &Handle) {
+    // The command line is the same one that was successfully parsed at the
+    // startup, so it cannot fail to parse now.
+    let args = std::env::args().collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let (command, flags) = args.split_first()
+        .expect("no command name");
+    let flags = <Args as argh::FromArgs>::from_args(&[command], flags)
+        .expect("invalid command-line arguments");
+
+    reload_with(handle, flags, |var| std::env::var(var).ok());
+}
+
+/// Reloads the configuration behind the given handle on top of the given flags.
+///
+/// Environment variables are looked up with the given function.
+fn reload_with<F>(handle: &Handle, flags: Args, var: F)
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let current = handle.load();
+    match configure(flags, &current, var) {
+        Ok(args) => {
+            crate::log::set_verbosity(args.verbosity);
+            handle.store(args);
+            info!("configuration reloaded");
+        }
+        Err(error) => {
+            error!("invalid configuration, keeping the previous one: {error}");
+        }
+    }
+}
+
+/// Applies the configuration file of the current configuration to the flags.
+fn configure<F>(mut args: Args, current: &Args, var: F) -> Result<Args, crate::config::Error>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some(path) = &current.config {
+        let mut config = crate::config::load(path)?;
+        config.override_from_env(var);
+        for warning in config.warnings() {
+            warn!("{warning}");
+        }
+
+        config.apply(&mut args)?;
+    }
+
+    retain_static(&mut args, current);
+
+    Ok(args)
+}
+
+/// Restores settings that cannot change at runtime to their current values.
+fn retain_static(args: &mut Args, current: &Args) {
+    fn retain<T: PartialEq + Clone>(name: &str, value: &mut T, current: &T) {
+        if value != current {
+            warn!("'{name}' cannot be changed without a restart, ignoring");
+            value.clone_from(current);
+        }
+    }
+
+    retain("state_dir", &mut args.state_dir, &current.state_dir);
+    retain("log.to_stdout", &mut args.log_to_stdout, &current.log_to_stdout);
+    retain("log.to_file", &mut args.log_to_file, &current.log_to_file);
+    retain("transport.heartbeat_rate", &mut args.heartbeat_rate, &current.heartbeat_rate);
+    retain("limits.memory_limit", &mut args.memory_limit, &current.memory_limit);
+    retain("limits.low_priority", &mut args.low_priority, &current.low_priority);
+}
+
+#[cfg(target_family = "unix")]
+mod sys {
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Whether the configuration has been requested to be reloaded.
+    static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+    /// Watcher of reload requests sent with `SIGHUP`.
+    pub struct Watcher;
+
+    impl Watcher {
+
+        /// Installs the handler of the `SIGHUP` signal.
+        pub fn new(_: &std::path::Path) -> std::io::Result<Watcher> {
+            // SAFETY: The handler is a valid function that only performs an
+            // async-signal-safe operation (an atomic store). We verify the
+            // result below.
+            let result = unsafe {
+                libc::signal(libc::SIGHUP, handle as extern "C" fn(libc::c_int) as libc::sighandler_t)
+            };
+            if result == libc::SIG_ERR {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            Ok(Watcher)
+        }
+
+        /// Returns whether a reload has been requested since the last call.
+        pub fn changed(&mut self) -> bool {
+            RELOAD_REQUESTED.swap(false, Ordering::SeqCst)
+        }
+    }
+
+    /// Handles the `SIGHUP` signal.
+    extern "C" fn handle(_: libc::c_int) {
+        RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    use std::path::{Path, PathBuf};
+    use std::time::SystemTime;
+
+    /// Watcher of changes to the modification time of the configuration file.
+    pub struct Watcher {
+        /// Path to the configuration file.
+        path: PathBuf,
+        /// Modification time of the file at the time of the last check.
+        modified: Option<SystemTime>,
+    }
+
+    impl Watcher {
+
+        /// Creates a watcher of the given configuration file.
+        pub fn new(path: &Path) -> std::io::Result<Watcher> {
+            Ok(Watcher {
+                path: path.to_path_buf(),
+                modified: Some(modified(path)?),
+            })
+        }
+
+        /// Returns whether the file has been modified since the last call.
+        pub fn changed(&mut self) -> bool {
+            // If the file cannot be read, we try to reload it anyway so that
+            // the problem is reported (but only once).
+            let modified = modified(&self.path).ok();
+            if modified == self.modified {
+                return false;
+            }
+
+            self.modified = modified;
+            true
+        }
+    }
+
+    /// Returns the modification time of the given file.
+    fn modified(path: &Path) -> std::io::Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn args(flags: &[&str]) -> Args {
+        use argh::FromArgs as _;
+
+        Args::from_args(&["rrg"], flags)
+            .unwrap()
+    }
+
+    #[test]
+    fn reload_verbosity() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("rrg.toml");
+        let path_str = path.to_str()
+            .unwrap();
+
+        std::fs::write(&path, "[log]\nverbosity = 'warn'\n")
+            .unwrap();
+
+        let handle = Handle::new(args(&["--config", path_str, "--verbosity", "error"]));
+
+        std::fs::write(&path, "[log]\nverbosity = 'debug'\n")
+            .unwrap();
+
+        // The verbosity given on the command line takes precedence.
+        reload_with(&handle, args(&["--config", path_str, "--verbosity", "error"]), |_| None);
+        assert_eq!(handle.load().verbosity, log::LevelFilter::Error);
+
+        reload_with(&handle, args(&["--config", path_str]), |_| None);
+        assert_eq!(handle.load().verbosity, log::LevelFilter::Debug);
+        assert_eq!(crate::log::verbosity(), log::LevelFilter::Debug);
+    }
+
+    #[test]
+    fn reload_invalid() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("rrg.toml");
+        let path_str = path.to_str()
+            .unwrap();
+
+        std::fs::write(&path, "[limits]\ncommand_rate_limit = 10\n")
+            .unwrap();
+
+        let handle = Handle::new(args(&["--config", path_str]));
+        let snapshot = handle.load();
+
+        std::fs::write(&path, "[limits]\ncommand_rate_limit = 'foo'\n")
+            .unwrap();
+
+        reload_with(&handle, args(&["--config", path_str]), |_| None);
+        assert!(Arc::ptr_eq(&handle.load(), &snapshot));
+    }
+
+    #[test]
+    fn reload_keeps_snapshot() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("rrg.toml");
+        let path_str = path.to_str()
+            .unwrap();
+
+        std::fs::write(&path, "[policy]\ndenied_actions = []\n")
+            .unwrap();
+
+        let handle = Handle::new(args(&["--config", path_str]));
+        let snapshot = handle.load();
+
+        std::fs::write(&path, "[policy]\ndenied_actions = ['execute_signed_command']\n")
+            .unwrap();
+
+        reload_with(&handle, args(&["--config", path_str]), |_| None);
+        assert!(snapshot.denied_actions.is_empty());
+        assert!(!handle.load().denied_actions.is_empty());
+    }
+
+    #[test]
+    fn reload_state_dir_ignored() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("rrg.toml");
+        let path_str = path.to_str()
+            .unwrap();
+
+        std::fs::write(&path, "state_dir = '/var/lib/rrg'\n")
+            .unwrap();
+
+        let mut flags = args(&["--config", path_str]);
+        crate::config::load(&path).unwrap().apply(&mut flags)
+            .unwrap();
+        let handle = Handle::new(flags);
+
+        std::fs::write(&path, "state_dir = '/var/lib/foo'\n")
+            .unwrap();
+
+        reload_with(&handle, args(&["--config", path_str]), |_| None);
+        assert_eq!(handle.load().state_dir, Some(std::path::PathBuf::from("/var/lib/rrg")));
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Recording and replaying of requests handled by the agent.
+//!
+//! Debugging a failure of an action handler reported from the field requires
+//! reconstructing the exact request. If the agent is started with the
+//! `--record-requests` flag, every request received from the server is written
+//! to the `recordings` subdirectory of the state directory together with a
+//! transcript of all replies and parcels the action handler sent through the
+//! session (see `rrg/replay.proto` for the format).
+//!
+//! Recordings can be executed again with the `--replay` flag. The handler is
+//! then given a [`ReplaySession`] which compares every interaction with the
+//! recorded one and the agent reports all the points at which the execution
+//! diverged from the recording.
+//!
+//! Note that only interactions that carry results are recorded: heartbeats
+//! and limit checks depend on timing and are not part of the transcript (the
+//! same goes for snapshots of the agent clock included in results).
+//! Recordings contain results of the actions (redacted just as results sent
+//! to the server), so they should be enabled only for debugging.
+//!
+//! [`ReplaySession`]: crate::session::ReplaySession
+
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+/// Name of the state subdirectory to write recordings to.
+const RECORDINGS_DIR_NAME: &str = "recordings";
+
+/// Extension of recording files.
+const RECORDING_EXTENSION: &str = "recording";
+
+/// Returns the directory to write recordings to (if recording is enabled).
+pub fn recordings_dir(args: &crate::args::Args) -> Option<PathBuf> {
+    if !args.record_requests {
+        return None;
+    }
+
+    match &args.state_dir {
+        Some(state_dir) => Some(state_dir.join(RECORDINGS_DIR_NAME)),
+        None => {
+            warn!("no state directory to record requests in");
+            None
+        }
+    }
+}
+
+/// Dispatches the given request and writes its recording to `recordings_dir`.
+///
+/// Failures to write the recording are logged but they do not affect the
+/// result of the request.
+pub fn dispatch_recorded<S>(
+    session: &mut S,
+    request: crate::Request,
+    recordings_dir: &Path,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let request_id = request.id();
+    let request_proto = request.to_handler_proto();
+
+    let mut session = crate::session::RecordingSession::new(session);
+    let result = crate::action::dispatch(&mut session, request);
+
+    let recording = session.finish(request_proto, &result);
+    if let Err(error) = write(recordings_dir, request_id, &recording) {
+        warn!("failed to write recording of request '{request_id}': {error}");
+    }
+
+    result
+}
+
+/// Executes the request recorded at the given path again.
+///
+/// The returned report describes the points at which the execution diverged
+/// from the recording.
+pub fn run(
+    args: &crate::args::Args,
+    recording_path: &Path,
+) -> std::io::Result<Report> {
+    use protobuf::Message as _;
+
+    let mut recording = rrg_proto::replay::Recording::parse_from_bytes(&std::fs::read(recording_path)?)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    let request = crate::Request::try_from(recording.take_request())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string()))?;
+
+    let recorded_error = recording.error.take();
+
+    let mut session = crate::session::ReplaySession::new(
+        args,
+        request.id(),
+        request.action(),
+        request.is_dry_run(),
+        recording.take_interactions(),
+    );
+    let result = crate::action::dispatch(&mut session, request);
+
+    Ok(session.finish(&result, recorded_error.as_deref()))
+}
+
+/// Writes the recording of the given request to the given directory.
+fn write(
+    recordings_dir: &Path,
+    request_id: crate::RequestId,
+    recording: &rrg_proto::replay::Recording,
+) -> std::io::Result<()> {
+    use protobuf::Message as _;
+
+    std::fs::create_dir_all(recordings_dir)?;
+
+    let name = format! {
+        "{:X}-{}.{RECORDING_EXTENSION}",
+        request_id.flow_id(),
+        request_id.request_id(),
+    };
+
+    let data = recording.write_to_bytes()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    crate::state::write_atomic(recordings_dir.join(name), &data)
+}
+
+/// Creates a recorded interaction for the given reply.
+///
+/// The item is redacted with the given redactor, so that the recording does
+/// not include anything that would not be sent to the server.
+pub fn reply_interaction<M>(
+    mut item: M,
+    redactor: &crate::redact::Redactor,
+) -> protobuf::Result<rrg_proto::replay::Interaction>
+where
+    M: protobuf::MessageFull,
+{
+    redactor.redact_message(&mut item);
+    // Snapshots of the clock are different every time the request is handled,
+    // so they are not part of the recording.
+    crate::response::clear_clock_snapshots(&mut item);
+
+    let mut interaction = rrg_proto::replay::Interaction::new();
+    interaction.set_reply(protobuf::well_known_types::any::Any::pack(&item)?);
+
+    Ok(interaction)
+}
+
+/// Creates a recorded interaction for the given parcel.
+///
+/// See [`reply_interaction`] for more details.
+pub fn parcel_interaction<M>(
+    sink: crate::Sink,
+    mut item: M,
+    redactor: &crate::redact::Redactor,
+) -> protobuf::Result<rrg_proto::replay::Interaction>
+where
+    M: protobuf::MessageFull,
+{
+    redactor.redact_message(&mut item);
+
+    let mut parcel = rrg_proto::rrg::Parcel::new();
+    parcel.set_sink(sink.into());
+    parcel.set_payload(protobuf::well_known_types::any::Any::pack(&item)?);
+
+    let mut interaction = rrg_proto::replay::Interaction::new();
+    interaction.set_parcel(parcel);
+
+    Ok(interaction)
+}
+
+/// Report of a replayed execution of a request.
+#[derive(Debug)]
+pub struct Report {
+    /// Number of interactions of the replayed execution.
+    pub interaction_count: usize,
+    /// Points at which the execution diverged from the recording.
+    pub divergences: Vec<Divergence>,
+}
+
+impl Report {
+
+    /// Returns whether the replayed execution matches the recording.
+    pub fn is_ok(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+impl std::fmt::Display for Report {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "replayed {} interactions", self.interaction_count)?;
+        if self.divergences.is_empty() {
+            return write!(fmt, ", no divergences");
+        }
+
+        write!(fmt, ", {} divergences:", self.divergences.len())?;
+        for divergence in &self.divergences {
+            write!(fmt, "\n  {divergence}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A point at which the replayed execution diverged from the recording.
+#[derive(Debug)]
+pub struct Divergence {
+    /// Index of the interaction that diverged (or `None` for the result).
+    pub index: Option<usize>,
+    /// Description of what has been recorded.
+    pub expected: String,
+    /// Description of what has happened instead.
+    pub actual: String,
+}
+
+impl std::fmt::Display for Divergence {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.index {
+            Some(index) => write!(fmt, "interaction #{index}")?,
+            None => write!(fmt, "result")?,
+        }
+        write!(fmt, ": expected {}, got {}", self.expected, self.actual)
+    }
+}
+
+/// Returns a human-readable description of the given interaction.
+pub fn describe(interaction: Option<&rrg_proto::replay::Interaction>) -> String {
+    use rrg_proto::replay::interaction::Kind;
+
+    fn describe_any(any: &protobuf::well_known_types::any::Any) -> String {
+        use sha2::Digest as _;
+
+        let digest = sha2::Sha256::digest(&any.value);
+        format! {
+            "'{}' ({} bytes, sha256:{:02x}{:02x}{:02x}{:02x})",
+            any.type_url, any.value.len(), digest[0], digest[1], digest[2], digest[3],
+        }
+    }
+
+    match interaction.and_then(|interaction| interaction.kind.as_ref()) {
+        Some(Kind::Reply(item)) => {
+            format!("reply of {}", describe_any(item))
+        }
+        Some(Kind::Parcel(parcel)) => {
+            format!("parcel of {} to {:?}", describe_any(parcel.payload()), parcel.sink())
+        }
+        Some(_) => String::from("unknown interaction"),
+        None => String::from("no interaction"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::session::Session as _;
+
+    /// Toy handler sending the given replies and parcels.
+    fn handle_toy<S>(session: &mut S, values: &[u64]) -> crate::session::Result<()>
+    where
+        S: crate::session::Session,
+    {
+        for value in values {
+            session.reply(ToyItem(*value))?;
+        }
+        session.send(crate::Sink::Blob, ToyItem(values.iter().sum()))?;
+
+        Ok(())
+    }
+
+    struct ToyItem(u64);
+
+    impl crate::response::Item for ToyItem {
+
+        type Proto = protobuf::well_known_types::wrappers::UInt64Value;
+
+        fn into_proto(self) -> Self::Proto {
+            let mut proto = Self::Proto::new();
+            proto.value = self.0;
+
+            proto
+        }
+    }
+
+    /// Records an execution of the toy handler.
+    fn record_toy(values: &[u64]) -> Vec<rrg_proto::replay::Interaction> {
+        let mut session = crate::session::FakeSession::new();
+
+        let mut session = crate::session::RecordingSession::new(&mut session);
+        let result = handle_toy(&mut session, values);
+
+        session.finish(rrg_proto::rrg::Request::new(), &result)
+            .take_interactions()
+    }
+
+    /// Replays an execution of the toy handler against the given recording.
+    fn replay_toy(values: &[u64], recorded: Vec<rrg_proto::replay::Interaction>) -> Report {
+        let fake_session = crate::session::FakeSession::new();
+
+        let mut session = crate::session::ReplaySession::new(
+            fake_session.args(),
+            crate::RequestId::new(0xf00, 0xba7),
+            crate::request::Action::GetSystemMetadata,
+            false,
+            recorded,
+        );
+        let result = handle_toy(&mut session, values);
+
+        session.finish(&result, None)
+    }
+
+    #[test]
+    fn replay_toy_same() {
+        let report = replay_toy(&[1, 2, 3], record_toy(&[1, 2, 3]));
+        assert!(report.is_ok());
+        assert_eq!(report.interaction_count, 4);
+    }
+
+    #[test]
+    fn replay_toy_changed_reply() {
+        let report = replay_toy(&[1, 5, 3], record_toy(&[1, 2, 3]));
+        assert!(!report.is_ok());
+
+        // The sum sent to the sink also changed.
+        let indices = report.divergences.iter()
+            .map(|divergence| divergence.index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, vec![Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn replay_toy_missing_reply() {
+        let report = replay_toy(&[1, 2], record_toy(&[1, 2, 0]));
+        assert!(!report.is_ok());
+        assert_eq!(report.divergences[0].index, Some(2));
+        assert!(report.divergences[0].expected.starts_with("reply"));
+        assert!(report.divergences[0].actual.starts_with("parcel"));
+    }
+
+    #[test]
+    fn replay_toy_different_result() {
+        let fake_session = crate::session::FakeSession::new();
+
+        let mut session = crate::session::ReplaySession::new(
+            fake_session.args(),
+            crate::RequestId::new(0xf00, 0xba7),
+            crate::request::Action::GetSystemMetadata,
+            false,
+            record_toy(&[1]),
+        );
+        let result = handle_toy(&mut session, &[1]);
+
+        let report = session.finish(&result, Some("foo"));
+        assert_eq!(report.divergences.len(), 1);
+        assert_eq!(report.divergences[0].index, None);
+    }
+
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[test]
+    fn record_and_replay_timeline() {
+        use protobuf::Message as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let root = tempdir.path().join("root");
+        std::fs::create_dir(&root)
+            .unwrap();
+        rrg_test_support::tree::many_small_files(&root, 4, 8, 16)
+            .unwrap();
+
+        // Listing directories can update their access times (depending on the
+        // mount options), so we list them once upfront for the recorded and
+        // replayed results to be the same.
+        for entry in crate::fs::walk_dir(&root).unwrap() {
+            entry.unwrap();
+        }
+
+        let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+        args.set_root(root.clone().into());
+
+        let mut request = rrg_proto::rrg::Request::new();
+        request.set_flow_id(0xf00);
+        request.set_request_id(0xba7);
+        request.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+        request.set_args(protobuf::well_known_types::any::Any::pack(&args).unwrap());
+
+        let request = crate::Request::try_from(request)
+            .unwrap();
+
+        let recordings_dir = tempdir.path().join("recordings");
+
+        let mut session = crate::session::FakeSession::new();
+        dispatch_recorded(&mut session, request, &recordings_dir)
+            .unwrap();
+
+        let recording_path = recordings_dir.join("F00-2983.recording");
+        let recording = rrg_proto::replay::Recording::parse_from_bytes(&std::fs::read(&recording_path).unwrap())
+            .unwrap();
+        assert!(!recording.interactions.is_empty());
+        assert!(!recording.has_error());
+
+        let report = run(session.args(), &recording_path)
+            .unwrap();
+        assert!(report.is_ok(), "{report}");
+        assert_eq!(report.interaction_count, recording.interactions.len());
+
+        // Changing the filesystem makes the replayed execution diverge.
+        std::fs::write(root.join("foo"), b"foo")
+            .unwrap();
+
+        let report = run(session.args(), &recording_path)
+            .unwrap();
+        assert!(!report.is_ok());
+    }
+}
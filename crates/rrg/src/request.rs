@@ -51,6 +51,98 @@ pub enum Action {
     QueryWmi,
     /// Execute a signed command.
     ExecuteSignedCommand,
+    /// List user accounts available on the system.
+    ListUsers,
+    /// List software packages installed on the system.
+    ListPackages,
+    /// List scheduled tasks (cron jobs, systemd timers, launchd items, etc.).
+    ListScheduledTasks,
+    /// List system services and daemons.
+    ListServices,
+    /// Get entries of the system log.
+    GetSystemLogs,
+    /// Get events from the Windows Event Log.
+    GetWindowsEventLogs,
+    /// List keys and values of the Windows registry.
+    ListWinreg,
+    /// List keys and values of a Windows registry hive file.
+    ListWinregHiveFile,
+    /// Collect artifacts of program execution (Windows-only).
+    GetExecutionArtifacts,
+    /// List persistence mechanisms (launchd jobs, login items, cron) on macOS.
+    ListMacosPersistence,
+    /// Get entries of the unified logging system on macOS.
+    GetMacosLogs,
+    /// Get parsed authentication configuration (users, groups, sudoers, sshd).
+    GetAuthConfig,
+    /// List SSH authorized keys and known hosts of all users.
+    ListSshKeys,
+    /// List loaded kernel modules (drivers, kernel extensions).
+    ListKernelModules,
+    /// Get the neighbor cache and the routing table.
+    GetNetworkTables,
+    /// Get the hosts file, the resolver configuration and the DNS cache.
+    GetDnsConfig,
+    /// Scan files with YARA rules.
+    ScanFilesYara,
+    /// Scan process memory with YARA rules.
+    ScanProcessMemoryYara,
+    /// Run an osquery SQL query.
+    QueryOsquery,
+    /// Update the agent executable.
+    UpdateAgent,
+    /// Rotate keys used to verify signed commands.
+    RotateCommandVerificationKey,
+    /// Get results of the agent self-checks.
+    GetAgentHealth,
+    /// Export the raw NTFS master file table of a volume.
+    GetNtfsMft,
+    /// Read records of the NTFS change journal of a volume.
+    GetUsnJournal,
+    /// List volume shadow copies available on the system.
+    ListShadowCopies,
+    /// Collect a bundle of procfs files.
+    GetProcSnapshot,
+    /// List containers running on the system.
+    ListContainers,
+    /// Get capabilities (e.g. supported action versions) of the agent.
+    GetAgentCapabilities,
+    /// Replace the schedule of periodically executed actions.
+    UpdateSchedule,
+    /// List files opened by processes.
+    ListOpenFiles,
+    /// Collect browser history and extensions.
+    GetBrowserArtifacts,
+    /// Change the mode in which the agent handles requests.
+    SetAgentMode,
+    /// List deleted files still held open by processes.
+    ListDeletedOpenFiles,
+    /// Verify files against a manifest of their expected digests.
+    VerifySystemIntegrity,
+    /// Watch files and directories for changes.
+    WatchFiles,
+    /// Get the access token of processes (Windows-only).
+    GetProcessToken,
+    /// List physical disks and their partitions.
+    ListDisks,
+    /// Image raw block devices and volumes.
+    GetRawDevice,
+    /// Sweep registry locations used for persistence (Windows-only).
+    ListAutoruns,
+    /// List persistence locations on Linux.
+    ListLinuxPersistence,
+    /// Get changes of the filesystem timeline since a previous collection.
+    GetFilesystemTimelineDelta,
+    /// Get security configuration of a Windows machine.
+    GetWindowsSecurityConfig,
+    /// Get security configuration of a Linux machine.
+    GetLinuxSecurityConfig,
+    /// Sweep files for known indicators of compromise.
+    SweepIndicators,
+    /// Get the environment variables and audit directories of `PATH`.
+    GetEnvironment,
+    /// Get the history of logins from `utmp` records.
+    GetLoginHistory,
 }
 
 impl std::fmt::Display for Action {
@@ -76,6 +168,52 @@ impl std::fmt::Display for Action {
             Action::QueryWmi => write!(fmt, "query_wmi"),
             Action::GetTcpResponse => write!(fmt,  "get_tcp_response"),
             Action::ExecuteSignedCommand => write!(fmt, "execute_signed_command"),
+            Action::ListUsers => write!(fmt, "list_users"),
+            Action::ListPackages => write!(fmt, "list_packages"),
+            Action::ListScheduledTasks => write!(fmt, "list_scheduled_tasks"),
+            Action::ListServices => write!(fmt, "list_services"),
+            Action::GetSystemLogs => write!(fmt, "get_system_logs"),
+            Action::GetWindowsEventLogs => write!(fmt, "get_windows_event_logs"),
+            Action::ListWinreg => write!(fmt, "list_winreg"),
+            Action::ListWinregHiveFile => write!(fmt, "list_winreg_hive_file"),
+            Action::GetExecutionArtifacts => write!(fmt, "get_execution_artifacts"),
+            Action::ListMacosPersistence => write!(fmt, "list_macos_persistence"),
+            Action::GetMacosLogs => write!(fmt, "get_macos_logs"),
+            Action::GetAuthConfig => write!(fmt, "get_auth_config"),
+            Action::ListSshKeys => write!(fmt, "list_ssh_keys"),
+            Action::ListKernelModules => write!(fmt, "list_kernel_modules"),
+            Action::GetNetworkTables => write!(fmt, "get_network_tables"),
+            Action::GetDnsConfig => write!(fmt, "get_dns_config"),
+            Action::ScanFilesYara => write!(fmt, "scan_files_yara"),
+            Action::ScanProcessMemoryYara => write!(fmt, "scan_process_memory_yara"),
+            Action::QueryOsquery => write!(fmt, "query_osquery"),
+            Action::UpdateAgent => write!(fmt, "update_agent"),
+            Action::RotateCommandVerificationKey => write!(fmt, "rotate_command_verification_key"),
+            Action::GetAgentHealth => write!(fmt, "get_agent_health"),
+            Action::GetNtfsMft => write!(fmt, "get_ntfs_mft"),
+            Action::GetUsnJournal => write!(fmt, "get_usn_journal"),
+            Action::ListShadowCopies => write!(fmt, "list_shadow_copies"),
+            Action::GetProcSnapshot => write!(fmt, "get_proc_snapshot"),
+            Action::ListContainers => write!(fmt, "list_containers"),
+            Action::GetAgentCapabilities => write!(fmt, "get_agent_capabilities"),
+            Action::UpdateSchedule => write!(fmt, "update_schedule"),
+            Action::ListOpenFiles => write!(fmt, "list_open_files"),
+            Action::GetBrowserArtifacts => write!(fmt, "get_browser_artifacts"),
+            Action::SetAgentMode => write!(fmt, "set_agent_mode"),
+            Action::ListDeletedOpenFiles => write!(fmt, "list_deleted_open_files"),
+            Action::VerifySystemIntegrity => write!(fmt, "verify_system_integrity"),
+            Action::WatchFiles => write!(fmt, "watch_files"),
+            Action::GetProcessToken => write!(fmt, "get_process_token"),
+            Action::ListDisks => write!(fmt, "list_disks"),
+            Action::GetRawDevice => write!(fmt, "get_raw_device"),
+            Action::ListAutoruns => write!(fmt, "list_autoruns"),
+            Action::ListLinuxPersistence => write!(fmt, "list_linux_persistence"),
+            Action::GetFilesystemTimelineDelta => write!(fmt, "get_filesystem_timeline_delta"),
+            Action::GetWindowsSecurityConfig => write!(fmt, "get_windows_security_config"),
+            Action::GetLinuxSecurityConfig => write!(fmt, "get_linux_security_config"),
+            Action::SweepIndicators => write!(fmt, "sweep_indicators"),
+            Action::GetEnvironment => write!(fmt, "get_environment"),
+            Action::GetLoginHistory => write!(fmt, "get_login_history"),
         }
     }
 }
@@ -100,6 +238,36 @@ impl std::fmt::Display for UnknownAction {
     }
 }
 
+impl std::str::FromStr for Action {
+
+    type Err = ParseActionError;
+
+    /// Parses an action from its name as in the protocol (e.g. `get_file_contents`).
+    fn from_str(name: &str) -> Result<Action, ParseActionError> {
+        let proto = <rrg_proto::rrg::Action as protobuf::Enum>::from_str(&name.to_ascii_uppercase())
+            .ok_or_else(|| ParseActionError { name: String::from(name) })?;
+
+        Action::try_from(proto)
+            .map_err(|_| ParseActionError { name: String::from(name) })
+    }
+}
+
+/// The error type for cases when parsing an action name fails.
+#[derive(Debug, Clone)]
+pub struct ParseActionError {
+    /// Name that failed to parse.
+    name: String,
+}
+
+impl std::fmt::Display for ParseActionError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "unknown action name '{}'", self.name)
+    }
+}
+
+impl std::error::Error for ParseActionError {}
+
 impl TryFrom<rrg_proto::rrg::Action> for Action {
 
     type Error = UnknownAction;
@@ -127,6 +295,52 @@ impl TryFrom<rrg_proto::rrg::Action> for Action {
             LIST_WINREG_KEYS => Ok(Action::ListWinregKeys),
             QUERY_WMI => Ok(Action::QueryWmi),
             EXECUTE_SIGNED_COMMAND => Ok(Action::ExecuteSignedCommand),
+            LIST_USERS => Ok(Action::ListUsers),
+            LIST_PACKAGES => Ok(Action::ListPackages),
+            LIST_SCHEDULED_TASKS => Ok(Action::ListScheduledTasks),
+            LIST_SERVICES => Ok(Action::ListServices),
+            GET_SYSTEM_LOGS => Ok(Action::GetSystemLogs),
+            GET_WINDOWS_EVENT_LOGS => Ok(Action::GetWindowsEventLogs),
+            LIST_WINREG => Ok(Action::ListWinreg),
+            LIST_WINREG_HIVE_FILE => Ok(Action::ListWinregHiveFile),
+            GET_EXECUTION_ARTIFACTS => Ok(Action::GetExecutionArtifacts),
+            LIST_MACOS_PERSISTENCE => Ok(Action::ListMacosPersistence),
+            GET_MACOS_LOGS => Ok(Action::GetMacosLogs),
+            GET_AUTH_CONFIG => Ok(Action::GetAuthConfig),
+            LIST_SSH_KEYS => Ok(Action::ListSshKeys),
+            LIST_KERNEL_MODULES => Ok(Action::ListKernelModules),
+            GET_NETWORK_TABLES => Ok(Action::GetNetworkTables),
+            GET_DNS_CONFIG => Ok(Action::GetDnsConfig),
+            SCAN_FILES_YARA => Ok(Action::ScanFilesYara),
+            SCAN_PROCESS_MEMORY_YARA => Ok(Action::ScanProcessMemoryYara),
+            QUERY_OSQUERY => Ok(Action::QueryOsquery),
+            UPDATE_AGENT => Ok(Action::UpdateAgent),
+            ROTATE_COMMAND_VERIFICATION_KEY => Ok(Action::RotateCommandVerificationKey),
+            GET_AGENT_HEALTH => Ok(Action::GetAgentHealth),
+            GET_NTFS_MFT => Ok(Action::GetNtfsMft),
+            GET_USN_JOURNAL => Ok(Action::GetUsnJournal),
+            LIST_SHADOW_COPIES => Ok(Action::ListShadowCopies),
+            GET_PROC_SNAPSHOT => Ok(Action::GetProcSnapshot),
+            LIST_CONTAINERS => Ok(Action::ListContainers),
+            GET_AGENT_CAPABILITIES => Ok(Action::GetAgentCapabilities),
+            UPDATE_SCHEDULE => Ok(Action::UpdateSchedule),
+            LIST_OPEN_FILES => Ok(Action::ListOpenFiles),
+            GET_BROWSER_ARTIFACTS => Ok(Action::GetBrowserArtifacts),
+            SET_AGENT_MODE => Ok(Action::SetAgentMode),
+            LIST_DELETED_OPEN_FILES => Ok(Action::ListDeletedOpenFiles),
+            VERIFY_SYSTEM_INTEGRITY => Ok(Action::VerifySystemIntegrity),
+            WATCH_FILES => Ok(Action::WatchFiles),
+            GET_PROCESS_TOKEN => Ok(Action::GetProcessToken),
+            LIST_DISKS => Ok(Action::ListDisks),
+            GET_RAW_DEVICE => Ok(Action::GetRawDevice),
+            LIST_AUTORUNS => Ok(Action::ListAutoruns),
+            LIST_LINUX_PERSISTENCE => Ok(Action::ListLinuxPersistence),
+            GET_FILESYSTEM_TIMELINE_DELTA => Ok(Action::GetFilesystemTimelineDelta),
+            GET_WINDOWS_SECURITY_CONFIG => Ok(Action::GetWindowsSecurityConfig),
+            GET_LINUX_SECURITY_CONFIG => Ok(Action::GetLinuxSecurityConfig),
+            SWEEP_INDICATORS => Ok(Action::SweepIndicators),
+            GET_ENVIRONMENT => Ok(Action::GetEnvironment),
+            GET_LOGIN_HISTORY => Ok(Action::GetLoginHistory),
             _ => {
                 let value = protobuf::Enum::value(&proto);
                 Err(UnknownAction { value })
@@ -135,6 +349,81 @@ impl TryFrom<rrg_proto::rrg::Action> for Action {
     }
 }
 
+impl From<Action> for rrg_proto::rrg::Action {
+
+    fn from(action: Action) -> rrg_proto::rrg::Action {
+        use rrg_proto::rrg::Action::*;
+
+        match action {
+            Action::GetSystemMetadata => GET_SYSTEM_METADATA,
+            Action::GetFileMetadata => GET_FILE_METADATA,
+            Action::GetFileContents => GET_FILE_CONTENTS,
+            Action::GetFileHash => GET_FILE_HASH,
+            Action::GrepFileContents => GREP_FILE_CONTENTS,
+            Action::ListDirectory => LIST_DIRECTORY,
+            Action::ListProcesses => LIST_PROCESSES,
+            Action::ListConnections => LIST_CONNECTIONS,
+            Action::ListNamedPipes => LIST_NAMED_PIPES,
+            Action::ListInterfaces => LIST_INTERFACES,
+            Action::ListMounts => LIST_MOUNTS,
+            Action::ListUtmpUsers => LIST_UTMP_USERS,
+            Action::GetFilesystemTimeline => GET_FILESYSTEM_TIMELINE,
+            Action::GetTcpResponse => GET_TCP_RESPONSE,
+            Action::GetWinregValue => GET_WINREG_VALUE,
+            Action::ListWinregValues => LIST_WINREG_VALUES,
+            Action::ListWinregKeys => LIST_WINREG_KEYS,
+            Action::QueryWmi => QUERY_WMI,
+            Action::ExecuteSignedCommand => EXECUTE_SIGNED_COMMAND,
+            Action::ListUsers => LIST_USERS,
+            Action::ListPackages => LIST_PACKAGES,
+            Action::ListScheduledTasks => LIST_SCHEDULED_TASKS,
+            Action::ListServices => LIST_SERVICES,
+            Action::GetSystemLogs => GET_SYSTEM_LOGS,
+            Action::GetWindowsEventLogs => GET_WINDOWS_EVENT_LOGS,
+            Action::ListWinreg => LIST_WINREG,
+            Action::ListWinregHiveFile => LIST_WINREG_HIVE_FILE,
+            Action::GetExecutionArtifacts => GET_EXECUTION_ARTIFACTS,
+            Action::ListMacosPersistence => LIST_MACOS_PERSISTENCE,
+            Action::GetMacosLogs => GET_MACOS_LOGS,
+            Action::GetAuthConfig => GET_AUTH_CONFIG,
+            Action::ListSshKeys => LIST_SSH_KEYS,
+            Action::ListKernelModules => LIST_KERNEL_MODULES,
+            Action::GetNetworkTables => GET_NETWORK_TABLES,
+            Action::GetDnsConfig => GET_DNS_CONFIG,
+            Action::ScanFilesYara => SCAN_FILES_YARA,
+            Action::ScanProcessMemoryYara => SCAN_PROCESS_MEMORY_YARA,
+            Action::QueryOsquery => QUERY_OSQUERY,
+            Action::UpdateAgent => UPDATE_AGENT,
+            Action::RotateCommandVerificationKey => ROTATE_COMMAND_VERIFICATION_KEY,
+            Action::GetAgentHealth => GET_AGENT_HEALTH,
+            Action::GetNtfsMft => GET_NTFS_MFT,
+            Action::GetUsnJournal => GET_USN_JOURNAL,
+            Action::ListShadowCopies => LIST_SHADOW_COPIES,
+            Action::GetProcSnapshot => GET_PROC_SNAPSHOT,
+            Action::ListContainers => LIST_CONTAINERS,
+            Action::GetAgentCapabilities => GET_AGENT_CAPABILITIES,
+            Action::UpdateSchedule => UPDATE_SCHEDULE,
+            Action::ListOpenFiles => LIST_OPEN_FILES,
+            Action::GetBrowserArtifacts => GET_BROWSER_ARTIFACTS,
+            Action::SetAgentMode => SET_AGENT_MODE,
+            Action::ListDeletedOpenFiles => LIST_DELETED_OPEN_FILES,
+            Action::VerifySystemIntegrity => VERIFY_SYSTEM_INTEGRITY,
+            Action::WatchFiles => WATCH_FILES,
+            Action::GetProcessToken => GET_PROCESS_TOKEN,
+            Action::ListDisks => LIST_DISKS,
+            Action::GetRawDevice => GET_RAW_DEVICE,
+            Action::ListAutoruns => LIST_AUTORUNS,
+            Action::ListLinuxPersistence => LIST_LINUX_PERSISTENCE,
+            Action::GetFilesystemTimelineDelta => GET_FILESYSTEM_TIMELINE_DELTA,
+            Action::GetWindowsSecurityConfig => GET_WINDOWS_SECURITY_CONFIG,
+            Action::GetLinuxSecurityConfig => GET_LINUX_SECURITY_CONFIG,
+            Action::SweepIndicators => SWEEP_INDICATORS,
+            Action::GetEnvironment => GET_ENVIRONMENT,
+            Action::GetLoginHistory => GET_LOGIN_HISTORY,
+        }
+    }
+}
+
 /// A unique identifier of a request.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct RequestId {
@@ -156,6 +445,18 @@ impl RequestId {
     }
 }
 
+#[cfg(any(test, feature = "test-fake_session"))]
+impl RequestId {
+
+    /// Creates a request identifier out of the given flow and request ones.
+    pub fn new(flow_id: u64, request_id: u64) -> RequestId {
+        RequestId {
+            flow_id,
+            request_id,
+        }
+    }
+}
+
 impl std::fmt::Display for RequestId {
 
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -186,6 +487,16 @@ pub struct Request {
     log_level: log::LevelFilter,
     /// Filters to apply to result messages.
     filters: crate::filter::FilterSet,
+    /// Compression to apply to result messages (if overridden).
+    result_compression: Option<crate::response::Compression>,
+    /// Minimum version of the action arguments the agent has to support.
+    min_action_version: u32,
+    /// Identifier of the schedule entry that issued the request (if any).
+    schedule_id: Option<String>,
+    /// Fraction of result messages to send (if sampled).
+    sample_rate: Option<f64>,
+    /// Whether the action should only estimate the cost of the request.
+    dry_run: bool,
 }
 
 impl Request {
@@ -210,11 +521,26 @@ impl Request {
     ///
     /// This function will return an error if it is not possible to interpret
     /// the serialized request arguments as the expected type (e.g. the message
-    /// is malformed or some of the required fields are not present).
+    /// is malformed or some of the required fields are not present) or if the
+    /// request requires a newer version of the action than the agent supports.
     pub fn args<A>(&self) -> Result<A, ParseArgsError>
     where
         A: Args,
     {
+        // Arguments of newer versions might contain fields that we would just
+        // silently ignore, so we refuse to even look at them.
+        let version = crate::action::version(self.action).unwrap_or(0);
+        if self.min_action_version > version {
+            return Err(ParseArgsError {
+                kind: ParseArgsErrorKind::UnsupportedVersion,
+                error: Box::new(UnsupportedVersionError {
+                    action: self.action,
+                    min_version: self.min_action_version,
+                    version,
+                }),
+            });
+        }
+
         let args_proto = protobuf::Message::parse_from_bytes(&self.serialized_args[..])
             .map_err(|error| ParseArgsError {
                 kind: ParseArgsErrorKind::MalformedBytes,
@@ -224,6 +550,30 @@ impl Request {
         A::from_proto(args_proto)
     }
 
+    /// Returns the part of the request relevant to the action handler.
+    ///
+    /// The returned message invokes the same action with the same arguments,
+    /// but limits, filters and other options applied by the session are not
+    /// included.
+    pub fn to_handler_proto(&self) -> rrg_proto::rrg::Request {
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_flow_id(self.id.flow_id);
+        proto.set_request_id(self.id.request_id);
+        proto.set_action(self.action.into());
+        proto.mut_args().value = self.serialized_args.clone();
+        proto.set_min_action_version(self.min_action_version);
+        proto.set_dry_run(self.dry_run);
+
+        proto
+    }
+
+    /// Gets the minimum version of the action arguments required by the request.
+    ///
+    /// See [`crate::action::version`] for more details.
+    pub fn min_action_version(&self) -> u32 {
+        self.min_action_version
+    }
+
     /// Gets the limit on the number of bytes the request handler can send.
     pub fn network_bytes_limit(&self) -> Option<u64> {
         self.network_bytes_limit
@@ -244,6 +594,41 @@ impl Request {
         self.log_level
     }
 
+    /// Gets the compression of result messages requested by the server.
+    ///
+    /// If the request does not override the compression, `None` is returned
+    /// and the compression the agent is configured with should be used.
+    pub fn result_compression(&self) -> Option<crate::response::Compression> {
+        self.result_compression
+    }
+
+    /// Gets the fraction of result messages that should be sent.
+    ///
+    /// If the results should not be sampled, `None` is returned.
+    pub fn sample_rate(&self) -> Option<f64> {
+        self.sample_rate
+    }
+
+    /// Checks whether the request should be executed as a dry run.
+    ///
+    /// See [`crate::action::supports_dry_run`] for more details.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Gets the identifier of the schedule entry that issued the request.
+    ///
+    /// Requests sent by the server are not issued by any schedule entry and
+    /// `None` is returned for them.
+    pub fn schedule_id(&self) -> Option<&str> {
+        self.schedule_id.as_deref()
+    }
+
+    /// Marks the request as issued by the schedule entry with the given id.
+    pub(crate) fn set_schedule_id(&mut self, schedule_id: String) {
+        self.schedule_id = Some(schedule_id);
+    }
+
     /// Takes the filters specified in the request.
     ///
     /// Note that calling this method will permanently clear filters contained
@@ -265,8 +650,24 @@ impl Request {
     /// irrecoverable error like Fleetspeak connection issue as it makes little
     /// sense to continue running in such a state.
     pub fn receive(heartbeat_rate: std::time::Duration) -> Result<Request, ParseRequestError> {
-        let message = fleetspeak::receive_with_heartbeat(heartbeat_rate);
+        Request::from_message(Request::receive_message(heartbeat_rate))
+    }
 
+    /// Awaits for a new raw request message from Fleetspeak.
+    ///
+    /// This is the same as [`Request::receive`] but does not parse the message.
+    /// Unlike parsed requests, raw messages can be sent across threads.
+    pub fn receive_message(heartbeat_rate: std::time::Duration) -> fleetspeak::Message {
+        fleetspeak::receive_with_heartbeat(heartbeat_rate)
+    }
+
+    /// Parses a request out of a message received from Fleetspeak.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in case the request was invalid (e.g.
+    /// it was missing some necessary fields).
+    pub fn from_message(message: fleetspeak::Message) -> Result<Request, ParseRequestError> {
         if message.service != "GRR" {
             let service = message.service;
             log::warn!("request send by service '{service}' (instead of 'GRR')");
@@ -349,6 +750,22 @@ impl TryFrom<rrg_proto::rrg::Request> for Request {
                 error: Some(Box::new(error)),
             })?;
 
+        let sample_rate = match proto.sample_rate() {
+            rate if rate == 0.0 || rate == 1.0 => None,
+            rate if 0.0 < rate && rate < 1.0 => Some(rate),
+            _ => return Err(ParseRequestError {
+                request_id: Some(request_id),
+                kind: ParseRequestErrorKind::InvalidSampleRate,
+                error: None,
+            }),
+        };
+
+        let result_compression = if proto.has_result_compression() {
+            Some(proto.result_compression().into())
+        } else {
+            None
+        };
+
         Ok(Request {
             id: request_id,
             action,
@@ -358,6 +775,11 @@ impl TryFrom<rrg_proto::rrg::Request> for Request {
             real_time_limit,
             log_level: proto.log_level().into(),
             filters,
+            result_compression,
+            min_action_version: proto.min_action_version(),
+            schedule_id: None,
+            sample_rate,
+            dry_run: proto.dry_run(),
         })
     }
 }
@@ -422,6 +844,8 @@ pub enum ParseRequestErrorKind {
     InvalidRealTimeLimit,
     /// A filter in the request is invalid.
     InvalidFilter,
+    /// The sample rate in the request is invalid.
+    InvalidSampleRate,
 }
 
 impl std::fmt::Display for ParseRequestErrorKind {
@@ -435,6 +859,7 @@ impl std::fmt::Display for ParseRequestErrorKind {
             InvalidCpuTimeLimit => write!(fmt, "invalid CPU time limit"),
             InvalidRealTimeLimit => write!(fmt, "invalid real time limit"),
             InvalidFilter => write!(fmt, "invalid filter"),
+            InvalidSampleRate => write!(fmt, "invalid sample rate"),
         }
     }
 }
@@ -454,6 +879,7 @@ impl From<ParseRequestErrorKind> for rrg_proto::rrg::status::error::Type {
             InvalidCpuTimeLimit => Self::INVALID_CPU_TIME_LIMIT,
             InvalidRealTimeLimit => Self::INVALID_REAL_TIME_LIMIT,
             InvalidFilter => Self::INVALID_FILTER,
+            InvalidSampleRate => Self::INVALID_SAMPLE_RATE,
         }
     }
 }
@@ -472,6 +898,15 @@ pub trait Args: Sized {
 
     /// Converts a low-level type to a structured request arguments.
     fn from_proto(proto: Self::Proto) -> Result<Self, ParseArgsError>;
+
+    /// Returns paths of the files that the action is going to access.
+    ///
+    /// These are checked against path restrictions of the agent policy before
+    /// the action is executed. Actions not working with files do not need to
+    /// implement this.
+    fn paths(&self) -> Vec<&std::path::Path> {
+        Vec::new()
+    }
 }
 
 impl Args for () {
@@ -518,6 +953,8 @@ pub enum ParseArgsErrorKind {
     MalformedBytes,
     /// One of the fields of the arguments struct is invalid.
     InvalidField(&'static str),
+    /// The request requires a newer version of the action arguments.
+    UnsupportedVersion,
 }
 
 impl std::fmt::Display for ParseArgsErrorKind {
@@ -532,6 +969,9 @@ impl std::fmt::Display for ParseArgsErrorKind {
             InvalidField(name) => {
                 write!(fmt, "invalid argument field '{name}'")
             }
+            UnsupportedVersion => {
+                write!(fmt, "unsupported argument version")
+            }
         }
     }
 }
@@ -550,6 +990,31 @@ impl std::error::Error for ParseArgsError {
     }
 }
 
+/// An error type for when the request requires a newer version of an action.
+#[derive(Debug)]
+struct UnsupportedVersionError {
+    /// Action that the request invokes.
+    action: Action,
+    /// Minimum version of the action arguments required by the request.
+    min_version: u32,
+    /// Version of the action arguments supported by the agent.
+    version: u32,
+}
+
+impl std::fmt::Display for UnsupportedVersionError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write! {
+            fmt,
+            "action '{}' requires version {} but the agent supports only {}",
+            self.action, self.min_version, self.version,
+        }
+    }
+}
+
+impl std::error::Error for UnsupportedVersionError {
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -572,4 +1037,165 @@ mod tests {
     fn action_try_from_proto_unknown() {
         assert!(Action::try_from(rrg_proto::rrg::Action::UNKNOWN).is_err());
     }
+
+    #[test]
+    fn action_from_str_all_known() {
+        use protobuf::Enum as _;
+
+        for action in rrg_proto::rrg::Action::VALUES {
+            if *action == rrg_proto::rrg::Action::UNKNOWN {
+                continue;
+            }
+
+            let action = Action::try_from(*action).unwrap();
+            assert_eq!(action.to_string().parse::<Action>().unwrap(), action);
+        }
+    }
+
+    #[test]
+    fn action_from_str_unknown() {
+        assert!("unknown".parse::<Action>().is_err());
+        assert!("foo_bar".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn request_try_from_proto_result_compression() {
+        use crate::response::Compression;
+
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+
+        let request = Request::try_from(proto.clone())
+            .unwrap();
+        assert_eq!(request.result_compression(), None);
+
+        proto.set_result_compression(rrg_proto::rrg::Compression::NONE);
+        let request = Request::try_from(proto.clone())
+            .unwrap();
+        assert_eq!(request.result_compression(), Some(Compression::None));
+
+        proto.set_result_compression(rrg_proto::rrg::Compression::ZSTD);
+        let request = Request::try_from(proto)
+            .unwrap();
+        assert_eq!(request.result_compression(), Some(Compression::Zstd));
+    }
+
+    #[test]
+    fn request_to_handler_proto() {
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_flow_id(0xf00);
+        proto.set_request_id(0xba7);
+        proto.set_action(rrg_proto::rrg::Action::GET_FILE_METADATA);
+        proto.mut_args().value = b"foo".to_vec();
+        proto.set_sample_rate(0.25);
+        proto.set_dry_run(true);
+
+        let request = Request::try_from(proto)
+            .unwrap();
+
+        let request = Request::try_from(request.to_handler_proto())
+            .unwrap();
+        assert_eq!(request.id(), RequestId::new(0xf00, 0xba7));
+        assert_eq!(request.action(), Action::GetFileMetadata);
+        assert_eq!(request.serialized_args, b"foo");
+        assert_eq!(request.sample_rate(), None);
+        assert!(request.is_dry_run());
+    }
+
+    #[test]
+    fn request_try_from_proto_sample_rate() {
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+
+        let request = Request::try_from(proto.clone())
+            .unwrap();
+        assert_eq!(request.sample_rate(), None);
+
+        proto.set_sample_rate(1.0);
+        let request = Request::try_from(proto.clone())
+            .unwrap();
+        assert_eq!(request.sample_rate(), None);
+
+        proto.set_sample_rate(0.25);
+        let request = Request::try_from(proto)
+            .unwrap();
+        assert_eq!(request.sample_rate(), Some(0.25));
+    }
+
+    #[test]
+    fn request_try_from_proto_invalid_sample_rate() {
+        for rate in [-0.5, 1.5, f64::NAN, f64::INFINITY] {
+            let mut proto = rrg_proto::rrg::Request::new();
+            proto.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+            proto.set_sample_rate(rate);
+
+            let Err(error) = Request::try_from(proto) else {
+                panic!("invalid sample rate {rate} accepted");
+            };
+            assert_eq!(error.kind(), ParseRequestErrorKind::InvalidSampleRate);
+        }
+    }
+
+    #[test]
+    fn action_into_proto_all_known() {
+        use protobuf::Enum as _;
+
+        for proto in rrg_proto::rrg::Action::VALUES {
+            if let Ok(action) = Action::try_from(*proto) {
+                assert_eq!(rrg_proto::rrg::Action::from(action), *proto);
+            }
+        }
+    }
+
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[test]
+    fn request_args_supported_version() {
+        let version = crate::action::version(Action::GetFilesystemTimeline)
+            .unwrap();
+
+        let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+        args.set_root(std::env::temp_dir().into());
+
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+        proto.set_min_action_version(version);
+        proto.mut_args().value = protobuf::Message::write_to_bytes(&args)
+            .unwrap();
+
+        let request = Request::try_from(proto)
+            .unwrap();
+        assert_eq!(request.min_action_version(), version);
+        assert!(request.args::<crate::action::get_filesystem_timeline::Args>().is_ok());
+    }
+
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[test]
+    fn request_args_unsupported_version() {
+        let version = crate::action::version(Action::GetFilesystemTimeline)
+            .unwrap();
+
+        let mut args = rrg_proto::get_filesystem_timeline::Args::new();
+        args.set_root(std::env::temp_dir().into());
+
+        let mut proto = rrg_proto::rrg::Request::new();
+        proto.set_action(rrg_proto::rrg::Action::GET_FILESYSTEM_TIMELINE);
+        proto.set_min_action_version(version + 1);
+        proto.mut_args().value = protobuf::Message::write_to_bytes(&args)
+            .unwrap();
+
+        let request = Request::try_from(proto)
+            .unwrap();
+
+        let Err(error) = request.args::<crate::action::get_filesystem_timeline::Args>() else {
+            panic!("unsupported version accepted");
+        };
+        assert_eq!(error.kind(), ParseArgsErrorKind::UnsupportedVersion);
+
+        let error = crate::session::Error::from(error);
+        assert_eq!(error.kind(), crate::session::ErrorKind::UnsupportedVersion);
+        assert_eq! {
+            rrg_proto::rrg::status::error::Type::from(error.kind()),
+            rrg_proto::rrg::status::error::Type::UNSUPPORTED_VERSION
+        };
+    }
 }
@@ -0,0 +1,252 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Limits on resources that the agent process is allowed to use.
+//!
+//! The memory limit is enforced by the operating system (which terminates the
+//! agent if it is crossed). To avoid getting there, the agent also keeps track
+//! of the memory it allocates and long-running actions are expected to abort
+//! once the usage crosses a watermark below the limit.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Fraction (in percent) of the memory limit at which the watermark is set.
+const MEMORY_WATERMARK_PERCENT: usize = 80;
+
+/// Number of bytes currently allocated through the global allocator.
+static MEMORY_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of allocated bytes above which actions should abort (if set).
+static MEMORY_WATERMARK: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Global allocator that keeps track of the number of allocated bytes.
+struct Allocator;
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
+// SAFETY: All the allocation work is delegated to the system allocator, we only
+// update the counter of allocated bytes.
+unsafe impl std::alloc::GlobalAlloc for Allocator {
+
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        // SAFETY: We uphold the same contract as the caller.
+        let ptr = unsafe { std::alloc::System.alloc(layout) };
+        if !ptr.is_null() {
+            MEMORY_ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: std::alloc::Layout) -> *mut u8 {
+        // SAFETY: We uphold the same contract as the caller.
+        let ptr = unsafe { std::alloc::System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            MEMORY_ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        // SAFETY: We uphold the same contract as the caller.
+        unsafe { std::alloc::System.dealloc(ptr, layout) };
+        MEMORY_ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: std::alloc::Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        // SAFETY: We uphold the same contract as the caller.
+        let new_ptr = unsafe { std::alloc::System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            MEMORY_ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+            MEMORY_ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        }
+
+        new_ptr
+    }
+}
+
+/// Applies resource limits specified in the agent arguments.
+///
+/// This function should be called only once, at the beginning of the process
+/// lifetime. Failures to apply the limits are logged but not fatal.
+pub fn init(args: &crate::args::Args) {
+    if let Some(memory_limit) = args.memory_limit {
+        let memory_watermark = memory_limit / 100 * MEMORY_WATERMARK_PERCENT;
+        MEMORY_WATERMARK.store(memory_watermark, Ordering::Relaxed);
+
+        match sys::limit_memory(memory_limit) {
+            Ok(()) => log::info!("limited memory to {memory_limit} bytes"),
+            Err(error) => log::error!("failed to limit memory: {error}"),
+        }
+    }
+
+    if args.low_priority {
+        match sys::lower_priority() {
+            Ok(()) => log::info!("lowered scheduling priority"),
+            Err(error) => log::error!("failed to lower scheduling priority: {error}"),
+        }
+    }
+}
+
+/// Returns the number of bytes currently allocated by the agent.
+pub fn memory_allocated() -> usize {
+    MEMORY_ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Returns the number of allocated bytes above which actions should abort.
+///
+/// If no memory limit is configured, `None` is returned.
+pub fn memory_watermark() -> Option<usize> {
+    match MEMORY_WATERMARK.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        memory_watermark => Some(memory_watermark),
+    }
+}
+
+#[cfg(target_family = "unix")]
+mod sys {
+
+    /// Limits the memory of the agent process to the given number of bytes.
+    pub fn limit_memory(memory_limit: usize) -> std::io::Result<()> {
+        let limit = libc::rlimit {
+            rlim_cur: memory_limit as libc::rlim_t,
+            rlim_max: memory_limit as libc::rlim_t,
+        };
+
+        // We set both the limit on the address space and on the data segment:
+        // on some systems only one of them is actually enforced.
+        for resource in [libc::RLIMIT_AS, libc::RLIMIT_DATA] {
+            // SAFETY: We pass a valid pointer to the limit structure. The call
+            // fails (which we verify below) if the limit is invalid.
+            let code = unsafe {
+                libc::setrlimit(resource, &limit)
+            };
+            if code != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lowers the scheduling priority of the agent process.
+    pub fn lower_priority() -> std::io::Result<()> {
+        /// Niceness value to run the agent with.
+        const NICENESS: libc::c_int = 10;
+
+        // SAFETY: This function is safe to call with any arguments, we verify
+        // the result below.
+        let code = unsafe {
+            libc::setpriority(libc::PRIO_PROCESS, 0, NICENESS)
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    /// Limits the memory of the agent process to the given number of bytes.
+    pub fn limit_memory(memory_limit: usize) -> std::io::Result<()> {
+        use windows_sys::Win32::{
+            Foundation::*,
+            System::JobObjects::*,
+            System::Threading::*,
+        };
+
+        // SAFETY: This function is safe to call with null arguments (in which
+        // case an anonymous job with default attributes is created). We verify
+        // the returned handle below.
+        let job = unsafe {
+            CreateJobObjectW(std::ptr::null(), std::ptr::null())
+        };
+        if job.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: The structure consists of plain integers for which zero is a
+        // valid value.
+        let mut info = unsafe {
+            std::mem::zeroed::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>()
+        };
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        info.ProcessMemoryLimit = memory_limit;
+
+        // SAFETY: The job handle is valid (verified above) and we pass a valid
+        // limit structure together with its size. We verify the result below.
+        let status = unsafe {
+            SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                std::ptr::addr_of!(info).cast(),
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // SAFETY: The job handle is valid and the pseudo-handle of the current
+        // process is always valid. We verify the result below.
+        let status = unsafe {
+            AssignProcessToJobObject(job, GetCurrentProcess())
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // Note that we deliberately do not close the job handle: the job (and
+        // its limits) should stay in place for the whole process lifetime.
+        Ok(())
+    }
+
+    /// Lowers the scheduling priority of the agent process.
+    pub fn lower_priority() -> std::io::Result<()> {
+        use windows_sys::Win32::{
+            Foundation::*,
+            System::Threading::*,
+        };
+
+        // SAFETY: The pseudo-handle of the current process is always valid. We
+        // verify the result below.
+        let status = unsafe {
+            SetPriorityClass(GetCurrentProcess(), BELOW_NORMAL_PRIORITY_CLASS)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn memory_allocated_grows() {
+        let memory_allocated_before = memory_allocated();
+
+        // Other tests allocate concurrently, so we cannot assert exact numbers
+        // but an allocation this large should be noticeable.
+        let buf = vec![0u8; 64 * 1024 * 1024];
+        assert!(memory_allocated() > memory_allocated_before);
+
+        drop(buf);
+    }
+}
@@ -45,6 +45,11 @@ impl<I: Item> PreparedItem<I> {
     pub fn as_proto(&self) -> &I::Proto {
         &self.proto
     }
+
+    /// Redacts paths in the Protocol Buffers message of the item.
+    pub fn redact(&mut self, redactor: &crate::redact::Redactor) {
+        redactor.redact_message(&mut self.proto);
+    }
 }
 
 impl<I: Item> From<I> for PreparedItem<I> {
@@ -56,6 +61,31 @@ impl<I: Item> From<I> for PreparedItem<I> {
     }
 }
 
+/// Clears snapshots of the agent clock in the given message.
+///
+/// Snapshots (i.e. `rrg.os.ClockSnapshot` messages) differ between executions
+/// of the same request even if the collected data is the same. They are cleared
+/// before results are sampled and recorded, so that these stay deterministic.
+///
+/// Only singular fields of the top-level message are cleared.
+pub fn clear_clock_snapshots(message: &mut dyn protobuf::MessageDyn) {
+    use protobuf::MessageFull as _;
+    use protobuf::reflect::{RuntimeFieldType, RuntimeType};
+
+    let snapshot_descriptor = rrg_proto::os::ClockSnapshot::descriptor();
+
+    for field in message.descriptor_dyn().fields() {
+        match field.runtime_field_type() {
+            RuntimeFieldType::Singular(RuntimeType::Message(descriptor))
+                if descriptor == snapshot_descriptor =>
+            {
+                field.clear_field(message);
+            }
+            _ => (),
+        }
+    }
+}
+
 /// An action reply message.
 ///
 /// This is a message wrapper around the [`Item`] type but associates it with a
@@ -65,10 +95,16 @@ impl<I: Item> From<I> for PreparedItem<I> {
 pub struct Reply<I: Item> {
     /// A unique request identifier for which this item was yielded.
     request_id: RequestId,
+    /// Identifier of the schedule entry that issued the request (if any).
+    schedule_id: Option<String>,
     /// A unique response identifier of this item.
     response_id: ResponseId,
     /// An actual item that the action yielded.
     item: PreparedItem<I>,
+    /// Compression to apply to the serialized item.
+    compression: Compression,
+    /// Minimum size of the serialized item to apply the compression to.
+    compression_threshold: usize,
 }
 
 impl<I: Item> Reply<I> {
@@ -86,22 +122,7 @@ impl<I: Item> Reply<I> {
     ///
     /// [`Session::reply`]: crate::session::Session::reply
     pub fn send_unaccounted(self) -> usize {
-        use protobuf::Message as _;
-
-        let data = rrg_proto::rrg::Response::from(self).write_to_bytes()
-            // This should only fail in case we are out of memory, which we are
-            // almost certainly not (and if we are, we have bigger issue).
-            .expect("failed to serialize a result response");
-
-        let data_len = data.len();
-
-        fleetspeak::send(fleetspeak::Message {
-            service: String::from("GRR"),
-            kind: Some(String::from("rrg.Response")),
-            data,
-        });
-
-        data_len
+        send_response(rrg_proto::rrg::Response::from(self))
     }
 }
 
@@ -113,10 +134,14 @@ impl<I: Item> Reply<I> {
 pub struct Status {
     /// A unique request identifier for which this status is generated.
     request_id: RequestId,
+    /// Identifier of the schedule entry that issued the request (if any).
+    schedule_id: Option<String>,
     /// A unique response identifier of this status.
     response_id: ResponseId,
     /// Number of items that have been rejected by filters.
     filtered_out_count: u32,
+    /// Number of items that have not been picked by the sampling.
+    sampled_out_count: u32,
     /// The action execution status.
     result: Result<(), crate::session::Error>,
 }
@@ -135,22 +160,7 @@ impl Status {
     ///
     /// [`Session::send`]: crate::session::Session::send
     pub fn send_unaccounted(self) -> usize {
-        use protobuf::Message as _;
-
-        let data = rrg_proto::rrg::Response::from(self).write_to_bytes()
-            // This should only fail in case we are out of memory, which we are
-            // almost certainly not (and if we are, we have bigger issue).
-            .expect("failed to serialize a status response");
-
-        let data_len = data.len();
-
-        fleetspeak::send(fleetspeak::Message {
-            service: String::from("GRR"),
-            kind: Some(String::from("rrg.Response")),
-            data,
-        });
-
-        data_len
+        send_response(rrg_proto::rrg::Response::from(self))
     }
 }
 
@@ -184,19 +194,34 @@ impl<'r, 'a> Log<'r, 'a> {
     /// "accounted" method for sending logs as they should not contribute to the
     /// network usage statistics.
     pub fn send_unaccounted(self) {
-        use protobuf::Message as _;
+        send_response(rrg_proto::rrg::Response::from(self));
+    }
+}
 
-        let data = rrg_proto::rrg::Response::from(self).write_to_bytes()
-            // This should only fail in case we are out of memory, which we are
-            // almost certainly not (and if we are, we have bigger issue).
-            .expect("failed to serialize a log response");
+/// Sends the given response message through Fleetspeak to the GRR server.
+///
+/// Note that this function will not do any network traffic accounting and
+/// should not be used in general (see [`Reply::send_unaccounted`]).
+///
+/// This function returns number of bytes in the serialized response sent to
+/// Fleetspeak.
+pub fn send_response(response: rrg_proto::rrg::Response) -> usize {
+    use protobuf::Message as _;
 
-        fleetspeak::send(fleetspeak::Message {
-            service: String::from("GRR"),
-            kind: Some(String::from("rrg.Response")),
-            data,
-        });
-    }
+    let data = response.write_to_bytes()
+        // This should only fail in case we are out of memory, which we are
+        // almost certainly not (and if we are, we have bigger issue).
+        .expect("failed to serialize a response");
+
+    let data_len = data.len();
+
+    fleetspeak::send(fleetspeak::Message {
+        service: String::from("GRR"),
+        kind: Some(String::from("rrg.Response")),
+        data,
+    });
+
+    data_len
 }
 
 /// An action reply message after applying filters to the contained item.
@@ -205,6 +230,8 @@ pub enum FilteredReply<I: Item> {
     Accepted(Reply<I>),
     /// Item was rejected by the filters.
     Rejected,
+    /// Item passed the filters but was not picked by the sampling.
+    SampledOut,
     /// Error occurred when applying filters to the item.
     Error(crate::filter::Error),
 }
@@ -216,24 +243,75 @@ pub enum FilteredReply<I: Item> {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct ResponseId(pub(super) u64);
 
+/// Default minimum size of serialized results to compress.
+///
+/// It is used when the compression is requested but the agent has not been
+/// configured with any specific threshold.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
+/// Compression algorithm applied to results sent to the server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Results are sent as-is.
+    None,
+    /// Results are compressed using the Zstandard algorithm.
+    Zstd,
+}
+
+impl From<rrg_proto::rrg::Compression> for Compression {
+
+    fn from(proto: rrg_proto::rrg::Compression) -> Compression {
+        match proto {
+            rrg_proto::rrg::Compression::NONE => Compression::None,
+            rrg_proto::rrg::Compression::ZSTD => Compression::Zstd,
+        }
+    }
+}
+
+impl From<Compression> for rrg_proto::rrg::Compression {
+
+    fn from(compression: Compression) -> rrg_proto::rrg::Compression {
+        match compression {
+            Compression::None => rrg_proto::rrg::Compression::NONE,
+            Compression::Zstd => rrg_proto::rrg::Compression::ZSTD,
+        }
+    }
+}
+
 /// Response factory for building many responses to a single request.
 pub struct ResponseBuilder {
     /// A unique request identifier for which we build responses.
     request_id: RequestId,
+    /// Identifier of the schedule entry that issued the request (if any).
+    schedule_id: Option<String>,
     /// The response identifier assigned to the next generated response.
     next_response_id: ResponseId,
     /// Filters to apply to the results before they are sent.
     filters: FilterSet,
     /// Number of items that have been rejected by filters.
     filtered_out_count: u32,
+    /// Items with hashes below this threshold are sent (if sampling).
+    sample_threshold: Option<u64>,
+    /// Number of items that have not been picked by the sampling.
+    sampled_out_count: u32,
+    /// Compression to apply to the results before they are sent.
+    compression: Compression,
+    /// Minimum size of serialized results to apply the compression to.
+    compression_threshold: usize,
 }
 
 impl ResponseBuilder {
 
+    /// Returns the identifier of the request for which we build responses.
+    pub fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
     /// Creates a new response builder for the specified request.
     pub fn new(request_id: RequestId) -> ResponseBuilder {
         ResponseBuilder {
             request_id,
+            schedule_id: None,
             // Response identifiers that GRR agents use start at 1. The server
             // assumes this to determine the number of expected messages when
             // the status message is received. Thus, we have to replicate the
@@ -241,6 +319,10 @@ impl ResponseBuilder {
             next_response_id: ResponseId(1),
             filters: FilterSet::empty(),
             filtered_out_count: 0,
+            sample_threshold: None,
+            sampled_out_count: 0,
+            compression: Compression::None,
+            compression_threshold: 0,
         }
     }
 
@@ -250,32 +332,95 @@ impl ResponseBuilder {
         self
     }
 
+    /// Creates a new response builder that will tag responses with the given
+    /// schedule entry identifier.
+    pub fn with_schedule_id(mut self, schedule_id: Option<String>) -> ResponseBuilder {
+        self.schedule_id = schedule_id;
+        self
+    }
+
+    /// Creates a new response builder that will send only a sample of results.
+    ///
+    /// The rate is the fraction of results to send (`None` to send all). An
+    /// item is picked based on the hash of its serialized form, so the same
+    /// items are always picked for the same rate.
+    pub fn with_sample_rate(mut self, rate: Option<f64>) -> ResponseBuilder {
+        // The float-to-integer cast saturates, so a rate that is close to one
+        // ends up as the maximum threshold.
+        self.sample_threshold = rate.map(|rate| (rate * 2f64.powi(64)) as u64);
+        self
+    }
+
+    /// Checks whether only a sample of results is sent.
+    pub fn is_sampling(&self) -> bool {
+        self.sample_threshold.is_some()
+    }
+
+    /// Creates a new response builder that will compress results.
+    ///
+    /// Only results that serialize to at least `threshold` bytes are going to
+    /// be compressed, smaller ones are sent as-is.
+    pub fn with_compression(
+        mut self,
+        compression: Compression,
+        threshold: usize,
+    ) -> ResponseBuilder {
+        self.compression = compression;
+        self.compression_threshold = threshold;
+        self
+    }
+
     /// Builds a new status response for the given action outcome.
     pub fn status(self, result: crate::session::Result<()>) -> Status {
         Status {
             request_id: self.request_id,
+            schedule_id: self.schedule_id,
             // Because this method consumes the builder, we do not need to
             // increment the response id.
             response_id: self.next_response_id,
             filtered_out_count: self.filtered_out_count,
+            sampled_out_count: self.sampled_out_count,
             result,
         }
     }
 
+    /// Builds a status response for the request being interrupted.
+    ///
+    /// Unlike [`ResponseBuilder::status`], this does not consume the builder:
+    /// the status accounts for the responses built so far and takes the same
+    /// identifier as the next response.
+    pub fn interrupted_status(&self, error: crate::session::Error) -> Status {
+        Status {
+            request_id: self.request_id,
+            schedule_id: self.schedule_id.clone(),
+            response_id: self.next_response_id,
+            filtered_out_count: self.filtered_out_count,
+            sampled_out_count: self.sampled_out_count,
+            result: Err(error),
+        }
+    }
+
     /// Builds a new reply response for the given action item.
     pub fn reply<I: Item>(&mut self, item: PreparedItem<I>) -> FilteredReply<I>
     where
         I: Item,
     {
         match self.filters.eval(&item.proto) {
+            Ok(true) if !self.is_sampled(&item.proto) => {
+                self.sampled_out_count += 1;
+                FilteredReply::SampledOut
+            }
             Ok(true) => {
                 let response_id = self.next_response_id;
                 self.next_response_id.0 += 1;
 
                 FilteredReply::Accepted(Reply {
                     request_id: self.request_id.clone(),
+                    schedule_id: self.schedule_id.clone(),
                     response_id,
                     item,
+                    compression: self.compression,
+                    compression_threshold: self.compression_threshold,
                 })
             }
             Ok(false) => {
@@ -285,6 +430,31 @@ impl ResponseBuilder {
             Err(error) => FilteredReply::Error(error),
         }
     }
+
+    /// Checks whether the given item is picked by the sampling.
+    fn is_sampled<M>(&self, proto: &M) -> bool
+    where
+        M: protobuf::MessageFull,
+    {
+        use sha2::Digest as _;
+
+        let Some(sample_threshold) = self.sample_threshold else {
+            return true;
+        };
+
+        let mut proto = proto.clone();
+        clear_clock_snapshots(&mut proto);
+
+        let bytes = proto.write_to_bytes()
+            // This should only fail in case we are out of memory, which we are
+            // almost certainly not (and if we are, we have a bigger issue).
+            .expect("failed to serialize a result");
+
+        let sha256 = sha2::Sha256::digest(&bytes);
+        let hash = u64::from_be_bytes(sha256[..8].try_into().unwrap());
+
+        hash < sample_threshold
+    }
 }
 
 /// Log factory for building many log responses to a single request.
@@ -328,6 +498,10 @@ pub enum Sink {
     Startup,
     /// Collects binary blobs (e.g. fragments of files).
     Blob,
+    /// Collects results of agent self-checks performed at startup.
+    AgentHealth,
+    /// Collects reports about previous crashes of the agent.
+    CrashReport,
 }
 
 impl From<Sink> for rrg_proto::rrg::Sink {
@@ -336,6 +510,8 @@ impl From<Sink> for rrg_proto::rrg::Sink {
         match sink {
             Sink::Startup => rrg_proto::rrg::Sink::STARTUP,
             Sink::Blob => rrg_proto::rrg::Sink::BLOB,
+            Sink::AgentHealth => rrg_proto::rrg::Sink::AGENT_HEALTH,
+            Sink::CrashReport => rrg_proto::rrg::Sink::CRASH_REPORT,
         }
     }
 }
@@ -383,22 +559,22 @@ impl<I: crate::response::Item> Parcel<I> {
     /// [session]: crate::session::Session
     /// [`Session::send`]: crate::session::Session::send
     pub fn send_unaccounted(self) -> usize {
-        use protobuf::Message as _;
-
-        let data = rrg_proto::rrg::Parcel::from(self).write_to_bytes()
-            // This should only fail in case we are out of memory, which we are
-            // almost certainly not (and if we are, we have a bigger issue).
-            .unwrap();
-
-        let data_len = data.len();
-
-        fleetspeak::send(fleetspeak::Message {
-            service: String::from("GRR"),
-            kind: Some(String::from("rrg.Parcel")),
-            data,
-        });
+        self.send_unaccounted_via(&mut crate::transport::Fleetspeak)
+    }
 
-        data_len
+    /// Sends the parcel message to the GRR server through the given backend.
+    ///
+    /// The same caveats about network usage accounting as with [`send_unac-
+    /// counted`] apply.
+    ///
+    /// This function returns number of bytes sent by the backend.
+    ///
+    /// [`send_unaccounted`]: Parcel::send_unaccounted
+    pub fn send_unaccounted_via(
+        self,
+        backend: &mut dyn crate::transport::SinkBackend,
+    ) -> usize {
+        backend.send(rrg_proto::rrg::Parcel::from(self))
     }
 }
 
@@ -408,7 +584,7 @@ where
 {
     fn from(reply: Reply<I>) -> rrg_proto::rrg::Response {
         let result_proto = reply.item.as_proto();
-        let result_any = protobuf::well_known_types::any::Any::pack(result_proto)
+        let mut result_any = protobuf::well_known_types::any::Any::pack(result_proto)
             // This should only fail in case we are out of memory, which we are
             // almost certainly not (and if we are, we have a bigger issue).
             .expect("failed to serialize a result");
@@ -417,6 +593,28 @@ where
         proto.set_flow_id(reply.request_id.flow_id());
         proto.set_request_id(reply.request_id.request_id());
         proto.set_response_id(reply.response_id.0);
+        if let Some(schedule_id) = reply.schedule_id {
+            proto.set_schedule_id(schedule_id);
+        }
+
+        match reply.compression {
+            Compression::None => (),
+            Compression::Zstd if result_any.value.len() < reply.compression_threshold => (),
+            Compression::Zstd => {
+                let compressed = ruzstd::encoding::compress_to_vec(
+                    &result_any.value[..],
+                    ruzstd::encoding::CompressionLevel::Fastest,
+                );
+
+                // Data that does not compress well can end up being larger than
+                // the original, in which case we just send it uncompressed.
+                if compressed.len() < result_any.value.len() {
+                    result_any.value = compressed;
+                    proto.set_result_compression(Compression::Zstd.into());
+                }
+            }
+        }
+
         proto.set_result(result_any);
 
         proto
@@ -430,6 +628,9 @@ impl From<Status> for rrg_proto::rrg::Response {
         proto.set_flow_id(status.request_id.flow_id());
         proto.set_request_id(status.request_id.request_id());
         proto.set_response_id(status.response_id.0);
+        if let Some(schedule_id) = &status.schedule_id {
+            proto.set_schedule_id(schedule_id.clone());
+        }
         proto.set_status(status.into());
 
         proto
@@ -445,6 +646,10 @@ impl From<Status> for rrg_proto::rrg::Status {
         }
 
         proto.set_filtered_out_count(status.filtered_out_count);
+        proto.set_sampled_out_count(status.sampled_out_count);
+        // Response identifiers start at 1 and the status takes the one after
+        // the last reply.
+        proto.set_result_count((status.response_id.0 - 1) as u32);
 
         proto
     }
@@ -492,3 +697,194 @@ where
         proto
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn reply(
+        data: Vec<u8>,
+        compression: Compression,
+        threshold: usize,
+    ) -> rrg_proto::rrg::Response {
+        let mut builder = ResponseBuilder::new(RequestId::new(0xf00, 0xba7))
+            .with_compression(compression, threshold);
+
+        let mut item = protobuf::well_known_types::wrappers::BytesValue::new();
+        item.value = data;
+
+        match builder.reply(PreparedItem::<BytesItem>::from(BytesItem(item))) {
+            FilteredReply::Accepted(reply) => reply.into(),
+            _ => panic!("reply not accepted"),
+        }
+    }
+
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        use std::io::Read as _;
+
+        let mut data = data;
+        let mut decoder = ruzstd::decoding::StreamingDecoder::new(&mut data)
+            .unwrap();
+
+        let mut buf = Vec::new();
+        decoder.read_to_end(&mut buf)
+            .unwrap();
+
+        buf
+    }
+
+    struct BytesItem(protobuf::well_known_types::wrappers::BytesValue);
+
+    impl Item for BytesItem {
+
+        type Proto = protobuf::well_known_types::wrappers::BytesValue;
+
+        fn into_proto(self) -> Self::Proto {
+            self.0
+        }
+    }
+
+    #[test]
+    fn reply_large_compressed() {
+        use protobuf::well_known_types::wrappers::BytesValue;
+
+        let data = b"foobarbaz".repeat(64 * 1024);
+
+        let proto = reply(data.clone(), Compression::Zstd, 1024);
+        assert_eq!(proto.result_compression(), rrg_proto::rrg::Compression::ZSTD);
+
+        let mut result = proto.result().clone();
+        assert!(result.value.len() < data.len());
+
+        result.value = decompress(&result.value);
+        let item = result.unpack::<BytesValue>()
+            .unwrap().unwrap();
+        assert_eq!(item.value, data);
+    }
+
+    #[test]
+    fn reply_small_raw() {
+        use protobuf::well_known_types::wrappers::BytesValue;
+
+        let proto = reply(b"foobarbaz".to_vec(), Compression::Zstd, 1024);
+        assert_eq!(proto.result_compression(), rrg_proto::rrg::Compression::NONE);
+
+        let item = proto.result().unpack::<BytesValue>()
+            .unwrap().unwrap();
+        assert_eq!(item.value, b"foobarbaz");
+    }
+
+    #[test]
+    fn reply_large_uncompressed() {
+        use protobuf::well_known_types::wrappers::BytesValue;
+
+        let data = b"foobarbaz".repeat(64 * 1024);
+
+        let proto = reply(data.clone(), Compression::None, 1024);
+        assert_eq!(proto.result_compression(), rrg_proto::rrg::Compression::NONE);
+
+        let item = proto.result().unpack::<BytesValue>()
+            .unwrap().unwrap();
+        assert_eq!(item.value, data);
+    }
+
+    #[test]
+    fn reply_incompressible_raw() {
+        use protobuf::well_known_types::wrappers::BytesValue;
+        use rand::RngCore as _;
+
+        let mut data = vec![0; 64 * 1024];
+        rand::rngs::OsRng.fill_bytes(&mut data);
+
+        let proto = reply(data.clone(), Compression::Zstd, 1024);
+        assert_eq!(proto.result_compression(), rrg_proto::rrg::Compression::NONE);
+
+        let item = proto.result().unpack::<BytesValue>()
+            .unwrap().unwrap();
+        assert_eq!(item.value, data);
+    }
+
+    fn sampled_values(rate: f64) -> (Vec<u32>, rrg_proto::rrg::Status) {
+        let mut builder = ResponseBuilder::new(RequestId::new(0xf00, 0xba7))
+            .with_sample_rate(Some(rate));
+
+        let mut values = Vec::new();
+        for value in 0..1000 {
+            let mut item = protobuf::well_known_types::wrappers::BytesValue::new();
+            item.value = format!("item-{value}").into_bytes();
+
+            match builder.reply(PreparedItem::<BytesItem>::from(BytesItem(item))) {
+                FilteredReply::Accepted(_) => values.push(value),
+                FilteredReply::SampledOut => (),
+                _ => panic!("unexpected reply"),
+            }
+        }
+
+        let status = rrg_proto::rrg::Response::from(builder.status(Ok(())))
+            .take_status();
+
+        (values, status)
+    }
+
+    #[test]
+    fn reply_sampled() {
+        let (values, status) = sampled_values(0.1);
+
+        // Sampling is based on hashes, so we only expect to be roughly close
+        // to the rate.
+        assert!(50 < values.len() && values.len() < 150);
+        assert_eq!(status.result_count() as usize, values.len());
+        assert_eq!(status.sampled_out_count() as usize, 1000 - values.len());
+    }
+
+    #[test]
+    fn reply_sampled_deterministic() {
+        assert_eq!(sampled_values(0.1).0, sampled_values(0.1).0);
+    }
+
+    #[test]
+    fn clear_clock_snapshots_singular() {
+        let mut proto = rrg_proto::get_file_metadata::Result::new();
+        proto.set_path(std::path::PathBuf::from("/foo").into());
+        proto.mut_collection_clock().set_timezone_name(String::from("CET"));
+
+        clear_clock_snapshots(&mut proto);
+
+        assert!(!proto.has_collection_clock());
+        assert!(proto.has_path());
+    }
+
+    #[test]
+    fn reply_sampled_nested() {
+        // Items picked with a lower rate should be picked with a higher one as
+        // well.
+        let (values_low, _) = sampled_values(0.1);
+        let (values_high, _) = sampled_values(0.5);
+        assert!(values_low.iter().all(|value| values_high.contains(value)));
+    }
+
+    #[test]
+    fn responses_schedule_id() {
+        let mut builder = ResponseBuilder::new(RequestId::new(0, 42))
+            .with_schedule_id(Some(String::from("foo")));
+
+        let mut item = protobuf::well_known_types::wrappers::BytesValue::new();
+        item.value = b"bar".to_vec();
+
+        let reply = match builder.reply(PreparedItem::<BytesItem>::from(BytesItem(item))) {
+            FilteredReply::Accepted(reply) => rrg_proto::rrg::Response::from(reply),
+            _ => panic!("reply not accepted"),
+        };
+        assert_eq!(reply.schedule_id(), "foo");
+
+        let status = rrg_proto::rrg::Response::from(builder.status(Ok(())));
+        assert_eq!(status.schedule_id(), "foo");
+    }
+
+    #[test]
+    fn responses_no_schedule_id() {
+        let proto = reply(b"foo".to_vec(), Compression::None, 0);
+        assert_eq!(proto.schedule_id(), "");
+    }
+}
@@ -0,0 +1,535 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Running subprocesses as a different (less privileged) identity.
+//!
+//! The agent usually runs as `root` or `SYSTEM` but helper binaries it spawns
+//! rarely need that. On Unix, the subprocess drops to the given user and groups
+//! in a pre-exec hook (and on Linux it is also prevented from regaining any
+//! privileges through set-user-ID executables). On Windows, the subprocess is
+//! created with a restricted copy of the agent token or with a token of a user
+//! logged on with given credentials.
+
+#[cfg(target_family = "windows")]
+mod windows;
+
+/// Identity to run a subprocess as.
+#[derive(Clone)]
+pub enum RunAs {
+    /// Unix user and groups.
+    #[cfg(target_family = "unix")]
+    Unix {
+        /// Identifier of the user.
+        uid: u32,
+        /// Identifier of the primary group.
+        gid: u32,
+        /// Identifiers of supplementary groups.
+        groups: Vec<u32>,
+    },
+    /// Restricted copy of the agent token.
+    #[cfg(target_family = "windows")]
+    RestrictedToken,
+    /// Token of a user logged on with the given credentials.
+    #[cfg(target_family = "windows")]
+    Logon(Credentials),
+}
+
+/// Credentials of a Windows user account.
+#[cfg(target_family = "windows")]
+#[derive(Clone)]
+pub struct Credentials {
+    /// Name of the user account.
+    user_name: String,
+    /// Name of the domain of the account (if specified).
+    domain: Option<String>,
+    /// Password of the account.
+    password: String,
+}
+
+impl std::fmt::Debug for RunAs {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // We implement this manually, so that passwords never end up in logs.
+        match self {
+            #[cfg(target_family = "unix")]
+            RunAs::Unix { uid, gid, groups } => {
+                write!(fmt, "uid={uid} gid={gid} groups={groups:?}")
+            }
+            #[cfg(target_family = "windows")]
+            RunAs::RestrictedToken => {
+                write!(fmt, "restricted token")
+            }
+            #[cfg(target_family = "windows")]
+            RunAs::Logon(credentials) => match &credentials.domain {
+                Some(domain) => write!(fmt, "logon {domain}\\{}", credentials.user_name),
+                None => write!(fmt, "logon {}", credentials.user_name),
+            },
+        }
+    }
+}
+
+impl RunAs {
+
+    /// Parses the identity from its Protocol Buffers representation.
+    ///
+    /// `None` is returned if the message does not specify any identity (in
+    /// which case the subprocess should run as the agent does).
+    pub fn from_proto(mut proto: rrg_proto::os::RunAs) -> Result<Option<RunAs>, ParseRunAsError> {
+        let has_unix = proto.has_unix_uid() ||
+            proto.has_unix_gid() ||
+            !proto.unix_supplementary_gids().is_empty();
+        let has_windows = proto.windows_restricted_token() ||
+            proto.has_windows_logon();
+
+        if has_unix && has_windows {
+            return Err(ParseRunAsError::Conflicting);
+        }
+
+        if has_unix {
+            #[cfg(target_family = "unix")]
+            {
+                let (Some(uid), Some(gid)) = (proto.unix_uid, proto.unix_gid) else {
+                    return Err(ParseRunAsError::MissingIds);
+                };
+
+                return Ok(Some(RunAs::Unix {
+                    uid,
+                    gid,
+                    groups: proto.take_unix_supplementary_gids(),
+                }));
+            }
+
+            #[cfg(target_family = "windows")]
+            return Err(ParseRunAsError::Unsupported);
+        }
+
+        if has_windows {
+            #[cfg(target_family = "windows")]
+            {
+                if proto.windows_restricted_token() && proto.has_windows_logon() {
+                    return Err(ParseRunAsError::Conflicting);
+                }
+
+                if proto.windows_restricted_token() {
+                    return Ok(Some(RunAs::RestrictedToken));
+                }
+
+                let mut logon = proto.take_windows_logon();
+                if logon.user_name().is_empty() {
+                    return Err(ParseRunAsError::MissingUserName);
+                }
+
+                let domain = match logon.take_domain() {
+                    domain if domain.is_empty() => None,
+                    domain => Some(domain),
+                };
+
+                return Ok(Some(RunAs::Logon(Credentials {
+                    user_name: logon.take_user_name(),
+                    domain,
+                    password: logon.take_password(),
+                })));
+            }
+
+            #[cfg(target_family = "unix")]
+            return Err(ParseRunAsError::Unsupported);
+        }
+
+        Ok(None)
+    }
+}
+
+/// An error indicating that the run-as identity is invalid.
+#[derive(Debug)]
+pub enum ParseRunAsError {
+    /// Both Unix and Windows identities (or two Windows ones) were specified.
+    Conflicting,
+    /// Identity for a different operating system was specified.
+    Unsupported,
+    /// Only one of the user and group identifiers was specified.
+    #[cfg(target_family = "unix")]
+    MissingIds,
+    /// Logon credentials without a user name were specified.
+    #[cfg(target_family = "windows")]
+    MissingUserName,
+}
+
+impl std::fmt::Display for ParseRunAsError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseRunAsError::Conflicting => {
+                write!(fmt, "conflicting identities specified")
+            }
+            ParseRunAsError::Unsupported => {
+                write!(fmt, "identity not supported on this system")
+            }
+            #[cfg(target_family = "unix")]
+            ParseRunAsError::MissingIds => {
+                write!(fmt, "both user and group identifiers must be specified")
+            }
+            #[cfg(target_family = "windows")]
+            ParseRunAsError::MissingUserName => {
+                write!(fmt, "missing logon user name")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRunAsError {
+}
+
+/// Subprocess spawned by [`spawn`].
+///
+/// This mirrors the subset of [`std::process::Child`] that the actions need,
+/// as on Windows subprocesses running as a different identity cannot be
+/// spawned through the standard library.
+pub struct Child {
+    /// Handle to the subprocess.
+    inner: ChildInner,
+    /// Standard input of the subprocess (if piped).
+    pub stdin: Option<Box<dyn std::io::Write + Send>>,
+    /// Standard output of the subprocess (if piped).
+    pub stdout: Option<Box<dyn std::io::Read + Send>>,
+    /// Standard error of the subprocess (if piped).
+    pub stderr: Option<Box<dyn std::io::Read + Send>>,
+}
+
+/// Handle to a subprocess depending on how it was spawned.
+enum ChildInner {
+    /// Subprocess spawned through the standard library.
+    Std(std::process::Child),
+    /// Subprocess spawned with a different token.
+    #[cfg(target_family = "windows")]
+    Token(self::windows::Process),
+}
+
+impl Child {
+
+    /// Returns the identifier of the subprocess.
+    pub fn id(&self) -> u32 {
+        match &self.inner {
+            ChildInner::Std(child) => child.id(),
+            #[cfg(target_family = "windows")]
+            ChildInner::Token(process) => process.id(),
+        }
+    }
+
+    /// Returns the exit status of the subprocess if it has already exited.
+    pub fn try_wait(&mut self) -> std::io::Result<Option<std::process::ExitStatus>> {
+        match &mut self.inner {
+            ChildInner::Std(child) => child.try_wait(),
+            #[cfg(target_family = "windows")]
+            ChildInner::Token(process) => process.try_wait(),
+        }
+    }
+
+    /// Waits for the subprocess to exit and returns its exit status.
+    pub fn wait(&mut self) -> std::io::Result<std::process::ExitStatus> {
+        match &mut self.inner {
+            ChildInner::Std(child) => child.wait(),
+            #[cfg(target_family = "windows")]
+            ChildInner::Token(process) => process.wait(),
+        }
+    }
+
+    /// Kills the subprocess.
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        match &mut self.inner {
+            ChildInner::Std(child) => child.kill(),
+            #[cfg(target_family = "windows")]
+            ChildInner::Token(process) => process.kill(),
+        }
+    }
+}
+
+impl From<std::process::Child> for Child {
+
+    fn from(mut child: std::process::Child) -> Child {
+        let stdin = child.stdin.take()
+            .map(|stdin| Box::new(stdin) as Box<dyn std::io::Write + Send>);
+        let stdout = child.stdout.take()
+            .map(|stdout| Box::new(stdout) as Box<dyn std::io::Read + Send>);
+        let stderr = child.stderr.take()
+            .map(|stderr| Box::new(stderr) as Box<dyn std::io::Read + Send>);
+
+        Child {
+            inner: ChildInner::Std(child),
+            stdin,
+            stdout,
+            stderr,
+        }
+    }
+}
+
+/// Spawns the given command as the given identity.
+///
+/// If no identity is given, the command is spawned as is.
+///
+/// On Windows, subprocesses running as a different identity always have all of
+/// their standard streams piped and get only the environment variables that
+/// were explicitly set on the command (the environment of the agent is never
+/// passed to them).
+pub fn spawn(
+    mut command: std::process::Command,
+    run_as: Option<&RunAs>,
+) -> std::io::Result<Child> {
+    match run_as {
+        None => command.spawn().map(Child::from),
+        #[cfg(target_family = "unix")]
+        Some(RunAs::Unix { uid, gid, groups }) => {
+            use std::os::unix::process::CommandExt as _;
+
+            let (uid, gid, groups) = (*uid, *gid, groups.clone());
+
+            // SAFETY: The hook only makes system calls that are safe to make
+            // between `fork` and `exec` and does not allocate.
+            unsafe {
+                command.pre_exec(move || drop_privileges(uid, gid, &groups));
+            }
+
+            command.spawn().map(Child::from)
+        }
+        #[cfg(target_family = "windows")]
+        Some(run_as) => {
+            let spawned = self::windows::spawn(&command, run_as)?;
+
+            Ok(Child {
+                inner: ChildInner::Token(spawned.process),
+                stdin: Some(Box::new(spawned.stdin)),
+                stdout: Some(Box::new(spawned.stdout)),
+                stderr: Some(Box::new(spawned.stderr)),
+            })
+        }
+    }
+}
+
+/// Drops privileges of the current process to the given user and groups.
+///
+/// This is meant to be called in a subprocess right before it executes the
+/// target binary.
+#[cfg(target_family = "unix")]
+fn drop_privileges(uid: u32, gid: u32, groups: &[u32]) -> std::io::Result<()> {
+    // SAFETY: `geteuid` is always safe to call.
+    let euid = unsafe {
+        libc::geteuid()
+    };
+
+    // Supplementary groups can be changed only by privileged processes. An
+    // unprivileged agent cannot belong to any groups that it should not have
+    // access to, so there is nothing to drop then.
+    if euid == 0 {
+        // SAFETY: We pass a valid array of group identifiers with its length.
+        let code = unsafe {
+            libc::setgroups(groups.len() as _, groups.as_ptr())
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    // The group has to be changed before the user, as afterwards we might no
+    // longer be allowed to do that.
+    //
+    // SAFETY: These are just system calls without memory-related invariants.
+    let code = unsafe {
+        libc::setgid(gid)
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let code = unsafe {
+        libc::setuid(uid)
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // This makes sure that the subprocess (and all its descendants) cannot
+    // regain privileges by executing set-user-ID binaries (e.g. `sudo`) [1].
+    //
+    // [1]: https://www.kernel.org/doc/html/latest/userspace-api/no_new_privs.html
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: This is just a system call without memory-related invariants.
+        let code = unsafe {
+            libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0)
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[cfg(target_family = "unix")]
+    fn own_identity() -> RunAs {
+        // SAFETY: These functions are always safe to call.
+        let (uid, gid) = unsafe {
+            (libc::geteuid(), libc::getegid())
+        };
+
+        RunAs::Unix {
+            uid,
+            gid,
+            groups: Vec::new(),
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    fn output(mut child: Child) -> String {
+        use std::io::Read as _;
+
+        let mut stdout = String::new();
+        child.stdout.take().unwrap()
+            .read_to_string(&mut stdout).unwrap();
+
+        assert!(child.wait().unwrap().success());
+
+        stdout
+    }
+
+    #[test]
+    fn from_proto_empty() {
+        let run_as = RunAs::from_proto(rrg_proto::os::RunAs::new())
+            .unwrap();
+        assert!(run_as.is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn from_proto_unix() {
+        let mut proto = rrg_proto::os::RunAs::new();
+        proto.set_unix_uid(1000);
+        proto.set_unix_gid(100);
+        proto.mut_unix_supplementary_gids().push(20);
+
+        let run_as = RunAs::from_proto(proto)
+            .unwrap();
+        assert! {
+            matches!(run_as, Some(RunAs::Unix { uid: 1000, gid: 100, ref groups }) if groups == &[20])
+        };
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn from_proto_unix_missing_gid() {
+        let mut proto = rrg_proto::os::RunAs::new();
+        proto.set_unix_uid(1000);
+
+        assert! {
+            matches!(RunAs::from_proto(proto), Err(ParseRunAsError::MissingIds))
+        };
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn from_proto_windows_unsupported() {
+        let mut proto = rrg_proto::os::RunAs::new();
+        proto.set_windows_restricted_token(true);
+
+        assert! {
+            matches!(RunAs::from_proto(proto), Err(ParseRunAsError::Unsupported))
+        };
+    }
+
+    #[test]
+    fn from_proto_conflicting() {
+        let mut proto = rrg_proto::os::RunAs::new();
+        proto.set_unix_uid(1000);
+        proto.set_unix_gid(100);
+        proto.set_windows_restricted_token(true);
+
+        assert! {
+            matches!(RunAs::from_proto(proto), Err(ParseRunAsError::Conflicting))
+        };
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn spawn_own_uid() {
+        let mut command = std::process::Command::new("id");
+        command
+            .arg("-u")
+            .stdout(std::process::Stdio::piped());
+
+        let child = spawn(command, Some(&own_identity()))
+            .unwrap();
+
+        // SAFETY: `geteuid` is always safe to call.
+        let uid = unsafe { libc::geteuid() };
+        assert_eq!(output(child).trim(), uid.to_string());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn spawn_own_gid() {
+        let mut command = std::process::Command::new("id");
+        command
+            .arg("-g")
+            .stdout(std::process::Stdio::piped());
+
+        let child = spawn(command, Some(&own_identity()))
+            .unwrap();
+
+        // SAFETY: `getegid` is always safe to call.
+        let gid = unsafe { libc::getegid() };
+        assert_eq!(output(child).trim(), gid.to_string());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn spawn_no_new_privs() {
+        let mut command = std::process::Command::new("grep");
+        command
+            .args(["NoNewPrivs", "/proc/self/status"])
+            .stdout(std::process::Stdio::piped());
+
+        let child = spawn(command, Some(&own_identity()))
+            .unwrap();
+
+        let stdout = output(child);
+        assert_eq!(stdout.split_whitespace().collect::<Vec<_>>(), ["NoNewPrivs:", "1"]);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn spawn_without_identity() {
+        let mut command = std::process::Command::new("echo");
+        command
+            .arg("foo")
+            .stdout(std::process::Stdio::piped());
+
+        let child = spawn(command, None)
+            .unwrap();
+        assert_eq!(output(child), "foo\n");
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn spawn_restricted_token() {
+        use std::io::Read as _;
+
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "echo", "foo"]);
+
+        let mut child = spawn(command, Some(&RunAs::RestrictedToken))
+            .unwrap();
+        drop(child.stdin.take());
+
+        let mut stdout = String::new();
+        child.stdout.take().unwrap()
+            .read_to_string(&mut stdout).unwrap();
+
+        assert!(child.wait().unwrap().success());
+        assert_eq!(stdout, "foo\r\n");
+    }
+}
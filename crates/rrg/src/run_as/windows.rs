@@ -0,0 +1,470 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Spawning subprocesses with a different token using `CreateProcessAsUserW`.
+//!
+//! The standard library does not allow to specify the token of a subprocess,
+//! so we have to create it (together with its standard stream pipes) by hand.
+
+use std::os::windows::io::{AsRawHandle as _, FromRawHandle as _, OwnedHandle};
+
+use windows_sys::Win32::{
+    Foundation::*,
+    Security::*,
+    Security::Authorization::*,
+    System::Threading::*,
+};
+
+use super::{Credentials, RunAs};
+
+/// Security identifier of the built-in `Administrators` group.
+const ADMINISTRATORS_SID: &str = "S-1-5-32-544";
+
+/// Subprocess created with a different token.
+pub struct Process {
+    /// Handle to the subprocess.
+    handle: OwnedHandle,
+    /// Identifier of the subprocess.
+    pid: u32,
+}
+
+/// Subprocess along with its standard stream pipes.
+pub struct Spawned {
+    /// Spawned subprocess.
+    pub process: Process,
+    /// Writing end of the standard input pipe of the subprocess.
+    pub stdin: std::io::PipeWriter,
+    /// Reading end of the standard output pipe of the subprocess.
+    pub stdout: std::io::PipeReader,
+    /// Reading end of the standard error pipe of the subprocess.
+    pub stderr: std::io::PipeReader,
+}
+
+impl Process {
+
+    /// Returns the identifier of the subprocess.
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the exit status of the subprocess if it has already exited.
+    pub fn try_wait(&mut self) -> std::io::Result<Option<std::process::ExitStatus>> {
+        // SAFETY: The handle is valid. We verify the result below.
+        let status = unsafe {
+            WaitForSingleObject(self.handle.as_raw_handle(), 0)
+        };
+        match status {
+            WAIT_OBJECT_0 => self.exit_status().map(Some),
+            WAIT_TIMEOUT => Ok(None),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    }
+
+    /// Waits for the subprocess to exit and returns its exit status.
+    pub fn wait(&mut self) -> std::io::Result<std::process::ExitStatus> {
+        // SAFETY: The handle is valid. We verify the result below.
+        let status = unsafe {
+            WaitForSingleObject(self.handle.as_raw_handle(), INFINITE)
+        };
+        if status != WAIT_OBJECT_0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        self.exit_status()
+    }
+
+    /// Kills the subprocess.
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        // SAFETY: The handle is valid. We verify the result below.
+        let status = unsafe {
+            TerminateProcess(self.handle.as_raw_handle(), 1)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the exit status of the (exited) subprocess.
+    fn exit_status(&self) -> std::io::Result<std::process::ExitStatus> {
+        use std::os::windows::process::ExitStatusExt as _;
+
+        let mut code = 0;
+
+        // SAFETY: The handle is valid. We verify the result below.
+        let status = unsafe {
+            GetExitCodeProcess(self.handle.as_raw_handle(), &mut code)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(std::process::ExitStatus::from_raw(code))
+    }
+}
+
+/// Spawns the given command with a token for the given identity.
+pub fn spawn(command: &std::process::Command, run_as: &RunAs) -> std::io::Result<Spawned> {
+    use crate::os::windows::privileges::*;
+
+    match run_as {
+        // A restricted copy of the agent token can be assigned without any
+        // special privileges.
+        RunAs::RestrictedToken => {
+            create(command, &restricted_token()?)
+        }
+        // Assigning a token of a different user requires privileges that are
+        // held (but not necessarily enabled) by `SYSTEM`.
+        RunAs::Logon(credentials) => {
+            let token = logon_token(credentials)?;
+
+            let spawn = || create(command, &token);
+            let spawn = || with_privilege(SE_INCREASE_QUOTA_NAME, spawn);
+            with_privilege(SE_ASSIGNPRIMARYTOKEN_NAME, spawn)
+                .and_then(|result| result)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::PermissionDenied, error))?
+        }
+    }
+}
+
+/// Creates the subprocess for the given command with the given token.
+fn create(command: &std::process::Command, token: &OwnedHandle) -> std::io::Result<Spawned> {
+    let mut command_line = command_line(command);
+    let env = env_block(command);
+
+    let (stdin_reader, stdin_writer) = std::io::pipe()?;
+    let (stdout_reader, stdout_writer) = std::io::pipe()?;
+    let (stderr_reader, stderr_writer) = std::io::pipe()?;
+
+    // Pipes are created non-inheritable, we make only the ends that belong to
+    // the subprocess inheritable.
+    inheritable(stdin_reader.as_raw_handle())?;
+    inheritable(stdout_writer.as_raw_handle())?;
+    inheritable(stderr_writer.as_raw_handle())?;
+
+    // SAFETY: The structure consists of plain integers and pointers for which
+    // zero is a valid value.
+    let mut startup_info = unsafe {
+        std::mem::zeroed::<STARTUPINFOW>()
+    };
+    startup_info.cb = std::mem::size_of::<STARTUPINFOW>() as u32;
+    startup_info.dwFlags = STARTF_USESTDHANDLES;
+    startup_info.hStdInput = stdin_reader.as_raw_handle();
+    startup_info.hStdOutput = stdout_writer.as_raw_handle();
+    startup_info.hStdError = stderr_writer.as_raw_handle();
+
+    // SAFETY: The structure consists of plain integers and pointers for which
+    // zero is a valid value.
+    let mut process_info = unsafe {
+        std::mem::zeroed::<PROCESS_INFORMATION>()
+    };
+
+    // SAFETY: We pass a valid primary token, a mutable null-terminated command
+    // line, a double null-terminated Unicode environment block and a startup
+    // information with valid inheritable handles. We verify the result below.
+    let status = unsafe {
+        CreateProcessAsUserW(
+            token.as_raw_handle(),
+            std::ptr::null(),
+            command_line.as_mut_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            TRUE,
+            CREATE_UNICODE_ENVIRONMENT | CREATE_NO_WINDOW,
+            env.as_ptr().cast(),
+            std::ptr::null(),
+            &startup_info,
+            &mut process_info,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so both handles are valid and owned by us.
+    // We do not need the thread one, so we close it right away.
+    let handle = unsafe {
+        drop(OwnedHandle::from_raw_handle(process_info.hThread));
+        OwnedHandle::from_raw_handle(process_info.hProcess)
+    };
+
+    // Our copies of the subprocess ends of the pipes have to be closed, as
+    // otherwise reading the output would never reach the end.
+    drop(stdin_reader);
+    drop(stdout_writer);
+    drop(stderr_writer);
+
+    Ok(Spawned {
+        process: Process {
+            handle,
+            pid: process_info.dwProcessId,
+        },
+        stdin: stdin_writer,
+        stdout: stdout_reader,
+        stderr: stderr_reader,
+    })
+}
+
+/// Creates a restricted primary token from the token of the agent process.
+///
+/// The restricted token has all privileges (except for the one to traverse
+/// directories) removed and the `Administrators` group marked as deny-only.
+fn restricted_token() -> std::io::Result<OwnedHandle> {
+    let mut token = std::ptr::null_mut();
+
+    // SAFETY: The pseudo-handle of the current process is always valid. On
+    // success, the token handle is written to the given location.
+    let status = unsafe {
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_DUPLICATE | TOKEN_QUERY | TOKEN_ASSIGN_PRIMARY,
+            &mut token,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the handle is valid and owned by us.
+    let token = unsafe {
+        OwnedHandle::from_raw_handle(token)
+    };
+
+    let admins_sid_wide = ADMINISTRATORS_SID.encode_utf16()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+    let mut admins_sid = std::ptr::null_mut();
+
+    // SAFETY: We pass a null-terminated SID string. The SID is allocated by the
+    // system and we free it below.
+    let status = unsafe {
+        ConvertStringSidToSidW(admins_sid_wide.as_ptr(), &mut admins_sid)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let sids_to_disable = [SID_AND_ATTRIBUTES {
+        Sid: admins_sid,
+        Attributes: 0,
+    }];
+    let mut restricted_token = std::ptr::null_mut();
+
+    // SAFETY: We pass a valid token handle and an array of valid SIDs with its
+    // length. We verify the result below.
+    let status = unsafe {
+        CreateRestrictedToken(
+            token.as_raw_handle(),
+            DISABLE_MAX_PRIVILEGE | LUA_TOKEN,
+            sids_to_disable.len() as u32,
+            sids_to_disable.as_ptr(),
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            &mut restricted_token,
+        )
+    };
+    let error = std::io::Error::last_os_error();
+
+    // SAFETY: The SID was allocated by `ConvertStringSidToSidW` and it has to
+    // be freed with `LocalFree` [1]. It is no longer used after the call.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/sddl/nf-sddl-convertstringsidtosidw
+    unsafe {
+        LocalFree(admins_sid);
+    }
+
+    if status == FALSE {
+        return Err(error);
+    }
+
+    // SAFETY: The call succeeded, so the handle is valid and owned by us.
+    Ok(unsafe {
+        OwnedHandle::from_raw_handle(restricted_token)
+    })
+}
+
+/// Logs the user with the given credentials on and returns its primary token.
+fn logon_token(credentials: &Credentials) -> std::io::Result<OwnedHandle> {
+    let wide = |string: &str| {
+        string.encode_utf16()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>()
+    };
+
+    let user_name = wide(&credentials.user_name);
+    let domain = credentials.domain.as_deref().map(wide);
+    let password = wide(&credentials.password);
+
+    let mut token = std::ptr::null_mut();
+
+    // SAFETY: We pass null-terminated strings (or null for the domain). On
+    // success, the token handle is written to the given location.
+    let status = unsafe {
+        LogonUserW(
+            user_name.as_ptr(),
+            domain.as_ref().map_or(std::ptr::null(), |domain| domain.as_ptr()),
+            password.as_ptr(),
+            LOGON32_LOGON_INTERACTIVE,
+            LOGON32_PROVIDER_DEFAULT,
+            &mut token,
+        )
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the handle is valid and owned by us.
+    Ok(unsafe {
+        OwnedHandle::from_raw_handle(token)
+    })
+}
+
+/// Marks the given handle as inheritable by subprocesses.
+fn inheritable(handle: HANDLE) -> std::io::Result<()> {
+    // SAFETY: The handle is valid. We verify the result below.
+    let status = unsafe {
+        SetHandleInformation(handle, HANDLE_FLAG_INHERIT, HANDLE_FLAG_INHERIT)
+    };
+    if status == FALSE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Builds a null-terminated command line for the given command.
+///
+/// Arguments are quoted according to the rules of `CommandLineToArgvW` [1].
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-commandlinetoargvw
+fn command_line(command: &std::process::Command) -> Vec<u16> {
+    let mut line = Vec::new();
+
+    push_arg(&mut line, command.get_program());
+    for arg in command.get_args() {
+        line.push(u16::from(b' '));
+        push_arg(&mut line, arg);
+    }
+    line.push(0);
+
+    line
+}
+
+/// Appends the given argument (quoted if needed) to the command line.
+fn push_arg(line: &mut Vec<u16>, arg: &std::ffi::OsStr) {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    const QUOTE: u16 = b'"' as u16;
+    const BACKSLASH: u16 = b'\\' as u16;
+
+    let arg = arg.encode_wide().collect::<Vec<u16>>();
+
+    let needs_quotes = arg.is_empty() || arg.iter().any(|&unit| {
+        unit == u16::from(b' ') || unit == u16::from(b'\t') || unit == QUOTE
+    });
+    if !needs_quotes {
+        line.extend(arg);
+        return;
+    }
+
+    line.push(QUOTE);
+
+    // Backslashes are literal unless they precede a quote, in which case they
+    // have to be escaped (together with the quote itself).
+    let mut backslash_count = 0;
+    for unit in arg {
+        if unit == BACKSLASH {
+            backslash_count += 1;
+        } else {
+            if unit == QUOTE {
+                line.extend(std::iter::repeat_n(BACKSLASH, backslash_count + 1));
+            }
+            backslash_count = 0;
+        }
+        line.push(unit);
+    }
+    line.extend(std::iter::repeat_n(BACKSLASH, backslash_count));
+
+    line.push(QUOTE);
+}
+
+/// Builds a double null-terminated environment block for the given command.
+///
+/// Only variables explicitly set on the command are included.
+fn env_block(command: &std::process::Command) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    let mut vars = command.get_envs()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect::<Vec<_>>();
+
+    // The system expects the variables to be sorted (case-insensitively).
+    vars.sort_by_key(|(key, _)| key.to_ascii_uppercase());
+
+    let mut block = Vec::new();
+    for (key, value) in vars {
+        block.extend(key.encode_wide());
+        block.push(u16::from(b'='));
+        block.extend(value.encode_wide());
+        block.push(0);
+    }
+    // Empty block still needs to be terminated by two null characters.
+    if block.is_empty() {
+        block.push(0);
+    }
+    block.push(0);
+
+    block
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn command_line_string(command: &std::process::Command) -> String {
+        let line = command_line(command);
+        String::from_utf16(&line[..line.len() - 1]).unwrap()
+    }
+
+    #[test]
+    fn command_line_simple() {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "echo"]);
+
+        assert_eq!(command_line_string(&command), "cmd /C echo");
+    }
+
+    #[test]
+    fn command_line_quoted() {
+        let mut command = std::process::Command::new("C:\\Program Files\\foo.exe");
+        command.args(["foo bar", "", "a\"b", "c\\\\", "d e\\"]);
+
+        assert_eq! {
+            command_line_string(&command),
+            "\"C:\\Program Files\\foo.exe\" \"foo bar\" \"\" \"a\\\"b\" c\\\\ \"d e\\\\\""
+        };
+    }
+
+    #[test]
+    fn env_block_empty() {
+        let mut command = std::process::Command::new("cmd");
+        command.env_clear();
+
+        assert_eq!(env_block(&command), [0, 0]);
+    }
+
+    #[test]
+    fn env_block_sorted() {
+        let mut command = std::process::Command::new("cmd");
+        command.env("b", "2").env("A", "1");
+
+        let block = env_block(&command);
+        assert_eq!(String::from_utf16(&block).unwrap(), "A=1\0b=2\0\0");
+    }
+}
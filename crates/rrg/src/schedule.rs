@@ -0,0 +1,823 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Periodic execution of actions that the agent issues on its own.
+//!
+//! The schedule is a list of entries, each describing an action to execute
+//! with its arguments, the interval between executions and the maximum random
+//! delay (jitter) added to each of them. It is either given in a file on the
+//! command line or replaced by the server with the signed `update_schedule`
+//! action (in which case it is persisted in the state directory).
+//!
+//! Times at which the entries should be executed next are persisted in the
+//! state directory, so that restarts of the agent do not affect the schedule.
+//! If the agent was not running when an entry should have been executed, the
+//! entry is executed only once when the agent starts again (and not once for
+//! every run that has been missed).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Name of the file within the state directory with the schedule set by the
+/// server.
+const SCHEDULE_FILE_NAME: &str = "schedule";
+
+/// Name of the file within the state directory with the next run times.
+const STATE_FILE_NAME: &str = "schedule.state";
+
+/// Whether the schedule should be reloaded after handling the current request.
+static RELOAD_REQUESTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+/// Source of the current time.
+///
+/// The scheduler never asks the operating system for the time directly, so
+/// that tests can control the passage of time.
+pub trait Clock {
+
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// [`Clock`] that returns the system time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl<C: Clock> Clock for &C {
+
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+}
+
+/// Schedule of actions that the agent executes periodically.
+#[derive(Debug, Default)]
+pub struct Schedule {
+    /// Entries of the schedule.
+    entries: Vec<Entry>,
+}
+
+/// Single action executed periodically by the agent.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Unique identifier of the entry.
+    id: String,
+    /// Action to execute.
+    action: crate::request::Action,
+    /// Arguments to execute the action with.
+    args: protobuf::well_known_types::any::Any,
+    /// Interval between consecutive executions of the action.
+    interval: Duration,
+    /// Maximum random delay added to each execution of the action.
+    jitter: Duration,
+}
+
+impl Schedule {
+
+    /// Returns identifiers of all the entries of the schedule.
+    pub fn entry_ids(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.id.as_str())
+    }
+
+    /// Parses the schedule from a serialized `rrg.schedule.Schedule` message.
+    pub fn parse(bytes: &[u8]) -> Result<Schedule, ParseError> {
+        use protobuf::Message as _;
+
+        let proto = rrg_proto::schedule::Schedule::parse_from_bytes(bytes)
+            .map_err(ParseError::Malformed)?;
+
+        Schedule::try_from(proto)
+    }
+
+    /// Loads the schedule from the file at the given path.
+    pub fn load<P>(path: P) -> std::io::Result<Schedule>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = std::fs::read(path)?;
+
+        Schedule::parse(&bytes)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// Returns the path to the schedule set by the server with the given args.
+///
+/// `None` is returned if the agent has no state directory to keep it in.
+pub fn path(args: &crate::args::Args) -> Option<PathBuf> {
+    args.state_dir.as_ref()
+        .map(|state_dir| state_dir.join(SCHEDULE_FILE_NAME))
+}
+
+/// Loads the schedule that the agent with the given args should follow.
+///
+/// The schedule set by the server takes precedence over the one given on the
+/// command line. `None` is returned if no schedule can ever be followed, i.e.
+/// neither one is given nor can be set later.
+pub fn load(args: &crate::args::Args) -> Option<Schedule> {
+    if let Some(path) = self::path(args) {
+        match Schedule::load(&path) {
+            Ok(schedule) => return Some(schedule),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+            Err(error) => {
+                log::error!("invalid schedule '{}': {error}", path.display());
+            }
+        }
+    }
+
+    match &args.schedule {
+        Some(path) => match Schedule::load(path) {
+            Ok(schedule) => Some(schedule),
+            Err(error) => {
+                log::error!("invalid schedule '{}': {error}", path.display());
+                Some(Schedule::default())
+            }
+        },
+        // Even if there is no schedule yet, the server can set one later if
+        // there is a place to persist it in.
+        None if args.state_dir.is_some() => Some(Schedule::default()),
+        None => None,
+    }
+}
+
+/// Requests the schedule to be reloaded after handling the current request.
+#[cfg_attr(not(feature = "action-update_schedule"), allow(dead_code))]
+pub fn request_reload() {
+    RELOAD_REQUESTED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Checks whether the schedule should be reloaded and clears the request.
+pub fn take_reload_request() -> bool {
+    RELOAD_REQUESTED.swap(false, std::sync::atomic::Ordering::SeqCst)
+}
+
+/// Scheduler issuing requests for the schedule entries as they become due.
+pub struct Scheduler<C: Clock> {
+    /// Source of the current time.
+    clock: C,
+    /// Schedule to follow.
+    schedule: Schedule,
+    /// Times at which the schedule entries should be executed next.
+    next_runs: HashMap<String, SystemTime>,
+    /// Path to the file to persist the next run times in (if any).
+    state_path: Option<PathBuf>,
+}
+
+impl<C: Clock> Scheduler<C> {
+
+    /// Creates a scheduler following the given schedule.
+    ///
+    /// If a state directory is given, the next run times persisted there by
+    /// a previous instance of the agent are picked up.
+    pub fn new(clock: C, schedule: Schedule, state_dir: Option<&Path>) -> Scheduler<C> {
+        let state_path = state_dir.map(|state_dir| state_dir.join(STATE_FILE_NAME));
+
+        let next_runs = match &state_path {
+            Some(path) => match load_next_runs(path) {
+                Ok(next_runs) => next_runs,
+                Err(error) => {
+                    log::error!("invalid schedule state '{}': {error}", path.display());
+                    HashMap::new()
+                }
+            },
+            None => HashMap::new(),
+        };
+
+        let mut scheduler = Scheduler {
+            clock,
+            schedule: Schedule::default(),
+            next_runs,
+            state_path,
+        };
+        scheduler.reload(schedule);
+
+        scheduler
+    }
+
+    /// Replaces the schedule that the scheduler follows.
+    ///
+    /// Entries that were part of the previous schedule keep their next run
+    /// times, new entries are first executed after a random delay.
+    pub fn reload(&mut self, schedule: Schedule) {
+        let now = self.clock.now();
+
+        let mut next_runs = HashMap::new();
+        for entry in &schedule.entries {
+            // The interval of an entry might have been shortened, so we never
+            // wait longer than the new interval allows.
+            let latest = now + entry.interval + entry.jitter;
+            let next_run = match self.next_runs.get(&entry.id) {
+                Some(next_run) => std::cmp::min(*next_run, latest),
+                None => now + random_duration(entry.jitter),
+            };
+            next_runs.insert(entry.id.clone(), next_run);
+        }
+
+        self.schedule = schedule;
+        self.next_runs = next_runs;
+        self.persist();
+    }
+
+    /// Returns the earliest time at which any of the entries becomes due.
+    ///
+    /// `None` is returned if the schedule is empty.
+    pub fn next_run_time(&self) -> Option<SystemTime> {
+        self.next_runs.values().min().copied()
+    }
+
+    /// Returns requests for all the entries that are due now.
+    ///
+    /// Each due entry is issued only once, no matter how many of its runs have
+    /// been missed, and its next run is scheduled an interval (and a random
+    /// jitter) from now.
+    pub fn poll(&mut self) -> Vec<crate::Request> {
+        let now = self.clock.now();
+
+        let mut requests = Vec::new();
+        for entry in &self.schedule.entries {
+            let Some(next_run) = self.next_runs.get_mut(&entry.id) else {
+                continue;
+            };
+            if now < *next_run {
+                continue;
+            }
+
+            *next_run = now + entry.interval + random_duration(entry.jitter);
+
+            match entry.request(now) {
+                Ok(request) => requests.push(request),
+                Err(error) => {
+                    log::error!("invalid request of schedule entry '{}': {error}", entry.id);
+                }
+            }
+        }
+
+        if !requests.is_empty() {
+            self.persist();
+        }
+
+        requests
+    }
+
+    /// Persists the next run times in the state directory (if there is one).
+    fn persist(&self) {
+        let Some(path) = &self.state_path else {
+            return;
+        };
+
+        if let Err(error) = save_next_runs(path, &self.next_runs) {
+            log::error!("failed to persist schedule state '{}': {error}", path.display());
+        }
+    }
+}
+
+impl Entry {
+
+    /// Creates a request for the run of the entry at the given time.
+    fn request(&self, time: SystemTime) -> Result<crate::Request, crate::ParseRequestError> {
+        let mut proto = rrg_proto::rrg::Request::new();
+        // Scheduled requests are not issued by any flow. Identifiers of their
+        // runs are the run times, which are unique as intervals are at least
+        // a second long.
+        proto.set_flow_id(0);
+        proto.set_request_id(rrg_proto::secs(time).unwrap_or(0));
+        proto.set_action(self.action.into());
+        proto.set_args(self.args.clone());
+
+        let mut request = crate::Request::try_from(proto)?;
+        request.set_schedule_id(self.id.clone());
+
+        Ok(request)
+    }
+}
+
+/// Returns a random duration not longer than the given one.
+fn random_duration(max: Duration) -> Duration {
+    use std::hash::{BuildHasher as _, Hasher as _};
+
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+
+    // Jitter does not need to be cryptographically secure, so we just use the
+    // randomly seeded hasher from the standard library as a source of entropy.
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+
+    let max_nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+    Duration::from_nanos(random % max_nanos.saturating_add(1))
+}
+
+/// Loads the next run times persisted at the given path.
+///
+/// If the file does not exist (e.g. the agent runs for the first time), no run
+/// times are returned.
+fn load_next_runs(path: &Path) -> std::io::Result<HashMap<String, SystemTime>> {
+    use protobuf::Message as _;
+
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(HashMap::new());
+        }
+        Err(error) => return Err(error),
+    };
+
+    fn invalid_data<E>(error: E) -> std::io::Error
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+
+    let mut proto = rrg_proto::schedule::State::parse_from_bytes(&bytes)
+        .map_err(invalid_data)?;
+
+    let mut next_runs = HashMap::new();
+    for mut next_run in proto.take_next_runs() {
+        let time = rrg_proto::try_from_timestamp(next_run.take_time())
+            .map_err(invalid_data)?;
+        next_runs.insert(next_run.take_entry_id(), time);
+    }
+
+    Ok(next_runs)
+}
+
+/// Persists the given next run times at the given path.
+fn save_next_runs(
+    path: &Path,
+    next_runs: &HashMap<String, SystemTime>,
+) -> std::io::Result<()> {
+    use protobuf::Message as _;
+
+    let mut proto = rrg_proto::schedule::State::new();
+    for (entry_id, time) in next_runs {
+        let mut next_run = rrg_proto::schedule::NextRun::new();
+        next_run.set_entry_id(entry_id.clone());
+        next_run.set_time(rrg_proto::into_timestamp(*time));
+        proto.mut_next_runs().push(next_run);
+    }
+
+    let bytes = proto.write_to_bytes()
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    crate::state::write_atomic(path, &bytes)
+}
+
+impl TryFrom<rrg_proto::schedule::Schedule> for Schedule {
+
+    type Error = ParseError;
+
+    fn try_from(mut proto: rrg_proto::schedule::Schedule) -> Result<Schedule, ParseError> {
+        let mut entries = Vec::<Entry>::new();
+
+        for proto in proto.take_entries() {
+            let entry = Entry::try_from(proto)?;
+            if entries.iter().any(|other| other.id == entry.id) {
+                return Err(ParseError::DuplicateId(entry.id));
+            }
+            entries.push(entry);
+        }
+
+        Ok(Schedule { entries })
+    }
+}
+
+impl TryFrom<rrg_proto::schedule::Entry> for Entry {
+
+    type Error = ParseError;
+
+    fn try_from(mut proto: rrg_proto::schedule::Entry) -> Result<Entry, ParseError> {
+        let id = proto.take_id();
+        if id.is_empty() {
+            return Err(ParseError::MissingId);
+        }
+
+        let action = crate::request::Action::try_from(proto.action())
+            .map_err(|action| ParseError::UnknownAction(id.clone(), action))?;
+
+        let interval = rrg_proto::try_from_duration(proto.take_interval())
+            .map_err(|error| ParseError::InvalidDuration(id.clone(), error))?;
+        if interval < Duration::from_secs(1) {
+            return Err(ParseError::IntervalTooShort(id));
+        }
+
+        let jitter = rrg_proto::try_from_duration(proto.take_jitter())
+            .map_err(|error| ParseError::InvalidDuration(id.clone(), error))?;
+
+        Ok(Entry {
+            id,
+            action,
+            args: proto.take_args(),
+            interval,
+            jitter,
+        })
+    }
+}
+
+/// An error that can occur when parsing a schedule.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The serialized schedule message is malformed.
+    Malformed(protobuf::Error),
+    /// One of the entries has no identifier.
+    MissingId,
+    /// More than one entry has the given identifier.
+    DuplicateId(String),
+    /// The entry with the given identifier specifies an unknown action.
+    UnknownAction(String, crate::request::UnknownAction),
+    /// The entry with the given identifier has an invalid interval or jitter.
+    InvalidDuration(String, rrg_proto::ParseDurationError),
+    /// The entry with the given identifier has an interval below a second.
+    IntervalTooShort(String),
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::Malformed(error) => {
+                write!(fmt, "malformed schedule: {error}")
+            }
+            ParseError::MissingId => {
+                write!(fmt, "schedule entry without identifier")
+            }
+            ParseError::DuplicateId(id) => {
+                write!(fmt, "duplicated schedule entry '{id}'")
+            }
+            ParseError::UnknownAction(id, action) => {
+                write!(fmt, "schedule entry '{id}' with invalid action: {action}")
+            }
+            ParseError::InvalidDuration(id, error) => {
+                write!(fmt, "schedule entry '{id}' with invalid duration: {error}")
+            }
+            ParseError::IntervalTooShort(id) => {
+                write!(fmt, "schedule entry '{id}' with interval below a second")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Malformed(error) => Some(error),
+            ParseError::MissingId => None,
+            ParseError::DuplicateId(_) => None,
+            ParseError::UnknownAction(_, _) => None,
+            ParseError::InvalidDuration(_, error) => Some(error),
+            ParseError::IntervalTooShort(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// [`Clock`] that only moves forward when explicitly told to.
+    struct FakeClock {
+        now: Cell<SystemTime>,
+    }
+
+    impl FakeClock {
+
+        fn new() -> FakeClock {
+            FakeClock {
+                now: Cell::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)),
+            }
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+    }
+
+    impl Clock for FakeClock {
+
+        fn now(&self) -> SystemTime {
+            self.now.get()
+        }
+    }
+
+    fn entry(id: &str, interval_secs: u64, jitter_secs: u64) -> Entry {
+        Entry {
+            id: String::from(id),
+            action: crate::request::Action::GetSystemMetadata,
+            args: protobuf::well_known_types::any::Any::new(),
+            interval: Duration::from_secs(interval_secs),
+            jitter: Duration::from_secs(jitter_secs),
+        }
+    }
+
+    fn schedule(entries: Vec<Entry>) -> Schedule {
+        Schedule { entries }
+    }
+
+    fn poll_ids<C: Clock>(scheduler: &mut Scheduler<C>) -> Vec<String> {
+        scheduler.poll().into_iter()
+            .map(|request| request.schedule_id().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn poll_fires_at_interval() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 10, 0),
+        ]), None);
+
+        // New entries without jitter are due right away.
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+        assert!(poll_ids(&mut scheduler).is_empty());
+
+        clock.advance(Duration::from_secs(9));
+        assert!(poll_ids(&mut scheduler).is_empty());
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+
+        assert_eq!(scheduler.next_run_time(), Some(clock.now() + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn poll_many_entries() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 2, 0),
+            entry("bar", 3, 0),
+        ]), None);
+
+        let mut foo_count = 0;
+        let mut bar_count = 0;
+        for _ in 0..12 {
+            for id in poll_ids(&mut scheduler) {
+                match id.as_str() {
+                    "foo" => foo_count += 1,
+                    "bar" => bar_count += 1,
+                    _ => panic!("unexpected entry '{id}'"),
+                }
+            }
+            clock.advance(Duration::from_secs(1));
+        }
+
+        assert_eq!(foo_count, 6);
+        assert_eq!(bar_count, 4);
+    }
+
+    #[test]
+    fn poll_request() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 10, 0),
+        ]), None);
+
+        let requests = scheduler.poll();
+        assert_eq!(requests.len(), 1);
+
+        let request = &requests[0];
+        assert_eq!(request.action(), crate::request::Action::GetSystemMetadata);
+        assert_eq!(request.schedule_id(), Some("foo"));
+        assert_eq!(request.id().flow_id(), 0);
+        assert_eq!(request.id().request_id(), rrg_proto::secs(clock.now()).unwrap());
+    }
+
+    #[test]
+    fn poll_missed_runs_once() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 10, 0),
+        ]), None);
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+
+        // Many runs were missed (e.g. because the system was suspended), but
+        // we execute the entry only once.
+        clock.advance(Duration::from_secs(100));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+        assert!(poll_ids(&mut scheduler).is_empty());
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+    }
+
+    #[test]
+    fn poll_jitter_bounds() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 10, 5),
+        ]), None);
+
+        // The first run is delayed by at most the jitter.
+        let first_run = scheduler.next_run_time().unwrap();
+        assert!(clock.now() <= first_run);
+        assert!(first_run <= clock.now() + Duration::from_secs(5));
+
+        clock.advance(Duration::from_secs(5));
+
+        for _ in 0..64 {
+            assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+
+            let next_run = scheduler.next_run_time().unwrap();
+            assert!(clock.now() + Duration::from_secs(10) <= next_run);
+            assert!(next_run <= clock.now() + Duration::from_secs(15));
+
+            clock.advance(Duration::from_secs(15));
+        }
+    }
+
+    #[test]
+    fn poll_jitter_varies() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 1, 3600),
+        ]), None);
+
+        clock.advance(Duration::from_secs(3600));
+
+        let mut delays = std::collections::HashSet::new();
+        for _ in 0..16 {
+            assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+            delays.insert(scheduler.next_run_time().unwrap().duration_since(clock.now()).unwrap());
+
+            clock.advance(Duration::from_secs(3601));
+        }
+
+        assert!(delays.len() > 1);
+    }
+
+    #[test]
+    fn new_persisted_next_runs() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let clock = FakeClock::new();
+
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 60, 0),
+        ]), Some(tempdir.path()));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+        drop(scheduler);
+
+        // The agent restarts before the next run is due: the entry should not
+        // be executed again right away.
+        clock.advance(Duration::from_secs(30));
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 60, 0),
+        ]), Some(tempdir.path()));
+        assert!(poll_ids(&mut scheduler).is_empty());
+        assert_eq!(scheduler.next_run_time(), Some(clock.now() + Duration::from_secs(30)));
+        drop(scheduler);
+
+        // The agent was down for a long time: the entry should be executed only
+        // once.
+        clock.advance(Duration::from_secs(3600));
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 60, 0),
+        ]), Some(tempdir.path()));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+        assert!(poll_ids(&mut scheduler).is_empty());
+    }
+
+    #[test]
+    fn new_invalid_state() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join(STATE_FILE_NAME), b"\xff\xff\xff")
+            .unwrap();
+
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 60, 0),
+        ]), Some(tempdir.path()));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+    }
+
+    #[test]
+    fn reload_keeps_next_runs() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 60, 0),
+        ]), None);
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+
+        clock.advance(Duration::from_secs(10));
+        scheduler.reload(schedule(vec![
+            entry("foo", 60, 0),
+            entry("bar", 60, 0),
+        ]));
+        assert_eq!(poll_ids(&mut scheduler), vec!["bar"]);
+
+        clock.advance(Duration::from_secs(50));
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+    }
+
+    #[test]
+    fn reload_shorter_interval() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 3600, 0),
+        ]), None);
+        assert_eq!(poll_ids(&mut scheduler), vec!["foo"]);
+
+        scheduler.reload(schedule(vec![
+            entry("foo", 60, 0),
+        ]));
+        assert_eq!(scheduler.next_run_time(), Some(clock.now() + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn reload_empty() {
+        let clock = FakeClock::new();
+        let mut scheduler = Scheduler::new(&clock, schedule(vec![
+            entry("foo", 60, 0),
+        ]), None);
+
+        scheduler.reload(Schedule::default());
+        assert_eq!(scheduler.next_run_time(), None);
+        assert!(poll_ids(&mut scheduler).is_empty());
+    }
+
+    #[test]
+    fn parse_ok() {
+        use protobuf::Message as _;
+
+        let mut entry = rrg_proto::schedule::Entry::new();
+        entry.set_id(String::from("foo"));
+        entry.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+        entry.mut_interval().seconds = 60;
+        entry.mut_jitter().seconds = 5;
+
+        let mut proto = rrg_proto::schedule::Schedule::new();
+        proto.mut_entries().push(entry);
+
+        let schedule = Schedule::parse(&proto.write_to_bytes().unwrap())
+            .unwrap();
+        assert_eq!(schedule.entry_ids().collect::<Vec<_>>(), vec!["foo"]);
+        assert_eq!(schedule.entries[0].interval, Duration::from_secs(60));
+        assert_eq!(schedule.entries[0].jitter, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn parse_interval_too_short() {
+        use protobuf::Message as _;
+
+        let mut entry = rrg_proto::schedule::Entry::new();
+        entry.set_id(String::from("foo"));
+        entry.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+
+        let mut proto = rrg_proto::schedule::Schedule::new();
+        proto.mut_entries().push(entry);
+
+        let error = Schedule::parse(&proto.write_to_bytes().unwrap())
+            .unwrap_err();
+        assert!(matches!(error, ParseError::IntervalTooShort(id) if id == "foo"));
+    }
+
+    #[test]
+    fn parse_duplicate_id() {
+        use protobuf::Message as _;
+
+        let mut entry = rrg_proto::schedule::Entry::new();
+        entry.set_id(String::from("foo"));
+        entry.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+        entry.mut_interval().seconds = 60;
+
+        let mut proto = rrg_proto::schedule::Schedule::new();
+        proto.mut_entries().push(entry.clone());
+        proto.mut_entries().push(entry);
+
+        let error = Schedule::parse(&proto.write_to_bytes().unwrap())
+            .unwrap_err();
+        assert!(matches!(error, ParseError::DuplicateId(id) if id == "foo"));
+    }
+
+    #[test]
+    fn parse_missing_id() {
+        use protobuf::Message as _;
+
+        let mut entry = rrg_proto::schedule::Entry::new();
+        entry.set_action(rrg_proto::rrg::Action::GET_SYSTEM_METADATA);
+        entry.mut_interval().seconds = 60;
+
+        let mut proto = rrg_proto::schedule::Schedule::new();
+        proto.mut_entries().push(entry);
+
+        let error = Schedule::parse(&proto.write_to_bytes().unwrap())
+            .unwrap_err();
+        assert!(matches!(error, ParseError::MissingId));
+    }
+}
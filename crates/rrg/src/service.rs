@@ -0,0 +1,168 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Integration with service managers of the operating system.
+//!
+//! On Linux, the agent can run as a systemd unit of `Type=notify`: it notifies
+//! systemd once it is ready to handle requests and, if the unit has a watchdog
+//! configured, keeps pinging it as long as the main loop is alive (see the
+//! [`systemd`] module). A hung agent stops pinging and is restarted.
+//!
+//! On Windows, the agent can run as a service started by the Service Control
+//! Manager (see the [`windows`] module).
+//!
+//! The main loop is considered alive while it waits for requests and while the
+//! request it handles makes progress (i.e. the action uses its session). It is
+//! considered hung once it has not ticked for the duration of the watchdog
+//! timeout.
+
+#[cfg(target_os = "linux")]
+mod systemd;
+
+#[cfg(target_family = "windows")]
+pub mod windows;
+
+/// Time of the last tick of the main loop, `None` while it is idle.
+static LAST_TICK: std::sync::Mutex<Option<std::time::Instant>> = {
+    std::sync::Mutex::new(None)
+};
+
+/// Runs the given agent main function under the service manager.
+///
+/// On Windows, if the agent has been started by the Service Control Manager,
+/// the calling thread becomes the service control dispatcher and the function
+/// is run on the service thread. Otherwise, the function is simply called.
+pub fn run<F>(main: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(target_family = "windows")]
+    windows::run(main);
+
+    #[cfg(not(target_family = "windows"))]
+    main();
+}
+
+/// Notifies the service manager that the agent is ready to handle requests.
+///
+/// On Linux, this also spawns the thread pinging the systemd watchdog if it is
+/// enabled for the unit.
+pub fn ready() {
+    tick();
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Err(error) = systemd::notify(&[("READY", "1")]) {
+            log::error!("failed to notify systemd about readiness: {error}");
+        }
+
+        if let Some(timeout) = systemd::watchdog_timeout() {
+            log::info! {
+                "systemd watchdog enabled with timeout of {}",
+                humantime::format_duration(timeout),
+            };
+
+            let result = std::thread::Builder::new()
+                .name(String::from("watchdog"))
+                .spawn(move || watch(timeout));
+            if let Err(error) = result {
+                log::error!("failed to spawn systemd watchdog: {error}");
+            }
+        }
+    }
+
+    #[cfg(target_family = "windows")]
+    windows::ready();
+}
+
+/// Notifies the service manager that the agent is shutting down.
+///
+/// The agent is expected to stop within the given duration.
+#[cfg_attr(not(target_family = "windows"), allow(unused_variables))]
+pub fn stopping(wait_hint: std::time::Duration) {
+    #[cfg(target_os = "linux")]
+    if let Err(error) = systemd::notify(&[("STOPPING", "1")]) {
+        log::error!("failed to notify systemd about stopping: {error}");
+    }
+
+    #[cfg(target_family = "windows")]
+    windows::stopping(wait_hint);
+}
+
+/// Notifies the service manager that the agent exits with the given code.
+///
+/// This should be called right before the process exits.
+#[cfg_attr(not(target_family = "windows"), allow(unused_variables))]
+pub fn stopped(exit_code: u32) {
+    #[cfg(target_family = "windows")]
+    windows::stopped(exit_code);
+}
+
+/// Marks the main loop as alive and busy (e.g. handling a request).
+pub fn tick() {
+    *last_tick() = Some(std::time::Instant::now());
+}
+
+/// Marks the main loop as alive and idle (waiting for requests).
+pub fn idle() {
+    *last_tick() = None;
+}
+
+/// Returns whether the main loop ticked within the given timeout (or is idle).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn is_alive(timeout: std::time::Duration) -> bool {
+    match *last_tick() {
+        Some(last_tick) => last_tick.elapsed() < timeout,
+        None => true,
+    }
+}
+
+/// Locks the time of the last tick of the main loop.
+fn last_tick() -> std::sync::MutexGuard<'static, Option<std::time::Instant>> {
+    // The value is always valid, so there is no harm in using it even if some
+    // thread panicked while holding the lock.
+    LAST_TICK.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Pings the systemd watchdog for as long as the main loop is alive.
+#[cfg(target_os = "linux")]
+fn watch(timeout: std::time::Duration) -> ! {
+    // Pinging at half of the timeout is what systemd recommends so that a
+    // delayed ping does not get the unit killed.
+    let interval = timeout / 2;
+
+    loop {
+        std::thread::sleep(interval);
+
+        if !is_alive(timeout) {
+            log::error!("main loop is not alive, not pinging systemd watchdog");
+            continue;
+        }
+
+        if let Err(error) = systemd::notify(&[("WATCHDOG", "1")]) {
+            log::error!("failed to ping systemd watchdog: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn is_alive_after_tick_and_idle() {
+        let timeout = std::time::Duration::from_millis(50);
+
+        tick();
+        assert!(is_alive(timeout));
+
+        std::thread::sleep(timeout);
+        assert!(!is_alive(timeout));
+
+        idle();
+        assert!(is_alive(timeout));
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Support for the systemd service notification protocol [1].
+//!
+//! Notifications are datagrams with newline-separated `KEY=VALUE` assignments
+//! sent to the socket specified in the `NOTIFY_SOCKET` environment variable.
+//! If the variable is not set, the agent has not been started by systemd (or
+//! the unit is not of `Type=notify`) and notifications are silently dropped.
+//!
+//! [1]: https://www.freedesktop.org/software/systemd/man/latest/sd_notify.html
+
+/// Sends a notification with the given state assignments to systemd.
+pub fn notify(state: &[(&str, &str)]) -> std::io::Result<()> {
+    match std::env::var_os("NOTIFY_SOCKET") {
+        Some(socket_path) => notify_to(&socket_path, state),
+        None => Ok(()),
+    }
+}
+
+/// Returns the timeout of the watchdog if it is enabled for the agent.
+pub fn watchdog_timeout() -> Option<std::time::Duration> {
+    parse_watchdog_timeout(
+        std::env::var("WATCHDOG_USEC").ok().as_deref(),
+        std::env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+/// Sends a notification with the given state assignments to the given socket.
+///
+/// Socket paths starting with `@` refer to the abstract namespace.
+fn notify_to(
+    socket_path: &std::ffi::OsStr,
+    state: &[(&str, &str)],
+) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+    let message = format(state);
+
+    match socket_path.as_bytes() {
+        [b'@', name @ ..] => {
+            use std::os::linux::net::SocketAddrExt as _;
+
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(message.as_bytes(), &addr)?;
+        }
+        _ => {
+            socket.send_to(message.as_bytes(), socket_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats the given state assignments as a notification message.
+///
+/// Assignments are separated by newlines, so newlines in values (e.g. in the
+/// free-form `STATUS`) are replaced with spaces.
+fn format(state: &[(&str, &str)]) -> String {
+    let mut message = String::new();
+    for (key, value) in state {
+        message.push_str(key);
+        message.push('=');
+        message.push_str(&value.replace('\n', " "));
+        message.push('\n');
+    }
+
+    message
+}
+
+/// Parses the watchdog timeout out of the values of the environment variables.
+///
+/// The watchdog is enabled only if the timeout is set and the process it is
+/// meant for (if specified) is the given one.
+fn parse_watchdog_timeout(
+    usec: Option<&str>,
+    pid: Option<&str>,
+    own_pid: u32,
+) -> Option<std::time::Duration> {
+    if let Some(pid) = pid {
+        if pid.parse::<u32>().ok() != Some(own_pid) {
+            return None;
+        }
+    }
+
+    match usec?.parse::<u64>() {
+        Ok(0) | Err(_) => None,
+        Ok(usec) => Some(std::time::Duration::from_micros(usec)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn format_empty() {
+        assert_eq!(format(&[]), "");
+    }
+
+    #[test]
+    fn format_single() {
+        assert_eq!(format(&[("READY", "1")]), "READY=1\n");
+    }
+
+    #[test]
+    fn format_multiple() {
+        let message = format(&[
+            ("READY", "1"),
+            ("STATUS", "listening for messages"),
+        ]);
+        assert_eq!(message, "READY=1\nSTATUS=listening for messages\n");
+    }
+
+    #[test]
+    fn format_newline_in_value() {
+        assert_eq!(format(&[("STATUS", "foo\nbar")]), "STATUS=foo bar\n");
+    }
+
+    #[test]
+    fn notify_to_path() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let socket_path = tempdir.path().join("notify");
+
+        let socket = std::os::unix::net::UnixDatagram::bind(&socket_path)
+            .unwrap();
+
+        notify_to(socket_path.as_os_str(), &[("WATCHDOG", "1")])
+            .unwrap();
+
+        let mut buf = [0; 64];
+        let len = socket.recv(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"WATCHDOG=1\n");
+    }
+
+    #[test]
+    fn notify_to_abstract() {
+        use std::os::linux::net::SocketAddrExt as _;
+
+        let name = format!("rrg-test-notify-{}", std::process::id());
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(&name)
+            .unwrap();
+        let socket = std::os::unix::net::UnixDatagram::bind_addr(&addr)
+            .unwrap();
+
+        notify_to(format!("@{name}").as_ref(), &[("STOPPING", "1")])
+            .unwrap();
+
+        let mut buf = [0; 64];
+        let len = socket.recv(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"STOPPING=1\n");
+    }
+
+    #[test]
+    fn parse_watchdog_timeout_unset() {
+        assert_eq!(parse_watchdog_timeout(None, None, 42), None);
+    }
+
+    #[test]
+    fn parse_watchdog_timeout_without_pid() {
+        assert_eq! {
+            parse_watchdog_timeout(Some("30000000"), None, 42),
+            Some(std::time::Duration::from_secs(30)),
+        };
+    }
+
+    #[test]
+    fn parse_watchdog_timeout_own_pid() {
+        assert_eq! {
+            parse_watchdog_timeout(Some("500000"), Some("42"), 42),
+            Some(std::time::Duration::from_millis(500)),
+        };
+    }
+
+    #[test]
+    fn parse_watchdog_timeout_other_pid() {
+        assert_eq!(parse_watchdog_timeout(Some("500000"), Some("1337"), 42), None);
+    }
+
+    #[test]
+    fn parse_watchdog_timeout_invalid() {
+        assert_eq!(parse_watchdog_timeout(Some("foo"), None, 42), None);
+        assert_eq!(parse_watchdog_timeout(Some("0"), None, 42), None);
+    }
+}
@@ -0,0 +1,496 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Integration with the Windows Service Control Manager (SCM).
+//!
+//! If the agent has been started by the SCM, the main thread becomes the
+//! service control dispatcher [1] and the agent runs on the thread of the
+//! service main function. Stop and shutdown controls request the agent to shut
+//! down gracefully (see the [`crate::shutdown`] module).
+//!
+//! The SCM is informed about the exit code once the agent stops: a non-zero
+//! code is reported as a service-specific error, so that recovery actions of
+//! the service are triggered if they are enabled for non-crash failures. If
+//! the agent crashes without reporting the stop, the SCM considers it a crash
+//! and triggers the recovery actions regardless.
+//!
+//! [1]: https://learn.microsoft.com/en-us/windows/win32/services/service-control-dispatcher
+
+/// Time that the SCM should wait for the agent to stop if not specified.
+const DEFAULT_STOP_WAIT_HINT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// State machine of the service if the agent is running as one.
+static SERVICE: std::sync::Mutex<Option<StateMachine<sys::Handle>>> = {
+    std::sync::Mutex::new(None)
+};
+
+/// Main function of the agent to run on the service thread.
+static MAIN: std::sync::Mutex<Option<Box<dyn FnOnce() + Send>>> = {
+    std::sync::Mutex::new(None)
+};
+
+/// Runs the given agent main function as a service if started by the SCM.
+///
+/// If the agent has not been started by the SCM, the function is just called.
+pub fn run<F>(main: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    *lock(&MAIN) = Some(Box::new(main));
+
+    match sys::dispatch() {
+        // The dispatcher returns once the service is stopped.
+        Ok(()) => return,
+        Err(error) if error.raw_os_error() == Some(sys::NOT_SERVICE_ERROR) => (),
+        Err(error) => log::error!("failed to start service dispatcher: {error}"),
+    }
+
+    if let Some(main) = lock(&MAIN).take() {
+        main();
+    }
+}
+
+/// Reports that the service is running (if the agent runs as a service).
+pub fn ready() {
+    if let Some(service) = lock(&SERVICE).as_mut() {
+        service.running();
+    }
+}
+
+/// Reports that the service is stopping (if the agent runs as a service).
+pub fn stopping(wait_hint: std::time::Duration) {
+    if let Some(service) = lock(&SERVICE).as_mut() {
+        service.stop_pending(wait_hint);
+    }
+}
+
+/// Reports that the service has stopped (if the agent runs as a service).
+pub fn stopped(exit_code: u32) {
+    if let Some(service) = lock(&SERVICE).as_mut() {
+        service.stopped(exit_code);
+    }
+}
+
+/// State of the service as reported to the SCM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    /// The service is starting.
+    StartPending,
+    /// The service is running and accepts stop and shutdown controls.
+    Running,
+    /// The service is stopping.
+    StopPending,
+    /// The service has stopped.
+    Stopped,
+}
+
+/// Status of the service as reported to the SCM.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    /// Current state of the service.
+    pub state: State,
+    /// Service-specific exit code (zero if there was no error).
+    pub exit_code: u32,
+    /// Progress indicator of a pending state, incremented on every report.
+    pub checkpoint: u32,
+    /// Time the SCM should wait until the next report of a pending state.
+    pub wait_hint: std::time::Duration,
+}
+
+/// Control request sent by the SCM to the service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// The service should stop.
+    Stop,
+    /// The system is shutting down.
+    Shutdown,
+    /// The service should report its current status.
+    Interrogate,
+}
+
+/// Interface of the SCM to which the service reports its status.
+pub trait Scm {
+
+    /// Reports the given status of the service to the SCM.
+    fn set_status(&mut self, status: Status) -> std::io::Result<()>;
+}
+
+/// State machine of the service reporting its transitions to the SCM.
+///
+/// The service goes through the start pending, running, stop pending and
+/// stopped states (the stop pending state can also be entered directly from
+/// the start pending one). Transitions in any other direction are ignored.
+pub struct StateMachine<S: Scm> {
+    /// Interface to report the status of the service to.
+    scm: S,
+    /// Last reported status of the service.
+    status: Status,
+}
+
+impl<S: Scm> StateMachine<S> {
+
+    /// Creates a state machine of a service that is starting.
+    pub fn new(scm: S) -> StateMachine<S> {
+        let mut machine = StateMachine {
+            scm,
+            status: Status {
+                state: State::StartPending,
+                exit_code: 0,
+                checkpoint: 0,
+                wait_hint: std::time::Duration::ZERO,
+            },
+        };
+        machine.set(State::StartPending, std::time::Duration::ZERO);
+
+        machine
+    }
+
+    /// Returns the current state of the service.
+    pub fn state(&self) -> State {
+        self.status.state
+    }
+
+    /// Transitions the service to the running state.
+    pub fn running(&mut self) {
+        if self.status.state == State::StartPending {
+            self.set(State::Running, std::time::Duration::ZERO);
+        }
+    }
+
+    /// Transitions the service to the stop pending state.
+    ///
+    /// If the service is stopping already, the progress is reported again with
+    /// the new wait hint.
+    pub fn stop_pending(&mut self, wait_hint: std::time::Duration) {
+        if self.status.state != State::Stopped {
+            self.set(State::StopPending, wait_hint);
+        }
+    }
+
+    /// Transitions the service to the stopped state with the given exit code.
+    pub fn stopped(&mut self, exit_code: u32) {
+        if self.status.state != State::Stopped {
+            self.status.exit_code = exit_code;
+            self.set(State::Stopped, std::time::Duration::ZERO);
+        }
+    }
+
+    /// Handles the given control request sent by the SCM.
+    ///
+    /// Returns `true` if the agent should shut down as a result.
+    pub fn control(&mut self, control: Control) -> bool {
+        match control {
+            Control::Stop | Control::Shutdown => match self.status.state {
+                State::StartPending | State::Running => {
+                    self.stop_pending(DEFAULT_STOP_WAIT_HINT);
+                    true
+                }
+                State::StopPending | State::Stopped => false,
+            },
+            Control::Interrogate => {
+                self.report();
+                false
+            }
+        }
+    }
+
+    /// Sets the service to the given state and reports it to the SCM.
+    fn set(&mut self, state: State, wait_hint: std::time::Duration) {
+        self.status.checkpoint = match state {
+            State::StartPending | State::StopPending => {
+                self.status.checkpoint + 1
+            }
+            State::Running | State::Stopped => 0,
+        };
+        self.status.state = state;
+        self.status.wait_hint = wait_hint;
+
+        self.report();
+    }
+
+    /// Reports the current status of the service to the SCM.
+    fn report(&mut self) {
+        if let Err(error) = self.scm.set_status(self.status) {
+            log::error!("failed to report service status: {error}");
+        }
+    }
+}
+
+/// Locks the given mutex even if some thread panicked while holding it.
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+mod sys {
+
+    use windows_sys::core::PWSTR;
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::Services::*,
+    };
+
+    /// Error returned by the dispatcher if not started by the SCM.
+    pub const NOT_SERVICE_ERROR: i32 = ERROR_FAILED_SERVICE_CONTROLLER_CONNECT as i32;
+
+    /// Handle to report the status of the service with.
+    pub struct Handle(SERVICE_STATUS_HANDLE);
+
+    // SAFETY: Service status handles can be used from any thread.
+    unsafe impl Send for Handle {}
+
+    impl super::Scm for Handle {
+
+        fn set_status(&mut self, status: super::Status) -> std::io::Result<()> {
+            use super::State::*;
+
+            let (current_state, controls_accepted) = match status.state {
+                StartPending => (SERVICE_START_PENDING, 0),
+                Running => (SERVICE_RUNNING, SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN),
+                StopPending => (SERVICE_STOP_PENDING, 0),
+                Stopped => (SERVICE_STOPPED, 0),
+            };
+
+            let (win32_exit_code, service_specific_exit_code) = match status.exit_code {
+                0 => (NO_ERROR, 0),
+                exit_code => (ERROR_SERVICE_SPECIFIC_ERROR, exit_code),
+            };
+
+            let status = SERVICE_STATUS {
+                dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+                dwCurrentState: current_state,
+                dwControlsAccepted: controls_accepted,
+                dwWin32ExitCode: win32_exit_code,
+                dwServiceSpecificExitCode: service_specific_exit_code,
+                dwCheckPoint: status.checkpoint,
+                dwWaitHint: u32::try_from(status.wait_hint.as_millis())
+                    .unwrap_or(u32::MAX),
+            };
+
+            // SAFETY: The handle was returned by the handler registration and
+            // the status is a valid structure. We verify the result below.
+            let result = unsafe {
+                SetServiceStatus(self.0, &status)
+            };
+            if result == FALSE {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Connects the calling thread to the SCM as the service dispatcher.
+    ///
+    /// The function returns once the service is stopped.
+    pub fn dispatch() -> std::io::Result<()> {
+        // The name is ignored for services running in their own process.
+        let mut name = [0u16];
+
+        let table = [
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: name.as_mut_ptr(),
+                lpServiceProc: Some(service_main),
+            },
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: std::ptr::null_mut(),
+                lpServiceProc: None,
+            },
+        ];
+
+        // SAFETY: The table is terminated with a null entry and outlives the
+        // call. We verify the result below.
+        let result = unsafe {
+            StartServiceCtrlDispatcherW(table.as_ptr())
+        };
+        if result == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Entry point of the service called by the dispatcher on a new thread.
+    unsafe extern "system" fn service_main(argc: u32, argv: *mut PWSTR) {
+        // The first argument is the name of the service.
+        let name = if argc > 0 && !argv.is_null() {
+            // SAFETY: There are `argc` valid arguments.
+            unsafe { *argv }
+        } else {
+            return;
+        };
+
+        // SAFETY: The name is a valid null-terminated string passed by the
+        // dispatcher and the handler is a valid function with the expected
+        // signature. We verify the result below.
+        let handle = unsafe {
+            RegisterServiceCtrlHandlerExW(name, Some(handle_control), std::ptr::null())
+        };
+        if handle.is_null() {
+            let error = std::io::Error::last_os_error();
+            log::error!("failed to register service control handler: {error}");
+            return;
+        }
+
+        *super::lock(&super::SERVICE) = Some(super::StateMachine::new(Handle(handle)));
+
+        if let Some(main) = super::lock(&super::MAIN).take() {
+            main();
+        }
+
+        // The agent main function is not expected to return, but if it does
+        // the service has to be reported as stopped.
+        super::stopped(0);
+    }
+
+    /// Handles control requests sent by the SCM to the service.
+    unsafe extern "system" fn handle_control(
+        control: u32,
+        _event_type: u32,
+        _event_data: *mut std::ffi::c_void,
+        _context: *mut std::ffi::c_void,
+    ) -> u32 {
+        let control = match control {
+            SERVICE_CONTROL_STOP => super::Control::Stop,
+            SERVICE_CONTROL_SHUTDOWN => super::Control::Shutdown,
+            SERVICE_CONTROL_INTERROGATE => super::Control::Interrogate,
+            _ => return ERROR_CALL_NOT_IMPLEMENTED,
+        };
+
+        let should_shut_down = match super::lock(&super::SERVICE).as_mut() {
+            Some(service) => service.control(control),
+            None => false,
+        };
+        if should_shut_down {
+            crate::shutdown::request();
+        }
+
+        NO_ERROR
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// SCM interface that records reported statuses.
+    #[derive(Default)]
+    struct FakeScm {
+        statuses: Vec<Status>,
+    }
+
+    impl Scm for FakeScm {
+
+        fn set_status(&mut self, status: Status) -> std::io::Result<()> {
+            self.statuses.push(status);
+            Ok(())
+        }
+    }
+
+    /// Returns states of all the statuses reported by the state machine.
+    fn states(machine: &StateMachine<FakeScm>) -> Vec<State> {
+        machine.scm.statuses.iter()
+            .map(|status| status.state)
+            .collect()
+    }
+
+    #[test]
+    fn state_machine_start_pending() {
+        let machine = StateMachine::new(FakeScm::default());
+        assert_eq!(machine.state(), State::StartPending);
+        assert_eq!(states(&machine), vec![State::StartPending]);
+        assert_eq!(machine.scm.statuses[0].checkpoint, 1);
+    }
+
+    #[test]
+    fn state_machine_running() {
+        let mut machine = StateMachine::new(FakeScm::default());
+        machine.running();
+
+        assert_eq!(machine.state(), State::Running);
+        assert_eq!(states(&machine), vec![State::StartPending, State::Running]);
+        assert_eq!(machine.scm.statuses[1].checkpoint, 0);
+    }
+
+    #[test]
+    fn state_machine_stop() {
+        let mut machine = StateMachine::new(FakeScm::default());
+        machine.running();
+
+        assert!(machine.control(Control::Stop));
+        assert_eq!(machine.state(), State::StopPending);
+
+        machine.stop_pending(std::time::Duration::from_secs(5));
+        machine.stopped(0);
+
+        assert_eq!(states(&machine), vec![
+            State::StartPending,
+            State::Running,
+            State::StopPending,
+            State::StopPending,
+            State::Stopped,
+        ]);
+
+        let statuses = &machine.scm.statuses;
+        assert_eq!(statuses[2].checkpoint, 1);
+        assert_eq!(statuses[2].wait_hint, DEFAULT_STOP_WAIT_HINT);
+        assert_eq!(statuses[3].checkpoint, 2);
+        assert_eq!(statuses[3].wait_hint, std::time::Duration::from_secs(5));
+        assert_eq!(statuses[4].exit_code, 0);
+    }
+
+    #[test]
+    fn state_machine_shutdown_while_starting() {
+        let mut machine = StateMachine::new(FakeScm::default());
+
+        assert!(machine.control(Control::Shutdown));
+        assert_eq!(machine.state(), State::StopPending);
+
+        // The agent might finish its startup before it notices the shutdown,
+        // but the service must not be reported as running again.
+        machine.running();
+        assert_eq!(machine.state(), State::StopPending);
+    }
+
+    #[test]
+    fn state_machine_stop_twice() {
+        let mut machine = StateMachine::new(FakeScm::default());
+        machine.running();
+
+        assert!(machine.control(Control::Stop));
+        assert!(!machine.control(Control::Stop));
+        assert_eq!(states(&machine).len(), 3);
+    }
+
+    #[test]
+    fn state_machine_interrogate() {
+        let mut machine = StateMachine::new(FakeScm::default());
+        machine.running();
+
+        assert!(!machine.control(Control::Interrogate));
+        assert_eq!(machine.state(), State::Running);
+        assert_eq!(states(&machine), vec![
+            State::StartPending,
+            State::Running,
+            State::Running,
+        ]);
+    }
+
+    #[test]
+    fn state_machine_stopped_with_error() {
+        let mut machine = StateMachine::new(FakeScm::default());
+        machine.running();
+        machine.stopped(1);
+
+        assert_eq!(machine.state(), State::Stopped);
+        assert_eq!(machine.scm.statuses[2].exit_code, 1);
+
+        // Nothing is reported once the service is stopped.
+        machine.stopped(0);
+        machine.stop_pending(std::time::Duration::ZERO);
+        assert!(!machine.control(Control::Stop));
+        assert_eq!(states(&machine).len(), 3);
+    }
+}
@@ -16,15 +16,21 @@
 
 mod error;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-fake_session"))]
 mod fake;
+mod file;
 mod fleetspeak;
+mod record;
+mod replay;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-fake_session"))]
 pub use crate::session::fake::FakeSession;
+pub use crate::session::file::FileSession;
 pub use crate::session::fleetspeak::FleetspeakSession;
+pub use crate::session::record::RecordingSession;
+pub use crate::session::replay::ReplaySession;
 
-pub use self::error::{Error};
+pub use self::error::{Error, ErrorCode, ErrorKind};
 
 /// A specialized `Result` type for sessions.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -35,6 +41,9 @@ pub trait Session {
     /// Provides the arguments passed to the agent.
     fn args(&self) -> &crate::args::Args;
 
+    /// Provides the identifier of the request the session handles.
+    fn request_id(&self) -> crate::RequestId;
+
     /// Sends a reply to the flow that call the action.
     fn reply<I>(&mut self, item: I) -> Result<()>
     where I: crate::response::Item + 'static;
@@ -43,8 +52,184 @@ pub trait Session {
     fn send<I>(&mut self, sink: crate::Sink, item: I) -> Result<()>
     where I: crate::response::Item + 'static;
 
+    /// Sends a blob to the blob sink computing its digests in the background.
+    ///
+    /// The blob is sent before this function returns, so a reply referencing
+    /// it (built once the returned digests are ready) is never sent before the
+    /// blob itself.
+    fn send_blob(&mut self, mut blob: crate::blob::Blob) -> Result<crate::blob::PendingDigests> {
+        let digests = blob.digests_pending(crate::blob::digest_algorithms(self.args()));
+        self.send(crate::Sink::Blob, blob)?;
+
+        Ok(digests)
+    }
+
     /// Sends a heartbeat signal to the Fleetspeak process.
     fn heartbeat(&mut self);
+
+    /// Provides the number of bytes sent to the given sink but not delivered.
+    ///
+    /// This covers parcels that are queued (either by the session itself or
+    /// by the transport backend) but have not been acknowledged yet. Actions
+    /// that produce a lot of parcels should not send more while this is high,
+    /// see [`wait_for_sink`].
+    fn sink_pressure(&self, sink: crate::Sink) -> usize;
+
+    /// Provides the redactor of paths in results sent to the server.
+    ///
+    /// Replies are redacted by the session itself, this is needed only for
+    /// paths in opaque payloads (e.g. blobs).
+    fn redactor(&self) -> &crate::redact::Redactor;
+
+    /// Checks whether the session is still within its resource limits.
+    ///
+    /// Replies and parcels are checked by the session itself. Actions that do
+    /// a lot of work between sending these should call this function to abort
+    /// early if any of the limits (e.g. the agent memory watermark) is crossed.
+    ///
+    /// The deadline of the request (if any) is one of the limits.
+    fn check_limits(&self) -> Result<()>;
+
+    /// Provides the moment by which the request should be handled (if any).
+    ///
+    /// The deadline is derived from the real time limit of the request. Long
+    /// running actions can use it to plan their work, e.g. to not start a unit
+    /// of work that has no chance of finishing in time.
+    fn deadline(&self) -> Option<std::time::Instant>;
+
+    /// Checks whether the deadline of the request has not passed yet.
+    ///
+    /// This is a cheaper subset of [`Session::check_limits`] meant for checks
+    /// before expensive phases of actions.
+    fn check_deadline(&self) -> Result<()> {
+        use crate::session::error::DeadlineExceededError;
+
+        if let Some(deadline) = self.deadline() {
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return Err(DeadlineExceededError {
+                    overrun: now.duration_since(deadline),
+                }.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the request should be executed as a dry run.
+    ///
+    /// Handlers are invoked in this mode only if the action supports it (see
+    /// [`crate::action::supports_dry_run`]). Such handlers should validate the
+    /// arguments and reply with a single [`crate::dry_run::Estimate`] without
+    /// sending anything to sinks.
+    fn is_dry_run(&self) -> bool;
+
+    /// Checks whether the agent policy allows the action to access the path.
+    ///
+    /// Paths in the action arguments are checked before the action is executed
+    /// (see [`crate::request::Args::paths`]). Actions that access other paths,
+    /// e.g. ones found when walking a directory or expanding a glob, need to
+    /// check them with this function before opening them.
+    fn check_path(&self, path: &std::path::Path) -> Result<()>;
+
+    /// Provides a scratch directory private to the request.
+    ///
+    /// The directory is created on first use and removed with all its contents
+    /// once the request is handled. See the [`temp`] module for more details.
+    ///
+    /// [`temp`]: crate::temp
+    fn temp_dir(&mut self) -> Result<crate::temp::TempDir>;
+}
+
+/// Interval at which the pressure of a congested sink is checked.
+const SINK_PRESSURE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Waits until the given sink is no longer congested.
+///
+/// If the pressure of the sink (see [`Session::sink_pressure`]) is above the
+/// high watermark specified in the agent arguments, this function blocks until
+/// it drops to the low watermark. If no high watermark is specified, it returns
+/// immediately.
+///
+/// Heartbeats are sent and limits are checked while waiting, so the request
+/// can still be aborted (e.g. when the deadline passes) while it is stalled.
+pub fn wait_for_sink<S>(session: &mut S, sink: crate::Sink) -> Result<()>
+where
+    S: Session,
+{
+    let Some(high_watermark) = session.args().sink_pressure_high_watermark else {
+        return Ok(());
+    };
+    let low_watermark = session.args().sink_pressure_low_watermark
+        .unwrap_or(high_watermark / 2)
+        .min(high_watermark);
+
+    let pressure = session.sink_pressure(sink);
+    if pressure <= high_watermark {
+        return Ok(());
+    }
+
+    log::info!("pausing, pressure of sink '{sink:?}' is {pressure} bytes");
+    let paused = std::time::Instant::now();
+
+    loop {
+        session.heartbeat();
+        session.check_limits()?;
+
+        std::thread::sleep(SINK_PRESSURE_POLL_INTERVAL);
+
+        if session.sink_pressure(sink) <= low_watermark {
+            break;
+        }
+    }
+
+    log::info!("resuming, sink '{sink:?}' was congested for {:?}", paused.elapsed());
+
+    Ok(())
+}
+
+/// Computes the total size of the given parcels addressed to the given sink.
+///
+/// This is meant for sessions that hold back parcels before sending them.
+fn parcels_len(parcels: &[rrg_proto::rrg::Parcel], sink: crate::Sink) -> usize {
+    use protobuf::Message as _;
+
+    let sink = rrg_proto::rrg::Sink::from(sink);
+
+    parcels.iter()
+        .filter(|parcel| parcel.sink() == sink)
+        .map(|parcel| parcel.compute_size() as usize)
+        .sum()
+}
+
+/// Checks whether the agent memory watermark was crossed.
+///
+/// This function will return an error if it was.
+fn check_memory_watermark() -> Result<()> {
+    use crate::session::error::MemoryWatermarkExceededError;
+
+    if let Some(memory_watermark) = crate::resource::memory_watermark() {
+        let memory_used = crate::resource::memory_allocated();
+        if memory_used > memory_watermark {
+            return Err(MemoryWatermarkExceededError {
+                memory_used,
+                memory_watermark,
+            }.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether the agent has been requested to shut down.
+///
+/// This function will return an error if it has.
+pub(crate) fn check_shutdown() -> Result<()> {
+    if crate::shutdown::requested() {
+        return Err(crate::session::error::ShutdownRequestedError.into());
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -84,6 +269,30 @@ mod tests {
         assert_eq!(session.parcel_count(Sink::Startup), 2);
     }
 
+    #[test]
+    fn test_fake_send_blob_order() {
+        let mut session = FakeSession::new();
+
+        let mut digests = Vec::new();
+        for index in 0..16u8 {
+            let blob = crate::blob::Blob::from(vec![index; 256 * 1024]);
+            let pending = session.send_blob(blob).unwrap();
+
+            // The blob has to be sent before the reply referencing it can be
+            // built (even though its digests might not be ready yet).
+            assert_eq!(session.parcel_count(Sink::Blob), session.reply_count() + 1);
+
+            digests.push(pending.wait().unwrap());
+            Session::reply(&mut session, ()).unwrap();
+        }
+
+        let blobs = session.parcels::<crate::blob::Blob>(Sink::Blob);
+        for (index, (blob, digests)) in blobs.zip(digests).enumerate() {
+            assert_eq!(blob.as_bytes(), &vec![index as u8; 256 * 1024][..]);
+            assert_eq!(digests, blob.digests(crate::blob::digest_algorithms(session.args())));
+        }
+    }
+
     #[test]
     fn test_fake_reply_correct_response() {
 
@@ -236,6 +445,60 @@ mod tests {
         assert_eq!(parcels.next(), None);
     }
 
+    #[test]
+    fn wait_for_sink_no_watermark() {
+        let mut session = FakeSession::new();
+        assert!(wait_for_sink(&mut session, Sink::Blob).is_ok());
+        assert!(session.heartbeats().is_empty());
+    }
+
+    #[test]
+    fn wait_for_sink_not_above_high() {
+        let mut session = FakeSession::new();
+        session.simulate_sink_pressure(Sink::Blob, 100, 50, pressures(vec![100]));
+
+        assert!(wait_for_sink(&mut session, Sink::Blob).is_ok());
+        assert!(session.heartbeats().is_empty());
+    }
+
+    #[test]
+    fn wait_for_sink_until_low() {
+        let mut session = FakeSession::new();
+        session.simulate_sink_pressure(Sink::Blob, 100, 50, pressures(vec![101, 75, 75, 50]));
+
+        assert!(wait_for_sink(&mut session, Sink::Blob).is_ok());
+        // Pressure between the watermarks does not resume sending, so we keep
+        // heartbeating until it drops to the low watermark.
+        assert_eq!(session.heartbeats().len(), 3);
+    }
+
+    #[test]
+    fn wait_for_sink_other_sink() {
+        let mut session = FakeSession::new();
+        session.simulate_sink_pressure(Sink::Blob, 100, 50, || 1000);
+
+        assert!(wait_for_sink(&mut session, Sink::Startup).is_ok());
+        assert!(session.heartbeats().is_empty());
+    }
+
+    #[test]
+    fn wait_for_sink_deadline() {
+        let mut session = FakeSession::new();
+        session.simulate_sink_pressure(Sink::Blob, 100, 50, || 1000);
+        session.set_deadline(std::time::Instant::now() + std::time::Duration::from_millis(200));
+
+        let error = wait_for_sink(&mut session, Sink::Blob)
+            .unwrap_err();
+        assert_eq!(error.code(), ErrorCode::DeadlineExceeded);
+        assert!(!session.heartbeats().is_empty());
+    }
+
+    /// Returns a function yielding the given pressures (and 0 afterwards).
+    fn pressures(pressures: Vec<usize>) -> impl Fn() -> usize {
+        let pressures = std::cell::RefCell::new(pressures.into_iter());
+        move || pressures.borrow_mut().next().unwrap_or(0)
+    }
+
     #[derive(Debug, PartialEq, Eq)]
     struct StringResponse(String);
 
@@ -8,6 +8,8 @@
 pub struct Error {
     /// A corresponding [`ErrorKind`] of this error.
     kind: ErrorKind,
+    /// A generic [`ErrorCode`] of this error.
+    code: ErrorCode,
     /// A detailed error object.
     error: Box<dyn std::error::Error>,
 }
@@ -21,14 +23,54 @@ pub enum ErrorKind {
     UnsupportedAction,
     /// The arguments given for the action were malformed.
     InvalidArgs,
+    /// The arguments given for the action require a newer version of it.
+    UnsupportedVersion,
     /// The action execution failed.
     ActionFailure,
+    /// The action cannot be executed because of a missing dependency.
+    ActionUnavailable,
+    /// The action (or some of its arguments) is not allowed by the policy.
+    PolicyDenied,
+    /// The action was executed too many times within a period of time.
+    RateLimited,
     /// Filter evaluation on action result failed.
     FilterFailure,
     /// Action execution crossed the allowed network bytes limit.
     NetworkBytesLimitExceeded,
     /// Action execution crossed the allowed real (wall) time limit.
     RealTimeLimitExceeded,
+    /// Agent resource usage crossed the configured watermark.
+    ResourceExhausted,
+    /// Action execution was cancelled because the agent is shutting down.
+    Cancelled,
+    /// The request was refused because of the mode the agent is in.
+    ModeRefused,
+}
+
+/// Generic codes of errors that can happen during a session.
+///
+/// Unlike [`ErrorKind`], which tells which stage of the session failed, the
+/// code tells the reason of the failure and is independent of the action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// The requested entity (e.g. a file) does not exist.
+    NotFound,
+    /// The agent does not have the permissions to perform the operation.
+    PermissionDenied,
+    /// The request or its arguments are invalid.
+    InvalidArgument,
+    /// Some resource (e.g. memory, disk space or a quota) has been exhausted.
+    ResourceExhausted,
+    /// Something the operation depends on is (possibly temporarily) unavailable.
+    Unavailable,
+    /// An unexpected error occurred.
+    Internal,
+    /// The operation was cancelled.
+    Cancelled,
+    /// The operation is not supported by the agent or on the system.
+    Unsupported,
+    /// The operation did not finish before the deadline of the request.
+    DeadlineExceeded,
 }
 
 impl Error {
@@ -37,12 +79,46 @@ impl Error {
     ///
     /// This function should be used to construct session errors from action
     /// specific error types and propagate them further in the session pipeline.
+    ///
+    /// The code of the error is derived from the first I/O error found in the
+    /// chain of its sources, [`ErrorCode::Internal`] is used if there is none.
     pub fn action<E>(error: E) -> Error
     where
         E: std::error::Error + 'static,
     {
+        let code = ErrorCode::of(&error);
         Error {
             kind: ErrorKind::ActionFailure,
+            code,
+            error: Box::new(error),
+        }
+    }
+
+    /// Converts an action-issued error about a missing dependency (e.g. an
+    /// external tool not installed on the system) to a session error.
+    ///
+    /// Unlike with [`Error::action`], the server can tell that the action did
+    /// not fail on its own but could not be executed at all.
+    pub fn action_unavailable<E>(error: E) -> Error
+    where
+        E: std::error::Error + 'static,
+    {
+        Error {
+            kind: ErrorKind::ActionUnavailable,
+            code: ErrorCode::Unavailable,
+            error: Box::new(error),
+        }
+    }
+
+    /// Converts an action-issued error about exceeding its execution rate limit
+    /// to a session error.
+    pub fn rate_limited<E>(error: E) -> Error
+    where
+        E: std::error::Error + 'static,
+    {
+        Error {
+            kind: ErrorKind::RateLimited,
+            code: ErrorCode::ResourceExhausted,
             error: Box::new(error),
         }
     }
@@ -51,14 +127,72 @@ impl Error {
     pub fn unsupported_action(action: crate::request::Action) -> Error {
         Error {
             kind: ErrorKind::UnsupportedAction,
+            code: ErrorCode::Unsupported,
             error: Box::new(UnsupportedActionError { action }),
         }
     }
 
+    /// Converts an action that does not support dry runs to a session error.
+    pub fn unsupported_dry_run(action: crate::request::Action) -> Error {
+        Error {
+            kind: ErrorKind::UnsupportedAction,
+            code: ErrorCode::Unsupported,
+            error: Box::new(UnsupportedDryRunError { action }),
+        }
+    }
+
     /// Returns the corresponding [`ErrorKind`] of this error.
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+
+    /// Returns the corresponding [`ErrorCode`] of this error.
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+}
+
+impl ErrorCode {
+
+    /// Determines the code of the given error based on its chain of sources.
+    fn of(error: &(dyn std::error::Error + 'static)) -> ErrorCode {
+        let mut source = Some(error);
+        while let Some(error) = source {
+            if let Some(error) = error.downcast_ref::<Error>() {
+                return error.code;
+            }
+            if let Some(error) = error.downcast_ref::<std::io::Error>() {
+                return ErrorCode::from(error.kind());
+            }
+            source = error.source();
+        }
+
+        ErrorCode::Internal
+    }
+}
+
+impl From<std::io::ErrorKind> for ErrorCode {
+
+    fn from(kind: std::io::ErrorKind) -> ErrorCode {
+        use std::io::ErrorKind::*;
+
+        match kind {
+            NotFound => ErrorCode::NotFound,
+            PermissionDenied | ReadOnlyFilesystem => ErrorCode::PermissionDenied,
+            InvalidInput | InvalidData | NotADirectory | IsADirectory => {
+                ErrorCode::InvalidArgument
+            }
+            StorageFull | FileTooLarge | OutOfMemory => ErrorCode::ResourceExhausted,
+            ConnectionRefused | ConnectionReset | ConnectionAborted |
+            NotConnected | BrokenPipe | TimedOut | WouldBlock | ResourceBusy |
+            HostUnreachable | NetworkUnreachable | NetworkDown => {
+                ErrorCode::Unavailable
+            }
+            Interrupted => ErrorCode::Cancelled,
+            Unsupported => ErrorCode::Unsupported,
+            _ => ErrorCode::Internal,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -81,9 +215,23 @@ impl std::fmt::Display for Error {
             InvalidArgs => {
                 write!(fmt, "invalid action arguments: {}", self.error)
             }
+            UnsupportedVersion => {
+                write!(fmt, "unsupported action version: {}", self.error)
+            }
             ActionFailure => {
                 write!(fmt, "action execution failed: {}", self.error)
             }
+            ActionUnavailable => {
+                write!(fmt, "action unavailable: {}", self.error)
+            }
+            PolicyDenied => {
+                // The `self.error` is an instance of `PolicyDeniedError` which
+                // has all the details.
+                write!(fmt, "{}", self.error)
+            }
+            RateLimited => {
+                write!(fmt, "rate limited: {}", self.error)
+            }
             FilterFailure => {
                 write!(fmt, "filter evaluation failed: {}", self.error)
             }
@@ -93,6 +241,15 @@ impl std::fmt::Display for Error {
             RealTimeLimitExceeded => {
                 write!(fmt, "real time limit exceeded: {}", self.error)
             }
+            ResourceExhausted => {
+                write!(fmt, "resource exhausted: {}", self.error)
+            }
+            Cancelled => {
+                write!(fmt, "cancelled: {}", self.error)
+            }
+            ModeRefused => {
+                write!(fmt, "request refused: {}", self.error)
+            }
         }
     }
 }
@@ -109,6 +266,7 @@ impl From<crate::request::ParseRequestError> for Error {
     fn from(error: crate::request::ParseRequestError) -> Error {
         Error {
             kind: ErrorKind::InvalidRequest(error.kind()),
+            code: ErrorCode::InvalidArgument,
             error: Box::new(error),
         }
     }
@@ -117,8 +275,18 @@ impl From<crate::request::ParseRequestError> for Error {
 impl From<crate::request::ParseArgsError> for Error {
 
     fn from(error: crate::request::ParseArgsError) -> Error {
+        use crate::request::ParseArgsErrorKind;
+
+        let (kind, code) = match error.kind() {
+            ParseArgsErrorKind::UnsupportedVersion => {
+                (ErrorKind::UnsupportedVersion, ErrorCode::Unsupported)
+            }
+            _ => (ErrorKind::InvalidArgs, ErrorCode::InvalidArgument),
+        };
+
         Error {
-            kind: ErrorKind::InvalidArgs,
+            kind,
+            code,
             error: Box::new(error),
         }
     }
@@ -129,17 +297,48 @@ impl From<crate::filter::Error> for Error {
     fn from(error: crate::filter::Error) -> Error {
         Error {
             kind: ErrorKind::FilterFailure,
+            code: ErrorCode::InvalidArgument,
             error: Box::new(error),
         }
     }
 }
 
+impl From<crate::policy::PolicyDeniedError> for Error {
+
+    fn from(error: crate::policy::PolicyDeniedError) -> Error {
+        Error {
+            kind: ErrorKind::PolicyDenied,
+            code: ErrorCode::PermissionDenied,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl From<crate::mode::RefusedError> for Error {
+
+    fn from(error: crate::mode::RefusedError) -> Error {
+        Error {
+            kind: ErrorKind::ModeRefused,
+            code: ErrorCode::Unavailable,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+
+    fn from(error: std::io::Error) -> Error {
+        Error::action(error)
+    }
+}
+
 impl From<Error> for rrg_proto::rrg::status::Error {
 
     fn from(error: Error) -> rrg_proto::rrg::status::Error {
         let mut proto = rrg_proto::rrg::status::Error::new();
         proto.set_type(error.kind.into());
         proto.set_message(error.to_string());
+        proto.set_code(error.code.into());
 
         proto
     }
@@ -154,10 +353,34 @@ impl From<ErrorKind> for rrg_proto::rrg::status::error::Type {
             InvalidRequest(kind) => kind.into(),
             UnsupportedAction => Self::UNSUPPORTED_ACTION,
             InvalidArgs => Self::INVALID_ARGS,
+            UnsupportedVersion => Self::UNSUPPORTED_VERSION,
             ActionFailure => Self::ACTION_FAILURE,
+            ActionUnavailable => Self::ACTION_UNAVAILABLE,
+            PolicyDenied => Self::POLICY_DENIED,
+            RateLimited => Self::RATE_LIMITED,
             FilterFailure => Self::FILTER_FAILURE,
             NetworkBytesLimitExceeded => Self::NETWORK_BYTES_SENT_LIMIT_EXCEEDED,
             RealTimeLimitExceeded => Self::REAL_TIME_LIMIT_EXCEEDED,
+            ResourceExhausted => Self::RESOURCE_EXHAUSTED,
+            Cancelled => Self::CANCELLED,
+            ModeRefused => Self::MODE_REFUSED,
+        }
+    }
+}
+
+impl From<ErrorCode> for rrg_proto::rrg::status::Code {
+
+    fn from(code: ErrorCode) -> rrg_proto::rrg::status::Code {
+        match code {
+            ErrorCode::NotFound => Self::NOT_FOUND,
+            ErrorCode::PermissionDenied => Self::PERMISSION_DENIED,
+            ErrorCode::InvalidArgument => Self::INVALID_ARGUMENT,
+            ErrorCode::ResourceExhausted => Self::RESOURCE_EXHAUSTED,
+            ErrorCode::Unavailable => Self::UNAVAILABLE,
+            ErrorCode::Internal => Self::INTERNAL,
+            ErrorCode::Cancelled => Self::CANCELLED,
+            ErrorCode::Unsupported => Self::UNSUPPORTED,
+            ErrorCode::DeadlineExceeded => Self::DEADLINE_EXCEEDED,
         }
     }
 }
@@ -178,6 +401,22 @@ impl std::fmt::Display for UnsupportedActionError {
 impl std::error::Error for UnsupportedActionError {
 }
 
+/// An error type for when the action in a dry run request does not support it.
+#[derive(Debug)]
+struct UnsupportedDryRunError {
+    action: crate::request::Action,
+}
+
+impl std::fmt::Display for UnsupportedDryRunError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "dry run of action '{}' is not supported", self.action)
+    }
+}
+
+impl std::error::Error for UnsupportedDryRunError {
+}
+
 /// An error type raised when the network bytes limit has been exceeded.
 #[derive(Debug)]
 pub struct NetworkBytesLimitExceededError {
@@ -207,6 +446,7 @@ impl From<NetworkBytesLimitExceededError> for Error {
     fn from(error: NetworkBytesLimitExceededError) -> Error {
         Error {
             kind: ErrorKind::NetworkBytesLimitExceeded,
+            code: ErrorCode::ResourceExhausted,
             error: Box::new(error),
         }
     }
@@ -241,7 +481,168 @@ impl From<RealTimeLimitExceededError> for Error {
     fn from(error: RealTimeLimitExceededError) -> Error {
         Error {
             kind: ErrorKind::RealTimeLimitExceeded,
+            code: ErrorCode::DeadlineExceeded,
+            error: Box::new(error),
+        }
+    }
+}
+
+/// An error type raised when the deadline of the request has passed.
+#[derive(Debug)]
+pub struct DeadlineExceededError {
+    /// Amount of time that passed since the deadline.
+    pub overrun: std::time::Duration,
+}
+
+impl std::fmt::Display for DeadlineExceededError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "deadline passed {} ago",
+            humantime::format_duration(self.overrun),
+        }
+    }
+}
+
+impl std::error::Error for DeadlineExceededError {
+}
+
+impl From<DeadlineExceededError> for Error {
+
+    fn from(error: DeadlineExceededError) -> Error {
+        Error {
+            kind: ErrorKind::RealTimeLimitExceeded,
+            code: ErrorCode::DeadlineExceeded,
+            error: Box::new(error),
+        }
+    }
+}
+
+/// An error type raised when the memory watermark has been exceeded.
+#[derive(Debug)]
+pub struct MemoryWatermarkExceededError {
+    /// Number of bytes the agent actually uses.
+    pub memory_used: usize,
+    /// Number of bytes the agent is allowed to use before aborting actions.
+    pub memory_watermark: usize,
+}
+
+impl std::fmt::Display for MemoryWatermarkExceededError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "used {} bytes of memory out of allowed {}",
+            self.memory_used,
+            self.memory_watermark,
+        }
+    }
+}
+
+impl std::error::Error for MemoryWatermarkExceededError {
+}
+
+impl From<MemoryWatermarkExceededError> for Error {
+
+    fn from(error: MemoryWatermarkExceededError) -> Error {
+        Error {
+            kind: ErrorKind::ResourceExhausted,
+            code: ErrorCode::ResourceExhausted,
+            error: Box::new(error),
+        }
+    }
+}
+
+/// An error type raised when the agent has been requested to shut down.
+#[derive(Debug)]
+pub struct ShutdownRequestedError;
+
+impl std::fmt::Display for ShutdownRequestedError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "agent shutdown requested")
+    }
+}
+
+impl std::error::Error for ShutdownRequestedError {
+}
+
+impl From<ShutdownRequestedError> for Error {
+
+    fn from(error: ShutdownRequestedError) -> Error {
+        Error {
+            kind: ErrorKind::Cancelled,
+            code: ErrorCode::Cancelled,
             error: Box::new(error),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn action_io_error_code() {
+        let error = Error::action(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert_eq!(error.code(), ErrorCode::PermissionDenied);
+    }
+
+    #[test]
+    fn action_nested_io_error_code() {
+        #[derive(Debug)]
+        struct NestedError(std::io::Error);
+
+        impl std::fmt::Display for NestedError {
+
+            fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(fmt, "nested error")
+            }
+        }
+
+        impl std::error::Error for NestedError {
+
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let error = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let error = Error::action(NestedError(error));
+        assert_eq!(error.code(), ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn action_other_error_code() {
+        let error = Error::action(std::fmt::Error);
+        assert_eq!(error.code(), ErrorCode::Internal);
+    }
+
+    #[test]
+    fn shutdown_requested_error_code() {
+        let error = Error::from(ShutdownRequestedError);
+        assert_eq!(error.code(), ErrorCode::Cancelled);
+    }
+
+    #[test]
+    fn deadline_exceeded_error_proto_code() {
+        let error = Error::from(DeadlineExceededError {
+            overrun: std::time::Duration::from_secs(1),
+        });
+
+        let proto = rrg_proto::rrg::status::Error::from(error);
+        assert_eq!(proto.code(), rrg_proto::rrg::status::Code::DEADLINE_EXCEEDED);
+        assert_eq!(proto.type_(), rrg_proto::rrg::status::error::Type::REAL_TIME_LIMIT_EXCEEDED);
+    }
+
+    #[test]
+    fn status_error_proto_code() {
+        let error = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+
+        let proto = rrg_proto::rrg::status::Error::from(error);
+        assert_eq!(proto.code(), rrg_proto::rrg::status::Code::NOT_FOUND);
+        assert_eq!(proto.type_(), rrg_proto::rrg::status::error::Type::ACTION_FAILURE);
+    }
+}
@@ -15,6 +15,15 @@ pub struct FakeSession {
     args: crate::args::Args,
     replies: Vec<Box<dyn Any>>,
     parcels: std::collections::HashMap<Sink, Vec<Box<dyn Any>>>,
+    redactor: crate::redact::Redactor,
+    memory_watermark: Option<usize>,
+    memory_usage: Box<dyn Fn() -> usize>,
+    sink_pressures: std::collections::HashMap<Sink, Box<dyn Fn() -> usize>>,
+    heartbeats: Vec<usize>,
+    deadline: Option<std::time::Instant>,
+    dry_run: bool,
+    action: Option<crate::request::Action>,
+    temp: crate::temp::Scope,
 }
 
 impl FakeSession {
@@ -22,23 +31,79 @@ impl FakeSession {
     /// Constructs a new fake session with test default agent arguments.
     pub fn new() -> FakeSession {
         FakeSession::with_args(crate::args::Args {
-            heartbeat_rate: std::time::Duration::from_secs(0),
             command_verification_key: Some(ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng).verifying_key()),
-            verbosity: log::LevelFilter::Debug,
-            log_to_stdout: false,
-            log_to_file: None,
+            ..Default::default()
         })
     }
 
     /// Constructs a new fake session with the given agent arguments.
     pub fn with_args(args: crate::args::Args) -> FakeSession {
+        let redactor = crate::redact::Redactor::new(&args.redacted_paths);
+        let temp = crate::temp::Scope::new(&args, crate::RequestId::new(0xf00, 0xba7));
+
         FakeSession {
             args,
             replies: Vec::new(),
             parcels: std::collections::HashMap::new(),
+            redactor,
+            memory_watermark: None,
+            memory_usage: Box::new(|| 0),
+            sink_pressures: std::collections::HashMap::new(),
+            heartbeats: Vec::new(),
+            deadline: None,
+            dry_run: false,
+            action: None,
+            temp,
         }
     }
 
+    /// Simulates memory usage of the agent for the purpose of limit checks.
+    ///
+    /// The `usage` function is called every time the session limits are checked
+    /// and the check fails once it returns a value above `watermark`.
+    pub fn simulate_memory_usage<F>(&mut self, watermark: usize, usage: F)
+    where
+        F: Fn() -> usize + 'static,
+    {
+        self.memory_watermark = Some(watermark);
+        self.memory_usage = Box::new(usage);
+    }
+
+    /// Simulates pressure of the given sink for the purpose of throttling.
+    ///
+    /// The `pressure` function is called every time the pressure of the sink
+    /// is queried. Actions pause sending once it returns a value above `high`
+    /// and resume once it returns a value not above `low` (see [`wait_for_sink`]).
+    /// Sinks without simulated pressure have none.
+    ///
+    /// [`wait_for_sink`]: crate::session::wait_for_sink
+    pub fn simulate_sink_pressure<F>(&mut self, sink: Sink, high: usize, low: usize, pressure: F)
+    where
+        F: Fn() -> usize + 'static,
+    {
+        self.args.sink_pressure_high_watermark = Some(high);
+        self.args.sink_pressure_low_watermark = Some(low);
+        self.sink_pressures.insert(sink, Box::new(pressure));
+    }
+
+    /// Sets the deadline of the request handled by the session.
+    pub fn set_deadline(&mut self, deadline: std::time::Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Makes the request handled by the session a dry run.
+    pub fn set_dry_run(&mut self) {
+        self.dry_run = true;
+    }
+
+    /// Sets the action invoked by the request handled by the session.
+    ///
+    /// The policy on paths that the action is allowed to access is enforced
+    /// only once the action is set.
+    pub fn set_action(&mut self, action: crate::request::Action) {
+        self.action = Some(action);
+    }
+
     /// Yields the number of replies that this session sent so far.
     pub fn reply_count(&self) -> usize {
         self.replies.len()
@@ -55,8 +120,10 @@ impl FakeSession {
     where
         R: crate::response::Item + 'static,
     {
-        match self.replies().nth(id) {
-            Some(reply) => reply,
+        // We do not go through `replies` as actions can reply with items of
+        // different types and only the requested one has to be of `R` type.
+        match self.replies.get(id) {
+            Some(reply) => reply.downcast_ref().expect("unexpected reply type"),
             None => panic!("no reply #{}", id),
         }
     }
@@ -82,6 +149,14 @@ impl FakeSession {
         }
     }
 
+    /// Yields the number of parcels sent so far at the time of each heartbeat.
+    ///
+    /// The number includes parcels sent to all the sinks. This can be used to
+    /// verify that the action keeps heartbeating while not sending anything.
+    pub fn heartbeats(&self) -> &[usize] {
+        &self.heartbeats
+    }
+
     /// Retrieves a parcel with the given id sent to a particular sink.
     ///
     /// The identifier corresponding to the first parcel to the particular sink
@@ -128,6 +203,10 @@ impl crate::session::Session for FakeSession {
         &self.args
     }
 
+    fn request_id(&self) -> crate::RequestId {
+        crate::RequestId::new(0xf00, 0xba7)
+    }
+
     fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
     where
         I: crate::response::Item + 'static,
@@ -148,5 +227,57 @@ impl crate::session::Session for FakeSession {
     }
 
     fn heartbeat(&mut self) {
+        self.heartbeats.push(self.parcels.values().map(Vec::len).sum());
+    }
+
+    fn sink_pressure(&self, sink: Sink) -> usize {
+        match self.sink_pressures.get(&sink) {
+            Some(pressure) => pressure(),
+            None => 0,
+        }
+    }
+
+    fn redactor(&self) -> &crate::redact::Redactor {
+        &self.redactor
+    }
+
+    fn check_limits(&self) -> crate::session::Result<()> {
+        use crate::session::error::MemoryWatermarkExceededError;
+
+        crate::session::Session::check_deadline(self)?;
+
+        if let Some(memory_watermark) = self.memory_watermark {
+            let memory_used = (self.memory_usage)();
+            if memory_used > memory_watermark {
+                return Err(MemoryWatermarkExceededError {
+                    memory_used,
+                    memory_watermark,
+                }.into());
+            }
+        }
+
+        // Shutdown is requested only in tests that run in a separate process,
+        // so respecting it does not affect any other test.
+        crate::session::check_shutdown()
+    }
+
+    fn deadline(&self) -> Option<std::time::Instant> {
+        self.deadline
+    }
+
+    fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn check_path(&self, path: &std::path::Path) -> crate::session::Result<()> {
+        if let Some(action) = self.action {
+            crate::policy::check_path(&self.args, action, path)?;
+        }
+
+        Ok(())
+    }
+
+    fn temp_dir(&mut self) -> crate::session::Result<crate::temp::TempDir> {
+        self.temp.dir().map_err(crate::session::Error::action)
     }
 }
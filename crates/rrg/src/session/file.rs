@@ -0,0 +1,301 @@
+use log::{error, info};
+
+/// A session implementation that writes results to local files.
+///
+/// This session type is used when the agent executes requests without being
+/// connected to the server. Responses (replies and the final status) and sink
+/// parcels are written to separate files as length-delimited protobuf messages
+/// in exactly the same format as they would be sent through Fleetspeak. If an
+/// encryption key is specified in the agent arguments, the files are encrypted
+/// to it.
+pub struct FileSession<'a> {
+    /// Arguments passed to the agent.
+    args: &'a crate::args::Args,
+    /// Action invoked by the request.
+    action: crate::request::Action,
+    /// A builder for responses written to the responses file.
+    response_builder: crate::ResponseBuilder,
+    /// Redactor of paths in results written within the session.
+    redactor: crate::redact::Redactor,
+    /// File to write responses to.
+    responses_file: OutputFile,
+    /// File to write parcels to.
+    parcels_file: OutputFile,
+    /// Parcels held back until the next reply is sampled (if sampling).
+    pending_parcels: Vec<rrg_proto::rrg::Parcel>,
+    /// Moment by which the request should be handled (if any).
+    deadline: Option<std::time::Instant>,
+    /// Scratch directory of the request.
+    temp: crate::temp::Scope,
+    /// Whether the request is executed as a dry run.
+    dry_run: bool,
+}
+
+impl<'a> FileSession<'a> {
+
+    /// Dispatches the given `request` to an appropriate action handler.
+    ///
+    /// This is the main entry point of the session. It processes the request
+    /// and writes its results to files in the `output_dir` directory. Paths to
+    /// all the written files are returned.
+    ///
+    /// Similarly to [`FleetspeakSession::dispatch`], the function accepts a
+    /// `Result` so that errors in parsing the request end up in the final
+    /// status written for it.
+    ///
+    /// [`FleetspeakSession::dispatch`]: crate::session::FleetspeakSession::dispatch
+    pub fn dispatch(
+        args: &'a crate::args::Args,
+        request: Result<crate::Request, crate::ParseRequestError>,
+        output_dir: &std::path::Path,
+    ) -> std::io::Result<Vec<std::path::PathBuf>> {
+        let request_id = match &request {
+            Ok(request) => request.id(),
+            Err(error) => match error.request_id() {
+                Some(request_id) => request_id,
+                None => {
+                    error!("invalid request: {}", error);
+                    return Ok(Vec::new());
+                }
+            }
+        };
+
+        info!("executing request '{request_id}'");
+
+        let name = format!("{:X}-{}", request_id.flow_id(), request_id.request_id());
+        let responses_path = output_dir.join(format!("{name}.responses"));
+        let parcels_path = output_dir.join(format!("{name}.parcels"));
+
+        let encryption_key = args.offline_encryption_key.as_ref();
+        let responses_file = OutputFile::create(&responses_path, encryption_key)?;
+        let parcels_file = OutputFile::create(&parcels_path, encryption_key)?;
+
+        let response_builder = crate::ResponseBuilder::new(request_id);
+
+        let (status, mut responses_file, parcels_file) = match request {
+            Ok(mut request) => {
+                let filters = request.take_filters();
+                let sample_rate = request.sample_rate();
+
+                let mut session = FileSession {
+                    args,
+                    action: request.action(),
+                    response_builder: response_builder
+                        .with_filters(filters)
+                        .with_sample_rate(sample_rate),
+                    redactor: crate::redact::Redactor::new(&args.redacted_paths),
+                    responses_file,
+                    parcels_file,
+                    pending_parcels: Vec::new(),
+                    deadline: request.real_time_limit()
+                        .and_then(|real_time_limit| std::time::Instant::now().checked_add(real_time_limit)),
+                    temp: crate::temp::Scope::new(args, request_id),
+                    dry_run: request.is_dry_run(),
+                };
+
+                let result = {
+                    let _guard = crate::crash::RequestGuard::new(request_id);
+                    crate::action::dispatch(&mut session, request)
+                };
+
+                // Parcels sent after the last reply are not referenced by any
+                // result that could have been sampled out.
+                let flush_result = session.flush_parcels();
+                let result = result.and(flush_result);
+                if let Err(error) = &result {
+                    error!("failed to execute request '{request_id}': {error}");
+                }
+
+                let status = session.response_builder.status(result);
+                (status, session.responses_file, session.parcels_file)
+            },
+            Err(error) => {
+                error!("invalid request '{request_id}': {error}");
+                (response_builder.status(Err(error.into())), responses_file, parcels_file)
+            }
+        };
+
+        write_message(&mut responses_file, &rrg_proto::rrg::Response::from(status))?;
+
+        responses_file.finish()?;
+        parcels_file.finish()?;
+
+        Ok(vec![responses_path, parcels_path])
+    }
+}
+
+impl<'a> FileSession<'a> {
+
+    /// Writes all the parcels held back because of sampling.
+    fn flush_parcels(&mut self) -> crate::session::Result<()> {
+        for parcel in std::mem::take(&mut self.pending_parcels) {
+            write_message(&mut self.parcels_file, &parcel)
+                .map_err(crate::session::Error::action)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> crate::session::Session for FileSession<'a> {
+
+    fn args(&self) -> &crate::args::Args {
+        self.args
+    }
+
+    fn request_id(&self) -> crate::RequestId {
+        self.response_builder.request_id()
+    }
+
+    fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item,
+    {
+        let mut item = crate::response::PreparedItem::from(item);
+        // Redaction happens before filters are applied so that filters cannot
+        // be used to learn anything about redacted paths.
+        item.redact(&self.redactor);
+
+        use crate::response::FilteredReply::*;
+        let reply = match self.response_builder.reply(item) {
+            Accepted(reply) => reply,
+            Rejected => return self.flush_parcels(),
+            // Parcels sent just before the reply are assumed to be referenced
+            // by it, so they are dropped together with it.
+            SampledOut => {
+                self.pending_parcels.clear();
+                return Ok(());
+            }
+            Error(error) => return Err(error.into()),
+        };
+
+        self.flush_parcels()?;
+
+        let proto = rrg_proto::rrg::Response::from(reply);
+        write_message(&mut self.responses_file, &proto)
+            .map_err(crate::session::Error::action)?;
+
+        crate::session::check_memory_watermark()
+    }
+
+    fn send<I>(&mut self, sink: crate::Sink, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item,
+    {
+        let parcel = crate::response::Parcel::new(sink, item);
+
+        let proto = rrg_proto::rrg::Parcel::from(parcel);
+
+        // When sampling, we do not know whether the parcel should be written
+        // until we see the reply that references it.
+        if self.response_builder.is_sampling() {
+            self.pending_parcels.push(proto);
+            return crate::session::check_memory_watermark();
+        }
+
+        write_message(&mut self.parcels_file, &proto)
+            .map_err(crate::session::Error::action)?;
+
+        crate::session::check_memory_watermark()
+    }
+
+    fn heartbeat(&mut self) {
+        // There is no Fleetspeak process to notify when executing offline.
+    }
+
+    fn sink_pressure(&self, sink: crate::Sink) -> usize {
+        // Parcels that are not held back are written to the file right away.
+        crate::session::parcels_len(&self.pending_parcels, sink)
+    }
+
+    fn redactor(&self) -> &crate::redact::Redactor {
+        &self.redactor
+    }
+
+    fn check_limits(&self) -> crate::session::Result<()> {
+        crate::session::Session::check_deadline(self)?;
+        crate::session::check_memory_watermark()?;
+        crate::session::check_shutdown()
+    }
+
+    fn deadline(&self) -> Option<std::time::Instant> {
+        self.deadline
+    }
+
+    fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn check_path(&self, path: &std::path::Path) -> crate::session::Result<()> {
+        crate::policy::check_path(self.args, self.action, path)?;
+
+        Ok(())
+    }
+
+    fn temp_dir(&mut self) -> crate::session::Result<crate::temp::TempDir> {
+        self.temp.dir().map_err(crate::session::Error::action)
+    }
+}
+
+/// File to which results of the session are written.
+enum OutputFile {
+    /// File written as-is.
+    Plain(std::fs::File),
+    /// File encrypted to a public key.
+    Encrypted(crate::envelope::Writer<std::fs::File>),
+}
+
+impl OutputFile {
+
+    /// Creates a new output file, encrypted if `encryption_key` is given.
+    fn create(
+        path: &std::path::Path,
+        encryption_key: Option<&x25519_dalek::PublicKey>,
+    ) -> std::io::Result<OutputFile> {
+        let file = std::fs::File::create(path)?;
+
+        match encryption_key {
+            Some(encryption_key) => {
+                let writer = crate::envelope::Writer::new(file, encryption_key)?;
+                Ok(OutputFile::Encrypted(writer))
+            }
+            None => Ok(OutputFile::Plain(file)),
+        }
+    }
+
+    /// Writes the remaining data to the file.
+    fn finish(self) -> std::io::Result<()> {
+        let file = match self {
+            OutputFile::Plain(file) => file,
+            OutputFile::Encrypted(writer) => writer.finish()?,
+        };
+
+        file.sync_all()
+    }
+}
+
+impl std::io::Write for OutputFile {
+
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputFile::Plain(file) => file.write(buf),
+            OutputFile::Encrypted(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputFile::Plain(file) => file.flush(),
+            OutputFile::Encrypted(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Writes the given message to the file as a length-delimited protobuf.
+fn write_message<M>(file: &mut OutputFile, message: &M) -> std::io::Result<()>
+where
+    M: protobuf::Message,
+{
+    message.write_length_delimited_to_writer(file)
+        .map_err(std::io::Error::from)
+}
@@ -8,6 +8,8 @@ use log::{error, info};
 pub struct FleetspeakSession<'a> {
     /// Arguments passed to the agent.
     args: &'a crate::args::Args,
+    /// Action invoked by the request.
+    action: crate::request::Action,
     /// A builder for responses sent through Fleetspeak to the GRR server.
     response_builder: crate::ResponseBuilder,
     /// Number of bytes sent since the session was created.
@@ -18,6 +20,19 @@ pub struct FleetspeakSession<'a> {
     real_time_start: std::time::Instant,
     /// Time which we are allowed to spend within the session.
     real_time_limit: Option<std::time::Duration>,
+    /// Redactor of paths in results sent within the session.
+    redactor: crate::redact::Redactor,
+    /// Backend through which parcels are delivered.
+    sink_backend: Box<dyn crate::transport::SinkBackend>,
+    /// Guard of the request holding back parcels until the next reply is
+    /// sampled (if sampling).
+    in_flight: crate::shutdown::InFlightGuard,
+    /// Guard reporting the progress of the request to the admin endpoint.
+    admin_guard: crate::admin::RequestGuard,
+    /// Scratch directory of the request.
+    temp: crate::temp::Scope,
+    /// Whether the request is executed as a dry run.
+    dry_run: bool,
 }
 
 impl<'a> FleetspeakSession<'a> {
@@ -52,37 +67,117 @@ impl<'a> FleetspeakSession<'a> {
 
         info!("received request '{request_id}'");
 
+        // The status has to be sent before the request stops being in flight,
+        // so the guard is held for the whole dispatch.
+        let in_flight = crate::shutdown::InFlightGuard::new();
+
         let response_builder = crate::ResponseBuilder::new(request_id);
 
-        let status = match request {
+        let (status, in_flight) = match request {
             Ok(mut request) => {
                 let filters = request.take_filters();
+
+                // The server can override the compression for a particular
+                // request, otherwise we compress only if the agent has been
+                // configured to do so.
+                use crate::response::Compression;
+                let compression = match request.result_compression() {
+                    Some(compression) => compression,
+                    None if args.reply_compression_threshold.is_some() => Compression::Zstd,
+                    None => Compression::None,
+                };
+                let compression_threshold = args.reply_compression_threshold
+                    .unwrap_or(crate::response::DEFAULT_COMPRESSION_THRESHOLD);
+
+                let schedule_id = request.schedule_id().map(String::from);
+                let sample_rate = request.sample_rate();
+
+                let admin_guard = crate::admin::RequestGuard::new(request_id, request.action());
+
                 let mut session = FleetspeakSession {
                     args,
-                    response_builder: response_builder.with_filters(filters),
+                    action: request.action(),
+                    response_builder: response_builder
+                        .with_schedule_id(schedule_id)
+                        .with_sample_rate(sample_rate)
+                        .with_filters(filters)
+                        .with_compression(compression, compression_threshold),
                     network_bytes_sent: 0,
                     network_bytes_limit: request.network_bytes_limit(),
                     real_time_start: std::time::Instant::now(),
                     real_time_limit: request.real_time_limit(),
+                    redactor: crate::redact::Redactor::new(&args.redacted_paths),
+                    sink_backend: crate::transport::sink_backend(args),
+                    in_flight,
+                    admin_guard,
+                    temp: crate::temp::Scope::new(args, request_id),
+                    dry_run: request.is_dry_run(),
                 };
+                session.in_flight.send_with_status(Some(session.interrupted_status()), || ());
 
-                let result = crate::log::ResponseLogger::new(&request)
-                    .context(|| crate::action::dispatch(&mut session, request));
+                let result = {
+                    let _guard = crate::crash::RequestGuard::new(request_id);
+                    crate::log::ResponseLogger::new(&request)
+                        .context(|| match crate::replay::recordings_dir(args) {
+                            Some(dir) => crate::replay::dispatch_recorded(&mut session, request, &dir),
+                            None => crate::action::dispatch(&mut session, request),
+                        })
+                };
 
-                session.response_builder.status(result)
+                // Parcels sent after the last reply are not referenced by any
+                // result that could have been sampled out.
+                let flush_result = session.flush_parcels();
+                let result = result.and(flush_result);
+
+                (session.response_builder.status(result), session.in_flight)
             },
             Err(error) => {
                 error!("invalid request '{request_id}': {error}");
-                response_builder.status(Err(error.into()))
+                (response_builder.status(Err(error.into())), in_flight)
             }
         };
 
-        status.send_unaccounted();
+        in_flight.send_with_status(None, || status.send_unaccounted());
+    }
+
+    /// Refuses the request with the given identifier without dispatching it.
+    ///
+    /// Only the status with the given error is sent back to the server.
+    pub fn refuse(request_id: crate::RequestId, error: crate::session::Error) {
+        error!("refused request '{request_id}': {error}");
+
+        let _in_flight = crate::shutdown::InFlightGuard::new();
+
+        crate::ResponseBuilder::new(request_id)
+            .status(Err(error))
+            .send_unaccounted();
     }
 }
 
 impl<'a> FleetspeakSession<'a> {
 
+    /// Sends all the parcels held back because of sampling.
+    fn flush_parcels(&mut self) -> crate::session::Result<()> {
+        while let Some(len) = self.in_flight.send_held(|parcel| self.sink_backend.send(parcel)) {
+            self.record_network_bytes(len);
+            self.check_network_bytes_limit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the status to deliver if the request is interrupted now.
+    fn interrupted_status(&self) -> rrg_proto::rrg::Response {
+        let error = crate::session::error::ShutdownRequestedError.into();
+        self.response_builder.interrupted_status(error).into()
+    }
+
+    /// Records that the given number of bytes has been sent.
+    fn record_network_bytes(&mut self, len: usize) {
+        self.network_bytes_sent += len as u64;
+        self.admin_guard.record_network_bytes(len as u64);
+    }
+
     /// Checks whether the network bytes limit was crossed.
     ///
     /// This function will return an error if it was.
@@ -127,24 +222,46 @@ impl<'a> crate::session::Session for FleetspeakSession<'a> {
         self.args
     }
 
+    fn request_id(&self) -> crate::RequestId {
+        self.response_builder.request_id()
+    }
+
     fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
     where
         I: crate::response::Item,
     {
-        let item = crate::response::PreparedItem::from(item);
+        crate::service::tick();
+
+        let mut item = crate::response::PreparedItem::from(item);
+        // Redaction happens before filters are applied so that filters cannot
+        // be used to learn anything about redacted paths.
+        item.redact(&self.redactor);
 
         use crate::response::FilteredReply::*;
         let reply = match self.response_builder.reply(item) {
             Accepted(reply) => reply,
-            Rejected => return Ok(()),
+            Rejected => return self.flush_parcels(),
+            // Parcels sent just before the reply are assumed to be referenced
+            // by it, so they are dropped together with it.
+            SampledOut => {
+                self.in_flight.clear_held();
+                return Ok(());
+            }
             Error(error) => return Err(error.into()),
         };
 
-        self.network_bytes_sent += reply.send_unaccounted() as u64;
+        self.flush_parcels()?;
+
+        let status = self.interrupted_status();
+        self.record_network_bytes(self.in_flight.send_with_status(Some(status), || {
+            reply.send_unaccounted()
+        }));
+        self.admin_guard.record_reply();
         self.check_network_bytes_limit()?;
 
         // TODO(@panhania): Enforce CPU time limits.
         self.check_real_time_limit()?;
+        crate::session::check_memory_watermark()?;
 
         Ok(())
     }
@@ -153,18 +270,69 @@ impl<'a> crate::session::Session for FleetspeakSession<'a> {
     where
         I: crate::response::Item,
     {
+        crate::service::tick();
+
         let parcel = crate::response::Parcel::new(sink, item);
 
-        self.network_bytes_sent += parcel.send_unaccounted() as u64;
+        // When sampling, we do not know whether the parcel should be sent until
+        // we see the reply that references it.
+        if self.response_builder.is_sampling() {
+            self.in_flight.hold(rrg_proto::rrg::Parcel::from(parcel));
+            return crate::session::check_memory_watermark();
+        }
+
+        let len = parcel.send_unaccounted_via(&mut *self.sink_backend);
+        self.record_network_bytes(len);
         self.check_network_bytes_limit()?;
 
         // TODO(@panhania): Enforce CPU time limits.
         self.check_real_time_limit()?;
+        crate::session::check_memory_watermark()?;
 
         Ok(())
     }
 
     fn heartbeat(&mut self) {
+        crate::service::tick();
         fleetspeak::heartbeat_with_throttle(self.args.heartbeat_rate);
     }
+
+    fn sink_pressure(&self, sink: crate::Sink) -> usize {
+        self.in_flight.with_held(|parcels| crate::session::parcels_len(parcels, sink)) +
+            self.sink_backend.pressure(sink)
+    }
+
+    fn redactor(&self) -> &crate::redact::Redactor {
+        &self.redactor
+    }
+
+    fn check_limits(&self) -> crate::session::Result<()> {
+        crate::service::tick();
+
+        self.check_network_bytes_limit()?;
+        self.check_real_time_limit()?;
+        crate::session::check_memory_watermark()?;
+        crate::session::check_shutdown()?;
+
+        Ok(())
+    }
+
+    fn deadline(&self) -> Option<std::time::Instant> {
+        self.real_time_limit
+            .and_then(|real_time_limit| self.real_time_start.checked_add(real_time_limit))
+    }
+
+    fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn check_path(&self, path: &std::path::Path) -> crate::session::Result<()> {
+        crate::policy::check_path(self.args, self.action, path)?;
+
+        Ok(())
+    }
+
+    fn temp_dir(&mut self) -> crate::session::Result<crate::temp::TempDir> {
+        self.temp.dir().map_err(crate::session::Error::action)
+    }
 }
@@ -0,0 +1,121 @@
+/// A session implementation that records interactions of an action handler.
+///
+/// This session wraps another session and passes everything through to it, but
+/// also keeps a transcript of all replies and parcels sent by the handler (see
+/// the [`replay`] module for more details).
+///
+/// [`replay`]: crate::replay
+pub struct RecordingSession<'s, S: crate::session::Session> {
+    /// Session that all the interactions are passed to.
+    inner: &'s mut S,
+    /// Interactions recorded so far.
+    interactions: Vec<rrg_proto::replay::Interaction>,
+}
+
+impl<'s, S: crate::session::Session> RecordingSession<'s, S> {
+
+    /// Creates a new session recording interactions with the given one.
+    pub fn new(inner: &'s mut S) -> RecordingSession<'s, S> {
+        RecordingSession {
+            inner,
+            interactions: Vec::new(),
+        }
+    }
+
+    /// Finishes the recording of the given request with the given result.
+    pub fn finish(
+        self,
+        request: rrg_proto::rrg::Request,
+        result: &crate::session::Result<()>,
+    ) -> rrg_proto::replay::Recording {
+        let mut recording = rrg_proto::replay::Recording::new();
+        recording.set_request(request);
+        recording.set_interactions(self.interactions);
+        if let Err(error) = result {
+            recording.set_error(error.to_string());
+        }
+
+        recording
+    }
+}
+
+impl<'s, S: crate::session::Session> crate::session::Session for RecordingSession<'s, S> {
+
+    fn args(&self) -> &crate::args::Args {
+        self.inner.args()
+    }
+
+    fn request_id(&self) -> crate::RequestId {
+        self.inner.request_id()
+    }
+
+    fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item + 'static,
+    {
+        let proto = item.into_proto();
+
+        let interaction = crate::replay::reply_interaction(proto.clone(), self.inner.redactor())
+            .map_err(crate::session::Error::action)?;
+        self.interactions.push(interaction);
+
+        self.inner.reply(ProtoItem(proto))
+    }
+
+    fn send<I>(&mut self, sink: crate::Sink, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item + 'static,
+    {
+        let proto = item.into_proto();
+
+        let interaction = crate::replay::parcel_interaction(sink, proto.clone(), self.inner.redactor())
+            .map_err(crate::session::Error::action)?;
+        self.interactions.push(interaction);
+
+        self.inner.send(sink, ProtoItem(proto))
+    }
+
+    fn heartbeat(&mut self) {
+        self.inner.heartbeat()
+    }
+
+    fn sink_pressure(&self, sink: crate::Sink) -> usize {
+        self.inner.sink_pressure(sink)
+    }
+
+    fn redactor(&self) -> &crate::redact::Redactor {
+        self.inner.redactor()
+    }
+
+    fn check_limits(&self) -> crate::session::Result<()> {
+        self.inner.check_limits()
+    }
+
+    fn deadline(&self) -> Option<std::time::Instant> {
+        self.inner.deadline()
+    }
+
+    fn is_dry_run(&self) -> bool {
+        self.inner.is_dry_run()
+    }
+
+    fn check_path(&self, path: &std::path::Path) -> crate::session::Result<()> {
+        self.inner.check_path(path)
+    }
+
+    fn temp_dir(&mut self) -> crate::session::Result<crate::temp::TempDir> {
+        self.inner.temp_dir()
+    }
+}
+
+/// An item that has already been converted to its Protocol Buffers message.
+struct ProtoItem<M>(M);
+
+impl<M: protobuf::MessageFull> crate::response::Item for ProtoItem<M> {
+
+    type Proto = M;
+
+    fn into_proto(self) -> M {
+        self.0
+    }
+}
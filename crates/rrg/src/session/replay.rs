@@ -0,0 +1,188 @@
+/// A session implementation that compares interactions with a recording.
+///
+/// This session is used when replaying recorded requests (see the [`replay`]
+/// module for more details). Nothing is sent anywhere: every reply and parcel
+/// is compared with the next recorded interaction instead and all the points
+/// at which they diverge are collected into a report.
+///
+/// [`replay`]: crate::replay
+pub struct ReplaySession<'a> {
+    /// Arguments passed to the agent.
+    args: &'a crate::args::Args,
+    /// Identifier of the request being replayed.
+    request_id: crate::RequestId,
+    /// Action invoked by the request being replayed.
+    action: crate::request::Action,
+    /// Redactor of paths in results of the session.
+    redactor: crate::redact::Redactor,
+    /// Interactions of the recorded execution.
+    recorded: Vec<rrg_proto::replay::Interaction>,
+    /// Number of interactions of the replayed execution so far.
+    interaction_count: usize,
+    /// Points at which the replayed execution diverged so far.
+    divergences: Vec<crate::replay::Divergence>,
+    /// Scratch directory of the replayed request.
+    temp: crate::temp::Scope,
+    /// Whether the replayed request is executed as a dry run.
+    dry_run: bool,
+}
+
+impl<'a> ReplaySession<'a> {
+
+    /// Creates a new session replaying the given recorded interactions.
+    pub fn new(
+        args: &'a crate::args::Args,
+        request_id: crate::RequestId,
+        action: crate::request::Action,
+        dry_run: bool,
+        recorded: Vec<rrg_proto::replay::Interaction>,
+    ) -> ReplaySession<'a> {
+        ReplaySession {
+            args,
+            request_id,
+            action,
+            redactor: crate::redact::Redactor::new(&args.redacted_paths),
+            recorded,
+            interaction_count: 0,
+            divergences: Vec::new(),
+            temp: crate::temp::Scope::new(args, request_id),
+            dry_run,
+        }
+    }
+
+    /// Finishes the replay with the given result.
+    ///
+    /// `recorded_error` is the error of the recorded execution (if it failed).
+    pub fn finish(
+        mut self,
+        result: &crate::session::Result<()>,
+        recorded_error: Option<&str>,
+    ) -> crate::replay::Report {
+        use crate::replay::{describe, Divergence};
+
+        // Interactions missing from the replayed execution are reported only
+        // if it has not diverged in the last interaction already (in which case
+        // the rest of the recording is likely to be shifted anyway).
+        if self.interaction_count < self.recorded.len() {
+            let last_diverged = self.divergences.last()
+                .is_some_and(|divergence| divergence.index == Some(self.interaction_count - 1));
+
+            if !last_diverged {
+                self.divergences.push(Divergence {
+                    index: Some(self.interaction_count),
+                    expected: describe(self.recorded.get(self.interaction_count)),
+                    actual: describe(None),
+                });
+            }
+        }
+
+        fn describe_result(error: Option<&str>) -> String {
+            match error {
+                Some(error) => format!("error '{error}'"),
+                None => String::from("success"),
+            }
+        }
+
+        let error = result.as_ref().err().map(|error| error.to_string());
+        if error.as_deref() != recorded_error {
+            self.divergences.push(Divergence {
+                index: None,
+                expected: describe_result(recorded_error),
+                actual: describe_result(error.as_deref()),
+            });
+        }
+
+        crate::replay::Report {
+            interaction_count: self.interaction_count,
+            divergences: self.divergences,
+        }
+    }
+
+    /// Compares the given interaction with the next recorded one.
+    fn replay(&mut self, interaction: rrg_proto::replay::Interaction) {
+        use crate::replay::{describe, Divergence};
+
+        let index = self.interaction_count;
+        self.interaction_count += 1;
+
+        let recorded = self.recorded.get(index);
+        if recorded != Some(&interaction) {
+            self.divergences.push(Divergence {
+                index: Some(index),
+                expected: describe(recorded),
+                actual: describe(Some(&interaction)),
+            });
+        }
+    }
+}
+
+impl<'a> crate::session::Session for ReplaySession<'a> {
+
+    fn args(&self) -> &crate::args::Args {
+        self.args
+    }
+
+    fn request_id(&self) -> crate::RequestId {
+        self.request_id
+    }
+
+    fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item,
+    {
+        let interaction = crate::replay::reply_interaction(item.into_proto(), &self.redactor)
+            .map_err(crate::session::Error::action)?;
+        self.replay(interaction);
+
+        Ok(())
+    }
+
+    fn send<I>(&mut self, sink: crate::Sink, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item,
+    {
+        let interaction = crate::replay::parcel_interaction(sink, item.into_proto(), &self.redactor)
+            .map_err(crate::session::Error::action)?;
+        self.replay(interaction);
+
+        Ok(())
+    }
+
+    fn heartbeat(&mut self) {
+        // There is no Fleetspeak process to notify when replaying.
+    }
+
+    fn sink_pressure(&self, _: crate::Sink) -> usize {
+        // Parcels are only compared against the recording, never queued.
+        0
+    }
+
+    fn redactor(&self) -> &crate::redact::Redactor {
+        &self.redactor
+    }
+
+    fn check_limits(&self) -> crate::session::Result<()> {
+        // Limits of the recorded request are not recorded, so there is nothing
+        // to check (except for the shutdown).
+        crate::session::check_shutdown()
+    }
+
+    fn deadline(&self) -> Option<std::time::Instant> {
+        // Limits of the recorded request are not recorded, see above.
+        None
+    }
+
+    fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    fn check_path(&self, path: &std::path::Path) -> crate::session::Result<()> {
+        crate::policy::check_path(self.args, self.action, path)?;
+
+        Ok(())
+    }
+
+    fn temp_dir(&mut self) -> crate::session::Result<crate::temp::TempDir> {
+        self.temp.dir().map_err(crate::session::Error::action)
+    }
+}
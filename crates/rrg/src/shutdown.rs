@@ -0,0 +1,806 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Graceful shutdown of the agent.
+//!
+//! Once the agent is requested to terminate (e.g. with `SIGTERM` on Unix or a
+//! console control event on Windows), it stops receiving new requests (the ones
+//! that arrive nevertheless are refused with an error) and all
+//! sessions start reporting their actions as cancelled when the limits are
+//! checked. Actions are then given a grace period to flush their partial
+//! results and finish: the process exits as soon as there are no requests in
+//! flight or once the grace period is over, whichever comes first.
+//!
+//! Requests that are still in flight once the grace period is over have their
+//! undelivered responses (parcels held back by the session and the status that
+//! the server would never receive otherwise) written to the spool directory
+//! (the same one that crash reports are spooled to). Spooled responses are
+//! delivered once the agent is started again.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Interval at which the shutdown watchdog checks the state of the agent.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Extension of spooled status response files.
+const RESPONSE_EXTENSION: &str = "response";
+
+/// Extension of spooled parcel files.
+const PARCEL_EXTENSION: &str = "parcel";
+
+/// Maximum size of a spooled file accepted for delivery (in bytes).
+const MAX_SPOOLED_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Time the watchdog waits for the undelivered responses to be spoolable.
+const SPOOL_LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Whether the agent has been requested to shut down.
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Number of requests that are currently being handled.
+static IN_FLIGHT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Identifier to assign to the next [`InFlightGuard`].
+static NEXT_GUARD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Undelivered responses of requests in flight (keyed by guard identifiers).
+static UNDELIVERED: std::sync::Mutex<std::collections::BTreeMap<u64, Undelivered>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+/// Responses of a request in flight that have not been delivered yet.
+#[derive(Default)]
+struct Undelivered {
+    /// Status to deliver in case the request is interrupted.
+    status: Option<rrg_proto::rrg::Response>,
+    /// Parcels held back by the session.
+    parcels: std::collections::VecDeque<rrg_proto::rrg::Parcel>,
+}
+
+/// Installs handlers of termination signals and spawns the shutdown watchdog.
+///
+/// This function should be called only once, at the beginning of the process
+/// lifetime.
+pub fn init(args: &crate::args::Args) {
+    if let Err(error) = sys::install() {
+        log::error!("failed to install termination handler: {error}");
+    }
+
+    let grace_period = args.shutdown_grace_period;
+    let spool_dir = crate::crash::spool_dir(args);
+
+    let result = std::thread::Builder::new()
+        .name(String::from("shutdown"))
+        .spawn(move || watch(grace_period, spool_dir));
+    if let Err(error) = result {
+        log::error!("failed to spawn shutdown watchdog: {error}");
+    }
+}
+
+/// Returns whether the agent has been requested to shut down.
+pub fn requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Requests the agent to shut down.
+pub fn request() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Blocks the current thread until the process is terminated.
+///
+/// This should be called by the thread that accepts new requests once the
+/// shutdown has been requested. The process is terminated by the watchdog.
+pub fn wait() -> ! {
+    loop {
+        std::thread::park();
+    }
+}
+
+/// Sends responses spooled before the previous shutdown to the GRR server.
+///
+/// Responses are removed from the spool once sent, malformed ones are removed
+/// without being sent.
+pub fn deliver(args: &crate::args::Args) {
+    let Some(spool_dir) = crate::crash::spool_dir(args) else {
+        return;
+    };
+
+    let paths = match spooled(&spool_dir) {
+        Ok(paths) => paths,
+        // The spool directory is created only once something is spooled.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+        Err(error) => {
+            log::error!("failed to list spooled responses: {error}");
+            return;
+        }
+    };
+
+    for path in paths {
+        match read(&path) {
+            Ok(Spooled::Response(response)) => {
+                log::info!("sending spooled response '{}'", path.display());
+                crate::response::send_response(response);
+            }
+            Ok(Spooled::Parcel(parcel)) => {
+                use crate::transport::SinkBackend as _;
+
+                log::info!("sending spooled parcel '{}'", path.display());
+                crate::transport::Fleetspeak.send(parcel);
+            }
+            Err(error) => {
+                log::error!("invalid spooled response '{}': {error}", path.display());
+            }
+        }
+
+        if let Err(error) = std::fs::remove_file(&path) {
+            log::error!("failed to remove spooled response '{}': {error}", path.display());
+        }
+    }
+}
+
+/// Guard marking a request as being in flight.
+///
+/// Once the shutdown is requested, the agent is not terminated (until the grace
+/// period is over) as long as there are live guards.
+///
+/// Sessions use the guard to keep track of responses that the server has not
+/// received yet, so that they can be spooled if the request does not finish
+/// within the grace period.
+pub struct InFlightGuard {
+    id: u64,
+}
+
+impl InFlightGuard {
+
+    /// Marks a new request as being in flight.
+    pub fn new() -> InFlightGuard {
+        let id = NEXT_GUARD_ID.fetch_add(1, Ordering::SeqCst);
+        undelivered().insert(id, Undelivered::default());
+
+        IN_FLIGHT_COUNT.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard { id }
+    }
+
+    /// Sends responses of the request and updates its interrupted status.
+    ///
+    /// The status is the one to deliver if the request is interrupted after
+    /// the responses are sent, `None` means that the final status is among
+    /// them (and that held back parcels are no longer needed).
+    ///
+    /// The status is updated only once the responses are sent, but sending
+    /// does not block spooling (so that a stalled send cannot delay the exit).
+    /// Thus, a status spooled in the meantime might not account for the
+    /// replies that were being sent.
+    pub fn send_with_status<F, T>(
+        &self,
+        status: Option<rrg_proto::rrg::Response>,
+        send: F,
+    ) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        let result = send();
+
+        if let Some(entry) = undelivered().get_mut(&self.id) {
+            if status.is_none() {
+                entry.parcels.clear();
+            }
+            entry.status = status;
+        }
+
+        result
+    }
+
+    /// Holds back the given parcel until it is sent with [`send_held`].
+    ///
+    /// [`send_held`]: InFlightGuard::send_held
+    pub fn hold(&self, parcel: rrg_proto::rrg::Parcel) {
+        if let Some(entry) = undelivered().get_mut(&self.id) {
+            entry.parcels.push_back(parcel);
+        }
+    }
+
+    /// Sends the oldest held back parcel with the given function.
+    ///
+    /// The parcel stops being held as it is being sent (so a parcel the send
+    /// of which stalls is not spooled). The result of the function is returned
+    /// or `None` if there are no held back parcels.
+    pub fn send_held<F, T>(&self, send: F) -> Option<T>
+    where
+        F: FnOnce(rrg_proto::rrg::Parcel) -> T,
+    {
+        // The lock must not be held while sending, see `send_with_status`.
+        let parcel = undelivered().get_mut(&self.id)?.parcels.pop_front()?;
+
+        Some(send(parcel))
+    }
+
+    /// Drops all the held back parcels.
+    pub fn clear_held(&self) {
+        if let Some(entry) = undelivered().get_mut(&self.id) {
+            entry.parcels.clear();
+        }
+    }
+
+    /// Calls the given function with the held back parcels.
+    pub fn with_held<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&[rrg_proto::rrg::Parcel]) -> T,
+    {
+        match undelivered().get_mut(&self.id) {
+            Some(entry) => f(entry.parcels.make_contiguous()),
+            None => f(&[]),
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+
+    fn drop(&mut self) {
+        undelivered().remove(&self.id);
+        IN_FLIGHT_COUNT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Locks the undelivered responses of requests in flight.
+fn undelivered() -> std::sync::MutexGuard<'static, std::collections::BTreeMap<u64, Undelivered>> {
+    UNDELIVERED.lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Locks the undelivered responses unless it takes longer than the timeout.
+fn try_undelivered(
+    timeout: std::time::Duration,
+) -> Option<std::sync::MutexGuard<'static, std::collections::BTreeMap<u64, Undelivered>>> {
+    let deadline = std::time::Instant::now() + timeout;
+
+    loop {
+        match UNDELIVERED.try_lock() {
+            Ok(guard) => return Some(guard),
+            Err(std::sync::TryLockError::Poisoned(error)) => return Some(error.into_inner()),
+            Err(std::sync::TryLockError::WouldBlock) => (),
+        }
+
+        if std::time::Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
+
+/// Waits for the shutdown request and terminates the process.
+fn watch(grace_period: std::time::Duration, spool_dir: Option<std::path::PathBuf>) -> ! {
+    while !requested() {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    log::info! {
+        "shutdown requested, waiting up to {} for in-flight requests",
+        humantime::format_duration(grace_period),
+    };
+    crate::service::stopping(grace_period);
+
+    let deadline = std::time::Instant::now() + grace_period;
+    while IN_FLIGHT_COUNT.load(Ordering::SeqCst) > 0 {
+        if std::time::Instant::now() >= deadline {
+            log::error!("in-flight requests did not finish within the grace period");
+
+            // The lock is held until the process exits, so that no status is
+            // updated after its request is spooled. It is held only briefly by
+            // the requests (never while sending), but we do not want to risk
+            // not exiting at all if this is not the case for some reason.
+            let undelivered = try_undelivered(SPOOL_LOCK_TIMEOUT);
+            match (&spool_dir, &undelivered) {
+                (Some(spool_dir), Some(undelivered)) => {
+                    if let Err(error) = spool(spool_dir, undelivered) {
+                        log::error!("failed to spool undelivered responses: {error}");
+                    }
+                }
+                (Some(_), None) => log::error! {
+                    "failed to lock undelivered responses, they are lost"
+                },
+                (None, _) => log::warn!("no spool directory, undelivered responses are lost"),
+            }
+
+            // A non-zero exit code makes the service manager consider this a
+            // failure (and possibly apply the recovery policy).
+            crate::service::stopped(1);
+            std::process::exit(1);
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    log::info!("shutting down");
+    crate::service::stopped(0);
+    std::process::exit(0);
+}
+
+/// Response read from the spool directory.
+#[derive(Debug)]
+enum Spooled {
+    /// Response to send through Fleetspeak.
+    Response(rrg_proto::rrg::Response),
+    /// Parcel to send through Fleetspeak.
+    Parcel(rrg_proto::rrg::Parcel),
+}
+
+/// Writes the undelivered responses to new files in the spool directory.
+///
+/// Held back parcels of each request are written before its status, so that
+/// they are delivered in the same order as they would be by the session.
+fn spool(
+    spool_dir: &std::path::Path,
+    undelivered: &std::collections::BTreeMap<u64, Undelivered>,
+) -> std::io::Result<()> {
+    use protobuf::Message as _;
+
+    std::fs::create_dir_all(spool_dir)?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    let mut files = Vec::new();
+    for entry in undelivered.values() {
+        for parcel in &entry.parcels {
+            files.push((parcel.write_to_bytes()?, PARCEL_EXTENSION));
+        }
+        if let Some(status) = &entry.status {
+            files.push((status.write_to_bytes()?, RESPONSE_EXTENSION));
+        }
+    }
+
+    for (index, (bytes, extension)) in files.into_iter().enumerate() {
+        // Names start with the timestamp and the index within the spooling, so
+        // sorting them by name yields them in the order of spooling.
+        let name = format!("{timestamp}-{index:06}-{}", std::process::id());
+
+        let path = spool_dir.join(name).with_extension(extension);
+        crate::state::write_atomic(path, &bytes)?;
+    }
+
+    Ok(())
+}
+
+/// Returns paths of all spooled responses in the spool directory.
+///
+/// The paths are sorted in the order in which the responses were spooled.
+fn spooled(
+    spool_dir: &std::path::Path,
+) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut paths = Vec::new();
+
+    for entry in std::fs::read_dir(spool_dir)? {
+        let path = entry?.path();
+
+        let extension = path.extension();
+        if extension == Some(std::ffi::OsStr::new(RESPONSE_EXTENSION)) ||
+            extension == Some(std::ffi::OsStr::new(PARCEL_EXTENSION))
+        {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Reads a spooled response from the given path.
+fn read(path: &std::path::Path) -> std::io::Result<Spooled> {
+    use protobuf::Message as _;
+
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_SPOOLED_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("spooled response too large ({size} bytes)"),
+        ));
+    }
+
+    let bytes = std::fs::read(path)?;
+
+    if path.extension() == Some(std::ffi::OsStr::new(PARCEL_EXTENSION)) {
+        Ok(Spooled::Parcel(rrg_proto::rrg::Parcel::parse_from_bytes(&bytes)?))
+    } else {
+        Ok(Spooled::Response(rrg_proto::rrg::Response::parse_from_bytes(&bytes)?))
+    }
+}
+
+#[cfg(target_family = "unix")]
+mod sys {
+
+    /// Installs handlers of termination signals.
+    pub fn install() -> std::io::Result<()> {
+        for signal in [libc::SIGTERM, libc::SIGINT] {
+            // SAFETY: The handler is a valid function that only performs an
+            // async-signal-safe operation (an atomic store). We verify the
+            // result below.
+            let result = unsafe {
+                libc::signal(signal, handle as extern "C" fn(libc::c_int) as libc::sighandler_t)
+            };
+            if result == libc::SIG_ERR {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles a termination signal.
+    extern "C" fn handle(_: libc::c_int) {
+        super::request();
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    use windows_sys::Win32::{
+        Foundation::*,
+        System::Console::*,
+    };
+
+    /// Installs handlers of console control events.
+    pub fn install() -> std::io::Result<()> {
+        // SAFETY: The handler is a valid function with the expected signature.
+        // We verify the result below.
+        let status = unsafe {
+            SetConsoleCtrlHandler(Some(handle), TRUE)
+        };
+        if status == FALSE {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Handles a console control event.
+    unsafe extern "system" fn handle(event: u32) -> BOOL {
+        match event {
+            CTRL_C_EVENT | CTRL_BREAK_EVENT => {
+                super::request();
+                TRUE
+            }
+            CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => {
+                super::request();
+                // For these events the system terminates the process as soon
+                // as the handler returns, so we block until the watchdog exits
+                // the process.
+                super::wait()
+            }
+            _ => FALSE,
+        }
+    }
+}
+
+// Termination signals can be sent to a particular process only on Unix, so we
+// do not have tests for the Windows console control events.
+#[cfg(all(test, target_family = "unix"))]
+mod tests {
+
+    use super::*;
+
+    /// Environment variable with the directory of the terminated process.
+    const OUTPUT_DIR_ENV: &str = "RRG_TEST_SHUTDOWN_OUTPUT_DIR";
+
+    /// Grace period given to actions in the terminated process.
+    const GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(2);
+
+    /// Action handler that is running until cancelled and then flushes.
+    fn handle_until_cancelled<S>(
+        session: &mut S,
+        output_dir: std::path::PathBuf,
+    ) -> crate::session::Result<()>
+    where
+        S: crate::session::Session,
+    {
+        let error = loop {
+            if let Err(error) = session.check_limits() {
+                break error;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        std::fs::write(output_dir.join("flushed"), error.to_string())
+            .unwrap();
+
+        Err(error)
+    }
+
+    /// Action handler that ignores cancellation.
+    fn handle_forever<S>(_: &mut S, _: ()) -> crate::session::Result<()>
+    where
+        S: crate::session::Session,
+    {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Runs the given test in a separate process and terminates it.
+    ///
+    /// Within the separate process, the test is expected to initialize the
+    /// shutdown handling and start a long-running action. The returned time is
+    /// the time it took the process to exit after the termination request.
+    fn run_terminated(
+        test: &str,
+        output_dir: &std::path::Path,
+    ) -> (std::process::ExitStatus, std::time::Duration) {
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", test, "--test-threads", "1"])
+            .env(OUTPUT_DIR_ENV, output_dir)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+
+        // We wait for the child process to signal that the action has started.
+        let started_path = output_dir.join("started");
+        while !started_path.exists() {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        let terminated = std::time::Instant::now();
+        terminate(&child);
+
+        let status = child.wait()
+            .unwrap();
+
+        (status, terminated.elapsed())
+    }
+
+    /// Sends the termination signal to the given process.
+    fn terminate(child: &std::process::Child) {
+        // SAFETY: This function is safe to call with any arguments. We verify
+        // the result below.
+        let code = unsafe {
+            libc::kill(child.id() as libc::pid_t, libc::SIGTERM)
+        };
+        assert_eq!(code, 0);
+    }
+
+    /// Initializes the shutdown handling in the terminated process.
+    fn init_terminated(output_dir: &std::path::Path) {
+        use argh::FromArgs as _;
+
+        let grace_period = format!("{}s", GRACE_PERIOD.as_secs());
+        let spool_dir = output_dir.join("spool");
+        let args = crate::args::Args::from_args(&["rrg"], &[
+            "--shutdown-grace-period", &grace_period,
+            "--crash-spool-dir", spool_dir.to_str().unwrap(),
+        ]).unwrap();
+
+        init(&args);
+    }
+
+    /// Creates a parcel with the given payload.
+    fn parcel(payload: &str) -> rrg_proto::rrg::Parcel {
+        let mut payload_proto = protobuf::well_known_types::wrappers::StringValue::new();
+        payload_proto.value = String::from(payload);
+
+        let mut proto = rrg_proto::rrg::Parcel::new();
+        proto.set_sink(rrg_proto::rrg::Sink::BLOB);
+        proto.set_payload(protobuf::well_known_types::any::Any::pack(&payload_proto).unwrap());
+
+        proto
+    }
+
+    /// Creates a status response of an interrupted request.
+    fn interrupted_status(request_id: u64) -> rrg_proto::rrg::Response {
+        let error = crate::session::Error::action(std::io::Error::other("interrupted"));
+
+        crate::ResponseBuilder::new(crate::RequestId::new(0xf00, request_id))
+            .interrupted_status(error)
+            .into()
+    }
+
+    #[test]
+    fn shutdown_flushes_and_exits() {
+        if let Some(output_dir) = std::env::var_os(OUTPUT_DIR_ENV) {
+            let output_dir = std::path::PathBuf::from(output_dir);
+            init_terminated(&output_dir);
+
+            {
+                let _guard = InFlightGuard::new();
+                std::fs::write(output_dir.join("started"), b"").unwrap();
+
+                let mut session = crate::session::FakeSession::new();
+                let error = handle_until_cancelled(&mut session, output_dir)
+                    .unwrap_err();
+                assert_eq!(error.kind(), crate::session::ErrorKind::Cancelled);
+            }
+
+            wait();
+        }
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let (status, elapsed) = run_terminated("shutdown::tests::shutdown_flushes_and_exits", tempdir.path());
+        assert!(status.success());
+        assert!(elapsed < GRACE_PERIOD);
+
+        let flushed = std::fs::read_to_string(tempdir.path().join("flushed"))
+            .unwrap();
+        assert!(flushed.contains("shutdown"));
+    }
+
+    #[test]
+    fn shutdown_exits_after_grace_period() {
+        if let Some(output_dir) = std::env::var_os(OUTPUT_DIR_ENV) {
+            let output_dir = std::path::PathBuf::from(output_dir);
+            init_terminated(&output_dir);
+
+            let _guard = InFlightGuard::new();
+            std::fs::write(output_dir.join("started"), b"").unwrap();
+
+            let mut session = crate::session::FakeSession::new();
+            let _ = handle_forever(&mut session, ());
+
+            unreachable!();
+        }
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let (status, elapsed) = run_terminated("shutdown::tests::shutdown_exits_after_grace_period", tempdir.path());
+        assert!(!status.success());
+        assert!(elapsed >= GRACE_PERIOD);
+    }
+
+    #[test]
+    fn shutdown_spools_undelivered() {
+        if let Some(output_dir) = std::env::var_os(OUTPUT_DIR_ENV) {
+            let output_dir = std::path::PathBuf::from(output_dir);
+            init_terminated(&output_dir);
+
+            let guard = InFlightGuard::new();
+            guard.hold(parcel("foo"));
+            guard.send_with_status(Some(interrupted_status(0xba7)), || ());
+            std::fs::write(output_dir.join("started"), b"").unwrap();
+
+            let mut session = crate::session::FakeSession::new();
+            let _ = handle_forever(&mut session, ());
+
+            unreachable!();
+        }
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let (status, _) = run_terminated("shutdown::tests::shutdown_spools_undelivered", tempdir.path());
+        assert!(!status.success());
+
+        let paths = spooled(&tempdir.path().join("spool"))
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+
+        match read(&paths[0]).unwrap() {
+            Spooled::Parcel(proto) => assert_eq!(proto, parcel("foo")),
+            Spooled::Response(_) => panic!("unexpected response"),
+        }
+        match read(&paths[1]).unwrap() {
+            Spooled::Response(proto) => {
+                assert_eq!(proto.request_id(), 0xba7);
+                assert!(proto.status().has_error());
+            }
+            Spooled::Parcel(_) => panic!("unexpected parcel"),
+        }
+    }
+
+    #[test]
+    fn guard_send_held_in_order() {
+        let guard = InFlightGuard::new();
+        guard.hold(parcel("foo"));
+        guard.hold(parcel("bar"));
+        assert_eq!(guard.with_held(|parcels| parcels.len()), 2);
+
+        let mut sent = Vec::new();
+        while guard.send_held(|parcel| sent.push(parcel)).is_some() {
+        }
+        assert_eq!(sent, vec![parcel("foo"), parcel("bar")]);
+        assert_eq!(guard.with_held(|parcels| parcels.len()), 0);
+    }
+
+    #[test]
+    fn guard_send_with_status_final() {
+        let guard = InFlightGuard::new();
+        guard.hold(parcel("foo"));
+
+        guard.send_with_status(Some(interrupted_status(1)), || ());
+        assert!(undelivered()[&guard.id].status.is_some());
+        assert_eq!(guard.with_held(|parcels| parcels.len()), 1);
+
+        guard.send_with_status(None, || ());
+        assert!(undelivered()[&guard.id].status.is_none());
+        assert_eq!(guard.with_held(|parcels| parcels.len()), 0);
+    }
+
+    #[test]
+    fn guard_send_held_unlocked() {
+        let guard = InFlightGuard::new();
+        guard.hold(parcel("foo"));
+
+        // Spooling has to be possible while the parcel is being sent.
+        let locked = guard.send_held(|_| try_undelivered(SPOOL_LOCK_TIMEOUT).is_some());
+        assert_eq!(locked, Some(true));
+    }
+
+    #[test]
+    fn guard_send_with_status_unlocked() {
+        let guard = InFlightGuard::new();
+
+        // Spooling has to be possible while the responses are being sent.
+        let locked = guard.send_with_status(Some(interrupted_status(1)), || {
+            try_undelivered(SPOOL_LOCK_TIMEOUT).is_some()
+        });
+        assert!(locked);
+    }
+
+    #[test]
+    fn try_undelivered_timeout() {
+        let _undelivered = undelivered();
+
+        let locked = std::thread::spawn(|| {
+            try_undelivered(std::time::Duration::from_millis(10)).is_some()
+        }).join().unwrap();
+        assert!(!locked);
+    }
+
+    #[test]
+    fn guard_drop_forgets_undelivered() {
+        let guard = InFlightGuard::new();
+        let id = guard.id;
+        guard.send_with_status(Some(interrupted_status(1)), || ());
+        drop(guard);
+
+        assert!(!undelivered().contains_key(&id));
+    }
+
+    #[test]
+    fn spool_read_roundtrip() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut undelivered = std::collections::BTreeMap::new();
+        undelivered.insert(2, Undelivered {
+            status: Some(interrupted_status(2)),
+            parcels: std::collections::VecDeque::new(),
+        });
+        undelivered.insert(1, Undelivered {
+            status: Some(interrupted_status(1)),
+            parcels: std::collections::VecDeque::from([parcel("foo")]),
+        });
+        spool(tempdir.path(), &undelivered)
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("0.crash"), b"").unwrap();
+
+        let spooled = spooled(tempdir.path())
+            .unwrap()
+            .iter()
+            .map(|path| read(path).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(spooled.len(), 3);
+        assert!(matches!(&spooled[0], Spooled::Parcel(proto) if *proto == parcel("foo")));
+        assert!(matches!(&spooled[1], Spooled::Response(proto) if proto.request_id() == 1));
+        assert!(matches!(&spooled[2], Spooled::Response(proto) if proto.request_id() == 2));
+    }
+
+    #[test]
+    fn read_too_large() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("1.response");
+        std::fs::write(&path, vec![0; MAX_SPOOLED_SIZE as usize + 1]).unwrap();
+
+        let error = read(&path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
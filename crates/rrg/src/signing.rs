@@ -0,0 +1,483 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Verification of signed commands with a rotatable set of keys.
+//!
+//! The agent always trusts the key given on the command line (unless it was
+//! revoked). Further keys can be added (and revoked) through signed rotations
+//! that are persisted in the key store file, so that they survive restarts.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Key used to verify signed commands.
+#[derive(Debug, Clone)]
+pub struct VerificationKey {
+    /// Ed25519 public key.
+    key: ed25519_dalek::VerifyingKey,
+    /// Time from which the key is valid (if bounded).
+    valid_since: Option<SystemTime>,
+    /// Time until which the key is valid (if bounded).
+    valid_until: Option<SystemTime>,
+}
+
+impl VerificationKey {
+
+    /// Creates a new key without any validity bounds.
+    #[cfg(test)]
+    pub fn new(key: ed25519_dalek::VerifyingKey) -> VerificationKey {
+        VerificationKey {
+            key,
+            valid_since: None,
+            valid_until: None,
+        }
+    }
+
+    /// Returns the Ed25519 public key.
+    pub fn key(&self) -> &ed25519_dalek::VerifyingKey {
+        &self.key
+    }
+
+    /// Checks whether the key is valid at the given time.
+    pub fn is_valid_at(&self, time: SystemTime) -> bool {
+        self.valid_since.is_none_or(|valid_since| valid_since <= time) &&
+        self.valid_until.is_none_or(|valid_until| time <= valid_until)
+    }
+}
+
+/// Set of keys trusted for verifying signed commands.
+#[derive(Debug)]
+pub struct KeySet {
+    /// Key given on the command line (if any).
+    initial_key: Option<ed25519_dalek::VerifyingKey>,
+    /// Keys added through rotations.
+    keys: Vec<VerificationKey>,
+    /// Keys that must never be trusted again.
+    revoked_keys: Vec<ed25519_dalek::VerifyingKey>,
+}
+
+impl KeySet {
+
+    /// Creates a key set trusting only the given initial key.
+    pub fn new(initial_key: Option<ed25519_dalek::VerifyingKey>) -> KeySet {
+        KeySet {
+            initial_key,
+            keys: Vec::new(),
+            revoked_keys: Vec::new(),
+        }
+    }
+
+    /// Loads the key set persisted at the given path.
+    ///
+    /// If the file does not exist (e.g. no rotation happened yet), the key set
+    /// trusts only the initial key.
+    pub fn load<P>(
+        initial_key: Option<ed25519_dalek::VerifyingKey>,
+        path: P,
+    ) -> std::io::Result<KeySet>
+    where
+        P: AsRef<Path>,
+    {
+        use protobuf::Message as _;
+
+        let mut set = KeySet::new(initial_key);
+
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(set);
+            }
+            Err(error) => return Err(error),
+        };
+
+        fn invalid_data<E>(error: E) -> std::io::Error
+        where
+            E: std::error::Error + Send + Sync + 'static,
+        {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+        }
+
+        let mut proto = rrg_proto::signing::VerificationKeySet::parse_from_bytes(&bytes)
+            .map_err(invalid_data)?;
+
+        for key in proto.take_keys() {
+            let key = VerificationKey::try_from(key)
+                .map_err(invalid_data)?;
+            set.keys.push(key);
+        }
+
+        for key in proto.take_revoked_ed25519_public_keys() {
+            let key = parse_key(&key)
+                .map_err(invalid_data)?;
+            set.revoked_keys.push(key);
+        }
+
+        Ok(set)
+    }
+
+    /// Persists the key set at the given path.
+    ///
+    /// The initial key is not persisted. The file is replaced atomically, so a
+    /// failure never leaves a partially written key set behind.
+    pub fn save<P>(&self, path: P) -> std::io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        use protobuf::Message as _;
+
+        let mut proto = rrg_proto::signing::VerificationKeySet::new();
+        for key in &self.keys {
+            proto.mut_keys().push(key.clone().into());
+        }
+        for key in &self.revoked_keys {
+            proto.mut_revoked_ed25519_public_keys().push(key.to_bytes().to_vec());
+        }
+
+        let bytes = proto.write_to_bytes()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+        crate::state::write_atomic(path, &bytes)
+    }
+
+    /// Returns an iterator over keys trusted at the given time.
+    pub fn trusted_keys(
+        &self,
+        time: SystemTime,
+    ) -> impl Iterator<Item = &ed25519_dalek::VerifyingKey> {
+        let keys = self.keys.iter()
+            .filter(move |key| key.is_valid_at(time))
+            .map(VerificationKey::key);
+
+        self.initial_key.iter()
+            .chain(keys)
+            .filter(|key| !self.is_revoked(key))
+    }
+
+    /// Returns an iterator over revoked keys.
+    pub fn revoked_keys(&self) -> impl Iterator<Item = &ed25519_dalek::VerifyingKey> {
+        self.revoked_keys.iter()
+    }
+
+    /// Checks whether the given key has been revoked.
+    pub fn is_revoked(&self, key: &ed25519_dalek::VerifyingKey) -> bool {
+        self.revoked_keys.contains(key)
+    }
+
+    /// Verifies the signature of the given message at the given time.
+    ///
+    /// The signature is accepted if it was made with any of the keys trusted
+    /// at that time. The key that the signature was made with is returned.
+    pub fn verify(
+        &self,
+        message: &[u8],
+        signature: &ed25519_dalek::Signature,
+        time: SystemTime,
+    ) -> Result<ed25519_dalek::VerifyingKey, VerifyError> {
+        self.trusted_keys(time)
+            .find(|key| key.verify_strict(message, signature).is_ok())
+            .copied()
+            .ok_or(VerifyError)
+    }
+
+    /// Adds the new key to the set and revokes the other one (if given).
+    ///
+    /// If the new key is already in the set, its validity bounds are updated.
+    /// Revoked keys cannot be added back.
+    pub fn rotate(
+        &mut self,
+        new_key: VerificationKey,
+        revoked_key: Option<ed25519_dalek::VerifyingKey>,
+    ) -> Result<(), RevokedKeyError> {
+        if self.is_revoked(new_key.key()) || revoked_key == Some(new_key.key) {
+            return Err(RevokedKeyError);
+        }
+
+        self.keys.retain(|key| key.key != new_key.key);
+        self.keys.push(new_key);
+
+        if let Some(revoked_key) = revoked_key {
+            self.keys.retain(|key| key.key != revoked_key);
+            if !self.is_revoked(&revoked_key) {
+                self.revoked_keys.push(revoked_key);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An error indicating that none of the trusted keys verifies a signature.
+#[derive(Debug)]
+pub struct VerifyError;
+
+impl std::fmt::Display for VerifyError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "signature not verified by any trusted key")
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// An error indicating an attempt to trust a revoked key.
+#[derive(Debug)]
+pub struct RevokedKeyError;
+
+impl std::fmt::Display for RevokedKeyError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "key has been revoked")
+    }
+}
+
+impl std::error::Error for RevokedKeyError {}
+
+/// An error indicating that a verification key message is invalid.
+#[derive(Debug)]
+pub enum ParseKeyError {
+    /// The public key is malformed.
+    Key(ed25519_dalek::SignatureError),
+    /// One of the validity bounds is malformed.
+    Time(rrg_proto::ParseDurationError),
+}
+
+impl std::fmt::Display for ParseKeyError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseKeyError::Key(error) => write!(fmt, "invalid key: {error}"),
+            ParseKeyError::Time(error) => write!(fmt, "invalid validity bound: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseKeyError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseKeyError::Key(error) => Some(error),
+            ParseKeyError::Time(error) => Some(error),
+        }
+    }
+}
+
+/// Parses a raw Ed25519 public key.
+pub fn parse_key(bytes: &[u8]) -> Result<ed25519_dalek::VerifyingKey, ParseKeyError> {
+    ed25519_dalek::VerifyingKey::try_from(bytes)
+        .map_err(ParseKeyError::Key)
+}
+
+impl TryFrom<rrg_proto::signing::VerificationKey> for VerificationKey {
+
+    type Error = ParseKeyError;
+
+    fn try_from(
+        mut proto: rrg_proto::signing::VerificationKey,
+    ) -> Result<VerificationKey, ParseKeyError> {
+        let key = parse_key(proto.ed25519_public_key())?;
+
+        let valid_since = if proto.has_valid_since() {
+            Some(rrg_proto::try_from_timestamp(proto.take_valid_since())
+                .map_err(ParseKeyError::Time)?)
+        } else {
+            None
+        };
+
+        let valid_until = if proto.has_valid_until() {
+            Some(rrg_proto::try_from_timestamp(proto.take_valid_until())
+                .map_err(ParseKeyError::Time)?)
+        } else {
+            None
+        };
+
+        Ok(VerificationKey {
+            key,
+            valid_since,
+            valid_until,
+        })
+    }
+}
+
+impl From<VerificationKey> for rrg_proto::signing::VerificationKey {
+
+    fn from(key: VerificationKey) -> rrg_proto::signing::VerificationKey {
+        let mut proto = rrg_proto::signing::VerificationKey::new();
+        proto.set_ed25519_public_key(key.key.to_bytes().to_vec());
+        if let Some(valid_since) = key.valid_since {
+            proto.set_valid_since(rrg_proto::into_timestamp(valid_since));
+        }
+        if let Some(valid_until) = key.valid_until {
+            proto.set_valid_until(rrg_proto::into_timestamp(valid_until));
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use ed25519_dalek::Signer as _;
+
+    use super::*;
+
+    fn signing_key() -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn verify_initial_key() {
+        let signing_key = signing_key();
+        let set = KeySet::new(Some(signing_key.verifying_key()));
+
+        let signature = signing_key.sign(b"foo");
+        assert_eq! {
+            set.verify(b"foo", &signature, SystemTime::now()).unwrap(),
+            signing_key.verifying_key(),
+        };
+        assert!(set.verify(b"bar", &signature, SystemTime::now()).is_err());
+    }
+
+    #[test]
+    fn verify_no_keys() {
+        let set = KeySet::new(None);
+
+        let signature = signing_key().sign(b"foo");
+        assert!(set.verify(b"foo", &signature, SystemTime::now()).is_err());
+    }
+
+    #[test]
+    fn verify_rotated_key() {
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+
+        let mut set = KeySet::new(Some(initial_signing_key.verifying_key()));
+        set.rotate(VerificationKey::new(new_signing_key.verifying_key()), None)
+            .unwrap();
+
+        let now = SystemTime::now();
+        assert!(set.verify(b"foo", &initial_signing_key.sign(b"foo"), now).is_ok());
+        assert!(set.verify(b"foo", &new_signing_key.sign(b"foo"), now).is_ok());
+    }
+
+    #[test]
+    fn verify_validity_bounds() {
+        let signing_key = signing_key();
+        let now = SystemTime::now();
+
+        let mut set = KeySet::new(None);
+        set.rotate(VerificationKey {
+            key: signing_key.verifying_key(),
+            valid_since: Some(now),
+            valid_until: Some(now + Duration::from_secs(60)),
+        }, None).unwrap();
+
+        let signature = signing_key.sign(b"foo");
+        assert!(set.verify(b"foo", &signature, now - Duration::from_secs(1)).is_err());
+        assert!(set.verify(b"foo", &signature, now).is_ok());
+        assert!(set.verify(b"foo", &signature, now + Duration::from_secs(61)).is_err());
+    }
+
+    #[test]
+    fn rotate_revoke_initial_key() {
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+
+        let mut set = KeySet::new(Some(initial_signing_key.verifying_key()));
+        set.rotate(
+            VerificationKey::new(new_signing_key.verifying_key()),
+            Some(initial_signing_key.verifying_key()),
+        ).unwrap();
+
+        let now = SystemTime::now();
+        assert!(set.verify(b"foo", &initial_signing_key.sign(b"foo"), now).is_err());
+        assert!(set.verify(b"foo", &new_signing_key.sign(b"foo"), now).is_ok());
+    }
+
+    #[test]
+    fn rotate_revoked_key() {
+        let revoked_signing_key = signing_key();
+        let other_signing_key = signing_key();
+
+        let mut set = KeySet::new(None);
+        set.rotate(
+            VerificationKey::new(other_signing_key.verifying_key()),
+            Some(revoked_signing_key.verifying_key()),
+        ).unwrap();
+
+        assert!(set.rotate(VerificationKey::new(revoked_signing_key.verifying_key()), None).is_err());
+        assert_eq!(set.trusted_keys(SystemTime::now()).count(), 1);
+    }
+
+    #[test]
+    fn rotate_revoke_new_key() {
+        let signing_key = signing_key();
+
+        let mut set = KeySet::new(None);
+        assert!(set.rotate(
+            VerificationKey::new(signing_key.verifying_key()),
+            Some(signing_key.verifying_key()),
+        ).is_err());
+    }
+
+    #[test]
+    fn load_not_existing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let signing_key = signing_key();
+        let set = KeySet::load(Some(signing_key.verifying_key()), tempdir.path().join("keys"))
+            .unwrap();
+
+        assert_eq!(set.trusted_keys(SystemTime::now()).count(), 1);
+    }
+
+    #[test]
+    fn load_invalid() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("keys");
+        std::fs::write(&path, b"\xff\xff\xff")
+            .unwrap();
+
+        let error = KeySet::load(None, &path)
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("keys");
+
+        let initial_signing_key = signing_key();
+        let new_signing_key = signing_key();
+        let now = SystemTime::now();
+
+        let mut set = KeySet::new(Some(initial_signing_key.verifying_key()));
+        set.rotate(VerificationKey {
+            key: new_signing_key.verifying_key(),
+            valid_since: None,
+            valid_until: Some(now + Duration::from_secs(60)),
+        }, Some(initial_signing_key.verifying_key())).unwrap();
+        set.save(&path)
+            .unwrap();
+
+        // We simulate a restart by loading the set from scratch with the same
+        // initial key (as it is given on the command line).
+        let set = KeySet::load(Some(initial_signing_key.verifying_key()), &path)
+            .unwrap();
+
+        assert!(set.verify(b"foo", &initial_signing_key.sign(b"foo"), now).is_err());
+        assert!(set.verify(b"foo", &new_signing_key.sign(b"foo"), now).is_ok());
+
+        let later = now + Duration::from_secs(61);
+        assert!(set.verify(b"foo", &new_signing_key.sign(b"foo"), later).is_err());
+    }
+}
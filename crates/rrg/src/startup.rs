@@ -57,6 +57,12 @@ pub struct Metadata {
     pub name: String,
     /// Version of the RRG agent.
     pub version: Version,
+    /// Versions of arguments of the actions supported by the agent.
+    pub action_versions: Vec<(crate::request::Action, u32)>,
+    /// Time at which the agent executable was built.
+    pub build_time: std::time::SystemTime,
+    /// Information about the build of the agent executable.
+    pub build: crate::version::Build,
 }
 
 impl Metadata {
@@ -69,6 +75,9 @@ impl Metadata {
         Metadata {
             name: String::from(env!("CARGO_PKG_NAME")),
             version: Version::from_cargo(),
+            action_versions: crate::action::versions(),
+            build_time: crate::version::build_time(),
+            build: crate::version::Build::current(),
         }
     }
 }
@@ -109,7 +118,7 @@ impl Into<rrg_proto::startup::Startup> for Startup {
         let mut proto = rrg_proto::startup::Startup::new();
         proto.set_metadata(self.metadata.into());
         if let Some(path) = self.path {
-            proto.set_path(path.into());
+            proto.set_path(crate::path::into_proto(path));
         }
         proto.set_args(self.args.into());
         proto.set_agent_startup_time(into_timestamp(self.agent_started));
@@ -123,13 +132,29 @@ impl Into<rrg_proto::startup::Metadata> for Metadata {
     fn into(self) -> rrg_proto::startup::Metadata {
         let mut proto = rrg_proto::startup::Metadata::new();
         proto.set_name(self.name);
-        // TODO(@panhania): Add support for remaining fields.
         proto.set_version(self.version.into());
+        proto.set_build_time(rrg_proto::into_timestamp(self.build_time));
+        for (action, version) in self.action_versions {
+            proto.mut_action_versions().push(action_version_into_proto(action, version));
+        }
+        proto.set_build(self.build.into());
 
         proto
     }
 }
 
+/// Converts an action and the version of its arguments into a proto message.
+pub fn action_version_into_proto(
+    action: crate::request::Action,
+    version: u32,
+) -> rrg_proto::startup::ActionVersion {
+    let mut proto = rrg_proto::startup::ActionVersion::new();
+    proto.set_action(action.into());
+    proto.set_version(version);
+
+    proto
+}
+
 impl Into<rrg_proto::startup::Version> for Version {
 
     fn into(self) -> rrg_proto::startup::Version {
@@ -0,0 +1,423 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Directory in which the agent keeps its state.
+//!
+//! The state directory is locked by the running agent so that two instances
+//! never run concurrently (and corrupt the state or duplicate results). Files
+//! that the agent keeps there should be written using [`write_atomic`], so that
+//! a crash in the middle of writing never leaves a corrupted file behind.
+
+/// Name of the lock file within the state directory.
+const LOCK_FILE_NAME: &str = "rrg.lock";
+
+/// Exit code of the agent process if another instance is already running.
+///
+/// This is `EX_TEMPFAIL` from the BSD `sysexits.h` conventions.
+pub const LOCKED_EXIT_CODE: i32 = 75;
+
+/// Exclusive lock on the state directory.
+///
+/// The lock is held as long as this object is alive (and released by the
+/// operating system once the process dies, so there is no need to explicitly
+/// release it).
+pub struct Lock {
+    /// Locked file within the state directory.
+    _file: std::fs::File,
+}
+
+/// Acquires an exclusive lock on the given state directory.
+///
+/// The directory is created if it does not exist. The identifier of the current
+/// process is written to the lock file. An error is returned if another live
+/// instance of the agent holds the lock.
+pub fn lock(state_dir: &std::path::Path) -> Result<Lock, LockError> {
+    std::fs::create_dir_all(state_dir)?;
+
+    let path = state_dir.join(LOCK_FILE_NAME);
+
+    match try_lock(&path) {
+        Err(LockError::Locked { pid: Some(pid) }) if !sys::is_alive(pid) => {
+            // The process recorded in the lock file is dead but the file is
+            // still locked: it must have been inherited by its child. We cannot
+            // take the lock away from the child, but we can remove the file and
+            // lock a new one.
+            log::warn!("removing stale lock of dead process {pid}");
+            std::fs::remove_file(&path)?;
+
+            try_lock(&path)
+        }
+        result => result,
+    }
+}
+
+/// Attempts to lock the file at the given path once.
+fn try_lock(path: &std::path::Path) -> Result<Lock, LockError> {
+    use std::io::{Read as _, Seek as _, Write as _};
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    let mut content = String::new();
+    // The lock file is meaningful only as long as it is held, so if we fail
+    // to read it we just treat it as if there was no pid recorded.
+    let pid = match file.read_to_string(&mut content) {
+        Ok(_) => content.trim().parse::<u32>().ok(),
+        Err(_) => None,
+    };
+
+    match sys::try_lock(&file) {
+        Ok(()) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+            return Err(LockError::Locked { pid });
+        }
+        Err(error) => return Err(error.into()),
+    }
+
+    if let Some(pid) = pid {
+        log::info!("recovered lock of a previous instance (pid {pid})");
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+    file.sync_all()?;
+
+    Ok(Lock { _file: file })
+}
+
+/// Writes the given data to a file so that it is either fully written or not.
+///
+/// The data is first written to a temporary file next to the target one and
+/// then renamed into place.
+pub fn write_atomic<P>(path: P, data: &[u8]) -> std::io::Result<()>
+where
+    P: AsRef<std::path::Path>,
+{
+    use std::io::Write as _;
+
+    let path = path.as_ref();
+
+    let mut temp_path = path.as_os_str().to_os_string();
+    temp_path.push(".tmp");
+
+    let mut file = std::fs::File::create(&temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&temp_path, path)
+}
+
+/// An error that can occur when locking the state directory.
+#[derive(Debug)]
+pub enum LockError {
+    /// The state directory is locked by another instance of the agent.
+    Locked {
+        /// Identifier of the process holding the lock (if known).
+        pid: Option<u32>,
+    },
+    /// An I/O error occurred when locking the state directory.
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for LockError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LockError::Locked { pid: Some(pid) } => {
+                write!(fmt, "state directory locked by process {pid}")
+            }
+            LockError::Locked { pid: None } => {
+                write!(fmt, "state directory locked by another process")
+            }
+            LockError::Io(error) => {
+                write!(fmt, "failed to lock state directory: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LockError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LockError::Locked { .. } => None,
+            LockError::Io(error) => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for LockError {
+
+    fn from(error: std::io::Error) -> LockError {
+        LockError::Io(error)
+    }
+}
+
+#[cfg(target_family = "unix")]
+mod sys {
+
+    use std::os::unix::io::AsRawFd as _;
+
+    /// Attempts to exclusively lock the given file without blocking.
+    pub fn try_lock(file: &std::fs::File) -> std::io::Result<()> {
+        // SAFETY: The file descriptor is valid for the lifetime of the file
+        // object. We verify the result below.
+        let code = unsafe {
+            libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB)
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the process with the given identifier is alive.
+    pub fn is_alive(pid: u32) -> bool {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+
+        // SAFETY: Sending no signal only checks whether the process exists, so
+        // this is safe to call with any arguments.
+        let code = unsafe {
+            libc::kill(pid, 0)
+        };
+
+        // If we are not allowed to signal the process, it still exists.
+        code == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod sys {
+
+    use std::os::windows::io::AsRawHandle as _;
+
+    use windows_sys::Win32::{
+        Foundation::*,
+        Storage::FileSystem::*,
+        System::Threading::*,
+    };
+
+    /// Attempts to exclusively lock the given file without blocking.
+    pub fn try_lock(file: &std::fs::File) -> std::io::Result<()> {
+        // SAFETY: The structure consists of plain integers for which zero is a
+        // valid value.
+        let mut overlapped = unsafe {
+            std::mem::zeroed::<windows_sys::Win32::System::IO::OVERLAPPED>()
+        };
+
+        // SAFETY: The file handle is valid for the lifetime of the file object
+        // and we pass a valid overlapped structure. We verify the result below.
+        let status = unsafe {
+            LockFileEx(
+                file.as_raw_handle(),
+                LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+                0,
+                u32::MAX,
+                u32::MAX,
+                &mut overlapped,
+            )
+        };
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) {
+                return Err(std::io::ErrorKind::WouldBlock.into());
+            }
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the process with the given identifier is alive.
+    pub fn is_alive(pid: u32) -> bool {
+        // SAFETY: This function is safe to call with any arguments. We verify
+        // the returned handle below.
+        let handle = unsafe {
+            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid)
+        };
+        if handle.is_null() {
+            // If we are not allowed to open the process, it still exists.
+            let error = std::io::Error::last_os_error();
+            return error.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32);
+        }
+
+        let mut exit_code = 0;
+        // SAFETY: The handle is valid (verified above) and we pass a valid
+        // pointer to the exit code. We verify the result below.
+        let status = unsafe {
+            GetExitCodeProcess(handle, &mut exit_code)
+        };
+
+        // SAFETY: The handle is valid (verified above) and not used afterwards.
+        unsafe {
+            CloseHandle(handle);
+        }
+
+        status != FALSE && exit_code == STILL_ACTIVE as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn lock_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let _lock = lock(tempdir.path())
+            .unwrap();
+
+        let pid = std::fs::read_to_string(tempdir.path().join(LOCK_FILE_NAME))
+            .unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+    }
+
+    #[test]
+    fn lock_creates_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let state_dir = tempdir.path().join("foo").join("bar");
+        let _lock = lock(&state_dir)
+            .unwrap();
+
+        assert!(state_dir.is_dir());
+    }
+
+    #[test]
+    fn lock_twice() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let _lock = lock(tempdir.path())
+            .unwrap();
+
+        // We are alive, so the existing lock is not stale.
+        let error = lock(tempdir.path()).err()
+            .unwrap();
+        assert!(matches! {
+            error,
+            LockError::Locked { pid: Some(pid) } if pid == std::process::id()
+        });
+    }
+
+    #[test]
+    fn lock_after_release() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let lock_1 = lock(tempdir.path())
+            .unwrap();
+        drop(lock_1);
+
+        let _lock_2 = lock(tempdir.path())
+            .unwrap();
+    }
+
+    #[test]
+    fn lock_stale_unlocked() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // A lock file left by a dead process that is no longer locked.
+        std::fs::write(tempdir.path().join(LOCK_FILE_NAME), dead_pid().to_string())
+            .unwrap();
+
+        let _lock = lock(tempdir.path())
+            .unwrap();
+
+        let pid = std::fs::read_to_string(tempdir.path().join(LOCK_FILE_NAME))
+            .unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+    }
+
+    #[test]
+    fn lock_stale_locked() {
+        use std::io::Write as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // A lock file still locked (e.g. by a child process that inherited the
+        // handle) but recorded with a pid of a dead process.
+        let path = tempdir.path().join(LOCK_FILE_NAME);
+        let mut file = std::fs::File::create(&path)
+            .unwrap();
+        write!(file, "{}", dead_pid())
+            .unwrap();
+        sys::try_lock(&file)
+            .unwrap();
+
+        let _lock = lock(tempdir.path())
+            .unwrap();
+
+        let pid = std::fs::read_to_string(&path)
+            .unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+    }
+
+    #[test]
+    fn write_atomic_new() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        write_atomic(&path, b"bar")
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"bar");
+
+        let entry_count = std::fs::read_dir(tempdir.path())
+            .unwrap()
+            .count();
+        assert_eq!(entry_count, 1);
+    }
+
+    #[test]
+    fn write_atomic_replace() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let path = tempdir.path().join("foo");
+        std::fs::write(&path, b"bar")
+            .unwrap();
+
+        write_atomic(&path, b"quux")
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"quux");
+    }
+
+    /// Returns an identifier of a process that is known not to be alive.
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+
+        child.wait()
+            .unwrap();
+
+        // The child has been reaped, so its pid is not alive (unless it has
+        // been reused in the meantime, which is very unlikely).
+        assert!(!sys::is_alive(pid));
+
+        pid
+    }
+}
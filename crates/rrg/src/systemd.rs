@@ -0,0 +1,634 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for reading configuration of systemd units.
+
+use std::path::{Path, PathBuf};
+
+/// Paths to directories with system unit files.
+///
+/// The paths are ordered by priority: if a unit with the same name exists in
+/// more than one of them, the first one takes precedence [1].
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.unit.html#Unit%20File%20Load%20Path
+const UNIT_DIR_PATHS: &[&str] = &[
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// Path to the directory in which units are enabled (by symlinking).
+const ENABLED_UNIT_DIR_PATH: &str = "/etc/systemd/system";
+
+/// Returns names and paths of all units of the given type on the system.
+///
+/// Units from directories with higher priority shadow the units with the same
+/// name from the ones with lower priority, so every name is returned once.
+#[cfg(any(
+    feature = "action-list_scheduled_tasks",
+    feature = "action-list_services",
+))]
+pub fn units(unit_type: &str) -> Vec<(String, PathBuf)> {
+    let mut units = Vec::new();
+    let mut names = std::collections::HashSet::new();
+
+    for dir_path in UNIT_DIR_PATHS {
+        for path in unit_paths(Path::new(dir_path), unit_type) {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let name = name.to_string_lossy().into_owned();
+
+            if names.insert(name.clone()) {
+                units.push((name, path));
+            }
+        }
+    }
+
+    units
+}
+
+/// Returns paths to all unit files of the given type in the given directory.
+fn unit_paths(dir_path: &Path, unit_type: &str) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(dir_path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(error) => {
+            log::warn!("failed to list units in '{}': {error}", dir_path.display());
+            return Vec::new();
+        }
+    };
+
+    let mut paths = Vec::new();
+
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(error) => {
+                log::warn!("failed to read unit entry: {error}");
+                continue;
+            }
+        };
+
+        if path.extension() == Some(std::ffi::OsStr::new(unit_type)) {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+    paths
+}
+
+/// Returns the path of the unit file with the given name (if it exists).
+#[cfg(any(
+    feature = "action-list_linux_persistence",
+    feature = "action-list_scheduled_tasks",
+))]
+pub fn find_unit(name: &str) -> Option<PathBuf> {
+    UNIT_DIR_PATHS.iter()
+        .map(|dir_path| Path::new(dir_path).join(name))
+        .find(|path| path.exists())
+}
+
+/// Checks whether the unit with the given name is enabled.
+///
+/// Units are enabled by symlinking them into the `.wants` or `.requires`
+/// directories of other units (usually targets) [1].
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemctl.html#enable%20UNIT%E2%80%A6
+#[cfg(any(
+    feature = "action-list_scheduled_tasks",
+    feature = "action-list_services",
+))]
+pub fn is_enabled(name: &str) -> bool {
+    let entries = match std::fs::read_dir(ENABLED_UNIT_DIR_PATH) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to list enabled units: {error}");
+            return false;
+        }
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let extension = path.extension();
+            extension == Some(std::ffi::OsStr::new("wants")) ||
+            extension == Some(std::ffi::OsStr::new("requires"))
+        })
+        .any(|path| path.join(name).symlink_metadata().is_ok())
+}
+
+/// Returns names and paths of all enabled units.
+///
+/// The returned paths are the paths of the symlinks enabling the units, so they
+/// should be resolved to get to the actual unit files. Units enabled multiple
+/// times (e.g. wanted by more than one target) are returned once.
+#[cfg(feature = "action-list_linux_persistence")]
+pub fn enabled_units() -> Vec<(String, PathBuf)> {
+    let entries = match std::fs::read_dir(ENABLED_UNIT_DIR_PATH) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::warn!("failed to list enabled units: {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut dir_paths = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let extension = path.extension();
+            extension == Some(std::ffi::OsStr::new("wants")) ||
+            extension == Some(std::ffi::OsStr::new("requires"))
+        })
+        .collect::<Vec<_>>();
+    dir_paths.sort();
+
+    let mut units = Vec::new();
+    let mut names = std::collections::HashSet::new();
+
+    for dir_path in dir_paths {
+        let entries = match std::fs::read_dir(&dir_path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                log::warn!("failed to list units in '{}': {error}", dir_path.display());
+                continue;
+            }
+        };
+
+        let mut paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let name = name.to_string_lossy().into_owned();
+
+            if names.insert(name.clone()) {
+                units.push((name, path));
+            }
+        }
+    }
+
+    units
+}
+
+/// Returns names of units and paths of all drop-in files on the system.
+///
+/// Drop-in files are `.conf` files in `<unit>.d` directories next to unit
+/// files and override settings of the unit they are named after [1].
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.unit.html#Description
+#[cfg(feature = "action-list_linux_persistence")]
+pub fn drop_ins() -> Vec<(String, PathBuf)> {
+    let mut drop_ins = Vec::new();
+
+    for dir_path in UNIT_DIR_PATHS {
+        let entries = match std::fs::read_dir(dir_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                log::warn!("failed to list drop-ins in '{dir_path}': {error}");
+                continue;
+            }
+        };
+
+        let mut drop_in_dir_paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == Some(std::ffi::OsStr::new("d")) && path.is_dir())
+            .collect::<Vec<_>>();
+        drop_in_dir_paths.sort();
+
+        for drop_in_dir_path in drop_in_dir_paths {
+            let Some(name) = drop_in_dir_path.file_stem() else {
+                continue;
+            };
+            let name = name.to_string_lossy().into_owned();
+
+            for path in unit_paths(&drop_in_dir_path, "conf") {
+                drop_ins.push((name.clone(), path));
+            }
+        }
+    }
+
+    drop_ins
+}
+
+/// Checks whether the unit at the given path is masked.
+///
+/// Masked units are symlinked to `/dev/null` and cannot be started at all [1].
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemctl.html#mask%20UNIT%E2%80%A6
+#[cfg(feature = "action-list_services")]
+pub fn is_masked(path: &Path) -> bool {
+    match std::fs::read_link(path) {
+        Ok(target) => target == Path::new("/dev/null"),
+        Err(_) => false,
+    }
+}
+
+/// Strips the special prefixes of an `ExecStart=` value.
+///
+/// Commands can be prefixed with special characters that change how they are
+/// executed (e.g. `-` to ignore the exit code). These are not part of the
+/// command itself, so we strip them. See the [documentation][1] for details.
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.service.html#Command%20lines
+pub fn strip_exec_prefix(command: &str) -> &str {
+    command.trim_start_matches(['@', '-', ':', '+', '!'])
+}
+
+/// Expands specifiers (e.g. `%i`) in a setting of the unit with the given name.
+///
+/// Only specifiers that can be resolved from the name of the unit and the
+/// well-known system directories are expanded, the other ones are left as they
+/// are. See the [documentation][1] for the full list.
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.unit.html#Specifiers
+#[cfg(feature = "action-list_linux_persistence")]
+pub fn expand_specifiers(value: &str, unit_name: &str) -> String {
+    let (prefix, instance) = match unit_name.rsplit_once('.') {
+        Some((stem, _)) => match stem.split_once('@') {
+            Some((prefix, instance)) => (prefix, instance),
+            None => (stem, ""),
+        },
+        None => (unit_name, ""),
+    };
+
+    let mut result = String::new();
+
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '%' {
+            result.push(char);
+            continue;
+        }
+
+        let Some(specifier) = chars.next() else {
+            result.push('%');
+            break;
+        };
+
+        match specifier {
+            '%' => result.push('%'),
+            'n' => result.push_str(unit_name),
+            'N' => result.push_str(unit_name.rsplit_once('.').map_or(unit_name, |(stem, _)| stem)),
+            'p' => result.push_str(prefix),
+            'P' => result.push_str(&unescape_name(prefix)),
+            'i' => result.push_str(instance),
+            'I' => result.push_str(&unescape_name(instance)),
+            'f' => {
+                result.push('/');
+                result.push_str(&unescape_name(if instance.is_empty() { prefix } else { instance }));
+            }
+            'E' => result.push_str("/etc"),
+            'S' => result.push_str("/var/lib"),
+            'C' => result.push_str("/var/cache"),
+            'L' => result.push_str("/var/log"),
+            't' => result.push_str("/run"),
+            'T' => result.push_str("/tmp"),
+            'V' => result.push_str("/var/tmp"),
+            _ => {
+                result.push('%');
+                result.push(specifier);
+            }
+        }
+    }
+
+    result
+}
+
+/// Unescapes a part of a unit name escaped with `systemd-escape`.
+///
+/// Dashes are turned into slashes and `\xNN` sequences into the bytes they
+/// encode [1].
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd-escape.html
+#[cfg(feature = "action-list_linux_persistence")]
+fn unescape_name(name: &str) -> String {
+    let mut bytes = Vec::new();
+
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+
+        if byte == b'-' {
+            bytes.push(b'/');
+            continue;
+        }
+
+        if byte == b'\\' && rest.len() >= 3 && rest[0] == b'x' {
+            let hex = std::str::from_utf8(&rest[1..3]).ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = hex {
+                bytes.push(byte);
+                rest = &rest[3..];
+                continue;
+            }
+        }
+
+        bytes.push(byte);
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Splits an `ExecStart=` (or similar) command line into words.
+///
+/// Words can be quoted with either double or single quotes and the usual
+/// C-style escapes are supported. Special prefixes of the command should be
+/// stripped with [`strip_exec_prefix`] before. See the [documentation][1] for
+/// details about the syntax.
+///
+/// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.service.html#Command%20lines
+#[cfg(feature = "action-list_linux_persistence")]
+pub fn split_exec(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+
+    let mut word = None::<String>;
+    let mut quote = None;
+
+    let mut chars = command.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(escaped) => escaped,
+                    None => '\\',
+                };
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            '"' | '\'' if quote == Some(char) => {
+                quote = None;
+            }
+            '"' | '\'' if quote.is_none() && word.is_none() => {
+                quote = Some(char);
+                word = Some(String::new());
+            }
+            char if char.is_whitespace() && quote.is_none() => {
+                words.extend(word.take());
+            }
+            char => {
+                word.get_or_insert_with(String::new).push(char);
+            }
+        }
+    }
+    words.extend(word);
+
+    words
+}
+
+/// Parsed systemd unit file.
+#[derive(Debug, Default)]
+pub struct Unit {
+    /// All settings (section, key and value) in the order of definition.
+    settings: Vec<(String, String, String)>,
+}
+
+impl Unit {
+
+    /// Parses the unit file at the given path.
+    pub fn open<P>(path: P) -> std::io::Result<Unit>
+    where
+        P: AsRef<Path>,
+    {
+        let file = std::fs::File::open(path)?;
+        Unit::parse(std::io::BufReader::new(file))
+    }
+
+    /// Parses a unit file from the given reader.
+    ///
+    /// See the [documentation][1] for details about the format.
+    ///
+    /// [1]: https://www.freedesktop.org/software/systemd/man/latest/systemd.syntax.html
+    pub fn parse<R>(unit: R) -> std::io::Result<Unit>
+    where
+        R: std::io::BufRead,
+    {
+        let mut settings = Vec::new();
+        let mut section = String::new();
+
+        let mut lines = unit.lines();
+        while let Some(line) = lines.next() {
+            let mut line = line?;
+
+            // Lines ending with a backslash are continued in the next line and
+            // the backslash is replaced with a space. We also collapse leading
+            // indentation of the continued line.
+            while line.ends_with('\\') {
+                line.pop();
+                line.truncate(line.trim_end().len());
+                line.push(' ');
+
+                match lines.next() {
+                    Some(next) => line.push_str(next?.trim_start()),
+                    None => break,
+                }
+            }
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = String::from(name);
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            settings.push((
+                section.clone(),
+                String::from(key.trim()),
+                String::from(value.trim()),
+            ));
+        }
+
+        Ok(Unit {
+            settings,
+        })
+    }
+
+    /// Returns the last value of the given setting.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.get_all(section, key).last().copied()
+    }
+
+    /// Returns all values of the given setting.
+    ///
+    /// Assigning an empty value to a setting resets the list of values defined
+    /// before, so only values after the last empty assignment are returned.
+    pub fn get_all(&self, section: &str, key: &str) -> Vec<&str> {
+        let mut values = self.settings.iter()
+            .filter(|(s, k, _)| s == section && k == key)
+            .map(|(_, _, value)| value.as_str())
+            .collect::<Vec<_>>();
+
+        let start = values.iter()
+            .rposition(|value| value.is_empty())
+            .map_or(0, |index| index + 1);
+
+        values.drain(..start);
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn unit_parse_timer() {
+        let unit = Unit::parse(&b"\
+[Unit]
+Description=Daily apt download activities
+
+[Timer]
+OnCalendar=*-*-* 6,18:00
+RandomizedDelaySec=12h
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+"[..]).unwrap();
+
+        assert_eq!(unit.get("Unit", "Description"), Some("Daily apt download activities"));
+        assert_eq!(unit.get("Timer", "OnCalendar"), Some("*-*-* 6,18:00"));
+        assert_eq!(unit.get("Timer", "Unit"), None);
+        assert_eq!(unit.get("Install", "WantedBy"), Some("timers.target"));
+    }
+
+    #[test]
+    fn unit_parse_comments() {
+        let unit = Unit::parse(&b"\
+# Comment.
+; Another comment.
+[Service]
+#ExecStart=/bin/false
+ExecStart=/bin/true
+"[..]).unwrap();
+
+        assert_eq!(unit.get_all("Service", "ExecStart"), vec![
+            "/bin/true",
+        ]);
+    }
+
+    #[test]
+    fn unit_parse_continuation() {
+        let unit = Unit::parse(&b"\
+[Service]
+ExecStart=/usr/bin/foo \\
+    --bar \\
+    --baz
+User=nobody
+"[..]).unwrap();
+
+        assert_eq!(unit.get("Service", "ExecStart"), Some("/usr/bin/foo --bar --baz"));
+        assert_eq!(unit.get("Service", "User"), Some("nobody"));
+    }
+
+    #[test]
+    fn unit_get_all_reset() {
+        let unit = Unit::parse(&b"\
+[Timer]
+OnCalendar=daily
+OnCalendar=
+OnCalendar=weekly
+OnCalendar=monthly
+"[..]).unwrap();
+
+        assert_eq!(unit.get_all("Timer", "OnCalendar"), vec![
+            "weekly",
+            "monthly",
+        ]);
+    }
+
+    #[test]
+    fn strip_exec_prefix_many() {
+        assert_eq!(strip_exec_prefix("/bin/true"), "/bin/true");
+        assert_eq!(strip_exec_prefix("-/bin/true"), "/bin/true");
+        assert_eq!(strip_exec_prefix("+@/bin/true"), "/bin/true");
+    }
+
+    #[cfg(feature = "action-list_linux_persistence")]
+    #[test]
+    fn expand_specifiers_instance() {
+        assert_eq! {
+            expand_specifiers("/usr/bin/foo --tty=/dev/%i --unit=%n", "foo@tty1.service"),
+            "/usr/bin/foo --tty=/dev/tty1 --unit=foo@tty1.service"
+        };
+        assert_eq! {
+            expand_specifiers("/usr/bin/mount-%p %I %f", "backup@mnt-data.service"),
+            "/usr/bin/mount-backup mnt/data /mnt/data"
+        };
+    }
+
+    #[cfg(feature = "action-list_linux_persistence")]
+    #[test]
+    fn expand_specifiers_dirs() {
+        assert_eq! {
+            expand_specifiers("%S/foo %t/foo.sock %E/foo.conf", "foo.service"),
+            "/var/lib/foo /run/foo.sock /etc/foo.conf"
+        };
+    }
+
+    #[cfg(feature = "action-list_linux_persistence")]
+    #[test]
+    fn expand_specifiers_unknown() {
+        assert_eq!(expand_specifiers("100%% %Z %", "foo.service"), "100% %Z %");
+    }
+
+    #[cfg(feature = "action-list_linux_persistence")]
+    #[test]
+    fn split_exec_quoted() {
+        assert_eq!(split_exec("/bin/sh -c 'echo foo; echo bar'"), vec![
+            "/bin/sh", "-c", "echo foo; echo bar",
+        ]);
+        assert_eq!(split_exec("\"/opt/foo bar/baz\" --quux \"\""), vec![
+            "/opt/foo bar/baz", "--quux", "",
+        ]);
+    }
+
+    #[cfg(feature = "action-list_linux_persistence")]
+    #[test]
+    fn split_exec_escapes() {
+        assert_eq!(split_exec("/usr/bin/foo bar\\ baz \"qu\\\"ux\""), vec![
+            "/usr/bin/foo", "bar baz", "qu\"ux",
+        ]);
+        assert_eq!(split_exec("  /usr/bin/foo   --bar  "), vec![
+            "/usr/bin/foo", "--bar",
+        ]);
+    }
+
+    #[cfg(any(
+        feature = "action-list_scheduled_tasks",
+        feature = "action-list_services",
+    ))]
+    #[test]
+    fn units_unique() {
+        let units = units("service");
+
+        let names = units.iter()
+            .map(|(name, _)| name)
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(names.len(), units.len());
+    }
+}
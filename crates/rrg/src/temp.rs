@@ -0,0 +1,524 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Scratch space for action handlers.
+//!
+//! Some actions need to write intermediate files (e.g. copies of databases that
+//! are locked by other processes) before they can report anything. Instead of
+//! using system temporary files that can be left behind, handlers should ask
+//! the session for a scratch directory (see [`Session::temp_dir`]).
+//!
+//! Every request gets its own directory, created lazily once the handler asks
+//! for it. Directories are kept in the `tmp` subdirectory of the agent state
+//! directory (or in the system temporary directory if the agent has no state
+//! directory) and are removed once the request is handled. This happens also
+//! when the handler panics, as the session is dropped while the stack unwinds.
+//! Directories left behind by crashes that do not unwind (e.g. the process
+//! being killed) are removed when the agent starts again (see [`purge`]).
+//!
+//! Files written through [`TempDir::create`] count towards a size limit shared
+//! by the whole directory, so that a single request cannot exhaust the disk
+//! space of the state partition.
+//!
+//! [`Session::temp_dir`]: crate::session::Session::temp_dir
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Name of the subdirectory of the state directory with scratch directories.
+const DIR_NAME: &str = "tmp";
+
+/// Default limit on the total size of files written to a scratch directory.
+pub const DEFAULT_SIZE_LIMIT: u64 = 1024 * 1024 * 1024;
+
+/// Scratch directory of a single request.
+///
+/// The directory is created only once it is asked for and it is removed (with
+/// all its contents) when the scope is dropped.
+pub struct Scope {
+    /// Path at which the directory is created.
+    path: PathBuf,
+    /// Limit on the total size of files written to the directory.
+    size_limit: u64,
+    /// Handle to the directory (if it has been created).
+    dir: Option<TempDir>,
+}
+
+impl Scope {
+
+    /// Creates a new scope for the request with the given identifier.
+    pub fn new(args: &crate::args::Args, request_id: crate::RequestId) -> Scope {
+        let name = format!("{:X}-{}", request_id.flow_id(), request_id.request_id());
+
+        let path = match &args.state_dir {
+            Some(state_dir) => state_dir.join(DIR_NAME).join(name),
+            // The system temporary directory is shared with other processes
+            // (possibly other agent instances) and the same request can be
+            // handled multiple times within one process (e.g. in tests), so
+            // we need a unique name.
+            None => {
+                static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+                let seq = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+
+                let pid = std::process::id();
+                std::env::temp_dir().join(format!("rrg-{pid}-{seq}-{name}"))
+            }
+        };
+
+        Scope {
+            path,
+            size_limit: args.temp_dir_size_limit.unwrap_or(DEFAULT_SIZE_LIMIT),
+            dir: None,
+        }
+    }
+
+    /// Returns the scratch directory, creating it if it does not exist yet.
+    pub fn dir(&mut self) -> std::io::Result<TempDir> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // The directory might exist if the agent crashed while handling the
+        // same request before, we do not want to reuse anything from it.
+        match create_private_dir(&self.path) {
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                std::fs::remove_dir_all(&self.path)?;
+                create_private_dir(&self.path)?;
+            }
+            result => result?,
+        }
+
+        let dir = TempDir {
+            path: self.path.clone(),
+            budget: Arc::new(Budget {
+                used: AtomicU64::new(0),
+                limit: self.size_limit,
+            }),
+        };
+        self.dir = Some(dir.clone());
+
+        Ok(dir)
+    }
+}
+
+impl Drop for Scope {
+
+    fn drop(&mut self) {
+        if self.dir.is_none() {
+            return;
+        }
+
+        if let Err(error) = std::fs::remove_dir_all(&self.path) {
+            log::error!("failed to remove scratch directory '{}': {error}", self.path.display());
+        }
+    }
+}
+
+/// Handle to a scratch directory of a request.
+#[derive(Clone, Debug)]
+pub struct TempDir {
+    /// Path to the directory.
+    path: PathBuf,
+    /// Budget of bytes that can be written to the directory.
+    budget: Arc<Budget>,
+}
+
+impl TempDir {
+
+    /// Returns the path to the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path to the given file within the directory.
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+
+    /// Creates a file within the directory for writing.
+    ///
+    /// Bytes written to the file count towards the size limit of the directory
+    /// and writes that would exceed it fail.
+    pub fn create<P: AsRef<Path>>(&self, path: P) -> std::io::Result<Writer> {
+        let file = std::fs::File::create(self.join(path))?;
+
+        Ok(Writer {
+            file,
+            budget: self.budget.clone(),
+        })
+    }
+}
+
+/// Writer to a file within a scratch directory.
+///
+/// See [`TempDir::create`] for more details.
+pub struct Writer {
+    /// File being written to.
+    file: std::fs::File,
+    /// Budget of bytes that can be written to the directory.
+    budget: Arc<Budget>,
+}
+
+impl Writer {
+
+    /// Returns the file being written to.
+    pub fn file(&self) -> &std::fs::File {
+        &self.file
+    }
+}
+
+impl std::io::Write for Writer {
+
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.budget.reserve(buf.len() as u64)?;
+
+        match self.file.write(buf) {
+            Ok(len) => {
+                // Not everything might have been written, so we give back what
+                // was reserved in excess.
+                self.budget.release((buf.len() - len) as u64);
+                Ok(len)
+            }
+            Err(error) => {
+                self.budget.release(buf.len() as u64);
+                Err(error)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Budget of bytes that can be written to a scratch directory.
+#[derive(Debug)]
+struct Budget {
+    /// Number of bytes written so far.
+    used: AtomicU64,
+    /// Maximum number of bytes that can be written.
+    limit: u64,
+}
+
+impl Budget {
+
+    /// Reserves the given number of bytes of the budget.
+    fn reserve(&self, len: u64) -> std::io::Result<()> {
+        let result = self.used.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+            used.checked_add(len).filter(|used| *used <= self.limit)
+        });
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(_) => Err(std::io::Error::new(std::io::ErrorKind::StorageFull, SizeLimitError {
+                limit: self.limit,
+            })),
+        }
+    }
+
+    /// Gives back the given number of bytes reserved before.
+    fn release(&self, len: u64) {
+        self.used.fetch_sub(len, Ordering::SeqCst);
+    }
+}
+
+/// Removes scratch directories left behind in the given state directory.
+///
+/// This should be called once the state directory is locked by the agent (so
+/// that no other instance can be using these directories).
+pub fn purge(state_dir: &Path) {
+    let path = state_dir.join(DIR_NAME);
+
+    match std::fs::remove_dir_all(&path) {
+        Ok(()) => log::info!("removed leftover scratch directories"),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => {
+            log::error!("failed to remove scratch directories '{}': {error}", path.display());
+        }
+    }
+}
+
+/// Creates a directory accessible only to the agent.
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::DirBuilderExt as _;
+        builder.mode(0o700);
+    }
+
+    // On Windows, the directory inherits permissions of the state directory
+    // (which should be accessible only to privileged users).
+    builder.create(path)
+}
+
+/// An error indicating that the scratch directory size limit was exceeded.
+#[derive(Debug)]
+struct SizeLimitError {
+    /// Limit on the total size of files written to the directory.
+    limit: u64,
+}
+
+impl std::fmt::Display for SizeLimitError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "scratch directory size limit ({} bytes) exceeded", self.limit)
+    }
+}
+
+impl std::error::Error for SizeLimitError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::session::Session as _;
+
+    fn args(state_dir: &Path) -> crate::args::Args {
+        use argh::FromArgs as _;
+
+        crate::args::Args::from_args(&["rrg"], &[
+            "--state-dir", state_dir.to_str().unwrap(),
+        ]).unwrap()
+    }
+
+    #[test]
+    fn scope_lazy() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let scope = Scope::new(&args(tempdir.path()), crate::RequestId::new(0xf00, 42));
+        drop(scope);
+
+        assert!(!tempdir.path().join(DIR_NAME).exists());
+    }
+
+    #[test]
+    fn scope_dir_removed_on_drop() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut scope = Scope::new(&args(tempdir.path()), crate::RequestId::new(0xf00, 42));
+
+        let dir = scope.dir()
+            .unwrap();
+        assert!(dir.path().starts_with(tempdir.path().join(DIR_NAME)));
+
+        std::fs::create_dir(dir.join("foo"))
+            .unwrap();
+        std::fs::write(dir.join("foo").join("bar"), b"baz")
+            .unwrap();
+
+        drop(scope);
+        assert!(!dir.path().exists());
+    }
+
+    #[test]
+    fn scope_dir_same() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut scope = Scope::new(&args(tempdir.path()), crate::RequestId::new(0xf00, 42));
+
+        let dir_1 = scope.dir()
+            .unwrap();
+        std::fs::write(dir_1.join("foo"), b"")
+            .unwrap();
+
+        let dir_2 = scope.dir()
+            .unwrap();
+        assert_eq!(dir_1.path(), dir_2.path());
+        assert!(dir_2.join("foo").exists());
+    }
+
+    #[test]
+    fn scope_dir_leftover() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut scope = Scope::new(&args(tempdir.path()), crate::RequestId::new(0xf00, 42));
+        std::fs::create_dir_all(&scope.path)
+            .unwrap();
+        std::fs::write(scope.path.join("foo"), b"")
+            .unwrap();
+
+        let dir = scope.dir()
+            .unwrap();
+        assert!(!dir.join("foo").exists());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn scope_dir_private() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut scope = Scope::new(&args(tempdir.path()), crate::RequestId::new(0xf00, 42));
+        let dir = scope.dir()
+            .unwrap();
+
+        let metadata = std::fs::metadata(dir.path())
+            .unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o700);
+    }
+
+    #[test]
+    fn session_handle_ok() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        fn handle<S: crate::session::Session>(session: &mut S, _: ()) -> crate::session::Result<PathBuf> {
+            let dir = session.temp_dir()?;
+            std::fs::write(dir.join("foo"), b"bar")
+                .map_err(crate::session::Error::action)?;
+
+            Ok(dir.path().to_path_buf())
+        }
+
+        let mut session = crate::session::FakeSession::with_args(args(tempdir.path()));
+        let path = handle(&mut session, ())
+            .unwrap();
+        assert!(path.join("foo").exists());
+
+        drop(session);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn session_handle_error() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        fn handle<S: crate::session::Session>(session: &mut S, _: ()) -> crate::session::Result<()> {
+            let dir = session.temp_dir()?;
+            std::fs::write(dir.join("foo"), b"bar")
+                .map_err(crate::session::Error::action)?;
+
+            let error = std::io::Error::from(std::io::ErrorKind::Other);
+            Err(crate::session::Error::action(error))
+        }
+
+        let mut session = crate::session::FakeSession::with_args(args(tempdir.path()));
+        assert!(handle(&mut session, ()).is_err());
+
+        drop(session);
+        let entries = std::fs::read_dir(tempdir.path().join(DIR_NAME))
+            .unwrap();
+        assert_eq!(entries.count(), 0);
+    }
+
+    #[test]
+    fn session_handle_panic() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let state_dir = tempdir.path().to_path_buf();
+        let result = std::panic::catch_unwind(move || {
+            let mut session = crate::session::FakeSession::with_args(args(&state_dir));
+
+            let dir = session.temp_dir()
+                .unwrap();
+            std::fs::write(dir.join("foo"), b"bar")
+                .unwrap();
+
+            panic!("handler failure");
+        });
+        assert!(result.is_err());
+
+        let entries = std::fs::read_dir(tempdir.path().join(DIR_NAME))
+            .unwrap();
+        assert_eq!(entries.count(), 0);
+    }
+
+    #[test]
+    fn writer_within_limit() {
+        use std::io::Write as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut args = args(tempdir.path());
+        args.temp_dir_size_limit = Some(8);
+
+        let mut scope = Scope::new(&args, crate::RequestId::new(0xf00, 42));
+        let dir = scope.dir()
+            .unwrap();
+
+        let mut writer = dir.create("foo")
+            .unwrap();
+        writer.write_all(b"1234")
+            .unwrap();
+        writer.write_all(b"5678")
+            .unwrap();
+        drop(writer);
+
+        assert_eq!(std::fs::read(dir.join("foo")).unwrap(), b"12345678");
+    }
+
+    #[test]
+    fn writer_exceeding_limit() {
+        use std::io::Write as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut args = args(tempdir.path());
+        args.temp_dir_size_limit = Some(8);
+
+        let mut scope = Scope::new(&args, crate::RequestId::new(0xf00, 42));
+        let dir = scope.dir()
+            .unwrap();
+
+        // The limit is shared by all the files in the directory.
+        let mut writer_foo = dir.create("foo")
+            .unwrap();
+        writer_foo.write_all(b"123456")
+            .unwrap();
+
+        let mut writer_bar = dir.create("bar")
+            .unwrap();
+        let error = writer_bar.write_all(b"789")
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::StorageFull);
+
+        // Failed writes do not count towards the limit.
+        writer_bar.write_all(b"78")
+            .unwrap();
+    }
+
+    #[test]
+    fn purge_leftovers() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let leftover = tempdir.path().join(DIR_NAME).join("F00-42");
+        std::fs::create_dir_all(&leftover)
+            .unwrap();
+        std::fs::write(leftover.join("foo"), b"bar")
+            .unwrap();
+
+        purge(tempdir.path());
+        assert!(!tempdir.path().join(DIR_NAME).exists());
+    }
+
+    #[test]
+    fn purge_nothing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        purge(tempdir.path());
+        assert!(!tempdir.path().join(DIR_NAME).exists());
+    }
+}
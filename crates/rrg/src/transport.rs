@@ -0,0 +1,82 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Channels through which parcels are delivered to the server.
+//!
+//! By default all parcels are sent through Fleetspeak. Depending on the agent
+//! configuration, parcels addressed to some sinks can be delivered through
+//! other backends instead (e.g. large blobs can be uploaded directly to an
+//! HTTPS endpoint). Such backends fall back to Fleetspeak if they fail.
+
+#[cfg(feature = "transport-https_blob")]
+pub mod https_blob;
+
+/// Backend delivering parcels to their sinks.
+pub trait SinkBackend {
+
+    /// Sends the given parcel to the server.
+    ///
+    /// This function returns number of bytes sent to the server.
+    fn send(&mut self, parcel: rrg_proto::rrg::Parcel) -> usize;
+
+    /// Returns the number of bytes sent to the given sink but not delivered.
+    ///
+    /// Backends that deliver parcels synchronously (as part of the `send` call)
+    /// never have anything queued, which is the default.
+    fn pressure(&self, _: crate::Sink) -> usize {
+        0
+    }
+}
+
+/// Backend delivering parcels through Fleetspeak.
+pub struct Fleetspeak;
+
+impl SinkBackend for Fleetspeak {
+
+    fn send(&mut self, parcel: rrg_proto::rrg::Parcel) -> usize {
+        use protobuf::Message as _;
+
+        let data = parcel.write_to_bytes()
+            // This should only fail in case we are out of memory, which we are
+            // almost certainly not (and if we are, we have a bigger issue).
+            .unwrap();
+
+        let data_len = data.len();
+
+        fleetspeak::send(fleetspeak::Message {
+            service: String::from("GRR"),
+            kind: Some(String::from("rrg.Parcel")),
+            data,
+        });
+
+        data_len
+    }
+}
+
+/// Creates a backend for delivering parcels as specified in agent arguments.
+pub fn sink_backend(args: &crate::args::Args) -> Box<dyn SinkBackend> {
+    let Some(url) = &args.blob_upload_url else {
+        return Box::new(Fleetspeak);
+    };
+
+    #[cfg(feature = "transport-https_blob")]
+    match https_blob::Uploader::new(url, &args.blob_upload_pins) {
+        Ok(uploader) => {
+            return Box::new(https_blob::Backend::new(
+                uploader,
+                args.blob_upload_threshold,
+                Fleetspeak,
+            ));
+        }
+        Err(error) => {
+            log::error!("invalid blob upload endpoint '{url}': {error}");
+        }
+    }
+
+    #[cfg(not(feature = "transport-https_blob"))]
+    log::warn!("blob upload endpoint '{url}' ignored (not supported)");
+
+    Box::new(Fleetspeak)
+}
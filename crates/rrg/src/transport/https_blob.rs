@@ -0,0 +1,740 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Direct upload of large blobs to an HTTPS endpoint.
+//!
+//! Blobs are uploaded to `<url>/<sha256>` (where `<sha256>` is a hex-encoded
+//! digest of the blob) with a sequence of `PUT` requests, each carrying one
+//! chunk of the blob and its range in the `Content-Range` header. The server
+//! responds with `308` and a `Range` header with the bytes received so far
+//! until the blob is complete, in which case it responds with `200` or `201`.
+//!
+//! If an upload is interrupted, it is resumed from the offset that the server
+//! reports in response to an empty `PUT` request with `bytes */<size>` range.
+//!
+//! The endpoint is trusted only if the public key of its certificate matches
+//! one of the configured pins (SHA-256 digests of the DER-encoded SPKI). Pins
+//! are the only source of trust: certificate chains, names and validity time
+//! are not checked. Pin mismatches are never retried.
+
+use std::io::{BufRead as _, Write as _};
+use std::sync::Arc;
+
+/// Default size of the chunks in which blobs are uploaded.
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024; // 1 MiB.
+
+/// Maximum number of attempts to upload a blob.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Timeout for reading from and writing to the endpoint.
+const IO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Backend uploading large blobs to an HTTPS endpoint.
+///
+/// Parcels that are not blobs, blobs below the size threshold and blobs that
+/// failed to upload are sent through the fallback backend.
+pub struct Backend<B: crate::transport::SinkBackend> {
+    /// Uploader of the blobs.
+    uploader: Uploader,
+    /// Minimum size of blobs to upload.
+    threshold: usize,
+    /// Backend used for parcels that are not uploaded.
+    fallback: B,
+}
+
+impl<B: crate::transport::SinkBackend> Backend<B> {
+
+    /// Creates a new backend uploading blobs of at least `threshold` bytes.
+    pub fn new(uploader: Uploader, threshold: usize, fallback: B) -> Backend<B> {
+        Backend {
+            uploader,
+            threshold,
+            fallback,
+        }
+    }
+}
+
+impl<B: crate::transport::SinkBackend> crate::transport::SinkBackend for Backend<B> {
+
+    fn send(&mut self, parcel: rrg_proto::rrg::Parcel) -> usize {
+        if parcel.sink() != rrg_proto::rrg::Sink::BLOB {
+            return self.fallback.send(parcel);
+        }
+
+        let blob = match parcel.payload.unpack::<rrg_proto::blob::Blob>() {
+            Ok(Some(blob)) => blob,
+            Ok(None) | Err(_) => return self.fallback.send(parcel),
+        };
+
+        // Empty blobs are sent through the fallback even if the threshold is 0
+        // as there is nothing to upload.
+        if blob.data().is_empty() || blob.data().len() < self.threshold {
+            return self.fallback.send(parcel);
+        }
+
+        match self.uploader.upload(blob.data()) {
+            Ok(()) => blob.data().len(),
+            Err(error) => {
+                log::warn!("failed to upload blob, falling back: {error}");
+                self.fallback.send(parcel)
+            }
+        }
+    }
+
+    fn pressure(&self, sink: crate::Sink) -> usize {
+        // Blobs are uploaded synchronously, so only the fallback can have any
+        // parcels queued.
+        self.fallback.pressure(sink)
+    }
+}
+
+/// Uploader of blobs to an HTTPS endpoint.
+pub struct Uploader {
+    /// Configuration of TLS connections to the endpoint.
+    tls_config: Arc<rustls::ClientConfig>,
+    /// Host of the endpoint.
+    host: String,
+    /// Port of the endpoint.
+    port: u16,
+    /// Path of the endpoint (without the trailing slash).
+    path: String,
+    /// Size of the chunks in which blobs are uploaded.
+    chunk_size: usize,
+}
+
+impl Uploader {
+
+    /// Creates a new uploader for the endpoint at the given URL.
+    ///
+    /// Only `https://` URLs are supported.
+    pub fn new(url: &str, pins: &[[u8; 32]]) -> Result<Uploader, InvalidEndpointError> {
+        let url = url.strip_prefix("https://")
+            .ok_or(InvalidEndpointError::Scheme)?;
+
+        let (authority, path) = match url.find('/') {
+            Some(index) => url.split_at(index),
+            None => (url, ""),
+        };
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse::<u16>()
+                    .map_err(|_| InvalidEndpointError::Port)?;
+                (host, port)
+            }
+            None => (authority, 443),
+        };
+        if host.is_empty() {
+            return Err(InvalidEndpointError::Host);
+        }
+
+        if pins.is_empty() {
+            return Err(InvalidEndpointError::NoPins);
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = PinVerifier {
+            pins: pins.to_vec(),
+            algorithms: provider.signature_verification_algorithms,
+        };
+
+        let tls_config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(InvalidEndpointError::Tls)?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Ok(Uploader {
+            tls_config: Arc::new(tls_config),
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        })
+    }
+
+    /// Uploads the given blob to the endpoint.
+    pub fn upload(&self, data: &[u8]) -> std::io::Result<()> {
+        let sha256 = {
+            use sha2::Digest as _;
+            sha2::Sha256::digest(data)
+        };
+
+        let mut path = format!("{}/", self.path);
+        for byte in sha256 {
+            path.push_str(&format!("{byte:02x}"));
+        }
+
+        let mut offset = 0;
+        let mut attempt = 1;
+        loop {
+            let error = match self.upload_from(&path, data, offset) {
+                Ok(()) => return Ok(()),
+                Err(error) if is_fatal(&error) || attempt == MAX_ATTEMPTS => {
+                    return Err(error)
+                }
+                Err(error) => error,
+            };
+
+            log::warn!("blob upload interrupted (attempt {attempt}): {error}");
+            attempt += 1;
+
+            offset = match self.query_offset(&path, data.len()) {
+                Ok(Some(offset)) => offset,
+                Ok(None) => return Ok(()),
+                Err(error) if is_fatal(&error) => return Err(error),
+                // If we cannot determine where to resume from, we start over.
+                Err(_) => 0,
+            };
+        }
+    }
+
+    /// Uploads the blob starting at the given offset.
+    fn upload_from(&self, path: &str, data: &[u8], mut offset: usize) -> std::io::Result<()> {
+        loop {
+            let end = std::cmp::min(offset + self.chunk_size, data.len());
+            let range = format!("bytes {}-{}/{}", offset, end - 1, data.len());
+
+            match self.put(path, &range, &data[offset..end])? {
+                Status::Complete => return Ok(()),
+                Status::Incomplete(received) if received > offset && received < data.len() => {
+                    offset = received;
+                }
+                Status::Incomplete(received) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("unexpected number of bytes received: {received}"),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Queries the endpoint for the number of bytes of the blob it received.
+    ///
+    /// Returns `None` if the endpoint has the complete blob already.
+    fn query_offset(&self, path: &str, len: usize) -> std::io::Result<Option<usize>> {
+        match self.put(path, &format!("bytes */{len}"), &[])? {
+            Status::Complete => Ok(None),
+            Status::Incomplete(received) => Ok(Some(std::cmp::min(received, len))),
+        }
+    }
+
+    /// Sends a single `PUT` request to the endpoint.
+    fn put(&self, path: &str, range: &str, body: &[u8]) -> std::io::Result<Status> {
+        let server_name = rustls::pki_types::ServerName::try_from(self.host.clone())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+        let conn = rustls::ClientConnection::new(self.tls_config.clone(), server_name)
+            .map_err(std::io::Error::other)?;
+
+        let tcp = std::net::TcpStream::connect((self.host.as_str(), self.port))?;
+        tcp.set_read_timeout(Some(IO_TIMEOUT))?;
+        tcp.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        let mut stream = rustls::StreamOwned::new(conn, tcp);
+
+        write! {
+            stream,
+            "PUT {path} HTTP/1.1\r\n\
+             Host: {host}\r\n\
+             Content-Type: application/octet-stream\r\n\
+             Content-Length: {len}\r\n\
+             Content-Range: {range}\r\n\
+             Connection: close\r\n\
+             \r\n",
+            host = self.host,
+            len = body.len(),
+        }?;
+        stream.write_all(body)?;
+        stream.flush()?;
+
+        let mut reader = std::io::BufReader::new(stream);
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let code = line.split(' ').nth(1)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| invalid_response(format!("malformed status line: {line:?}")))?;
+
+        let mut received = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            if !name.eq_ignore_ascii_case("range") {
+                continue;
+            }
+
+            // The `Range` header is in the `bytes=0-<last>` format, so the next
+            // offset to upload from is right after the last byte.
+            received = value.trim().strip_prefix("bytes=0-")
+                .and_then(|last| last.parse::<usize>().ok())
+                .map(|last| last + 1)
+                .ok_or_else(|| invalid_response(format!("malformed range: {value:?}")))?;
+        }
+
+        match code {
+            200 | 201 => Ok(Status::Complete),
+            308 => Ok(Status::Incomplete(received)),
+            _ => Err(invalid_response(format!("unexpected status: {code}"))),
+        }
+    }
+}
+
+/// Status of a blob upload as reported by the endpoint.
+enum Status {
+    /// The endpoint has the complete blob.
+    Complete,
+    /// The endpoint has the given number of bytes of the blob.
+    Incomplete(usize),
+}
+
+/// Verifier of endpoint certificates against pinned public keys.
+#[derive(Debug)]
+struct PinVerifier {
+    /// SHA-256 digests of the allowed DER-encoded SPKIs.
+    pins: Vec<[u8; 32]>,
+    /// Algorithms supported for verification of handshake signatures.
+    algorithms: rustls::crypto::WebPkiSupportedAlgorithms,
+}
+
+impl rustls::client::danger::ServerCertVerifier for PinVerifier {
+
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        use sha2::Digest as _;
+
+        let cert = rustls::server::ParsedCertificate::try_from(end_entity)?;
+        let spki_sha256 = <[u8; 32]>::from(sha2::Sha256::digest(cert.subject_public_key_info()));
+
+        if self.pins.contains(&spki_sha256) {
+            Ok(rustls::client::danger::ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// Determines whether the given upload error should not be retried.
+fn is_fatal(error: &std::io::Error) -> bool {
+    let Some(error) = error.get_ref() else {
+        return false;
+    };
+
+    matches!(error.downcast_ref(), Some(rustls::Error::InvalidCertificate(_)))
+}
+
+/// Creates an error about an unexpected response from the endpoint.
+fn invalid_response(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// An error indicating that the blob upload endpoint is not valid.
+#[derive(Debug)]
+pub enum InvalidEndpointError {
+    /// The URL does not use the `https` scheme.
+    Scheme,
+    /// The URL has no host.
+    Host,
+    /// The URL has an invalid port.
+    Port,
+    /// There are no pinned keys for the endpoint.
+    NoPins,
+    /// The TLS configuration could not be created.
+    Tls(rustls::Error),
+}
+
+impl std::fmt::Display for InvalidEndpointError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use InvalidEndpointError::*;
+
+        match self {
+            Scheme => write!(fmt, "not an https:// URL"),
+            Host => write!(fmt, "missing host"),
+            Port => write!(fmt, "invalid port"),
+            NoPins => write!(fmt, "no pinned keys"),
+            Tls(error) => write!(fmt, "invalid TLS configuration: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidEndpointError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InvalidEndpointError::Tls(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Read as _;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Certificate (self-signed) used by the test server.
+    const CERT: &[u8] = include_bytes!("../../testdata/https_blob_cert.der");
+
+    /// Private key (PKCS #8) of the certificate used by the test server.
+    const KEY: &[u8] = include_bytes!("../../testdata/https_blob_key.der");
+
+    /// SHA-256 digest of the SPKI of the certificate used by the test server.
+    const PIN: [u8; 32] = [
+        0xa5, 0x9e, 0x4c, 0x2d, 0xba, 0xf0, 0xd6, 0x0c,
+        0x1f, 0xdf, 0xa2, 0xeb, 0xcf, 0x43, 0x02, 0x4f,
+        0xfb, 0x8c, 0x67, 0xc7, 0xd0, 0x8b, 0xb5, 0x76,
+        0x75, 0xba, 0xa5, 0x77, 0x7b, 0x32, 0x52, 0xae,
+    ];
+
+    /// State of the test server shared with the test.
+    #[derive(Default)]
+    struct State {
+        /// Bytes of the blob received so far.
+        data: Vec<u8>,
+        /// Number of connections accepted so far.
+        connection_count: usize,
+        /// Number of requests querying the upload status.
+        query_count: usize,
+        /// Offset at which the server drops the connection (once).
+        interrupt_at: Option<usize>,
+    }
+
+    /// Starts a test server on a random port and returns its URL.
+    fn server(state: Arc<Mutex<State>>) -> String {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::pki_types::CertificateDer::from(CERT.to_vec())],
+                rustls::pki_types::PrivateKeyDer::Pkcs8(KEY.to_vec().into()),
+            )
+            .unwrap();
+        let config = Arc::new(config);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap();
+        let port = listener.local_addr()
+            .unwrap()
+            .port();
+
+        std::thread::spawn(move || {
+            for tcp in listener.incoming() {
+                let Ok(tcp) = tcp else {
+                    continue;
+                };
+                state.lock().unwrap().connection_count += 1;
+
+                let conn = rustls::ServerConnection::new(config.clone())
+                    .unwrap();
+                // Errors are expected (e.g. on handshake failures), they are
+                // verified on the client side.
+                let _ = handle(rustls::StreamOwned::new(conn, tcp), &state);
+            }
+        });
+
+        format!("https://localhost:{port}/upload")
+    }
+
+    /// Handles a single request to the test server.
+    fn handle(
+        stream: rustls::StreamOwned<rustls::ServerConnection, std::net::TcpStream>,
+        state: &Mutex<State>,
+    ) -> std::io::Result<()> {
+        let mut reader = std::io::BufReader::new(stream);
+
+        let mut content_length = 0;
+        let mut content_range = String::new();
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(": ") {
+                match name {
+                    "Content-Length" => content_length = value.parse().unwrap(),
+                    "Content-Range" => content_range = value.to_string(),
+                    _ => (),
+                }
+            }
+        }
+
+        let mut state = state.lock().unwrap();
+
+        let (range, total) = content_range.strip_prefix("bytes ").unwrap()
+            .split_once('/').unwrap();
+        let total = total.parse::<usize>().unwrap();
+
+        if range == "*" {
+            state.query_count += 1;
+        } else {
+            let start = range.split_once('-').unwrap().0.parse::<usize>().unwrap();
+            assert_eq!(start, state.data.len());
+
+            let mut body = vec![0; content_length];
+
+            if let Some(interrupt_at) = state.interrupt_at {
+                if interrupt_at < start + content_length {
+                    state.interrupt_at = None;
+
+                    // We keep whatever was received before the interruption.
+                    let partial = &mut body[..interrupt_at - start];
+                    reader.read_exact(partial)?;
+                    state.data.extend_from_slice(partial);
+
+                    return Ok(());
+                }
+            }
+
+            reader.read_exact(&mut body)?;
+            state.data.extend_from_slice(&body);
+        }
+
+        let mut stream = reader.into_inner();
+        if state.data.len() == total {
+            write!(stream, "HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n")?;
+        } else if state.data.is_empty() {
+            write!(stream, "HTTP/1.1 308 Resume Incomplete\r\nContent-Length: 0\r\n\r\n")?;
+        } else {
+            write! {
+                stream,
+                "HTTP/1.1 308 Resume Incomplete\r\nRange: bytes=0-{}\r\nContent-Length: 0\r\n\r\n",
+                state.data.len() - 1,
+            }?;
+        }
+        stream.flush()?;
+        stream.conn.send_close_notify();
+        stream.flush()
+    }
+
+    /// Backend recording parcels sent through it.
+    #[derive(Default)]
+    struct FakeBackend {
+        parcels: Vec<rrg_proto::rrg::Parcel>,
+    }
+
+    impl crate::transport::SinkBackend for FakeBackend {
+
+        fn send(&mut self, parcel: rrg_proto::rrg::Parcel) -> usize {
+            self.parcels.push(parcel);
+            0
+        }
+    }
+
+    fn blob_parcel(data: &[u8]) -> rrg_proto::rrg::Parcel {
+        let blob = crate::blob::Blob::from(data.to_vec());
+        rrg_proto::rrg::Parcel::from(crate::response::Parcel::new(crate::Sink::Blob, blob))
+    }
+
+    #[test]
+    fn uploader_new_invalid_url() {
+        assert!(matches! {
+            Uploader::new("http://localhost/upload", &[PIN]),
+            Err(InvalidEndpointError::Scheme),
+        });
+        assert!(matches! {
+            Uploader::new("https://localhost:foo/upload", &[PIN]),
+            Err(InvalidEndpointError::Port),
+        });
+        assert!(matches! {
+            Uploader::new("https:///upload", &[PIN]),
+            Err(InvalidEndpointError::Host),
+        });
+        assert!(matches! {
+            Uploader::new("https://localhost/upload", &[]),
+            Err(InvalidEndpointError::NoPins),
+        });
+    }
+
+    #[test]
+    fn upload_single_chunk() {
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let uploader = Uploader::new(&url, &[PIN])
+            .unwrap();
+        uploader.upload(b"foobar")
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.data, b"foobar");
+        assert_eq!(state.connection_count, 1);
+    }
+
+    #[test]
+    fn upload_many_chunks() {
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let data = (0..10 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+
+        let mut uploader = Uploader::new(&url, &[PIN])
+            .unwrap();
+        uploader.chunk_size = 1024;
+        uploader.upload(&data)
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.data, data);
+        assert_eq!(state.connection_count, 10);
+    }
+
+    #[test]
+    fn upload_resume_after_interrupt() {
+        let state = Arc::new(Mutex::new(State {
+            interrupt_at: Some(3 * 1024 + 512),
+            ..State::default()
+        }));
+        let url = server(state.clone());
+
+        let data = (0..10 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+
+        let mut uploader = Uploader::new(&url, &[PIN])
+            .unwrap();
+        uploader.chunk_size = 1024;
+        uploader.upload(&data)
+            .unwrap();
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.data, data);
+        assert_eq!(state.query_count, 1);
+    }
+
+    #[test]
+    fn upload_pin_mismatch() {
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let uploader = Uploader::new(&url, &[[0xf0; 32]])
+            .unwrap();
+        let error = uploader.upload(b"foobar")
+            .unwrap_err();
+        assert!(is_fatal(&error));
+
+        let state = state.lock().unwrap();
+        assert!(state.data.is_empty());
+        // Pin mismatches must not be retried.
+        assert_eq!(state.connection_count, 1);
+    }
+
+    #[test]
+    fn backend_upload() {
+        use crate::transport::SinkBackend as _;
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let uploader = Uploader::new(&url, &[PIN])
+            .unwrap();
+        let mut backend = Backend::new(uploader, 4, FakeBackend::default());
+
+        assert_eq!(backend.send(blob_parcel(b"foobar")), 6);
+        assert!(backend.fallback.parcels.is_empty());
+        assert_eq!(state.lock().unwrap().data, b"foobar");
+    }
+
+    #[test]
+    fn backend_below_threshold() {
+        use crate::transport::SinkBackend as _;
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let uploader = Uploader::new(&url, &[PIN])
+            .unwrap();
+        let mut backend = Backend::new(uploader, 1024, FakeBackend::default());
+
+        backend.send(blob_parcel(b"foobar"));
+        assert_eq!(backend.fallback.parcels.len(), 1);
+        assert_eq!(state.lock().unwrap().connection_count, 0);
+    }
+
+    #[test]
+    fn backend_not_blob() {
+        use crate::transport::SinkBackend as _;
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let uploader = Uploader::new(&url, &[PIN])
+            .unwrap();
+        let mut backend = Backend::new(uploader, 0, FakeBackend::default());
+
+        let parcel = crate::response::Parcel::new(crate::Sink::Startup, ());
+        backend.send(rrg_proto::rrg::Parcel::from(parcel));
+        assert_eq!(backend.fallback.parcels.len(), 1);
+        assert_eq!(state.lock().unwrap().connection_count, 0);
+    }
+
+    #[test]
+    fn backend_fallback_on_pin_mismatch() {
+        use crate::transport::SinkBackend as _;
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let url = server(state.clone());
+
+        let uploader = Uploader::new(&url, &[[0xf0; 32]])
+            .unwrap();
+        let mut backend = Backend::new(uploader, 4, FakeBackend::default());
+
+        backend.send(blob_parcel(b"foobar"));
+        assert_eq!(backend.fallback.parcels.len(), 1);
+        assert!(state.lock().unwrap().data.is_empty());
+    }
+}
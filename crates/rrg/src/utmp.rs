@@ -0,0 +1,614 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Parsing of the `utmp` login record files [1].
+//!
+//! The files (the current logins, the log of logins and logouts and the log of
+//! failed login attempts) are parsed directly rather than through the libc API
+//! so that the files can be parsed regardless of the system they come from.
+//!
+//! glibc uses one of two record layouts depending on the architecture that the
+//! file was written on. Unless specified explicitly, the layout of the system
+//! we run on is assumed and the size of the file is used only to recognize
+//! files that fit just the other layout.
+//!
+//! [1]: https://man7.org/linux/man-pages/man5/utmp.5.html
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Layout of the `utmp` structure used by glibc.
+///
+/// The structure consists of the same fields everywhere but on architectures
+/// that are not meant to be compatible with their 32-bit counterparts, the
+/// session identifier and the time fields are 64 bits wide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Layout with 32-bit time fields (384 bytes per record).
+    Time32,
+    /// Layout with 64-bit time fields (400 bytes per record).
+    Time64,
+}
+
+impl Layout {
+
+    /// Returns the layout of the `utmpx` structure of the system we run on.
+    pub fn native() -> Layout {
+        match std::mem::size_of::<libc::utmpx>() {
+            400 => Layout::Time64,
+            _ => Layout::Time32,
+        }
+    }
+
+    /// Picks the layout of a file with the given size.
+    ///
+    /// The native layout is picked unless the size is a multiple of the other
+    /// layout's record size and not of the native one. Note that files with a
+    /// truncated record at the end fit neither, in which case the native layout
+    /// is picked as well.
+    pub fn detect(len: u64) -> Layout {
+        let fits = |layout: Layout| len.is_multiple_of(layout.record_len() as u64);
+
+        let native = Layout::native();
+        let other = match native {
+            Layout::Time32 => Layout::Time64,
+            Layout::Time64 => Layout::Time32,
+        };
+
+        if !fits(native) && fits(other) {
+            other
+        } else {
+            native
+        }
+    }
+
+    /// Returns the size of a single record.
+    pub fn record_len(self) -> usize {
+        match self {
+            Layout::Time32 => 384,
+            Layout::Time64 => 400,
+        }
+    }
+
+    /// Returns the offset of the `ut_tv` field.
+    fn tv_offset(self) -> usize {
+        match self {
+            Layout::Time32 => 340,
+            Layout::Time64 => 344,
+        }
+    }
+
+    /// Returns the offset of the `ut_addr_v6` field.
+    fn addr_offset(self) -> usize {
+        match self {
+            Layout::Time32 => 348,
+            Layout::Time64 => 360,
+        }
+    }
+}
+
+/// Offset of the `ut_type` field.
+const TYPE_OFFSET: usize = 0;
+
+/// Offset of the `ut_pid` field.
+const PID_OFFSET: usize = 4;
+
+/// Offset and length of the `ut_line` field.
+const LINE_RANGE: std::ops::Range<usize> = 8..40;
+
+/// Offset and length of the `ut_user` field.
+const USER_RANGE: std::ops::Range<usize> = 44..76;
+
+/// Offset and length of the `ut_host` field.
+const HOST_RANGE: std::ops::Range<usize> = 76..332;
+
+/// Type of a `utmp` record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordType {
+    /// Change in the system run-level.
+    RunLvl,
+    /// Time of the system boot.
+    BootTime,
+    /// Time after the system clock change.
+    NewTime,
+    /// Time before the system clock change.
+    OldTime,
+    /// Process spawned by init.
+    InitProcess,
+    /// Session leader process for user login.
+    LoginProcess,
+    /// Normal process (user session).
+    UserProcess,
+    /// Terminated process (user logout).
+    DeadProcess,
+    /// Accounting record.
+    Accounting,
+    /// Type that we do not know about.
+    Unknown,
+}
+
+impl RecordType {
+
+    /// Maps the raw value of the `ut_type` field to the record type.
+    fn from_raw(raw: i16) -> RecordType {
+        match raw {
+            1 => RecordType::RunLvl,
+            2 => RecordType::BootTime,
+            3 => RecordType::NewTime,
+            4 => RecordType::OldTime,
+            5 => RecordType::InitProcess,
+            6 => RecordType::LoginProcess,
+            7 => RecordType::UserProcess,
+            8 => RecordType::DeadProcess,
+            9 => RecordType::Accounting,
+            _ => RecordType::Unknown,
+        }
+    }
+}
+
+/// A single `utmp` record.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Type of the record.
+    pub r#type: RecordType,
+    /// Raw value of the record type.
+    pub raw_type: i16,
+    /// Name of the user.
+    pub user: OsString,
+    /// Name of the terminal device.
+    pub tty: OsString,
+    /// Name of the remote host.
+    pub host: OsString,
+    /// IP address of the remote host.
+    pub ip_addr: Option<std::net::IpAddr>,
+    /// Identifier of the process.
+    pub pid: i32,
+    /// Time at which the record was written.
+    pub time: std::time::SystemTime,
+}
+
+/// Opens the `utmp` file at the given path for reading its records.
+///
+/// If no layout is given, the native one is used unless the size of the file
+/// fits only the other one (see [`Layout::detect`]).
+pub fn open<P>(
+    path: P,
+    layout: Option<Layout>,
+) -> std::io::Result<Records<std::io::BufReader<std::fs::File>>>
+where
+    P: AsRef<Path>,
+{
+    let file = std::fs::File::open(path)?;
+
+    let layout = match layout {
+        Some(layout) => layout,
+        None => Layout::detect(file.metadata()?.len()),
+    };
+
+    Ok(Records::new(std::io::BufReader::new(file), layout))
+}
+
+/// Iterator over records of a `utmp` file.
+///
+/// Empty and corrupted records are skipped. A record cut short at the end of
+/// the file (e.g. because the file was being written to while it was copied)
+/// is ignored.
+pub struct Records<R> {
+    /// Reader of the file.
+    reader: R,
+    /// Layout of the records.
+    layout: Layout,
+    /// Buffer for the current record.
+    buf: Vec<u8>,
+}
+
+impl<R: std::io::Read> Records<R> {
+
+    /// Creates an iterator over records of the given reader.
+    pub fn new(reader: R, layout: Layout) -> Records<R> {
+        Records {
+            reader,
+            layout,
+            buf: vec![0; layout.record_len()],
+        }
+    }
+
+    /// Reads the next record into the buffer.
+    ///
+    /// Returns `false` if there are no more (complete) records.
+    fn read_record(&mut self) -> std::io::Result<bool> {
+        let mut len = 0;
+        while len < self.buf.len() {
+            match self.reader.read(&mut self.buf[len..]) {
+                Ok(0) => break,
+                Ok(count) => len += count,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(error) => return Err(error),
+            }
+        }
+
+        if len == 0 {
+            return Ok(false);
+        }
+        if len < self.buf.len() {
+            log::warn!("ignoring truncated utmp record ({len} bytes)");
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: std::io::Read> Iterator for Records<R> {
+
+    type Item = std::io::Result<Record>;
+
+    fn next(&mut self) -> Option<std::io::Result<Record>> {
+        loop {
+            match self.read_record() {
+                Ok(true) => (),
+                Ok(false) => return None,
+                Err(error) => return Some(Err(error)),
+            }
+
+            // Empty records (`EMPTY` type) are left behind by `utmp` writers in
+            // place of records they removed. Records with invalid time are
+            // skipped as well.
+            if let Some(record) = parse_record(&self.buf, self.layout) {
+                return Some(Ok(record));
+            }
+        }
+    }
+}
+
+/// Parses a single record in the given layout.
+///
+/// Values are stored in the native byte order of the system that wrote the
+/// file. `None` is returned for empty records and records with invalid time.
+fn parse_record(buf: &[u8], layout: Layout) -> Option<Record> {
+    let i16_at = |offset: usize| i16::from_ne_bytes([buf[offset], buf[offset + 1]]);
+    let i32_at = |offset: usize| i32::from_ne_bytes([
+        buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3],
+    ]);
+    let i64_at = |offset: usize| i64::from_ne_bytes([
+        buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3],
+        buf[offset + 4], buf[offset + 5], buf[offset + 6], buf[offset + 7],
+    ]);
+
+    let raw_type = i16_at(TYPE_OFFSET);
+    if raw_type == 0 {
+        return None;
+    }
+
+    let tv_offset = layout.tv_offset();
+    let (secs, usecs) = match layout {
+        // The 32-bit seconds are interpreted as unsigned so that the records
+        // written after 2038 are not reported as ones from the 1900s.
+        Layout::Time32 => (i64::from(i32_at(tv_offset) as u32), i64::from(i32_at(tv_offset + 4))),
+        Layout::Time64 => (i64_at(tv_offset), i64_at(tv_offset + 8)),
+    };
+
+    let since_epoch = std::time::Duration::from_secs(secs.unsigned_abs())
+        + std::time::Duration::from_micros(usecs.clamp(0, 999_999) as u64);
+    let time = if secs < 0 {
+        std::time::UNIX_EPOCH.checked_sub(since_epoch)
+    } else {
+        std::time::UNIX_EPOCH.checked_add(since_epoch)
+    };
+    // Corrupted records can have time that is not representable on the system,
+    // there is nothing meaningful we could report for them.
+    let Some(time) = time else {
+        log::warn!("ignoring utmp record with invalid time ({secs} seconds)");
+        return None;
+    };
+
+    let addr_offset = layout.addr_offset();
+    let addr = <[u8; 16]>::try_from(&buf[addr_offset..addr_offset + 16])
+        .expect("invalid address length");
+
+    Some(Record {
+        r#type: RecordType::from_raw(raw_type),
+        raw_type,
+        user: parse_string(&buf[USER_RANGE]),
+        tty: parse_string(&buf[LINE_RANGE]),
+        host: parse_string(&buf[HOST_RANGE]),
+        ip_addr: parse_ip_addr(addr),
+        pid: i32_at(PID_OFFSET),
+        time,
+    })
+}
+
+/// Parses a string field padded with null bytes.
+///
+/// Fields are null-terminated only if they are shorter than the space reserved
+/// for them.
+fn parse_string(field: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let len = field.iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(field.len());
+
+    std::ffi::OsStr::from_bytes(&field[..len]).to_os_string()
+}
+
+/// Parses the `ut_addr_v6` field.
+///
+/// The address is stored in the network byte order. IPv4 addresses occupy only
+/// the first 4 bytes of the field.
+fn parse_ip_addr(addr: [u8; 16]) -> Option<std::net::IpAddr> {
+    if addr == [0; 16] {
+        return None;
+    }
+
+    if addr[4..] == [0; 12] {
+        let octets = <[u8; 4]>::try_from(&addr[..4])
+            .expect("invalid IPv4 address length");
+        Some(std::net::IpAddr::from(octets))
+    } else {
+        Some(std::net::IpAddr::from(addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Fixtures are written in the little-endian byte order. The `time32` one
+    // was written by `utmpdump --reverse` (util-linux 2.38.1) on x86_64 and the
+    // `time64` one holds the same records in the layout used on aarch64. Both
+    // have 100 bytes of a truncated record appended.
+    const WTMP_TIME32: &[u8] = include_bytes!("../testdata/wtmp_time32");
+    const WTMP_TIME64: &[u8] = include_bytes!("../testdata/wtmp_time64");
+
+    fn time(secs: u64, usecs: u64) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH
+            + std::time::Duration::from_secs(secs)
+            + std::time::Duration::from_micros(usecs)
+    }
+
+    fn parse(data: &[u8], layout: Layout) -> Vec<Record> {
+        Records::new(data, layout)
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap()
+    }
+
+    fn assert_fixture_records(records: &[Record]) {
+        // The fixture has 6 complete records and a truncated one at the end.
+        assert_eq!(records.len(), 6);
+
+        assert_eq!(records[0], Record {
+            r#type: RecordType::BootTime,
+            raw_type: 2,
+            user: OsString::from("reboot"),
+            tty: OsString::from("~"),
+            host: OsString::from("6.8.0-45-generic"),
+            ip_addr: None,
+            pid: 0,
+            time: time(1718000000, 0),
+        });
+        assert_eq!(records[1].r#type, RecordType::RunLvl);
+        assert_eq!(records[1].pid, 53);
+        assert_eq!(records[1].time, time(1718000005, 123456));
+        assert_eq!(records[2].r#type, RecordType::LoginProcess);
+        assert_eq!(records[2].user, "LOGIN");
+        assert_eq!(records[2].tty, "tty1");
+        assert_eq!(records[3], Record {
+            r#type: RecordType::UserProcess,
+            raw_type: 7,
+            user: OsString::from("alice"),
+            tty: OsString::from("pts/0"),
+            host: OsString::from("192.0.2.10"),
+            ip_addr: Some(std::net::IpAddr::from([192, 0, 2, 10])),
+            pid: 2301,
+            time: time(1718003600, 500000),
+        });
+        assert_eq!(records[4].user, "bob");
+        assert_eq!(records[4].host, "host.example.com");
+        assert_eq!(records[4].ip_addr, Some("2001:db8::1".parse().unwrap()));
+        assert_eq!(records[5], Record {
+            r#type: RecordType::DeadProcess,
+            raw_type: 8,
+            user: OsString::new(),
+            tty: OsString::from("pts/0"),
+            host: OsString::new(),
+            ip_addr: None,
+            pid: 2301,
+            time: time(1718010800, 0),
+        });
+    }
+
+    /// Returns the fixture without the truncated record at its end.
+    fn complete(fixture: &[u8], layout: Layout) -> &[u8] {
+        &fixture[..fixture.len() / layout.record_len() * layout.record_len()]
+    }
+
+    #[test]
+    #[cfg_attr(target_endian = "big", ignore)]
+    fn records_time32() {
+        assert_fixture_records(&parse(WTMP_TIME32, Layout::Time32));
+    }
+
+    #[test]
+    #[cfg_attr(target_endian = "big", ignore)]
+    fn records_time64() {
+        assert_fixture_records(&parse(WTMP_TIME64, Layout::Time64));
+    }
+
+    #[test]
+    fn records_empty() {
+        assert!(parse(b"", Layout::Time32).is_empty());
+    }
+
+    #[test]
+    fn records_only_truncated() {
+        assert!(parse(&[0xFF; 100], Layout::Time64).is_empty());
+    }
+
+    #[test]
+    fn records_skip_empty() {
+        let mut data = vec![0; Layout::Time32.record_len()];
+        data.extend_from_slice(&WTMP_TIME32[..Layout::Time32.record_len()]);
+
+        let records = parse(&data, Layout::Time32);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].r#type, RecordType::BootTime);
+    }
+
+    /// Returns the fixture in the native layout.
+    fn native_fixture() -> &'static [u8] {
+        match Layout::native() {
+            Layout::Time32 => WTMP_TIME32,
+            Layout::Time64 => WTMP_TIME64,
+        }
+    }
+
+    #[test]
+    fn detect_time32() {
+        let len = complete(WTMP_TIME32, Layout::Time32).len() as u64;
+        assert_eq!(Layout::detect(len), Layout::Time32);
+    }
+
+    #[test]
+    fn detect_time64() {
+        let len = complete(WTMP_TIME64, Layout::Time64).len() as u64;
+        assert_eq!(Layout::detect(len), Layout::Time64);
+    }
+
+    #[test]
+    fn detect_empty() {
+        assert_eq!(Layout::detect(0), Layout::native());
+    }
+
+    #[test]
+    fn detect_ambiguous() {
+        // 25 records in the 32-bit layout and 24 in the 64-bit one.
+        assert_eq!(Layout::detect(9600), Layout::native());
+    }
+
+    #[test]
+    fn detect_truncated() {
+        assert_eq!(Layout::detect(native_fixture().len() as u64), Layout::native());
+    }
+
+    #[test]
+    #[cfg_attr(target_endian = "big", ignore)]
+    fn open_detect() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        for (fixture, layout) in [
+            (WTMP_TIME32, Layout::Time32),
+            (WTMP_TIME64, Layout::Time64),
+        ] {
+            let path = tempdir.path().join("wtmp");
+            std::fs::write(&path, complete(fixture, layout))
+                .unwrap();
+
+            let records = open(&path, None)
+                .unwrap()
+                .collect::<std::io::Result<Vec<_>>>()
+                .unwrap();
+            assert_fixture_records(&records);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_endian = "big", ignore)]
+    fn open_detect_truncated() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+        std::fs::write(tempfile.path(), native_fixture())
+            .unwrap();
+
+        let records = open(tempfile.path(), None)
+            .unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_fixture_records(&records);
+    }
+
+    #[test]
+    #[cfg_attr(target_endian = "big", ignore)]
+    fn open_explicit_truncated() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+        std::fs::write(tempfile.path(), WTMP_TIME64)
+            .unwrap();
+
+        let records = open(tempfile.path(), Some(Layout::Time64))
+            .unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_fixture_records(&records);
+    }
+
+    #[test]
+    fn parse_record_time32_after_2038() {
+        let mut buf = vec![0; Layout::Time32.record_len()];
+        buf[TYPE_OFFSET..TYPE_OFFSET + 2].copy_from_slice(&7i16.to_ne_bytes());
+
+        let tv_offset = Layout::Time32.tv_offset();
+        buf[tv_offset..tv_offset + 4].copy_from_slice(&0x8000_0000u32.to_ne_bytes());
+
+        let record = parse_record(&buf, Layout::Time32)
+            .unwrap();
+        assert_eq!(record.time, time(0x8000_0000, 0));
+    }
+
+    /// Creates a user process record in the 64-bit layout with the given time.
+    fn time64_record(secs: i64, usecs: i64) -> Vec<u8> {
+        let mut buf = vec![0; Layout::Time64.record_len()];
+        buf[TYPE_OFFSET..TYPE_OFFSET + 2].copy_from_slice(&7i16.to_ne_bytes());
+
+        let tv_offset = Layout::Time64.tv_offset();
+        buf[tv_offset..tv_offset + 8].copy_from_slice(&secs.to_ne_bytes());
+        buf[tv_offset + 8..tv_offset + 16].copy_from_slice(&usecs.to_ne_bytes());
+
+        buf
+    }
+
+    #[test]
+    fn parse_record_time64_max() {
+        let record = parse_record(&time64_record(i64::MAX, 999_999), Layout::Time64)
+            .unwrap();
+
+        let since_epoch = std::time::Duration::new(i64::MAX as u64, 999_999_000);
+        assert_eq!(Some(record.time), std::time::UNIX_EPOCH.checked_add(since_epoch));
+    }
+
+    #[test]
+    fn parse_record_time64_min() {
+        assert_eq!(parse_record(&time64_record(i64::MIN, 1), Layout::Time64), None);
+    }
+
+    #[test]
+    fn records_skip_invalid_time() {
+        let mut data = time64_record(i64::MIN, 1);
+        data.extend_from_slice(&WTMP_TIME64[..Layout::Time64.record_len()]);
+
+        let records = parse(&data, Layout::Time64);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].r#type, RecordType::BootTime);
+    }
+
+    #[test]
+    fn parse_record_unknown_type() {
+        let mut buf = vec![0; Layout::Time64.record_len()];
+        buf[TYPE_OFFSET..TYPE_OFFSET + 2].copy_from_slice(&42i16.to_ne_bytes());
+
+        let record = parse_record(&buf, Layout::Time64)
+            .unwrap();
+        assert_eq!(record.r#type, RecordType::Unknown);
+        assert_eq!(record.raw_type, 42);
+    }
+
+    #[test]
+    fn parse_string_full_length() {
+        assert_eq!(parse_string(b"abcd"), "abcd");
+        assert_eq!(parse_string(b"ab\0d"), "ab");
+    }
+}
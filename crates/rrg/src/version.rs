@@ -0,0 +1,136 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Information about the build of the agent executable.
+//!
+//! All the information is embedded into the executable by the build script.
+
+/// Information about the build of the agent executable.
+pub struct Build {
+    /// Hash of the Git commit the agent was built from (if known).
+    pub commit: Option<&'static str>,
+    /// Version of the Rust compiler the agent was built with.
+    pub rustc_version: &'static str,
+    /// Target triple the agent was built for.
+    pub target: &'static str,
+    /// Cargo features the agent was built with.
+    pub features: Vec<&'static str>,
+    /// SHA-256 fingerprint of the descriptors of the compiled-in protos.
+    pub proto_fingerprint: [u8; 32],
+}
+
+impl Build {
+
+    /// Returns information about the build of the running agent.
+    pub fn current() -> Build {
+        let mut features = env!("RRG_FEATURES")
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .collect::<Vec<_>>();
+        // Cargo does not guarantee any specific order of features.
+        features.sort_unstable();
+
+        Build {
+            commit: option_env!("RRG_GIT_COMMIT"),
+            rustc_version: env!("RRG_RUSTC_VERSION"),
+            target: env!("RRG_TARGET"),
+            features,
+            proto_fingerprint: rrg_proto::FINGERPRINT,
+        }
+    }
+}
+
+/// Returns the time at which the agent executable was built.
+pub fn build_time() -> std::time::SystemTime {
+    let secs = env!("RRG_BUILD_TIME").parse::<u64>()
+        // The variable is set by our build script, so it is always valid
+        // (unless the build is misconfigured).
+        .unwrap_or(0);
+
+    std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs)
+}
+
+impl From<Build> for rrg_proto::startup::Build {
+
+    fn from(build: Build) -> rrg_proto::startup::Build {
+        let mut proto = rrg_proto::startup::Build::new();
+        if let Some(commit) = build.commit {
+            proto.set_commit(String::from(commit));
+        }
+        proto.set_rustc_version(String::from(build.rustc_version));
+        proto.set_target(String::from(build.target));
+        proto.set_features(build.features.into_iter().map(String::from).collect());
+        proto.set_proto_fingerprint(build.proto_fingerprint.to_vec());
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn build_commit_hex() {
+        // The commit is not available when building from source archives, so
+        // we can only verify its format if it is there.
+        if let Some(commit) = Build::current().commit {
+            assert_eq!(commit.len(), 40);
+            assert!(commit.chars().all(|char| char.is_ascii_hexdigit()));
+        }
+    }
+
+    #[test]
+    fn build_rustc_version_not_empty() {
+        let build = Build::current();
+        assert!(build.rustc_version.starts_with("rustc "));
+    }
+
+    #[test]
+    fn build_target_not_empty() {
+        let build = Build::current();
+        assert!(!build.target.is_empty());
+        assert!(build.target.contains(std::env::consts::ARCH));
+    }
+
+    #[test]
+    fn build_features_match_cfg() {
+        let build = Build::current();
+
+        assert_eq! {
+            build.features.contains(&"action-get_system_metadata"),
+            cfg!(feature = "action-get_system_metadata")
+        };
+        assert_eq! {
+            build.features.contains(&"action-scan_files_yara"),
+            cfg!(feature = "action-scan_files_yara")
+        };
+        assert_eq! {
+            build.features.contains(&"action-query_osquery"),
+            cfg!(feature = "action-query_osquery")
+        };
+        assert_eq! {
+            build.features.contains(&"action-query_wmi"),
+            cfg!(feature = "action-query_wmi")
+        };
+        assert_eq! {
+            build.features.contains(&"test-setfattr"),
+            cfg!(feature = "test-setfattr")
+        };
+    }
+
+    #[test]
+    fn build_proto_fingerprint_not_empty() {
+        let build = Build::current();
+        assert_ne!(build.proto_fingerprint, [0; 32]);
+    }
+
+    #[test]
+    fn build_time_in_past() {
+        assert!(build_time() > std::time::UNIX_EPOCH);
+        assert!(build_time() <= std::time::SystemTime::now());
+    }
+}
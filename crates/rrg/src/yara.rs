@@ -0,0 +1,246 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for scanning with [YARA][1] rules shared by multiple actions.
+//!
+//! [1]: https://virustotal.github.io/yara-x/
+
+/// Maximum number of bytes of the matched data included in a single match.
+pub const MAX_MATCH_DATA_LEN: usize = 256;
+
+/// Maximum number of matches of a single pattern of a rule.
+pub const MAX_MATCHES_PER_PATTERN: usize = 1024;
+
+/// Parses rules given either as source code or in the serialized form.
+///
+/// Compilation errors are reported as invalid argument errors that include the
+/// compiler message (pointing at the offending part of the source).
+pub fn parse_rules(
+    source: &str,
+    compiled: Option<Vec<u8>>,
+) -> Result<yara_x::Rules, crate::request::ParseArgsError> {
+    use crate::request::ParseArgsError;
+
+    match compiled {
+        Some(compiled) => yara_x::Rules::deserialize(compiled)
+            .map_err(|error| ParseArgsError::invalid_field("rules_compiled", error)),
+        None => yara_x::compile(source)
+            .map_err(|error| ParseArgsError::invalid_field("rules_source", error)),
+    }
+}
+
+/// Creates a scanner for the given rules with the common configuration.
+pub fn scanner(
+    rules: &yara_x::Rules,
+    timeout: Option<std::time::Duration>,
+) -> yara_x::Scanner<'_> {
+    let mut scanner = yara_x::Scanner::new(rules);
+    scanner.max_matches_per_pattern(MAX_MATCHES_PER_PATTERN);
+    if let Some(timeout) = timeout {
+        scanner.set_timeout(timeout);
+    }
+
+    scanner
+}
+
+/// Rule that matched scanned data.
+#[derive(Debug)]
+pub struct RuleMatch {
+    /// Identifier of the rule.
+    pub name: String,
+    /// Namespace of the rule.
+    pub namespace: String,
+    /// Tags of the rule.
+    pub tags: Vec<String>,
+    /// Metadata of the rule.
+    pub metadata: Vec<(String, MetaValue)>,
+    /// Matches of the patterns of the rule.
+    pub string_matches: Vec<StringMatch>,
+    /// Whether some of the matches were dropped.
+    pub string_matches_truncated: bool,
+}
+
+/// Owned value of a rule metadata entry.
+#[derive(Debug, PartialEq)]
+pub enum MetaValue {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+/// Single match of a pattern of a rule.
+#[derive(Debug, PartialEq, Eq)]
+pub struct StringMatch {
+    /// Identifier of the pattern.
+    pub identifier: String,
+    /// Offset within the scanned data at which the match starts.
+    pub offset: u64,
+    /// Length of the match.
+    pub len: u64,
+    /// Matched data (capped at [`MAX_MATCH_DATA_LEN`] bytes).
+    pub data: Vec<u8>,
+}
+
+impl From<yara_x::Rule<'_, '_>> for RuleMatch {
+
+    fn from(rule: yara_x::Rule<'_, '_>) -> RuleMatch {
+        let mut string_matches = Vec::new();
+        let mut string_matches_truncated = false;
+
+        for pattern in rule.patterns() {
+            let matches = pattern.matches();
+            // The scanner stops collecting matches of a pattern at the limit,
+            // so reaching it means that there might have been more of them.
+            if matches.len() >= MAX_MATCHES_PER_PATTERN {
+                string_matches_truncated = true;
+            }
+
+            string_matches.extend(matches.map(|mat| {
+                let data = mat.data();
+                StringMatch {
+                    identifier: String::from(pattern.identifier()),
+                    offset: mat.range().start as u64,
+                    len: mat.range().len() as u64,
+                    data: data[..data.len().min(MAX_MATCH_DATA_LEN)].to_vec(),
+                }
+            }));
+        }
+
+        RuleMatch {
+            name: String::from(rule.identifier()),
+            namespace: String::from(rule.namespace()),
+            tags: rule.tags()
+                .map(|tag| String::from(tag.identifier()))
+                .collect(),
+            metadata: rule.metadata()
+                .map(|(key, value)| (String::from(key), MetaValue::from(value)))
+                .collect(),
+            string_matches,
+            string_matches_truncated,
+        }
+    }
+}
+
+impl From<yara_x::MetaValue<'_>> for MetaValue {
+
+    fn from(value: yara_x::MetaValue<'_>) -> MetaValue {
+        use yara_x::MetaValue::*;
+        match value {
+            Integer(value) => MetaValue::Integer(value),
+            Float(value) => MetaValue::Float(value),
+            Bool(value) => MetaValue::Bool(value),
+            String(value) => MetaValue::String(value.to_string()),
+            Bytes(value) => MetaValue::Bytes(value.to_vec()),
+        }
+    }
+}
+
+impl From<RuleMatch> for rrg_proto::yara::RuleMatch {
+
+    fn from(rule_match: RuleMatch) -> rrg_proto::yara::RuleMatch {
+        let mut proto = rrg_proto::yara::RuleMatch::new();
+        proto.set_rule_name(rule_match.name);
+        proto.set_rule_namespace(rule_match.namespace);
+        proto.set_tags(rule_match.tags);
+        proto.set_string_matches_truncated(rule_match.string_matches_truncated);
+
+        for (key, value) in rule_match.metadata {
+            let mut metadata_proto = rrg_proto::yara::Metadata::new();
+            metadata_proto.set_key(key);
+            match value {
+                MetaValue::Integer(value) => metadata_proto.set_integer(value),
+                MetaValue::Float(value) => metadata_proto.set_float(value),
+                MetaValue::Bool(value) => metadata_proto.set_bool(value),
+                MetaValue::String(value) => metadata_proto.set_string(value),
+                MetaValue::Bytes(value) => metadata_proto.set_bytes(value),
+            }
+            proto.mut_metadata().push(metadata_proto);
+        }
+
+        for string_match in rule_match.string_matches {
+            let mut string_match_proto = rrg_proto::yara::StringMatch::new();
+            string_match_proto.set_identifier(string_match.identifier);
+            string_match_proto.set_offset(string_match.offset);
+            string_match_proto.set_length(string_match.len);
+            string_match_proto.set_data(string_match.data);
+            proto.mut_string_matches().push(string_match_proto);
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn rule_matches(source: &str, data: &[u8]) -> Vec<RuleMatch> {
+        let rules = yara_x::compile(source)
+            .unwrap();
+
+        let mut scanner = scanner(&rules, None);
+        scanner.scan(data)
+            .unwrap()
+            .matching_rules()
+            .map(RuleMatch::from)
+            .collect()
+    }
+
+    #[test]
+    fn parse_rules_source_invalid() {
+        let error = parse_rules("rule foo { condition: bar }", None)
+            .unwrap_err();
+
+        assert!(error.to_string().contains("unknown identifier `bar`"));
+    }
+
+    #[test]
+    fn parse_rules_compiled_invalid() {
+        assert!(parse_rules("", Some(b"foo".to_vec())).is_err());
+    }
+
+    #[test]
+    fn rule_match_metadata() {
+        let rule_matches = rule_matches(r#"
+rule foo : bar baz {
+    meta:
+        string = "quux"
+        integer = 42
+        bool = true
+    condition:
+        true
+}
+"#, b"");
+
+        assert_eq!(rule_matches.len(), 1);
+        assert_eq!(rule_matches[0].name, "foo");
+        assert_eq!(rule_matches[0].tags, vec!["bar", "baz"]);
+        assert_eq!(rule_matches[0].metadata, vec! {
+            (String::from("string"), MetaValue::String(String::from("quux"))),
+            (String::from("integer"), MetaValue::Integer(42)),
+            (String::from("bool"), MetaValue::Bool(true)),
+        });
+    }
+
+    #[test]
+    fn rule_match_data_truncated() {
+        let rule_matches = rule_matches(r#"
+rule foo {
+    strings:
+        $a = /A{1000}/
+    condition:
+        $a
+}
+"#, &[b'A'; 1024]);
+
+        let string_match = &rule_matches[0].string_matches[0];
+        assert_eq!(string_match.offset, 0);
+        assert!(string_match.len >= 1000);
+        assert_eq!(string_match.data.len(), MAX_MATCH_DATA_LEN);
+    }
+}
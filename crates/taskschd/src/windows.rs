@@ -0,0 +1,758 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+mod bstr;
+mod com;
+mod error;
+mod ffi;
+
+use std::ffi::OsString;
+
+use self::com::Object;
+use self::error::Error;
+
+/// Connects to the Task Scheduler of the local computer.
+///
+/// # Examples
+///
+/// ```no_run
+/// let scheduler = taskschd::scheduler()
+///     .unwrap();
+///
+/// for task in scheduler.tasks().unwrap() {
+///     let task = task.unwrap();
+///     println!("{}", task.path.to_string_lossy());
+/// }
+/// ```
+pub fn scheduler() -> std::io::Result<Scheduler> {
+    let com = self::com::init()?;
+
+    Ok(Scheduler {
+        com,
+    })
+}
+
+/// Handle to the Task Scheduler.
+///
+/// This struct is created by the [`scheduler`] function.
+pub struct Scheduler {
+    /// COM library initialization guard ensuring validity of the scheduler.
+    com: self::com::InitGuard,
+}
+
+impl Scheduler {
+
+    /// Returns an iterator over all [tasks][1] registered in the scheduler.
+    ///
+    /// Tasks from all the folders are yielded, including the hidden ones.
+    ///
+    /// [1]: Task
+    pub fn tasks(&self) -> std::io::Result<Tasks<'_>> {
+        let mut service_ptr = std::ptr::null_mut::<ffi::ITaskService>();
+
+        // SAFETY: Simple FFI call as described in the documentation [1]. This
+        // is based on the official example [2].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-cocreateinstance
+        // [2]: https://learn.microsoft.com/en-us/windows/win32/taskschd/displaying-task-names-and-state--c---
+        let status = unsafe {
+            windows_sys::Win32::System::Com::CoCreateInstance(
+                &ffi::CLSID_TaskScheduler,
+                std::ptr::null_mut(),
+                windows_sys::Win32::System::Com::CLSCTX_INPROC_SERVER,
+                &ffi::IID_ITaskService,
+                (&mut service_ptr as *mut *mut ffi::ITaskService).cast(),
+            )
+        };
+
+        if status != windows_sys::Win32::Foundation::S_OK {
+            return Err(Error::from_raw_hresult(status).into());
+        }
+
+        // SAFETY: We verified that the call succeeded, so `service_ptr` now
+        // points to a valid task service instance.
+        let service = unsafe {
+            Object::from_raw_ptr(&self.com, service_ptr)
+        };
+
+        // SAFETY: Simple FFI call as described in the documentation [1]. Empty
+        // variants mean that we connect to the local computer as the current
+        // user.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskservice-connect
+        let status = unsafe {
+            (service.vtable().Connect)(
+                service.as_raw(),
+                empty_variant(),
+                empty_variant(),
+                empty_variant(),
+                empty_variant(),
+            )
+        };
+
+        if status != windows_sys::Win32::Foundation::S_OK {
+            return Err(Error::from_raw_hresult(status).into());
+        }
+
+        // SAFETY: We call the method [1] of a valid service object to get the
+        // root folder. The getter is called according to the `get_object`
+        // requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskservice-getfolder
+        let root = unsafe {
+            get_object(&self.com, |root| {
+                (service.vtable().GetFolder)(
+                    service.as_raw(),
+                    self::bstr::BString::new("\\").as_raw_bstr(),
+                    root,
+                )
+            })
+        }?;
+
+        Ok(Tasks {
+            _service: service,
+            folders: vec![root],
+            current: None,
+        })
+    }
+}
+
+/// Iterator over [tasks][1] registered in the scheduler.
+///
+/// This struct is created by the [`Scheduler::tasks`] method.
+///
+/// [1]: Task
+pub struct Tasks<'com> {
+    /// Task service that the tasks are obtained from.
+    ///
+    /// We do not use it directly but it is kept alive as long as the iterator
+    /// to ensure that the connection to the scheduler is not closed.
+    _service: Object<'com, ffi::ITaskService>,
+    /// Folders that are still to be visited.
+    folders: Vec<Object<'com, ffi::ITaskFolder>>,
+    /// Tasks of the folder currently being visited.
+    current: Option<FolderTasks<'com>>,
+}
+
+/// Tasks of a single task folder.
+struct FolderTasks<'com> {
+    /// Collection of the tasks in the folder.
+    tasks: Object<'com, ffi::IRegisteredTaskCollection>,
+    /// Number of tasks in the collection.
+    count: std::ffi::c_long,
+    /// Index of the last yielded task (collections are indexed from 1).
+    index: std::ffi::c_long,
+}
+
+impl<'com> Tasks<'com> {
+
+    /// Starts visiting the given folder.
+    ///
+    /// Subfolders of the given folder are scheduled to be visited later.
+    fn visit(&mut self, folder: Object<'com, ffi::ITaskFolder>) -> std::io::Result<()> {
+        // SAFETY: We call the method [1] of a valid folder object without any
+        // flags (as required). The getter is called according to the
+        // `get_object` requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-getfolders
+        let subfolders = unsafe {
+            get_object(folder.com(), |subfolders| {
+                (folder.vtable().GetFolders)(folder.as_raw(), 0, subfolders)
+            })
+        }?;
+
+        // SAFETY: We call the method [1] of a valid folder collection object.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfoldercollection-get_count
+        let subfolder_count = unsafe {
+            get_value(|count| {
+                (subfolders.vtable().get_Count)(subfolders.as_raw(), count)
+            })
+        }?;
+
+        // We push the subfolders in the reverse order, so that they are popped
+        // from the stack in the order the scheduler returned them.
+        for index in (1..=subfolder_count).rev() {
+            // SAFETY: We call the method [1] of a valid folder collection with
+            // an index within the collection bounds. The getter is called
+            // according to the `get_object` requirements.
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfoldercollection-get_item
+            let subfolder = unsafe {
+                get_object(folder.com(), |subfolder| {
+                    (subfolders.vtable().get_Item)(
+                        subfolders.as_raw(),
+                        long_variant(index),
+                        subfolder,
+                    )
+                })
+            }?;
+
+            self.folders.push(subfolder);
+        }
+
+        // SAFETY: We call the method [1] of a valid folder object asking also
+        // for hidden tasks. The getter is called according to the `get_object`
+        // requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-gettasks
+        let tasks = unsafe {
+            get_object(folder.com(), |tasks| {
+                (folder.vtable().GetTasks)(folder.as_raw(), ffi::TASK_ENUM_HIDDEN, tasks)
+            })
+        }?;
+
+        // SAFETY: We call the method [1] of a valid task collection object.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtaskcollection-get_count
+        let count = unsafe {
+            get_value(|count| (tasks.vtable().get_Count)(tasks.as_raw(), count))
+        }?;
+
+        self.current = Some(FolderTasks {
+            tasks,
+            count,
+            index: 0,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'com> Iterator for Tasks<'com> {
+
+    type Item = std::io::Result<Task>;
+
+    fn next(&mut self) -> Option<std::io::Result<Task>> {
+        loop {
+            if let Some(current) = &mut self.current {
+                if current.index < current.count {
+                    current.index += 1;
+
+                    let tasks = &current.tasks;
+
+                    // SAFETY: We call the method [1] of a valid task collection
+                    // with an index within the collection bounds. The getter
+                    // is called according to the `get_object` requirements.
+                    //
+                    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtaskcollection-get_item
+                    let task = unsafe {
+                        get_object(tasks.com(), |task| {
+                            (tasks.vtable().get_Item)(
+                                tasks.as_raw(),
+                                long_variant(current.index),
+                                task,
+                            )
+                        })
+                    };
+
+                    return Some(task.and_then(|task| Task::from_object(&task)));
+                }
+
+                self.current = None;
+            }
+
+            let folder = self.folders.pop()?;
+            if let Err(error) = self.visit(folder) {
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// Information about a task registered in the scheduler.
+#[derive(Debug, Clone)]
+pub struct Task {
+    /// Name of the task.
+    pub name: OsString,
+    /// Path of the task (including the folder it belongs to).
+    pub path: OsString,
+    /// Whether the task is enabled.
+    pub enabled: bool,
+    /// Author of the task (if specified).
+    pub author: Option<OsString>,
+    /// Identifier of the user that the task runs as (if specified).
+    pub user: Option<OsString>,
+    /// Identifier of the group that the task runs as (if specified).
+    pub group: Option<OsString>,
+    /// Triggers that start the task.
+    pub triggers: Vec<Trigger>,
+    /// Actions that the task performs.
+    pub actions: Vec<Action>,
+}
+
+impl Task {
+
+    /// Collects information about a task from the given COM object.
+    fn from_object(task: &Object<ffi::IRegisteredTask>) -> std::io::Result<Task> {
+        let com = task.com();
+
+        // SAFETY: We call methods of a valid registered task object [1]. The
+        // getters are called according to the `get_*` helper requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregisteredtask
+        let name = unsafe {
+            get_string(|name| (task.vtable().get_Name)(task.as_raw(), name))
+        }?.unwrap_or_default();
+        let path = unsafe {
+            get_string(|path| (task.vtable().get_Path)(task.as_raw(), path))
+        }?.unwrap_or_default();
+        let enabled = unsafe {
+            get_bool(|enabled| (task.vtable().get_Enabled)(task.as_raw(), enabled))
+        }?;
+        let definition = unsafe {
+            get_object(com, |definition| {
+                (task.vtable().get_Definition)(task.as_raw(), definition)
+            })
+        }?;
+
+        // SAFETY: We call methods of a valid task definition object [1] and of
+        // its registration info [2] and principal [3] objects. The getters are
+        // called according to the `get_*` helper requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskdefinition
+        // [2]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregistrationinfo
+        // [3]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iprincipal
+        let info = unsafe {
+            get_object(com, |info| {
+                (definition.vtable().get_RegistrationInfo)(definition.as_raw(), info)
+            })
+        }?;
+        let author = unsafe {
+            get_string(|author| (info.vtable().get_Author)(info.as_raw(), author))
+        }?;
+        let principal = unsafe {
+            get_object(com, |principal| {
+                (definition.vtable().get_Principal)(definition.as_raw(), principal)
+            })
+        }?;
+        let user = unsafe {
+            get_string(|user| (principal.vtable().get_UserId)(principal.as_raw(), user))
+        }?;
+        let group = unsafe {
+            get_string(|group| (principal.vtable().get_GroupId)(principal.as_raw(), group))
+        }?;
+
+        // SAFETY: We call methods of a valid task definition object [1] and of
+        // the trigger collection [2] with indices within collection bounds. The
+        // getters are called according to the `get_*` helper requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskdefinition
+        // [2]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itriggercollection
+        let trigger_objects = unsafe {
+            get_object(com, |triggers| {
+                (definition.vtable().get_Triggers)(definition.as_raw(), triggers)
+            })
+        }?;
+        let trigger_count = unsafe {
+            get_value(|count| {
+                (trigger_objects.vtable().get_Count)(trigger_objects.as_raw(), count)
+            })
+        }?;
+
+        let mut triggers = Vec::new();
+        for index in 1..=trigger_count {
+            let trigger = unsafe {
+                get_object(com, |trigger| {
+                    (trigger_objects.vtable().get_Item)(trigger_objects.as_raw(), index, trigger)
+                })
+            }?;
+
+            triggers.push(Trigger::from_object(&trigger)?);
+        }
+
+        // SAFETY: We call methods of a valid task definition object [1] and of
+        // the action collection [2] with indices within collection bounds. The
+        // getters are called according to the `get_*` helper requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskdefinition
+        // [2]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iactioncollection
+        let action_objects = unsafe {
+            get_object(com, |actions| {
+                (definition.vtable().get_Actions)(definition.as_raw(), actions)
+            })
+        }?;
+        let action_count = unsafe {
+            get_value(|count| {
+                (action_objects.vtable().get_Count)(action_objects.as_raw(), count)
+            })
+        }?;
+
+        let mut actions = Vec::new();
+        for index in 1..=action_count {
+            let action = unsafe {
+                get_object(com, |action| {
+                    (action_objects.vtable().get_Item)(action_objects.as_raw(), index, action)
+                })
+            }?;
+
+            actions.push(Action::from_object(&action)?);
+        }
+
+        Ok(Task {
+            name,
+            path,
+            enabled,
+            author,
+            user,
+            group,
+            triggers,
+            actions,
+        })
+    }
+}
+
+/// Information about a trigger that starts a task.
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    /// Kind of the trigger.
+    pub kind: TriggerKind,
+    /// Time at which the trigger is activated (in the ISO 8601 format).
+    pub start: Option<OsString>,
+    /// Time at which the trigger is deactivated (in the ISO 8601 format).
+    pub end: Option<OsString>,
+    /// Whether the trigger is enabled.
+    pub enabled: bool,
+}
+
+impl Trigger {
+
+    /// Collects information about a trigger from the given COM object.
+    fn from_object(trigger: &Object<ffi::ITrigger>) -> std::io::Result<Trigger> {
+        // SAFETY: We call methods of a valid trigger object [1]. The getters
+        // are called according to the `get_*` helper requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itrigger
+        let kind = unsafe {
+            get_value(|kind| (trigger.vtable().get_Type)(trigger.as_raw(), kind))
+        }?;
+        let start = unsafe {
+            get_string(|start| (trigger.vtable().get_StartBoundary)(trigger.as_raw(), start))
+        }?;
+        let end = unsafe {
+            get_string(|end| (trigger.vtable().get_EndBoundary)(trigger.as_raw(), end))
+        }?;
+        let enabled = unsafe {
+            get_bool(|enabled| (trigger.vtable().get_Enabled)(trigger.as_raw(), enabled))
+        }?;
+
+        Ok(Trigger {
+            kind: TriggerKind::from_raw(kind),
+            start,
+            end,
+            enabled,
+        })
+    }
+}
+
+/// Kinds of triggers that can start a task.
+///
+/// See the [documentation][1] for more details about each kind.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/ne-taskschd-task_trigger_type2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerKind {
+    /// Task is started when a particular event occurs.
+    Event,
+    /// Task is started at a specific time.
+    Time,
+    /// Task is started on a daily schedule.
+    Daily,
+    /// Task is started on a weekly schedule.
+    Weekly,
+    /// Task is started on a monthly schedule.
+    Monthly,
+    /// Task is started on a monthly day-of-week schedule.
+    MonthlyDayOfWeek,
+    /// Task is started when the computer goes into an idle state.
+    Idle,
+    /// Task is started when the task is registered.
+    Registration,
+    /// Task is started when the computer boots.
+    Boot,
+    /// Task is started when a user logs on.
+    Logon,
+    /// Task is started when the state of a session changes.
+    SessionStateChange,
+    /// Task is started by a custom trigger.
+    Custom,
+    /// Trigger of an unknown kind (with the given raw value).
+    Unknown(i32),
+}
+
+impl TriggerKind {
+
+    /// Converts a raw trigger type to the corresponding trigger kind.
+    fn from_raw(raw: ffi::TASK_TRIGGER_TYPE2) -> TriggerKind {
+        match raw {
+            ffi::TASK_TRIGGER_EVENT => TriggerKind::Event,
+            ffi::TASK_TRIGGER_TIME => TriggerKind::Time,
+            ffi::TASK_TRIGGER_DAILY => TriggerKind::Daily,
+            ffi::TASK_TRIGGER_WEEKLY => TriggerKind::Weekly,
+            ffi::TASK_TRIGGER_MONTHLY => TriggerKind::Monthly,
+            ffi::TASK_TRIGGER_MONTHLYDOW => TriggerKind::MonthlyDayOfWeek,
+            ffi::TASK_TRIGGER_IDLE => TriggerKind::Idle,
+            ffi::TASK_TRIGGER_REGISTRATION => TriggerKind::Registration,
+            ffi::TASK_TRIGGER_BOOT => TriggerKind::Boot,
+            ffi::TASK_TRIGGER_LOGON => TriggerKind::Logon,
+            ffi::TASK_TRIGGER_SESSION_STATE_CHANGE => TriggerKind::SessionStateChange,
+            ffi::TASK_TRIGGER_CUSTOM_TRIGGER_01 => TriggerKind::Custom,
+            _ => TriggerKind::Unknown(raw),
+        }
+    }
+}
+
+/// Information about an action that a task performs.
+///
+/// See the [documentation][1] for more details about each kind of action.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/ne-taskschd-task_action_type
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Action that executes a command-line operation.
+    Exec {
+        /// Path to the executable.
+        path: OsString,
+        /// Arguments passed to the executable (if any).
+        arguments: Option<OsString>,
+        /// Directory in which the executable is run (if specified).
+        working_dir: Option<OsString>,
+    },
+    /// Action that fires a COM handler.
+    ComHandler {
+        /// Identifier of the COM class of the handler.
+        class_id: OsString,
+        /// Additional data passed to the handler (if any).
+        data: Option<OsString>,
+    },
+    /// Action that sends an email (deprecated).
+    SendEmail,
+    /// Action that shows a message box (deprecated).
+    ShowMessage,
+    /// Action of an unknown kind (with the given raw value).
+    Unknown(i32),
+}
+
+impl Action {
+
+    /// Collects information about an action from the given COM object.
+    fn from_object(action: &Object<ffi::IAction>) -> std::io::Result<Action> {
+        // SAFETY: We call the method [1] of a valid action object. The getter
+        // is called according to the `get_value` requirements.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iaction-get_type
+        let kind = unsafe {
+            get_value(|kind| (action.vtable().get_Type)(action.as_raw(), kind))
+        }?;
+
+        match kind {
+            ffi::TASK_ACTION_EXEC => {
+                let action = action.cast::<ffi::IExecAction>(&ffi::IID_IExecAction)?;
+
+                // SAFETY: We call methods of a valid exec action object [1]. The
+                // getters are called according to the `get_string` requirements.
+                //
+                // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iexecaction
+                let path = unsafe {
+                    get_string(|path| (action.vtable().get_Path)(action.as_raw(), path))
+                }?.unwrap_or_default();
+                let arguments = unsafe {
+                    get_string(|args| (action.vtable().get_Arguments)(action.as_raw(), args))
+                }?;
+                let working_dir = unsafe {
+                    get_string(|dir| (action.vtable().get_WorkingDirectory)(action.as_raw(), dir))
+                }?;
+
+                Ok(Action::Exec {
+                    path,
+                    arguments,
+                    working_dir,
+                })
+            }
+            ffi::TASK_ACTION_COM_HANDLER => {
+                let action = action.cast::<ffi::IComHandlerAction>(&ffi::IID_IComHandlerAction)?;
+
+                // SAFETY: We call methods of a valid COM handler action object
+                // [1]. The getters are called according to the `get_string`
+                // requirements.
+                //
+                // [1]: https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-icomhandleraction
+                let class_id = unsafe {
+                    get_string(|id| (action.vtable().get_ClassId)(action.as_raw(), id))
+                }?.unwrap_or_default();
+                let data = unsafe {
+                    get_string(|data| (action.vtable().get_Data)(action.as_raw(), data))
+                }?;
+
+                Ok(Action::ComHandler {
+                    class_id,
+                    data,
+                })
+            }
+            ffi::TASK_ACTION_SEND_EMAIL => Ok(Action::SendEmail),
+            ffi::TASK_ACTION_SHOW_MESSAGE => Ok(Action::ShowMessage),
+            _ => Ok(Action::Unknown(kind)),
+        }
+    }
+}
+
+/// Calls a COM getter method returning an object.
+///
+/// # Safety
+///
+/// `getter` must call a method that (upon success) stores a pointer to a new
+/// reference to an instance of `I` valid for the `com` lifetime in the given
+/// location.
+unsafe fn get_object<'com, I, F>(
+    com: &'com self::com::InitGuard,
+    getter: F,
+) -> std::io::Result<Object<'com, I>>
+where
+    I: self::com::Interface,
+    F: FnOnce(*mut *mut I) -> windows_sys::core::HRESULT,
+{
+    let mut ptr = std::ptr::null_mut();
+
+    let status = getter(&mut ptr);
+    if status != windows_sys::Win32::Foundation::S_OK {
+        return Err(Error::from_raw_hresult(status).into());
+    }
+
+    if ptr.is_null() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, {
+            "null object returned"
+        }));
+    }
+
+    Ok(Object::from_raw_ptr(com, ptr))
+}
+
+/// Calls a COM getter method returning a string.
+///
+/// Empty strings are returned as [`None`].
+///
+/// # Safety
+///
+/// `getter` must call a method that (upon success) stores a newly allocated
+/// `BSTR` (or null) in the given location.
+unsafe fn get_string<F>(getter: F) -> std::io::Result<Option<OsString>>
+where
+    F: FnOnce(*mut windows_sys::core::BSTR) -> windows_sys::core::HRESULT,
+{
+    let mut raw = std::ptr::null();
+
+    let status = getter(&mut raw);
+    if status != windows_sys::Win32::Foundation::S_OK {
+        return Err(Error::from_raw_hresult(status).into());
+    }
+
+    // Null `BSTR` is semantically equivalent to an empty string.
+    if raw.is_null() {
+        return Ok(None);
+    }
+
+    let string = self::bstr::BString::from_raw_bstr(raw).to_os_string();
+    if string.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(string))
+}
+
+/// Calls a COM getter method returning a boolean.
+///
+/// # Safety
+///
+/// `getter` must call a method that (upon success) stores a `VARIANT_BOOL` in
+/// the given location.
+unsafe fn get_bool<F>(getter: F) -> std::io::Result<bool>
+where
+    F: FnOnce(*mut windows_sys::Win32::Foundation::VARIANT_BOOL) -> windows_sys::core::HRESULT,
+{
+    // `VARIANT_FALSE` is 0 and `VARIANT_TRUE` is -1 but we are lenient here and
+    // treat any non-zero value as true.
+    Ok(get_value(getter)? != windows_sys::Win32::Foundation::VARIANT_FALSE)
+}
+
+/// Calls a COM getter method returning a plain value.
+///
+/// # Safety
+///
+/// `getter` must call a method that (upon success) stores a valid instance of
+/// `T` in the given location.
+unsafe fn get_value<T, F>(getter: F) -> std::io::Result<T>
+where
+    T: Default,
+    F: FnOnce(*mut T) -> windows_sys::core::HRESULT,
+{
+    let mut value = T::default();
+
+    let status = getter(&mut value);
+    if status != windows_sys::Win32::Foundation::S_OK {
+        return Err(Error::from_raw_hresult(status).into());
+    }
+
+    Ok(value)
+}
+
+/// Creates an empty (`VT_EMPTY`) variant.
+fn empty_variant() -> windows_sys::Win32::System::Variant::VARIANT {
+    // SAFETY: `VARIANT` is a plain C structure and all-zero bit pattern is its
+    // valid value (of type `VT_EMPTY`).
+    unsafe {
+        std::mem::zeroed()
+    }
+}
+
+/// Creates a variant holding the given integer (`VT_I4`).
+fn long_variant(value: std::ffi::c_long) -> windows_sys::Win32::System::Variant::VARIANT {
+    let mut variant = empty_variant();
+    variant.Anonymous.Anonymous.vt = windows_sys::Win32::System::Variant::VT_I4;
+    variant.Anonymous.Anonymous.Anonymous.lVal = value;
+
+    variant
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn tasks_not_empty() {
+        let scheduler = scheduler().unwrap();
+
+        let tasks = scheduler.tasks().unwrap()
+            .collect::<std::io::Result<Vec<_>>>().unwrap();
+
+        // Every Windows installation comes with a lot of built-in tasks.
+        assert!(!tasks.is_empty());
+    }
+
+    #[test]
+    fn tasks_paths() {
+        let scheduler = scheduler().unwrap();
+
+        for task in scheduler.tasks().unwrap() {
+            let task = task.unwrap();
+
+            let path = task.path.to_string_lossy();
+            assert!(path.starts_with('\\'));
+            assert!(path.ends_with(&*task.name.to_string_lossy()));
+        }
+    }
+
+    #[test]
+    fn tasks_builtin_exec_action() {
+        let scheduler = scheduler().unwrap();
+
+        let has_exec_action = scheduler.tasks().unwrap()
+            .map(Result::unwrap)
+            .flat_map(|task| task.actions)
+            .any(|action| matches!(action, Action::Exec { .. }));
+
+        assert!(has_exec_action);
+    }
+}
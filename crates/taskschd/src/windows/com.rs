@@ -0,0 +1,272 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// RAII guarantee that COM subsystem is initialized.
+///
+/// When this structure is dropped, COM subsystem is de-initialized for the
+/// current thread.
+pub struct InitGuard(());
+
+/// Initializes the COM subsystem.
+pub fn init() -> std::io::Result<InitGuard> {
+    // SAFETY: Simple FFI call as described in the documentation [1]. We
+    // verify the return code below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-coinitializeex
+    let status = unsafe {
+        windows_sys::Win32::System::Com::CoInitializeEx(
+            std::ptr::null(),
+            windows_sys::Win32::System::Com::COINIT_MULTITHREADED as u32,
+        )
+    };
+
+    // As described in the documentation, we return the guard even if the
+    // call returned `S_FALSE` or `RPC_E_CHANGED_MODE` (it means that the
+    // library was initialized before) as _every_ initialization call should
+    // be balanced be corresponding uninitialization.
+    match status {
+        windows_sys::Win32::Foundation::S_OK => {}
+        windows_sys::Win32::Foundation::S_FALSE => {}
+        windows_sys::Win32::Foundation::RPC_E_CHANGED_MODE => (),
+        _ => return Err(super::Error::from_raw_hresult(status).into()),
+    }
+
+    // We create the guard before calling security initialization, so that the
+    // destructor is invoked and even in case the following call fails.
+    let com = InitGuard(());
+
+    // SAFETY: Simple FFI call as described in the documentation [1] using
+    // default parameters. We verify the return code below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/combaseapi/nf-combaseapi-coinitializesecurity
+    let status = unsafe {
+        windows_sys::Win32::System::Com::CoInitializeSecurity(
+            std::ptr::null_mut(),
+            -1,
+            std::ptr::null(),
+            std::ptr::null(),
+            windows_sys::Win32::System::Com::RPC_C_AUTHN_LEVEL_DEFAULT,
+            windows_sys::Win32::System::Com::RPC_C_IMP_LEVEL_IMPERSONATE,
+            std::ptr::null(),
+            windows_sys::Win32::System::Com::EOAC_NONE as u32,
+            std::ptr::null(),
+        )
+    };
+
+    // `RPC_E_TOO_LATE` is also fine, it just means that the security was
+    // initialized before (e.g. by some other library using COM).
+    match status {
+        windows_sys::Win32::Foundation::S_OK => (),
+        windows_sys::Win32::Foundation::RPC_E_TOO_LATE => (),
+        _ => return Err(super::Error::from_raw_hresult(status).into()),
+    }
+
+    Ok(com)
+}
+
+impl Drop for InitGuard {
+
+    fn drop(&mut self) {
+        // SAFETY: Simple FFI call as described in the documentation [1]. If we
+        // reach this line, it means that the initialization did not error and
+        // should be balanced by uninitialization.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/desktop/api/combaseapi/nf-combaseapi-couninitialize
+        unsafe {
+            windows_sys::Win32::System::Com::CoUninitialize();
+        }
+    }
+}
+
+/// COM interface that can be put into an [`Object`] wrapper.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` structs consisting of a single pointer to
+/// a `#[repr(C)]` vtable of type [`Interface::Vtbl`]. The vtable must start with
+/// [`IUnknown`][1] methods.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nn-unknwn-iunknown
+pub unsafe trait Interface {
+    /// Type of the vtable of the interface.
+    type Vtbl;
+}
+
+/// RAII wrapper for COM objects.
+///
+/// When this structure is dropped, the underlying COM object is automatically
+/// released.
+pub struct Object<'com, I: Interface> {
+    ptr: *mut I,
+    com: &'com InitGuard,
+}
+
+impl<'com, I: Interface> Object<'com, I> {
+
+    /// Creates a new wrapper instance from the given raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid non-null pointer to an instance of the interface
+    /// valid for the given COM initialization guard lifetime. The wrapper
+    /// takes over the reference that the pointer holds.
+    pub unsafe fn from_raw_ptr(com: &'com InitGuard, ptr: *mut I) -> Object<'com, I> {
+        Object {
+            ptr,
+            com,
+        }
+    }
+
+    /// Returns the COM initialization guard the object is tied to.
+    pub fn com(&self) -> &'com InitGuard {
+        self.com
+    }
+
+    /// Returns raw pointer to the underlying COM object.
+    ///
+    /// The pointer is type-erased so that it can be passed as the `this`
+    /// argument to methods of the interface and all of its base interfaces.
+    pub fn as_raw(&self) -> *mut std::ffi::c_void {
+        self.ptr.cast()
+    }
+
+    /// Returns reference to the vtable of the underlying COM object.
+    pub fn vtable(&self) -> &I::Vtbl {
+        // SAFETY: The pointers are guaranteed to be valid and the cast is sound
+        // because of the layout requirements of the `Interface` trait.
+        unsafe {
+            &**self.ptr.cast::<*const I::Vtbl>()
+        }
+    }
+
+    /// Casts the object to a different interface that it implements.
+    ///
+    /// `iid` must be the interface identifier corresponding to `J`.
+    pub fn cast<J: Interface>(
+        &self,
+        iid: &windows_sys::core::GUID,
+    ) -> std::io::Result<Object<'com, J>> {
+        let mut result = std::ptr::null_mut();
+
+        // SAFETY: We call the [`QueryInterface`][1] method of a valid object.
+        // If the call succeeds, `result` holds a new reference that is owned
+        // by us (and released when the wrapper is dropped).
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nf-unknwn-iunknown-queryinterface(refiid_void)
+        let status = unsafe {
+            (self.unknown_vtable().QueryInterface)(self.as_raw(), iid, &mut result)
+        };
+
+        if status != windows_sys::Win32::Foundation::S_OK {
+            return Err(super::Error::from_raw_hresult(status).into());
+        }
+
+        // SAFETY: The call succeeded, so `result` points to a valid object with
+        // the interface we requested.
+        Ok(unsafe {
+            Object::from_raw_ptr(self.com, result.cast::<J>())
+        })
+    }
+
+    /// Returns reference to the `IUnknown` part of the object vtable.
+    fn unknown_vtable(&self) -> &super::ffi::IUnknownVtbl {
+        // SAFETY: The pointers are guaranteed to be valid and every vtable of
+        // a COM interface starts with `IUnknown` methods.
+        unsafe {
+            &**self.ptr.cast::<*const super::ffi::IUnknownVtbl>()
+        }
+    }
+}
+
+impl<'com, I: Interface> Drop for Object<'com, I> {
+
+    fn drop(&mut self) {
+        // SAFETY: We call the [`Release`][1] method of valid COM object. It
+        // returns a new reference count, so we are not interested in it.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nf-unknwn-iunknown-release
+        let _ = unsafe {
+            (self.unknown_vtable().Release)(self.as_raw())
+        };
+    }
+}
+
+// SAFETY: All the interfaces defined in the `ffi` module satisfy the layout
+// requirements of the trait.
+unsafe impl Interface for super::ffi::ITaskService {
+    type Vtbl = super::ffi::ITaskServiceVtbl;
+}
+
+unsafe impl Interface for super::ffi::ITaskFolder {
+    type Vtbl = super::ffi::ITaskFolderVtbl;
+}
+
+unsafe impl Interface for super::ffi::ITaskFolderCollection {
+    type Vtbl = super::ffi::ITaskFolderCollectionVtbl;
+}
+
+unsafe impl Interface for super::ffi::IRegisteredTaskCollection {
+    type Vtbl = super::ffi::IRegisteredTaskCollectionVtbl;
+}
+
+unsafe impl Interface for super::ffi::IRegisteredTask {
+    type Vtbl = super::ffi::IRegisteredTaskVtbl;
+}
+
+unsafe impl Interface for super::ffi::ITaskDefinition {
+    type Vtbl = super::ffi::ITaskDefinitionVtbl;
+}
+
+unsafe impl Interface for super::ffi::IRegistrationInfo {
+    type Vtbl = super::ffi::IRegistrationInfoVtbl;
+}
+
+unsafe impl Interface for super::ffi::IPrincipal {
+    type Vtbl = super::ffi::IPrincipalVtbl;
+}
+
+unsafe impl Interface for super::ffi::ITriggerCollection {
+    type Vtbl = super::ffi::ITriggerCollectionVtbl;
+}
+
+unsafe impl Interface for super::ffi::ITrigger {
+    type Vtbl = super::ffi::ITriggerVtbl;
+}
+
+unsafe impl Interface for super::ffi::IActionCollection {
+    type Vtbl = super::ffi::IActionCollectionVtbl;
+}
+
+unsafe impl Interface for super::ffi::IAction {
+    type Vtbl = super::ffi::IActionVtbl;
+}
+
+unsafe impl Interface for super::ffi::IExecAction {
+    type Vtbl = super::ffi::IExecActionVtbl;
+}
+
+unsafe impl Interface for super::ffi::IComHandlerAction {
+    type Vtbl = super::ffi::IComHandlerActionVtbl;
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn init_ok() {
+        assert!(init().is_ok());
+    }
+
+    #[test]
+    fn init_nested_ok() {
+        let guard_1 = init().unwrap();
+        let guard_2 = init().unwrap();
+
+        drop(guard_1);
+        drop(guard_2);
+    }
+}
@@ -0,0 +1,638 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+// Task Scheduler interfaces are not part of `windows-sys` (similarly to WMI
+// ones), so we have to define them ourselves. All of them are based on the
+// `inc/taskschd.h` header file.
+//
+// Interfaces are laid out as in C: the vtable of a derived interface starts
+// with the vtable of its base interface. Note that vtables below define only
+// methods up to the last one that we need: since we only ever access vtables
+// through pointers that the system gives us, omitting trailing methods is fine.
+//
+// All the `this` parameters are declared as opaque pointers to make it possible
+// to call base interface methods on derived interface objects.
+
+#![allow(non_snake_case, non_camel_case_types, non_upper_case_globals)]
+
+use std::ffi::c_void;
+
+use windows_sys::core::{BSTR, GUID, HRESULT};
+use windows_sys::Win32::Foundation::VARIANT_BOOL;
+use windows_sys::Win32::System::Variant::VARIANT;
+
+/// `CLSID_TaskScheduler` defined in `inc/taskschd.h`.
+pub const CLSID_TaskScheduler: GUID = GUID {
+    data1: 0x0f87369f,
+    data2: 0xa4e5,
+    data3: 0x4cfc,
+    data4: [0xbd, 0x3e, 0x73, 0xe6, 0x15, 0x45, 0x72, 0xdd],
+};
+
+/// `IID_ITaskService` defined in `inc/taskschd.h`.
+pub const IID_ITaskService: GUID = GUID {
+    data1: 0x2faba4c7,
+    data2: 0x4da9,
+    data3: 0x4013,
+    data4: [0x96, 0x97, 0x20, 0xcc, 0x3f, 0xd4, 0x0f, 0x85],
+};
+
+/// `IID_IExecAction` defined in `inc/taskschd.h`.
+pub const IID_IExecAction: GUID = GUID {
+    data1: 0x4c3d624d,
+    data2: 0xfd6b,
+    data3: 0x49a3,
+    data4: [0xb9, 0xb7, 0x09, 0xcb, 0x3c, 0xd3, 0xf0, 0x47],
+};
+
+/// `IID_IComHandlerAction` defined in `inc/taskschd.h`.
+pub const IID_IComHandlerAction: GUID = GUID {
+    data1: 0x6d2fd252,
+    data2: 0x75c5,
+    data3: 0x4f66,
+    data4: [0x90, 0xba, 0x2a, 0x7d, 0x8c, 0xc3, 0x03, 0x9f],
+};
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/ne-taskschd-task_enum_flags
+pub const TASK_ENUM_HIDDEN: i32 = 0x1;
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/ne-taskschd-task_action_type
+pub type TASK_ACTION_TYPE = i32;
+pub const TASK_ACTION_EXEC: TASK_ACTION_TYPE = 0;
+pub const TASK_ACTION_COM_HANDLER: TASK_ACTION_TYPE = 5;
+pub const TASK_ACTION_SEND_EMAIL: TASK_ACTION_TYPE = 6;
+pub const TASK_ACTION_SHOW_MESSAGE: TASK_ACTION_TYPE = 7;
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/ne-taskschd-task_trigger_type2
+pub type TASK_TRIGGER_TYPE2 = i32;
+pub const TASK_TRIGGER_EVENT: TASK_TRIGGER_TYPE2 = 0;
+pub const TASK_TRIGGER_TIME: TASK_TRIGGER_TYPE2 = 1;
+pub const TASK_TRIGGER_DAILY: TASK_TRIGGER_TYPE2 = 2;
+pub const TASK_TRIGGER_WEEKLY: TASK_TRIGGER_TYPE2 = 3;
+pub const TASK_TRIGGER_MONTHLY: TASK_TRIGGER_TYPE2 = 4;
+pub const TASK_TRIGGER_MONTHLYDOW: TASK_TRIGGER_TYPE2 = 5;
+pub const TASK_TRIGGER_IDLE: TASK_TRIGGER_TYPE2 = 6;
+pub const TASK_TRIGGER_REGISTRATION: TASK_TRIGGER_TYPE2 = 7;
+pub const TASK_TRIGGER_BOOT: TASK_TRIGGER_TYPE2 = 8;
+pub const TASK_TRIGGER_LOGON: TASK_TRIGGER_TYPE2 = 9;
+pub const TASK_TRIGGER_SESSION_STATE_CHANGE: TASK_TRIGGER_TYPE2 = 11;
+pub const TASK_TRIGGER_CUSTOM_TRIGGER_01: TASK_TRIGGER_TYPE2 = 12;
+
+// https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nn-unknwn-iunknown
+#[repr(C)]
+pub struct IUnknownVtbl {
+    // https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nf-unknwn-iunknown-queryinterface(refiid_void)
+    pub QueryInterface: unsafe extern "system" fn(
+        this: *mut c_void,
+        riid: *const GUID,
+        ppvObject: *mut *mut c_void,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nf-unknwn-iunknown-addref
+    pub AddRef: unsafe extern "system" fn(
+        this: *mut c_void,
+    ) -> std::ffi::c_ulong,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/unknwn/nf-unknwn-iunknown-release
+    pub Release: unsafe extern "system" fn(
+        this: *mut c_void,
+    ) -> std::ffi::c_ulong,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/oaidl/nn-oaidl-idispatch
+//
+// We never use the automation methods, so their arguments are not typed.
+#[repr(C)]
+pub struct IDispatchVtbl {
+    pub base: IUnknownVtbl,
+    pub GetTypeInfoCount: *const c_void,
+    pub GetTypeInfo: *const c_void,
+    pub GetIDsOfNames: *const c_void,
+    pub Invoke: *const c_void,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskservice
+#[repr(C)]
+pub struct ITaskServiceVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskservice-getfolder
+    pub GetFolder: unsafe extern "system" fn(
+        this: *mut c_void,
+        path: BSTR,
+        ppFolder: *mut *mut ITaskFolder,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskservice-getrunningtasks
+    pub GetRunningTasks: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskservice-newtask
+    pub NewTask: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskservice-connect
+    pub Connect: unsafe extern "system" fn(
+        this: *mut c_void,
+        serverName: VARIANT,
+        user: VARIANT,
+        domain: VARIANT,
+        password: VARIANT,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskservice
+#[repr(C)]
+pub struct ITaskService {
+    pub lpVtbl: *const ITaskServiceVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskfolder
+#[repr(C)]
+pub struct ITaskFolderVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-get_name
+    pub get_Name: unsafe extern "system" fn(
+        this: *mut c_void,
+        pName: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-get_path
+    pub get_Path: unsafe extern "system" fn(
+        this: *mut c_void,
+        pPath: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-getfolder
+    pub GetFolder: unsafe extern "system" fn(
+        this: *mut c_void,
+        path: BSTR,
+        ppFolder: *mut *mut ITaskFolder,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-getfolders
+    pub GetFolders: unsafe extern "system" fn(
+        this: *mut c_void,
+        flags: std::ffi::c_long,
+        ppFolders: *mut *mut ITaskFolderCollection,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-createfolder
+    pub CreateFolder: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-deletefolder
+    pub DeleteFolder: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-gettask
+    pub GetTask: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfolder-gettasks
+    pub GetTasks: unsafe extern "system" fn(
+        this: *mut c_void,
+        flags: std::ffi::c_long,
+        ppTasks: *mut *mut IRegisteredTaskCollection,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskfolder
+#[repr(C)]
+pub struct ITaskFolder {
+    pub lpVtbl: *const ITaskFolderVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskfoldercollection
+#[repr(C)]
+pub struct ITaskFolderCollectionVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfoldercollection-get_count
+    pub get_Count: unsafe extern "system" fn(
+        this: *mut c_void,
+        pCount: *mut std::ffi::c_long,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskfoldercollection-get_item
+    pub get_Item: unsafe extern "system" fn(
+        this: *mut c_void,
+        index: VARIANT,
+        ppFolder: *mut *mut ITaskFolder,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskfoldercollection
+#[repr(C)]
+pub struct ITaskFolderCollection {
+    pub lpVtbl: *const ITaskFolderCollectionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregisteredtaskcollection
+#[repr(C)]
+pub struct IRegisteredTaskCollectionVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtaskcollection-get_count
+    pub get_Count: unsafe extern "system" fn(
+        this: *mut c_void,
+        pCount: *mut std::ffi::c_long,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtaskcollection-get_item
+    pub get_Item: unsafe extern "system" fn(
+        this: *mut c_void,
+        index: VARIANT,
+        ppRegisteredTask: *mut *mut IRegisteredTask,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregisteredtaskcollection
+#[repr(C)]
+pub struct IRegisteredTaskCollection {
+    pub lpVtbl: *const IRegisteredTaskCollectionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregisteredtask
+#[repr(C)]
+pub struct IRegisteredTaskVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_name
+    pub get_Name: unsafe extern "system" fn(
+        this: *mut c_void,
+        pName: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_path
+    pub get_Path: unsafe extern "system" fn(
+        this: *mut c_void,
+        pPath: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_state
+    pub get_State: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_enabled
+    pub get_Enabled: unsafe extern "system" fn(
+        this: *mut c_void,
+        pEnabled: *mut VARIANT_BOOL,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-put_enabled
+    pub put_Enabled: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-run
+    pub Run: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-runex
+    pub RunEx: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-getinstances
+    pub GetInstances: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_lastruntime
+    pub get_LastRunTime: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_lasttaskresult
+    pub get_LastTaskResult: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_numberofmissedruns
+    pub get_NumberOfMissedRuns: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_nextruntime
+    pub get_NextRunTime: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregisteredtask-get_definition
+    pub get_Definition: unsafe extern "system" fn(
+        this: *mut c_void,
+        ppDefinition: *mut *mut ITaskDefinition,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregisteredtask
+#[repr(C)]
+pub struct IRegisteredTask {
+    pub lpVtbl: *const IRegisteredTaskVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskdefinition
+#[repr(C)]
+pub struct ITaskDefinitionVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-get_registrationinfo
+    pub get_RegistrationInfo: unsafe extern "system" fn(
+        this: *mut c_void,
+        ppRegistrationInfo: *mut *mut IRegistrationInfo,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-put_registrationinfo
+    pub put_RegistrationInfo: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-get_triggers
+    pub get_Triggers: unsafe extern "system" fn(
+        this: *mut c_void,
+        ppTriggers: *mut *mut ITriggerCollection,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-put_triggers
+    pub put_Triggers: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-get_settings
+    pub get_Settings: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-put_settings
+    pub put_Settings: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-get_data
+    pub get_Data: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-put_data
+    pub put_Data: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-get_principal
+    pub get_Principal: unsafe extern "system" fn(
+        this: *mut c_void,
+        ppPrincipal: *mut *mut IPrincipal,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-put_principal
+    pub put_Principal: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itaskdefinition-get_actions
+    pub get_Actions: unsafe extern "system" fn(
+        this: *mut c_void,
+        ppActions: *mut *mut IActionCollection,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itaskdefinition
+#[repr(C)]
+pub struct ITaskDefinition {
+    pub lpVtbl: *const ITaskDefinitionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregistrationinfo
+#[repr(C)]
+pub struct IRegistrationInfoVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregistrationinfo-get_description
+    pub get_Description: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregistrationinfo-put_description
+    pub put_Description: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iregistrationinfo-get_author
+    pub get_Author: unsafe extern "system" fn(
+        this: *mut c_void,
+        pAuthor: *mut BSTR,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iregistrationinfo
+#[repr(C)]
+pub struct IRegistrationInfo {
+    pub lpVtbl: *const IRegistrationInfoVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iprincipal
+#[repr(C)]
+pub struct IPrincipalVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-get_id
+    pub get_Id: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-put_id
+    pub put_Id: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-get_displayname
+    pub get_DisplayName: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-put_displayname
+    pub put_DisplayName: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-get_userid
+    pub get_UserId: unsafe extern "system" fn(
+        this: *mut c_void,
+        pUser: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-put_userid
+    pub put_UserId: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-get_logontype
+    pub get_LogonType: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-put_logontype
+    pub put_LogonType: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iprincipal-get_groupid
+    pub get_GroupId: unsafe extern "system" fn(
+        this: *mut c_void,
+        pGroup: *mut BSTR,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iprincipal
+#[repr(C)]
+pub struct IPrincipal {
+    pub lpVtbl: *const IPrincipalVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itriggercollection
+#[repr(C)]
+pub struct ITriggerCollectionVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itriggercollection-get_count
+    pub get_Count: unsafe extern "system" fn(
+        this: *mut c_void,
+        pCount: *mut std::ffi::c_long,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itriggercollection-get_item
+    pub get_Item: unsafe extern "system" fn(
+        this: *mut c_void,
+        index: std::ffi::c_long,
+        ppTrigger: *mut *mut ITrigger,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itriggercollection
+#[repr(C)]
+pub struct ITriggerCollection {
+    pub lpVtbl: *const ITriggerCollectionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itrigger
+#[repr(C)]
+pub struct ITriggerVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_type
+    pub get_Type: unsafe extern "system" fn(
+        this: *mut c_void,
+        pType: *mut TASK_TRIGGER_TYPE2,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_id
+    pub get_Id: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-put_id
+    pub put_Id: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_repetition
+    pub get_Repetition: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-put_repetition
+    pub put_Repetition: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_executiontimelimit
+    pub get_ExecutionTimeLimit: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-put_executiontimelimit
+    pub put_ExecutionTimeLimit: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_startboundary
+    pub get_StartBoundary: unsafe extern "system" fn(
+        this: *mut c_void,
+        pStart: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-put_startboundary
+    pub put_StartBoundary: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_endboundary
+    pub get_EndBoundary: unsafe extern "system" fn(
+        this: *mut c_void,
+        pEnd: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-put_endboundary
+    pub put_EndBoundary: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-itrigger-get_enabled
+    pub get_Enabled: unsafe extern "system" fn(
+        this: *mut c_void,
+        pEnabled: *mut VARIANT_BOOL,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-itrigger
+#[repr(C)]
+pub struct ITrigger {
+    pub lpVtbl: *const ITriggerVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iactioncollection
+#[repr(C)]
+pub struct IActionCollectionVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iactioncollection-get_count
+    pub get_Count: unsafe extern "system" fn(
+        this: *mut c_void,
+        pCount: *mut std::ffi::c_long,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iactioncollection-get_item
+    pub get_Item: unsafe extern "system" fn(
+        this: *mut c_void,
+        index: std::ffi::c_long,
+        ppAction: *mut *mut IAction,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iactioncollection
+#[repr(C)]
+pub struct IActionCollection {
+    pub lpVtbl: *const IActionCollectionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iaction
+#[repr(C)]
+pub struct IActionVtbl {
+    pub base: IDispatchVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iaction-get_id
+    pub get_Id: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iaction-put_id
+    pub put_Id: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iaction-get_type
+    pub get_Type: unsafe extern "system" fn(
+        this: *mut c_void,
+        pType: *mut TASK_ACTION_TYPE,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iaction
+#[repr(C)]
+pub struct IAction {
+    pub lpVtbl: *const IActionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iexecaction
+#[repr(C)]
+pub struct IExecActionVtbl {
+    pub base: IActionVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iexecaction-get_path
+    pub get_Path: unsafe extern "system" fn(
+        this: *mut c_void,
+        pPath: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iexecaction-put_path
+    pub put_Path: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iexecaction-get_arguments
+    pub get_Arguments: unsafe extern "system" fn(
+        this: *mut c_void,
+        pArgument: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iexecaction-put_arguments
+    pub put_Arguments: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-iexecaction-get_workingdirectory
+    pub get_WorkingDirectory: unsafe extern "system" fn(
+        this: *mut c_void,
+        pWorkingDirectory: *mut BSTR,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-iexecaction
+#[repr(C)]
+pub struct IExecAction {
+    pub lpVtbl: *const IExecActionVtbl,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-icomhandleraction
+#[repr(C)]
+pub struct IComHandlerActionVtbl {
+    pub base: IActionVtbl,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-icomhandleraction-get_classid
+    pub get_ClassId: unsafe extern "system" fn(
+        this: *mut c_void,
+        pClsid: *mut BSTR,
+    ) -> HRESULT,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-icomhandleraction-put_classid
+    pub put_ClassId: *const c_void,
+
+    // https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nf-taskschd-icomhandleraction-get_data
+    pub get_Data: unsafe extern "system" fn(
+        this: *mut c_void,
+        pData: *mut BSTR,
+    ) -> HRESULT,
+}
+
+// https://learn.microsoft.com/en-us/windows/win32/api/taskschd/nn-taskschd-icomhandleraction
+#[repr(C)]
+pub struct IComHandlerAction {
+    pub lpVtbl: *const IComHandlerActionVtbl,
+}
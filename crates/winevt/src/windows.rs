@@ -0,0 +1,274 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use windows_sys::Win32::System::EventLog::*;
+
+/// Number of event handles requested from the system at once.
+const BATCH_SIZE: usize = 64;
+
+/// Runs a structured XML query against the local event log.
+///
+/// The query has to be a `<QueryList>` document as described in the [event
+/// query schema][1]. Events are yielded from the oldest to the newest.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/wes/queryschema-schema
+///
+/// # Examples
+///
+/// ```no_run
+/// let query = r#"
+///     <QueryList>
+///       <Query Id="0">
+///         <Select Path="Application">*[System[Level=2]]</Select>
+///       </Query>
+///     </QueryList>
+/// "#;
+///
+/// for event in winevt::query(query).unwrap() {
+///     println!("{}", event.unwrap().render_xml().unwrap());
+/// }
+/// ```
+pub fn query<S>(query: &S) -> std::io::Result<Events>
+where
+    S: AsRef<std::ffi::OsStr> + ?Sized,
+{
+    let query = wide_string(query.as_ref());
+
+    // SAFETY: We pass a null-terminated query string. The path has to be null
+    // for structured XML queries and the null session means the local machine
+    // as described in the documentation [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winevt/nf-winevt-evtquery
+    let handle = unsafe {
+        EvtQuery(
+            0,
+            std::ptr::null(),
+            query.as_ptr(),
+            EvtQueryChannelPath | EvtQueryForwardDirection,
+        )
+    };
+
+    Ok(Events {
+        handle: Handle::new(handle)?,
+        batch: Vec::new(),
+        done: false,
+    })
+}
+
+/// Iterator over events yielded by a [query].
+///
+/// This struct is created by the [`query`] function.
+pub struct Events {
+    /// Handle to result set of the query.
+    handle: Handle,
+    /// Events that have been fetched but not yet yielded (in reverse order).
+    batch: Vec<Handle>,
+    /// Whether there are no more events to fetch.
+    done: bool,
+}
+
+impl Events {
+
+    /// Fetches the next batch of events from the result set.
+    fn fetch(&mut self) -> std::io::Result<()> {
+        use windows_sys::Win32::Foundation::*;
+
+        let mut handles = [0; BATCH_SIZE];
+        let mut count = 0;
+
+        // SAFETY: We pass a buffer for the event handles along with its size.
+        // On success, `count` is set to the number of handles written to it
+        // and we become responsible for closing them [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winevt/nf-winevt-evtnext
+        let status = unsafe {
+            EvtNext(
+                self.handle.0,
+                BATCH_SIZE as u32,
+                handles.as_mut_ptr(),
+                windows_sys::Win32::System::Threading::INFINITE,
+                0,
+                &mut count,
+            )
+        };
+
+        if status == FALSE {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(ERROR_NO_MORE_ITEMS as i32) {
+                self.done = true;
+                return Ok(());
+            }
+
+            return Err(error);
+        }
+
+        self.batch.extend(handles[..count as usize].iter().rev().map(|handle| Handle(*handle)));
+        Ok(())
+    }
+}
+
+impl Iterator for Events {
+
+    type Item = std::io::Result<Event>;
+
+    fn next(&mut self) -> Option<std::io::Result<Event>> {
+        if self.batch.is_empty() && !self.done {
+            if let Err(error) = self.fetch() {
+                // There is no way to know whether we can proceed after errors,
+                // so we do not attempt to do so.
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+
+        self.batch.pop().map(|handle| Ok(Event { handle }))
+    }
+}
+
+/// Individual event from the event log.
+pub struct Event {
+    /// Handle to the event.
+    handle: Handle,
+}
+
+impl Event {
+
+    /// Renders the event into its XML representation.
+    ///
+    /// See the [event schema][1] for details about the format.
+    ///
+    /// [1]: https://learn.microsoft.com/en-us/windows/win32/wes/eventschema-schema
+    pub fn render_xml(&self) -> std::io::Result<String> {
+        use windows_sys::Win32::Foundation::*;
+
+        let mut buf = Vec::<u16>::new();
+
+        loop {
+            let mut buf_used = 0;
+            let mut property_count = 0;
+
+            // SAFETY: We pass a buffer along with its size (in bytes). If it is
+            // not big enough, the call fails with `ERROR_INSUFFICIENT_BUFFER`
+            // and tells us how many bytes are needed [1].
+            //
+            // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winevt/nf-winevt-evtrender
+            let status = unsafe {
+                EvtRender(
+                    0,
+                    self.handle.0,
+                    EvtRenderEventXml,
+                    (buf.len() * std::mem::size_of::<u16>()) as u32,
+                    buf.as_mut_ptr().cast(),
+                    &mut buf_used,
+                    &mut property_count,
+                )
+            };
+
+            if status == FALSE {
+                let error = std::io::Error::last_os_error();
+                if error.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER as i32) {
+                    return Err(error);
+                }
+
+                buf.resize((buf_used as usize).div_ceil(std::mem::size_of::<u16>()), 0);
+                continue;
+            }
+
+            buf.truncate(buf_used as usize / std::mem::size_of::<u16>());
+            break;
+        }
+
+        // The rendered string is null-terminated.
+        if buf.last() == Some(&0) {
+            buf.pop();
+        }
+
+        String::from_utf16(&buf)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// RAII wrapper around event log handles.
+struct Handle(EVT_HANDLE);
+
+impl Handle {
+
+    /// Wraps the given handle, returning the last error if it is null.
+    fn new(handle: EVT_HANDLE) -> std::io::Result<Handle> {
+        if handle == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Handle(handle))
+    }
+}
+
+impl Drop for Handle {
+
+    fn drop(&mut self) {
+        // SAFETY: The handle is valid and owned by us, so we can close it [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winevt/nf-winevt-evtclose
+        unsafe {
+            EvtClose(self.0);
+        }
+    }
+}
+
+/// Converts the given string to a null-terminated wide string.
+fn wide_string(string: &std::ffi::OsStr) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    string.encode_wide().chain(std::iter::once(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn query_application() {
+        let events = query(r#"
+<QueryList>
+  <Query Id="0">
+    <Select Path="Application">*</Select>
+  </Query>
+</QueryList>
+"#).unwrap();
+
+        for event in events.take(BATCH_SIZE * 2) {
+            let xml = event.unwrap().render_xml().unwrap();
+            assert!(xml.starts_with("<Event "));
+            assert!(xml.ends_with("</Event>"));
+        }
+    }
+
+    #[test]
+    fn query_no_events() {
+        let events = query(r#"
+<QueryList>
+  <Query Id="0">
+    <Select Path="Application">*[System[Provider[@Name='rrg-non-existent-provider']]]</Select>
+  </Query>
+</QueryList>
+"#).unwrap();
+
+        assert_eq!(events.count(), 0);
+    }
+
+    #[test]
+    fn query_invalid_channel() {
+        let result = query(r#"
+<QueryList>
+  <Query Id="0">
+    <Select Path="rrg-non-existent-channel">*</Select>
+  </Query>
+</QueryList>
+"#);
+
+        assert!(result.is_err());
+    }
+}
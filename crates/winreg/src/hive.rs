@@ -0,0 +1,1132 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Read-only parser of registry hive files.
+//!
+//! Hive files (e.g. `C:\Windows\System32\config\SOFTWARE`) store the registry
+//! on disk in the "regf" format [1]. Unlike the rest of this crate, this module
+//! does not use any system APIs and so it can be used to inspect hives copied
+//! from other machines or extracted from disk images on any platform.
+//!
+//! Hive files can be "dirty", meaning that some of the changes are recorded
+//! only in the accompanying transaction logs (`.LOG1` and `.LOG2` files). This
+//! parser does not apply these logs, so such hives are read as they are (see
+//! [`Hive::is_dirty`]).
+//!
+//! [1]: https://github.com/libyal/libregf/blob/main/documentation/Windows%20NT%20Registry%20File%20(REGF)%20format.asciidoc
+//!
+//! # Examples
+//!
+//! ```no_run
+//! let hive = winreg::hive::Hive::open("SOFTWARE").unwrap();
+//! let key = hive.root().unwrap().open("Microsoft\\Windows NT\\CurrentVersion").unwrap();
+//!
+//! for value in key.values().unwrap() {
+//!     let value = value.unwrap();
+//!     println!("{}: {:?}", value.name, value.data);
+//! }
+//! ```
+
+/// Length of the base block (the header of the hive file).
+const BASE_BLOCK_LEN: usize = 4096;
+
+/// Length of the base block prefix covered by the checksum.
+const CHECKSUM_LEN: usize = 508;
+
+/// Maximum number of data bytes stored in a single segment of big data.
+const BIG_DATA_SEGMENT_LEN: usize = 16344;
+
+/// Offset value used by the format to denote a missing cell.
+const NULL_OFFSET: u32 = u32::MAX;
+
+/// Key node flag denoting that the key name is stored as an ASCII string.
+const KEY_COMP_NAME: u16 = 0x0020;
+
+/// Key value flag denoting that the value name is stored as an ASCII string.
+const VALUE_COMP_NAME: u16 = 0x0001;
+
+/// Registry hive loaded from a file.
+pub struct Hive {
+    /// Raw content of the hive file (including the base block).
+    data: Vec<u8>,
+    /// Offset of the root key cell (relative to the start of hive bins).
+    root_offset: u32,
+    /// Minor version of the format the hive is stored in.
+    minor_version: u32,
+    /// Whether the hive was not properly flushed to disk.
+    dirty: bool,
+    /// Time at which the hive was last written.
+    last_write_time: std::time::SystemTime,
+}
+
+impl Hive {
+
+    /// Loads a hive from the file at the specified path.
+    ///
+    /// The whole file is read into memory.
+    pub fn open<P>(path: P) -> std::io::Result<Hive>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Hive::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Loads a hive from the given raw content of a hive file.
+    pub fn from_bytes(mut data: Vec<u8>) -> std::io::Result<Hive> {
+        if data.len() < BASE_BLOCK_LEN {
+            return Err(ParseError::truncated(0).into());
+        }
+        if &data[0..4] != b"regf" {
+            return Err(ParseError::new(ParseErrorKind::Signature("regf"), 0).into());
+        }
+
+        let checksum = data[..CHECKSUM_LEN].chunks_exact(4)
+            .map(|chunk| u32_le(chunk, 0))
+            .fold(0, |checksum, dword| checksum ^ dword);
+        let checksum = match checksum {
+            0 => 1,
+            u32::MAX => u32::MAX - 1,
+            checksum => checksum,
+        };
+        if checksum != u32_le(&data, CHECKSUM_LEN) {
+            return Err(ParseError::new(ParseErrorKind::Checksum, 0).into());
+        }
+
+        let primary_seq = u32_le(&data, 4);
+        let secondary_seq = u32_le(&data, 8);
+        let last_write_time = u64_le(&data, 12);
+        let major_version = u32_le(&data, 20);
+        let minor_version = u32_le(&data, 24);
+        let file_type = u32_le(&data, 28);
+        let root_offset = u32_le(&data, 36);
+        let hbins_len = u32_le(&data, 40);
+
+        if major_version != 1 {
+            return Err(ParseError::new(ParseErrorKind::Version(major_version), 0).into());
+        }
+        // Other file types are used for transaction logs.
+        if file_type != 0 {
+            return Err(ParseError::new(ParseErrorKind::FileType(file_type), 0).into());
+        }
+
+        // Hive files are sometimes padded, we do not want to interpret the data
+        // past the declared hive bins as cells. Note that truncated hives are
+        // still fine: reads beyond the end of the data will simply fail.
+        let data_len = BASE_BLOCK_LEN.saturating_add(hbins_len as usize);
+        data.truncate(data_len);
+
+        Ok(Hive {
+            data,
+            root_offset,
+            minor_version,
+            dirty: primary_seq != secondary_seq,
+            last_write_time: crate::time::from_file_time(last_write_time),
+        })
+    }
+
+    /// Returns whether the hive was not properly flushed to disk.
+    ///
+    /// Dirty hives might not reflect the latest changes to the registry as they
+    /// are recorded only in the transaction logs (which this parser ignores).
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the time at which the hive was last written.
+    pub fn last_write_time(&self) -> std::time::SystemTime {
+        self.last_write_time
+    }
+
+    /// Returns the root key of the hive.
+    pub fn root(&self) -> std::io::Result<Key<'_>> {
+        Key::parse(self, self.root_offset)
+    }
+
+    /// Returns data of the cell at the given offset.
+    ///
+    /// The offset is relative to the start of the hive bins (i.e. the end of
+    /// the base block) as all offsets stored in the hive are.
+    fn cell(&self, offset: u32) -> std::io::Result<&[u8]> {
+        if offset == NULL_OFFSET {
+            return Err(ParseError::new(ParseErrorKind::NullOffset, offset).into());
+        }
+
+        let start = BASE_BLOCK_LEN + offset as usize;
+        let size = match self.data.get(start..start + 4) {
+            Some(size) => i32::from_le_bytes([size[0], size[1], size[2], size[3]]),
+            None => return Err(ParseError::truncated(offset).into()),
+        };
+
+        // Allocated cells have negative sizes. We should never be referencing
+        // unallocated ones but in case of a dirty hive this might happen and
+        // the data is likely still there, so we do not reject such cells.
+        let len = size.unsigned_abs() as usize;
+        if len < 4 {
+            return Err(ParseError::new(ParseErrorKind::CellSize(size), offset).into());
+        }
+
+        match self.data.get(start + 4..start + len) {
+            Some(data) => Ok(data),
+            None => Err(ParseError::truncated(offset).into()),
+        }
+    }
+
+    /// Returns data of the cell at the given offset verifying its signature.
+    fn record(&self, offset: u32, signature: &'static str) -> std::io::Result<&[u8]> {
+        let cell = self.cell(offset)?;
+        if cell.get(..signature.len()) != Some(signature.as_bytes()) {
+            return Err(ParseError::new(ParseErrorKind::Signature(signature), offset).into());
+        }
+
+        Ok(cell)
+    }
+}
+
+/// Key stored in a registry hive.
+#[derive(Clone, Copy)]
+pub struct Key<'h> {
+    /// Hive the key belongs to.
+    hive: &'h Hive,
+    /// Offset of the key cell.
+    offset: u32,
+    /// Data of the key cell.
+    cell: &'h [u8],
+}
+
+impl<'h> Key<'h> {
+
+    /// Length of the fixed part of the key node record.
+    const HEADER_LEN: usize = 76;
+
+    /// Parses the key cell at the given offset in the hive.
+    fn parse(hive: &'h Hive, offset: u32) -> std::io::Result<Key<'h>> {
+        let cell = hive.record(offset, "nk")?;
+        if cell.len() < Self::HEADER_LEN {
+            return Err(ParseError::truncated(offset).into());
+        }
+        if cell.len() < Self::HEADER_LEN + usize::from(u16_le(cell, 72)) {
+            return Err(ParseError::truncated(offset).into());
+        }
+
+        Ok(Key {
+            hive,
+            offset,
+            cell,
+        })
+    }
+
+    /// Returns the name of the key.
+    pub fn name(&self) -> String {
+        let name_len = usize::from(u16_le(self.cell, 72));
+        let name = &self.cell[Self::HEADER_LEN..Self::HEADER_LEN + name_len];
+
+        decode_name(name, self.flags() & KEY_COMP_NAME != 0)
+    }
+
+    /// Returns the time at which the key (or any of its values) was last
+    /// modified.
+    pub fn last_write_time(&self) -> std::time::SystemTime {
+        crate::time::from_file_time(u64_le(self.cell, 4))
+    }
+
+    /// Returns the [class][1] of the key.
+    ///
+    /// Most of the keys do not have a class, in which case the returned string
+    /// is empty.
+    ///
+    /// [1]: https://learn.microsoft.com/en-us/windows/win32/sysinfo/structure-of-the-registry
+    pub fn class(&self) -> std::io::Result<String> {
+        let class_offset = u32_le(self.cell, 48);
+        let class_len = usize::from(u16_le(self.cell, 74));
+        if class_offset == NULL_OFFSET || class_len == 0 {
+            return Ok(String::new());
+        }
+
+        let cell = self.hive.cell(class_offset)?;
+        match cell.get(..class_len) {
+            Some(class) => Ok(decode_utf16(class)),
+            None => Err(ParseError::truncated(class_offset).into()),
+        }
+    }
+
+    /// Returns the raw [security descriptor][1] of the key.
+    ///
+    /// The descriptor is stored in the self-relative format.
+    ///
+    /// [1]: https://learn.microsoft.com/en-us/windows/win32/secauthz/security-descriptors
+    pub fn security_descriptor(&self) -> std::io::Result<&'h [u8]> {
+        let sk_offset = u32_le(self.cell, 44);
+
+        let cell = self.hive.record(sk_offset, "sk")?;
+        if cell.len() < 20 {
+            return Err(ParseError::truncated(sk_offset).into());
+        }
+
+        let len = u32_le(cell, 16) as usize;
+        match cell.get(20..20 + len) {
+            Some(descriptor) => Ok(descriptor),
+            None => Err(ParseError::truncated(sk_offset).into()),
+        }
+    }
+
+    /// Returns an iterator over subkeys of the key.
+    pub fn subkeys(&self) -> std::io::Result<Subkeys<'h>> {
+        let mut offsets = Vec::new();
+
+        let count = u32_le(self.cell, 20);
+        let list_offset = u32_le(self.cell, 28);
+        if count > 0 {
+            subkey_offsets(self.hive, list_offset, &mut offsets, false)?;
+        }
+
+        Ok(Subkeys {
+            hive: self.hive,
+            offsets: offsets.into_iter(),
+        })
+    }
+
+    /// Returns the subkey with the given name.
+    ///
+    /// Like everywhere else in the registry, names are compared in a case
+    /// insensitive way.
+    pub fn subkey(&self, name: &str) -> std::io::Result<Key<'h>> {
+        let name = name.to_uppercase();
+
+        for subkey in self.subkeys()? {
+            let subkey = subkey?;
+            if subkey.name().to_uppercase() == name {
+                return Ok(subkey);
+            }
+        }
+
+        Err(std::io::ErrorKind::NotFound.into())
+    }
+
+    /// Returns the key at the given path relative to this key.
+    ///
+    /// The path consists of key names separated with `\`. An empty path yields
+    /// this key.
+    pub fn open(&self, path: &str) -> std::io::Result<Key<'h>> {
+        path.split('\\')
+            .filter(|name| !name.is_empty())
+            .try_fold(*self, |key, name| key.subkey(name))
+    }
+
+    /// Returns an iterator over values of the key.
+    pub fn values(&self) -> std::io::Result<Values<'h>> {
+        let count = u32_le(self.cell, 36) as usize;
+        let list_offset = u32_le(self.cell, 40);
+        if count == 0 {
+            return Ok(Values {
+                hive: self.hive,
+                list: &[],
+            });
+        }
+
+        let cell = self.hive.cell(list_offset)?;
+        match cell.get(..count.saturating_mul(4)) {
+            Some(list) => Ok(Values {
+                hive: self.hive,
+                list,
+            }),
+            None => Err(ParseError::truncated(list_offset).into()),
+        }
+    }
+
+    /// Returns flags of the key.
+    fn flags(&self) -> u16 {
+        u16_le(self.cell, 2)
+    }
+}
+
+impl<'h> std::fmt::Debug for Key<'h> {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt.debug_struct("Key")
+            .field("offset", &self.offset)
+            .field("name", &self.name())
+            .finish()
+    }
+}
+
+/// Collects offsets of keys in the given subkey list to `offsets`.
+///
+/// `nested` indicates whether the list is referenced by an index root (which
+/// is not allowed to reference other index roots).
+fn subkey_offsets(
+    hive: &Hive,
+    list_offset: u32,
+    offsets: &mut Vec<u32>,
+    nested: bool,
+) -> std::io::Result<()> {
+    let cell = hive.cell(list_offset)?;
+    if cell.len() < 4 {
+        return Err(ParseError::truncated(list_offset).into());
+    }
+
+    let signature = [cell[0], cell[1]];
+    let count = usize::from(u16_le(cell, 2));
+
+    let entry_len = match &signature {
+        // Fast leaf and hash leaf entries are followed by name hints we do not
+        // need (since we compare names anyway).
+        b"lf" | b"lh" => 8,
+        b"li" => 4,
+        b"ri" if !nested => 4,
+        _ => {
+            let kind = ParseErrorKind::SubkeyList(signature);
+            return Err(ParseError::new(kind, list_offset).into());
+        }
+    };
+
+    let entries = match cell.get(4..4 + count * entry_len) {
+        Some(entries) => entries,
+        None => return Err(ParseError::truncated(list_offset).into()),
+    };
+
+    for entry in entries.chunks_exact(entry_len) {
+        let offset = u32_le(entry, 0);
+        if &signature == b"ri" {
+            subkey_offsets(hive, offset, offsets, true)?;
+        } else {
+            offsets.push(offset);
+        }
+    }
+
+    Ok(())
+}
+
+/// Iterator over subkeys of a hive key.
+///
+/// This iterator can be created using the [`Key::subkeys`] function.
+pub struct Subkeys<'h> {
+    /// Hive the subkeys belong to.
+    hive: &'h Hive,
+    /// Offsets of the subkeys that have not been yielded yet.
+    offsets: std::vec::IntoIter<u32>,
+}
+
+impl<'h> Iterator for Subkeys<'h> {
+
+    type Item = std::io::Result<Key<'h>>;
+
+    fn next(&mut self) -> Option<std::io::Result<Key<'h>>> {
+        let offset = self.offsets.next()?;
+        Some(Key::parse(self.hive, offset))
+    }
+}
+
+/// Iterator over values of a hive key.
+///
+/// This iterator can be created using the [`Key::values`] function.
+pub struct Values<'h> {
+    /// Hive the values belong to.
+    hive: &'h Hive,
+    /// Remaining part of the list of offsets to the values.
+    list: &'h [u8],
+}
+
+impl<'h> Iterator for Values<'h> {
+
+    type Item = std::io::Result<Value>;
+
+    fn next(&mut self) -> Option<std::io::Result<Value>> {
+        if self.list.is_empty() {
+            return None;
+        }
+
+        let offset = u32_le(self.list, 0);
+        self.list = &self.list[4..];
+
+        Some(parse_value(self.hive, offset))
+    }
+}
+
+/// Value stored in a registry hive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Value {
+    /// Name of the value (empty for the default value).
+    pub name: String,
+    /// Data associated with the value.
+    pub data: ValueData,
+}
+
+/// Data of a value stored in a registry hive.
+///
+/// Unlike data of live registry values, data of values stored in hives is not
+/// validated by the system. Data of unknown types or data that is not valid
+/// for its type is represented as raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ValueData {
+    /// No data (`REG_NONE`).
+    None,
+    /// Byte string (`REG_BINARY` and all unsupported types).
+    Bytes(Vec<u8>),
+    /// Unicode string (`REG_SZ`).
+    String(String),
+    /// Unicode string with unexpanded references to environment variables
+    /// (`REG_EXPAND_SZ`).
+    ExpandString(String),
+    /// Sequence of unicode strings (`REG_MULTI_SZ`).
+    MultiString(Vec<String>),
+    /// Symbolic link to another registry key (`REG_LINK`).
+    Link(String),
+    /// 32-bit number (`REG_DWORD` and `REG_DWORD_BIG_ENDIAN`).
+    U32(u32),
+    /// 64-bit number (`REG_QWORD`).
+    U64(u64),
+}
+
+impl ValueData {
+
+    /// Interprets the given raw data according to the given value type.
+    fn from_raw_data(data_type: u32, data: Vec<u8>) -> ValueData {
+        // See the list of value types [1] for the constants.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/sysinfo/registry-value-types
+        match data_type {
+            0 if data.is_empty() => ValueData::None,
+            1 => ValueData::String(decode_utf16_sz(&data)),
+            2 => ValueData::ExpandString(decode_utf16_sz(&data)),
+            4 if data.len() == 4 => ValueData::U32(u32_le(&data, 0)),
+            5 if data.len() == 4 => ValueData::U32(u32::from_be_bytes([data[0], data[1], data[2], data[3]])),
+            6 => ValueData::Link(decode_utf16_sz(&data)),
+            7 => {
+                let strings = decode_utf16(&data).split('\0')
+                    .filter(|string| !string.is_empty())
+                    .map(String::from)
+                    .collect();
+
+                ValueData::MultiString(strings)
+            }
+            11 if data.len() == 8 => ValueData::U64(u64_le(&data, 0)),
+            _ => ValueData::Bytes(data),
+        }
+    }
+}
+
+/// Parses the value cell at the given offset in the hive.
+fn parse_value(hive: &Hive, offset: u32) -> std::io::Result<Value> {
+    /// Length of the fixed part of the key value record.
+    const HEADER_LEN: usize = 20;
+
+    let cell = hive.record(offset, "vk")?;
+    if cell.len() < HEADER_LEN {
+        return Err(ParseError::truncated(offset).into());
+    }
+
+    let name_len = usize::from(u16_le(cell, 2));
+    let data_len = u32_le(cell, 4);
+    let data_offset = u32_le(cell, 8);
+    let data_type = u32_le(cell, 12);
+    let flags = u16_le(cell, 16);
+
+    let name = match cell.get(HEADER_LEN..HEADER_LEN + name_len) {
+        Some(name) => decode_name(name, flags & VALUE_COMP_NAME != 0),
+        None => return Err(ParseError::truncated(offset).into()),
+    };
+
+    // If the most significant bit of the length is set, the data is stored
+    // directly in the offset field.
+    let data = if data_len & 0x8000_0000 != 0 {
+        let data_len = (data_len & 0x7FFF_FFFF) as usize;
+        match data_offset.to_le_bytes().get(..data_len) {
+            Some(data) => data.to_vec(),
+            None => {
+                let kind = ParseErrorKind::ValueDataSize(data_len);
+                return Err(ParseError::new(kind, offset).into());
+            }
+        }
+    } else if data_len == 0 {
+        Vec::new()
+    } else if data_len as usize > BIG_DATA_SEGMENT_LEN && hive.minor_version > 3 {
+        big_data(hive, data_offset, data_len as usize)?
+    } else {
+        let cell = hive.cell(data_offset)?;
+        match cell.get(..data_len as usize) {
+            Some(data) => data.to_vec(),
+            None => return Err(ParseError::truncated(data_offset).into()),
+        }
+    };
+
+    Ok(Value {
+        name,
+        data: ValueData::from_raw_data(data_type, data),
+    })
+}
+
+/// Assembles data of the given length stored in the big data cell at the given
+/// offset.
+fn big_data(hive: &Hive, offset: u32, len: usize) -> std::io::Result<Vec<u8>> {
+    let cell = hive.record(offset, "db")?;
+    if cell.len() < 8 {
+        return Err(ParseError::truncated(offset).into());
+    }
+
+    let segment_count = usize::from(u16_le(cell, 2));
+    let segment_list_offset = u32_le(cell, 4);
+
+    let segment_list = hive.cell(segment_list_offset)?;
+    let segment_list = match segment_list.get(..segment_count * 4) {
+        Some(segment_list) => segment_list,
+        None => return Err(ParseError::truncated(segment_list_offset).into()),
+    };
+
+    let mut data = Vec::with_capacity(len);
+    for segment_offset in segment_list.chunks_exact(4).map(|chunk| u32_le(chunk, 0)) {
+        let segment_len = std::cmp::min(len - data.len(), BIG_DATA_SEGMENT_LEN);
+        if segment_len == 0 {
+            break;
+        }
+
+        let segment = hive.cell(segment_offset)?;
+        match segment.get(..segment_len) {
+            Some(segment) => data.extend_from_slice(segment),
+            None => return Err(ParseError::truncated(segment_offset).into()),
+        }
+    }
+
+    if data.len() != len {
+        return Err(ParseError::new(ParseErrorKind::ValueDataSize(len), offset).into());
+    }
+
+    Ok(data)
+}
+
+/// Decodes a key or value name.
+///
+/// `compressed` indicates whether the name is stored using one byte per
+/// character rather than as UTF-16.
+fn decode_name(name: &[u8], compressed: bool) -> String {
+    if compressed {
+        // "Compressed" names use Latin-1 rather than ASCII in practice, which
+        // map directly to first 256 Unicode code points.
+        name.iter().copied().map(char::from).collect()
+    } else {
+        decode_utf16(name)
+    }
+}
+
+/// Decodes the given UTF-16 string (replacing invalid sequences).
+fn decode_utf16(string: &[u8]) -> String {
+    let string = string.chunks_exact(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]));
+
+    char::decode_utf16(string)
+        .map(|char| char.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Decodes the given null-terminated UTF-16 string (replacing invalid
+/// sequences).
+///
+/// Everything after the first null character is ignored. Note that the string
+/// does not need to actually be terminated.
+fn decode_utf16_sz(string: &[u8]) -> String {
+    let mut string = decode_utf16(string);
+    if let Some(index) = string.find('\0') {
+        string.truncate(index);
+    }
+
+    string
+}
+
+/// Reads a little-endian 16-bit integer at the given offset.
+///
+/// The offset must be within the bounds of the buffer.
+fn u16_le(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Reads a little-endian 32-bit integer at the given offset.
+///
+/// The offset must be within the bounds of the buffer.
+fn u32_le(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+/// Reads a little-endian 64-bit integer at the given offset.
+///
+/// The offset must be within the bounds of the buffer.
+fn u64_le(buf: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&buf[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// An error indicating that the hive file is malformed.
+#[derive(Debug)]
+struct ParseError {
+    /// Details about the error.
+    kind: ParseErrorKind,
+    /// Offset of the cell (relative to the start of hive bins) at which the
+    /// error occurred.
+    offset: u32,
+}
+
+/// Specific kind of the hive file parsing error.
+#[derive(Debug)]
+enum ParseErrorKind {
+    /// The data ends before the structure that was being parsed.
+    Truncated,
+    /// The checksum of the base block does not match.
+    Checksum,
+    /// The major version of the format is not supported.
+    Version(u32),
+    /// The file is not a primary hive file (e.g. it is a transaction log).
+    FileType(u32),
+    /// A structure has a signature other than the expected one.
+    Signature(&'static str),
+    /// A cell has an invalid size.
+    CellSize(i32),
+    /// A subkey list has an unexpected signature.
+    SubkeyList([u8; 2]),
+    /// Data of a value has an invalid size.
+    ValueDataSize(usize),
+    /// A required cell is missing.
+    NullOffset,
+}
+
+impl ParseError {
+
+    /// Creates a new error of the given kind at the given offset.
+    fn new(kind: ParseErrorKind, offset: u32) -> ParseError {
+        ParseError {
+            kind,
+            offset,
+        }
+    }
+
+    /// Creates a new error indicating truncated data at the given offset.
+    fn truncated(offset: u32) -> ParseError {
+        ParseError::new(ParseErrorKind::Truncated, offset)
+    }
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ParseErrorKind::*;
+
+        match &self.kind {
+            Truncated => write!(fmt, "truncated hive data")?,
+            Checksum => write!(fmt, "invalid base block checksum")?,
+            Version(version) => write!(fmt, "unsupported format version: {version}")?,
+            FileType(file_type) => write!(fmt, "not a primary hive file: {file_type}")?,
+            Signature(signature) => write!(fmt, "missing {signature:?} signature")?,
+            CellSize(size) => write!(fmt, "invalid cell size: {size}")?,
+            SubkeyList(signature) => {
+                write!(fmt, "invalid subkey list: {:?}", String::from_utf8_lossy(signature))?
+            }
+            ValueDataSize(size) => write!(fmt, "invalid value data size: {size}")?,
+            NullOffset => write!(fmt, "missing cell")?,
+        }
+
+        write!(fmt, " (at offset {:#x})", self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+impl From<ParseError> for std::io::Error {
+
+    fn from(error: ParseError) -> std::io::Error {
+        let kind = match error.kind {
+            ParseErrorKind::Truncated => std::io::ErrorKind::UnexpectedEof,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+
+        std::io::Error::new(kind, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Small hive with a few keys and values of all the common types.
+    ///
+    /// The hive has the following structure (classes are given in brackets):
+    ///
+    /// ```text
+    /// ROOT
+    /// ├── Bar
+    /// │   ├── A
+    /// │   ├── B
+    /// │   └── C
+    /// ├── Foo [FooClass]
+    /// │   └── Baz
+    /// │       └── Quux
+    /// └── Ünicode
+    /// ```
+    ///
+    /// Subkeys of the keys are stored using all kinds of subkey lists and the
+    /// `Foo\Big` value is big enough to be stored as big data. See the tests
+    /// below for the values.
+    const SMALL_HIVE: &[u8] = include_bytes!("../testdata/small.hive");
+
+    fn small_hive() -> Hive {
+        Hive::from_bytes(SMALL_HIVE.to_vec()).unwrap()
+    }
+
+    /// Recomputes the checksum of the base block of the given hive data.
+    fn fix_checksum(data: &mut [u8]) {
+        let checksum = data[..CHECKSUM_LEN].chunks_exact(4)
+            .map(|chunk| u32_le(chunk, 0))
+            .fold(0, |checksum, dword| checksum ^ dword);
+        data[CHECKSUM_LEN..CHECKSUM_LEN + 4].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    /// Offset of the first cell slot in hives created by [`hive_with_cells`].
+    const FIRST_SLOT_OFFSET: u32 = 0x20;
+
+    /// Length of a cell slot in hives created by [`hive_with_cells`].
+    const SLOT_LEN: u32 = 0x100;
+
+    /// Creates a hive with the given cells, the first of which is the root.
+    ///
+    /// Each cell is put into its own slot (see [`slot_offset`]) and is exactly
+    /// as long as the given data.
+    fn hive_with_cells(cells: &[&[u8]]) -> Hive {
+        let hbins_len = 4096;
+
+        let mut data = SMALL_HIVE[..BASE_BLOCK_LEN].to_vec();
+        data[36..40].copy_from_slice(&FIRST_SLOT_OFFSET.to_le_bytes());
+        data[40..44].copy_from_slice(&(hbins_len as u32).to_le_bytes());
+        fix_checksum(&mut data);
+
+        let mut hbin = vec![0; hbins_len];
+        hbin[0..4].copy_from_slice(b"hbin");
+        for (index, cell) in cells.iter().enumerate() {
+            let start = slot_offset(index) as usize;
+            let size = -((cell.len() + 4) as i32);
+            hbin[start..start + 4].copy_from_slice(&size.to_le_bytes());
+            hbin[start + 4..start + 4 + cell.len()].copy_from_slice(cell);
+        }
+        data.extend(hbin);
+
+        Hive::from_bytes(data).unwrap()
+    }
+
+    /// Returns the offset of the slot with the given index.
+    fn slot_offset(index: usize) -> u32 {
+        FIRST_SLOT_OFFSET + index as u32 * SLOT_LEN
+    }
+
+    /// Creates a key node cell with an empty name.
+    fn key_cell() -> Vec<u8> {
+        let mut cell = vec![0; Key::HEADER_LEN];
+        cell[0..2].copy_from_slice(b"nk");
+        cell
+    }
+
+    fn subkey_names(key: &Key) -> Vec<String> {
+        key.subkeys().unwrap()
+            .map(|subkey| subkey.unwrap().name())
+            .collect()
+    }
+
+    fn value(key: &Key, name: &str) -> ValueData {
+        key.values().unwrap()
+            .map(Result::unwrap)
+            .find(|value| value.name == name)
+            .unwrap()
+            .data
+    }
+
+    #[test]
+    fn from_bytes_not_dirty() {
+        let hive = small_hive();
+        assert!(!hive.is_dirty());
+    }
+
+    #[test]
+    fn from_bytes_dirty() {
+        let mut data = SMALL_HIVE.to_vec();
+        data[4..8].copy_from_slice(&2u32.to_le_bytes());
+        fix_checksum(&mut data);
+
+        let hive = Hive::from_bytes(data).unwrap();
+        assert!(hive.is_dirty());
+        assert_eq!(hive.root().unwrap().name(), "ROOT");
+    }
+
+    #[test]
+    fn from_bytes_invalid_signature() {
+        let mut data = SMALL_HIVE.to_vec();
+        data[0..4].copy_from_slice(b"fger");
+        fix_checksum(&mut data);
+
+        let error = Hive::from_bytes(data).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn from_bytes_invalid_checksum() {
+        let mut data = SMALL_HIVE.to_vec();
+        data[CHECKSUM_LEN] ^= 0xFF;
+
+        let error = Hive::from_bytes(data).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn from_bytes_transaction_log() {
+        let mut data = SMALL_HIVE.to_vec();
+        data[28..32].copy_from_slice(&6u32.to_le_bytes());
+        fix_checksum(&mut data);
+
+        let error = Hive::from_bytes(data).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn from_bytes_truncated_base_block() {
+        let error = Hive::from_bytes(SMALL_HIVE[..1024].to_vec()).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn from_bytes_truncated_everywhere() {
+        /// Walks the whole hive, returning the number of successfully parsed
+        /// keys and values.
+        fn walk(key: Key) -> usize {
+            let mut count = 1;
+            let _ = key.class();
+            let _ = key.security_descriptor();
+
+            if let Ok(values) = key.values() {
+                count += values.filter(Result::is_ok).count();
+            }
+            if let Ok(subkeys) = key.subkeys() {
+                count += subkeys.filter_map(Result::ok).map(walk).sum::<usize>();
+            }
+
+            count
+        }
+
+        let full_count = walk(small_hive().root().unwrap());
+
+        // We verify that whatever the length of the data is, the parser does
+        // not panic and parses less than the full hive.
+        for len in (BASE_BLOCK_LEN..SMALL_HIVE.len()).step_by(3) {
+            let hive = Hive::from_bytes(SMALL_HIVE[..len].to_vec()).unwrap();
+            if let Ok(root) = hive.root() {
+                assert!(walk(root) <= full_count);
+            }
+        }
+    }
+
+    #[test]
+    fn root_name() {
+        let hive = small_hive();
+        assert_eq!(hive.root().unwrap().name(), "ROOT");
+    }
+
+    #[test]
+    fn key_last_write_time() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("Foo").unwrap();
+
+        // 2024-01-01T00:00:01Z.
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_704_067_201);
+        assert_eq!(key.last_write_time(), time);
+    }
+
+    #[test]
+    fn key_class() {
+        let hive = small_hive();
+        let root = hive.root().unwrap();
+
+        assert_eq!(root.open("Foo").unwrap().class().unwrap(), "FooClass");
+        assert_eq!(root.open("Bar").unwrap().class().unwrap(), "");
+    }
+
+    #[test]
+    fn key_security_descriptor() {
+        let hive = small_hive();
+        let descriptor = hive.root().unwrap().security_descriptor().unwrap();
+
+        // Revision and control bits (`SE_SELF_RELATIVE`).
+        assert_eq!(&descriptor[0..4], b"\x01\x00\x00\x80");
+    }
+
+    #[test]
+    fn key_subkeys_hash_leaf() {
+        let hive = small_hive();
+        assert_eq! {
+            subkey_names(&hive.root().unwrap()),
+            vec!["Bar", "Foo", "Ünicode"]
+        };
+    }
+
+    #[test]
+    fn key_subkeys_fast_leaf() {
+        let hive = small_hive();
+        assert_eq! {
+            subkey_names(&hive.root().unwrap().open("Foo").unwrap()),
+            vec!["Baz"]
+        };
+    }
+
+    #[test]
+    fn key_subkeys_index_root() {
+        let hive = small_hive();
+        let root = hive.root().unwrap();
+
+        assert_eq!(subkey_names(&root.open("Bar").unwrap()), vec!["A", "B", "C"]);
+        assert_eq!(subkey_names(&root.open("Foo\\Baz").unwrap()), vec!["Quux"]);
+    }
+
+    #[test]
+    fn key_subkeys_none() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("Bar\\A").unwrap();
+
+        assert!(subkey_names(&key).is_empty());
+    }
+
+    #[test]
+    fn key_open_case_insensitive() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("fOO\\BAZ\\quux").unwrap();
+
+        assert_eq!(key.name(), "Quux");
+    }
+
+    #[test]
+    fn key_open_unicode() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("ÜNICODE").unwrap();
+
+        assert_eq!(key.name(), "Ünicode");
+    }
+
+    #[test]
+    fn key_open_empty() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("").unwrap();
+
+        assert_eq!(key.name(), "ROOT");
+    }
+
+    #[test]
+    fn key_open_not_found() {
+        let hive = small_hive();
+        let error = hive.root().unwrap().open("Foo\\Norf").unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn key_values_default() {
+        let hive = small_hive();
+        let root = hive.root().unwrap();
+
+        assert_eq!(value(&root, ""), ValueData::String(String::from("root")));
+    }
+
+    #[test]
+    fn key_values_none() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("Bar").unwrap();
+
+        assert_eq!(key.values().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn key_values_types() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("Foo").unwrap();
+
+        assert_eq! {
+            value(&key, "String"),
+            ValueData::String(String::from("Hello, world!"))
+        };
+        assert_eq! {
+            value(&key, "Expand"),
+            ValueData::ExpandString(String::from("%SystemRoot%\\foo"))
+        };
+        assert_eq! {
+            value(&key, "Multi"),
+            ValueData::MultiString(vec![String::from("foo"), String::from("bar")])
+        };
+        assert_eq!(value(&key, "DWord"), ValueData::U32(0xdeadbeef));
+        assert_eq!(value(&key, "DWordBE"), ValueData::U32(0xdeadbeef));
+        assert_eq!(value(&key, "QWord"), ValueData::U64(0xcafebabe_deadbeef));
+        assert_eq!(value(&key, "Binary"), ValueData::Bytes((0..16).collect()));
+        assert_eq!(value(&key, "Empty"), ValueData::None);
+        assert_eq!(value(&key, "Ünicode"), ValueData::String(String::from("zażółć")));
+    }
+
+    #[test]
+    fn key_values_big_data() {
+        let hive = small_hive();
+        let key = hive.root().unwrap().open("Foo").unwrap();
+
+        let expected = (0..20000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        assert_eq!(value(&key, "Big"), ValueData::Bytes(expected));
+    }
+
+    #[test]
+    fn value_data_invalid_dword() {
+        assert_eq! {
+            ValueData::from_raw_data(4, vec![0x01, 0x02]),
+            ValueData::Bytes(vec![0x01, 0x02])
+        };
+    }
+
+    #[test]
+    fn value_data_unknown_type() {
+        assert_eq! {
+            ValueData::from_raw_data(0x1337, vec![0x01, 0x02, 0x03]),
+            ValueData::Bytes(vec![0x01, 0x02, 0x03])
+        };
+    }
+
+    #[test]
+    fn value_data_string_garbage_after_null() {
+        let data = "foo\0bar".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(ValueData::from_raw_data(1, data), ValueData::String(String::from("foo")));
+    }
+
+    #[test]
+    fn root_truncated_key_cell() {
+        let hive = hive_with_cells(&[b"nk      "]);
+
+        let error = hive.root().err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn key_values_truncated_value_cell() {
+        let mut key = key_cell();
+        key[36..40].copy_from_slice(&1u32.to_le_bytes());
+        key[40..44].copy_from_slice(&slot_offset(1).to_le_bytes());
+
+        let hive = hive_with_cells(&[&key, &slot_offset(2).to_le_bytes(), b"vk  "]);
+
+        let error = hive.root().unwrap().values().unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn key_subkeys_truncated_list_cell() {
+        let mut key = key_cell();
+        key[20..24].copy_from_slice(&1u32.to_le_bytes());
+        key[28..32].copy_from_slice(&slot_offset(1).to_le_bytes());
+
+        for signature in [b"lf", b"lh", b"li", b"ri"] {
+            let hive = hive_with_cells(&[&key, signature]);
+
+            let error = hive.root().unwrap().subkeys().err().unwrap();
+            assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        }
+    }
+}
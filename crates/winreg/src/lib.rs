@@ -1,4 +1,6 @@
+pub mod hive;
 pub mod path;
+pub mod time;
 
 #[cfg(target_os = "windows")]
 mod windows;
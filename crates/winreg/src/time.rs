@@ -0,0 +1,55 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Number of seconds between 1601-01-01 and 1970-01-01.
+const EPOCH_DIFF_SECS: u64 = 11_644_473_600;
+
+/// Converts the given [`FILETIME`] value to [`std::time::SystemTime`].
+///
+/// `FILETIME` is the number of 100-nanosecond intervals since 1601-01-01 and
+/// it is what the registry uses to store timestamps.
+///
+/// [`FILETIME`]: https://learn.microsoft.com/en-us/windows/win32/api/minwinbase/ns-minwinbase-filetime
+pub fn from_file_time(ticks: u64) -> std::time::SystemTime {
+    let since_1601 = std::time::Duration::new(
+        ticks / 10_000_000,
+        (ticks % 10_000_000) as u32 * 100,
+    );
+
+    let epoch_diff = std::time::Duration::from_secs(EPOCH_DIFF_SECS);
+    match since_1601.checked_sub(epoch_diff) {
+        Some(since_epoch) => std::time::UNIX_EPOCH + since_epoch,
+        None => std::time::UNIX_EPOCH - (epoch_diff - since_1601),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn from_file_time_epoch() {
+        let ticks = EPOCH_DIFF_SECS * 10_000_000;
+        assert_eq!(from_file_time(ticks), std::time::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn from_file_time_after_epoch() {
+        let ticks = (EPOCH_DIFF_SECS + 1_704_067_200) * 10_000_000 + 1_234_567;
+        assert_eq! {
+            from_file_time(ticks),
+            std::time::UNIX_EPOCH + std::time::Duration::new(1_704_067_200, 123_456_700)
+        };
+    }
+
+    #[test]
+    fn from_file_time_before_epoch() {
+        assert_eq! {
+            from_file_time(0),
+            std::time::UNIX_EPOCH - std::time::Duration::from_secs(EPOCH_DIFF_SECS)
+        };
+    }
+}
@@ -25,9 +25,16 @@ impl PredefinedKey {
     ///
     /// [`RegOpenKeyExW`]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regopenkeyexw
     pub fn open(&self, subkey_name: &OsStr) -> std::io::Result<OpenKey> {
+        self.open_with_view(subkey_name, View::Default)
+    }
+
+    /// Opens a subkey with the given name of the key in the given view.
+    ///
+    /// See [`PredefinedKey::open`] and [`View`] for more details.
+    pub fn open_with_view(&self, subkey_name: &OsStr, view: View) -> std::io::Result<OpenKey> {
         // SAFETY: Predefined keys are guaranteed to be valid open keys.
         unsafe {
-            open_raw_key(self.as_raw_key(), subkey_name)
+            open_raw_key(self.as_raw_key(), subkey_name, view)
         }
     }
 
@@ -74,6 +81,24 @@ impl PredefinedKey {
     }
 }
 
+/// [View][1] of the Windows registry to access.
+///
+/// On 64-bit systems, some of the keys are redirected to separate locations for
+/// 32-bit applications. Views allow accessing these locations regardless of the
+/// architecture of the calling process.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/winprog64/accessing-an-alternate-registry-view
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum View {
+    /// View native to the calling process.
+    #[default]
+    Default,
+    /// 64-bit view of the registry (`KEY_WOW64_64KEY`).
+    Registry64,
+    /// 32-bit view of the registry (`KEY_WOW64_32KEY`).
+    Registry32,
+}
+
 /// Open key of the Windows registry.
 ///
 /// Unlike [predefined keys][1], open keys must be opened explicitly and are
@@ -93,7 +118,7 @@ impl OpenKey {
     pub fn open(&self, subkey_name: &OsStr) -> std::io::Result<OpenKey> {
         // SAFETY: The key is guaranteed to be open and valid.
         unsafe {
-            open_raw_key(self.0, subkey_name)
+            open_raw_key(self.0, subkey_name, View::Default)
         }
     }
 
@@ -153,10 +178,30 @@ pub struct KeyInfo {
     max_value_name_len: u32,
     // Maximum length of the value data (in bytes).
     max_value_data_len: u32,
+    // Class of the key.
+    class: OsString,
+    // Time at which the key (or any of its values) was last modified.
+    last_write_time: std::time::SystemTime,
 }
 
 impl KeyInfo {
 
+    /// Returns the [class][1] of the key.
+    ///
+    /// Most of the keys do not have a class, in which case the returned string
+    /// is empty.
+    ///
+    /// [1]: https://learn.microsoft.com/en-us/windows/win32/sysinfo/structure-of-the-registry
+    pub fn class(&self) -> &OsStr {
+        &self.class
+    }
+
+    /// Returns the time at which the key (or any of its values) was last
+    /// modified.
+    pub fn last_write_time(&self) -> std::time::SystemTime {
+        self.last_write_time
+    }
+
     /// Returns an iterator over the key subkey names.
     ///
     /// Iteration corresponds to calling the [`RegEnumKeyExA`] function of the
@@ -558,6 +603,51 @@ impl Iterator for Values {
     }
 }
 
+/// Expands references to environment variables in the given string.
+///
+/// This is useful for interpreting [`ValueData::ExpandString`] data. Variables
+/// that are not defined are left as they are.
+///
+/// This function corresponds to the [`ExpandEnvironmentStringsW`] function of
+/// the Windows API.
+///
+/// [`ExpandEnvironmentStringsW`]: https://learn.microsoft.com/en-us/windows/win32/api/processenv/nf-processenv-expandenvironmentstringsw
+pub fn expand_string(string: &OsStr) -> std::io::Result<OsString> {
+    use std::os::windows::ffi::{OsStrExt as _, OsStringExt as _};
+    let mut string = string.encode_wide().collect::<Vec<u16>>();
+    string.push(0);
+
+    let mut buf = Vec::<u16>::new();
+
+    loop {
+        // SAFETY: This is just an FFI call as described in the docs [1]. We
+        // pass the buffer along with its size (in characters). If it is not
+        // big enough, the call returns the size needed (including the null
+        // character) without writing anything.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/processenv/nf-processenv-expandenvironmentstringsw
+        let len = unsafe {
+            windows_sys::Win32::System::Environment::ExpandEnvironmentStringsW(
+                string.as_ptr(),
+                buf.as_mut_ptr(),
+                buf.len() as u32,
+            )
+        } as usize;
+
+        if len == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if len > buf.len() {
+            buf.resize(len, 0);
+            continue;
+        }
+
+        // The returned length includes the terminating null character.
+        buf.truncate(len - 1);
+        return Ok(OsString::from_wide(&buf));
+    }
+}
+
 /// Opens a subkey of the given raw registry key.
 ///
 /// # Safety
@@ -566,6 +656,7 @@ impl Iterator for Values {
 unsafe fn open_raw_key(
     key: windows_sys::Win32::System::Registry::HKEY,
     subkey_name: &OsStr,
+    view: View,
 ) -> std::io::Result<OpenKey> {
     // Windows API functions expect null-terminated 16-bit characater strings,
     // so we have to encode the name.
@@ -573,6 +664,12 @@ unsafe fn open_raw_key(
     let mut subkey_name = subkey_name.encode_wide().collect::<Vec<u16>>();
     subkey_name.push(0);
 
+    let view_flags = match view {
+        View::Default => 0,
+        View::Registry64 => windows_sys::Win32::System::Registry::KEY_WOW64_64KEY,
+        View::Registry32 => windows_sys::Win32::System::Registry::KEY_WOW64_32KEY,
+    };
+
     let mut subkey = std::mem::MaybeUninit::uninit();
 
     // SAFETY: This is just an FFI call as described in the docs [1].
@@ -584,7 +681,7 @@ unsafe fn open_raw_key(
             key,
             subkey_name.as_ptr(),
             0,
-            windows_sys::Win32::System::Registry::KEY_READ,
+            windows_sys::Win32::System::Registry::KEY_READ | view_flags,
             subkey.as_mut_ptr(),
         )
     };
@@ -611,31 +708,63 @@ unsafe fn query_raw_key_info(
     let mut max_subkey_name_len = std::mem::MaybeUninit::uninit();
     let mut max_value_name_len = std::mem::MaybeUninit::uninit();
     let mut max_value_data_len = std::mem::MaybeUninit::uninit();
+    let mut last_write_time = std::mem::MaybeUninit::uninit();
 
-    // SAFETY: This is just an FFI call as described in the docs [1].
-    //
-    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regqueryinfokeyw
-    let code = unsafe {
-        windows_sys::Win32::System::Registry::RegQueryInfoKeyW(
-            key,
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
-            max_subkey_name_len.as_mut_ptr(),
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
-            max_value_name_len.as_mut_ptr(),
-            max_value_data_len.as_mut_ptr(),
-            std::ptr::null_mut(),
-            std::ptr::null_mut(),
-        )
+    // Classes are usually empty or very short, so we start with a small buffer
+    // and grow it only if the system tells us that it is not big enough.
+    let mut class_buf = Vec::<u16>::with_capacity(64);
+
+    let class_len = loop {
+        // The length is expected to include the trailing null character.
+        let mut class_len = class_buf.capacity() as u32;
+
+        // SAFETY: This is just an FFI call as described in the docs [1].
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-regqueryinfokeyw
+        let code = unsafe {
+            windows_sys::Win32::System::Registry::RegQueryInfoKeyW(
+                key,
+                class_buf.as_mut_ptr(),
+                &mut class_len,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                max_subkey_name_len.as_mut_ptr(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                max_value_name_len.as_mut_ptr(),
+                max_value_data_len.as_mut_ptr(),
+                std::ptr::null_mut(),
+                last_write_time.as_mut_ptr(),
+            )
+        };
+
+        match code {
+            windows_sys::Win32::Foundation::ERROR_SUCCESS => {
+                break class_len;
+            }
+            windows_sys::Win32::Foundation::ERROR_MORE_DATA => {
+                class_buf.reserve(class_buf.capacity() * 2);
+            }
+            _ => {
+                return Err(std::io::Error::from_raw_os_error(code as i32));
+            }
+        }
     };
 
-    if code != windows_sys::Win32::Foundation::ERROR_SUCCESS {
-        return Err(std::io::Error::from_raw_os_error(code as i32));
+    // SAFETY: We verified that the call above succeeded, the class buffer is
+    // now filled with `class_len` characters (excluding the null character).
+    unsafe {
+        class_buf.set_len(class_len as usize);
     }
 
+    // SAFETY: We verified that the call above succeeded, the value is now
+    // initialized.
+    let last_write_time = unsafe {
+        last_write_time.assume_init()
+    };
+
+    use std::os::windows::ffi::OsStringExt as _;
+
     Ok(KeyInfo {
         key,
         // SAFETY: We verified that the call above succeeded, the value is now
@@ -653,6 +782,11 @@ unsafe fn query_raw_key_info(
         max_value_data_len: unsafe {
             max_value_data_len.assume_init()
         },
+        class: OsString::from_wide(&class_buf),
+        last_write_time: crate::time::from_file_time(
+            u64::from(last_write_time.dwHighDateTime) << 32 |
+            u64::from(last_write_time.dwLowDateTime)
+        ),
     })
 }
 
@@ -766,6 +900,16 @@ mod tests {
             .info().unwrap();
     }
 
+    #[test]
+    fn predefined_key_info_last_write_time() {
+        let info = PredefinedKey::LocalMachine
+            .open(OsStr::new("SOFTWARE")).unwrap()
+            .info().unwrap();
+
+        assert!(info.last_write_time() > std::time::UNIX_EPOCH);
+        assert!(info.last_write_time() < std::time::SystemTime::now());
+    }
+
     #[test]
     fn predefined_key_subkeys() {
         let subkeys = PredefinedKey::LocalMachine
@@ -903,4 +1047,36 @@ mod tests {
 
         assert!(matches!(current, ValueData::U32(_)));
     }
+
+    #[test]
+    fn predefined_key_open_with_view() {
+        let key_name = OsStr::new("SOFTWARE\\Microsoft\\Windows\\CurrentVersion");
+        let value_name = OsStr::new("ProgramFilesDir");
+
+        let program_files_64 = PredefinedKey::LocalMachine
+            .open_with_view(key_name, View::Registry64).unwrap()
+            .value_data(value_name).unwrap();
+        let program_files_32 = PredefinedKey::LocalMachine
+            .open_with_view(key_name, View::Registry32).unwrap()
+            .value_data(value_name).unwrap();
+
+        // On 64-bit systems 32-bit programs are installed to a separate folder
+        // and the 32-bit view of the registry reflects that (we assume that the
+        // tests are not run on 32-bit systems anymore).
+        assert_ne!(program_files_64, program_files_32);
+    }
+
+    #[test]
+    fn expand_string_defined() {
+        let system_root = std::env::var_os("SystemRoot").unwrap();
+
+        let expanded = expand_string(OsStr::new("%SystemRoot%\\System32")).unwrap();
+        assert_eq!(expanded, std::path::Path::new(&system_root).join("System32"));
+    }
+
+    #[test]
+    fn expand_string_undefined() {
+        let expanded = expand_string(OsStr::new("%RRG_UNDEFINED_VARIABLE%\\foo")).unwrap();
+        assert_eq!(expanded, "%RRG_UNDEFINED_VARIABLE%\\foo");
+    }
 }
@@ -0,0 +1,149 @@
+// Copyright 2025 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Parses the given [`CIM_DATETIME`][1] timestamp string.
+///
+/// Timestamps have the `yyyymmddHHMMSS.mmmmmmsUUU` format where `sUUU` is the
+/// offset from UTC in minutes. Intervals (which share the same CIM type but
+/// use the `ddddddddHHMMSS.mmmmmm:000` format) and timestamps with wildcard
+/// (`*`) fields cannot be represented as a point in time and yield `None`.
+///
+/// [1]: https://learn.microsoft.com/en-us/windows/win32/wmisdk/cim-datetime
+pub fn parse(string: &str) -> Option<std::time::SystemTime> {
+    let string = string.as_bytes();
+    if string.len() != 25 || string[14] != b'.' {
+        return None;
+    }
+
+    let year = digits(&string[0..4])?;
+    let month = digits(&string[4..6])?;
+    let day = digits(&string[6..8])?;
+    let hour = digits(&string[8..10])?;
+    let min = digits(&string[10..12])?;
+    let sec = digits(&string[12..14])?;
+    let micros = digits(&string[15..21])?;
+    let offset_mins = match string[21] {
+        b'+' => i64::from(digits(&string[22..25])?),
+        b'-' => -i64::from(digits(&string[22..25])?),
+        _ => return None,
+    };
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    if hour > 23 || min > 59 || sec > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(i64::from(year), month, day);
+    let secs = days * 86400
+        + i64::from(hour) * 3600
+        + i64::from(min) * 60
+        + i64::from(sec)
+        // The timestamp is in local time, so to get to UTC we need to subtract
+        // the offset.
+        - offset_mins * 60;
+
+    let since_epoch = std::time::Duration::from_secs(secs.unsigned_abs())
+        + std::time::Duration::from_micros(u64::from(micros));
+
+    if secs >= 0 {
+        std::time::UNIX_EPOCH.checked_add(since_epoch)
+    } else {
+        // Microseconds always move the time forward, so we need to handle them
+        // separately in case of timestamps before the epoch.
+        std::time::UNIX_EPOCH
+            .checked_sub(std::time::Duration::from_secs(secs.unsigned_abs()))?
+            .checked_add(std::time::Duration::from_micros(u64::from(micros)))
+    }
+}
+
+/// Parses the given ASCII decimal digits.
+fn digits(string: &[u8]) -> Option<u32> {
+    string.iter().try_fold(0, |result, byte| match byte {
+        b'0'..=b'9' => Some(result * 10 + u32::from(byte - b'0')),
+        _ => None,
+    })
+}
+
+/// Returns the number of days since the Unix epoch of the given civil date.
+///
+/// This is the `days_from_civil` algorithm described by Howard Hinnant [1].
+///
+/// [1]: https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+        + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn time(secs: u64, micros: u64) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH
+            + std::time::Duration::from_secs(secs)
+            + std::time::Duration::from_micros(micros)
+    }
+
+    #[test]
+    fn parse_epoch() {
+        assert_eq!(parse("19700101000000.000000+000"), Some(std::time::UNIX_EPOCH));
+    }
+
+    #[test]
+    fn parse_utc() {
+        assert_eq! {
+            parse("20240229123456.789012+000"),
+            Some(time(1_709_210_096, 789_012))
+        };
+    }
+
+    #[test]
+    fn parse_positive_offset() {
+        // 2024-01-01T01:00:00+01:00 is 2024-01-01T00:00:00Z.
+        assert_eq!(parse("20240101010000.000000+060"), Some(time(1_704_067_200, 0)));
+    }
+
+    #[test]
+    fn parse_negative_offset() {
+        // 2023-12-31T19:00:00-05:00 is 2024-01-01T00:00:00Z.
+        assert_eq!(parse("20231231190000.000000-300"), Some(time(1_704_067_200, 0)));
+    }
+
+    #[test]
+    fn parse_before_epoch() {
+        assert_eq! {
+            parse("19691231235959.500000+000"),
+            Some(std::time::UNIX_EPOCH - std::time::Duration::from_millis(500))
+        };
+    }
+
+    #[test]
+    fn parse_interval() {
+        assert_eq!(parse("00000001132312.000000:000"), None);
+    }
+
+    #[test]
+    fn parse_wildcard() {
+        assert_eq!(parse("2024****123456.000000+000"), None);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("foo"), None);
+        assert_eq!(parse("20241301000000.000000+000"), None);
+        assert_eq!(parse("20240101000000.000000+0000"), None);
+    }
+}
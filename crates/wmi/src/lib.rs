@@ -3,6 +3,9 @@
 // Use of this source code is governed by an MIT-style license that can be found
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
 
+#[cfg(any(target_os = "windows", test))]
+mod datetime;
+
 #[cfg(target_os = "windows")]
 mod windows;
 
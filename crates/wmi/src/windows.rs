@@ -10,8 +10,14 @@ mod ffi;
 
 use self::error::Error;
 
+/// Namespace in which queries are executed by default.
+const DEFAULT_NAMESPACE: &str = "root\\cimv2";
+
 /// Creates a WQL query that can then be iterated to poll for results.
 ///
+/// The query is executed in the `root\cimv2` namespace unless specified
+/// otherwise with [`Query::namespace`].
+///
 /// # Examples
 ///
 /// ```no_run
@@ -37,6 +43,8 @@ where
 
     Ok(Query {
         query: query.as_ref(),
+        namespace: std::ffi::OsStr::new(DEFAULT_NAMESPACE),
+        timeout: None,
         com,
     })
 }
@@ -47,12 +55,39 @@ where
 pub struct Query<'s> {
     /// WQL string tied to the query.
     query: &'s std::ffi::OsStr,
+    /// WMI namespace to execute the query in.
+    namespace: &'s std::ffi::OsStr,
+    /// Time after which polling for results is aborted.
+    timeout: Option<std::time::Duration>,
     /// COM library initialization guard ensuring validity of the query.
     com: self::com::InitGuard,
 }
 
 impl<'s> Query<'s> {
 
+    /// Sets the WMI [namespace][1] to execute the query in.
+    ///
+    /// [1]: https://learn.microsoft.com/en-us/windows/win32/wmisdk/wmi-namespaces
+    pub fn namespace<S>(mut self, namespace: &'s S) -> Query<'s>
+    where
+        S: AsRef<std::ffi::OsStr> + ?Sized,
+    {
+        self.namespace = namespace.as_ref();
+        self
+    }
+
+    /// Sets the time after which polling for results is aborted.
+    ///
+    /// The timeout applies to the whole iteration over the [rows][1] (rather
+    /// than to fetching each of them individually). Once it elapses, the rows
+    /// iterator yields an error of the [`std::io::ErrorKind::TimedOut`] kind.
+    ///
+    /// [1]: Query::rows
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Query<'s> {
+        self.timeout = Some(timeout);
+        self
+    }
+
     /// Returns an iterator over the [rows][1] the query yielded.
     ///
     /// [1]: QueryRow
@@ -69,7 +104,7 @@ impl<'s> Query<'s> {
         let status = unsafe {
             ((loc.vtable()).ConnectServer)(
                 loc.as_raw_mut(),
-                self::bstr::BString::new("root\\cimv2").as_raw_bstr(),
+                self::bstr::BString::new(self.namespace).as_raw_bstr(),
                 std::ptr::null(),
                 std::ptr::null(),
                 std::ptr::null(),
@@ -122,6 +157,8 @@ impl<'s> Query<'s> {
             raw: unsafe {
                 self::com::EnumWbemClassObject::from_raw_ptr(&self.com, enum_ptr.assume_init())
             },
+            deadline: self.timeout.map(|timeout| std::time::Instant::now() + timeout),
+            timed_out: false,
         })
     }
 }
@@ -134,6 +171,10 @@ impl<'s> Query<'s> {
 /// [2]: Query
 pub struct QueryRows<'com> {
     raw: self::com::EnumWbemClassObject<'com>,
+    /// Instant after which polling for more rows is aborted.
+    deadline: Option<std::time::Instant>,
+    /// Whether the deadline has passed already.
+    timed_out: bool,
 }
 
 impl<'com> Iterator for QueryRows<'com> {
@@ -141,10 +182,33 @@ impl<'com> Iterator for QueryRows<'com> {
     type Item = std::io::Result<QueryRow>;
 
     fn next(&mut self) -> Option<std::io::Result<QueryRow>> {
-        let mut object = match self.raw.next() {
+        if self.timed_out {
+            return None;
+        }
+
+        let next = match self.deadline {
+            Some(deadline) => {
+                let time_left = deadline.saturating_duration_since(std::time::Instant::now());
+                let time_left_millis = time_left.as_millis()
+                    .try_into()
+                    .unwrap_or(std::ffi::c_long::MAX);
+
+                self.raw.next_with_timeout(time_left_millis)
+            }
+            None => self.raw.next(),
+        };
+
+        let mut object = match next {
             None => return None,
             Some(Ok(object)) => object,
-            Some(Err(error)) => return Some(Err(error)),
+            Some(Err(error)) => {
+                // There is no point in polling for more rows once the deadline
+                // has passed, so we stop the iteration.
+                if error.kind() == std::io::ErrorKind::TimedOut {
+                    self.timed_out = true;
+                }
+                return Some(Err(error));
+            }
         };
 
         // SAFETY: We start the enumeration on a valid object without any extra
@@ -164,6 +228,7 @@ impl<'com> Iterator for QueryRows<'com> {
         loop {
             let mut raw_name = std::mem::MaybeUninit::uninit();
             let mut raw_value = std::mem::MaybeUninit::uninit();
+            let mut raw_cim_type = std::mem::MaybeUninit::uninit();
 
             // SAFETY: We advance the iterator [1] without any extra flags. Note
             // that we already called `BeginEnumeration`.
@@ -178,7 +243,7 @@ impl<'com> Iterator for QueryRows<'com> {
                     0,
                     raw_name.as_mut_ptr(),
                     raw_value.as_mut_ptr(),
-                    std::ptr::null_mut(),
+                    raw_cim_type.as_mut_ptr(),
                     std::ptr::null_mut(),
                 )
             };
@@ -210,6 +275,12 @@ impl<'com> Iterator for QueryRows<'com> {
                 raw_value.assume_init()
             };
 
+            // SAFETY: Call to `Next` succeeded, the type should be properly
+            // initialized now.
+            let raw_cim_type = unsafe {
+                raw_cim_type.assume_init()
+            };
+
             // SAFETY: `raw_name` is guaranteed to be a valid `BSTR` instance
             // and we should dispose it after we are done with it, so we put it
             // into the owned wrapper.
@@ -222,7 +293,7 @@ impl<'com> Iterator for QueryRows<'com> {
             // We do not unwrap the conversion error yet, because we have to
             // clear the variant first. We do it afterwards.
             let value = unsafe {
-                QueryValue::from_variant(&raw_value)
+                QueryValue::from_variant(&raw_value, raw_cim_type)
             };
 
             // SAFETY: We call the deinitialization function [1] after the value
@@ -277,6 +348,16 @@ pub enum QueryValue {
     F32(f32),
     F64(f64),
     String(std::ffi::OsString),
+    /// Timestamp (values of the `CIM_DATETIME` type).
+    ///
+    /// Note that intervals (which share the type with timestamps) are yielded
+    /// as strings.
+    DateTime(std::time::SystemTime),
+    /// Array of values.
+    ///
+    /// Multi-dimensional arrays (which WMI does not use in practice) are
+    /// flattened to a single dimension.
+    Array(Vec<QueryValue>),
     Unsupported(UnsupportedQueryValue),
 }
 
@@ -284,14 +365,27 @@ impl QueryValue {
 
     /// Constructs a value from native [`VARIANT`] instance.
     ///
+    /// `cim_type` is the [CIM type][1] of the property the value belongs to.
+    ///
     /// # Safety
     ///
     /// `variant` must be a properly initialized [`VARIANT`] instance.
+    ///
+    /// [1]: https://learn.microsoft.com/en-us/windows/win32/api/wbemcli/ne-wbemcli-cimtype_enumeration
     unsafe fn from_variant(
         variant: &windows_sys::Win32::System::Variant::VARIANT,
+        cim_type: self::ffi::CIMTYPE,
     ) -> std::io::Result<QueryValue> {
         let variant = variant.Anonymous.Anonymous;
 
+        if variant.vt & windows_sys::Win32::System::Variant::VT_ARRAY != 0 {
+            // SAFETY: We verified that the variant holds an array, so this is
+            // the union field to use.
+            return unsafe {
+                QueryValue::from_safe_array(variant.Anonymous.parray, cim_type)
+            };
+        }
+
         // Based on [1] and following header files:
         //
         //   * `inc/wnet/comutil.h`
@@ -347,10 +441,19 @@ impl QueryValue {
                 Ok(QueryValue::F64(variant.Anonymous.dblVal))
             }
             windows_sys::Win32::System::Variant::VT_BSTR => {
-                Ok(QueryValue::String({
-                    self::bstr::BStr::from_raw_bstr(variant.Anonymous.bstrVal)
-                        .to_os_string()
-                }))
+                let string = self::bstr::BStr::from_raw_bstr(variant.Anonymous.bstrVal)
+                    .to_os_string();
+
+                // Timestamps are passed around as strings, we can tell them
+                // apart from the actual strings only by the property type.
+                let cim_type = cim_type & !windows_sys::Win32::System::Wmi::CIM_FLAG_ARRAY;
+                if cim_type == windows_sys::Win32::System::Wmi::CIM_DATETIME {
+                    if let Some(time) = string.to_str().and_then(crate::datetime::parse) {
+                        return Ok(QueryValue::DateTime(time));
+                    }
+                }
+
+                Ok(QueryValue::String(string))
             }
             raw_type => {
                 Ok(QueryValue::Unsupported(UnsupportedQueryValue {
@@ -361,6 +464,135 @@ impl QueryValue {
     }
 }
 
+impl QueryValue {
+
+    /// Constructs an array value from native [`SAFEARRAY`] instance.
+    ///
+    /// # Safety
+    ///
+    /// `array` must be a valid pointer to a properly initialized [`SAFEARRAY`]
+    /// instance.
+    ///
+    /// [`SAFEARRAY`]: https://learn.microsoft.com/en-us/windows/win32/api/oaidl/ns-oaidl-safearray
+    unsafe fn from_safe_array(
+        array: *const windows_sys::Win32::System::Com::SAFEARRAY,
+        cim_type: self::ffi::CIMTYPE,
+    ) -> std::io::Result<QueryValue> {
+        use windows_sys::Win32::System::Variant::*;
+
+        let mut elem_type = std::mem::MaybeUninit::uninit();
+
+        // SAFETY: Simple FFI call as described in the documentation [1]. We
+        // verify the return code below.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/oleauto/nf-oleauto-safearraygetvartype
+        let status = unsafe {
+            windows_sys::Win32::System::Ole::SafeArrayGetVartype(array, elem_type.as_mut_ptr())
+        };
+        if status != windows_sys::Win32::Foundation::S_OK {
+            return Err(Error::from_raw_hresult(status).into());
+        }
+
+        // SAFETY: The call succeeded, so the type is now initialized.
+        let elem_type = unsafe {
+            elem_type.assume_init()
+        };
+
+        // SAFETY: The array is guaranteed to be valid by the caller. Bounds of
+        // all the dimensions are stored inline right after the header.
+        let (elem_count, elem_len) = unsafe {
+            let bounds = std::slice::from_raw_parts(
+                (*array).rgsabound.as_ptr(),
+                usize::from((*array).cDims),
+            );
+            let elem_count = bounds.iter()
+                .map(|bound| bound.cElements as usize)
+                .product::<usize>();
+
+            (elem_count, (*array).cbElements as usize)
+        };
+
+        let mut data = std::ptr::null_mut();
+
+        // SAFETY: Simple FFI call as described in the documentation [1]. We
+        // verify the return code below and unlock the array once we are done.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/oleauto/nf-oleauto-safearrayaccessdata
+        let status = unsafe {
+            windows_sys::Win32::System::Ole::SafeArrayAccessData(array, &mut data)
+        };
+        if status != windows_sys::Win32::Foundation::S_OK {
+            return Err(Error::from_raw_hresult(status).into());
+        }
+
+        let mut values = Vec::with_capacity(elem_count);
+        let mut result = Ok(());
+
+        for i in 0..elem_count {
+            // SAFETY: The data is locked and has `elem_count` elements of size
+            // `elem_len` each (no matter the number of dimensions).
+            let elem = unsafe {
+                data.cast::<u8>().add(i * elem_len)
+            };
+
+            let value = if elem_type == VT_VARIANT {
+                // SAFETY: Elements of variant arrays are variants themselves.
+                unsafe {
+                    QueryValue::from_variant(&*elem.cast::<VARIANT>(), cim_type)
+                }
+            } else {
+                // Elements of other arrays are stored in the same way in which
+                // the corresponding variant stores them, so we construct such
+                // a variant (that borrows the data) and convert that.
+                //
+                // SAFETY: All-zeros is a valid (empty) variant instance.
+                let mut variant = unsafe {
+                    std::mem::zeroed::<VARIANT>()
+                };
+
+                // SAFETY: We copy at most as many bytes as the variant union
+                // can hold from a valid element.
+                unsafe {
+                    variant.Anonymous.Anonymous.vt = elem_type;
+
+                    let variant_data = std::ptr::addr_of_mut!(variant.Anonymous.Anonymous.Anonymous);
+                    std::ptr::copy_nonoverlapping(
+                        elem,
+                        variant_data.cast::<u8>(),
+                        std::cmp::min(elem_len, std::mem::size_of_val(&*variant_data)),
+                    );
+                }
+
+                // SAFETY: The variant is properly initialized. Note that we do
+                // not clear it afterwards as the data belongs to the array.
+                unsafe {
+                    QueryValue::from_variant(&variant, cim_type)
+                }
+            };
+
+            match value {
+                // Nested arrays are flattened into the outer one.
+                Ok(QueryValue::Array(nested)) => values.extend(nested),
+                Ok(value) => values.push(value),
+                Err(error) => {
+                    result = Err(error);
+                    break;
+                }
+            }
+        }
+
+        // SAFETY: We locked the array above, so we need to unlock it [1]. The
+        // return code does not matter since we are done with the data anyway.
+        //
+        // [1]: https://learn.microsoft.com/en-us/windows/win32/api/oleauto/nf-oleauto-safearrayunaccessdata
+        let _ = unsafe {
+            windows_sys::Win32::System::Ole::SafeArrayUnaccessData(array)
+        };
+
+        result.map(|()| QueryValue::Array(values))
+    }
+}
+
 /// Values that WQL queries can yield but are not supported by the crate.
 #[derive(Debug, Clone)]
 pub struct UnsupportedQueryValue {
@@ -418,6 +650,61 @@ mod tests {
         });
     }
 
+    #[test]
+    fn query_win32_process_namespace() {
+        let rows = query("SELECT Name FROM Win32_Process").unwrap()
+            .namespace("root\\cimv2")
+            .rows().unwrap()
+            .collect::<std::io::Result<Vec<_>>>().unwrap();
+
+        assert!(!rows.is_empty());
+    }
+
+    #[test]
+    fn query_invalid_namespace() {
+        let query = query("SELECT * FROM Win32_OperatingSystem").unwrap()
+            .namespace("root\\rrg-non-existent");
+
+        assert!(query.rows().is_err());
+    }
+
+    #[test]
+    fn query_datetime() {
+        let rows = query("SELECT LastBootUpTime FROM Win32_OperatingSystem").unwrap()
+            .rows().unwrap()
+            .collect::<std::io::Result<Vec<_>>>().unwrap();
+
+        let boot_time = match rows[0].get(std::ffi::OsStr::new("LastBootUpTime")).unwrap() {
+            QueryValue::DateTime(boot_time) => *boot_time,
+            value => panic!("unexpected value: {value:?}"),
+        };
+        assert!(boot_time < std::time::SystemTime::now());
+    }
+
+    #[test]
+    fn query_array() {
+        let rows = query("SELECT Roles FROM Win32_ComputerSystem").unwrap()
+            .rows().unwrap()
+            .collect::<std::io::Result<Vec<_>>>().unwrap();
+
+        let roles = match rows[0].get(std::ffi::OsStr::new("Roles")).unwrap() {
+            QueryValue::Array(roles) => roles,
+            value => panic!("unexpected value: {value:?}"),
+        };
+        assert!(roles.iter().all(|role| matches!(role, QueryValue::String(_))));
+    }
+
+    #[test]
+    fn query_timeout() {
+        let rows = query("SELECT * FROM CIM_DataFile").unwrap()
+            .timeout(std::time::Duration::ZERO)
+            .rows().unwrap()
+            .collect::<Vec<_>>();
+
+        let error = rows.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    }
+
     #[test]
     fn query_win32_environment() {
         let rows = query("SELECT * FROM Win32_ComputerSystem").unwrap()
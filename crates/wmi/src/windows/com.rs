@@ -292,6 +292,20 @@ impl<'com> Iterator for EnumWbemClassObject<'com> {
     type Item = std::io::Result<WbemClassObject<'com>>;
 
     fn next(&mut self) -> Option<std::io::Result<WbemClassObject<'com>>> {
+        self.next_with_timeout(windows_sys::Win32::System::Wmi::WBEM_INFINITE)
+    }
+}
+
+impl<'com> EnumWbemClassObject<'com> {
+
+    /// Advances the enumerator waiting at most `timeout` milliseconds.
+    ///
+    /// If no result is available within the timeout, an error of the
+    /// [`std::io::ErrorKind::TimedOut`] kind is returned.
+    pub fn next_with_timeout(
+        &mut self,
+        timeout: std::ffi::c_long,
+    ) -> Option<std::io::Result<WbemClassObject<'com>>> {
         let mut result = std::mem::MaybeUninit::uninit();
         let mut count = std::mem::MaybeUninit::uninit();
 
@@ -306,7 +320,7 @@ impl<'com> Iterator for EnumWbemClassObject<'com> {
         let status = unsafe {
             (self.vtable().Next)(
                 self.as_raw_mut(),
-                windows_sys::Win32::System::Wmi::WBEM_INFINITE,
+                timeout,
                 1,
                 result.as_mut_ptr(),
                 count.as_mut_ptr(),
@@ -334,6 +348,9 @@ impl<'com> Iterator for EnumWbemClassObject<'com> {
                 }))
             }
             windows_sys::Win32::System::Wmi::WBEM_S_FALSE => None,
+            windows_sys::Win32::System::Wmi::WBEM_S_TIMEDOUT => {
+                Some(Err(std::io::ErrorKind::TimedOut.into()))
+            }
             _ => Some(Err(super::Error::from_raw_hresult(status).into())),
         }
     }